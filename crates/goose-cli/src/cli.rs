@@ -14,6 +14,7 @@ use crate::commands::configure::{configure_telemetry_consent_dialog, handle_conf
 use crate::commands::info::handle_info;
 use crate::commands::project::{handle_project_default, handle_projects_interactive};
 use crate::commands::recipe::{handle_deeplink, handle_list, handle_open, handle_validate};
+use crate::commands::release_notes::handle_release_notes;
 use crate::commands::term::{
     handle_term_info, handle_term_init, handle_term_log, handle_term_run, Shell,
 };
@@ -32,7 +33,12 @@ use crate::commands::orchestrator::{
 use crate::commands::permissions::{
     handle_permissions_list, handle_permissions_reset, handle_permissions_set,
 };
+use crate::commands::bench::handle_bench;
+use crate::commands::storage::{handle_storage_encrypt, handle_storage_status};
 use crate::commands::cost::handle_cost_status;
+use crate::commands::emergency::{
+    handle_emergency_resume, handle_emergency_status, handle_emergency_stop,
+};
 use crate::commands::session::{handle_session_list, handle_session_remove, handle_session_search};
 use crate::commands::tunnel::{
     handle_tunnel_start, handle_tunnel_status, handle_tunnel_stop,
@@ -127,6 +133,14 @@ pub struct SessionOptions {
     )]
     pub sandbox: bool,
 
+    #[arg(
+        long = "devcontainer",
+        help = "Run the session in the workspace's dev container",
+        long_help = "Build or pull the container described by .devcontainer/devcontainer.json,\nmount the workspace, publish its forwardPorts on localhost and run extensions\nand shell tools inside it. The container is removed when the session ends.\nMutually exclusive with --container and --sandbox.",
+        conflicts_with_all = ["container", "sandbox"]
+    )]
+    pub devcontainer: bool,
+
     #[arg(
         long = "approval-policy",
         value_name = "POLICY",
@@ -157,6 +171,23 @@ pub struct SessionOptions {
         default_missing_value = "10000",
     )]
     pub thinking: Option<i32>,
+
+    #[arg(
+        long = "record-provider",
+        value_name = "FILE",
+        help = "Record every provider request and response to FILE",
+        long_help = "Save each provider request/response pair to FILE as the session runs, so it can be replayed later with --replay-provider.",
+        conflicts_with = "replay_provider"
+    )]
+    pub record_provider: Option<PathBuf>,
+
+    #[arg(
+        long = "replay-provider",
+        value_name = "FILE",
+        help = "Serve provider responses from a recording instead of calling the provider",
+        long_help = "Replay responses recorded with --record-provider. Requests are matched by hash, falling back to the closest recorded request with the same conversation shape. No provider credentials are needed."
+    )]
+    pub replay_provider: Option<PathBuf>,
 }
 
 #[derive(Debug, Clone)]
@@ -326,8 +357,9 @@ pub struct OutputOptions {
     /// Output format (text, json, stream-json)
     #[arg(
         long = "output-format",
+        alias = "output",
         value_name = "FORMAT",
-        help = "Output format (text, json, stream-json)",
+        help = "Output format (text, json, stream-json). stream-json emits one JSON event per line and ends with an exit event",
         default_value = "text",
         value_parser = clap::builder::PossibleValuesParser::new(["text", "json", "stream-json"])
     )]
@@ -1154,6 +1186,115 @@ enum Command {
     /// Show cost tracking status and budget information
     #[command(about = "Show cost tracking status and budget information")]
     Cost {},
+
+    /// Stop all work on the goosed server, or resume it (requires goosed server running)
+    #[command(about = "Emergency stop or resume all work on the goosed server")]
+    Emergency {
+        #[command(subcommand)]
+        command: EmergencyCommand,
+    },
+
+    /// Manage encryption of stored sessions, memories and checkpoints
+    #[command(about = "Manage encryption of stored data")]
+    Storage {
+        #[command(subcommand)]
+        command: StorageCommand,
+    },
+
+    /// Run the goose-bench regression corpus
+    #[command(
+        about = "Run the goose-bench regression corpus and compare against a baseline",
+        long_about = "Runs each benchmark task once per matrix entry in a fresh workspace and grades the result.\n\
+                      Exits non-zero when a critical regression against --baseline is found."
+    )]
+    Bench {
+        /// Directory of task specs (defaults to the built-in corpus)
+        #[arg(long, value_name = "DIR", help = "Directory of YAML/JSON task specs")]
+        corpus: Option<PathBuf>,
+
+        /// Matrix of cores, providers, models and config to run under
+        #[arg(
+            long,
+            value_name = "FILE",
+            help = "YAML matrix file (defaults to current config)"
+        )]
+        matrix: Option<PathBuf>,
+
+        /// Stored baseline to compare against
+        #[arg(long, value_name = "NAME", help = "Baseline to compare against")]
+        baseline: Option<String>,
+
+        /// Store this run as a baseline
+        #[arg(
+            long,
+            value_name = "NAME",
+            help = "Save this run as the named baseline"
+        )]
+        save_baseline: Option<String>,
+
+        /// Spend cap for the whole run
+        #[arg(
+            long,
+            value_name = "USD",
+            help = "Stop running tasks once this many dollars are spent"
+        )]
+        max_cost: Option<f64>,
+
+        /// Write the full report as JSON
+        #[arg(long, value_name = "FILE", help = "Write the JSON report to this file")]
+        report: Option<PathBuf>,
+    },
+
+    /// Write release notes for different audiences from the changelog
+    #[command(
+        about = "Write release notes for end users, developers and migrations from CHANGELOG.md",
+        long_about = "Turns one changelog entry into separate release notes per audience, one model pass each.\n\
+                      Migration notes are only written when the entry lists breaking changes."
+    )]
+    ReleaseNotes {
+        /// Keep a Changelog file to read
+        #[arg(
+            long,
+            value_name = "FILE",
+            default_value = "CHANGELOG.md",
+            help = "Changelog to read"
+        )]
+        changelog: PathBuf,
+
+        /// Version to write notes for
+        #[arg(
+            long,
+            value_name = "VERSION",
+            help = "Changelog version to use (defaults to the first entry)"
+        )]
+        version: Option<String>,
+
+        /// Audiences to write for
+        #[arg(
+            long = "audience",
+            value_name = "AUDIENCE",
+            value_delimiter = ',',
+            help = "end-user, developer and/or migration (defaults to all)"
+        )]
+        audiences: Vec<String>,
+
+        /// Directory for the generated files
+        #[arg(
+            long,
+            value_name = "DIR",
+            default_value = ".",
+            help = "Directory to write the notes to"
+        )]
+        output_dir: PathBuf,
+
+        /// GitHub release to attach the notes to
+        #[arg(
+            long,
+            value_name = "TAG",
+            help = "Upload the notes to this GitHub release with gh"
+        )]
+        attach: Option<String>,
+    },
 }
 
 /// Subcommands for managing tool permissions
@@ -1221,6 +1362,17 @@ enum OrchestratorCommand {
     },
 }
 
+/// Subcommands for managing stored data
+#[derive(Subcommand, Debug)]
+enum StorageCommand {
+    /// Turn on encryption at rest and encrypt existing data
+    #[command(about = "Encrypt stored sessions, memories and checkpoints")]
+    Encrypt {},
+    /// Show whether stored data is encrypted
+    #[command(about = "Show encryption at rest status")]
+    Status {},
+}
+
 /// Subcommands for managing the remote access tunnel
 #[derive(Subcommand, Debug)]
 enum TunnelCommand {
@@ -1235,6 +1387,24 @@ enum TunnelCommand {
     Status {},
 }
 
+/// Subcommands for the goosed emergency stop
+#[derive(Subcommand, Debug)]
+enum EmergencyCommand {
+    /// Cancel every running reply, pause schedules and block new work
+    #[command(about = "Cancel all running work and block new work")]
+    Stop {
+        /// Shown in the status and written to the audit log
+        #[arg(long)]
+        reason: Option<String>,
+    },
+    /// Lift the stop after confirming a one-time code
+    #[command(about = "Resume work after confirming a one-time code")]
+    Resume {},
+    /// Show whether work is stopped
+    #[command(about = "Show emergency stop status")]
+    Status {},
+}
+
 #[derive(Subcommand)]
 enum TermCommand {
     /// Print shell initialization script
@@ -1331,10 +1501,24 @@ fn get_command_name(command: &Option<Command>) -> &'static str {
         Some(Command::Orchestrator { .. }) => "orchestrator",
         Some(Command::Tunnel { .. }) => "tunnel",
         Some(Command::Cost {}) => "cost",
+        Some(Command::Emergency { .. }) => "emergency",
+        Some(Command::Storage { .. }) => "storage",
+        Some(Command::Bench { .. }) => "bench",
+        Some(Command::ReleaseNotes { .. }) => "release_notes",
         None => "default_session",
     }
 }
 
+/// Points provider creation at a recording file for --record-provider / --replay-provider.
+fn set_provider_recording_env(session_opts: &SessionOptions) {
+    if let Some(path) = &session_opts.record_provider {
+        std::env::set_var("GOOSE_PROVIDER_RECORD", path);
+    }
+    if let Some(path) = &session_opts.replay_provider {
+        std::env::set_var("GOOSE_PROVIDER_REPLAY", path);
+    }
+}
+
 async fn handle_mcp_command(server: McpCommand) -> Result<()> {
     let name = server.name();
     let _ = crate::logging::setup_logging(Some(&format!("mcp-{name}")));
@@ -1467,6 +1651,8 @@ async fn handle_interactive_session(
         }
     }
 
+    set_provider_recording_env(&session_opts);
+
     // Wire --thinking flag to environment variables so ModelConfig picks them up
     if let Some(thinking_budget) = session_opts.thinking {
         std::env::set_var("GOOSE_THINKING", "true");
@@ -1499,6 +1685,7 @@ async fn handle_interactive_session(
         output_format: "text".to_string(),
         container: session_opts.container.map(Container::new),
         sandbox: session_opts.sandbox,
+        devcontainer: session_opts.devcontainer,
         approval_policy: session_opts.approval_policy.clone(),
         execution_mode: session_opts.execution_mode.clone(),
     })
@@ -1691,6 +1878,8 @@ async fn handle_run_command(
     let session_id =
         get_or_create_session_id(identifier, run_behavior.resume, run_behavior.no_session).await?;
 
+    set_provider_recording_env(&session_opts);
+
     // Wire --thinking flag to environment variables so ModelConfig picks them up
     if let Some(thinking_budget) = session_opts.thinking {
         std::env::set_var("GOOSE_THINKING", "true");
@@ -1725,6 +1914,7 @@ async fn handle_run_command(
         output_format: output_opts.output_format,
         container: session_opts.container.map(Container::new),
         sandbox: session_opts.sandbox,
+        devcontainer: session_opts.devcontainer,
         approval_policy: session_opts.approval_policy.clone(),
         execution_mode: session_opts.execution_mode.clone(),
     })
@@ -1843,6 +2033,7 @@ async fn handle_default_session() -> Result<()> {
         output_format: "text".to_string(),
         container: None,
         sandbox: false,
+        devcontainer: false,
         approval_policy: None,
         execution_mode: None,
     })
@@ -1955,6 +2146,23 @@ pub async fn cli() -> anyhow::Result<()> {
         Some(Command::Orchestrator { command }) => handle_orchestrator_command(command).await,
         Some(Command::Tunnel { command }) => handle_tunnel_command(command).await,
         Some(Command::Cost {}) => handle_cost_status().await,
+        Some(Command::Emergency { command }) => handle_emergency_command(command).await,
+        Some(Command::Storage { command }) => handle_storage_command(command).await,
+        Some(Command::Bench {
+            corpus,
+            matrix,
+            baseline,
+            save_baseline,
+            max_cost,
+            report,
+        }) => handle_bench(corpus, matrix, baseline, save_baseline, max_cost, report).await,
+        Some(Command::ReleaseNotes {
+            changelog,
+            version,
+            audiences,
+            output_dir,
+            attach,
+        }) => handle_release_notes(changelog, version, audiences, output_dir, attach).await,
         None => handle_default_session().await,
     }
 }
@@ -1986,6 +2194,21 @@ async fn handle_orchestrator_command(command: OrchestratorCommand) -> Result<()>
     }
 }
 
+async fn handle_storage_command(command: StorageCommand) -> Result<()> {
+    match command {
+        StorageCommand::Encrypt {} => handle_storage_encrypt().await,
+        StorageCommand::Status {} => handle_storage_status().await,
+    }
+}
+
+async fn handle_emergency_command(command: EmergencyCommand) -> Result<()> {
+    match command {
+        EmergencyCommand::Stop { reason } => handle_emergency_stop(reason).await,
+        EmergencyCommand::Resume {} => handle_emergency_resume().await,
+        EmergencyCommand::Status {} => handle_emergency_status().await,
+    }
+}
+
 async fn handle_tunnel_command(command: TunnelCommand) -> Result<()> {
     match command {
         TunnelCommand::Start {} => handle_tunnel_start().await,
@@ -0,0 +1,81 @@
+use std::path::PathBuf;
+
+use anyhow::{bail, Result};
+use goose::agents::benchmark_matrix::{
+    builtin_corpus, load_corpus, BaselineStore, BenchmarkMatrix, CliExecutor, MatrixEntry,
+    MatrixRunner,
+};
+use goose::agents::core_selector::CoreExperience;
+
+pub async fn handle_bench(
+    corpus: Option<PathBuf>,
+    matrix: Option<PathBuf>,
+    baseline: Option<String>,
+    save_baseline: Option<String>,
+    max_cost: Option<f64>,
+    report_path: Option<PathBuf>,
+) -> Result<()> {
+    let corpus = match corpus {
+        Some(dir) => load_corpus(&dir)?,
+        None => builtin_corpus(),
+    };
+    if corpus.is_empty() {
+        bail!("benchmark corpus is empty");
+    }
+    let matrix = match matrix {
+        Some(path) => BenchmarkMatrix::load(&path)?,
+        None => BenchmarkMatrix {
+            entries: vec![MatrixEntry::current()],
+        },
+    };
+
+    let executor = CliExecutor::new(std::env::current_exe()?);
+    let mut runner = MatrixRunner::new(Box::new(executor));
+    if let Some(max_cost) = max_cost {
+        runner = runner.with_max_total_cost(max_cost);
+    }
+
+    println!(
+        "Running {} tasks across {} matrix entries...",
+        corpus.len(),
+        matrix.entries.len()
+    );
+    let report = runner.run(&corpus, &matrix).await?;
+    println!("{}", report);
+
+    let mut experience = CoreExperience::load_default();
+    experience.record_matrix(&report, &corpus);
+    if let Err(e) = experience.save() {
+        eprintln!("Could not record core experience: {}", e);
+    }
+
+    if let Some(path) = report_path {
+        report.save(&path)?;
+        println!("Report written to {}", path.display());
+    }
+
+    let store = BaselineStore::default_store();
+    let mut gate_failed = false;
+    if let Some(name) = baseline {
+        match store.load(&name)? {
+            Some(previous) => {
+                println!("Compared with baseline '{}':", name);
+                for comparison in report.compare(&previous) {
+                    print!("{}", comparison);
+                    gate_failed |= !comparison.passes_gate();
+                }
+            }
+            None => println!("Baseline '{}' not found, skipping comparison", name),
+        }
+    }
+
+    if let Some(name) = save_baseline {
+        let path = store.save(&name, &report)?;
+        println!("Baseline '{}' saved to {}", name, path.display());
+    }
+
+    if gate_failed {
+        bail!("benchmark regressed against baseline");
+    }
+    Ok(())
+}
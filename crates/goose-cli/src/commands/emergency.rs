@@ -0,0 +1,112 @@
+use anyhow::Result;
+use serde_json::{json, Value};
+
+const DEFAULT_SERVER_URL: &str = "http://127.0.0.1:3284";
+
+fn server_url() -> String {
+    std::env::var("GOOSE_SERVER_URL").unwrap_or_else(|_| DEFAULT_SERVER_URL.to_string())
+}
+
+/// Sends a request to the goosed emergency stop routes and returns the JSON reply
+async fn call(method: reqwest::Method, path: &str, body: Option<Value>) -> Result<Value> {
+    let base = server_url();
+    let secret_key =
+        std::env::var("GOOSE_SERVER__SECRET_KEY").unwrap_or_else(|_| "test".to_string());
+    let mut request = reqwest::Client::new()
+        .request(method, format!("{}{}", base, path))
+        .header("X-Secret-Key", secret_key)
+        .timeout(std::time::Duration::from_secs(30));
+    if let Some(body) = body {
+        request = request.json(&body);
+    }
+
+    let resp = request.send().await.map_err(|e| {
+        anyhow::anyhow!(
+            "Failed to connect to goosed server at {}. Is `goosed` running?\n  Error: {}",
+            base,
+            e
+        )
+    })?;
+
+    if !resp.status().is_success() {
+        let status = resp.status();
+        let body = resp.text().await.unwrap_or_default();
+        anyhow::bail!("Emergency stop request failed (HTTP {}): {}", status, body);
+    }
+    resp.json()
+        .await
+        .map_err(|e| anyhow::anyhow!("Server returned non-JSON response: {}", e))
+}
+
+fn print_status(status: &Value) {
+    let stopped = status["stopped"].as_bool().unwrap_or(false);
+    println!("Emergency Stop");
+    println!("{}", "-".repeat(50));
+    println!("  Stopped:        {}", if stopped { "yes" } else { "no" });
+    if let Some(at) = status["stopped_at"].as_str() {
+        println!("  Since:          {}", at);
+    }
+    if let Some(reason) = status["reason"].as_str() {
+        println!("  Reason:         {}", reason);
+    }
+    if let Some(schedules) = status["paused_schedules"].as_array() {
+        if !schedules.is_empty() {
+            let names: Vec<&str> = schedules.iter().filter_map(|s| s.as_str()).collect();
+            println!("  Paused:         {}", names.join(", "));
+        }
+    }
+    if status["resume_pending"].as_bool().unwrap_or(false) {
+        println!("  Resume:         awaiting confirmation");
+    }
+    println!(
+        "  Active replies: {}",
+        status["active_replies"].as_u64().unwrap_or(0)
+    );
+    println!("{}", "-".repeat(50));
+}
+
+/// Cancel all running work on the goosed server and block new work
+pub async fn handle_emergency_stop(reason: Option<String>) -> Result<()> {
+    let status = call(
+        reqwest::Method::POST,
+        "/system/emergency-stop",
+        Some(json!({ "reason": reason })),
+    )
+    .await?;
+    print_status(&status);
+    Ok(())
+}
+
+/// Show whether the goosed server is stopped
+pub async fn handle_emergency_status() -> Result<()> {
+    let status = call(reqwest::Method::GET, "/system/emergency-stop", None).await?;
+    print_status(&status);
+    Ok(())
+}
+
+/// Lift the emergency stop: request a confirmation code, then send it back
+/// once the user has typed it in
+pub async fn handle_emergency_resume() -> Result<()> {
+    let challenge = call(reqwest::Method::POST, "/system/emergency-stop/resume", None).await?;
+    let code = challenge["confirmation_code"]
+        .as_str()
+        .ok_or_else(|| anyhow::anyhow!("Server did not return a confirmation code"))?;
+
+    println!("Confirmation code: {}", code);
+    if let Some(expires_at) = challenge["expires_at"].as_str() {
+        println!("Expires at:        {}", expires_at);
+    }
+    let entered: String = cliclack::input("Type the confirmation code to resume").interact()?;
+    if entered.trim() != code {
+        anyhow::bail!("Confirmation code does not match; the emergency stop is still active");
+    }
+
+    let status = call(
+        reqwest::Method::POST,
+        "/system/emergency-stop/resume/confirm",
+        Some(json!({ "confirmation_code": code })),
+    )
+    .await?;
+    print_status(&status);
+    Ok(())
+}
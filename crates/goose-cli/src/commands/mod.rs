@@ -1,12 +1,16 @@
+pub mod bench;
 pub mod configure;
 pub mod cost;
+pub mod emergency;
 pub mod info;
 pub mod orchestrator;
 pub mod permissions;
 pub mod project;
 pub mod recipe;
+pub mod release_notes;
 pub mod schedule;
 pub mod session;
+pub mod storage;
 pub mod term;
 pub mod tunnel;
 pub mod update;
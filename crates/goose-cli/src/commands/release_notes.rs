@@ -0,0 +1,93 @@
+use std::path::PathBuf;
+use std::process::Command;
+
+use anyhow::{anyhow, bail, Context, Result};
+use goose::config::Config;
+use goose::model::ModelConfig;
+use goose::release_notes::{generate_release_notes, parse_changelog, Audience};
+
+pub async fn handle_release_notes(
+    changelog: PathBuf,
+    version: Option<String>,
+    audiences: Vec<String>,
+    output_dir: PathBuf,
+    attach: Option<String>,
+) -> Result<()> {
+    let markdown = std::fs::read_to_string(&changelog)
+        .with_context(|| format!("Failed to read {}", changelog.display()))?;
+    let entries = parse_changelog(&markdown);
+    let entry = match &version {
+        Some(version) => entries
+            .iter()
+            .find(|entry| entry.version.trim_start_matches('v') == version.trim_start_matches('v'))
+            .ok_or_else(|| anyhow!("Version {} not found in {}", version, changelog.display()))?,
+        None => entries
+            .first()
+            .ok_or_else(|| anyhow!("No versions found in {}", changelog.display()))?,
+    };
+
+    let audiences = if audiences.is_empty() {
+        Audience::ALL.to_vec()
+    } else {
+        audiences
+            .iter()
+            .map(|a| a.parse())
+            .collect::<Result<Vec<Audience>>>()?
+    };
+
+    let config = Config::global();
+    let provider_name = config
+        .get_goose_provider()
+        .map_err(|_| anyhow!("No provider configured. Run 'goose configure' first"))?;
+    let model = config
+        .get_goose_model()
+        .map_err(|_| anyhow!("No model configured. Run 'goose configure' first"))?;
+    let provider = goose::providers::create(&provider_name, ModelConfig::new(&model)?).await?;
+
+    println!(
+        "Writing release notes for {} ({})...",
+        entry.version,
+        audiences
+            .iter()
+            .map(ToString::to_string)
+            .collect::<Vec<_>>()
+            .join(", ")
+    );
+    let notes = generate_release_notes(provider.as_ref(), entry, &audiences).await?;
+    if audiences.contains(&Audience::Migration)
+        && !notes.iter().any(|n| n.audience == Audience::Migration)
+    {
+        println!("No breaking changes listed, skipping migration notes");
+    }
+
+    std::fs::create_dir_all(&output_dir)?;
+    let mut files = Vec::new();
+    for note in &notes {
+        let path = output_dir.join(note.file_name());
+        std::fs::write(&path, format!("{}\n", note.content))?;
+        println!("  {} -> {}", note.audience, path.display());
+        files.push(path);
+    }
+
+    if let Some(tag) = attach {
+        attach_to_release(&tag, &files)?;
+        println!("Attached {} files to GitHub release {}", files.len(), tag);
+    }
+    Ok(())
+}
+
+/// Uploads the notes as assets of an existing GitHub release using `gh`.
+fn attach_to_release(tag: &str, files: &[PathBuf]) -> Result<()> {
+    if files.is_empty() {
+        return Ok(());
+    }
+    let status = Command::new("gh")
+        .args(["release", "upload", tag, "--clobber"])
+        .args(files.iter().map(PathBuf::as_os_str))
+        .status()
+        .context("Failed to run gh; is the GitHub CLI installed?")?;
+    if !status.success() {
+        bail!("gh release upload {} failed with {}", tag, status);
+    }
+    Ok(())
+}
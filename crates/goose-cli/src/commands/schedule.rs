@@ -87,8 +87,12 @@ pub async fn handle_schedule_add(
         last_run: None,
         currently_running: false,
         paused: false,
+        paused_by_upstream: false,
         current_session_id: None,
         process_start_time: None,
+        depends_on: Vec::new(),
+        last_status: None,
+        last_finished: None,
     };
 
     let scheduler_storage_path =
@@ -0,0 +1,56 @@
+use anyhow::Result;
+use goose::agents::persistence::SqliteCheckpointer;
+use goose::config::Config;
+use goose::security::at_rest;
+use goose::session::SessionManager;
+
+/// Turns on encryption at rest and seals everything stored before it was on.
+pub async fn handle_storage_encrypt() -> Result<()> {
+    Config::global().set_param(at_rest::ENCRYPT_AT_REST_KEY, true)?;
+    at_rest::ensure_key()?;
+    println!("Encryption at rest is on. New data is encrypted as it is written.");
+
+    let session_manager = SessionManager::instance();
+    let messages = session_manager.seal_existing_messages().await?;
+    println!("  Encrypted {} stored messages", messages);
+
+    let checkpoint_path = SqliteCheckpointer::default_path();
+    if checkpoint_path.exists() {
+        let checkpoints = SqliteCheckpointer::new(&checkpoint_path)
+            .await?
+            .seal_existing()
+            .await?;
+        println!("  Encrypted {} checkpoints", checkpoints);
+    }
+
+    if goose::memory::seal_existing_snapshot()? {
+        println!("  Encrypted the memory snapshot");
+    }
+
+    let backups = session_manager.list_backups()?;
+    if !backups.is_empty() {
+        println!();
+        println!("These database backups were taken before encryption and still hold plaintext:");
+        for backup in &backups {
+            println!("  {}", backup.display());
+        }
+        println!("Delete them once you no longer need them.");
+    }
+    Ok(())
+}
+
+pub async fn handle_storage_status() -> Result<()> {
+    println!(
+        "Encryption at rest: {}",
+        if at_rest::is_enabled() { "on" } else { "off" }
+    );
+    println!(
+        "Storage key: {}",
+        if at_rest::may_have_sealed_data() {
+            "present"
+        } else {
+            "not created"
+        }
+    );
+    Ok(())
+}
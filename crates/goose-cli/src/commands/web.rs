@@ -13,6 +13,7 @@ use axum::{
 };
 use base64::Engine;
 use futures::{sink::SinkExt, stream::StreamExt};
+use goose::agents::stream_chunking::chunk_events;
 use goose::agents::{Agent, AgentEvent};
 use goose::conversation::message::Message as GooseMessage;
 use goose::session::session_manager::SessionType;
@@ -574,7 +575,8 @@ async fn process_message_streaming(
     };
 
     match agent.reply(user_message, session_config, None).await {
-        Ok(mut stream) => {
+        Ok(stream) => {
+            let mut stream = chunk_events(stream);
             while let Some(result) = stream.next().await {
                 match result {
                     Ok(AgentEvent::Message(message)) => {
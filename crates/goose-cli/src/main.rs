@@ -32,5 +32,14 @@ async fn main() -> Result<()> {
         goose::tracing::shutdown_otlp();
     }
 
+    // Headless runs that did not complete exit with a code for their failure
+    if let Some(failed) = result
+        .as_ref()
+        .err()
+        .and_then(|e| e.downcast_ref::<goose_cli::session::RunFailed>())
+    {
+        std::process::exit(failed.exit_code);
+    }
+
     result
 }
@@ -171,6 +171,7 @@ mod tests {
                     available_tools: Vec::new(),
                 },
             ]),
+            dependencies: None,
             settings: None,
             activities: None,
             author: None,
@@ -178,6 +179,7 @@ mod tests {
             response: None,
             sub_recipes: None,
             retry: None,
+            validation: None,
         }
     }
 
@@ -214,6 +216,7 @@ mod tests {
             instructions: Some("Test instructions".to_string()),
             prompt: None,
             extensions: None,
+            dependencies: None,
             settings: None,
             activities: None,
             author: None,
@@ -221,6 +224,7 @@ mod tests {
             response: None,
             sub_recipes: None,
             retry: None,
+            validation: None,
         };
 
         let secrets = discover_recipe_secrets(&recipe);
@@ -259,6 +263,7 @@ mod tests {
                     available_tools: Vec::new(),
                 },
             ]),
+            dependencies: None,
             settings: None,
             activities: None,
             author: None,
@@ -266,6 +271,7 @@ mod tests {
             response: None,
             sub_recipes: None,
             retry: None,
+            validation: None,
         };
 
         let secrets = discover_recipe_secrets(&recipe);
@@ -313,12 +319,14 @@ mod tests {
                 sequential_when_repeated: false,
                 description: None,
             }]),
+            dependencies: None,
             settings: None,
             activities: None,
             author: None,
             parameters: None,
             response: None,
             retry: None,
+            validation: None,
         };
 
         let secrets = discover_recipe_secrets(&recipe);
@@ -3,6 +3,7 @@ use crate::cli::StreamableHttpOptions;
 use super::output;
 use super::CliSession;
 use console::style;
+use goose::agents::devcontainer;
 use goose::agents::{Agent, Container, ContainerConfig};
 use goose::config::get_enabled_extensions;
 use goose::config::resolve_extensions_for_new_session;
@@ -119,6 +120,8 @@ pub struct SessionBuilderConfig {
     pub container: Option<Container>,
     /// Auto-create a Docker sandbox container for isolated execution
     pub sandbox: bool,
+    /// Run the session in the workspace's devcontainer.json container
+    pub devcontainer: bool,
     /// Approval policy for shell commands (safe, paranoid, autopilot)
     pub approval_policy: Option<String>,
     /// Execution mode for the agent (freeform, structured)
@@ -151,6 +154,7 @@ impl Default for SessionBuilderConfig {
             output_format: "text".to_string(),
             container: None,
             sandbox: false,
+            devcontainer: false,
             approval_policy: None,
             execution_mode: None,
         }
@@ -378,6 +382,38 @@ pub async fn build_session(session_config: SessionBuilderConfig) -> CliSession {
                 );
             }
         }
+    } else if session_config.devcontainer {
+        let workspace = std::env::current_dir().unwrap_or_default();
+        match devcontainer::launch_for_workspace(&workspace) {
+            Ok(Some((container, launch))) => {
+                eprintln!(
+                    "{}",
+                    style(format!(
+                        "Dev container {} started from {} with the workspace at {}",
+                        &launch.container_id[..launch.container_id.len().min(12)],
+                        launch.image,
+                        launch.workspace_folder
+                    ))
+                    .dim()
+                );
+                agent.set_container(Some(container)).await;
+            }
+            Ok(None) => {
+                eprintln!(
+                    "{} No devcontainer.json in {}. Continuing without a container.",
+                    style("warning:").yellow().bold(),
+                    workspace.display()
+                );
+            }
+            Err(e) => {
+                tracing::error!(error = %e, "Failed to start dev container");
+                eprintln!(
+                    "{} Failed to start dev container: {:#}",
+                    style("error:").red().bold(),
+                    e
+                );
+            }
+        }
     }
 
     // When sandbox mode is active and no explicit approval policy was set, default to autopilot
@@ -403,7 +439,7 @@ pub async fn build_session(session_config: SessionBuilderConfig) -> CliSession {
     if let Some(mode_str) = session_config.execution_mode {
         match mode_str.parse::<goose::agents::ExecutionMode>() {
             Ok(mode) => {
-                agent.set_execution_mode(mode).await;
+                agent.set_core(mode, "requested for this session").await;
                 tracing::info!("Using execution mode: {}", mode);
             }
             Err(e) => {
@@ -472,9 +508,13 @@ pub async fn build_session(session_config: SessionBuilderConfig) -> CliSession {
         .apply_recipe_components(
             recipe.and_then(|r| r.sub_recipes.clone()),
             recipe.and_then(|r| r.response.clone()),
+            recipe.and_then(|r| r.validation.clone()),
             true,
         )
         .await;
+    if let Some(template) = recipe_settings.and_then(|s| s.system_prompt_template.clone()) {
+        agent.set_system_prompt_template(Some(template)).await;
+    }
 
     let new_provider = match create(&provider_name, model_config).await {
         Ok(provider) => provider,
@@ -729,6 +769,7 @@ mod tests {
             output_format: "text".to_string(),
             container: None,
             sandbox: false,
+            devcontainer: false,
             approval_policy: None,
             execution_mode: None,
         };
@@ -1,4 +1,5 @@
 use console::style;
+use goose::elicitation_form::{PREVIEW_KEY, WIDGET_KEY};
 use serde_json::Value;
 use std::collections::HashMap;
 use std::io::{self, BufRead, IsTerminal, Write};
@@ -33,13 +34,49 @@ pub fn collect_elicitation_input(
         let description = field_schema.get("description").and_then(|d| d.as_str());
         let default = field_schema.get("default");
         let enum_values = field_schema.get("enum").and_then(|e| e.as_array());
+        let widget = field_schema.get(WIDGET_KEY).and_then(|w| w.as_str());
+        let label = match description {
+            Some(desc) => format!("{} ({})", name, desc),
+            None => name.clone(),
+        };
+
+        if let Some(preview) = field_schema.get(PREVIEW_KEY).and_then(|p| p.as_str()) {
+            println!("{}", preview);
+        }
+
+        if widget == Some("password") {
+            match cliclack::password(&label).mask('▪').interact() {
+                Ok(v) => {
+                    data.insert(name.clone(), Value::String(v));
+                }
+                Err(e) if e.kind() == io::ErrorKind::Interrupted => return Ok(None),
+                Err(e) => return Err(e),
+            }
+            continue;
+        }
+
+        if widget == Some("multi_select") {
+            let options: Vec<&str> = field_schema
+                .pointer("/items/enum")
+                .and_then(|e| e.as_array())
+                .map(|arr| arr.iter().filter_map(|v| v.as_str()).collect())
+                .unwrap_or_default();
+            match cliclack::multiselect(&label)
+                .required(is_required)
+                .items(&options.iter().map(|o| (*o, *o, "")).collect::<Vec<_>>())
+                .interact()
+            {
+                Ok(selected) => {
+                    data.insert(name.clone(), serde_json::json!(selected));
+                }
+                Err(e) if e.kind() == io::ErrorKind::Interrupted => return Ok(None),
+                Err(e) => return Err(e),
+            }
+            continue;
+        }
 
         // makes a little true/false toggle
         if field_type == "boolean" {
-            let label = match description {
-                Some(desc) => format!("{} ({})", name, desc),
-                None => name.clone(),
-            };
             let default_bool = default.and_then(|v| v.as_bool()).unwrap_or(false);
 
             match cliclack::confirm(&label)
@@ -64,6 +101,9 @@ pub fn collect_elicitation_input(
         if let Some(desc) = description {
             print!(" {}", style(format!("({})", desc)).dim());
         }
+        if let Some(kind @ ("file" | "directory")) = widget {
+            print!(" {}", style(format!("[{} path]", kind)).dim());
+        }
         if is_required {
             print!("{}", style("*").red());
         }
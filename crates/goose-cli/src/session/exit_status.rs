@@ -0,0 +1,119 @@
+//! Outcome of a non-interactive run, for scripts and CI.
+//!
+//! A failure is classified by the [`ProviderError`] behind it, if any: its
+//! code is the one goosed reports for the same error, such as
+//! `provider_rate_limited` (or `internal`), and it is retryable when the
+//! provider retry loop would retry it. Each failure also
+//! maps to a process exit code:
+//!
+//! | exit | meaning                                  |
+//! |-----:|------------------------------------------|
+//! |    0 | completed                                |
+//! |    1 | internal error                           |
+//! |    3 | provider authentication failed           |
+//! |    4 | provider rate limited                    |
+//! |    5 | context length exceeded                  |
+//! |    6 | provider unavailable or request failed   |
+//! |  130 | cancelled                                |
+
+use goose::providers::errors::{ProviderError, ProviderErrorKind};
+use goose::providers::should_retry;
+use serde::Serialize;
+
+pub const EXIT_CANCELLED: i32 = 130;
+
+fn provider_error(err: &anyhow::Error) -> Option<&ProviderError> {
+    err.chain()
+        .find_map(|cause| cause.downcast_ref::<ProviderError>())
+}
+
+fn exit_code(err: Option<&ProviderError>) -> i32 {
+    match err.map(ProviderError::kind) {
+        None => 1,
+        Some(ProviderErrorKind::AuthFailed) => 3,
+        Some(ProviderErrorKind::RateLimited) => 4,
+        Some(ProviderErrorKind::ContextLengthExceeded) => 5,
+        Some(_) => 6,
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct RunError {
+    pub message: String,
+    pub code: &'static str,
+    pub category: &'static str,
+    pub retryable: bool,
+    #[serde(skip)]
+    pub exit_code: i32,
+}
+
+impl RunError {
+    pub fn from_anyhow(err: &anyhow::Error) -> Self {
+        let provider_error = provider_error(err);
+        Self {
+            message: err.to_string(),
+            code: provider_error.map_or("internal", |err| err.kind().code()),
+            category: if provider_error.is_some() {
+                "provider"
+            } else {
+                "internal"
+            },
+            retryable: provider_error.is_some_and(should_retry),
+            exit_code: exit_code(provider_error),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum RunStatus {
+    #[default]
+    Completed,
+    Cancelled,
+    Failed,
+}
+
+/// Returned from a headless run that did not complete so the process exits
+/// with the matching code instead of the generic 1.
+#[derive(Debug)]
+pub struct RunFailed {
+    pub exit_code: i32,
+}
+
+impl std::fmt::Display for RunFailed {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "run did not complete (exit code {})", self.exit_code)
+    }
+}
+
+impl std::error::Error for RunFailed {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_provider_errors_map_to_codes_through_context() {
+        let err = anyhow::Error::new(ProviderError::RateLimitExceeded {
+            details: "slow down".to_string(),
+            retry_delay: None,
+        })
+        .context("reply failed");
+        let run_error = RunError::from_anyhow(&err);
+        assert_eq!(run_error.code, "provider_rate_limited");
+        assert!(run_error.retryable);
+        assert_eq!(run_error.exit_code, 4);
+
+        let err = anyhow::Error::new(ProviderError::Authentication("bad key".to_string()));
+        let run_error = RunError::from_anyhow(&err);
+        assert!(!run_error.retryable);
+        assert_eq!(run_error.exit_code, 3);
+
+        let err = anyhow::anyhow!("boom");
+        assert_eq!(RunError::from_anyhow(&err).exit_code, 1);
+        assert_eq!(
+            serde_json::to_value(RunError::from_anyhow(&err)).unwrap()["code"],
+            "internal"
+        );
+    }
+}
@@ -2,6 +2,7 @@ mod builder;
 mod completion;
 mod editor;
 mod elicitation;
+mod exit_status;
 mod export;
 mod input;
 mod output;
@@ -9,6 +10,7 @@ mod prompt;
 mod task_execution_display;
 mod thinking;
 
+use crate::session::exit_status::{RunError, RunStatus, EXIT_CANCELLED};
 use crate::session::task_execution_display::{
     format_task_execution_notification, TASK_EXECUTION_NOTIFICATION_TYPE,
 };
@@ -18,9 +20,12 @@ use std::str::FromStr;
 use tokio::signal::ctrl_c;
 use tokio_util::task::AbortOnDropHandle;
 
+pub use self::exit_status::RunFailed;
 pub use self::export::message_to_markdown;
 pub use builder::{build_session, SessionBuilderConfig};
 use console::Color;
+use goose::agents::final_output_tool::FINAL_OUTPUT_TOOL_NAME;
+use goose::agents::stream_chunking::chunk_events;
 use goose::agents::subagent_handler::SUBAGENT_TOOL_REQUEST_TYPE;
 use goose::agents::AgentEvent;
 use goose::permission::permission_confirmation::PrincipalType;
@@ -43,7 +48,7 @@ use rmcp::model::{ErrorCode, ErrorData};
 use goose::config::paths::Paths;
 use goose::conversation::message::{ActionRequiredData, Message, MessageContent};
 use rustyline::EditMode;
-use serde::{Deserialize, Serialize};
+use serde::Serialize;
 use serde_json::Value;
 use std::collections::HashMap;
 use std::path::PathBuf;
@@ -53,16 +58,18 @@ use tokio;
 use tokio_util::sync::CancellationToken;
 use tracing::warn;
 
-#[derive(Serialize, Deserialize, Debug)]
+#[derive(Serialize, Debug)]
 struct JsonOutput {
     messages: Vec<Message>,
     metadata: JsonMetadata,
 }
 
-#[derive(Serialize, Deserialize, Debug)]
+#[derive(Serialize, Debug)]
 struct JsonMetadata {
     total_tokens: Option<i32>,
-    status: String,
+    status: RunStatus,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    error: Option<RunError>,
 }
 
 #[derive(Serialize, Debug)]
@@ -80,12 +87,38 @@ enum StreamEvent {
         model: String,
         mode: String,
     },
+    ToolCall {
+        id: String,
+        name: String,
+        arguments: Value,
+    },
+    /// Output of the recipe's final output tool
+    FinalOutput {
+        output: Value,
+    },
     Error {
         error: String,
+        code: &'static str,
+        category: &'static str,
+        retryable: bool,
+    },
+    Cost {
+        input_tokens: Option<i32>,
+        output_tokens: Option<i32>,
+        total_tokens: Option<i32>,
+        /// Estimated from known model pricing, if available
+        cost_usd: Option<f64>,
     },
     Complete {
         total_tokens: Option<i32>,
     },
+    /// Last event of a headless run
+    Exit {
+        status: RunStatus,
+        exit_code: i32,
+        #[serde(skip_serializing_if = "Option::is_none")]
+        error: Option<RunError>,
+    },
 }
 
 #[derive(Serialize, Debug)]
@@ -165,6 +198,8 @@ pub struct CliSession {
     edit_mode: Option<EditMode>,
     retry_config: Option<RetryConfig>,
     output_format: String,
+    run_status: RunStatus,
+    run_error: Option<RunError>,
 }
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
@@ -260,6 +295,8 @@ impl CliSession {
             edit_mode,
             retry_config,
             output_format,
+            run_status: RunStatus::default(),
+            run_error: None,
         }
     }
 
@@ -911,14 +948,47 @@ impl CliSession {
         Ok(())
     }
 
-    /// Process a single message and exit
+    /// Process a single message and exit. Fails with [`RunFailed`] when the
+    /// run errors or is cancelled so the process exits with a matching code.
     pub async fn headless(&mut self, prompt: String) -> Result<()> {
         let message = Message::user().with_text(&prompt);
-        self.process_message(message, CancellationToken::default())
-            .await?;
+        let is_stream_json_mode = self.output_format == "stream-json";
+        if let Err(e) = self
+            .process_message(message, CancellationToken::default())
+            .await
+        {
+            handle_agent_error(&e, is_stream_json_mode);
+            self.record_failure(&e);
+        }
+
+        let exit_code = self.exit_code();
+        if is_stream_json_mode {
+            emit_stream_event(&StreamEvent::Exit {
+                status: self.run_status,
+                exit_code,
+                error: self.run_error.clone(),
+            });
+        }
+        if exit_code != 0 {
+            return Err(RunFailed { exit_code }.into());
+        }
         Ok(())
     }
 
+    fn record_failure(&mut self, e: &anyhow::Error) {
+        self.run_status = RunStatus::Failed;
+        self.run_error = Some(RunError::from_anyhow(e));
+    }
+
+    /// Process exit code for the outcome of the last run.
+    pub fn exit_code(&self) -> i32 {
+        match self.run_status {
+            RunStatus::Completed => 0,
+            RunStatus::Cancelled => EXIT_CANCELLED,
+            RunStatus::Failed => self.run_error.as_ref().map_or(1, |error| error.exit_code),
+        }
+    }
+
     async fn process_agent_response(
         &mut self,
         interactive: bool,
@@ -926,6 +996,8 @@ impl CliSession {
     ) -> Result<()> {
         let is_json_mode = self.output_format == "json";
         let is_stream_json_mode = self.output_format == "stream-json";
+        self.run_status = RunStatus::Completed;
+        self.run_error = None;
 
         let session_config = SessionConfig {
             id: self.session_id.clone(),
@@ -946,7 +1018,7 @@ impl CliSession {
         });
         let _drop_handle = AbortOnDropHandle::new(handle);
 
-        let mut stream = self
+        let reply = self
             .agent
             .reply(
                 user_message.clone(),
@@ -954,6 +1026,7 @@ impl CliSession {
                 Some(cancel_token.clone()),
             )
             .await?;
+        let mut stream = chunk_events(reply);
 
         let mut progress_bars = output::McpSpinners::new();
         let cancel_token_clone = cancel_token.clone();
@@ -1028,6 +1101,7 @@ impl CliSession {
 
                                 if is_stream_json_mode {
                                     emit_stream_event(&StreamEvent::Message { message: message.clone() });
+                                    emit_tool_call_events(&message);
                                 } else if !is_json_mode {
                                     output::render_message(&message, self.debug);
                                 }
@@ -1056,6 +1130,7 @@ impl CliSession {
                         }
                         Some(Err(e)) => {
                             handle_agent_error(&e, is_stream_json_mode);
+                            self.record_failure(&e);
                             cancel_token_clone.cancel();
                             drop(stream);
                             if let Err(e) = self.handle_interrupted_messages(false).await {
@@ -1075,6 +1150,7 @@ impl CliSession {
                 }
                 _ = cancel_token_clone.cancelled() => {
                     drop(stream);
+                    self.run_status = RunStatus::Cancelled;
                     if let Err(e) = self.handle_interrupted_messages(true).await {
                         eprintln!("Error handling interruption: {}", e);
                     }
@@ -1093,11 +1169,13 @@ impl CliSession {
             {
                 Ok(session) => JsonMetadata {
                     total_tokens: session.total_tokens,
-                    status: "completed".to_string(),
+                    status: self.run_status,
+                    error: self.run_error.clone(),
                 },
                 Err(_) => JsonMetadata {
                     total_tokens: None,
-                    status: "completed".to_string(),
+                    status: self.run_status,
+                    error: self.run_error.clone(),
                 },
             };
             let json_output = JsonOutput {
@@ -1106,14 +1184,24 @@ impl CliSession {
             };
             println!("{}", serde_json::to_string_pretty(&json_output)?);
         } else if is_stream_json_mode {
-            let total_tokens = self
+            let session = self
                 .agent
                 .config
                 .session_manager
                 .get_session(&self.session_id, false)
                 .await
-                .ok()
-                .and_then(|s| s.total_tokens);
+                .ok();
+            let total_tokens = session.as_ref().and_then(|s| s.total_tokens);
+            if let Some(session) = &session {
+                emit_stream_event(&StreamEvent::Cost {
+                    input_tokens: session.input_tokens,
+                    output_tokens: session.output_tokens,
+                    total_tokens,
+                    cost_usd: self
+                        .estimate_cost(session.input_tokens, session.output_tokens)
+                        .await,
+                });
+            }
             emit_stream_event(&StreamEvent::Complete { total_tokens });
         } else {
             println!();
@@ -1297,6 +1385,21 @@ impl CliSession {
         Ok(metadata.total_tokens)
     }
 
+    async fn estimate_cost(
+        &self,
+        input_tokens: Option<i32>,
+        output_tokens: Option<i32>,
+    ) -> Option<f64> {
+        let provider_name = Config::global().get_goose_provider().ok()?;
+        let model_config = self.agent.provider().await.ok()?.get_model_config();
+        output::estimate_cost_usd(
+            &provider_name,
+            &model_config.model_name,
+            input_tokens.unwrap_or(0).max(0) as usize,
+            output_tokens.unwrap_or(0).max(0) as usize,
+        )
+    }
+
     /// Display enhanced context usage with session totals
     pub async fn display_context_usage(&self) -> Result<()> {
         let provider = self.agent.provider().await?;
@@ -1465,6 +1568,34 @@ fn emit_stream_event(event: &StreamEvent) {
     }
 }
 
+/// Emit a `tool_call` event per tool request in `message`, and `final_output`
+/// when the request is the recipe's final output tool.
+fn emit_tool_call_events(message: &Message) {
+    for content in &message.content {
+        let MessageContent::ToolRequest(request) = content else {
+            continue;
+        };
+        let Ok(tool_call) = &request.tool_call else {
+            continue;
+        };
+        let arguments = tool_call
+            .arguments
+            .clone()
+            .map(Value::Object)
+            .unwrap_or_default();
+        if tool_call.name == FINAL_OUTPUT_TOOL_NAME {
+            emit_stream_event(&StreamEvent::FinalOutput {
+                output: arguments.clone(),
+            });
+        }
+        emit_stream_event(&StreamEvent::ToolCall {
+            id: request.id.clone(),
+            name: tool_call.name.to_string(),
+            arguments,
+        });
+    }
+}
+
 /// Prompt user for tool call confirmation, returns the Permission selected
 fn prompt_tool_confirmation(security_prompt: &Option<String>) -> Result<Permission> {
     output::hide_thinking();
@@ -1803,8 +1934,12 @@ fn handle_agent_error(e: &anyhow::Error, is_stream_json_mode: bool) {
     let error_msg = e.to_string();
 
     if is_stream_json_mode {
+        let run_error = RunError::from_anyhow(e);
         emit_stream_event(&StreamEvent::Error {
             error: error_msg.clone(),
+            code: run_error.code,
+            category: run_error.category,
+            retryable: run_error.retryable,
         });
     }
 
@@ -3,6 +3,8 @@ mod editor_models;
 mod lang;
 pub mod paths;
 mod shell;
+mod shell_output;
+mod structural_edit;
 mod text_editor;
 
 pub mod rmcp_developer;
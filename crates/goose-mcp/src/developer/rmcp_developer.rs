@@ -9,9 +9,9 @@ use rmcp::{
     handler::server::{router::tool::ToolRouter, wrapper::Parameters},
     model::{
         CallToolResult, CancelledNotificationParam, Content, ErrorCode, ErrorData,
-        GetPromptRequestParams, GetPromptResult, Implementation, ListPromptsResult, LoggingLevel,
-        LoggingMessageNotificationParam, Meta, PaginatedRequestParams, Prompt, PromptArgument,
-        PromptMessage, PromptMessageRole, Role, ServerCapabilities, ServerInfo,
+        GetPromptRequestParams, GetPromptResult, Implementation, ListPromptsResult, Meta,
+        PaginatedRequestParams, Prompt, PromptArgument, PromptMessage, PromptMessageRole, Role,
+        ServerCapabilities, ServerInfo,
     },
     schemars::JsonSchema,
     service::{NotificationContext, RequestContext},
@@ -20,6 +20,7 @@ use rmcp::{
 
 const WORKING_DIR_HEADER: &str = "agent-working-dir";
 const SESSION_ID_HEADER: &str = "agent-session-id";
+const ENV_HEADER: &str = "agent-env";
 
 fn extract_working_dir_from_meta(meta: &Meta) -> Option<PathBuf> {
     meta.0
@@ -30,6 +31,19 @@ fn extract_working_dir_from_meta(meta: &Meta) -> Option<PathBuf> {
         .map(PathBuf::from)
 }
 
+/// The session's own environment variables, set on top of goosed's.
+fn extract_env_from_meta(meta: &Meta) -> Vec<(OsString, OsString)> {
+    meta.0
+        .get(ENV_HEADER)
+        .and_then(|v| v.as_object())
+        .map(|env| {
+            env.iter()
+                .filter_map(|(key, value)| Some((key.into(), value.as_str()?.into())))
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
 fn extract_session_id_from_meta(meta: &Meta) -> Option<String> {
     meta.0
         .get(SESSION_ID_HEADER)
@@ -70,8 +84,13 @@ use crate::developer::{paths::get_shell_path_dirs, shell::ShellConfig};
 use super::analyze::{types::AnalyzeParams, CodeAnalyzer};
 use super::editor_models::{create_editor_model, EditorModel};
 use super::shell::{configure_shell_command, expand_path, is_absolute_path, kill_process_group};
+use super::shell_output::{
+    OutputBatch, OutputBuffer, ShellOutput, OUTPUT_FLUSH_INTERVAL, TAIL_LINES,
+};
+use super::structural_edit::EditPosition;
 use super::text_editor::{
-    text_editor_insert, text_editor_replace, text_editor_undo, text_editor_view, text_editor_write,
+    text_editor_insert, text_editor_replace, text_editor_symbol_edit, text_editor_undo,
+    text_editor_view, text_editor_write,
 };
 
 /// Parameters for the screen_capture tool
@@ -116,6 +135,14 @@ pub struct TextEditorParams {
 
     /// The line number after which to insert text (0 for beginning). Required for `insert` command.
     pub insert_line: Option<i64>,
+
+    /// Name of a function, method, class or other definition to edit structurally, located by
+    /// parsing the file instead of by string match. Qualify it with its enclosing definition
+    /// when the name is not unique, e.g. `Parser::parse` or `Config.load`.
+    /// With `str_replace`, `new_str` replaces the whole definition and `old_str` is optional:
+    /// pass the definition as you last viewed it and concurrent changes are merged or reported.
+    /// With `insert`, `new_str` goes after the definition, or before it when `insert_line` is 0.
+    pub symbol: Option<String>,
 }
 
 /// Parameters for the shell tool
@@ -806,8 +833,27 @@ impl DeveloperServer {
                 Ok(CallToolResult::success(content))
             }
             "str_replace" => {
+                if let Some(symbol) = params.symbol.as_deref() {
+                    let new_str = params.new_str.ok_or_else(|| {
+                        ErrorData::new(
+                            ErrorCode::INVALID_PARAMS,
+                            "Missing 'new_str' parameter for str_replace command".to_string(),
+                            None,
+                        )
+                    })?;
+                    let content = text_editor_symbol_edit(
+                        &path,
+                        symbol,
+                        EditPosition::Replace,
+                        params.old_str.as_deref(),
+                        &new_str,
+                        &self.file_history,
+                    )
+                    .await?;
+                    Ok(CallToolResult::success(content))
+                }
                 // Check if diff parameter is provided
-                if let Some(ref diff) = params.diff {
+                else if let Some(ref diff) = params.diff {
                     // When diff is provided, old_str and new_str are not required
                     let content = text_editor_replace(
                         &path,
@@ -848,6 +894,30 @@ impl DeveloperServer {
                 }
             }
             "insert" => {
+                if let Some(symbol) = params.symbol.as_deref() {
+                    let new_str = params.new_str.ok_or_else(|| {
+                        ErrorData::new(
+                            ErrorCode::INVALID_PARAMS,
+                            "Missing 'new_str' parameter for insert command".to_string(),
+                            None,
+                        )
+                    })?;
+                    let position = if params.insert_line == Some(0) {
+                        EditPosition::Before
+                    } else {
+                        EditPosition::After
+                    };
+                    let content = text_editor_symbol_edit(
+                        &path,
+                        symbol,
+                        position,
+                        None,
+                        &new_str,
+                        &self.file_history,
+                    )
+                    .await?;
+                    return Ok(CallToolResult::success(content));
+                }
                 let insert_line = params.insert_line.ok_or_else(|| {
                     ErrorData::new(
                         ErrorCode::INVALID_PARAMS,
@@ -904,6 +974,7 @@ impl DeveloperServer {
 
         let working_dir = extract_working_dir_from_meta(&context.meta);
         let session_id = extract_session_id_from_meta(&context.meta);
+        let session_env = extract_env_from_meta(&context.meta);
 
         // Validate the shell command
         self.validate_shell_command(command)?;
@@ -924,6 +995,7 @@ impl DeveloperServer {
                 cancellation_token.clone(),
                 working_dir,
                 session_id,
+                session_env,
             )
             .await;
 
@@ -940,13 +1012,15 @@ impl DeveloperServer {
             }
         }
 
-        let output_str = output_result?;
-
-        // Validate output size
-        self.validate_shell_output_size(command, &output_str)?;
-
         // Process and format the output
-        let (final_output, user_output) = self.process_shell_output(&output_str)?;
+        let (final_output, user_output) = match output_result? {
+            ShellOutput::Complete(output_str) => self.process_shell_output(&output_str)?,
+            ShellOutput::Spilled {
+                tail,
+                line_count,
+                path,
+            } => truncated_shell_output(line_count, &path, &tail),
+        };
 
         Ok(CallToolResult::success(vec![
             Content::text(final_output).with_audience(vec![Role::Assistant]),
@@ -1010,7 +1084,8 @@ impl DeveloperServer {
         cancellation_token: CancellationToken,
         working_dir: Option<PathBuf>,
         session_id: Option<String>,
-    ) -> Result<String, ErrorData> {
+        session_env: Vec<(OsString, OsString)>,
+    ) -> Result<ShellOutput, ErrorData> {
         let mut shell_config = ShellConfig::default();
         let shell_name = std::path::Path::new(&shell_config.executable)
             .file_name()
@@ -1031,6 +1106,7 @@ impl DeveloperServer {
                 .envs
                 .push((OsString::from("AGENT_SESSION_ID"), OsString::from(sid)));
         }
+        shell_config.envs.extend(session_env);
 
         let mut command = configure_shell_command(&shell_config, command, working_dir.as_deref());
 
@@ -1092,18 +1168,22 @@ impl DeveloperServer {
 
     /// Stream shell output in real-time and return the combined output.
     ///
-    /// Merges stdout and stderr streams and sends each line as a logging notification.
+    /// Merges stdout and stderr streams and sends their lines in batches as logging
+    /// notifications. A slow client holds up reading, and so the command, instead of
+    /// notifications piling up.
     async fn stream_shell_output(
         &self,
         stdout: tokio::process::ChildStdout,
         stderr: tokio::process::ChildStderr,
         peer: rmcp::service::Peer<RoleServer>,
-    ) -> Result<String, ErrorData> {
+    ) -> Result<ShellOutput, ErrorData> {
         let stdout = BufReader::new(stdout);
         let stderr = BufReader::new(stderr);
 
         let output_task = tokio::spawn(async move {
-            let mut combined_output = String::new();
+            let mut output = OutputBuffer::default();
+            let mut batch = OutputBatch::default();
+            let mut flush = tokio::time::interval(OUTPUT_FLUSH_INTERVAL);
 
             // Merge stdout and stderr streams
             // ref https://blog.yoshuawuyts.com/futures-concurrency-3
@@ -1111,37 +1191,31 @@ impl DeveloperServer {
             let stderr = SplitStream::new(stderr.split(b'\n')).map(|v| ("stderr", v));
             let mut merged = stdout.merge(stderr);
 
-            while let Some((stream_type, line)) = merged.next().await {
-                let mut line = line?;
-                // Re-add newline as clients expect it
-                line.push(b'\n');
-                // Convert to UTF-8 to avoid corrupted output
-                let line_str = String::from_utf8_lossy(&line);
-
-                combined_output.push_str(&line_str);
-
-                // Stream each line back to the client in real-time
-                let trimmed_line = line_str.trim();
-                if !trimmed_line.is_empty() {
-                    // Send the output line as a structured logging message
-                    if let Err(e) = peer
-                        .notify_logging_message(LoggingMessageNotificationParam {
-                            level: LoggingLevel::Info,
-                            data: serde_json::json!({
-                                "type": "shell_output",
-                                "stream": stream_type,
-                                "output": trimmed_line
-                            }),
-                            logger: Some("shell_tool".to_string()),
-                        })
-                        .await
-                    {
-                        // Don't break execution if streaming fails, just log it
-                        eprintln!("Failed to stream output line: {}", e);
+            loop {
+                tokio::select! {
+                    next = merged.next() => {
+                        let Some((stream_type, line)) = next else {
+                            break;
+                        };
+                        let mut line = line?;
+                        // Re-add newline as clients expect it
+                        line.push(b'\n');
+                        // Convert to UTF-8 to avoid corrupted output
+                        let line_str = String::from_utf8_lossy(&line);
+
+                        output.push(&line_str)?;
+
+                        let trimmed_line = line_str.trim_end();
+                        if trimmed_line.is_empty() {
+                            continue;
+                        }
+                        batch.push(&peer, stream_type, trimmed_line).await;
                     }
+                    _ = flush.tick() => batch.send(&peer).await,
                 }
             }
-            Ok::<_, std::io::Error>(combined_output)
+            batch.send(&peer).await;
+            output.finish()
         });
 
         match output_task.await {
@@ -1156,27 +1230,6 @@ impl DeveloperServer {
         }
     }
 
-    /// Validate that shell output doesn't exceed size limits.
-    fn validate_shell_output_size(&self, command: &str, output: &str) -> Result<(), ErrorData> {
-        const MAX_CHAR_COUNT: usize = 400_000; // 400KB
-        let char_count = output.chars().count();
-
-        if char_count > MAX_CHAR_COUNT {
-            return Err(ErrorData::new(
-                ErrorCode::INTERNAL_ERROR,
-                format!(
-                    "Shell output from command '{}' has too many characters ({}). Maximum character count is {}.",
-                    command,
-                    char_count,
-                    MAX_CHAR_COUNT
-                ),
-                None,
-            ));
-        }
-
-        Ok(())
-    }
-
     /// Analyze code structure and relationships.
     ///
     /// Automatically selects the appropriate analysis:
@@ -1470,57 +1523,61 @@ impl DeveloperServer {
         let lines: Vec<&str> = output_str.lines().collect();
         let line_count = lines.len();
 
-        let start = lines.len().saturating_sub(100);
-        let last_100_lines_str = lines[start..].join("\n");
-
-        let final_output = if line_count > 100 {
-            let tmp_file = tempfile::NamedTempFile::new().map_err(|e| {
-                ErrorData::new(
-                    ErrorCode::INTERNAL_ERROR,
-                    format!("Failed to create temporary file: {}", e),
-                    None,
-                )
-            })?;
+        if line_count <= TAIL_LINES {
+            return Ok((output_str.to_string(), output_str.to_string()));
+        }
 
-            std::fs::write(tmp_file.path(), output_str).map_err(|e| {
-                ErrorData::new(
-                    ErrorCode::INTERNAL_ERROR,
-                    format!("Failed to write to temporary file: {}", e),
-                    None,
-                )
-            })?;
+        let start = lines.len().saturating_sub(TAIL_LINES);
+        let last_100_lines_str = lines[start..].join("\n");
 
-            let (_, path) = tmp_file.keep().map_err(|e| {
-                ErrorData::new(
-                    ErrorCode::INTERNAL_ERROR,
-                    format!("Failed to persist temporary file: {}", e),
-                    None,
-                )
-            })?;
+        let tmp_file = tempfile::NamedTempFile::new().map_err(|e| {
+            ErrorData::new(
+                ErrorCode::INTERNAL_ERROR,
+                format!("Failed to create temporary file: {}", e),
+                None,
+            )
+        })?;
 
-            format!(
-                "private note: output was {} lines and we are only showing the most recent lines, remainder of lines in {} do not show tmp file to user, that file can be searched if extra context needed to fulfill request. truncated output: \n{}",
-                line_count,
-                path.display(),
-                last_100_lines_str
+        std::fs::write(tmp_file.path(), output_str).map_err(|e| {
+            ErrorData::new(
+                ErrorCode::INTERNAL_ERROR,
+                format!("Failed to write to temporary file: {}", e),
+                None,
             )
-        } else {
-            output_str.to_string()
-        };
+        })?;
 
-        let user_output = if line_count > 100 {
-            format!(
-                "NOTE: Output was {} lines, showing only the last 100 lines.\n\n{}",
-                line_count, last_100_lines_str
+        let (_, path) = tmp_file.keep().map_err(|e| {
+            ErrorData::new(
+                ErrorCode::INTERNAL_ERROR,
+                format!("Failed to persist temporary file: {}", e),
+                None,
             )
-        } else {
-            output_str.to_string()
-        };
+        })?;
 
-        Ok((final_output, user_output))
+        Ok(truncated_shell_output(
+            line_count,
+            &path,
+            &last_100_lines_str,
+        ))
     }
 }
 
+/// The assistant and user versions of output that only shows its last lines,
+/// with the full output in `path`.
+fn truncated_shell_output(line_count: usize, path: &Path, tail: &str) -> (String, String) {
+    let final_output = format!(
+        "private note: output was {} lines and we are only showing the most recent lines, remainder of lines in {} do not show tmp file to user, that file can be searched if extra context needed to fulfill request. truncated output: \n{}",
+        line_count,
+        path.display(),
+        tail
+    );
+    let user_output = format!(
+        "NOTE: Output was {} lines, showing only the last {} lines.\n\n{}",
+        line_count, TAIL_LINES, tail
+    );
+    (final_output, user_output)
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -1678,6 +1735,7 @@ mod tests {
                 old_str: None,
                 new_str: None,
                 insert_line: None,
+                symbol: None,
                 diff: None,
             });
 
@@ -1705,6 +1763,7 @@ mod tests {
                 old_str: None,
                 new_str: None,
                 insert_line: None,
+                symbol: None,
                 diff: None,
             });
 
@@ -1736,6 +1795,7 @@ mod tests {
             old_str: None,
             new_str: None,
             insert_line: None,
+            symbol: None,
             diff: None,
         });
 
@@ -1750,6 +1810,7 @@ mod tests {
             old_str: None,
             new_str: None,
             insert_line: None,
+            symbol: None,
             diff: None,
         });
 
@@ -1788,6 +1849,7 @@ mod tests {
             old_str: None,
             new_str: None,
             insert_line: None,
+            symbol: None,
             diff: None,
         });
 
@@ -1802,6 +1864,7 @@ mod tests {
             old_str: Some("world".to_string()),
             new_str: Some("Rust".to_string()),
             insert_line: None,
+            symbol: None,
             diff: None,
         });
 
@@ -1828,6 +1891,44 @@ mod tests {
         assert!(content.contains("Hello, Rust!"));
     }
 
+    #[tokio::test]
+    #[serial]
+    async fn test_text_editor_symbol_replace() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let file_path = temp_dir.path().join("shapes.py");
+        let file_path_str = file_path.to_str().unwrap();
+        std::env::set_current_dir(&temp_dir).unwrap();
+        fs::write(
+            &file_path,
+            "class Square:\n    def area(self):\n        return self.side * self.side\n\n\ndef area(shape):\n    return shape.area()\n",
+        )
+        .unwrap();
+
+        let server = create_test_server();
+        let params = |symbol: &str| {
+            Parameters(TextEditorParams {
+                path: file_path_str.to_string(),
+                command: "str_replace".to_string(),
+                view_range: None,
+                file_text: None,
+                old_str: None,
+                new_str: Some("    def area(self):\n        return self.side ** 2".to_string()),
+                insert_line: None,
+                symbol: Some(symbol.to_string()),
+                diff: None,
+            })
+        };
+
+        let err = server.text_editor(params("area")).await.unwrap_err();
+        assert!(err.message.contains("matches 2 definitions"));
+        assert_eq!(err.data.unwrap()["kind"], "ambiguous_anchor");
+
+        server.text_editor(params("Square.area")).await.unwrap();
+        let content = fs::read_to_string(&file_path).unwrap();
+        assert!(content.contains("        return self.side ** 2\n"));
+        assert!(content.contains("def area(shape):\n    return shape.area()"));
+    }
+
     #[tokio::test]
     #[serial]
     async fn test_text_editor_undo_edit() {
@@ -1847,6 +1948,7 @@ mod tests {
             old_str: None,
             new_str: None,
             insert_line: None,
+            symbol: None,
             diff: None,
         });
 
@@ -1861,6 +1963,7 @@ mod tests {
             old_str: Some("Original".to_string()),
             new_str: Some("Modified".to_string()),
             insert_line: None,
+            symbol: None,
             diff: None,
         });
 
@@ -1879,6 +1982,7 @@ mod tests {
             old_str: None,
             new_str: None,
             insert_line: None,
+            symbol: None,
             diff: None,
         });
 
@@ -1959,6 +2063,7 @@ mod tests {
             old_str: None,
             new_str: None,
             insert_line: None,
+            symbol: None,
             diff: None,
         });
 
@@ -1979,6 +2084,7 @@ mod tests {
             old_str: None,
             new_str: None,
             insert_line: None,
+            symbol: None,
             diff: None,
         });
 
@@ -2096,6 +2202,7 @@ mod tests {
             old_str: None,
             new_str: None,
             insert_line: None,
+            symbol: None,
             diff: None,
         });
 
@@ -2110,6 +2217,7 @@ mod tests {
             old_str: None,
             new_str: None,
             insert_line: None,
+            symbol: None,
             diff: None,
         });
 
@@ -2157,6 +2265,7 @@ mod tests {
             old_str: None,
             new_str: None,
             insert_line: None,
+            symbol: None,
             diff: None,
         });
 
@@ -2171,6 +2280,7 @@ mod tests {
             old_str: None,
             new_str: None,
             insert_line: None,
+            symbol: None,
             diff: None,
         });
 
@@ -2217,6 +2327,7 @@ mod tests {
             old_str: None,
             new_str: None,
             insert_line: None,
+            symbol: None,
             diff: None,
         });
 
@@ -2231,6 +2342,7 @@ mod tests {
             old_str: None,
             new_str: None,
             insert_line: None,
+            symbol: None,
             diff: None,
         });
 
@@ -2261,6 +2373,7 @@ mod tests {
             old_str: None,
             new_str: None,
             insert_line: None,
+            symbol: None,
             diff: None,
         });
 
@@ -2275,6 +2388,7 @@ mod tests {
             old_str: None,
             new_str: Some("Line 1".to_string()),
             insert_line: Some(0),
+            symbol: None,
             diff: None,
         });
 
@@ -2318,6 +2432,7 @@ mod tests {
             old_str: None,
             new_str: None,
             insert_line: None,
+            symbol: None,
             diff: None,
         });
 
@@ -2332,6 +2447,7 @@ mod tests {
             old_str: None,
             new_str: Some("Line 3".to_string()),
             insert_line: Some(2),
+            symbol: None,
             diff: None,
         });
 
@@ -2380,6 +2496,7 @@ mod tests {
             old_str: None,
             new_str: None,
             insert_line: None,
+            symbol: None,
             diff: None,
         });
 
@@ -2394,6 +2511,7 @@ mod tests {
             old_str: None,
             new_str: Some("Line 4".to_string()),
             insert_line: Some(3),
+            symbol: None,
             diff: None,
         });
 
@@ -2437,6 +2555,7 @@ mod tests {
             old_str: None,
             new_str: None,
             insert_line: None,
+            symbol: None,
             diff: None,
         });
 
@@ -2451,6 +2570,7 @@ mod tests {
             old_str: None,
             new_str: Some("Line 4".to_string()),
             insert_line: Some(-1),
+            symbol: None,
             diff: None,
         });
 
@@ -2494,6 +2614,7 @@ mod tests {
             old_str: None,
             new_str: None,
             insert_line: None,
+            symbol: None,
             diff: None,
         });
 
@@ -2508,6 +2629,7 @@ mod tests {
             old_str: None,
             new_str: Some("Line 11".to_string()),
             insert_line: Some(10),
+            symbol: None,
             diff: None,
         });
 
@@ -2538,6 +2660,7 @@ mod tests {
             old_str: None,
             new_str: None,
             insert_line: None,
+            symbol: None,
             diff: None,
         });
 
@@ -2552,6 +2675,7 @@ mod tests {
             old_str: None,
             new_str: None, // Missing required parameter
             insert_line: Some(1),
+            symbol: None,
             diff: None,
         });
 
@@ -2570,6 +2694,7 @@ mod tests {
             old_str: None,
             new_str: Some("New text".to_string()),
             insert_line: None, // Missing required parameter
+            symbol: None,
             diff: None,
         });
 
@@ -2600,6 +2725,7 @@ mod tests {
             old_str: None,
             new_str: None,
             insert_line: None,
+            symbol: None,
             diff: None,
         });
 
@@ -2614,6 +2740,7 @@ mod tests {
             old_str: None,
             new_str: Some("Inserted Line".to_string()),
             insert_line: Some(1),
+            symbol: None,
             diff: None,
         });
 
@@ -2628,6 +2755,7 @@ mod tests {
             old_str: None,
             new_str: None,
             insert_line: None,
+            symbol: None,
             diff: None,
         });
 
@@ -2667,6 +2795,7 @@ mod tests {
             old_str: None,
             new_str: Some("New line".to_string()),
             insert_line: Some(0),
+            symbol: None,
             diff: None,
         });
 
@@ -2702,6 +2831,7 @@ mod tests {
             old_str: None,
             new_str: None,
             insert_line: None,
+            symbol: None,
             diff: None,
         });
 
@@ -2716,6 +2846,7 @@ mod tests {
             old_str: None,
             new_str: None,
             insert_line: None,
+            symbol: None,
             diff: None,
         });
 
@@ -2741,6 +2872,7 @@ mod tests {
             old_str: None,
             new_str: None,
             insert_line: None,
+            symbol: None,
             diff: None,
         });
 
@@ -2773,6 +2905,7 @@ mod tests {
             old_str: None,
             new_str: None,
             insert_line: None,
+            symbol: None,
             diff: None,
         });
 
@@ -2804,6 +2937,7 @@ mod tests {
             old_str: None,
             new_str: None,
             insert_line: None,
+            symbol: None,
             diff: None,
         });
 
@@ -2818,6 +2952,7 @@ mod tests {
             old_str: None,
             new_str: None,
             insert_line: None,
+            symbol: None,
             diff: None,
         });
 
@@ -2865,6 +3000,7 @@ mod tests {
             old_str: None,
             new_str: None,
             insert_line: None,
+            symbol: None,
             diff: None,
         });
 
@@ -2879,6 +3015,7 @@ mod tests {
             old_str: None,
             new_str: None,
             insert_line: None,
+            symbol: None,
             diff: None,
         });
 
@@ -2932,6 +3069,7 @@ mod tests {
                 old_str: None,
                 new_str: None,
                 insert_line: None,
+                symbol: None,
                 diff: None,
             }))
             .await;
@@ -2995,6 +3133,7 @@ mod tests {
                 old_str: None,
                 new_str: None,
                 insert_line: None,
+                symbol: None,
                 diff: None,
             }))
             .await;
@@ -3042,6 +3181,7 @@ mod tests {
                 old_str: None,
                 new_str: None,
                 insert_line: None,
+                symbol: None,
                 diff: None,
             }))
             .await;
@@ -3382,6 +3522,7 @@ mod tests {
             old_str: None,
             new_str: None,
             insert_line: None,
+            symbol: None,
             diff: None,
         });
 
@@ -3409,6 +3550,7 @@ mod tests {
             old_str: None,
             new_str: None,
             insert_line: None,
+            symbol: None,
             diff: None,
         });
 
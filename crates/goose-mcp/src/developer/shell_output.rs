@@ -0,0 +1,166 @@
+//! Output of a running shell command.
+//!
+//! While the command runs its lines go to the client in batches of
+//! `shell_output` logging notifications, so long builds and test runs show
+//! progress. Those can also print far more than the model should read: the
+//! output is kept in memory up to `MAX_IN_MEMORY_BYTES`, past that the full
+//! output moves to a temporary file and only the last `TAIL_LINES` lines are
+//! kept for the tool result.
+
+use rmcp::model::{LoggingLevel, LoggingMessageNotificationParam};
+use rmcp::service::Peer;
+use rmcp::RoleServer;
+use std::collections::VecDeque;
+use std::fs::File;
+use std::io::{self, Write};
+use std::path::PathBuf;
+use std::time::Duration;
+
+pub const TAIL_LINES: usize = 100;
+const MAX_IN_MEMORY_BYTES: usize = 400 * 1024;
+/// Longest a streamed line waits for its batch to fill up
+pub const OUTPUT_FLUSH_INTERVAL: Duration = Duration::from_millis(100);
+const OUTPUT_BATCH_LINES: usize = 50;
+
+/// Output lines not sent to the client yet, all from the same stream.
+#[derive(Default)]
+pub struct OutputBatch {
+    stream: &'static str,
+    text: String,
+    lines: usize,
+}
+
+impl OutputBatch {
+    /// Adds a line, sending the batch first when the line comes from the
+    /// other stream, and after when the batch is full.
+    pub async fn push(&mut self, peer: &Peer<RoleServer>, stream: &'static str, line: &str) {
+        if self.stream != stream {
+            self.send(peer).await;
+            self.stream = stream;
+        }
+        if !self.text.is_empty() {
+            self.text.push('\n');
+        }
+        self.text.push_str(line);
+        self.lines += 1;
+        if self.lines >= OUTPUT_BATCH_LINES {
+            self.send(peer).await;
+        }
+    }
+
+    pub async fn send(&mut self, peer: &Peer<RoleServer>) {
+        if self.text.is_empty() {
+            return;
+        }
+        let output = std::mem::take(&mut self.text);
+        self.lines = 0;
+        if let Err(e) = peer
+            .notify_logging_message(LoggingMessageNotificationParam {
+                level: LoggingLevel::Info,
+                data: serde_json::json!({
+                    "type": "shell_output",
+                    "stream": self.stream,
+                    "output": output
+                }),
+                logger: Some("shell_tool".to_string()),
+            })
+            .await
+        {
+            // Don't break execution if streaming fails, just log it
+            eprintln!("Failed to stream output: {}", e);
+        }
+    }
+}
+
+pub enum ShellOutput {
+    /// The whole output
+    Complete(String),
+    /// The output outgrew memory and was written to `path`
+    Spilled {
+        tail: String,
+        line_count: usize,
+        path: PathBuf,
+    },
+}
+
+#[derive(Default)]
+pub struct OutputBuffer {
+    text: String,
+    tail: VecDeque<String>,
+    line_count: usize,
+    spill: Option<(File, PathBuf)>,
+}
+
+impl OutputBuffer {
+    pub fn push(&mut self, line: &str) -> io::Result<()> {
+        self.line_count += 1;
+        if self.tail.len() == TAIL_LINES {
+            self.tail.pop_front();
+        }
+        self.tail.push_back(line.trim_end_matches('\n').to_string());
+
+        if let Some((file, _)) = &mut self.spill {
+            return file.write_all(line.as_bytes());
+        }
+        self.text.push_str(line);
+        if self.text.len() > MAX_IN_MEMORY_BYTES {
+            let (mut file, path) = tempfile::NamedTempFile::new()?
+                .keep()
+                .map_err(|e| e.error)?;
+            file.write_all(self.text.as_bytes())?;
+            self.text = String::new();
+            self.spill = Some((file, path));
+        }
+        Ok(())
+    }
+
+    pub fn finish(self) -> io::Result<ShellOutput> {
+        match self.spill {
+            None => Ok(ShellOutput::Complete(self.text)),
+            Some((mut file, path)) => {
+                file.flush()?;
+                Ok(ShellOutput::Spilled {
+                    tail: Vec::from(self.tail).join("\n"),
+                    line_count: self.line_count,
+                    path,
+                })
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_large_output_spills_to_file_and_keeps_tail() {
+        let mut small = OutputBuffer::default();
+        small.push("one\n").unwrap();
+        small.push("two\n").unwrap();
+        assert!(matches!(small.finish().unwrap(), ShellOutput::Complete(s) if s == "one\ntwo\n"));
+
+        let mut large = OutputBuffer::default();
+        let line = format!("{}\n", "x".repeat(1023));
+        let lines = MAX_IN_MEMORY_BYTES / line.len() + 50;
+        for _ in 0..lines {
+            large.push(&line).unwrap();
+        }
+        large.push("last line\n").unwrap();
+
+        let ShellOutput::Spilled {
+            tail,
+            line_count,
+            path,
+        } = large.finish().unwrap()
+        else {
+            panic!("expected the output to spill");
+        };
+        assert_eq!(line_count, lines + 1);
+        assert_eq!(tail.lines().count(), TAIL_LINES);
+        assert!(tail.ends_with("last line"));
+        let written = std::fs::read_to_string(&path).unwrap();
+        assert_eq!(written.len(), lines * line.len() + "last line\n".len());
+        std::fs::remove_file(path).unwrap();
+    }
+}
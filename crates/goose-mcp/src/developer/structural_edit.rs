@@ -0,0 +1,535 @@
+//! Structural edits: apply a change to a named definition instead of to a
+//! literal string.
+//!
+//! The target is located with tree-sitter by name (`parse`, `Parser::parse`,
+//! `Config.load`), so the edit lands on the right definition even if the
+//! surrounding file moved. When the caller passes the definition as it was
+//! when the change was planned and the file has drifted since, the change is
+//! three-way merged into the current definition; overlapping changes come
+//! back as a structured conflict rather than being applied.
+
+use serde::Serialize;
+use std::fmt;
+use tree_sitter::Node;
+
+use super::analyze::parser::ParserManager;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum EditPosition {
+    Replace,
+    Before,
+    After,
+}
+
+pub struct SymbolEdit<'a> {
+    /// Definition name, optionally qualified by enclosing definitions
+    pub anchor: &'a str,
+    pub position: EditPosition,
+    /// The definition as it was when the change was planned
+    pub expected: Option<&'a str>,
+    pub text: &'a str,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct AppliedEdit {
+    pub content: String,
+    /// First and last line (1-based) of the edited text in `content`
+    pub start_line: usize,
+    pub end_line: usize,
+    /// The definition had drifted and the change was merged into it
+    pub merged: bool,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct MergeConflict {
+    /// Line within the definition (1-based) where the conflict starts
+    pub line: usize,
+    pub base: Vec<String>,
+    pub ours: Vec<String>,
+    pub theirs: Vec<String>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum ApplyError {
+    UnsupportedLanguage {
+        language: String,
+    },
+    AnchorNotFound {
+        anchor: String,
+    },
+    AmbiguousAnchor {
+        anchor: String,
+        lines: Vec<usize>,
+    },
+    /// The definition changed since the edit was planned and the change
+    /// could not be merged cleanly
+    Conflict {
+        anchor: String,
+        conflicts: Vec<MergeConflict>,
+    },
+}
+
+impl fmt::Display for ApplyError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ApplyError::UnsupportedLanguage { language } => {
+                write!(f, "Structural edits are not supported for '{}' files", language)
+            }
+            ApplyError::AnchorNotFound { anchor } => {
+                write!(f, "No definition named '{}' was found", anchor)
+            }
+            ApplyError::AmbiguousAnchor { anchor, lines } => write!(
+                f,
+                "'{}' matches {} definitions (lines {}); qualify it with the enclosing type or module",
+                anchor,
+                lines.len(),
+                lines
+                    .iter()
+                    .map(|l| l.to_string())
+                    .collect::<Vec<_>>()
+                    .join(", ")
+            ),
+            ApplyError::Conflict { anchor, conflicts } => write!(
+                f,
+                "'{}' changed since the edit was planned and {} hunk{} conflict; view the file and edit it again",
+                anchor,
+                conflicts.len(),
+                if conflicts.len() == 1 { "" } else { "s" }
+            ),
+        }
+    }
+}
+
+pub type ApplyResult = Result<AppliedEdit, ApplyError>;
+
+pub fn apply_symbol_edit(source: &str, language: &str, edit: &SymbolEdit) -> ApplyResult {
+    let tree = ParserManager::new().parse(source, language).map_err(|_| {
+        ApplyError::UnsupportedLanguage {
+            language: language.to_string(),
+        }
+    })?;
+
+    let anchor: Vec<&str> = edit
+        .anchor
+        .split("::")
+        .flat_map(|s| s.split('.'))
+        .filter(|s| !s.is_empty())
+        .collect();
+    if anchor.is_empty() {
+        return Err(ApplyError::AnchorNotFound {
+            anchor: edit.anchor.to_string(),
+        });
+    }
+
+    let mut matches = Vec::new();
+    collect_matches(
+        tree.root_node(),
+        source,
+        &anchor,
+        &mut Vec::new(),
+        &mut matches,
+    );
+    // A Rust type and its impl block share a name; the impl only scopes methods
+    if matches.iter().any(|n| n.kind() != "impl_item") {
+        matches.retain(|n| n.kind() != "impl_item");
+    }
+    let node = match matches.as_slice() {
+        [] => {
+            return Err(ApplyError::AnchorNotFound {
+                anchor: edit.anchor.to_string(),
+            })
+        }
+        [node] => *node,
+        _ => {
+            return Err(ApplyError::AmbiguousAnchor {
+                anchor: edit.anchor.to_string(),
+                lines: matches.iter().map(|n| n.start_position().row + 1).collect(),
+            })
+        }
+    };
+
+    // Start at the beginning of the line so indentation belongs to the span
+    let line_start = source[..node.start_byte()]
+        .rfind('\n')
+        .map(|i| i + 1)
+        .unwrap_or(0);
+    let start = if source[line_start..node.start_byte()].trim().is_empty() {
+        line_start
+    } else {
+        node.start_byte()
+    };
+    let end = node.end_byte();
+    let current = &source[start..end];
+    let text = edit.text.trim_end_matches('\n');
+
+    let (offset, inserted, merged) = match edit.position {
+        EditPosition::Before => (start, format!("{}\n", text), false),
+        EditPosition::After => (end, format!("\n{}", text), false),
+        EditPosition::Replace => match edit.expected {
+            Some(expected) if normalize(expected) != normalize(current) => {
+                let merged =
+                    merge3(expected, text, current).map_err(|conflicts| ApplyError::Conflict {
+                        anchor: edit.anchor.to_string(),
+                        conflicts,
+                    })?;
+                (start, merged, true)
+            }
+            _ => (start, text.to_string(), false),
+        },
+    };
+
+    let removed = if edit.position == EditPosition::Replace {
+        end - start
+    } else {
+        0
+    };
+    let mut content = String::with_capacity(source.len() + inserted.len());
+    content.push_str(&source[..offset]);
+    content.push_str(&inserted);
+    content.push_str(&source[offset + removed..]);
+
+    let leading_newline = inserted.starts_with('\n') as usize;
+    let start_line = source[..offset].matches('\n').count() + 1 + leading_newline;
+    let end_line = start_line + inserted.trim_matches('\n').lines().count().max(1) - 1;
+
+    Ok(AppliedEdit {
+        content,
+        start_line,
+        end_line,
+        merged,
+    })
+}
+
+fn definition_name(node: &Node, source: &str) -> Option<String> {
+    let kind = node.kind();
+    let is_definition = kind.ends_with("_item")
+        || kind.ends_with("_definition")
+        || kind.ends_with("_declaration")
+        || matches!(kind, "method" | "singleton_method" | "class" | "module");
+    if !is_definition {
+        return None;
+    }
+    let name = node.child_by_field_name("name").or_else(|| {
+        if kind == "impl_item" {
+            node.child_by_field_name("type")
+        } else {
+            None
+        }
+    })?;
+    let text = source.get(name.byte_range())?;
+    // `impl<T> Parser<T>` is anchored as `Parser`
+    Some(text.split('<').next().unwrap_or(text).trim().to_string())
+}
+
+fn collect_matches<'t>(
+    node: Node<'t>,
+    source: &str,
+    anchor: &[&str],
+    scope: &mut Vec<String>,
+    matches: &mut Vec<Node<'t>>,
+) {
+    let name = definition_name(&node, source);
+    if let Some(name) = &name {
+        let (last, parents) = anchor.split_last().expect("anchor is not empty");
+        if name == last && is_subsequence(parents, scope) {
+            matches.push(node);
+        }
+        scope.push(name.clone());
+    }
+    let mut cursor = node.walk();
+    for child in node.named_children(&mut cursor) {
+        collect_matches(child, source, anchor, scope, matches);
+    }
+    if name.is_some() {
+        scope.pop();
+    }
+}
+
+fn is_subsequence(needle: &[&str], haystack: &[String]) -> bool {
+    let mut remaining = needle.iter().peekable();
+    for item in haystack {
+        if remaining.peek().is_some_and(|n| **n == item.as_str()) {
+            remaining.next();
+        }
+    }
+    remaining.peek().is_none()
+}
+
+/// Lines without surrounding blank lines, common indentation or trailing
+/// whitespace, so a definition quoted without its first indent still matches
+fn dedent(text: &str) -> (String, Vec<String>) {
+    let lines: Vec<&str> = text.lines().collect();
+    let first = lines.iter().position(|l| !l.trim().is_empty());
+    let last = lines.iter().rposition(|l| !l.trim().is_empty());
+    let (Some(first), Some(last)) = (first, last) else {
+        return (String::new(), Vec::new());
+    };
+    let lines = &lines[first..=last];
+    let indent = lines
+        .iter()
+        .filter(|l| !l.trim().is_empty())
+        .map(|l| l.len() - l.trim_start().len())
+        .min()
+        .unwrap_or(0);
+    let prefix = lines
+        .iter()
+        .find(|l| l.len() - l.trim_start().len() == indent)
+        .map(|l| l[..indent].to_string())
+        .unwrap_or_default();
+    let dedented = lines
+        .iter()
+        .map(|l| {
+            l.get(indent..)
+                .unwrap_or(l.trim_start())
+                .trim_end()
+                .to_string()
+        })
+        .collect();
+    (prefix, dedented)
+}
+
+fn normalize(text: &str) -> Vec<String> {
+    dedent(text).1
+}
+
+#[derive(Debug, Clone, PartialEq)]
+struct Hunk {
+    start: usize,
+    end: usize,
+    lines: Vec<String>,
+}
+
+/// Changes that turn `base` into `other`, as replaced ranges of `base`
+fn diff_hunks(base: &[String], other: &[String]) -> Vec<Hunk> {
+    let (n, m) = (base.len(), other.len());
+    let mut lcs = vec![vec![0u32; m + 1]; n + 1];
+    for i in (0..n).rev() {
+        for j in (0..m).rev() {
+            lcs[i][j] = if base[i] == other[j] {
+                lcs[i + 1][j + 1] + 1
+            } else {
+                lcs[i + 1][j].max(lcs[i][j + 1])
+            };
+        }
+    }
+
+    let mut hunks = Vec::new();
+    let (mut i, mut j) = (0, 0);
+    let (mut hunk_i, mut hunk_j) = (0, 0);
+    loop {
+        let matched = i < n && j < m && base[i] == other[j];
+        if matched || (i == n && j == m) {
+            if hunk_i < i || hunk_j < j {
+                hunks.push(Hunk {
+                    start: hunk_i,
+                    end: i,
+                    lines: other[hunk_j..j].to_vec(),
+                });
+            }
+            if !matched {
+                break;
+            }
+            i += 1;
+            j += 1;
+            hunk_i = i;
+            hunk_j = j;
+        } else if j < m && (i == n || lcs[i][j + 1] >= lcs[i + 1][j]) {
+            j += 1;
+        } else {
+            i += 1;
+        }
+    }
+    hunks
+}
+
+fn apply_hunks(base: &[String], start: usize, end: usize, hunks: &[&Hunk]) -> Vec<String> {
+    let mut out = Vec::new();
+    let mut pos = start;
+    for hunk in hunks {
+        out.extend_from_slice(&base[pos..hunk.start]);
+        out.extend(hunk.lines.iter().cloned());
+        pos = hunk.end;
+    }
+    out.extend_from_slice(&base[pos..end]);
+    out
+}
+
+/// Three-way merge of `ours` and `theirs` against `base`. The result keeps
+/// the indentation of `theirs`, the text currently in the file.
+fn merge3(base: &str, ours: &str, theirs: &str) -> Result<String, Vec<MergeConflict>> {
+    let base = normalize(base);
+    let ours = normalize(ours);
+    let (indent, theirs) = dedent(theirs);
+
+    let mut hunks: Vec<(bool, Hunk)> = diff_hunks(&base, &ours)
+        .into_iter()
+        .map(|h| (true, h))
+        .chain(diff_hunks(&base, &theirs).into_iter().map(|h| (false, h)))
+        .collect();
+    hunks.sort_by_key(|(_, h)| (h.start, h.end));
+
+    let mut merged = Vec::new();
+    let mut conflicts = Vec::new();
+    let mut pos = 0;
+    let mut i = 0;
+    while i < hunks.len() {
+        let start = hunks[i].1.start;
+        let mut end = hunks[i].1.end;
+        let mut j = i + 1;
+        while j < hunks.len() && (hunks[j].1.start < end || hunks[j].1.start == start) {
+            end = end.max(hunks[j].1.end);
+            j += 1;
+        }
+        let cluster = &hunks[i..j];
+        let ours_side: Vec<&Hunk> = cluster.iter().filter(|(o, _)| *o).map(|(_, h)| h).collect();
+        let theirs_side: Vec<&Hunk> = cluster
+            .iter()
+            .filter(|(o, _)| !*o)
+            .map(|(_, h)| h)
+            .collect();
+
+        merged.extend_from_slice(&base[pos..start]);
+        if theirs_side.is_empty() {
+            merged.extend(apply_hunks(&base, start, end, &ours_side));
+        } else if ours_side.is_empty() {
+            merged.extend(apply_hunks(&base, start, end, &theirs_side));
+        } else {
+            let ours_lines = apply_hunks(&base, start, end, &ours_side);
+            let theirs_lines = apply_hunks(&base, start, end, &theirs_side);
+            if ours_lines == theirs_lines {
+                merged.extend(ours_lines);
+            } else {
+                conflicts.push(MergeConflict {
+                    line: start + 1,
+                    base: base[start..end].to_vec(),
+                    ours: ours_lines,
+                    theirs: theirs_lines,
+                });
+            }
+        }
+        pos = end;
+        i = j;
+    }
+    merged.extend_from_slice(&base[pos..]);
+
+    if !conflicts.is_empty() {
+        return Err(conflicts);
+    }
+    Ok(merged
+        .iter()
+        .map(|l| {
+            if l.is_empty() {
+                String::new()
+            } else {
+                format!("{}{}", indent, l)
+            }
+        })
+        .collect::<Vec<_>>()
+        .join("\n"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SOURCE: &str = r#"struct Parser {
+    strict: bool,
+}
+
+impl Parser {
+    fn parse(&self, input: &str) -> usize {
+        let trimmed = input.trim();
+        let count = trimmed.len();
+        count
+    }
+}
+
+fn parse(input: &str) -> usize {
+    Parser { strict: false }.parse(input)
+}
+"#;
+
+    fn replace<'a>(anchor: &'a str, expected: Option<&'a str>, text: &'a str) -> SymbolEdit<'a> {
+        SymbolEdit {
+            anchor,
+            position: EditPosition::Replace,
+            expected,
+            text,
+        }
+    }
+
+    #[test]
+    fn test_locates_qualified_anchor() {
+        let err = apply_symbol_edit(SOURCE, "rust", &replace("parse", None, "")).unwrap_err();
+        assert_eq!(
+            err,
+            ApplyError::AmbiguousAnchor {
+                anchor: "parse".to_string(),
+                lines: vec![6, 13],
+            }
+        );
+
+        let new = "    fn parse(&self, input: &str) -> usize {\n        input.len()\n    }";
+        let applied =
+            apply_symbol_edit(SOURCE, "rust", &replace("Parser::parse", None, new)).unwrap();
+        assert!(applied.content.contains("        input.len()\n    }\n}\n"));
+        assert!(applied
+            .content
+            .contains("Parser { strict: false }.parse(input)"));
+        assert_eq!((applied.start_line, applied.end_line), (6, 8));
+        assert!(!applied.merged);
+
+        let applied = apply_symbol_edit(
+            SOURCE,
+            "rust",
+            &SymbolEdit {
+                anchor: "Parser",
+                position: EditPosition::Before,
+                expected: None,
+                text: "#[derive(Debug)]",
+            },
+        )
+        .unwrap();
+        assert!(applied
+            .content
+            .starts_with("#[derive(Debug)]\nstruct Parser {"));
+
+        assert!(matches!(
+            apply_symbol_edit(SOURCE, "rust", &replace("Lexer", None, "")),
+            Err(ApplyError::AnchorNotFound { .. })
+        ));
+    }
+
+    #[test]
+    fn test_merges_drifted_definition() {
+        // Planned before `trim` was added to the file
+        let planned_base = "fn parse(&self, input: &str) -> usize {\n    let trimmed = input;\n    let count = trimmed.len();\n    count\n}";
+        let ours = "fn parse(&self, input: &str) -> usize {\n    let trimmed = input;\n    let count = trimmed.len();\n    count * 2\n}";
+
+        let applied = apply_symbol_edit(
+            SOURCE,
+            "rust",
+            &replace("Parser::parse", Some(planned_base), ours),
+        )
+        .unwrap();
+        assert!(applied.merged);
+        assert!(applied.content.contains(
+            "    fn parse(&self, input: &str) -> usize {\n        let trimmed = input.trim();\n        let count = trimmed.len();\n        count * 2\n    }"
+        ));
+
+        let conflicting = "fn parse(&self, input: &str) -> usize {\n    let trimmed = input.to_lowercase();\n    let count = trimmed.len();\n    count\n}";
+        let err = apply_symbol_edit(
+            SOURCE,
+            "rust",
+            &replace("Parser::parse", Some(planned_base), conflicting),
+        )
+        .unwrap_err();
+        let ApplyError::Conflict { conflicts, .. } = err else {
+            panic!("expected a conflict, got {:?}", err);
+        };
+        assert_eq!(conflicts.len(), 1);
+        assert_eq!(conflicts[0].line, 2);
+        assert_eq!(conflicts[0].theirs, vec!["    let trimmed = input.trim();"]);
+    }
+}
@@ -14,6 +14,7 @@ use rmcp::model::{Content, ErrorCode, ErrorData, Role};
 use super::editor_models::EditorModel;
 use super::lang;
 use super::shell::normalize_line_endings;
+use super::structural_edit::{apply_symbol_edit, EditPosition, SymbolEdit};
 
 // Constants
 pub const LINE_READ_LIMIT: usize = 2000;
@@ -1049,6 +1050,120 @@ pub async fn text_editor_insert(
     ])
 }
 
+/// Edits a named definition located with tree-sitter rather than by string
+/// match. `expected`, when given, is the definition as the caller last saw
+/// it; if the file has changed since, the edit is merged into the current
+/// definition or rejected with the conflicting hunks.
+pub async fn text_editor_symbol_edit(
+    path: &PathBuf,
+    symbol: &str,
+    position: EditPosition,
+    expected: Option<&str>,
+    new_str: &str,
+    file_history: &std::sync::Arc<
+        std::sync::Mutex<std::collections::HashMap<PathBuf, Vec<String>>>,
+    >,
+) -> Result<Vec<Content>, ErrorData> {
+    if !path.exists() {
+        return Err(ErrorData::new(
+            ErrorCode::INVALID_PARAMS,
+            format!(
+                "File '{}' does not exist, you can write a new file with the `write` command",
+                path.display()
+            ),
+            None,
+        ));
+    }
+
+    let content = std::fs::read_to_string(path).map_err(|e| {
+        ErrorData::new(
+            ErrorCode::INTERNAL_ERROR,
+            format!("Failed to read file: {}", e),
+            None,
+        )
+    })?;
+
+    let language = lang::get_language_identifier(path);
+    let applied = apply_symbol_edit(
+        &content,
+        language,
+        &SymbolEdit {
+            anchor: symbol,
+            position,
+            expected,
+            text: new_str,
+        },
+    )
+    .map_err(|e| {
+        ErrorData::new(
+            ErrorCode::INVALID_PARAMS,
+            e.to_string(),
+            serde_json::to_value(&e).ok(),
+        )
+    })?;
+
+    save_file_history(path, file_history)?;
+
+    let mut normalized_content = normalize_line_endings(&applied.content);
+    if !normalized_content.ends_with('\n') {
+        normalized_content.push('\n');
+    }
+    std::fs::write(path, &normalized_content).map_err(|e| {
+        ErrorData::new(
+            ErrorCode::INTERNAL_ERROR,
+            format!("Failed to write file: {}", e),
+            None,
+        )
+    })?;
+
+    const SNIPPET_LINES: usize = 4;
+    let start_line = applied.start_line.saturating_sub(SNIPPET_LINES).max(1);
+    let end_line = applied.end_line + SNIPPET_LINES;
+    let snippet = normalized_content
+        .lines()
+        .enumerate()
+        .skip(start_line - 1)
+        .take(end_line - start_line + 1)
+        .map(|(i, line)| format!("{}: {}", i + 1, line))
+        .collect::<Vec<_>>()
+        .join("\n");
+
+    let output = formatdoc! {r#"
+        ```{language}
+        {snippet}
+        ```
+        "#,
+        language=language,
+        snippet=snippet
+    };
+
+    let merge_note = if applied.merged {
+        format!(
+            "`{}` had changed since it was last viewed; the edit was merged into the current version.\n",
+            symbol
+        )
+    } else {
+        String::new()
+    };
+    let success_message = formatdoc! {r#"
+        The file {} has been edited at `{}`. {}The section now reads:
+        {}
+        Review the changes above for errors. Undo and edit the file again if necessary!
+        "#,
+        path.display(),
+        symbol,
+        merge_note,
+        output
+    };
+
+    Ok(vec![
+        Content::text(success_message).with_audience(vec![Role::Assistant]),
+        Content::text(output)
+            .with_audience(vec![Role::User])
+            .with_priority(0.2),
+    ])
+}
+
 pub async fn text_editor_undo(
     path: &PathBuf,
     file_history: &std::sync::Arc<
@@ -0,0 +1,382 @@
+//! API reference pages for the running goosed.
+//!
+//! Renders the OpenAPI document and the tools of a session's loaded
+//! extensions as Docusaurus markdown, plus a Mermaid diagram of how the
+//! route groups and extensions hang together. The OpenAPI document is walked
+//! as JSON so the pages follow the spec rather than utoipa's types.
+
+use rmcp::model::Tool;
+use serde::Serialize;
+use serde_json::Value;
+use std::collections::BTreeMap;
+use utoipa::ToSchema;
+
+const HTTP_METHODS: [&str; 8] = [
+    "get", "post", "put", "patch", "delete", "head", "options", "trace",
+];
+
+#[derive(Debug, Clone, Serialize, ToSchema)]
+pub struct DocPage {
+    /// Path relative to the docs root, e.g. `api/system.md`
+    pub path: String,
+    pub title: String,
+    pub content: String,
+}
+
+/// Tools grouped by the extension that serves them.
+pub type ExtensionTools = BTreeMap<String, Vec<Tool>>;
+
+/// Groups prefixed tool names (`extension__tool`) by extension.
+pub fn group_tools(tools: Vec<Tool>) -> ExtensionTools {
+    let mut grouped = ExtensionTools::new();
+    for tool in tools {
+        let extension = tool
+            .name
+            .split_once("__")
+            .map(|(extension, _)| extension.to_string())
+            .unwrap_or_else(|| "platform".to_string());
+        grouped.entry(extension).or_default().push(tool);
+    }
+    for tools in grouped.values_mut() {
+        tools.sort_by(|a, b| a.name.cmp(&b.name));
+    }
+    grouped
+}
+
+/// All pages: an index, one page per route group, schemas, tools and the
+/// architecture diagram.
+pub fn render(openapi: &Value, extensions: &ExtensionTools) -> Vec<DocPage> {
+    let groups = route_groups(openapi);
+    let version = openapi["info"]["version"].as_str().unwrap_or("unknown");
+
+    let mut index = front_matter("API Reference", 1);
+    index.push_str(&format!(
+        "Generated from goosed {}.\n\n| Group | Endpoints |\n| --- | --- |\n",
+        version
+    ));
+    for (group, operations) in &groups {
+        index.push_str(&format!(
+            "| [{}](./{}.md) | {} |\n",
+            group,
+            group,
+            operations.len()
+        ));
+    }
+    index.push_str(
+        "\nSee also [schemas](./schemas.md), [tools](./tools.md) \
+         and the [architecture](./architecture.md).\n",
+    );
+
+    let mut pages = vec![DocPage {
+        path: "api/index.md".to_string(),
+        title: "API Reference".to_string(),
+        content: index,
+    }];
+    for (position, (group, operations)) in groups.iter().enumerate() {
+        pages.push(render_group(group, operations, position + 2));
+    }
+    let next = groups.len() + 2;
+    pages.push(render_schemas(openapi, next));
+    pages.push(render_tools(extensions, next + 1));
+    pages.push(render_architecture(&groups, extensions, next + 2));
+    pages
+}
+
+struct Operation<'a> {
+    method: &'a str,
+    path: &'a str,
+    spec: &'a Value,
+}
+
+fn route_groups(openapi: &Value) -> BTreeMap<String, Vec<Operation<'_>>> {
+    let mut groups: BTreeMap<String, Vec<Operation>> = BTreeMap::new();
+    let Some(paths) = openapi["paths"].as_object() else {
+        return groups;
+    };
+    for (path, item) in paths {
+        let group = path
+            .trim_start_matches('/')
+            .split('/')
+            .next()
+            .filter(|segment| !segment.is_empty() && !segment.starts_with('{'))
+            .unwrap_or("root")
+            .to_string();
+        for method in HTTP_METHODS {
+            if let Some(spec) = item.get(method) {
+                groups
+                    .entry(group.clone())
+                    .or_default()
+                    .push(Operation { method, path, spec });
+            }
+        }
+    }
+    groups
+}
+
+fn render_group(group: &str, operations: &[Operation], position: usize) -> DocPage {
+    let title = format!("/{}", group);
+    let mut content = front_matter(&title, position);
+    for operation in operations {
+        content.push_str(&format!(
+            "## `{} {}`\n\n",
+            operation.method.to_uppercase(),
+            operation.path
+        ));
+        for key in ["summary", "description"] {
+            if let Some(text) = operation.spec[key].as_str() {
+                content.push_str(text.trim());
+                content.push_str("\n\n");
+            }
+        }
+
+        if let Some(parameters) = operation.spec["parameters"].as_array() {
+            content.push_str(
+                "| Parameter | In | Required | Description |\n| --- | --- | --- | --- |\n",
+            );
+            for parameter in parameters {
+                content.push_str(&format!(
+                    "| `{}` | {} | {} | {} |\n",
+                    parameter["name"].as_str().unwrap_or_default(),
+                    parameter["in"].as_str().unwrap_or_default(),
+                    parameter["required"].as_bool().unwrap_or(false),
+                    cell(parameter["description"].as_str().unwrap_or_default())
+                ));
+            }
+            content.push('\n');
+        }
+
+        if let Some(schema) = body_schema(&operation.spec["requestBody"]) {
+            content.push_str(&format!("**Request body:** {}\n\n", schema));
+        }
+
+        if let Some(responses) = operation.spec["responses"].as_object() {
+            content.push_str("| Status | Description | Body |\n| --- | --- | --- |\n");
+            for (status, response) in responses {
+                content.push_str(&format!(
+                    "| {} | {} | {} |\n",
+                    status,
+                    cell(response["description"].as_str().unwrap_or_default()),
+                    body_schema(response).unwrap_or_default()
+                ));
+            }
+            content.push('\n');
+        }
+    }
+    DocPage {
+        path: format!("api/{}.md", group),
+        title,
+        content,
+    }
+}
+
+/// Names the JSON body of a request or response, linking component schemas.
+fn body_schema(body: &Value) -> Option<String> {
+    let schema = body["content"]
+        .as_object()?
+        .values()
+        .next()?
+        .get("schema")?;
+    Some(schema_name(schema))
+}
+
+fn schema_name(schema: &Value) -> String {
+    if let Some(reference) = schema["$ref"].as_str() {
+        let name = reference.rsplit('/').next().unwrap_or(reference);
+        return format!("[`{}`](./schemas.md#{})", name, name.to_lowercase());
+    }
+    if schema["type"] == "array" {
+        return format!("array of {}", schema_name(&schema["items"]));
+    }
+    schema["type"]
+        .as_str()
+        .map(|t| format!("`{}`", t))
+        .unwrap_or_else(|| "`object`".to_string())
+}
+
+fn render_schemas(openapi: &Value, position: usize) -> DocPage {
+    let mut content = front_matter("Schemas", position);
+    if let Some(schemas) = openapi["components"]["schemas"].as_object() {
+        for (name, schema) in schemas {
+            content.push_str(&format!("## {}\n\n", name));
+            if let Some(description) = schema["description"].as_str() {
+                content.push_str(description.trim());
+                content.push_str("\n\n");
+            }
+            let Some(properties) = schema["properties"].as_object() else {
+                continue;
+            };
+            let required: Vec<&str> = schema["required"]
+                .as_array()
+                .map(|r| r.iter().filter_map(Value::as_str).collect())
+                .unwrap_or_default();
+            content
+                .push_str("| Field | Type | Required | Description |\n| --- | --- | --- | --- |\n");
+            for (field, property) in properties {
+                content.push_str(&format!(
+                    "| `{}` | {} | {} | {} |\n",
+                    field,
+                    schema_name(property),
+                    required.contains(&field.as_str()),
+                    cell(property["description"].as_str().unwrap_or_default())
+                ));
+            }
+            content.push('\n');
+        }
+    }
+    DocPage {
+        path: "api/schemas.md".to_string(),
+        title: "Schemas".to_string(),
+        content,
+    }
+}
+
+fn render_tools(extensions: &ExtensionTools, position: usize) -> DocPage {
+    let mut content = front_matter("Tools", position);
+    if extensions.is_empty() {
+        content.push_str("No extensions are loaded for this session.\n");
+    }
+    for (extension, tools) in extensions {
+        content.push_str(&format!("## {}\n\n", extension));
+        for tool in tools {
+            content.push_str(&format!("### `{}`\n\n", tool.name));
+            if let Some(description) = &tool.description {
+                content.push_str(description.trim());
+                content.push_str("\n\n");
+            }
+            let schema =
+                serde_json::to_string_pretty(tool.input_schema.as_ref()).unwrap_or_default();
+            content.push_str(&format!("```json\n{}\n```\n\n", schema));
+        }
+    }
+    DocPage {
+        path: "api/tools.md".to_string(),
+        title: "Tools".to_string(),
+        content,
+    }
+}
+
+fn render_architecture(
+    groups: &BTreeMap<String, Vec<Operation>>,
+    extensions: &ExtensionTools,
+    position: usize,
+) -> DocPage {
+    let mut content = front_matter("Architecture", position);
+    content.push_str("```mermaid\nflowchart LR\n");
+    content.push_str("    client[Desktop / CLI / API clients] --> goosed\n");
+    content.push_str("    goosed --> agent[Agent]\n");
+    content.push_str("    agent --> provider[LLM provider]\n");
+    for (group, operations) in groups {
+        content.push_str(&format!(
+            "    goosed --> route_{}[\"/{} ({} endpoints)\"]\n",
+            mermaid_id(group),
+            group,
+            operations.len()
+        ));
+    }
+    for (extension, tools) in extensions {
+        content.push_str(&format!(
+            "    agent --> ext_{}[\"{} ({} tools)\"]\n",
+            mermaid_id(extension),
+            extension,
+            tools.len()
+        ));
+    }
+    content.push_str("```\n");
+    DocPage {
+        path: "api/architecture.md".to_string(),
+        title: "Architecture".to_string(),
+        content,
+    }
+}
+
+fn front_matter(title: &str, position: usize) -> String {
+    format!(
+        "---\ntitle: \"{}\"\nsidebar_position: {}\n---\n\n# {}\n\n",
+        title.replace('"', "\\\""),
+        position,
+        title
+    )
+}
+
+fn cell(text: &str) -> String {
+    text.replace('|', "\\|").replace('\n', " ")
+}
+
+fn mermaid_id(name: &str) -> String {
+    name.chars()
+        .map(|c| if c.is_ascii_alphanumeric() { c } else { '_' })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+    use std::sync::Arc;
+
+    #[test]
+    fn test_renders_routes_schemas_and_tools() {
+        let openapi = json!({
+            "info": { "version": "1.2.3" },
+            "paths": {
+                "/system/drain": {
+                    "get": {
+                        "responses": {
+                            "200": {
+                                "description": "Drain progress",
+                                "content": { "application/json": {
+                                    "schema": { "$ref": "#/components/schemas/DrainStatus" }
+                                } }
+                            }
+                        }
+                    },
+                    "post": { "summary": "Start draining | now", "responses": {} }
+                },
+                "/status": { "get": { "responses": {} } }
+            },
+            "components": { "schemas": { "DrainStatus": {
+                "properties": { "draining": { "type": "boolean" } },
+                "required": ["draining"]
+            } } }
+        });
+        let schema = json!({ "type": "object" }).as_object().unwrap().clone();
+        let extensions = group_tools(vec![
+            Tool::new(
+                "developer__shell",
+                "Run a command",
+                Arc::new(schema.clone()),
+            ),
+            Tool::new("developer__edit", "Edit a file", Arc::new(schema)),
+        ]);
+
+        let pages = render(&openapi, &extensions);
+        let paths: Vec<&str> = pages.iter().map(|p| p.path.as_str()).collect();
+        assert_eq!(
+            paths,
+            vec![
+                "api/index.md",
+                "api/status.md",
+                "api/system.md",
+                "api/schemas.md",
+                "api/tools.md",
+                "api/architecture.md"
+            ]
+        );
+
+        let system = &pages[2].content;
+        assert!(system.starts_with("---\ntitle: \"/system\""));
+        assert!(system.contains("## `GET /system/drain`"));
+        assert!(system.contains("[`DrainStatus`](./schemas.md#drainstatus)"));
+        assert!(system.contains("Start draining | now"));
+        assert!(pages[0].content.contains("goosed 1.2.3"));
+        assert!(pages[3]
+            .content
+            .contains("| `draining` | `boolean` | true |"));
+
+        let tools = &pages[4].content;
+        assert!(tools.find("developer__edit") < tools.find("developer__shell"));
+        assert!(pages[5]
+            .content
+            .contains("agent --> ext_developer[\"developer (2 tools)\"]"));
+    }
+}
@@ -11,8 +11,14 @@ pub async fn check_token(
     next: Next,
 ) -> Result<Response, StatusCode> {
     if request.uri().path() == "/status"
+        || request.uri().path() == crate::kubernetes::LIVENESS_PATH
+        || request.uri().path() == crate::kubernetes::READINESS_PATH
         || request.uri().path() == "/mcp-ui-proxy"
         || request.uri().path() == "/mcp-app-proxy"
+        || request
+            .uri()
+            .path()
+            .starts_with(crate::routes::issues::WEBHOOK_PREFIX)
     {
         return Ok(next.run(request).await);
     }
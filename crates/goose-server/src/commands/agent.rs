@@ -1,10 +1,15 @@
 use crate::configuration;
+use crate::drain::DEFAULT_DRAIN_DEADLINE;
+use crate::idempotency::idempotency;
+use crate::kubernetes;
 use crate::state;
 use anyhow::Result;
 use axum::middleware;
+use goose::startup::{self, StartupPhase};
 use goose_server::auth::check_token;
+use std::future::Future;
 use tower_http::cors::{Any, CorsLayer};
-use tracing::info;
+use tracing::{info, warn};
 
 // Graceful shutdown signal
 #[cfg(unix)]
@@ -26,14 +31,53 @@ async fn shutdown_signal() {
 }
 
 pub async fn run() -> Result<()> {
+    run_until(shutdown_signal()).await
+}
+
+/// Serves until `shutdown` resolves, then drains. Service managers that stop
+/// goosed without a signal, such as the Windows SCM, pass their own trigger.
+pub async fn run_until(shutdown: impl Future<Output = ()> + Send + 'static) -> Result<()> {
     crate::logging::setup_logging(Some("goosed"))?;
 
+    // Mounted ConfigMaps and Secrets must be exported before settings are read
+    let kubernetes_mode = kubernetes::init();
+    let drain_deadline = if kubernetes_mode {
+        kubernetes::eviction_drain_deadline()
+    } else {
+        DEFAULT_DRAIN_DEADLINE
+    };
+
     let settings = configuration::Settings::new()?;
 
     let secret_key =
         std::env::var("GOOSE_SERVER__SECRET_KEY").unwrap_or_else(|_| "test".to_string());
 
-    let app_state = state::AppState::new().await?;
+    let app_state =
+        startup::timed("app_state", StartupPhase::Startup, state::AppState::new()).await?;
+
+    // Kept alive for the lifetime of the server so config edits apply live
+    let _config_watcher = goose::config::Config::global()
+        .watch()
+        .inspect_err(|e| warn!("Config live reload disabled: {}", e))
+        .ok();
+
+    if startup::is_fast_start() {
+        startup::defer("session_maintenance");
+        startup::defer("retention");
+        let deferred_state = app_state.clone();
+        tokio::spawn(async move {
+            startup::first_reply().await;
+            deferred_state.session_manager().spawn_maintenance();
+            goose::privacy::spawn_retention_job();
+        });
+    } else {
+        app_state.session_manager().spawn_maintenance();
+        goose::privacy::spawn_retention_job();
+    }
+    goose::calendar::spawn_refresh();
+    app_state.restart.spawn();
+    app_state.supervisor.spawn();
+    app_state.jobs.spawn(app_state.clone());
 
     let cors = CorsLayer::new()
         .allow_origin(Any)
@@ -41,22 +85,54 @@ pub async fn run() -> Result<()> {
         .allow_headers(Any);
 
     let app = crate::routes::configure(app_state.clone(), secret_key.clone())
+        .layer(middleware::from_fn_with_state(
+            app_state.idempotency.clone(),
+            idempotency,
+        ))
         .layer(middleware::from_fn_with_state(
             secret_key.clone(),
             check_token,
         ))
+        .layer(middleware::from_fn_with_state(
+            app_state.clone(),
+            kubernetes::instance_header,
+        ))
         .layer(cors);
 
     let listener = tokio::net::TcpListener::bind(settings.socket_addr()).await?;
     info!("listening on {}", listener.local_addr()?);
+    startup::mark_ready();
 
     let tunnel_manager = app_state.tunnel_manager.clone();
     tokio::spawn(async move {
         tunnel_manager.check_auto_start().await;
     });
 
+    // Keep serving while draining so open streams finish and new work is refused
+    let drain = app_state.drain.clone();
+    let drain_state = app_state.clone();
+    let drained = async move {
+        tokio::select! {
+            _ = shutdown => {
+                drain.begin(drain_deadline);
+            }
+            _ = drain.requested() => {}
+        }
+        drain.wait().await;
+        if let Err(e) = drain_state.session_manager().flush_all_pending().await {
+            warn!("Failed to write queued session messages: {}", e);
+        }
+        if kubernetes_mode {
+            let status = drain.status();
+            info!(
+                checkpointed_sessions = ?status.checkpointed_sessions,
+                "Drained for pod shutdown"
+            );
+        }
+    };
+
     axum::serve(listener, app)
-        .with_graceful_shutdown(shutdown_signal())
+        .with_graceful_shutdown(drained)
         .await?;
     info!("server shutdown complete");
     Ok(())
@@ -0,0 +1,577 @@
+//! Registers goosed to start at boot under the platform's service manager.
+//!
+//! Linux gets a systemd user unit and macOS a launchd agent; both stop
+//! goosed with SIGTERM, which drains in-flight work before exiting. Windows
+//! gets a service registered with the SCM whose stop request starts the same
+//! drain. Each restarts goosed when it fails and writes to a service log
+//! next to goosed's own logs: its output on Linux and macOS, start and stop
+//! events on Windows, where services have no console.
+//!
+//! The installed server starts without a terminal to configure it from, so
+//! install gives it a secret key (`GOOSE_SERVER__SECRET_KEY` when set,
+//! otherwise a random one) stored where only the installing user, or on
+//! Windows administrators, can read it.
+
+use crate::drain::DEFAULT_DRAIN_DEADLINE;
+use anyhow::{Context, Result};
+use goose::config::paths::Paths;
+use std::path::{Path, PathBuf};
+#[cfg(any(target_os = "linux", target_os = "macos"))]
+use std::process::Command;
+use std::time::Duration;
+
+pub const SERVICE_NAME: &str = "goosed";
+const SERVICE_DESCRIPTION: &str = "goose agent server";
+/// Delay before the service manager starts goosed again after a failure
+const RESTART_DELAY: Duration = Duration::from_secs(5);
+/// Server settings carried into the service's environment when set
+const FORWARDED_ENV: &[&str] = &["GOOSE_HOST", "GOOSE_PORT"];
+const SECRET_KEY_ENV: &str = "GOOSE_SERVER__SECRET_KEY";
+
+/// Time the service manager waits for the drain before killing goosed
+fn stop_timeout() -> Duration {
+    DEFAULT_DRAIN_DEADLINE + Duration::from_secs(10)
+}
+
+pub fn log_path() -> PathBuf {
+    Paths::in_state_dir("logs")
+        .join("server")
+        .join(format!("{}-service.log", SERVICE_NAME))
+}
+
+/// The service's environment: the forwarded settings and its secret key
+fn service_env() -> Vec<(&'static str, String)> {
+    let mut env: Vec<_> = FORWARDED_ENV
+        .iter()
+        .filter_map(|key| std::env::var(key).ok().map(|value| (*key, value)))
+        .collect();
+    let secret = std::env::var(SECRET_KEY_ENV)
+        .ok()
+        .filter(|secret| !secret.is_empty() && secret != "test")
+        .unwrap_or_else(|| hex::encode(rand::random::<[u8; 32]>()));
+    env.push((SECRET_KEY_ENV, secret));
+    env
+}
+
+/// Writes a file only its owner can read, since it holds the secret key.
+#[cfg(any(target_os = "linux", target_os = "macos"))]
+fn write_private(path: &Path, content: &str) -> Result<()> {
+    use std::io::Write;
+    use std::os::unix::fs::{OpenOptionsExt, PermissionsExt};
+    let mut file = std::fs::OpenOptions::new()
+        .write(true)
+        .create(true)
+        .truncate(true)
+        .mode(0o600)
+        .open(path)
+        .with_context(|| format!("Failed to write {}", path.display()))?;
+    // The mode only applies to new files
+    file.set_permissions(std::fs::Permissions::from_mode(0o600))?;
+    file.write_all(content.as_bytes())?;
+    Ok(())
+}
+
+fn current_exe() -> Result<PathBuf> {
+    let exe = std::env::current_exe().context("Failed to locate the goosed executable")?;
+    Ok(exe.canonicalize().unwrap_or(exe))
+}
+
+pub fn install() -> Result<()> {
+    let exe = current_exe()?;
+    let log = log_path();
+    if let Some(dir) = log.parent() {
+        std::fs::create_dir_all(dir)
+            .with_context(|| format!("Failed to create log directory {}", dir.display()))?;
+    }
+    platform::install(&exe, &log)?;
+    println!(
+        "Installed {} service running {}",
+        SERVICE_NAME,
+        exe.display()
+    );
+    println!("Logs: {}", log.display());
+    Ok(())
+}
+
+pub fn uninstall() -> Result<()> {
+    platform::uninstall()?;
+    println!("Uninstalled {} service", SERVICE_NAME);
+    Ok(())
+}
+
+#[cfg(windows)]
+pub use platform::run as run_windows_service;
+
+#[cfg(any(target_os = "linux", target_os = "macos"))]
+fn run_command(program: &str, args: &[&str]) -> Result<()> {
+    let output = Command::new(program)
+        .args(args)
+        .output()
+        .with_context(|| format!("Failed to run {}", program))?;
+    if !output.status.success() {
+        anyhow::bail!(
+            "{} {} failed: {}",
+            program,
+            args.join(" "),
+            String::from_utf8_lossy(&output.stderr).trim()
+        );
+    }
+    Ok(())
+}
+
+#[cfg(any(target_os = "linux", test))]
+fn environment_file(env: &[(&str, String)]) -> String {
+    env.iter()
+        .map(|(key, value)| format!("{}={}\n", key, value))
+        .collect()
+}
+
+#[cfg(any(target_os = "linux", test))]
+fn systemd_unit(exe: &Path, log: &Path, env_file: &Path) -> String {
+    format!(
+        "[Unit]\n\
+         Description={description}\n\
+         After=network-online.target\n\
+         \n\
+         [Service]\n\
+         Type=simple\n\
+         ExecStart=\"{exe}\" agent\n\
+         EnvironmentFile={env_file}\n\
+         Restart=on-failure\n\
+         RestartSec={restart}\n\
+         KillSignal=SIGTERM\n\
+         TimeoutStopSec={stop}\n\
+         StandardOutput=append:{log}\n\
+         StandardError=append:{log}\n\
+         \n\
+         [Install]\n\
+         WantedBy=default.target\n",
+        description = SERVICE_DESCRIPTION,
+        exe = exe.display(),
+        env_file = env_file.display(),
+        restart = RESTART_DELAY.as_secs(),
+        stop = stop_timeout().as_secs(),
+        log = log.display(),
+    )
+}
+
+#[cfg(any(target_os = "macos", test))]
+fn xml_escape(value: &str) -> String {
+    value
+        .replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+}
+
+#[cfg(any(target_os = "macos", test))]
+fn launchd_plist(label: &str, exe: &Path, log: &Path, env: &[(&str, String)]) -> String {
+    let environment: String = env
+        .iter()
+        .map(|(key, value)| {
+            format!(
+                "        <key>{}</key>\n        <string>{}</string>\n",
+                key,
+                xml_escape(value)
+            )
+        })
+        .collect();
+    let log = xml_escape(&log.display().to_string());
+    format!(
+        r#"<?xml version="1.0" encoding="UTF-8"?>
+<!DOCTYPE plist PUBLIC "-//Apple//DTD PLIST 1.0//EN" "http://www.apple.com/DTDs/PropertyList-1.0.dtd">
+<plist version="1.0">
+<dict>
+    <key>Label</key>
+    <string>{label}</string>
+    <key>ProgramArguments</key>
+    <array>
+        <string>{exe}</string>
+        <string>agent</string>
+    </array>
+    <key>EnvironmentVariables</key>
+    <dict>
+{environment}    </dict>
+    <key>RunAtLoad</key>
+    <true/>
+    <key>KeepAlive</key>
+    <dict>
+        <key>SuccessfulExit</key>
+        <false/>
+    </dict>
+    <key>ThrottleInterval</key>
+    <integer>{restart}</integer>
+    <key>ExitTimeOut</key>
+    <integer>{stop}</integer>
+    <key>StandardOutPath</key>
+    <string>{log}</string>
+    <key>StandardErrorPath</key>
+    <string>{log}</string>
+</dict>
+</plist>
+"#,
+        label = label,
+        exe = xml_escape(&exe.display().to_string()),
+        environment = environment,
+        restart = RESTART_DELAY.as_secs(),
+        stop = stop_timeout().as_secs(),
+        log = log,
+    )
+}
+
+#[cfg(target_os = "linux")]
+mod platform {
+    use super::*;
+
+    fn unit_path() -> Result<PathBuf> {
+        let config = dirs::config_dir().context("Could not determine the config directory")?;
+        Ok(config
+            .join("systemd")
+            .join("user")
+            .join(format!("{}.service", SERVICE_NAME)))
+    }
+
+    fn env_file_path() -> PathBuf {
+        Paths::config_dir().join(format!("{}-service.env", SERVICE_NAME))
+    }
+
+    pub fn install(exe: &Path, log: &Path) -> Result<()> {
+        let path = unit_path()?;
+        if let Some(dir) = path.parent() {
+            std::fs::create_dir_all(dir)?;
+        }
+        let env_file = env_file_path();
+        if let Some(dir) = env_file.parent() {
+            std::fs::create_dir_all(dir)?;
+        }
+        write_private(&env_file, &environment_file(&service_env()))?;
+        std::fs::write(&path, systemd_unit(exe, log, &env_file))
+            .with_context(|| format!("Failed to write {}", path.display()))?;
+        run_command("systemctl", &["--user", "daemon-reload"])?;
+        run_command(
+            "systemctl",
+            &[
+                "--user",
+                "enable",
+                "--now",
+                &format!("{}.service", SERVICE_NAME),
+            ],
+        )?;
+        println!("Unit: {}", path.display());
+        println!("Secret key: {} in {}", SECRET_KEY_ENV, env_file.display());
+        println!(
+            "To start it at boot without logging in, run: loginctl enable-linger {}",
+            std::env::var("USER").unwrap_or_else(|_| "$USER".to_string())
+        );
+        Ok(())
+    }
+
+    pub fn uninstall() -> Result<()> {
+        let path = unit_path()?;
+        if !path.exists() {
+            anyhow::bail!("{} is not installed", path.display());
+        }
+        // Stopping lets the running server drain before the unit goes away
+        run_command(
+            "systemctl",
+            &[
+                "--user",
+                "disable",
+                "--now",
+                &format!("{}.service", SERVICE_NAME),
+            ],
+        )?;
+        std::fs::remove_file(&path)?;
+        let _ = std::fs::remove_file(env_file_path());
+        run_command("systemctl", &["--user", "daemon-reload"])
+    }
+}
+
+#[cfg(target_os = "macos")]
+mod platform {
+    use super::*;
+
+    const LABEL: &str = "com.block.goose.goosed";
+
+    fn plist_path() -> Result<PathBuf> {
+        let home = dirs::home_dir().context("Could not determine the home directory")?;
+        Ok(home
+            .join("Library")
+            .join("LaunchAgents")
+            .join(format!("{}.plist", LABEL)))
+    }
+
+    pub fn install(exe: &Path, log: &Path) -> Result<()> {
+        let path = plist_path()?;
+        if let Some(dir) = path.parent() {
+            std::fs::create_dir_all(dir)?;
+        }
+        if path.exists() {
+            // Reinstalling replaces the loaded agent
+            let _ = run_command("launchctl", &["unload", "-w", &path.to_string_lossy()]);
+        }
+        // The plist carries the secret key in its environment
+        write_private(&path, &launchd_plist(LABEL, exe, log, &service_env()))?;
+        run_command("launchctl", &["load", "-w", &path.to_string_lossy()])?;
+        println!("Launch agent: {}", path.display());
+        println!("Secret key: {} in {}", SECRET_KEY_ENV, path.display());
+        Ok(())
+    }
+
+    pub fn uninstall() -> Result<()> {
+        let path = plist_path()?;
+        if !path.exists() {
+            anyhow::bail!("{} is not installed", path.display());
+        }
+        run_command("launchctl", &["unload", "-w", &path.to_string_lossy()])?;
+        std::fs::remove_file(&path)?;
+        Ok(())
+    }
+}
+
+#[cfg(windows)]
+mod platform {
+    use super::*;
+    use std::ffi::OsString;
+    use std::fs::OpenOptions;
+    use std::io::Write;
+    use std::sync::{Mutex, OnceLock};
+    use windows_service::service::{
+        ServiceAccess, ServiceAction, ServiceActionType, ServiceControl, ServiceControlAccept,
+        ServiceErrorControl, ServiceExitCode, ServiceFailureActions, ServiceFailureResetPeriod,
+        ServiceInfo, ServiceStartType, ServiceState, ServiceStatus, ServiceType,
+    };
+    use windows_service::service_control_handler::{
+        self, ServiceControlHandlerResult, ServiceStatusHandle,
+    };
+    use windows_service::service_manager::{ServiceManager, ServiceManagerAccess};
+    use windows_service::{define_windows_service, service_dispatcher};
+    use winreg::enums::{HKEY_LOCAL_MACHINE, KEY_SET_VALUE};
+    use winreg::RegKey;
+
+    /// Hidden subcommand the SCM launches goosed with
+    pub const SERVICE_COMMAND: &str = "service-run";
+    /// Failures within this window count towards the restart actions
+    const FAILURE_RESET_PERIOD: Duration = Duration::from_secs(24 * 60 * 60);
+
+    /// Log file named on the command line the SCM launches goosed with
+    static SERVICE_LOG: OnceLock<PathBuf> = OnceLock::new();
+
+    pub fn install(exe: &Path, log: &Path) -> Result<()> {
+        let manager = ServiceManager::local_computer(
+            None::<&str>,
+            ServiceManagerAccess::CONNECT | ServiceManagerAccess::CREATE_SERVICE,
+        )
+        .context("Failed to connect to the service manager; run as administrator")?;
+        let info = ServiceInfo {
+            name: OsString::from(SERVICE_NAME),
+            display_name: OsString::from(SERVICE_DESCRIPTION),
+            service_type: ServiceType::OWN_PROCESS,
+            start_type: ServiceStartType::AutoStart,
+            error_control: ServiceErrorControl::Normal,
+            executable_path: exe.to_path_buf(),
+            launch_arguments: vec![
+                OsString::from(SERVICE_COMMAND),
+                OsString::from("--log"),
+                log.as_os_str().to_os_string(),
+            ],
+            dependencies: vec![],
+            account_name: None,
+            account_password: None,
+        };
+        let service = manager
+            .create_service(&info, ServiceAccess::CHANGE_CONFIG | ServiceAccess::START)
+            .context("Failed to register the service")?;
+        service.set_description(SERVICE_DESCRIPTION)?;
+        service.update_failure_actions(ServiceFailureActions {
+            reset_period: ServiceFailureResetPeriod::After(FAILURE_RESET_PERIOD),
+            reboot_msg: None,
+            command: None,
+            actions: Some(vec![
+                ServiceAction {
+                    action_type: ServiceActionType::Restart,
+                    delay: RESTART_DELAY,
+                };
+                3
+            ]),
+        })?;
+        // Exiting with an error code counts as a failure, not only crashes
+        service.set_failure_actions_on_non_crash_failures(true)?;
+        set_environment(&service_env())?;
+        service.start::<&str>(&[])?;
+        println!(
+            "Secret key: {} in the service's registry key",
+            SECRET_KEY_ENV
+        );
+        Ok(())
+    }
+
+    /// The SCM reads a service's environment from its registry key, which
+    /// only administrators and SYSTEM can read
+    fn set_environment(env: &[(&str, String)]) -> Result<()> {
+        let key = RegKey::predef(HKEY_LOCAL_MACHINE)
+            .open_subkey_with_flags(
+                format!("SYSTEM\\CurrentControlSet\\Services\\{}", SERVICE_NAME),
+                KEY_SET_VALUE,
+            )
+            .context("Failed to open the service's registry key")?;
+        let values: Vec<String> = env
+            .iter()
+            .map(|(key, value)| format!("{}={}", key, value))
+            .collect();
+        key.set_value("Environment", &values)?;
+        Ok(())
+    }
+
+    pub fn uninstall() -> Result<()> {
+        let manager = ServiceManager::local_computer(None::<&str>, ServiceManagerAccess::CONNECT)
+            .context("Failed to connect to the service manager; run as administrator")?;
+        let service = manager
+            .open_service(
+                SERVICE_NAME,
+                ServiceAccess::QUERY_STATUS | ServiceAccess::STOP | ServiceAccess::DELETE,
+            )
+            .context("The service is not installed")?;
+        if service.query_status()?.current_state != ServiceState::Stopped {
+            service.stop()?;
+            let deadline = std::time::Instant::now() + stop_timeout();
+            while service.query_status()?.current_state != ServiceState::Stopped
+                && std::time::Instant::now() < deadline
+            {
+                std::thread::sleep(Duration::from_millis(500));
+            }
+        }
+        service.delete()?;
+        Ok(())
+    }
+
+    define_windows_service!(ffi_service_main, service_main);
+
+    /// Appends a service lifecycle line to the log file. The server's own
+    /// logs are written by goosed's logging as usual.
+    fn log_event(message: &str) {
+        let Some(path) = SERVICE_LOG.get() else {
+            return;
+        };
+        if let Ok(mut file) = OpenOptions::new().create(true).append(true).open(path) {
+            let _ = writeln!(file, "{} {}", chrono::Utc::now().to_rfc3339(), message);
+        }
+    }
+
+    /// Hands the process to the SCM; returns once the service has stopped.
+    pub fn run(log: PathBuf) -> Result<()> {
+        let _ = SERVICE_LOG.set(log);
+        service_dispatcher::start(SERVICE_NAME, ffi_service_main)
+            .context("goosed service-run must be started by the service manager")?;
+        Ok(())
+    }
+
+    fn service_main(_arguments: Vec<OsString>) {
+        log_event("goosed service starting");
+        match run_service() {
+            Ok(()) => log_event("goosed service stopped"),
+            Err(e) => log_event(&format!("goosed service failed: {:#}", e)),
+        }
+    }
+
+    fn set_state(
+        handle: &ServiceStatusHandle,
+        state: ServiceState,
+        exit_code: u32,
+        wait_hint: Duration,
+    ) -> windows_service::Result<()> {
+        let controls_accepted = if state == ServiceState::Running {
+            ServiceControlAccept::STOP | ServiceControlAccept::SHUTDOWN
+        } else {
+            ServiceControlAccept::empty()
+        };
+        handle.set_service_status(ServiceStatus {
+            service_type: ServiceType::OWN_PROCESS,
+            current_state: state,
+            controls_accepted,
+            exit_code: ServiceExitCode::Win32(exit_code),
+            checkpoint: 0,
+            wait_hint,
+            process_id: None,
+        })
+    }
+
+    fn run_service() -> Result<()> {
+        let (stop_tx, stop_rx) = tokio::sync::oneshot::channel();
+        let stop_tx = Mutex::new(Some(stop_tx));
+        let handle =
+            service_control_handler::register(SERVICE_NAME, move |control| match control {
+                ServiceControl::Stop | ServiceControl::Shutdown => {
+                    if let Some(tx) = stop_tx.lock().unwrap().take() {
+                        let _ = tx.send(());
+                    }
+                    ServiceControlHandlerResult::NoError
+                }
+                ServiceControl::Interrogate => ServiceControlHandlerResult::NoError,
+                _ => ServiceControlHandlerResult::NotImplemented,
+            })?;
+        set_state(&handle, ServiceState::Running, 0, Duration::ZERO)?;
+
+        // The stop request starts the same drain a signal does elsewhere
+        let shutdown = async move {
+            let _ = stop_rx.await;
+            log_event("stop requested, draining");
+            let _ = set_state(&handle, ServiceState::StopPending, 0, stop_timeout());
+        };
+        let result =
+            tokio::runtime::Runtime::new()?.block_on(crate::commands::agent::run_until(shutdown));
+
+        let exit_code = if result.is_ok() { 0 } else { 1 };
+        set_state(&handle, ServiceState::Stopped, exit_code, Duration::ZERO)?;
+        result
+    }
+}
+
+#[cfg(not(any(target_os = "linux", target_os = "macos", windows)))]
+mod platform {
+    use super::*;
+
+    pub fn install(_exe: &Path, _log: &Path) -> Result<()> {
+        anyhow::bail!("Service installation is not supported on this platform")
+    }
+
+    pub fn uninstall() -> Result<()> {
+        anyhow::bail!("Service installation is not supported on this platform")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_service_definitions_wire_restart_logs_and_env() {
+        let exe = Path::new("/opt/goose/bin/goosed");
+        let log = Path::new("/home/me/.local/state/goose/logs/server/goosed-service.log");
+        let env = vec![("GOOSE_PORT", "3000".to_string())];
+        let env_file = Path::new("/home/me/.config/goose/goosed-service.env");
+
+        let unit = systemd_unit(exe, log, env_file);
+        assert!(unit.contains("ExecStart=\"/opt/goose/bin/goosed\" agent\n"));
+        assert!(unit.contains(&format!("EnvironmentFile={}\n", env_file.display())));
+        assert_eq!(environment_file(&env), "GOOSE_PORT=3000\n");
+        assert!(unit.contains("Restart=on-failure\n"));
+        assert!(unit.contains(&format!("StandardOutput=append:{}\n", log.display())));
+        assert!(unit.contains(&format!("TimeoutStopSec={}\n", stop_timeout().as_secs())));
+
+        let plist = launchd_plist("com.block.goose.goosed", exe, log, &env);
+        assert!(plist.contains("<string>/opt/goose/bin/goosed</string>"));
+        assert!(plist.contains("<key>GOOSE_PORT</key>\n        <string>3000</string>"));
+        assert!(plist.contains(&format!("<string>{}</string>", log.display())));
+        assert!(plist.contains("<key>SuccessfulExit</key>\n        <false/>"));
+    }
+    #[test]
+    fn test_service_env_always_has_a_secret_key() {
+        let env = service_env();
+        let (_, secret) = env
+            .iter()
+            .find(|(key, _)| *key == SECRET_KEY_ENV)
+            .expect("secret key");
+        assert!(!secret.is_empty());
+        assert_ne!(secret, "test");
+    }
+}
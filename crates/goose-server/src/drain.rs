@@ -0,0 +1,301 @@
+//! Graceful drain before goosed exits.
+//!
+//! Draining refuses new sessions and replies, tells open reply streams that
+//! the server is restarting, and waits for in-flight turns to finish. Turns
+//! still running at the deadline are cancelled and their conversation is
+//! checkpointed so the session can be resumed after the restart.
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tokio::sync::watch;
+use tokio_util::sync::CancellationToken;
+use utoipa::ToSchema;
+
+pub const DEFAULT_DRAIN_DEADLINE: Duration = Duration::from_secs(30);
+/// How long cancelled turns get to unwind and checkpoint after the deadline
+const CANCEL_GRACE: Duration = Duration::from_secs(5);
+const POLL_INTERVAL: Duration = Duration::from_millis(100);
+
+#[derive(Debug, Clone, Serialize, ToSchema)]
+pub struct DrainStatus {
+    pub draining: bool,
+    pub started_at: Option<DateTime<Utc>>,
+    pub deadline: Option<DateTime<Utc>>,
+    pub active_turns: usize,
+    /// Sessions with a turn in flight
+    pub active_sessions: Vec<String>,
+    /// Sessions whose turns were cancelled at the deadline
+    pub checkpointed_sessions: Vec<String>,
+}
+
+#[derive(Debug, Default, Deserialize, ToSchema)]
+pub struct DrainRequest {
+    /// Seconds in-flight turns get to finish; defaults to 30
+    pub deadline_secs: Option<u64>,
+}
+
+#[derive(Debug, thiserror::Error, PartialEq)]
+pub enum DrainError {
+    #[error("Server is restarting and not accepting new work; retry shortly")]
+    Draining,
+}
+
+struct Turn {
+    session_id: String,
+    cancel: CancellationToken,
+    interrupted: Arc<AtomicBool>,
+}
+
+#[derive(Default)]
+struct DrainState {
+    started_at: Option<DateTime<Utc>>,
+    turns: HashMap<u64, Turn>,
+    next_turn_id: u64,
+    checkpointed: Vec<String>,
+    /// When a turn last started or finished
+    last_activity: DateTime<Utc>,
+}
+
+pub struct Drain {
+    state: Mutex<DrainState>,
+    deadline: watch::Sender<Option<DateTime<Utc>>>,
+    requested: CancellationToken,
+}
+
+impl Default for Drain {
+    fn default() -> Self {
+        Self {
+            state: Mutex::new(DrainState {
+                last_activity: Utc::now(),
+                ..Default::default()
+            }),
+            deadline: watch::channel(None).0,
+            requested: CancellationToken::new(),
+        }
+    }
+}
+
+/// Keeps a turn registered with the drain until dropped.
+pub struct TurnRegistration {
+    owner: Arc<Drain>,
+    id: u64,
+    interrupted: Arc<AtomicBool>,
+    /// Receives the drain deadline once the server starts restarting
+    pub restarting: watch::Receiver<Option<DateTime<Utc>>>,
+}
+
+impl TurnRegistration {
+    /// Whether the turn was cancelled because the drain deadline passed.
+    pub fn interrupted(&self) -> bool {
+        self.interrupted.load(Ordering::SeqCst)
+    }
+}
+
+impl Drop for TurnRegistration {
+    fn drop(&mut self) {
+        self.owner.with_state(|state| {
+            state.turns.remove(&self.id);
+            state.last_activity = Utc::now();
+        });
+    }
+}
+
+impl Drain {
+    fn with_state<T>(&self, f: impl FnOnce(&mut DrainState) -> T) -> T {
+        let mut state = self.state.lock().unwrap_or_else(|e| e.into_inner());
+        f(&mut state)
+    }
+
+    pub fn is_draining(&self) -> bool {
+        self.deadline.borrow().is_some()
+    }
+
+    /// Fails once draining has started so no new sessions are created.
+    pub fn ensure_accepting(&self) -> Result<(), DrainError> {
+        if self.is_draining() {
+            Err(DrainError::Draining)
+        } else {
+            Ok(())
+        }
+    }
+
+    /// Track a running turn so the drain can wait for it or cancel it.
+    pub fn register_turn(
+        self: &Arc<Self>,
+        session_id: impl Into<String>,
+        cancel: CancellationToken,
+    ) -> Result<TurnRegistration, DrainError> {
+        self.with_state(|state| {
+            if self.is_draining() {
+                return Err(DrainError::Draining);
+            }
+            let id = state.next_turn_id;
+            state.next_turn_id += 1;
+            state.last_activity = Utc::now();
+            let interrupted = Arc::new(AtomicBool::new(false));
+            state.turns.insert(
+                id,
+                Turn {
+                    session_id: session_id.into(),
+                    cancel,
+                    interrupted: interrupted.clone(),
+                },
+            );
+            Ok(TurnRegistration {
+                owner: self.clone(),
+                id,
+                interrupted,
+                restarting: self.deadline.subscribe(),
+            })
+        })
+    }
+
+    pub fn status(&self) -> DrainStatus {
+        let deadline = *self.deadline.borrow();
+        self.with_state(|state| DrainStatus {
+            draining: deadline.is_some(),
+            started_at: state.started_at,
+            deadline,
+            active_turns: state.turns.len(),
+            active_sessions: {
+                let mut sessions: Vec<String> =
+                    state.turns.values().map(|t| t.session_id.clone()).collect();
+                sessions.sort();
+                sessions.dedup();
+                sessions
+            },
+            checkpointed_sessions: state.checkpointed.clone(),
+        })
+    }
+
+    /// When the last turn finished, or `None` while turns are running.
+    pub fn idle_since(&self) -> Option<DateTime<Utc>> {
+        self.with_state(|state| state.turns.is_empty().then_some(state.last_activity))
+    }
+
+    /// Starts draining. Calling again while draining keeps the first deadline.
+    pub fn begin(&self, deadline: Duration) -> DrainStatus {
+        self.with_state(|state| {
+            if state.started_at.is_none() {
+                let now = Utc::now();
+                state.started_at = Some(now);
+                let deadline = now + chrono::Duration::from_std(deadline).unwrap_or_default();
+                self.deadline.send_replace(Some(deadline));
+                tracing::info!(
+                    active_turns = state.turns.len(),
+                    "Draining before shutdown until {}",
+                    deadline
+                );
+            }
+        });
+        self.requested.cancel();
+        self.status()
+    }
+
+    /// Resolves once draining has been requested.
+    pub async fn requested(&self) {
+        self.requested.cancelled().await
+    }
+
+    /// Waits for in-flight turns to finish, cancelling those still running at
+    /// the deadline and giving them a short grace period to checkpoint.
+    pub async fn wait(&self) {
+        let Some(deadline) = *self.deadline.borrow() else {
+            return;
+        };
+        let deadline = tokio::time::Instant::now()
+            + (deadline - Utc::now()).to_std().unwrap_or(Duration::ZERO);
+
+        if !self.wait_until_idle(deadline).await {
+            let cancelled = self.with_state(|state| {
+                let sessions: Vec<String> = state
+                    .turns
+                    .values()
+                    .map(|turn| {
+                        turn.interrupted.store(true, Ordering::SeqCst);
+                        turn.cancel.cancel();
+                        turn.session_id.clone()
+                    })
+                    .collect();
+                state.checkpointed.extend(sessions.iter().cloned());
+                sessions
+            });
+            tracing::warn!(
+                "Drain deadline passed, cancelled turns for sessions: {:?}",
+                cancelled
+            );
+            if !self
+                .wait_until_idle(tokio::time::Instant::now() + CANCEL_GRACE)
+                .await
+            {
+                tracing::error!("Cancelled turns did not finish before shutdown");
+            }
+        }
+    }
+
+    async fn wait_until_idle(&self, deadline: tokio::time::Instant) -> bool {
+        loop {
+            if self.with_state(|state| state.turns.is_empty()) {
+                return true;
+            }
+            if tokio::time::Instant::now() >= deadline {
+                return false;
+            }
+            tokio::time::sleep(POLL_INTERVAL).await;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_draining_refuses_new_turns_and_waits_for_running_ones() {
+        let drain = Arc::new(Drain::default());
+        let mut turn = drain.register_turn("s1", CancellationToken::new()).unwrap();
+
+        drain.begin(Duration::from_secs(10));
+        assert!(turn.restarting.has_changed().unwrap());
+        assert_eq!(
+            drain.register_turn("s2", CancellationToken::new()).err(),
+            Some(DrainError::Draining)
+        );
+        assert_eq!(drain.ensure_accepting(), Err(DrainError::Draining));
+        assert!(turn.restarting.borrow_and_update().is_some());
+
+        let waiter = {
+            let drain = drain.clone();
+            tokio::spawn(async move { drain.wait().await })
+        };
+        drop(turn);
+        waiter.await.unwrap();
+
+        let status = drain.status();
+        assert_eq!(status.active_turns, 0);
+        assert!(status.checkpointed_sessions.is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_deadline_cancels_and_marks_turns_interrupted() {
+        let drain = Arc::new(Drain::default());
+        let cancel = CancellationToken::new();
+        let turn = drain.register_turn("s1", cancel.clone()).unwrap();
+
+        drain.begin(Duration::ZERO);
+        let waiter = {
+            let drain = drain.clone();
+            tokio::spawn(async move { drain.wait().await })
+        };
+        cancel.cancelled().await;
+        assert!(turn.interrupted());
+        drop(turn);
+        waiter.await.unwrap();
+
+        assert_eq!(drain.status().checkpointed_sessions, vec!["s1".to_string()]);
+    }
+}
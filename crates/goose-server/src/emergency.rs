@@ -0,0 +1,402 @@
+//! Global emergency stop for goosed.
+//!
+//! Stopping cancels every in-flight reply, pauses and kills scheduled jobs and
+//! refuses new work. Resuming takes two calls: one to request a confirmation
+//! code and one to confirm it before the code expires. Stop and resume events
+//! are appended to the audit log.
+
+use chrono::{DateTime, Duration, Utc};
+use goose::scheduler_trait::SchedulerTrait;
+use goose::security::audit_log::{self, AuditLog};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use tokio_util::sync::CancellationToken;
+use utoipa::ToSchema;
+
+const AUDIT_CATEGORY: &str = "emergency_stop";
+const RESUME_CONFIRMATION_SECS: i64 = 120;
+const MAX_RECENT_EVENTS: usize = 20;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum EmergencyAction {
+    Stop,
+    ResumeRequested,
+    Resume,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct EmergencyEvent {
+    pub action: EmergencyAction,
+    pub timestamp: DateTime<Utc>,
+    pub reason: Option<String>,
+    pub cancelled_replies: usize,
+    pub schedules: Vec<String>,
+}
+
+#[derive(Debug, Clone, Serialize, ToSchema)]
+pub struct EmergencyStatus {
+    pub stopped: bool,
+    pub stopped_at: Option<DateTime<Utc>>,
+    pub reason: Option<String>,
+    /// Schedules paused by the stop; they are unpaused on resume
+    pub paused_schedules: Vec<String>,
+    pub resume_pending: bool,
+    pub active_replies: usize,
+    pub recent_events: Vec<EmergencyEvent>,
+}
+
+#[derive(Debug, Clone, Serialize, ToSchema)]
+pub struct ResumeChallenge {
+    /// Send back to confirm the resume
+    pub confirmation_code: String,
+    pub expires_at: DateTime<Utc>,
+}
+
+#[derive(Debug, thiserror::Error, PartialEq)]
+pub enum EmergencyError {
+    #[error("Emergency stop is active; resume before starting new work")]
+    Stopped,
+    #[error("Emergency stop is not active")]
+    NotStopped,
+    #[error("Resume confirmation code is invalid or expired")]
+    InvalidConfirmation,
+}
+
+struct StopInfo {
+    stopped_at: DateTime<Utc>,
+    reason: Option<String>,
+    paused_schedules: Vec<String>,
+}
+
+#[derive(Default)]
+struct EmergencyState {
+    stopped: Option<StopInfo>,
+    pending_resume: Option<ResumeChallenge>,
+    replies: HashMap<u64, CancellationToken>,
+    next_reply_id: u64,
+    recent_events: Vec<EmergencyEvent>,
+}
+
+#[derive(Default)]
+pub struct EmergencyStop {
+    state: Mutex<EmergencyState>,
+    audit_log: Option<Arc<AuditLog>>,
+}
+
+/// Keeps a reply registered for cancellation until dropped.
+pub struct ReplyRegistration {
+    owner: Arc<EmergencyStop>,
+    id: u64,
+}
+
+impl Drop for ReplyRegistration {
+    fn drop(&mut self) {
+        self.owner
+            .with_state(|state| state.replies.remove(&self.id));
+    }
+}
+
+impl EmergencyStop {
+    /// Instance that writes stop and resume events to the audit log on disk.
+    pub fn persistent() -> Self {
+        Self {
+            state: Mutex::new(EmergencyState::default()),
+            audit_log: audit_log::global(),
+        }
+    }
+
+    fn with_state<T>(&self, f: impl FnOnce(&mut EmergencyState) -> T) -> T {
+        let mut state = self.state.lock().unwrap_or_else(|e| e.into_inner());
+        f(&mut state)
+    }
+
+    pub fn is_stopped(&self) -> bool {
+        self.with_state(|state| state.stopped.is_some())
+    }
+
+    /// Fails while stopped so no new work starts.
+    pub fn ensure_running(&self) -> Result<(), EmergencyError> {
+        if self.is_stopped() {
+            Err(EmergencyError::Stopped)
+        } else {
+            Ok(())
+        }
+    }
+
+    /// Track a running reply so an emergency stop can cancel it.
+    pub fn register_reply(
+        self: &Arc<Self>,
+        token: CancellationToken,
+    ) -> Result<ReplyRegistration, EmergencyError> {
+        self.with_state(|state| {
+            if state.stopped.is_some() {
+                return Err(EmergencyError::Stopped);
+            }
+            let id = state.next_reply_id;
+            state.next_reply_id += 1;
+            state.replies.insert(id, token);
+            Ok(ReplyRegistration {
+                owner: self.clone(),
+                id,
+            })
+        })
+    }
+
+    pub fn status(&self) -> EmergencyStatus {
+        self.with_state(|state| EmergencyStatus {
+            stopped: state.stopped.is_some(),
+            stopped_at: state.stopped.as_ref().map(|s| s.stopped_at),
+            reason: state.stopped.as_ref().and_then(|s| s.reason.clone()),
+            paused_schedules: state
+                .stopped
+                .as_ref()
+                .map(|s| s.paused_schedules.clone())
+                .unwrap_or_default(),
+            resume_pending: state.pending_resume.is_some(),
+            active_replies: state.replies.len(),
+            recent_events: state.recent_events.clone(),
+        })
+    }
+
+    /// Cancel all replies, pause and kill scheduled jobs, and block new work.
+    /// Stopping again while stopped cancels anything that slipped through.
+    pub async fn stop(
+        &self,
+        scheduler: &dyn SchedulerTrait,
+        reason: Option<String>,
+    ) -> EmergencyStatus {
+        let cancelled_replies = self.with_state(|state| {
+            state.pending_resume = None;
+            if state.stopped.is_none() {
+                state.stopped = Some(StopInfo {
+                    stopped_at: Utc::now(),
+                    reason: reason.clone(),
+                    paused_schedules: Vec::new(),
+                });
+            }
+            let tokens: Vec<_> = state.replies.drain().map(|(_, token)| token).collect();
+            tokens.iter().for_each(|token| token.cancel());
+            tokens.len()
+        });
+
+        let mut paused = Vec::new();
+        for job in scheduler.list_scheduled_jobs().await {
+            if !job.paused {
+                match scheduler.pause_schedule(&job.id).await {
+                    Ok(()) => paused.push(job.id.clone()),
+                    Err(e) => tracing::error!("Emergency stop failed to pause {}: {}", job.id, e),
+                }
+            }
+            if job.currently_running {
+                if let Err(e) = scheduler.kill_running_job(&job.id).await {
+                    tracing::error!("Emergency stop failed to kill {}: {}", job.id, e);
+                }
+            }
+        }
+
+        self.with_state(|state| {
+            if let Some(info) = state.stopped.as_mut() {
+                info.paused_schedules.extend(paused.iter().cloned());
+            }
+        });
+
+        tracing::warn!(
+            cancelled_replies,
+            paused_schedules = paused.len(),
+            "Emergency stop engaged"
+        );
+        self.record(EmergencyEvent {
+            action: EmergencyAction::Stop,
+            timestamp: Utc::now(),
+            reason,
+            cancelled_replies,
+            schedules: paused,
+        });
+        self.status()
+    }
+
+    /// First resume step: issue a short-lived confirmation code.
+    pub fn request_resume(&self) -> Result<ResumeChallenge, EmergencyError> {
+        let challenge = self.with_state(|state| {
+            if state.stopped.is_none() {
+                return Err(EmergencyError::NotStopped);
+            }
+            let challenge = ResumeChallenge {
+                confirmation_code: uuid::Uuid::new_v4().simple().to_string(),
+                expires_at: Utc::now() + Duration::seconds(RESUME_CONFIRMATION_SECS),
+            };
+            state.pending_resume = Some(challenge.clone());
+            Ok(challenge)
+        })?;
+
+        self.record(EmergencyEvent {
+            action: EmergencyAction::ResumeRequested,
+            timestamp: Utc::now(),
+            reason: None,
+            cancelled_replies: 0,
+            schedules: Vec::new(),
+        });
+        Ok(challenge)
+    }
+
+    /// Second resume step: check the code, then unpause the schedules the stop paused.
+    pub async fn confirm_resume(
+        &self,
+        scheduler: &dyn SchedulerTrait,
+        confirmation_code: &str,
+    ) -> Result<EmergencyStatus, EmergencyError> {
+        let paused = self.take_stop(confirmation_code)?;
+
+        for id in &paused {
+            if let Err(e) = scheduler.unpause_schedule(id).await {
+                tracing::warn!("Failed to unpause {} after emergency stop: {}", id, e);
+            }
+        }
+
+        tracing::warn!(unpaused_schedules = paused.len(), "Emergency stop lifted");
+        self.record(EmergencyEvent {
+            action: EmergencyAction::Resume,
+            timestamp: Utc::now(),
+            reason: None,
+            cancelled_replies: 0,
+            schedules: paused,
+        });
+        Ok(self.status())
+    }
+
+    /// Validate the confirmation code and clear the stop, returning the
+    /// schedules it paused.
+    fn take_stop(&self, confirmation_code: &str) -> Result<Vec<String>, EmergencyError> {
+        self.with_state(|state| {
+            if state.stopped.is_none() {
+                return Err(EmergencyError::NotStopped);
+            }
+            let valid = state.pending_resume.as_ref().is_some_and(|challenge| {
+                challenge.confirmation_code == confirmation_code
+                    && challenge.expires_at > Utc::now()
+            });
+            if !valid {
+                return Err(EmergencyError::InvalidConfirmation);
+            }
+            state.pending_resume = None;
+            Ok(state
+                .stopped
+                .take()
+                .map(|info| info.paused_schedules)
+                .unwrap_or_default())
+        })
+    }
+
+    fn record(&self, event: EmergencyEvent) {
+        if let Some(log) = &self.audit_log {
+            let entry = serde_json::to_value(&event)
+                .map_err(anyhow::Error::from)
+                .and_then(|data| log.append(AUDIT_CATEGORY, data));
+            if let Err(e) = entry {
+                tracing::error!("Failed to write emergency stop audit entry: {}", e);
+            }
+        }
+        self.with_state(|state| {
+            state.recent_events.push(event);
+            if state.recent_events.len() > MAX_RECENT_EVENTS {
+                state.recent_events.remove(0);
+            }
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn stopped(audit_log: Option<Arc<AuditLog>>) -> Arc<EmergencyStop> {
+        let stop = Arc::new(EmergencyStop {
+            state: Mutex::new(EmergencyState::default()),
+            audit_log,
+        });
+        stop.with_state(|state| {
+            state.stopped = Some(StopInfo {
+                stopped_at: Utc::now(),
+                reason: Some("test".to_string()),
+                paused_schedules: vec!["nightly".to_string()],
+            })
+        });
+        stop
+    }
+
+    #[test]
+    fn test_registration_is_released_on_drop() {
+        let stop = Arc::new(EmergencyStop::default());
+        let registration = stop.register_reply(CancellationToken::new()).unwrap();
+        assert_eq!(stop.status().active_replies, 1);
+        drop(registration);
+        assert_eq!(stop.status().active_replies, 0);
+    }
+
+    #[test]
+    fn test_new_work_is_refused_while_stopped() {
+        let stop = stopped(None);
+        assert!(matches!(
+            stop.register_reply(CancellationToken::new()),
+            Err(EmergencyError::Stopped)
+        ));
+        assert_eq!(stop.ensure_running(), Err(EmergencyError::Stopped));
+    }
+
+    #[test]
+    fn test_resume_takes_two_steps() {
+        assert_eq!(
+            EmergencyStop::default().request_resume().unwrap_err(),
+            EmergencyError::NotStopped
+        );
+
+        let stop = stopped(None);
+        assert_eq!(
+            stop.take_stop("guess"),
+            Err(EmergencyError::InvalidConfirmation)
+        );
+
+        let challenge = stop.request_resume().unwrap();
+        assert!(stop.status().resume_pending);
+        assert_eq!(
+            stop.take_stop(&challenge.confirmation_code),
+            Ok(vec!["nightly".to_string()])
+        );
+        assert!(!stop.is_stopped());
+    }
+
+    #[test]
+    fn test_expired_confirmation_is_rejected() {
+        let stop = stopped(None);
+        let challenge = stop.request_resume().unwrap();
+        stop.with_state(|state| {
+            state.pending_resume.as_mut().unwrap().expires_at = Utc::now() - Duration::seconds(1)
+        });
+        assert_eq!(
+            stop.take_stop(&challenge.confirmation_code),
+            Err(EmergencyError::InvalidConfirmation)
+        );
+        assert!(stop.is_stopped());
+    }
+
+    #[test]
+    fn test_audit_entries_are_appended() {
+        let dir = std::env::temp_dir().join(format!("goose-audit-{}", uuid::Uuid::new_v4()));
+        let log =
+            Arc::new(AuditLog::open(dir.join(audit_log::AUDIT_LOG_FILE), b"key".to_vec()).unwrap());
+        let stop = stopped(Some(log.clone()));
+        stop.request_resume().unwrap();
+        stop.request_resume().unwrap();
+
+        let records = log.records().unwrap();
+        let verification = log.verify().unwrap();
+        let _ = std::fs::remove_dir_all(&dir);
+        assert_eq!(records.len(), 2);
+        assert_eq!(records[0].category, "emergency_stop");
+        assert_eq!(records[0].data["action"], "resume_requested");
+        assert!(verification.valid);
+        assert_eq!(stop.status().recent_events.len(), 2);
+    }
+}
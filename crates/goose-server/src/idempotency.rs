@@ -0,0 +1,417 @@
+//! Idempotency keys for mutating requests.
+//!
+//! A client that may retry a POST, PUT, PATCH or DELETE sends an
+//! `Idempotency-Key` header. The first response for a (key, route) pair is
+//! stored with a hash of the request body and replayed on retries instead of
+//! running the handler again. Reusing a key with a different body is rejected,
+//! as is a retry that arrives while the original request is still running.
+//! Server errors and streamed responses are never stored.
+//!
+//! Keyed requests and stored responses are limited to a small body size, and
+//! the store holds a bounded number of responses and bytes, evicting the least
+//! recently used ones first.
+
+use crate::routes::errors::{ErrorCode, ErrorResponse};
+use axum::{
+    body::{to_bytes, Body, Bytes},
+    extract::{Request, State},
+    http::{header, HeaderMap, HeaderValue, Method, StatusCode},
+    middleware::Next,
+    response::{IntoResponse, Response},
+};
+use std::collections::HashMap;
+use std::hash::{DefaultHasher, Hash, Hasher};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+pub const IDEMPOTENCY_KEY_HEADER: &str = "idempotency-key";
+/// Set on responses that were replayed from the store
+pub const REPLAYED_HEADER: &str = "idempotent-replayed";
+
+const DEFAULT_TTL: Duration = Duration::from_secs(24 * 60 * 60);
+const MAX_KEY_LEN: usize = 255;
+/// Largest request body accepted with an idempotency key
+const MAX_KEYED_BODY_BYTES: usize = 1024 * 1024;
+/// Larger responses are returned but not stored
+const MAX_STORED_BODY_BYTES: usize = 1024 * 1024;
+const MAX_ENTRIES: usize = 10_000;
+/// Total size of the stored response bodies
+const MAX_STORED_BYTES: usize = 64 * 1024 * 1024;
+
+type EntryKey = (String, String);
+
+#[derive(Clone)]
+struct StoredResponse {
+    status: StatusCode,
+    headers: HeaderMap,
+    body: Bytes,
+}
+
+enum Entry {
+    InFlight {
+        body_hash: u64,
+    },
+    Complete {
+        body_hash: u64,
+        response: StoredResponse,
+        expires_at: Instant,
+        last_used: Instant,
+    },
+}
+
+enum Begin {
+    Proceed,
+    Replay(StoredResponse),
+    Mismatch,
+    InFlight,
+}
+
+pub struct IdempotencyStore {
+    ttl: Duration,
+    max_entries: usize,
+    max_bytes: usize,
+    entries: Mutex<HashMap<EntryKey, Entry>>,
+}
+
+impl Default for IdempotencyStore {
+    fn default() -> Self {
+        Self::new(DEFAULT_TTL)
+    }
+}
+
+impl IdempotencyStore {
+    pub fn new(ttl: Duration) -> Self {
+        Self {
+            ttl,
+            max_entries: MAX_ENTRIES,
+            max_bytes: MAX_STORED_BYTES,
+            entries: Mutex::new(HashMap::new()),
+        }
+    }
+
+    fn begin(&self, key: &EntryKey, body_hash: u64) -> Begin {
+        let mut entries = self.entries.lock().unwrap();
+        let now = Instant::now();
+        entries.retain(|_, entry| match entry {
+            Entry::InFlight { .. } => true,
+            Entry::Complete { expires_at, .. } => *expires_at > now,
+        });
+
+        match entries.get_mut(key) {
+            None => {
+                entries.insert(key.clone(), Entry::InFlight { body_hash });
+                Begin::Proceed
+            }
+            Some(Entry::InFlight { body_hash: stored }) if *stored == body_hash => Begin::InFlight,
+            Some(Entry::Complete {
+                body_hash: stored,
+                response,
+                last_used,
+                ..
+            }) if *stored == body_hash => {
+                *last_used = now;
+                Begin::Replay(response.clone())
+            }
+            Some(_) => Begin::Mismatch,
+        }
+    }
+
+    fn complete(&self, key: &EntryKey, body_hash: u64, response: StoredResponse) {
+        let mut entries = self.entries.lock().unwrap();
+        let now = Instant::now();
+        entries.insert(
+            key.clone(),
+            Entry::Complete {
+                body_hash,
+                response,
+                expires_at: now + self.ttl,
+                last_used: now,
+            },
+        );
+
+        let stored_bytes = |entries: &HashMap<EntryKey, Entry>| {
+            entries
+                .values()
+                .map(|entry| match entry {
+                    Entry::InFlight { .. } => 0,
+                    Entry::Complete { response, .. } => response.body.len(),
+                })
+                .sum::<usize>()
+        };
+        while entries.len() > self.max_entries || stored_bytes(&entries) > self.max_bytes {
+            let oldest = entries
+                .iter()
+                .filter_map(|(key, entry)| match entry {
+                    Entry::InFlight { .. } => None,
+                    Entry::Complete { last_used, .. } => Some((key, *last_used)),
+                })
+                .min_by_key(|(_, last_used)| *last_used)
+                .map(|(key, _)| key.clone());
+            match oldest {
+                Some(oldest) => entries.remove(&oldest),
+                None => break,
+            };
+        }
+    }
+
+    fn abandon(&self, key: &EntryKey) {
+        let mut entries = self.entries.lock().unwrap();
+        if matches!(entries.get(key), Some(Entry::InFlight { .. })) {
+            entries.remove(key);
+        }
+    }
+}
+
+/// Clears the in-flight marker if the request fails, streams, or is dropped
+/// before a response could be stored, so a retry runs the handler again.
+struct InFlightGuard<'a> {
+    store: &'a IdempotencyStore,
+    key: EntryKey,
+}
+
+impl Drop for InFlightGuard<'_> {
+    fn drop(&mut self) {
+        self.store.abandon(&self.key);
+    }
+}
+
+fn is_mutating(method: &Method) -> bool {
+    matches!(
+        *method,
+        Method::POST | Method::PUT | Method::PATCH | Method::DELETE
+    )
+}
+
+fn is_stream(response: &Response) -> bool {
+    response
+        .headers()
+        .get(header::CONTENT_TYPE)
+        .and_then(|value| value.to_str().ok())
+        .is_some_and(|value| value.starts_with("text/event-stream"))
+}
+
+fn hash_body(body: &[u8]) -> u64 {
+    let mut hasher = DefaultHasher::new();
+    body.hash(&mut hasher);
+    hasher.finish()
+}
+
+fn replay(stored: StoredResponse) -> Response {
+    let mut response = Response::new(Body::from(stored.body));
+    *response.status_mut() = stored.status;
+    *response.headers_mut() = stored.headers;
+    response
+        .headers_mut()
+        .insert(REPLAYED_HEADER, HeaderValue::from_static("true"));
+    response
+}
+
+pub async fn idempotency(
+    State(store): State<Arc<IdempotencyStore>>,
+    request: Request,
+    next: Next,
+) -> Response {
+    if !is_mutating(request.method()) {
+        return next.run(request).await;
+    }
+    let Some(key) = request
+        .headers()
+        .get(IDEMPOTENCY_KEY_HEADER)
+        .map(|value| value.to_str().map(str::to_string))
+    else {
+        return next.run(request).await;
+    };
+    let key = match key {
+        Ok(key) if !key.is_empty() && key.len() <= MAX_KEY_LEN => key,
+        _ => {
+            return ErrorResponse::bad_request(format!(
+                "Idempotency key must be 1 to {} visible ASCII characters",
+                MAX_KEY_LEN
+            ))
+            .into_response()
+        }
+    };
+
+    let entry_key = (
+        key,
+        format!("{} {}", request.method(), request.uri().path()),
+    );
+    let (parts, body) = request.into_parts();
+    let body = match to_bytes(body, MAX_KEYED_BODY_BYTES).await {
+        Ok(body) => body,
+        Err(e) => {
+            return ErrorResponse::new(
+                StatusCode::PAYLOAD_TOO_LARGE,
+                format!(
+                    "Requests with an idempotency key are limited to {} bytes: {}",
+                    MAX_KEYED_BODY_BYTES, e
+                ),
+            )
+            .into_response()
+        }
+    };
+    let body_hash = hash_body(&body);
+
+    match store.begin(&entry_key, body_hash) {
+        Begin::Proceed => {}
+        Begin::Replay(stored) => return replay(stored),
+        Begin::Mismatch => {
+            return ErrorResponse::from_code(
+                ErrorCode::IdempotencyKeyReused,
+                "Idempotency key was already used with a different request body",
+            )
+            .into_response()
+        }
+        Begin::InFlight => {
+            return ErrorResponse::from_code(
+                ErrorCode::RequestInProgress,
+                "A request with this idempotency key is still in progress",
+            )
+            .into_response()
+        }
+    }
+
+    let guard = InFlightGuard {
+        store: &store,
+        key: entry_key,
+    };
+    let response = next.run(Request::from_parts(parts, Body::from(body))).await;
+    if response.status().is_server_error() || is_stream(&response) {
+        return response;
+    }
+
+    let (parts, body) = response.into_parts();
+    let body = match to_bytes(body, usize::MAX).await {
+        Ok(body) => body,
+        Err(e) => {
+            return ErrorResponse::internal(format!("Failed to read response body: {}", e))
+                .into_response()
+        }
+    };
+    if body.len() > MAX_STORED_BODY_BYTES {
+        return Response::from_parts(parts, Body::from(body));
+    }
+    store.complete(
+        &guard.key,
+        body_hash,
+        StoredResponse {
+            status: parts.status,
+            headers: parts.headers.clone(),
+            body: body.clone(),
+        },
+    );
+    Response::from_parts(parts, Body::from(body))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::{middleware, routing::post, Router};
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use tower::ServiceExt;
+
+    fn app(store: Arc<IdempotencyStore>, calls: Arc<AtomicUsize>) -> Router {
+        Router::new()
+            .route(
+                "/sessions",
+                post(move |body: String| async move {
+                    let n = calls.fetch_add(1, Ordering::SeqCst);
+                    if body == "fail" {
+                        StatusCode::INTERNAL_SERVER_ERROR.into_response()
+                    } else {
+                        format!("session-{}", n).into_response()
+                    }
+                }),
+            )
+            .layer(middleware::from_fn_with_state(store, idempotency))
+    }
+
+    fn request(key: Option<&str>, body: &str) -> Request {
+        let mut builder = Request::builder().method("POST").uri("/sessions");
+        if let Some(key) = key {
+            builder = builder.header(IDEMPOTENCY_KEY_HEADER, key);
+        }
+        builder.body(Body::from(body.to_string())).unwrap()
+    }
+
+    async fn send(app: &Router, request: Request) -> (StatusCode, bool, String) {
+        let response = app.clone().oneshot(request).await.unwrap();
+        let status = response.status();
+        let replayed = response.headers().contains_key(REPLAYED_HEADER);
+        let body = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        (status, replayed, String::from_utf8(body.to_vec()).unwrap())
+    }
+
+    #[tokio::test]
+    async fn test_retry_replays_original_response() {
+        let store = Arc::new(IdempotencyStore::default());
+        let calls = Arc::new(AtomicUsize::new(0));
+        let app = app(store.clone(), calls.clone());
+
+        let first = send(&app, request(Some("abc"), "new")).await;
+        let retry = send(&app, request(Some("abc"), "new")).await;
+        assert_eq!(first, (StatusCode::OK, false, "session-0".to_string()));
+        assert_eq!(retry, (StatusCode::OK, true, "session-0".to_string()));
+        assert_eq!(calls.load(Ordering::SeqCst), 1);
+
+        let (status, _, _) = send(&app, request(Some("abc"), "other")).await;
+        assert_eq!(status, StatusCode::UNPROCESSABLE_ENTITY);
+
+        send(&app, request(None, "new")).await;
+        send(&app, request(Some("def"), "new")).await;
+        assert_eq!(calls.load(Ordering::SeqCst), 3);
+    }
+
+    #[tokio::test]
+    async fn test_server_errors_are_not_stored() {
+        let store = Arc::new(IdempotencyStore::default());
+        let calls = Arc::new(AtomicUsize::new(0));
+        let app = app(store.clone(), calls.clone());
+
+        for _ in 0..2 {
+            let (status, replayed, _) = send(&app, request(Some("abc"), "fail")).await;
+            assert_eq!(status, StatusCode::INTERNAL_SERVER_ERROR);
+            assert!(!replayed);
+        }
+        assert_eq!(calls.load(Ordering::SeqCst), 2);
+        assert!(store.entries.lock().unwrap().is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_least_recently_used_responses_are_evicted() {
+        let store = Arc::new(IdempotencyStore {
+            max_entries: 2,
+            ..IdempotencyStore::default()
+        });
+        let calls = Arc::new(AtomicUsize::new(0));
+        let app = app(store.clone(), calls.clone());
+
+        send(&app, request(Some("a"), "new")).await;
+        send(&app, request(Some("b"), "new")).await;
+        // Replaying `a` makes `b` the least recently used
+        send(&app, request(Some("a"), "new")).await;
+        send(&app, request(Some("c"), "new")).await;
+        assert_eq!(store.entries.lock().unwrap().len(), 2);
+
+        let (_, replayed, _) = send(&app, request(Some("a"), "new")).await;
+        assert!(replayed);
+        let (_, replayed, _) = send(&app, request(Some("b"), "new")).await;
+        assert!(!replayed);
+
+        let large = "x".repeat(MAX_KEYED_BODY_BYTES + 1);
+        let (status, _, _) = send(&app, request(Some("d"), &large)).await;
+        assert_eq!(status, StatusCode::PAYLOAD_TOO_LARGE);
+    }
+
+    #[tokio::test]
+    async fn test_expired_responses_are_dropped() {
+        let store = Arc::new(IdempotencyStore::new(Duration::ZERO));
+        let calls = Arc::new(AtomicUsize::new(0));
+        let app = app(store.clone(), calls.clone());
+
+        send(&app, request(Some("abc"), "new")).await;
+        let (_, replayed, body) = send(&app, request(Some("abc"), "new")).await;
+        assert!(!replayed);
+        assert_eq!(body, "session-1");
+    }
+}
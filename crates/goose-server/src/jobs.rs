@@ -0,0 +1,629 @@
+//! Detached agent jobs.
+//!
+//! A job runs a prompt against a session without an open reply stream.
+//! Queued jobs wait in priority lanes and start when a slot under the global
+//! concurrency limit frees up. Within a lane the owner with the fewest
+//! running jobs goes first, then the oldest submission, so one busy user
+//! cannot starve the others. Low-priority jobs also wait while the user's
+//! calendar shows a meeting. Jobs linked to a tracker ticket move it to in
+//! progress when they start and report on it when they finish. Every change
+//! is saved as JSON, and clients either poll a job or long-poll for its next
+//! change. A job that was running when goosed stopped comes back as failed.
+//! Its session keeps the conversation up to that point.
+
+use crate::state::AppState;
+use chrono::{DateTime, Duration, Utc};
+use futures::StreamExt;
+use goose::agents::{AgentEvent, SessionConfig};
+use goose::config::paths::Paths;
+use goose::config::Config;
+use goose::conversation::message::{Message, MessageContent};
+use goose::execution::priority::with_execution_mode;
+use goose::execution::SessionExecutionMode;
+use goose::issues::{self, IssueState, LinkedItem};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::path::PathBuf;
+use std::sync::{Arc, Mutex};
+use tokio::sync::{watch, Notify};
+use tokio_util::sync::CancellationToken;
+use utoipa::ToSchema;
+
+pub const JOB_CONCURRENCY_KEY: &str = "GOOSE_JOB_CONCURRENCY";
+pub const DEFAULT_JOB_CONCURRENCY: usize = 2;
+pub const JOBS_FOLDER: &str = "jobs";
+/// Longest a client may long-poll for a change
+pub const MAX_WAIT: std::time::Duration = std::time::Duration::from_secs(60);
+const DISPATCH_INTERVAL: std::time::Duration = std::time::Duration::from_secs(5);
+
+#[derive(
+    Debug, Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize, ToSchema,
+)]
+#[serde(rename_all = "snake_case")]
+pub enum JobPriority {
+    Low,
+    #[default]
+    Normal,
+    High,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum JobState {
+    Queued,
+    Running,
+    Succeeded,
+    Failed,
+    Cancelled,
+    /// The deadline passed, or the token budget ran out
+    Stopped,
+}
+
+impl JobState {
+    pub fn is_finished(self) -> bool {
+        !matches!(self, Self::Queued | Self::Running)
+    }
+}
+
+#[derive(Debug, Clone, Deserialize, ToSchema)]
+pub struct JobRequest {
+    pub session_id: String,
+    pub prompt: String,
+    #[serde(default)]
+    pub priority: JobPriority,
+    /// Key for fair scheduling, such as a user or team; defaults to the session
+    #[serde(default)]
+    pub owner: Option<String>,
+    /// Agent turns the job may take without user input
+    #[serde(default)]
+    pub max_turns: Option<u32>,
+    /// Tokens the job may spend before it is stopped
+    #[serde(default)]
+    pub max_tokens: Option<i64>,
+    /// Seconds from submission until the job is stopped, time queued included
+    #[serde(default)]
+    pub deadline_secs: Option<u64>,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize, ToSchema)]
+pub struct JobProgress {
+    pub messages: usize,
+    pub tool_calls: usize,
+    pub tokens_used: i64,
+    /// Latest assistant text, for showing what the job is doing
+    pub last_text: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct Job {
+    pub id: String,
+    pub session_id: String,
+    pub owner: String,
+    pub prompt: String,
+    pub priority: JobPriority,
+    pub max_turns: Option<u32>,
+    pub max_tokens: Option<i64>,
+    pub deadline: Option<DateTime<Utc>>,
+    pub state: JobState,
+    pub submitted_at: DateTime<Utc>,
+    pub started_at: Option<DateTime<Utc>>,
+    pub finished_at: Option<DateTime<Utc>>,
+    pub progress: JobProgress,
+    /// Final assistant text once the job succeeded
+    pub result: Option<String>,
+    pub error: Option<String>,
+    /// Bumped on every change, for long-polling
+    pub version: u64,
+}
+
+#[derive(Debug, thiserror::Error, PartialEq)]
+pub enum JobError {
+    #[error("Job {0} not found")]
+    NotFound(String),
+    #[error("Job {0} has already finished")]
+    AlreadyFinished(String),
+    #[error("A job needs a prompt")]
+    EmptyPrompt,
+}
+
+#[derive(Default)]
+struct QueueState {
+    jobs: HashMap<String, Job>,
+    running: HashMap<String, CancellationToken>,
+}
+
+/// The queued job to start next: highest lane first, then the owner with
+/// the fewest running jobs, then the oldest submission. The low lane is
+/// skipped while `hold_low` is set.
+fn next_job(jobs: &HashMap<String, Job>, hold_low: bool) -> Option<String> {
+    let mut running: HashMap<&str, usize> = HashMap::new();
+    for job in jobs.values().filter(|j| j.state == JobState::Running) {
+        *running.entry(job.owner.as_str()).or_default() += 1;
+    }
+    jobs.values()
+        .filter(|j| j.state == JobState::Queued)
+        .filter(|j| !(hold_low && j.priority == JobPriority::Low))
+        .min_by_key(|j| {
+            (
+                std::cmp::Reverse(j.priority),
+                running.get(j.owner.as_str()).copied().unwrap_or(0),
+                j.submitted_at,
+            )
+        })
+        .map(|j| j.id.clone())
+}
+
+enum Outcome {
+    Succeeded(Option<String>),
+    Stopped(String),
+    Cancelled,
+}
+
+pub struct JobQueue {
+    state: Mutex<QueueState>,
+    dir: Option<PathBuf>,
+    concurrency: usize,
+    changed: watch::Sender<u64>,
+    wake: Notify,
+}
+
+impl JobQueue {
+    /// In-memory queue, for tests
+    pub fn new(concurrency: usize) -> Self {
+        Self {
+            state: Mutex::new(QueueState::default()),
+            dir: None,
+            concurrency: concurrency.max(1),
+            changed: watch::channel(0).0,
+            wake: Notify::new(),
+        }
+    }
+
+    /// Queue saved under the data dir, reloading jobs from earlier runs
+    pub fn persistent() -> Self {
+        let concurrency = Config::global()
+            .get_param::<usize>(JOB_CONCURRENCY_KEY)
+            .unwrap_or(DEFAULT_JOB_CONCURRENCY);
+        let dir = Paths::in_data_dir(JOBS_FOLDER);
+        let queue = Self {
+            dir: Some(dir.clone()),
+            ..Self::new(concurrency)
+        };
+
+        let Ok(entries) = std::fs::read_dir(&dir) else {
+            return queue;
+        };
+        let now = Utc::now();
+        queue.with_state(|state| {
+            for entry in entries.filter_map(Result::ok) {
+                let Ok(json) = std::fs::read_to_string(entry.path()) else {
+                    continue;
+                };
+                let Ok(mut job) = serde_json::from_str::<Job>(&json) else {
+                    tracing::warn!("Skipping unreadable job file {}", entry.path().display());
+                    continue;
+                };
+                if job.state == JobState::Running {
+                    job.state = JobState::Failed;
+                    job.finished_at = Some(now);
+                    job.error = Some(
+                        "Interrupted by a server restart; the session has the conversation so far"
+                            .to_string(),
+                    );
+                    job.version += 1;
+                    queue.persist(&job);
+                }
+                state.jobs.insert(job.id.clone(), job);
+            }
+        });
+        queue
+    }
+
+    fn with_state<T>(&self, f: impl FnOnce(&mut QueueState) -> T) -> T {
+        let mut state = self.state.lock().unwrap_or_else(|e| e.into_inner());
+        f(&mut state)
+    }
+
+    fn persist(&self, job: &Job) {
+        let Some(dir) = &self.dir else {
+            return;
+        };
+        let result = std::fs::create_dir_all(dir).and_then(|_| {
+            std::fs::write(
+                dir.join(format!("{}.json", job.id)),
+                serde_json::to_string_pretty(job).unwrap_or_default(),
+            )
+        });
+        if let Err(e) = result {
+            tracing::warn!("Failed to save job {}: {}", job.id, e);
+        }
+    }
+
+    fn update(&self, id: &str, f: impl FnOnce(&mut Job)) -> Option<Job> {
+        let job = self.with_state(|state| {
+            let job = state.jobs.get_mut(id)?;
+            f(job);
+            job.version += 1;
+            Some(job.clone())
+        })?;
+        self.persist(&job);
+        self.changed.send_modify(|v| *v += 1);
+        Some(job)
+    }
+
+    pub fn submit(&self, request: JobRequest) -> Result<Job, JobError> {
+        if request.prompt.trim().is_empty() {
+            return Err(JobError::EmptyPrompt);
+        }
+        let now = Utc::now();
+        let job = Job {
+            id: uuid::Uuid::new_v4().to_string(),
+            owner: request.owner.unwrap_or_else(|| request.session_id.clone()),
+            session_id: request.session_id,
+            prompt: request.prompt,
+            priority: request.priority,
+            max_turns: request.max_turns,
+            max_tokens: request.max_tokens,
+            deadline: request
+                .deadline_secs
+                .map(|secs| now + Duration::seconds(secs as i64)),
+            state: JobState::Queued,
+            submitted_at: now,
+            started_at: None,
+            finished_at: None,
+            progress: JobProgress::default(),
+            result: None,
+            error: None,
+            version: 0,
+        };
+        self.with_state(|state| state.jobs.insert(job.id.clone(), job.clone()));
+        self.persist(&job);
+        self.changed.send_modify(|v| *v += 1);
+        self.wake.notify_one();
+        Ok(job)
+    }
+
+    pub fn get(&self, id: &str) -> Result<Job, JobError> {
+        self.with_state(|state| state.jobs.get(id).cloned())
+            .ok_or_else(|| JobError::NotFound(id.to_string()))
+    }
+
+    /// Newest first
+    pub fn list(&self, session_id: Option<&str>) -> Vec<Job> {
+        let mut jobs: Vec<Job> = self.with_state(|state| {
+            state
+                .jobs
+                .values()
+                .filter(|j| session_id.is_none_or(|id| j.session_id == id))
+                .cloned()
+                .collect()
+        });
+        jobs.sort_by(|a, b| b.submitted_at.cmp(&a.submitted_at));
+        jobs
+    }
+
+    /// Returns the job once its version is past `after_version`, or as it is
+    /// when `timeout` runs out.
+    pub async fn wait(
+        &self,
+        id: &str,
+        after_version: u64,
+        timeout: std::time::Duration,
+    ) -> Result<Job, JobError> {
+        let mut changed = self.changed.subscribe();
+        let wait = async {
+            loop {
+                let job = self.get(id)?;
+                if job.version > after_version || job.state.is_finished() {
+                    return Ok(job);
+                }
+                if changed.changed().await.is_err() {
+                    return Ok(job);
+                }
+            }
+        };
+        match tokio::time::timeout(timeout.min(MAX_WAIT), wait).await {
+            Ok(result) => result,
+            Err(_) => self.get(id),
+        }
+    }
+
+    pub fn cancel(&self, id: &str) -> Result<Job, JobError> {
+        let job = self.get(id)?;
+        match job.state {
+            JobState::Queued => Ok(self
+                .update(id, |job| {
+                    job.state = JobState::Cancelled;
+                    job.finished_at = Some(Utc::now());
+                })
+                .unwrap_or(job)),
+            JobState::Running => {
+                if let Some(token) = self.with_state(|state| state.running.get(id).cloned()) {
+                    token.cancel();
+                }
+                Ok(job)
+            }
+            _ => Err(JobError::AlreadyFinished(id.to_string())),
+        }
+    }
+
+    /// Stops queued jobs whose deadline passed and claims the next job if a
+    /// slot is free.
+    fn take_next(&self) -> Option<(Job, CancellationToken)> {
+        let now = Utc::now();
+        let expired: Vec<String> = self.with_state(|state| {
+            state
+                .jobs
+                .values()
+                .filter(|j| j.state == JobState::Queued && j.deadline.is_some_and(|d| d <= now))
+                .map(|j| j.id.clone())
+                .collect()
+        });
+        for id in expired {
+            self.update(&id, |job| {
+                job.state = JobState::Stopped;
+                job.finished_at = Some(now);
+                job.error = Some("Deadline passed before the job could start".to_string());
+            });
+        }
+
+        let hold_low = goose::calendar::is_busy(now);
+        let id = self.with_state(|state| {
+            if state.running.len() >= self.concurrency {
+                return None;
+            }
+            let id = next_job(&state.jobs, hold_low)?;
+            state.running.insert(id.clone(), CancellationToken::new());
+            Some(id)
+        })?;
+        let token = self.with_state(|state| state.running.get(&id).cloned())?;
+        let job = self.update(&id, |job| {
+            job.state = JobState::Running;
+            job.started_at = Some(now);
+        })?;
+        Some((job, token))
+    }
+
+    fn finish(&self, id: &str, outcome: anyhow::Result<Outcome>) {
+        self.with_state(|state| state.running.remove(id));
+        self.update(id, |job| {
+            job.finished_at = Some(Utc::now());
+            match outcome {
+                Ok(Outcome::Succeeded(result)) => {
+                    job.state = JobState::Succeeded;
+                    job.result = result;
+                }
+                Ok(Outcome::Stopped(reason)) => {
+                    job.state = JobState::Stopped;
+                    job.error = Some(reason);
+                }
+                Ok(Outcome::Cancelled) => job.state = JobState::Cancelled,
+                Err(e) => {
+                    job.state = JobState::Failed;
+                    job.error = Some(e.to_string());
+                }
+            }
+        });
+        self.wake.notify_one();
+    }
+
+    async fn session_tokens(state: &AppState, session_id: &str) -> i64 {
+        state
+            .session_manager()
+            .get_session(session_id, false)
+            .await
+            .ok()
+            .and_then(|s| s.accumulated_total_tokens)
+            .unwrap_or(0) as i64
+    }
+
+    async fn execute(
+        &self,
+        state: &Arc<AppState>,
+        job: &Job,
+        cancel: CancellationToken,
+    ) -> anyhow::Result<Outcome> {
+        let _emergency_registration = state.emergency.register_reply(cancel.clone())?;
+        let drain_registration = state
+            .drain
+            .register_turn(job.session_id.clone(), cancel.clone())?;
+        let agent = state.get_agent(job.session_id.clone()).await?;
+        let start_tokens = Self::session_tokens(state, &job.session_id).await;
+
+        let session_config = SessionConfig {
+            id: job.session_id.clone(),
+            schedule_id: None,
+            max_turns: job.max_turns,
+            retry_config: None,
+        };
+        let stream = agent
+            .reply(
+                Message::user().with_text(&job.prompt),
+                session_config,
+                Some(cancel.clone()),
+            )
+            .await?;
+        let mut stream = std::pin::pin!(stream);
+
+        let mut last_text = None;
+        loop {
+            let remaining = job
+                .deadline
+                .map(|d| (d - Utc::now()).to_std().unwrap_or_default());
+            let next = match remaining {
+                Some(remaining) => match tokio::time::timeout(remaining, stream.next()).await {
+                    Ok(next) => next,
+                    Err(_) => {
+                        cancel.cancel();
+                        return Ok(Outcome::Stopped("Deadline passed".to_string()));
+                    }
+                },
+                None => stream.next().await,
+            };
+            let message = match next {
+                Some(Ok(AgentEvent::Message(message))) => message,
+                Some(Ok(_)) => continue,
+                Some(Err(e)) => return Err(e),
+                None => break,
+            };
+
+            let text = message.as_concat_text();
+            if message.role == rmcp::model::Role::Assistant && !text.trim().is_empty() {
+                last_text = Some(text);
+            }
+            let tool_calls = message
+                .content
+                .iter()
+                .filter(|c| matches!(c, MessageContent::ToolRequest(_)))
+                .count();
+            let tokens_used = Self::session_tokens(state, &job.session_id).await - start_tokens;
+            self.update(&job.id, |job| {
+                job.progress.messages += 1;
+                job.progress.tool_calls += tool_calls;
+                job.progress.tokens_used = tokens_used;
+                job.progress.last_text = last_text.clone();
+            });
+            if job.max_tokens.is_some_and(|max| tokens_used > max) {
+                cancel.cancel();
+                return Ok(Outcome::Stopped(format!(
+                    "Token budget of {} exceeded",
+                    job.max_tokens.unwrap_or_default()
+                )));
+            }
+        }
+
+        if drain_registration.interrupted() {
+            anyhow::bail!(
+                "Interrupted by a server shutdown; the session has the conversation so far"
+            );
+        }
+        if cancel.is_cancelled() {
+            return Ok(Outcome::Cancelled);
+        }
+        Ok(Outcome::Succeeded(last_text))
+    }
+
+    /// What to write on the ticket a finished job came from: success closes
+    /// it when the tracker says so, anything else sends it back to open.
+    fn tracker_report(job: &Job) -> (Option<IssueState>, Option<String>) {
+        let close = job.state == JobState::Succeeded
+            && issues::link_for(&LinkedItem::Job(job.id.clone()))
+                .and_then(|link| issues::tracker_config(&link.tracker).ok())
+                .is_some_and(|config| config.close_on_success);
+        let state = if close {
+            IssueState::Closed
+        } else {
+            IssueState::Open
+        };
+        let comment = match job.state {
+            JobState::Succeeded => format!(
+                "goose finished this ticket:\n\n{}",
+                job.result.as_deref().unwrap_or("(no summary)")
+            ),
+            other => format!(
+                "goose stopped working on this ticket ({:?}): {}",
+                other,
+                job.error.as_deref().unwrap_or("no details")
+            ),
+        };
+        (Some(state), Some(comment))
+    }
+
+    /// Starts queued jobs as slots free up. Nothing starts while goosed is
+    /// draining or stopped.
+    pub fn spawn(self: &Arc<Self>, state: Arc<AppState>) {
+        let queue = self.clone();
+        tokio::spawn(async move {
+            loop {
+                tokio::select! {
+                    _ = queue.wake.notified() => {}
+                    _ = tokio::time::sleep(DISPATCH_INTERVAL) => {}
+                }
+                if state.drain.is_draining() || state.emergency.is_stopped() {
+                    continue;
+                }
+                while let Some((job, cancel)) = queue.take_next() {
+                    let queue = queue.clone();
+                    let state = state.clone();
+                    tokio::spawn(async move {
+                        let item = LinkedItem::Job(job.id.clone());
+                        issues::sync(&item, Some(IssueState::InProgress), None).await;
+                        let outcome = with_execution_mode(
+                            SessionExecutionMode::Background,
+                            queue.execute(&state, &job, cancel),
+                        )
+                        .await;
+                        queue.finish(&job.id, outcome);
+                        if let Ok(job) = queue.get(&job.id) {
+                            let (ticket_state, comment) = Self::tracker_report(&job);
+                            issues::sync(&item, ticket_state, comment).await;
+                        }
+                    });
+                }
+            }
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn request(owner: &str, priority: JobPriority) -> JobRequest {
+        JobRequest {
+            session_id: format!("session-{}", owner),
+            prompt: "summarize the repo".to_string(),
+            priority,
+            owner: Some(owner.to_string()),
+            max_turns: None,
+            max_tokens: None,
+            deadline_secs: None,
+        }
+    }
+
+    #[test]
+    fn test_lanes_and_fairness_across_owners() {
+        let queue = JobQueue::new(3);
+        let first = queue.submit(request("alice", JobPriority::Normal)).unwrap();
+        let second = queue.submit(request("alice", JobPriority::Normal)).unwrap();
+        let bob = queue.submit(request("bob", JobPriority::Normal)).unwrap();
+        let urgent = queue.submit(request("carol", JobPriority::High)).unwrap();
+
+        let order: Vec<String> = (0..3).map(|_| queue.take_next().unwrap().0.id).collect();
+        assert_eq!(order, vec![urgent.id, first.id, bob.id]);
+        assert!(queue.take_next().is_none());
+        assert_eq!(queue.get(&second.id).unwrap().state, JobState::Queued);
+    }
+
+    #[test]
+    fn test_low_lane_waits_while_held() {
+        let queue = JobQueue::new(1);
+        let batch = queue.submit(request("alice", JobPriority::Low)).unwrap();
+        queue.with_state(|state| {
+            assert_eq!(next_job(&state.jobs, true), None);
+            assert_eq!(next_job(&state.jobs, false), Some(batch.id.clone()));
+        });
+    }
+
+    #[tokio::test]
+    async fn test_cancel_and_wait_for_change() {
+        let queue = Arc::new(JobQueue::new(1));
+        let job = queue.submit(request("alice", JobPriority::Low)).unwrap();
+
+        let waiter = {
+            let queue = queue.clone();
+            let id = job.id.clone();
+            tokio::spawn(async move { queue.wait(&id, 0, MAX_WAIT).await })
+        };
+        tokio::task::yield_now().await;
+        queue.cancel(&job.id).unwrap();
+
+        let seen = waiter.await.unwrap().unwrap();
+        assert_eq!(seen.state, JobState::Cancelled);
+        assert_eq!(
+            queue.cancel(&job.id).unwrap_err(),
+            JobError::AlreadyFinished(job.id.clone())
+        );
+        assert!(queue.take_next().is_none());
+    }
+}
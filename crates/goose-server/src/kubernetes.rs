@@ -0,0 +1,216 @@
+//! Running goosed as a pod.
+//!
+//! With `GOOSE_KUBERNETES` set, goosed reads configuration from mounted
+//! ConfigMap and Secret volumes. Each file in a mounted directory is one key,
+//! e.g. `GOOSE_PROVIDER`, and is exported as an environment variable unless
+//! that variable is already set. This runs before anything else reads
+//! config. A SIGTERM from pod eviction drains within
+//! `GOOSE_EVICTION_DRAIN_SECS`, which should stay under the pod's
+//! `terminationGracePeriodSeconds`. Turns still running at that deadline are
+//! cancelled and their sessions are checkpointed.
+//!
+//! The liveness and readiness probes are served without the secret key. An
+//! external controller can poll `/system/instance` for the instance's state.
+//! Every response carries `X-Goose-Instance` so an ingress or client can keep
+//! a session on the replica that served it. Replicas that share storage still
+//! use session revisions to reject stale conversation writes.
+
+use axum::extract::{Request, State};
+use axum::http::HeaderValue;
+use axum::middleware::Next;
+use axum::response::Response;
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::time::Duration;
+use utoipa::ToSchema;
+
+use crate::state::AppState;
+use crate::supervisor::SupervisorState;
+
+pub const KUBERNETES_MODE_ENV: &str = "GOOSE_KUBERNETES";
+/// Colon-separated directories holding mounted ConfigMaps and Secrets
+pub const MOUNTED_CONFIG_DIRS_ENV: &str = "GOOSE_MOUNTED_CONFIG_DIRS";
+pub const DEFAULT_MOUNTED_CONFIG_DIRS: &str = "/etc/goose/config:/etc/goose/secrets";
+pub const EVICTION_DRAIN_SECS_ENV: &str = "GOOSE_EVICTION_DRAIN_SECS";
+/// Leaves 5s of the default 30s grace period for checkpointing and exit
+pub const DEFAULT_EVICTION_DRAIN: Duration = Duration::from_secs(25);
+
+pub const LIVENESS_PATH: &str = "/healthz";
+pub const READINESS_PATH: &str = "/readyz";
+/// Response header naming the replica, as a session affinity hint
+pub const INSTANCE_HEADER: &str = "x-goose-instance";
+
+#[derive(Debug, Clone, Serialize, ToSchema)]
+pub struct ProbeStatus {
+    pub ready: bool,
+    /// Why the instance is not taking new work
+    pub reason: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, ToSchema)]
+pub struct InstanceStatus {
+    pub instance_id: String,
+    pub started_at: DateTime<Utc>,
+    pub version: String,
+    pub kubernetes: bool,
+    pub ready: bool,
+    pub draining: bool,
+    pub drain_deadline: Option<DateTime<Utc>>,
+    pub restart_pending: bool,
+    pub restarting: bool,
+    pub supervisor: SupervisorState,
+    pub active_turns: usize,
+    /// Sessions with a turn in flight
+    pub active_sessions: Vec<String>,
+    /// Sessions cancelled and checkpointed by the drain
+    pub checkpointed_sessions: Vec<String>,
+}
+
+pub fn readiness(state: &AppState) -> ProbeStatus {
+    let reason = if state.drain.is_draining() {
+        Some("draining before shutdown")
+    } else if state.emergency.status().stopped {
+        Some("emergency stop engaged")
+    } else {
+        None
+    };
+    ProbeStatus {
+        ready: reason.is_none(),
+        reason: reason.map(String::from),
+    }
+}
+
+pub fn instance_status(state: &AppState) -> InstanceStatus {
+    let drain = state.drain.status();
+    let restart = state.restart.status();
+    InstanceStatus {
+        instance_id: state.supervisor.instance_id().to_string(),
+        started_at: state.supervisor.started_at(),
+        version: env!("CARGO_PKG_VERSION").to_string(),
+        kubernetes: enabled(),
+        ready: readiness(state).ready,
+        draining: drain.draining,
+        drain_deadline: drain.deadline,
+        restart_pending: restart.pending,
+        restarting: restart.restarting,
+        supervisor: state.supervisor.status().state,
+        active_turns: drain.active_turns,
+        active_sessions: drain.active_sessions,
+        checkpointed_sessions: drain.checkpointed_sessions,
+    }
+}
+
+pub async fn instance_header(
+    State(state): State<Arc<AppState>>,
+    request: Request,
+    next: Next,
+) -> Response {
+    let mut response = next.run(request).await;
+    if let Ok(value) = HeaderValue::from_str(state.supervisor.instance_id()) {
+        response.headers_mut().insert(INSTANCE_HEADER, value);
+    }
+    response
+}
+
+pub fn enabled() -> bool {
+    std::env::var(KUBERNETES_MODE_ENV)
+        .is_ok_and(|v| matches!(v.to_ascii_lowercase().as_str(), "1" | "true" | "yes"))
+}
+
+pub fn eviction_drain_deadline() -> Duration {
+    std::env::var(EVICTION_DRAIN_SECS_ENV)
+        .ok()
+        .and_then(|v| v.trim().parse().ok())
+        .map(Duration::from_secs)
+        .unwrap_or(DEFAULT_EVICTION_DRAIN)
+}
+
+fn mounted_config_dirs() -> Vec<PathBuf> {
+    std::env::var(MOUNTED_CONFIG_DIRS_ENV)
+        .unwrap_or_else(|_| DEFAULT_MOUNTED_CONFIG_DIRS.to_string())
+        .split(':')
+        .filter(|dir| !dir.trim().is_empty())
+        .map(|dir| PathBuf::from(dir.trim()))
+        .collect()
+}
+
+/// Keys and values of a mounted volume. Kubernetes keeps the real files
+/// behind `..data` symlinks, so dot entries are skipped and links followed.
+fn mounted_entries(dir: &Path) -> Vec<(String, String)> {
+    let Ok(entries) = std::fs::read_dir(dir) else {
+        return Vec::new();
+    };
+    let mut values: Vec<(String, String)> = entries
+        .filter_map(Result::ok)
+        .filter_map(|entry| {
+            let key = entry.file_name().into_string().ok()?;
+            let valid = key.chars().all(|c| c.is_ascii_alphanumeric() || c == '_');
+            if key.starts_with('.') || !valid || !entry.path().is_file() {
+                return None;
+            }
+            let value = std::fs::read_to_string(entry.path()).ok()?;
+            Some((key, value.trim_end_matches(['\r', '\n']).to_string()))
+        })
+        .collect();
+    values.sort();
+    values
+}
+
+/// Exports mounted keys that are not already set in the environment and
+/// returns their names. Values are never logged.
+pub fn load_mounted_config(dirs: &[PathBuf]) -> Vec<String> {
+    let mut loaded = Vec::new();
+    for dir in dirs {
+        for (key, value) in mounted_entries(dir) {
+            if std::env::var_os(&key).is_some() {
+                continue;
+            }
+            std::env::set_var(&key, value);
+            loaded.push(key);
+        }
+    }
+    loaded
+}
+
+/// Applies the pod configuration when Kubernetes mode is on. Call before
+/// anything reads settings or config.
+pub fn init() -> bool {
+    if !enabled() {
+        return false;
+    }
+    let dirs = mounted_config_dirs();
+    let loaded = load_mounted_config(&dirs);
+    tracing::info!(
+        dirs = ?dirs,
+        keys = ?loaded,
+        "Kubernetes mode: loaded mounted configuration"
+    );
+    true
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_mounted_config_skips_metadata_and_keeps_env() {
+        let dir = std::env::temp_dir().join(format!("goose-k8s-{}", uuid::Uuid::new_v4()));
+        std::fs::create_dir_all(dir.join("..2026_10_17_data")).unwrap();
+        std::fs::write(dir.join("GOOSE_K8S_TEST_MODEL"), "gpt-4o\n").unwrap();
+        std::fs::write(dir.join("GOOSE_K8S_TEST_PRESET"), "from-volume").unwrap();
+        std::fs::write(dir.join("..data"), "ignored").unwrap();
+        std::fs::write(dir.join("not-a-key.txt"), "ignored").unwrap();
+        std::env::set_var("GOOSE_K8S_TEST_PRESET", "from-env");
+
+        let loaded = load_mounted_config(std::slice::from_ref(&dir));
+        assert_eq!(loaded, vec!["GOOSE_K8S_TEST_MODEL".to_string()]);
+        assert_eq!(std::env::var("GOOSE_K8S_TEST_MODEL").unwrap(), "gpt-4o");
+        assert_eq!(std::env::var("GOOSE_K8S_TEST_PRESET").unwrap(), "from-env");
+
+        std::env::remove_var("GOOSE_K8S_TEST_MODEL");
+        std::env::remove_var("GOOSE_K8S_TEST_PRESET");
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+}
@@ -1,9 +1,18 @@
+pub mod api_docs;
 pub mod auth;
 pub mod configuration;
+pub mod drain;
+pub mod emergency;
 pub mod error;
+pub mod idempotency;
+pub mod jobs;
+pub mod kubernetes;
+pub mod maintenance;
 pub mod openapi;
 pub mod routes;
+pub mod shell_update;
 pub mod state;
+pub mod supervisor;
 pub mod tunnel;
 
 // Re-export commonly used items
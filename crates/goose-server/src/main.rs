@@ -1,10 +1,19 @@
+mod api_docs;
 mod commands;
 mod configuration;
+mod drain;
+mod emergency;
 mod error;
+mod idempotency;
+mod jobs;
+mod kubernetes;
 mod logging;
+mod maintenance;
 mod openapi;
 mod routes;
+mod shell_update;
 mod state;
+mod supervisor;
 mod tunnel;
 
 use clap::{Parser, Subcommand};
@@ -25,22 +34,47 @@ struct Cli {
 #[derive(Subcommand)]
 enum Commands {
     /// Run the agent server
-    Agent,
+    Agent {
+        /// Defer optional subsystems until the first reply
+        #[arg(long)]
+        fast_start: bool,
+    },
     /// Run the MCP server
     Mcp {
         #[arg(value_parser = clap::value_parser!(McpCommand))]
         server: McpCommand,
     },
+    /// Start the agent server at boot as a systemd unit, launchd agent or
+    /// Windows service
+    InstallService,
+    /// Stop and remove the installed service
+    UninstallService,
+    /// Entry point the Windows service manager starts goosed with
+    #[cfg(windows)]
+    #[command(hide = true)]
+    ServiceRun {
+        #[arg(long)]
+        log: std::path::PathBuf,
+    },
 }
 
 #[tokio::main]
 async fn main() -> anyhow::Result<()> {
+    goose::startup::begin();
     let cli = Cli::parse();
 
     match cli.command {
-        Commands::Agent => {
+        Commands::Agent { fast_start } => {
+            goose::startup::set_fast_start(fast_start);
             commands::agent::run().await?;
         }
+        Commands::InstallService => commands::service::install()?,
+        Commands::UninstallService => commands::service::uninstall()?,
+        #[cfg(windows)]
+        Commands::ServiceRun { log } => {
+            tokio::task::spawn_blocking(move || commands::service::run_windows_service(log))
+                .await??;
+        }
         Commands::Mcp { server } => {
             logging::setup_logging(Some(&format!("mcp-{}", server.name())))?;
             match server {
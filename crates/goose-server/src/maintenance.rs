@@ -0,0 +1,499 @@
+//! Deferred restarts for goosed.
+//!
+//! A supervisor that has a new binary ready asks goosed to restart instead of
+//! killing it. The restart is held until a configured maintenance window
+//! opens or no turn has run for a while, then goosed drains and exits so the
+//! supervisor can swap the binary. Neither applies while the user's calendar
+//! shows a meeting. The pending restart is visible in the status so the UI
+//! can say when the update will apply.
+
+use crate::drain::{Drain, DEFAULT_DRAIN_DEADLINE};
+use crate::supervisor::SupervisorWatch;
+use chrono::{
+    DateTime, Datelike, Duration, Local, NaiveDateTime, NaiveTime, TimeZone, Utc, Weekday,
+};
+use goose::calendar;
+use goose::config::Config;
+use serde::{Deserialize, Serialize};
+use std::str::FromStr;
+use std::sync::{Arc, Mutex};
+use utoipa::ToSchema;
+
+/// Semicolon-separated windows, e.g. `Mon-Fri 02:00-04:00; Sat,Sun 00:00-06:00`
+pub const MAINTENANCE_WINDOWS_KEY: &str = "GOOSE_MAINTENANCE_WINDOWS";
+/// Minutes without a running turn after which a pending restart applies
+/// outside a window
+pub const RESTART_IDLE_MINUTES_KEY: &str = "GOOSE_RESTART_IDLE_MINUTES";
+pub const DEFAULT_RESTART_IDLE_MINUTES: i64 = 15;
+const CHECK_INTERVAL: std::time::Duration = std::time::Duration::from_secs(30);
+/// Free calendar time a restart needs around it
+const RESTART_SLOT_MINUTES: i64 = 5;
+
+#[derive(Debug, thiserror::Error, PartialEq)]
+pub enum MaintenanceError {
+    #[error("Invalid maintenance window '{0}': {1}")]
+    InvalidWindow(String, String),
+    #[error("No restart is pending")]
+    NotPending,
+    #[error("Restart is already in progress")]
+    AlreadyRestarting,
+    #[error("Supervisor is not responding; restarts are held until it returns")]
+    SupervisorLost,
+}
+
+/// A recurring local-time window. Windows whose end is not after their start
+/// run past midnight; the days filter applies to the day the window opens.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MaintenanceWindow {
+    /// Empty means every day
+    days: Vec<Weekday>,
+    start: NaiveTime,
+    end: NaiveTime,
+}
+
+impl MaintenanceWindow {
+    fn allows(&self, day: Weekday) -> bool {
+        self.days.is_empty() || self.days.contains(&day)
+    }
+
+    pub fn contains(&self, at: NaiveDateTime) -> bool {
+        let time = at.time();
+        let day = at.weekday();
+        if self.start < self.end {
+            self.allows(day) && time >= self.start && time < self.end
+        } else {
+            (self.allows(day) && time >= self.start) || (self.allows(day.pred()) && time < self.end)
+        }
+    }
+
+    /// The first opening strictly after `after`.
+    pub fn next_start(&self, after: NaiveDateTime) -> Option<NaiveDateTime> {
+        (0..=7)
+            .filter_map(|offset| after.date().checked_add_signed(Duration::days(offset)))
+            .filter(|date| self.allows(date.weekday()))
+            .map(|date| date.and_time(self.start))
+            .find(|start| *start > after)
+    }
+}
+
+impl FromStr for MaintenanceWindow {
+    type Err = MaintenanceError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let invalid = |reason: &str| MaintenanceError::InvalidWindow(s.to_string(), reason.into());
+        let s = s.trim();
+        let (days, times) = match s.rsplit_once(' ') {
+            Some((days, times)) => (parse_days(days.trim()).map_err(|e| invalid(&e))?, times),
+            None => (Vec::new(), s),
+        };
+        let (start, end) = times
+            .split_once('-')
+            .ok_or_else(|| invalid("expected HH:MM-HH:MM"))?;
+        let parse_time = |t: &str| {
+            NaiveTime::parse_from_str(t.trim(), "%H:%M")
+                .map_err(|_| invalid(&format!("'{}' is not a HH:MM time", t.trim())))
+        };
+        let (start, end) = (parse_time(start)?, parse_time(end)?);
+        if start == end {
+            return Err(invalid("window is empty"));
+        }
+        Ok(Self { days, start, end })
+    }
+}
+
+fn parse_days(spec: &str) -> Result<Vec<Weekday>, String> {
+    let parse_day =
+        |d: &str| Weekday::from_str(d.trim()).map_err(|_| format!("unknown day '{}'", d.trim()));
+    let mut days = Vec::new();
+    for part in spec.split(',') {
+        match part.split_once('-') {
+            Some((from, to)) => {
+                let (mut day, to) = (parse_day(from)?, parse_day(to)?);
+                days.push(day);
+                while day != to {
+                    day = day.succ();
+                    days.push(day);
+                }
+            }
+            None => days.push(parse_day(part)?),
+        }
+    }
+    Ok(days)
+}
+
+#[derive(Debug, Clone)]
+pub struct MaintenanceSchedule {
+    pub windows: Vec<MaintenanceWindow>,
+    pub idle_after: Duration,
+}
+
+impl Default for MaintenanceSchedule {
+    fn default() -> Self {
+        Self {
+            windows: Vec::new(),
+            idle_after: Duration::minutes(DEFAULT_RESTART_IDLE_MINUTES),
+        }
+    }
+}
+
+impl MaintenanceSchedule {
+    /// Reads the schedule from config, skipping windows that do not parse.
+    pub fn from_config() -> Self {
+        let config = Config::global();
+        let windows = config
+            .get_param::<String>(MAINTENANCE_WINDOWS_KEY)
+            .map(|spec| Self::parse_windows(&spec))
+            .unwrap_or_default();
+        let idle_minutes = config
+            .get_param::<i64>(RESTART_IDLE_MINUTES_KEY)
+            .unwrap_or(DEFAULT_RESTART_IDLE_MINUTES);
+        Self {
+            windows,
+            idle_after: Duration::minutes(idle_minutes.max(0)),
+        }
+    }
+
+    fn parse_windows(spec: &str) -> Vec<MaintenanceWindow> {
+        spec.split(';')
+            .filter(|w| !w.trim().is_empty())
+            .filter_map(|w| {
+                w.parse()
+                    .inspect_err(|e| tracing::warn!("Ignoring maintenance window: {}", e))
+                    .ok()
+            })
+            .collect()
+    }
+
+    pub fn is_open(&self, at: NaiveDateTime) -> bool {
+        self.windows.iter().any(|w| w.contains(at))
+    }
+
+    pub fn next_open(&self, after: NaiveDateTime) -> Option<NaiveDateTime> {
+        if self.is_open(after) {
+            return Some(after);
+        }
+        self.windows
+            .iter()
+            .filter_map(|w| w.next_start(after))
+            .min()
+    }
+}
+
+#[derive(Debug, Default, Deserialize, ToSchema)]
+pub struct RestartRequest {
+    /// Shown in the status, e.g. why the supervisor wants to restart
+    #[serde(default)]
+    pub reason: Option<String>,
+    /// Version the restart will switch to
+    #[serde(default)]
+    pub version: Option<String>,
+    /// Skip the maintenance window and idle checks
+    #[serde(default)]
+    pub immediate: bool,
+}
+
+#[derive(Debug, Clone, Serialize, ToSchema)]
+pub struct RestartStatus {
+    pub pending: bool,
+    /// Draining has started; goosed exits once in-flight turns finish
+    pub restarting: bool,
+    pub requested_at: Option<DateTime<Utc>>,
+    pub reason: Option<String>,
+    pub version: Option<String>,
+    /// Start of the next maintenance window clear of meetings. The restart
+    /// applies earlier if no turn runs for `idle_minutes`.
+    pub apply_at: Option<DateTime<Utc>>,
+    pub idle_minutes: i64,
+    pub active_turns: usize,
+    /// The supervisor stopped sending heartbeats, so the restart is held
+    pub safe_mode: bool,
+    /// The user's calendar shows a meeting now, so the restart waits
+    pub calendar_busy: bool,
+}
+
+struct PendingRestart {
+    requested_at: DateTime<Utc>,
+    reason: Option<String>,
+    version: Option<String>,
+}
+
+pub struct RestartScheduler {
+    drain: Arc<Drain>,
+    supervisor: Arc<SupervisorWatch>,
+    pending: Mutex<Option<PendingRestart>>,
+    /// Fixed schedule for tests; otherwise config is read on every check so
+    /// edits apply without a restart
+    schedule: Option<MaintenanceSchedule>,
+}
+
+impl RestartScheduler {
+    pub fn new(drain: Arc<Drain>, supervisor: Arc<SupervisorWatch>) -> Self {
+        Self {
+            drain,
+            supervisor,
+            pending: Mutex::new(None),
+            schedule: None,
+        }
+    }
+
+    pub fn with_schedule(
+        drain: Arc<Drain>,
+        supervisor: Arc<SupervisorWatch>,
+        schedule: MaintenanceSchedule,
+    ) -> Self {
+        Self {
+            schedule: Some(schedule),
+            ..Self::new(drain, supervisor)
+        }
+    }
+
+    fn schedule(&self) -> MaintenanceSchedule {
+        self.schedule
+            .clone()
+            .unwrap_or_else(MaintenanceSchedule::from_config)
+    }
+
+    fn with_pending<T>(&self, f: impl FnOnce(&mut Option<PendingRestart>) -> T) -> T {
+        let mut pending = self.pending.lock().unwrap_or_else(|e| e.into_inner());
+        f(&mut pending)
+    }
+
+    pub fn status(&self) -> RestartStatus {
+        self.status_at(Utc::now())
+    }
+
+    fn status_at(&self, now: DateTime<Utc>) -> RestartStatus {
+        let schedule = self.schedule();
+        let drain = self.drain.status();
+        self.with_pending(|pending| RestartStatus {
+            pending: pending.is_some(),
+            restarting: drain.draining,
+            requested_at: pending.as_ref().map(|p| p.requested_at),
+            reason: pending.as_ref().and_then(|p| p.reason.clone()),
+            version: pending.as_ref().and_then(|p| p.version.clone()),
+            apply_at: pending
+                .as_ref()
+                .and_then(|_| schedule.next_open(now.with_timezone(&Local).naive_local()))
+                .and_then(|at| Local.from_local_datetime(&at).earliest())
+                .map(|at| {
+                    calendar::next_safe_window(
+                        at.with_timezone(&Utc),
+                        Duration::minutes(RESTART_SLOT_MINUTES),
+                    )
+                }),
+            idle_minutes: schedule.idle_after.num_minutes(),
+            active_turns: drain.active_turns,
+            safe_mode: self.supervisor.is_safe_mode(),
+            calendar_busy: calendar::is_busy(now),
+        })
+    }
+
+    /// Records a pending restart, replacing any earlier request, and applies
+    /// it right away if allowed.
+    pub fn request(&self, request: RestartRequest) -> Result<RestartStatus, MaintenanceError> {
+        if self.drain.is_draining() {
+            return Err(MaintenanceError::AlreadyRestarting);
+        }
+        if request.immediate && self.supervisor.is_safe_mode() {
+            return Err(MaintenanceError::SupervisorLost);
+        }
+        self.with_pending(|pending| {
+            *pending = Some(PendingRestart {
+                requested_at: Utc::now(),
+                reason: request.reason,
+                version: request.version,
+            })
+        });
+        if request.immediate {
+            self.apply();
+        } else {
+            self.check_at(Utc::now());
+        }
+        Ok(self.status())
+    }
+
+    pub fn cancel(&self) -> Result<RestartStatus, MaintenanceError> {
+        if self.drain.is_draining() {
+            return Err(MaintenanceError::AlreadyRestarting);
+        }
+        self.with_pending(|pending| pending.take())
+            .ok_or(MaintenanceError::NotPending)?;
+        Ok(self.status())
+    }
+
+    /// Applies the pending restart if a window is open or goosed has been
+    /// idle long enough, unless the supervisor is lost or the user is in a
+    /// meeting. Returns whether draining started.
+    fn check_at(&self, now: DateTime<Utc>) -> bool {
+        if self.drain.is_draining()
+            || self.supervisor.is_safe_mode()
+            || self.with_pending(|pending| pending.is_none())
+        {
+            return false;
+        }
+        let schedule = self.schedule();
+        let in_window = schedule.is_open(now.with_timezone(&Local).naive_local());
+        let idle = self
+            .drain
+            .idle_since()
+            .is_some_and(|since| now - since >= schedule.idle_after);
+        if (in_window || idle) && calendar::is_busy(now) {
+            tracing::debug!("Holding pending restart until the calendar is free");
+            false
+        } else if in_window || idle {
+            tracing::info!(in_window, idle, "Applying pending restart");
+            self.apply();
+            true
+        } else {
+            false
+        }
+    }
+
+    fn apply(&self) {
+        self.drain.begin(DEFAULT_DRAIN_DEADLINE);
+    }
+
+    /// Checks the pending restart periodically until goosed starts draining.
+    pub fn spawn(self: &Arc<Self>) {
+        let scheduler = self.clone();
+        tokio::spawn(async move {
+            let mut interval = tokio::time::interval(CHECK_INTERVAL);
+            loop {
+                interval.tick().await;
+                if scheduler.drain.is_draining() || scheduler.check_at(Utc::now()) {
+                    break;
+                }
+            }
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::supervisor::SupervisorHeartbeat;
+    use chrono::NaiveDate;
+    use tokio_util::sync::CancellationToken;
+
+    fn at(day: u32, time: &str) -> NaiveDateTime {
+        // 2026-10-12 is a Monday
+        NaiveDate::from_ymd_opt(2026, 10, day)
+            .unwrap()
+            .and_time(NaiveTime::parse_from_str(time, "%H:%M").unwrap())
+    }
+
+    #[test]
+    fn test_parse_and_match_windows() {
+        let weekdays: MaintenanceWindow = "Mon-Fri 02:00-04:00".parse().unwrap();
+        assert!(weekdays.contains(at(12, "02:30")));
+        assert!(!weekdays.contains(at(12, "04:00")));
+        assert!(!weekdays.contains(at(17, "02:30")));
+        assert_eq!(weekdays.next_start(at(16, "05:00")), Some(at(19, "02:00")));
+
+        let overnight: MaintenanceWindow = "Sat 22:00-02:00".parse().unwrap();
+        assert!(overnight.contains(at(17, "23:00")));
+        assert!(overnight.contains(at(18, "01:00")));
+        assert!(!overnight.contains(at(19, "01:00")));
+
+        let daily: MaintenanceWindow = "01:00-03:00".parse().unwrap();
+        assert_eq!(daily.next_start(at(12, "01:30")), Some(at(13, "01:00")));
+
+        assert!("Funday 01:00-02:00".parse::<MaintenanceWindow>().is_err());
+        assert!("01:00".parse::<MaintenanceWindow>().is_err());
+        assert_eq!(
+            MaintenanceSchedule::parse_windows("Mon 01:00-02:00; bogus").len(),
+            1
+        );
+    }
+
+    #[test]
+    fn test_next_open_picks_earliest_window() {
+        let schedule = MaintenanceSchedule {
+            windows: MaintenanceSchedule::parse_windows("Sun 03:00-05:00; Wed 02:00-04:00"),
+            ..Default::default()
+        };
+        assert_eq!(schedule.next_open(at(12, "12:00")), Some(at(14, "02:00")));
+        assert_eq!(schedule.next_open(at(14, "03:00")), Some(at(14, "03:00")));
+    }
+
+    #[tokio::test]
+    async fn test_restart_waits_for_idle_sessions() {
+        let drain = Arc::new(Drain::default());
+        let scheduler = RestartScheduler::with_schedule(
+            drain.clone(),
+            Arc::new(SupervisorWatch::default()),
+            MaintenanceSchedule {
+                windows: Vec::new(),
+                idle_after: Duration::minutes(10),
+            },
+        );
+        let turn = drain.register_turn("s1", CancellationToken::new()).unwrap();
+
+        let status = scheduler
+            .request(RestartRequest {
+                version: Some("1.2.3".into()),
+                ..Default::default()
+            })
+            .unwrap();
+        assert!(status.pending);
+        assert!(!status.restarting);
+        assert_eq!(status.version.as_deref(), Some("1.2.3"));
+
+        assert!(!scheduler.check_at(Utc::now() + Duration::hours(1)));
+        drop(turn);
+        assert!(!scheduler.check_at(Utc::now()));
+        assert!(scheduler.check_at(Utc::now() + Duration::minutes(11)));
+        assert!(drain.is_draining());
+        assert_eq!(
+            scheduler.cancel().err(),
+            Some(MaintenanceError::AlreadyRestarting)
+        );
+    }
+
+    #[test]
+    fn test_lost_supervisor_holds_restart() {
+        let supervisor = Arc::new(SupervisorWatch::default());
+        let scheduler = RestartScheduler::with_schedule(
+            Arc::new(Drain::default()),
+            supervisor.clone(),
+            MaintenanceSchedule::default(),
+        );
+        let heartbeat = || SupervisorHeartbeat {
+            supervisor_id: "conductor".into(),
+            interval_secs: Some(5),
+            version: None,
+        };
+        supervisor.heartbeat_at(heartbeat(), Utc::now() - Duration::minutes(5));
+
+        assert_eq!(
+            scheduler
+                .request(RestartRequest {
+                    immediate: true,
+                    ..Default::default()
+                })
+                .err(),
+            Some(MaintenanceError::SupervisorLost)
+        );
+        let status = scheduler.request(RestartRequest::default()).unwrap();
+        assert!(status.pending && status.safe_mode);
+        assert!(!scheduler.check_at(Utc::now() + Duration::days(1)));
+
+        supervisor.heartbeat(heartbeat());
+        assert!(scheduler.check_at(Utc::now() + Duration::hours(1)));
+    }
+
+    #[test]
+    fn test_cancel_clears_pending_restart() {
+        let scheduler = RestartScheduler::with_schedule(
+            Arc::new(Drain::default()),
+            Arc::new(SupervisorWatch::default()),
+            MaintenanceSchedule::default(),
+        );
+        assert_eq!(scheduler.cancel().err(), Some(MaintenanceError::NotPending));
+
+        scheduler.request(RestartRequest::default()).unwrap();
+        let status = scheduler.cancel().unwrap();
+        assert!(!status.pending);
+        assert!(!scheduler.check_at(Utc::now() + Duration::days(1)));
+    }
+}
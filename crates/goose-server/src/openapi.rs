@@ -328,8 +328,55 @@ derive_utoipa!(Icon as IconSchema);
 #[openapi(
     paths(
         super::routes::status::status,
+        super::routes::status::connectivity_status,
+        super::routes::status::resource_status,
+        super::routes::status::database_status,
+        super::routes::status::startup_status,
         super::routes::status::system_info,
         super::routes::status::diagnostics,
+        super::routes::status::liveness,
+        super::routes::status::readiness,
+        super::routes::system::emergency_status,
+        super::routes::system::emergency_stop,
+        super::routes::system::request_resume,
+        super::routes::system::confirm_resume,
+        super::routes::system::verify_audit_log,
+        super::routes::system::export_audit_log,
+        super::routes::system::drain_status,
+        super::routes::system::drain,
+        super::routes::system::restart_status,
+        super::routes::system::request_restart,
+        super::routes::system::cancel_restart,
+        super::routes::system::shell_update_status,
+        super::routes::system::stage_shell_update,
+        super::routes::system::restart_shell,
+        super::routes::system::report_shell_health,
+        super::routes::system::confirm_shell_rollback,
+        super::routes::system::instance_status,
+        super::routes::system::priority_status,
+        super::routes::system::api_reference,
+        super::routes::bus::list_mailboxes,
+        super::routes::bus::receive_messages,
+        super::routes::bus::ack_message,
+        super::routes::bus::list_dead_letters,
+        super::routes::bus::requeue_dead_letter,
+        super::routes::bus::purge_dead_letter,
+        super::routes::bus::wake_report,
+        super::routes::bus::list_claims,
+        super::routes::bus::claim_files,
+        super::routes::bus::release_claim,
+        super::routes::bus::reply_to_negotiation,
+        super::routes::bus::supervisor_heartbeat,
+        super::routes::bus::supervisor_status,
+        super::routes::learning::submit_feedback,
+        super::routes::learning::list_feedback,
+        super::routes::learning::feedback_stats,
+        super::routes::learning::core_scores,
+        super::routes::learning::set_core_pin,
+        super::routes::swarm::list_team_presets,
+        super::routes::swarm::save_team_preset,
+        super::routes::swarm::delete_team_preset,
+        super::routes::swarm::record_team_outcome,
         super::routes::mcp_ui_proxy::mcp_ui_proxy,
         super::routes::config_management::backup_config,
         super::routes::config_management::detect_provider,
@@ -343,6 +390,12 @@ derive_utoipa!(Icon as IconSchema);
         super::routes::config_management::remove_extension,
         super::routes::config_management::get_extensions,
         super::routes::config_management::read_all_config,
+        super::routes::config_management::read_effective_config,
+        super::routes::config_management::set_effective_config,
+        super::routes::config_management::trust_workspace_config,
+        super::routes::config_management::list_settings_profiles,
+        super::routes::config_management::save_settings_profile,
+        super::routes::config_management::delete_settings_profile,
         super::routes::config_management::providers,
         super::routes::config_management::get_provider_models,
         super::routes::config_management::get_slash_commands,
@@ -355,6 +408,7 @@ derive_utoipa!(Icon as IconSchema);
         super::routes::config_management::set_config_provider,
         super::routes::config_management::configure_provider_oauth,
         super::routes::config_management::get_pricing,
+        super::routes::config_management::get_model_routes,
         super::routes::prompts::get_prompts,
         super::routes::prompts::get_prompt,
         super::routes::prompts::save_prompt,
@@ -362,9 +416,19 @@ derive_utoipa!(Icon as IconSchema);
         super::routes::agent::start_agent,
         super::routes::agent::resume_agent,
         super::routes::agent::stop_agent,
+        super::routes::agent::list_cores,
+        super::routes::agent::update_core,
+        super::routes::agent::get_running_tool_calls,
+        super::routes::agent::cancel_tool_call,
+        super::routes::agent::get_project_status,
+        super::routes::agent::forget_project_status,
         super::routes::agent::restart_agent,
         super::routes::agent::update_working_dir,
+        super::routes::agent::update_workspace_roots,
         super::routes::agent::get_tools,
+        super::routes::agent::preview_system_prompt,
+        super::routes::agent::diagnose,
+        super::routes::agent::start_devcontainer,
         super::routes::agent::read_resource,
         super::routes::agent::call_tool,
         super::routes::agent::list_apps,
@@ -375,20 +439,67 @@ derive_utoipa!(Icon as IconSchema);
         super::routes::agent::agent_remove_extension,
         super::routes::agent::update_agent_provider,
         super::routes::action_required::confirm_tool_action,
+        super::routes::action_required::list_pending_elicitations,
+        super::routes::action_required::submit_elicitation_response,
+        super::routes::action_required::claim_session,
         super::routes::reply::reply,
         super::routes::session::list_sessions,
         super::routes::session::search_sessions,
         super::routes::session::get_session,
+        super::routes::session::get_session_messages,
         super::routes::session::get_session_insights,
         super::routes::session::update_session_name,
+        super::routes::session::update_session_tags,
+        super::routes::session::archive_session,
+        super::routes::session::unarchive_session,
+        super::routes::session::get_retention_policy,
+        super::routes::session::apply_retention,
+        super::routes::privacy::get_retention,
+        super::routes::privacy::apply_retention,
+        super::routes::privacy::purge,
+        super::routes::privacy::memory_classes,
+        super::routes::artifacts::list_artifacts,
+        super::routes::artifacts::get_artifact,
+        super::routes::artifacts::fetch_artifact,
+        super::routes::artifacts::delete_artifact,
+        super::routes::artifacts::collect_artifacts,
+        super::routes::calendar::get_availability,
+        super::routes::calendar::refresh_availability,
+        super::routes::features::list_flags,
+        super::routes::features::set_flag,
+        super::routes::features::remove_flag,
+        super::routes::features::evaluate,
+        super::routes::features::set_session_overrides,
+        super::routes::features::flag_socket,
+        super::routes::issues::list_links,
+        super::routes::issues::mirror_schedule,
+        super::routes::issues::receive_webhook,
         super::routes::session::delete_session,
         super::routes::session::export_session,
         super::routes::session::import_session,
         super::routes::session::update_session_user_recipe_values,
         super::routes::session::fork_session,
         super::routes::session::get_session_extensions,
+        super::routes::session::get_session_reviews,
+        super::routes::session::run_session_review,
+        super::routes::session::get_session_repair_diagnostics,
+        super::routes::session::redact_session_messages,
+        super::routes::session::upload_attachment,
+        super::routes::session::list_attachments,
+        super::routes::session::delete_attachment,
+        super::routes::session::list_session_turns,
+        super::routes::session::get_session_turn,
+        super::routes::session::get_session_profile,
+        super::routes::session::switch_session_profile,
+        super::routes::session::get_session_environment,
+        super::routes::session::update_session_environment,
+        super::routes::jobs::submit_job,
+        super::routes::jobs::list_jobs,
+        super::routes::jobs::get_job,
+        super::routes::jobs::cancel_job,
         super::routes::schedule::create_schedule,
         super::routes::schedule::list_schedules,
+        super::routes::schedule::list_schedule_chains,
         super::routes::schedule::delete_schedule,
         super::routes::schedule::update_schedule,
         super::routes::schedule::run_now_handler,
@@ -408,12 +519,35 @@ derive_utoipa!(Icon as IconSchema);
         super::routes::recipe::save_recipe,
         super::routes::recipe::parse_recipe,
         super::routes::recipe::recipe_to_yaml,
+        super::routes::recipe::list_registry,
+        super::routes::recipe::install_registry_recipe,
+        super::routes::recipe::upgrade_registry_recipe,
+        super::routes::recipe::downgrade_registry_recipe,
+        super::routes::recipe::uninstall_registry_recipe,
         super::routes::setup::start_openrouter_setup,
         super::routes::setup::start_tetrate_setup,
         super::routes::tunnel::start_tunnel,
         super::routes::tunnel::stop_tunnel,
         super::routes::tunnel::get_tunnel_status,
+        super::routes::tunnel::list_access_tokens,
+        super::routes::tunnel::create_access_token,
+        super::routes::tunnel::revoke_access_token,
+        super::routes::tunnel::list_tunnel_clients,
+        super::routes::tunnel::kill_tunnel,
         super::routes::telemetry::send_telemetry_event,
+        super::routes::telemetry::get_local_usage_summary,
+        super::routes::telemetry::get_local_daily_rollups,
+        super::routes::telemetry::get_context_attribution,
+        super::routes::enterprise::assign_session_team,
+        super::routes::enterprise::get_team_quota,
+        super::routes::enterprise::set_team_quota,
+        super::routes::enterprise::approve_team_quota,
+        super::routes::enterprise::chargeback_report,
+        super::routes::enterprise::spend_forecast,
+        super::routes::enterprise::list_provider_keys,
+        super::routes::enterprise::register_provider_key,
+        super::routes::enterprise::remove_provider_key,
+        super::routes::enterprise::set_provider_key_selection,
         super::routes::dictation::transcribe_dictation,
         super::routes::dictation::get_dictation_config,
         super::routes::dictation::list_models,
@@ -421,6 +555,11 @@ derive_utoipa!(Icon as IconSchema);
         super::routes::dictation::get_download_progress,
         super::routes::dictation::cancel_download,
         super::routes::dictation::delete_model,
+        super::routes::tts::get_tts_config,
+        super::routes::tts::synthesize_speech,
+        super::routes::tts::speak_message,
+        super::routes::tts::get_speech_settings,
+        super::routes::tts::update_speech_settings,
     ),
     components(schemas(
         super::routes::config_management::UpsertConfigQuery,
@@ -428,6 +567,90 @@ derive_utoipa!(Icon as IconSchema);
         super::routes::config_management::DetectProviderRequest,
         super::routes::config_management::DetectProviderResponse,
         super::routes::config_management::ConfigResponse,
+        super::routes::config_management::EffectiveConfigResponse,
+        super::routes::config_management::SetEffectiveConfigRequest,
+        super::routes::config_management::TrustWorkspaceRequest,
+        goose::config::EffectiveValue,
+        goose::config::ConfigSource,
+        goose::config::profiles::SettingsProfile,
+        goose::config::profiles::ActiveProfileState,
+        goose::config::profiles::ProfileSwitch,
+        goose::session::SessionEnvironment,
+        super::routes::system::EmergencyStopRequest,
+        super::routes::system::ConfirmResumeRequest,
+        super::emergency::EmergencyStatus,
+        super::emergency::EmergencyEvent,
+        super::emergency::EmergencyAction,
+        super::emergency::ResumeChallenge,
+        super::drain::DrainStatus,
+        super::drain::DrainRequest,
+        super::maintenance::RestartStatus,
+        super::maintenance::RestartRequest,
+        super::shell_update::ShellUpdateStatus,
+        super::shell_update::ShellUpdatePhase,
+        super::shell_update::ShellPackage,
+        super::shell_update::StageShellRequest,
+        super::shell_update::ShellHealthReport,
+        super::jobs::Job,
+        super::jobs::JobRequest,
+        super::jobs::JobPriority,
+        super::jobs::JobState,
+        super::jobs::JobProgress,
+        super::api_docs::DocPage,
+        super::supervisor::SupervisorHeartbeat,
+        super::supervisor::ServerHeartbeat,
+        super::supervisor::SupervisorStatus,
+        super::supervisor::SupervisorState,
+        super::kubernetes::ProbeStatus,
+        goose::connectivity::ConnectivityStatus,
+        goose::resources::ResourceStatus,
+        goose::resources::ResourceSample,
+        goose::resources::ResourceLimits,
+        goose::resources::GpuStatus,
+        goose::database::StoreMetrics,
+        goose::startup::StartupProfile,
+        goose::startup::SubsystemTiming,
+        goose::startup::StartupPhase,
+        super::kubernetes::InstanceStatus,
+        goose::execution::priority::ProviderGateStatus,
+        goose::execution::priority::PreemptionEvent,
+        goose::execution::priority::PreemptionKind,
+        super::routes::learning::FeedbackRequest,
+        super::routes::learning::FeedbackResponse,
+        super::routes::learning::CorePinRequest,
+        super::routes::swarm::TeamPresetEntry,
+        super::routes::swarm::TeamOutcomeRequest,
+        goose::agents::swarm_presets::TeamPreset,
+        goose::agents::swarm_presets::PresetRole,
+        goose::agents::swarm_presets::TeamTopology,
+        goose::agents::swarm_presets::Termination,
+        goose::agents::swarm_presets::PresetStats,
+        goose::agents::swarm::SwarmRole,
+        goose::agents::core_selector::CoreScore,
+        goose::session::feedback::Feedback,
+        goose::session::feedback::Rating,
+        goose::session::feedback::FeedbackStats,
+        goose::session::feedback::FeedbackCounts,
+        goose::session::feedback::FeedbackGroup,
+        goose::security::audit_log::AuditRecord,
+        goose::security::audit_log::AuditCheckpoint,
+        goose::security::audit_log::AuditIssue,
+        goose::security::audit_log::AuditVerification,
+        goose::security::audit_log::AuditExport,
+        goose::security::audit_log::AuditAnchor,
+        goose::agents::mailbox::MailboxStats,
+        goose::agents::mailbox::DeadLetter,
+        goose::agents::mailbox::Delivery,
+        goose::agents::mailbox::WakeReport,
+        goose::agents::file_claims::FileClaim,
+        goose::agents::file_claims::Negotiation,
+        goose::agents::file_claims::NegotiationStatus,
+        goose::agents::file_claims::ClaimOutcome,
+        super::routes::bus::ClaimsResponse,
+        super::routes::bus::ClaimRequest,
+        super::routes::bus::NegotiationReply,
+        goose::agents::mailbox::WakeRuleCount,
+        goose::agents::mailbox::RecipientWakes,
         super::routes::config_management::ProvidersResponse,
         super::routes::config_management::ProviderDetails,
         super::routes::config_management::SlashCommandsResponse,
@@ -443,20 +666,33 @@ derive_utoipa!(Icon as IconSchema);
         super::routes::config_management::PricingQuery,
         super::routes::config_management::PricingResponse,
         super::routes::config_management::PricingData,
+        goose::providers::task_routing::ModelRoutes,
+        goose::providers::task_routing::TaskCategory,
+        goose::providers::task_routing::TaskRoute,
+        goose::providers::task_routing::RouteTarget,
+        goose::providers::task_routing::RouteUsage,
         super::routes::prompts::PromptsListResponse,
         super::routes::prompts::PromptContentResponse,
         super::routes::prompts::SavePromptRequest,
         goose::prompt_template::Template,
         super::routes::action_required::ConfirmToolActionRequest,
+        super::routes::action_required::ElicitationResponseRequest,
+        super::routes::action_required::ClaimSessionRequest,
+        goose::action_required_manager::PendingElicitation,
         super::routes::reply::ChatRequest,
         super::routes::session::ImportSessionRequest,
         super::routes::session::SessionListResponse,
         super::routes::session::UpdateSessionNameRequest,
+        super::routes::session::UpdateSessionTagsRequest,
+        super::routes::session::ListSessionsQuery,
+        super::routes::session::MessagePageQuery,
         super::routes::session::UpdateSessionUserRecipeValuesRequest,
         super::routes::session::UpdateSessionUserRecipeValuesResponse,
         super::routes::session::ForkRequest,
         super::routes::session::ForkResponse,
         super::routes::session::SessionExtensionsResponse,
+        super::routes::session::SwitchSessionProfileRequest,
+        super::routes::session::RunReviewRequest,
         Message,
         MessageContent,
         MessageMetadata,
@@ -506,6 +742,58 @@ derive_utoipa!(Icon as IconSchema);
         Session,
         SessionInsights,
         SessionType,
+        goose::session::ArchivedFilter,
+        goose::session::MessagePage,
+        goose::session::retention::RetentionPolicy,
+        goose::session::retention::RetentionReport,
+        goose::privacy::DataClass,
+        goose::privacy::DataRetention,
+        goose::privacy::PurgeScope,
+        goose::privacy::RetainedData,
+        goose::privacy::DeletionReport,
+        goose::memory::classification::NamespaceClassification,
+        goose::artifacts::ArtifactKind,
+        goose::artifacts::RetentionClass,
+        goose::artifacts::ArtifactRecord,
+        goose::artifacts::GcReport,
+        goose::calendar::BusyBlock,
+        goose::calendar::Availability,
+        super::routes::calendar::CalendarAvailability,
+        goose::feature_flags::FlagDefinition,
+        goose::feature_flags::FlagSubject,
+        goose::feature_flags::FlagReason,
+        goose::feature_flags::FlagEvaluation,
+        super::routes::features::FlagPush,
+        goose::issues::IssueState,
+        goose::issues::LinkedItem,
+        goose::issues::IssueLink,
+        super::routes::issues::MirrorScheduleRequest,
+        goose::agents::ReviewHistory,
+        goose::conversation::repair::RepairDiagnostics,
+        goose::conversation::repair::RepairStepReport,
+        goose::session::redaction::RedactionAction,
+        goose::session::redaction::RedactionRequest,
+        goose::session::redaction::RedactionOutcome,
+        goose::session::redaction::RedactionReport,
+        goose::session::redaction::GuardrailFlag,
+        goose::session::attachments::Attachment,
+        goose::session::turn_snapshots::TurnSummary,
+        goose::session::turn_snapshots::TurnUsage,
+        goose::session::turn_snapshots::TurnSnapshot,
+        goose::session::turn_snapshots::InjectedContext,
+        goose::session::turn_snapshots::ContextAttribution,
+        goose::session::turn_snapshots::ComponentAttribution,
+        goose::session::turn_snapshots::TurnContext,
+        goose::context_mgmt::budget::ComponentTokens,
+        goose::context_mgmt::budget::BlockPriority,
+        goose::session::attachments::AttachmentKind,
+        goose::agents::ReviewTranscript,
+        goose::agents::ReviewOutcome,
+        goose::agents::IssueCategory,
+        goose::agents::adversarial::transcript::TranscriptCycle,
+        goose::agents::adversarial::transcript::PlayerSubmission,
+        goose::agents::adversarial::transcript::IssuesBySeverity,
+        goose::agents::adversarial::transcript::TranscriptIssue,
         SystemInfo,
         Conversation,
         IconSchema,
@@ -515,6 +803,12 @@ derive_utoipa!(Icon as IconSchema);
         super::routes::schedule::KillJobResponse,
         super::routes::schedule::InspectJobResponse,
         goose::scheduler::ScheduledJob,
+        goose::scheduler::JobDependency,
+        goose::scheduler::DependencyCondition,
+        goose::scheduler::JobRunStatus,
+        goose::scheduler::JobChain,
+        goose::scheduler::ChainStatus,
+        super::routes::schedule::ListChainsResponse,
         super::routes::schedule::RunNowResponse,
         super::routes::schedule::ListSchedulesResponse,
         super::routes::schedule::SessionsQuery,
@@ -535,26 +829,54 @@ derive_utoipa!(Icon as IconSchema);
         super::routes::recipe::SaveRecipeRequest,
         super::routes::recipe::SaveRecipeResponse,
         super::routes::errors::ErrorResponse,
+        super::routes::errors::ErrorCode,
+        super::routes::errors::ErrorCategory,
         super::routes::recipe::ParseRecipeRequest,
         super::routes::recipe::ParseRecipeResponse,
         super::routes::recipe::RecipeToYamlRequest,
         super::routes::recipe::RecipeToYamlResponse,
+        super::routes::recipe::InstallRecipeRequest,
+        super::routes::recipe::ChangeRecipeVersionRequest,
+        goose::recipe::registry::RecipeSource,
+        goose::recipe::registry::RegistryEntry,
+        goose::recipe::registry::InstalledVersion,
+        goose::recipe::registry::InstallReport,
         goose::recipe::Recipe,
         goose::recipe::Author,
+        goose::recipe::ExtensionDependency,
         goose::recipe::Settings,
         goose::recipe::RecipeParameter,
         goose::recipe::RecipeParameterInputType,
         goose::recipe::RecipeParameterRequirement,
         goose::recipe::Response,
         goose::recipe::SubRecipe,
+        goose::recipe::validation::Validation,
+        goose::recipe::validation::CommandGate,
+        goose::recipe::validation::FileGate,
+        goose::recipe::validation::QualityGate,
         goose::agents::types::RetryConfig,
         goose::agents::types::SuccessCheck,
         super::routes::agent::UpdateProviderRequest,
         super::routes::agent::GetToolsQuery,
+        super::routes::agent::SystemPromptQuery,
+        super::routes::agent::SystemPromptPreviewResponse,
+        goose::agents::PromptSection,
+        goose::agents::SectionTokens,
+        super::routes::agent::DiagnoseQuery,
+        goose::agents::self_report::SelfReport,
+        super::routes::agent::StartDevContainerRequest,
+        goose::agents::devcontainer::DevContainerLaunch,
+        goose::agents::self_report::ExtensionStatus,
+        goose::agents::self_report::GuardrailsStatus,
+        goose::agents::self_report::BreakerStatus,
+        goose::agents::self_report::JobStatus,
+        goose::providers::routing::EndpointHealth,
         super::routes::agent::ReadResourceRequest,
         super::routes::agent::ReadResourceResponse,
         super::routes::agent::CallToolRequest,
         super::routes::agent::CallToolResponse,
+        super::routes::agent::CoreConfig,
+        goose::agents::core_registry::CoreSettings,
         super::routes::agent::ListAppsRequest,
         super::routes::agent::ListAppsResponse,
         super::routes::agent::ImportAppRequest,
@@ -562,8 +884,15 @@ derive_utoipa!(Icon as IconSchema);
         super::routes::agent::StartAgentRequest,
         super::routes::agent::ResumeAgentRequest,
         super::routes::agent::StopAgentRequest,
+        super::routes::agent::RunningToolCallsQuery,
+        super::routes::agent::CancelToolCallRequest,
+        goose::agents::running_tools::RunningToolCall,
+        super::routes::agent::ProjectStatusQuery,
+        goose::session::continuity::ProjectStatus,
         super::routes::agent::RestartAgentRequest,
         super::routes::agent::UpdateWorkingDirRequest,
+        super::routes::agent::UpdateWorkspaceRootsRequest,
+        goose::session::WorkspaceRoot,
         super::routes::agent::UpdateFromSessionRequest,
         super::routes::agent::AddExtensionRequest,
         super::routes::agent::RemoveExtensionRequest,
@@ -573,7 +902,34 @@ derive_utoipa!(Icon as IconSchema);
         super::routes::setup::SetupResponse,
         super::tunnel::TunnelInfo,
         super::tunnel::TunnelState,
+        super::routes::tunnel::CreateAccessTokenRequest,
+        super::tunnel::access::RoutePermission,
+        super::tunnel::access::AccessTokenInfo,
+        super::tunnel::access::MintedAccessToken,
+        super::tunnel::access::ConnectedClient,
         super::routes::telemetry::TelemetryEventRequest,
+        goose::local_analytics::AnalyticsMetric,
+        goose::local_analytics::DailyRollup,
+        goose::local_analytics::KeyTotal,
+        goose::local_analytics::UsageSummary,
+        super::routes::enterprise::TeamQuotaStatus,
+        super::routes::enterprise::ChargebackParams,
+        super::routes::enterprise::ForecastParams,
+        super::routes::enterprise::ProviderKeySelection,
+        goose::providers::key_pool::ProviderKey,
+        goose::providers::key_pool::NewProviderKey,
+        goose::providers::key_pool::KeySelection,
+        goose::providers::key_pool::KeyStatus,
+        goose::observability::team_accounting::TeamAssignment,
+        goose::observability::team_accounting::TeamQuota,
+        goose::observability::team_accounting::QuotaAction,
+        goose::observability::team_accounting::QuotaDecision,
+        goose::observability::team_accounting::TeamUsage,
+        goose::observability::team_accounting::ChargebackGroupBy,
+        goose::observability::forecast::SpendForecast,
+        goose::observability::forecast::ForecastRow,
+        goose::observability::forecast::DailySpend,
+        goose::observability::ReportFormat,
         goose::goose_apps::GooseApp,
         goose::goose_apps::WindowProps,
         goose::goose_apps::McpAppResource,
@@ -586,6 +942,10 @@ derive_utoipa!(Icon as IconSchema);
         goose::dictation::providers::DictationProvider,
         super::routes::dictation::DictationProviderStatus,
         super::routes::dictation::WhisperModelResponse,
+        super::routes::tts::SynthesizeRequest,
+        super::routes::tts::TtsProviderStatus,
+        goose::tts::providers::TtsProvider,
+        goose::tts::settings::SpeechSettings,
         DownloadProgress,
         DownloadStatus,
     ))
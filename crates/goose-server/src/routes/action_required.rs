@@ -1,6 +1,11 @@
 use crate::routes::errors::ErrorResponse;
 use crate::state::AppState;
-use axum::{extract::State, routing::post, Json, Router};
+use axum::{
+    extract::{Query, State},
+    routing::{get, post},
+    Json, Router,
+};
+use goose::action_required_manager::{ActionRequiredManager, PendingElicitation};
 use goose::permission::permission_confirmation::PrincipalType;
 use goose::permission::{Permission, PermissionConfirmation};
 use serde::{Deserialize, Serialize};
@@ -51,12 +56,106 @@ pub async fn confirm_tool_action(
     Ok(Json(Value::Object(serde_json::Map::new())))
 }
 
+#[derive(Debug, Deserialize)]
+pub struct PendingElicitationsQuery {
+    session_id: Option<String>,
+}
+
+#[derive(Debug, Deserialize, Serialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct ElicitationResponseRequest {
+    id: String,
+    /// Client answering; must match the client that claimed the session
+    client_id: Option<String>,
+    user_data: Value,
+}
+
+#[derive(Debug, Deserialize, Serialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct ClaimSessionRequest {
+    session_id: String,
+    client_id: String,
+    /// Give up the claim instead of taking it
+    #[serde(default)]
+    release: bool,
+}
+
+#[utoipa::path(
+    get,
+    path = "/action-required/pending",
+    params(
+        ("session_id" = Option<String>, Query, description = "Only list elicitations shown in this session")
+    ),
+    responses(
+        (status = 200, description = "Unanswered elicitations, oldest first", body = Vec<PendingElicitation>),
+        (status = 401, description = "Unauthorized - invalid secret key")
+    )
+)]
+pub async fn list_pending_elicitations(
+    Query(query): Query<PendingElicitationsQuery>,
+) -> Json<Vec<PendingElicitation>> {
+    Json(
+        ActionRequiredManager::global()
+            .list_pending(query.session_id.as_deref())
+            .await,
+    )
+}
+
+#[utoipa::path(
+    post,
+    path = "/action-required/elicitation-response",
+    request_body = ElicitationResponseRequest,
+    responses(
+        (status = 200, description = "Answer delivered to the waiting request; returns the answer as delivered", body = Value),
+        (status = 401, description = "Unauthorized - invalid secret key"),
+        (status = 400, description = "No such elicitation, another client must answer it, or the answer fails its schema", body = ErrorResponse)
+    )
+)]
+pub async fn submit_elicitation_response(
+    Json(request): Json<ElicitationResponseRequest>,
+) -> Result<Json<Value>, ErrorResponse> {
+    let delivered = ActionRequiredManager::global()
+        .submit_response_from(&request.id, request.client_id.as_deref(), request.user_data)
+        .await
+        .map_err(|e| ErrorResponse::bad_request(e.to_string()))?;
+
+    Ok(Json(delivered))
+}
+
+#[utoipa::path(
+    post,
+    path = "/action-required/claim",
+    request_body = ClaimSessionRequest,
+    responses(
+        (status = 200, description = "The client now answers the session's elicitations", body = Value),
+        (status = 401, description = "Unauthorized - invalid secret key")
+    )
+)]
+pub async fn claim_session(Json(request): Json<ClaimSessionRequest>) -> Json<Value> {
+    let manager = ActionRequiredManager::global();
+    if request.release {
+        manager.release_session(&request.session_id).await;
+    } else {
+        manager
+            .claim_session(&request.session_id, &request.client_id)
+            .await;
+    }
+
+    Json(Value::Object(serde_json::Map::new()))
+}
+
 pub fn routes(state: Arc<AppState>) -> Router {
     Router::new()
         .route(
             "/action-required/tool-confirmation",
             post(confirm_tool_action),
         )
+        .route("/action-required/pending", get(list_pending_elicitations))
+        .route(
+            "/action-required/elicitation-response",
+            post(submit_elicitation_response),
+        )
+        .route("/action-required/claim", post(claim_session))
         .with_state(state)
 }
 
@@ -1,4 +1,4 @@
-use crate::routes::errors::ErrorResponse;
+use crate::routes::errors::{ErrorCode, ErrorResponse};
 use crate::routes::recipe_utils::{
     apply_recipe_to_agent, build_recipe_with_parameter_values, load_recipe_by_id, validate_recipe,
 };
@@ -7,10 +7,13 @@ use axum::response::IntoResponse;
 use axum::{
     extract::{Query, State},
     http::StatusCode,
-    routing::{get, post},
+    routing::{get, post, put},
     Json, Router,
 };
-use goose::agents::{Container, ExtensionLoadResult};
+use goose::agents::core_registry::{self, CoreSettings};
+use goose::agents::devcontainer::{self, DevContainerLaunch};
+use goose::agents::running_tools::RunningToolCall;
+use goose::agents::{Container, ExecutionMode, ExtensionLoadResult, SectionTokens, SelfReport};
 use goose::goose_apps::{fetch_mcp_apps, GooseApp, McpAppCache};
 
 use base64::Engine;
@@ -22,8 +25,10 @@ use goose::prompt_template::render_template;
 use goose::providers::create;
 use goose::recipe::Recipe;
 use goose::recipe_deeplink;
+use goose::session::continuity::{self, ProjectMemory, ProjectStatus};
 use goose::session::extension_data::ExtensionState;
 use goose::session::session_manager::SessionType;
+use goose::session::workspace::{WorkspaceError, WorkspaceRoot, WorkspaceRoots};
 use goose::session::{EnabledExtensionsState, Session};
 use goose::{
     agents::{extension::ToolInfo, extension_manager::get_parameter_names},
@@ -58,6 +63,24 @@ pub struct GetToolsQuery {
     session_id: String,
 }
 
+#[derive(Deserialize, utoipa::ToSchema)]
+pub struct SystemPromptQuery {
+    session_id: String,
+}
+
+#[derive(Deserialize, utoipa::ToSchema)]
+pub struct DiagnoseQuery {
+    session_id: String,
+}
+
+#[derive(Serialize, utoipa::ToSchema)]
+pub struct SystemPromptPreviewResponse {
+    /// Fully rendered system prompt, before per-turn context is injected
+    system_prompt: String,
+    /// Tokens in each section of the prompt
+    sections: Vec<SectionTokens>,
+}
+
 #[derive(Deserialize, utoipa::ToSchema)]
 pub struct StartAgentRequest {
     working_dir: String,
@@ -69,6 +92,15 @@ pub struct StartAgentRequest {
     recipe_deeplink: Option<String>,
     #[serde(default)]
     extension_overrides: Option<Vec<ExtensionConfig>>,
+    /// Inject the previous session's hand-off note for this working
+    /// directory; defaults to true
+    #[serde(default)]
+    project_context: Option<bool>,
+}
+
+#[derive(Deserialize, utoipa::ToSchema)]
+pub struct ProjectStatusQuery {
+    working_dir: String,
 }
 
 #[derive(Deserialize, utoipa::ToSchema)]
@@ -76,6 +108,18 @@ pub struct StopAgentRequest {
     session_id: String,
 }
 
+#[derive(Deserialize, utoipa::ToSchema)]
+pub struct RunningToolCallsQuery {
+    session_id: String,
+}
+
+#[derive(Deserialize, utoipa::ToSchema)]
+pub struct CancelToolCallRequest {
+    session_id: String,
+    /// Id of the tool request to cancel
+    request_id: String,
+}
+
 #[derive(Deserialize, utoipa::ToSchema)]
 pub struct RestartAgentRequest {
     session_id: String,
@@ -87,6 +131,13 @@ pub struct UpdateWorkingDirRequest {
     working_dir: String,
 }
 
+#[derive(Deserialize, utoipa::ToSchema)]
+pub struct UpdateWorkspaceRootsRequest {
+    session_id: String,
+    /// The first root becomes the session's working directory
+    roots: Vec<WorkspaceRoot>,
+}
+
 #[derive(Deserialize, utoipa::ToSchema)]
 pub struct ResumeAgentRequest {
     session_id: String,
@@ -111,6 +162,11 @@ pub struct SetContainerRequest {
     container_id: Option<String>,
 }
 
+#[derive(Deserialize, utoipa::ToSchema)]
+pub struct StartDevContainerRequest {
+    session_id: String,
+}
+
 #[derive(Deserialize, utoipa::ToSchema)]
 pub struct ReadResourceRequest {
     session_id: String,
@@ -166,6 +222,7 @@ pub struct RestartAgentResponse {
         (status = 200, description = "Agent started successfully", body = Session),
         (status = 400, description = "Bad request", body = ErrorResponse),
         (status = 401, description = "Unauthorized - invalid secret key"),
+        (status = 503, description = "Server is draining before a restart", body = ErrorResponse),
         (status = 500, description = "Internal server error", body = ErrorResponse)
     )
 )]
@@ -174,6 +231,7 @@ async fn start_agent(
     State(state): State<Arc<AppState>>,
     Json(payload): Json<StartAgentRequest>,
 ) -> Result<Json<Session>, ErrorResponse> {
+    state.drain.ensure_accepting()?;
     goose::posthog::set_session_context("desktop", false);
 
     let StartAgentRequest {
@@ -182,7 +240,9 @@ async fn start_agent(
         recipe_id,
         recipe_deeplink,
         extension_overrides,
+        project_context,
     } = payload;
+    let use_project_context = project_context.unwrap_or(true) && continuity::is_enabled();
 
     let original_recipe = if let Some(deeplink) = recipe_deeplink {
         match recipe_deeplink::decode(&deeplink) {
@@ -190,10 +250,7 @@ async fn start_agent(
             Err(err) => {
                 error!("Failed to decode recipe deeplink: {}", err);
                 goose::posthog::emit_error("recipe_deeplink_decode_failed", &err.to_string());
-                return Err(ErrorResponse {
-                    message: err.to_string(),
-                    status: StatusCode::BAD_REQUEST,
-                });
+                return Err(ErrorResponse::bad_request(err.to_string()));
             }
         }
     } else if let Some(id) = recipe_id {
@@ -207,10 +264,7 @@ async fn start_agent(
 
     if let Some(ref recipe) = original_recipe {
         if let Err(err) = validate_recipe(recipe) {
-            return Err(ErrorResponse {
-                message: err.message,
-                status: err.status,
-            });
+            return Err(err.into());
         }
     }
 
@@ -224,10 +278,7 @@ async fn start_agent(
         .map_err(|err| {
             error!("Failed to create session: {}", err);
             goose::posthog::emit_error("session_create_failed", &err.to_string());
-            ErrorResponse {
-                message: format!("Failed to create session: {}", err),
-                status: StatusCode::BAD_REQUEST,
-            }
+            ErrorResponse::bad_request(format!("Failed to create session: {}", err))
         })?;
 
     let recipe_extensions = original_recipe
@@ -247,10 +298,7 @@ async fn start_agent(
             .await
             .map_err(|err| {
                 error!("Failed to save initial extension state: {}", err);
-                ErrorResponse {
-                    message: format!("Failed to save initial extension state: {}", err),
-                    status: StatusCode::INTERNAL_SERVER_ERROR,
-                }
+                ErrorResponse::internal(format!("Failed to save initial extension state: {}", err))
             })?;
     }
 
@@ -262,10 +310,7 @@ async fn start_agent(
             .await
             .map_err(|err| {
                 error!("Failed to update session with recipe: {}", err);
-                ErrorResponse {
-                    message: format!("Failed to update session with recipe: {}", err),
-                    status: StatusCode::INTERNAL_SERVER_ERROR,
-                }
+                ErrorResponse::internal(format!("Failed to update session with recipe: {}", err))
             })?;
     }
 
@@ -275,10 +320,7 @@ async fn start_agent(
         .await
         .map_err(|err| {
             error!("Failed to get updated session: {}", err);
-            ErrorResponse {
-                message: format!("Failed to get updated session: {}", err),
-                status: StatusCode::INTERNAL_SERVER_ERROR,
-            }
+            ErrorResponse::internal(format!("Failed to get updated session: {}", err))
         })?;
 
     // Eagerly start loading extensions in the background
@@ -291,6 +333,11 @@ async fn start_agent(
             .await
         {
             Ok(agent) => {
+                if use_project_context {
+                    let context = ProjectMemory::default()
+                        .context_for(&session_for_spawn.working_dir, &session_for_spawn.id);
+                    agent.set_project_context(context).await;
+                }
                 let results = agent.load_extensions_from_session(&session_for_spawn).await;
                 tracing::debug!(
                     "Background extension loading completed for session {}",
@@ -339,28 +386,20 @@ async fn resume_agent(
         .map_err(|err| {
             error!("Failed to resume session {}: {}", payload.session_id, err);
             goose::posthog::emit_error("session_resume_failed", &err.to_string());
-            ErrorResponse {
-                message: format!("Failed to resume session: {}", err),
-                status: StatusCode::NOT_FOUND,
-            }
+            ErrorResponse::not_found(format!("Failed to resume session: {}", err))
+                .with_code(ErrorCode::SessionNotFound)
         })?;
 
     let extension_results = if payload.load_model_and_extensions {
         let agent = state
             .get_agent_for_route(payload.session_id.clone())
             .await
-            .map_err(|code| ErrorResponse {
-                message: "Failed to get agent for route".into(),
-                status: code,
-            })?;
+            .map_err(|code| ErrorResponse::new(code, "Failed to get agent for route"))?;
 
         agent
             .restore_provider_from_session(&session)
             .await
-            .map_err(|e| ErrorResponse {
-                message: e.to_string(),
-                status: StatusCode::INTERNAL_SERVER_ERROR,
-            })?;
+            .map_err(|e| ErrorResponse::internal(e.to_string()))?;
 
         let extension_results =
             if let Some(results) = state.take_extension_loading_task(&payload.session_id).await {
@@ -408,18 +447,12 @@ async fn update_from_session(
     let agent = state
         .get_agent_for_route(payload.session_id.clone())
         .await
-        .map_err(|status| ErrorResponse {
-            message: format!("Failed to get agent: {}", status),
-            status,
-        })?;
+        .map_err(|status| ErrorResponse::new(status, format!("Failed to get agent: {}", status)))?;
     let session = state
         .session_manager()
         .get_session(&payload.session_id, false)
         .await
-        .map_err(|err| ErrorResponse {
-            message: format!("Failed to get session: {}", err),
-            status: StatusCode::INTERNAL_SERVER_ERROR,
-        })?;
+        .map_err(|err| ErrorResponse::internal(format!("Failed to get session: {}", err)))?;
     let context: HashMap<&str, Value> = HashMap::new();
     let desktop_prompt =
         render_template("desktop_prompt.md", &context).expect("Prompt should render");
@@ -440,10 +473,7 @@ async fn update_from_session(
                 // Recipe has missing parameters - use default prompt
             }
             Err(e) => {
-                return Err(ErrorResponse {
-                    message: e.to_string(),
-                    status: StatusCode::INTERNAL_SERVER_ERROR,
-                });
+                return Err(ErrorResponse::internal(e.to_string()));
             }
         }
     }
@@ -622,10 +652,7 @@ async fn agent_remove_extension(
         .await
         .map_err(|e| {
             error!("Failed to remove extension: {}", e);
-            ErrorResponse {
-                message: format!("Failed to remove extension: {}", e),
-                status: StatusCode::INTERNAL_SERVER_ERROR,
-            }
+            ErrorResponse::internal(format!("Failed to remove extension: {}", e))
         })?;
 
     Ok(StatusCode::OK)
@@ -661,6 +688,48 @@ async fn set_container(
     Ok(StatusCode::OK)
 }
 
+#[utoipa::path(
+    post,
+    path = "/agent/devcontainer",
+    request_body = StartDevContainerRequest,
+    responses(
+        (status = 200, description = "Dev container started; extensions added from now on run inside it", body = DevContainerLaunch),
+        (status = 401, description = "Unauthorized - invalid secret key"),
+        (status = 404, description = "The session's working directory has no devcontainer.json"),
+        (status = 424, description = "Agent not initialized"),
+        (status = 500, description = "Failed to build or start the container")
+    )
+)]
+async fn start_devcontainer(
+    State(state): State<Arc<AppState>>,
+    Json(request): Json<StartDevContainerRequest>,
+) -> Result<Json<DevContainerLaunch>, ErrorResponse> {
+    let agent = state.get_agent(request.session_id.clone()).await?;
+    let session = state
+        .session_manager()
+        .get_session(&request.session_id, false)
+        .await?;
+
+    let workspace = session.working_dir.clone();
+    let launched =
+        tokio::task::spawn_blocking(move || devcontainer::launch_for_workspace(&workspace))
+            .await
+            .map_err(|e| ErrorResponse::internal(e.to_string()))?
+            .map_err(|e| {
+                error!("Failed to start dev container: {:#}", e);
+                ErrorResponse::internal(format!("Failed to start dev container: {:#}", e))
+            })?;
+    let Some((container, launch)) = launched else {
+        return Err(ErrorResponse::not_found(format!(
+            "No devcontainer.json in {}",
+            session.working_dir.display()
+        )));
+    };
+    agent.set_container(Some(container)).await;
+
+    Ok(Json(launch))
+}
+
 #[utoipa::path(
     post,
     path = "/agent/stop",
@@ -677,18 +746,142 @@ async fn stop_agent(
     Json(payload): Json<StopAgentRequest>,
 ) -> Result<StatusCode, ErrorResponse> {
     let session_id = payload.session_id;
+    if continuity::is_enabled() && state.agent_manager.has_session(&session_id).await {
+        record_project_status(&state, &session_id).await;
+    }
     state
         .agent_manager
         .remove_session(&session_id)
         .await
-        .map_err(|e| ErrorResponse {
-            message: format!("Failed to stop agent for session {}: {}", session_id, e),
-            status: StatusCode::NOT_FOUND,
+        .map_err(|e| {
+            ErrorResponse::not_found(format!(
+                "Failed to stop agent for session {}: {}",
+                session_id, e
+            ))
         })?;
 
     Ok(StatusCode::OK)
 }
 
+#[utoipa::path(
+    get,
+    path = "/agent/tool_calls",
+    params(
+        ("session_id" = String, Query, description = "Session whose running tool calls to list")
+    ),
+    responses(
+        (status = 200, description = "Tool calls still running, longest running first", body = Vec<RunningToolCall>),
+        (status = 401, description = "Unauthorized - invalid secret key"),
+        (status = 424, description = "Agent not initialized")
+    )
+)]
+async fn get_running_tool_calls(
+    State(state): State<Arc<AppState>>,
+    Query(query): Query<RunningToolCallsQuery>,
+) -> Result<Json<Vec<RunningToolCall>>, StatusCode> {
+    let agent = state.get_agent_for_route(query.session_id).await?;
+    Ok(Json(agent.running_tool_calls()))
+}
+
+#[utoipa::path(
+    post,
+    path = "/agent/tool_calls/cancel",
+    request_body = CancelToolCallRequest,
+    responses(
+        (status = 200, description = "Tool call cancelled; the reply continues with a cancelled result for it"),
+        (status = 401, description = "Unauthorized - invalid secret key"),
+        (status = 404, description = "No running tool call with this id", body = ErrorResponse),
+        (status = 424, description = "Agent not initialized")
+    )
+)]
+async fn cancel_tool_call(
+    State(state): State<Arc<AppState>>,
+    Json(payload): Json<CancelToolCallRequest>,
+) -> Result<StatusCode, ErrorResponse> {
+    let agent = state
+        .get_agent_for_route(payload.session_id)
+        .await
+        .map_err(|code| ErrorResponse::new(code, "Agent not initialized"))?;
+    if agent.cancel_tool_call(&payload.request_id) {
+        Ok(StatusCode::OK)
+    } else {
+        Err(ErrorResponse::not_found(format!(
+            "No running tool call {}",
+            payload.request_id
+        )))
+    }
+}
+
+/// Writes the session's hand-off note in the background so stopping stays fast.
+async fn record_project_status(state: &AppState, session_id: &str) {
+    let session = match state.session_manager().get_session(session_id, true).await {
+        Ok(session) if session.session_type == SessionType::User => session,
+        _ => return,
+    };
+    let provider = match state.get_agent(session_id.to_string()).await {
+        Ok(agent) => agent.provider().await,
+        Err(e) => Err(e),
+    };
+    let Ok(provider) = provider else {
+        return;
+    };
+    tokio::spawn(async move {
+        match ProjectMemory::default()
+            .record_session(provider.as_ref(), &session)
+            .await
+        {
+            Ok(Some(_)) => {
+                tracing::info!("Saved project status for {}", session.working_dir.display())
+            }
+            Ok(None) => {}
+            Err(e) => warn!("Failed to save project status: {}", e),
+        }
+    });
+}
+
+#[utoipa::path(
+    get,
+    path = "/agent/project_status",
+    params(
+        ("working_dir" = String, Query, description = "Workspace the status was written for")
+    ),
+    responses(
+        (status = 200, description = "Hand-off note from the last session in this workspace", body = Option<ProjectStatus>),
+        (status = 401, description = "Unauthorized - invalid secret key")
+    )
+)]
+async fn get_project_status(
+    Query(query): Query<ProjectStatusQuery>,
+) -> Result<Json<Option<ProjectStatus>>, ErrorResponse> {
+    Ok(Json(
+        ProjectMemory::default().load(&PathBuf::from(query.working_dir))?,
+    ))
+}
+
+#[utoipa::path(
+    delete,
+    path = "/agent/project_status",
+    params(
+        ("working_dir" = String, Query, description = "Workspace to forget")
+    ),
+    responses(
+        (status = 204, description = "Status removed; new sessions start without it"),
+        (status = 401, description = "Unauthorized - invalid secret key"),
+        (status = 404, description = "No status for this workspace", body = ErrorResponse)
+    )
+)]
+async fn forget_project_status(
+    Query(query): Query<ProjectStatusQuery>,
+) -> Result<StatusCode, ErrorResponse> {
+    if ProjectMemory::default().forget(&PathBuf::from(query.working_dir))? {
+        Ok(StatusCode::NO_CONTENT)
+    } else {
+        Err(ErrorResponse::not_found(
+            "No project status for this workspace",
+        ))
+    }
+}
+
 async fn restart_agent_internal(
     state: &Arc<AppState>,
     session_id: &str,
@@ -700,19 +893,13 @@ async fn restart_agent_internal(
     let agent = state
         .get_agent_for_route(session_id.to_string())
         .await
-        .map_err(|code| ErrorResponse {
-            message: "Failed to create new agent during restart".into(),
-            status: code,
-        })?;
+        .map_err(|code| ErrorResponse::new(code, "Failed to create new agent during restart"))?;
 
     let provider_future = agent.restore_provider_from_session(session);
     let extensions_future = agent.load_extensions_from_session(session);
 
     let (provider_result, extension_results) = tokio::join!(provider_future, extensions_future);
-    provider_result.map_err(|e| ErrorResponse {
-        message: e.to_string(),
-        status: StatusCode::INTERNAL_SERVER_ERROR,
-    })?;
+    provider_result.map_err(|e| ErrorResponse::internal(e.to_string()))?;
 
     let context: HashMap<&str, Value> = HashMap::new();
     let desktop_prompt =
@@ -735,10 +922,7 @@ async fn restart_agent_internal(
                 // Recipe has missing parameters - use default prompt
             }
             Err(e) => {
-                return Err(ErrorResponse {
-                    message: e.to_string(),
-                    status: StatusCode::INTERNAL_SERVER_ERROR,
-                });
+                return Err(ErrorResponse::internal(e.to_string()));
             }
         }
     }
@@ -770,10 +954,8 @@ async fn restart_agent(
         .await
         .map_err(|err| {
             error!("Failed to get session during restart: {}", err);
-            ErrorResponse {
-                message: format!("Failed to get session: {}", err),
-                status: StatusCode::NOT_FOUND,
-            }
+            ErrorResponse::not_found(format!("Failed to get session: {}", err))
+                .with_code(ErrorCode::SessionNotFound)
         })?;
 
     let extension_results = restart_agent_internal(&state, &session_id, &session).await?;
@@ -801,18 +983,14 @@ async fn update_working_dir(
     let working_dir = payload.working_dir.trim();
 
     if working_dir.is_empty() {
-        return Err(ErrorResponse {
-            message: "Working directory cannot be empty".into(),
-            status: StatusCode::BAD_REQUEST,
-        });
+        return Err(ErrorResponse::bad_request(
+            "Working directory cannot be empty",
+        ));
     }
 
     let path = PathBuf::from(working_dir);
     if !path.exists() || !path.is_dir() {
-        return Err(ErrorResponse {
-            message: "Invalid directory path".into(),
-            status: StatusCode::BAD_REQUEST,
-        });
+        return Err(ErrorResponse::bad_request("Invalid directory path"));
     }
 
     // Update the session's working directory
@@ -824,10 +1002,7 @@ async fn update_working_dir(
         .await
         .map_err(|e| {
             error!("Failed to update session working directory: {}", e);
-            ErrorResponse {
-                message: format!("Failed to update working directory: {}", e),
-                status: StatusCode::INTERNAL_SERVER_ERROR,
-            }
+            ErrorResponse::internal(format!("Failed to update working directory: {}", e))
         })?;
 
     // Get the updated session and restart the agent
@@ -837,10 +1012,8 @@ async fn update_working_dir(
         .await
         .map_err(|err| {
             error!("Failed to get session after working dir update: {}", err);
-            ErrorResponse {
-                message: format!("Failed to get session: {}", err),
-                status: StatusCode::NOT_FOUND,
-            }
+            ErrorResponse::not_found(format!("Failed to get session: {}", err))
+                .with_code(ErrorCode::SessionNotFound)
         })?;
 
     restart_agent_internal(&state, &session_id, &session).await?;
@@ -848,6 +1021,128 @@ async fn update_working_dir(
     Ok(StatusCode::OK)
 }
 
+impl From<WorkspaceError> for ErrorResponse {
+    fn from(err: WorkspaceError) -> Self {
+        Self::bad_request(err.to_string())
+    }
+}
+
+#[utoipa::path(
+    post,
+    path = "/agent/update_workspace_roots",
+    request_body = UpdateWorkspaceRootsRequest,
+    responses(
+        (status = 200, description = "Workspace roots saved and agent restarted in the primary root"),
+        (status = 400, description = "Invalid, duplicate or missing root"),
+        (status = 401, description = "Unauthorized - invalid secret key"),
+        (status = 404, description = "Session not found"),
+        (status = 500, description = "Internal server error")
+    )
+)]
+async fn update_workspace_roots(
+    State(state): State<Arc<AppState>>,
+    Json(payload): Json<UpdateWorkspaceRootsRequest>,
+) -> Result<StatusCode, ErrorResponse> {
+    let session_id = payload.session_id;
+    let roots = WorkspaceRoots::new(payload.roots)?;
+    let session = state
+        .session_manager()
+        .get_session(&session_id, false)
+        .await
+        .map_err(|err| {
+            ErrorResponse::not_found(format!("Failed to get session: {}", err))
+                .with_code(ErrorCode::SessionNotFound)
+        })?;
+
+    let mut extension_data = session.extension_data;
+    roots
+        .to_extension_data(&mut extension_data)
+        .map_err(|e| ErrorResponse::internal(e.to_string()))?;
+    let primary = roots.roots[0].path.clone();
+    state
+        .session_manager()
+        .update(&session_id)
+        .working_dir(primary)
+        .extension_data(extension_data)
+        .apply()
+        .await
+        .map_err(|e| {
+            error!("Failed to update session workspace roots: {}", e);
+            ErrorResponse::internal(format!("Failed to update workspace roots: {}", e))
+        })?;
+
+    let session = state
+        .session_manager()
+        .get_session(&session_id, false)
+        .await
+        .map_err(|err| {
+            ErrorResponse::not_found(format!("Failed to get session: {}", err))
+                .with_code(ErrorCode::SessionNotFound)
+        })?;
+    restart_agent_internal(&state, &session_id, &session).await?;
+
+    Ok(StatusCode::OK)
+}
+
+#[utoipa::path(
+    get,
+    path = "/agent/system_prompt",
+    params(
+        ("session_id" = String, Query, description = "Session whose system prompt to render")
+    ),
+    responses(
+        (status = 200, description = "Rendered system prompt", body = SystemPromptPreviewResponse),
+        (status = 401, description = "Unauthorized - invalid secret key"),
+        (status = 424, description = "Agent not initialized"),
+        (status = 500, description = "Internal server error", body = ErrorResponse)
+    )
+)]
+async fn preview_system_prompt(
+    State(state): State<Arc<AppState>>,
+    Query(query): Query<SystemPromptQuery>,
+) -> Result<Json<SystemPromptPreviewResponse>, ErrorResponse> {
+    let agent = state
+        .get_agent_for_route(query.session_id.clone())
+        .await
+        .map_err(|code| ErrorResponse::new(code, "Agent not initialized"))?;
+    let system_prompt = agent
+        .preview_system_prompt(&query.session_id)
+        .await
+        .map_err(|e| ErrorResponse::internal(format!("Failed to render system prompt: {}", e)))?;
+    let sections = agent
+        .system_prompt_section_tokens()
+        .await
+        .map_err(|e| ErrorResponse::internal(format!("Failed to count prompt tokens: {}", e)))?;
+
+    Ok(Json(SystemPromptPreviewResponse {
+        system_prompt,
+        sections,
+    }))
+}
+
+#[utoipa::path(
+    get,
+    path = "/agent/diagnose",
+    params(
+        ("session_id" = String, Query, description = "Session whose agent to report on")
+    ),
+    responses(
+        (status = 200, description = "Core, budget, extensions, learned state, guardrails, circuit breakers, scheduled jobs and degraded subsystems", body = SelfReport),
+        (status = 401, description = "Unauthorized - invalid secret key"),
+        (status = 424, description = "Agent not initialized")
+    )
+)]
+async fn diagnose(
+    State(state): State<Arc<AppState>>,
+    Query(query): Query<DiagnoseQuery>,
+) -> Result<Json<SelfReport>, ErrorResponse> {
+    let agent = state
+        .get_agent_for_route(query.session_id.clone())
+        .await
+        .map_err(|code| ErrorResponse::new(code, "Agent not initialized"))?;
+    Ok(Json(agent.self_report(&query.session_id).await))
+}
+
 #[utoipa::path(
     post,
     path = "/agent/read_resource",
@@ -1020,17 +1315,11 @@ async fn list_apps(
     let agent = state
         .get_agent_for_route(session_id.clone())
         .await
-        .map_err(|status| ErrorResponse {
-            message: "Failed to get agent".to_string(),
-            status,
-        })?;
+        .map_err(|status| ErrorResponse::new(status, "Failed to get agent"))?;
 
     let apps = fetch_mcp_apps(&agent.extension_manager, &session_id)
         .await
-        .map_err(|e| ErrorResponse {
-            message: format!("Failed to list apps: {}", e.message),
-            status: StatusCode::INTERNAL_SERVER_ERROR,
-        })?;
+        .map_err(|e| ErrorResponse::internal(format!("Failed to list apps: {}", e.message)))?;
 
     if let Some(cache) = cache.as_ref() {
         let active_extensions: HashSet<String> = apps
@@ -1076,28 +1365,21 @@ async fn list_apps(
 async fn export_app(
     axum::extract::Path(name): axum::extract::Path<String>,
 ) -> Result<impl IntoResponse, ErrorResponse> {
-    let cache = McpAppCache::new().map_err(|e| ErrorResponse {
-        message: format!("Failed to access app cache: {}", e),
-        status: StatusCode::INTERNAL_SERVER_ERROR,
-    })?;
+    let cache = McpAppCache::new()
+        .map_err(|e| ErrorResponse::internal(format!("Failed to access app cache: {}", e)))?;
 
-    let apps = cache.list_apps().map_err(|e| ErrorResponse {
-        message: format!("Failed to list apps: {}", e),
-        status: StatusCode::INTERNAL_SERVER_ERROR,
-    })?;
+    let apps = cache
+        .list_apps()
+        .map_err(|e| ErrorResponse::internal(format!("Failed to list apps: {}", e)))?;
 
     let app = apps
         .into_iter()
         .find(|a| a.resource.name == name)
-        .ok_or_else(|| ErrorResponse {
-            message: format!("App '{}' not found", name),
-            status: StatusCode::NOT_FOUND,
-        })?;
+        .ok_or_else(|| ErrorResponse::not_found(format!("App '{}' not found", name)))?;
 
-    let html = app.to_html().map_err(|e| ErrorResponse {
-        message: format!("Failed to generate HTML: {}", e),
-        status: StatusCode::INTERNAL_SERVER_ERROR,
-    })?;
+    let html = app
+        .to_html()
+        .map_err(|e| ErrorResponse::internal(format!("Failed to generate HTML: {}", e)))?;
 
     Ok(html)
 }
@@ -1132,15 +1414,11 @@ pub struct ImportAppResponse {
 async fn import_app(
     Json(body): Json<ImportAppRequest>,
 ) -> Result<(StatusCode, Json<ImportAppResponse>), ErrorResponse> {
-    let cache = McpAppCache::new().map_err(|e| ErrorResponse {
-        message: format!("Failed to access app cache: {}", e),
-        status: StatusCode::INTERNAL_SERVER_ERROR,
-    })?;
+    let cache = McpAppCache::new()
+        .map_err(|e| ErrorResponse::internal(format!("Failed to access app cache: {}", e)))?;
 
-    let mut app = GooseApp::from_html(&body.html).map_err(|e| ErrorResponse {
-        message: format!("Invalid Goose App HTML: {}", e),
-        status: StatusCode::BAD_REQUEST,
-    })?;
+    let mut app = GooseApp::from_html(&body.html)
+        .map_err(|e| ErrorResponse::bad_request(format!("Invalid Goose App HTML: {}", e)))?;
 
     let original_name = app.resource.name.clone();
     let mut counter = 1;
@@ -1159,10 +1437,9 @@ async fn import_app(
 
     app.mcp_servers = vec!["apps".to_string()];
 
-    cache.store_app(&app).map_err(|e| ErrorResponse {
-        message: format!("Failed to store app: {}", e),
-        status: StatusCode::INTERNAL_SERVER_ERROR,
-    })?;
+    cache
+        .store_app(&app)
+        .map_err(|e| ErrorResponse::internal(format!("Failed to store app: {}", e)))?;
 
     Ok((
         StatusCode::CREATED,
@@ -1173,13 +1450,78 @@ async fn import_app(
     ))
 }
 
+#[derive(Serialize, utoipa::ToSchema)]
+pub struct CoreConfig {
+    /// freeform or structured
+    core: String,
+    settings: CoreSettings,
+}
+
+#[utoipa::path(
+    get,
+    path = "/agent/cores",
+    responses(
+        (status = 200, description = "Settings of every execution core", body = [CoreConfig]),
+    ),
+    security(
+        ("api_key" = [])
+    ),
+    tag = "Agent"
+)]
+pub async fn list_cores() -> Json<Vec<CoreConfig>> {
+    let mut cores: Vec<CoreConfig> = core_registry::all_settings()
+        .into_iter()
+        .map(|(core, settings)| CoreConfig {
+            core: core.to_string(),
+            settings,
+        })
+        .collect();
+    cores.sort_by(|a, b| a.core.cmp(&b.core));
+    Json(cores)
+}
+
+#[utoipa::path(
+    put,
+    path = "/agent/cores/{core}",
+    params(
+        ("core" = String, Path, description = "freeform or structured")
+    ),
+    request_body = CoreSettings,
+    responses(
+        (status = 200, description = "Core settings replaced", body = CoreConfig),
+        (status = 404, description = "Unknown core", body = ErrorResponse),
+    ),
+    security(
+        ("api_key" = [])
+    ),
+    tag = "Agent"
+)]
+pub async fn update_core(
+    axum::extract::Path(core): axum::extract::Path<String>,
+    Json(settings): Json<CoreSettings>,
+) -> Result<Json<CoreConfig>, ErrorResponse> {
+    let mode = match core.as_str() {
+        "freeform" => ExecutionMode::Freeform,
+        "structured" => ExecutionMode::Structured,
+        _ => return Err(ErrorResponse::not_found(format!("Unknown core '{}'", core))),
+    };
+    core_registry::set_settings(mode, settings.clone())?;
+    Ok(Json(CoreConfig { core, settings }))
+}
+
 pub fn routes(state: Arc<AppState>) -> Router {
     Router::new()
         .route("/agent/start", post(start_agent))
         .route("/agent/resume", post(resume_agent))
         .route("/agent/restart", post(restart_agent))
         .route("/agent/update_working_dir", post(update_working_dir))
+        .route(
+            "/agent/update_workspace_roots",
+            post(update_workspace_roots),
+        )
         .route("/agent/tools", get(get_tools))
+        .route("/agent/system_prompt", get(preview_system_prompt))
+        .route("/agent/diagnose", get(diagnose))
         .route("/agent/read_resource", post(read_resource))
         .route("/agent/call_tool", post(call_tool))
         .route("/agent/list_apps", get(list_apps))
@@ -1190,6 +1532,15 @@ pub fn routes(state: Arc<AppState>) -> Router {
         .route("/agent/add_extension", post(agent_add_extension))
         .route("/agent/remove_extension", post(agent_remove_extension))
         .route("/agent/set_container", post(set_container))
+        .route("/agent/devcontainer", post(start_devcontainer))
         .route("/agent/stop", post(stop_agent))
+        .route("/agent/cores", get(list_cores))
+        .route("/agent/cores/{core}", put(update_core))
+        .route("/agent/tool_calls", get(get_running_tool_calls))
+        .route("/agent/tool_calls/cancel", post(cancel_tool_call))
+        .route(
+            "/agent/project_status",
+            get(get_project_status).delete(forget_project_status),
+        )
         .with_state(state)
 }
@@ -0,0 +1,179 @@
+use crate::routes::errors::ErrorResponse;
+use crate::state::AppState;
+use axum::extract::{Path, Query, State};
+use axum::http::{header, StatusCode};
+use axum::response::{IntoResponse, Response};
+use axum::routing::{get, post};
+use axum::{Json, Router};
+use chrono::Utc;
+use goose::artifacts::{ArtifactFilter, ArtifactKind, ArtifactRecord, GcReport};
+use serde::Deserialize;
+use std::sync::Arc;
+use utoipa::IntoParams;
+
+#[derive(Debug, Deserialize, IntoParams)]
+pub struct ListArtifactsQuery {
+    /// Only artifacts stored by this producer, such as `attachments`
+    pub producer: Option<String>,
+    pub session_id: Option<String>,
+    pub task_id: Option<String>,
+    pub kind: Option<ArtifactKind>,
+}
+
+#[utoipa::path(
+    get,
+    path = "/artifacts",
+    params(ListArtifactsQuery),
+    responses(
+        (status = 200, description = "Matching artifacts, oldest first", body = Vec<ArtifactRecord>),
+        (status = 401, description = "Unauthorized - Invalid or missing API key"),
+        (status = 500, description = "Internal server error")
+    ),
+    security(
+        ("api_key" = [])
+    ),
+    tag = "Artifacts"
+)]
+async fn list_artifacts(
+    State(state): State<Arc<AppState>>,
+    Query(query): Query<ListArtifactsQuery>,
+) -> Result<Json<Vec<ArtifactRecord>>, ErrorResponse> {
+    let filter = ArtifactFilter {
+        producer: query.producer,
+        session_id: query.session_id,
+        task_id: query.task_id,
+        kind: query.kind,
+        created_before: None,
+    };
+    let artifacts = state
+        .session_manager()
+        .artifacts()
+        .list(&filter)
+        .map_err(|err| ErrorResponse::internal(err.to_string()))?;
+    Ok(Json(artifacts))
+}
+
+#[utoipa::path(
+    get,
+    path = "/artifacts/{artifact_id}",
+    params(
+        ("artifact_id" = String, Path, description = "Artifact to describe")
+    ),
+    responses(
+        (status = 200, description = "Metadata of the artifact", body = ArtifactRecord),
+        (status = 401, description = "Unauthorized - Invalid or missing API key"),
+        (status = 404, description = "Artifact not found")
+    ),
+    security(
+        ("api_key" = [])
+    ),
+    tag = "Artifacts"
+)]
+async fn get_artifact(
+    State(state): State<Arc<AppState>>,
+    Path(artifact_id): Path<String>,
+) -> Result<Json<ArtifactRecord>, ErrorResponse> {
+    let artifact = state
+        .session_manager()
+        .artifacts()
+        .get(&artifact_id)
+        .map_err(|err| ErrorResponse::not_found(err.to_string()))?;
+    Ok(Json(artifact))
+}
+
+#[utoipa::path(
+    get,
+    path = "/artifacts/{artifact_id}/content",
+    params(
+        ("artifact_id" = String, Path, description = "Artifact to fetch")
+    ),
+    responses(
+        (status = 200, description = "Content of the artifact, with its MIME type"),
+        (status = 401, description = "Unauthorized - Invalid or missing API key"),
+        (status = 404, description = "Artifact not found")
+    ),
+    security(
+        ("api_key" = [])
+    ),
+    tag = "Artifacts"
+)]
+async fn fetch_artifact(
+    State(state): State<Arc<AppState>>,
+    Path(artifact_id): Path<String>,
+) -> Result<Response, ErrorResponse> {
+    let registry = state.session_manager().artifacts();
+    let artifact = registry
+        .get(&artifact_id)
+        .map_err(|err| ErrorResponse::not_found(err.to_string()))?;
+    let body = registry
+        .read(&artifact_id)
+        .map_err(|err| ErrorResponse::not_found(err.to_string()))?;
+    let content_type = artifact
+        .mime_type
+        .unwrap_or_else(|| "application/octet-stream".to_string());
+    Ok(([(header::CONTENT_TYPE, content_type)], body).into_response())
+}
+
+#[utoipa::path(
+    delete,
+    path = "/artifacts/{artifact_id}",
+    params(
+        ("artifact_id" = String, Path, description = "Artifact to delete")
+    ),
+    responses(
+        (status = 200, description = "Artifact deleted; its content goes once nothing else refers to it"),
+        (status = 401, description = "Unauthorized - Invalid or missing API key"),
+        (status = 404, description = "Artifact not found")
+    ),
+    security(
+        ("api_key" = [])
+    ),
+    tag = "Artifacts"
+)]
+async fn delete_artifact(
+    State(state): State<Arc<AppState>>,
+    Path(artifact_id): Path<String>,
+) -> Result<StatusCode, ErrorResponse> {
+    state
+        .session_manager()
+        .artifacts()
+        .delete(&artifact_id)
+        .map_err(|err| ErrorResponse::not_found(err.to_string()))?;
+    Ok(StatusCode::OK)
+}
+
+#[utoipa::path(
+    post,
+    path = "/artifacts/gc",
+    responses(
+        (status = 200, description = "Artifacts past their retention were deleted", body = GcReport),
+        (status = 401, description = "Unauthorized - Invalid or missing API key"),
+        (status = 500, description = "Internal server error")
+    ),
+    security(
+        ("api_key" = [])
+    ),
+    tag = "Artifacts"
+)]
+async fn collect_artifacts(
+    State(state): State<Arc<AppState>>,
+) -> Result<Json<GcReport>, ErrorResponse> {
+    let report = state
+        .session_manager()
+        .artifacts()
+        .gc(Utc::now())
+        .map_err(|err| ErrorResponse::internal(err.to_string()))?;
+    Ok(Json(report))
+}
+
+pub fn routes(state: Arc<AppState>) -> Router {
+    Router::new()
+        .route("/artifacts", get(list_artifacts))
+        .route("/artifacts/gc", post(collect_artifacts))
+        .route(
+            "/artifacts/{artifact_id}",
+            get(get_artifact).delete(delete_artifact),
+        )
+        .route("/artifacts/{artifact_id}/content", get(fetch_artifact))
+        .with_state(state)
+}
@@ -0,0 +1,316 @@
+use crate::routes::errors::ErrorResponse;
+use crate::state::AppState;
+use crate::supervisor::{ServerHeartbeat, SupervisorHeartbeat, SupervisorStatus};
+use axum::{
+    extract::{Path, Query, State},
+    http::StatusCode,
+    routing::{delete, get, post},
+    Json, Router,
+};
+use chrono::{DateTime, Duration, Utc};
+use goose::agents::file_claims::{self, ClaimOutcome, FileClaim, Negotiation, DEFAULT_LEASE_SECS};
+use goose::agents::mailbox::{self, DeadLetter, Delivery, MailboxStats, WakeReport};
+use serde::{Deserialize, Serialize};
+use std::sync::Arc;
+use utoipa::{IntoParams, ToSchema};
+
+#[derive(Debug, Deserialize, IntoParams)]
+pub struct WakeReportQuery {
+    /// Start of the period; 24 hours before `until` when omitted
+    pub since: Option<DateTime<Utc>>,
+    /// End of the period; now when omitted
+    pub until: Option<DateTime<Utc>>,
+}
+
+#[utoipa::path(
+    get,
+    path = "/bus/mailboxes",
+    responses(
+        (status = 200, description = "Pending and in-flight message counts per recipient", body = [MailboxStats])
+    )
+)]
+pub async fn list_mailboxes() -> Result<Json<Vec<MailboxStats>>, ErrorResponse> {
+    Ok(Json(mailbox::global().await?.stats().await?))
+}
+
+#[derive(Debug, Deserialize, IntoParams)]
+pub struct ReceiveQuery {
+    /// Most messages to take; defaults to 10
+    pub limit: Option<usize>,
+}
+
+#[utoipa::path(
+    post,
+    path = "/bus/mailboxes/{recipient}/receive",
+    params(("recipient" = String, Path, description = "Agent whose mailbox to read"), ReceiveQuery),
+    responses(
+        (status = 200, description = "Messages now hidden from other receivers until acknowledged or the visibility timeout passes", body = [Delivery])
+    )
+)]
+pub async fn receive_messages(
+    Path(recipient): Path<String>,
+    Query(query): Query<ReceiveQuery>,
+) -> Result<Json<Vec<Delivery>>, ErrorResponse> {
+    let limit = query.limit.unwrap_or(10).max(1);
+    Ok(Json(
+        mailbox::global().await?.receive(&recipient, limit).await?,
+    ))
+}
+
+#[utoipa::path(
+    post,
+    path = "/bus/mailboxes/{recipient}/ack/{receipt}",
+    params(
+        ("recipient" = String, Path, description = "Agent the message was delivered to"),
+        ("receipt" = String, Path, description = "Receipt from the delivery being acknowledged")
+    ),
+    responses(
+        (status = 204, description = "Message handled and removed from its mailbox"),
+        (status = 404, description = "Message already acknowledged, dead-lettered or delivered again")
+    )
+)]
+pub async fn ack_message(
+    Path((recipient, receipt)): Path<(String, String)>,
+) -> Result<StatusCode, ErrorResponse> {
+    if mailbox::global().await?.ack(&recipient, &receipt).await? {
+        Ok(StatusCode::NO_CONTENT)
+    } else {
+        Err(ErrorResponse::not_found(format!(
+            "Delivery {} not found",
+            receipt
+        )))
+    }
+}
+
+#[utoipa::path(
+    get,
+    path = "/bus/dead-letters",
+    responses(
+        (status = 200, description = "Messages that exhausted their delivery attempts", body = [DeadLetter])
+    )
+)]
+pub async fn list_dead_letters() -> Result<Json<Vec<DeadLetter>>, ErrorResponse> {
+    Ok(Json(mailbox::global().await?.dead_letters().await?))
+}
+
+#[utoipa::path(
+    post,
+    path = "/bus/dead-letters/{id}/requeue",
+    params(("id" = i64, Path, description = "Dead letter id")),
+    responses(
+        (status = 204, description = "Message moved back to its mailbox"),
+        (status = 404, description = "Dead letter not found")
+    )
+)]
+pub async fn requeue_dead_letter(Path(id): Path<i64>) -> Result<StatusCode, ErrorResponse> {
+    if mailbox::global().await?.requeue_dead_letter(id).await? {
+        Ok(StatusCode::NO_CONTENT)
+    } else {
+        Err(ErrorResponse::not_found(format!(
+            "Dead letter {} not found",
+            id
+        )))
+    }
+}
+
+#[utoipa::path(
+    delete,
+    path = "/bus/dead-letters/{id}",
+    params(("id" = i64, Path, description = "Dead letter id")),
+    responses(
+        (status = 204, description = "Dead letter discarded"),
+        (status = 404, description = "Dead letter not found")
+    )
+)]
+pub async fn purge_dead_letter(Path(id): Path<i64>) -> Result<StatusCode, ErrorResponse> {
+    if mailbox::global().await?.purge_dead_letter(id).await? {
+        Ok(StatusCode::NO_CONTENT)
+    } else {
+        Err(ErrorResponse::not_found(format!(
+            "Dead letter {} not found",
+            id
+        )))
+    }
+}
+
+#[utoipa::path(
+    get,
+    path = "/bus/wake-report",
+    params(WakeReportQuery),
+    responses(
+        (status = 200, description = "What the wake policy would have done with the messages sent in the period, recorded while GOOSE_WAKE_POLICY_MODE is simulate", body = WakeReport)
+    )
+)]
+pub async fn wake_report(
+    Query(query): Query<WakeReportQuery>,
+) -> Result<Json<WakeReport>, ErrorResponse> {
+    let until = query.until.unwrap_or_else(Utc::now);
+    let since = query.since.unwrap_or(until - Duration::hours(24));
+    Ok(Json(
+        mailbox::global().await?.wake_report(since, until).await?,
+    ))
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+pub struct ClaimsResponse {
+    pub claims: Vec<FileClaim>,
+    pub negotiations: Vec<Negotiation>,
+}
+
+#[derive(Debug, Deserialize, ToSchema)]
+pub struct ClaimRequest {
+    pub agent: String,
+    /// Files or directories the agent is about to edit
+    pub paths: Vec<String>,
+    /// Lease length; the claim lapses unless renewed. Defaults to 300
+    pub lease_secs: Option<i64>,
+}
+
+#[derive(Debug, Deserialize, ToSchema)]
+pub struct NegotiationReply {
+    /// The agent holding the contested claim
+    pub agent: String,
+    /// Hand the contested paths over, or keep them and escalate
+    #[serde(rename = "yield")]
+    pub yield_paths: bool,
+}
+
+#[utoipa::path(
+    get,
+    path = "/bus/claims",
+    responses(
+        (status = 200, description = "Live file claims and every negotiation over contested paths", body = ClaimsResponse)
+    )
+)]
+pub async fn list_claims() -> Json<ClaimsResponse> {
+    let mut registry = file_claims::registry().lock().unwrap();
+    let events = registry.expire(Utc::now());
+    let response = ClaimsResponse {
+        claims: registry.claims().to_vec(),
+        negotiations: registry.negotiations().to_vec(),
+    };
+    drop(registry);
+    if !events.is_empty() {
+        tokio::spawn(async move {
+            if let Err(e) = file_claims::publish(events).await {
+                tracing::warn!("Failed to publish expired claims: {}", e);
+            }
+        });
+    }
+    Json(response)
+}
+
+#[utoipa::path(
+    post,
+    path = "/bus/claims",
+    request_body = ClaimRequest,
+    responses(
+        (status = 200, description = "The claim, or the negotiation opened with the agent already holding some of the paths", body = ClaimOutcome),
+        (status = 400, description = "No paths given")
+    )
+)]
+pub async fn claim_files(
+    Json(request): Json<ClaimRequest>,
+) -> Result<Json<ClaimOutcome>, ErrorResponse> {
+    if request.paths.is_empty() {
+        return Err(ErrorResponse::bad_request("No paths to claim"));
+    }
+    let lease = Duration::seconds(request.lease_secs.unwrap_or(DEFAULT_LEASE_SECS).max(1));
+    Ok(Json(
+        file_claims::claim(&request.agent, request.paths, lease).await?,
+    ))
+}
+
+#[utoipa::path(
+    delete,
+    path = "/bus/claims/{id}",
+    params(("id" = u64, Path, description = "Claim id")),
+    responses(
+        (status = 204, description = "Claim released; queued requests for its paths are granted"),
+        (status = 404, description = "Claim not found")
+    )
+)]
+pub async fn release_claim(Path(id): Path<u64>) -> Result<StatusCode, ErrorResponse> {
+    if file_claims::release(id).await? {
+        Ok(StatusCode::NO_CONTENT)
+    } else {
+        Err(ErrorResponse::not_found(format!("Claim {} not found", id)))
+    }
+}
+
+#[utoipa::path(
+    post,
+    path = "/bus/negotiations/{id}/reply",
+    params(("id" = u64, Path, description = "Negotiation id")),
+    request_body = NegotiationReply,
+    responses(
+        (status = 200, description = "The negotiation after the holder's reply", body = Negotiation),
+        (status = 400, description = "Negotiation unknown, already answered or addressed to another agent")
+    )
+)]
+pub async fn reply_to_negotiation(
+    Path(id): Path<u64>,
+    Json(reply): Json<NegotiationReply>,
+) -> Result<Json<Negotiation>, ErrorResponse> {
+    file_claims::respond(id, &reply.agent, reply.yield_paths)
+        .await
+        .map(Json)
+        .map_err(|e| ErrorResponse::bad_request(e.to_string()))
+}
+
+#[utoipa::path(
+    post,
+    path = "/bus/heartbeat",
+    request_body = SupervisorHeartbeat,
+    responses(
+        (status = 200, description = "goosed's heartbeat in reply; registers the supervisor if it was new or lost", body = ServerHeartbeat)
+    )
+)]
+pub async fn supervisor_heartbeat(
+    State(state): State<Arc<AppState>>,
+    Json(heartbeat): Json<SupervisorHeartbeat>,
+) -> Json<ServerHeartbeat> {
+    let registered = state.supervisor.heartbeat(heartbeat);
+    let drain = state.drain.status();
+    Json(ServerHeartbeat {
+        instance_id: state.supervisor.instance_id().to_string(),
+        started_at: state.supervisor.started_at(),
+        registered,
+        active_turns: drain.active_turns,
+        draining: drain.draining,
+        restart_pending: state.restart.status().pending,
+    })
+}
+
+#[utoipa::path(
+    get,
+    path = "/bus/heartbeat",
+    responses(
+        (status = 200, description = "Supervisor heartbeat tracking and whether goosed is in safe mode", body = SupervisorStatus)
+    )
+)]
+pub async fn supervisor_status(State(state): State<Arc<AppState>>) -> Json<SupervisorStatus> {
+    Json(state.supervisor.status())
+}
+
+pub fn routes(state: Arc<AppState>) -> Router {
+    Router::new()
+        .route("/bus/mailboxes", get(list_mailboxes))
+        .route("/bus/mailboxes/{recipient}/receive", post(receive_messages))
+        .route(
+            "/bus/mailboxes/{recipient}/ack/{receipt}",
+            post(ack_message),
+        )
+        .route(
+            "/bus/heartbeat",
+            get(supervisor_status).post(supervisor_heartbeat),
+        )
+        .route("/bus/dead-letters", get(list_dead_letters))
+        .route("/bus/dead-letters/{id}", delete(purge_dead_letter))
+        .route("/bus/dead-letters/{id}/requeue", post(requeue_dead_letter))
+        .route("/bus/wake-report", get(wake_report))
+        .route("/bus/claims", get(list_claims).post(claim_files))
+        .route("/bus/claims/{id}", delete(release_claim))
+        .route("/bus/negotiations/{id}/reply", post(reply_to_negotiation))
+        .with_state(state)
+}
@@ -0,0 +1,87 @@
+use crate::routes::errors::ErrorResponse;
+use crate::state::AppState;
+use axum::extract::{Query, State};
+use axum::routing::{get, post};
+use axum::{Json, Router};
+use chrono::{DateTime, Duration, Utc};
+use goose::calendar::{self, Availability};
+use serde::{Deserialize, Serialize};
+use std::sync::Arc;
+use utoipa::{IntoParams, ToSchema};
+
+const DEFAULT_WINDOW_MINUTES: i64 = 30;
+
+#[derive(Debug, Deserialize, IntoParams)]
+pub struct AvailabilityQuery {
+    /// Length of the window to look for, in minutes (default 30)
+    pub minutes: Option<i64>,
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+pub struct CalendarAvailability {
+    /// Whether any calendar is configured
+    pub configured: bool,
+    pub busy_now: bool,
+    /// Earliest start of a window of the requested length clear of meetings
+    pub next_safe_window: DateTime<Utc>,
+    /// Busy blocks for the coming week; absent when unknown or stale
+    pub availability: Option<Availability>,
+}
+
+fn describe(minutes: Option<i64>) -> CalendarAvailability {
+    let now = Utc::now();
+    let length = Duration::minutes(minutes.unwrap_or(DEFAULT_WINDOW_MINUTES).max(1));
+    CalendarAvailability {
+        configured: !calendar::calendars().is_empty(),
+        busy_now: calendar::is_busy(now),
+        next_safe_window: calendar::next_safe_window(now, length),
+        availability: calendar::snapshot(),
+    }
+}
+
+#[utoipa::path(
+    get,
+    path = "/calendar/availability",
+    params(AvailabilityQuery),
+    responses(
+        (status = 200, description = "Availability from the last calendar refresh", body = CalendarAvailability),
+        (status = 401, description = "Unauthorized - Invalid or missing API key")
+    ),
+    security(
+        ("api_key" = [])
+    ),
+    tag = "Calendar"
+)]
+async fn get_availability(Query(query): Query<AvailabilityQuery>) -> Json<CalendarAvailability> {
+    Json(describe(query.minutes))
+}
+
+#[utoipa::path(
+    post,
+    path = "/calendar/refresh",
+    params(AvailabilityQuery),
+    responses(
+        (status = 200, description = "Availability after reading the calendars again", body = CalendarAvailability),
+        (status = 401, description = "Unauthorized - Invalid or missing API key"),
+        (status = 500, description = "No calendar could be read")
+    ),
+    security(
+        ("api_key" = [])
+    ),
+    tag = "Calendar"
+)]
+async fn refresh_availability(
+    Query(query): Query<AvailabilityQuery>,
+) -> Result<Json<CalendarAvailability>, ErrorResponse> {
+    calendar::refresh()
+        .await
+        .map_err(|err| ErrorResponse::internal(err.to_string()))?;
+    Ok(Json(describe(query.minutes)))
+}
+
+pub fn routes(state: Arc<AppState>) -> Router {
+    Router::new()
+        .route("/calendar/availability", get(get_availability))
+        .route("/calendar/refresh", post(refresh_availability))
+        .with_state(state)
+}
@@ -3,20 +3,23 @@ use crate::routes::utils::check_provider_configured;
 use crate::state::AppState;
 use axum::routing::put;
 use axum::{
-    extract::Path,
+    extract::{Path, Query, State},
     routing::{delete, get, post},
     Json, Router,
 };
 use goose::config::declarative_providers::LoadedProvider;
+use goose::config::layers::find_workspace_config;
 use goose::config::paths::Paths;
+use goose::config::profiles::{self, SettingsProfile};
 use goose::config::ExtensionEntry;
-use goose::config::{Config, ConfigError};
+use goose::config::{Config, ConfigError, ConfigSource, EffectiveValue};
 use goose::model::ModelConfig;
 use goose::providers::auto_detect::detect_provider_from_api_key;
 use goose::providers::base::{ProviderMetadata, ProviderType};
 use goose::providers::canonical::maybe_get_canonical_model;
 use goose::providers::create_with_default_model;
 use goose::providers::providers as get_providers;
+use goose::providers::task_routing::{self, ModelRoutes};
 use goose::{
     agents::execute_commands, agents::ExtensionConfig, config::permission::PermissionLevel,
     slash_commands,
@@ -24,8 +27,8 @@ use goose::{
 use serde::{Deserialize, Serialize};
 use serde_json::Value;
 use serde_yaml;
-use std::{collections::HashMap, sync::Arc};
-use utoipa::ToSchema;
+use std::{collections::HashMap, path::PathBuf, sync::Arc};
+use utoipa::{IntoParams, ToSchema};
 
 #[derive(Serialize, ToSchema)]
 pub struct ExtensionResponse {
@@ -59,6 +62,71 @@ pub struct ConfigResponse {
     pub config: HashMap<String, Value>,
 }
 
+#[derive(Serialize, ToSchema)]
+pub struct EffectiveConfigResponse {
+    pub values: Vec<EffectiveValue>,
+    pub user_config_path: String,
+    pub workspace_config_path: Option<String>,
+    /// Whether the workspace config has been trusted; untrusted files are
+    /// not part of `values`
+    pub workspace_trusted: bool,
+}
+
+#[derive(Deserialize, ToSchema)]
+pub struct SetEffectiveConfigRequest {
+    pub key: String,
+    pub value: Value,
+    /// Layer to write to, either `user` or `workspace`; defaults to `user`
+    #[serde(default)]
+    pub layer: Option<ConfigSource>,
+    /// Session whose working directory holds the workspace layer; the
+    /// server's directory when omitted
+    #[serde(default)]
+    pub session_id: Option<String>,
+}
+
+#[derive(Deserialize, ToSchema)]
+pub struct TrustWorkspaceRequest {
+    /// Session whose working directory holds the workspace layer; the
+    /// server's directory when omitted
+    #[serde(default)]
+    pub session_id: Option<String>,
+}
+
+#[derive(Deserialize, IntoParams)]
+pub struct EffectiveConfigQuery {
+    /// Session whose working directory holds the workspace layer; the
+    /// server's directory when omitted
+    pub session_id: Option<String>,
+}
+
+/// The working directory of `session_id`, if given.
+async fn session_workspace(
+    state: &AppState,
+    session_id: Option<&str>,
+) -> Result<Option<PathBuf>, ErrorResponse> {
+    let Some(session_id) = session_id else {
+        return Ok(None);
+    };
+    let session = state
+        .session_manager()
+        .get_session(session_id, false)
+        .await
+        .map_err(|_| ErrorResponse::not_found(format!("Session {} not found", session_id)))?;
+    Ok(Some(session.working_dir))
+}
+
+/// Effective values with the workspace layer taken from `workspace`, or the
+/// server's directory.
+fn effective_values(
+    workspace: Option<&std::path::Path>,
+) -> Result<std::collections::BTreeMap<String, EffectiveValue>, ConfigError> {
+    match workspace {
+        Some(dir) => Config::global().effective_values_in(Some(dir)),
+        None => Config::global().effective_values(),
+    }
+}
+
 #[derive(Debug, Serialize, Deserialize, ToSchema)]
 pub struct ProviderDetails {
     pub name: String,
@@ -128,6 +196,7 @@ pub enum ConfigValueResponse {
 pub enum CommandType {
     Builtin,
     Recipe,
+    Custom,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
@@ -330,6 +399,181 @@ pub async fn read_all_config() -> Result<Json<ConfigResponse>, ErrorResponse> {
     Ok(Json(ConfigResponse { config: values }))
 }
 
+#[utoipa::path(
+    get,
+    path = "/config/effective",
+    params(EffectiveConfigQuery),
+    responses(
+        (status = 200, description = "Merged configuration with the layer each value came from", body = EffectiveConfigResponse),
+        (status = 404, description = "Session not found"),
+        (status = 422, description = "Configuration could not be read")
+    )
+)]
+pub async fn read_effective_config(
+    State(state): State<Arc<AppState>>,
+    Query(query): Query<EffectiveConfigQuery>,
+) -> Result<Json<EffectiveConfigResponse>, ErrorResponse> {
+    let config = Config::global();
+    let workspace = session_workspace(&state, query.session_id.as_deref()).await?;
+    let values = effective_values(workspace.as_deref())
+        .map_err(|e| ErrorResponse::unprocessable(e.to_string()))?;
+    let workspace_config_path = match &workspace {
+        Some(dir) => find_workspace_config(dir),
+        None => config.workspace_config_path(),
+    };
+    let workspace_trusted = match &workspace_config_path {
+        Some(path) => config.is_workspace_trusted(path)?,
+        None => false,
+    };
+    Ok(Json(EffectiveConfigResponse {
+        values: values.into_values().collect(),
+        user_config_path: config.path(),
+        workspace_config_path: workspace_config_path.map(|path| path.to_string_lossy().to_string()),
+        workspace_trusted,
+    }))
+}
+
+#[utoipa::path(
+    post,
+    path = "/config/workspace/trust",
+    request_body = TrustWorkspaceRequest,
+    responses(
+        (status = 200, description = "Workspace config trusted; returns the resulting configuration", body = EffectiveConfigResponse),
+        (status = 404, description = "Session or workspace config not found"),
+        (status = 500, description = "Internal server error")
+    )
+)]
+pub async fn trust_workspace_config(
+    State(state): State<Arc<AppState>>,
+    Json(request): Json<TrustWorkspaceRequest>,
+) -> Result<Json<EffectiveConfigResponse>, ErrorResponse> {
+    let config = Config::global();
+    let workspace = session_workspace(&state, request.session_id.as_deref()).await?;
+    let path = match &workspace {
+        Some(dir) => find_workspace_config(dir),
+        None => config.workspace_config_path(),
+    }
+    .ok_or_else(|| ErrorResponse::not_found("No workspace config found"))?;
+    config.trust_workspace(&path)?;
+
+    read_effective_config(
+        State(state),
+        Query(EffectiveConfigQuery {
+            session_id: request.session_id,
+        }),
+    )
+    .await
+}
+
+#[utoipa::path(
+    put,
+    path = "/config/effective",
+    request_body = SetEffectiveConfigRequest,
+    responses(
+        (status = 200, description = "Value written; returns the resulting effective value", body = EffectiveValue),
+        (status = 400, description = "Layer cannot be written to"),
+        (status = 404, description = "Session not found"),
+        (status = 500, description = "Internal server error")
+    )
+)]
+pub async fn set_effective_config(
+    State(state): State<Arc<AppState>>,
+    Json(request): Json<SetEffectiveConfigRequest>,
+) -> Result<Json<EffectiveValue>, ErrorResponse> {
+    let config = Config::global();
+    let workspace = session_workspace(&state, request.session_id.as_deref()).await?;
+    match request.layer.unwrap_or(ConfigSource::User) {
+        ConfigSource::User => config.set_param(&request.key, &request.value)?,
+        ConfigSource::Workspace => match &workspace {
+            Some(dir) => config.set_workspace_param_in(&request.key, &request.value, dir)?,
+            None => config.set_workspace_param(&request.key, &request.value)?,
+        },
+        layer => {
+            return Err(ErrorResponse::bad_request(format!(
+                "Cannot write to the {:?} configuration layer",
+                layer
+            )))
+        }
+    }
+
+    effective_values(workspace.as_deref())?
+        .remove(&request.key)
+        .map(Json)
+        .ok_or_else(|| ErrorResponse::internal("Value was written but could not be read back"))
+}
+
+#[utoipa::path(
+    get,
+    path = "/config/profiles",
+    responses(
+        (status = 200, description = "Built-in and custom settings profiles", body = [SettingsProfile])
+    )
+)]
+pub async fn list_settings_profiles() -> Json<Vec<SettingsProfile>> {
+    Json(profiles::list_profiles(Config::global()))
+}
+
+#[utoipa::path(
+    put,
+    path = "/config/profiles/{name}",
+    params(
+        ("name" = String, Path, description = "Profile name")
+    ),
+    request_body = SettingsProfile,
+    responses(
+        (status = 200, description = "Profile saved", body = SettingsProfile),
+        (status = 400, description = "Invalid profile"),
+        (status = 500, description = "Internal server error")
+    )
+)]
+pub async fn save_settings_profile(
+    Path(name): Path<String>,
+    Json(mut profile): Json<SettingsProfile>,
+) -> Result<Json<SettingsProfile>, ErrorResponse> {
+    let name = name.trim();
+    if name.is_empty() || name.contains(char::is_whitespace) {
+        return Err(ErrorResponse::bad_request(
+            "Profile name must be a single non-empty word",
+        ));
+    }
+    if profile
+        .budget_usd
+        .is_some_and(|b| !b.is_finite() || b < 0.0)
+    {
+        return Err(ErrorResponse::bad_request(
+            "Budget must be a positive amount",
+        ));
+    }
+
+    profile.name = name.to_string();
+    profiles::save_profile(Config::global(), profile.clone())?;
+    Ok(Json(profile))
+}
+
+#[utoipa::path(
+    delete,
+    path = "/config/profiles/{name}",
+    params(
+        ("name" = String, Path, description = "Profile name")
+    ),
+    responses(
+        (status = 200, description = "Custom profile removed, or built-in profile reset to defaults", body = String),
+        (status = 404, description = "No stored profile with that name"),
+        (status = 500, description = "Internal server error")
+    )
+)]
+pub async fn delete_settings_profile(
+    Path(name): Path<String>,
+) -> Result<Json<String>, ErrorResponse> {
+    if !profiles::delete_profile(Config::global(), &name)? {
+        return Err(ErrorResponse::not_found(format!(
+            "No stored profile named {}",
+            name
+        )));
+    }
+    Ok(Json(format!("Removed profile {}", name)))
+}
+
 #[utoipa::path(
     get,
     path = "/config/providers",
@@ -416,9 +660,13 @@ pub async fn get_slash_commands() -> Result<Json<SlashCommandsResponse>, ErrorRe
 
     for cmd_def in execute_commands::list_commands() {
         commands.push(SlashCommand {
-            command: cmd_def.name.to_string(),
-            help: cmd_def.description.to_string(),
-            command_type: CommandType::Builtin,
+            command_type: if cmd_def.is_custom() {
+                CommandType::Custom
+            } else {
+                CommandType::Builtin
+            },
+            command: cmd_def.name,
+            help: cmd_def.description,
         });
     }
 
@@ -488,6 +736,17 @@ pub async fn get_pricing(
     }))
 }
 
+#[utoipa::path(
+    get,
+    path = "/config/model-routes",
+    responses(
+        (status = 200, description = "Model routing table and the calls and spend of each route", body = ModelRoutes)
+    )
+)]
+pub async fn get_model_routes() -> Json<ModelRoutes> {
+    Json(task_routing::global().status())
+}
+
 #[utoipa::path(
     post,
     path = "/config/init",
@@ -832,17 +1091,28 @@ pub async fn configure_provider_oauth(
 pub fn routes(state: Arc<AppState>) -> Router {
     Router::new()
         .route("/config", get(read_all_config))
+        .route(
+            "/config/effective",
+            get(read_effective_config).put(set_effective_config),
+        )
+        .route("/config/workspace/trust", post(trust_workspace_config))
         .route("/config/upsert", post(upsert_config))
         .route("/config/remove", post(remove_config))
         .route("/config/read", post(read_config))
         .route("/config/extensions", get(get_extensions))
         .route("/config/extensions", post(add_extension))
         .route("/config/extensions/{name}", delete(remove_extension))
+        .route("/config/profiles", get(list_settings_profiles))
+        .route(
+            "/config/profiles/{name}",
+            put(save_settings_profile).delete(delete_settings_profile),
+        )
         .route("/config/providers", get(providers))
         .route("/config/providers/{name}/models", get(get_provider_models))
         .route("/config/detect-provider", post(detect_provider))
         .route("/config/slash_commands", get(get_slash_commands))
         .route("/config/pricing", post(get_pricing))
+        .route("/config/model-routes", get(get_model_routes))
         .route("/config/init", post(init_config))
         .route("/config/backup", post(backup_config))
         .route("/config/recover", post(recover_config))
@@ -75,44 +75,29 @@ fn validate_audio(audio: &str, mime_type: &str) -> Result<(Vec<u8>, &'static str
         "audio/m4a" => "m4a",
         "audio/wav" | "audio/x-wav" => "wav",
         _ => {
-            return Err(ErrorResponse {
-                message: format!("Unsupported audio format: {}", mime_type),
-                status: StatusCode::UNSUPPORTED_MEDIA_TYPE,
-            })
+            return Err(ErrorResponse::new(
+                StatusCode::UNSUPPORTED_MEDIA_TYPE,
+                format!("Unsupported audio format: {}", mime_type),
+            ))
         }
     };
 
     Ok((audio_bytes, extension))
 }
 
-fn convert_error(e: anyhow::Error) -> ErrorResponse {
+pub(crate) fn convert_error(e: anyhow::Error) -> ErrorResponse {
     let error_msg = e.to_string();
 
     if error_msg.contains("Invalid API key") {
-        ErrorResponse {
-            message: error_msg,
-            status: StatusCode::UNAUTHORIZED,
-        }
+        ErrorResponse::new(StatusCode::UNAUTHORIZED, error_msg)
     } else if error_msg.contains("Rate limit exceeded") || error_msg.contains("quota") {
-        ErrorResponse {
-            message: error_msg,
-            status: StatusCode::TOO_MANY_REQUESTS,
-        }
+        ErrorResponse::new(StatusCode::TOO_MANY_REQUESTS, error_msg)
     } else if error_msg.contains("not configured") {
-        ErrorResponse {
-            message: error_msg,
-            status: StatusCode::PRECONDITION_FAILED,
-        }
+        ErrorResponse::new(StatusCode::PRECONDITION_FAILED, error_msg)
     } else if error_msg.contains("timeout") {
-        ErrorResponse {
-            message: error_msg,
-            status: StatusCode::GATEWAY_TIMEOUT,
-        }
+        ErrorResponse::new(StatusCode::GATEWAY_TIMEOUT, error_msg)
     } else if error_msg.contains("API error") {
-        ErrorResponse {
-            message: error_msg,
-            status: StatusCode::BAD_GATEWAY,
-        }
+        ErrorResponse::new(StatusCode::BAD_GATEWAY, error_msg)
     } else {
         ErrorResponse::internal(error_msg)
     }
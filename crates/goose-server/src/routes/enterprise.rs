@@ -0,0 +1,397 @@
+use crate::routes::errors::{ErrorCode, ErrorResponse};
+use crate::state::AppState;
+use axum::{
+    extract::{Path, Query, State},
+    http::{header, StatusCode},
+    response::{IntoResponse, Response},
+    routing::{delete, get, post, put},
+    Json, Router,
+};
+use chrono::{DateTime, Utc};
+use goose::observability::forecast::{project_month, SpendForecast};
+use goose::observability::team_accounting::{
+    approve_team_overage, build_chargeback_report, cache_team_usage, cached_team_usage,
+    check_quota, invalidate_team_usage, load_team_quotas, save_team_quota, team_usage,
+    usage_records, ChargebackGroupBy, ChargebackQuery, QuotaDecision, SessionUsageRecord,
+    TeamAssignment, TeamQuota, TeamUsage,
+};
+use goose::observability::{CostTracker, ReportFormat};
+use goose::providers::key_pool::{self, KeySelection, KeyStatus, NewProviderKey, ProviderKey};
+use goose::session::ExtensionState;
+use serde::{Deserialize, Serialize};
+use std::sync::Arc;
+use utoipa::{IntoParams, ToSchema};
+
+#[derive(Serialize, ToSchema)]
+pub struct TeamQuotaStatus {
+    team: String,
+    quota: Option<TeamQuota>,
+    usage: TeamUsage,
+    decision: QuotaDecision,
+}
+
+#[derive(Deserialize, IntoParams, ToSchema)]
+pub struct ChargebackParams {
+    #[serde(default)]
+    group_by: ChargebackGroupBy,
+    start: Option<DateTime<Utc>>,
+    end: Option<DateTime<Utc>>,
+    team: Option<String>,
+    format: Option<ReportFormat>,
+}
+
+async fn load_usage_records(state: &AppState) -> Result<Vec<SessionUsageRecord>, ErrorResponse> {
+    let sessions = state
+        .session_manager()
+        .list_sessions()
+        .await
+        .map_err(|e| ErrorResponse::internal(format!("Failed to list sessions: {}", e)))?;
+    Ok(usage_records(&sessions, &CostTracker::new()))
+}
+
+/// Rejects the request when the session's team is over its monthly quota,
+/// and lets the session use its team's and user's provider keys.
+pub(crate) async fn enforce_team_quota(
+    state: &AppState,
+    session_id: &str,
+) -> Result<(), ErrorResponse> {
+    let quotas = load_team_quotas();
+    if quotas.is_empty() && key_pool::is_empty() {
+        return Ok(());
+    }
+
+    let Ok(session) = state.session_manager().get_session(session_id, false).await else {
+        return Ok(());
+    };
+    let Some(assignment) = TeamAssignment::from_extension_data(&session.extension_data) else {
+        return Ok(());
+    };
+    key_pool::assign_session(
+        session_id,
+        Some(&assignment.team),
+        assignment.user.as_deref(),
+    );
+    let Some(quota) = quotas.get(&assignment.team) else {
+        return Ok(());
+    };
+
+    let now = Utc::now();
+    let usage = match cached_team_usage(&assignment.team, now) {
+        Some(usage) => usage,
+        None => cache_team_usage(&load_usage_records(state).await?, &assignment.team, now),
+    };
+    match check_quota(quota, &usage, now) {
+        QuotaDecision::Allowed => Ok(()),
+        QuotaDecision::Rejected { reason } => Err(ErrorResponse::from_code(
+            ErrorCode::QuotaExceeded,
+            format!("Monthly quota exceeded: {}", reason),
+        )),
+        QuotaDecision::RequiresApproval { reason } => Err(ErrorResponse::new(
+            StatusCode::FORBIDDEN,
+            format!("Monthly quota exceeded, approval required: {}", reason),
+        )
+        .with_code(ErrorCode::QuotaExceeded)),
+    }
+}
+
+#[utoipa::path(
+    put,
+    path = "/enterprise/sessions/{session_id}/team",
+    params(
+        ("session_id" = String, Path, description = "Session to assign")
+    ),
+    request_body = TeamAssignment,
+    responses(
+        (status = 200, description = "Session assigned to team", body = TeamAssignment),
+        (status = 404, description = "Session not found"),
+        (status = 500, description = "Internal server error")
+    ),
+    tag = "Enterprise"
+)]
+async fn assign_session_team(
+    State(state): State<Arc<AppState>>,
+    Path(session_id): Path<String>,
+    Json(assignment): Json<TeamAssignment>,
+) -> Result<Json<TeamAssignment>, ErrorResponse> {
+    if assignment.team.trim().is_empty() {
+        return Err(ErrorResponse::bad_request("Team name cannot be empty"));
+    }
+
+    let session = state
+        .session_manager()
+        .get_session(&session_id, false)
+        .await
+        .map_err(|_| ErrorResponse::not_found(format!("Session not found: {}", session_id)))?;
+
+    let mut extension_data = session.extension_data;
+    assignment.to_extension_data(&mut extension_data)?;
+    state
+        .session_manager()
+        .update(&session_id)
+        .extension_data(extension_data)
+        .apply()
+        .await?;
+    invalidate_team_usage();
+    key_pool::assign_session(
+        &session_id,
+        Some(&assignment.team),
+        assignment.user.as_deref(),
+    );
+
+    Ok(Json(assignment))
+}
+
+#[utoipa::path(
+    get,
+    path = "/enterprise/teams/{team}/quota",
+    params(
+        ("team" = String, Path, description = "Team name")
+    ),
+    responses(
+        (status = 200, description = "Quota and current month usage", body = TeamQuotaStatus),
+        (status = 500, description = "Internal server error")
+    ),
+    tag = "Enterprise"
+)]
+async fn get_team_quota(
+    State(state): State<Arc<AppState>>,
+    Path(team): Path<String>,
+) -> Result<Json<TeamQuotaStatus>, ErrorResponse> {
+    let quota = load_team_quotas().remove(&team);
+    let records = load_usage_records(&state).await?;
+    let now = Utc::now();
+    let usage = team_usage(&records, &team, now);
+    let decision = quota
+        .as_ref()
+        .map(|q| check_quota(q, &usage, now))
+        .unwrap_or(QuotaDecision::Allowed);
+
+    Ok(Json(TeamQuotaStatus {
+        team,
+        quota,
+        usage,
+        decision,
+    }))
+}
+
+#[utoipa::path(
+    put,
+    path = "/enterprise/teams/{team}/quota",
+    params(
+        ("team" = String, Path, description = "Team name")
+    ),
+    request_body = TeamQuota,
+    responses(
+        (status = 200, description = "Quota saved", body = TeamQuota),
+        (status = 500, description = "Internal server error")
+    ),
+    tag = "Enterprise"
+)]
+async fn set_team_quota(
+    Path(team): Path<String>,
+    Json(quota): Json<TeamQuota>,
+) -> Result<Json<TeamQuota>, ErrorResponse> {
+    save_team_quota(&team, quota.clone()).map_err(|e| ErrorResponse::internal(e.to_string()))?;
+    Ok(Json(quota))
+}
+
+#[utoipa::path(
+    post,
+    path = "/enterprise/teams/{team}/approve",
+    params(
+        ("team" = String, Path, description = "Team name")
+    ),
+    responses(
+        (status = 200, description = "Overage approved until the end of the month", body = TeamQuota),
+        (status = 404, description = "No quota configured for team")
+    ),
+    tag = "Enterprise"
+)]
+async fn approve_team_quota(Path(team): Path<String>) -> Result<Json<TeamQuota>, ErrorResponse> {
+    approve_team_overage(&team, Utc::now())
+        .map(Json)
+        .map_err(|e| ErrorResponse::not_found(e.to_string()))
+}
+
+#[utoipa::path(
+    get,
+    path = "/enterprise/chargeback",
+    params(ChargebackParams),
+    responses(
+        (status = 200, description = "Chargeback report as JSON, CSV or Markdown"),
+        (status = 500, description = "Internal server error")
+    ),
+    tag = "Enterprise"
+)]
+async fn chargeback_report(
+    State(state): State<Arc<AppState>>,
+    Query(params): Query<ChargebackParams>,
+) -> Result<Response, ErrorResponse> {
+    let records = load_usage_records(&state).await?;
+    let report = build_chargeback_report(
+        &records,
+        &ChargebackQuery {
+            group_by: params.group_by,
+            start: params.start,
+            end: params.end,
+            team: params.team,
+        },
+    );
+
+    let format = params.format.unwrap_or(ReportFormat::Json);
+    let body = report
+        .export(format)
+        .map_err(|e| ErrorResponse::internal(e.to_string()))?;
+    let content_type = match format {
+        ReportFormat::Json => "application/json",
+        ReportFormat::Csv => "text/csv",
+        ReportFormat::Markdown => "text/markdown",
+    };
+
+    Ok(([(header::CONTENT_TYPE, content_type)], body).into_response())
+}
+
+#[derive(Deserialize, IntoParams, ToSchema)]
+pub struct ForecastParams {
+    #[serde(default)]
+    group_by: ChargebackGroupBy,
+}
+
+#[utoipa::path(
+    get,
+    path = "/enterprise/forecast",
+    params(ForecastParams),
+    responses(
+        (status = 200, description = "Month-end spend projected from the trailing daily burn rate", body = SpendForecast),
+        (status = 500, description = "Internal server error")
+    ),
+    tag = "Enterprise"
+)]
+async fn spend_forecast(
+    State(state): State<Arc<AppState>>,
+    Query(params): Query<ForecastParams>,
+) -> Result<Json<SpendForecast>, ErrorResponse> {
+    let records = load_usage_records(&state).await?;
+    Ok(Json(project_month(
+        &records,
+        params.group_by,
+        &load_team_quotas(),
+        Utc::now(),
+    )))
+}
+
+#[derive(Serialize, Deserialize, ToSchema)]
+pub struct ProviderKeySelection {
+    selection: KeySelection,
+}
+
+#[utoipa::path(
+    get,
+    path = "/enterprise/provider_keys",
+    responses(
+        (status = 200, description = "Registered provider keys and their usage", body = Vec<KeyStatus>)
+    ),
+    tag = "Enterprise"
+)]
+async fn list_provider_keys() -> Json<Vec<KeyStatus>> {
+    Json(key_pool::statuses())
+}
+
+#[utoipa::path(
+    post,
+    path = "/enterprise/provider_keys",
+    request_body = NewProviderKey,
+    responses(
+        (status = 200, description = "Key registered", body = ProviderKey),
+        (status = 400, description = "Provider or key missing"),
+        (status = 500, description = "Internal server error")
+    ),
+    tag = "Enterprise"
+)]
+async fn register_provider_key(
+    Json(new): Json<NewProviderKey>,
+) -> Result<Json<ProviderKey>, ErrorResponse> {
+    if new.provider.trim().is_empty() || new.key.trim().is_empty() {
+        return Err(ErrorResponse::bad_request("Provider and key are required"));
+    }
+    key_pool::register(new)
+        .map(Json)
+        .map_err(|e| ErrorResponse::internal(format!("Failed to register key: {}", e)))
+}
+
+#[utoipa::path(
+    delete,
+    path = "/enterprise/provider_keys/{key_id}",
+    params(
+        ("key_id" = String, Path, description = "Key to remove")
+    ),
+    responses(
+        (status = 204, description = "Key removed"),
+        (status = 404, description = "Key not found"),
+        (status = 500, description = "Internal server error")
+    ),
+    tag = "Enterprise"
+)]
+async fn remove_provider_key(Path(key_id): Path<String>) -> Result<StatusCode, ErrorResponse> {
+    match key_pool::remove(&key_id) {
+        Ok(true) => Ok(StatusCode::NO_CONTENT),
+        Ok(false) => Err(ErrorResponse::not_found(format!(
+            "Provider key not found: {}",
+            key_id
+        ))),
+        Err(e) => Err(ErrorResponse::internal(format!(
+            "Failed to remove key: {}",
+            e
+        ))),
+    }
+}
+
+#[utoipa::path(
+    put,
+    path = "/enterprise/providers/{provider}/key_selection",
+    params(
+        ("provider" = String, Path, description = "Provider name")
+    ),
+    request_body = ProviderKeySelection,
+    responses(
+        (status = 200, description = "Selection saved", body = ProviderKeySelection),
+        (status = 500, description = "Internal server error")
+    ),
+    tag = "Enterprise"
+)]
+async fn set_provider_key_selection(
+    Path(provider): Path<String>,
+    Json(request): Json<ProviderKeySelection>,
+) -> Result<Json<ProviderKeySelection>, ErrorResponse> {
+    key_pool::set_selection(&provider, request.selection)
+        .map_err(|e| ErrorResponse::internal(e.to_string()))?;
+    Ok(Json(request))
+}
+
+pub fn routes(state: Arc<AppState>) -> Router {
+    Router::new()
+        .route(
+            "/enterprise/sessions/{session_id}/team",
+            put(assign_session_team),
+        )
+        .route(
+            "/enterprise/teams/{team}/quota",
+            get(get_team_quota).put(set_team_quota),
+        )
+        .route("/enterprise/teams/{team}/approve", post(approve_team_quota))
+        .route("/enterprise/chargeback", get(chargeback_report))
+        .route("/enterprise/forecast", get(spend_forecast))
+        .route(
+            "/enterprise/provider_keys",
+            get(list_provider_keys).post(register_provider_key),
+        )
+        .route(
+            "/enterprise/provider_keys/{key_id}",
+            delete(remove_provider_key),
+        )
+        .route(
+            "/enterprise/providers/{provider}/key_selection",
+            put(set_provider_key_selection),
+        )
+        .with_state(state)
+}
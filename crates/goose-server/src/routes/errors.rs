@@ -5,67 +5,264 @@ use axum::{
 };
 use goose::config::ConfigError;
 use goose::model::ConfigError as ModelConfigError;
-use goose::providers::errors::ProviderError;
+use goose::providers::errors::{ProviderError, ProviderErrorKind};
+use goose::recipe::registry::RegistryError;
 use serde::Serialize;
 use utoipa::ToSchema;
 
+/// Broad class of an error, for clients that only need coarse handling.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum ErrorCategory {
+    /// The request was invalid; fix it before retrying
+    Client,
+    Auth,
+    NotFound,
+    Conflict,
+    RateLimit,
+    /// The LLM provider failed or rejected the request
+    Provider,
+    /// goosed cannot do this right now
+    Unavailable,
+    Internal,
+}
+
+/// Stable, machine-readable error codes. New codes may be added; existing
+/// codes keep their meaning.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum ErrorCode {
+    BadRequest,
+    Unauthorized,
+    Forbidden,
+    NotFound,
+    Conflict,
+    PreconditionFailed,
+    Unprocessable,
+    RateLimited,
+    Internal,
+    BadGateway,
+    ServiceUnavailable,
+    Timeout,
+    ConfigNotFound,
+    InvalidConfig,
+    SessionNotFound,
+    InvalidRecipe,
+    QuotaExceeded,
+    EmergencyStopActive,
+    ServerDraining,
+    IdempotencyKeyReused,
+    RequestInProgress,
+    ProviderAuthFailed,
+    ProviderRateLimited,
+    ProviderContextLengthExceeded,
+    ProviderRequestFailed,
+    ProviderUnavailable,
+    ProviderUnsupported,
+}
+
+impl ErrorCode {
+    pub fn category(self) -> ErrorCategory {
+        match self {
+            Self::BadRequest
+            | Self::PreconditionFailed
+            | Self::Unprocessable
+            | Self::InvalidConfig
+            | Self::InvalidRecipe
+            | Self::IdempotencyKeyReused => ErrorCategory::Client,
+            Self::Unauthorized | Self::Forbidden => ErrorCategory::Auth,
+            Self::NotFound | Self::ConfigNotFound | Self::SessionNotFound => {
+                ErrorCategory::NotFound
+            }
+            Self::Conflict | Self::RequestInProgress => ErrorCategory::Conflict,
+            Self::RateLimited | Self::QuotaExceeded => ErrorCategory::RateLimit,
+            Self::ProviderAuthFailed
+            | Self::ProviderRateLimited
+            | Self::ProviderContextLengthExceeded
+            | Self::ProviderRequestFailed
+            | Self::ProviderUnavailable
+            | Self::ProviderUnsupported => ErrorCategory::Provider,
+            Self::BadGateway
+            | Self::ServiceUnavailable
+            | Self::Timeout
+            | Self::EmergencyStopActive
+            | Self::ServerDraining => ErrorCategory::Unavailable,
+            Self::Internal => ErrorCategory::Internal,
+        }
+    }
+
+    /// Whether repeating the same request later may succeed.
+    pub fn retryable(self) -> bool {
+        matches!(
+            self,
+            Self::RateLimited
+                | Self::RequestInProgress
+                | Self::BadGateway
+                | Self::ServiceUnavailable
+                | Self::Timeout
+                | Self::ServerDraining
+                | Self::ProviderRateLimited
+                | Self::ProviderUnavailable
+        )
+    }
+
+    pub fn status(self) -> StatusCode {
+        match self {
+            Self::BadRequest
+            | Self::InvalidRecipe
+            | Self::ProviderAuthFailed
+            | Self::ProviderContextLengthExceeded
+            | Self::ProviderUnsupported => StatusCode::BAD_REQUEST,
+            Self::Unauthorized => StatusCode::UNAUTHORIZED,
+            Self::Forbidden => StatusCode::FORBIDDEN,
+            Self::NotFound | Self::ConfigNotFound | Self::SessionNotFound => StatusCode::NOT_FOUND,
+            Self::Conflict | Self::RequestInProgress => StatusCode::CONFLICT,
+            Self::PreconditionFailed => StatusCode::PRECONDITION_FAILED,
+            Self::Unprocessable | Self::InvalidConfig | Self::IdempotencyKeyReused => {
+                StatusCode::UNPROCESSABLE_ENTITY
+            }
+            Self::RateLimited | Self::QuotaExceeded | Self::ProviderRateLimited => {
+                StatusCode::TOO_MANY_REQUESTS
+            }
+            Self::Internal | Self::ProviderRequestFailed => StatusCode::INTERNAL_SERVER_ERROR,
+            Self::BadGateway | Self::ProviderUnavailable => StatusCode::BAD_GATEWAY,
+            Self::ServiceUnavailable | Self::EmergencyStopActive | Self::ServerDraining => {
+                StatusCode::SERVICE_UNAVAILABLE
+            }
+            Self::Timeout => StatusCode::GATEWAY_TIMEOUT,
+        }
+    }
+
+    /// The generic code for an HTTP status.
+    pub fn from_status(status: StatusCode) -> Self {
+        match status {
+            StatusCode::BAD_REQUEST => Self::BadRequest,
+            StatusCode::UNAUTHORIZED => Self::Unauthorized,
+            StatusCode::FORBIDDEN => Self::Forbidden,
+            StatusCode::NOT_FOUND => Self::NotFound,
+            StatusCode::CONFLICT => Self::Conflict,
+            StatusCode::PRECONDITION_FAILED => Self::PreconditionFailed,
+            StatusCode::UNPROCESSABLE_ENTITY => Self::Unprocessable,
+            StatusCode::TOO_MANY_REQUESTS => Self::RateLimited,
+            StatusCode::BAD_GATEWAY => Self::BadGateway,
+            StatusCode::SERVICE_UNAVAILABLE => Self::ServiceUnavailable,
+            StatusCode::GATEWAY_TIMEOUT => Self::Timeout,
+            status if status.is_client_error() => Self::BadRequest,
+            _ => Self::Internal,
+        }
+    }
+
+    pub fn from_provider_error(err: &ProviderError) -> Self {
+        match err.kind() {
+            ProviderErrorKind::AuthFailed => Self::ProviderAuthFailed,
+            ProviderErrorKind::ContextLengthExceeded => Self::ProviderContextLengthExceeded,
+            ProviderErrorKind::RateLimited => Self::ProviderRateLimited,
+            ProviderErrorKind::Unavailable => Self::ProviderUnavailable,
+            ProviderErrorKind::Unsupported => Self::ProviderUnsupported,
+            ProviderErrorKind::RequestFailed => Self::ProviderRequestFailed,
+        }
+    }
+
+    /// Best code for an error from the agent, looking through anyhow context
+    /// for a provider error.
+    pub fn from_anyhow(err: &anyhow::Error) -> Self {
+        err.chain()
+            .find_map(|cause| cause.downcast_ref::<ProviderError>())
+            .map(Self::from_provider_error)
+            .unwrap_or(Self::Internal)
+    }
+}
+
 #[derive(Debug, Serialize, ToSchema)]
 pub struct ErrorResponse {
+    /// Human-readable message that can be shown to users
     pub message: String,
+    pub code: ErrorCode,
+    pub category: ErrorCategory,
+    pub retryable: bool,
+    /// Diagnostic detail for logs and bug reports
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub detail: Option<String>,
     #[serde(skip)]
     pub status: StatusCode,
 }
 
 impl ErrorResponse {
-    pub(crate) fn internal(message: impl Into<String>) -> Self {
+    /// An error with the generic code for `status`.
+    pub(crate) fn new(status: StatusCode, message: impl Into<String>) -> Self {
+        let code = ErrorCode::from_status(status);
         Self {
             message: message.into(),
-            status: StatusCode::INTERNAL_SERVER_ERROR,
+            code,
+            category: code.category(),
+            retryable: code.retryable(),
+            detail: None,
+            status,
         }
     }
 
+    pub(crate) fn from_code(code: ErrorCode, message: impl Into<String>) -> Self {
+        Self::new(code.status(), message).with_code(code)
+    }
+
+    /// Replaces the code while keeping the HTTP status.
+    pub(crate) fn with_code(mut self, code: ErrorCode) -> Self {
+        self.code = code;
+        self.category = code.category();
+        self.retryable = code.retryable();
+        self
+    }
+
+    pub(crate) fn with_detail(mut self, detail: impl Into<String>) -> Self {
+        self.detail = Some(detail.into());
+        self
+    }
+
+    pub(crate) fn internal(message: impl Into<String>) -> Self {
+        Self::new(StatusCode::INTERNAL_SERVER_ERROR, message)
+    }
+
     pub(crate) fn bad_request(message: impl Into<String>) -> Self {
-        Self {
-            message: message.into(),
-            status: StatusCode::BAD_REQUEST,
-        }
+        Self::new(StatusCode::BAD_REQUEST, message)
     }
 
     pub(crate) fn not_found(message: impl Into<String>) -> Self {
-        Self {
-            message: message.into(),
-            status: StatusCode::NOT_FOUND,
-        }
+        Self::new(StatusCode::NOT_FOUND, message)
     }
 
     pub(crate) fn unprocessable(message: impl Into<String>) -> Self {
-        Self {
-            message: message.into(),
-            status: StatusCode::UNPROCESSABLE_ENTITY,
-        }
+        Self::new(StatusCode::UNPROCESSABLE_ENTITY, message)
     }
 }
 
 impl IntoResponse for ErrorResponse {
     fn into_response(self) -> Response {
-        let body = Json(serde_json::json!({
-            "message": self.message,
-        }));
-
-        (self.status, body).into_response()
+        (self.status, Json(self)).into_response()
     }
 }
 
 impl From<anyhow::Error> for ErrorResponse {
     fn from(err: anyhow::Error) -> Self {
-        Self::internal(err.to_string())
+        let code = ErrorCode::from_anyhow(&err);
+        let response = Self::new(code.status(), err.to_string()).with_code(code);
+        if err.chain().len() > 1 {
+            response.with_detail(format!("{:#}", err))
+        } else {
+            response
+        }
     }
 }
 
 impl From<ConfigError> for ErrorResponse {
     fn from(err: ConfigError) -> Self {
         match err {
-            ConfigError::NotFound(key) => Self::not_found(format!("Config key not found: {}", key)),
+            ConfigError::NotFound(key) => Self::from_code(
+                ErrorCode::ConfigNotFound,
+                format!("Config key not found: {}", key),
+            ),
+            ConfigError::DeserializeError(_) => {
+                Self::internal(err.to_string()).with_code(ErrorCode::InvalidConfig)
+            }
             _ => Self::internal(err.to_string()),
         }
     }
@@ -74,16 +271,37 @@ impl From<ConfigError> for ErrorResponse {
 impl From<ModelConfigError> for ErrorResponse {
     fn from(err: ModelConfigError) -> Self {
         Self::internal(format!("Model configuration error: {}", err))
+            .with_code(ErrorCode::InvalidConfig)
+    }
+}
+
+impl From<RegistryError> for ErrorResponse {
+    fn from(err: RegistryError) -> Self {
+        let code = match &err {
+            RegistryError::NotInstalled(_) | RegistryError::VersionNotInstalled { .. } => {
+                ErrorCode::NotFound
+            }
+            RegistryError::ChecksumMismatch { .. }
+            | RegistryError::VersionConflict { .. }
+            | RegistryError::NotOwned(_) => ErrorCode::Conflict,
+            RegistryError::InvalidVersion(_) | RegistryError::InvalidSource(_) => {
+                ErrorCode::BadRequest
+            }
+            RegistryError::UnmetDependencies(_) => ErrorCode::Unprocessable,
+            RegistryError::Invalid(_) => {
+                return Self::bad_request(err.to_string()).with_code(ErrorCode::InvalidRecipe)
+            }
+            RegistryError::Fetch(_) => ErrorCode::BadGateway,
+            RegistryError::Io(_) | RegistryError::Json(_) => ErrorCode::Internal,
+        };
+        Self::from_code(code, err.to_string())
     }
 }
 
 impl From<StatusCode> for ErrorResponse {
     fn from(status: StatusCode) -> Self {
         let message = status.canonical_reason().unwrap_or("Unknown error");
-        Self {
-            message: message.to_string(),
-            status,
-        }
+        Self::new(status, message)
     }
 }
 
@@ -125,6 +343,79 @@ impl From<ProviderError> for ErrorResponse {
             ),
         };
 
-        Self { message, status }
+        Self::new(status, message).with_code(ErrorCode::from_provider_error(&err))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_every_code_round_trips_through_its_status() {
+        for status in [
+            StatusCode::BAD_REQUEST,
+            StatusCode::NOT_FOUND,
+            StatusCode::TOO_MANY_REQUESTS,
+            StatusCode::SERVICE_UNAVAILABLE,
+            StatusCode::INTERNAL_SERVER_ERROR,
+        ] {
+            assert_eq!(ErrorCode::from_status(status).status(), status);
+        }
+        assert_eq!(
+            ErrorCode::from_status(StatusCode::IM_A_TEAPOT),
+            ErrorCode::BadRequest
+        );
+    }
+
+    #[test]
+    fn test_provider_codes_match_the_shared_names() {
+        for err in [
+            ProviderError::Authentication(String::new()),
+            ProviderError::ContextLengthExceeded(String::new()),
+            ProviderError::ServerError(String::new()),
+            ProviderError::ExecutionError(String::new()),
+            ProviderError::NotImplemented(String::new()),
+        ] {
+            assert_eq!(
+                serde_json::to_value(ErrorCode::from_provider_error(&err)).unwrap(),
+                err.kind().code()
+            );
+        }
+    }
+
+    #[test]
+    fn test_provider_errors_are_found_through_context() {
+        let err = anyhow::Error::from(ProviderError::RateLimitExceeded {
+            details: "slow down".to_string(),
+            retry_delay: None,
+        })
+        .context("Reply failed");
+
+        let response = ErrorResponse::from(err);
+        assert_eq!(response.code, ErrorCode::ProviderRateLimited);
+        assert_eq!(response.category, ErrorCategory::Provider);
+        assert!(response.retryable);
+        assert_eq!(response.status, StatusCode::TOO_MANY_REQUESTS);
+        assert_eq!(response.message, "Reply failed");
+        assert!(response.detail.unwrap().contains("slow down"));
+    }
+
+    #[test]
+    fn test_body_includes_code_and_category() {
+        let body = serde_json::to_value(ErrorResponse::from_code(
+            ErrorCode::SessionNotFound,
+            "Session not found: abc",
+        ))
+        .unwrap();
+        assert_eq!(
+            body,
+            serde_json::json!({
+                "message": "Session not found: abc",
+                "code": "session_not_found",
+                "category": "not_found",
+                "retryable": false,
+            })
+        );
     }
 }
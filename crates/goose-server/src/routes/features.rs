@@ -0,0 +1,235 @@
+use crate::routes::errors::ErrorResponse;
+use crate::state::AppState;
+use axum::extract::ws::{Message, WebSocket, WebSocketUpgrade};
+use axum::extract::{Path, Query};
+use axum::http::StatusCode;
+use axum::response::Response;
+use axum::routing::{get, post, put};
+use axum::{Json, Router};
+use goose::config::Config;
+use goose::feature_flags::{self, FlagDefinition, FlagEvaluation, FlagSubject, FLAGS_KEY};
+use serde::Serialize;
+use std::collections::{BTreeMap, HashMap};
+use std::sync::Arc;
+use tokio::sync::broadcast::error::RecvError;
+use utoipa::ToSchema;
+
+#[derive(Debug, Serialize, ToSchema)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum FlagPush {
+    /// Every flag's current value for the connection's subject
+    Flags {
+        flags: BTreeMap<String, FlagEvaluation>,
+    },
+    Error {
+        message: String,
+    },
+}
+
+#[utoipa::path(
+    get,
+    path = "/features",
+    responses(
+        (status = 200, description = "Configured feature flags", body = HashMap<String, FlagDefinition>),
+        (status = 401, description = "Unauthorized - Invalid or missing API key")
+    ),
+    security(
+        ("api_key" = [])
+    ),
+    tag = "Features"
+)]
+async fn list_flags() -> Json<HashMap<String, FlagDefinition>> {
+    Json(feature_flags::flags())
+}
+
+#[utoipa::path(
+    put,
+    path = "/features/{flag}",
+    params(
+        ("flag" = String, Path, description = "Flag name")
+    ),
+    request_body = FlagDefinition,
+    responses(
+        (status = 200, description = "Flag saved; connected clients receive the new values", body = FlagDefinition),
+        (status = 400, description = "Rollout out of range, unknown required flag, or flags requiring each other"),
+        (status = 401, description = "Unauthorized - Invalid or missing API key")
+    ),
+    security(
+        ("api_key" = [])
+    ),
+    tag = "Features"
+)]
+async fn set_flag(
+    Path(flag): Path<String>,
+    Json(definition): Json<FlagDefinition>,
+) -> Result<Json<FlagDefinition>, ErrorResponse> {
+    feature_flags::set_flag(&flag, definition.clone())
+        .map_err(|err| ErrorResponse::bad_request(err.to_string()))?;
+    Ok(Json(definition))
+}
+
+#[utoipa::path(
+    delete,
+    path = "/features/{flag}",
+    params(
+        ("flag" = String, Path, description = "Flag name")
+    ),
+    responses(
+        (status = 204, description = "Flag removed"),
+        (status = 400, description = "Another flag requires this one"),
+        (status = 401, description = "Unauthorized - Invalid or missing API key"),
+        (status = 404, description = "Flag not found")
+    ),
+    security(
+        ("api_key" = [])
+    ),
+    tag = "Features"
+)]
+async fn remove_flag(Path(flag): Path<String>) -> Result<StatusCode, ErrorResponse> {
+    match feature_flags::remove_flag(&flag) {
+        Ok(true) => Ok(StatusCode::NO_CONTENT),
+        Ok(false) => Err(ErrorResponse::not_found(format!(
+            "Flag '{}' not found",
+            flag
+        ))),
+        Err(err) => Err(ErrorResponse::bad_request(err.to_string())),
+    }
+}
+
+#[utoipa::path(
+    post,
+    path = "/features/evaluate",
+    request_body = FlagSubject,
+    responses(
+        (status = 200, description = "Every flag's value for the session and user; clients call this at session start", body = BTreeMap<String, FlagEvaluation>),
+        (status = 401, description = "Unauthorized - Invalid or missing API key"),
+        (status = 404, description = "Session not found")
+    ),
+    security(
+        ("api_key" = [])
+    ),
+    tag = "Features"
+)]
+async fn evaluate(
+    Json(subject): Json<FlagSubject>,
+) -> Result<Json<BTreeMap<String, FlagEvaluation>>, ErrorResponse> {
+    feature_flags::evaluate_for(&subject)
+        .await
+        .map(Json)
+        .map_err(|err| ErrorResponse::not_found(err.to_string()))
+}
+
+#[utoipa::path(
+    put,
+    path = "/features/sessions/{session_id}",
+    params(
+        ("session_id" = String, Path, description = "Session whose overrides are replaced")
+    ),
+    request_body = BTreeMap<String, bool>,
+    responses(
+        (status = 200, description = "Overrides saved; the session's connected clients receive the new values", body = BTreeMap<String, FlagEvaluation>),
+        (status = 401, description = "Unauthorized - Invalid or missing API key"),
+        (status = 404, description = "Session not found")
+    ),
+    security(
+        ("api_key" = [])
+    ),
+    tag = "Features"
+)]
+async fn set_session_overrides(
+    Path(session_id): Path<String>,
+    Json(overrides): Json<BTreeMap<String, bool>>,
+) -> Result<Json<BTreeMap<String, FlagEvaluation>>, ErrorResponse> {
+    feature_flags::set_session_overrides(&session_id, overrides)
+        .await
+        .map_err(|err| ErrorResponse::not_found(err.to_string()))?;
+    let subject = FlagSubject {
+        session_id: Some(session_id),
+        user_id: None,
+    };
+    feature_flags::evaluate_for(&subject)
+        .await
+        .map(Json)
+        .map_err(|err| ErrorResponse::internal(err.to_string()))
+}
+
+#[utoipa::path(
+    get,
+    path = "/features/ws",
+    params(
+        ("session_id" = Option<String>, Query, description = "Session to evaluate for"),
+        ("user_id" = Option<String>, Query, description = "User to evaluate for; defaults to the session's")
+    ),
+    responses(
+        (status = 101, description = "WebSocket pushing the flag values now and whenever they change", body = FlagPush),
+        (status = 401, description = "Unauthorized - Invalid or missing API key")
+    ),
+    security(
+        ("api_key" = [])
+    ),
+    tag = "Features"
+)]
+async fn flag_socket(ws: WebSocketUpgrade, Query(subject): Query<FlagSubject>) -> Response {
+    ws.on_upgrade(move |socket| push_flags(socket, subject))
+}
+
+/// Sends the subject's flags, then again each time a definition or the
+/// session's overrides change the result, until the client goes away.
+async fn push_flags(mut socket: WebSocket, subject: FlagSubject) {
+    let mut config_changes = Config::global().subscribe();
+    let mut session_changes = feature_flags::subscribe_session_changes();
+    let mut sent = None;
+    let mut changed = true;
+    loop {
+        if changed {
+            let push = match feature_flags::evaluate_for(&subject).await {
+                Ok(flags) if sent.as_ref() == Some(&flags) => None,
+                Ok(flags) => {
+                    sent = Some(flags.clone());
+                    Some(FlagPush::Flags { flags })
+                }
+                Err(err) => Some(FlagPush::Error {
+                    message: err.to_string(),
+                }),
+            };
+            if let Some(push) = push {
+                let Ok(text) = serde_json::to_string(&push) else {
+                    break;
+                };
+                if socket.send(Message::Text(text.into())).await.is_err() {
+                    break;
+                }
+            }
+        }
+
+        changed = tokio::select! {
+            change = config_changes.recv() => match change {
+                Ok(change) => change.touches(FLAGS_KEY),
+                Err(RecvError::Lagged(_)) => true,
+                Err(RecvError::Closed) => break,
+            },
+            id = session_changes.recv() => match id {
+                Ok(id) => subject.session_id.as_ref() == Some(&id),
+                Err(RecvError::Lagged(_)) => true,
+                Err(RecvError::Closed) => break,
+            },
+            message = socket.recv() => match message {
+                Some(Ok(Message::Close(_))) | Some(Err(_)) | None => break,
+                Some(Ok(_)) => false,
+            },
+        };
+    }
+}
+
+pub fn routes(state: Arc<AppState>) -> Router {
+    Router::new()
+        .route("/features", get(list_flags))
+        .route("/features/evaluate", post(evaluate))
+        .route("/features/ws", get(flag_socket))
+        .route(
+            "/features/sessions/{session_id}",
+            put(set_session_overrides),
+        )
+        .route("/features/{flag}", put(set_flag).delete(remove_flag))
+        .with_state(state)
+}
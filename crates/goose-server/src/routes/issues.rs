@@ -0,0 +1,212 @@
+use crate::jobs::{Job, JobPriority, JobRequest};
+use crate::routes::errors::ErrorResponse;
+use crate::state::AppState;
+use axum::body::Bytes;
+use axum::extract::{Path, State};
+use axum::http::{HeaderMap, StatusCode};
+use axum::routing::{get, post};
+use axum::{Json, Router};
+use goose::config::resolve_extensions_for_new_session;
+use goose::issues::{self, Issue, IssueLink, LinkedItem, NewIssue};
+use goose::session::session_manager::SessionType;
+use goose::session::EnabledExtensionsState;
+use serde::Deserialize;
+use std::sync::Arc;
+use utoipa::ToSchema;
+
+/// Paths under this prefix are authenticated by their signature instead of
+/// the API key, since trackers cannot send it.
+pub const WEBHOOK_PREFIX: &str = "/issues/webhook/";
+
+#[derive(Debug, Deserialize, ToSchema)]
+pub struct MirrorScheduleRequest {
+    pub tracker: String,
+    pub schedule_id: String,
+}
+
+#[utoipa::path(
+    get,
+    path = "/issues/links",
+    responses(
+        (status = 200, description = "Local work linked to tracker tickets", body = Vec<IssueLink>),
+        (status = 401, description = "Unauthorized - Invalid or missing API key")
+    ),
+    security(
+        ("api_key" = [])
+    ),
+    tag = "Issues"
+)]
+async fn list_links() -> Json<Vec<IssueLink>> {
+    Json(issues::links())
+}
+
+#[utoipa::path(
+    post,
+    path = "/issues/mirror",
+    request_body = MirrorScheduleRequest,
+    responses(
+        (status = 200, description = "Ticket mirroring the scheduled job; each run is reported on it", body = IssueLink),
+        (status = 401, description = "Unauthorized - Invalid or missing API key"),
+        (status = 404, description = "Scheduled job or tracker not found"),
+        (status = 500, description = "The tracker rejected the ticket")
+    ),
+    security(
+        ("api_key" = [])
+    ),
+    tag = "Issues"
+)]
+async fn mirror_schedule(
+    State(state): State<Arc<AppState>>,
+    Json(request): Json<MirrorScheduleRequest>,
+) -> Result<Json<IssueLink>, ErrorResponse> {
+    issues::tracker_config(&request.tracker)
+        .map_err(|err| ErrorResponse::not_found(err.to_string()))?;
+    let job = state
+        .scheduler()
+        .list_scheduled_jobs()
+        .await
+        .into_iter()
+        .find(|job| job.id == request.schedule_id)
+        .ok_or_else(|| {
+            ErrorResponse::not_found(format!("Scheduled job '{}' not found", request.schedule_id))
+        })?;
+    let issue = NewIssue {
+        title: format!("Scheduled task: {}", job.id),
+        body: format!(
+            "Runs `{}` on the schedule `{}`. Each run is reported here.",
+            job.source, job.cron
+        ),
+        labels: Vec::new(),
+    };
+    let link = issues::mirror(&request.tracker, LinkedItem::Schedule(job.id), issue)
+        .await
+        .map_err(|err| ErrorResponse::internal(err.to_string()))?;
+    Ok(Json(link))
+}
+
+/// Header carrying the id of each webhook delivery, per tracker
+const DELIVERY_HEADERS: [&str; 2] = ["X-GitHub-Delivery", "X-Atlassian-Webhook-Identifier"];
+
+/// Whether a job started from this ticket is still queued or running, or was
+/// started from this same assignment. The delivery id header is not signed,
+/// so a replayed payload is also caught by its ticket's last update being no
+/// newer than the job already started for it.
+fn already_handled(state: &AppState, tracker: &str, issue: &Issue) -> bool {
+    issues::links().iter().any(|link| match &link.item {
+        LinkedItem::Job(id) if link.tracker == tracker && link.key == issue.key => {
+            let replayed = issue
+                .updated_at
+                .is_none_or(|updated_at| updated_at <= link.linked_at);
+            replayed || state.jobs.get(id).is_ok_and(|job| !job.state.is_finished())
+        }
+        _ => false,
+    })
+}
+
+#[utoipa::path(
+    post,
+    path = "/issues/webhook/{tracker}",
+    params(
+        ("tracker" = String, Path, description = "Tracker the webhook is configured for")
+    ),
+    request_body(content = String, description = "Webhook payload, signed with the tracker's webhook secret"),
+    responses(
+        (status = 202, description = "The ticket was assigned to goose and a job was queued for it", body = Job),
+        (status = 204, description = "Not an assignment to goose, a replayed delivery, or a job already started for this assignment"),
+        (status = 401, description = "Missing or invalid signature"),
+        (status = 404, description = "Tracker not found")
+    ),
+    tag = "Issues"
+)]
+async fn receive_webhook(
+    State(state): State<Arc<AppState>>,
+    Path(tracker): Path<String>,
+    headers: HeaderMap,
+    body: Bytes,
+) -> Result<(StatusCode, Json<Option<Job>>), ErrorResponse> {
+    let config = issues::tracker_config(&tracker)
+        .map_err(|err| ErrorResponse::not_found(err.to_string()))?;
+    let signature = headers
+        .get("X-Hub-Signature-256")
+        .or_else(|| headers.get("X-Hub-Signature"))
+        .and_then(|value| value.to_str().ok());
+    issues::verify_webhook(&tracker, &body, signature)
+        .map_err(|err| ErrorResponse::new(StatusCode::UNAUTHORIZED, err.to_string()))?;
+    let payload: serde_json::Value = serde_json::from_slice(&body)
+        .map_err(|err| ErrorResponse::bad_request(format!("Invalid payload: {}", err)))?;
+
+    let delivery_id = DELIVERY_HEADERS
+        .iter()
+        .find_map(|name| headers.get(*name))
+        .and_then(|value| value.to_str().ok());
+    if let Some(delivery_id) = delivery_id {
+        let first = issues::record_delivery(&tracker, delivery_id)
+            .map_err(|err| ErrorResponse::internal(err.to_string()))?;
+        if !first {
+            tracing::warn!(tracker = %tracker, delivery = %delivery_id, "Ignoring replayed webhook");
+            return Ok((StatusCode::NO_CONTENT, Json(None)));
+        }
+    }
+
+    let Some(issue) = issues::assignment(&config, &payload) else {
+        return Ok((StatusCode::NO_CONTENT, Json(None)));
+    };
+    if already_handled(&state, &tracker, &issue) {
+        return Ok((StatusCode::NO_CONTENT, Json(None)));
+    }
+    state.emergency.ensure_running()?;
+    state.drain.ensure_accepting()?;
+
+    let working_dir = match config.working_dir {
+        Some(dir) => dir,
+        None => std::env::current_dir().map_err(|err| ErrorResponse::internal(err.to_string()))?,
+    };
+    let manager = state.session_manager();
+    let session = manager
+        .create_session(
+            working_dir,
+            format!("{} {}: {}", tracker, issue.key, issue.title),
+            SessionType::Scheduled,
+        )
+        .await
+        .map_err(|err| ErrorResponse::internal(format!("Failed to create session: {}", err)))?;
+    let mut extension_data = session.extension_data.clone();
+    let extensions = EnabledExtensionsState::new(resolve_extensions_for_new_session(None, None));
+    if let Err(e) = extensions.to_extension_data(&mut extension_data) {
+        tracing::warn!("Failed to initialize session with extensions: {}", e);
+    } else {
+        manager
+            .update(&session.id)
+            .extension_data(extension_data)
+            .apply()
+            .await
+            .map_err(|err| ErrorResponse::internal(err.to_string()))?;
+    }
+
+    let job = state.jobs.submit(JobRequest {
+        session_id: session.id,
+        prompt: issues::task_prompt(&tracker, &issue),
+        priority: JobPriority::Normal,
+        owner: Some(format!("{}:{}", tracker, issue.key)),
+        max_turns: None,
+        max_tokens: None,
+        deadline_secs: None,
+    })?;
+    issues::link(LinkedItem::Job(job.id.clone()), &tracker, &issue)
+        .map_err(|err| ErrorResponse::internal(err.to_string()))?;
+    tracing::info!(
+        tracker = %tracker,
+        key = %issue.key,
+        job = %job.id,
+        "Queued job for assigned ticket"
+    );
+    Ok((StatusCode::ACCEPTED, Json(Some(job))))
+}
+
+pub fn routes(state: Arc<AppState>) -> Router {
+    Router::new()
+        .route("/issues/links", get(list_links))
+        .route("/issues/mirror", post(mirror_schedule))
+        .route("/issues/webhook/{tracker}", post(receive_webhook))
+        .with_state(state)
+}
@@ -0,0 +1,136 @@
+use crate::jobs::{Job, JobError, JobRequest, MAX_WAIT};
+use crate::routes::enterprise::enforce_team_quota;
+use crate::routes::errors::{ErrorCode, ErrorResponse};
+use crate::state::AppState;
+use axum::{
+    extract::{Path, Query, State},
+    http::StatusCode,
+    routing::{get, post},
+    Json, Router,
+};
+use serde::Deserialize;
+use std::sync::Arc;
+use std::time::Duration;
+use utoipa::IntoParams;
+
+impl From<JobError> for ErrorResponse {
+    fn from(err: JobError) -> Self {
+        match err {
+            JobError::NotFound(_) => Self::not_found(err.to_string()),
+            JobError::AlreadyFinished(_) => Self::new(StatusCode::CONFLICT, err.to_string()),
+            JobError::EmptyPrompt => Self::bad_request(err.to_string()),
+        }
+    }
+}
+
+#[derive(Debug, Deserialize, IntoParams)]
+pub struct ListJobsQuery {
+    /// Only jobs for this session
+    pub session_id: Option<String>,
+}
+
+#[derive(Debug, Deserialize, IntoParams)]
+pub struct GetJobQuery {
+    /// Wait until the job's version is past this one, or it finishes
+    pub after_version: Option<u64>,
+    /// Seconds to wait for a change; capped at 60
+    pub wait_secs: Option<u64>,
+}
+
+#[utoipa::path(
+    post,
+    path = "/jobs",
+    request_body = JobRequest,
+    responses(
+        (status = 202, description = "Job queued; it runs detached from this request", body = Job),
+        (status = 400, description = "Missing prompt"),
+        (status = 404, description = "Session not found"),
+        (status = 503, description = "Emergency stop is active or the server is draining")
+    )
+)]
+pub async fn submit_job(
+    State(state): State<Arc<AppState>>,
+    Json(request): Json<JobRequest>,
+) -> Result<(StatusCode, Json<Job>), ErrorResponse> {
+    state.emergency.ensure_running()?;
+    state.drain.ensure_accepting()?;
+    state
+        .session_manager()
+        .get_session(&request.session_id, false)
+        .await
+        .map_err(|e| {
+            ErrorResponse::not_found(format!("Session not found: {}", e))
+                .with_code(ErrorCode::SessionNotFound)
+        })?;
+    enforce_team_quota(&state, &request.session_id).await?;
+
+    Ok((StatusCode::ACCEPTED, Json(state.jobs.submit(request)?)))
+}
+
+#[utoipa::path(
+    get,
+    path = "/jobs",
+    params(ListJobsQuery),
+    responses(
+        (status = 200, description = "Jobs, newest first", body = [Job])
+    )
+)]
+pub async fn list_jobs(
+    State(state): State<Arc<AppState>>,
+    Query(query): Query<ListJobsQuery>,
+) -> Json<Vec<Job>> {
+    Json(state.jobs.list(query.session_id.as_deref()))
+}
+
+#[utoipa::path(
+    get,
+    path = "/jobs/{job_id}",
+    params(
+        ("job_id" = String, Path, description = "Job id"),
+        GetJobQuery
+    ),
+    responses(
+        (status = 200, description = "Job status, progress and result", body = Job),
+        (status = 404, description = "Job not found")
+    )
+)]
+pub async fn get_job(
+    State(state): State<Arc<AppState>>,
+    Path(job_id): Path<String>,
+    Query(query): Query<GetJobQuery>,
+) -> Result<Json<Job>, ErrorResponse> {
+    let job = match query.after_version {
+        Some(version) => {
+            let wait = query.wait_secs.map(Duration::from_secs).unwrap_or(MAX_WAIT);
+            state.jobs.wait(&job_id, version, wait).await?
+        }
+        None => state.jobs.get(&job_id)?,
+    };
+    Ok(Json(job))
+}
+
+#[utoipa::path(
+    delete,
+    path = "/jobs/{job_id}",
+    params(
+        ("job_id" = String, Path, description = "Job id")
+    ),
+    responses(
+        (status = 200, description = "Queued job cancelled, or cancellation requested for a running one", body = Job),
+        (status = 404, description = "Job not found"),
+        (status = 409, description = "Job already finished")
+    )
+)]
+pub async fn cancel_job(
+    State(state): State<Arc<AppState>>,
+    Path(job_id): Path<String>,
+) -> Result<Json<Job>, ErrorResponse> {
+    Ok(Json(state.jobs.cancel(&job_id)?))
+}
+
+pub fn routes(state: Arc<AppState>) -> Router {
+    Router::new()
+        .route("/jobs", post(submit_job).get(list_jobs))
+        .route("/jobs/{job_id}", get(get_job).delete(cancel_job))
+        .with_state(state)
+}
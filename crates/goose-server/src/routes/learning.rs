@@ -0,0 +1,184 @@
+use crate::routes::errors::{ErrorCode, ErrorResponse};
+use crate::state::AppState;
+use axum::{
+    extract::{Path, State},
+    routing::{get, put},
+    Json, Router,
+};
+use goose::agents::core_selector::{self, parse_category, CoreScore, CoreSelector};
+use goose::agents::ExecutionMode;
+use goose::session::feedback::{Feedback, FeedbackStats, Rating, MAX_COMMENT_LENGTH};
+use serde::{Deserialize, Serialize};
+use std::sync::Arc;
+use utoipa::ToSchema;
+
+#[derive(Deserialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct FeedbackRequest {
+    rating: Rating,
+    /// What was good or bad about the answer (max 2000 characters)
+    comment: Option<String>,
+    /// Message to rate; omit to rate the whole session
+    message_id: Option<String>,
+}
+
+#[derive(Serialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct FeedbackResponse {
+    feedback: Feedback,
+    /// The feedback was turned into a reflection the agent will see on
+    /// similar requests
+    learned: bool,
+}
+
+#[utoipa::path(
+    post,
+    path = "/sessions/{session_id}/feedback",
+    request_body = FeedbackRequest,
+    params(
+        ("session_id" = String, Path, description = "Unique identifier for the session")
+    ),
+    responses(
+        (status = 200, description = "Feedback recorded, replacing any earlier rating of the same target", body = FeedbackResponse),
+        (status = 400, description = "Comment too long", body = ErrorResponse),
+        (status = 404, description = "Session or message not found", body = ErrorResponse)
+    )
+)]
+pub async fn submit_feedback(
+    State(state): State<Arc<AppState>>,
+    Path(session_id): Path<String>,
+    Json(request): Json<FeedbackRequest>,
+) -> Result<Json<FeedbackResponse>, ErrorResponse> {
+    let comment = request
+        .comment
+        .as_deref()
+        .map(str::trim)
+        .filter(|c| !c.is_empty());
+    if comment.is_some_and(|c| c.chars().count() > MAX_COMMENT_LENGTH) {
+        return Err(ErrorResponse::bad_request(format!(
+            "Comment must be at most {} characters",
+            MAX_COMMENT_LENGTH
+        )));
+    }
+
+    let session = state
+        .session_manager()
+        .get_session(&session_id, true)
+        .await
+        .map_err(|_| ErrorResponse::from_code(ErrorCode::SessionNotFound, "Session not found"))?;
+    let conversation = session.conversation.unwrap_or_default();
+    if let Some(message_id) = &request.message_id {
+        if !conversation
+            .messages()
+            .iter()
+            .any(|m| m.id.as_deref() == Some(message_id))
+        {
+            return Err(ErrorResponse::not_found("Message not found"));
+        }
+    }
+
+    let feedback = state
+        .session_manager()
+        .add_feedback(
+            &session_id,
+            request.message_id.as_deref(),
+            request.rating,
+            comment,
+        )
+        .await?;
+
+    let agent = state.get_agent(session_id).await?;
+    let learned = agent.learn_from_feedback(&feedback, &conversation).await;
+
+    Ok(Json(FeedbackResponse { feedback, learned }))
+}
+
+#[utoipa::path(
+    get,
+    path = "/sessions/{session_id}/feedback",
+    params(
+        ("session_id" = String, Path, description = "Unique identifier for the session")
+    ),
+    responses(
+        (status = 200, description = "Ratings given in this session, oldest first", body = [Feedback])
+    )
+)]
+pub async fn list_feedback(
+    State(state): State<Arc<AppState>>,
+    Path(session_id): Path<String>,
+) -> Result<Json<Vec<Feedback>>, ErrorResponse> {
+    Ok(Json(
+        state.session_manager().list_feedback(&session_id).await?,
+    ))
+}
+
+#[utoipa::path(
+    get,
+    path = "/learning/feedback/stats",
+    responses(
+        (status = 200, description = "Ratings overall and by model and recipe", body = FeedbackStats)
+    )
+)]
+pub async fn feedback_stats(
+    State(state): State<Arc<AppState>>,
+) -> Result<Json<FeedbackStats>, ErrorResponse> {
+    Ok(Json(state.session_manager().feedback_stats().await?))
+}
+
+#[utoipa::path(
+    get,
+    path = "/learning/core-scores",
+    responses(
+        (status = 200, description = "Suitability of each core per task category, blended with benchmark outcomes", body = [CoreScore])
+    )
+)]
+pub async fn core_scores() -> Json<Vec<CoreScore>> {
+    Json(CoreSelector::from_config().scores())
+}
+
+#[derive(Deserialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct CorePinRequest {
+    /// Task category, e.g. code_fix
+    category: String,
+    /// Core to use for the category, freeform or structured; omit to unpin
+    core: Option<String>,
+}
+
+#[utoipa::path(
+    put,
+    path = "/learning/core-pins",
+    request_body = CorePinRequest,
+    responses(
+        (status = 200, description = "Pin updated; returns the new scores", body = [CoreScore]),
+        (status = 400, description = "Unknown category or core", body = ErrorResponse)
+    )
+)]
+pub async fn set_core_pin(
+    Json(request): Json<CorePinRequest>,
+) -> Result<Json<Vec<CoreScore>>, ErrorResponse> {
+    let category = parse_category(&request.category).ok_or_else(|| {
+        ErrorResponse::bad_request(format!("Unknown task category '{}'", request.category))
+    })?;
+    let core = match request.core {
+        Some(core) => Some(
+            serde_json::from_value::<ExecutionMode>(serde_json::Value::String(core.clone()))
+                .map_err(|_| ErrorResponse::bad_request(format!("Unknown core '{}'", core)))?,
+        ),
+        None => None,
+    };
+    core_selector::set_pin(category, core)?;
+    Ok(Json(CoreSelector::from_config().scores()))
+}
+
+pub fn routes(state: Arc<AppState>) -> Router {
+    Router::new()
+        .route(
+            "/sessions/{session_id}/feedback",
+            get(list_feedback).post(submit_feedback),
+        )
+        .route("/learning/feedback/stats", get(feedback_stats))
+        .route("/learning/core-scores", get(core_scores))
+        .route("/learning/core-pins", put(set_core_pin))
+        .with_state(state)
+}
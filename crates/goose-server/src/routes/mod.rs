@@ -1,11 +1,20 @@
 pub mod action_required;
 pub mod agent;
+pub mod artifacts;
+pub mod bus;
+pub mod calendar;
 pub mod config_management;
 pub mod orchestrator;
 pub mod dictation;
+pub mod enterprise;
 pub mod errors;
+pub mod features;
+pub mod issues;
+pub mod jobs;
+pub mod learning;
 pub mod mcp_app_proxy;
 pub mod mcp_ui_proxy;
+pub mod privacy;
 pub mod prompts;
 pub mod recipe;
 pub mod recipe_utils;
@@ -14,7 +23,10 @@ pub mod schedule;
 pub mod session;
 pub mod setup;
 pub mod status;
+pub mod swarm;
+pub mod system;
 pub mod telemetry;
+pub mod tts;
 pub mod tunnel;
 pub mod utils;
 
@@ -26,17 +38,29 @@ use axum::Router;
 pub fn configure(state: Arc<crate::state::AppState>, secret_key: String) -> Router {
     Router::new()
         .merge(status::routes(state.clone()))
+        .merge(system::routes(state.clone()))
+        .merge(jobs::routes(state.clone()))
         .merge(reply::routes(state.clone()))
         .merge(action_required::routes(state.clone()))
         .merge(agent::routes(state.clone()))
+        .merge(artifacts::routes(state.clone()))
+        .merge(bus::routes(state.clone()))
+        .merge(calendar::routes(state.clone()))
         .merge(dictation::routes(state.clone()))
+        .merge(enterprise::routes(state.clone()))
+        .merge(features::routes(state.clone()))
+        .merge(issues::routes(state.clone()))
         .merge(config_management::routes(state.clone()))
+        .merge(learning::routes(state.clone()))
+        .merge(privacy::routes(state.clone()))
         .merge(prompts::routes())
         .merge(recipe::routes(state.clone()))
         .merge(session::routes(state.clone()))
+        .merge(swarm::routes(state.clone()))
         .merge(schedule::routes(state.clone()))
         .merge(setup::routes(state.clone()))
         .merge(telemetry::routes(state.clone()))
+        .merge(tts::routes(state.clone()))
         .merge(tunnel::routes(state.clone()))
         .merge(mcp_ui_proxy::routes(secret_key.clone()))
         .merge(orchestrator::routes(state.clone()))
@@ -0,0 +1,103 @@
+use crate::routes::errors::ErrorResponse;
+use crate::state::AppState;
+use axum::extract::State;
+use axum::routing::{get, post};
+use axum::{Json, Router};
+use chrono::Utc;
+use goose::memory::classification::{self, NamespaceClassification};
+use goose::privacy::{self, DataRetention, DeletionReport, PurgeScope};
+use std::sync::Arc;
+
+#[utoipa::path(
+    get,
+    path = "/privacy/retention",
+    responses(
+        (status = 200, description = "Configured retention period of each data class", body = DataRetention),
+        (status = 401, description = "Unauthorized - Invalid or missing API key")
+    ),
+    security(
+        ("api_key" = [])
+    ),
+    tag = "Privacy"
+)]
+async fn get_retention() -> Json<DataRetention> {
+    Json(DataRetention::from_config())
+}
+
+#[utoipa::path(
+    post,
+    path = "/privacy/retention",
+    request_body(content = Option<DataRetention>, description = "Periods to apply instead of the configured ones"),
+    responses(
+        (status = 200, description = "Data past its retention period was deleted", body = DeletionReport),
+        (status = 401, description = "Unauthorized - Invalid or missing API key")
+    ),
+    security(
+        ("api_key" = [])
+    ),
+    tag = "Privacy"
+)]
+async fn apply_retention(retention: Option<Json<DataRetention>>) -> Json<DeletionReport> {
+    let retention = retention
+        .map(|Json(retention)| retention)
+        .unwrap_or_else(DataRetention::from_config);
+    Json(privacy::apply_retention(&retention, Utc::now()).await)
+}
+
+#[utoipa::path(
+    post,
+    path = "/privacy/purge",
+    request_body = PurgeScope,
+    responses(
+        (status = 200, description = "Everything stored for the scope was erased; the report lists what was deleted and what was kept", body = DeletionReport),
+        (status = 401, description = "Unauthorized - Invalid or missing API key"),
+        (status = 500, description = "Internal server error", body = ErrorResponse)
+    ),
+    security(
+        ("api_key" = [])
+    ),
+    tag = "Privacy"
+)]
+async fn purge(
+    State(state): State<Arc<AppState>>,
+    Json(scope): Json<PurgeScope>,
+) -> Result<Json<DeletionReport>, ErrorResponse> {
+    if let PurgeScope::Session { id } = &scope {
+        let _ = state.agent_manager.remove_session(id).await;
+    }
+    let report = privacy::purge(scope).await?;
+    for id in &report.sessions {
+        let _ = state.agent_manager.remove_session(id).await;
+    }
+    Ok(Json(report))
+}
+
+#[utoipa::path(
+    get,
+    path = "/privacy/memory_classes",
+    responses(
+        (status = 200, description = "PII and secret classes found in saved memories, per user, project or session", body = Vec<NamespaceClassification>),
+        (status = 401, description = "Unauthorized - Invalid or missing API key"),
+        (status = 500, description = "Internal server error", body = ErrorResponse)
+    ),
+    security(
+        ("api_key" = [])
+    ),
+    tag = "Privacy"
+)]
+async fn memory_classes() -> Result<Json<Vec<NamespaceClassification>>, ErrorResponse> {
+    let entries =
+        goose::memory::saved_entries().map_err(|e| ErrorResponse::internal(e.to_string()))?;
+    Ok(Json(classification::report(&entries)))
+}
+
+pub fn routes(state: Arc<AppState>) -> Router {
+    Router::new()
+        .route(
+            "/privacy/retention",
+            get(get_retention).post(apply_retention),
+        )
+        .route("/privacy/purge", post(purge))
+        .route("/privacy/memory_classes", get(memory_classes))
+        .with_state(state)
+}
@@ -81,6 +81,7 @@ pub async fn get_prompt(
     responses(
         (status = 200, description = "Prompt saved successfully", body = String),
         (status = 404, description = "Prompt not found"),
+        (status = 422, description = "Prompt template is invalid"),
         (status = 500, description = "Failed to save prompt")
     )
 )]
@@ -88,12 +89,14 @@ pub async fn save_prompt(
     Path(name): Path<String>,
     Json(request): Json<SavePromptRequest>,
 ) -> Result<Json<String>, ErrorResponse> {
-    save_template(&name, &request.content).map_err(|e| {
-        if e.kind() == std::io::ErrorKind::NotFound {
+    save_template(&name, &request.content).map_err(|e| match e.kind() {
+        std::io::ErrorKind::NotFound => {
             ErrorResponse::not_found(format!("Prompt template '{}' not found", name))
-        } else {
-            ErrorResponse::internal(format!("Failed to save prompt '{}': {}", name, e))
         }
+        std::io::ErrorKind::InvalidData => {
+            ErrorResponse::unprocessable(format!("Invalid prompt template '{}': {}", name, e))
+        }
+        _ => ErrorResponse::internal(format!("Failed to save prompt '{}': {}", name, e)),
     })?;
 
     Ok(Json(format!("Saved prompt: {}", name)))
@@ -4,9 +4,11 @@ use std::path::PathBuf;
 use std::sync::Arc;
 
 use axum::extract::rejection::JsonRejection;
-use axum::routing::get;
+use axum::extract::Path;
+use axum::routing::{delete, get};
 use axum::{extract::State, http::StatusCode, routing::post, Json, Router};
 use goose::recipe::local_recipes;
+use goose::recipe::registry::{InstallReport, RecipeRegistry, RecipeSource, RegistryEntry};
 use goose::recipe::validate_recipe::validate_recipe_template_from_content;
 use goose::recipe::{strip_error_location, Recipe};
 use goose::{recipe_deeplink, slash_commands};
@@ -36,7 +38,7 @@ fn clean_data_error(err: &axum::extract::rejection::JsonDataError) -> String {
         .unwrap_or_else(|| message.to_string())
 }
 
-use crate::routes::errors::ErrorResponse;
+use crate::routes::errors::{ErrorCode, ErrorResponse};
 use crate::routes::recipe_utils::{
     get_all_recipes_manifests, get_recipe_file_path_by_id, short_id_from_path, validate_recipe,
     RecipeManifest, RecipeValidationError,
@@ -146,6 +148,21 @@ pub struct RecipeToYamlResponse {
     yaml: String,
 }
 
+#[derive(Debug, Deserialize, ToSchema)]
+pub struct InstallRecipeRequest {
+    source: RecipeSource,
+    /// Registry name; defaults to the recipe file name
+    name: Option<String>,
+    /// Expected SHA-256 of the recipe file
+    checksum: Option<String>,
+}
+
+#[derive(Debug, Default, Deserialize, ToSchema)]
+pub struct ChangeRecipeVersionRequest {
+    /// Installed version to switch to; upgrades without one fetch the source again
+    version: Option<String>,
+}
+
 #[utoipa::path(
     post,
     path = "/recipes/create",
@@ -446,10 +463,7 @@ async fn save_recipe(
     let request: SaveRecipeRequest = deserialize_save_recipe_request(raw_json)?;
     let has_security_warnings = request.recipe.check_for_security_warnings();
     if has_security_warnings {
-        return Err(ErrorResponse {
-            message: "This recipe contains hidden characters that could be malicious. Please remove them before trying to save.".to_string(),
-            status: StatusCode::BAD_REQUEST,
-        });
+        return Err(ErrorResponse::bad_request("This recipe contains hidden characters that could be malicious. Please remove them before trying to save."));
     }
     ensure_recipe_valid(&request.recipe)?;
 
@@ -462,26 +476,17 @@ async fn save_recipe(
         Ok(save_file_path) => Ok(Json(SaveRecipeResponse {
             id: short_id_from_path(&save_file_path.display().to_string()),
         })),
-        Err(e) => Err(ErrorResponse {
-            message: e.to_string(),
-            status: StatusCode::INTERNAL_SERVER_ERROR,
-        }),
+        Err(e) => Err(ErrorResponse::internal(e.to_string())),
     }
 }
 
 fn json_rejection_to_error_response(rejection: JsonRejection) -> ErrorResponse {
-    ErrorResponse {
-        message: format_json_rejection_message(&rejection),
-        status: StatusCode::BAD_REQUEST,
-    }
+    ErrorResponse::bad_request(format_json_rejection_message(&rejection))
 }
 
 fn ensure_recipe_valid(recipe: &Recipe) -> Result<(), ErrorResponse> {
     if let Err(err) = validate_recipe(recipe) {
-        return Err(ErrorResponse {
-            message: err.message,
-            status: err.status,
-        });
+        return Err(err.into());
     }
     Ok(())
 }
@@ -502,10 +507,7 @@ fn deserialize_save_recipe_request(value: Value) -> Result<SaveRecipeRequest, Er
                 inner
             )
         };
-        ErrorResponse {
-            message,
-            status: StatusCode::BAD_REQUEST,
-        }
+        ErrorResponse::bad_request(message)
     })
 }
 
@@ -524,10 +526,8 @@ async fn parse_recipe(
     Json(request): Json<ParseRecipeRequest>,
 ) -> Result<Json<ParseRecipeResponse>, ErrorResponse> {
     let recipe = validate_recipe_template_from_content(&request.content, None).map_err(|e| {
-        ErrorResponse {
-            message: format!("Invalid recipe format: {}", e),
-            status: StatusCode::BAD_REQUEST,
-        }
+        ErrorResponse::bad_request(format!("Invalid recipe format: {}", e))
+            .with_code(ErrorCode::InvalidRecipe)
     })?;
 
     Ok(Json(ParseRecipeResponse { recipe }))
@@ -546,14 +546,123 @@ async fn parse_recipe(
 async fn recipe_to_yaml(
     Json(request): Json<RecipeToYamlRequest>,
 ) -> Result<Json<RecipeToYamlResponse>, ErrorResponse> {
-    let yaml = request.recipe.to_yaml().map_err(|e| ErrorResponse {
-        message: format!("Failed to convert recipe to YAML: {}", e),
-        status: StatusCode::BAD_REQUEST,
+    let yaml = request.recipe.to_yaml().map_err(|e| {
+        ErrorResponse::bad_request(format!("Failed to convert recipe to YAML: {}", e))
     })?;
 
     Ok(Json(RecipeToYamlResponse { yaml }))
 }
 
+#[utoipa::path(
+    get,
+    path = "/recipes/registry",
+    responses(
+        (status = 200, description = "Recipes installed through the registry", body = [RegistryEntry]),
+        (status = 500, description = "Internal server error", body = ErrorResponse)
+    ),
+    tag = "Recipe Management"
+)]
+async fn list_registry() -> Result<Json<Vec<RegistryEntry>>, ErrorResponse> {
+    Ok(Json(RecipeRegistry::default().list()?))
+}
+
+#[utoipa::path(
+    post,
+    path = "/recipes/registry/install",
+    request_body = InstallRecipeRequest,
+    responses(
+        (status = 200, description = "Recipe installed and made active", body = InstallReport),
+        (status = 400, description = "Invalid recipe or version", body = ErrorResponse),
+        (status = 409, description = "Checksum mismatch or version already installed with different content", body = ErrorResponse),
+        (status = 422, description = "Declared extension dependencies are not met", body = ErrorResponse),
+        (status = 502, description = "Failed to fetch the recipe", body = ErrorResponse)
+    ),
+    tag = "Recipe Management"
+)]
+async fn install_registry_recipe(
+    Json(request): Json<InstallRecipeRequest>,
+) -> Result<Json<InstallReport>, ErrorResponse> {
+    Ok(Json(
+        RecipeRegistry::default()
+            .install(
+                request.source,
+                request.name.as_deref(),
+                request.checksum.as_deref(),
+            )
+            .await?,
+    ))
+}
+
+#[utoipa::path(
+    post,
+    path = "/recipes/registry/{name}/upgrade",
+    request_body = ChangeRecipeVersionRequest,
+    params(
+        ("name" = String, Path, description = "Registry name of the recipe")
+    ),
+    responses(
+        (status = 200, description = "Recipe upgraded", body = InstallReport),
+        (status = 400, description = "Target is not newer than the active version", body = ErrorResponse),
+        (status = 404, description = "Recipe not installed", body = ErrorResponse),
+        (status = 409, description = "Stored version no longer matches its checksum", body = ErrorResponse),
+        (status = 502, description = "Failed to fetch the recipe", body = ErrorResponse)
+    ),
+    tag = "Recipe Management"
+)]
+async fn upgrade_registry_recipe(
+    Path(name): Path<String>,
+    request: Option<Json<ChangeRecipeVersionRequest>>,
+) -> Result<Json<InstallReport>, ErrorResponse> {
+    let Json(request) = request.unwrap_or_default();
+    Ok(Json(
+        RecipeRegistry::default()
+            .upgrade(&name, request.version.as_deref())
+            .await?,
+    ))
+}
+
+#[utoipa::path(
+    post,
+    path = "/recipes/registry/{name}/downgrade",
+    request_body = ChangeRecipeVersionRequest,
+    params(
+        ("name" = String, Path, description = "Registry name of the recipe")
+    ),
+    responses(
+        (status = 200, description = "Older version made active", body = InstallReport),
+        (status = 400, description = "No version given, or it is not older than the active one", body = ErrorResponse),
+        (status = 404, description = "Recipe or version not installed", body = ErrorResponse),
+        (status = 409, description = "Stored version no longer matches its checksum", body = ErrorResponse)
+    ),
+    tag = "Recipe Management"
+)]
+async fn downgrade_registry_recipe(
+    Path(name): Path<String>,
+    Json(request): Json<ChangeRecipeVersionRequest>,
+) -> Result<Json<InstallReport>, ErrorResponse> {
+    let version = request
+        .version
+        .ok_or_else(|| ErrorResponse::bad_request("A version to downgrade to is required"))?;
+    Ok(Json(RecipeRegistry::default().downgrade(&name, &version)?))
+}
+
+#[utoipa::path(
+    delete,
+    path = "/recipes/registry/{name}",
+    params(
+        ("name" = String, Path, description = "Registry name of the recipe")
+    ),
+    responses(
+        (status = 204, description = "All versions removed"),
+        (status = 404, description = "Recipe not installed", body = ErrorResponse)
+    ),
+    tag = "Recipe Management"
+)]
+async fn uninstall_registry_recipe(Path(name): Path<String>) -> Result<StatusCode, ErrorResponse> {
+    RecipeRegistry::default().uninstall(&name)?;
+    Ok(StatusCode::NO_CONTENT)
+}
+
 pub fn routes(state: Arc<AppState>) -> Router {
     Router::new()
         .route("/recipes/create", post(create_recipe))
@@ -567,6 +676,20 @@ pub fn routes(state: Arc<AppState>) -> Router {
         .route("/recipes/save", post(save_recipe))
         .route("/recipes/parse", post(parse_recipe))
         .route("/recipes/to-yaml", post(recipe_to_yaml))
+        .route("/recipes/registry", get(list_registry))
+        .route("/recipes/registry/install", post(install_registry_recipe))
+        .route(
+            "/recipes/registry/{name}/upgrade",
+            post(upgrade_registry_recipe),
+        )
+        .route(
+            "/recipes/registry/{name}/downgrade",
+            post(downgrade_registry_recipe),
+        )
+        .route(
+            "/recipes/registry/{name}",
+            delete(uninstall_registry_recipe),
+        )
         .with_state(state)
 }
 
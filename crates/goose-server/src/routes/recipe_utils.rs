@@ -5,7 +5,7 @@ use std::hash::{Hash, Hasher};
 use std::path::PathBuf;
 use std::sync::Arc;
 
-use crate::routes::errors::ErrorResponse;
+use crate::routes::errors::{ErrorCode, ErrorResponse};
 use crate::state::AppState;
 use anyhow::Result;
 use axum::http::StatusCode;
@@ -25,6 +25,12 @@ pub struct RecipeValidationError {
     pub message: String,
 }
 
+impl From<RecipeValidationError> for ErrorResponse {
+    fn from(err: RecipeValidationError) -> Self {
+        ErrorResponse::new(err.status, err.message).with_code(ErrorCode::InvalidRecipe)
+    }
+}
+
 #[derive(Debug, Serialize, ToSchema)]
 pub struct RecipeManifest {
     pub id: String,
@@ -119,19 +125,14 @@ pub async fn get_recipe_file_path_by_id(
 
     state.set_recipe_file_hash_map(recipe_file_hash_map).await;
 
-    resolved_path.ok_or_else(|| ErrorResponse {
-        message: format!("Recipe not found: {}", id),
-        status: StatusCode::NOT_FOUND,
-    })
+    resolved_path.ok_or_else(|| ErrorResponse::not_found(format!("Recipe not found: {}", id)))
 }
 
 pub async fn load_recipe_by_id(state: &AppState, id: &str) -> Result<Recipe, ErrorResponse> {
     let path = get_recipe_file_path_by_id(state, id).await?;
 
-    Recipe::from_file_path(&path).map_err(|err| ErrorResponse {
-        message: format!("Failed to load recipe: {}", err),
-        status: StatusCode::INTERNAL_SERVER_ERROR,
-    })
+    Recipe::from_file_path(&path)
+        .map_err(|err| ErrorResponse::internal(format!("Failed to load recipe: {}", err)))
 }
 
 pub async fn build_recipe_with_parameter_values(
@@ -166,9 +167,18 @@ pub async fn apply_recipe_to_agent(
         .apply_recipe_components(
             recipe.sub_recipes.clone(),
             recipe.response.clone(),
+            recipe.validation.clone(),
             include_final_output_tool,
         )
         .await;
+    agent
+        .set_system_prompt_template(
+            recipe
+                .settings
+                .as_ref()
+                .and_then(|settings| settings.system_prompt_template.clone()),
+        )
+        .await;
 
     recipe.instructions.as_ref().map(|instructions| {
         let mut context: HashMap<&str, Value> = HashMap::new();
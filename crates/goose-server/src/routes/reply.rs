@@ -1,4 +1,5 @@
-use crate::routes::errors::ErrorResponse;
+use crate::routes::enterprise::enforce_team_quota;
+use crate::routes::errors::{ErrorCode, ErrorResponse};
 use crate::state::AppState;
 #[cfg(test)]
 use axum::http::StatusCode;
@@ -10,12 +11,17 @@ use axum::{
     Json, Router,
 };
 use bytes::Bytes;
+use chrono::{DateTime, Utc};
 use futures::{stream::StreamExt, Stream};
+use goose::agents::stream_chunking::chunk_events;
 use goose::agents::{AgentEvent, SessionConfig};
+use goose::connectivity::{self, ConnectivityStatus};
 use goose::conversation::message::{Message, MessageContent, TokenState};
 use goose::conversation::Conversation;
-use goose::session::SessionManager;
-use rmcp::model::ServerNotification;
+use goose::session::{ExtensionState, SessionConflict, SessionManager};
+use goose::tts::providers::prepare_text;
+use goose::tts::settings::SpeechSettings;
+use rmcp::model::{Role, ServerNotification};
 use serde::{Deserialize, Serialize};
 use std::{
     convert::Infallible,
@@ -84,6 +90,10 @@ pub struct ChatRequest {
     session_id: String,
     recipe_name: Option<String>,
     recipe_version: Option<String>,
+    /// Session revision the client last saw. The reply is refused if the
+    /// conversation has been written since, e.g. by another replica.
+    #[serde(default)]
+    expected_revision: Option<i64>,
 }
 
 pub struct SseResponse {
@@ -129,6 +139,7 @@ pub enum MessageEvent {
     },
     Error {
         error: String,
+        code: ErrorCode,
     },
     Finish {
         reason: String,
@@ -138,6 +149,15 @@ pub enum MessageEvent {
         model: String,
         mode: String,
     },
+    /// goosed is draining; the turn is cancelled if still running at the deadline
+    ServerRestarting {
+        deadline: DateTime<Utc>,
+    },
+    /// goose went offline or came back; sent at the start of a turn too if
+    /// it is already offline
+    Connectivity {
+        status: ConnectivityStatus,
+    },
     Notification {
         request_id: String,
         #[schema(value_type = Object)]
@@ -146,6 +166,11 @@ pub enum MessageEvent {
     UpdateConversation {
         conversation: Conversation,
     },
+    /// The session reads responses aloud; fetch the audio for this message
+    /// from `/tts/sessions/{session_id}/messages/{message_id}`
+    Speak {
+        message_id: String,
+    },
     Ping,
 }
 
@@ -171,6 +196,22 @@ async fn get_token_state(session_manager: &SessionManager, session_id: &str) ->
         .unwrap_or_default()
 }
 
+/// The id of `response` when the session has asked for responses to be read
+/// aloud and it has something to say.
+async fn response_to_speak(
+    session_manager: &SessionManager,
+    session_id: &str,
+    response: Option<&Message>,
+) -> Option<String> {
+    let response = response?;
+    let session = session_manager.get_session(session_id, false).await.ok()?;
+    let settings = SpeechSettings::from_extension_data(&session.extension_data)?;
+    if !settings.speak_responses || prepare_text(&response.as_concat_text()).is_empty() {
+        return None;
+    }
+    response.id.clone()
+}
+
 async fn stream_event(
     event: MessageEvent,
     tx: &mpsc::Sender<String>,
@@ -199,6 +240,7 @@ async fn stream_event(
          body = MessageEvent,
          content_type = "text/event-stream"),
         (status = 424, description = "Agent not initialized"),
+        (status = 503, description = "Emergency stop is active or the server is draining"),
         (status = 500, description = "Internal server error")
     )
 )]
@@ -216,6 +258,7 @@ pub async fn reply(
     );
 
     let session_id = request.session_id.clone();
+    enforce_team_quota(&state, &session_id).await?;
 
     if let Some(recipe_name) = request.recipe_name.clone() {
         if state.mark_recipe_run_if_absent(&session_id).await {
@@ -238,14 +281,21 @@ pub async fn reply(
     let (tx, rx) = mpsc::channel(100);
     let stream = ReceiverStream::new(rx);
     let cancel_token = CancellationToken::new();
+    let emergency_registration = state.emergency.register_reply(cancel_token.clone())?;
+    let mut drain_registration = state
+        .drain
+        .register_turn(session_id.clone(), cancel_token.clone())?;
 
     let user_message = request.user_message;
     let conversation_so_far = request.conversation_so_far;
+    let expected_revision = request.expected_revision;
 
     let task_cancel = cancel_token.clone();
     let task_tx = tx.clone();
 
     drop(tokio::spawn(async move {
+        let _emergency_registration = emergency_registration;
+        let mut restart_notified = false;
         let agent = match state.get_agent(session_id.clone()).await {
             Ok(agent) => agent,
             Err(e) => {
@@ -253,6 +303,7 @@ pub async fn reply(
                 let _ = stream_event(
                     MessageEvent::Error {
                         error: format!("Failed to get session agent: {}", e),
+                        code: ErrorCode::from_anyhow(&e),
                     },
                     &task_tx,
                     &task_cancel,
@@ -262,13 +313,18 @@ pub async fn reply(
             }
         };
 
-        let session = match state.session_manager().get_session(&session_id, true).await {
+        let session = match state
+            .session_manager()
+            .get_session(&session_id, false)
+            .await
+        {
             Ok(metadata) => metadata,
             Err(e) => {
                 tracing::error!("Failed to read session for {}: {}", session_id, e);
                 let _ = stream_event(
                     MessageEvent::Error {
                         error: format!("Failed to read session: {}", e),
+                        code: ErrorCode::Internal,
                     },
                     &task_tx,
                     &cancel_token,
@@ -285,25 +341,75 @@ pub async fn reply(
             retry_config: None,
         };
 
-        let mut all_messages = match conversation_so_far {
-            Some(history) => {
-                let conv = Conversation::new_unvalidated(history);
-                if let Err(e) = state
+        let history = conversation_so_far.map(Conversation::new_unvalidated);
+        let written = match (&history, expected_revision) {
+            (Some(conv), Some(revision)) => state
+                .session_manager()
+                .replace_conversation_if(&session_id, conv, revision)
+                .await
+                .map(|_| ()),
+            (Some(conv), None) => {
+                state
                     .session_manager()
-                    .replace_conversation(&session_id, &conv)
+                    .replace_conversation(&session_id, conv)
                     .await
-                {
-                    tracing::warn!(
-                        "Failed to replace session conversation for {}: {}",
-                        session_id,
-                        e
-                    );
-                }
-                conv
             }
-            None => session.conversation.unwrap_or_default(),
+            (None, Some(revision)) if revision != session.revision => Err(SessionConflict {
+                session_id: session_id.clone(),
+                expected: revision,
+                current: session.revision,
+            }
+            .into()),
+            (None, _) => Ok(()),
+        };
+        if let Err(e) = written {
+            if let Some(conflict) = e.downcast_ref::<SessionConflict>() {
+                let _ = stream_event(
+                    MessageEvent::Error {
+                        error: conflict.to_string(),
+                        code: ErrorCode::Conflict,
+                    },
+                    &task_tx,
+                    &cancel_token,
+                )
+                .await;
+                return;
+            }
+            tracing::warn!(
+                "Failed to replace session conversation for {}: {}",
+                session_id,
+                e
+            );
+        }
+
+        // Without history from the client only the agent's window is loaded;
+        // a checkpoint then rewrites just that part
+        let mut windowed = history.is_none();
+        let mut all_messages = match history {
+            Some(history) => history,
+            None => match state
+                .session_manager()
+                .get_conversation_window(&session_id)
+                .await
+            {
+                Ok(window) => window,
+                Err(e) => {
+                    tracing::error!("Failed to read conversation for {}: {}", session_id, e);
+                    let _ = stream_event(
+                        MessageEvent::Error {
+                            error: format!("Failed to read session: {}", e),
+                            code: ErrorCode::Internal,
+                        },
+                        &task_tx,
+                        &cancel_token,
+                    )
+                    .await;
+                    return;
+                }
+            },
         };
         all_messages.push(user_message.clone());
+        let mut last_response: Option<Message> = None;
 
         let mut stream = match agent
             .reply(
@@ -313,12 +419,13 @@ pub async fn reply(
             )
             .await
         {
-            Ok(stream) => stream,
+            Ok(stream) => chunk_events(stream),
             Err(e) => {
                 tracing::error!("Failed to start reply stream: {:?}", e);
                 stream_event(
                     MessageEvent::Error {
                         error: e.to_string(),
+                        code: ErrorCode::from_anyhow(&e),
                     },
                     &task_tx,
                     &cancel_token,
@@ -328,6 +435,10 @@ pub async fn reply(
             }
         };
 
+        let mut connectivity = connectivity::global().subscribe();
+        if !connectivity.borrow().online {
+            connectivity.mark_changed();
+        }
         let mut heartbeat_interval = tokio::time::interval(Duration::from_millis(500));
         loop {
             tokio::select! {
@@ -338,6 +449,17 @@ pub async fn reply(
                 _ = heartbeat_interval.tick() => {
                     stream_event(MessageEvent::Ping, &tx, &cancel_token).await;
                 }
+                Ok(()) = drain_registration.restarting.changed(), if !restart_notified => {
+                    let deadline = *drain_registration.restarting.borrow_and_update();
+                    if let Some(deadline) = deadline {
+                        restart_notified = true;
+                        stream_event(MessageEvent::ServerRestarting { deadline }, &tx, &cancel_token).await;
+                    }
+                }
+                Ok(()) = connectivity.changed() => {
+                    let status = connectivity.borrow_and_update().clone();
+                    stream_event(MessageEvent::Connectivity { status }, &tx, &cancel_token).await;
+                }
                 response = timeout(Duration::from_millis(500), stream.next()) => {
                     match response {
                         Ok(Some(Ok(AgentEvent::Message(message)))) => {
@@ -346,6 +468,9 @@ pub async fn reply(
                             }
 
                             all_messages.push(message.clone());
+                            if message.role == Role::Assistant {
+                                last_response = Some(message.clone());
+                            }
 
                             let token_state = get_token_state(state.session_manager(), &session_id).await;
 
@@ -353,6 +478,7 @@ pub async fn reply(
                         }
                         Ok(Some(Ok(AgentEvent::HistoryReplaced(new_messages)))) => {
                             all_messages = new_messages.clone();
+                            windowed = false;
                             stream_event(MessageEvent::UpdateConversation {conversation: new_messages}, &tx, &cancel_token).await;
 
                         }
@@ -371,6 +497,7 @@ pub async fn reply(
                             stream_event(
                                 MessageEvent::Error {
                                     error: e.to_string(),
+                                    code: ErrorCode::from_anyhow(&e),
                                 },
                                 &tx,
                                 &cancel_token,
@@ -391,9 +518,30 @@ pub async fn reply(
             }
         }
 
+        if drain_registration.interrupted() {
+            let manager = state.session_manager();
+            let checkpoint = if windowed {
+                manager
+                    .replace_conversation_window(&session_id, &all_messages)
+                    .await
+            } else {
+                manager
+                    .replace_conversation(&session_id, &all_messages)
+                    .await
+            };
+            match checkpoint {
+                Ok(()) => tracing::info!("Checkpointed session {} before shutdown", session_id),
+                Err(e) => tracing::error!("Failed to checkpoint session {}: {}", session_id, e),
+            }
+        }
+
         let session_duration = session_start.elapsed();
 
-        if let Ok(session) = state.session_manager().get_session(&session_id, true).await {
+        if let Ok(session) = state
+            .session_manager()
+            .get_session(&session_id, false)
+            .await
+        {
             let total_tokens = session.total_tokens.unwrap_or(0);
             tracing::info!(
                 counter.goose.session_completions = 1,
@@ -441,6 +589,12 @@ pub async fn reply(
             );
         }
 
+        if let Some(message_id) =
+            response_to_speak(state.session_manager(), &session_id, last_response.as_ref()).await
+        {
+            stream_event(MessageEvent::Speak { message_id }, &task_tx, &cancel_token).await;
+        }
+
         let final_token_state = get_token_state(state.session_manager(), &session_id).await;
 
         let _ = stream_event(
@@ -493,6 +647,7 @@ mod tests {
                         session_id: "test-session".to_string(),
                         recipe_name: None,
                         recipe_version: None,
+                        expected_revision: None,
                     })
                     .unwrap(),
                 ))
@@ -13,7 +13,9 @@ use crate::routes::errors::ErrorResponse;
 use crate::routes::recipe_utils::validate_recipe;
 use crate::state::AppState;
 use goose::recipe::Recipe;
-use goose::scheduler::{get_default_scheduled_recipes_dir, ScheduledJob};
+use goose::scheduler::{
+    get_default_scheduled_recipes_dir, group_job_chains, JobChain, JobDependency, ScheduledJob,
+};
 
 fn validate_schedule_id(id: &str) -> Result<(), ErrorResponse> {
     let is_valid = !id.is_empty()
@@ -35,6 +37,8 @@ pub struct CreateScheduleRequest {
     id: String,
     recipe: Recipe,
     cron: String,
+    #[serde(default)]
+    depends_on: Vec<JobDependency>,
 }
 
 #[derive(Deserialize, Serialize, utoipa::ToSchema)]
@@ -47,6 +51,11 @@ pub struct ListSchedulesResponse {
     jobs: Vec<ScheduledJob>,
 }
 
+#[derive(Serialize, utoipa::ToSchema)]
+pub struct ListChainsResponse {
+    chains: Vec<JobChain>,
+}
+
 // Response for the kill endpoint
 #[derive(Serialize, utoipa::ToSchema)]
 pub struct KillJobResponse {
@@ -116,10 +125,7 @@ async fn create_schedule(
         ));
     }
     if let Err(err) = validate_recipe(&req.recipe) {
-        return Err(ErrorResponse {
-            message: err.message,
-            status: err.status,
-        });
+        return Err(err.into());
     }
     let scheduled_recipes_dir = get_default_scheduled_recipes_dir().map_err(|e| {
         ErrorResponse::internal(format!("Failed to get scheduled recipes directory: {}", e))
@@ -141,8 +147,12 @@ async fn create_schedule(
         last_run: None,
         currently_running: false,
         paused: false,
+        paused_by_upstream: false,
         current_session_id: None,
         process_start_time: None,
+        depends_on: req.depends_on,
+        last_status: None,
+        last_finished: None,
     };
 
     let scheduler = state.scheduler();
@@ -156,10 +166,13 @@ async fn create_schedule(
             goose::scheduler::SchedulerError::RecipeLoadError(msg) => {
                 ErrorResponse::bad_request(format!("Recipe load error: {}", msg))
             }
-            goose::scheduler::SchedulerError::JobIdExists(msg) => ErrorResponse {
-                message: format!("Job ID already exists: {}", msg),
-                status: StatusCode::CONFLICT,
-            },
+            goose::scheduler::SchedulerError::JobIdExists(msg) => ErrorResponse::new(
+                StatusCode::CONFLICT,
+                format!("Job ID already exists: {}", msg),
+            ),
+            goose::scheduler::SchedulerError::InvalidDependency(msg) => {
+                ErrorResponse::bad_request(msg)
+            }
             _ => ErrorResponse::internal(format!("Error creating schedule: {}", e)),
         })?;
 
@@ -185,6 +198,27 @@ async fn list_schedules(
     Ok(Json(ListSchedulesResponse { jobs }))
 }
 
+#[utoipa::path(
+    get,
+    path = "/schedule/chains",
+    responses(
+        (status = 200, description = "Scheduled jobs grouped into dependency chains", body = ListChainsResponse),
+        (status = 500, description = "Internal server error")
+    ),
+    tag = "schedule"
+)]
+#[axum::debug_handler]
+async fn list_schedule_chains(
+    State(state): State<Arc<AppState>>,
+) -> Result<Json<ListChainsResponse>, ErrorResponse> {
+    let scheduler = state.scheduler();
+
+    let jobs = scheduler.list_scheduled_jobs().await;
+    Ok(Json(ListChainsResponse {
+        chains: group_job_chains(&jobs),
+    }))
+}
+
 #[utoipa::path(
     delete,
     path = "/schedule/delete/{id}",
@@ -194,6 +228,7 @@ async fn list_schedules(
     responses(
         (status = 204, description = "Scheduled job deleted successfully"),
         (status = 404, description = "Scheduled job not found"),
+        (status = 409, description = "Other scheduled jobs depend on this one"),
         (status = 500, description = "Internal server error")
     ),
     tag = "schedule"
@@ -211,6 +246,9 @@ async fn delete_schedule(
             goose::scheduler::SchedulerError::JobNotFound(msg) => {
                 ErrorResponse::not_found(format!("Schedule not found: {}", msg))
             }
+            goose::scheduler::SchedulerError::InvalidDependency(msg) => {
+                ErrorResponse::new(StatusCode::CONFLICT, msg)
+            }
             _ => ErrorResponse::internal(format!("Error deleting schedule: {}", e)),
         })?;
     Ok(StatusCode::NO_CONTENT)
@@ -225,7 +263,8 @@ async fn delete_schedule(
     responses(
         (status = 200, description = "Scheduled job triggered successfully, returns new session ID", body = RunNowResponse),
         (status = 404, description = "Scheduled job not found"),
-        (status = 500, description = "Internal server error when trying to run the job")
+        (status = 500, description = "Internal server error when trying to run the job"),
+        (status = 503, description = "Emergency stop is active")
     ),
     tag = "schedule"
 )]
@@ -234,6 +273,7 @@ async fn run_now_handler(
     State(state): State<Arc<AppState>>,
     Path(id): Path<String>,
 ) -> Result<Json<RunNowResponse>, ErrorResponse> {
+    state.emergency.ensure_running()?;
     let scheduler = state.scheduler();
 
     let (recipe_display_name, recipe_version_opt) = if let Some(job) = scheduler
@@ -398,7 +438,8 @@ async fn pause_schedule(
     responses(
         (status = 204, description = "Scheduled job unpaused successfully"),
         (status = 404, description = "Scheduled job not found"),
-        (status = 500, description = "Internal server error")
+        (status = 500, description = "Internal server error"),
+        (status = 503, description = "Emergency stop is active")
     ),
     tag = "schedule"
 )]
@@ -407,6 +448,7 @@ async fn unpause_schedule(
     State(state): State<Arc<AppState>>,
     Path(id): Path<String>,
 ) -> Result<StatusCode, ErrorResponse> {
+    state.emergency.ensure_running()?;
     let scheduler = state.scheduler();
 
     scheduler.unpause_schedule(&id).await.map_err(|e| match e {
@@ -454,6 +496,9 @@ async fn update_schedule(
             goose::scheduler::SchedulerError::CronParseError(msg) => {
                 ErrorResponse::bad_request(format!("Invalid cron expression: {}", msg))
             }
+            goose::scheduler::SchedulerError::InvalidDependency(msg) => {
+                ErrorResponse::bad_request(format!("Cannot update schedule: {}", msg))
+            }
             _ => ErrorResponse::internal(format!("Error updating schedule: {}", e)),
         })?;
 
@@ -546,6 +591,7 @@ pub fn routes(state: Arc<AppState>) -> Router {
     Router::new()
         .route("/schedule/create", post(create_schedule))
         .route("/schedule/list", get(list_schedules))
+        .route("/schedule/chains", get(list_schedule_chains))
         .route("/schedule/delete/{id}", delete(delete_schedule)) // Corrected
         .route("/schedule/{id}", put(update_schedule))
         .route("/schedule/{id}/run_now", post(run_now_handler)) // Corrected
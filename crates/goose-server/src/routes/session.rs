@@ -1,6 +1,7 @@
 use crate::routes::errors::ErrorResponse;
 use crate::routes::recipe_utils::{apply_recipe_to_agent, build_recipe_with_parameter_values};
 use crate::state::AppState;
+use axum::body::Bytes;
 use axum::extract::{DefaultBodyLimit, State};
 use axum::routing::post;
 use axum::{
@@ -9,11 +10,19 @@ use axum::{
     routing::{delete, get, put},
     Json, Router,
 };
-use goose::agents::ExtensionConfig;
+use goose::agents::{ExtensionConfig, ReviewCycle, ReviewHistory, ReviewTranscript};
+use goose::config::profiles::{get_profile, ActiveProfileState};
+use goose::conversation::repair::{ConversationFixer, RepairDiagnostics};
 use goose::recipe::Recipe;
+use goose::session::attachments::Attachment;
 use goose::session::extension_data::ExtensionState;
+use goose::session::redaction::{redact_conversation, RedactionReport, RedactionRequest};
+use goose::session::retention::{RetentionPolicy, RetentionReport};
 use goose::session::session_manager::SessionInsights;
-use goose::session::{EnabledExtensionsState, Session};
+use goose::session::turn_snapshots::{TurnSnapshot, TurnSummary};
+use goose::session::{
+    ArchivedFilter, EnabledExtensionsState, MessagePage, Session, SessionEnvironment, SessionFilter,
+};
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
 use std::sync::Arc;
@@ -33,6 +42,20 @@ pub struct UpdateSessionNameRequest {
     name: String,
 }
 
+#[derive(Deserialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct RunReviewRequest {
+    /// Task for the Player to carry out under Coach review
+    task: String,
+}
+
+#[derive(Deserialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct SwitchSessionProfileRequest {
+    /// Name of the settings profile to apply
+    name: String,
+}
+
 #[derive(Deserialize, ToSchema)]
 #[serde(rename_all = "camelCase")]
 pub struct UpdateSessionUserRecipeValuesRequest {
@@ -65,11 +88,33 @@ pub struct ForkResponse {
     session_id: String,
 }
 
+#[derive(Deserialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct ListSessionsQuery {
+    /// Comma-separated tags; sessions must carry all of them
+    tags: Option<String>,
+    /// Whether to exclude (default), include or only return archived sessions
+    archived: Option<ArchivedFilter>,
+}
+
+#[derive(Deserialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct UpdateSessionTagsRequest {
+    /// Replaces the session's tags; stored lowercase without duplicates
+    tags: Vec<String>,
+}
+
 const MAX_NAME_LENGTH: usize = 200;
+const MAX_TAGS: usize = 20;
+const MAX_TAG_LENGTH: usize = 50;
 
 #[utoipa::path(
     get,
     path = "/sessions",
+    params(
+        ("tags" = Option<String>, Query, description = "Comma-separated tags the sessions must all carry"),
+        ("archived" = Option<ArchivedFilter>, Query, description = "exclude (default), include or only")
+    ),
     responses(
         (status = 200, description = "List of available sessions retrieved successfully", body = SessionListResponse),
         (status = 401, description = "Unauthorized - Invalid or missing API key"),
@@ -82,10 +127,18 @@ const MAX_NAME_LENGTH: usize = 200;
 )]
 async fn list_sessions(
     State(state): State<Arc<AppState>>,
+    axum::extract::Query(params): axum::extract::Query<ListSessionsQuery>,
 ) -> Result<Json<SessionListResponse>, StatusCode> {
+    let filter = SessionFilter {
+        tags: params
+            .tags
+            .map(|tags| tags.split(',').map(str::to_string).collect())
+            .unwrap_or_default(),
+        archived: params.archived.unwrap_or_default(),
+    };
     let sessions = state
         .session_manager()
-        .list_sessions()
+        .list_sessions_filtered(&filter)
         .await
         .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
 
@@ -121,6 +174,56 @@ async fn get_session(
 
     Ok(Json(session))
 }
+
+const DEFAULT_PAGE_SIZE: usize = 50;
+const MAX_PAGE_SIZE: usize = 500;
+
+#[derive(Deserialize, ToSchema)]
+pub struct MessagePageQuery {
+    /// Cursor from the previous page; the latest messages without it
+    before: Option<i64>,
+    limit: Option<usize>,
+}
+
+#[utoipa::path(
+    get,
+    path = "/sessions/{session_id}/messages",
+    params(
+        ("session_id" = String, Path, description = "Unique identifier for the session"),
+        ("before" = Option<i64>, Query, description = "Cursor from the previous page"),
+        ("limit" = Option<usize>, Query, description = "Messages per page (default 50, at most 500)")
+    ),
+    responses(
+        (status = 200, description = "Page of the session's messages, oldest first", body = MessagePage),
+        (status = 401, description = "Unauthorized - Invalid or missing API key"),
+        (status = 404, description = "Session not found"),
+        (status = 500, description = "Internal server error")
+    ),
+    security(
+        ("api_key" = [])
+    ),
+    tag = "Session Management"
+)]
+async fn get_session_messages(
+    State(state): State<Arc<AppState>>,
+    Path(session_id): Path<String>,
+    axum::extract::Query(query): axum::extract::Query<MessagePageQuery>,
+) -> Result<Json<MessagePage>, StatusCode> {
+    let manager = state.session_manager();
+    manager
+        .get_session(&session_id, false)
+        .await
+        .map_err(|_| StatusCode::NOT_FOUND)?;
+    let limit = query
+        .limit
+        .unwrap_or(DEFAULT_PAGE_SIZE)
+        .clamp(1, MAX_PAGE_SIZE);
+    let page = manager
+        .get_messages_page(&session_id, query.before, limit)
+        .await
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+    Ok(Json(page))
+}
 #[utoipa::path(
     get,
     path = "/sessions/insights",
@@ -188,6 +291,331 @@ async fn update_session_name(
     Ok(StatusCode::OK)
 }
 
+#[utoipa::path(
+    put,
+    path = "/sessions/{session_id}/tags",
+    request_body = UpdateSessionTagsRequest,
+    params(
+        ("session_id" = String, Path, description = "Unique identifier for the session")
+    ),
+    responses(
+        (status = 200, description = "Session tags replaced", body = Session),
+        (status = 400, description = "Bad request - Too many tags (max 20) or a tag is too long (max 50 characters)"),
+        (status = 401, description = "Unauthorized - Invalid or missing API key"),
+        (status = 404, description = "Session not found"),
+        (status = 500, description = "Internal server error")
+    ),
+    security(
+        ("api_key" = [])
+    ),
+    tag = "Session Management"
+)]
+async fn update_session_tags(
+    State(state): State<Arc<AppState>>,
+    Path(session_id): Path<String>,
+    Json(request): Json<UpdateSessionTagsRequest>,
+) -> Result<Json<Session>, StatusCode> {
+    if request.tags.len() > MAX_TAGS
+        || request
+            .tags
+            .iter()
+            .any(|tag| tag.trim().chars().count() > MAX_TAG_LENGTH)
+    {
+        return Err(StatusCode::BAD_REQUEST);
+    }
+
+    let session_manager = state.session_manager();
+    session_manager
+        .get_session(&session_id, false)
+        .await
+        .map_err(|_| StatusCode::NOT_FOUND)?;
+    session_manager
+        .update(&session_id)
+        .tags(request.tags)
+        .apply()
+        .await
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+
+    let session = session_manager
+        .get_session(&session_id, false)
+        .await
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+    Ok(Json(session))
+}
+
+async fn set_archived(
+    state: &AppState,
+    session_id: &str,
+    archived: bool,
+) -> Result<Json<Session>, StatusCode> {
+    let session_manager = state.session_manager();
+    let session = session_manager
+        .get_session(session_id, false)
+        .await
+        .map_err(|_| StatusCode::NOT_FOUND)?;
+    if session.archived_at.is_some() == archived {
+        return Ok(Json(session));
+    }
+
+    session_manager
+        .update(session_id)
+        .archived(archived)
+        .apply()
+        .await
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+
+    let session = session_manager
+        .get_session(session_id, false)
+        .await
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+    Ok(Json(session))
+}
+
+#[utoipa::path(
+    post,
+    path = "/sessions/{session_id}/archive",
+    params(
+        ("session_id" = String, Path, description = "Unique identifier for the session")
+    ),
+    responses(
+        (status = 200, description = "Session archived; archiving twice keeps the original time", body = Session),
+        (status = 401, description = "Unauthorized - Invalid or missing API key"),
+        (status = 404, description = "Session not found"),
+        (status = 500, description = "Internal server error")
+    ),
+    security(
+        ("api_key" = [])
+    ),
+    tag = "Session Management"
+)]
+async fn archive_session(
+    State(state): State<Arc<AppState>>,
+    Path(session_id): Path<String>,
+) -> Result<Json<Session>, StatusCode> {
+    set_archived(&state, &session_id, true).await
+}
+
+#[utoipa::path(
+    delete,
+    path = "/sessions/{session_id}/archive",
+    params(
+        ("session_id" = String, Path, description = "Unique identifier for the session")
+    ),
+    responses(
+        (status = 200, description = "Session restored to the session list", body = Session),
+        (status = 401, description = "Unauthorized - Invalid or missing API key"),
+        (status = 404, description = "Session not found"),
+        (status = 500, description = "Internal server error")
+    ),
+    security(
+        ("api_key" = [])
+    ),
+    tag = "Session Management"
+)]
+async fn unarchive_session(
+    State(state): State<Arc<AppState>>,
+    Path(session_id): Path<String>,
+) -> Result<Json<Session>, StatusCode> {
+    set_archived(&state, &session_id, false).await
+}
+
+#[utoipa::path(
+    get,
+    path = "/sessions/retention",
+    responses(
+        (status = 200, description = "Configured retention policy", body = RetentionPolicy),
+        (status = 401, description = "Unauthorized - Invalid or missing API key")
+    ),
+    security(
+        ("api_key" = [])
+    ),
+    tag = "Session Management"
+)]
+async fn get_retention_policy() -> Json<RetentionPolicy> {
+    Json(RetentionPolicy::from_config())
+}
+
+#[utoipa::path(
+    post,
+    path = "/sessions/retention",
+    request_body(content = Option<RetentionPolicy>, description = "Policy to apply instead of the configured one"),
+    responses(
+        (status = 200, description = "Sessions archived and purged; purged sessions are exported first", body = RetentionReport),
+        (status = 401, description = "Unauthorized - Invalid or missing API key"),
+        (status = 500, description = "Internal server error")
+    ),
+    security(
+        ("api_key" = [])
+    ),
+    tag = "Session Management"
+)]
+async fn apply_retention(
+    State(state): State<Arc<AppState>>,
+    policy: Option<Json<RetentionPolicy>>,
+) -> Result<Json<RetentionReport>, StatusCode> {
+    let policy = policy
+        .map(|Json(policy)| policy)
+        .unwrap_or_else(RetentionPolicy::from_config);
+    let report = state
+        .session_manager()
+        .apply_retention(&policy)
+        .await
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+    Ok(Json(report))
+}
+
+#[utoipa::path(
+    get,
+    path = "/sessions/{session_id}/profile",
+    params(
+        ("session_id" = String, Path, description = "Unique identifier for the session")
+    ),
+    responses(
+        (status = 200, description = "Active settings profile and switch history", body = ActiveProfileState),
+        (status = 401, description = "Unauthorized - Invalid or missing API key"),
+        (status = 404, description = "Session not found")
+    ),
+    security(
+        ("api_key" = [])
+    ),
+    tag = "Session Management"
+)]
+async fn get_session_profile(
+    State(state): State<Arc<AppState>>,
+    Path(session_id): Path<String>,
+) -> Result<Json<ActiveProfileState>, StatusCode> {
+    let session = state
+        .session_manager()
+        .get_session(&session_id, false)
+        .await
+        .map_err(|_| StatusCode::NOT_FOUND)?;
+
+    Ok(Json(
+        ActiveProfileState::from_extension_data(&session.extension_data).unwrap_or_default(),
+    ))
+}
+
+#[utoipa::path(
+    put,
+    path = "/sessions/{session_id}/profile",
+    request_body = SwitchSessionProfileRequest,
+    params(
+        ("session_id" = String, Path, description = "Unique identifier for the session")
+    ),
+    responses(
+        (status = 200, description = "Profile applied to the session", body = ActiveProfileState),
+        (status = 401, description = "Unauthorized - Invalid or missing API key"),
+        (status = 404, description = "Session or profile not found"),
+        (status = 500, description = "Internal server error")
+    ),
+    security(
+        ("api_key" = [])
+    ),
+    tag = "Session Management"
+)]
+async fn switch_session_profile(
+    State(state): State<Arc<AppState>>,
+    Path(session_id): Path<String>,
+    Json(request): Json<SwitchSessionProfileRequest>,
+) -> Result<Json<ActiveProfileState>, ErrorResponse> {
+    let profile = get_profile(goose::config::Config::global(), &request.name)
+        .ok_or_else(|| ErrorResponse::not_found(format!("Unknown profile {}", request.name)))?;
+
+    let agent = state.get_agent(session_id.clone()).await?;
+    agent
+        .apply_settings_profile(&session_id, &profile, "api")
+        .await?;
+
+    let session = state
+        .session_manager()
+        .get_session(&session_id, false)
+        .await?;
+    Ok(Json(
+        ActiveProfileState::from_extension_data(&session.extension_data).unwrap_or_default(),
+    ))
+}
+
+#[utoipa::path(
+    get,
+    path = "/sessions/{session_id}/environment",
+    params(
+        ("session_id" = String, Path, description = "Unique identifier for the session")
+    ),
+    responses(
+        (status = 200, description = "Variables and secret references given to the session's tool processes", body = SessionEnvironment),
+        (status = 401, description = "Unauthorized - Invalid or missing API key"),
+        (status = 404, description = "Session not found")
+    ),
+    security(
+        ("api_key" = [])
+    ),
+    tag = "Session Management"
+)]
+async fn get_session_environment(
+    State(state): State<Arc<AppState>>,
+    Path(session_id): Path<String>,
+) -> Result<Json<SessionEnvironment>, StatusCode> {
+    let session = state
+        .session_manager()
+        .get_session(&session_id, false)
+        .await
+        .map_err(|_| StatusCode::NOT_FOUND)?;
+
+    Ok(Json(SessionEnvironment::for_session(&session)))
+}
+
+#[utoipa::path(
+    put,
+    path = "/sessions/{session_id}/environment",
+    request_body = SessionEnvironment,
+    params(
+        ("session_id" = String, Path, description = "Unique identifier for the session")
+    ),
+    responses(
+        (status = 200, description = "Environment saved; builtin tools use it from their next call, stdio extensions once restarted", body = SessionEnvironment),
+        (status = 400, description = "Invalid variable name or unknown secret"),
+        (status = 401, description = "Unauthorized - Invalid or missing API key"),
+        (status = 404, description = "Session not found"),
+        (status = 500, description = "Internal server error")
+    ),
+    security(
+        ("api_key" = [])
+    ),
+    tag = "Session Management"
+)]
+async fn update_session_environment(
+    State(state): State<Arc<AppState>>,
+    Path(session_id): Path<String>,
+    Json(environment): Json<SessionEnvironment>,
+) -> Result<Json<SessionEnvironment>, ErrorResponse> {
+    environment
+        .validate()
+        .and_then(|_| environment.resolve())
+        .map_err(|e| ErrorResponse::bad_request(e.to_string()))?;
+
+    let session = state
+        .session_manager()
+        .get_session(&session_id, false)
+        .await
+        .map_err(|_| ErrorResponse::not_found(format!("Session {} not found", session_id)))?;
+    let mut extension_data = session.extension_data;
+    environment.to_extension_data(&mut extension_data)?;
+    state
+        .session_manager()
+        .update(&session_id)
+        .extension_data(extension_data)
+        .apply()
+        .await?;
+
+    let agent = state.get_agent(session_id.clone()).await?;
+    agent
+        .extension_manager
+        .refresh_session_environment(&session_id)
+        .await?;
+
+    Ok(Json(environment))
+}
+
 #[utoipa::path(
     put,
     path = "/sessions/{session_id}/user_recipe_values",
@@ -218,23 +646,16 @@ async fn update_session_user_recipe_values(
         .user_recipe_values(Some(request.user_recipe_values))
         .apply()
         .await
-        .map_err(|err| ErrorResponse {
-            message: err.to_string(),
-            status: StatusCode::INTERNAL_SERVER_ERROR,
-        })?;
+        .map_err(|err| ErrorResponse::internal(err.to_string()))?;
 
     let session = state
         .session_manager()
         .get_session(&session_id, false)
         .await
-        .map_err(|err| ErrorResponse {
-            message: err.to_string(),
-            status: StatusCode::INTERNAL_SERVER_ERROR,
-        })?;
-    let recipe = session.recipe.ok_or_else(|| ErrorResponse {
-        message: "Recipe not found".to_string(),
-        status: StatusCode::NOT_FOUND,
-    })?;
+        .map_err(|err| ErrorResponse::internal(err.to_string()))?;
+    let recipe = session
+        .recipe
+        .ok_or_else(|| ErrorResponse::not_found("Recipe not found"))?;
 
     let user_recipe_values = session.user_recipe_values.unwrap_or_default();
     match build_recipe_with_parameter_values(&recipe, user_recipe_values).await {
@@ -242,23 +663,16 @@ async fn update_session_user_recipe_values(
             let agent = state
                 .get_agent_for_route(session_id.clone())
                 .await
-                .map_err(|status| ErrorResponse {
-                    message: format!("Failed to get agent: {}", status),
-                    status,
+                .map_err(|status| {
+                    ErrorResponse::new(status, format!("Failed to get agent: {}", status))
                 })?;
             if let Some(prompt) = apply_recipe_to_agent(&agent, &recipe, false).await {
                 agent.extend_system_prompt(prompt).await;
             }
             Ok(Json(UpdateSessionUserRecipeValuesResponse { recipe }))
         }
-        Ok(None) => Err(ErrorResponse {
-            message: "Missing required parameters".to_string(),
-            status: StatusCode::BAD_REQUEST,
-        }),
-        Err(e) => Err(ErrorResponse {
-            message: e.to_string(),
-            status: StatusCode::INTERNAL_SERVER_ERROR,
-        }),
+        Ok(None) => Err(ErrorResponse::bad_request("Missing required parameters")),
+        Err(e) => Err(ErrorResponse::internal(e.to_string())),
     }
 }
 
@@ -381,10 +795,9 @@ async fn fork_session(
     Json(request): Json<ForkRequest>,
 ) -> Result<Json<ForkResponse>, ErrorResponse> {
     if request.truncate && request.timestamp.is_none() {
-        return Err(ErrorResponse {
-            message: "truncate=true requires a timestamp".to_string(),
-            status: StatusCode::BAD_REQUEST,
-        });
+        return Err(ErrorResponse::bad_request(
+            "truncate=true requires a timestamp",
+        ));
     }
 
     let session_manager = state.session_manager();
@@ -396,18 +809,18 @@ async fn fork_session(
             .map_err(|e| {
                 tracing::error!("Failed to get session: {}", e);
                 goose::posthog::emit_error("session_get_failed", &e.to_string());
-                ErrorResponse {
-                    message: if e.to_string().contains("not found") {
-                        format!("Session {} not found", session_id)
-                    } else {
-                        format!("Failed to get session: {}", e)
-                    },
-                    status: if e.to_string().contains("not found") {
+                ErrorResponse::new(
+                    if e.to_string().contains("not found") {
                         StatusCode::NOT_FOUND
                     } else {
                         StatusCode::INTERNAL_SERVER_ERROR
                     },
-                }
+                    if e.to_string().contains("not found") {
+                        format!("Session {} not found", session_id)
+                    } else {
+                        format!("Failed to get session: {}", e)
+                    },
+                )
             })?;
 
         let copied = session_manager
@@ -416,10 +829,7 @@ async fn fork_session(
             .map_err(|e| {
                 tracing::error!("Failed to copy session: {}", e);
                 goose::posthog::emit_error("session_copy_failed", &e.to_string());
-                ErrorResponse {
-                    message: format!("Failed to copy session: {}", e),
-                    status: StatusCode::INTERNAL_SERVER_ERROR,
-                }
+                ErrorResponse::internal(format!("Failed to copy session: {}", e))
             })?;
 
         copied.id
@@ -434,10 +844,7 @@ async fn fork_session(
             .map_err(|e| {
                 tracing::error!("Failed to truncate conversation: {}", e);
                 goose::posthog::emit_error("session_truncate_failed", &e.to_string());
-                ErrorResponse {
-                    message: format!("Failed to truncate conversation: {}", e),
-                    status: StatusCode::INTERNAL_SERVER_ERROR,
-                }
+                ErrorResponse::internal(format!("Failed to truncate conversation: {}", e))
             })?;
     }
 
@@ -487,28 +894,396 @@ async fn get_session_extensions(
     Ok(Json(SessionExtensionsResponse { extensions }))
 }
 
+#[utoipa::path(
+    get,
+    path = "/sessions/{session_id}/reviews",
+    params(
+        ("session_id" = String, Path, description = "Unique identifier for the session")
+    ),
+    responses(
+        (status = 200, description = "Coach/Player review transcripts for the session", body = ReviewHistory),
+        (status = 401, description = "Unauthorized - Invalid or missing API key"),
+        (status = 404, description = "Session not found"),
+        (status = 500, description = "Internal server error")
+    ),
+    security(
+        ("api_key" = [])
+    ),
+    tag = "Session Management"
+)]
+async fn get_session_reviews(
+    State(state): State<Arc<AppState>>,
+    Path(session_id): Path<String>,
+) -> Result<Json<ReviewHistory>, StatusCode> {
+    let session = state
+        .session_manager()
+        .get_session(&session_id, false)
+        .await
+        .map_err(|_| StatusCode::NOT_FOUND)?;
+
+    Ok(Json(
+        ReviewHistory::from_extension_data(&session.extension_data).unwrap_or_default(),
+    ))
+}
+
+#[utoipa::path(
+    post,
+    path = "/sessions/{session_id}/reviews",
+    request_body = RunReviewRequest,
+    params(
+        ("session_id" = String, Path, description = "Unique identifier for the session")
+    ),
+    responses(
+        (status = 200, description = "Transcript of the finished Coach/Player review", body = ReviewTranscript),
+        (status = 400, description = "Empty task"),
+        (status = 401, description = "Unauthorized - Invalid or missing API key"),
+        (status = 404, description = "Session not found"),
+        (status = 500, description = "Internal server error")
+    ),
+    security(
+        ("api_key" = [])
+    ),
+    tag = "Session Management"
+)]
+async fn run_session_review(
+    State(state): State<Arc<AppState>>,
+    Path(session_id): Path<String>,
+    Json(request): Json<RunReviewRequest>,
+) -> Result<Json<ReviewTranscript>, ErrorResponse> {
+    if request.task.trim().is_empty() {
+        return Err(ErrorResponse::bad_request("Task must not be empty"));
+    }
+    let manager = state.session_manager();
+    manager
+        .get_session(&session_id, false)
+        .await
+        .map_err(|_| ErrorResponse::not_found(format!("Session not found: {}", session_id)))?;
+
+    let stats = ReviewCycle::new()
+        .execute_with_review(&request.task)
+        .await?;
+    let agent = state.get_agent(session_id.clone()).await?;
+    let notification = agent
+        .record_review(&session_id, &request.task, &stats)
+        .await?;
+    manager.add_message(&session_id, &notification).await?;
+
+    let session = manager.get_session(&session_id, false).await?;
+    ReviewHistory::from_extension_data(&session.extension_data)
+        .and_then(|history| history.reviews.last().cloned())
+        .map(Json)
+        .ok_or_else(|| ErrorResponse::internal("Review was not recorded"))
+}
+
+#[utoipa::path(
+    get,
+    path = "/sessions/{session_id}/repair_diagnostics",
+    params(
+        ("session_id" = String, Path, description = "Unique identifier for the session")
+    ),
+    responses(
+        (status = 200, description = "Dry run of the conversation repair pipeline on the stored conversation", body = RepairDiagnostics),
+        (status = 401, description = "Unauthorized - Invalid or missing API key"),
+        (status = 404, description = "Session not found"),
+        (status = 500, description = "Internal server error")
+    ),
+    security(
+        ("api_key" = [])
+    ),
+    tag = "Session Management"
+)]
+async fn get_session_repair_diagnostics(
+    State(state): State<Arc<AppState>>,
+    Path(session_id): Path<String>,
+) -> Result<Json<RepairDiagnostics>, StatusCode> {
+    let session = state
+        .session_manager()
+        .get_session(&session_id, true)
+        .await
+        .map_err(|_| StatusCode::NOT_FOUND)?;
+
+    let conversation = session.conversation.unwrap_or_default();
+    Ok(Json(
+        ConversationFixer::from_global_config().diagnose(&conversation),
+    ))
+}
+
+#[utoipa::path(
+    post,
+    path = "/sessions/{session_id}/redact",
+    request_body = RedactionRequest,
+    params(
+        ("session_id" = String, Path, description = "Unique identifier for the session")
+    ),
+    responses(
+        (status = 200, description = "Messages redacted or deleted and the conversation compacted again", body = RedactionReport),
+        (status = 400, description = "Invalid redaction request"),
+        (status = 401, description = "Unauthorized - Invalid or missing API key"),
+        (status = 404, description = "Session not found"),
+        (status = 500, description = "Internal server error")
+    ),
+    security(
+        ("api_key" = [])
+    ),
+    tag = "Session Management"
+)]
+async fn redact_session_messages(
+    State(state): State<Arc<AppState>>,
+    Path(session_id): Path<String>,
+    Json(request): Json<RedactionRequest>,
+) -> Result<Json<RedactionReport>, ErrorResponse> {
+    let session = state
+        .session_manager()
+        .get_session(&session_id, true)
+        .await
+        .map_err(|_| ErrorResponse::not_found("Session not found"))?;
+    let conversation = session.conversation.unwrap_or_default();
+    redact_conversation(&conversation, &request)
+        .map_err(|err| ErrorResponse::bad_request(err.to_string()))?;
+
+    let agent = state
+        .get_agent_for_route(session_id.clone())
+        .await
+        .map_err(|status| ErrorResponse::new(status, format!("Failed to get agent: {}", status)))?;
+    let report = agent
+        .redact_session_messages(&session_id, &request)
+        .await
+        .map_err(|err| ErrorResponse::internal(err.to_string()))?;
+    Ok(Json(report))
+}
+
+#[derive(Deserialize, ToSchema)]
+pub struct UploadAttachmentQuery {
+    /// Original file name, used for display and to tell CSV from plain text
+    filename: String,
+}
+
+#[utoipa::path(
+    post,
+    path = "/sessions/{session_id}/attachments",
+    request_body(content = Vec<u8>, content_type = "application/octet-stream"),
+    params(
+        ("session_id" = String, Path, description = "Unique identifier for the session"),
+        ("filename" = String, Query, description = "Original file name")
+    ),
+    responses(
+        (status = 200, description = "Attachment stored", body = Attachment),
+        (status = 400, description = "Empty or oversized upload"),
+        (status = 401, description = "Unauthorized - Invalid or missing API key"),
+        (status = 404, description = "Session not found"),
+        (status = 500, description = "Internal server error")
+    ),
+    security(
+        ("api_key" = [])
+    ),
+    tag = "Session Management"
+)]
+async fn upload_attachment(
+    State(state): State<Arc<AppState>>,
+    Path(session_id): Path<String>,
+    axum::extract::Query(query): axum::extract::Query<UploadAttachmentQuery>,
+    body: Bytes,
+) -> Result<Json<Attachment>, ErrorResponse> {
+    let manager = state.session_manager();
+    manager
+        .get_session(&session_id, false)
+        .await
+        .map_err(|_| ErrorResponse::not_found("Session not found"))?;
+
+    let attachment = manager
+        .attachments()
+        .store(&session_id, &query.filename, &body)
+        .map_err(|err| ErrorResponse::bad_request(err.to_string()))?;
+    Ok(Json(attachment))
+}
+
+#[utoipa::path(
+    get,
+    path = "/sessions/{session_id}/attachments",
+    params(
+        ("session_id" = String, Path, description = "Unique identifier for the session")
+    ),
+    responses(
+        (status = 200, description = "Attachments of the session, oldest first", body = Vec<Attachment>),
+        (status = 401, description = "Unauthorized - Invalid or missing API key"),
+        (status = 500, description = "Internal server error")
+    ),
+    security(
+        ("api_key" = [])
+    ),
+    tag = "Session Management"
+)]
+async fn list_attachments(
+    State(state): State<Arc<AppState>>,
+    Path(session_id): Path<String>,
+) -> Result<Json<Vec<Attachment>>, ErrorResponse> {
+    let attachments = state
+        .session_manager()
+        .attachments()
+        .list(&session_id)
+        .map_err(|err| ErrorResponse::internal(err.to_string()))?;
+    Ok(Json(attachments))
+}
+
+#[utoipa::path(
+    delete,
+    path = "/sessions/{session_id}/attachments/{attachment_id}",
+    params(
+        ("session_id" = String, Path, description = "Unique identifier for the session"),
+        ("attachment_id" = String, Path, description = "Attachment to delete")
+    ),
+    responses(
+        (status = 200, description = "Attachment deleted"),
+        (status = 401, description = "Unauthorized - Invalid or missing API key"),
+        (status = 404, description = "Attachment not found"),
+        (status = 500, description = "Internal server error")
+    ),
+    security(
+        ("api_key" = [])
+    ),
+    tag = "Session Management"
+)]
+async fn delete_attachment(
+    State(state): State<Arc<AppState>>,
+    Path((session_id, attachment_id)): Path<(String, String)>,
+) -> Result<StatusCode, ErrorResponse> {
+    state
+        .session_manager()
+        .attachments()
+        .delete(&session_id, &attachment_id)
+        .map_err(|err| ErrorResponse::not_found(err.to_string()))?;
+    Ok(StatusCode::OK)
+}
+
+#[utoipa::path(
+    get,
+    path = "/sessions/{session_id}/turns",
+    params(
+        ("session_id" = String, Path, description = "Unique identifier for the session")
+    ),
+    responses(
+        (status = 200, description = "Recorded turns of the session, oldest first", body = Vec<TurnSummary>),
+        (status = 401, description = "Unauthorized - Invalid or missing API key"),
+        (status = 500, description = "Internal server error")
+    ),
+    security(
+        ("api_key" = [])
+    ),
+    tag = "Session Management"
+)]
+async fn list_session_turns(
+    State(state): State<Arc<AppState>>,
+    Path(session_id): Path<String>,
+) -> Result<Json<Vec<TurnSummary>>, ErrorResponse> {
+    let turns = state
+        .session_manager()
+        .turn_snapshots()
+        .list(&session_id)
+        .map_err(|err| ErrorResponse::internal(err.to_string()))?;
+    Ok(Json(turns))
+}
+
+#[utoipa::path(
+    get,
+    path = "/sessions/{session_id}/turns/{turn}",
+    params(
+        ("session_id" = String, Path, description = "Unique identifier for the session"),
+        ("turn" = u32, Path, description = "Turn number, counted from 1")
+    ),
+    responses(
+        (status = 200, description = "System prompt, tools, conversation and usage of the turn as sent to the provider", body = TurnSnapshot),
+        (status = 401, description = "Unauthorized - Invalid or missing API key"),
+        (status = 404, description = "No recorded turn with this number"),
+        (status = 500, description = "Internal server error")
+    ),
+    security(
+        ("api_key" = [])
+    ),
+    tag = "Session Management"
+)]
+async fn get_session_turn(
+    State(state): State<Arc<AppState>>,
+    Path((session_id, turn)): Path<(String, u32)>,
+) -> Result<Json<TurnSnapshot>, ErrorResponse> {
+    state
+        .session_manager()
+        .turn_snapshots()
+        .get(&session_id, turn)
+        .map_err(|err| ErrorResponse::internal(err.to_string()))?
+        .map(Json)
+        .ok_or_else(|| {
+            ErrorResponse::not_found(format!(
+                "Turn {} of session {} was not recorded",
+                turn, session_id
+            ))
+        })
+}
+
 pub fn routes(state: Arc<AppState>) -> Router {
+    let max_attachment_bytes = state.session_manager().attachments().max_bytes() as usize;
     Router::new()
         .route("/sessions", get(list_sessions))
         .route("/sessions/search", get(search_sessions))
         .route("/sessions/{session_id}", get(get_session))
         .route("/sessions/{session_id}", delete(delete_session))
+        .route("/sessions/{session_id}/messages", get(get_session_messages))
         .route("/sessions/{session_id}/export", get(export_session))
         .route(
             "/sessions/import",
             post(import_session).layer(DefaultBodyLimit::max(25 * 1024 * 1024)),
         )
         .route("/sessions/insights", get(get_session_insights))
+        .route(
+            "/sessions/retention",
+            get(get_retention_policy).post(apply_retention),
+        )
+        .route("/sessions/{session_id}/tags", put(update_session_tags))
+        .route(
+            "/sessions/{session_id}/archive",
+            post(archive_session).delete(unarchive_session),
+        )
         .route("/sessions/{session_id}/name", put(update_session_name))
         .route(
             "/sessions/{session_id}/user_recipe_values",
             put(update_session_user_recipe_values),
         )
         .route("/sessions/{session_id}/fork", post(fork_session))
+        .route(
+            "/sessions/{session_id}/profile",
+            get(get_session_profile).put(switch_session_profile),
+        )
+        .route(
+            "/sessions/{session_id}/environment",
+            get(get_session_environment).put(update_session_environment),
+        )
         .route(
             "/sessions/{session_id}/extensions",
             get(get_session_extensions),
         )
+        .route(
+            "/sessions/{session_id}/reviews",
+            get(get_session_reviews).post(run_session_review),
+        )
+        .route(
+            "/sessions/{session_id}/repair_diagnostics",
+            get(get_session_repair_diagnostics),
+        )
+        .route(
+            "/sessions/{session_id}/redact",
+            post(redact_session_messages),
+        )
+        .route(
+            "/sessions/{session_id}/attachments",
+            get(list_attachments)
+                .post(upload_attachment)
+                .layer(DefaultBodyLimit::max(max_attachment_bytes)),
+        )
+        .route(
+            "/sessions/{session_id}/attachments/{attachment_id}",
+            delete(delete_attachment),
+        )
+        .route("/sessions/{session_id}/turns", get(list_session_turns))
+        .route("/sessions/{session_id}/turns/{turn}", get(get_session_turn))
         .with_state(state)
 }
 #[derive(Deserialize, ToSchema)]
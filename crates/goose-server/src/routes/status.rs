@@ -3,9 +3,14 @@ use axum::extract::State;
 use axum::http::HeaderValue;
 use axum::response::IntoResponse;
 use axum::{extract::Path, http::StatusCode, routing::get, Json, Router};
+use goose::connectivity::{self, ConnectivityStatus};
+use goose::database::{self, StoreMetrics};
+use goose::resources::{self, ResourceStatus};
 use goose::session::{generate_diagnostics, get_system_info, SystemInfo};
+use goose::startup::{self, StartupProfile};
 use std::sync::Arc;
 
+use crate::kubernetes::{self, ProbeStatus};
 use crate::state::AppState;
 
 #[utoipa::path(get, path = "/status",
@@ -17,6 +22,67 @@ async fn status() -> String {
     "ok".to_string()
 }
 
+#[utoipa::path(get, path = "/status/connectivity",
+    responses(
+        (status = 200, description = "Whether goose can reach the network, and what is degraded while it cannot", body = ConnectivityStatus),
+    )
+)]
+async fn connectivity_status() -> Json<ConnectivityStatus> {
+    Json(connectivity::global().status())
+}
+
+#[utoipa::path(get, path = "/status/resources",
+    responses(
+        (status = 200, description = "CPU, memory and GPU headroom, and local-model and heavy-tool calls running or queued", body = ResourceStatus),
+    )
+)]
+async fn resource_status() -> Json<ResourceStatus> {
+    Json(resources::global().status().await)
+}
+
+#[utoipa::path(get, path = "/status/databases",
+    responses(
+        (status = 200, description = "Connection pool settings and usage of each open SQLite store", body = [StoreMetrics]),
+    )
+)]
+async fn database_status() -> Json<Vec<StoreMetrics>> {
+    Json(database::metrics())
+}
+
+#[utoipa::path(get, path = "/status/startup",
+    responses(
+        (status = 200, description = "How long each subsystem took to start, and what fast start deferred", body = StartupProfile),
+    )
+)]
+async fn startup_status() -> Json<StartupProfile> {
+    Json(startup::profile())
+}
+
+#[utoipa::path(get, path = "/healthz",
+    responses(
+        (status = 200, description = "The server is up", body = String),
+    )
+)]
+async fn liveness() -> String {
+    "ok".to_string()
+}
+
+#[utoipa::path(get, path = "/readyz",
+    responses(
+        (status = 200, description = "Accepting new sessions and replies", body = ProbeStatus),
+        (status = 503, description = "Draining or stopped; route no new work here", body = ProbeStatus),
+    )
+)]
+async fn readiness(State(state): State<Arc<AppState>>) -> (StatusCode, Json<ProbeStatus>) {
+    let probe = kubernetes::readiness(&state);
+    let code = if probe.ready {
+        StatusCode::OK
+    } else {
+        StatusCode::SERVICE_UNAVAILABLE
+    };
+    (code, Json(probe))
+}
+
 #[utoipa::path(get, path = "/system_info",
     responses(
         (status = 200, description = "System information", body = SystemInfo),
@@ -58,6 +124,12 @@ async fn diagnostics(
 pub fn routes(state: Arc<AppState>) -> Router {
     Router::new()
         .route("/status", get(status))
+        .route("/status/connectivity", get(connectivity_status))
+        .route("/status/resources", get(resource_status))
+        .route("/status/databases", get(database_status))
+        .route("/status/startup", get(startup_status))
+        .route(kubernetes::LIVENESS_PATH, get(liveness))
+        .route(kubernetes::READINESS_PATH, get(readiness))
         .route("/system_info", get(system_info))
         .route("/diagnostics/{session_id}", get(diagnostics))
         .with_state(state)
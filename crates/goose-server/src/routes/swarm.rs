@@ -0,0 +1,137 @@
+use crate::routes::errors::ErrorResponse;
+use crate::state::AppState;
+use axum::{
+    extract::Path,
+    routing::{get, post, put},
+    Json, Router,
+};
+use goose::agents::swarm_presets::{self, PresetStats, TeamExperience, TeamLibrary, TeamPreset};
+use serde::{Deserialize, Serialize};
+use std::sync::Arc;
+use utoipa::ToSchema;
+
+#[derive(Serialize, ToSchema)]
+pub struct TeamPresetEntry {
+    preset: TeamPreset,
+    /// Ships with goose; deleting it resets it rather than removing it
+    builtin: bool,
+    stats: PresetStats,
+}
+
+#[derive(Deserialize, ToSchema)]
+pub struct TeamOutcomeRequest {
+    /// Whether the team run achieved its task
+    passed: bool,
+}
+
+#[utoipa::path(
+    get,
+    path = "/swarm/teams",
+    responses(
+        (status = 200, description = "Built-in and saved team presets with their run outcomes", body = [TeamPresetEntry])
+    )
+)]
+async fn list_team_presets() -> Json<Vec<TeamPresetEntry>> {
+    let experience = TeamExperience::load_default();
+    let entries = TeamLibrary::load_default()
+        .list()
+        .into_iter()
+        .map(|preset| TeamPresetEntry {
+            builtin: TeamPreset::is_builtin(&preset.name),
+            stats: experience.stats(&preset.name),
+            preset,
+        })
+        .collect();
+    Json(entries)
+}
+
+#[utoipa::path(
+    put,
+    path = "/swarm/teams/{name}",
+    params(
+        ("name" = String, Path, description = "Preset name")
+    ),
+    request_body = TeamPreset,
+    responses(
+        (status = 200, description = "Preset saved", body = TeamPreset),
+        (status = 400, description = "Invalid preset"),
+        (status = 500, description = "Internal server error")
+    )
+)]
+async fn save_team_preset(
+    Path(name): Path<String>,
+    Json(mut preset): Json<TeamPreset>,
+) -> Result<Json<TeamPreset>, ErrorResponse> {
+    preset.name = name.trim().to_string();
+    preset
+        .validate()
+        .map_err(|e| ErrorResponse::bad_request(e.to_string()))?;
+    TeamLibrary::load_default()
+        .save(&preset)
+        .map_err(|e| ErrorResponse::internal(format!("Failed to save preset: {}", e)))?;
+    Ok(Json(preset))
+}
+
+#[utoipa::path(
+    delete,
+    path = "/swarm/teams/{name}",
+    params(
+        ("name" = String, Path, description = "Preset name")
+    ),
+    responses(
+        (status = 200, description = "Saved preset removed, or built-in preset reset to defaults", body = String),
+        (status = 404, description = "No saved preset with that name"),
+        (status = 500, description = "Internal server error")
+    )
+)]
+async fn delete_team_preset(Path(name): Path<String>) -> Result<Json<String>, ErrorResponse> {
+    let removed = TeamLibrary::load_default()
+        .delete(&name)
+        .map_err(|e| ErrorResponse::internal(format!("Failed to delete preset: {}", e)))?;
+    if !removed {
+        return Err(ErrorResponse::not_found(format!(
+            "No saved team preset named {}",
+            name
+        )));
+    }
+    Ok(Json(format!("Removed team preset {}", name)))
+}
+
+#[utoipa::path(
+    post,
+    path = "/swarm/teams/{name}/outcome",
+    params(
+        ("name" = String, Path, description = "Preset name")
+    ),
+    request_body = TeamOutcomeRequest,
+    responses(
+        (status = 200, description = "Outcome recorded; the preset's updated stats", body = PresetStats),
+        (status = 404, description = "No team preset with that name"),
+        (status = 500, description = "Internal server error")
+    )
+)]
+async fn record_team_outcome(
+    Path(name): Path<String>,
+    Json(request): Json<TeamOutcomeRequest>,
+) -> Result<Json<PresetStats>, ErrorResponse> {
+    if TeamLibrary::load_default().get(&name).is_none() {
+        return Err(ErrorResponse::not_found(format!(
+            "No team preset named {}",
+            name
+        )));
+    }
+    swarm_presets::record_outcome(&name, request.passed)
+        .map(Json)
+        .map_err(|e| ErrorResponse::internal(format!("Failed to record outcome: {}", e)))
+}
+
+pub fn routes(state: Arc<AppState>) -> Router {
+    Router::new()
+        .route("/swarm/teams", get(list_team_presets))
+        .route(
+            "/swarm/teams/{name}",
+            put(save_team_preset).delete(delete_team_preset),
+        )
+        .route("/swarm/teams/{name}/outcome", post(record_team_outcome))
+        .with_state(state)
+}
@@ -0,0 +1,413 @@
+use crate::api_docs::{self, DocPage};
+use crate::drain::{DrainError, DrainRequest, DrainStatus, DEFAULT_DRAIN_DEADLINE};
+use crate::emergency::{EmergencyError, EmergencyStatus, ResumeChallenge};
+use crate::kubernetes::InstanceStatus;
+use crate::maintenance::{MaintenanceError, RestartRequest, RestartStatus};
+use crate::routes::errors::{ErrorCode, ErrorResponse};
+use crate::shell_update::{
+    ShellHealthReport, ShellUpdateError, ShellUpdateStatus, StageShellRequest,
+};
+use crate::state::AppState;
+use axum::{
+    extract::{Query, State},
+    http::StatusCode,
+    routing::{delete, get, post},
+    Json, Router,
+};
+use goose::execution::priority::{self, ProviderGateStatus};
+use goose::security::audit_log::{self, AuditExport, AuditLog, AuditVerification};
+use serde::Deserialize;
+use std::sync::Arc;
+use std::time::Duration;
+use utoipa::{OpenApi, ToSchema};
+
+#[derive(Debug, Default, Deserialize, ToSchema)]
+pub struct EmergencyStopRequest {
+    /// Shown in the status and written to the audit log
+    #[serde(default)]
+    pub reason: Option<String>,
+}
+
+#[derive(Debug, Deserialize, ToSchema)]
+pub struct ConfirmResumeRequest {
+    pub confirmation_code: String,
+}
+
+impl From<EmergencyError> for ErrorResponse {
+    fn from(err: EmergencyError) -> Self {
+        match err {
+            EmergencyError::Stopped => {
+                Self::from_code(ErrorCode::EmergencyStopActive, err.to_string())
+            }
+            EmergencyError::NotStopped => Self::new(StatusCode::CONFLICT, err.to_string()),
+            EmergencyError::InvalidConfirmation => {
+                Self::new(StatusCode::FORBIDDEN, err.to_string())
+            }
+        }
+    }
+}
+
+#[utoipa::path(
+    get,
+    path = "/system/emergency-stop",
+    responses(
+        (status = 200, description = "Emergency stop status", body = EmergencyStatus)
+    )
+)]
+pub async fn emergency_status(State(state): State<Arc<AppState>>) -> Json<EmergencyStatus> {
+    Json(state.emergency.status())
+}
+
+#[utoipa::path(
+    post,
+    path = "/system/emergency-stop",
+    request_body = EmergencyStopRequest,
+    responses(
+        (status = 200, description = "All running work cancelled and new work blocked", body = EmergencyStatus)
+    )
+)]
+pub async fn emergency_stop(
+    State(state): State<Arc<AppState>>,
+    request: Option<Json<EmergencyStopRequest>>,
+) -> Json<EmergencyStatus> {
+    let reason = request.and_then(|Json(r)| r.reason);
+    let scheduler = state.scheduler();
+    Json(state.emergency.stop(scheduler.as_ref(), reason).await)
+}
+
+#[utoipa::path(
+    post,
+    path = "/system/emergency-stop/resume",
+    responses(
+        (status = 200, description = "Confirmation code for the second resume step", body = ResumeChallenge),
+        (status = 409, description = "Emergency stop is not active")
+    )
+)]
+pub async fn request_resume(
+    State(state): State<Arc<AppState>>,
+) -> Result<Json<ResumeChallenge>, ErrorResponse> {
+    Ok(Json(state.emergency.request_resume()?))
+}
+
+#[utoipa::path(
+    post,
+    path = "/system/emergency-stop/resume/confirm",
+    request_body = ConfirmResumeRequest,
+    responses(
+        (status = 200, description = "Emergency stop lifted", body = EmergencyStatus),
+        (status = 403, description = "Confirmation code invalid or expired"),
+        (status = 409, description = "Emergency stop is not active")
+    )
+)]
+pub async fn confirm_resume(
+    State(state): State<Arc<AppState>>,
+    Json(request): Json<ConfirmResumeRequest>,
+) -> Result<Json<EmergencyStatus>, ErrorResponse> {
+    let scheduler = state.scheduler();
+    let status = state
+        .emergency
+        .confirm_resume(scheduler.as_ref(), &request.confirmation_code)
+        .await?;
+    Ok(Json(status))
+}
+
+impl From<DrainError> for ErrorResponse {
+    fn from(err: DrainError) -> Self {
+        match err {
+            DrainError::Draining => Self::from_code(ErrorCode::ServerDraining, err.to_string()),
+        }
+    }
+}
+
+#[utoipa::path(
+    get,
+    path = "/system/drain",
+    responses(
+        (status = 200, description = "Drain progress", body = DrainStatus)
+    )
+)]
+pub async fn drain_status(State(state): State<Arc<AppState>>) -> Json<DrainStatus> {
+    Json(state.drain.status())
+}
+
+#[utoipa::path(
+    post,
+    path = "/system/drain",
+    request_body = DrainRequest,
+    responses(
+        (status = 200, description = "New work refused; goosed exits once in-flight turns finish or the deadline passes", body = DrainStatus)
+    )
+)]
+pub async fn drain(
+    State(state): State<Arc<AppState>>,
+    request: Option<Json<DrainRequest>>,
+) -> Json<DrainStatus> {
+    let deadline = request
+        .and_then(|Json(r)| r.deadline_secs)
+        .map(Duration::from_secs)
+        .unwrap_or(DEFAULT_DRAIN_DEADLINE);
+    Json(state.drain.begin(deadline))
+}
+
+impl From<MaintenanceError> for ErrorResponse {
+    fn from(err: MaintenanceError) -> Self {
+        match err {
+            MaintenanceError::InvalidWindow(..) => {
+                Self::new(StatusCode::BAD_REQUEST, err.to_string())
+            }
+            MaintenanceError::NotPending
+            | MaintenanceError::AlreadyRestarting
+            | MaintenanceError::SupervisorLost => Self::new(StatusCode::CONFLICT, err.to_string()),
+        }
+    }
+}
+
+#[utoipa::path(
+    get,
+    path = "/system/restart",
+    responses(
+        (status = 200, description = "Pending restart and when it will apply", body = RestartStatus)
+    )
+)]
+pub async fn restart_status(State(state): State<Arc<AppState>>) -> Json<RestartStatus> {
+    Json(state.restart.status())
+}
+
+#[utoipa::path(
+    post,
+    path = "/system/restart",
+    request_body = RestartRequest,
+    responses(
+        (status = 200, description = "Restart scheduled for the next maintenance window or idle period", body = RestartStatus),
+        (status = 409, description = "Restart already in progress")
+    )
+)]
+pub async fn request_restart(
+    State(state): State<Arc<AppState>>,
+    request: Option<Json<RestartRequest>>,
+) -> Result<Json<RestartStatus>, ErrorResponse> {
+    let request = request.map(|Json(r)| r).unwrap_or_default();
+    Ok(Json(state.restart.request(request)?))
+}
+
+#[utoipa::path(
+    delete,
+    path = "/system/restart",
+    responses(
+        (status = 200, description = "Pending restart cancelled", body = RestartStatus),
+        (status = 409, description = "No restart pending, or restart already in progress")
+    )
+)]
+pub async fn cancel_restart(
+    State(state): State<Arc<AppState>>,
+) -> Result<Json<RestartStatus>, ErrorResponse> {
+    Ok(Json(state.restart.cancel()?))
+}
+
+impl From<ShellUpdateError> for ErrorResponse {
+    fn from(err: ShellUpdateError) -> Self {
+        match err {
+            ShellUpdateError::InvalidChecksum(..) | ShellUpdateError::InvalidVersion(..) => {
+                Self::new(StatusCode::BAD_REQUEST, err.to_string())
+            }
+            ShellUpdateError::Busy(..)
+            | ShellUpdateError::NotStaged
+            | ShellUpdateError::NotRestarting => Self::new(StatusCode::CONFLICT, err.to_string()),
+        }
+    }
+}
+
+#[utoipa::path(
+    get,
+    path = "/system/shell-update",
+    responses(
+        (status = 200, description = "Shell update progress, and whether the shell can restart now", body = ShellUpdateStatus)
+    )
+)]
+pub async fn shell_update_status(State(state): State<Arc<AppState>>) -> Json<ShellUpdateStatus> {
+    Json(state.shell_update.status())
+}
+
+#[utoipa::path(
+    post,
+    path = "/system/shell-update",
+    request_body = StageShellRequest,
+    responses(
+        (status = 200, description = "Shell package download and verification started", body = ShellUpdateStatus),
+        (status = 400, description = "Invalid checksum"),
+        (status = 409, description = "Another shell update is in progress")
+    )
+)]
+pub async fn stage_shell_update(
+    State(state): State<Arc<AppState>>,
+    Json(request): Json<StageShellRequest>,
+) -> Result<Json<ShellUpdateStatus>, ErrorResponse> {
+    state.shell_update.stage(request)?;
+    Ok(Json(state.shell_update.status()))
+}
+
+#[utoipa::path(
+    post,
+    path = "/system/shell-update/restart",
+    responses(
+        (status = 200, description = "Shell restart started; waiting for the new shell's health check", body = ShellUpdateStatus),
+        (status = 409, description = "No verified shell package is staged")
+    )
+)]
+pub async fn restart_shell(
+    State(state): State<Arc<AppState>>,
+) -> Result<Json<ShellUpdateStatus>, ErrorResponse> {
+    Ok(Json(state.shell_update.begin_restart()?))
+}
+
+#[utoipa::path(
+    post,
+    path = "/system/shell-update/health",
+    request_body = ShellHealthReport,
+    responses(
+        (status = 200, description = "Update completed, or rollback required", body = ShellUpdateStatus),
+        (status = 409, description = "No shell restart is in progress")
+    )
+)]
+pub async fn report_shell_health(
+    State(state): State<Arc<AppState>>,
+    Json(report): Json<ShellHealthReport>,
+) -> Result<Json<ShellUpdateStatus>, ErrorResponse> {
+    Ok(Json(state.shell_update.report_health(report)?))
+}
+
+#[utoipa::path(
+    post,
+    path = "/system/shell-update/rolled-back",
+    responses(
+        (status = 200, description = "Previous shell is running again", body = ShellUpdateStatus),
+        (status = 409, description = "No rollback was required")
+    )
+)]
+pub async fn confirm_shell_rollback(
+    State(state): State<Arc<AppState>>,
+) -> Result<Json<ShellUpdateStatus>, ErrorResponse> {
+    Ok(Json(state.shell_update.confirm_rollback()?))
+}
+
+#[utoipa::path(
+    get,
+    path = "/system/instance",
+    responses(
+        (status = 200, description = "Instance state for an external controller: readiness, drain, restart and in-flight sessions", body = InstanceStatus)
+    )
+)]
+pub async fn instance_status(State(state): State<Arc<AppState>>) -> Json<InstanceStatus> {
+    Json(crate::kubernetes::instance_status(&state))
+}
+
+#[utoipa::path(
+    get,
+    path = "/system/priority",
+    responses(
+        (status = 200, description = "Provider slots held by interactive and background work, and recent preemptions of background work", body = ProviderGateStatus)
+    )
+)]
+pub async fn priority_status() -> Json<ProviderGateStatus> {
+    Json(priority::gate().status())
+}
+
+#[derive(Debug, Deserialize)]
+pub struct ApiReferenceQuery {
+    /// Session whose loaded extensions are documented; omit for routes only
+    pub session_id: Option<String>,
+}
+
+#[utoipa::path(
+    get,
+    path = "/system/api-reference",
+    params(
+        ("session_id" = Option<String>, Query, description = "Session whose extension tools to include")
+    ),
+    responses(
+        (status = 200, description = "Docusaurus pages for the routes, schemas and tools of this instance", body = [DocPage]),
+        (status = 424, description = "Agent not initialized for the session")
+    )
+)]
+pub async fn api_reference(
+    State(state): State<Arc<AppState>>,
+    Query(query): Query<ApiReferenceQuery>,
+) -> Result<Json<Vec<DocPage>>, StatusCode> {
+    let openapi = serde_json::to_value(crate::openapi::ApiDoc::openapi())
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+    let tools = match query.session_id {
+        Some(session_id) => {
+            let agent = state.get_agent_for_route(session_id.clone()).await?;
+            agent.list_tools(&session_id, None).await
+        }
+        None => Vec::new(),
+    };
+    Ok(Json(api_docs::render(
+        &openapi,
+        &api_docs::group_tools(tools),
+    )))
+}
+
+fn audit_log() -> Result<Arc<AuditLog>, ErrorResponse> {
+    audit_log::global().ok_or_else(|| ErrorResponse::internal("Audit log is unavailable"))
+}
+
+#[utoipa::path(
+    get,
+    path = "/system/audit/verify",
+    responses(
+        (status = 200, description = "Hash chain and signed checkpoint verification", body = AuditVerification),
+        (status = 500, description = "Audit log could not be read")
+    )
+)]
+pub async fn verify_audit_log() -> Result<Json<AuditVerification>, ErrorResponse> {
+    Ok(Json(audit_log()?.verify()?))
+}
+
+#[utoipa::path(
+    get,
+    path = "/system/audit/export",
+    responses(
+        (status = 200, description = "Signed export of the full audit log", body = AuditExport),
+        (status = 500, description = "Audit log could not be read")
+    )
+)]
+pub async fn export_audit_log() -> Result<Json<AuditExport>, ErrorResponse> {
+    Ok(Json(audit_log()?.export()?))
+}
+
+pub fn routes(state: Arc<AppState>) -> Router {
+    Router::new()
+        .route(
+            "/system/emergency-stop",
+            get(emergency_status).post(emergency_stop),
+        )
+        .route("/system/emergency-stop/resume", post(request_resume))
+        .route(
+            "/system/emergency-stop/resume/confirm",
+            post(confirm_resume),
+        )
+        .route("/system/drain", get(drain_status).post(drain))
+        .route(
+            "/system/restart",
+            get(restart_status)
+                .post(request_restart)
+                .delete(cancel_restart),
+        )
+        .route(
+            "/system/shell-update",
+            get(shell_update_status).post(stage_shell_update),
+        )
+        .route("/system/shell-update/restart", post(restart_shell))
+        .route("/system/shell-update/health", post(report_shell_health))
+        .route(
+            "/system/shell-update/rolled-back",
+            post(confirm_shell_rollback),
+        )
+        .route("/system/instance", get(instance_status))
+        .route("/system/priority", get(priority_status))
+        .route("/system/api-reference", get(api_reference))
+        .route("/system/audit/verify", get(verify_audit_log))
+        .route("/system/audit/export", get(export_audit_log))
+        .with_state(state)
+}
@@ -1,9 +1,20 @@
-use axum::{extract::State, http::StatusCode, routing::post, Json, Router};
+use crate::routes::errors::ErrorResponse;
+use axum::{
+    extract::{Path, Query, State},
+    http::StatusCode,
+    routing::{get, post},
+    Json, Router,
+};
+use chrono::NaiveDate;
+use goose::local_analytics::{
+    AnalyticsMetric, DailyRollup, LocalAnalyticsStore, RollupQuery, UsageSummary,
+};
 use goose::posthog::emit_event;
+use goose::session::turn_snapshots::ContextAttribution;
 use serde::Deserialize;
 use std::collections::HashMap;
 use std::sync::Arc;
-use utoipa::ToSchema;
+use utoipa::{IntoParams, ToSchema};
 
 use crate::state::AppState;
 
@@ -14,6 +25,16 @@ pub struct TelemetryEventRequest {
     pub properties: HashMap<String, serde_json::Value>,
 }
 
+#[derive(Debug, Deserialize, IntoParams)]
+pub struct LocalUsageParams {
+    /// First day to include (YYYY-MM-DD)
+    pub start: Option<NaiveDate>,
+    /// Last day to include (YYYY-MM-DD)
+    pub end: Option<NaiveDate>,
+    /// Restrict daily rollups to a single metric
+    pub metric: Option<AnalyticsMetric>,
+}
+
 #[utoipa::path(
     post,
     path = "/telemetry/event",
@@ -38,8 +59,79 @@ async fn send_telemetry_event(
     StatusCode::ACCEPTED
 }
 
+#[utoipa::path(
+    get,
+    path = "/telemetry/local/summary",
+    params(LocalUsageParams),
+    responses(
+        (status = 200, description = "Usage totals from the local analytics store", body = UsageSummary),
+        (status = 500, description = "Internal server error")
+    )
+)]
+async fn get_local_usage_summary(
+    Query(params): Query<LocalUsageParams>,
+) -> Result<Json<UsageSummary>, ErrorResponse> {
+    let summary = LocalAnalyticsStore::global()
+        .summary(params.start, params.end)
+        .await
+        .map_err(|e| ErrorResponse::internal(format!("Failed to load local analytics: {}", e)))?;
+    Ok(Json(summary))
+}
+
+#[utoipa::path(
+    get,
+    path = "/telemetry/local/daily",
+    params(LocalUsageParams),
+    responses(
+        (status = 200, description = "Daily rollups from the local analytics store", body = Vec<DailyRollup>),
+        (status = 500, description = "Internal server error")
+    )
+)]
+async fn get_local_daily_rollups(
+    Query(params): Query<LocalUsageParams>,
+) -> Result<Json<Vec<DailyRollup>>, ErrorResponse> {
+    let rollups = LocalAnalyticsStore::global()
+        .daily_rollups(&RollupQuery {
+            metric: params.metric,
+            start: params.start,
+            end: params.end,
+        })
+        .await
+        .map_err(|e| ErrorResponse::internal(format!("Failed to load local analytics: {}", e)))?;
+    Ok(Json(rollups))
+}
+
+#[utoipa::path(
+    get,
+    path = "/telemetry/context/{session_id}",
+    params(
+        ("session_id" = String, Path, description = "Unique identifier for the session")
+    ),
+    responses(
+        (status = 200, description = "Context tokens per component across the session's recorded turns", body = ContextAttribution),
+        (status = 500, description = "Internal server error")
+    )
+)]
+async fn get_context_attribution(
+    State(state): State<Arc<AppState>>,
+    Path(session_id): Path<String>,
+) -> Result<Json<ContextAttribution>, ErrorResponse> {
+    let attribution = state
+        .session_manager()
+        .turn_snapshots()
+        .context_attribution(&session_id)
+        .map_err(|e| ErrorResponse::internal(format!("Failed to load turn snapshots: {}", e)))?;
+    Ok(Json(attribution))
+}
+
 pub fn routes(state: Arc<AppState>) -> Router {
     Router::new()
         .route("/telemetry/event", post(send_telemetry_event))
+        .route("/telemetry/local/summary", get(get_local_usage_summary))
+        .route("/telemetry/local/daily", get(get_local_daily_rollups))
+        .route(
+            "/telemetry/context/{session_id}",
+            get(get_context_attribution),
+        )
         .with_state(state)
 }
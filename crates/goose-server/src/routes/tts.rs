@@ -0,0 +1,233 @@
+use crate::routes::dictation::convert_error;
+use crate::routes::errors::ErrorResponse;
+use crate::state::AppState;
+use axum::{
+    body::Body,
+    extract::{Path, State},
+    http::header,
+    response::{IntoResponse, Response},
+    routing::{get, post},
+    Json, Router,
+};
+use goose::conversation::message::Message;
+use goose::session::ExtensionState;
+use goose::tts::providers::{
+    is_configured, prepare_text, resolve_provider, synthesize, AudioStream, TtsProvider, PROVIDERS,
+};
+use goose::tts::settings::SpeechSettings;
+use rmcp::model::Role;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::sync::Arc;
+use utoipa::ToSchema;
+
+#[derive(Debug, Deserialize, ToSchema)]
+pub struct SynthesizeRequest {
+    /// Text or markdown to speak
+    pub text: String,
+    /// Provider to use; falls back to the first available provider
+    pub provider: Option<TtsProvider>,
+    /// Provider-specific voice name or id
+    pub voice: Option<String>,
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+pub struct TtsProviderStatus {
+    /// Whether the provider is ready to use
+    pub configured: bool,
+    /// Whether synthesis happens on this machine
+    pub local: bool,
+    /// Description of what this provider does
+    pub description: String,
+    /// Config key name for the API key or model, if any
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub config_key: Option<String>,
+}
+
+async fn speak(
+    text: &str,
+    provider: Option<TtsProvider>,
+    voice: Option<&str>,
+) -> Result<Response, ErrorResponse> {
+    if prepare_text(text).is_empty() {
+        return Err(ErrorResponse::bad_request("Nothing to speak"));
+    }
+
+    let provider = resolve_provider(provider)
+        .ok_or_else(|| convert_error(anyhow::anyhow!("No text-to-speech provider configured")))?;
+    let AudioStream { mime_type, chunks } = synthesize(provider, text, voice)
+        .await
+        .map_err(convert_error)?;
+
+    Ok((
+        [(header::CONTENT_TYPE, mime_type)],
+        Body::from_stream(chunks),
+    )
+        .into_response())
+}
+
+async fn load_speech_settings(
+    state: &AppState,
+    session_id: &str,
+    include_messages: bool,
+) -> Result<(goose::session::Session, SpeechSettings), ErrorResponse> {
+    let session = state
+        .session_manager()
+        .get_session(session_id, include_messages)
+        .await
+        .map_err(|_| ErrorResponse::not_found(format!("Session not found: {}", session_id)))?;
+    let settings = SpeechSettings::from_extension_data(&session.extension_data).unwrap_or_default();
+    Ok((session, settings))
+}
+
+#[utoipa::path(
+    get,
+    path = "/tts/config",
+    responses(
+        (status = 200, description = "Text-to-speech provider configurations", body = HashMap<String, TtsProviderStatus>)
+    )
+)]
+pub async fn get_tts_config() -> Json<HashMap<TtsProvider, TtsProviderStatus>> {
+    let providers = PROVIDERS
+        .iter()
+        .map(|def| {
+            (
+                def.provider,
+                TtsProviderStatus {
+                    configured: is_configured(def.provider),
+                    local: def.local,
+                    description: def.description.to_string(),
+                    config_key: def.config_key.map(|s| s.to_string()),
+                },
+            )
+        })
+        .collect();
+
+    Json(providers)
+}
+
+#[utoipa::path(
+    post,
+    path = "/tts/synthesize",
+    request_body = SynthesizeRequest,
+    responses(
+        (status = 200, description = "Synthesized audio stream (audio/wav or audio/mpeg)"),
+        (status = 400, description = "Nothing to speak"),
+        (status = 401, description = "Invalid API key"),
+        (status = 412, description = "Provider not configured"),
+        (status = 429, description = "Rate limit exceeded"),
+        (status = 500, description = "Internal server error"),
+        (status = 502, description = "Provider API error")
+    )
+)]
+pub async fn synthesize_speech(
+    Json(request): Json<SynthesizeRequest>,
+) -> Result<Response, ErrorResponse> {
+    speak(&request.text, request.provider, request.voice.as_deref()).await
+}
+
+#[utoipa::path(
+    get,
+    path = "/tts/sessions/{session_id}/messages/{message_id}",
+    params(
+        ("session_id" = String, Path, description = "Session containing the message"),
+        ("message_id" = String, Path, description = "Assistant message to speak")
+    ),
+    responses(
+        (status = 200, description = "Synthesized audio stream for the message"),
+        (status = 400, description = "Message has no speakable text"),
+        (status = 404, description = "Session or assistant message not found"),
+        (status = 412, description = "Provider not configured"),
+        (status = 500, description = "Internal server error")
+    )
+)]
+pub async fn speak_message(
+    State(state): State<Arc<AppState>>,
+    Path((session_id, message_id)): Path<(String, String)>,
+) -> Result<Response, ErrorResponse> {
+    let (session, settings) = load_speech_settings(&state, &session_id, true).await?;
+    let message: Message = session
+        .conversation
+        .and_then(|conversation| {
+            conversation
+                .messages()
+                .iter()
+                .find(|m| m.role == Role::Assistant && m.id.as_deref() == Some(&message_id))
+                .cloned()
+        })
+        .ok_or_else(|| {
+            ErrorResponse::not_found(format!("Assistant message not found: {}", message_id))
+        })?;
+
+    speak(
+        &message.as_concat_text(),
+        settings.provider,
+        settings.voice.as_deref(),
+    )
+    .await
+}
+
+#[utoipa::path(
+    get,
+    path = "/tts/sessions/{session_id}/settings",
+    params(
+        ("session_id" = String, Path, description = "Session id")
+    ),
+    responses(
+        (status = 200, description = "Speech settings for the session", body = SpeechSettings),
+        (status = 404, description = "Session not found")
+    )
+)]
+pub async fn get_speech_settings(
+    State(state): State<Arc<AppState>>,
+    Path(session_id): Path<String>,
+) -> Result<Json<SpeechSettings>, ErrorResponse> {
+    let (_, settings) = load_speech_settings(&state, &session_id, false).await?;
+    Ok(Json(settings))
+}
+
+#[utoipa::path(
+    put,
+    path = "/tts/sessions/{session_id}/settings",
+    params(
+        ("session_id" = String, Path, description = "Session id")
+    ),
+    request_body = SpeechSettings,
+    responses(
+        (status = 200, description = "Speech settings updated", body = SpeechSettings),
+        (status = 404, description = "Session not found"),
+        (status = 500, description = "Internal server error")
+    )
+)]
+pub async fn update_speech_settings(
+    State(state): State<Arc<AppState>>,
+    Path(session_id): Path<String>,
+    Json(settings): Json<SpeechSettings>,
+) -> Result<Json<SpeechSettings>, ErrorResponse> {
+    let (session, _) = load_speech_settings(&state, &session_id, false).await?;
+    let mut extension_data = session.extension_data;
+    settings.to_extension_data(&mut extension_data)?;
+    state
+        .session_manager()
+        .update(&session_id)
+        .extension_data(extension_data)
+        .apply()
+        .await?;
+
+    Ok(Json(settings))
+}
+
+pub fn routes(state: Arc<AppState>) -> Router {
+    Router::new()
+        .route("/tts/config", get(get_tts_config))
+        .route("/tts/synthesize", post(synthesize_speech))
+        .route(
+            "/tts/sessions/{session_id}/messages/{message_id}",
+            get(speak_message),
+        )
+        .route(
+            "/tts/sessions/{session_id}/settings",
+            get(get_speech_settings).put(update_speech_settings),
+        )
+        .with_state(state)
+}
@@ -1,12 +1,13 @@
 use crate::state::AppState;
+use crate::tunnel::access::{AccessTokenInfo, ConnectedClient, MintedAccessToken, RoutePermission};
 use axum::{
-    extract::State,
+    extract::{Path, State},
     http::StatusCode,
     response::{IntoResponse, Response},
-    routing::{get, post},
+    routing::{delete, get, post},
     Json, Router,
 };
-use serde::Serialize;
+use serde::{Deserialize, Serialize};
 use std::sync::Arc;
 use utoipa::ToSchema;
 
@@ -15,6 +16,26 @@ pub struct ErrorResponse {
     pub error: String,
 }
 
+fn error_response(status: StatusCode, error: impl ToString) -> Response {
+    (
+        status,
+        Json(ErrorResponse {
+            error: error.to_string(),
+        }),
+    )
+        .into_response()
+}
+
+#[derive(Debug, Deserialize, ToSchema)]
+pub struct CreateAccessTokenRequest {
+    /// Label for the device or person using the token
+    pub name: String,
+    /// Routes the token may call through the tunnel
+    pub permissions: Vec<RoutePermission>,
+    /// Lifetime in seconds (max 30 days)
+    pub expires_in_secs: i64,
+}
+
 /// Start the tunnel
 #[utoipa::path(
     post,
@@ -69,10 +90,112 @@ pub async fn get_tunnel_status(State(state): State<Arc<AppState>>) -> Response {
     (StatusCode::OK, Json(info)).into_response()
 }
 
+/// List access tokens
+#[utoipa::path(
+    get,
+    path = "/tunnel/tokens",
+    responses(
+        (status = 200, description = "Active access tokens", body = Vec<AccessTokenInfo>)
+    )
+)]
+pub async fn list_access_tokens(State(state): State<Arc<AppState>>) -> Json<Vec<AccessTokenInfo>> {
+    Json(state.tunnel_manager.access().list())
+}
+
+/// Mint a scoped access token
+#[utoipa::path(
+    post,
+    path = "/tunnel/tokens",
+    request_body = CreateAccessTokenRequest,
+    responses(
+        (status = 200, description = "Token created; the token value is only returned once", body = MintedAccessToken),
+        (status = 400, description = "Bad request", body = ErrorResponse)
+    )
+)]
+pub async fn create_access_token(
+    State(state): State<Arc<AppState>>,
+    Json(request): Json<CreateAccessTokenRequest>,
+) -> Response {
+    if request.name.trim().is_empty() {
+        return error_response(StatusCode::BAD_REQUEST, "Token name cannot be empty");
+    }
+
+    match state.tunnel_manager.access().mint(
+        request.name.trim(),
+        request.permissions,
+        request.expires_in_secs,
+    ) {
+        Ok(minted) => (StatusCode::OK, Json::<MintedAccessToken>(minted)).into_response(),
+        Err(e) => error_response(StatusCode::BAD_REQUEST, e),
+    }
+}
+
+/// Revoke an access token
+#[utoipa::path(
+    delete,
+    path = "/tunnel/tokens/{token_id}",
+    params(
+        ("token_id" = String, Path, description = "Access token id")
+    ),
+    responses(
+        (status = 200, description = "Token revoked"),
+        (status = 404, description = "Token not found", body = ErrorResponse),
+        (status = 500, description = "Internal server error", body = ErrorResponse)
+    )
+)]
+pub async fn revoke_access_token(
+    State(state): State<Arc<AppState>>,
+    Path(token_id): Path<String>,
+) -> Response {
+    match state.tunnel_manager.access().revoke(&token_id) {
+        Ok(true) => StatusCode::OK.into_response(),
+        Ok(false) => error_response(StatusCode::NOT_FOUND, "Access token not found"),
+        Err(e) => error_response(StatusCode::INTERNAL_SERVER_ERROR, e),
+    }
+}
+
+/// List clients that have connected through the tunnel
+#[utoipa::path(
+    get,
+    path = "/tunnel/clients",
+    responses(
+        (status = 200, description = "Clients seen since the tunnel started", body = Vec<ConnectedClient>)
+    )
+)]
+pub async fn list_tunnel_clients(State(state): State<Arc<AppState>>) -> Json<Vec<ConnectedClient>> {
+    Json(state.tunnel_manager.access().clients())
+}
+
+/// Kill switch: stop the tunnel, revoke all tokens and rotate the secret
+#[utoipa::path(
+    post,
+    path = "/tunnel/kill",
+    responses(
+        (status = 200, description = "Tunnel stopped and all remote credentials invalidated"),
+        (status = 500, description = "Internal server error", body = ErrorResponse)
+    )
+)]
+pub async fn kill_tunnel(State(state): State<Arc<AppState>>) -> Response {
+    match state.tunnel_manager.kill().await {
+        Ok(()) => StatusCode::OK.into_response(),
+        Err(e) => {
+            tracing::error!("Failed to fully kill tunnel: {}", e);
+            error_response(StatusCode::INTERNAL_SERVER_ERROR, e)
+        }
+    }
+}
+
 pub fn routes(state: Arc<AppState>) -> Router {
     Router::new()
         .route("/tunnel/start", post(start_tunnel))
         .route("/tunnel/stop", post(stop_tunnel))
         .route("/tunnel/status", get(get_tunnel_status))
+        .route(
+            "/tunnel/tokens",
+            get(list_access_tokens).post(create_access_token),
+        )
+        .route("/tunnel/tokens/{token_id}", delete(revoke_access_token))
+        .route("/tunnel/clients", get(list_tunnel_clients))
+        .route("/tunnel/kill", post(kill_tunnel))
         .with_state(state)
 }
@@ -0,0 +1,490 @@
+//! Updates of the Electron shell, coordinated with the supervisor.
+//!
+//! The supervisor swaps goosed through deferred restarts, but the desktop
+//! shell it also runs needs the same care. The supervisor hands goosed a new
+//! shell package (URL and SHA-256); goosed downloads and verifies it, then
+//! reports `restart_ready` once no turn has run for a while, so the shell is
+//! not restarted under the user. The supervisor announces the restart, and
+//! the new shell reports its first health check. A failed check, or none
+//! within `HEALTH_CHECK_DEADLINE`, asks the supervisor to roll back to the
+//! previous package, which stays on disk until the next update completes.
+//!
+//! Packages are kept pinned in the artifact registry while they are the
+//! target, installed or previous package; once released they are left for
+//! the registry's GC.
+
+use crate::drain::Drain;
+use chrono::{DateTime, Duration, Utc};
+use goose::artifacts::{ArtifactKind, ArtifactRegistry, NewArtifact, RetentionClass};
+use goose::config::paths::Paths;
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use sha2::{Digest, Sha256};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use utoipa::ToSchema;
+
+const SHELL_FOLDER: &str = "shell";
+const VERSIONS_FILE: &str = "versions.json";
+const DOWNLOADS_FOLDER: &str = "downloads";
+const PRODUCER: &str = "shell_update";
+/// Time without a running turn before the shell may restart
+const RESTART_IDLE: Duration = Duration::minutes(2);
+/// Time the new shell gets to pass its first health check
+const HEALTH_CHECK_DEADLINE: Duration = Duration::minutes(2);
+
+#[derive(Debug, thiserror::Error, PartialEq)]
+pub enum ShellUpdateError {
+    #[error("A shell update is already {0}")]
+    Busy(&'static str),
+    #[error("No verified shell package is staged")]
+    NotStaged,
+    #[error("No shell restart is in progress")]
+    NotRestarting,
+    #[error("Invalid SHA-256 '{0}'")]
+    InvalidChecksum(String),
+    #[error("Invalid version '{0}'")]
+    InvalidVersion(String),
+}
+
+#[derive(Debug, Clone, Deserialize, ToSchema)]
+pub struct StageShellRequest {
+    pub version: String,
+    /// Where to download the package from
+    pub url: String,
+    /// Hex SHA-256 of the package
+    pub sha256: String,
+}
+
+#[derive(Debug, Clone, Deserialize, ToSchema)]
+pub struct ShellHealthReport {
+    /// Version of the shell that ran the check
+    pub version: String,
+    pub healthy: bool,
+    #[serde(default)]
+    pub detail: Option<String>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, ToSchema)]
+pub struct ShellPackage {
+    pub version: String,
+    pub path: PathBuf,
+    /// Artifact holding the package
+    #[serde(default)]
+    pub artifact_id: Option<String>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum ShellUpdatePhase {
+    Idle,
+    Downloading,
+    /// Verified and waiting for a safe moment to restart the shell
+    Staged,
+    /// The supervisor is restarting the shell; waiting for its health check
+    Restarting,
+    /// The new shell failed its health check; the supervisor should start
+    /// the previous package again
+    RollbackRequired,
+    RolledBack,
+    Completed,
+    Failed,
+}
+
+#[derive(Debug, Clone, Serialize, ToSchema)]
+pub struct ShellUpdateStatus {
+    pub phase: ShellUpdatePhase,
+    /// Package the update installs
+    pub target: Option<ShellPackage>,
+    pub installed: Option<ShellPackage>,
+    /// Package to roll back to
+    pub previous: Option<ShellPackage>,
+    /// A package is staged and no turn has run for a while, so the shell can
+    /// restart without interrupting the user
+    pub restart_ready: bool,
+    pub health_deadline: Option<DateTime<Utc>>,
+    pub error: Option<String>,
+}
+
+/// Installed and previous packages, kept across goosed restarts
+#[derive(Debug, Default, Serialize, Deserialize)]
+struct ShellVersions {
+    installed: Option<ShellPackage>,
+    previous: Option<ShellPackage>,
+}
+
+struct UpdateState {
+    phase: ShellUpdatePhase,
+    target: Option<ShellPackage>,
+    health_deadline: Option<DateTime<Utc>>,
+    error: Option<String>,
+    versions: ShellVersions,
+}
+
+pub struct ShellUpdater {
+    dir: PathBuf,
+    artifacts: ArtifactRegistry,
+    drain: Arc<Drain>,
+    state: Mutex<UpdateState>,
+}
+
+impl ShellUpdater {
+    pub fn new(dir: PathBuf, artifacts: ArtifactRegistry, drain: Arc<Drain>) -> Self {
+        let versions = std::fs::read_to_string(dir.join(VERSIONS_FILE))
+            .ok()
+            .and_then(|json| serde_json::from_str(&json).ok())
+            .unwrap_or_default();
+        Self {
+            dir,
+            artifacts,
+            drain,
+            state: Mutex::new(UpdateState {
+                phase: ShellUpdatePhase::Idle,
+                target: None,
+                health_deadline: None,
+                error: None,
+                versions,
+            }),
+        }
+    }
+
+    pub fn persistent(drain: Arc<Drain>) -> Self {
+        Self::new(
+            Paths::in_data_dir(SHELL_FOLDER),
+            ArtifactRegistry::persistent(),
+            drain,
+        )
+    }
+
+    fn with_state<T>(&self, f: impl FnOnce(&mut UpdateState) -> T) -> T {
+        let mut state = self.state.lock().unwrap_or_else(|e| e.into_inner());
+        f(&mut state)
+    }
+
+    /// Leaves a package that is no longer needed for rollback to the GC.
+    fn release(&self, package: Option<ShellPackage>) {
+        let Some(id) = package.and_then(|p| p.artifact_id) else {
+            return;
+        };
+        if let Err(e) = self.artifacts.set_retention(&id, RetentionClass::Ephemeral) {
+            tracing::warn!("Failed to release shell package {}: {}", id, e);
+        }
+    }
+
+    fn save_versions(&self, versions: &ShellVersions) {
+        let saved = std::fs::create_dir_all(&self.dir).and_then(|_| {
+            let json = serde_json::to_string_pretty(versions).map_err(std::io::Error::other)?;
+            std::fs::write(self.dir.join(VERSIONS_FILE), json)
+        });
+        if let Err(e) = saved {
+            tracing::warn!("Failed to save shell versions: {}", e);
+        }
+    }
+
+    pub fn status(&self) -> ShellUpdateStatus {
+        self.status_at(Utc::now())
+    }
+
+    fn status_at(&self, now: DateTime<Utc>) -> ShellUpdateStatus {
+        let idle = self
+            .drain
+            .idle_since()
+            .is_some_and(|since| now - since >= RESTART_IDLE);
+        self.with_state(|state| {
+            if state.phase == ShellUpdatePhase::Restarting
+                && state.health_deadline.is_some_and(|deadline| now > deadline)
+            {
+                tracing::warn!("New shell missed its health check; rollback required");
+                state.phase = ShellUpdatePhase::RollbackRequired;
+                state.error = Some("The new shell did not report a health check in time".into());
+            }
+            ShellUpdateStatus {
+                phase: state.phase,
+                target: state.target.clone(),
+                installed: state.versions.installed.clone(),
+                previous: state.versions.previous.clone(),
+                restart_ready: state.phase == ShellUpdatePhase::Staged
+                    && idle
+                    && !self.drain.is_draining(),
+                health_deadline: state.health_deadline,
+                error: state.error.clone(),
+            }
+        })
+    }
+
+    /// Starts downloading a package. An earlier staged package is replaced.
+    pub fn stage(self: &Arc<Self>, request: StageShellRequest) -> Result<(), ShellUpdateError> {
+        let expected = hex::decode(request.sha256.trim())
+            .ok()
+            .filter(|digest| digest.len() == 32)
+            .ok_or_else(|| ShellUpdateError::InvalidChecksum(request.sha256.clone()))?;
+        let version_dir = sanitize_version(&request.version)
+            .ok_or_else(|| ShellUpdateError::InvalidVersion(request.version.clone()))?;
+        self.with_state(|state| match state.phase {
+            ShellUpdatePhase::Downloading => Err(ShellUpdateError::Busy("downloading")),
+            ShellUpdatePhase::Restarting => Err(ShellUpdateError::Busy("restarting the shell")),
+            ShellUpdatePhase::RollbackRequired => Err(ShellUpdateError::Busy("rolling back")),
+            _ => {
+                state.phase = ShellUpdatePhase::Downloading;
+                self.release(state.target.take());
+                state.health_deadline = None;
+                state.error = None;
+                Ok(())
+            }
+        })?;
+
+        let updater = self.clone();
+        tokio::spawn(async move {
+            let result = updater.download(&request, &version_dir, &expected).await;
+            updater.with_state(|state| match result {
+                Ok(package) => {
+                    tracing::info!(version = %request.version, "Shell package verified and staged");
+                    state.phase = ShellUpdatePhase::Staged;
+                    state.target = Some(package);
+                }
+                Err(e) => {
+                    tracing::warn!(version = %request.version, "Shell package rejected: {}", e);
+                    state.phase = ShellUpdatePhase::Failed;
+                    state.error = Some(e.to_string());
+                }
+            });
+        });
+        Ok(())
+    }
+
+    async fn download(
+        &self,
+        request: &StageShellRequest,
+        version_dir: &str,
+        expected: &[u8],
+    ) -> anyhow::Result<ShellPackage> {
+        let file_name = request
+            .url
+            .rsplit('/')
+            .next()
+            .filter(|name| !matches!(*name, "" | "." | "..") && !name.contains(['?', '\\']))
+            .unwrap_or("shell-package");
+        let dir = self.dir.join(DOWNLOADS_FOLDER).join(version_dir);
+        std::fs::create_dir_all(&dir)?;
+        let path = dir.join(file_name);
+
+        let mut response = reqwest::get(&request.url).await?.error_for_status()?;
+        let mut file = std::fs::File::create(&path)?;
+        let mut hasher = Sha256::new();
+        while let Some(chunk) = response.chunk().await? {
+            hasher.update(&chunk);
+            file.write_all(&chunk)?;
+        }
+        file.flush()?;
+        verify(&path, hasher.finalize().as_slice(), expected)?;
+
+        let artifact = NewArtifact::new(PRODUCER, ArtifactKind::Package, file_name)
+            .with_retention(RetentionClass::Pinned)
+            .with_metadata(json!({ "version": request.version, "url": request.url }));
+        let record = self.artifacts.import_file(artifact, &path)?;
+        let _ = std::fs::remove_dir(&dir);
+        Ok(ShellPackage {
+            version: request.version.clone(),
+            path: self.artifacts.path(&record),
+            artifact_id: Some(record.id),
+        })
+    }
+
+    /// The supervisor is about to restart the shell on the staged package.
+    pub fn begin_restart(&self) -> Result<ShellUpdateStatus, ShellUpdateError> {
+        self.with_state(|state| {
+            if state.phase != ShellUpdatePhase::Staged {
+                return Err(ShellUpdateError::NotStaged);
+            }
+            state.phase = ShellUpdatePhase::Restarting;
+            state.health_deadline = Some(Utc::now() + HEALTH_CHECK_DEADLINE);
+            Ok(())
+        })?;
+        Ok(self.status())
+    }
+
+    /// Records the first health check of the restarted shell. A healthy
+    /// report for the target version completes the update; anything else
+    /// asks for a rollback.
+    pub fn report_health(
+        &self,
+        report: ShellHealthReport,
+    ) -> Result<ShellUpdateStatus, ShellUpdateError> {
+        self.with_state(|state| {
+            if state.phase != ShellUpdatePhase::Restarting {
+                return Err(ShellUpdateError::NotRestarting);
+            }
+            let target = state.target.clone().ok_or(ShellUpdateError::NotStaged)?;
+            state.health_deadline = None;
+            if report.healthy && report.version == target.version {
+                tracing::info!(version = %target.version, "Shell update completed");
+                let versions = &mut state.versions;
+                let released =
+                    std::mem::replace(&mut versions.previous, versions.installed.replace(target));
+                self.release(released);
+                state.phase = ShellUpdatePhase::Completed;
+                state.target = None;
+                self.save_versions(&state.versions);
+            } else {
+                tracing::warn!(
+                    version = %report.version,
+                    detail = ?report.detail,
+                    "New shell failed its health check; rollback required"
+                );
+                state.phase = ShellUpdatePhase::RollbackRequired;
+                state.error = Some(report.detail.unwrap_or_else(|| {
+                    format!("Shell {} failed its health check", report.version)
+                }));
+            }
+            Ok(())
+        })?;
+        Ok(self.status())
+    }
+
+    /// The supervisor started the installed package again after a failed
+    /// update.
+    pub fn confirm_rollback(&self) -> Result<ShellUpdateStatus, ShellUpdateError> {
+        self.with_state(|state| {
+            if state.phase != ShellUpdatePhase::RollbackRequired {
+                return Err(ShellUpdateError::NotRestarting);
+            }
+            state.phase = ShellUpdatePhase::RolledBack;
+            self.release(state.target.take());
+            Ok(())
+        })?;
+        Ok(self.status())
+    }
+}
+
+/// A folder name for `version`, or `None` when it would name no folder of
+/// its own, like `..`, and the download would land in the updater's state.
+fn sanitize_version(version: &str) -> Option<String> {
+    if matches!(version.trim(), "" | "." | "..") {
+        return None;
+    }
+    Some(
+        version
+            .chars()
+            .map(|c| {
+                if c.is_ascii_alphanumeric() || matches!(c, '.' | '-' | '_') {
+                    c
+                } else {
+                    '_'
+                }
+            })
+            .collect(),
+    )
+}
+
+/// Removes the download when its digest is not the expected one.
+fn verify(path: &Path, actual: &[u8], expected: &[u8]) -> anyhow::Result<()> {
+    if actual == expected {
+        return Ok(());
+    }
+    let _ = std::fs::remove_file(path);
+    anyhow::bail!(
+        "checksum mismatch: expected {}, got {}",
+        hex::encode(expected),
+        hex::encode(actual)
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tokio_util::sync::CancellationToken;
+
+    fn registry(dir: &Path) -> ArtifactRegistry {
+        ArtifactRegistry::new(dir.join("artifacts"))
+    }
+
+    fn staged(dir: &Path, drain: Arc<Drain>, version: &str) -> ShellUpdater {
+        let updater = ShellUpdater::new(dir.to_path_buf(), registry(dir), drain);
+        updater.with_state(|state| {
+            state.phase = ShellUpdatePhase::Staged;
+            state.target = Some(ShellPackage {
+                version: version.into(),
+                path: dir.join(version),
+                artifact_id: None,
+            });
+        });
+        updater
+    }
+
+    #[test]
+    fn test_restart_waits_for_idle_and_health_check_completes_update() {
+        let dir = tempfile::tempdir().unwrap();
+        let drain = Arc::new(Drain::default());
+        let updater = staged(dir.path(), drain.clone(), "1.1.0");
+
+        let turn = drain.register_turn("s1", CancellationToken::new()).unwrap();
+        assert!(
+            !updater
+                .status_at(Utc::now() + Duration::hours(1))
+                .restart_ready
+        );
+        drop(turn);
+        assert!(!updater.status_at(Utc::now()).restart_ready);
+        assert!(
+            updater
+                .status_at(Utc::now() + Duration::minutes(3))
+                .restart_ready
+        );
+
+        updater.begin_restart().unwrap();
+        let status = updater
+            .report_health(ShellHealthReport {
+                version: "1.1.0".into(),
+                healthy: true,
+                detail: None,
+            })
+            .unwrap();
+        assert_eq!(status.phase, ShellUpdatePhase::Completed);
+        assert_eq!(status.installed.unwrap().version, "1.1.0");
+
+        let reloaded = ShellUpdater::new(dir.path().to_path_buf(), registry(dir.path()), drain);
+        assert_eq!(reloaded.status().installed.unwrap().version, "1.1.0");
+    }
+
+    #[test]
+    fn test_missed_health_check_requires_rollback() {
+        let dir = tempfile::tempdir().unwrap();
+        let updater = staged(dir.path(), Arc::new(Drain::default()), "2.0.0");
+        assert_eq!(
+            updater.confirm_rollback().err(),
+            Some(ShellUpdateError::NotRestarting)
+        );
+
+        updater.begin_restart().unwrap();
+        let status = updater.status_at(Utc::now() + Duration::minutes(5));
+        assert_eq!(status.phase, ShellUpdatePhase::RollbackRequired);
+        assert!(status.installed.is_none());
+        assert_eq!(
+            updater.confirm_rollback().unwrap().phase,
+            ShellUpdatePhase::RolledBack
+        );
+    }
+
+    #[test]
+    fn test_checksum_mismatch_removes_download() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("Goose.zip");
+        std::fs::write(&path, b"package").unwrap();
+        let digest = Sha256::digest(b"package");
+        assert!(verify(&path, &digest, &digest).is_ok());
+        assert!(verify(&path, &digest, &[0; 32]).is_err());
+        assert!(!path.exists());
+    }
+
+    #[test]
+    fn test_sanitize_version_rejects_parent_folders() {
+        assert_eq!(
+            sanitize_version("1.2.0/../x").as_deref(),
+            Some("1.2.0_.._x")
+        );
+        for version in ["", " ", ".", ".."] {
+            assert_eq!(sanitize_version(version), None);
+        }
+    }
+}
@@ -9,6 +9,13 @@ use std::sync::Arc;
 use tokio::sync::Mutex;
 use tokio::task::JoinHandle;
 
+use crate::drain::Drain;
+use crate::emergency::EmergencyStop;
+use crate::idempotency::IdempotencyStore;
+use crate::jobs::JobQueue;
+use crate::maintenance::RestartScheduler;
+use crate::shell_update::ShellUpdater;
+use crate::supervisor::SupervisorWatch;
 use crate::tunnel::TunnelManager;
 use goose::agents::ExtensionLoadResult;
 
@@ -23,6 +30,13 @@ pub struct AppState {
     recipe_session_tracker: Arc<Mutex<HashSet<String>>>,
     pub tunnel_manager: Arc<TunnelManager>,
     pub extension_loading_tasks: ExtensionLoadingTasks,
+    pub emergency: Arc<EmergencyStop>,
+    pub idempotency: Arc<IdempotencyStore>,
+    pub drain: Arc<Drain>,
+    pub restart: Arc<RestartScheduler>,
+    pub shell_update: Arc<ShellUpdater>,
+    pub supervisor: Arc<SupervisorWatch>,
+    pub jobs: Arc<JobQueue>,
 }
 
 impl AppState {
@@ -31,6 +45,8 @@ impl AppState {
 
         let agent_manager = AgentManager::instance().await?;
         let tunnel_manager = Arc::new(TunnelManager::new());
+        let drain = Arc::new(Drain::default());
+        let supervisor = Arc::new(SupervisorWatch::default());
 
         Ok(Arc::new(Self {
             agent_manager,
@@ -38,6 +54,13 @@ impl AppState {
             recipe_session_tracker: Arc::new(Mutex::new(HashSet::new())),
             tunnel_manager,
             extension_loading_tasks: Arc::new(Mutex::new(HashMap::new())),
+            emergency: Arc::new(EmergencyStop::persistent()),
+            idempotency: Arc::new(IdempotencyStore::default()),
+            restart: Arc::new(RestartScheduler::new(drain.clone(), supervisor.clone())),
+            shell_update: Arc::new(ShellUpdater::persistent(drain.clone())),
+            drain,
+            supervisor,
+            jobs: Arc::new(JobQueue::persistent()),
         }))
     }
 
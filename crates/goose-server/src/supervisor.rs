@@ -0,0 +1,262 @@
+//! Heartbeats between goosed and the process supervising it.
+//!
+//! The supervisor posts a heartbeat on the bus at a fixed interval and gets
+//! goosed's own heartbeat back. Once a supervisor has registered, missing
+//! several heartbeats in a row puts goosed in safe mode: nothing would
+//! restart it, so pending restarts are held until the supervisor returns and
+//! registers again. A goosed that never saw a supervisor runs standalone.
+
+use chrono::{DateTime, Duration, Utc};
+use serde::{Deserialize, Serialize};
+use std::sync::{Arc, Mutex};
+use utoipa::ToSchema;
+
+pub const DEFAULT_HEARTBEAT_INTERVAL_SECS: u64 = 10;
+/// Consecutive missed heartbeats before the supervisor counts as lost
+const MISSED_HEARTBEAT_LIMIT: i64 = 3;
+
+#[derive(Debug, Clone, Deserialize, ToSchema)]
+pub struct SupervisorHeartbeat {
+    pub supervisor_id: String,
+    /// Seconds until the next heartbeat; defaults to 10
+    #[serde(default)]
+    pub interval_secs: Option<u64>,
+    #[serde(default)]
+    pub version: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, ToSchema)]
+pub struct ServerHeartbeat {
+    /// Changes every time goosed starts, so the supervisor can spot restarts
+    pub instance_id: String,
+    pub started_at: DateTime<Utc>,
+    /// This heartbeat registered the supervisor, either for the first time
+    /// or after it was lost
+    pub registered: bool,
+    pub active_turns: usize,
+    pub draining: bool,
+    pub restart_pending: bool,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum SupervisorState {
+    /// No supervisor has registered
+    Standalone,
+    Connected,
+    /// Heartbeats stopped arriving; goosed is in safe mode
+    Lost,
+}
+
+#[derive(Debug, Clone, Serialize, ToSchema)]
+pub struct SupervisorStatus {
+    pub state: SupervisorState,
+    pub supervisor_id: Option<String>,
+    pub supervisor_version: Option<String>,
+    pub last_heartbeat: Option<DateTime<Utc>>,
+    pub interval_secs: u64,
+    pub missed_heartbeats: i64,
+    /// Restarts are held because nothing would bring goosed back
+    pub safe_mode: bool,
+    pub registrations: u32,
+}
+
+struct Registration {
+    supervisor_id: String,
+    version: Option<String>,
+    last_heartbeat: DateTime<Utc>,
+    interval: Duration,
+}
+
+#[derive(Default)]
+struct WatchState {
+    registration: Option<Registration>,
+    registrations: u32,
+    /// Whether the last check saw the supervisor as lost, for logging
+    lost: bool,
+}
+
+pub struct SupervisorWatch {
+    state: Mutex<WatchState>,
+    instance_id: String,
+    started_at: DateTime<Utc>,
+}
+
+impl Default for SupervisorWatch {
+    fn default() -> Self {
+        Self {
+            state: Mutex::new(WatchState::default()),
+            instance_id: uuid::Uuid::new_v4().to_string(),
+            started_at: Utc::now(),
+        }
+    }
+}
+
+impl SupervisorWatch {
+    fn with_state<T>(&self, f: impl FnOnce(&mut WatchState) -> T) -> T {
+        let mut state = self.state.lock().unwrap_or_else(|e| e.into_inner());
+        f(&mut state)
+    }
+
+    pub fn instance_id(&self) -> &str {
+        &self.instance_id
+    }
+
+    pub fn started_at(&self) -> DateTime<Utc> {
+        self.started_at
+    }
+
+    fn missed(registration: &Registration, now: DateTime<Utc>) -> i64 {
+        let interval = registration.interval.num_milliseconds().max(1);
+        (now - registration.last_heartbeat)
+            .num_milliseconds()
+            .max(0)
+            / interval
+    }
+
+    /// Records a heartbeat and returns whether it (re-)registered the
+    /// supervisor.
+    pub fn heartbeat(&self, heartbeat: SupervisorHeartbeat) -> bool {
+        self.heartbeat_at(heartbeat, Utc::now())
+    }
+
+    pub(crate) fn heartbeat_at(&self, heartbeat: SupervisorHeartbeat, now: DateTime<Utc>) -> bool {
+        let interval_secs = heartbeat
+            .interval_secs
+            .unwrap_or(DEFAULT_HEARTBEAT_INTERVAL_SECS)
+            .max(1);
+        self.with_state(|state| {
+            let registered = match &state.registration {
+                Some(current) => {
+                    current.supervisor_id != heartbeat.supervisor_id
+                        || Self::missed(current, now) >= MISSED_HEARTBEAT_LIMIT
+                }
+                None => true,
+            };
+            if registered {
+                state.registrations += 1;
+                tracing::info!(
+                    supervisor_id = %heartbeat.supervisor_id,
+                    "Supervisor registered; restarts are allowed"
+                );
+            }
+            state.lost = false;
+            state.registration = Some(Registration {
+                supervisor_id: heartbeat.supervisor_id,
+                version: heartbeat.version,
+                last_heartbeat: now,
+                interval: Duration::seconds(interval_secs as i64),
+            });
+            registered
+        })
+    }
+
+    pub fn status(&self) -> SupervisorStatus {
+        self.status_at(Utc::now())
+    }
+
+    fn status_at(&self, now: DateTime<Utc>) -> SupervisorStatus {
+        self.with_state(|state| {
+            let missed = state
+                .registration
+                .as_ref()
+                .map(|r| Self::missed(r, now))
+                .unwrap_or(0);
+            let supervisor_state = match state.registration {
+                None => SupervisorState::Standalone,
+                Some(_) if missed >= MISSED_HEARTBEAT_LIMIT => SupervisorState::Lost,
+                Some(_) => SupervisorState::Connected,
+            };
+            let registration = state.registration.as_ref();
+            SupervisorStatus {
+                state: supervisor_state,
+                supervisor_id: registration.map(|r| r.supervisor_id.clone()),
+                supervisor_version: registration.and_then(|r| r.version.clone()),
+                last_heartbeat: registration.map(|r| r.last_heartbeat),
+                interval_secs: registration
+                    .map(|r| r.interval.num_seconds() as u64)
+                    .unwrap_or(DEFAULT_HEARTBEAT_INTERVAL_SECS),
+                missed_heartbeats: missed,
+                safe_mode: supervisor_state == SupervisorState::Lost,
+                registrations: state.registrations,
+            }
+        })
+    }
+
+    pub fn is_safe_mode(&self) -> bool {
+        self.status().safe_mode
+    }
+
+    /// Logs when the supervisor is lost so the transition shows up even if
+    /// nobody polls the status.
+    pub fn spawn(self: &Arc<Self>) {
+        let watch = self.clone();
+        tokio::spawn(async move {
+            let mut interval = tokio::time::interval(std::time::Duration::from_secs(
+                DEFAULT_HEARTBEAT_INTERVAL_SECS,
+            ));
+            loop {
+                interval.tick().await;
+                let status = watch.status();
+                let newly_lost = watch.with_state(|state| {
+                    let newly_lost = status.safe_mode && !state.lost;
+                    state.lost = status.safe_mode;
+                    newly_lost
+                });
+                if newly_lost {
+                    tracing::warn!(
+                        supervisor_id = ?status.supervisor_id,
+                        missed = status.missed_heartbeats,
+                        "Supervisor heartbeats stopped; entering safe mode and holding restarts"
+                    );
+                }
+            }
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn heartbeat(id: &str) -> SupervisorHeartbeat {
+        SupervisorHeartbeat {
+            supervisor_id: id.to_string(),
+            interval_secs: Some(5),
+            version: None,
+        }
+    }
+
+    #[test]
+    fn test_missed_heartbeats_enter_safe_mode_until_reregistered() {
+        let watch = SupervisorWatch::default();
+        let now = Utc::now();
+        assert_eq!(watch.status_at(now).state, SupervisorState::Standalone);
+        assert!(!watch.status_at(now).safe_mode);
+
+        assert!(watch.heartbeat_at(heartbeat("conductor"), now));
+        assert!(!watch.heartbeat_at(heartbeat("conductor"), now + Duration::seconds(5)));
+
+        let status = watch.status_at(now + Duration::seconds(14));
+        assert_eq!(status.state, SupervisorState::Connected);
+        assert_eq!(status.missed_heartbeats, 1);
+
+        let status = watch.status_at(now + Duration::seconds(21));
+        assert_eq!(status.state, SupervisorState::Lost);
+        assert!(status.safe_mode);
+
+        assert!(watch.heartbeat_at(heartbeat("conductor"), now + Duration::seconds(30)));
+        let status = watch.status_at(now + Duration::seconds(31));
+        assert_eq!(status.state, SupervisorState::Connected);
+        assert_eq!(status.registrations, 2);
+    }
+
+    #[test]
+    fn test_new_supervisor_id_registers() {
+        let watch = SupervisorWatch::default();
+        let now = Utc::now();
+        assert!(watch.heartbeat_at(heartbeat("a"), now));
+        assert!(watch.heartbeat_at(heartbeat("b"), now));
+        assert_eq!(watch.status_at(now).supervisor_id.as_deref(), Some("b"));
+    }
+}
@@ -0,0 +1,516 @@
+//! Scoped access tokens for remote clients reaching goosed through the tunnel.
+//!
+//! The tunnel secret grants full access. Access tokens are minted for a single
+//! device or person, expire, and only allow the routes they were scoped to.
+//! Tunnel management routes are never reachable with an access token.
+
+use chrono::{DateTime, Duration, Utc};
+use goose::config::Config;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::sync::Mutex;
+use utoipa::ToSchema;
+
+const ACCESS_TOKENS_KEY: &str = "tunnel_access_tokens";
+const TOKEN_PREFIX: &str = "gtk_";
+const RESERVED_PATH_PREFIX: &str = "/tunnel";
+const MAX_TOKEN_LIFETIME_SECS: i64 = 30 * 24 * 60 * 60;
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, ToSchema)]
+pub struct RoutePermission {
+    /// Path prefix this permission applies to, e.g. "/sessions"
+    pub path_prefix: String,
+    /// Allowed HTTP methods; empty allows every method
+    #[serde(default)]
+    pub methods: Vec<String>,
+}
+
+impl RoutePermission {
+    pub fn allows(&self, method: &str, path: &str) -> bool {
+        let prefix = self.path_prefix.trim_end_matches('/');
+        let path_matches = prefix.is_empty()
+            || path == prefix
+            || path
+                .strip_prefix(prefix)
+                .is_some_and(|rest| rest.starts_with('/'));
+
+        path_matches
+            && (self.methods.is_empty()
+                || self.methods.iter().any(|m| m.eq_ignore_ascii_case(method)))
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct StoredAccessToken {
+    id: String,
+    name: String,
+    token: String,
+    permissions: Vec<RoutePermission>,
+    created_at: DateTime<Utc>,
+    expires_at: DateTime<Utc>,
+}
+
+/// Public view of an access token; the token value itself is only shown once.
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct AccessTokenInfo {
+    pub id: String,
+    pub name: String,
+    pub permissions: Vec<RoutePermission>,
+    pub created_at: DateTime<Utc>,
+    pub expires_at: DateTime<Utc>,
+    pub last_used_at: Option<DateTime<Utc>>,
+}
+
+#[derive(Debug, Clone, Serialize, ToSchema)]
+pub struct MintedAccessToken {
+    #[serde(flatten)]
+    pub info: AccessTokenInfo,
+    /// Send as the X-Secret-Key header; not retrievable later
+    pub token: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct ConnectedClient {
+    /// Access token used, or None when the tunnel secret was used
+    pub token_id: Option<String>,
+    pub token_name: Option<String>,
+    pub address: Option<String>,
+    pub user_agent: Option<String>,
+    pub first_seen: DateTime<Utc>,
+    pub last_seen: DateTime<Utc>,
+    pub request_count: u64,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum AccessGrant {
+    Owner,
+    Token { id: String, name: String },
+}
+
+#[derive(Debug, thiserror::Error, PartialEq)]
+pub enum AccessError {
+    #[error("Invalid tunnel secret")]
+    InvalidCredentials,
+    #[error("Access token expired or revoked")]
+    Expired,
+    #[error("Access token does not permit {0} {1}")]
+    Forbidden(String, String),
+    #[error("Invalid request path: {0}")]
+    InvalidPath(String),
+}
+
+/// Clients are keyed by (token id, address, user agent)
+type ClientKey = (Option<String>, Option<String>, Option<String>);
+
+#[derive(Default)]
+struct AccessState {
+    loaded: bool,
+    tokens: Vec<StoredAccessToken>,
+    last_used: HashMap<String, DateTime<Utc>>,
+    clients: HashMap<ClientKey, ConnectedClient>,
+}
+
+#[derive(Default)]
+pub struct AccessRegistry {
+    state: Mutex<AccessState>,
+    persist: bool,
+}
+
+/// Constant-time comparison so token checks don't leak prefix matches
+fn constant_time_eq(a: &str, b: &str) -> bool {
+    let (a, b) = (a.as_bytes(), b.as_bytes());
+    if a.len() != b.len() {
+        return false;
+    }
+    a.iter().zip(b).fold(0u8, |acc, (x, y)| acc | (x ^ y)) == 0
+}
+
+fn percent_decode(segment: &str) -> Option<String> {
+    let bytes = segment.as_bytes();
+    let mut decoded = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        if bytes[i] == b'%' {
+            let hex = bytes.get(i + 1..i + 3)?;
+            if !hex.iter().all(u8::is_ascii_hexdigit) {
+                return None;
+            }
+            decoded.push(u8::from_str_radix(std::str::from_utf8(hex).ok()?, 16).ok()?);
+            i += 3;
+        } else {
+            decoded.push(bytes[i]);
+            i += 1;
+        }
+    }
+    String::from_utf8(decoded).ok()
+}
+
+/// Validates a tunneled request path and returns it unchanged for forwarding.
+///
+/// The forwarded request is built with `url::Url`, which drops tabs and
+/// newlines and collapses `.` and `..` segments (including their
+/// percent-encoded forms), so a path is only accepted when none of that can
+/// change which route it reaches. Scopes are then checked against exactly
+/// the path that is forwarded.
+pub fn forwarded_path(path: &str) -> Result<&str, AccessError> {
+    let invalid = || AccessError::InvalidPath(path.to_string());
+    if !path.starts_with('/') || path.contains(['\\', '#']) {
+        return Err(invalid());
+    }
+    if path.chars().any(|c| c.is_control() || c.is_whitespace()) {
+        return Err(invalid());
+    }
+    let route = path.split('?').next().unwrap_or(path);
+    for segment in route.split('/').skip(1) {
+        let decoded = percent_decode(segment).ok_or_else(invalid)?;
+        if decoded == "." || decoded == ".." || decoded.contains(['/', '\\']) {
+            return Err(invalid());
+        }
+    }
+    Ok(path)
+}
+
+fn header<'a>(headers: Option<&'a HashMap<String, String>>, name: &str) -> Option<&'a String> {
+    headers.and_then(|h| {
+        h.iter()
+            .find(|(k, _)| k.eq_ignore_ascii_case(name))
+            .map(|(_, v)| v)
+    })
+}
+
+impl AccessRegistry {
+    /// Registry backed by the secret store, shared by the tunnel and its routes.
+    pub fn persistent() -> Self {
+        Self {
+            state: Mutex::new(AccessState::default()),
+            persist: true,
+        }
+    }
+
+    fn with_state<T>(&self, f: impl FnOnce(&mut AccessState) -> T) -> T {
+        let mut state = self.state.lock().unwrap_or_else(|e| e.into_inner());
+        if !state.loaded {
+            state.loaded = true;
+            if self.persist {
+                state.tokens = Config::global()
+                    .get_secret::<Vec<StoredAccessToken>>(ACCESS_TOKENS_KEY)
+                    .unwrap_or_default();
+            }
+        }
+        let now = Utc::now();
+        state.tokens.retain(|t| t.expires_at > now);
+        f(&mut state)
+    }
+
+    fn save(&self, tokens: &[StoredAccessToken]) -> anyhow::Result<()> {
+        if !self.persist {
+            return Ok(());
+        }
+        Config::global()
+            .set_secret(ACCESS_TOKENS_KEY, &tokens.to_vec())
+            .map_err(|e| anyhow::anyhow!("Failed to save tunnel access tokens: {}", e))
+    }
+
+    fn info(
+        token: &StoredAccessToken,
+        last_used: &HashMap<String, DateTime<Utc>>,
+    ) -> AccessTokenInfo {
+        AccessTokenInfo {
+            id: token.id.clone(),
+            name: token.name.clone(),
+            permissions: token.permissions.clone(),
+            created_at: token.created_at,
+            expires_at: token.expires_at,
+            last_used_at: last_used.get(&token.id).copied(),
+        }
+    }
+
+    pub fn mint(
+        &self,
+        name: &str,
+        permissions: Vec<RoutePermission>,
+        expires_in_secs: i64,
+    ) -> anyhow::Result<MintedAccessToken> {
+        if permissions.is_empty() {
+            anyhow::bail!("An access token needs at least one route permission");
+        }
+        if expires_in_secs <= 0 || expires_in_secs > MAX_TOKEN_LIFETIME_SECS {
+            anyhow::bail!(
+                "Token lifetime must be between 1 and {} seconds",
+                MAX_TOKEN_LIFETIME_SECS
+            );
+        }
+
+        let now = Utc::now();
+        let stored = StoredAccessToken {
+            id: uuid::Uuid::new_v4().to_string(),
+            name: name.to_string(),
+            token: format!("{}{}", TOKEN_PREFIX, super::generate_secret()),
+            permissions,
+            created_at: now,
+            expires_at: now + Duration::seconds(expires_in_secs),
+        };
+
+        self.with_state(|state| {
+            state.tokens.push(stored.clone());
+            self.save(&state.tokens)?;
+            Ok(MintedAccessToken {
+                info: Self::info(&stored, &state.last_used),
+                token: stored.token,
+            })
+        })
+    }
+
+    pub fn list(&self) -> Vec<AccessTokenInfo> {
+        self.with_state(|state| {
+            state
+                .tokens
+                .iter()
+                .map(|t| Self::info(t, &state.last_used))
+                .collect()
+        })
+    }
+
+    /// Revokes a token and forgets its clients; returns false if it didn't exist.
+    pub fn revoke(&self, id: &str) -> anyhow::Result<bool> {
+        self.with_state(|state| {
+            let before = state.tokens.len();
+            state.tokens.retain(|t| t.id != id);
+            if state.tokens.len() == before {
+                return Ok(false);
+            }
+            state.last_used.remove(id);
+            state
+                .clients
+                .retain(|(token_id, _, _), _| token_id.as_deref() != Some(id));
+            self.save(&state.tokens)?;
+            Ok(true)
+        })
+    }
+
+    pub fn revoke_all(&self) -> anyhow::Result<()> {
+        self.with_state(|state| {
+            state.tokens.clear();
+            state.last_used.clear();
+            state.clients.clear();
+            self.save(&state.tokens)
+        })
+    }
+
+    pub fn clients(&self) -> Vec<ConnectedClient> {
+        self.with_state(|state| {
+            let mut clients: Vec<_> = state.clients.values().cloned().collect();
+            clients.sort_by(|a, b| b.last_seen.cmp(&a.last_seen));
+            clients
+        })
+    }
+
+    pub fn clear_clients(&self) {
+        self.with_state(|state| state.clients.clear());
+    }
+
+    /// Checks the credential sent with a tunneled request and records the client.
+    pub fn authorize(
+        &self,
+        credential: &str,
+        tunnel_secret: &str,
+        method: &str,
+        path: &str,
+        headers: Option<&HashMap<String, String>>,
+    ) -> Result<AccessGrant, AccessError> {
+        let path = forwarded_path(path)?;
+        let path = path.split('?').next().unwrap_or(path);
+
+        self.with_state(|state| {
+            let grant = if constant_time_eq(credential, tunnel_secret) {
+                AccessGrant::Owner
+            } else {
+                let stored = state
+                    .tokens
+                    .iter()
+                    .find(|t| constant_time_eq(&t.token, credential));
+                let Some(token) = stored else {
+                    // Expired tokens were pruned; tell the caller when the prefix says it was one
+                    return Err(if credential.starts_with(TOKEN_PREFIX) {
+                        AccessError::Expired
+                    } else {
+                        AccessError::InvalidCredentials
+                    });
+                };
+
+                let reserved = path == RESERVED_PATH_PREFIX
+                    || path.starts_with(&format!("{}/", RESERVED_PATH_PREFIX));
+                if reserved || !token.permissions.iter().any(|p| p.allows(method, path)) {
+                    return Err(AccessError::Forbidden(method.to_string(), path.to_string()));
+                }
+                AccessGrant::Token {
+                    id: token.id.clone(),
+                    name: token.name.clone(),
+                }
+            };
+
+            let now = Utc::now();
+            let (token_id, token_name) = match &grant {
+                AccessGrant::Owner => (None, None),
+                AccessGrant::Token { id, name } => {
+                    state.last_used.insert(id.clone(), now);
+                    (Some(id.clone()), Some(name.clone()))
+                }
+            };
+            let address = header(headers, "cf-connecting-ip")
+                .or_else(|| header(headers, "x-forwarded-for"))
+                .cloned();
+            let user_agent = header(headers, "user-agent").cloned();
+
+            let client = state
+                .clients
+                .entry((token_id.clone(), address.clone(), user_agent.clone()))
+                .or_insert_with(|| ConnectedClient {
+                    token_id,
+                    token_name,
+                    address,
+                    user_agent,
+                    first_seen: now,
+                    last_seen: now,
+                    request_count: 0,
+                });
+            client.last_seen = now;
+            client.request_count += 1;
+
+            Ok(grant)
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SECRET: &str = "tunnel-secret";
+
+    fn read_sessions() -> Vec<RoutePermission> {
+        vec![RoutePermission {
+            path_prefix: "/sessions".to_string(),
+            methods: vec!["GET".to_string()],
+        }]
+    }
+
+    #[test]
+    fn test_route_permission_matches_segments() {
+        let permission = &read_sessions()[0];
+        assert!(permission.allows("GET", "/sessions"));
+        assert!(permission.allows("get", "/sessions/abc"));
+        assert!(!permission.allows("GET", "/sessionsx"));
+        assert!(!permission.allows("DELETE", "/sessions/abc"));
+    }
+
+    #[test]
+    fn test_owner_and_scoped_token_access() {
+        let registry = AccessRegistry::default();
+        let minted = registry.mint("phone", read_sessions(), 3600).unwrap();
+
+        assert_eq!(
+            registry.authorize(SECRET, SECRET, "POST", "/reply", None),
+            Ok(AccessGrant::Owner)
+        );
+        assert!(matches!(
+            registry.authorize(&minted.token, SECRET, "GET", "/sessions/1?x=1", None),
+            Ok(AccessGrant::Token { .. })
+        ));
+        assert_eq!(
+            registry.authorize(&minted.token, SECRET, "POST", "/reply", None),
+            Err(AccessError::Forbidden("POST".into(), "/reply".into()))
+        );
+        assert_eq!(
+            registry.authorize("nope", SECRET, "GET", "/sessions", None),
+            Err(AccessError::InvalidCredentials)
+        );
+        assert!(registry.list()[0].last_used_at.is_some());
+    }
+
+    #[test]
+    fn test_tokens_cannot_reach_tunnel_routes() {
+        let registry = AccessRegistry::default();
+        let minted = registry
+            .mint(
+                "everything",
+                vec![RoutePermission {
+                    path_prefix: "/".to_string(),
+                    methods: vec![],
+                }],
+                3600,
+            )
+            .unwrap();
+
+        assert!(registry
+            .authorize(&minted.token, SECRET, "GET", "/status", None)
+            .is_ok());
+        assert!(registry
+            .authorize(&minted.token, SECRET, "POST", "/tunnel/tokens", None)
+            .is_err());
+    }
+
+    #[test]
+    fn test_dot_segments_cannot_escape_scope() {
+        let registry = AccessRegistry::default();
+        let minted = registry.mint("phone", read_sessions(), 3600).unwrap();
+
+        for path in [
+            "/sessions/../config/read",
+            "/sessions/%2e%2e/config/read",
+            "/sessions/.%2E/tunnel/tokens",
+            "/sessions/./x",
+            "/sessions/.\t./config",
+            "/sessions/%2fconfig",
+            "sessions",
+        ] {
+            assert!(
+                matches!(
+                    registry.authorize(&minted.token, SECRET, "GET", path, None),
+                    Err(AccessError::InvalidPath(_))
+                ),
+                "{} was accepted",
+                path
+            );
+        }
+        assert_eq!(
+            forwarded_path("/sessions/a%20b?q=1"),
+            Ok("/sessions/a%20b?q=1")
+        );
+    }
+
+    #[test]
+    fn test_revoke_removes_token_and_clients() {
+        let registry = AccessRegistry::default();
+        let minted = registry.mint("tablet", read_sessions(), 60).unwrap();
+        let headers = HashMap::from([("User-Agent".to_string(), "Safari".to_string())]);
+        registry
+            .authorize(&minted.token, SECRET, "GET", "/sessions", Some(&headers))
+            .unwrap();
+        registry
+            .authorize(&minted.token, SECRET, "GET", "/sessions", Some(&headers))
+            .unwrap();
+
+        let clients = registry.clients();
+        assert_eq!(clients.len(), 1);
+        assert_eq!(clients[0].request_count, 2);
+        assert_eq!(clients[0].user_agent.as_deref(), Some("Safari"));
+
+        assert!(registry.revoke(&minted.info.id).unwrap());
+        assert!(registry.clients().is_empty());
+        assert_eq!(
+            registry.authorize(&minted.token, SECRET, "GET", "/sessions", None),
+            Err(AccessError::Expired)
+        );
+    }
+
+    #[test]
+    fn test_mint_validates_scope_and_lifetime() {
+        let registry = AccessRegistry::default();
+        assert!(registry.mint("none", vec![], 60).is_err());
+        assert!(registry.mint("forever", read_sessions(), 0).is_err());
+        assert!(registry
+            .mint("too long", read_sessions(), MAX_TOKEN_LIFETIME_SECS + 1)
+            .is_err());
+    }
+}
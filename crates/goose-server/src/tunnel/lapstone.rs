@@ -1,3 +1,4 @@
+use super::access::{self, AccessError, AccessRegistry};
 use super::TunnelInfo;
 use anyhow::{Context, Result};
 use futures::{SinkExt, StreamExt};
@@ -13,22 +14,6 @@ use tokio_tungstenite::{connect_async, tungstenite::Message};
 use tracing::{error, info, warn};
 use url::Url;
 
-/// Constant-time comparison using hash to prevent timing attacks
-fn secure_compare(a: &str, b: &str) -> bool {
-    use std::collections::hash_map::DefaultHasher;
-    use std::hash::{Hash, Hasher};
-
-    let mut hasher_a = DefaultHasher::new();
-    a.hash(&mut hasher_a);
-    let hash_a = hasher_a.finish();
-
-    let mut hasher_b = DefaultHasher::new();
-    b.hash(&mut hasher_b);
-    let hash_b = hasher_b.finish();
-
-    hash_a == hash_b
-}
-
 const WORKER_URL: &str = "https://cloudflare-tunnel-proxy.michael-neale.workers.dev";
 const IDLE_TIMEOUT_SECS: u64 = 300;
 const CONNECTION_TIMEOUT_SECS: u64 = 30;
@@ -94,10 +79,11 @@ struct TunnelResponse {
 
 fn validate_and_build_request(
     client: &reqwest::Client,
-    url: &str,
+    port: u16,
     message: &TunnelMessage,
     tunnel_secret: &str,
     server_secret: &str,
+    access: &AccessRegistry,
 ) -> Result<reqwest::RequestBuilder> {
     let incoming_secret = message
         .headers
@@ -109,17 +95,23 @@ fn validate_and_build_request(
         })
         .ok_or_else(|| anyhow::anyhow!("Missing tunnel secret header"))?;
 
-    if !secure_compare(incoming_secret, tunnel_secret) {
-        anyhow::bail!("Invalid tunnel secret");
-    }
+    let path = access::forwarded_path(&message.path)?;
+    access.authorize(
+        incoming_secret,
+        tunnel_secret,
+        &message.method,
+        path,
+        message.headers.as_ref(),
+    )?;
+    let url = format!("http://127.0.0.1:{}{}", port, path);
 
     let mut request_builder = match message.method.as_str() {
-        "GET" => client.get(url),
-        "POST" => client.post(url),
-        "PUT" => client.put(url),
-        "DELETE" => client.delete(url),
-        "PATCH" => client.patch(url),
-        _ => client.get(url),
+        "GET" => client.get(&url),
+        "POST" => client.post(&url),
+        "PUT" => client.put(&url),
+        "DELETE" => client.delete(&url),
+        "PATCH" => client.patch(&url),
+        _ => client.get(&url),
     };
 
     if let Some(headers) = &message.headers {
@@ -257,34 +249,45 @@ async fn handle_request(
     ws_tx: WebSocketSender,
     tunnel_secret: String,
     server_secret: String,
+    access: Arc<AccessRegistry>,
 ) -> Result<()> {
     let request_id = message.request_id.clone();
 
     let client = reqwest::Client::new();
-    let url = format!("http://127.0.0.1:{}{}", port, message.path);
 
-    let request_builder =
-        match validate_and_build_request(&client, &url, &message, &tunnel_secret, &server_secret) {
-            Ok(builder) => builder,
-            Err(e) => {
-                error!("✗ Authentication error [{}]: {}", request_id, e);
-                let error_response = TunnelResponse {
-                    request_id,
-                    status: 401,
-                    headers: None,
-                    body: None,
-                    error: Some(e.to_string()),
-                    chunk_index: None,
-                    total_chunks: None,
-                    is_chunked: false,
-                    is_streaming: false,
-                    is_first_chunk: false,
-                    is_last_chunk: false,
-                };
-                send_response(ws_tx, error_response).await?;
-                return Ok(());
-            }
-        };
+    let request_builder = match validate_and_build_request(
+        &client,
+        port,
+        &message,
+        &tunnel_secret,
+        &server_secret,
+        &access,
+    ) {
+        Ok(builder) => builder,
+        Err(e) => {
+            error!("✗ Authentication error [{}]: {}", request_id, e);
+            let status = match e.downcast_ref::<AccessError>() {
+                Some(AccessError::Forbidden(..)) => 403,
+                Some(AccessError::InvalidPath(_)) => 400,
+                _ => 401,
+            };
+            let error_response = TunnelResponse {
+                request_id,
+                status,
+                headers: None,
+                body: None,
+                error: Some(e.to_string()),
+                chunk_index: None,
+                total_chunks: None,
+                is_chunked: false,
+                is_streaming: false,
+                is_first_chunk: false,
+                is_last_chunk: false,
+            };
+            send_response(ws_tx, error_response).await?;
+            return Ok(());
+        }
+    };
 
     let response = match request_builder.send().await {
         Ok(resp) => resp,
@@ -392,6 +395,7 @@ fn configure_tcp_keepalive(
     }
 }
 
+#[allow(clippy::too_many_arguments)]
 async fn handle_websocket_messages(
     mut read: futures::stream::SplitStream<
         tokio_tungstenite::WebSocketStream<
@@ -402,6 +406,7 @@ async fn handle_websocket_messages(
     port: u16,
     tunnel_secret: String,
     server_secret: String,
+    access: Arc<AccessRegistry>,
     last_activity: Arc<RwLock<Instant>>,
     active_tasks: Arc<RwLock<Vec<JoinHandle<()>>>>,
 ) {
@@ -415,6 +420,7 @@ async fn handle_websocket_messages(
                         let ws_tx_clone = ws_tx.clone();
                         let tunnel_secret_clone = tunnel_secret.clone();
                         let server_secret_clone = server_secret.clone();
+                        let access_clone = access.clone();
                         let task = tokio::spawn(async move {
                             if let Err(e) = handle_request(
                                 tunnel_msg,
@@ -422,6 +428,7 @@ async fn handle_websocket_messages(
                                 ws_tx_clone,
                                 tunnel_secret_clone,
                                 server_secret_clone,
+                                access_clone,
                             )
                             .await
                             {
@@ -475,6 +482,7 @@ async fn run_single_connection(
     agent_id: String,
     tunnel_secret: String,
     server_secret: String,
+    access: Arc<AccessRegistry>,
     restart_tx: mpsc::Sender<()>,
 ) {
     let _ = rustls::crypto::ring::default_provider().install_default();
@@ -548,6 +556,7 @@ async fn run_single_connection(
             port,
             tunnel_secret.clone(),
             server_secret.clone(),
+            access,
             last_activity,
             active_tasks.clone()
         ) => {
@@ -565,6 +574,7 @@ pub async fn start(
     tunnel_secret: String,
     server_secret: String,
     agent_id: String,
+    access: Arc<AccessRegistry>,
     handle: Arc<RwLock<Option<tokio::task::JoinHandle<()>>>>,
     restart_tx: mpsc::Sender<()>,
 ) -> Result<TunnelInfo> {
@@ -580,6 +590,7 @@ pub async fn start(
             agent_id_clone,
             tunnel_secret_clone,
             server_secret_clone,
+            access,
             restart_tx,
         )
         .await;
@@ -6,6 +6,7 @@
 //! 3. Make requests to the public HTTPS URL
 //! 4. Verify they proxy through to the local server
 
+use super::access::AccessRegistry;
 use super::lapstone;
 use axum::{
     extract::Request,
@@ -88,6 +89,7 @@ async fn test_tunnel_end_to_end() {
         tunnel_secret.clone(),
         server_secret.clone(),
         agent_id.clone(),
+        Arc::new(AccessRegistry::default()),
         handle.clone(),
         restart_tx,
     )
@@ -138,6 +140,7 @@ async fn test_tunnel_post_request() {
         tunnel_secret.clone(),
         server_secret.clone(),
         agent_id.clone(),
+        Arc::new(AccessRegistry::default()),
         handle.clone(),
         restart_tx,
     )
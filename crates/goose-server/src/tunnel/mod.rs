@@ -1,9 +1,11 @@
+pub mod access;
 pub mod lapstone;
 
 #[cfg(test)]
 mod lapstone_test;
 
 use crate::configuration::Settings;
+use access::AccessRegistry;
 use fs2::FileExt as _;
 use goose::config::{paths::Paths, Config};
 use serde::{Deserialize, Serialize};
@@ -91,6 +93,7 @@ pub struct TunnelManager {
     restart_tx: Arc<RwLock<Option<mpsc::Sender<()>>>>,
     watchdog_handle: Arc<RwLock<Option<tokio::task::JoinHandle<()>>>>,
     lock_file: Arc<std::sync::Mutex<Option<File>>>,
+    access: Arc<AccessRegistry>,
 }
 
 impl Default for TunnelManager {
@@ -108,9 +111,14 @@ impl TunnelManager {
             restart_tx: Arc::new(RwLock::new(None)),
             watchdog_handle: Arc::new(RwLock::new(None)),
             lock_file: Arc::new(std::sync::Mutex::new(None)),
+            access: Arc::new(AccessRegistry::persistent()),
         }
     }
 
+    pub fn access(&self) -> &Arc<AccessRegistry> {
+        &self.access
+    }
+
     fn get_auto_start() -> bool {
         Config::global()
             .get_param("tunnel_auto_start")
@@ -229,6 +237,7 @@ impl TunnelManager {
             tunnel_secret,
             server_secret,
             agent_id,
+            self.access.clone(),
             self.lapstone_handle.clone(),
             restart_tx,
         )
@@ -316,6 +325,7 @@ impl TunnelManager {
             restart_tx: self.restart_tx.clone(),
             watchdog_handle: self.watchdog_handle.clone(),
             lock_file: self.lock_file.clone(),
+            access: self.access.clone(),
         }
     }
 
@@ -340,10 +350,20 @@ impl TunnelManager {
         *self.state.write().await = TunnelState::Idle;
         *self.info.write().await = None;
 
+        self.access.clear_clients();
+
         if clear_auto_start {
             let _ = Self::set_auto_start(false);
         }
     }
+
+    /// Stops the tunnel, revokes every access token and rotates the tunnel
+    /// secret so no previously shared credential keeps working.
+    pub async fn kill(&self) -> anyhow::Result<()> {
+        self.stop(true).await;
+        self.access.revoke_all()?;
+        Self::set_secret(&generate_secret())
+    }
 }
 
 fn generate_secret() -> String {
@@ -1,32 +1,255 @@
+//! Elicitations: questions the agent, its extensions or its guardrails put
+//! to the user while a turn waits for the answer.
+//!
+//! Each elicitation belongs to the session that raised it, taken from the
+//! caller or the task's session scope, and is queued for that session's
+//! reply stream. A subagent's session can be routed to its parent, so the
+//! question shows up where the user is looking. A client (one connected UI)
+//! can claim a session; elicitations raised there record the claiming
+//! client and only it may answer them. Elicitations raised outside any
+//! session go to whichever session drains first.
+//!
+//! Unanswered elicitations expire. `GOOSE_ELICITATION_DEFAULTS` sets, per
+//! kind, how long to wait and the response to use on expiry:
+//!
+//! ```yaml
+//! GOOSE_ELICITATION_DEFAULTS:
+//!   prompt_injection:
+//!     timeout_secs: 120
+//!     response: { approved: false }
+//! ```
+//!
+//! Answers are validated against the elicitation's schema, with the form
+//! widgets of [`crate::elicitation_form`] applied, before the waiting
+//! request receives them.
+//!
+//! Pending elicitations and the most recently resolved ones, with how they
+//! were resolved, are listed by [`ActionRequiredManager::list_pending`] and
+//! [`ActionRequiredManager::recent`], so a request is never silently lost.
+
 use anyhow::Result;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
 use serde_json::Value;
-use std::collections::HashMap;
+use std::collections::{HashMap, VecDeque};
+use std::path::PathBuf;
 use std::sync::Arc;
 use std::time::Duration;
-use tokio::sync::{mpsc, Mutex, RwLock};
+use tokio::sync::{Mutex, RwLock};
 use tokio::time::timeout;
 use tracing::warn;
+use utoipa::ToSchema;
 use uuid::Uuid;
 
+use crate::config::Config;
 use crate::conversation::message::{Message, MessageContent};
+use crate::elicitation_form::{check_response, has_path_widgets};
+use crate::session::SessionManager;
+use crate::session_context::current_session_id;
+
+pub const ELICITATION_DEFAULTS_KEY: &str = "GOOSE_ELICITATION_DEFAULTS";
+const DEFAULT_KIND: &str = "elicitation";
+const DEFAULT_TIMEOUT: Duration = Duration::from_secs(600);
+const RECENT_RESOLVED: usize = 50;
+/// Longest chain of session routes followed, so a cycle cannot hang a request
+const MAX_ROUTE_DEPTH: usize = 8;
+
+/// What happens to an elicitation of one kind when nobody answers.
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct ElicitationDefault {
+    /// Replaces the caller's timeout
+    pub timeout_secs: Option<u64>,
+    /// Used as the answer on expiry; without it the caller gets an error
+    pub response: Option<Value>,
+}
+
+fn default_for(kind: &str) -> ElicitationDefault {
+    Config::global()
+        .get_param::<HashMap<String, ElicitationDefault>>(ELICITATION_DEFAULTS_KEY)
+        .ok()
+        .and_then(|mut defaults| defaults.remove(kind))
+        .unwrap_or_default()
+}
+
+/// A question to put to the user.
+#[derive(Debug, Clone)]
+pub struct Elicitation {
+    kind: String,
+    message: String,
+    schema: Value,
+    session_id: Option<String>,
+    timeout: Duration,
+    secret_namespace: String,
+}
+
+impl Elicitation {
+    /// An elicitation for the session in scope, if any. `kind` names the
+    /// source, e.g. `mcp` or `plan_approval`, for defaults and listings.
+    pub fn new(kind: &str, message: String, schema: Value) -> Self {
+        Self {
+            kind: kind.to_string(),
+            message,
+            schema,
+            session_id: current_session_id(),
+            timeout: DEFAULT_TIMEOUT,
+            secret_namespace: kind.to_string(),
+        }
+    }
+
+    /// Stores the answer's secrets under the extension's namespace rather
+    /// than the kind's.
+    pub fn from_extension(mut self, name: &str) -> Self {
+        self.secret_namespace = name.to_string();
+        self
+    }
+
+    pub fn in_session(mut self, session_id: Option<String>) -> Self {
+        if session_id.is_some() {
+            self.session_id = session_id;
+        }
+        self
+    }
+
+    pub fn with_timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = timeout;
+        self
+    }
+}
+
+#[derive(Debug, Clone, Serialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct PendingElicitation {
+    pub id: String,
+    pub kind: String,
+    /// Session the question is shown in, after routing
+    pub session_id: Option<String>,
+    /// Client that must answer, when the session was claimed
+    pub client_id: Option<String>,
+    pub message: String,
+    #[schema(value_type = Object)]
+    pub schema: Value,
+    pub created_at: DateTime<Utc>,
+    pub expires_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum ElicitationOutcome {
+    Answered,
+    /// Expired and answered with the configured default
+    Defaulted,
+    Expired,
+    /// The waiting caller went away before an answer came
+    Abandoned,
+}
+
+#[derive(Debug, Clone, Serialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct ResolvedElicitation {
+    pub elicitation: PendingElicitation,
+    pub outcome: ElicitationOutcome,
+    pub resolved_at: DateTime<Utc>,
+}
 
 struct PendingRequest {
     response_tx: Option<tokio::sync::oneshot::Sender<Value>>,
+    info: PendingElicitation,
+    secret_namespace: String,
+}
+
+type PendingMap = Arc<RwLock<HashMap<String, Arc<Mutex<PendingRequest>>>>>;
+
+/// The user's answer to an elicitation created with
+/// [`ActionRequiredManager::request`].
+pub struct PendingResponse {
+    id: String,
+    kind: String,
+    rx: tokio::sync::oneshot::Receiver<Value>,
+    pending: PendingMap,
+    recent: Arc<Mutex<VecDeque<ResolvedElicitation>>>,
+}
+
+impl PendingResponse {
+    pub fn id(&self) -> &str {
+        &self.id
+    }
+
+    /// Waits for the answer. `timeout_duration` applies unless the kind has
+    /// a configured timeout.
+    pub async fn wait(mut self, timeout_duration: Duration) -> Result<Value> {
+        let default = default_for(&self.kind);
+        let limit = default
+            .timeout_secs
+            .map(Duration::from_secs)
+            .unwrap_or(timeout_duration);
+        let (result, outcome) = match timeout(limit, &mut self.rx).await {
+            Ok(Ok(user_data)) => (Ok(user_data), ElicitationOutcome::Answered),
+            Ok(Err(_)) => {
+                warn!("Response channel closed for request: {}", self.id);
+                (
+                    Err(anyhow::anyhow!("Response channel closed")),
+                    ElicitationOutcome::Abandoned,
+                )
+            }
+            Err(_) => match default.response {
+                Some(response) => {
+                    warn!(
+                        "No response to {} request {}; using the configured default",
+                        self.kind, self.id
+                    );
+                    (Ok(response), ElicitationOutcome::Defaulted)
+                }
+                None => {
+                    warn!("Timeout waiting for response: {}", self.id);
+                    (
+                        Err(anyhow::anyhow!("Timeout waiting for user response")),
+                        ElicitationOutcome::Expired,
+                    )
+                }
+            },
+        };
+
+        self.resolve(outcome).await;
+        result
+    }
+
+    async fn resolve(&self, outcome: ElicitationOutcome) {
+        let Some(request) = self.pending.write().await.remove(&self.id) else {
+            return;
+        };
+        let elicitation = request.lock().await.info.clone();
+        let mut recent = self.recent.lock().await;
+        if recent.len() == RECENT_RESOLVED {
+            recent.pop_front();
+        }
+        recent.push_back(ResolvedElicitation {
+            elicitation,
+            outcome,
+            resolved_at: Utc::now(),
+        });
+    }
 }
 
 pub struct ActionRequiredManager {
-    pending: Arc<RwLock<HashMap<String, Arc<Mutex<PendingRequest>>>>>,
-    request_tx: mpsc::UnboundedSender<Message>,
-    pub request_rx: Mutex<mpsc::UnboundedReceiver<Message>>,
+    pending: PendingMap,
+    /// Messages waiting for a session's reply stream; `None` holds those
+    /// raised outside any session
+    queues: Mutex<HashMap<Option<String>, VecDeque<Message>>>,
+    /// Sessions whose elicitations are shown in another session
+    routes: RwLock<HashMap<String, String>>,
+    /// Client that answers for each claimed session
+    claims: RwLock<HashMap<String, String>>,
+    recent: Arc<Mutex<VecDeque<ResolvedElicitation>>>,
 }
 
 impl ActionRequiredManager {
     fn new() -> Self {
-        let (request_tx, request_rx) = mpsc::unbounded_channel();
         Self {
             pending: Arc::new(RwLock::new(HashMap::new())),
-            request_tx,
-            request_rx: Mutex::new(request_rx),
+            queues: Mutex::new(HashMap::new()),
+            routes: RwLock::new(HashMap::new()),
+            claims: RwLock::new(HashMap::new()),
+            recent: Arc::new(Mutex::new(VecDeque::new())),
         }
     }
 
@@ -42,58 +265,304 @@ impl ActionRequiredManager {
         schema: Value,
         timeout_duration: Duration,
     ) -> Result<Value> {
+        self.elicit(Elicitation::new(DEFAULT_KIND, message, schema).with_timeout(timeout_duration))
+            .await
+    }
+
+    /// Queues an elicitation for its session's reply stream and waits for
+    /// the answer.
+    pub async fn elicit(&self, elicitation: Elicitation) -> Result<Value> {
+        let timeout_duration = elicitation.timeout;
+        let (message, pending) = self.request(elicitation).await;
+        let session_id = pending_session(&self.pending, pending.id()).await;
+        self.queues
+            .lock()
+            .await
+            .entry(session_id)
+            .or_default()
+            .push_back(message);
+
+        pending.wait(timeout_duration).await
+    }
+
+    /// Registers an elicitation and returns its message instead of queueing
+    /// it, for callers that deliver the message to the client themselves.
+    pub async fn request(&self, elicitation: Elicitation) -> (Message, PendingResponse) {
         let id = Uuid::new_v4().to_string();
         let (tx, rx) = tokio::sync::oneshot::channel();
-        let pending_request = PendingRequest {
-            response_tx: Some(tx),
+        let session_id = match elicitation.session_id {
+            Some(session_id) => Some(self.route(session_id).await),
+            None => None,
+        };
+        let client_id = match &session_id {
+            Some(session_id) => self.claims.read().await.get(session_id).cloned(),
+            None => None,
+        };
+        let created_at = Utc::now();
+        let info = PendingElicitation {
+            id: id.clone(),
+            kind: elicitation.kind.clone(),
+            session_id,
+            client_id,
+            message: elicitation.message.clone(),
+            schema: elicitation.schema.clone(),
+            created_at,
+            expires_at: created_at
+                + chrono::Duration::from_std(elicitation.timeout).unwrap_or_default(),
         };
 
-        self.pending
-            .write()
-            .await
-            .insert(id.clone(), Arc::new(Mutex::new(pending_request)));
-
-        let action_required_message = Message::assistant().with_content(
-            MessageContent::action_required_elicitation(id.clone(), message, schema),
+        self.pending.write().await.insert(
+            id.clone(),
+            Arc::new(Mutex::new(PendingRequest {
+                response_tx: Some(tx),
+                info,
+                secret_namespace: elicitation.secret_namespace,
+            })),
         );
 
-        if let Err(e) = self.request_tx.send(action_required_message) {
-            warn!("Failed to send action required message: {}", e);
+        let action_required_message =
+            Message::assistant().with_content(MessageContent::action_required_elicitation(
+                id.clone(),
+                elicitation.message,
+                elicitation.schema,
+            ));
+
+        let pending = PendingResponse {
+            id,
+            kind: elicitation.kind,
+            rx,
+            pending: self.pending.clone(),
+            recent: self.recent.clone(),
+        };
+        (action_required_message, pending)
+    }
+
+    /// The session a session's elicitations are shown in.
+    async fn route(&self, mut session_id: String) -> String {
+        let routes = self.routes.read().await;
+        for _ in 0..MAX_ROUTE_DEPTH {
+            match routes.get(&session_id) {
+                Some(target) => session_id = target.clone(),
+                None => break,
+            }
         }
+        session_id
+    }
 
-        let result = match timeout(timeout_duration, rx).await {
-            Ok(Ok(user_data)) => Ok(user_data),
-            Ok(Err(_)) => {
-                warn!("Response channel closed for request: {}", id);
-                Err(anyhow::anyhow!("Response channel closed"))
+    /// Shows `from`'s elicitations in `to`, e.g. a subagent's in its parent.
+    pub async fn route_session(&self, from: &str, to: &str) {
+        if from != to {
+            self.routes
+                .write()
+                .await
+                .insert(from.to_string(), to.to_string());
+        }
+    }
+
+    pub async fn unroute_session(&self, from: &str) {
+        self.routes.write().await.remove(from);
+    }
+
+    /// Makes `client_id` the client that answers `session_id`'s elicitations.
+    pub async fn claim_session(&self, session_id: &str, client_id: &str) {
+        self.claims
+            .write()
+            .await
+            .insert(session_id.to_string(), client_id.to_string());
+        for request in self.pending.read().await.values() {
+            let mut request = request.lock().await;
+            if request.info.session_id.as_deref() == Some(session_id) {
+                request.info.client_id = Some(client_id.to_string());
             }
-            Err(_) => {
-                warn!("Timeout waiting for response: {}", id);
-                Err(anyhow::anyhow!("Timeout waiting for user response"))
+        }
+    }
+
+    pub async fn release_session(&self, session_id: &str) {
+        self.claims.write().await.remove(session_id);
+    }
+
+    /// Elicitation messages waiting for `session_id`'s reply stream, and
+    /// those raised outside any session.
+    pub async fn drain(&self, session_id: &str) -> Vec<Message> {
+        let mut queues = self.queues.lock().await;
+        let mut messages: Vec<Message> = queues
+            .remove(&Some(session_id.to_string()))
+            .unwrap_or_default()
+            .into();
+        messages.extend(queues.remove(&None).unwrap_or_default());
+        messages
+    }
+
+    /// Unanswered elicitations, oldest first, optionally for one session.
+    pub async fn list_pending(&self, session_id: Option<&str>) -> Vec<PendingElicitation> {
+        let mut pending = Vec::new();
+        for request in self.pending.read().await.values() {
+            let info = request.lock().await.info.clone();
+            if session_id.is_none() || info.session_id.as_deref() == session_id {
+                pending.push(info);
             }
-        };
+        }
+        pending.sort_by_key(|p| p.created_at);
+        pending
+    }
 
-        self.pending.write().await.remove(&id);
+    /// The most recently resolved elicitations, oldest first.
+    pub async fn recent(&self) -> Vec<ResolvedElicitation> {
+        self.recent.lock().await.iter().cloned().collect()
+    }
 
-        result
+    /// Answers an elicitation; see [`Self::submit_response_from`].
+    pub async fn submit_response(&self, request_id: String, user_data: Value) -> Result<Value> {
+        self.submit_response_from(&request_id, None, user_data)
+            .await
     }
 
-    pub async fn submit_response(&self, request_id: String, user_data: Value) -> Result<()> {
+    /// Answers an elicitation. When the session was claimed, only the
+    /// claiming client may answer; answers without a client id are accepted
+    /// from the session's own reply stream. The answer is checked against
+    /// the schema and its widgets applied first; a rejected answer leaves
+    /// the elicitation pending. Returns the answer as handed on, which is
+    /// what may be recorded.
+    pub async fn submit_response_from(
+        &self,
+        request_id: &str,
+        client_id: Option<&str>,
+        user_data: Value,
+    ) -> Result<Value> {
         let pending_arc = {
             let pending = self.pending.read().await;
             pending
-                .get(&request_id)
+                .get(request_id)
                 .cloned()
                 .ok_or_else(|| anyhow::anyhow!("Request not found: {}", request_id))?
         };
 
         let mut pending = pending_arc.lock().await;
+        if let (Some(expected), Some(client_id)) = (&pending.info.client_id, client_id) {
+            if expected != client_id {
+                anyhow::bail!(
+                    "Request {} must be answered by client {}",
+                    request_id,
+                    expected
+                );
+            }
+        }
+
+        let workspace = if has_path_widgets(&pending.info.schema) {
+            workspace_for(pending.info.session_id.as_deref()).await
+        } else {
+            None
+        };
+        let checked = check_response(
+            &pending.info.schema,
+            user_data,
+            workspace.as_deref(),
+            &pending.secret_namespace,
+        )
+        .map_err(|errors| anyhow::anyhow!("Invalid response: {}", errors.join("; ")))?;
+        for (key, value) in &checked.secrets {
+            Config::global().set_secret(key, value)?;
+        }
+
         if let Some(tx) = pending.response_tx.take() {
-            if tx.send(user_data).is_err() {
+            if tx.send(checked.user_data.clone()).is_err() {
                 warn!("Failed to send response through oneshot channel");
             }
         }
 
-        Ok(())
+        Ok(checked.user_data)
+    }
+}
+
+/// Where relative paths in a session's answers resolve. Without a session
+/// there is no workspace to confine them to, so path answers are refused.
+async fn workspace_for(session_id: Option<&str>) -> Option<PathBuf> {
+    let session_id = session_id?;
+    match SessionManager::instance()
+        .get_session(session_id, false)
+        .await
+    {
+        Ok(session) => Some(session.working_dir),
+        Err(e) => {
+            warn!("No working directory for session {}: {}", session_id, e);
+            None
+        }
+    }
+}
+
+async fn pending_session(pending: &PendingMap, id: &str) -> Option<String> {
+    match pending.read().await.get(id) {
+        Some(request) => request.lock().await.info.session_id.clone(),
+        None => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[tokio::test]
+    async fn test_concurrent_elicitations_reach_their_sessions() {
+        let manager = Arc::new(ActionRequiredManager::new());
+        manager.route_session("subagent-1", "parent").await;
+        manager.claim_session("parent", "desktop").await;
+
+        let ask = |session: &str| {
+            let manager = manager.clone();
+            let elicitation = Elicitation::new("test", format!("from {}", session), json!({}))
+                .in_session(Some(session.to_string()))
+                .with_timeout(Duration::from_secs(5));
+            tokio::spawn(async move { manager.elicit(elicitation).await })
+        };
+        let parent = ask("subagent-1");
+        let other = ask("other");
+        while manager.list_pending(None).await.len() < 2 {
+            tokio::task::yield_now().await;
+        }
+
+        assert_eq!(manager.drain("parent").await.len(), 1);
+        assert_eq!(manager.drain("other").await.len(), 1);
+        let pending = manager.list_pending(Some("parent")).await;
+        assert_eq!(pending.len(), 1);
+        assert_eq!(pending[0].client_id.as_deref(), Some("desktop"));
+
+        let id = pending[0].id.clone();
+        assert!(manager
+            .submit_response_from(&id, Some("phone"), json!({"ok": true}))
+            .await
+            .is_err());
+        manager
+            .submit_response_from(&id, Some("desktop"), json!({"ok": true}))
+            .await
+            .unwrap();
+        assert_eq!(parent.await.unwrap().unwrap(), json!({"ok": true}));
+
+        let other_id = manager.list_pending(None).await[0].id.clone();
+        manager
+            .submit_response(other_id, json!({"ok": false}))
+            .await
+            .unwrap();
+        other.await.unwrap().unwrap();
+        assert!(manager.list_pending(None).await.is_empty());
+        let recent = manager.recent().await;
+        assert_eq!(recent.len(), 2);
+        assert!(recent
+            .iter()
+            .all(|r| r.outcome == ElicitationOutcome::Answered));
+    }
+
+    #[tokio::test]
+    async fn test_expired_elicitation_is_recorded() {
+        let manager = ActionRequiredManager::new();
+        let (_, pending) = manager
+            .request(Elicitation::new("test", "still there?".into(), json!({})))
+            .await;
+        assert!(pending.wait(Duration::from_millis(10)).await.is_err());
+        assert!(manager.list_pending(None).await.is_empty());
+        assert_eq!(
+            manager.recent().await[0].outcome,
+            ElicitationOutcome::Expired
+        );
     }
 }
@@ -6,10 +6,17 @@
 
 use super::QualityStandards;
 use crate::agents::adversarial::player::PlayerResult;
+use crate::quality::{ChangeValidator, CheckResult, PostCodeValidator};
+use crate::utils::safe_truncate;
 use anyhow::Result;
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
+use std::sync::Arc;
 use tracing::{debug, info, warn};
+use utoipa::ToSchema;
+
+/// Validator output and tool output quoted as evidence are cut to this length
+const MAX_EVIDENCE_CHARS: usize = 2000;
 
 /// Configuration for Coach agent
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -29,6 +36,13 @@ pub struct CoachConfig {
     pub system_prompt: String,
     /// Read-only mode (Coach cannot modify files)
     pub read_only: bool,
+    /// Run the quality validators on the Player's changed files before reviewing
+    #[serde(default = "default_static_analysis")]
+    pub static_analysis: bool,
+}
+
+fn default_static_analysis() -> bool {
+    true
 }
 
 impl Default for CoachConfig {
@@ -46,6 +60,7 @@ impl Default for CoachConfig {
                 completeness, code quality, and adherence to best practices."
                 .to_string(),
             read_only: true,
+            static_analysis: true,
         }
     }
 }
@@ -80,10 +95,13 @@ pub struct ReviewIssue {
     pub description: String,
     /// Location of the issue (file path, line number, etc.)
     pub location: Option<String>,
+    /// Validator finding or tool output backing the issue
+    #[serde(default)]
+    pub evidence: Option<String>,
 }
 
 /// Severity of a review issue
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
 pub enum IssueSeverity {
     /// Critical issue that must be fixed
     Critical,
@@ -96,7 +114,7 @@ pub enum IssueSeverity {
 }
 
 /// Category of review issue
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
 pub enum IssueCategory {
     /// Compilation or syntax errors
     CompilationError,
@@ -118,6 +136,26 @@ pub enum IssueCategory {
     Other,
 }
 
+impl IssueCategory {
+    /// Correctness (compilation, tests) and style (code quality, best
+    /// practice) issues must cite a validator finding or a code location.
+    pub fn requires_citation(self) -> bool {
+        matches!(
+            self,
+            IssueCategory::CompilationError
+                | IssueCategory::TestFailure
+                | IssueCategory::CodeQuality
+                | IssueCategory::BestPractice
+        )
+    }
+}
+
+impl ReviewIssue {
+    pub fn is_grounded(&self) -> bool {
+        !self.category.requires_citation() || self.location.is_some() || self.evidence.is_some()
+    }
+}
+
 impl CoachReview {
     /// Create an approved review
     pub fn approved(quality_score: f32) -> Self {
@@ -193,6 +231,13 @@ pub struct CoachAgent {
     review_count: usize,
     total_approvals: usize,
     total_rejections: usize,
+    /// Runs on the changed files when `static_analysis` is on
+    validator: Arc<dyn ChangeValidator>,
+}
+
+/// The validators the Coach runs, without the release build
+fn default_validator() -> Arc<dyn ChangeValidator> {
+    Arc::new(PostCodeValidator::with_strict_mode(false))
 }
 
 impl CoachAgent {
@@ -203,6 +248,7 @@ impl CoachAgent {
             review_count: 0,
             total_approvals: 0,
             total_rejections: 0,
+            validator: default_validator(),
         }
     }
 
@@ -213,9 +259,16 @@ impl CoachAgent {
             review_count: 0,
             total_approvals: 0,
             total_rejections: 0,
+            validator: default_validator(),
         }
     }
 
+    /// Replace the validator run on the Player's changed files
+    pub fn with_validator(mut self, validator: Arc<dyn ChangeValidator>) -> Self {
+        self.validator = validator;
+        self
+    }
+
     /// Get the current configuration
     pub fn config(&self) -> &CoachConfig {
         &self.config
@@ -276,7 +329,9 @@ impl CoachAgent {
     ///
     /// This checks the PlayerResult against the configured QualityStandards without
     /// making any LLM calls. It validates success state, scans for TODO/FIXME markers,
-    /// detects test command failures, and scores quality accordingly.
+    /// detects test command failures, and scores quality accordingly. With
+    /// `static_analysis` on, the quality validators run on the changed files
+    /// first and their findings are reviewed like any other issue.
     async fn review_work_internal(&self, player_result: &PlayerResult) -> Result<CoachReview> {
         let standards = &self.config.quality_standards;
         let mut issues: Vec<ReviewIssue> = Vec::new();
@@ -292,6 +347,12 @@ impl CoachAgent {
             "Reviewing player result against quality standards"
         );
 
+        // --- Check 0: static analysis — validator findings on changed files ---
+        if self.config.static_analysis {
+            issues.extend(validator_issues(self.validator.as_ref(), player_result).await);
+        }
+        let validator_findings = issues.len();
+
         // --- Check 1: zero_errors — player task must have succeeded ---
         if standards.zero_errors {
             if !player_result.success {
@@ -303,6 +364,7 @@ impl CoachAgent {
                         player_result.output.lines().next().unwrap_or("unknown error")
                     ),
                     location: None,
+                    evidence: Some(safe_truncate(&player_result.output, MAX_EVIDENCE_CHARS)),
                 });
                 suggestions.push(
                     "Fix the reported errors and re-run the task".to_string(),
@@ -326,7 +388,6 @@ impl CoachAgent {
 
             if !test_commands.is_empty() {
                 // Tests were executed — check output for failure indicators
-                let output_lower = player_result.output.to_lowercase();
                 let failure_markers = [
                     "test failed",
                     "tests failed",
@@ -336,11 +397,9 @@ impl CoachAgent {
                     "panicked at",
                     "assertion failed",
                 ];
-                let has_failure = failure_markers
-                    .iter()
-                    .any(|marker| output_lower.contains(marker));
+                let failure_lines = matching_lines(&player_result.output, &failure_markers);
 
-                if has_failure {
+                if let Some(failure_lines) = failure_lines {
                     issues.push(ReviewIssue {
                         severity: IssueSeverity::Critical,
                         category: IssueCategory::TestFailure,
@@ -353,6 +412,7 @@ impl CoachAgent {
                                 .join(", ")
                         ),
                         location: None,
+                        evidence: Some(failure_lines),
                     });
                     suggestions.push(
                         "Review test output, fix failing tests, and re-run".to_string(),
@@ -397,6 +457,7 @@ impl CoachAgent {
                             .join("; ")
                     ),
                     location: None,
+                    evidence: Some(found_markers.join("\n")),
                 });
                 suggestions.push(
                     "Resolve all TODO/FIXME comments before finalizing".to_string(),
@@ -408,18 +469,15 @@ impl CoachAgent {
 
         // --- Check 4: zero_warnings — scan output for warning indicators ---
         if standards.zero_warnings {
-            let output_lower = player_result.output.to_lowercase();
             let warning_markers = ["warning:", "warn[", "warn:"];
-            let has_warnings = warning_markers
-                .iter()
-                .any(|marker| output_lower.contains(marker));
 
-            if has_warnings {
+            if let Some(warning_lines) = matching_lines(&player_result.output, &warning_markers) {
                 issues.push(ReviewIssue {
                     severity: IssueSeverity::Minor,
                     category: IssueCategory::CodeQuality,
                     description: "Compiler or tool warnings detected in output".to_string(),
                     location: None,
+                    evidence: Some(warning_lines),
                 });
                 suggestions.push(
                     "Address all warnings to maintain clean build output".to_string(),
@@ -439,6 +497,7 @@ impl CoachAgent {
                     category: IssueCategory::Documentation,
                     description: "No documentation evidence found for changed files".to_string(),
                     location: None,
+                    evidence: None,
                 });
                 suggestions.push(
                     "Add documentation comments for public APIs in changed files".to_string(),
@@ -446,6 +505,9 @@ impl CoachAgent {
             }
         }
 
+        // --- Grounding: uncited correctness/style issues cannot block approval ---
+        let unverified = demote_ungrounded(&mut issues);
+
         // --- Score calculation ---
         // Start at 1.0 and deduct based on issue severity
         let mut quality_score: f32 = 1.0;
@@ -521,7 +583,9 @@ impl CoachAgent {
                 "files_changed",
                 player_result.files_changed.len().to_string(),
             )
-            .with_metadata("review_type", "offline_standards_check");
+            .with_metadata("review_type", "offline_standards_check")
+            .with_metadata("validator_findings", validator_findings.to_string())
+            .with_metadata("unverified_issues", unverified.to_string());
 
         Ok(review)
     }
@@ -534,6 +598,103 @@ impl CoachAgent {
     }
 }
 
+/// Runs the quality validators on the changed files that exist on disk and
+/// turns failed or warning checks into issues citing the validator output.
+async fn validator_issues(
+    validator: &dyn ChangeValidator,
+    player_result: &PlayerResult,
+) -> Vec<ReviewIssue> {
+    let files: Vec<String> = player_result
+        .files_changed
+        .iter()
+        .filter(|path| path.exists())
+        .map(|path| path.display().to_string())
+        .collect();
+    if files.is_empty() {
+        return Vec::new();
+    }
+
+    let report = match validator.validate(&files).await {
+        Ok(report) => report,
+        Err(e) => {
+            warn!(error = %e, "Quality validators failed to run");
+            return Vec::new();
+        }
+    };
+
+    report
+        .checks()
+        .iter()
+        .filter_map(|(name, result)| {
+            let (reason, details, failed) = match result {
+                CheckResult::Pass => return None,
+                CheckResult::Fail { reason, details } => (reason, details, true),
+                CheckResult::Warning { reason, details } => (reason, details, false),
+            };
+            let category = match name.as_str() {
+                "Syntax Check" | "Type Check" => IssueCategory::CompilationError,
+                "Lint Check" => IssueCategory::CodeQuality,
+                "Incomplete Markers Scan" => IssueCategory::Incomplete,
+                "Component Wiring Check" => IssueCategory::BestPractice,
+                _ => IssueCategory::Other,
+            };
+            let severity = match (failed, category) {
+                (true, IssueCategory::CompilationError) => IssueSeverity::Critical,
+                (true, _) => IssueSeverity::Major,
+                (false, _) => IssueSeverity::Minor,
+            };
+            Some(ReviewIssue {
+                severity,
+                category,
+                description: format!("{}: {}", name, reason),
+                location: details
+                    .iter()
+                    .find_map(|detail| code_location(detail, &files)),
+                evidence: Some(safe_truncate(&details.join("\n"), MAX_EVIDENCE_CHARS)),
+            })
+        })
+        .collect()
+}
+
+/// First `file:line` reference to one of `files` in validator output, either
+/// at the start of a line or after clippy's `-->` marker.
+fn code_location(detail: &str, files: &[String]) -> Option<String> {
+    detail.lines().find_map(|line| {
+        let line = line.trim().trim_start_matches("--> ");
+        files.iter().find_map(|file| {
+            let rest = line.strip_prefix(file.as_str())?.strip_prefix(':')?;
+            let number: String = rest.chars().take_while(|c| c.is_ascii_digit()).collect();
+            (!number.is_empty()).then(|| format!("{}:{}", file, number))
+        })
+    })
+}
+
+/// Up to five output lines containing any of the (lowercase) markers.
+fn matching_lines(output: &str, markers: &[&str]) -> Option<String> {
+    let lines: Vec<&str> = output
+        .lines()
+        .filter(|line| {
+            let lower = line.to_lowercase();
+            markers.iter().any(|marker| lower.contains(marker))
+        })
+        .map(str::trim)
+        .take(5)
+        .collect();
+    (!lines.is_empty()).then(|| lines.join("\n"))
+}
+
+/// Demotes correctness/style issues with neither a location nor evidence to
+/// informational notes and returns how many were demoted.
+fn demote_ungrounded(issues: &mut [ReviewIssue]) -> usize {
+    let mut demoted = 0;
+    for issue in issues.iter_mut().filter(|issue| !issue.is_grounded()) {
+        issue.severity = IssueSeverity::Info;
+        issue.description = format!("Unverified: {}", issue.description);
+        demoted += 1;
+    }
+    demoted
+}
+
 impl Default for CoachAgent {
     fn default() -> Self {
         Self::new()
@@ -543,6 +704,7 @@ impl Default for CoachAgent {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::quality::ValidationReport;
 
     #[test]
     fn test_coach_config_default() {
@@ -576,6 +738,7 @@ mod tests {
             category: IssueCategory::CompilationError,
             description: "Missing semicolon".to_string(),
             location: Some("main.rs:10".to_string()),
+            evidence: None,
         };
 
         let review = CoachReview::rejected("Compilation failed")
@@ -679,4 +842,100 @@ mod tests {
         assert_eq!(coach.review_count(), 0);
         assert_eq!(coach.approval_rate(), 0.0);
     }
+
+    #[test]
+    fn test_code_location_from_validator_output() {
+        let files = vec!["src/parser.rs".to_string()];
+        let clippy = "warning: unused variable\n  --> src/parser.rs:42:9\n   |";
+        assert_eq!(
+            code_location(clippy, &files),
+            Some("src/parser.rs:42".to_string())
+        );
+        assert_eq!(
+            code_location("src/parser.rs:7: // TODO handle eof", &files),
+            Some("src/parser.rs:7".to_string())
+        );
+        assert_eq!(code_location("src/lexer.rs:3: oops", &files), None);
+    }
+
+    #[test]
+    fn test_ungrounded_issues_are_demoted() {
+        let mut issues = vec![
+            ReviewIssue {
+                severity: IssueSeverity::Major,
+                category: IssueCategory::CodeQuality,
+                description: "Function is too long".to_string(),
+                location: None,
+                evidence: None,
+            },
+            ReviewIssue {
+                severity: IssueSeverity::Major,
+                category: IssueCategory::CodeQuality,
+                description: "Function is too long".to_string(),
+                location: Some("src/parser.rs:10".to_string()),
+                evidence: None,
+            },
+            ReviewIssue {
+                severity: IssueSeverity::Minor,
+                category: IssueCategory::Documentation,
+                description: "Missing module docs".to_string(),
+                location: None,
+                evidence: None,
+            },
+        ];
+
+        assert_eq!(demote_ungrounded(&mut issues), 1);
+        assert_eq!(issues[0].severity, IssueSeverity::Info);
+        assert!(issues[0].description.starts_with("Unverified: "));
+        assert_eq!(issues[1].severity, IssueSeverity::Major);
+        assert_eq!(issues[2].severity, IssueSeverity::Minor);
+    }
+
+    #[derive(Debug)]
+    struct FakeValidator;
+
+    #[async_trait::async_trait]
+    impl ChangeValidator for FakeValidator {
+        async fn validate(&self, files: &[String]) -> Result<ValidationReport, String> {
+            let mut report = ValidationReport::new();
+            report.add_check(
+                "Lint Check",
+                CheckResult::Fail {
+                    reason: "Linting errors found".to_string(),
+                    details: vec![format!("warning: unused import\n  --> {}:3:5", files[0])],
+                },
+            );
+            Ok(report)
+        }
+    }
+
+    #[tokio::test]
+    async fn test_coach_reviews_validator_findings() {
+        let dir = tempfile::tempdir().unwrap();
+        let file = dir.path().join("lib.rs");
+        std::fs::write(&file, "use std::fmt;\n").unwrap();
+        let mut coach = CoachAgent::new().with_validator(Arc::new(FakeValidator));
+        let player_result = PlayerResult::success("Done").with_file_change(file.clone());
+
+        let review = coach.review_work(&player_result).await.unwrap();
+        assert!(!review.approved);
+        let issue = &review.issues[0];
+        assert_eq!(issue.category, IssueCategory::CodeQuality);
+        assert_eq!(issue.location, Some(format!("{}:3", file.display())));
+        assert_eq!(review.metadata.get("validator_findings").unwrap(), "1");
+    }
+
+    #[tokio::test]
+    async fn test_coach_review_cites_test_output() {
+        let mut coach = CoachAgent::new();
+        let player_result = PlayerResult::success("running 3 tests\ntest parse ... FAILED\nok")
+            .with_command("cargo test");
+
+        let review = coach.review_work(&player_result).await.unwrap();
+        assert!(!review.approved);
+        let issue = &review.issues[0];
+        assert_eq!(issue.category, IssueCategory::TestFailure);
+        assert_eq!(issue.evidence.as_deref(), Some("test parse ... FAILED"));
+        assert_eq!(review.metadata.get("unverified_issues").unwrap(), "0");
+    }
 }
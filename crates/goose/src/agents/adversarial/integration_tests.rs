@@ -153,6 +153,7 @@ mod tests {
             category: IssueCategory::CompilationError,
             description: "Missing semicolon at line 10".to_string(),
             location: Some("main.rs:10".to_string()),
+            evidence: None,
         };
 
         let major_issue = ReviewIssue {
@@ -160,6 +161,7 @@ mod tests {
             category: IssueCategory::CodeQuality,
             description: "Complex function needs refactoring".to_string(),
             location: Some("lib.rs:50".to_string()),
+            evidence: None,
         };
 
         let review = CoachReview::rejected("Multiple issues found")
@@ -9,6 +9,7 @@
 pub mod coach;
 pub mod player;
 pub mod review;
+pub mod transcript;
 
 #[cfg(test)]
 mod integration_tests;
@@ -16,6 +17,7 @@ mod integration_tests;
 pub use coach::{CoachAgent, CoachConfig, CoachReview, IssueCategory, IssueSeverity, ReviewIssue};
 pub use player::{PlayerAgent, PlayerConfig, PlayerResult};
 pub use review::{ReviewCycle, ReviewFeedback, ReviewOutcome, ReviewStats};
+pub use transcript::{ReviewHistory, ReviewTranscript};
 
 use serde::{Deserialize, Serialize};
 
@@ -10,9 +10,10 @@ use anyhow::Result;
 use serde::{Deserialize, Serialize};
 use std::time::Instant;
 use tracing::{debug, info, warn};
+use utoipa::ToSchema;
 
 /// Outcome of a review cycle
-#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
 pub enum ReviewOutcome {
     /// Work approved by Coach
     Approved,
@@ -0,0 +1,273 @@
+//! Review Transcripts - Coach/Player exchanges kept with the session
+//!
+//! A finished review cycle is condensed into a transcript (each Player
+//! submission, the Coach's issues grouped by severity, and whether the next
+//! cycle was a revision) and stored in the session's extension data, so the
+//! exchange stays inspectable after only the approved answer reaches the user.
+
+use super::coach::{IssueCategory, IssueSeverity};
+use super::review::{ReviewFeedback, ReviewOutcome, ReviewStats};
+use crate::conversation::message::{Message, SystemNotificationType};
+use crate::session::extension_data::ExtensionState;
+use crate::utils::safe_truncate;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use utoipa::ToSchema;
+use uuid::Uuid;
+
+/// Reviews kept per session; older ones are dropped first
+const MAX_REVIEWS: usize = 20;
+/// Player output beyond this is truncated in the transcript
+const MAX_OUTPUT_CHARS: usize = 4000;
+
+/// Kind carried in the notification data so clients can render a review panel
+pub const REVIEW_HISTORY_HINT: &str = "review_history";
+
+/// What the Player handed in for one cycle
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, ToSchema)]
+pub struct PlayerSubmission {
+    pub success: bool,
+    pub output: String,
+    pub files_changed: Vec<String>,
+    pub commands_executed: Vec<String>,
+    pub duration_ms: u64,
+}
+
+/// A Coach issue as shown in the transcript
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, ToSchema)]
+pub struct TranscriptIssue {
+    pub category: IssueCategory,
+    pub description: String,
+    pub location: Option<String>,
+    /// Validator finding or tool output backing the issue
+    #[serde(default)]
+    pub evidence: Option<String>,
+}
+
+/// Coach issues grouped by severity
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize, ToSchema)]
+pub struct IssuesBySeverity {
+    #[serde(default)]
+    pub critical: Vec<TranscriptIssue>,
+    #[serde(default)]
+    pub major: Vec<TranscriptIssue>,
+    #[serde(default)]
+    pub minor: Vec<TranscriptIssue>,
+    #[serde(default)]
+    pub info: Vec<TranscriptIssue>,
+}
+
+impl IssuesBySeverity {
+    pub fn total(&self) -> usize {
+        self.critical.len() + self.major.len() + self.minor.len() + self.info.len()
+    }
+}
+
+/// One Player submission and the Coach's verdict on it
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, ToSchema)]
+pub struct TranscriptCycle {
+    /// Cycle number (1-based)
+    pub cycle: usize,
+    /// Whether this submission revised the previous one after Coach feedback
+    pub revision: bool,
+    pub submission: PlayerSubmission,
+    pub approved: bool,
+    pub quality_score: f32,
+    pub feedback: String,
+    pub issues: IssuesBySeverity,
+    pub suggestions: Vec<String>,
+    pub outcome: ReviewOutcome,
+}
+
+impl From<&ReviewFeedback> for TranscriptCycle {
+    fn from(feedback: &ReviewFeedback) -> Self {
+        let player = &feedback.player_result;
+        let review = &feedback.coach_review;
+        let mut issues = IssuesBySeverity::default();
+        for issue in &review.issues {
+            let bucket = match issue.severity {
+                IssueSeverity::Critical => &mut issues.critical,
+                IssueSeverity::Major => &mut issues.major,
+                IssueSeverity::Minor => &mut issues.minor,
+                IssueSeverity::Info => &mut issues.info,
+            };
+            bucket.push(TranscriptIssue {
+                category: issue.category,
+                description: issue.description.clone(),
+                location: issue.location.clone(),
+                evidence: issue.evidence.clone(),
+            });
+        }
+        Self {
+            cycle: feedback.cycle,
+            revision: feedback.cycle > 1,
+            submission: PlayerSubmission {
+                success: player.success,
+                output: safe_truncate(&player.output, MAX_OUTPUT_CHARS),
+                files_changed: player
+                    .files_changed
+                    .iter()
+                    .map(|p| p.display().to_string())
+                    .collect(),
+                commands_executed: player.commands_executed.clone(),
+                duration_ms: player.duration_ms,
+            },
+            approved: review.approved,
+            quality_score: review.quality_score,
+            feedback: review.feedback.clone(),
+            issues,
+            suggestions: review.suggestions.clone(),
+            outcome: feedback.outcome.clone(),
+        }
+    }
+}
+
+/// The full Coach/Player exchange for one task
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, ToSchema)]
+pub struct ReviewTranscript {
+    pub id: String,
+    pub task: String,
+    pub created_at: DateTime<Utc>,
+    pub outcome: ReviewOutcome,
+    pub avg_quality_score: f32,
+    pub total_duration_ms: u64,
+    pub cycles: Vec<TranscriptCycle>,
+}
+
+impl ReviewTranscript {
+    pub fn from_stats(task: &str, stats: &ReviewStats) -> Self {
+        Self {
+            id: Uuid::new_v4().to_string(),
+            task: task.to_string(),
+            created_at: Utc::now(),
+            outcome: stats.final_outcome.clone(),
+            avg_quality_score: stats.avg_quality_score,
+            total_duration_ms: stats.total_duration_ms,
+            cycles: stats
+                .all_feedback
+                .iter()
+                .map(TranscriptCycle::from)
+                .collect(),
+        }
+    }
+
+    /// Notification telling clients a review history is available for this
+    /// answer. Only shown to the user, never sent to the model.
+    pub fn notification(&self) -> Message {
+        let issues: usize = self.cycles.iter().map(|c| c.issues.total()).sum();
+        let msg = format!(
+            "Reviewed in {} cycle{} ({} issue{} raised)",
+            self.cycles.len(),
+            if self.cycles.len() == 1 { "" } else { "s" },
+            issues,
+            if issues == 1 { "" } else { "s" },
+        );
+        Message::assistant().with_system_notification_with_data(
+            SystemNotificationType::InlineMessage,
+            msg,
+            json!({
+                "kind": REVIEW_HISTORY_HINT,
+                "review_id": self.id,
+                "outcome": self.outcome,
+                "cycles": self.cycles.len(),
+            }),
+        )
+    }
+}
+
+/// Review transcripts stored in a session's extension data
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize, ToSchema)]
+pub struct ReviewHistory {
+    pub reviews: Vec<ReviewTranscript>,
+}
+
+impl ExtensionState for ReviewHistory {
+    const EXTENSION_NAME: &'static str = "adversarial_review";
+    const VERSION: &'static str = "v0";
+}
+
+impl ReviewHistory {
+    pub fn push(&mut self, transcript: ReviewTranscript) {
+        self.reviews.push(transcript);
+        if self.reviews.len() > MAX_REVIEWS {
+            let excess = self.reviews.len() - MAX_REVIEWS;
+            self.reviews.drain(..excess);
+        }
+    }
+
+    pub fn get(&self, id: &str) -> Option<&ReviewTranscript> {
+        self.reviews.iter().find(|r| r.id == id)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::agents::adversarial::{CoachReview, PlayerResult, ReviewIssue};
+    use crate::session::extension_data::ExtensionData;
+
+    fn stats() -> ReviewStats {
+        let mut stats = ReviewStats::new();
+        stats.add_feedback(ReviewFeedback {
+            cycle: 1,
+            player_result: PlayerResult::success("first draft"),
+            coach_review: CoachReview::rejected("Tests are missing")
+                .with_issue(ReviewIssue {
+                    severity: IssueSeverity::Major,
+                    category: IssueCategory::TestFailure,
+                    description: "No tests for the parser".to_string(),
+                    location: Some("src/parser.rs".to_string()),
+                    evidence: None,
+                })
+                .with_issue(ReviewIssue {
+                    severity: IssueSeverity::Info,
+                    category: IssueCategory::Documentation,
+                    description: "Consider a module doc".to_string(),
+                    location: None,
+                    evidence: None,
+                }),
+            outcome: ReviewOutcome::Rejected,
+        });
+        stats.add_feedback(ReviewFeedback {
+            cycle: 2,
+            player_result: PlayerResult::success("second draft"),
+            coach_review: CoachReview::approved(0.9),
+            outcome: ReviewOutcome::Approved,
+        });
+        stats.final_outcome = ReviewOutcome::Approved;
+        stats
+    }
+
+    #[test]
+    fn test_transcript_groups_issues_by_severity() {
+        let transcript = ReviewTranscript::from_stats("Write a parser", &stats());
+
+        assert_eq!(transcript.outcome, ReviewOutcome::Approved);
+        assert_eq!(transcript.cycles.len(), 2);
+        let first = &transcript.cycles[0];
+        assert!(!first.revision);
+        assert_eq!(first.submission.output, "first draft");
+        assert_eq!(first.issues.major.len(), 1);
+        assert_eq!(first.issues.info.len(), 1);
+        assert_eq!(first.issues.total(), 2);
+        assert!(transcript.cycles[1].revision);
+        assert!(transcript.cycles[1].approved);
+    }
+
+    #[test]
+    fn test_history_round_trip_and_cap() {
+        let mut history = ReviewHistory::default();
+        for _ in 0..MAX_REVIEWS + 2 {
+            history.push(ReviewTranscript::from_stats("task", &stats()));
+        }
+        assert_eq!(history.reviews.len(), MAX_REVIEWS);
+
+        let id = history.reviews[0].id.clone();
+        let mut extension_data = ExtensionData::default();
+        history.to_extension_data(&mut extension_data).unwrap();
+        let restored = ReviewHistory::from_extension_data(&extension_data).unwrap();
+        assert!(restored.get(&id).is_some());
+        assert_eq!(restored, history);
+    }
+}
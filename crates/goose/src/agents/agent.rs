@@ -2,9 +2,11 @@ use std::collections::HashMap;
 use std::future::Future;
 use std::pin::Pin;
 use std::sync::Arc;
+use std::path::{Path, PathBuf};
 use std::sync::atomic::{AtomicBool, Ordering};
 
 use anyhow::{anyhow, Context, Result};
+use futures::future::BoxFuture;
 use futures::stream::BoxStream;
 use futures::{stream, FutureExt, Stream, StreamExt, TryStreamExt};
 use uuid::Uuid;
@@ -13,21 +15,32 @@ use super::container::Container;
 use super::final_output_tool::FinalOutputTool;
 use super::platform_tools;
 use super::tool_execution::{ToolCallResult, CHAT_MODE_TOOL_SKIPPED_RESPONSE, DECLINED_RESPONSE};
+use super::tool_output;
+use super::vision::{self, CaptureTarget};
 use crate::action_required_manager::ActionRequiredManager;
+use crate::agents::adversarial::{ReviewHistory, ReviewStats, ReviewTranscript};
+use crate::agents::capability_grant::CapabilityGrant;
 use crate::agents::critic::{AggregatedCritique, CriticManager, CritiqueContext};
 use crate::agents::persistence::CheckpointManager;
 use crate::agents::reasoning::{ReasoningConfig, ReasoningManager, ReasoningMode};
 use crate::agents::observability::CostTracker;
 use crate::agents::reflexion::{AttemptAction, AttemptOutcome, ReflexionAgent, ReflexionConfig};
-use crate::guardrails::{DetectionContext, GuardrailsEngine};
+use crate::guardrails::tool_results::{InjectionAction, ToolResultScreen};
+use crate::guardrails::{DetectionContext, GuardrailsConfig, GuardrailsEngine};
 use crate::agents::extension::{ExtensionConfig, ExtensionResult, ToolInfo};
 use crate::agents::extension_manager::{get_parameter_names, ExtensionManager};
 use crate::agents::extension_manager_extension::MANAGE_EXTENSIONS_TOOL_NAME_COMPLETE;
 use crate::agents::final_output_tool::{FINAL_OUTPUT_CONTINUATION_MESSAGE, FINAL_OUTPUT_TOOL_NAME};
 use crate::agents::planner::{PlanContext, PlanManager};
-use crate::agents::platform_tools::PLATFORM_MANAGE_SCHEDULE_TOOL_NAME;
-use crate::agents::prompt_manager::PromptManager;
+use crate::agents::prefetch::{self, ToolPrefetcher};
+use crate::agents::platform_tools::{
+    PLATFORM_INSPECT_SCREEN_TOOL_NAME, PLATFORM_MANAGE_SCHEDULE_TOOL_NAME,
+    PLATFORM_READ_ATTACHMENT_TOOL_NAME, PLATFORM_SEARCH_KNOWLEDGE_TOOL_NAME,
+    PLATFORM_UPGRADE_DEPENDENCIES_TOOL_NAME, PLATFORM_WRITE_COMMIT_MESSAGE_TOOL_NAME,
+};
+use crate::agents::prompt_manager::{PromptManager, SectionTokens};
 use crate::agents::retry::{RetryManager, RetryResult};
+use crate::agents::running_tools::{RunningToolCall, RunningToolCalls};
 use crate::agents::shell_guard::ShellGuard;
 use crate::agents::subagent_task_config::TaskConfig;
 use crate::agents::subagent_tool::{
@@ -35,8 +48,11 @@ use crate::agents::subagent_tool::{
 };
 use crate::agents::types::{FrontendTool, SessionConfig, SharedProvider, ToolResultReceiver};
 use crate::approval::ApprovalPreset;
+use crate::config::layers::find_workspace_config;
 use crate::config::permission::PermissionManager;
-use crate::config::{get_enabled_extensions, Config, GooseMode};
+use crate::config::profiles::{get_profile, ActiveProfileState, SettingsProfile};
+use crate::config::{get_enabled_extensions, Config, ConfigChange, GooseMode};
+use crate::context_mgmt::budget::{with_blocks, BlockPriority, ContextBudget, InjectedBlock};
 use crate::context_mgmt::{
     check_if_compaction_needed, compact_messages, DEFAULT_COMPACTION_THRESHOLD,
 };
@@ -46,33 +62,67 @@ use crate::conversation::message::{
 };
 use crate::conversation::tool_result_serde::call_tool_result;
 use crate::conversation::{debug_conversation_fix, fix_conversation, Conversation};
+use crate::local_analytics::AnalyticsMetric;
 use crate::mcp_utils::ToolResult;
 use crate::permission::permission_inspector::PermissionInspector;
 use crate::permission::permission_judge::PermissionCheckResult;
 use crate::permission::PermissionConfirmation;
 use crate::providers::base::Provider;
 use crate::providers::errors::ProviderError;
+use crate::providers::task_routing::{self, TaskCategory};
+use crate::recipe::validation::Validation;
 use crate::recipe::{Author, Recipe, Response, Settings, SubRecipe};
 use crate::scheduler_trait::SchedulerTrait;
+use crate::security::audit_log;
+use crate::security::egress::EgressInspector;
 use crate::security::security_inspector::SecurityInspector;
+use crate::session::attachments::AttachmentContent;
 use crate::session::extension_data::{EnabledExtensionsState, ExtensionState};
-use crate::session::{Session, SessionManager, SessionType};
+use crate::session::redaction::{
+    guardrail_window, redact_conversation, GuardrailFlag, RedactionReport, RedactionRequest,
+    REDACTION_AUDIT_CATEGORY,
+};
+use crate::session::turn_snapshots;
+use crate::session::{Session, SessionManager, SessionType, WorkspaceRoots};
+use crate::startup::{self, StartupPhase};
+use crate::token_counter::create_token_counter;
 use crate::tool_inspection::ToolInspectionManager;
 use crate::tool_monitor::RepetitionInspector;
 use crate::utils::is_token_cancelled;
 use regex::Regex;
 use rmcp::model::{
     CallToolRequestParams, CallToolResult, Content, ErrorCode, ErrorData, GetPromptResult, Prompt,
-    ServerNotification, Tool,
+    Role, ServerNotification, Tool,
 };
 use serde_json::Value;
-use tokio::sync::{mpsc, Mutex};
+use tokio::sync::{broadcast, mpsc, Mutex};
 use tokio_util::sync::CancellationToken;
 use tracing::{debug, error, info, instrument, warn};
 
 const DEFAULT_MAX_TURNS: u32 = 1000;
 const COMPACTION_THINKING_TEXT: &str = "goose is compacting the conversation...";
 
+const GUARDRAILS_ENABLED_KEY: &str = "GOOSE_GUARDRAILS_ENABLED";
+const BUDGET_USD_KEY: &str = "GOOSE_BUDGET_USD";
+const AUTO_COMPACT_THRESHOLD_KEY: &str = "GOOSE_AUTO_COMPACT_THRESHOLD";
+/// Configuration keys the agent picks up without a restart
+const LIVE_CONFIG_KEYS: &[&str] = &[
+    GUARDRAILS_ENABLED_KEY,
+    BUDGET_USD_KEY,
+    AUTO_COMPACT_THRESHOLD_KEY,
+];
+
+/// A live setting as seen from the session's working directory
+fn live_param<T: for<'de> serde::Deserialize<'de>>(
+    key: &str,
+    working_dir: Option<&Path>,
+) -> Result<T, crate::config::ConfigError> {
+    match working_dir {
+        Some(dir) => Config::global().get_param_in(key, dir),
+        None => Config::global().get_param(key),
+    }
+}
+
 /// Context needed for the reply function
 pub struct ReplyContext {
     pub conversation: Conversation,
@@ -126,7 +176,9 @@ impl AgentConfig {
 }
 
 /// Execution mode determines how the agent processes tasks
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, serde::Serialize, serde::Deserialize)]
+#[derive(
+    Debug, Clone, Copy, PartialEq, Eq, Hash, Default, serde::Serialize, serde::Deserialize,
+)]
 #[serde(rename_all = "lowercase")]
 pub enum ExecutionMode {
     /// Freeform mode: LLM has full autonomy to decide tool usage and iteration
@@ -250,17 +302,28 @@ pub struct Agent {
     container: Mutex<Option<Container>>,
     shell_guard: Mutex<Option<ShellGuard>>,
     execution_mode: Mutex<ExecutionMode>,
+    /// Why the current execution mode was chosen
+    core_reason: Mutex<String>,
     plan_manager: Mutex<PlanManager>,
+    tool_prefetcher: ToolPrefetcher,
+    running_tool_calls: RunningToolCalls,
     critic_manager: Mutex<CriticManager>,
     last_critique: Mutex<Option<AggregatedCritique>>,
     guardrails_engine: Mutex<GuardrailsEngine>,
     reasoning_manager: Mutex<ReasoningManager>,
     reflexion_agent: Mutex<ReflexionAgent>,
+    /// Hand-off note from the previous session in the same workspace
+    project_context: Mutex<Option<String>>,
+    /// Recipe commands waiting on their parameter form, by elicitation id
+    pub(super) recipe_parameter_requests:
+        Mutex<HashMap<String, super::execute_commands::RecipeParameterRequest>>,
     #[cfg(feature = "memory")]
     pub(crate) memory_manager: Mutex<crate::memory::MemoryManager>,
     #[cfg(feature = "memory")]
     memory_loaded: AtomicBool,
     #[cfg(feature = "memory")]
+    embeddings_attached: AtomicBool,
+    #[cfg(feature = "memory")]
     pub(crate) mem0_client: Mutex<Option<super::mem0_client::Mem0Client>>,
     #[cfg(feature = "memory")]
     pub(crate) interactive_session: Mutex<super::hitl::InteractiveSession>,
@@ -272,6 +335,14 @@ pub struct Agent {
     compaction_manager: Mutex<crate::compaction::CompactionManager>,
     /// Per-tool rate limiter for runaway loop prevention
     tool_call_counts: Mutex<HashMap<String, (u32, std::time::Instant)>>,
+    /// Live configuration changes, applied at the start of each reply
+    config_changes: Mutex<broadcast::Receiver<ConfigChange>>,
+    /// Workspace config file the live settings were last read from, and
+    /// when it was modified
+    config_workspace: Mutex<(Option<PathBuf>, Option<std::time::SystemTime>)>,
+    /// Goose mode chosen by a settings profile, overriding the configured one
+    goose_mode_override: Mutex<Option<GooseMode>>,
+    profile_restored: AtomicBool,
 }
 
 #[derive(Clone, Debug)]
@@ -299,7 +370,8 @@ pub type ToolStream =
 // tool_stream combines a stream of ServerNotifications with a future representing the
 // final result of the tool call. MCP notifications are not request-scoped, but
 // this lets us capture all notifications emitted during the tool call for
-// simpler consumption
+// simpler consumption. Streamed tool output that piled up while the consumer
+// was busy is merged into fewer notifications.
 pub fn tool_stream<S, F>(rx: S, done: F) -> ToolStream
 where
     S: Stream<Item = ServerNotification> + Send + Unpin + 'static,
@@ -311,8 +383,18 @@ where
 
         loop {
             tokio::select! {
-                Some(msg) = rx.next() => {
+                Some(mut msg) = rx.next() => {
+                    let mut unmerged = None;
+                    while let Some(Some(next)) = rx.next().now_or_never() {
+                        if !tool_output::merge(&mut msg, &next) {
+                            unmerged = Some(next);
+                            break;
+                        }
+                    }
                     yield ToolStreamItem::Message(msg);
+                    if let Some(next) = unmerged {
+                        yield ToolStreamItem::Message(next);
+                    }
                 }
                 r = &mut done => {
                     yield ToolStreamItem::Result(r);
@@ -362,12 +444,17 @@ impl Agent {
             container: Mutex::new(None),
             shell_guard: Mutex::new(None),
             execution_mode: Mutex::new(ExecutionMode::default()),
+            core_reason: Mutex::new("the default core".to_string()),
             plan_manager: Mutex::new(PlanManager::new()),
+            tool_prefetcher: ToolPrefetcher::default(),
+            running_tool_calls: RunningToolCalls::default(),
             critic_manager: Mutex::new(CriticManager::with_defaults()),
             last_critique: Mutex::new(None),
             guardrails_engine: Mutex::new(GuardrailsEngine::with_default_detectors()),
             reasoning_manager: Mutex::new(ReasoningManager::default()),
             reflexion_agent: Mutex::new(ReflexionAgent::new(ReflexionConfig::default())),
+            project_context: Mutex::new(None),
+            recipe_parameter_requests: Mutex::new(HashMap::new()),
             #[cfg(feature = "memory")]
             memory_manager: Mutex::new(
                 crate::memory::MemoryManager::new(crate::memory::MemoryConfig::default())
@@ -376,6 +463,8 @@ impl Agent {
             #[cfg(feature = "memory")]
             memory_loaded: AtomicBool::new(false),
             #[cfg(feature = "memory")]
+            embeddings_attached: AtomicBool::new(false),
+            #[cfg(feature = "memory")]
             mem0_client: Mutex::new(None),
             #[cfg(feature = "memory")]
             interactive_session: Mutex::new(super::hitl::InteractiveSession::new()),
@@ -386,6 +475,10 @@ impl Agent {
                 crate::compaction::CompactionConfig::default(),
             )),
             tool_call_counts: Mutex::new(HashMap::new()),
+            config_changes: Mutex::new(Config::global().subscribe()),
+            config_workspace: Mutex::new((None, None)),
+            goose_mode_override: Mutex::new(None),
+            profile_restored: AtomicBool::new(false),
         }
     }
 
@@ -394,11 +487,84 @@ impl Agent {
         &self.cost_tracker
     }
 
+    /// Apply configuration that changed since the last reply to guardrails,
+    /// the cost budget and compaction thresholds. The workspace layer is the
+    /// one above the session's working directory.
+    async fn apply_config_changes(&self, working_dir: Option<&Path>) {
+        let mut keys = Vec::new();
+        let workspace = working_dir.and_then(find_workspace_config);
+        let modified = workspace
+            .as_ref()
+            .and_then(|path| path.metadata().ok()?.modified().ok());
+        {
+            let mut last = self.config_workspace.lock().await;
+            if *last != (workspace.clone(), modified) {
+                // The session's workspace file is not watched; re-read
+                // everything when it appears, moves or is edited
+                keys.extend(LIVE_CONFIG_KEYS.iter().map(|k| k.to_string()));
+                *last = (workspace, modified);
+            }
+        }
+        {
+            let mut changes = self.config_changes.lock().await;
+            loop {
+                match changes.try_recv() {
+                    Ok(change) => keys.extend(change.keys),
+                    Err(broadcast::error::TryRecvError::Lagged(_)) => {
+                        // Missed some changes; re-read everything we care about
+                        keys.extend(LIVE_CONFIG_KEYS.iter().map(|k| k.to_string()));
+                    }
+                    Err(_) => break,
+                }
+            }
+        }
+
+        if keys.iter().any(|k| k == GUARDRAILS_ENABLED_KEY) {
+            let enabled = live_param::<bool>(GUARDRAILS_ENABLED_KEY, working_dir).unwrap_or(true);
+            self.set_guardrails_enabled(enabled).await;
+        }
+        if keys.iter().any(|k| k == BUDGET_USD_KEY) {
+            match live_param::<f64>(BUDGET_USD_KEY, working_dir) {
+                Ok(limit) => self.cost_tracker.set_budget(limit).await,
+                Err(_) => self.cost_tracker.clear_budget().await,
+            }
+            info!("Cost budget updated from configuration");
+        }
+        if keys.iter().any(|k| k == AUTO_COMPACT_THRESHOLD_KEY) {
+            let threshold = live_param::<f64>(AUTO_COMPACT_THRESHOLD_KEY, working_dir)
+                .unwrap_or(DEFAULT_COMPACTION_THRESHOLD);
+            self.compaction_manager
+                .lock()
+                .await
+                .set_trigger_threshold(threshold as f32);
+            info!("Compaction threshold updated to {}", threshold);
+        }
+    }
+
     /// Get compaction statistics
     pub async fn compaction_stats(&self) -> crate::compaction::CompactionStats {
         self.compaction_manager.lock().await.stats()
     }
 
+    /// Attribute details the latest compaction dropped, shown by the agent
+    /// re-reading or re-asking for them, and tune compaction accordingly.
+    async fn observe_summary_misses(&self, session_id: &str, conversation: &Conversation) {
+        let misses = crate::context_mgmt::summary_misses::global()
+            .lock()
+            .unwrap()
+            .observe(session_id, conversation.messages());
+        if misses.is_empty() {
+            return;
+        }
+        for miss in &misses {
+            debug!("Compaction summary miss ({:?}): {}", miss.kind, miss.detail);
+        }
+        self.compaction_manager
+            .lock()
+            .await
+            .record_summary_misses(misses.len());
+    }
+
     /// Create a tool inspection manager with default inspectors
     fn create_tool_inspection_manager(
         permission_manager: Arc<PermissionManager>,
@@ -408,6 +574,9 @@ impl Agent {
         // Add security inspector (highest priority - runs first)
         tool_inspection_manager.add_inspector(Box::new(SecurityInspector::new()));
 
+        // Add egress inspector (network allowlist/denylist for tool arguments)
+        tool_inspection_manager.add_inspector(Box::new(EgressInspector::new()));
+
         // Add permission inspector (medium-high priority)
         tool_inspection_manager.add_inspector(Box::new(PermissionInspector::new(
             std::collections::HashSet::new(), // readonly tools - will be populated from extension manager
@@ -462,8 +631,7 @@ impl Agent {
     async fn drain_elicitation_messages(&self, session_id: &str) -> Vec<Message> {
         let mut messages = Vec::new();
         let manager = self.config.session_manager.clone();
-        let mut elicitation_rx = ActionRequiredManager::global().request_rx.lock().await;
-        while let Ok(mut elicitation_message) = elicitation_rx.try_recv() {
+        for mut elicitation_message in ActionRequiredManager::global().drain(session_id).await {
             if elicitation_message.id.is_none() {
                 elicitation_message = elicitation_message.with_generated_id();
             }
@@ -504,7 +672,7 @@ impl Agent {
             tools,
             toolshim_tools,
             system_prompt,
-            goose_mode: self.config.goose_mode,
+            goose_mode: self.goose_mode().await,
             tool_call_cut_off: Config::global()
                 .get_param::<usize>("GOOSE_TOOL_CALL_CUTOFF")
                 .unwrap_or(10),
@@ -600,8 +768,9 @@ impl Agent {
     }
 
     /// When set, all stdio extensions will be started via `docker exec` in the specified container.
+    /// A managed container is kept, and so removed, with the agent.
     pub async fn set_container(&self, container: Option<Container>) {
-        *self.container.lock().await = container.clone();
+        *self.container.lock().await = container;
     }
 
     pub async fn container(&self) -> Option<Container> {
@@ -634,6 +803,388 @@ impl Agent {
         self.reasoning_manager.lock().await.config().mode
     }
 
+    /// The effective goose mode, including any settings profile override
+    pub async fn goose_mode(&self) -> GooseMode {
+        self.goose_mode_override
+            .lock()
+            .await
+            .unwrap_or(self.config.goose_mode)
+    }
+
+    /// Apply every setting in a profile to this agent and record the switch
+    /// in the session's audit history.
+    pub async fn apply_settings_profile(
+        &self,
+        session_id: &str,
+        profile: &SettingsProfile,
+        source: &str,
+    ) -> Result<()> {
+        self.apply_profile_settings(profile).await;
+
+        let session = self
+            .config
+            .session_manager
+            .get_session(session_id, false)
+            .await?;
+        let mut extension_data = session.extension_data;
+        let mut state =
+            ActiveProfileState::from_extension_data(&extension_data).unwrap_or_default();
+        state.record_switch(&profile.name, source);
+        state.to_extension_data(&mut extension_data)?;
+        self.config
+            .session_manager
+            .update(session_id)
+            .extension_data(extension_data)
+            .apply()
+            .await?;
+
+        audit_log::record(
+            "settings_profile",
+            serde_json::json!({
+                "session_id": session_id,
+                "profile": profile.name,
+                "source": source,
+            }),
+        );
+        info!(
+            session_id,
+            profile = %profile.name,
+            source,
+            "Settings profile switched: {}",
+            profile.summary()
+        );
+        Ok(())
+    }
+
+    /// Keep the Coach/Player exchange behind a reviewed answer with the
+    /// session and return the notification that points clients at it.
+    pub async fn record_review(
+        &self,
+        session_id: &str,
+        task: &str,
+        stats: &ReviewStats,
+    ) -> Result<Message> {
+        let transcript = ReviewTranscript::from_stats(task, stats);
+        let notification = transcript.notification();
+
+        let session = self
+            .config
+            .session_manager
+            .get_session(session_id, false)
+            .await?;
+        let mut extension_data = session.extension_data;
+        let mut history = ReviewHistory::from_extension_data(&extension_data).unwrap_or_default();
+        history.push(transcript);
+        history.to_extension_data(&mut extension_data)?;
+        self.config
+            .session_manager
+            .update(session_id)
+            .extension_data(extension_data)
+            .apply()
+            .await?;
+
+        Ok(notification)
+    }
+
+    /// Redacts or deletes stored messages, re-checks the messages around the
+    /// change with guardrails and compacts again so no summary still quotes
+    /// the removed content
+    pub async fn redact_session_messages(
+        &self,
+        session_id: &str,
+        request: &RedactionRequest,
+    ) -> Result<RedactionReport> {
+        let manager = self.config.session_manager.clone();
+        let session = manager.get_session(session_id, true).await?;
+        let conversation = session
+            .conversation
+            .ok_or_else(|| anyhow!("Session {} has no conversation", session_id))?;
+        let (redacted, outcome) = redact_conversation(&conversation, request)?;
+
+        let mut guardrail_flags = Vec::new();
+        {
+            let guardrails = self.guardrails_engine.lock().await;
+            let detection_ctx = DetectionContext {
+                session_id: session_id.to_string(),
+                ..Default::default()
+            };
+            for index in guardrail_window(&outcome.changed_indices, redacted.len()) {
+                let message = &redacted.messages()[index];
+                let text = message.as_concat_text();
+                if text.is_empty() {
+                    continue;
+                }
+                match guardrails.scan(&text, &detection_ctx).await {
+                    Ok(result) if !result.passed => guardrail_flags.push(GuardrailFlag {
+                        index,
+                        message_id: message.id.clone(),
+                        reason: result
+                            .blocked_reason
+                            .unwrap_or_else(|| "Safety check triggered".to_string()),
+                    }),
+                    Err(e) => warn!("Guardrails scan error during redaction: {}", e),
+                    _ => {}
+                }
+            }
+        }
+
+        // The removed content leaves the store before anything that can fail
+        manager.replace_conversation(session_id, &redacted).await?;
+        // Turn snapshots quote the requests as sent, removed content included
+        manager.turn_snapshots().remove_session(session_id)?;
+
+        // Compaction only tidies up after the dropped summaries, so without a
+        // provider the redacted conversation is simply summarized later
+        let mut compacted = false;
+        if redacted.iter().any(|m| m.is_agent_visible()) {
+            let compaction = async {
+                compact_messages(self.provider().await?.as_ref(), session_id, &redacted, true).await
+            };
+            match compaction.await {
+                Ok((compacted_conversation, usage)) => {
+                    manager
+                        .replace_conversation(session_id, &compacted_conversation)
+                        .await?;
+                    self.update_session_metrics(session_id, session.schedule_id, &usage, true)
+                        .await?;
+                    compacted = true;
+                }
+                Err(e) => warn!("Compaction after redaction failed: {}", e),
+            }
+        }
+
+        // The audit entry must not carry what was removed
+        audit_log::record(
+            REDACTION_AUDIT_CATEGORY,
+            serde_json::json!({
+                "session_id": session_id,
+                "action": request.action,
+                "message_ids": outcome.affected,
+                "text_chars": request.text.as_ref().map(|t| t.chars().count()),
+                "reason": request.reason,
+                "summaries_dropped": outcome.summaries_dropped,
+                "guardrail_flags": guardrail_flags.len(),
+                "compacted": compacted,
+            }),
+        );
+
+        Ok(RedactionReport {
+            outcome,
+            guardrail_flags,
+            compacted,
+        })
+    }
+
+    async fn handle_read_attachment(
+        &self,
+        session_id: &str,
+        arguments: Value,
+    ) -> ToolResult<Vec<Content>> {
+        let attachment_id = arguments
+            .get("attachment_id")
+            .and_then(|v| v.as_str())
+            .ok_or_else(|| {
+                ErrorData::new(
+                    ErrorCode::INVALID_PARAMS,
+                    "Missing 'attachment_id' parameter".to_string(),
+                    None,
+                )
+            })?;
+        let store = self.config.session_manager.attachments();
+        let not_readable =
+            |e: anyhow::Error| ErrorData::new(ErrorCode::INVALID_PARAMS, e.to_string(), None);
+        let attachment = store.get(session_id, attachment_id).map_err(not_readable)?;
+
+        match store
+            .read(session_id, attachment_id)
+            .map_err(not_readable)?
+        {
+            AttachmentContent::Text(text) => Ok(vec![Content::text(text)]),
+            AttachmentContent::Image { data, mime_type } => {
+                if self.provider_supports_images().await {
+                    Ok(vec![Content::image(data, mime_type)])
+                } else {
+                    Ok(vec![Content::text(format!(
+                        "{} is a {} image of {} bytes. The current model cannot view images.",
+                        attachment.filename, attachment.mime_type, attachment.size
+                    ))])
+                }
+            }
+        }
+    }
+
+    async fn handle_inspect_screen(
+        &self,
+        session_id: &str,
+        arguments: Value,
+    ) -> ToolResult<Vec<Content>> {
+        let target = match arguments.get("window_title").and_then(|v| v.as_str()) {
+            Some(title) => CaptureTarget::Window(title.to_string()),
+            None => CaptureTarget::Display(
+                arguments
+                    .get("display")
+                    .and_then(|v| v.as_u64())
+                    .unwrap_or(0) as usize,
+            ),
+        };
+        let question = arguments
+            .get("question")
+            .and_then(|v| v.as_str())
+            .map(str::to_string);
+        let internal =
+            |e: anyhow::Error| ErrorData::new(ErrorCode::INTERNAL_ERROR, e.to_string(), None);
+
+        let (data, mime_type) =
+            tokio::task::spawn_blocking(move || vision::capture_screen(&target))
+                .await
+                .map_err(|e| internal(e.into()))?
+                .map_err(internal)?;
+        let provider = self.provider().await.map_err(internal)?;
+        let agent_can_see = vision::supports_images(provider.as_ref());
+
+        let description = match vision::vision_provider(provider).await {
+            Ok(vision_provider) => vision::describe_screenshot(
+                vision_provider.as_ref(),
+                session_id,
+                &data,
+                mime_type,
+                question.as_deref(),
+            )
+            .await
+            .and_then(|description| Ok(serde_json::to_string_pretty(&description)?))
+            .unwrap_or_else(|e| format!("Screenshot captured but could not be described: {}", e)),
+            Err(e) => format!("Screenshot captured but could not be described: {}", e),
+        };
+
+        // Only models that can see images get the screenshot itself
+        let image = Content::image(data, mime_type).with_priority(0.0);
+        let image = if agent_can_see {
+            image
+        } else {
+            image.with_audience(vec![Role::User])
+        };
+        Ok(vec![
+            Content::text(description).with_audience(vec![Role::Assistant]),
+            image,
+        ])
+    }
+
+    async fn handle_search_knowledge(
+        &self,
+        session: &Session,
+        arguments: Value,
+    ) -> ToolResult<Vec<Content>> {
+        let query = arguments
+            .get("query")
+            .and_then(|v| v.as_str())
+            .ok_or_else(|| {
+                ErrorData::new(
+                    ErrorCode::INVALID_PARAMS,
+                    "Missing 'query' parameter".to_string(),
+                    None,
+                )
+            })?;
+        let limit = arguments
+            .get("limit")
+            .and_then(|v| v.as_u64())
+            .unwrap_or(5)
+            .clamp(1, 20) as usize;
+
+        #[cfg(feature = "memory")]
+        {
+            let roots = WorkspaceRoots::for_session(session);
+            let hits = crate::knowledge::search(&roots.paths(), query, limit)
+                .await
+                .map_err(|e| ErrorData::new(ErrorCode::INTERNAL_ERROR, e.to_string(), None))?;
+            if hits.is_empty() {
+                return Ok(vec![Content::text(format!(
+                    "No indexed documents in this workspace. Add README.md, docs/ or entries in {} to build the knowledge base.",
+                    crate::knowledge::SOURCES_FILE
+                ))]);
+            }
+            let passages: Vec<String> = hits.iter().map(crate::knowledge::format_hit).collect();
+            Ok(vec![Content::text(passages.join("\n\n"))])
+        }
+        #[cfg(not(feature = "memory"))]
+        {
+            let _ = (session, query, limit);
+            Err(ErrorData::new(
+                ErrorCode::INVALID_REQUEST,
+                "The knowledge base needs the memory feature".to_string(),
+                None,
+            ))
+        }
+    }
+
+    /// Runs an extension tool call in the workspace root it names, and
+    /// refuses calls that may write into a read-only root
+    async fn scope_to_workspace_root(
+        &self,
+        session: &Session,
+        tool_call: &mut CallToolRequestParams,
+    ) -> Result<std::path::PathBuf, crate::session::workspace::WorkspaceError> {
+        let roots = WorkspaceRoots::for_session(session);
+        if roots.roots.iter().all(|r| r.writable) && !roots.is_multi_root() {
+            return Ok(session.working_dir.clone());
+        }
+        let read_only = self
+            .extension_manager
+            .get_prefixed_tools_shared(&session.id, None)
+            .await
+            .unwrap_or_default()
+            .iter()
+            .find(|t| t.name == tool_call.name)
+            .and_then(|t| t.annotations.as_ref())
+            .and_then(|a| a.read_only_hint)
+            .unwrap_or(false);
+        roots.scope_call(&tool_call.name, &mut tool_call.arguments, !read_only)
+    }
+
+    /// Whether the current model is known to accept image input
+    async fn provider_supports_images(&self) -> bool {
+        match self.provider().await {
+            Ok(provider) => vision::supports_images(provider.as_ref()),
+            Err(_) => false,
+        }
+    }
+
+    async fn apply_profile_settings(&self, profile: &SettingsProfile) {
+        *self.goose_mode_override.lock().await = Some(profile.goose_mode);
+        self.set_approval_policy(profile.approval_preset).await;
+        self.set_reasoning_mode(profile.reasoning_mode).await;
+        self.set_core(profile.core, format!("settings profile '{}'", profile.name))
+            .await;
+        match profile.budget_usd {
+            Some(limit) => self.cost_tracker.set_budget(limit).await,
+            None => self.cost_tracker.clear_budget().await,
+        }
+        let guardrails = self.guardrails_engine.lock().await;
+        let config = profile.guardrails.apply(&guardrails.get_config().await);
+        guardrails.update_config(config).await;
+    }
+
+    /// Re-apply the session's active profile once after the agent is created,
+    /// e.g. when a session is resumed after a restart.
+    async fn restore_settings_profile(&self, session_id: &str) {
+        if self.profile_restored.swap(true, Ordering::SeqCst) {
+            return;
+        }
+        let Ok(session) = self
+            .config
+            .session_manager
+            .get_session(session_id, false)
+            .await
+        else {
+            return;
+        };
+        let profile = ActiveProfileState::from_extension_data(&session.extension_data)
+            .and_then(|state| state.active)
+            .and_then(|name| get_profile(Config::global(), &name));
+        if let Some(profile) = profile {
+            self.apply_profile_settings(&profile).await;
+        }
+    }
+
     /// Enable or disable guardrails scanning
     pub async fn set_guardrails_enabled(&self, enabled: bool) {
         let guardrails = self.guardrails_engine.lock().await;
@@ -643,6 +1194,11 @@ impl Agent {
         info!("Guardrails enabled: {}", enabled);
     }
 
+    /// The guardrails configuration in effect
+    pub async fn guardrails_config(&self) -> GuardrailsConfig {
+        self.guardrails_engine.lock().await.get_config().await
+    }
+
     /// Run a structured code→test→fix loop using StateGraphRunner (AlphaCode/LATS parity)
     /// Returns true if the loop completed successfully (all tests pass)
     pub async fn run_structured_loop(
@@ -757,6 +1313,12 @@ impl Agent {
 
     /// Set the execution mode for the agent
     pub async fn set_execution_mode(&self, mode: ExecutionMode) {
+        self.set_core(mode, "set explicitly").await;
+    }
+
+    /// Set the execution mode, recording why it was chosen
+    pub async fn set_core(&self, mode: ExecutionMode, reason: impl Into<String>) {
+        *self.core_reason.lock().await = reason.into();
         let mut current = self.execution_mode.lock().await;
         *current = mode;
 
@@ -780,6 +1342,11 @@ impl Agent {
         *self.execution_mode.lock().await
     }
 
+    /// Why the current execution mode was chosen
+    pub async fn core_reason(&self) -> String {
+        self.core_reason.lock().await.clone()
+    }
+
     /// Check if agent is in structured execution mode
     pub async fn is_structured_mode(&self) -> bool {
         *self.execution_mode.lock().await == ExecutionMode::Structured
@@ -827,6 +1394,50 @@ impl Agent {
         Ok(())
     }
 
+    /// Tool calls of the current reply that are still running
+    pub fn running_tool_calls(&self) -> Vec<RunningToolCall> {
+        self.running_tool_calls.list()
+    }
+
+    /// Cancels a single running tool call; the model gets a "cancelled by
+    /// user" result for it and the reply carries on. Returns false when no
+    /// call with this request id is running.
+    pub fn cancel_tool_call(&self, request_id: &str) -> bool {
+        self.running_tool_calls.cancel(request_id)
+    }
+
+    /// Starts the reads the current plan step hints at, so that they run
+    /// while the model works out its next reply
+    async fn prefetch_plan_tools(&self, session: &Session, tools: &[Tool], goose_mode: GooseMode) {
+        self.tool_prefetcher.clear();
+        if !prefetch::mode_allows_prefetch(goose_mode) {
+            return;
+        }
+        let calls = {
+            let manager = self.plan_manager.lock().await;
+            if !manager.is_enabled() {
+                return;
+            }
+            match manager.current_plan().and_then(|plan| plan.current()) {
+                Some(step) => prefetch::speculative_calls(
+                    step,
+                    tools,
+                    &session.working_dir,
+                    prefetch::budget(),
+                ),
+                None => return,
+            }
+        };
+        if !calls.is_empty() {
+            self.tool_prefetcher.start(
+                self.extension_manager.clone(),
+                &session.id,
+                &session.working_dir,
+                calls,
+            );
+        }
+    }
+
     /// Get the current plan context for injection into prompts
     pub async fn get_plan_context(&self) -> Option<String> {
         self.plan_manager.lock().await.get_step_context()
@@ -1020,6 +1631,55 @@ impl Agent {
         }
     }
 
+    /// Set the "previously on this project" block injected on every turn.
+    pub async fn set_project_context(&self, context: Option<String>) {
+        *self.project_context.lock().await = context;
+    }
+
+    /// Number of reflections learned from past attempts and feedback
+    pub async fn reflection_count(&self) -> usize {
+        self.reflexion_agent.lock().await.memory().all().len()
+    }
+
+    /// Remember a negative rating so similar requests see it as a past reflection.
+    /// The reflection is stored with the rating so it outlives this agent.
+    /// Returns whether the feedback produced a reflection.
+    pub async fn learn_from_feedback(
+        &self,
+        feedback: &crate::session::feedback::Feedback,
+        conversation: &Conversation,
+    ) -> bool {
+        match feedback.to_reflection(conversation) {
+            Some(reflection) => {
+                if let Err(e) = self
+                    .config
+                    .session_manager
+                    .save_feedback_reflection(feedback.id, &reflection)
+                    .await
+                {
+                    warn!(
+                        "Failed to store reflection for feedback {}: {}",
+                        feedback.id, e
+                    );
+                }
+                self.reflexion_agent.lock().await.add_reflection(reflection);
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Load the reflections learned from earlier ratings.
+    pub async fn load_feedback_reflections(&self) -> Result<usize> {
+        let reflections = self.config.session_manager.feedback_reflections().await?;
+        let count = reflections.len();
+        let mut reflexion = self.reflexion_agent.lock().await;
+        for reflection in reflections {
+            reflexion.add_reflection(reflection);
+        }
+        Ok(count)
+    }
+
     /// Check if a tool is a frontend tool
     pub async fn is_frontend_tool(&self, name: &str) -> bool {
         self.frontend_tools.lock().await.contains_key(name)
@@ -1030,9 +1690,9 @@ impl Agent {
         self.frontend_tools.lock().await.get(name).cloned()
     }
 
-    pub async fn add_final_output_tool(&self, response: Response) {
+    pub async fn add_final_output_tool(&self, response: Response, validation: Option<Validation>) {
         let mut final_output_tool = self.final_output_tool.lock().await;
-        let created_final_output_tool = FinalOutputTool::new(response);
+        let created_final_output_tool = FinalOutputTool::new(response).with_validation(validation);
         let final_output_system_prompt = created_final_output_tool.system_prompt();
         *final_output_tool = Some(created_final_output_tool);
         self.extend_system_prompt(final_output_system_prompt).await;
@@ -1049,6 +1709,7 @@ impl Agent {
         &self,
         sub_recipes: Option<Vec<SubRecipe>>,
         response: Option<Response>,
+        validation: Option<Validation>,
         include_final_output: bool,
     ) {
         if let Some(sub_recipes) = sub_recipes {
@@ -1057,7 +1718,7 @@ impl Agent {
 
         if include_final_output {
             if let Some(response) = response {
-                self.add_final_output_tool(response).await;
+                self.add_final_output_tool(response, validation).await;
             }
         }
     }
@@ -1091,6 +1752,12 @@ impl Agent {
             );
         }
 
+        crate::execution::priority::gate()
+            .yield_to_interactive(&session.id)
+            .await;
+
+        crate::local_analytics::record_count(AnalyticsMetric::ToolUsed, tool_call.name.to_string());
+
         if tool_call.name == PLATFORM_MANAGE_SCHEDULE_TOOL_NAME {
             let arguments = tool_call
                 .arguments
@@ -1108,9 +1775,88 @@ impl Agent {
             return (request_id, Ok(ToolCallResult::from(wrapped_result)));
         }
 
+        if tool_call.name == PLATFORM_INSPECT_SCREEN_TOOL_NAME {
+            let arguments = tool_call
+                .arguments
+                .map(Value::Object)
+                .unwrap_or(Value::Object(serde_json::Map::new()));
+            let result = self.handle_inspect_screen(&session.id, arguments).await;
+            let wrapped_result = result.map(|content| CallToolResult {
+                content,
+                structured_content: None,
+                is_error: Some(false),
+                meta: None,
+            });
+            return (request_id, Ok(ToolCallResult::from(wrapped_result)));
+        }
+
+        if tool_call.name == PLATFORM_SEARCH_KNOWLEDGE_TOOL_NAME {
+            let arguments = tool_call
+                .arguments
+                .map(Value::Object)
+                .unwrap_or(Value::Object(serde_json::Map::new()));
+            let result = self.handle_search_knowledge(session, arguments).await;
+            let wrapped_result = result.map(|content| CallToolResult {
+                content,
+                structured_content: None,
+                is_error: Some(false),
+                meta: None,
+            });
+            return (request_id, Ok(ToolCallResult::from(wrapped_result)));
+        }
+
+        if tool_call.name == PLATFORM_WRITE_COMMIT_MESSAGE_TOOL_NAME {
+            let arguments = tool_call
+                .arguments
+                .map(Value::Object)
+                .unwrap_or(Value::Object(serde_json::Map::new()));
+            let result =
+                super::commit_assistant::handle_tool_call(&session.working_dir, arguments).await;
+            let wrapped_result = result.map(|content| CallToolResult {
+                content,
+                structured_content: None,
+                is_error: Some(false),
+                meta: None,
+            });
+            return (request_id, Ok(ToolCallResult::from(wrapped_result)));
+        }
+
+        if tool_call.name == PLATFORM_UPGRADE_DEPENDENCIES_TOOL_NAME {
+            let arguments = tool_call
+                .arguments
+                .map(Value::Object)
+                .unwrap_or(Value::Object(serde_json::Map::new()));
+            let result =
+                super::dependency_upgrade::handle_tool_call(&session.working_dir, arguments).await;
+            let wrapped_result = result.map(|content| CallToolResult {
+                content,
+                structured_content: None,
+                is_error: Some(false),
+                meta: None,
+            });
+            return (request_id, Ok(ToolCallResult::from(wrapped_result)));
+        }
+
+        if tool_call.name == PLATFORM_READ_ATTACHMENT_TOOL_NAME {
+            let arguments = tool_call
+                .arguments
+                .map(Value::Object)
+                .unwrap_or(Value::Object(serde_json::Map::new()));
+            let result = self.handle_read_attachment(&session.id, arguments).await;
+            let wrapped_result = result.map(|content| CallToolResult {
+                content,
+                structured_content: None,
+                is_error: Some(false),
+                meta: None,
+            });
+            return (request_id, Ok(ToolCallResult::from(wrapped_result)));
+        }
+
         if tool_call.name == FINAL_OUTPUT_TOOL_NAME {
             return if let Some(final_output_tool) = self.final_output_tool.lock().await.as_mut() {
-                let result = final_output_tool.execute_tool_call(tool_call.clone()).await;
+                let result = final_output_tool
+                    .execute_tool_call_in(tool_call.clone(), &session.working_dir)
+                    .await;
                 (request_id, Ok(result))
             } else {
                 (
@@ -1125,6 +1871,12 @@ impl Agent {
         }
 
         debug!("WAITING_TOOL_START: {}", tool_call.name);
+        let running = self.running_tool_calls.start(
+            &request_id,
+            &tool_call.name,
+            cancellation_token.as_ref(),
+        );
+        let cancellation_token = Some(running.token());
         let result: ToolCallResult = if tool_call.name == SUBAGENT_TOOL_NAME {
             let provider = match self.provider().await {
                 Ok(p) => p,
@@ -1150,7 +1902,8 @@ impl Agent {
 
             let task_config =
                 TaskConfig::new(provider, &session.id, &session.working_dir, extensions)
-                    .with_max_turns(max_turns_from_recipe);
+                    .with_max_turns(max_turns_from_recipe)
+                    .with_capabilities(self.extension_manager.capability_grant());
             let sub_recipes = self.sub_recipes.lock().await.clone();
 
             let arguments = tool_call
@@ -1175,18 +1928,37 @@ impl Agent {
                 None,
             )))
         } else {
-            // Clone the result to ensure no references to extension_manager are returned
-            let shell_guard = self.shell_guard().await;
-            let result = self
-                .extension_manager
-                .dispatch_tool_call_with_guard(
-                    &session.id,
-                    tool_call.clone(),
-                    Some(session.working_dir.as_path()),
-                    cancellation_token.unwrap_or_default(),
-                    shell_guard.as_ref(),
-                )
-                .await;
+            let mut tool_call = tool_call.clone();
+            let working_dir = match self.scope_to_workspace_root(session, &mut tool_call).await {
+                Ok(working_dir) => working_dir,
+                Err(e) => {
+                    return (
+                        request_id,
+                        Err(ErrorData::new(
+                            ErrorCode::INVALID_PARAMS,
+                            e.to_string(),
+                            None,
+                        )),
+                    );
+                }
+            };
+            // A read the plan hinted at may already have run during the model call
+            let result = match self.tool_prefetcher.take(&tool_call, &working_dir).await {
+                Some(prefetched) => Ok(ToolCallResult::from(prefetched)),
+                None => {
+                    // Clone the result to ensure no references to extension_manager are returned
+                    let shell_guard = self.shell_guard().await;
+                    self.extension_manager
+                        .dispatch_tool_call_with_guard(
+                            &session.id,
+                            tool_call.clone(),
+                            Some(working_dir.as_path()),
+                            cancellation_token.unwrap_or_default(),
+                            shell_guard.as_ref(),
+                        )
+                        .await
+                }
+            };
             result.unwrap_or_else(|e| {
                 crate::posthog::emit_error(
                     "tool_execution_failed",
@@ -1207,15 +1979,31 @@ impl Agent {
             "Tool dispatch completed"
         );
 
+        let screen = {
+            let guardrails = self.guardrails_engine.lock().await;
+            ToolResultScreen::new(
+                &guardrails.get_config().await,
+                InjectionAction::from_config(),
+            )
+        };
+        let session_id = session.id.clone();
+        let tool_name = tool_call.name.to_string();
+        let response = result
+            .result
+            .map(super::large_response_handler::process_tool_response);
+
         (
             request_id,
             Ok(ToolCallResult {
                 notification_stream: result.notification_stream,
-                result: Box::new(
-                    result
-                        .result
-                        .map(super::large_response_handler::process_tool_response),
-                ),
+                result: Box::new(Box::pin(async move {
+                    match (running.run(response).await, screen) {
+                        (Ok(result), Some(screen)) => {
+                            Ok(screen.screen(&session_id, &tool_name, result).await)
+                        }
+                        (response, _) => response,
+                    }
+                })),
             }),
         )
     }
@@ -1409,7 +2197,7 @@ impl Agent {
     }
 
     pub async fn subagents_enabled(&self, session_id: &str) -> bool {
-        if self.config.goose_mode != GooseMode::Auto {
+        if self.goose_mode().await != GooseMode::Auto {
             return false;
         }
         let context = self.extension_manager.get_context();
@@ -1439,12 +2227,43 @@ impl Agent {
             .await
             .unwrap_or_default();
 
+        if let Ok(session) = self
+            .config
+            .session_manager
+            .get_session(session_id, false)
+            .await
+        {
+            let roots = WorkspaceRoots::for_session(&session);
+            if roots.is_multi_root() {
+                for tool in prefixed_tools.iter_mut() {
+                    roots.add_root_parameter(tool);
+                }
+            }
+        }
+
         let subagents_enabled = self.subagents_enabled(session_id).await;
         if (extension_name.is_none() || extension_name.as_deref() == Some("platform"))
             && self.config.scheduler_service.is_some()
         {
             prefixed_tools.push(platform_tools::manage_schedule_tool());
         }
+        if (extension_name.is_none() || extension_name.as_deref() == Some("platform"))
+            && self
+                .config
+                .session_manager
+                .attachments()
+                .list(session_id)
+                .is_ok_and(|attachments| !attachments.is_empty())
+        {
+            prefixed_tools.push(platform_tools::read_attachment_tool());
+        }
+        if extension_name.is_none() || extension_name.as_deref() == Some("platform") {
+            prefixed_tools.push(platform_tools::inspect_screen_tool());
+            prefixed_tools.push(platform_tools::write_commit_message_tool());
+            prefixed_tools.push(platform_tools::upgrade_dependencies_tool());
+            #[cfg(feature = "memory")]
+            prefixed_tools.push(platform_tools::search_knowledge_tool());
+        }
 
         if extension_name.is_none() {
             if let Some(final_output_tool) = self.final_output_tool.lock().await.as_ref() {
@@ -1497,6 +2316,16 @@ impl Agent {
         }
     }
 
+    /// Boxed so a reply stream can hand a resolved message back to `reply`.
+    fn reply_boxed(
+        &self,
+        user_message: Message,
+        session_config: SessionConfig,
+        cancel_token: Option<CancellationToken>,
+    ) -> BoxFuture<'_, Result<BoxStream<'_, Result<AgentEvent>>>> {
+        Box::pin(self.reply(user_message, session_config, cancel_token))
+    }
+
     #[instrument(skip(self, user_message, session_config), fields(user_message))]
     pub async fn reply(
         &self,
@@ -1505,26 +2334,45 @@ impl Agent {
         cancel_token: Option<CancellationToken>,
     ) -> Result<BoxStream<'_, Result<AgentEvent>>> {
         let session_manager = self.config.session_manager.clone();
+        self.restore_settings_profile(&session_config.id).await;
+        let working_dir = session_manager
+            .get_session(&session_config.id, false)
+            .await
+            .map(|session| session.working_dir)
+            .ok();
+        self.apply_config_changes(working_dir.as_deref()).await;
 
         for content in &user_message.content {
             if let MessageContent::ActionRequired(action_required) = content {
                 if let ActionRequiredData::ElicitationResponse { id, user_data } =
                     &action_required.data
                 {
-                    if let Err(e) = ActionRequiredManager::global()
+                    let recorded = match ActionRequiredManager::global()
                         .submit_response(id.clone(), user_data.clone())
                         .await
                     {
-                        let error_text = format!("Failed to submit elicitation response: {}", e);
-                        error!(error_text);
-                        return Ok(Box::pin(stream::once(async {
-                            Ok(AgentEvent::Message(
-                                Message::assistant().with_text(error_text),
-                            ))
-                        })));
-                    }
+                        Ok(recorded) => recorded,
+                        Err(e) => {
+                            let error_text =
+                                format!("Failed to submit elicitation response: {}", e);
+                            error!(error_text);
+                            return Ok(Box::pin(stream::once(async {
+                                Ok(AgentEvent::Message(
+                                    Message::assistant().with_text(error_text),
+                                ))
+                            })));
+                        }
+                    };
+                    // Record the answer as handed on, with passwords
+                    // replaced by their secret keys
+                    let mut recorded_message = user_message.clone();
+                    recorded_message.content =
+                        vec![MessageContent::action_required_elicitation_response(
+                            id.clone(),
+                            recorded,
+                        )];
                     session_manager
-                        .add_message(&session_config.id, &user_message)
+                        .add_message(&session_config.id, &recorded_message)
                         .await?;
                     return Ok(Box::pin(futures::stream::empty()));
                 }
@@ -1557,6 +2405,50 @@ impl Agent {
                 })));
             }
             Ok(Some(response)) if response.role == rmcp::model::Role::Assistant => {
+                if let Some(request) = self.take_recipe_parameter_request(&response).await {
+                    session_manager
+                        .add_message(
+                            &session_config.id,
+                            &user_message.clone().with_visibility(true, false),
+                        )
+                        .await?;
+                    session_manager
+                        .add_message(
+                            &session_config.id,
+                            &response.clone().with_visibility(true, false),
+                        )
+                        .await?;
+
+                    // The form is answered through a separate reply carrying the
+                    // elicitation response; this stream resumes once it arrives.
+                    return Ok(Box::pin(async_stream::try_stream! {
+                        yield AgentEvent::Message(user_message);
+                        yield AgentEvent::Message(response);
+
+                        let resolved = self.complete_recipe_parameters(request).await?;
+                        if resolved.role == rmcp::model::Role::Assistant {
+                            session_manager
+                                .add_message(
+                                    &session_config.id,
+                                    &resolved.clone().with_visibility(true, false),
+                                )
+                                .await?;
+                            yield AgentEvent::Message(resolved);
+                        } else {
+                            let mut stream = self
+                                .reply_boxed(
+                                    resolved.with_visibility(false, true),
+                                    session_config,
+                                    cancel_token,
+                                )
+                                .await?;
+                            while let Some(event) = stream.next().await {
+                                yield event?;
+                            }
+                        }
+                    }));
+                }
+
                 session_manager
                     .add_message(
                         &session_config.id,
@@ -1611,13 +2503,13 @@ impl Agent {
                     .await?;
             }
         }
+        // Replies work on the window; history compaction hid stays on disk
         let session = session_manager
-            .get_session(&session_config.id, true)
+            .get_session(&session_config.id, false)
+            .await?;
+        let conversation = session_manager
+            .get_conversation_window(&session_config.id)
             .await?;
-        let conversation = session
-            .conversation
-            .clone()
-            .ok_or_else(|| anyhow::anyhow!("Session {} has no conversation", session_config.id))?;
 
         let needs_auto_compact = check_if_compaction_needed(
             self.provider().await?.as_ref(),
@@ -1667,10 +2559,10 @@ impl Agent {
                 .await
                 {
                     Ok((compacted_conversation, summarization_usage)) => {
-                        session_manager.replace_conversation(&session_config.id, &compacted_conversation).await?;
+                        session_manager.replace_conversation_window(&session_config.id, &compacted_conversation).await?;
                         self.update_session_metrics(&session_config.id, session_config.schedule_id.clone(), &summarization_usage, true).await?;
 
-                        yield AgentEvent::HistoryReplaced(compacted_conversation.clone());
+                        yield AgentEvent::HistoryReplaced(session_manager.get_conversation(&session_config.id).await?);
 
                         yield AgentEvent::Message(
                             Message::assistant().with_system_notification(
@@ -1718,6 +2610,12 @@ impl Agent {
             goose_mode,
             initial_messages,
         } = context;
+        // Optional context appended to the system prompt, trimmed to fit the window
+        let mut injected_blocks: Vec<InjectedBlock> = Vec::new();
+        let token_counter = create_token_counter()
+            .await
+            .inspect_err(|e| warn!("Context budgeting disabled: {}", e))
+            .ok();
         let reply_span = tracing::Span::current();
         self.reset_retry_attempts().await;
 
@@ -1738,13 +2636,10 @@ impl Agent {
 
         // === CHECKPOINT: Lazy-init SQLite CheckpointManager (LangGraph parity) ===
         if !self.checkpoint_initialized.load(Ordering::Relaxed) {
+            let checkpoint_start = std::time::Instant::now();
             let mut cp_guard = self.checkpoint_manager.lock().await;
             if cp_guard.is_none() {
-                let cp_path = dirs::config_dir()
-                    .unwrap_or_else(|| std::path::PathBuf::from("."))
-                    .join("goose")
-                    .join("checkpoints")
-                    .join("agent.db");
+                let cp_path = super::persistence::SqliteCheckpointer::default_path();
                 match CheckpointManager::sqlite(&cp_path).await {
                     Ok(mgr) => {
                         mgr.set_thread(&session_config.id).await;
@@ -1759,6 +2654,8 @@ impl Agent {
                 mgr.set_thread(&session_config.id).await;
             }
             self.checkpoint_initialized.store(true, Ordering::Relaxed);
+            let elapsed = checkpoint_start.elapsed();
+            startup::record("checkpoints", StartupPhase::FirstReply, elapsed);
         }
 
         // === GUARDRAILS: Scan user input before processing ===
@@ -1776,7 +2673,8 @@ impl Agent {
             if !user_text.is_empty() {
                 let guardrails = self.guardrails_engine.lock().await;
                 let detection_ctx = DetectionContext::default();
-                match guardrails.scan(&user_text, &detection_ctx).await {
+                let scan = guardrails.scan(&user_text, &detection_ctx);
+                match startup::timed("guardrails", StartupPhase::FirstReply, scan).await {
                     Ok(result) if !result.passed => {
                         let reason = result.blocked_reason.unwrap_or_else(|| "Safety check triggered".to_string());
                         info!(
@@ -1803,28 +2701,55 @@ impl Agent {
             if !self.memory_loaded.load(Ordering::Relaxed) {
                 let mut memory_mgr = self.memory_manager.lock().await;
                 if memory_mgr.config().enabled {
-                    match memory_mgr.load_from_disk().await {
+                    let load = memory_mgr.load_from_disk();
+                    match startup::timed("memory", StartupPhase::FirstReply, load).await {
                         Ok(0) => { /* No persisted memories on disk */ }
                         Ok(n) => info!("Loaded {} persisted memories from disk", n),
                         Err(e) => warn!("Failed to load persisted memories (non-blocking): {}", e),
                     }
                 }
-                // Initialize embedding provider (real sentence-transformer or hash fallback)
-                let embedding_dim = memory_mgr.config().embedding_dimension;
-                let provider = crate::memory::embeddings::create_embedding_provider(embedding_dim).await;
-                info!(provider = provider.name(), "Memory embedding provider ready");
-                memory_mgr.set_embedding_provider(provider);
                 drop(memory_mgr); // Release lock before Mem0 initialization
-                // Initialize Mem0 client (graph memory — optional, graceful fallback)
-                let mut mem0 = super::mem0_client::Mem0Client::new();
-                if mem0.check_health().await {
-                    info!("Mem0 graph memory service connected — dual-write enabled");
-                } else {
-                    debug!("Mem0 not available — using local memory only (this is OK)");
-                }
+                // Initialize Mem0 client (graph memory — optional, graceful fallback).
+                // It joins in once its health check answers, off the reply's path.
+                let mem0 = super::mem0_client::Mem0Client::new();
+                mem0.check_health_in_background();
                 *self.mem0_client.lock().await = Some(mem0);
                 self.memory_loaded.store(true, Ordering::Relaxed);
             }
+
+            // Use the process-wide embedding service (loaded once, batched and cached)
+            if !self.embeddings_attached.load(Ordering::Relaxed) {
+                use crate::memory::embedding_service::EmbeddingService;
+                use crate::memory::embeddings::EmbeddingProvider;
+                // Fast start answers with hash embeddings while the model loads
+                let service = if startup::is_fast_start() {
+                    EmbeddingService::ready_or_load_in_background()
+                } else {
+                    let load = EmbeddingService::shared();
+                    Some(startup::timed("embeddings", StartupPhase::FirstReply, load).await)
+                };
+                if let Some(service) = service {
+                    let mut memory_mgr = self.memory_manager.lock().await;
+                    let embedding_dim = memory_mgr.config().embedding_dimension;
+                    if service.dimension() == embedding_dim {
+                        memory_mgr.set_embedding_provider(Arc::new(service));
+                        // Memories stored or loaded before now carry hash vectors
+                        match memory_mgr.reembed_stale().await {
+                            Ok(0) => {}
+                            Ok(n) => info!("Re-embedded {} memories with the embedding model", n),
+                            Err(e) => warn!("Failed to re-embed memories (non-blocking): {}", e),
+                        }
+                    } else {
+                        warn!(
+                            backend = service.name(),
+                            "Embedding service produces {}-dim vectors but memory expects {}; using hash embeddings for memory",
+                            service.dimension(),
+                            embedding_dim
+                        );
+                    }
+                    self.embeddings_attached.store(true, Ordering::Relaxed);
+                }
+            }
         }
 
         // === MEMORY RECALL: Inject relevant memories as context ===
@@ -1868,9 +2793,9 @@ impl Agent {
                                         }
                                     }
                                 }
-                                system_prompt.push_str(&format!(
-                                    "\n\n[RECALLED MEMORIES]:\n{}\n",
-                                    memory_context
+                                injected_blocks.push(InjectedBlock::new(
+                                    BlockPriority::Memories,
+                                    format!("[RECALLED MEMORIES]:\n{}\n", memory_context),
                                 ));
                                 info!("Injected recalled memories into context");
                             }
@@ -1885,9 +2810,9 @@ impl Agent {
                                                 .map(|r| format!("- [graph] {}", r))
                                                 .collect::<Vec<_>>()
                                                 .join("\n");
-                                            system_prompt.push_str(&format!(
-                                                "\n\n[RECALLED MEMORIES]:\n{}\n",
-                                                memory_context
+                                            injected_blocks.push(InjectedBlock::new(
+                                                BlockPriority::Memories,
+                                                format!("[RECALLED MEMORIES]:\n{}\n", memory_context),
                                             ));
                                             info!("Injected {} Mem0 graph memories into context", mem0_results.len());
                                         }
@@ -1925,7 +2850,7 @@ impl Agent {
             let reasoning_mgr = self.reasoning_manager.lock().await;
             let reasoning_prompt = reasoning_mgr.get_system_prompt();
             if !reasoning_prompt.is_empty() {
-                system_prompt.push_str(&format!("\n\n{}", reasoning_prompt));
+                injected_blocks.push(InjectedBlock::new(BlockPriority::Reasoning, reasoning_prompt));
                 info!("Injected {} reasoning prompt", reasoning_mgr.config().mode);
             }
         }
@@ -1935,12 +2860,66 @@ impl Agent {
             let reflexion = self.reflexion_agent.lock().await;
             let reflexion_context = reflexion.generate_context_with_reflections(&system_prompt);
             if !reflexion_context.is_empty() {
-                system_prompt.push_str(&format!("\n\n{}", reflexion_context));
                 info!("Injected reflexion context ({} chars) for self-improvement", reflexion_context.len());
+                injected_blocks.push(
+                    InjectedBlock::new(BlockPriority::Reflections, reflexion_context)
+                        .with_source("reflexion"),
+                );
+            }
+        }
+
+        // === PROJECT CONTINUITY: Inject the previous session's hand-off note ===
+        if let Some(project_context) = self.project_context.lock().await.clone() {
+            injected_blocks.push(
+                InjectedBlock::new(BlockPriority::Memories, project_context)
+                    .with_source("project_continuity"),
+            );
+        }
+
+        // === ATTACHMENTS: List uploaded files so the agent can read them ===
+        if let Some(attachment_context) = self
+            .config
+            .session_manager
+            .attachments()
+            .context_for(&session.id)
+        {
+            injected_blocks.push(
+                InjectedBlock::new(BlockPriority::Memories, attachment_context)
+                    .with_source("attachments"),
+            );
+        }
+
+        // === WORKSPACE ROOTS: Tell the agent which repositories it can work in ===
+        if let Some(roots_context) = WorkspaceRoots::for_session(&session).prompt_context() {
+            injected_blocks.push(
+                InjectedBlock::new(BlockPriority::Memories, roots_context)
+                    .with_source("workspace_roots"),
+            );
+        }
+
+        // === KNOWLEDGE: Inject workspace document passages relevant to this turn ===
+        #[cfg(feature = "memory")]
+        {
+            let last_user_text = conversation
+                .messages()
+                .iter()
+                .rev()
+                .find(|m| m.role == rmcp::model::Role::User)
+                .map(|m| m.as_concat_text())
+                .unwrap_or_default();
+            let roots = WorkspaceRoots::for_session(&session);
+            if let Some(knowledge_context) =
+                crate::knowledge::context_for(&roots.paths(), &last_user_text).await
+            {
+                injected_blocks.push(
+                    InjectedBlock::new(BlockPriority::Memories, knowledge_context)
+                        .with_source("knowledge"),
+                );
             }
         }
 
         let working_dir = session.working_dir.clone();
+        startup::mark_first_reply();
         Ok(Box::pin(async_stream::try_stream! {
             let _ = reply_span.enter();
 
@@ -2000,7 +2979,7 @@ impl Agent {
                     if !structured_fallback {
                         // Structured loop handled the task — save and return
                         let done_msg = Message::assistant().with_text("Structured execution complete.");
-                        session_manager.add_message(&session_config.id, &done_msg).await?;
+                        session_manager.queue_message(&session_config.id, &done_msg).await?;
                         yield AgentEvent::Message(done_msg);
                         return;
                     }
@@ -2011,9 +2990,14 @@ impl Agent {
             let mut turns_taken = 0u32;
             let max_turns = session_config.max_turns.unwrap_or(DEFAULT_MAX_TURNS);
             let mut compaction_attempts = 0;
+            let mut refusal_retry = super::refusal_retry::RefusalRetry::from_config();
+            let mut refusal_fallback: Option<Arc<dyn Provider>> = None;
+            let mut watchdog = super::progress_watchdog::ProgressWatchdog::for_mode(goose_mode);
             let mut continuation_resets = 0u32;
             const MAX_CONTINUATION_RESETS: u32 = 3;
             let mut last_auto_checkpoint = std::time::Instant::now();
+            let mut burn_rate = crate::observability::BurnRate::new();
+            let mut budget_warned = false;
             let auto_checkpoint_interval = std::time::Duration::from_secs(600); // 10 minutes
 
             loop {
@@ -2023,6 +3007,9 @@ impl Agent {
 
                 // === AUTO-SAVE: Periodic checkpoint every 10 minutes (crash recovery) ===
                 if last_auto_checkpoint.elapsed() >= auto_checkpoint_interval && turns_taken > 0 {
+                    if let Err(e) = session_manager.flush_pending(&session_config.id).await {
+                        warn!("Failed to write queued session messages: {}", e);
+                    }
                     let cp_guard = self.checkpoint_manager.lock().await;
                     if let Some(ref mgr) = *cp_guard {
                         let cp_state = AgentCheckpointState {
@@ -2061,6 +3048,7 @@ impl Agent {
                 }
 
                 turns_taken += 1;
+                self.observe_summary_misses(&session_config.id, &conversation).await;
 
                 // === HITL: Check turn breakpoints and pause state ===
                 #[cfg(feature = "memory")]
@@ -2078,8 +3066,9 @@ impl Agent {
                         drop(session);
                         let schema = serde_json::json!({"type":"object","properties":{"continue":{"type":"boolean"},"feedback":{"type":"string"}},"required":["continue"]});
                         let msg = format!("⏸ Agent paused at turn {}. Continue?", turns_taken);
-                        if let Ok(resp) = crate::action_required_manager::ActionRequiredManager::global()
-                            .request_and_wait(msg, schema, std::time::Duration::from_secs(600)).await {
+                        let pause = crate::action_required_manager::Elicitation::new("pause", msg, schema)
+                            .in_session(Some(session_config.id.clone()));
+                        if let Ok(resp) = ActionRequiredManager::global().elicit(pause).await {
                             if let Some(fb) = resp.get("feedback").and_then(|v| v.as_str()) {
                                 if !fb.is_empty() {
                                     self.interactive_session.lock().await
@@ -2114,6 +3103,16 @@ impl Agent {
                     break;
                 }
 
+                // === FORECAST: Warn while the budget still covers a few turns ===
+                if let Some(remaining) = self.cost_tracker.remaining_budget().await {
+                    burn_rate.record(chrono::Utc::now(), self.cost_tracker.get_cost().await);
+                    if let Some(warning) = burn_rate.forecast(remaining).warning().filter(|_| !budget_warned) {
+                        info!("Budget forecast: {}", warning);
+                        budget_warned = true;
+                        yield AgentEvent::Message(Message::assistant().with_text(&warning));
+                    }
+                }
+
                 let tool_pair_summarization_task = crate::context_mgmt::maybe_summarize_tool_pair(
                     self.provider().await?,
                     session_config.id.clone(),
@@ -2152,10 +3151,58 @@ impl Agent {
                 #[cfg(not(feature = "memory"))]
                 let effective_system_prompt = &system_prompt;
 
+                // === CONTEXT BUDGET: Reserve room for the response, trim optional blocks ===
+                let (budgeted_system_prompt, context_usage) = match &token_counter {
+                    Some(counter) => {
+                        let budget = ContextBudget::for_model(&self.provider().await?.get_model_config());
+                        let (prompt, usage) = budget.fit(
+                            counter,
+                            effective_system_prompt,
+                            &injected_blocks,
+                            conversation.messages(),
+                            conversation_with_moim.messages(),
+                            &tools,
+                        );
+                        if !usage.trimmed.is_empty() {
+                            warn!(trimmed = ?usage.trimmed, total = usage.total, limit = usage.context_limit, "Trimmed injected context to fit the context window");
+                        }
+                        debug!(?usage, "Context budget");
+                        (prompt, Some(usage))
+                    }
+                    None => (with_blocks(effective_system_prompt, &injected_blocks), None),
+                };
+
+                let turn_provider = match task_routing::global()
+                    .provider_for(TaskCategory::CodeGeneration)
+                    .await
+                {
+                    Some(provider) => provider,
+                    None => self.provider().await?,
+                };
+                // A provider that declined an earlier turn of this reply stays replaced
+                let turn_provider = refusal_fallback.clone().unwrap_or(turn_provider);
+                self.prefetch_plan_tools(&session, &tools, goose_mode).await;
+                // Held for the rest of this turn; waits while the core is at
+                // its concurrent turn limit
+                let core = self.execution_mode().await;
+                let _core_slot = super::core_registry::acquire_turn_slot(core).await;
+                let snapshot_turn = turn_snapshots::snapshot_request(
+                    &session_manager.turn_snapshots(),
+                    &session_config.id,
+                    turn_snapshots::TurnRequest {
+                        provider: turn_provider.get_name().to_string(),
+                        model: turn_provider.get_model_config().model_name,
+                        system_prompt: &budgeted_system_prompt,
+                        injected: &injected_blocks,
+                        tools: &tools,
+                        messages: conversation_with_moim.messages(),
+                        context: context_usage.as_ref(),
+                    },
+                );
                 let mut stream = Self::stream_response_from_provider(
-                    self.provider().await?,
+                    turn_provider.clone(),
                     &session_config.id,
-                    effective_system_prompt,
+                    &budgeted_system_prompt,
                     conversation_with_moim.messages(),
                     &tools,
                     &toolshim_tools,
@@ -2165,6 +3212,7 @@ impl Agent {
                 let mut messages_to_add = Conversation::default();
                 let mut tools_updated = false;
                 let mut did_recovery_compact_this_iteration = false;
+                let mut refusal: Option<String> = None;
 
                 while let Some(next) = stream.next().await {
                     if is_token_cancelled(&cancel_token) {
@@ -2198,6 +3246,8 @@ impl Agent {
 
                             if let Some(ref usage) = usage {
                                 self.update_session_metrics(&session_config.id, session_config.schedule_id.clone(), usage, false).await?;
+                                task_routing::global().record(TaskCategory::CodeGeneration, turn_provider.get_name(), usage);
+                                turn_snapshots::snapshot_usage(&session_manager.turn_snapshots(), &session_config.id, snapshot_turn, usage);
                                 // === COST TRACKING: Record token usage for budget enforcement ===
                                 let input_toks = usage.usage.input_tokens.unwrap_or(0).max(0) as u64;
                                 let output_toks = usage.usage.output_tokens.unwrap_or(0).max(0) as u64;
@@ -2207,6 +3257,11 @@ impl Agent {
                                     let token_usage = crate::agents::observability::TokenUsage::new(input_toks, output_toks)
                                         .with_cached(cached_toks);
                                     self.cost_tracker.record_llm_call(&token_usage);
+                                    let request_cost = crate::observability::CostTracker::new().calculate_cost(
+                                        &crate::observability::TokenUsage::with_cache(input_toks, output_toks, cached_toks),
+                                        &usage.model,
+                                    );
+                                    crate::local_analytics::record(AnalyticsMetric::CostUsd, usage.model.clone(), request_cost);
                                 }
                             }
 
@@ -2324,8 +3379,9 @@ impl Agent {
                                         drop(session);
                                         if let Some(msg) = matched_bp {
                                             let schema = serde_json::json!({"type":"object","properties":{"continue":{"type":"boolean"},"feedback":{"type":"string"}},"required":["continue"]});
-                                            if let Ok(resp) = crate::action_required_manager::ActionRequiredManager::global()
-                                                .request_and_wait(msg, schema, std::time::Duration::from_secs(600)).await {
+                                            let pause = crate::action_required_manager::Elicitation::new("pause", msg, schema)
+                                                .in_session(Some(session_config.id.clone()));
+                                            if let Ok(resp) = ActionRequiredManager::global().elicit(pause).await {
                                                 if let Some(fb) = resp.get("feedback").and_then(|v| v.as_str()) {
                                                     if !fb.is_empty() {
                                                         self.interactive_session.lock().await
@@ -2662,7 +3718,7 @@ impl Agent {
                                     Message::user().with_text(&continuation_prompt)
                                 ])?;
 
-                                session_manager.replace_conversation(&session_config.id, &fresh_conversation).await?;
+                                session_manager.replace_conversation_window(&session_config.id, &fresh_conversation).await?;
                                 conversation = fresh_conversation;
                                 compaction_attempts = 0;
 
@@ -2672,7 +3728,7 @@ impl Agent {
                                         "Context limit reached — continuing seamlessly from checkpoint. No progress lost.",
                                     )
                                 );
-                                yield AgentEvent::HistoryReplaced(conversation.clone());
+                                yield AgentEvent::HistoryReplaced(session_manager.get_conversation(&session_config.id).await?);
                                 // Continue the loop instead of breaking
                                 continue;
                             }
@@ -2734,11 +3790,11 @@ impl Agent {
                                         }
                                     }
 
-                                    session_manager.replace_conversation(&session_config.id, &compacted_conversation).await?;
+                                    session_manager.replace_conversation_window(&session_config.id, &compacted_conversation).await?;
                                     self.update_session_metrics(&session_config.id, session_config.schedule_id.clone(), &usage, true).await?;
                                     conversation = compacted_conversation;
                                     did_recovery_compact_this_iteration = true;
-                                    yield AgentEvent::HistoryReplaced(conversation.clone());
+                                    yield AgentEvent::HistoryReplaced(session_manager.get_conversation(&session_config.id).await?);
                                     break;
                                 }
                                 Err(e) => {
@@ -2748,6 +3804,14 @@ impl Agent {
                                 }
                             }
                         }
+                        Err(ref provider_err)
+                            if super::refusal_retry::is_refusal_error(provider_err)
+                                && refusal_retry.attempts() < refusal_retry.max_attempts() =>
+                        {
+                            warn!("{} refused the turn: {}", turn_provider.get_name(), provider_err);
+                            refusal = Some(provider_err.to_string());
+                            break;
+                        }
                         Err(ref provider_err) => {
                             crate::posthog::emit_error(provider_err.telemetry_type(), &provider_err.to_string());
                             error!("Error: {}", provider_err);
@@ -2760,6 +3824,67 @@ impl Agent {
                         }
                     }
                 }
+                if refusal.is_none() && no_tools_called {
+                    refusal = super::refusal_retry::refusal_text(messages_to_add.messages());
+                }
+                if let Some(reason) = refusal {
+                    let provider_name = turn_provider.get_name().to_string();
+                    let attempt = refusal_retry.attempts() + 1;
+                    let max_attempts = refusal_retry.max_attempts();
+                    match refusal_retry.next_step(&provider_name, conversation.messages()).await {
+                        Some(step) => {
+                            let notice = match step {
+                                super::refusal_retry::RetryStep::Rephrase(message) => {
+                                    // Keep the declined reply so the restated request follows it
+                                    if messages_to_add.is_empty() {
+                                        messages_to_add.push(
+                                            Message::assistant()
+                                                .with_text(format!("(The request was blocked: {})", reason))
+                                                .with_metadata(crate::conversation::message::MessageMetadata::agent_only()),
+                                        );
+                                    }
+                                    messages_to_add.push(message);
+                                    for msg in messages_to_add.messages() {
+                                        session_manager.queue_message(&session_config.id, msg).await?;
+                                    }
+                                    conversation.extend(messages_to_add);
+                                    format!("{} declined this request. Retrying with the request restated ({}/{}).", provider_name, attempt, max_attempts)
+                                }
+                                super::refusal_retry::RetryStep::Fallback(provider) => {
+                                    let notice = format!("{} declined this request. Retrying on {} ({}/{}).", provider_name, provider.get_name(), attempt, max_attempts);
+                                    refusal_fallback = Some(provider);
+                                    notice
+                                }
+                            };
+                            audit_log::record("refusal_retry", serde_json::json!({
+                                "session_id": session_config.id,
+                                "provider": provider_name,
+                                "attempt": attempt,
+                                "reason": reason.chars().take(200).collect::<String>(),
+                            }));
+                            yield AgentEvent::Message(
+                                Message::assistant().with_system_notification(
+                                    SystemNotificationType::InlineMessage,
+                                    notice,
+                                )
+                            );
+                            continue;
+                        }
+                        None if refusal_retry.attempts() > 0 => {
+                            let notice = format!(
+                                "The request was still declined after {} retries (restated, and on fallback providers where the routing policy has one). Rewording it or choosing another provider may help.",
+                                refusal_retry.attempts()
+                            );
+                            yield AgentEvent::Message(
+                                Message::assistant().with_system_notification(
+                                    SystemNotificationType::InlineMessage,
+                                    notice,
+                                )
+                            );
+                        }
+                        None => {}
+                    }
+                }
                 if tools_updated {
                     (tools, toolshim_tools, system_prompt) =
                         self.prepare_tools_and_prompt(&session_config.id, &session.working_dir).await?;
@@ -2864,8 +3989,45 @@ impl Agent {
                     }
                 }
 
+                // === PROGRESS WATCHDOG: Break loops that make no progress ===
+                if !exit_chat {
+                    let core = self.execution_mode().await;
+                    if let Some(intervention) = watchdog.observe(messages_to_add.messages(), core) {
+                        use super::progress_watchdog::Intervention;
+                        let notice = match intervention {
+                            Intervention::Nudge(nudge) => {
+                                messages_to_add.push(nudge);
+                                "The last turns repeated without progress. Asking the model to change strategy.".to_string()
+                            }
+                            Intervention::AskUser(question) => {
+                                messages_to_add.push(question.clone());
+                                yield AgentEvent::Message(question);
+                                exit_chat = true;
+                                "Stopped a loop that made no progress.".to_string()
+                            }
+                            Intervention::SwitchCore(next, nudge) => {
+                                self.set_core(next, "loop watchdog").await;
+                                messages_to_add.push(nudge);
+                                format!("The loop continued after a nudge. Switched to the {} core.", next)
+                            }
+                        };
+                        warn!("Progress watchdog: {}", notice);
+                        audit_log::record("loop_watchdog", serde_json::json!({
+                            "session_id": session_config.id,
+                            "turn": turns_taken,
+                            "action": notice,
+                        }));
+                        yield AgentEvent::Message(
+                            Message::assistant().with_system_notification(
+                                SystemNotificationType::InlineMessage,
+                                notice,
+                            )
+                        );
+                    }
+                }
+
                 for msg in &messages_to_add {
-                    session_manager.add_message(&session_config.id, msg).await?;
+                    session_manager.queue_message(&session_config.id, msg).await?;
                 }
                 conversation.extend(messages_to_add);
                 if exit_chat {
@@ -2934,6 +4096,9 @@ impl Agent {
 
                 tokio::task::yield_now().await;
             }
+
+            // The turn's messages were queued; write them in one go
+            session_manager.flush_pending(&session_config.id).await?;
         }))
     }
 
@@ -2993,12 +4158,52 @@ impl Agent {
         self.update_provider(provider, &session.id).await
     }
 
+    /// Limits a subagent to what it was granted, in both the tools it sees
+    /// and the calls it may make.
+    pub async fn restrict_capabilities(&self, grant: CapabilityGrant) {
+        self.tool_inspection_manager
+            .set_capability_grant(Some(grant.clone()));
+        self.extension_manager
+            .set_capability_grant(Some(grant))
+            .await;
+    }
+
     /// Override the system prompt with a custom template
     pub async fn override_system_prompt(&self, template: String) {
         let mut prompt_manager = self.prompt_manager.lock().await;
         prompt_manager.set_system_prompt_override(template);
     }
 
+    /// Render the system prompt from a named template in the workspace or
+    /// user prompts dir instead of `system.md`; `None` restores the default.
+    pub async fn set_system_prompt_template(&self, name: Option<String>) {
+        let mut prompt_manager = self.prompt_manager.lock().await;
+        prompt_manager.set_system_prompt_template(name);
+    }
+
+    /// The system prompt the next turn of `session_id` would start from,
+    /// before per-turn context such as recalled memories is injected.
+    pub async fn preview_system_prompt(&self, session_id: &str) -> Result<String> {
+        let session = self
+            .config
+            .session_manager
+            .get_session(session_id, false)
+            .await?;
+        let (_, _, system_prompt) = self
+            .prepare_tools_and_prompt(session_id, &session.working_dir)
+            .await?;
+        Ok(system_prompt)
+    }
+
+    /// Tokens in each section of the last built system prompt.
+    pub async fn system_prompt_section_tokens(&self) -> Result<Vec<SectionTokens>> {
+        let token_counter = create_token_counter()
+            .await
+            .map_err(|e| anyhow!("Failed to create token counter: {}", e))?;
+        let prompt_manager = self.prompt_manager.lock().await;
+        Ok(prompt_manager.section_tokens(&token_counter))
+    }
+
     pub async fn list_extension_prompts(&self, session_id: &str) -> HashMap<String, Vec<Prompt>> {
         self.extension_manager
             .list_prompts(session_id, CancellationToken::default())
@@ -3237,6 +4442,7 @@ impl Agent {
             goose_model: Some(model_name.clone()),
             temperature: Some(model_config.temperature.unwrap_or(0.0)),
             max_turns: None,
+            system_prompt_template: None,
         };
 
         tracing::debug!(
@@ -3455,7 +4661,7 @@ mod tests {
             })),
         };
 
-        agent.add_final_output_tool(response).await;
+        agent.add_final_output_tool(response, None).await;
 
         let tools = agent.list_tools("test-session-id", None).await;
         let final_output_tool = tools
@@ -13,6 +13,7 @@
 
 use std::collections::HashMap;
 use std::fmt;
+use std::path::{Path, PathBuf};
 use std::time::Duration;
 
 use anyhow::Result;
@@ -56,6 +57,9 @@ pub enum TaskCategory {
     Memory,
     Safety,
     Performance,
+    CodeFix,
+    Refactor,
+    Research,
 }
 
 impl fmt::Display for TaskCategory {
@@ -69,6 +73,9 @@ impl fmt::Display for TaskCategory {
             TaskCategory::Memory => write!(f, "memory"),
             TaskCategory::Safety => write!(f, "safety"),
             TaskCategory::Performance => write!(f, "performance"),
+            TaskCategory::CodeFix => write!(f, "code_fix"),
+            TaskCategory::Refactor => write!(f, "refactor"),
+            TaskCategory::Research => write!(f, "research"),
         }
     }
 }
@@ -105,6 +112,10 @@ pub enum ExpectedOutput {
     FileExists { path: String, contains: Option<String> },
     /// A tool must have been called with these parameters
     ToolCalled { tool_name: String, args_contain: Option<String> },
+    /// A file must not contain the given text (e.g. a renamed symbol)
+    FileNotContains { path: String, text: String },
+    /// A shell command must exit successfully (e.g. the task's tests)
+    CommandSucceeds { command: String },
     /// Custom evaluator function name
     Custom(String),
 }
@@ -134,6 +145,9 @@ pub struct BenchmarkResult {
     pub error: Option<String>,
     /// Agent output text
     pub output: Option<String>,
+    /// Provider spend for the run, when known
+    #[serde(default)]
+    pub cost_usd: Option<f64>,
 }
 
 /// Result of a single evaluation criterion.
@@ -179,71 +193,157 @@ impl Evaluator for DefaultEvaluator {
         output: &str,
         tool_calls: &[String],
     ) -> Result<Vec<CriterionResult>> {
-        let mut results = Vec::new();
+        evaluate_expected(task, output, tool_calls, None).await
+    }
+}
 
-        for expected in &task.expected {
-            let (criterion, passed, details) = match expected {
-                ExpectedOutput::Contains(substr) => {
-                    let found = output.contains(substr.as_str());
-                    (
-                        format!("contains '{}'", substr),
-                        found,
-                        if found { None } else { Some("Substring not found in output".into()) },
-                    )
-                }
-                ExpectedOutput::Matches(pattern) => {
-                    let matched = regex::Regex::new(pattern)
-                        .map(|re| re.is_match(output))
-                        .unwrap_or(false);
-                    (
-                        format!("matches /{}/", pattern),
-                        matched,
-                        if matched { None } else { Some("Pattern not matched".into()) },
-                    )
-                }
-                ExpectedOutput::ToolCalled { tool_name, args_contain } => {
-                    let called = tool_calls.iter().any(|tc| tc.contains(tool_name.as_str()));
-                    let args_ok = args_contain.as_ref().map_or(true, |args| {
-                        tool_calls.iter().any(|tc| tc.contains(args.as_str()))
-                    });
-                    (
-                        format!("tool_called({})", tool_name),
-                        called && args_ok,
-                        if called { None } else { Some(format!("Tool '{}' not called", tool_name)) },
-                    )
-                }
-                ExpectedOutput::FileExists { path, contains } => {
-                    let exists = std::path::Path::new(path).exists();
-                    let content_ok = if exists {
-                        contains.as_ref().map_or(true, |pattern| {
-                            std::fs::read_to_string(path)
-                                .map(|content| content.contains(pattern.as_str()))
-                                .unwrap_or(false)
-                        })
-                    } else {
-                        false
-                    };
-                    (
-                        format!("file_exists({})", path),
-                        exists && content_ok,
-                        if exists { None } else { Some(format!("File '{}' not found", path)) },
-                    )
-                }
-                ExpectedOutput::Custom(name) => {
-                    // Custom evaluators are handled by specialized Evaluator implementations
-                    (format!("custom({})", name), true, Some("Skipped (no custom handler)".into()))
-                }
-            };
+/// Evaluator for tasks run in their own workspace: file paths and grader
+/// commands are resolved against the workspace root.
+pub struct WorkspaceEvaluator {
+    root: PathBuf,
+}
 
-            results.push(CriterionResult {
-                criterion,
-                passed,
-                score: if passed { 1.0 } else { 0.0 },
-                details,
-            });
-        }
+impl WorkspaceEvaluator {
+    pub fn new(root: impl Into<PathBuf>) -> Self {
+        Self { root: root.into() }
+    }
+}
+
+#[async_trait::async_trait]
+impl Evaluator for WorkspaceEvaluator {
+    fn name(&self) -> &str {
+        "workspace"
+    }
 
-        Ok(results)
+    async fn evaluate(
+        &self,
+        task: &BenchmarkTask,
+        output: &str,
+        tool_calls: &[String],
+    ) -> Result<Vec<CriterionResult>> {
+        evaluate_expected(task, output, tool_calls, Some(&self.root)).await
+    }
+}
+
+async fn evaluate_expected(
+    task: &BenchmarkTask,
+    output: &str,
+    tool_calls: &[String],
+    root: Option<&Path>,
+) -> Result<Vec<CriterionResult>> {
+    let resolve = |path: &str| match root {
+        Some(root) => root.join(path),
+        None => PathBuf::from(path),
+    };
+    let mut results = Vec::new();
+
+    for expected in &task.expected {
+        let (criterion, passed, details) = match expected {
+            ExpectedOutput::Contains(substr) => {
+                let found = output.contains(substr.as_str());
+                (
+                    format!("contains '{}'", substr),
+                    found,
+                    if found { None } else { Some("Substring not found in output".into()) },
+                )
+            }
+            ExpectedOutput::Matches(pattern) => {
+                let matched = regex::Regex::new(pattern)
+                    .map(|re| re.is_match(output))
+                    .unwrap_or(false);
+                (
+                    format!("matches /{}/", pattern),
+                    matched,
+                    if matched { None } else { Some("Pattern not matched".into()) },
+                )
+            }
+            ExpectedOutput::ToolCalled { tool_name, args_contain } => {
+                let called = tool_calls.iter().any(|tc| tc.contains(tool_name.as_str()));
+                let args_ok = args_contain.as_ref().map_or(true, |args| {
+                    tool_calls.iter().any(|tc| tc.contains(args.as_str()))
+                });
+                (
+                    format!("tool_called({})", tool_name),
+                    called && args_ok,
+                    if called { None } else { Some(format!("Tool '{}' not called", tool_name)) },
+                )
+            }
+            ExpectedOutput::FileExists { path, contains } => {
+                let resolved = resolve(path);
+                let exists = resolved.exists();
+                let content_ok = if exists {
+                    contains.as_ref().map_or(true, |pattern| {
+                        std::fs::read_to_string(&resolved)
+                            .map(|content| content.contains(pattern.as_str()))
+                            .unwrap_or(false)
+                    })
+                } else {
+                    false
+                };
+                (
+                    format!("file_exists({})", path),
+                    exists && content_ok,
+                    if exists { None } else { Some(format!("File '{}' not found", path)) },
+                )
+            }
+            ExpectedOutput::FileNotContains { path, text } => {
+                let found = std::fs::read_to_string(resolve(path))
+                    .map(|content| content.contains(text.as_str()))
+                    .unwrap_or(false);
+                (
+                    format!("file_not_contains({}, '{}')", path, text),
+                    !found,
+                    if found { Some(format!("'{}' still present in '{}'", text, path)) } else { None },
+                )
+            }
+            ExpectedOutput::CommandSucceeds { command } => {
+                let (passed, details) = run_grader_command(command, root).await;
+                (format!("command_succeeds({})", command), passed, details)
+            }
+            ExpectedOutput::Custom(name) => {
+                // Custom evaluators are handled by specialized Evaluator implementations
+                (format!("custom({})", name), true, Some("Skipped (no custom handler)".into()))
+            }
+        };
+
+        results.push(CriterionResult {
+            criterion,
+            passed,
+            score: if passed { 1.0 } else { 0.0 },
+            details,
+        });
+    }
+
+    Ok(results)
+}
+
+/// Runs a grader command through the shell, returning whether it exited
+/// successfully and its trimmed output when it did not.
+async fn run_grader_command(
+    command: &str,
+    cwd: Option<&Path>,
+) -> (bool, Option<String>) {
+    let mut cmd = if cfg!(windows) {
+        let mut cmd = tokio::process::Command::new("cmd");
+        cmd.arg("/C").arg(command);
+        cmd
+    } else {
+        let mut cmd = tokio::process::Command::new("sh");
+        cmd.arg("-c").arg(command);
+        cmd
+    };
+    if let Some(cwd) = cwd {
+        cmd.current_dir(cwd);
+    }
+    match cmd.output().await {
+        Ok(output) if output.status.success() => (true, None),
+        Ok(output) => {
+            let mut details = String::from_utf8_lossy(&output.stdout).into_owned();
+            details.push_str(&String::from_utf8_lossy(&output.stderr));
+            let details: String = details.trim().chars().take(500).collect();
+            (false, Some(format!("exit {}: {}", output.status, details)))
+        }
+        Err(e) => (false, Some(format!("Failed to run command: {}", e))),
     }
 }
 
@@ -573,6 +673,7 @@ pub enum RegressionType {
     ScoreDrop,
     TaskRegression,
     PerformanceDrop,
+    CostIncrease,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -637,6 +738,7 @@ impl BenchmarkRunner {
             criteria_results: criteria,
             error: None,
             output: Some(output.to_string()),
+            cost_usd: None,
         })
     }
 
@@ -828,12 +930,12 @@ mod tests {
             BenchmarkResult {
                 task_id: "t1".into(), passed: true, score: 1.0,
                 duration: Duration::from_secs(5), turns_used: 2, tool_calls: 1,
-                criteria_results: vec![], error: None, output: None,
+                criteria_results: vec![], error: None, output: None, cost_usd: None,
             },
             BenchmarkResult {
                 task_id: "t2".into(), passed: false, score: 0.5,
                 duration: Duration::from_secs(10), turns_used: 4, tool_calls: 3,
-                criteria_results: vec![], error: Some("partial".into()), output: None,
+                criteria_results: vec![], error: Some("partial".into()), output: None, cost_usd: None,
             },
         ];
 
@@ -871,13 +973,13 @@ mod tests {
         let prev_result = BenchmarkResult {
             task_id: "t1".into(), passed: true, score: 1.0,
             duration: Duration::from_secs(5), turns_used: 1, tool_calls: 0,
-            criteria_results: vec![], error: None, output: None,
+            criteria_results: vec![], error: None, output: None, cost_usd: None,
         };
 
         let curr_result = BenchmarkResult {
             task_id: "t1".into(), passed: false, score: 0.0,
             duration: Duration::from_secs(5), turns_used: 1, tool_calls: 0,
-            criteria_results: vec![], error: Some("failed".into()), output: None,
+            criteria_results: vec![], error: Some("failed".into()), output: None, cost_usd: None,
         };
 
         let previous = BenchmarkReport {
@@ -0,0 +1,903 @@
+//! Matrix runs of the goose-bench corpus for regression testing.
+//!
+//! A [`CorpusTask`] is a benchmark task plus the fixture files it starts
+//! from. [`MatrixRunner`] runs every task once per [`MatrixEntry`] (core,
+//! provider, model and config overrides) in a fresh workspace seeded with
+//! those files, grades the workspace and output with [`WorkspaceEvaluator`],
+//! and stops an entry once its cost cap is spent. A [`MatrixReport`] can be
+//! stored as a named baseline and later runs compared against it, which is
+//! what CI gates on.
+//!
+//! Corpus directories hold one task per YAML or JSON file:
+//!
+//! ```yaml
+//! id: code-fix-001
+//! name: Fix off-by-one in range sum
+//! category: code_fix
+//! prompt: The tests in test_sum.py fail. Fix sum_range in sum.py.
+//! timeout_secs: 300
+//! files:
+//!   sum.py: |
+//!     def sum_range(n):
+//!         return sum(range(n))
+//!   test_sum.py: ...
+//! expected:
+//!   - CommandSucceeds:
+//!       command: python3 test_sum.py
+//! ```
+
+use std::collections::{BTreeMap, HashSet};
+use std::fmt;
+use std::path::{Component, Path, PathBuf};
+use std::time::{Duration, Instant};
+
+use anyhow::{bail, Context, Result};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use super::benchmark::{
+    AlertSeverity, BenchmarkReport, BenchmarkResult, BenchmarkRunner, BenchmarkTask, Difficulty,
+    ExpectedOutput, RegressionAlert, RegressionType, TaskCategory, WorkspaceEvaluator,
+};
+use super::ExecutionMode;
+use crate::config::paths::Paths;
+use crate::conversation::message::Message;
+
+/// Relative cost growth over the baseline that fails the gate.
+const COST_INCREASE_TOLERANCE: f64 = 0.25;
+/// Success rate drop (as a fraction of tasks) that fails the gate.
+const SUCCESS_RATE_TOLERANCE: f64 = 0.05;
+
+// ---------------------------------------------------------------------------
+// Corpus
+// ---------------------------------------------------------------------------
+
+fn default_difficulty() -> Difficulty {
+    Difficulty::Medium
+}
+
+fn default_timeout_secs() -> u64 {
+    300
+}
+
+/// A benchmark task together with the files its workspace starts from.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CorpusTask {
+    pub id: String,
+    pub name: String,
+    pub category: TaskCategory,
+    #[serde(default = "default_difficulty")]
+    pub difficulty: Difficulty,
+    pub prompt: String,
+    /// Graders, written as single-key maps (`- Contains: text`) in YAML
+    #[serde(with = "serde_yaml::with::singleton_map_recursive")]
+    pub expected: Vec<ExpectedOutput>,
+    #[serde(default = "default_timeout_secs")]
+    pub timeout_secs: u64,
+    #[serde(default)]
+    pub tags: Vec<String>,
+    /// Fixture files written into the workspace before the run, keyed by
+    /// path relative to the workspace root
+    #[serde(default)]
+    pub files: BTreeMap<String, String>,
+}
+
+impl CorpusTask {
+    pub fn to_task(&self) -> BenchmarkTask {
+        BenchmarkTask {
+            id: self.id.clone(),
+            name: self.name.clone(),
+            category: self.category,
+            difficulty: self.difficulty,
+            prompt: self.prompt.clone(),
+            expected: self.expected.clone(),
+            timeout: Duration::from_secs(self.timeout_secs),
+            tags: self.tags.clone(),
+        }
+    }
+
+    fn validate(&self) -> Result<()> {
+        if self.id.trim().is_empty() {
+            bail!("task id must not be empty");
+        }
+        if self.expected.is_empty() {
+            bail!("task '{}' has no expected outputs to grade", self.id);
+        }
+        for path in self.files.keys() {
+            let relative = Path::new(path);
+            if !relative
+                .components()
+                .all(|c| matches!(c, Component::Normal(_) | Component::CurDir))
+            {
+                bail!(
+                    "task '{}' fixture path '{}' must stay inside the workspace",
+                    self.id,
+                    path
+                );
+            }
+        }
+        Ok(())
+    }
+
+    /// Writes the fixture files into `root`.
+    pub fn prepare_workspace(&self, root: &Path) -> Result<()> {
+        for (path, content) in &self.files {
+            let target = root.join(path);
+            if let Some(parent) = target.parent() {
+                std::fs::create_dir_all(parent)?;
+            }
+            std::fs::write(&target, content)
+                .with_context(|| format!("writing fixture {}", target.display()))?;
+        }
+        Ok(())
+    }
+}
+
+/// Loads every `.yaml`, `.yml` and `.json` task spec in `dir`, sorted by id.
+pub fn load_corpus(dir: &Path) -> Result<Vec<CorpusTask>> {
+    let mut tasks = Vec::new();
+    for entry in std::fs::read_dir(dir)
+        .with_context(|| format!("reading corpus directory {}", dir.display()))?
+    {
+        let path = entry?.path();
+        let task: CorpusTask = match path.extension().and_then(|e| e.to_str()) {
+            Some("yaml") | Some("yml") => serde_yaml::from_str(&std::fs::read_to_string(&path)?)
+                .with_context(|| format!("parsing {}", path.display()))?,
+            Some("json") => serde_json::from_str(&std::fs::read_to_string(&path)?)
+                .with_context(|| format!("parsing {}", path.display()))?,
+            _ => continue,
+        };
+        task.validate()
+            .with_context(|| format!("invalid task in {}", path.display()))?;
+        tasks.push(task);
+    }
+    tasks.sort_by(|a, b| a.id.cmp(&b.id));
+    let mut seen = HashSet::new();
+    if let Some(dup) = tasks.iter().find(|t| !seen.insert(t.id.as_str())) {
+        bail!("duplicate task id '{}' in {}", dup.id, dir.display());
+    }
+    Ok(tasks)
+}
+
+/// The built-in regression corpus: one code fix, one refactor and one
+/// research task, each graded from its workspace.
+pub fn builtin_corpus() -> Vec<CorpusTask> {
+    let task = |id: &str, name: &str, category, prompt: &str, files: &[(&str, &str)], expected| {
+        CorpusTask {
+            id: id.into(),
+            name: name.into(),
+            category,
+            difficulty: Difficulty::Easy,
+            prompt: prompt.into(),
+            expected,
+            timeout_secs: default_timeout_secs(),
+            tags: vec!["regression".into()],
+            files: files
+                .iter()
+                .map(|(path, content)| (path.to_string(), content.to_string()))
+                .collect(),
+        }
+    };
+
+    vec![
+        task(
+            "code-fix-001",
+            "Fix off-by-one in range sum",
+            TaskCategory::CodeFix,
+            "The tests in test_sum.py fail. Fix the bug in sum.py without changing the tests.",
+            &[
+                ("sum.py", "def sum_range(n):\n    \"\"\"Sum of 1..n inclusive.\"\"\"\n    return sum(range(n))\n"),
+                ("test_sum.py", "from sum import sum_range\n\nassert sum_range(1) == 1\nassert sum_range(4) == 10\nprint(\"ok\")\n"),
+            ],
+            vec![ExpectedOutput::CommandSucceeds {
+                command: "python3 test_sum.py".into(),
+            }],
+        ),
+        task(
+            "refactor-001",
+            "Rename a function across modules",
+            TaskCategory::Refactor,
+            "Rename the function calc in util.py to compute_total and update every caller. Behaviour must not change.",
+            &[
+                ("util.py", "def calc(items):\n    return sum(i * 2 for i in items)\n"),
+                ("main.py", "from util import calc\n\nif __name__ == \"__main__\":\n    assert calc([1, 2, 3]) == 12\n    print(\"ok\")\n"),
+            ],
+            vec![
+                ExpectedOutput::FileNotContains {
+                    path: "util.py".into(),
+                    text: "def calc(".into(),
+                },
+                ExpectedOutput::FileNotContains {
+                    path: "main.py".into(),
+                    text: "calc(".into(),
+                },
+                ExpectedOutput::FileExists {
+                    path: "util.py".into(),
+                    contains: Some("def compute_total(".into()),
+                },
+                ExpectedOutput::CommandSucceeds {
+                    command: "python3 main.py".into(),
+                },
+            ],
+        ),
+        task(
+            "research-001",
+            "Answer from project notes",
+            TaskCategory::Research,
+            "Read NOTES.md and tell me which port the staging server listens on and who owns it.",
+            &[(
+                "NOTES.md",
+                "# Environments\n\n- production: port 8443, owned by the platform team\n- staging: port 9174, owned by Priya\n",
+            )],
+            vec![
+                ExpectedOutput::Contains("9174".into()),
+                ExpectedOutput::Contains("Priya".into()),
+            ],
+        ),
+    ]
+}
+
+// ---------------------------------------------------------------------------
+// Matrix
+// ---------------------------------------------------------------------------
+
+/// One configuration to run the corpus under.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MatrixEntry {
+    pub label: String,
+    #[serde(default)]
+    pub core: ExecutionMode,
+    #[serde(default)]
+    pub provider: Option<String>,
+    #[serde(default)]
+    pub model: Option<String>,
+    /// Config overrides, passed to the run as upper-cased environment variables
+    #[serde(default)]
+    pub config: BTreeMap<String, String>,
+    /// Remaining tasks are skipped once this entry has spent this many dollars
+    #[serde(default)]
+    pub max_cost_usd: Option<f64>,
+}
+
+impl MatrixEntry {
+    /// An entry that runs with the current configuration.
+    pub fn current() -> Self {
+        Self {
+            label: "default".into(),
+            core: ExecutionMode::default(),
+            provider: None,
+            model: None,
+            config: BTreeMap::new(),
+            max_cost_usd: None,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BenchmarkMatrix {
+    pub entries: Vec<MatrixEntry>,
+}
+
+impl BenchmarkMatrix {
+    pub fn load(path: &Path) -> Result<Self> {
+        let content = std::fs::read_to_string(path)
+            .with_context(|| format!("reading matrix {}", path.display()))?;
+        let matrix: Self = serde_yaml::from_str(&content)
+            .with_context(|| format!("parsing matrix {}", path.display()))?;
+        if matrix.entries.is_empty() {
+            bail!("matrix {} has no entries", path.display());
+        }
+        let mut seen = HashSet::new();
+        if let Some(dup) = matrix
+            .entries
+            .iter()
+            .find(|e| !seen.insert(e.label.as_str()))
+        {
+            bail!("duplicate matrix label '{}'", dup.label);
+        }
+        Ok(matrix)
+    }
+}
+
+// ---------------------------------------------------------------------------
+// Execution
+// ---------------------------------------------------------------------------
+
+/// What an agent produced for one task.
+#[derive(Debug, Clone, Default)]
+pub struct TaskRun {
+    pub output: String,
+    pub tool_calls: Vec<String>,
+    pub turns: u32,
+    pub cost_usd: Option<f64>,
+    pub error: Option<String>,
+}
+
+/// Runs a single task in a prepared workspace under one matrix entry.
+#[async_trait::async_trait]
+pub trait TaskExecutor: Send + Sync {
+    async fn execute(
+        &self,
+        task: &BenchmarkTask,
+        entry: &MatrixEntry,
+        workspace: &Path,
+    ) -> Result<TaskRun>;
+}
+
+/// Runs tasks through `goose run --output-format stream-json`.
+pub struct CliExecutor {
+    binary: PathBuf,
+}
+
+impl CliExecutor {
+    pub fn new(binary: impl Into<PathBuf>) -> Self {
+        Self {
+            binary: binary.into(),
+        }
+    }
+}
+
+#[async_trait::async_trait]
+impl TaskExecutor for CliExecutor {
+    async fn execute(
+        &self,
+        task: &BenchmarkTask,
+        entry: &MatrixEntry,
+        workspace: &Path,
+    ) -> Result<TaskRun> {
+        let mut cmd = tokio::process::Command::new(&self.binary);
+        cmd.args(["run", "--no-session", "--output-format", "stream-json"])
+            .args(["--execution-mode", &entry.core.to_string()])
+            .args(["--text", &task.prompt])
+            .current_dir(workspace)
+            .stdin(std::process::Stdio::null())
+            .kill_on_drop(true);
+        if let Some(provider) = &entry.provider {
+            cmd.args(["--provider", provider]);
+        }
+        if let Some(model) = &entry.model {
+            cmd.args(["--model", model]);
+        }
+        for (key, value) in &entry.config {
+            cmd.env(key.to_uppercase(), value);
+        }
+
+        let output = match tokio::time::timeout(task.timeout, cmd.output()).await {
+            Ok(output) => output.with_context(|| format!("running {}", self.binary.display()))?,
+            Err(_) => {
+                return Ok(TaskRun {
+                    error: Some(format!("timed out after {}s", task.timeout.as_secs())),
+                    ..Default::default()
+                })
+            }
+        };
+
+        let mut run = parse_stream_events(&String::from_utf8_lossy(&output.stdout));
+        if !output.status.success() && run.error.is_none() {
+            let stderr = String::from_utf8_lossy(&output.stderr);
+            run.error = Some(format!("exit {}: {}", output.status, stderr.trim()));
+        }
+        Ok(run)
+    }
+}
+
+#[derive(Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+enum RunEvent {
+    Message {
+        message: Message,
+    },
+    ToolCall {
+        name: String,
+        arguments: Value,
+    },
+    FinalOutput {
+        output: Value,
+    },
+    Error {
+        error: String,
+    },
+    Cost {
+        cost_usd: Option<f64>,
+    },
+    #[serde(other)]
+    Other,
+}
+
+/// Collects the output, tool calls and cost from stream-json run events.
+/// Lines that are not events are ignored.
+pub fn parse_stream_events(stream: &str) -> TaskRun {
+    let mut run = TaskRun::default();
+    let mut texts = Vec::new();
+    for event in stream
+        .lines()
+        .filter_map(|line| serde_json::from_str::<RunEvent>(line).ok())
+    {
+        match event {
+            RunEvent::Message { message } if message.role == rmcp::model::Role::Assistant => {
+                run.turns += 1;
+                let text = message.as_concat_text();
+                if !text.is_empty() {
+                    texts.push(text);
+                }
+            }
+            RunEvent::Message { .. } | RunEvent::Other => {}
+            RunEvent::ToolCall { name, arguments } => {
+                run.tool_calls.push(format!("{}({})", name, arguments));
+            }
+            RunEvent::FinalOutput { output } => texts.push(match output {
+                Value::String(s) => s,
+                other => other.to_string(),
+            }),
+            RunEvent::Error { error } => run.error = Some(error),
+            RunEvent::Cost { cost_usd } => run.cost_usd = cost_usd,
+        }
+    }
+    run.output = texts.join("\n");
+    run
+}
+
+// ---------------------------------------------------------------------------
+// Runner
+// ---------------------------------------------------------------------------
+
+/// Runs a corpus across a matrix with cost caps.
+pub struct MatrixRunner {
+    executor: Box<dyn TaskExecutor>,
+    max_total_cost_usd: Option<f64>,
+}
+
+impl MatrixRunner {
+    pub fn new(executor: Box<dyn TaskExecutor>) -> Self {
+        Self {
+            executor,
+            max_total_cost_usd: None,
+        }
+    }
+
+    /// Caps the spend of the whole run, across all entries.
+    pub fn with_max_total_cost(mut self, max_cost_usd: f64) -> Self {
+        self.max_total_cost_usd = Some(max_cost_usd);
+        self
+    }
+
+    pub async fn run(
+        &self,
+        corpus: &[CorpusTask],
+        matrix: &BenchmarkMatrix,
+    ) -> Result<MatrixReport> {
+        let tasks: Vec<BenchmarkTask> = corpus.iter().map(CorpusTask::to_task).collect();
+        let mut total_spent = 0.0;
+        let mut entries = Vec::new();
+
+        for entry in &matrix.entries {
+            let mut spent = 0.0;
+            let mut skipped = 0;
+            let mut results = Vec::new();
+
+            for (corpus_task, task) in corpus.iter().zip(&tasks) {
+                let cap_reached = entry.max_cost_usd.is_some_and(|cap| spent >= cap)
+                    || self
+                        .max_total_cost_usd
+                        .is_some_and(|cap| total_spent >= cap);
+                if cap_reached {
+                    skipped += 1;
+                    results.push(skipped_result(task, "cost cap reached"));
+                    continue;
+                }
+
+                let workspace = tempfile::tempdir()?;
+                corpus_task.prepare_workspace(workspace.path())?;
+
+                let started = Instant::now();
+                let run = match self.executor.execute(task, entry, workspace.path()).await {
+                    Ok(run) => run,
+                    Err(e) => TaskRun {
+                        error: Some(e.to_string()),
+                        ..Default::default()
+                    },
+                };
+                let duration = started.elapsed();
+
+                let mut result = BenchmarkRunner::new()
+                    .with_evaluator(Box::new(WorkspaceEvaluator::new(workspace.path())))
+                    .evaluate_task(task, &run.output, &run.tool_calls, duration, run.turns)
+                    .await?;
+                if let Some(error) = run.error {
+                    result.passed = false;
+                    result.error = Some(error);
+                }
+                result.cost_usd = run.cost_usd;
+                spent += run.cost_usd.unwrap_or(0.0);
+                total_spent += run.cost_usd.unwrap_or(0.0);
+                tracing::info!(
+                    entry = %entry.label,
+                    task = %task.id,
+                    passed = result.passed,
+                    "benchmark task finished"
+                );
+                results.push(result);
+            }
+
+            let suite_name = format!("goose-bench/{}", entry.label);
+            entries.push(EntryReport {
+                entry: entry.clone(),
+                report: BenchmarkReport::from_results(&suite_name, results, &tasks),
+                cost_usd: spent,
+                skipped,
+            });
+        }
+
+        Ok(MatrixReport {
+            timestamp: chrono::Utc::now(),
+            entries,
+        })
+    }
+}
+
+fn skipped_result(task: &BenchmarkTask, reason: &str) -> BenchmarkResult {
+    BenchmarkResult {
+        task_id: task.id.clone(),
+        passed: false,
+        score: 0.0,
+        duration: Duration::ZERO,
+        turns_used: 0,
+        tool_calls: 0,
+        criteria_results: vec![],
+        error: Some(format!("skipped: {}", reason)),
+        output: None,
+        cost_usd: None,
+    }
+}
+
+// ---------------------------------------------------------------------------
+// Reports and baselines
+// ---------------------------------------------------------------------------
+
+/// Results of the corpus under one matrix entry.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct EntryReport {
+    pub entry: MatrixEntry,
+    pub report: BenchmarkReport,
+    pub cost_usd: f64,
+    /// Tasks not run because a cost cap was reached
+    pub skipped: usize,
+}
+
+impl EntryReport {
+    pub fn success_rate(&self) -> f64 {
+        self.report.passed as f64 / self.report.total_tasks.max(1) as f64
+    }
+
+    /// Mean wall-clock time of the tasks that ran.
+    pub fn mean_latency(&self) -> Duration {
+        let ran = self.report.total_tasks.saturating_sub(self.skipped);
+        if ran == 0 {
+            return Duration::ZERO;
+        }
+        self.report.total_duration / ran as u32
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MatrixReport {
+    pub timestamp: chrono::DateTime<chrono::Utc>,
+    pub entries: Vec<EntryReport>,
+}
+
+impl MatrixReport {
+    pub fn save(&self, path: &Path) -> Result<()> {
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        std::fs::write(path, serde_json::to_string_pretty(self)?)?;
+        Ok(())
+    }
+
+    pub fn load(path: &Path) -> Result<Self> {
+        let content = std::fs::read_to_string(path)
+            .with_context(|| format!("reading report {}", path.display()))?;
+        Ok(serde_json::from_str(&content)?)
+    }
+
+    /// Compares each entry with the baseline entry of the same label.
+    /// Entries missing from the baseline are not compared.
+    pub fn compare(&self, baseline: &MatrixReport) -> Vec<EntryComparison> {
+        self.entries
+            .iter()
+            .filter_map(|current| {
+                let previous = baseline
+                    .entries
+                    .iter()
+                    .find(|e| e.entry.label == current.entry.label)?;
+                Some(EntryComparison::new(current, previous))
+            })
+            .collect()
+    }
+}
+
+impl fmt::Display for MatrixReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(
+            f,
+            "📊 **goose-bench** | {}",
+            self.timestamp.format("%Y-%m-%d %H:%M")
+        )?;
+        writeln!(f, "────────────────────────────────")?;
+        for entry in &self.entries {
+            write!(
+                f,
+                "  {} ({}): {}/{} passed ({:.0}%) | ${:.4} | {:.1}s mean",
+                entry.entry.label,
+                entry.entry.core,
+                entry.report.passed,
+                entry.report.total_tasks,
+                entry.success_rate() * 100.0,
+                entry.cost_usd,
+                entry.mean_latency().as_secs_f64()
+            )?;
+            if entry.skipped > 0 {
+                write!(f, " | {} skipped (cost cap)", entry.skipped)?;
+            }
+            writeln!(f)?;
+        }
+        Ok(())
+    }
+}
+
+/// Baseline and current value of one metric.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+pub struct MetricDelta {
+    pub baseline: f64,
+    pub current: f64,
+}
+
+/// How one matrix entry moved relative to its baseline.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct EntryComparison {
+    pub label: String,
+    pub success_rate: MetricDelta,
+    pub cost_usd: MetricDelta,
+    pub mean_latency_secs: MetricDelta,
+    pub alerts: Vec<RegressionAlert>,
+}
+
+impl EntryComparison {
+    fn new(current: &EntryReport, previous: &EntryReport) -> Self {
+        let success_rate = MetricDelta {
+            baseline: previous.success_rate(),
+            current: current.success_rate(),
+        };
+        let cost_usd = MetricDelta {
+            baseline: previous.cost_usd,
+            current: current.cost_usd,
+        };
+        let mut alerts = current.report.check_regression(&previous.report);
+
+        if success_rate.current < success_rate.baseline - SUCCESS_RATE_TOLERANCE {
+            alerts.push(RegressionAlert {
+                alert_type: RegressionType::ScoreDrop,
+                message: format!(
+                    "Success rate dropped: {:.0}% → {:.0}%",
+                    success_rate.baseline * 100.0,
+                    success_rate.current * 100.0
+                ),
+                severity: AlertSeverity::Critical,
+            });
+        }
+        if cost_usd.baseline > 0.0
+            && cost_usd.current > cost_usd.baseline * (1.0 + COST_INCREASE_TOLERANCE)
+        {
+            alerts.push(RegressionAlert {
+                alert_type: RegressionType::CostIncrease,
+                message: format!(
+                    "Cost rose: ${:.4} → ${:.4}",
+                    cost_usd.baseline, cost_usd.current
+                ),
+                severity: AlertSeverity::Critical,
+            });
+        }
+
+        Self {
+            label: current.entry.label.clone(),
+            success_rate,
+            cost_usd,
+            mean_latency_secs: MetricDelta {
+                baseline: previous.mean_latency().as_secs_f64(),
+                current: current.mean_latency().as_secs_f64(),
+            },
+            alerts,
+        }
+    }
+
+    /// Whether this entry may ship: no critical regressions.
+    pub fn passes_gate(&self) -> bool {
+        !self
+            .alerts
+            .iter()
+            .any(|a| matches!(a.severity, AlertSeverity::Critical))
+    }
+}
+
+impl fmt::Display for EntryComparison {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(
+            f,
+            "  {}: success {:.0}% → {:.0}% | cost ${:.4} → ${:.4} | latency {:.1}s → {:.1}s",
+            self.label,
+            self.success_rate.baseline * 100.0,
+            self.success_rate.current * 100.0,
+            self.cost_usd.baseline,
+            self.cost_usd.current,
+            self.mean_latency_secs.baseline,
+            self.mean_latency_secs.current
+        )?;
+        for alert in &self.alerts {
+            writeln!(f, "    [{:?}] {}", alert.severity, alert.message)?;
+        }
+        Ok(())
+    }
+}
+
+/// Named baseline reports, stored as JSON under the goose data directory.
+pub struct BaselineStore {
+    dir: PathBuf,
+}
+
+impl BaselineStore {
+    pub fn new(dir: impl Into<PathBuf>) -> Self {
+        Self { dir: dir.into() }
+    }
+
+    pub fn default_store() -> Self {
+        Self::new(Paths::in_data_dir("bench/baselines"))
+    }
+
+    fn path_for(&self, name: &str) -> Result<PathBuf> {
+        if name.is_empty()
+            || !name
+                .chars()
+                .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_' || c == '.')
+            || name.starts_with('.')
+        {
+            bail!("invalid baseline name '{}'", name);
+        }
+        Ok(self.dir.join(format!("{}.json", name)))
+    }
+
+    pub fn save(&self, name: &str, report: &MatrixReport) -> Result<PathBuf> {
+        let path = self.path_for(name)?;
+        report.save(&path)?;
+        Ok(path)
+    }
+
+    pub fn load(&self, name: &str) -> Result<Option<MatrixReport>> {
+        let path = self.path_for(name)?;
+        if !path.exists() {
+            return Ok(None);
+        }
+        MatrixReport::load(&path).map(Some)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    struct ScriptedExecutor;
+
+    #[async_trait::async_trait]
+    impl TaskExecutor for ScriptedExecutor {
+        async fn execute(
+            &self,
+            task: &BenchmarkTask,
+            _entry: &MatrixEntry,
+            workspace: &Path,
+        ) -> Result<TaskRun> {
+            if task.id == "code-fix-001" {
+                std::fs::write(
+                    workspace.join("sum.py"),
+                    "def sum_range(n):\n    return sum(range(1, n + 1))\n",
+                )?;
+            }
+            Ok(TaskRun {
+                output: "Staging listens on 9174.".into(),
+                turns: 1,
+                cost_usd: Some(0.5),
+                ..Default::default()
+            })
+        }
+    }
+
+    fn matrix(cap: Option<f64>) -> BenchmarkMatrix {
+        BenchmarkMatrix {
+            entries: vec![MatrixEntry {
+                max_cost_usd: cap,
+                ..MatrixEntry::current()
+            }],
+        }
+    }
+
+    #[tokio::test]
+    async fn test_matrix_run_grades_workspace_and_caps_cost() {
+        if which::which("python3").is_err() {
+            return;
+        }
+        let corpus = builtin_corpus();
+        let runner = MatrixRunner::new(Box::new(ScriptedExecutor));
+
+        let report = runner.run(&corpus, &matrix(None)).await.unwrap();
+        let entry = &report.entries[0];
+        let passed: Vec<_> = entry
+            .report
+            .results
+            .iter()
+            .filter(|r| r.passed)
+            .map(|r| r.task_id.as_str())
+            .collect();
+        assert_eq!(passed, vec!["code-fix-001"]);
+        assert!((entry.cost_usd - 1.5).abs() < f64::EPSILON);
+
+        let capped = runner.run(&corpus, &matrix(Some(1.0))).await.unwrap();
+        assert_eq!(capped.entries[0].skipped, 1);
+        assert!(capped.entries[0].cost_usd <= 1.0);
+
+        // Relative to the capped run, the full run costs 50% more
+        assert!(!report.compare(&capped)[0].passes_gate());
+        assert!(report.compare(&report)[0].passes_gate());
+    }
+
+    #[test]
+    fn test_parse_stream_events() {
+        let stream = [
+            r#"{"type":"message","message":{"role":"assistant","created":0,"content":[{"type":"text","text":"Looking"}],"metadata":{"userVisible":true,"agentVisible":true}}}"#,
+            r#"{"type":"tool_call","id":"1","name":"developer__shell","arguments":{"command":"ls"}}"#,
+            "not json",
+            r#"{"type":"cost","input_tokens":10,"output_tokens":5,"total_tokens":15,"cost_usd":0.02}"#,
+            r#"{"type":"complete","total_tokens":15}"#,
+        ]
+        .join("\n");
+        let run = parse_stream_events(&stream);
+        assert_eq!(run.output, "Looking");
+        assert_eq!(run.turns, 1);
+        assert_eq!(
+            run.tool_calls,
+            vec![r#"developer__shell({"command":"ls"})"#]
+        );
+        assert_eq!(run.cost_usd, Some(0.02));
+        assert!(run.error.is_none());
+    }
+
+    #[test]
+    fn test_load_corpus_and_baselines() {
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::write(
+            dir.path().join("research.yaml"),
+            "id: research-002\nname: Find owner\ncategory: research\nprompt: Who owns staging?\nexpected:\n  - Contains: Priya\n",
+        )
+        .unwrap();
+        std::fs::write(
+            dir.path().join("escape.yaml"),
+            "id: bad\nname: Bad\ncategory: code_fix\nprompt: x\nexpected:\n  - Contains: x\nfiles:\n  ../outside.py: x\n",
+        )
+        .unwrap();
+        assert!(load_corpus(dir.path()).is_err());
+
+        std::fs::remove_file(dir.path().join("escape.yaml")).unwrap();
+        let corpus = load_corpus(dir.path()).unwrap();
+        assert_eq!(corpus[0].category, TaskCategory::Research);
+        assert_eq!(corpus[0].to_task().timeout, Duration::from_secs(300));
+
+        let store = BaselineStore::new(dir.path().join("baselines"));
+        assert!(store.load("main").unwrap().is_none());
+        assert!(store
+            .save(
+                "../main",
+                &MatrixReport {
+                    timestamp: chrono::Utc::now(),
+                    entries: vec![]
+                }
+            )
+            .is_err());
+    }
+}
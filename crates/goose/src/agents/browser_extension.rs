@@ -0,0 +1,721 @@
+//! Browser automation backed by the Playwright MCP server.
+//!
+//! The Playwright server is added through the extension manager the first
+//! time a browser tool is called. Every navigation, click and form fill is
+//! recorded in the session as a step that `replay` can run again, and each
+//! step's screenshot is stored as a session attachment.
+
+use crate::agents::extension::{Envs, ExtensionConfig, PlatformExtensionContext};
+use crate::agents::extension_manager::ExtensionManager;
+use crate::agents::mcp_client::{Error, McpClientTrait};
+use crate::config::{Config, DEFAULT_EXTENSION_TIMEOUT};
+use crate::session::extension_data::ExtensionState;
+use anyhow::Result;
+use async_trait::async_trait;
+use base64::Engine;
+use chrono::{DateTime, Utc};
+use indoc::indoc;
+use rmcp::model::{
+    CallToolRequestParams, CallToolResult, Content, Implementation, InitializeResult, JsonObject,
+    ListToolsResult, ProtocolVersion, ServerCapabilities, Tool, ToolAnnotations, ToolsCapability,
+};
+use schemars::{schema_for, JsonSchema};
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use std::sync::Arc;
+use tokio_util::sync::CancellationToken;
+
+pub static EXTENSION_NAME: &str = "browser";
+pub const PLAYWRIGHT_EXTENSION_NAME: &str = "playwright";
+/// Set to false to show the browser window
+pub const BROWSER_HEADLESS_KEY: &str = "GOOSE_BROWSER_HEADLESS";
+
+/// Stdio config for the Playwright MCP server
+pub fn playwright_config() -> ExtensionConfig {
+    let mut args = vec!["-y".to_string(), "@playwright/mcp@latest".to_string()];
+    if Config::global()
+        .get_param::<bool>(BROWSER_HEADLESS_KEY)
+        .unwrap_or(true)
+    {
+        args.push("--headless".to_string());
+    }
+    ExtensionConfig::Stdio {
+        name: PLAYWRIGHT_EXTENSION_NAME.to_string(),
+        description: "Browser automation through Playwright".to_string(),
+        cmd: "npx".to_string(),
+        args,
+        envs: Envs::default(),
+        env_keys: Vec::new(),
+        timeout: Some(DEFAULT_EXTENSION_TIMEOUT),
+        bundled: Some(true),
+        available_tools: Vec::new(),
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, JsonSchema)]
+pub struct FormField {
+    /// Human-readable description of the input
+    pub element: String,
+    /// Element reference from the page snapshot
+    #[serde(rename = "ref")]
+    pub reference: String,
+    pub value: String,
+    /// Passwords and other secrets are filled but not recorded
+    #[serde(default)]
+    pub sensitive: bool,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "action", rename_all = "snake_case")]
+pub enum BrowserAction {
+    Navigate {
+        url: String,
+    },
+    Click {
+        element: String,
+        #[serde(rename = "ref")]
+        reference: String,
+    },
+    FillForm {
+        fields: Vec<FormField>,
+    },
+}
+
+impl BrowserAction {
+    /// The Playwright tool calls that perform this action
+    fn playwright_calls(&self) -> Vec<(&'static str, JsonObject)> {
+        let object = |value: serde_json::Value| value.as_object().cloned().unwrap_or_default();
+        match self {
+            Self::Navigate { url } => vec![("browser_navigate", object(json!({ "url": url })))],
+            Self::Click { element, reference } => vec![(
+                "browser_click",
+                object(json!({ "element": element, "ref": reference })),
+            )],
+            Self::FillForm { fields } => fields
+                .iter()
+                .map(|field| {
+                    (
+                        "browser_type",
+                        object(json!({
+                            "element": field.element,
+                            "ref": field.reference,
+                            "text": field.value,
+                        })),
+                    )
+                })
+                .collect(),
+        }
+    }
+
+    /// The action as it is stored, without sensitive values
+    fn for_recording(&self) -> Self {
+        match self {
+            Self::FillForm { fields } => Self::FillForm {
+                fields: fields
+                    .iter()
+                    .map(|field| FormField {
+                        value: if field.sensitive {
+                            String::new()
+                        } else {
+                            field.value.clone()
+                        },
+                        ..field.clone()
+                    })
+                    .collect(),
+            },
+            other => other.clone(),
+        }
+    }
+
+    fn has_redacted_values(&self) -> bool {
+        matches!(self, Self::FillForm { fields } if fields.iter().any(|f| f.sensitive))
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct BrowserStep {
+    #[serde(flatten)]
+    pub action: BrowserAction,
+    pub at: DateTime<Utc>,
+    /// Attachment id of the screenshot taken after the step
+    pub screenshot: Option<String>,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct BrowserRecording {
+    pub steps: Vec<BrowserStep>,
+}
+
+impl ExtensionState for BrowserRecording {
+    const EXTENSION_NAME: &'static str = "browser_recording";
+    const VERSION: &'static str = "v0";
+}
+
+impl BrowserRecording {
+    pub fn describe(&self) -> String {
+        if self.steps.is_empty() {
+            return "No browser steps recorded in this session".to_string();
+        }
+        self.steps
+            .iter()
+            .enumerate()
+            .map(|(index, step)| {
+                let action = match &step.action {
+                    BrowserAction::Navigate { url } => format!("navigate to {}", url),
+                    BrowserAction::Click { element, .. } => format!("click {}", element),
+                    BrowserAction::FillForm { fields } => format!(
+                        "fill {}",
+                        fields
+                            .iter()
+                            .map(|f| f.element.as_str())
+                            .collect::<Vec<_>>()
+                            .join(", ")
+                    ),
+                };
+                let screenshot = step
+                    .screenshot
+                    .as_deref()
+                    .map(|id| format!(" (screenshot {})", id))
+                    .unwrap_or_default();
+                format!("{}. {}{}", index + 1, action, screenshot)
+            })
+            .collect::<Vec<_>>()
+            .join("\n")
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize, JsonSchema)]
+struct NavigateParams {
+    url: String,
+}
+
+#[derive(Debug, Serialize, Deserialize, JsonSchema)]
+struct ClickParams {
+    /// Human-readable description of the element
+    element: String,
+    /// Element reference from the page snapshot
+    #[serde(rename = "ref")]
+    reference: String,
+}
+
+#[derive(Debug, Serialize, Deserialize, JsonSchema)]
+struct FillFormParams {
+    fields: Vec<FormField>,
+}
+
+#[derive(Debug, Serialize, Deserialize, JsonSchema)]
+struct PageParams {}
+
+#[derive(Debug, Serialize, Deserialize, JsonSchema)]
+struct RecordingParams {
+    /// Start a new recording
+    #[serde(default)]
+    clear: bool,
+}
+
+#[derive(Debug, Serialize, Deserialize, JsonSchema)]
+struct ReplayParams {
+    /// First step to replay, counting from 1
+    #[serde(default)]
+    from_step: Option<usize>,
+}
+
+pub struct BrowserClient {
+    info: InitializeResult,
+    context: PlatformExtensionContext,
+}
+
+impl BrowserClient {
+    pub fn new(context: PlatformExtensionContext) -> Result<Self> {
+        let info = InitializeResult {
+            protocol_version: ProtocolVersion::V_2025_03_26,
+            capabilities: ServerCapabilities {
+                tasks: None,
+                tools: Some(ToolsCapability {
+                    list_changed: Some(false),
+                }),
+                resources: None,
+                prompts: None,
+                completions: None,
+                experimental: None,
+                logging: None,
+            },
+            server_info: Implementation {
+                name: EXTENSION_NAME.to_string(),
+                title: Some("Browser".to_string()),
+                version: "1.0.0".to_string(),
+                icons: None,
+                website_url: None,
+            },
+            instructions: Some(
+                indoc! {r#"
+                Drive a real browser for research and web tasks.
+
+                Workflow:
+                - navigate to a page
+                - extract to read the page; it lists elements with their ref
+                - click or fill_form using the element ref from the latest extract
+                - screenshot when you need to see the page
+
+                Navigations, clicks and form fills are recorded. Use recording to see
+                the steps and replay to run them again.
+            "#}
+                .to_string(),
+            ),
+        };
+
+        Ok(Self { info, context })
+    }
+
+    async fn browser(&self) -> Result<Arc<ExtensionManager>, String> {
+        let manager = self
+            .context
+            .extension_manager
+            .as_ref()
+            .and_then(|weak| weak.upgrade())
+            .ok_or("Extension manager is no longer available")?;
+        if !manager
+            .is_extension_enabled(PLAYWRIGHT_EXTENSION_NAME)
+            .await
+        {
+            manager
+                .add_extension(playwright_config(), None, None, None)
+                .await
+                .map_err(|e| format!("Failed to start the Playwright browser: {}", e))?;
+        }
+        Ok(manager)
+    }
+
+    async fn call_playwright(
+        manager: &ExtensionManager,
+        session_id: &str,
+        tool: &str,
+        arguments: JsonObject,
+        cancellation_token: CancellationToken,
+    ) -> Result<CallToolResult, String> {
+        let call = CallToolRequestParams {
+            meta: None,
+            task: None,
+            name: format!("{}__{}", PLAYWRIGHT_EXTENSION_NAME, tool).into(),
+            arguments: Some(arguments),
+        };
+        let result = manager
+            .dispatch_tool_call(session_id, call, None, cancellation_token)
+            .await
+            .map_err(|e| e.to_string())?
+            .result
+            .await
+            .map_err(|e| e.message.to_string())?;
+        if result.is_error == Some(true) {
+            let text: Vec<String> = result
+                .content
+                .iter()
+                .filter_map(|c| c.as_text().map(|t| t.text.clone()))
+                .collect();
+            return Err(format!("{} failed: {}", tool, text.join("\n")));
+        }
+        Ok(result)
+    }
+
+    /// Screenshots the page and stores it as a session attachment
+    async fn screenshot(
+        &self,
+        manager: &ExtensionManager,
+        session_id: &str,
+        name: &str,
+        cancellation_token: CancellationToken,
+    ) -> Result<(String, Content), String> {
+        let result = Self::call_playwright(
+            manager,
+            session_id,
+            "browser_take_screenshot",
+            JsonObject::new(),
+            cancellation_token,
+        )
+        .await?;
+        let image = result
+            .content
+            .iter()
+            .find_map(|c| c.as_image().cloned())
+            .ok_or("The browser returned no screenshot")?;
+        let bytes = base64::engine::general_purpose::STANDARD
+            .decode(&image.data)
+            .map_err(|e| format!("Invalid screenshot data: {}", e))?;
+        let attachment = self
+            .context
+            .session_manager
+            .attachments()
+            .store(session_id, name, &bytes)
+            .map_err(|e| format!("Failed to store screenshot: {}", e))?;
+        Ok((
+            attachment.id,
+            Content::image(image.data, image.mime_type).with_priority(0.0),
+        ))
+    }
+
+    async fn load_recording(&self, session_id: &str) -> Result<BrowserRecording, String> {
+        let session = self
+            .context
+            .session_manager
+            .get_session(session_id, false)
+            .await
+            .map_err(|_| "Failed to read session metadata")?;
+        Ok(BrowserRecording::from_extension_data(&session.extension_data).unwrap_or_default())
+    }
+
+    async fn save_recording(
+        &self,
+        session_id: &str,
+        recording: &BrowserRecording,
+    ) -> Result<(), String> {
+        let manager = &self.context.session_manager;
+        let mut session = manager
+            .get_session(session_id, false)
+            .await
+            .map_err(|_| "Failed to read session metadata")?;
+        recording
+            .to_extension_data(&mut session.extension_data)
+            .map_err(|_| "Failed to serialize browser recording")?;
+        manager
+            .update(session_id)
+            .extension_data(session.extension_data)
+            .apply()
+            .await
+            .map_err(|_| "Failed to update session metadata".to_string())
+    }
+
+    /// Runs an action, screenshots the result and records the step
+    async fn perform(
+        &self,
+        session_id: &str,
+        action: BrowserAction,
+        cancellation_token: CancellationToken,
+    ) -> Result<Vec<Content>, String> {
+        let manager = self.browser().await?;
+        let mut content = Vec::new();
+        for (tool, arguments) in action.playwright_calls() {
+            let result = Self::call_playwright(
+                &manager,
+                session_id,
+                tool,
+                arguments,
+                cancellation_token.clone(),
+            )
+            .await?;
+            content.extend(result.content.into_iter().filter(|c| c.as_text().is_some()));
+        }
+
+        let mut recording = self.load_recording(session_id).await?;
+        let name = format!("browser-step-{}.png", recording.steps.len() + 1);
+        let screenshot = match self
+            .screenshot(&manager, session_id, &name, cancellation_token)
+            .await
+        {
+            Ok((id, image)) => {
+                content.push(image);
+                Some(id)
+            }
+            Err(e) => {
+                content.push(Content::text(format!("Screenshot not recorded: {}", e)));
+                None
+            }
+        };
+        recording.steps.push(BrowserStep {
+            action: action.for_recording(),
+            at: Utc::now(),
+            screenshot,
+        });
+        self.save_recording(session_id, &recording).await?;
+        Ok(content)
+    }
+
+    async fn replay(
+        &self,
+        session_id: &str,
+        from_step: usize,
+        cancellation_token: CancellationToken,
+    ) -> Result<Vec<Content>, String> {
+        let recording = self.load_recording(session_id).await?;
+        let steps: Vec<BrowserStep> = recording
+            .steps
+            .into_iter()
+            .skip(from_step.saturating_sub(1))
+            .collect();
+        if steps.is_empty() {
+            return Err("No recorded steps to replay".to_string());
+        }
+
+        let manager = self.browser().await?;
+        let mut report = Vec::new();
+        for (offset, step) in steps.iter().enumerate() {
+            let number = from_step.max(1) + offset;
+            if step.action.has_redacted_values() {
+                return Err(format!(
+                    "Replayed {} steps; step {} fills a sensitive field that was not recorded",
+                    offset, number
+                ));
+            }
+            for (tool, arguments) in step.action.playwright_calls() {
+                Self::call_playwright(
+                    &manager,
+                    session_id,
+                    tool,
+                    arguments,
+                    cancellation_token.clone(),
+                )
+                .await
+                .map_err(|e| format!("Replay stopped at step {}: {}", number, e))?;
+            }
+            report.push(format!("Replayed step {}", number));
+        }
+        Ok(vec![Content::text(report.join("\n"))])
+    }
+
+    async fn handle(
+        &self,
+        session_id: &str,
+        name: &str,
+        arguments: Option<JsonObject>,
+        cancellation_token: CancellationToken,
+    ) -> Result<Vec<Content>, String> {
+        fn params<T: serde::de::DeserializeOwned>(
+            arguments: Option<JsonObject>,
+        ) -> Result<T, String> {
+            serde_json::from_value(serde_json::Value::Object(arguments.unwrap_or_default()))
+                .map_err(|e| format!("Invalid parameters: {}", e))
+        }
+
+        match name {
+            "navigate" => {
+                let NavigateParams { url } = params(arguments)?;
+                self.perform(
+                    session_id,
+                    BrowserAction::Navigate { url },
+                    cancellation_token,
+                )
+                .await
+            }
+            "click" => {
+                let ClickParams { element, reference } = params(arguments)?;
+                self.perform(
+                    session_id,
+                    BrowserAction::Click { element, reference },
+                    cancellation_token,
+                )
+                .await
+            }
+            "fill_form" => {
+                let FillFormParams { fields } = params(arguments)?;
+                self.perform(
+                    session_id,
+                    BrowserAction::FillForm { fields },
+                    cancellation_token,
+                )
+                .await
+            }
+            "extract" => {
+                let manager = self.browser().await?;
+                let result = Self::call_playwright(
+                    &manager,
+                    session_id,
+                    "browser_snapshot",
+                    JsonObject::new(),
+                    cancellation_token,
+                )
+                .await?;
+                Ok(result.content)
+            }
+            "screenshot" => {
+                let manager = self.browser().await?;
+                let (id, image) = self
+                    .screenshot(
+                        &manager,
+                        session_id,
+                        "browser-screenshot.png",
+                        cancellation_token,
+                    )
+                    .await?;
+                Ok(vec![
+                    Content::text(format!("Screenshot stored as attachment {}", id)),
+                    image,
+                ])
+            }
+            "recording" => {
+                let RecordingParams { clear } = params(arguments)?;
+                if clear {
+                    self.save_recording(session_id, &BrowserRecording::default())
+                        .await?;
+                    return Ok(vec![Content::text("Browser recording cleared")]);
+                }
+                let recording = self.load_recording(session_id).await?;
+                Ok(vec![Content::text(recording.describe())])
+            }
+            "replay" => {
+                let ReplayParams { from_step } = params(arguments)?;
+                self.replay(session_id, from_step.unwrap_or(1), cancellation_token)
+                    .await
+            }
+            _ => Err(format!("Unknown tool: {}", name)),
+        }
+    }
+
+    fn get_tools() -> Vec<Tool> {
+        fn schema<T: JsonSchema>() -> JsonObject {
+            serde_json::to_value(schema_for!(T))
+                .expect("Failed to serialize browser tool schema")
+                .as_object()
+                .unwrap()
+                .clone()
+        }
+        let annotations = |title: &str, read_only: bool| ToolAnnotations {
+            title: Some(title.to_string()),
+            read_only_hint: Some(read_only),
+            destructive_hint: Some(false),
+            idempotent_hint: Some(read_only),
+            open_world_hint: Some(true),
+        };
+
+        vec![
+            Tool::new(
+                "navigate".to_string(),
+                "Open a URL in the browser. The step is recorded.".to_string(),
+                schema::<NavigateParams>(),
+            )
+            .annotate(annotations("Navigate", false)),
+            Tool::new(
+                "extract".to_string(),
+                "Read the current page as an accessibility snapshot: its text and the interactive elements with their ref.".to_string(),
+                schema::<PageParams>(),
+            )
+            .annotate(annotations("Extract page", true)),
+            Tool::new(
+                "click".to_string(),
+                "Click an element by its ref from the latest extract. The step is recorded.".to_string(),
+                schema::<ClickParams>(),
+            )
+            .annotate(annotations("Click", false)),
+            Tool::new(
+                "fill_form".to_string(),
+                "Type values into form inputs by their ref from the latest extract. Mark passwords and other secrets as sensitive so they are not recorded.".to_string(),
+                schema::<FillFormParams>(),
+            )
+            .annotate(annotations("Fill form", false)),
+            Tool::new(
+                "screenshot".to_string(),
+                "Screenshot the current page. It is stored as a session attachment.".to_string(),
+                schema::<PageParams>(),
+            )
+            .annotate(annotations("Screenshot", true)),
+            Tool::new(
+                "recording".to_string(),
+                "List the browser steps recorded in this session, or clear them to start over.".to_string(),
+                schema::<RecordingParams>(),
+            )
+            .annotate(annotations("Browser recording", false)),
+            Tool::new(
+                "replay".to_string(),
+                "Run the recorded browser steps again, optionally starting from a later step.".to_string(),
+                schema::<ReplayParams>(),
+            )
+            .annotate(annotations("Replay recording", false)),
+        ]
+    }
+}
+
+#[async_trait]
+impl McpClientTrait for BrowserClient {
+    async fn list_tools(
+        &self,
+        _session_id: &str,
+        _next_cursor: Option<String>,
+        _cancellation_token: CancellationToken,
+    ) -> Result<ListToolsResult, Error> {
+        Ok(ListToolsResult {
+            tools: Self::get_tools(),
+            next_cursor: None,
+            meta: None,
+        })
+    }
+
+    async fn call_tool(
+        &self,
+        session_id: &str,
+        name: &str,
+        arguments: Option<JsonObject>,
+        _working_dir: Option<&str>,
+        cancellation_token: CancellationToken,
+    ) -> Result<CallToolResult, Error> {
+        match self
+            .handle(session_id, name, arguments, cancellation_token)
+            .await
+        {
+            Ok(content) => Ok(CallToolResult::success(content)),
+            Err(error) => Ok(CallToolResult::error(vec![Content::text(format!(
+                "Error: {}",
+                error
+            ))])),
+        }
+    }
+
+    fn get_info(&self) -> Option<&InitializeResult> {
+        Some(&self.info)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_fill_form_records_without_secrets() {
+        let action = BrowserAction::FillForm {
+            fields: vec![
+                FormField {
+                    element: "Email".to_string(),
+                    reference: "e4".to_string(),
+                    value: "ada@example.com".to_string(),
+                    sensitive: false,
+                },
+                FormField {
+                    element: "Password".to_string(),
+                    reference: "e5".to_string(),
+                    value: "hunter2".to_string(),
+                    sensitive: true,
+                },
+            ],
+        };
+
+        let calls = action.playwright_calls();
+        assert_eq!(calls.len(), 2);
+        assert_eq!(calls[1].0, "browser_type");
+        assert_eq!(calls[1].1["text"], "hunter2");
+
+        let recorded = action.for_recording();
+        assert!(recorded.has_redacted_values());
+        let step = BrowserStep {
+            action: recorded,
+            at: Utc::now(),
+            screenshot: Some("abc".to_string()),
+        };
+        let json = serde_json::to_string(&step).unwrap();
+        assert!(!json.contains("hunter2"));
+        assert!(json.contains(r#""action":"fill_form""#));
+
+        let recording = BrowserRecording {
+            steps: vec![
+                BrowserStep {
+                    action: BrowserAction::Navigate {
+                        url: "https://example.com/login".to_string(),
+                    },
+                    at: Utc::now(),
+                    screenshot: None,
+                },
+                serde_json::from_str(&json).unwrap(),
+            ],
+        };
+        assert_eq!(
+            recording.describe(),
+            "1. navigate to https://example.com/login\n2. fill Email, Password (screenshot abc)"
+        );
+    }
+}
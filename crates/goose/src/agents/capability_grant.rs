@@ -0,0 +1,367 @@
+//! Least-privilege grants for subagents.
+//!
+//! The tool call that spawns a subagent can hand it a [`CapabilityGrant`]
+//! instead of everything its parent has. `tools` lists the extensions
+//! (`developer`) or single tools (`developer__text_editor`) the child may
+//! call; its ExtensionManager neither lists nor runs anything else, and its
+//! permission inspector denies anything else before it can be approved.
+//! `read_paths` makes the child read-only within those paths: every path
+//! in a tool call, with symlinks followed, has to fall under one of them,
+//! and only tools annotated read-only or the developer text editor's read
+//! commands run. Anything that could write,
+//! including unannotated tools and shell commands, is refused.
+//!
+//! A subagent spawning its own subagents can only hand on what it was
+//! granted: grants narrow down the tree, never widen.
+
+use crate::config::extensions::name_to_key;
+use rmcp::model::{JsonObject, Tool};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::path::{Component, Path, PathBuf};
+
+/// Argument names treated as paths
+const PATH_ARGUMENTS: &[&str] = &[
+    "path",
+    "paths",
+    "file",
+    "file_path",
+    "files",
+    "dir",
+    "directory",
+    "cwd",
+    "working_dir",
+];
+/// The developer extension's editor, whose read commands are allowed
+const TEXT_EDITOR_TOOL: &str = "developer__text_editor";
+/// Text-editor commands that only read
+const READ_COMMANDS: &[&str] = &["view", "read", "list", "search"];
+
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CapabilityGrant {
+    /// Extensions or `extension__tool` names the subagent may call; all of
+    /// its parent's when unset
+    #[serde(default)]
+    pub tools: Option<Vec<String>>,
+    /// Paths the subagent may read, and nothing else; unrestricted when unset
+    #[serde(default)]
+    pub read_paths: Option<Vec<PathBuf>>,
+}
+
+fn entry_allows(entry: &str, tool: &str) -> bool {
+    let entry = entry.trim();
+    // Platform tools such as `subagent` have no extension prefix
+    if entry == tool || entry.contains("__") {
+        return entry == tool;
+    }
+    tool.split_once("__")
+        .is_some_and(|(extension, _)| extension == name_to_key(entry))
+}
+
+/// `path` made absolute against `working_dir`, with `.` and `..` resolved
+/// without touching the filesystem.
+fn normalize(path: &Path, working_dir: Option<&Path>) -> PathBuf {
+    let joined = match working_dir {
+        Some(dir) if path.is_relative() => dir.join(path),
+        _ => path.to_path_buf(),
+    };
+    let mut normalized = PathBuf::new();
+    for component in joined.components() {
+        match component {
+            Component::CurDir => {}
+            Component::ParentDir => {
+                normalized.pop();
+            }
+            other => normalized.push(other),
+        }
+    }
+    normalized
+}
+
+/// `path` made absolute against `working_dir`, following symlinks as far as
+/// the path exists so a link inside a scope cannot point out of it. Each
+/// existing component is canonicalized before the next is applied, so a
+/// `..` after a symlink steps out of the link's target, as the OS would.
+fn resolve(path: &Path, working_dir: Option<&Path>) -> PathBuf {
+    let joined = match working_dir {
+        Some(dir) if path.is_relative() => dir.join(path),
+        _ => path.to_path_buf(),
+    };
+    let mut resolved = PathBuf::new();
+    let mut exists = true;
+    for component in joined.components() {
+        match component {
+            Component::CurDir => {}
+            Component::ParentDir => {
+                resolved.pop();
+            }
+            other => {
+                resolved.push(other);
+                if exists {
+                    match resolved.canonicalize() {
+                        Ok(canonical) => resolved = canonical,
+                        Err(_) => exists = false,
+                    }
+                }
+            }
+        }
+    }
+    resolved
+}
+
+fn path_arguments(arguments: &serde_json::Map<String, Value>) -> Vec<&str> {
+    PATH_ARGUMENTS
+        .iter()
+        .filter_map(|name| arguments.get(*name))
+        .flat_map(|value| match value {
+            Value::String(path) => vec![path.as_str()],
+            Value::Array(paths) => paths.iter().filter_map(Value::as_str).collect(),
+            _ => Vec::new(),
+        })
+        .collect()
+}
+
+fn unique<'a, T: PartialEq + Clone + 'a>(items: impl Iterator<Item = &'a T>) -> Vec<T> {
+    let mut unique: Vec<T> = Vec::new();
+    for item in items {
+        if !unique.contains(item) {
+            unique.push(item.clone());
+        }
+    }
+    unique
+}
+
+impl CapabilityGrant {
+    pub fn allows_tool(&self, tool: &str) -> bool {
+        self.tools
+            .as_ref()
+            .is_none_or(|entries| entries.iter().any(|entry| entry_allows(entry, tool)))
+    }
+
+    /// Whether any of the extension's tools is granted.
+    pub fn allows_extension(&self, extension: &str) -> bool {
+        let key = name_to_key(extension);
+        self.tools.as_ref().is_none_or(|entries| {
+            entries.iter().any(|entry| {
+                let entry = entry.trim();
+                let entry_extension = entry.split_once("__").map_or(entry, |(ext, _)| ext);
+                name_to_key(entry_extension) == key
+            })
+        })
+    }
+
+    fn allows_path(&self, path: &Path, working_dir: Option<&Path>) -> bool {
+        self.read_paths.as_ref().is_none_or(|scopes| {
+            let path = resolve(path, working_dir);
+            scopes
+                .iter()
+                .any(|scope| path.starts_with(resolve(scope, working_dir)))
+        })
+    }
+
+    /// Checks a call about to run of the prefixed tool `name`. `tool` is
+    /// its definition when known, and `runs_shell` whether it executes a
+    /// shell command.
+    pub fn check_call(
+        &self,
+        name: &str,
+        tool: Option<&Tool>,
+        arguments: Option<&JsonObject>,
+        working_dir: Option<&Path>,
+        runs_shell: bool,
+    ) -> Result<(), String> {
+        if !self.allows_tool(name) {
+            return Err(format!("Tool '{}' is not granted to this subagent", name));
+        }
+        if self.read_paths.is_none() {
+            return Ok(());
+        }
+
+        if runs_shell {
+            return Err("Shell commands are not allowed for a read-only subagent".to_string());
+        }
+        let marked_read_only = tool
+            .and_then(|t| t.annotations.as_ref())
+            .is_some_and(|a| a.read_only_hint == Some(true));
+        let arguments = arguments.cloned().unwrap_or_default();
+        let reads = if name == TEXT_EDITOR_TOOL {
+            arguments
+                .get("command")
+                .and_then(Value::as_str)
+                .is_some_and(|command| READ_COMMANDS.contains(&command))
+        } else {
+            marked_read_only
+        };
+        if !reads {
+            return Err(format!(
+                "Tool '{}' is not read-only, and this subagent is read-only",
+                name
+            ));
+        }
+        for path in path_arguments(&arguments) {
+            if !self.allows_path(Path::new(path), working_dir) {
+                return Err(format!("Path '{}' is outside this subagent's scope", path));
+            }
+        }
+        Ok(())
+    }
+
+    /// The grant with its read paths made absolute against `working_dir`,
+    /// as they are when the subagent is spawned.
+    pub fn resolved(mut self, working_dir: &Path) -> Self {
+        if let Some(paths) = &mut self.read_paths {
+            for path in paths.iter_mut() {
+                *path = normalize(path, Some(working_dir));
+            }
+        }
+        self
+    }
+
+    /// What both grants allow: a child's requested grant within its
+    /// parent's. Both must be [`resolved`](Self::resolved).
+    pub fn narrow(&self, requested: &CapabilityGrant) -> CapabilityGrant {
+        let tools = match (&self.tools, &requested.tools) {
+            (Some(granted), Some(wanted)) => Some(unique(
+                wanted
+                    .iter()
+                    .filter(|entry| self.allows_entry(entry))
+                    .chain(granted.iter().filter(|entry| requested.allows_entry(entry))),
+            )),
+            (granted, wanted) => granted.clone().or_else(|| wanted.clone()),
+        };
+        let read_paths = match (&self.read_paths, &requested.read_paths) {
+            (Some(granted), Some(wanted)) => Some(unique(
+                wanted
+                    .iter()
+                    .filter(|path| self.allows_path(path, None))
+                    .chain(
+                        granted
+                            .iter()
+                            .filter(|path| requested.allows_path(path, None)),
+                    ),
+            )),
+            (granted, wanted) => granted.clone().or_else(|| wanted.clone()),
+        };
+        CapabilityGrant { tools, read_paths }
+    }
+
+    /// Whether everything `entry` names is granted.
+    fn allows_entry(&self, entry: &str) -> bool {
+        let entry = entry.trim();
+        if entry.contains("__") {
+            return self.allows_tool(entry);
+        }
+        self.tools.as_ref().is_none_or(|entries| {
+            entries.iter().any(|granted| {
+                !granted.contains("__") && name_to_key(granted) == name_to_key(entry)
+            })
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rmcp::model::ToolAnnotations;
+    use rmcp::object;
+
+    #[test]
+    fn test_read_only_grant_allows_only_granted_reads_in_scope() {
+        let dir = Path::new("/work");
+        let grant = CapabilityGrant {
+            tools: Some(vec!["developer__text_editor".to_string()]),
+            read_paths: Some(vec![PathBuf::from("docs")]),
+        }
+        .resolved(dir);
+        let check = |name: &str, arguments: JsonObject| {
+            grant.check_call(name, None, Some(&arguments), Some(dir), false)
+        };
+
+        assert!(check(
+            "developer__text_editor",
+            object!({ "command": "view", "path": "docs/guide.md" })
+        )
+        .is_ok());
+        assert!(check("developer__shell", object!({ "command": "ls" })).is_err());
+        assert!(check(
+            "developer__text_editor",
+            object!({ "command": "write", "path": "docs/guide.md" })
+        )
+        .is_err());
+        assert!(check(
+            "developer__text_editor",
+            object!({ "command": "view", "path": "docs/../secrets.env" })
+        )
+        .is_err());
+
+        // Other tools must be annotated read-only
+        let any_tool = CapabilityGrant {
+            tools: None,
+            ..grant.clone()
+        };
+        let arguments = object!({ "path": "docs/guide.md" });
+        let check_tool = |tool: &Tool| {
+            any_tool.check_call(
+                "docs__fetch",
+                Some(tool),
+                Some(&arguments),
+                Some(dir),
+                false,
+            )
+        };
+        let mut tool = Tool::new("fetch", "", object!({}));
+        assert!(check_tool(&tool).is_err());
+        tool.annotations = Some(ToolAnnotations::new().read_only(true));
+        assert!(check_tool(&tool).is_ok());
+        // A `command` argument only means something to the text editor
+        let unannotated = Tool::new("run", "", object!({}));
+        let view = object!({ "command": "view", "path": "docs/guide.md" });
+        assert!(any_tool
+            .check_call(
+                "docs__run",
+                Some(&unannotated),
+                Some(&view),
+                Some(dir),
+                false
+            )
+            .is_err());
+
+        assert!(!grant.allows_extension("computercontroller"));
+        assert!(grant.allows_extension("developer"));
+
+        // A grandchild gets at most what its parent was granted
+        let narrowed = grant.narrow(&CapabilityGrant {
+            tools: Some(vec!["developer".to_string()]),
+            read_paths: Some(vec![PathBuf::from("/")]),
+        });
+        assert_eq!(
+            narrowed.tools,
+            Some(vec!["developer__text_editor".to_string()])
+        );
+        assert_eq!(narrowed.read_paths, Some(vec![PathBuf::from("/work/docs")]));
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_read_scope_follows_symlinks() {
+        let dir = tempfile::tempdir().unwrap();
+        let work = dir.path().join("work");
+        let docs = work.join("docs");
+        std::fs::create_dir_all(&docs).unwrap();
+        std::fs::write(work.join("secrets.env"), "").unwrap();
+        std::os::unix::fs::symlink(&work, docs.join("up")).unwrap();
+        let grant = CapabilityGrant {
+            tools: None,
+            read_paths: Some(vec![PathBuf::from("docs")]),
+        }
+        .resolved(&work);
+        let view = |path: &str| {
+            let arguments = object!({ "command": "view", "path": path });
+            grant.check_call(TEXT_EDITOR_TOOL, None, Some(&arguments), Some(&work), false)
+        };
+
+        assert!(view("docs/new.md").is_ok());
+        assert!(view("docs/up/secrets.env").is_err());
+        assert!(view("docs/up/docs/../secrets.env").is_err());
+    }
+}
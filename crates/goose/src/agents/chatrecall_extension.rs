@@ -33,6 +33,54 @@ struct ChatRecallParams {
     before_date: Option<String>,
 }
 
+/// Orders keyword hits by how close each session's matches are to the
+/// query. Keyword search still decides what matches.
+#[cfg(feature = "memory")]
+async fn rank_by_similarity(
+    query: &str,
+    mut results: crate::session::ChatRecallResults,
+) -> crate::session::ChatRecallResults {
+    use crate::memory::embedding_service::EmbeddingService;
+    use crate::memory::embeddings::EmbeddingProvider;
+
+    const MAX_CHARS: usize = 2000;
+    let service = EmbeddingService::shared().await;
+    // Hash vectors carry no meaning beyond the keywords that already matched
+    if results.results.len() < 2 || service.name() == "hash" {
+        return results;
+    }
+    let texts: Vec<String> = results
+        .results
+        .iter()
+        .map(|r| {
+            let joined: Vec<&str> = r.messages.iter().map(|m| m.content.as_str()).collect();
+            joined.join("\n").chars().take(MAX_CHARS).collect()
+        })
+        .collect();
+    let inputs: Vec<&str> = std::iter::once(query)
+        .chain(texts.iter().map(String::as_str))
+        .collect();
+    let vectors = match service.embed_batch(&inputs).await {
+        Ok(vectors) => vectors,
+        Err(e) => {
+            tracing::debug!("Chat recall ranking skipped: {}", e);
+            return results;
+        }
+    };
+
+    let Some((query_vector, hits)) = vectors.split_first() else {
+        return results;
+    };
+    let mut scored: Vec<_> = hits
+        .iter()
+        .map(|v| crate::memory::semantic_store::cosine_similarity(query_vector, v))
+        .zip(results.results.drain(..))
+        .collect();
+    scored.sort_by(|a, b| b.0.total_cmp(&a.0));
+    results.results = scored.into_iter().map(|(_, r)| r).collect();
+    results
+}
+
 pub struct ChatRecallClient {
     info: InitializeResult,
     context: PlatformExtensionContext,
@@ -200,6 +248,8 @@ impl ChatRecallClient {
                 .await
             {
                 Ok(results) => {
+                    #[cfg(feature = "memory")]
+                    let results = rank_by_similarity(&query, results).await;
                     let formatted_results = if results.total_matches == 0 {
                         format!("No results found for query: '{}'", query)
                     } else {
@@ -109,7 +109,7 @@ impl CodeExecutionClient {
             .await
             .ok()?;
         let mut cfgs = vec![];
-        for tool in tools {
+        for tool in tools.iter() {
             let full_name = tool.name.to_string();
             let (server_name, tool_name) = full_name.split_once("__")?;
             cfgs.push(CallbackConfig {
@@ -0,0 +1,499 @@
+//! Slash command registry.
+//!
+//! Every command declares its arguments, so input is parsed and validated
+//! before a handler runs, and `/help` is generated from the same definitions.
+//! Users can register their own commands under the `custom_commands` config
+//! key. A custom command runs a sequence of built-in commands, with `{name}`
+//! placeholders filled in from its arguments:
+//!
+//! ```yaml
+//! custom_commands:
+//!   - name: ship
+//!     description: Structured core with a spending cap
+//!     args:
+//!       - name: budget
+//!         kind: { type: number }
+//!         required: true
+//!     run: ["/core structured", "/budget {budget}"]
+//! ```
+
+use std::collections::HashMap;
+
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use tracing::warn;
+use utoipa::ToSchema;
+
+use crate::config::Config;
+
+const CUSTOM_COMMANDS_CONFIG_KEY: &str = "custom_commands";
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize, ToSchema)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum ArgType {
+    #[default]
+    String,
+    Integer,
+    Number,
+    Boolean,
+    Choice {
+        values: Vec<String>,
+    },
+    /// The rest of the input, verbatim. Only valid as the last argument.
+    Text,
+}
+
+impl std::fmt::Display for ArgType {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ArgType::String => write!(f, "string"),
+            ArgType::Integer => write!(f, "integer"),
+            ArgType::Number => write!(f, "number"),
+            ArgType::Boolean => write!(f, "true|false"),
+            ArgType::Choice { values } => write!(f, "{}", values.join("|")),
+            ArgType::Text => write!(f, "text"),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, ToSchema)]
+pub struct ArgSpec {
+    pub name: String,
+    #[serde(default)]
+    pub kind: ArgType,
+    #[serde(default)]
+    pub required: bool,
+    #[serde(default)]
+    pub description: String,
+}
+
+impl ArgSpec {
+    pub fn required(name: &str, kind: ArgType, description: &str) -> Self {
+        Self {
+            name: name.to_string(),
+            kind,
+            required: true,
+            description: description.to_string(),
+        }
+    }
+
+    pub fn optional(name: &str, kind: ArgType, description: &str) -> Self {
+        Self {
+            required: false,
+            ..Self::required(name, kind, description)
+        }
+    }
+
+    fn parse_value(&self, raw: &str) -> Result<Value, CommandError> {
+        let invalid = || CommandError::InvalidValue {
+            name: self.name.clone(),
+            value: raw.to_string(),
+            expected: self.kind.to_string(),
+        };
+        match &self.kind {
+            ArgType::String | ArgType::Text => Ok(Value::String(raw.to_string())),
+            ArgType::Integer => raw.parse::<i64>().map(Value::from).map_err(|_| invalid()),
+            ArgType::Number => raw
+                .parse::<f64>()
+                .ok()
+                .filter(|n| n.is_finite())
+                .map(Value::from)
+                .ok_or_else(invalid),
+            ArgType::Boolean => raw.parse::<bool>().map(Value::Bool).map_err(|_| invalid()),
+            ArgType::Choice { values } => values
+                .iter()
+                .find(|value| value.eq_ignore_ascii_case(raw))
+                .map(|value| Value::String(value.clone()))
+                .ok_or_else(invalid),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, thiserror::Error)]
+pub enum CommandError {
+    #[error("Unknown argument '{0}'")]
+    UnknownArgument(String),
+    #[error("Missing required argument '{0}'")]
+    MissingArgument(String),
+    #[error("Invalid value '{value}' for '{name}', expected {expected}")]
+    InvalidValue {
+        name: String,
+        value: String,
+        expected: String,
+    },
+    #[error("Too many arguments")]
+    TooManyArguments,
+    #[error("Unterminated quote")]
+    UnterminatedQuote,
+}
+
+/// Validated arguments keyed by name.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct CommandArgs(HashMap<String, Value>);
+
+impl CommandArgs {
+    pub fn get(&self, name: &str) -> Option<&Value> {
+        self.0.get(name)
+    }
+
+    pub fn str(&self, name: &str) -> Option<&str> {
+        self.get(name).and_then(Value::as_str)
+    }
+
+    pub fn f64(&self, name: &str) -> Option<f64> {
+        self.get(name).and_then(Value::as_f64)
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct CommandSpec {
+    pub name: String,
+    #[serde(default)]
+    pub aliases: Vec<String>,
+    pub description: String,
+    #[serde(default)]
+    pub args: Vec<ArgSpec>,
+    /// Built-in commands a custom command runs, in order. Empty for built-ins.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub run: Vec<String>,
+}
+
+impl CommandSpec {
+    pub fn builtin(name: &str, description: &str, args: Vec<ArgSpec>) -> Self {
+        Self {
+            name: name.to_string(),
+            aliases: Vec::new(),
+            description: description.to_string(),
+            args,
+            run: Vec::new(),
+        }
+    }
+
+    pub fn with_aliases(mut self, aliases: &[&str]) -> Self {
+        self.aliases = aliases.iter().map(|alias| alias.to_string()).collect();
+        self
+    }
+
+    pub fn is_custom(&self) -> bool {
+        !self.run.is_empty()
+    }
+
+    fn matches(&self, command: &str) -> bool {
+        self.name == command || self.aliases.iter().any(|alias| alias == command)
+    }
+
+    pub fn usage(&self) -> String {
+        let mut usage = format!("/{}", self.name);
+        for arg in &self.args {
+            let name = match arg.kind {
+                ArgType::Text => format!("{}...", arg.name),
+                _ => arg.name.clone(),
+            };
+            if arg.required {
+                usage.push_str(&format!(" <{}>", name));
+            } else {
+                usage.push_str(&format!(" [{}]", name));
+            }
+        }
+        usage
+    }
+
+    /// Parses `input` against the declared arguments. Arguments are given
+    /// positionally or as `name=value`; values may be double-quoted.
+    pub fn parse_args(&self, input: &str) -> Result<CommandArgs, CommandError> {
+        let mut values = HashMap::new();
+        let text_arg = self.args.iter().find(|arg| arg.kind == ArgType::Text);
+        let mut rest = input.trim();
+
+        while !rest.is_empty() {
+            // Free text keeps its quotes, so only tokenize what is consumed
+            let tokenized = next_token(rest);
+            let token = tokenized.as_ref().map(|(token, _)| token.as_str());
+            let named =
+                token
+                    .ok()
+                    .and_then(|token| token.split_once('='))
+                    .and_then(|(name, value)| {
+                        self.args
+                            .iter()
+                            .find(|arg| arg.name == name && arg.kind != ArgType::Text)
+                            .map(|arg| (arg, value.to_string()))
+                    });
+            let next_positional = self
+                .args
+                .iter()
+                .find(|arg| arg.kind != ArgType::Text && !values.contains_key(&arg.name));
+            if let (None, None, Some(text_arg)) = (&named, next_positional, text_arg) {
+                values.insert(text_arg.name.clone(), Value::String(rest.to_string()));
+                break;
+            }
+            let (token, remainder) = tokenized?;
+            let (arg, raw) = match (named, next_positional) {
+                (Some(named), _) => named,
+                (None, Some(arg)) => (arg, token),
+                (None, None) => {
+                    return Err(match token.split_once('=') {
+                        Some((name, _)) => CommandError::UnknownArgument(name.to_string()),
+                        None => CommandError::TooManyArguments,
+                    });
+                }
+            };
+            values.insert(arg.name.clone(), arg.parse_value(&raw)?);
+            rest = remainder.trim_start();
+        }
+
+        if let Some(missing) = self
+            .args
+            .iter()
+            .find(|arg| arg.required && !values.contains_key(&arg.name))
+        {
+            return Err(CommandError::MissingArgument(missing.name.clone()));
+        }
+        Ok(CommandArgs(values))
+    }
+
+    /// The commands to run for a custom command, with `{name}` placeholders
+    /// replaced by argument values. Unset optional arguments expand to "".
+    pub fn expand(&self, args: &CommandArgs) -> Vec<String> {
+        self.run
+            .iter()
+            .map(|step| {
+                let mut step = step.clone();
+                for arg in &self.args {
+                    let value = match args.get(&arg.name) {
+                        Some(Value::String(s)) => s.clone(),
+                        Some(value) => value.to_string(),
+                        None => String::new(),
+                    };
+                    step = step.replace(&format!("{{{}}}", arg.name), &value);
+                }
+                step.trim().to_string()
+            })
+            .collect()
+    }
+}
+
+/// Splits the first whitespace-delimited token off `input`, honouring double
+/// quotes around the token or around the value of a `name=value` token.
+fn next_token(input: &str) -> Result<(String, &str), CommandError> {
+    let mut token = String::new();
+    let mut in_quotes = false;
+    for (i, c) in input.char_indices() {
+        match c {
+            '"' => in_quotes = !in_quotes,
+            c if c.is_whitespace() && !in_quotes => return Ok((token, input.split_at(i).1)),
+            c => token.push(c),
+        }
+    }
+    if in_quotes {
+        return Err(CommandError::UnterminatedQuote);
+    }
+    Ok((token, ""))
+}
+
+/// Splits `/name args` into the lowercased command name and its arguments.
+pub fn split_command(input: &str) -> Option<(String, &str)> {
+    let command = input.trim().strip_prefix('/')?;
+    let (name, args) = command
+        .split_once(char::is_whitespace)
+        .unwrap_or((command, ""));
+    Some((name.to_lowercase(), args.trim()))
+}
+
+pub struct CommandRegistry {
+    commands: Vec<CommandSpec>,
+}
+
+impl CommandRegistry {
+    /// Built-in commands plus the user's custom commands. Custom commands that
+    /// shadow a built-in or run anything but built-in commands are skipped.
+    pub fn load(builtins: Vec<CommandSpec>) -> Self {
+        let custom: Vec<CommandSpec> = Config::global()
+            .get_param(CUSTOM_COMMANDS_CONFIG_KEY)
+            .unwrap_or_default();
+        Self::with_custom(builtins, custom)
+    }
+
+    pub fn with_custom(builtins: Vec<CommandSpec>, custom: Vec<CommandSpec>) -> Self {
+        let mut registry = Self { commands: builtins };
+        for command in custom {
+            match registry.validate_custom(&command) {
+                Ok(()) => registry.commands.push(command),
+                Err(reason) => warn!("Skipping custom command /{}: {}", command.name, reason),
+            }
+        }
+        registry
+    }
+
+    fn validate_custom(&self, command: &CommandSpec) -> Result<(), String> {
+        if command.name.is_empty() || command.name.contains(char::is_whitespace) {
+            return Err("command names must be a single word".to_string());
+        }
+        if command.run.is_empty() {
+            return Err("`run` must list at least one command".to_string());
+        }
+        if std::iter::once(&command.name)
+            .chain(&command.aliases)
+            .any(|name| self.get(name).is_some())
+        {
+            return Err("name is already registered".to_string());
+        }
+        if let Some(pos) = command
+            .args
+            .iter()
+            .position(|arg| arg.kind == ArgType::Text)
+        {
+            if pos != command.args.len() - 1 {
+                return Err("a text argument must come last".to_string());
+            }
+        }
+        for step in &command.run {
+            let name = split_command(step).map(|(name, _)| name);
+            match name.as_deref().and_then(|name| self.get(name)) {
+                Some(spec) if !spec.is_custom() => {}
+                _ => return Err(format!("'{}' is not a built-in command", step)),
+            }
+        }
+        Ok(())
+    }
+
+    pub fn get(&self, command: &str) -> Option<&CommandSpec> {
+        self.commands.iter().find(|spec| spec.matches(command))
+    }
+
+    pub fn commands(&self) -> &[CommandSpec] {
+        &self.commands
+    }
+
+    pub fn into_commands(self) -> Vec<CommandSpec> {
+        self.commands
+    }
+
+    /// Markdown listing of every command, generated from the definitions.
+    pub fn help(&self) -> String {
+        let mut help = String::from("# Available Commands\n\n");
+        let (custom, builtin): (Vec<&CommandSpec>, Vec<&CommandSpec>) =
+            self.commands.iter().partition(|spec| spec.is_custom());
+        for (title, specs) in [("Built-in", builtin), ("Custom", custom)] {
+            if specs.is_empty() {
+                continue;
+            }
+            help.push_str(&format!("## {} Commands\n\n", title));
+            for spec in specs {
+                help.push_str(&format!("- `{}` - {}\n", spec.usage(), spec.description));
+                for arg in spec.args.iter().filter(|arg| !arg.description.is_empty()) {
+                    help.push_str(&format!(
+                        "  - `{}` ({}): {}\n",
+                        arg.name, arg.kind, arg.description
+                    ));
+                }
+            }
+            help.push('\n');
+        }
+        help
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn budget_command() -> CommandSpec {
+        CommandSpec::builtin(
+            "budget",
+            "Set a spending limit",
+            vec![ArgSpec::optional("limit", ArgType::Number, "Limit in USD")],
+        )
+    }
+
+    fn core_command() -> CommandSpec {
+        CommandSpec::builtin(
+            "core",
+            "Switch the execution core",
+            vec![ArgSpec::required(
+                "mode",
+                ArgType::Choice {
+                    values: vec!["freeform".to_string(), "structured".to_string()],
+                },
+                "",
+            )],
+        )
+    }
+
+    #[test]
+    fn test_parse_args_validates_against_schema() {
+        let spec = CommandSpec::builtin(
+            "deploy",
+            "Deploy",
+            vec![
+                ArgSpec::required("env", ArgType::String, ""),
+                ArgSpec::optional("replicas", ArgType::Integer, ""),
+                ArgSpec::optional("note", ArgType::Text, ""),
+            ],
+        );
+
+        let args = spec
+            .parse_args("replicas=3 staging ship it \"now\"")
+            .unwrap();
+        assert_eq!(args.str("env"), Some("staging"));
+        assert_eq!(args.get("replicas"), Some(&Value::from(3)));
+        assert_eq!(args.str("note"), Some("ship it \"now\""));
+
+        assert_eq!(
+            spec.parse_args("").unwrap_err(),
+            CommandError::MissingArgument("env".to_string())
+        );
+        assert!(matches!(
+            spec.parse_args("prod replicas=many"),
+            Err(CommandError::InvalidValue { .. })
+        ));
+        assert_eq!(
+            core_command().parse_args("STRUCTURED").unwrap().str("mode"),
+            Some("structured")
+        );
+        assert_eq!(
+            budget_command().parse_args("5 6").unwrap_err(),
+            CommandError::TooManyArguments
+        );
+    }
+
+    #[test]
+    fn test_custom_commands_expand_to_builtins() {
+        let custom = vec![
+            CommandSpec {
+                name: "ship".to_string(),
+                aliases: vec![],
+                description: "Structured core with a budget".to_string(),
+                args: vec![ArgSpec::required("budget", ArgType::Number, "")],
+                run: vec![
+                    "/core structured".to_string(),
+                    "/budget {budget}".to_string(),
+                ],
+            },
+            CommandSpec {
+                name: "core".to_string(),
+                aliases: vec![],
+                description: "Shadows a built-in".to_string(),
+                args: vec![],
+                run: vec!["/budget 1".to_string()],
+            },
+            CommandSpec {
+                name: "loop".to_string(),
+                aliases: vec![],
+                description: "Runs another custom command".to_string(),
+                args: vec![],
+                run: vec!["/ship 1".to_string()],
+            },
+        ];
+        let registry = CommandRegistry::with_custom(vec![budget_command(), core_command()], custom);
+
+        assert_eq!(registry.commands().len(), 3);
+        let ship = registry.get("ship").unwrap();
+        let args = ship.parse_args("2.5").unwrap();
+        assert_eq!(ship.expand(&args), vec!["/core structured", "/budget 2.5"]);
+
+        let help = registry.help();
+        assert!(help.contains("`/budget [limit]`"));
+        assert!(help.contains("## Custom Commands\n\n- `/ship <budget>`"));
+    }
+}
@@ -0,0 +1,387 @@
+//! Commit messages and PR descriptions written from the staged diff.
+//!
+//! The model says what the change does in one line; everything else comes
+//! from git. The staged files are listed with their line counts, the
+//! conventional-commit type and scope are inferred from the branch name and
+//! the paths touched, and issue ids in the branch name (`PROJ-123`, or a
+//! number as in `fix/482-crash`) become references. The result is rendered
+//! through a template, `GOOSE_COMMIT_TEMPLATE` or `GOOSE_PR_TEMPLATE`, with
+//! the placeholders `{type}`, `{scope}`, `{summary}`, `{files}` and
+//! `{issues}`, and the header is checked against the conventional-commit
+//! rules before it is returned.
+
+use crate::config::Config;
+use crate::mcp_utils::ToolResult;
+use once_cell::sync::Lazy;
+use regex::Regex;
+use rmcp::model::{Content, ErrorCode, ErrorData};
+use serde_json::Value;
+use std::path::Path;
+use tokio::process::Command;
+
+pub const COMMIT_TEMPLATE_KEY: &str = "GOOSE_COMMIT_TEMPLATE";
+pub const PR_TEMPLATE_KEY: &str = "GOOSE_PR_TEMPLATE";
+
+const DEFAULT_COMMIT_TEMPLATE: &str = "{type}{scope}: {summary}\n\n{files}\n\n{issues}";
+const DEFAULT_PR_TEMPLATE: &str =
+    "## Summary\n\n{summary}\n\n## Changes\n\n{files}\n\n## Related issues\n\n{issues}";
+const MAX_HEADER_LEN: usize = 72;
+const COMMIT_TYPES: &[&str] = &[
+    "feat", "fix", "docs", "style", "refactor", "perf", "test", "build", "ci", "chore", "revert",
+];
+/// Folders whose children name the component a change belongs to
+const COMPONENT_ROOTS: &[&str] = &["crates", "packages", "apps", "services", "libs"];
+
+static JIRA_ID: Lazy<Regex> = Lazy::new(|| Regex::new(r"\b([A-Z][A-Z0-9]+-\d+)\b").unwrap());
+static ISSUE_NUMBER: Lazy<Regex> =
+    Lazy::new(|| Regex::new(r"(?:^|[/_-])#?(\d{1,7})(?:[/_-]|$)").unwrap());
+static HEADER: Lazy<Regex> =
+    Lazy::new(|| Regex::new(r"^([a-z]+)(\([\w./-]+\))?(!)?: (\S.*)$").unwrap());
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct FileChange {
+    /// A, M, D or R, as git reports it
+    pub status: char,
+    pub path: String,
+    pub added: usize,
+    pub removed: usize,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct StagedDiff {
+    pub branch: String,
+    pub files: Vec<FileChange>,
+}
+
+pub(super) async fn git(dir: &Path, args: &[&str]) -> anyhow::Result<String> {
+    let output = Command::new("git")
+        .args(args)
+        .current_dir(dir)
+        .output()
+        .await?;
+    if !output.status.success() {
+        anyhow::bail!("{}", String::from_utf8_lossy(&output.stderr).trim());
+    }
+    Ok(String::from_utf8_lossy(&output.stdout).to_string())
+}
+
+impl StagedDiff {
+    pub async fn read(dir: &Path) -> anyhow::Result<Self> {
+        let branch = git(dir, &["rev-parse", "--abbrev-ref", "HEAD"]).await?;
+        let statuses = git(dir, &["diff", "--cached", "--name-status", "-M"]).await?;
+        let numstat = git(dir, &["diff", "--cached", "--numstat", "-M"]).await?;
+        Ok(Self::parse(branch.trim(), &statuses, &numstat))
+    }
+
+    fn parse(branch: &str, statuses: &str, numstat: &str) -> Self {
+        let counts: Vec<(usize, usize)> = numstat
+            .lines()
+            .map(|line| {
+                let mut fields = line.split('\t');
+                let mut count = || fields.next().and_then(|n| n.parse().ok()).unwrap_or(0);
+                (count(), count())
+            })
+            .collect();
+        let files = statuses
+            .lines()
+            .filter(|line| !line.is_empty())
+            .zip(counts.into_iter().chain(std::iter::repeat((0, 0))))
+            .filter_map(|(line, (added, removed))| {
+                let fields: Vec<&str> = line.split('\t').collect();
+                Some(FileChange {
+                    status: fields.first()?.chars().next()?,
+                    path: fields.last()?.to_string(),
+                    added,
+                    removed,
+                })
+            })
+            .collect();
+        Self {
+            branch: branch.to_string(),
+            files,
+        }
+    }
+
+    /// Issue ids named in the branch, e.g. `PROJ-123` or `#482`.
+    pub fn issue_ids(&self) -> Vec<String> {
+        let mut ids: Vec<String> = JIRA_ID
+            .captures_iter(&self.branch)
+            .map(|c| c[1].to_string())
+            .collect();
+        if ids.is_empty() {
+            ids = ISSUE_NUMBER
+                .captures_iter(&self.branch)
+                .map(|c| format!("#{}", &c[1]))
+                .collect();
+        }
+        ids
+    }
+
+    /// Conventional-commit type: the branch prefix when it is one, otherwise
+    /// what the touched files suggest.
+    pub fn inferred_type(&self) -> &'static str {
+        if let Some(prefix) = self.branch.split(['/', '-']).next() {
+            if let Some(t) = COMMIT_TYPES.iter().find(|t| **t == prefix) {
+                return *t;
+            }
+            if prefix == "feature" {
+                return "feat";
+            }
+            if prefix == "bugfix" || prefix == "hotfix" {
+                return "fix";
+            }
+        }
+        let all = |pred: fn(&str) -> bool| {
+            !self.files.is_empty() && self.files.iter().all(|f| pred(&f.path))
+        };
+        if all(|p| p.ends_with(".md") || p.starts_with("docs/")) {
+            "docs"
+        } else if all(|p| p.contains("test")) {
+            "test"
+        } else if all(|p| p.starts_with(".github/")) {
+            "ci"
+        } else if self.files.iter().any(|f| f.status == 'A') {
+            "feat"
+        } else {
+            "fix"
+        }
+    }
+
+    /// The component every touched file belongs to, if they share one.
+    pub fn inferred_scope(&self) -> Option<String> {
+        let component = |path: &str| {
+            let parts: Vec<&str> = path.split('/').collect();
+            match parts.as_slice() {
+                [root, name, _, ..] if COMPONENT_ROOTS.contains(root) => Some(name.to_string()),
+                [dir, _, ..] => Some(dir.to_string()),
+                _ => None,
+            }
+        };
+        let first = component(&self.files.first()?.path)?;
+        self.files
+            .iter()
+            .all(|f| component(&f.path).as_deref() == Some(first.as_str()))
+            .then_some(first)
+    }
+
+    fn file_list(&self) -> String {
+        self.files
+            .iter()
+            .map(|f| {
+                let verb = match f.status {
+                    'A' => "add",
+                    'D' => "remove",
+                    'R' => "rename to",
+                    _ => "update",
+                };
+                format!("- {} {} (+{} -{})", verb, f.path, f.added, f.removed)
+            })
+            .collect::<Vec<_>>()
+            .join("\n")
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Output {
+    Commit,
+    PullRequest,
+}
+
+#[derive(Debug, Clone)]
+pub struct MessageRequest {
+    pub summary: String,
+    pub commit_type: Option<String>,
+    pub scope: Option<String>,
+    pub breaking: bool,
+    pub output: Output,
+}
+
+/// Problems with a commit header, empty when it follows the conventions.
+pub fn header_problems(header: &str) -> Vec<String> {
+    let mut problems = Vec::new();
+    if header.chars().count() > MAX_HEADER_LEN {
+        problems.push(format!(
+            "header is longer than {} characters",
+            MAX_HEADER_LEN
+        ));
+    }
+    match HEADER.captures(header) {
+        None => problems.push("header is not 'type(scope): summary'".to_string()),
+        Some(c) => {
+            if !COMMIT_TYPES.contains(&&c[1]) {
+                problems.push(format!("'{}' is not a conventional commit type", &c[1]));
+            }
+            if c[4].ends_with('.') {
+                problems.push("summary ends with a period".to_string());
+            }
+        }
+    }
+    problems
+}
+
+/// Renders the commit message or PR description for `diff`.
+pub fn render(diff: &StagedDiff, request: &MessageRequest, template: &str) -> String {
+    let commit_type = request
+        .commit_type
+        .clone()
+        .unwrap_or_else(|| diff.inferred_type().to_string());
+    let scope = request
+        .scope
+        .clone()
+        .or_else(|| diff.inferred_scope())
+        .map(|s| format!("({})", s))
+        .unwrap_or_default();
+    let scope = if request.breaking {
+        format!("{}!", scope)
+    } else {
+        scope
+    };
+    let issues = diff.issue_ids();
+    let issues = match (request.output, issues.is_empty()) {
+        (_, true) => String::new(),
+        (Output::Commit, false) => format!("Refs: {}", issues.join(", ")),
+        (Output::PullRequest, false) => issues
+            .iter()
+            .map(|id| format!("- {}", id))
+            .collect::<Vec<_>>()
+            .join("\n"),
+    };
+    let mut rendered = template
+        .replace("{type}", &commit_type)
+        .replace("{scope}", &scope)
+        .replace("{summary}", request.summary.trim())
+        .replace("{files}", &diff.file_list())
+        .replace("{issues}", &issues);
+    // Empty placeholders leave runs of blank lines behind
+    while rendered.contains("\n\n\n") {
+        rendered = rendered.replace("\n\n\n", "\n\n");
+    }
+    format!("{}\n", rendered.trim())
+}
+
+fn invalid(message: String) -> ErrorData {
+    ErrorData::new(ErrorCode::INVALID_PARAMS, message, None)
+}
+
+/// Handles the `platform__write_commit_message` tool.
+pub async fn handle_tool_call(working_dir: &Path, arguments: Value) -> ToolResult<Vec<Content>> {
+    let text = |key: &str| {
+        arguments
+            .get(key)
+            .and_then(Value::as_str)
+            .filter(|s| !s.trim().is_empty())
+            .map(String::from)
+    };
+    let request = MessageRequest {
+        summary: text("summary").ok_or_else(|| invalid("Missing 'summary' parameter".into()))?,
+        commit_type: text("type"),
+        scope: text("scope"),
+        breaking: arguments
+            .get("breaking")
+            .and_then(Value::as_bool)
+            .unwrap_or(false),
+        output: match text("output").as_deref() {
+            Some("pull_request") => Output::PullRequest,
+            _ => Output::Commit,
+        },
+    };
+
+    let diff = StagedDiff::read(working_dir)
+        .await
+        .map_err(|e| ErrorData::new(ErrorCode::INTERNAL_ERROR, e.to_string(), None))?;
+    if diff.files.is_empty() {
+        return Err(invalid(
+            "Nothing is staged; stage the changes with git add first".into(),
+        ));
+    }
+
+    let config = Config::global();
+    let message = match request.output {
+        Output::Commit => {
+            let template = config
+                .get_param::<String>(COMMIT_TEMPLATE_KEY)
+                .unwrap_or_else(|_| DEFAULT_COMMIT_TEMPLATE.to_string());
+            render(&diff, &request, &template)
+        }
+        Output::PullRequest => {
+            let template = config
+                .get_param::<String>(PR_TEMPLATE_KEY)
+                .unwrap_or_else(|_| DEFAULT_PR_TEMPLATE.to_string());
+            let commit = render(&diff, &request, DEFAULT_COMMIT_TEMPLATE);
+            let title = commit.lines().next().unwrap_or_default().to_string();
+            format!("{}\n\n{}", title, render(&diff, &request, &template))
+        }
+    };
+
+    let header = message.lines().next().unwrap_or_default();
+    let problems = header_problems(header);
+    if !problems.is_empty() {
+        return Err(invalid(format!(
+            "The generated header '{}' does not follow the commit conventions: {}. Shorten or reword the summary.",
+            header,
+            problems.join("; ")
+        )));
+    }
+    Ok(vec![Content::text(message)])
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn diff(branch: &str) -> StagedDiff {
+        StagedDiff::parse(
+            branch,
+            "M\tcrates/goose-server/src/routes/bus.rs\nA\tcrates/goose-server/src/shell.rs\n",
+            "12\t3\tcrates/goose-server/src/routes/bus.rs\n80\t0\tcrates/goose-server/src/shell.rs\n",
+        )
+    }
+
+    #[test]
+    fn test_infers_type_scope_and_issues() {
+        let feature = diff("feature/GOOSE-42-shell-updates");
+        assert_eq!(feature.inferred_type(), "feat");
+        assert_eq!(feature.inferred_scope().as_deref(), Some("goose-server"));
+        assert_eq!(feature.issue_ids(), vec!["GOOSE-42"]);
+        assert_eq!(feature.files[1].added, 80);
+
+        let fix = diff("fix/482-crash-on-start");
+        assert_eq!(fix.inferred_type(), "fix");
+        assert_eq!(fix.issue_ids(), vec!["#482"]);
+
+        let docs = StagedDiff::parse("main", "M\tREADME.md\n", "4\t1\tREADME.md\n");
+        assert_eq!(docs.inferred_type(), "docs");
+        assert_eq!(docs.inferred_scope(), None);
+        assert!(docs.issue_ids().is_empty());
+    }
+
+    #[test]
+    fn test_render_and_validate() {
+        let request = MessageRequest {
+            summary: "stage shell updates".to_string(),
+            commit_type: None,
+            scope: None,
+            breaking: false,
+            output: Output::Commit,
+        };
+        let message = render(&diff("fix/482-crash"), &request, DEFAULT_COMMIT_TEMPLATE);
+        assert_eq!(
+            message,
+            "fix(goose-server): stage shell updates\n\n\
+             - update crates/goose-server/src/routes/bus.rs (+12 -3)\n\
+             - add crates/goose-server/src/shell.rs (+80 -0)\n\n\
+             Refs: #482\n"
+        );
+        assert!(header_problems(message.lines().next().unwrap()).is_empty());
+
+        let no_issues = render(&diff("main"), &request, DEFAULT_COMMIT_TEMPLATE);
+        assert!(no_issues.ends_with("(+80 -0)\n"));
+
+        assert_eq!(header_problems("Fixed things.").len(), 1);
+        assert_eq!(
+            header_problems("wip(core): tidy up.").len(),
+            2,
+            "unknown type and trailing period"
+        );
+    }
+}
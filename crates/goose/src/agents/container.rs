@@ -82,6 +82,10 @@ pub struct ContainerConfig {
     pub volumes: Vec<(String, String)>,
     /// Environment variables as `(key, value)` pairs.
     pub env_vars: Vec<(String, String)>,
+    /// Published ports in `docker -p` form (e.g. `"127.0.0.1:3000:3000"`).
+    pub ports: Vec<String>,
+    /// Further `docker create` arguments, passed before the image.
+    pub extra_args: Vec<String>,
     /// Default timeout in seconds for container operations.
     pub timeout_secs: u64,
     /// Isolation backend (default: Docker).
@@ -98,6 +102,8 @@ impl Default for ContainerConfig {
             network: "none".to_string(),
             volumes: Vec::new(),
             env_vars: Vec::new(),
+            ports: Vec::new(),
+            extra_args: Vec::new(),
             timeout_secs: 30,
             backend: ContainerBackend::default(),
         }
@@ -279,6 +285,13 @@ impl Container {
             cmd.args(["-e", &format!("{}={}", key, value)]);
         }
 
+        // Published ports
+        for port in &config.ports {
+            cmd.args(["-p", port]);
+        }
+
+        cmd.args(&config.extra_args);
+
         // Image and keep-alive entrypoint
         cmd.arg(&config.image);
         cmd.args(["tail", "-f", "/dev/null"]);
@@ -668,6 +681,7 @@ mod tests {
         assert_eq!(cfg.cpu_limit, Some(1.0));
         assert!(cfg.volumes.is_empty());
         assert!(cfg.env_vars.is_empty());
+        assert!(cfg.ports.is_empty());
         assert_eq!(cfg.timeout_secs, 30);
         assert_eq!(cfg.backend, ContainerBackend::Docker);
     }
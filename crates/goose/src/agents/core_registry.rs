@@ -0,0 +1,173 @@
+//! Per-core settings, changeable at runtime.
+//!
+//! `GOOSE_CORE_SETTINGS` holds one record per execution core:
+//!
+//! ```yaml
+//! GOOSE_CORE_SETTINGS:
+//!   structured:
+//!     enabled: false
+//!   freeform:
+//!     max_concurrent_turns: 2
+//!     model: gpt-4o-mini
+//!     budget_usd: 5.0
+//! ```
+//!
+//! A disabled core cannot be switched to and is never picked by the core
+//! selector. `max_concurrent_turns` caps how many sessions on the core run a
+//! model turn at once across the process; further turns wait for a slot.
+//! `model` and `budget_usd` are applied to a session when it switches to the
+//! core. Records are changed with `/core config` or the goose-server
+//! `/agent/cores` routes.
+
+use super::ExecutionMode;
+use crate::config::{Config, ConfigError};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::sync::{LazyLock, Mutex};
+use tokio::sync::Notify;
+use utoipa::ToSchema;
+
+pub const CORE_SETTINGS_KEY: &str = "GOOSE_CORE_SETTINGS";
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, ToSchema)]
+#[serde(default)]
+pub struct CoreSettings {
+    pub enabled: bool,
+    /// Sessions on this core that may run a model turn at once
+    pub max_concurrent_turns: Option<usize>,
+    /// Model used by sessions that switch to this core
+    pub model: Option<String>,
+    /// Session budget in USD set when switching to this core
+    pub budget_usd: Option<f64>,
+}
+
+impl Default for CoreSettings {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            max_concurrent_turns: None,
+            model: None,
+            budget_usd: None,
+        }
+    }
+}
+
+impl CoreSettings {
+    /// Sets one field from a `key=value` pair; `none` clears an optional one.
+    pub fn apply(&mut self, key: &str, value: &str) -> Result<(), String> {
+        let cleared = value.eq_ignore_ascii_case("none");
+        let invalid = || format!("Invalid value '{}' for {}", value, key);
+        match key {
+            "enabled" => self.enabled = value.parse().map_err(|_| invalid())?,
+            "max_concurrent_turns" if cleared => self.max_concurrent_turns = None,
+            "max_concurrent_turns" => {
+                self.max_concurrent_turns = Some(value.parse().map_err(|_| invalid())?)
+            }
+            "model" => self.model = (!cleared).then(|| value.to_string()),
+            "budget_usd" if cleared => self.budget_usd = None,
+            "budget_usd" => self.budget_usd = Some(value.parse().map_err(|_| invalid())?),
+            _ => {
+                return Err(format!(
+                    "Unknown setting '{}'. Use enabled, max_concurrent_turns, model or budget_usd",
+                    key
+                ))
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Every core's settings, defaults included. An invalid table is ignored
+/// with a warning.
+pub fn all_settings() -> HashMap<ExecutionMode, CoreSettings> {
+    let mut stored = match Config::global().get_param(CORE_SETTINGS_KEY) {
+        Ok(settings) => settings,
+        Err(ConfigError::NotFound(_)) => HashMap::new(),
+        Err(e) => {
+            tracing::warn!("Ignoring invalid {}: {}", CORE_SETTINGS_KEY, e);
+            HashMap::new()
+        }
+    };
+    for core in [ExecutionMode::Freeform, ExecutionMode::Structured] {
+        stored.entry(core).or_default();
+    }
+    stored
+}
+
+pub fn settings(core: ExecutionMode) -> CoreSettings {
+    all_settings().remove(&core).unwrap_or_default()
+}
+
+pub fn is_enabled(core: ExecutionMode) -> bool {
+    settings(core).enabled
+}
+
+pub fn set_settings(core: ExecutionMode, settings: CoreSettings) -> Result<(), ConfigError> {
+    let mut all = all_settings();
+    all.insert(core, settings);
+    Config::global().set_param(CORE_SETTINGS_KEY, all)?;
+    // A raised limit may let waiting turns through
+    TURN_SLOTS.released.notify_waiters();
+    Ok(())
+}
+
+#[derive(Default)]
+struct TurnSlots {
+    active: Mutex<HashMap<ExecutionMode, usize>>,
+    released: Notify,
+}
+
+static TURN_SLOTS: LazyLock<TurnSlots> = LazyLock::new(TurnSlots::default);
+
+/// A model turn running on a core; frees its slot when dropped.
+pub struct CoreTurnSlot {
+    core: ExecutionMode,
+}
+
+impl Drop for CoreTurnSlot {
+    fn drop(&mut self) {
+        if let Some(count) = TURN_SLOTS.active.lock().unwrap().get_mut(&self.core) {
+            *count = count.saturating_sub(1);
+        }
+        TURN_SLOTS.released.notify_waiters();
+    }
+}
+
+/// Waits until `core` is below its `max_concurrent_turns`.
+pub async fn acquire_turn_slot(core: ExecutionMode) -> CoreTurnSlot {
+    loop {
+        let released = TURN_SLOTS.released.notified();
+        let limit = settings(core).max_concurrent_turns;
+        {
+            let mut active = TURN_SLOTS.active.lock().unwrap();
+            let count = active.entry(core).or_default();
+            if limit.is_none_or(|limit| *count < limit.max(1)) {
+                *count += 1;
+                return CoreTurnSlot { core };
+            }
+        }
+        released.await;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_apply_settings() {
+        let mut settings = CoreSettings::default();
+        settings.apply("enabled", "false").unwrap();
+        settings.apply("max_concurrent_turns", "2").unwrap();
+        settings.apply("model", "gpt-4o-mini").unwrap();
+        settings.apply("budget_usd", "5").unwrap();
+        assert!(!settings.enabled);
+        assert_eq!(settings.max_concurrent_turns, Some(2));
+        assert_eq!(settings.budget_usd, Some(5.0));
+
+        settings.apply("model", "none").unwrap();
+        assert_eq!(settings.model, None);
+        assert!(settings.apply("enabled", "maybe").is_err());
+        assert!(settings.apply("threads", "4").is_err());
+    }
+}
@@ -0,0 +1,353 @@
+//! Choosing an execution core for a task category from experience.
+//!
+//! Each core starts from a static suitability score per task category.
+//! Benchmark runs record how often each core passed tasks of each category,
+//! and the score shifts towards the observed success rate as outcomes
+//! accumulate: the static score counts as [`PRIOR_SAMPLES`] earlier
+//! outcomes, so a category with a handful of runs does not swing on one
+//! failure. Cores disabled in [`core_registry`](super::core_registry) are
+//! never picked. Users who disagree with the result pin a core per category
+//! in `GOOSE_CORE_PINS`:
+//!
+//! ```yaml
+//! GOOSE_CORE_PINS:
+//!   code_fix: structured
+//!   research: freeform
+//! ```
+
+use super::benchmark::TaskCategory;
+use super::benchmark_matrix::{CorpusTask, MatrixReport};
+use super::core_registry;
+use super::ExecutionMode;
+use crate::config::paths::Paths;
+use crate::config::{Config, ConfigError};
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use utoipa::ToSchema;
+
+pub const CORE_PINS_KEY: &str = "GOOSE_CORE_PINS";
+pub const EXPERIENCE_FILE: &str = "core_experience.json";
+/// Weight of the static suitability score, in outcomes
+pub const PRIOR_SAMPLES: f64 = 10.0;
+
+const CORES: &[ExecutionMode] = &[ExecutionMode::Freeform, ExecutionMode::Structured];
+pub const CATEGORIES: &[TaskCategory] = &[
+    TaskCategory::Coding,
+    TaskCategory::Reasoning,
+    TaskCategory::ToolUse,
+    TaskCategory::Planning,
+    TaskCategory::MultiStep,
+    TaskCategory::Memory,
+    TaskCategory::Safety,
+    TaskCategory::Performance,
+    TaskCategory::CodeFix,
+    TaskCategory::Refactor,
+    TaskCategory::Research,
+];
+
+/// How well a core suits a category before any outcomes are known. The
+/// structured core's test and fix loop pays off where the result can be
+/// checked; open-ended work goes better freeform.
+pub fn suitability_score(core: ExecutionMode, category: TaskCategory) -> f64 {
+    use TaskCategory::*;
+    match (core, category) {
+        (ExecutionMode::Structured, CodeFix) => 0.8,
+        (ExecutionMode::Structured, Coding | Refactor) => 0.7,
+        (ExecutionMode::Structured, MultiStep) => 0.65,
+        (ExecutionMode::Structured, Safety | Performance) => 0.6,
+        (ExecutionMode::Structured, _) => 0.4,
+        (ExecutionMode::Freeform, Research) => 0.8,
+        (ExecutionMode::Freeform, Reasoning) => 0.75,
+        (ExecutionMode::Freeform, Planning | ToolUse | Memory) => 0.7,
+        (ExecutionMode::Freeform, Coding) => 0.6,
+        (ExecutionMode::Freeform, CodeFix | Refactor | MultiStep) => 0.55,
+        (ExecutionMode::Freeform, Safety | Performance) => 0.5,
+    }
+}
+
+pub fn parse_category(name: &str) -> Option<TaskCategory> {
+    serde_json::from_value(serde_json::Value::String(name.to_lowercase())).ok()
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+struct ExperienceEntry {
+    core: ExecutionMode,
+    category: TaskCategory,
+    successes: u64,
+    attempts: u64,
+}
+
+/// Task outcomes per core and category, kept in the state directory.
+#[derive(Debug, Clone, Default)]
+pub struct CoreExperience {
+    path: PathBuf,
+    entries: Vec<ExperienceEntry>,
+}
+
+impl CoreExperience {
+    pub fn load(path: impl Into<PathBuf>) -> Self {
+        let path = path.into();
+        let entries = std::fs::read_to_string(&path)
+            .ok()
+            .and_then(|json| serde_json::from_str(&json).ok())
+            .unwrap_or_default();
+        Self { path, entries }
+    }
+
+    pub fn load_default() -> Self {
+        Self::load(Paths::in_state_dir(EXPERIENCE_FILE))
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    pub fn record(&mut self, core: ExecutionMode, category: TaskCategory, passed: bool) {
+        let index = match self
+            .entries
+            .iter()
+            .position(|e| e.core == core && e.category == category)
+        {
+            Some(index) => index,
+            None => {
+                self.entries.push(ExperienceEntry {
+                    core,
+                    category,
+                    successes: 0,
+                    attempts: 0,
+                });
+                self.entries.len() - 1
+            }
+        };
+        let entry = &mut self.entries[index];
+        entry.attempts += 1;
+        if passed {
+            entry.successes += 1;
+        }
+    }
+
+    /// Records the tasks a matrix run finished; tasks skipped by a cost cap
+    /// say nothing about the core.
+    pub fn record_matrix(&mut self, report: &MatrixReport, corpus: &[CorpusTask]) {
+        let categories: HashMap<&str, TaskCategory> = corpus
+            .iter()
+            .map(|task| (task.id.as_str(), task.category))
+            .collect();
+        for entry in &report.entries {
+            for result in &entry.report.results {
+                if result
+                    .error
+                    .as_deref()
+                    .is_some_and(|e| e.starts_with("skipped:"))
+                {
+                    continue;
+                }
+                if let Some(category) = categories.get(result.task_id.as_str()) {
+                    self.record(entry.entry.core, *category, result.passed);
+                }
+            }
+        }
+    }
+
+    /// Successes and attempts of `core` on `category`.
+    pub fn outcomes(&self, core: ExecutionMode, category: TaskCategory) -> (u64, u64) {
+        self.entries
+            .iter()
+            .find(|e| e.core == core && e.category == category)
+            .map_or((0, 0), |e| (e.successes, e.attempts))
+    }
+
+    pub fn save(&self) -> Result<()> {
+        if let Some(parent) = self.path.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        std::fs::write(&self.path, serde_json::to_string_pretty(&self.entries)?)?;
+        Ok(())
+    }
+}
+
+/// The configured pins. An invalid table is ignored with a warning.
+pub fn pins() -> HashMap<TaskCategory, ExecutionMode> {
+    match Config::global().get_param(CORE_PINS_KEY) {
+        Ok(pins) => pins,
+        Err(ConfigError::NotFound(_)) => HashMap::new(),
+        Err(e) => {
+            tracing::warn!("Ignoring invalid {}: {}", CORE_PINS_KEY, e);
+            HashMap::new()
+        }
+    }
+}
+
+/// Pins `core` for `category`, or removes the pin.
+pub fn set_pin(category: TaskCategory, core: Option<ExecutionMode>) -> Result<(), ConfigError> {
+    let mut pins = pins();
+    match core {
+        Some(core) => pins.insert(category, core),
+        None => pins.remove(&category),
+    };
+    Config::global().set_param(CORE_PINS_KEY, pins)
+}
+
+#[derive(Debug, Clone, Serialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct CoreScore {
+    #[schema(value_type = String)]
+    pub core: ExecutionMode,
+    #[schema(value_type = String)]
+    pub category: TaskCategory,
+    /// Static suitability score
+    pub heuristic: f64,
+    pub successes: u64,
+    pub attempts: u64,
+    /// Suitability blended with the observed success rate
+    pub blended: f64,
+    /// The user pinned this core for the category
+    pub pinned: bool,
+    /// Disabled cores are never selected
+    pub enabled: bool,
+}
+
+pub struct CoreSelector {
+    experience: CoreExperience,
+    pins: HashMap<TaskCategory, ExecutionMode>,
+    disabled: Vec<ExecutionMode>,
+}
+
+impl CoreSelector {
+    pub fn new(experience: CoreExperience, pins: HashMap<TaskCategory, ExecutionMode>) -> Self {
+        Self {
+            experience,
+            pins,
+            disabled: Vec::new(),
+        }
+    }
+
+    /// Cores that are never selected, pinned or not.
+    pub fn with_disabled(mut self, disabled: Vec<ExecutionMode>) -> Self {
+        self.disabled = disabled;
+        self
+    }
+
+    pub fn from_config() -> Self {
+        let disabled = core_registry::all_settings()
+            .into_iter()
+            .filter(|(_, settings)| !settings.enabled)
+            .map(|(core, _)| core)
+            .collect();
+        Self::new(CoreExperience::load_default(), pins()).with_disabled(disabled)
+    }
+
+    pub fn score(&self, core: ExecutionMode, category: TaskCategory) -> CoreScore {
+        let heuristic = suitability_score(core, category);
+        let (successes, attempts) = self.experience.outcomes(core, category);
+        CoreScore {
+            core,
+            category,
+            heuristic,
+            successes,
+            attempts,
+            blended: (PRIOR_SAMPLES * heuristic + successes as f64)
+                / (PRIOR_SAMPLES + attempts as f64),
+            pinned: self.pins.get(&category) == Some(&core),
+            enabled: !self.disabled.contains(&core),
+        }
+    }
+
+    /// Scores of every core on every category.
+    pub fn scores(&self) -> Vec<CoreScore> {
+        CATEGORIES
+            .iter()
+            .flat_map(|category| CORES.iter().map(|core| self.score(*core, *category)))
+            .collect()
+    }
+
+    /// The pinned core for `category`, or the enabled one with the best
+    /// blended score.
+    pub fn select(&self, category: TaskCategory) -> ExecutionMode {
+        if let Some(core) = self
+            .pins
+            .get(&category)
+            .filter(|core| !self.disabled.contains(core))
+        {
+            return *core;
+        }
+        CORES
+            .iter()
+            .map(|core| self.score(*core, category))
+            .filter(|score| score.enabled)
+            .max_by(|a, b| a.blended.total_cmp(&b.blended))
+            .map_or(ExecutionMode::default(), |score| score.core)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_experience_outweighs_heuristic_gradually() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut experience = CoreExperience::load(dir.path().join(EXPERIENCE_FILE));
+        let selector = CoreSelector::new(experience.clone(), HashMap::new());
+        assert_eq!(
+            selector.select(TaskCategory::CodeFix),
+            ExecutionMode::Structured
+        );
+
+        // One bad run does not flip the choice; a consistent record does
+        experience.record(ExecutionMode::Structured, TaskCategory::CodeFix, false);
+        let selector = CoreSelector::new(experience.clone(), HashMap::new());
+        assert_eq!(
+            selector.select(TaskCategory::CodeFix),
+            ExecutionMode::Structured
+        );
+        for _ in 0..10 {
+            experience.record(ExecutionMode::Structured, TaskCategory::CodeFix, false);
+            experience.record(ExecutionMode::Freeform, TaskCategory::CodeFix, true);
+        }
+        experience.save().unwrap();
+
+        let reloaded = CoreExperience::load(experience.path());
+        assert_eq!(
+            reloaded.outcomes(ExecutionMode::Structured, TaskCategory::CodeFix),
+            (0, 11)
+        );
+        let selector = CoreSelector::new(reloaded, HashMap::new());
+        let score = selector.score(ExecutionMode::Freeform, TaskCategory::CodeFix);
+        assert!((score.blended - 15.5 / 20.0).abs() < 1e-9);
+        assert_eq!(
+            selector.select(TaskCategory::CodeFix),
+            ExecutionMode::Freeform
+        );
+    }
+
+    #[test]
+    fn test_pins_and_disabled_cores() {
+        let pins = HashMap::from([(TaskCategory::Research, ExecutionMode::Structured)]);
+        let selector = CoreSelector::new(CoreExperience::default(), pins);
+        assert_eq!(
+            selector.select(TaskCategory::Research),
+            ExecutionMode::Structured
+        );
+        assert!(
+            selector
+                .score(ExecutionMode::Structured, TaskCategory::Research)
+                .pinned
+        );
+        assert_eq!(selector.scores().len(), CATEGORIES.len() * 2);
+
+        let selector = selector.with_disabled(vec![ExecutionMode::Structured]);
+        assert_eq!(
+            selector.select(TaskCategory::Research),
+            ExecutionMode::Freeform
+        );
+        assert_eq!(
+            selector.select(TaskCategory::CodeFix),
+            ExecutionMode::Freeform
+        );
+        assert_eq!(parse_category("Code_Fix"), Some(TaskCategory::CodeFix));
+        assert_eq!(parse_category("cooking"), None);
+    }
+}
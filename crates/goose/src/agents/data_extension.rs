@@ -0,0 +1,886 @@
+//! SQL over workspace data files.
+//!
+//! CSV files are loaded into an in-memory SQLite database and SQLite files
+//! are attached read-only by name, so one query can join across both. Each
+//! query runs on a fresh connection with `query_only` set and a timeout.
+//! Only a single SELECT statement is accepted. Results come back as a
+//! preview table. When rows are cut off, the full result is stored as a CSV
+//! session attachment. Charts are rendered as SVG attachments.
+
+use crate::agents::extension::PlatformExtensionContext;
+use crate::agents::mcp_client::{Error, McpClientTrait};
+use anyhow::{anyhow, bail, Context, Result};
+use async_trait::async_trait;
+use futures::{StreamExt, TryStreamExt};
+use indoc::indoc;
+use rmcp::model::{
+    CallToolResult, Content, Implementation, InitializeResult, JsonObject, ListToolsResult,
+    ProtocolVersion, ServerCapabilities, Tool, ToolAnnotations, ToolsCapability,
+};
+use schemars::{schema_for, JsonSchema};
+use serde::{Deserialize, Serialize};
+use sqlx::sqlite::{SqliteConnectOptions, SqliteRow};
+use sqlx::{Connection, Row, SqliteConnection};
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+use std::time::{Duration, Instant};
+use tokio::sync::Mutex;
+use tokio_util::sync::CancellationToken;
+
+pub static EXTENSION_NAME: &str = "data";
+
+const DEFAULT_PREVIEW_ROWS: usize = 50;
+const MAX_CELL_CHARS: usize = 80;
+const QUERY_TIMEOUT: Duration = Duration::from_secs(30);
+/// SQLite instructions between deadline checks
+const PROGRESS_OPS: i32 = 10_000;
+/// Rows kept for the full-result artifact
+const MAX_RESULT_ROWS: usize = 100_000;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SourceKind {
+    Csv,
+    Sqlite,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct DataSource {
+    pub name: String,
+    pub path: PathBuf,
+    pub kind: SourceKind,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Value {
+    Null,
+    Integer(i64),
+    Real(f64),
+    Text(String),
+}
+
+impl Value {
+    fn as_f64(&self) -> Option<f64> {
+        match self {
+            Self::Integer(i) => Some(*i as f64),
+            Self::Real(r) => Some(*r),
+            Self::Text(t) => t.trim().parse().ok(),
+            Self::Null => None,
+        }
+    }
+}
+
+impl std::fmt::Display for Value {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Null => Ok(()),
+            Self::Integer(i) => write!(f, "{}", i),
+            Self::Real(r) => write!(f, "{}", r),
+            Self::Text(t) => write!(f, "{}", t),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct QueryResult {
+    pub columns: Vec<String>,
+    pub rows: Vec<Vec<Value>>,
+    /// More rows matched than were kept
+    pub truncated: bool,
+}
+
+impl QueryResult {
+    /// Markdown table of the first `limit` rows
+    pub fn preview(&self, limit: usize) -> String {
+        let cell = |value: &Value| {
+            crate::utils::safe_truncate(&value.to_string(), MAX_CELL_CHARS)
+                .replace('|', "\\|")
+                .replace('\n', " ")
+        };
+        let mut table = format!(
+            "| {} |\n|{}|",
+            self.columns.join(" | "),
+            " --- |".repeat(self.columns.len())
+        );
+        for row in self.rows.iter().take(limit) {
+            table.push_str(&format!(
+                "\n| {} |",
+                row.iter().map(cell).collect::<Vec<_>>().join(" | ")
+            ));
+        }
+        table
+    }
+
+    pub fn to_csv(&self) -> String {
+        let mut csv = csv_line(self.columns.iter().map(String::as_str));
+        for row in &self.rows {
+            let cells: Vec<String> = row.iter().map(Value::to_string).collect();
+            csv.push_str(&csv_line(cells.iter().map(String::as_str)));
+        }
+        csv
+    }
+}
+
+fn csv_line<'a>(cells: impl Iterator<Item = &'a str>) -> String {
+    let quoted: Vec<String> = cells
+        .map(|cell| {
+            if cell.contains([',', '"', '\n', '\r']) {
+                format!("\"{}\"", cell.replace('"', "\"\""))
+            } else {
+                cell.to_string()
+            }
+        })
+        .collect();
+    format!("{}\n", quoted.join(","))
+}
+
+/// Splits CSV text into records, honouring quoted fields
+pub fn parse_csv(text: &str, delimiter: char) -> Vec<Vec<String>> {
+    let mut records = Vec::new();
+    let mut record = Vec::new();
+    let mut field = String::new();
+    let mut in_quotes = false;
+    let mut chars = text.chars().peekable();
+
+    while let Some(c) = chars.next() {
+        match c {
+            '"' if in_quotes && chars.peek() == Some(&'"') => {
+                field.push('"');
+                chars.next();
+            }
+            '"' => in_quotes = !in_quotes,
+            c if c == delimiter && !in_quotes => record.push(std::mem::take(&mut field)),
+            '\r' if !in_quotes => {}
+            '\n' if !in_quotes => {
+                record.push(std::mem::take(&mut field));
+                if record.iter().any(|f| !f.is_empty()) {
+                    records.push(std::mem::take(&mut record));
+                } else {
+                    record.clear();
+                }
+            }
+            c => field.push(c),
+        }
+    }
+    if !field.is_empty() || !record.is_empty() {
+        record.push(field);
+        records.push(record);
+    }
+    records
+}
+
+/// INTEGER, REAL or TEXT, whichever fits every non-empty value
+fn column_type<'a>(values: impl Iterator<Item = &'a str>) -> &'static str {
+    let mut column_type = "INTEGER";
+    for value in values.map(str::trim).filter(|v| !v.is_empty()) {
+        if value.parse::<i64>().is_ok() {
+            continue;
+        }
+        if value.parse::<f64>().is_ok() {
+            column_type = "REAL";
+        } else {
+            return "TEXT";
+        }
+    }
+    column_type
+}
+
+fn quote_identifier(name: &str) -> String {
+    format!("\"{}\"", name.replace('"', "\"\""))
+}
+
+/// Table name derived from a file name: letters, digits and underscores
+pub fn table_name(path: &Path) -> String {
+    let stem = path
+        .file_stem()
+        .and_then(|s| s.to_str())
+        .unwrap_or("data")
+        .chars()
+        .map(|c| if c.is_ascii_alphanumeric() { c } else { '_' })
+        .collect::<String>();
+    if stem.starts_with(|c: char| c.is_ascii_digit()) {
+        format!("t_{}", stem)
+    } else {
+        stem
+    }
+}
+
+/// Rejects anything but a single read statement
+pub fn check_query(sql: &str) -> Result<&str> {
+    let sql = sql.trim().trim_end_matches(';').trim();
+    if sql.contains(';') {
+        bail!("Only a single statement is allowed");
+    }
+    let first_word = sql
+        .split_whitespace()
+        .next()
+        .unwrap_or_default()
+        .to_ascii_uppercase();
+    if !matches!(first_word.as_str(), "SELECT" | "WITH" | "VALUES") {
+        bail!("Only SELECT queries are allowed");
+    }
+    Ok(sql)
+}
+
+async fn load_csv(conn: &mut SqliteConnection, source: &DataSource) -> Result<()> {
+    let text = tokio::fs::read_to_string(&source.path)
+        .await
+        .with_context(|| format!("Failed to read {}", source.path.display()))?;
+    let delimiter = if source.path.extension().is_some_and(|e| e == "tsv") {
+        '\t'
+    } else {
+        ','
+    };
+    let mut records = parse_csv(&text, delimiter).into_iter();
+    let header = records
+        .next()
+        .ok_or_else(|| anyhow!("{} is empty", source.path.display()))?;
+    let rows: Vec<Vec<String>> = records.collect();
+
+    let columns: Vec<String> = header
+        .iter()
+        .enumerate()
+        .map(|(index, name)| {
+            let column_type = column_type(
+                rows.iter()
+                    .map(|row| row.get(index).map(String::as_str).unwrap_or("")),
+            );
+            format!("{} {}", quote_identifier(name.trim()), column_type)
+        })
+        .collect();
+    let table = quote_identifier(&source.name);
+    sqlx::query(&format!("CREATE TABLE {} ({})", table, columns.join(", ")))
+        .execute(&mut *conn)
+        .await?;
+
+    let placeholders = vec!["?"; header.len()].join(", ");
+    let insert = format!("INSERT INTO {} VALUES ({})", table, placeholders);
+    let mut tx = conn.begin().await?;
+    for row in &rows {
+        let mut query = sqlx::query(&insert);
+        for index in 0..header.len() {
+            let value = row.get(index).map(|v| v.trim()).unwrap_or("");
+            query = if value.is_empty() {
+                query.bind(None::<String>)
+            } else {
+                query.bind(value.to_string())
+            };
+        }
+        query.execute(&mut *tx).await?;
+    }
+    tx.commit().await?;
+    Ok(())
+}
+
+/// `file:` URI that opens `path` read-only when attached
+fn read_only_uri(path: &Path) -> String {
+    let mut uri = String::from("file:");
+    for c in path.to_string_lossy().chars() {
+        match c {
+            '%' | '?' | '#' => uri.push_str(&format!("%{:02X}", c as u32)),
+            '\\' => uri.push('/'),
+            c => uri.push(c),
+        }
+    }
+    uri.push_str("?mode=ro");
+    uri
+}
+
+/// Loads the sources into a fresh connection and runs `sql` read-only
+pub async fn run_query(sources: &[DataSource], sql: &str, max_rows: usize) -> Result<QueryResult> {
+    let sql = check_query(sql)?;
+    let mut conn =
+        SqliteConnection::connect_with(&SqliteConnectOptions::from_str("sqlite::memory:")?).await?;
+    for source in sources {
+        match source.kind {
+            SourceKind::Csv => load_csv(&mut conn, source).await?,
+            SourceKind::Sqlite => {
+                sqlx::query(&format!(
+                    "ATTACH DATABASE ? AS {}",
+                    quote_identifier(&source.name)
+                ))
+                .bind(read_only_uri(&source.path))
+                .execute(&mut conn)
+                .await?;
+            }
+        }
+    }
+    sqlx::query("PRAGMA query_only = ON")
+        .execute(&mut conn)
+        .await?;
+
+    // Interrupts the statement inside SQLite once the deadline passes, so a
+    // runaway query stops rather than only being abandoned
+    let deadline = Instant::now() + QUERY_TIMEOUT;
+    conn.lock_handle()
+        .await?
+        .set_progress_handler(PROGRESS_OPS, move || Instant::now() < deadline);
+
+    let rows: Vec<SqliteRow> = sqlx::query(sql)
+        .fetch(&mut conn)
+        .take(max_rows + 1)
+        .try_collect()
+        .await
+        .map_err(|e| {
+            if Instant::now() >= deadline {
+                anyhow!("Query timed out after {}s", QUERY_TIMEOUT.as_secs())
+            } else {
+                e.into()
+            }
+        })?;
+
+    let columns = rows
+        .first()
+        .map(|row| {
+            row.columns()
+                .iter()
+                .map(|c| sqlx::Column::name(c).to_string())
+                .collect()
+        })
+        .unwrap_or_default();
+    let truncated = rows.len() > max_rows;
+    let rows = rows
+        .iter()
+        .take(max_rows)
+        .map(|row| {
+            (0..row.len())
+                .map(|index| {
+                    if let Ok(Some(value)) = row.try_get::<Option<i64>, _>(index) {
+                        Value::Integer(value)
+                    } else if let Ok(Some(value)) = row.try_get::<Option<f64>, _>(index) {
+                        Value::Real(value)
+                    } else if let Ok(Some(value)) = row.try_get::<Option<String>, _>(index) {
+                        Value::Text(value)
+                    } else if let Ok(Some(value)) = row.try_get::<Option<Vec<u8>>, _>(index) {
+                        Value::Text(format!("<{} bytes>", value.len()))
+                    } else {
+                        Value::Null
+                    }
+                })
+                .collect()
+        })
+        .collect();
+    Ok(QueryResult {
+        columns,
+        rows,
+        truncated,
+    })
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum ChartKind {
+    Bar,
+    Line,
+}
+
+fn escape_xml(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+/// SVG chart of the first column (labels) against the second (values)
+pub fn render_chart(result: &QueryResult, kind: ChartKind, title: &str) -> Result<String> {
+    if result.columns.len() < 2 {
+        bail!("A chart needs a label column and a value column");
+    }
+    let points: Vec<(String, f64)> = result
+        .rows
+        .iter()
+        .filter_map(|row| Some((row[0].to_string(), row[1].as_f64()?)))
+        .collect();
+    if points.is_empty() {
+        bail!("The second column has no numeric values");
+    }
+
+    let (width, height, margin) = (640.0, 400.0, 50.0);
+    let plot_width = width - 2.0 * margin;
+    let plot_height = height - 2.0 * margin;
+    let max = points.iter().map(|(_, v)| *v).fold(0.0, f64::max);
+    let max = if max > 0.0 { max } else { 1.0 };
+    let step = plot_width / points.len() as f64;
+    let y = |value: f64| height - margin - value.max(0.0) / max * plot_height;
+
+    let mut svg = format!(
+        r#"<svg xmlns="http://www.w3.org/2000/svg" width="{w}" height="{h}" viewBox="0 0 {w} {h}" font-family="sans-serif" font-size="11">
+<rect width="100%" height="100%" fill="white"/>
+<text x="{cx}" y="24" text-anchor="middle" font-size="15">{title}</text>
+<line x1="{m}" y1="{b}" x2="{r}" y2="{b}" stroke="black"/>
+<line x1="{m}" y1="{m}" x2="{m}" y2="{b}" stroke="black"/>
+<text x="{lx}" y="{ty}" text-anchor="end">{max}</text>
+<text x="{lx}" y="{b}" text-anchor="end">0</text>
+"#,
+        w = width,
+        h = height,
+        cx = width / 2.0,
+        title = escape_xml(title),
+        m = margin,
+        r = width - margin,
+        b = height - margin,
+        lx = margin - 4.0,
+        ty = margin + 4.0,
+        max = max,
+    );
+    let label_every = (points.len() / 20).max(1);
+    for (index, (label, value)) in points.iter().enumerate() {
+        let x = margin + step * index as f64;
+        match kind {
+            ChartKind::Bar => svg.push_str(&format!(
+                "<rect x=\"{:.1}\" y=\"{:.1}\" width=\"{:.1}\" height=\"{:.1}\" fill=\"steelblue\"/>\n",
+                x + step * 0.1,
+                y(*value),
+                step * 0.8,
+                height - margin - y(*value)
+            )),
+            ChartKind::Line => {
+                svg.push_str(&format!(
+                    "<circle cx=\"{:.1}\" cy=\"{:.1}\" r=\"3\" fill=\"steelblue\"/>\n",
+                    x + step / 2.0,
+                    y(*value)
+                ));
+            }
+        }
+        if index % label_every == 0 {
+            svg.push_str(&format!(
+                "<text x=\"{:.1}\" y=\"{:.1}\" text-anchor=\"middle\">{}</text>\n",
+                x + step / 2.0,
+                height - margin + 16.0,
+                escape_xml(&crate::utils::safe_truncate(label, 12))
+            ));
+        }
+    }
+    if kind == ChartKind::Line {
+        let path: Vec<String> = points
+            .iter()
+            .enumerate()
+            .map(|(index, (_, value))| {
+                format!(
+                    "{:.1},{:.1}",
+                    margin + step * index as f64 + step / 2.0,
+                    y(*value)
+                )
+            })
+            .collect();
+        svg.push_str(&format!(
+            "<polyline points=\"{}\" fill=\"none\" stroke=\"steelblue\" stroke-width=\"2\"/>\n",
+            path.join(" ")
+        ));
+    }
+    svg.push_str("</svg>\n");
+    Ok(svg)
+}
+
+#[derive(Debug, Serialize, Deserialize, JsonSchema)]
+struct LoadParams {
+    /// CSV, TSV or SQLite file in the workspace
+    path: String,
+    /// Table name for CSV files or schema name for SQLite files; defaults to the file name
+    #[serde(default)]
+    name: Option<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize, JsonSchema)]
+struct QueryParams {
+    /// A single SELECT statement
+    sql: String,
+    /// Rows to show (default 50); the full result is stored as an attachment
+    #[serde(default)]
+    limit: Option<usize>,
+}
+
+#[derive(Debug, Serialize, Deserialize, JsonSchema)]
+struct ChartParams {
+    /// SELECT returning a label column followed by a numeric column
+    sql: String,
+    kind: ChartKind,
+    #[serde(default)]
+    title: Option<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize, JsonSchema)]
+struct TablesParams {}
+
+pub struct DataClient {
+    info: InitializeResult,
+    context: PlatformExtensionContext,
+    /// Loaded sources per session
+    sources: Mutex<HashMap<String, Vec<DataSource>>>,
+}
+
+impl DataClient {
+    pub fn new(context: PlatformExtensionContext) -> Result<Self> {
+        let info = InitializeResult {
+            protocol_version: ProtocolVersion::V_2025_03_26,
+            capabilities: ServerCapabilities {
+                tasks: None,
+                tools: Some(ToolsCapability {
+                    list_changed: Some(false),
+                }),
+                resources: None,
+                prompts: None,
+                completions: None,
+                experimental: None,
+                logging: None,
+            },
+            server_info: Implementation {
+                name: EXTENSION_NAME.to_string(),
+                title: Some("Data".to_string()),
+                version: "1.0.0".to_string(),
+                icons: None,
+                website_url: None,
+            },
+            instructions: Some(
+                indoc! {r#"
+                Analyze tabular data with SQL (SQLite dialect).
+
+                - load a CSV/TSV file as a table, or a SQLite database as a schema
+                - tables shows what is loaded and the columns
+                - query runs one SELECT; long results are stored as a CSV attachment
+                - chart plots a label column against a numeric column
+
+                Prefer aggregating in SQL over reading raw rows.
+            "#}
+                .to_string(),
+            ),
+        };
+
+        Ok(Self {
+            info,
+            context,
+            sources: Mutex::new(HashMap::new()),
+        })
+    }
+
+    /// Resolves `path` and makes sure it stays inside the working directory
+    fn resolve(path: &str, working_dir: Option<&str>) -> Result<PathBuf> {
+        let base = working_dir
+            .map(PathBuf::from)
+            .or_else(|| std::env::current_dir().ok())
+            .ok_or_else(|| anyhow!("No working directory"))?
+            .canonicalize()?;
+        let path = base
+            .join(path)
+            .canonicalize()
+            .with_context(|| format!("{} not found", path))?;
+        if !path.starts_with(&base) {
+            bail!("{} is outside the workspace", path.display());
+        }
+        Ok(path)
+    }
+
+    async fn load(
+        &self,
+        session_id: &str,
+        params: LoadParams,
+        working_dir: Option<&str>,
+    ) -> Result<Vec<Content>> {
+        let path = Self::resolve(&params.path, working_dir)?;
+        let extension = path
+            .extension()
+            .and_then(|e| e.to_str())
+            .map(|e| e.to_ascii_lowercase())
+            .unwrap_or_default();
+        let kind = match extension.as_str() {
+            "csv" | "tsv" => SourceKind::Csv,
+            "sqlite" | "sqlite3" | "db" => SourceKind::Sqlite,
+            "parquet" => bail!("Parquet files are not supported yet; export the data to CSV"),
+            _ => bail!("Unsupported file type: {}", path.display()),
+        };
+        let source = DataSource {
+            name: params.name.unwrap_or_else(|| table_name(&path)),
+            path,
+            kind,
+        };
+
+        let mut sources = self.sources.lock().await;
+        let session_sources = sources.entry(session_id.to_string()).or_default();
+        session_sources.retain(|s| s.name != source.name);
+        session_sources.push(source.clone());
+        let summary = self.describe(session_sources).await?;
+        Ok(vec![Content::text(format!(
+            "Loaded {} as {}\n\n{}",
+            source.path.display(),
+            source.name,
+            summary
+        ))])
+    }
+
+    /// Columns of every loaded table
+    async fn describe(&self, sources: &[DataSource]) -> Result<String> {
+        let mut lines = Vec::new();
+        for source in sources {
+            let tables = match source.kind {
+                SourceKind::Csv => vec![source.name.clone()],
+                SourceKind::Sqlite => run_query(
+                    std::slice::from_ref(source),
+                    &format!(
+                        "SELECT name FROM {}.sqlite_master WHERE type IN ('table', 'view')",
+                        quote_identifier(&source.name)
+                    ),
+                    usize::MAX,
+                )
+                .await?
+                .rows
+                .into_iter()
+                .map(|row| format!("{}.{}", source.name, row[0]))
+                .collect(),
+            };
+            for table in tables {
+                let (schema, name) = table.split_once('.').unwrap_or(("main", &table));
+                let columns = run_query(
+                    std::slice::from_ref(source),
+                    &format!(
+                        "SELECT name, type FROM pragma_table_info({}, {})",
+                        sql_string(name),
+                        sql_string(schema)
+                    ),
+                    usize::MAX,
+                )
+                .await?;
+                let columns: Vec<String> = columns
+                    .rows
+                    .iter()
+                    .map(|row| format!("{} {}", row[0], row[1]))
+                    .collect();
+                lines.push(format!("{} ({})", table, columns.join(", ")));
+            }
+        }
+        Ok(lines.join("\n"))
+    }
+
+    async fn session_sources(&self, session_id: &str) -> Result<Vec<DataSource>> {
+        let sources = self
+            .sources
+            .lock()
+            .await
+            .get(session_id)
+            .cloned()
+            .unwrap_or_default();
+        if sources.is_empty() {
+            bail!("No data loaded yet; use load first");
+        }
+        Ok(sources)
+    }
+
+    async fn query(&self, session_id: &str, params: QueryParams) -> Result<Vec<Content>> {
+        let sources = self.session_sources(session_id).await?;
+        let result = run_query(&sources, &params.sql, MAX_RESULT_ROWS).await?;
+        let limit = params.limit.unwrap_or(DEFAULT_PREVIEW_ROWS);
+
+        let mut text = format!("{} rows\n\n{}", result.rows.len(), result.preview(limit));
+        if result.truncated {
+            text.push_str(&format!(
+                "\n\nOnly the first {} rows were kept.",
+                MAX_RESULT_ROWS
+            ));
+        }
+        if result.rows.len() > limit {
+            let attachment = self.context.session_manager.attachments().store(
+                session_id,
+                "query-result.csv",
+                result.to_csv().as_bytes(),
+            )?;
+            text.push_str(&format!(
+                "\n\nShowing {} of {} rows. The full result is attachment {}.",
+                limit,
+                result.rows.len(),
+                attachment.id
+            ));
+        }
+        Ok(vec![Content::text(text)])
+    }
+
+    async fn chart(&self, session_id: &str, params: ChartParams) -> Result<Vec<Content>> {
+        let sources = self.session_sources(session_id).await?;
+        let result = run_query(&sources, &params.sql, 1_000).await?;
+        let title = params.title.unwrap_or_default();
+        let svg = render_chart(&result, params.kind, &title)?;
+        let attachment = self.context.session_manager.attachments().store(
+            session_id,
+            "chart.svg",
+            svg.as_bytes(),
+        )?;
+        Ok(vec![Content::text(format!(
+            "Chart of {} points stored as attachment {}",
+            result.rows.len(),
+            attachment.id
+        ))])
+    }
+
+    async fn handle(
+        &self,
+        session_id: &str,
+        name: &str,
+        arguments: Option<JsonObject>,
+        working_dir: Option<&str>,
+    ) -> Result<Vec<Content>> {
+        fn params<T: serde::de::DeserializeOwned>(arguments: Option<JsonObject>) -> Result<T> {
+            serde_json::from_value(serde_json::Value::Object(arguments.unwrap_or_default()))
+                .map_err(|e| anyhow!("Invalid parameters: {}", e))
+        }
+
+        match name {
+            "load" => self.load(session_id, params(arguments)?, working_dir).await,
+            "tables" => {
+                let sources = self.session_sources(session_id).await?;
+                Ok(vec![Content::text(self.describe(&sources).await?)])
+            }
+            "query" => self.query(session_id, params(arguments)?).await,
+            "chart" => self.chart(session_id, params(arguments)?).await,
+            _ => Err(anyhow!("Unknown tool: {}", name)),
+        }
+    }
+
+    fn get_tools() -> Vec<Tool> {
+        fn schema<T: JsonSchema>() -> JsonObject {
+            serde_json::to_value(schema_for!(T))
+                .expect("Failed to serialize data tool schema")
+                .as_object()
+                .unwrap()
+                .clone()
+        }
+        let annotations = |title: &str| ToolAnnotations {
+            title: Some(title.to_string()),
+            read_only_hint: Some(true),
+            destructive_hint: Some(false),
+            idempotent_hint: Some(true),
+            open_world_hint: Some(false),
+        };
+
+        vec![
+            Tool::new(
+                "load".to_string(),
+                "Load a CSV/TSV file as a table or a SQLite database as a schema.".to_string(),
+                schema::<LoadParams>(),
+            )
+            .annotate(annotations("Load data")),
+            Tool::new(
+                "tables".to_string(),
+                "List the loaded tables and their columns.".to_string(),
+                schema::<TablesParams>(),
+            )
+            .annotate(annotations("List tables")),
+            Tool::new(
+                "query".to_string(),
+                "Run a single read-only SQL SELECT against the loaded data and preview the result."
+                    .to_string(),
+                schema::<QueryParams>(),
+            )
+            .annotate(annotations("Query data")),
+            Tool::new(
+                "chart".to_string(),
+                "Plot a query result as a bar or line chart, stored as an SVG attachment."
+                    .to_string(),
+                schema::<ChartParams>(),
+            )
+            .annotate(annotations("Chart data")),
+        ]
+    }
+}
+
+fn sql_string(value: &str) -> String {
+    format!("'{}'", value.replace('\'', "''"))
+}
+
+#[async_trait]
+impl McpClientTrait for DataClient {
+    async fn list_tools(
+        &self,
+        _session_id: &str,
+        _next_cursor: Option<String>,
+        _cancellation_token: CancellationToken,
+    ) -> Result<ListToolsResult, Error> {
+        Ok(ListToolsResult {
+            tools: Self::get_tools(),
+            next_cursor: None,
+            meta: None,
+        })
+    }
+
+    async fn call_tool(
+        &self,
+        session_id: &str,
+        name: &str,
+        arguments: Option<JsonObject>,
+        working_dir: Option<&str>,
+        _cancellation_token: CancellationToken,
+    ) -> Result<CallToolResult, Error> {
+        match self.handle(session_id, name, arguments, working_dir).await {
+            Ok(content) => Ok(CallToolResult::success(content)),
+            Err(error) => Ok(CallToolResult::error(vec![Content::text(format!(
+                "Error: {}",
+                error
+            ))])),
+        }
+    }
+
+    fn get_info(&self) -> Option<&InitializeResult> {
+        Some(&self.info)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_parse_csv_and_checks() {
+        let records = parse_csv(
+            "name,note\r\nada,\"says \"\"hi\"\", twice\"\n\nalan,\n",
+            ',',
+        );
+        assert_eq!(
+            records,
+            vec![
+                vec!["name", "note"],
+                vec!["ada", "says \"hi\", twice"],
+                vec!["alan", ""],
+            ]
+        );
+        assert_eq!(column_type(["1", "", "2"].into_iter()), "INTEGER");
+        assert_eq!(column_type(["1", "2.5"].into_iter()), "REAL");
+        assert_eq!(table_name(Path::new("2024 sales.csv")), "t_2024_sales");
+
+        assert!(check_query("SELECT 1;").is_ok());
+        assert!(check_query("DELETE FROM sales").is_err());
+        assert!(check_query("SELECT 1; DROP TABLE sales").is_err());
+    }
+
+    #[tokio::test]
+    async fn test_query_csv_and_chart() {
+        let dir = TempDir::new().unwrap();
+        let path = dir.path().join("sales.csv");
+        std::fs::write(&path, "region,amount\nnorth,10\nsouth,2.5\nnorth,5\n").unwrap();
+        let sources = vec![DataSource {
+            name: "sales".to_string(),
+            path,
+            kind: SourceKind::Csv,
+        }];
+
+        let result = run_query(
+            &sources,
+            "SELECT region, SUM(amount) AS total FROM sales GROUP BY region ORDER BY region",
+            1,
+        )
+        .await
+        .unwrap();
+        assert_eq!(result.columns, vec!["region", "total"]);
+        assert_eq!(
+            result.rows,
+            vec![vec![Value::Text("north".to_string()), Value::Real(15.0)]]
+        );
+        assert!(result.truncated);
+        assert_eq!(
+            result.preview(10),
+            "| region | total |\n| --- | --- |\n| north | 15 |"
+        );
+
+        let svg = render_chart(&result, ChartKind::Bar, "Sales <by region>").unwrap();
+        assert!(svg.contains("Sales &lt;by region&gt;"));
+        assert!(svg.contains("<rect x="));
+    }
+}
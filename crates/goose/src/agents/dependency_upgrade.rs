@@ -0,0 +1,531 @@
+//! Dependency upgrade cycles for a Cargo workspace and its npm packages.
+//!
+//! Outdated dependencies are found with `cargo outdated` and `npm outdated`,
+//! then upgraded in batches on a fresh `deps/upgrade-<date>` branch. Each
+//! batch is gated on the test suites (`cargo test --workspace`, `npm test`)
+//! and the post-code quality validators: a batch that passes is committed,
+//! one that breaks something is rolled back and noted. When at least one
+//! batch landed, the branch is pushed and a pull request is opened with
+//! `gh`, listing the upgrades and the breakage notes.
+//!
+//! Cargo upgrades use `cargo update --precise`, so they stay within the
+//! version requirements in Cargo.toml; a release that needs a manifest change
+//! is rolled back with the error cargo gives. The cycle runs through the
+//! `platform__upgrade_dependencies` tool, so a weekly run is a scheduled
+//! recipe that asks for it, e.g. `goose schedule add --cron "0 6 * * 1"`.
+
+use super::commit_assistant::git;
+use super::dependency_watch::parse_outdated;
+use crate::config::Config;
+use crate::mcp_utils::ToolResult;
+use crate::quality::{CheckResult, PostCodeValidator};
+use anyhow::{anyhow, bail, Result};
+use chrono::Utc;
+use rmcp::model::{Content, ErrorCode, ErrorData};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+use tokio::process::Command;
+use tracing::{info, warn};
+
+pub const BATCH_SIZE_KEY: &str = "GOOSE_DEPS_UPGRADE_BATCH_SIZE";
+const DEFAULT_BATCH_SIZE: usize = 5;
+const OUTDATED_TIMEOUT: Duration = Duration::from_secs(300);
+const INSTALL_TIMEOUT: Duration = Duration::from_secs(600);
+const TEST_TIMEOUT: Duration = Duration::from_secs(1800);
+/// Lines of a failing command's output kept in a breakage note
+const BREAKAGE_LINES: usize = 20;
+/// npm packages checked when no directories are configured
+const DEFAULT_NPM_DIRS: &[&str] = &[".", "ui/desktop"];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Ecosystem {
+    Cargo,
+    Npm,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Upgrade {
+    pub ecosystem: Ecosystem,
+    /// Directory the package manager runs in
+    pub dir: PathBuf,
+    pub package: String,
+    pub from: String,
+    pub to: String,
+}
+
+impl Upgrade {
+    fn command(&self) -> (&'static str, Vec<String>) {
+        match self.ecosystem {
+            Ecosystem::Cargo => (
+                "cargo",
+                vec![
+                    "update".into(),
+                    "-p".into(),
+                    format!("{}@{}", self.package, self.from),
+                    "--precise".into(),
+                    self.to.clone(),
+                ],
+            ),
+            Ecosystem::Npm => (
+                "npm",
+                vec!["install".into(), format!("{}@{}", self.package, self.to)],
+            ),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BatchOutcome {
+    pub upgrades: Vec<Upgrade>,
+    /// What broke, when the batch was rolled back
+    pub breakage: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct UpgradeCycle {
+    pub branch: String,
+    pub batches: Vec<BatchOutcome>,
+    pub pull_request: Option<String>,
+}
+
+impl UpgradeCycle {
+    pub fn landed(&self) -> impl Iterator<Item = &Upgrade> {
+        self.batches
+            .iter()
+            .filter(|b| b.breakage.is_none())
+            .flat_map(|b| &b.upgrades)
+    }
+
+    pub fn summary(&self) -> String {
+        let mut out = String::from("## Upgraded\n\n");
+        let landed: Vec<_> = self.landed().collect();
+        if landed.is_empty() {
+            out.push_str("Nothing; every batch was rolled back.\n");
+        } else {
+            out.push_str("| Package | Ecosystem | From | To |\n|---|---|---|---|\n");
+            for u in landed {
+                out.push_str(&format!(
+                    "| {} | {:?} | {} | {} |\n",
+                    u.package, u.ecosystem, u.from, u.to
+                ));
+            }
+        }
+
+        let broken: Vec<_> = self
+            .batches
+            .iter()
+            .filter_map(|b| b.breakage.as_ref().map(|note| (b, note)))
+            .collect();
+        if !broken.is_empty() {
+            out.push_str("\n## Breakage\n\nThese batches were rolled back:\n");
+            for (batch, note) in broken {
+                let packages: Vec<_> = batch
+                    .upgrades
+                    .iter()
+                    .map(|u| format!("{} {}", u.package, u.to))
+                    .collect();
+                out.push_str(&format!(
+                    "\n- {}\n\n```\n{}\n```\n",
+                    packages.join(", "),
+                    note
+                ));
+            }
+        }
+        out
+    }
+}
+
+fn commit_subject(batch: &[Upgrade]) -> String {
+    let packages: Vec<_> = batch
+        .iter()
+        .map(|u| format!("{} {}", u.package, u.to))
+        .collect();
+    format!("chore(deps): upgrade {}", packages.join(", "))
+}
+
+fn tail(output: &str, lines: usize) -> String {
+    let all: Vec<_> = output.lines().collect();
+    all[all.len().saturating_sub(lines)..].join("\n")
+}
+
+/// npm outdated prints an object keyed by package name
+fn parse_npm_outdated(output: &str, dir: &Path) -> Vec<Upgrade> {
+    let Ok(Value::Object(packages)) = serde_json::from_str::<Value>(output) else {
+        return Vec::new();
+    };
+    let mut upgrades: Vec<Upgrade> = packages
+        .iter()
+        .filter_map(|(name, info)| {
+            let current = info["current"].as_str()?;
+            let latest = info["latest"].as_str()?;
+            (current != latest).then(|| Upgrade {
+                ecosystem: Ecosystem::Npm,
+                dir: dir.to_path_buf(),
+                package: name.clone(),
+                from: current.to_string(),
+                to: latest.to_string(),
+            })
+        })
+        .collect();
+    upgrades.sort_by(|a, b| a.package.cmp(&b.package));
+    upgrades
+}
+
+struct CommandOutput {
+    success: bool,
+    stdout: String,
+    stderr: String,
+}
+
+impl CommandOutput {
+    fn note(&self, program: &str, args: &[String]) -> String {
+        let output = format!("{}{}", self.stdout, self.stderr);
+        format!(
+            "{} {} failed:\n{}",
+            program,
+            args.join(" "),
+            tail(&output, BREAKAGE_LINES)
+        )
+    }
+}
+
+async fn run(dir: &Path, program: &str, args: &[String], limit: Duration) -> Result<CommandOutput> {
+    let output = tokio::time::timeout(
+        limit,
+        Command::new(program)
+            .args(args)
+            .current_dir(dir)
+            .kill_on_drop(true)
+            .output(),
+    )
+    .await
+    .map_err(|_| anyhow!("{} {} timed out", program, args.join(" ")))??;
+    Ok(CommandOutput {
+        success: output.status.success(),
+        stdout: String::from_utf8_lossy(&output.stdout).to_string(),
+        stderr: String::from_utf8_lossy(&output.stderr).to_string(),
+    })
+}
+
+pub struct DependencyUpgrade {
+    workspace: PathBuf,
+    batch_size: usize,
+    npm_dirs: Vec<PathBuf>,
+    validate: bool,
+    open_pr: bool,
+}
+
+impl DependencyUpgrade {
+    pub fn new(workspace: impl Into<PathBuf>) -> Self {
+        let workspace = workspace.into();
+        Self {
+            npm_dirs: DEFAULT_NPM_DIRS.iter().map(|d| workspace.join(d)).collect(),
+            workspace,
+            batch_size: Config::global()
+                .get_param(BATCH_SIZE_KEY)
+                .unwrap_or(DEFAULT_BATCH_SIZE)
+                .max(1),
+            validate: true,
+            open_pr: true,
+        }
+    }
+
+    pub fn with_batch_size(mut self, size: usize) -> Self {
+        self.batch_size = size.max(1);
+        self
+    }
+
+    pub fn with_npm_dirs(mut self, dirs: Vec<PathBuf>) -> Self {
+        self.npm_dirs = dirs;
+        self
+    }
+
+    /// Whether batches must also pass the post-code quality validators
+    pub fn with_validation(mut self, enabled: bool) -> Self {
+        self.validate = enabled;
+        self
+    }
+
+    pub fn with_pull_request(mut self, enabled: bool) -> Self {
+        self.open_pr = enabled;
+        self
+    }
+
+    pub async fn outdated(&self) -> Vec<Upgrade> {
+        let mut upgrades = Vec::new();
+        if self.workspace.join("Cargo.toml").exists() {
+            let args = [
+                "outdated",
+                "--workspace",
+                "--root-deps-only",
+                "--format",
+                "json",
+            ]
+            .map(String::from);
+            match run(&self.workspace, "cargo", &args, OUTDATED_TIMEOUT).await {
+                Ok(output) => {
+                    for event in parse_outdated(&output.stdout) {
+                        let Some(to) = event.target_version else {
+                            continue;
+                        };
+                        upgrades.push(Upgrade {
+                            ecosystem: Ecosystem::Cargo,
+                            dir: self.workspace.clone(),
+                            package: event.package,
+                            from: event.current_version,
+                            to,
+                        });
+                    }
+                }
+                Err(e) => warn!("Skipping cargo upgrades: {}", e),
+            }
+        }
+        for dir in self
+            .npm_dirs
+            .iter()
+            .filter(|d| d.join("package.json").exists())
+        {
+            // npm outdated exits 1 when it finds something
+            let args = ["outdated", "--json"].map(String::from);
+            match run(dir, "npm", &args, OUTDATED_TIMEOUT).await {
+                Ok(output) => upgrades.extend(parse_npm_outdated(&output.stdout, dir)),
+                Err(e) => warn!("Skipping npm upgrades in {}: {}", dir.display(), e),
+            }
+        }
+        upgrades
+    }
+
+    /// Upgrades what is outdated on a new branch, then returns to the branch
+    /// that was checked out.
+    pub async fn run(&self) -> Result<UpgradeCycle> {
+        let dir = self.workspace.as_path();
+        let status = git(dir, &["status", "--porcelain"]).await?;
+        if !status.trim().is_empty() {
+            bail!("The working tree has uncommitted changes; commit or stash them first");
+        }
+        let base = git(dir, &["rev-parse", "--abbrev-ref", "HEAD"]).await?;
+        let base = base.trim();
+
+        let upgrades = self.outdated().await;
+        let branch = format!("deps/upgrade-{}", Utc::now().format("%Y-%m-%d"));
+        let mut cycle = UpgradeCycle {
+            branch: branch.clone(),
+            batches: Vec::new(),
+            pull_request: None,
+        };
+        if upgrades.is_empty() {
+            info!("All dependencies are up to date");
+            return Ok(cycle);
+        }
+
+        git(dir, &["checkout", "-b", &branch]).await?;
+        let result = self.upgrade_on_branch(&upgrades, &mut cycle).await;
+        if let Err(e) = git(dir, &["checkout", base]).await {
+            warn!("Failed to return to {}: {}", base, e);
+        }
+        result.map(|_| cycle)
+    }
+
+    async fn upgrade_on_branch(
+        &self,
+        upgrades: &[Upgrade],
+        cycle: &mut UpgradeCycle,
+    ) -> Result<()> {
+        let dir = self.workspace.as_path();
+        for batch in upgrades.chunks(self.batch_size) {
+            let breakage = self.apply_batch(batch).await?;
+            if let Some(note) = &breakage {
+                warn!("Rolling back {}: {}", commit_subject(batch), note);
+                git(dir, &["reset", "--hard", "HEAD"]).await?;
+            } else {
+                git(dir, &["add", "-A"]).await?;
+                git(dir, &["commit", "-m", &commit_subject(batch)]).await?;
+            }
+            cycle.batches.push(BatchOutcome {
+                upgrades: batch.to_vec(),
+                breakage,
+            });
+        }
+
+        if self.open_pr && cycle.landed().next().is_some() {
+            cycle.pull_request = self.open_pull_request(cycle).await;
+        }
+        Ok(())
+    }
+
+    /// Applies a batch and runs the gates. Returns what broke, if anything.
+    async fn apply_batch(&self, batch: &[Upgrade]) -> Result<Option<String>> {
+        for upgrade in batch {
+            let (program, args) = upgrade.command();
+            let output = run(&upgrade.dir, program, &args, INSTALL_TIMEOUT).await?;
+            if !output.success {
+                return Ok(Some(output.note(program, &args)));
+            }
+        }
+
+        let mut gates: Vec<(&Path, &str, Vec<String>)> = Vec::new();
+        if batch.iter().any(|u| u.ecosystem == Ecosystem::Cargo) {
+            gates.push((
+                self.workspace.as_path(),
+                "cargo",
+                vec!["test".into(), "--workspace".into()],
+            ));
+        }
+        for upgrade in batch.iter().filter(|u| u.ecosystem == Ecosystem::Npm) {
+            if !gates
+                .iter()
+                .any(|(dir, program, _)| *program == "npm" && *dir == upgrade.dir)
+            {
+                gates.push((upgrade.dir.as_path(), "npm", vec!["test".into()]));
+            }
+        }
+        for (dir, program, args) in gates {
+            let output = run(dir, program, &args, TEST_TIMEOUT).await?;
+            if !output.success {
+                return Ok(Some(output.note(program, &args)));
+            }
+        }
+
+        if self.validate {
+            let changed = git(&self.workspace, &["diff", "--name-only", "HEAD"]).await?;
+            let files: Vec<String> = changed.lines().map(String::from).collect();
+            let report = PostCodeValidator::with_strict_mode(false)
+                .validate_changes(&files)
+                .await
+                .map_err(|e| anyhow!(e))?;
+            let failed: Vec<_> = report
+                .checks()
+                .iter()
+                .filter_map(|(name, result)| match result {
+                    CheckResult::Fail { reason, .. } => Some(format!("{}: {}", name, reason)),
+                    _ => None,
+                })
+                .collect();
+            if !failed.is_empty() {
+                return Ok(Some(format!(
+                    "Quality validation failed:\n{}",
+                    failed.join("\n")
+                )));
+            }
+        }
+        Ok(None)
+    }
+
+    async fn open_pull_request(&self, cycle: &UpgradeCycle) -> Option<String> {
+        let dir = self.workspace.as_path();
+        if let Err(e) = git(dir, &["push", "-u", "origin", &cycle.branch]).await {
+            warn!("Failed to push {}: {}", cycle.branch, e);
+            return None;
+        }
+        let body = cycle.summary();
+        let args = [
+            "pr",
+            "create",
+            "--head",
+            cycle.branch.as_str(),
+            "--title",
+            "chore(deps): dependency upgrades",
+            "--body",
+            body.as_str(),
+        ]
+        .map(String::from);
+        match run(dir, "gh", &args, OUTDATED_TIMEOUT).await {
+            Ok(output) if output.success => output.stdout.lines().last().map(String::from),
+            Ok(output) => {
+                warn!("gh pr create failed: {}", output.stderr.trim());
+                None
+            }
+            Err(e) => {
+                warn!("gh pr create failed: {}", e);
+                None
+            }
+        }
+    }
+}
+
+/// Handles the `platform__upgrade_dependencies` tool.
+pub async fn handle_tool_call(working_dir: &Path, arguments: Value) -> ToolResult<Vec<Content>> {
+    let flag = |key: &str| arguments.get(key).and_then(Value::as_bool).unwrap_or(true);
+    let mut upgrade = DependencyUpgrade::new(working_dir)
+        .with_validation(flag("validate"))
+        .with_pull_request(flag("open_pr"));
+    if let Some(size) = arguments.get("batch_size").and_then(Value::as_u64) {
+        upgrade = upgrade.with_batch_size(size as usize);
+    }
+
+    let cycle = upgrade
+        .run()
+        .await
+        .map_err(|e| ErrorData::new(ErrorCode::INTERNAL_ERROR, e.to_string(), None))?;
+    if cycle.batches.is_empty() {
+        return Ok(vec![Content::text("All dependencies are up to date.")]);
+    }
+    let pull_request = match &cycle.pull_request {
+        Some(url) => format!("Pull request: {}", url),
+        None => format!(
+            "No pull request was opened; the work is on {}",
+            cycle.branch
+        ),
+    };
+    Ok(vec![Content::text(format!(
+        "{}\n{}",
+        cycle.summary(),
+        pull_request
+    ))])
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_npm_outdated() {
+        let output = r#"{
+            "react": { "current": "18.2.0", "wanted": "18.3.1", "latest": "19.0.0" },
+            "eslint": { "current": "9.1.0", "wanted": "9.1.0", "latest": "9.1.0" },
+            "vite": { "wanted": "5.4.0", "latest": "6.0.1" }
+        }"#;
+        let upgrades = parse_npm_outdated(output, Path::new("ui/desktop"));
+        assert_eq!(upgrades.len(), 1);
+        assert_eq!(upgrades[0].package, "react");
+        assert_eq!(upgrades[0].to, "19.0.0");
+        assert_eq!(upgrades[0].command().1, vec!["install", "react@19.0.0"]);
+        assert!(parse_npm_outdated("", Path::new(".")).is_empty());
+    }
+
+    #[test]
+    fn test_summary_lists_landed_and_broken_batches() {
+        let upgrade = |package: &str, to: &str| Upgrade {
+            ecosystem: Ecosystem::Cargo,
+            dir: PathBuf::from("."),
+            package: package.into(),
+            from: "1.0.0".into(),
+            to: to.into(),
+        };
+        let cycle = UpgradeCycle {
+            branch: "deps/upgrade-2026-10-17".into(),
+            batches: vec![
+                BatchOutcome {
+                    upgrades: vec![upgrade("serde", "1.0.5"), upgrade("regex", "1.2.0")],
+                    breakage: None,
+                },
+                BatchOutcome {
+                    upgrades: vec![upgrade("tokio", "1.9.0")],
+                    breakage: Some("cargo test --workspace failed:\nerror[E0425]".into()),
+                },
+            ],
+            pull_request: None,
+        };
+        assert_eq!(
+            commit_subject(&cycle.batches[0].upgrades),
+            "chore(deps): upgrade serde 1.0.5, regex 1.2.0"
+        );
+        let summary = cycle.summary();
+        assert!(summary.contains("| serde | Cargo | 1.0.0 | 1.0.5 |"));
+        assert!(!summary.contains("| tokio |"));
+        assert!(summary.contains("- tokio 1.9.0\n\n```\ncargo test --workspace failed:"));
+    }
+}
@@ -0,0 +1,444 @@
+//! Dependency Watch - advisory and freshness checks for a Cargo workspace
+//!
+//! Runs `cargo audit` (RustSec advisories) and `cargo outdated` against a
+//! workspace and turns what they report into update-available events with a
+//! severity. When an event is severe enough, a remediation plan (bump the
+//! affected crates, then build, then test) is produced for the planner to
+//! execute.
+
+use super::planner::{Plan, PlanStep};
+use anyhow::{anyhow, Result};
+use semver::{Op, Version, VersionReq};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::path::PathBuf;
+use std::time::Duration;
+use tokio::process::Command;
+use tracing::{info, warn};
+
+const COMMAND_TIMEOUT: Duration = Duration::from_secs(300);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum UpdateSeverity {
+    Low,
+    Medium,
+    High,
+    Critical,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum UpdateSource {
+    /// A RustSec advisory (vulnerability, yanked or unmaintained crate)
+    Advisory { id: String, title: String },
+    /// A newer release upstream
+    Outdated,
+}
+
+/// A dependency that should be updated
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct UpdateAvailable {
+    pub package: String,
+    pub current_version: String,
+    /// Version to move to, when the tool reported one
+    pub target_version: Option<String>,
+    pub severity: UpdateSeverity,
+    pub source: UpdateSource,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DependencyReport {
+    pub events: Vec<UpdateAvailable>,
+    /// Set when an event reached the auto-remediation threshold
+    pub remediation: Option<Plan>,
+}
+
+impl DependencyReport {
+    pub fn highest_severity(&self) -> Option<UpdateSeverity> {
+        self.events.iter().map(|e| e.severity).max()
+    }
+}
+
+pub struct DependencyWatch {
+    workspace: PathBuf,
+    check_freshness: bool,
+    auto_remediate: Option<UpdateSeverity>,
+}
+
+impl DependencyWatch {
+    pub fn new(workspace: impl Into<PathBuf>) -> Self {
+        Self {
+            workspace: workspace.into(),
+            check_freshness: true,
+            auto_remediate: Some(UpdateSeverity::High),
+        }
+    }
+
+    pub fn with_freshness(mut self, enabled: bool) -> Self {
+        self.check_freshness = enabled;
+        self
+    }
+
+    /// Minimum severity that produces a remediation plan; `None` disables it
+    pub fn with_auto_remediate(mut self, threshold: Option<UpdateSeverity>) -> Self {
+        self.auto_remediate = threshold;
+        self
+    }
+
+    pub async fn check(&self) -> Result<DependencyReport> {
+        let audit = self.run_cargo(&["audit", "--json"]).await?;
+        let mut events = parse_audit(&audit)?;
+
+        if self.check_freshness {
+            match self
+                .run_cargo(&["outdated", "--workspace", "--format", "json"])
+                .await
+            {
+                Ok(output) => {
+                    for event in parse_outdated(&output) {
+                        if !events.iter().any(|e| e.package == event.package) {
+                            events.push(event);
+                        }
+                    }
+                }
+                Err(e) => warn!("Skipping dependency freshness check: {}", e),
+            }
+        }
+
+        events.sort_by(|a, b| b.severity.cmp(&a.severity));
+        for event in &events {
+            info!(
+                "Update available for {} {} ({:?})",
+                event.package, event.current_version, event.severity
+            );
+        }
+
+        Ok(DependencyReport {
+            remediation: self.remediation_for(&events),
+            events,
+        })
+    }
+
+    fn remediation_for(&self, events: &[UpdateAvailable]) -> Option<Plan> {
+        let threshold = self.auto_remediate?;
+        let due: Vec<UpdateAvailable> = events
+            .iter()
+            .filter(|e| e.severity >= threshold)
+            .cloned()
+            .collect();
+        if due.is_empty() {
+            None
+        } else {
+            Some(remediation_plan(&due))
+        }
+    }
+
+    /// Runs a cargo subcommand in the workspace. cargo audit and cargo
+    /// outdated exit non-zero when they find something, so only a missing
+    /// tool or empty output is an error.
+    async fn run_cargo(&self, args: &[&str]) -> Result<String> {
+        let output = tokio::time::timeout(
+            COMMAND_TIMEOUT,
+            Command::new("cargo")
+                .args(args)
+                .current_dir(&self.workspace)
+                .output(),
+        )
+        .await
+        .map_err(|_| anyhow!("cargo {} timed out", args.join(" ")))??;
+
+        let stdout = String::from_utf8_lossy(&output.stdout).to_string();
+        if stdout.trim().is_empty() {
+            return Err(anyhow!(
+                "cargo {} produced no output: {}",
+                args.join(" "),
+                String::from_utf8_lossy(&output.stderr).trim()
+            ));
+        }
+        Ok(stdout)
+    }
+}
+
+/// Bump each affected crate, then build and test the workspace
+pub fn remediation_plan(events: &[UpdateAvailable]) -> Plan {
+    let mut steps = Vec::new();
+    for event in events {
+        let command = match &event.target_version {
+            Some(version) => format!("cargo update -p {} --precise {}", event.package, version),
+            None => format!("cargo update -p {}", event.package),
+        };
+        let reason = match &event.source {
+            UpdateSource::Advisory { id, .. } => format!(" ({})", id),
+            UpdateSource::Outdated => String::new(),
+        };
+        steps.push(
+            PlanStep::new(
+                steps.len() + 1,
+                format!(
+                    "Bump {} from {}{}",
+                    event.package, event.current_version, reason
+                ),
+            )
+            .with_tools(vec!["developer__shell".to_string()])
+            .with_validation(format!("`{}` succeeds", command)),
+        );
+    }
+    let bumps: Vec<usize> = steps.iter().map(|s| s.id).collect();
+    let build_id = steps.len() + 1;
+    steps.push(
+        PlanStep::new(build_id, "Build the workspace")
+            .with_tools(vec!["developer__shell".to_string()])
+            .with_validation("`cargo build --workspace` succeeds")
+            .with_dependencies(bumps),
+    );
+    steps.push(
+        PlanStep::new(build_id + 1, "Run the test suite")
+            .with_tools(vec!["developer__shell".to_string()])
+            .with_validation("`cargo test --workspace` passes")
+            .with_dependencies(vec![build_id]),
+    );
+
+    let packages: Vec<&str> = events.iter().map(|e| e.package.as_str()).collect();
+    Plan::new(format!(
+        "Update vulnerable or outdated dependencies: {}",
+        packages.join(", ")
+    ))
+    .with_steps(steps)
+}
+
+/// Severity from a CVSS vector: network-reachable with high impact is critical
+fn cvss_severity(vector: Option<&str>) -> UpdateSeverity {
+    match vector {
+        Some(v)
+            if v.contains("AV:N")
+                && (v.contains("C:H") || v.contains("I:H") || v.contains("A:H")) =>
+        {
+            UpdateSeverity::Critical
+        }
+        _ => UpdateSeverity::High,
+    }
+}
+
+/// The lowest version `range` accepts, found among its comparators' bounds.
+fn lowest_matching(range: &str) -> Option<Version> {
+    let req = VersionReq::parse(range).ok()?;
+    req.comparators
+        .iter()
+        .map(|c| {
+            let mut version = Version::new(c.major, c.minor.unwrap_or(0), c.patch.unwrap_or(0));
+            if c.op == Op::Greater {
+                match (c.minor, c.patch) {
+                    (Some(_), Some(_)) => version.patch += 1,
+                    (Some(_), None) => version = Version::new(c.major, version.minor + 1, 0),
+                    (None, _) => version = Version::new(c.major + 1, 0, 0),
+                }
+            } else {
+                version.pre = c.pre.clone();
+            }
+            version
+        })
+        .filter(|v| req.matches(v))
+        .min()
+}
+
+fn advisory_event(entry: &Value, severity: UpdateSeverity) -> Option<UpdateAvailable> {
+    let package = &entry["package"];
+    let name = package["name"].as_str()?;
+    let version = package["version"].as_str().unwrap_or_default();
+    let advisory = &entry["advisory"];
+    let source = match advisory["id"].as_str() {
+        Some(id) => UpdateSource::Advisory {
+            id: id.to_string(),
+            title: advisory["title"].as_str().unwrap_or_default().to_string(),
+        },
+        None => UpdateSource::Advisory {
+            id: format!("{}-{}", entry["kind"].as_str().unwrap_or("warning"), name),
+            title: format!(
+                "{} {} is {}",
+                name,
+                version,
+                entry["kind"].as_str().unwrap_or("flagged")
+            ),
+        },
+    };
+    // Patched ranges look like ">= 0.3.1, < 0.4"; the lowest patched release
+    // above the current version is a safe target.
+    let current = Version::parse(version).ok();
+    let target_version = entry["versions"]["patched"]
+        .as_array()
+        .into_iter()
+        .flatten()
+        .filter_map(|v| v.as_str())
+        .filter_map(lowest_matching)
+        .filter(|v| current.as_ref().is_none_or(|current| v > current))
+        .min()
+        .map(|v| v.to_string());
+    Some(UpdateAvailable {
+        package: name.to_string(),
+        current_version: version.to_string(),
+        target_version,
+        severity,
+        source,
+    })
+}
+
+fn parse_audit(output: &str) -> Result<Vec<UpdateAvailable>> {
+    let report: Value = serde_json::from_str(output)?;
+    let mut events = Vec::new();
+
+    if let Some(list) = report["vulnerabilities"]["list"].as_array() {
+        for entry in list {
+            let severity = cvss_severity(entry["advisory"]["cvss"].as_str());
+            events.extend(advisory_event(entry, severity));
+        }
+    }
+
+    if let Some(warnings) = report["warnings"].as_object() {
+        for (kind, entries) in warnings {
+            let severity = match kind.as_str() {
+                "unsound" | "yanked" => UpdateSeverity::Medium,
+                _ => UpdateSeverity::Low,
+            };
+            for entry in entries.as_array().into_iter().flatten() {
+                events.extend(advisory_event(entry, severity));
+            }
+        }
+    }
+
+    Ok(events)
+}
+
+/// cargo outdated prints one JSON object per workspace member
+pub(super) fn parse_outdated(output: &str) -> Vec<UpdateAvailable> {
+    let mut events: Vec<UpdateAvailable> = Vec::new();
+    for line in output.lines().filter(|l| l.trim_start().starts_with('{')) {
+        let Ok(member) = serde_json::from_str::<Value>(line) else {
+            continue;
+        };
+        for dep in member["dependencies"].as_array().into_iter().flatten() {
+            let (Some(name), Some(project), Some(latest)) = (
+                dep["name"].as_str(),
+                dep["project"].as_str(),
+                dep["latest"].as_str(),
+            ) else {
+                continue;
+            };
+            if project == latest || project == "---" || latest == "---" || latest == "Removed" {
+                continue;
+            }
+            if events.iter().any(|e| e.package == name) {
+                continue;
+            }
+            events.push(UpdateAvailable {
+                package: name.to_string(),
+                current_version: project.to_string(),
+                target_version: Some(latest.to_string()),
+                severity: UpdateSeverity::Low,
+                source: UpdateSource::Outdated,
+            });
+        }
+    }
+    events
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const AUDIT: &str = r#"{
+        "vulnerabilities": {
+            "found": true,
+            "count": 2,
+            "list": [
+                {
+                    "advisory": {
+                        "id": "RUSTSEC-2024-0001",
+                        "title": "Remote crash in parser",
+                        "cvss": "CVSS:3.1/AV:N/AC:L/PR:N/UI:N/S:U/C:N/I:N/A:H"
+                    },
+                    "versions": { "patched": [">= 0.3.1, < 0.4", ">=0.4.2"] },
+                    "package": { "name": "fastparse", "version": "0.4.1" }
+                },
+                {
+                    "advisory": { "id": "RUSTSEC-2024-0002", "title": "Local leak", "cvss": null },
+                    "versions": { "patched": [] },
+                    "package": { "name": "tinycache", "version": "1.0.0" }
+                }
+            ]
+        },
+        "warnings": {
+            "unmaintained": [
+                {
+                    "kind": "unmaintained",
+                    "advisory": { "id": "RUSTSEC-2023-0100", "title": "oldcrate is unmaintained" },
+                    "package": { "name": "oldcrate", "version": "2.0.0" }
+                }
+            ],
+            "yanked": [
+                { "kind": "yanked", "advisory": null, "package": { "name": "yankee", "version": "0.1.0" } }
+            ]
+        }
+    }"#;
+
+    #[test]
+    fn test_parse_audit_severities() {
+        let events = parse_audit(AUDIT).unwrap();
+        let by_name = |name: &str| events.iter().find(|e| e.package == name).unwrap();
+
+        assert_eq!(events.len(), 4);
+        assert_eq!(by_name("fastparse").severity, UpdateSeverity::Critical);
+        assert_eq!(
+            by_name("fastparse").target_version.as_deref(),
+            Some("0.4.2")
+        );
+        assert_eq!(by_name("tinycache").severity, UpdateSeverity::High);
+        assert_eq!(by_name("oldcrate").severity, UpdateSeverity::Low);
+        assert_eq!(by_name("yankee").severity, UpdateSeverity::Medium);
+        assert!(matches!(
+            &by_name("yankee").source,
+            UpdateSource::Advisory { id, .. } if id == "yanked-yankee"
+        ));
+    }
+
+    #[test]
+    fn test_parse_outdated() {
+        let output = r#"{"crate_name":"goose","dependencies":[{"name":"serde","project":"1.0.100","compat":"1.0.200","latest":"1.0.200","kind":"Normal","platform":null},{"name":"regex","project":"1.10.0","compat":"1.10.0","latest":"1.10.0","kind":"Normal","platform":null}]}
+{"crate_name":"goose-cli","dependencies":[{"name":"serde","project":"1.0.100","compat":"1.0.200","latest":"1.0.200","kind":"Normal","platform":null}]}"#;
+
+        let events = parse_outdated(output);
+        assert_eq!(events.len(), 1);
+        assert_eq!(events[0].package, "serde");
+        assert_eq!(events[0].target_version.as_deref(), Some("1.0.200"));
+        assert_eq!(events[0].source, UpdateSource::Outdated);
+    }
+
+    #[test]
+    fn test_remediation_threshold_and_plan() {
+        let events = parse_audit(AUDIT).unwrap();
+        let watch = DependencyWatch::new(".");
+
+        let plan = watch.remediation_for(&events).unwrap();
+        // Two bumps at High or above, then build, then test
+        assert_eq!(plan.steps.len(), 4);
+        assert!(plan.steps[0].description.contains("RUSTSEC-2024-0001"));
+        assert_eq!(plan.steps[2].dependencies, vec![1, 2]);
+        assert_eq!(plan.steps[3].dependencies, vec![3]);
+        assert!(plan.is_valid());
+
+        let watch = watch.with_auto_remediate(None);
+        assert!(watch.remediation_for(&events).is_none());
+    }
+
+    #[test]
+    fn test_lowest_matching() {
+        assert_eq!(
+            lowest_matching(">= 0.3.1, < 0.4"),
+            Some(Version::new(0, 3, 1))
+        );
+        assert_eq!(lowest_matching(">1.2"), Some(Version::new(1, 3, 0)));
+        assert_eq!(lowest_matching("^2.1"), Some(Version::new(2, 1, 0)));
+        assert_eq!(lowest_matching("< 0.2"), None);
+        assert_eq!(lowest_matching("not a range"), None);
+    }
+}
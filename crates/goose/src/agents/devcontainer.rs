@@ -0,0 +1,427 @@
+//! devcontainer.json support for session sandboxes.
+//!
+//! When a workspace ships a dev container definition, goose can run the
+//! session inside it instead of a hand-made container: the image is pulled
+//! or built from the definition, the workspace is mounted at its
+//! `workspaceFolder`, `forwardPorts` are published on localhost and
+//! `containerEnv`/`remoteEnv` are set. The resulting [`Container`] is handed
+//! to the agent, which starts stdio extensions and builtins (the developer
+//! shell included) inside it via `docker exec`.
+//!
+//! Only the single-container form is supported; `dockerComposeFile`
+//! definitions are rejected. Features are not installed.
+
+use super::container::{Container, ContainerConfig};
+use anyhow::{bail, Context, Result};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+use tracing::info;
+use utoipa::ToSchema;
+
+/// Where a workspace's definition is looked for, in order
+pub const DEVCONTAINER_PATHS: &[&str] = &[".devcontainer/devcontainer.json", ".devcontainer.json"];
+
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DevContainer {
+    pub name: Option<String>,
+    pub image: Option<String>,
+    pub build: Option<DevContainerBuild>,
+    /// Older spelling of `build.dockerfile`
+    #[serde(rename = "dockerFile")]
+    pub docker_file: Option<String>,
+    pub docker_compose_file: Option<serde_json::Value>,
+    #[serde(default)]
+    pub forward_ports: Vec<PortSpec>,
+    #[serde(default)]
+    pub container_env: BTreeMap<String, String>,
+    #[serde(default)]
+    pub remote_env: BTreeMap<String, Option<String>>,
+    pub workspace_folder: Option<String>,
+    #[serde(default)]
+    pub mounts: Vec<serde_json::Value>,
+    #[serde(default)]
+    pub run_args: Vec<String>,
+    pub post_create_command: Option<LifecycleCommand>,
+    pub container_user: Option<String>,
+    pub remote_user: Option<String>,
+    /// Directory holding the definition; relative build paths resolve here
+    #[serde(skip)]
+    pub dir: PathBuf,
+}
+
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DevContainerBuild {
+    pub dockerfile: Option<String>,
+    pub context: Option<String>,
+    #[serde(default)]
+    pub args: BTreeMap<String, String>,
+    pub target: Option<String>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+#[serde(untagged)]
+pub enum PortSpec {
+    Port(u16),
+    /// `"host:port"`; only local ports can be published
+    Address(String),
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+#[serde(untagged)]
+pub enum LifecycleCommand {
+    Shell(String),
+    Args(Vec<String>),
+}
+
+/// What was launched, for reporting back to the user.
+#[derive(Debug, Clone, Serialize, ToSchema)]
+pub struct DevContainerLaunch {
+    pub container_id: String,
+    pub image: String,
+    pub workspace_folder: String,
+    /// Ports published on localhost
+    pub ports: Vec<u16>,
+}
+
+/// Removes `//` and `/* */` comments and trailing commas, which
+/// devcontainer.json allows but JSON does not.
+fn strip_jsonc(text: &str) -> String {
+    let mut out = String::with_capacity(text.len());
+    let mut chars = text.chars().peekable();
+    let mut in_string = false;
+    while let Some(c) = chars.next() {
+        if in_string {
+            out.push(c);
+            match c {
+                '\\' => out.extend(chars.next()),
+                '"' => in_string = false,
+                _ => {}
+            }
+            continue;
+        }
+        let next = chars.peek().copied();
+        match (c, next) {
+            ('"', _) => {
+                in_string = true;
+                out.push(c);
+            }
+            ('/', Some('/')) => while chars.next_if(|&n| n != '\n').is_some() {},
+            ('/', Some('*')) => {
+                chars.next();
+                let mut last = ' ';
+                for n in chars.by_ref() {
+                    if last == '*' && n == '/' {
+                        break;
+                    }
+                    last = n;
+                }
+            }
+            (',', _) => {
+                let rest = chars.clone().find(|n| !n.is_whitespace());
+                if !matches!(rest, Some('}') | Some(']')) {
+                    out.push(c);
+                }
+            }
+            _ => out.push(c),
+        }
+    }
+    out
+}
+
+fn shell_quote(arg: &str) -> String {
+    format!("'{}'", arg.replace('\'', r"'\''"))
+}
+
+impl DevContainer {
+    /// The workspace's devcontainer.json, if it has one.
+    pub fn find(workspace: &Path) -> Option<PathBuf> {
+        DEVCONTAINER_PATHS
+            .iter()
+            .map(|p| workspace.join(p))
+            .find(|p| p.is_file())
+    }
+
+    pub fn load(path: &Path) -> Result<Self> {
+        let text = std::fs::read_to_string(path)
+            .with_context(|| format!("Failed to read {}", path.display()))?;
+        let dir = path.parent().unwrap_or(Path::new(".")).to_path_buf();
+        Self::parse(&text, dir).with_context(|| format!("Invalid {}", path.display()))
+    }
+
+    pub fn parse(text: &str, dir: PathBuf) -> Result<Self> {
+        let mut definition: Self = serde_json::from_str(&strip_jsonc(text))?;
+        definition.dir = dir;
+        if definition.docker_compose_file.is_some() {
+            bail!("dockerComposeFile dev containers are not supported");
+        }
+        if definition.image.is_none() && definition.dockerfile().is_none() {
+            bail!("Neither image nor build.dockerfile is set");
+        }
+        Ok(definition)
+    }
+
+    fn dockerfile(&self) -> Option<&str> {
+        self.build
+            .as_ref()
+            .and_then(|b| b.dockerfile.as_deref())
+            .or(self.docker_file.as_deref())
+    }
+
+    /// Where the workspace is mounted, `/workspaces/<name>` by default.
+    pub fn workspace_folder(&self, workspace: &Path) -> String {
+        match &self.workspace_folder {
+            Some(folder) => self.substitute(folder, workspace, ""),
+            None => format!("/workspaces/{}", basename(workspace)),
+        }
+    }
+
+    /// Expands the `${...}` variables devcontainer.json values may use.
+    fn substitute(&self, value: &str, workspace: &Path, container_folder: &str) -> String {
+        let mut result = value
+            .replace("${localWorkspaceFolder}", &workspace.to_string_lossy())
+            .replace("${localWorkspaceFolderBasename}", &basename(workspace))
+            .replace("${containerWorkspaceFolder}", container_folder);
+        while let Some(start) = result.find("${localEnv:") {
+            let Some(len) = result[start..].find('}') else {
+                break;
+            };
+            let spec = &result[start + "${localEnv:".len()..start + len];
+            let (name, default) = spec.split_once(':').unwrap_or((spec, ""));
+            let value = std::env::var(name).unwrap_or_else(|_| default.to_string());
+            result.replace_range(start..start + len + 1, &value);
+        }
+        result
+    }
+
+    /// Local ports to publish on 127.0.0.1.
+    pub fn ports(&self) -> Vec<u16> {
+        self.forward_ports
+            .iter()
+            .filter_map(|spec| match spec {
+                PortSpec::Port(port) => Some(*port),
+                PortSpec::Address(address) => {
+                    let (host, port) = address.rsplit_once(':').unwrap_or(("", address));
+                    if matches!(host, "" | "localhost" | "127.0.0.1") {
+                        port.parse().ok()
+                    } else {
+                        None
+                    }
+                }
+            })
+            .collect()
+    }
+
+    /// Tag for images built from this workspace's Dockerfile.
+    fn image_tag(workspace: &Path) -> String {
+        let digest = Sha256::digest(workspace.to_string_lossy().as_bytes());
+        let hash: String = digest
+            .iter()
+            .take(6)
+            .map(|b| format!("{:02x}", b))
+            .collect();
+        format!("goose-devcontainer-{}", hash)
+    }
+
+    /// The image to run, built with `docker build` when the definition names a
+    /// Dockerfile rather than an image.
+    pub fn resolve_image(&self, workspace: &Path) -> Result<String> {
+        let Some(dockerfile) = self.dockerfile() else {
+            return self.image.clone().context("No image to run");
+        };
+        let build = self.build.clone().unwrap_or_default();
+        let context = self.dir.join(build.context.as_deref().unwrap_or("."));
+        let tag = Self::image_tag(workspace);
+        info!(tag = %tag, dockerfile = %dockerfile, "Building dev container image");
+
+        let mut cmd = Command::new("docker");
+        cmd.arg("build")
+            .args(["-t", &tag])
+            .arg("-f")
+            .arg(self.dir.join(dockerfile));
+        for (key, value) in &build.args {
+            cmd.args(["--build-arg", &format!("{}={}", key, value)]);
+        }
+        if let Some(target) = &build.target {
+            cmd.args(["--target", target]);
+        }
+        let output = cmd
+            .arg(context)
+            .output()
+            .context("Failed to run `docker build` -- is Docker installed and running?")?;
+        if !output.status.success() {
+            bail!(
+                "docker build failed: {}",
+                String::from_utf8_lossy(&output.stderr).trim()
+            );
+        }
+        Ok(tag)
+    }
+
+    /// Container settings for running `image` with this definition.
+    pub fn container_config(&self, workspace: &Path, image: String) -> ContainerConfig {
+        let folder = self.workspace_folder(workspace);
+        let mut extra_args = Vec::new();
+        for mount in &self.mounts {
+            let mount = match mount {
+                serde_json::Value::String(spec) => self.substitute(spec, workspace, &folder),
+                serde_json::Value::Object(fields) => fields
+                    .iter()
+                    .filter_map(|(k, v)| Some(format!("{}={}", k, v.as_str()?)))
+                    .collect::<Vec<_>>()
+                    .join(","),
+                _ => continue,
+            };
+            extra_args.extend(["--mount".to_string(), mount]);
+        }
+        if let Some(user) = self.container_user.as_ref().or(self.remote_user.as_ref()) {
+            extra_args.extend(["--user".to_string(), user.clone()]);
+        }
+        extra_args.extend(
+            self.run_args
+                .iter()
+                .map(|arg| self.substitute(arg, workspace, &folder)),
+        );
+
+        let remote_env = self
+            .remote_env
+            .iter()
+            .filter_map(|(k, v)| Some((k.clone(), v.clone()?)));
+        ContainerConfig {
+            image,
+            workdir: folder.clone(),
+            memory_limit: None,
+            cpu_limit: None,
+            // Toolchains need the network to fetch dependencies
+            network: "bridge".to_string(),
+            volumes: vec![(workspace.to_string_lossy().to_string(), folder.clone())],
+            env_vars: self
+                .container_env
+                .clone()
+                .into_iter()
+                .chain(remote_env)
+                .map(|(k, v)| (k, self.substitute(&v, workspace, &folder)))
+                .collect(),
+            ports: self
+                .ports()
+                .iter()
+                .map(|port| format!("127.0.0.1:{}:{}", port, port))
+                .collect(),
+            extra_args,
+            ..ContainerConfig::default()
+        }
+    }
+
+    /// Builds or pulls the image, starts the container and runs
+    /// `postCreateCommand`. The container is removed when the returned handle
+    /// is dropped.
+    pub fn launch(&self, workspace: &Path) -> Result<(Container, DevContainerLaunch)> {
+        let image = self.resolve_image(workspace)?;
+        let config = self.container_config(workspace, image.clone());
+        let container = Container::create(&config)?;
+
+        if let Some(command) = &self.post_create_command {
+            let command = match command {
+                LifecycleCommand::Shell(command) => command.clone(),
+                LifecycleCommand::Args(args) => args
+                    .iter()
+                    .map(|a| shell_quote(a))
+                    .collect::<Vec<_>>()
+                    .join(" "),
+            };
+            let result = container.exec(&command)?;
+            if !result.success() {
+                bail!(
+                    "postCreateCommand exited with {}: {}",
+                    result.exit_code,
+                    result.stderr.trim()
+                );
+            }
+        }
+
+        let launch = DevContainerLaunch {
+            container_id: container.id().to_string(),
+            image,
+            workspace_folder: config.workdir,
+            ports: self.ports(),
+        };
+        info!(
+            container = %launch.container_id,
+            name = ?self.name,
+            "Dev container started"
+        );
+        Ok((container, launch))
+    }
+}
+
+fn basename(workspace: &Path) -> String {
+    workspace
+        .file_name()
+        .map(|n| n.to_string_lossy().to_string())
+        .unwrap_or_else(|| "workspace".to_string())
+}
+
+/// Launches the dev container defined in `workspace`, if there is one.
+pub fn launch_for_workspace(workspace: &Path) -> Result<Option<(Container, DevContainerLaunch)>> {
+    let Some(path) = DevContainer::find(workspace) else {
+        return Ok(None);
+    };
+    DevContainer::load(&path)?.launch(workspace).map(Some)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_jsonc_definition() {
+        let text = r#"{
+            // Rust toolchain
+            "name": "api",
+            "image": "mcr.microsoft.com/devcontainers/rust:1", /* pinned */
+            "forwardPorts": [3000, "localhost:5432", "db:6379",],
+            "containerEnv": { "URL": "http://x//y", "ROOT": "${containerWorkspaceFolder}" },
+            "remoteEnv": { "UNSET": null },
+            "runArgs": ["--cap-add=SYS_PTRACE"],
+            "postCreateCommand": ["cargo", "fetch"],
+        }"#;
+        let definition =
+            DevContainer::parse(text, PathBuf::from("/src/api/.devcontainer")).unwrap();
+        assert_eq!(definition.name.as_deref(), Some("api"));
+        assert_eq!(definition.ports(), vec![3000, 5432]);
+        assert_eq!(
+            definition.post_create_command,
+            Some(LifecycleCommand::Args(vec![
+                "cargo".to_string(),
+                "fetch".to_string()
+            ]))
+        );
+
+        let workspace = Path::new("/src/api");
+        let config = definition.container_config(workspace, "rust".to_string());
+        assert_eq!(config.workdir, "/workspaces/api");
+        assert_eq!(
+            config.volumes,
+            vec![("/src/api".to_string(), "/workspaces/api".to_string())]
+        );
+        assert_eq!(
+            config.env_vars,
+            vec![
+                ("ROOT".to_string(), "/workspaces/api".to_string()),
+                ("URL".to_string(), "http://x//y".to_string()),
+            ]
+        );
+        assert_eq!(
+            config.ports,
+            vec!["127.0.0.1:3000:3000", "127.0.0.1:5432:5432"]
+        );
+        assert_eq!(config.extra_args, vec!["--cap-add=SYS_PTRACE"]);
+
+        let compose = r#"{ "dockerComposeFile": "compose.yml", "service": "app" }"#;
+        assert!(DevContainer::parse(compose, PathBuf::new()).is_err());
+    }
+}
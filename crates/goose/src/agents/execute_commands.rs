@@ -1,42 +1,327 @@
 use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
 
 use anyhow::{anyhow, Result};
 
+use crate::action_required_manager::{ActionRequiredManager, Elicitation, PendingResponse};
+use crate::agents::command_registry::{
+    split_command, ArgSpec, ArgType, CommandArgs, CommandError, CommandRegistry, CommandSpec,
+};
+use crate::config::profiles::{get_profile, list_profiles, ActiveProfileState};
+use crate::config::Config;
 use crate::context_mgmt::compact_messages;
-use crate::conversation::message::{Message, SystemNotificationType};
-use crate::recipe::build_recipe::build_recipe_from_template_with_positional_params;
-
-use super::Agent;
+use crate::conversation::message::{
+    ActionRequiredData, Message, MessageContent, SystemNotificationType,
+};
+use crate::model::ModelConfig;
+use crate::recipe::build_recipe::{build_recipe_from_template, RecipeError};
+use crate::recipe::parameter_form::{form_schema, values_from_form};
+use crate::recipe::RecipeParameter;
+use crate::session::extension_data::ExtensionState;
+
+use super::core_registry::{self, CoreSettings};
+use super::core_selector::{self, parse_category, CoreSelector, CATEGORIES};
+use super::{Agent, ExecutionMode};
 
 pub const COMPACT_TRIGGERS: &[&str] =
     &["/compact", "Please compact this conversation", "/summarize"];
 
-pub struct CommandDef {
-    pub name: &'static str,
-    pub description: &'static str,
+const RECIPE_PARAMETER_TIMEOUT: Duration = Duration::from_secs(30 * 60);
+
+fn describe_core(core: ExecutionMode, settings: &CoreSettings) -> String {
+    let state = if settings.enabled {
+        "enabled"
+    } else {
+        "disabled"
+    };
+    let mut parts = vec![state.to_string()];
+    if let Some(limit) = settings.max_concurrent_turns {
+        parts.push(format!("at most {} concurrent turns", limit));
+    }
+    if let Some(model) = &settings.model {
+        parts.push(format!("model {}", model));
+    }
+    if let Some(budget) = settings.budget_usd {
+        parts.push(format!("budget ${:.2}", budget));
+    }
+    format!("- **{}**: {}", core, parts.join(", "))
+}
+
+/// `/core config [<core> [key=value ...]]`: shows or changes core settings.
+fn core_config(target: &str) -> Result<String> {
+    let mut words = target.split_whitespace();
+    let Some(name) = words.next() else {
+        let mut all: Vec<_> = core_registry::all_settings().into_iter().collect();
+        all.sort_by_key(|(core, _)| core.to_string());
+        let lines: Vec<_> = all
+            .iter()
+            .map(|(core, settings)| describe_core(*core, settings))
+            .collect();
+        return Ok(lines.join("\n"));
+    };
+    let core = match name {
+        "freeform" => ExecutionMode::Freeform,
+        "structured" => ExecutionMode::Structured,
+        _ => return Ok(format!("Unknown core '{}'", name)),
+    };
+
+    let mut settings = core_registry::settings(core);
+    let mut changed = false;
+    for pair in words {
+        let Some((key, value)) = pair.split_once('=') else {
+            return Ok(format!("Expected key=value, got '{}'", pair));
+        };
+        if let Err(e) = settings.apply(key, value) {
+            return Ok(e);
+        }
+        changed = true;
+    }
+    if changed {
+        core_registry::set_settings(core, settings.clone())?;
+    }
+    Ok(describe_core(core, &settings))
+}
+
+/// `/swarm [team <preset> <task> | teams | outcome <preset> passed|failed]`.
+/// Starting a team returns the kickoff prompt as a user message for the
+/// agent to run.
+#[cfg(feature = "memory")]
+fn swarm_command(args: &CommandArgs) -> Result<Message> {
+    use super::swarm_presets::{record_outcome, TeamExperience, TeamLibrary};
+
+    let library = TeamLibrary::load_default();
+    let target = args.str("target").unwrap_or_default().trim();
+    let (name, rest) = match target.split_once(char::is_whitespace) {
+        Some((name, rest)) => (name, rest.trim()),
+        None => (target, ""),
+    };
+    let text = match args.str("action") {
+        None | Some("teams") => {
+            let experience = TeamExperience::load_default();
+            let lines: Vec<String> = library
+                .list()
+                .iter()
+                .map(|preset| {
+                    let stats = experience.stats(&preset.name);
+                    format!(
+                        "- **{}** ({} roles, {:?}): {} ({} runs, {} passed, {} failed)",
+                        preset.name,
+                        preset.roles.len(),
+                        preset.topology,
+                        preset.description,
+                        stats.runs,
+                        stats.successes,
+                        stats.failures
+                    )
+                })
+                .collect();
+            format!(
+                "Team presets; start one with `/swarm team <name> <task>`:\n\n{}",
+                lines.join("\n")
+            )
+        }
+        Some("team") => match library.get(name) {
+            None => format!(
+                "No team preset named '{}'; list them with `/swarm teams`",
+                name
+            ),
+            Some(_) if rest.is_empty() => {
+                format!("`/swarm team {}` needs a task to work on", name)
+            }
+            Some(preset) => {
+                let swarm = preset.instantiate();
+                let mut experience = TeamExperience::load_default();
+                experience.record_run(&preset.name);
+                experience.save()?;
+                tracing::info!(
+                    "Starting team {} with {} agents",
+                    preset.name,
+                    swarm.agent_count()
+                );
+                return Ok(Message::user().with_text(preset.kickoff_prompt(rest)));
+            }
+        },
+        Some(_) => {
+            let passed = match rest {
+                "passed" | "pass" => true,
+                "failed" | "fail" => false,
+                _ => {
+                    return Ok(Message::assistant()
+                        .with_text("Usage: `/swarm outcome <preset> passed|failed`"))
+                }
+            };
+            match record_outcome(name, passed) {
+                Ok(stats) => format!(
+                    "Recorded for {}: {} runs, {} passed, {} failed",
+                    name, stats.runs, stats.successes, stats.failures
+                ),
+                Err(e) => e.to_string(),
+            }
+        }
+    };
+    Ok(Message::assistant().with_text(text))
 }
 
-static COMMANDS: &[CommandDef] = &[
-    CommandDef {
-        name: "prompts",
-        description: "List available prompts, optionally filtered by extension",
-    },
-    CommandDef {
-        name: "prompt",
-        description: "Execute a prompt or show its info with --info",
-    },
-    CommandDef {
-        name: "compact",
-        description: "Compact the conversation history",
-    },
-    CommandDef {
-        name: "clear",
-        description: "Clear the conversation history",
-    },
-];
-
-pub fn list_commands() -> &'static [CommandDef] {
-    COMMANDS
+/// A recipe command waiting for the user to fill in its parameter form.
+pub(super) struct RecipeParameterRequest {
+    command: String,
+    recipe_content: String,
+    recipe_dir: PathBuf,
+    /// Values given on the command line
+    values: Vec<(String, String)>,
+    /// Parameters shown in the form
+    parameters: Vec<RecipeParameter>,
+    response: PendingResponse,
+}
+
+/// Built-in commands and their arguments. Custom commands from the config are
+/// layered on top by [`CommandRegistry::load`].
+pub fn builtin_commands() -> Vec<CommandSpec> {
+    let core_modes = ArgType::Choice {
+        values: vec![
+            "freeform".to_string(),
+            "structured".to_string(),
+            "auto".to_string(),
+            "unpin".to_string(),
+            "config".to_string(),
+        ],
+    };
+    #[allow(unused_mut)]
+    let mut commands = vec![
+        CommandSpec::builtin("help", "List available commands", vec![]).with_aliases(&["?"]),
+        CommandSpec::builtin(
+            "prompts",
+            "List available prompts, optionally filtered by extension",
+            vec![ArgSpec::optional("extension", ArgType::String, "")],
+        ),
+        CommandSpec::builtin(
+            "prompt",
+            "Execute a prompt or show its info with --info",
+            vec![
+                ArgSpec::required("name", ArgType::String, ""),
+                ArgSpec::optional("arguments", ArgType::Text, "`--info` or key=value pairs"),
+            ],
+        ),
+        CommandSpec::builtin("compact", "Compact the conversation history", vec![]),
+        CommandSpec::builtin("clear", "Clear the conversation history", vec![]),
+        CommandSpec::builtin(
+            "profile",
+            "Show settings profiles or switch to one, e.g. /profile autonomous",
+            vec![ArgSpec::optional("name", ArgType::String, "")],
+        ),
+        CommandSpec::builtin(
+            "core",
+            "Show or switch the execution core; with a task category, pin it or pick by experience",
+            vec![
+                ArgSpec::optional("mode", core_modes, ""),
+                ArgSpec::optional(
+                    "target",
+                    ArgType::Text,
+                    "A task category such as code_fix; for config, a core and key=value settings",
+                ),
+            ],
+        ),
+        CommandSpec::builtin(
+            "budget",
+            "Show spend or set the session budget in USD; 0 removes the limit",
+            vec![ArgSpec::optional("limit", ArgType::Number, "")],
+        ),
+        CommandSpec::builtin(
+            "critique",
+            "Run a self-critique of the work in this session",
+            vec![ArgSpec::optional(
+                "task",
+                ArgType::Text,
+                "What the work was meant to achieve",
+            )],
+        ),
+        CommandSpec::builtin(
+            "diagnose",
+            "Report the core, budget, extensions, guardrails and anything degraded",
+            vec![],
+        )
+        .with_aliases(&["status"]),
+    ];
+    #[cfg(feature = "memory")]
+    {
+        let subcommand = || vec![ArgSpec::optional("args", ArgType::Text, "")];
+        commands.extend([
+            CommandSpec::builtin(
+                "memory",
+                "Show memory stats, clear or save memories",
+                subcommand(),
+            )
+            .with_aliases(&["memories"]),
+            CommandSpec::builtin(
+                "pause",
+                "Pause at the next turn boundary or tool call",
+                vec![],
+            ),
+            CommandSpec::builtin(
+                "resume",
+                "Resume a paused agent, optionally with feedback",
+                vec![ArgSpec::optional("feedback", ArgType::Text, "")],
+            ),
+            CommandSpec::builtin("breakpoint", "Manage breakpoints", subcommand())
+                .with_aliases(&["bp"]),
+            CommandSpec::builtin("inspect", "Inspect the agent state", vec![]),
+            CommandSpec::builtin("plan", "Show or edit the current plan", subcommand()),
+            CommandSpec::builtin(
+                "bookmark",
+                "Save, list or restore session bookmarks",
+                subcommand(),
+            )
+            .with_aliases(&["bm", "checkpoint"]),
+            CommandSpec::builtin(
+                "swarm",
+                "Run a team preset on a task, list presets, or record how a team run went",
+                vec![
+                    ArgSpec::optional(
+                        "action",
+                        ArgType::Choice {
+                            values: vec![
+                                "team".to_string(),
+                                "teams".to_string(),
+                                "outcome".to_string(),
+                            ],
+                        },
+                        "",
+                    ),
+                    ArgSpec::optional(
+                        "target",
+                        ArgType::Text,
+                        "A preset name, then the task for team or passed|failed for outcome",
+                    ),
+                ],
+            ),
+        ]);
+    }
+    commands
+}
+
+pub fn list_commands() -> Vec<CommandSpec> {
+    CommandRegistry::load(builtin_commands()).into_commands()
+}
+
+fn usage_error(spec: &CommandSpec, error: &CommandError) -> Message {
+    Message::assistant().with_text(format!("{}\n\nUsage: `{}`", error, spec.usage()))
+}
+
+/// `/help` output: registered commands followed by recipe shortcuts.
+fn help_text(registry: &CommandRegistry) -> String {
+    let mut help = registry.help();
+    let recipes = crate::slash_commands::list_commands();
+    if !recipes.is_empty() {
+        help.push_str("## Recipe Commands\n\n");
+        for mapping in recipes {
+            help.push_str(&format!(
+                "- `/{}` - {}\n",
+                mapping.command, mapping.recipe_path
+            ));
+        }
+    }
+    help
 }
 
 impl Agent {
@@ -51,48 +336,275 @@ impl Agent {
             trimmed = COMPACT_TRIGGERS[0].to_string();
         }
 
-        if !trimmed.starts_with('/') {
+        let Some((command, params_str)) = split_command(&trimmed) else {
             return Ok(None);
+        };
+
+        let registry = CommandRegistry::load(builtin_commands());
+        let Some(spec) = registry.get(&command) else {
+            return self
+                .handle_recipe_command(&command, params_str, session_id)
+                .await;
+        };
+        let args = match spec.parse_args(params_str) {
+            Ok(args) => args,
+            Err(e) => return Ok(Some(usage_error(spec, &e))),
+        };
+
+        if spec.is_custom() {
+            return self
+                .run_custom_command(&registry, spec, &args, session_id)
+                .await;
         }
+        self.dispatch_command(&registry, &spec.name, params_str, &args, session_id)
+            .await
+    }
 
-        let command_str = trimmed.strip_prefix('/').unwrap_or(&trimmed);
-        let (command, params_str) = command_str
-            .split_once(' ')
-            .map(|(cmd, p)| (cmd, p.trim()))
-            .unwrap_or((command_str, ""));
+    /// Runs the built-in commands a custom command expands to, stopping at
+    /// the first step whose arguments do not validate.
+    async fn run_custom_command(
+        &self,
+        registry: &CommandRegistry,
+        spec: &CommandSpec,
+        args: &CommandArgs,
+        session_id: &str,
+    ) -> Result<Option<Message>> {
+        let mut outputs = Vec::new();
+        for step in spec.expand(args) {
+            let Some((step_spec, params)) = split_command(&step)
+                .and_then(|(name, params)| registry.get(&name).map(|spec| (spec, params)))
+            else {
+                return Err(anyhow!("/{} runs unknown command '{}'", spec.name, step));
+            };
+            let step_args = match step_spec.parse_args(params) {
+                Ok(step_args) => step_args,
+                Err(e) => {
+                    outputs.push(format!("`{}` failed: {}", step, e));
+                    break;
+                }
+            };
+            if let Some(message) = self
+                .dispatch_command(registry, &step_spec.name, params, &step_args, session_id)
+                .await?
+            {
+                outputs.push(message.as_concat_text());
+            }
+        }
+        Ok(Some(Message::assistant().with_text(outputs.join("\n\n"))))
+    }
 
-        let params: Vec<&str> = if params_str.is_empty() {
-            vec![]
-        } else {
-            params_str.split_whitespace().collect()
-        };
+    async fn dispatch_command(
+        &self,
+        registry: &CommandRegistry,
+        command: &str,
+        params_str: &str,
+        args: &CommandArgs,
+        session_id: &str,
+    ) -> Result<Option<Message>> {
+        let params: Vec<&str> = params_str.split_whitespace().collect();
 
         match command {
+            "help" => Ok(Some(Message::assistant().with_text(help_text(registry)))),
             "prompts" => self.handle_prompts_command(&params, session_id).await,
             "prompt" => self.handle_prompt_command(&params, session_id).await,
             "compact" => self.handle_compact_command(session_id).await,
             "clear" => self.handle_clear_command(session_id).await,
+            "profile" => self.handle_profile_command(&params, session_id).await,
+            "core" => self.handle_core_command(args, session_id).await,
+            "budget" => self.handle_budget_command(args).await,
+            "critique" => self.handle_critique_command(args, session_id).await,
+            "diagnose" => Ok(Some(
+                Message::assistant().with_text(self.self_report(session_id).await.format_display()),
+            )),
             #[cfg(feature = "memory")]
-            "memory" | "memories" => self.handle_memory_command(&params, session_id).await,
-            #[cfg(feature = "memory")]
-            "pause" => self.handle_hitl_command("pause", &params, session_id).await,
-            #[cfg(feature = "memory")]
-            "resume" => self.handle_hitl_command("resume", &params, session_id).await,
-            #[cfg(feature = "memory")]
-            "breakpoint" | "bp" => self.handle_hitl_command("breakpoint", &params, session_id).await,
+            "memory" => self.handle_memory_command(&params, session_id).await,
             #[cfg(feature = "memory")]
-            "inspect" => self.handle_hitl_command("inspect", &params, session_id).await,
+            "pause" | "resume" | "breakpoint" | "inspect" | "plan" => {
+                self.handle_hitl_command(command, &params, session_id).await
+            }
             #[cfg(feature = "memory")]
-            "plan" => self.handle_hitl_command("plan", &params, session_id).await,
+            "bookmark" => self.handle_bookmark_command(&params, session_id).await,
             #[cfg(feature = "memory")]
-            "bookmark" | "bm" | "checkpoint" => {
-                self.handle_bookmark_command(&params, session_id).await
+            "swarm" => swarm_command(args).map(Some),
+            _ => Ok(None),
+        }
+    }
+
+    async fn handle_core_command(
+        &self,
+        args: &CommandArgs,
+        session_id: &str,
+    ) -> Result<Option<Message>> {
+        let Some(mode) = args.str("mode") else {
+            return Ok(Some(Message::assistant().with_text(format!(
+                "Current core: **{}**. Switch with `/core freeform|structured`, or pick by \
+                 experience with `/core auto <category>`.",
+                self.execution_mode().await
+            ))));
+        };
+        let target = args.str("target").unwrap_or_default().trim();
+        if mode == "config" {
+            return Ok(Some(Message::assistant().with_text(core_config(target)?)));
+        }
+
+        let category = match target {
+            "" => None,
+            name => match parse_category(name) {
+                Some(category) => Some(category),
+                None => {
+                    return Ok(Some(Message::assistant().with_text(format!(
+                        "Unknown task category '{}'. Use one of: {}",
+                        name,
+                        CATEGORIES
+                            .iter()
+                            .map(ToString::to_string)
+                            .collect::<Vec<_>>()
+                            .join(", ")
+                    ))))
+                }
+            },
+        };
+
+        let text = match (mode, category) {
+            ("auto" | "unpin", None) => {
+                format!(
+                    "`/core {}` needs a task category, e.g. `/core {} code_fix`",
+                    mode, mode
+                )
             }
-            _ => {
-                self.handle_recipe_command(command, params_str, session_id)
-                    .await
+            ("unpin", Some(category)) => {
+                core_selector::set_pin(category, None)?;
+                format!("Removed the core pin for {}", category)
+            }
+            ("auto", Some(category)) => {
+                let selector = CoreSelector::from_config();
+                let mode = selector.select(category);
+                let score = selector.score(mode, category);
+                let reason = format!(
+                    "picked for {} with score {:.2} from {} runs{}",
+                    category,
+                    score.blended,
+                    score.attempts,
+                    if score.pinned { ", pinned" } else { "" }
+                );
+                match self.switch_core(mode, reason, session_id).await? {
+                    Some(refused) => refused,
+                    None => format!(
+                        "Switched to the **{}** core for {} (score {:.2} from {} runs{})",
+                        mode,
+                        category,
+                        score.blended,
+                        score.attempts,
+                        if score.pinned { ", pinned" } else { "" }
+                    ),
+                }
+            }
+            (mode, category) => {
+                let mode = match mode {
+                    "structured" => ExecutionMode::Structured,
+                    _ => ExecutionMode::Freeform,
+                };
+                let reason = match category {
+                    Some(category) => format!("pinned for {} with /core", category),
+                    None => "chosen with /core".to_string(),
+                };
+                match (self.switch_core(mode, reason, session_id).await?, category) {
+                    (Some(refused), _) => refused,
+                    (None, Some(category)) => {
+                        core_selector::set_pin(category, Some(mode))?;
+                        format!(
+                            "Switched to and pinned the **{}** core for {}",
+                            mode, category
+                        )
+                    }
+                    (None, None) => format!("Switched to the **{}** core", mode),
+                }
+            }
+        };
+        Ok(Some(Message::assistant().with_text(text)))
+    }
+
+    /// Switches to `core` and applies its model and budget. Returns why the
+    /// switch was refused, if it was.
+    async fn switch_core(
+        &self,
+        core: ExecutionMode,
+        reason: String,
+        session_id: &str,
+    ) -> Result<Option<String>> {
+        let settings = core_registry::settings(core);
+        if !settings.enabled {
+            return Ok(Some(format!(
+                "The **{}** core is disabled; enable it with `/core config {} enabled=true`",
+                core, core
+            )));
+        }
+        self.set_core(core, reason).await;
+        if let Some(limit) = settings.budget_usd {
+            self.cost_tracker().set_budget(limit).await;
+        }
+        if let Some(model) = settings.model {
+            let provider = self.provider().await?;
+            if provider.get_model_config().model_name != model {
+                let provider =
+                    crate::providers::create(provider.get_name(), ModelConfig::new(&model)?)
+                        .await?;
+                self.update_provider(provider, session_id).await?;
             }
         }
+        Ok(None)
+    }
+
+    async fn handle_budget_command(&self, args: &CommandArgs) -> Result<Option<Message>> {
+        let tracker = self.cost_tracker();
+        let text = match args.f64("limit") {
+            None => {
+                let cost = tracker.get_cost().await;
+                match tracker.remaining_budget().await {
+                    Some(remaining) => format!(
+                        "Spent ${:.2}, ${:.2} of the budget remaining",
+                        cost, remaining
+                    ),
+                    None => format!("Spent ${:.2}, no budget set", cost),
+                }
+            }
+            Some(limit) if limit <= 0.0 => {
+                tracker.clear_budget().await;
+                "Budget limit removed".to_string()
+            }
+            Some(limit) => {
+                tracker.set_budget(limit).await;
+                format!("Budget set to ${:.2}", limit)
+            }
+        };
+        Ok(Some(Message::assistant().with_text(text)))
+    }
+
+    async fn handle_critique_command(
+        &self,
+        args: &CommandArgs,
+        session_id: &str,
+    ) -> Result<Option<Message>> {
+        let task = args
+            .str("task")
+            .unwrap_or("Review the work done in this session");
+        let session = self
+            .config
+            .session_manager
+            .get_session(session_id, false)
+            .await?;
+        let critique = self
+            .self_critique(
+                task,
+                Vec::new(),
+                &session.working_dir.display().to_string(),
+                None,
+                None,
+            )
+            .await?;
+        Ok(Some(
+            Message::assistant().with_text(critique.format_display()),
+        ))
     }
 
     async fn handle_compact_command(&self, session_id: &str) -> Result<Option<Message>> {
@@ -145,6 +657,56 @@ impl Agent {
         )))
     }
 
+    async fn handle_profile_command(
+        &self,
+        params: &[&str],
+        session_id: &str,
+    ) -> Result<Option<Message>> {
+        let config = Config::global();
+        let Some(name) = params.first() else {
+            let session = self
+                .config
+                .session_manager
+                .get_session(session_id, false)
+                .await?;
+            let active = ActiveProfileState::from_extension_data(&session.extension_data)
+                .and_then(|state| state.active);
+
+            let mut output = String::from("Settings profiles:\n");
+            for profile in list_profiles(config) {
+                let marker = if active.as_deref() == Some(profile.name.as_str()) {
+                    " (active)"
+                } else {
+                    ""
+                };
+                output.push_str(&format!(
+                    "- **{}**{}: {}\n  {}\n",
+                    profile.name,
+                    marker,
+                    profile.description,
+                    profile.summary()
+                ));
+            }
+            output.push_str("\nSwitch with `/profile <name>`.");
+            return Ok(Some(Message::assistant().with_text(output)));
+        };
+
+        let Some(profile) = get_profile(config, name) else {
+            return Ok(Some(Message::assistant().with_text(format!(
+                "Unknown profile '{}'. Run `/profile` to list available profiles.",
+                name
+            ))));
+        };
+
+        self.apply_settings_profile(session_id, &profile, "slash_command")
+            .await?;
+        Ok(Some(Message::assistant().with_text(format!(
+            "Switched to the **{}** profile: {}",
+            profile.name,
+            profile.summary()
+        ))))
+    }
+
     async fn handle_prompts_command(
         &self,
         params: &[&str],
@@ -298,7 +860,7 @@ impl Agent {
         &self,
         command: &str,
         params_str: &str,
-        _session_id: &str,
+        session_id: &str,
     ) -> Result<Option<Message>> {
         let full_command = format!("/{}", command);
         let recipe_path = match crate::slash_commands::get_recipe_for_command(&full_command) {
@@ -315,87 +877,139 @@ impl Agent {
 
         let recipe_dir = recipe_path
             .parent()
-            .ok_or_else(|| anyhow!("Recipe path has no parent directory"))?;
+            .ok_or_else(|| anyhow!("Recipe path has no parent directory"))?
+            .to_path_buf();
 
         let recipe_dir_str = recipe_dir.display().to_string();
-        let validation_result =
-            crate::recipe::validate_recipe::validate_recipe_template_from_content(
-                &recipe_content,
-                Some(recipe_dir_str),
-            )
-            .map_err(|e| anyhow!("Failed to parse recipe: {}", e))?;
+        let parameters = crate::recipe::validate_recipe::validate_recipe_template_from_content(
+            &recipe_content,
+            Some(recipe_dir_str),
+        )
+        .map_err(|e| anyhow!("Failed to parse recipe: {}", e))?
+        .parameters
+        .unwrap_or_default();
+
+        // The text after the command fills the first parameter; anything
+        // still missing is asked for with a form.
+        let mut values = Vec::new();
+        if let (false, Some(first)) = (params_str.is_empty(), parameters.first()) {
+            values.push((first.key.clone(), params_str.to_string()));
+        }
+        let unfilled: Vec<RecipeParameter> = parameters
+            .into_iter()
+            .filter(|p| !values.iter().any(|(key, _)| key == &p.key))
+            .collect();
 
-        let param_values: Vec<String> = if params_str.is_empty() {
-            vec![]
-        } else {
-            let params_without_default = validation_result
-                .parameters
-                .as_ref()
-                .map(|params| params.iter().filter(|p| p.default.is_none()).count())
-                .unwrap_or(0);
-
-            if params_without_default <= 1 {
-                vec![params_str.to_string()]
-            } else {
-                let param_names: Vec<String> = validation_result
-                    .parameters
-                    .as_ref()
-                    .map(|params| {
-                        params
-                            .iter()
-                            .filter(|p| p.default.is_none())
-                            .map(|p| p.key.clone())
-                            .collect()
-                    })
-                    .unwrap_or_default();
-
-                let error_message = format!(
-                    "The /{} recipe requires {} parameters: {}.\n\n\
-                    Slash command recipes only support 1 parameter.\n\n\
-                    **To use this recipe:**\n\
-                    • **CLI:** `goose run --recipe {} {}`\n\
-                    • **Desktop:** Launch from the recipes sidebar to fill in parameters",
-                    command,
-                    params_without_default,
-                    param_names
-                        .iter()
-                        .map(|name| format!("**{}**", name))
-                        .collect::<Vec<_>>()
-                        .join(", "),
-                    command,
-                    param_names
-                        .iter()
-                        .map(|name| format!("--params {}=\"...\"", name))
-                        .collect::<Vec<_>>()
-                        .join(" ")
-                );
+        if unfilled.iter().any(|p| p.default.is_none()) {
+            let message = format!("The /{} recipe needs a few more details.", command);
+            let elicitation =
+                Elicitation::new("recipe_parameters", message, form_schema(&unfilled))
+                    .in_session(Some(session_id.to_string()))
+                    .with_timeout(RECIPE_PARAMETER_TIMEOUT);
+            let (request, response) = ActionRequiredManager::global().request(elicitation).await;
+            self.recipe_parameter_requests.lock().await.insert(
+                response.id().to_string(),
+                RecipeParameterRequest {
+                    command: command.to_string(),
+                    recipe_content,
+                    recipe_dir,
+                    values,
+                    parameters: unfilled,
+                    response,
+                },
+            );
+            return Ok(Some(request));
+        }
+
+        self.run_recipe(command, recipe_content, &recipe_dir, values)
+            .await
+            .map(Some)
+    }
 
-                return Err(anyhow!(error_message));
+    /// Claims the parameter form in a command response, if it is one.
+    pub(super) async fn take_recipe_parameter_request(
+        &self,
+        response: &Message,
+    ) -> Option<RecipeParameterRequest> {
+        let id = response.content.iter().find_map(|content| match content {
+            MessageContent::ActionRequired(action) => match &action.data {
+                ActionRequiredData::Elicitation { id, .. } => Some(id.clone()),
+                _ => None,
+            },
+            _ => None,
+        })?;
+        self.recipe_parameter_requests.lock().await.remove(&id)
+    }
+
+    /// Waits for the parameter form and runs the recipe with its values.
+    /// Problems are reported as an assistant message.
+    pub(super) async fn complete_recipe_parameters(
+        &self,
+        request: RecipeParameterRequest,
+    ) -> Result<Message> {
+        let RecipeParameterRequest {
+            command,
+            recipe_content,
+            recipe_dir,
+            mut values,
+            parameters,
+            response,
+        } = request;
+
+        let user_data = match response.wait(RECIPE_PARAMETER_TIMEOUT).await {
+            Ok(user_data) => user_data,
+            Err(e) => {
+                return Ok(Message::assistant()
+                    .with_text(format!("The /{} recipe was not run: {}", command, e)))
             }
         };
+        match values_from_form(&parameters, &user_data) {
+            Ok(submitted) => values.extend(submitted),
+            Err(errors) => {
+                return Ok(Message::assistant().with_text(format!(
+                    "The /{} recipe was not run:\n- {}",
+                    command,
+                    errors.join("\n- ")
+                )))
+            }
+        }
 
-        let param_values_len = param_values.len();
+        self.run_recipe(&command, recipe_content, &recipe_dir, values)
+            .await
+    }
 
-        let recipe = match build_recipe_from_template_with_positional_params(
+    async fn run_recipe(
+        &self,
+        command: &str,
+        recipe_content: String,
+        recipe_dir: &Path,
+        values: Vec<(String, String)>,
+    ) -> Result<Message> {
+        let recipe = match build_recipe_from_template(
             recipe_content,
             recipe_dir,
-            param_values,
+            values,
             None::<fn(&str, &str) -> Result<String>>,
         ) {
             Ok(recipe) => recipe,
-            Err(crate::recipe::build_recipe::RecipeError::MissingParams { parameters }) => {
-                return Ok(Some(Message::assistant().with_text(format!(
-                    "Recipe requires {} parameter(s): {}. Provided: {}",
+            Err(RecipeError::MissingParams { parameters }) => {
+                return Ok(Message::assistant().with_text(format!(
+                    "The /{} recipe requires {} parameter(s): {}",
+                    command,
                     parameters.len(),
-                    parameters.join(", "),
-                    param_values_len
-                ))));
+                    parameters.join(", ")
+                )));
             }
             Err(e) => return Err(anyhow!("Failed to build recipe: {}", e)),
         };
 
-        self.apply_recipe_components(recipe.sub_recipes.clone(), recipe.response.clone(), true)
-            .await;
+        self.apply_recipe_components(
+            recipe.sub_recipes.clone(),
+            recipe.response.clone(),
+            recipe.validation.clone(),
+            true,
+        )
+        .await;
 
         let prompt = [recipe.instructions.as_deref(), recipe.prompt.as_deref()]
             .into_iter()
@@ -403,7 +1017,7 @@ impl Agent {
             .collect::<Vec<_>>()
             .join("\n\n");
 
-        Ok(Some(Message::user().with_text(prompt)))
+        Ok(Message::user().with_text(prompt))
     }
 
     /// Handle /memory command — show stats, list memories, or clear
@@ -1,7 +1,11 @@
 use crate::agents::apps_extension;
+use crate::agents::browser_extension;
 use crate::agents::chatrecall_extension;
 use crate::agents::code_execution_extension;
+use crate::agents::data_extension;
+use crate::agents::email_extension;
 use crate::agents::extension_manager_extension;
+use crate::agents::issues_extension;
 use crate::agents::skills_extension;
 use crate::agents::todo_extension;
 use crate::agents::tom_extension;
@@ -120,6 +124,55 @@ pub static PLATFORM_EXTENSIONS: Lazy<HashMap<&'static str, PlatformExtensionDef>
             },
         );
 
+        map.insert(
+            browser_extension::EXTENSION_NAME,
+            PlatformExtensionDef {
+                name: browser_extension::EXTENSION_NAME,
+                display_name: "Browser",
+                description:
+                    "Navigate, click, extract and fill forms in a Playwright browser, with every step recorded for replay",
+                default_enabled: false,
+                client_factory: |ctx| {
+                    Box::new(browser_extension::BrowserClient::new(ctx).unwrap())
+                },
+            },
+        );
+
+        map.insert(
+            data_extension::EXTENSION_NAME,
+            PlatformExtensionDef {
+                name: data_extension::EXTENSION_NAME,
+                display_name: "Data",
+                description:
+                    "Query CSV and SQLite files in the workspace with SQL and chart the results",
+                default_enabled: false,
+                client_factory: |ctx| Box::new(data_extension::DataClient::new(ctx).unwrap()),
+            },
+        );
+
+        map.insert(
+            email_extension::EXTENSION_NAME,
+            PlatformExtensionDef {
+                name: email_extension::EXTENSION_NAME,
+                display_name: "Email",
+                description:
+                    "Search, read and draft email; sending asks for your approval every time",
+                default_enabled: false,
+                client_factory: |ctx| Box::new(email_extension::EmailClient::new(ctx).unwrap()),
+            },
+        );
+
+        map.insert(
+            issues_extension::EXTENSION_NAME,
+            PlatformExtensionDef {
+                name: issues_extension::EXTENSION_NAME,
+                display_name: "Issues",
+                description: "Search, open and update tickets on GitHub Issues and Jira",
+                default_enabled: false,
+                client_factory: |ctx| Box::new(issues_extension::IssuesClient::new(ctx).unwrap()),
+            },
+        );
+
         map.insert(
             tom_extension::EXTENSION_NAME,
             PlatformExtensionDef {
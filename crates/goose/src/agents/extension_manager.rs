@@ -11,11 +11,13 @@ use rmcp::transport::streamable_http_client::{
 use rmcp::transport::{
     ConfigureCommandExt, DynamicTransportError, StreamableHttpClientTransport, TokioChildProcess,
 };
+use std::collections::hash_map::DefaultHasher;
 use std::collections::HashMap;
+use std::hash::{Hash, Hasher};
 use std::path::PathBuf;
 use std::process::Stdio;
 use std::sync::atomic::{AtomicU64, Ordering};
-use std::sync::Arc;
+use std::sync::{Arc, RwLock};
 use std::time::Duration;
 use tempfile::{tempdir, TempDir};
 use tokio::io::AsyncReadExt;
@@ -25,6 +27,7 @@ use tokio_stream::wrappers::ReceiverStream;
 use tokio_util::sync::CancellationToken;
 use tracing::{error, warn};
 
+use super::capability_grant::CapabilityGrant;
 use super::container::Container;
 use super::extension::{
     ExtensionConfig, ExtensionError, ExtensionInfo, ExtensionResult, PlatformExtensionContext,
@@ -34,13 +37,14 @@ use super::tool_execution::ToolCallResult;
 use super::types::SharedProvider;
 use crate::agents::extension::{Envs, ProcessExit};
 use crate::agents::extension_malware_check;
-use crate::agents::mcp_client::{McpClient, McpClientTrait};
+use crate::agents::mcp_client::{McpClient, McpClientTrait, SharedEnv};
 use crate::builtin_extension::get_builtin_extension;
 use crate::config::extensions::name_to_key;
 use crate::config::search_path::SearchPaths;
 use crate::config::{get_all_extensions, Config};
 use crate::oauth::oauth_flow;
 use crate::prompt_template;
+use crate::session::SessionEnvironment;
 use crate::subprocess::configure_subprocess;
 use rmcp::model::{
     CallToolRequestParams, Content, ErrorCode, ErrorData, GetPromptResult, Prompt, Resource,
@@ -98,8 +102,34 @@ pub struct ExtensionManager {
     extensions: Mutex<HashMap<String, Extension>>,
     context: PlatformExtensionContext,
     provider: SharedProvider,
-    tools_cache: Mutex<Option<Arc<Vec<Tool>>>>,
+    /// Assembled tool lists by session
+    tools_cache: Mutex<HashMap<String, SessionTools>>,
     tools_cache_version: AtomicU64,
+    /// The session's own environment, given to the tool processes it starts
+    session_env: SharedEnv,
+    /// What a subagent was granted; its other tools are neither listed nor run
+    capability_grant: RwLock<Option<CapabilityGrant>>,
+}
+
+/// Which tools of the assembled list a caller asked for
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+struct ToolView {
+    extension: Option<String>,
+    excluded: Option<String>,
+}
+
+impl ToolView {
+    const ALL: ToolView = ToolView {
+        extension: None,
+        excluded: None,
+    };
+}
+
+/// A session's tools, prefixed and filtered, valid while its extensions match
+/// `fingerprint`. Lists are shared between turns rather than rebuilt.
+struct SessionTools {
+    fingerprint: u64,
+    views: HashMap<ToolView, Arc<Vec<Tool>>>,
 }
 
 /// A flattened representation of a resource used by the agent to prepare inference
@@ -200,6 +230,7 @@ pub fn get_parameter_names(tool: &Tool) -> Vec<String> {
 }
 
 async fn child_process_client(
+    name: &str,
     mut command: Command,
     timeout: &Option<u64>,
     provider: SharedProvider,
@@ -246,7 +277,7 @@ async fn child_process_client(
     .await;
 
     match client_result {
-        Ok(client) => Ok(client),
+        Ok(client) => Ok(client.with_extension_name(name)),
         Err(error) => {
             let error_task_out = stderr_task.await?;
             Err::<McpClient, ExtensionError>(match error_task_out {
@@ -403,7 +434,9 @@ async fn create_streamable_http_client(
     let timeout_duration =
         Duration::from_secs(timeout.unwrap_or(crate::config::DEFAULT_EXTENSION_TIMEOUT));
 
-    let client_res = McpClient::connect(transport, timeout_duration, provider.clone()).await;
+    let client_res = McpClient::connect(transport, timeout_duration, provider.clone())
+        .await
+        .map(|client| client.with_extension_name(&name_to_key(name)));
 
     if extract_auth_error(&client_res).is_some() {
         let am = oauth_flow(&uri.to_string(), &name.to_string())
@@ -426,7 +459,9 @@ async fn create_streamable_http_client(
             },
         );
         Ok(Box::new(
-            McpClient::connect(transport, timeout_duration, provider).await?,
+            McpClient::connect(transport, timeout_duration, provider)
+                .await?
+                .with_extension_name(&name_to_key(name)),
         ))
     } else {
         Ok(Box::new(client_res?))
@@ -445,8 +480,10 @@ impl ExtensionManager {
                 session_manager,
             },
             provider,
-            tools_cache: Mutex::new(None),
+            tools_cache: Mutex::new(HashMap::new()),
             tools_cache_version: AtomicU64::new(0),
+            session_env: SharedEnv::default(),
+            capability_grant: RwLock::new(None),
         }
     }
 
@@ -456,6 +493,24 @@ impl ExtensionManager {
         Self::new(Arc::new(Mutex::new(None)), session_manager)
     }
 
+    /// Reloads the session's environment and secret references. Stdio
+    /// extensions already running keep the environment they started with;
+    /// builtin extensions see the change on their next call.
+    pub async fn refresh_session_environment(&self, session_id: &str) -> Result<()> {
+        let session = self
+            .context
+            .session_manager
+            .get_session(session_id, false)
+            .await?;
+        let env = SessionEnvironment::for_session(&session).resolve()?;
+        *self.session_env.write().unwrap() = env;
+        Ok(())
+    }
+
+    fn session_env(&self) -> HashMap<String, String> {
+        self.session_env.read().unwrap().clone()
+    }
+
     pub fn get_context(&self) -> &PlatformExtensionContext {
         &self.context
     }
@@ -489,6 +544,12 @@ impl ExtensionManager {
             return Ok(());
         }
 
+        if let Some(sid) = session_id {
+            if let Err(e) = self.refresh_session_environment(sid).await {
+                warn!("Session environment not applied to {}: {}", config_name, e);
+            }
+        }
+
         // Resolve working_dir: explicit > current_dir
         let effective_working_dir =
             working_dir.unwrap_or_else(|| std::env::current_dir().unwrap_or_default());
@@ -530,6 +591,7 @@ impl ExtensionManager {
                 ..
             } => {
                 let mut all_envs = merge_environments(envs, env_keys, &sanitized_name).await?;
+                all_envs.extend(self.session_env());
 
                 if let Some(sid) = session_id {
                     all_envs.insert("AGENT_SESSION_ID".to_string(), sid.to_string());
@@ -562,6 +624,7 @@ impl ExtensionManager {
                 };
 
                 let client = child_process_client(
+                    &sanitized_name,
                     command,
                     timeout,
                     self.provider.clone(),
@@ -598,6 +661,7 @@ impl ExtensionManager {
                     });
 
                     let client = child_process_client(
+                        &sanitized_name,
                         command,
                         timeout,
                         self.provider.clone(),
@@ -616,7 +680,9 @@ impl ExtensionManager {
                             timeout_duration,
                             self.provider.clone(),
                         )
-                        .await?,
+                        .await?
+                        .with_extension_name(&sanitized_name)
+                        .with_forwarded_env(self.session_env.clone()),
                     )
                 }
             }
@@ -643,8 +709,9 @@ impl ExtensionManager {
                 temp_dir = Some(dir);
                 std::fs::write(&file_path, code)?;
 
+                let session_env = self.session_env();
                 let command = Command::new("uvx").configure(|command| {
-                    command.arg("--with").arg("mcp");
+                    command.envs(&session_env).arg("--with").arg("mcp");
                     dependencies.iter().flatten().for_each(|dep| {
                         command.arg("--with").arg(dep);
                     });
@@ -652,6 +719,7 @@ impl ExtensionManager {
                 });
 
                 let client = child_process_client(
+                    &sanitized_name,
                     command,
                     timeout,
                     self.provider.clone(),
@@ -764,40 +832,51 @@ impl ExtensionManager {
         session_id: &str,
         extension_name: Option<String>,
     ) -> ExtensionResult<Vec<Tool>> {
-        let all_tools = self.get_all_tools_cached(session_id).await?;
-        Ok(self.filter_tools(&all_tools, extension_name.as_deref(), None))
+        let tools = self
+            .get_prefixed_tools_shared(session_id, extension_name.as_deref())
+            .await?;
+        Ok(tools.as_ref().clone())
     }
 
-    pub async fn get_prefixed_tools_excluding(
+    /// Like [`Self::get_prefixed_tools`], but shares the cached list instead
+    /// of copying every schema.
+    pub async fn get_prefixed_tools_shared(
         &self,
         session_id: &str,
-        exclude: &str,
-    ) -> ExtensionResult<Vec<Tool>> {
-        let all_tools = self.get_all_tools_cached(session_id).await?;
-        Ok(self.filter_tools(&all_tools, None, Some(exclude)))
+        extension_name: Option<&str>,
+    ) -> ExtensionResult<Arc<Vec<Tool>>> {
+        let view = ToolView {
+            extension: extension_name.map(name_to_key),
+            excluded: None,
+        };
+        self.get_tools_cached(session_id, view).await
     }
 
-    fn filter_tools(
+    pub async fn get_prefixed_tools_excluding(
         &self,
-        tools: &[Tool],
-        extension_name: Option<&str>,
-        exclude: Option<&str>,
-    ) -> Vec<Tool> {
-        let extension_name_normalized = extension_name.map(name_to_key);
-        let exclude_normalized = exclude.map(name_to_key);
+        session_id: &str,
+        exclude: &str,
+    ) -> ExtensionResult<Arc<Vec<Tool>>> {
+        let view = ToolView {
+            extension: None,
+            excluded: Some(name_to_key(exclude)),
+        };
+        self.get_tools_cached(session_id, view).await
+    }
 
+    fn filter_tools(tools: &[Tool], view: &ToolView) -> Vec<Tool> {
         tools
             .iter()
             .filter(|tool| {
                 let tool_prefix = tool.name.split("__").next().unwrap_or("");
 
-                if let Some(ref excluded) = exclude_normalized {
+                if let Some(ref excluded) = view.excluded {
                     if tool_prefix == excluded {
                         return false;
                     }
                 }
 
-                if let Some(ref name_filter) = extension_name_normalized {
+                if let Some(ref name_filter) = view.extension {
                     tool_prefix == name_filter
                 } else {
                     true
@@ -807,31 +886,82 @@ impl ExtensionManager {
             .collect()
     }
 
-    async fn get_all_tools_cached(&self, session_id: &str) -> ExtensionResult<Arc<Vec<Tool>>> {
+    /// Identifies the current set of extensions and their tool settings.
+    async fn extensions_fingerprint(&self) -> u64 {
+        let extensions = self.extensions.lock().await;
+        let mut names: Vec<_> = extensions.keys().collect();
+        names.sort();
+        let mut hasher = DefaultHasher::new();
+        for name in names {
+            name.hash(&mut hasher);
+            serde_json::to_string(&extensions[name].config)
+                .unwrap_or_default()
+                .hash(&mut hasher);
+        }
+        hasher.finish()
+    }
+
+    async fn get_tools_cached(
+        &self,
+        session_id: &str,
+        view: ToolView,
+    ) -> ExtensionResult<Arc<Vec<Tool>>> {
+        let fingerprint = self.extensions_fingerprint().await;
         {
-            let cache = self.tools_cache.lock().await;
-            if let Some(ref tools) = *cache {
-                return Ok(Arc::clone(tools));
+            let mut cache = self.tools_cache.lock().await;
+            let current = cache
+                .get_mut(session_id)
+                .filter(|entry| entry.fingerprint == fingerprint);
+            if let Some(entry) = current {
+                if let Some(tools) = entry.views.get(&view) {
+                    return Ok(Arc::clone(tools));
+                }
+                let tools = Arc::new(Self::filter_tools(&entry.views[&ToolView::ALL], &view));
+                entry.views.insert(view, Arc::clone(&tools));
+                return Ok(tools);
             }
         }
 
         let version_before = self.tools_cache_version.load(Ordering::SeqCst);
-        let tools = Arc::new(self.fetch_all_tools(session_id).await?);
+        let all = Arc::new(self.fetch_all_tools(session_id).await?);
+        let tools = if view == ToolView::ALL {
+            Arc::clone(&all)
+        } else {
+            Arc::new(Self::filter_tools(&all, &view))
+        };
 
         {
             let mut cache = self.tools_cache.lock().await;
             let version_after = self.tools_cache_version.load(Ordering::SeqCst);
-            if version_after == version_before && cache.is_none() {
-                *cache = Some(Arc::clone(&tools));
+            if version_after == version_before {
+                let mut views = HashMap::from([(ToolView::ALL, all)]);
+                views.insert(view, Arc::clone(&tools));
+                cache.insert(session_id.to_string(), SessionTools { fingerprint, views });
             }
         }
 
         Ok(tools)
     }
 
+    /// Limits the tools listed and run to `grant`, for a subagent.
+    pub async fn set_capability_grant(&self, grant: Option<CapabilityGrant>) {
+        *self
+            .capability_grant
+            .write()
+            .unwrap_or_else(|e| e.into_inner()) = grant;
+        self.invalidate_tools_cache_and_bump_version().await;
+    }
+
+    pub fn capability_grant(&self) -> Option<CapabilityGrant> {
+        self.capability_grant
+            .read()
+            .unwrap_or_else(|e| e.into_inner())
+            .clone()
+    }
+
     async fn invalidate_tools_cache_and_bump_version(&self) {
         self.tools_cache_version.fetch_add(1, Ordering::SeqCst);
-        *self.tools_cache.lock().await = None;
+        self.tools_cache.lock().await.clear();
     }
 
     async fn fetch_all_tools(&self, session_id: &str) -> ExtensionResult<Vec<Tool>> {
@@ -844,9 +974,11 @@ impl ExtensionManager {
             .collect();
 
         let cancel_token = CancellationToken::default();
+        let grant = self.capability_grant();
         let client_futures = clients.into_iter().map(|(name, config, client)| {
             let cancel_token = cancel_token.clone();
             let ext_name = name.clone();
+            let grant = grant.clone();
             async move {
                 let mut tools = Vec::new();
                 let client_guard = client.lock().await;
@@ -863,9 +995,11 @@ impl ExtensionManager {
 
                 loop {
                     for tool in client_tools.tools {
-                        if config.is_tool_available(&tool.name) {
+                        let prefixed = format!("{}__{}", name, tool.name);
+                        let granted = grant.as_ref().is_none_or(|g| g.allows_tool(&prefixed));
+                        if config.is_tool_available(&tool.name) && granted {
                             tools.push(Tool {
-                                name: format!("{}__{}", name, tool.name).into(),
+                                name: prefixed.into(),
                                 description: tool.description,
                                 input_schema: tool.input_schema,
                                 annotations: tool.annotations,
@@ -1284,6 +1418,20 @@ impl ExtensionManager {
             })?
             .to_string();
 
+        if let Some(grant) = self.capability_grant() {
+            let tools = self.get_prefixed_tools_shared(session_id, None).await?;
+            let tool = tools.iter().find(|t| t.name == prefixed_name);
+            grant
+                .check_call(
+                    &prefixed_name,
+                    tool,
+                    tool_call.arguments.as_ref(),
+                    working_dir,
+                    self.extract_shell_command(&tool_call).is_some(),
+                )
+                .map_err(|reason| ErrorData::new(ErrorCode::INVALID_REQUEST, reason, None))?;
+        }
+
         if let Some(extension) = self.extensions.lock().await.get(&client_name) {
             if !extension.config.is_tool_available(&tool_name) {
                 return Err(ErrorData::new(
@@ -1303,8 +1451,22 @@ impl ExtensionManager {
         let notifications_receiver = client.lock().await.subscribe().await;
         let session_id = session_id.to_string();
         let working_dir_str = working_dir.map(|p| p.to_string_lossy().to_string());
+        let heavy = crate::resources::is_heavy_tool(
+            &prefixed_name,
+            self.extract_shell_command(&tool_call).as_deref(),
+        );
 
         let fut = async move {
+            // Builds and indexing wait until the machine has room for them
+            let _permit = if heavy {
+                Some(
+                    crate::resources::global()
+                        .acquire(crate::resources::Workload::HeavyTool)
+                        .await,
+                )
+            } else {
+                None
+            };
             tracing::debug!(
                 "dispatch_tool_call fut: calling client.call_tool tool={} session_id={} working_dir={:?}",
                 tool_name,
@@ -2258,6 +2420,52 @@ mod tests {
         assert!(!tool_names.iter().any(|n| n.starts_with("ext_b__")));
     }
 
+    #[tokio::test]
+    async fn test_tools_are_shared_across_turns_per_session() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let extension_manager =
+            ExtensionManager::new_without_provider(temp_dir.path().to_path_buf());
+        extension_manager
+            .add_mock_extension(
+                "ext_a".to_string(),
+                Arc::new(Mutex::new(Box::new(MockClient {}))),
+            )
+            .await;
+
+        let first = extension_manager
+            .get_prefixed_tools_shared("session-1", None)
+            .await
+            .unwrap();
+        let second = extension_manager
+            .get_prefixed_tools_shared("session-1", None)
+            .await
+            .unwrap();
+        assert!(Arc::ptr_eq(&first, &second));
+        let filtered = extension_manager
+            .get_prefixed_tools_shared("session-1", Some("ext_a"))
+            .await
+            .unwrap();
+        assert!(Arc::ptr_eq(
+            &filtered,
+            &extension_manager
+                .get_prefixed_tools_shared("session-1", Some("ext_a"))
+                .await
+                .unwrap()
+        ));
+        let other_session = extension_manager
+            .get_prefixed_tools_shared("session-2", None)
+            .await
+            .unwrap();
+        assert!(!Arc::ptr_eq(&first, &other_session));
+
+        extension_manager.remove_extension("ext_a").await.unwrap();
+        let after_remove = extension_manager
+            .get_prefixed_tools_shared("session-1", None)
+            .await
+            .unwrap();
+        assert!(after_remove.is_empty());
+    }
+
     #[tokio::test]
     async fn test_get_prefixed_tools_excluding() {
         let temp_dir = tempfile::tempdir().unwrap();
@@ -0,0 +1,516 @@
+//! File claims: leases that keep swarm agents from overwriting each other.
+//!
+//! An agent claims the paths it is about to edit. A claim is a lease: it
+//! expires unless renewed, so a crashed agent does not hold files forever.
+//! When a claim overlaps one held by another agent the request is not
+//! granted; instead a negotiation opens and the holder is asked over the bus
+//! to yield. If the holder yields the requester gets the paths. If it holds,
+//! the negotiation is escalated to the coordinator and the request queues
+//! behind the holder, so the edits are serialized rather than lost.
+//!
+//! Every claim, conflict and resolution is written to the audit log.
+
+use super::mailbox;
+use super::swarm::{AgentId, MessageType, SwarmMessage};
+use crate::security::audit_log;
+use anyhow::{anyhow, Result};
+use chrono::{DateTime, Duration, Utc};
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use std::sync::{Mutex, OnceLock};
+use utoipa::ToSchema;
+
+/// Agent that escalated negotiations are sent to.
+pub const ORCHESTRATOR: &str = "coordinator";
+pub const DEFAULT_LEASE_SECS: i64 = 300;
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, ToSchema)]
+pub struct FileClaim {
+    pub id: u64,
+    pub agent: AgentId,
+    pub paths: Vec<String>,
+    pub acquired_at: DateTime<Utc>,
+    pub expires_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum NegotiationStatus {
+    /// Waiting for the holder to yield or hold
+    Open,
+    /// The holder released the contested paths to the requester
+    Yielded,
+    /// The holder kept its claim; the request waits for it to be released
+    Escalated,
+    /// The request was granted after the holder released its claim
+    Serialized,
+    /// The requester gave up, or its queued request was dropped
+    Withdrawn,
+}
+
+/// Two agents wanting the same paths.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, ToSchema)]
+pub struct Negotiation {
+    pub id: u64,
+    pub requester: AgentId,
+    pub holder: AgentId,
+    /// Paths the requester asked for
+    pub paths: Vec<String>,
+    /// The subset already claimed by the holder
+    pub contested: Vec<String>,
+    pub status: NegotiationStatus,
+    pub opened_at: DateTime<Utc>,
+    pub resolved_at: Option<DateTime<Utc>>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, ToSchema)]
+#[serde(tag = "outcome", rename_all = "snake_case")]
+pub enum ClaimOutcome {
+    Granted { claim: FileClaim },
+    Conflict { negotiation: Negotiation },
+}
+
+/// Something that happened to a claim; audited and, when it concerns another
+/// agent, sent to that agent's mailbox.
+#[derive(Debug, Clone)]
+pub struct ClaimEvent {
+    pub kind: &'static str,
+    pub data: Value,
+    pub notice: Option<SwarmMessage>,
+}
+
+impl ClaimEvent {
+    fn new(kind: &'static str, data: Value) -> Self {
+        Self {
+            kind,
+            data,
+            notice: None,
+        }
+    }
+
+    fn notify(mut self, from: &str, to: &str, message_type: MessageType) -> Self {
+        let mut content = self.data.clone();
+        content["event"] = json!(self.kind);
+        self.notice = Some(SwarmMessage {
+            from: from.to_string(),
+            to: to.to_string(),
+            message_type,
+            content: content.to_string(),
+            timestamp: Utc::now(),
+        });
+        self
+    }
+}
+
+/// Whether two claimed paths touch the same files: equal, or one is a
+/// directory containing the other.
+fn overlaps(a: &str, b: &str) -> bool {
+    let a = a.trim_end_matches('/');
+    let b = b.trim_end_matches('/');
+    a == b
+        || b.strip_prefix(a).is_some_and(|rest| rest.starts_with('/'))
+        || a.strip_prefix(b).is_some_and(|rest| rest.starts_with('/'))
+}
+
+#[derive(Debug, Default)]
+pub struct ClaimRegistry {
+    next_id: u64,
+    claims: Vec<FileClaim>,
+    negotiations: Vec<Negotiation>,
+}
+
+impl ClaimRegistry {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    fn next_id(&mut self) -> u64 {
+        self.next_id += 1;
+        self.next_id
+    }
+
+    pub fn claims(&self) -> &[FileClaim] {
+        &self.claims
+    }
+
+    pub fn negotiations(&self) -> &[Negotiation] {
+        &self.negotiations
+    }
+
+    /// Claims `paths` for `agent` for `lease`, or opens a negotiation with the
+    /// first agent already holding any of them.
+    pub fn claim(
+        &mut self,
+        agent: &str,
+        paths: Vec<String>,
+        lease: Duration,
+        now: DateTime<Utc>,
+    ) -> (ClaimOutcome, Vec<ClaimEvent>) {
+        let mut events = self.expire(now);
+        let conflict = self
+            .claims
+            .iter()
+            .filter(|c| c.agent != agent)
+            .find_map(|c| {
+                let contested: Vec<String> = paths
+                    .iter()
+                    .filter(|p| c.paths.iter().any(|held| overlaps(held, p)))
+                    .cloned()
+                    .collect();
+                (!contested.is_empty()).then(|| (c.agent.clone(), contested))
+            });
+
+        let Some((holder, contested)) = conflict else {
+            let claim = self.grant(agent, paths, lease, now);
+            events.push(ClaimEvent::new("granted", json!(claim)));
+            return (ClaimOutcome::Granted { claim }, events);
+        };
+
+        let negotiation = Negotiation {
+            id: self.next_id(),
+            requester: agent.to_string(),
+            holder: holder.clone(),
+            paths,
+            contested,
+            status: NegotiationStatus::Open,
+            opened_at: now,
+            resolved_at: None,
+        };
+        self.negotiations.push(negotiation.clone());
+        events.push(ClaimEvent::new("conflict", json!(negotiation)).notify(
+            agent,
+            &holder,
+            MessageType::Question,
+        ));
+        (ClaimOutcome::Conflict { negotiation }, events)
+    }
+
+    fn grant(
+        &mut self,
+        agent: &str,
+        paths: Vec<String>,
+        lease: Duration,
+        now: DateTime<Utc>,
+    ) -> FileClaim {
+        let claim = FileClaim {
+            id: self.next_id(),
+            agent: agent.to_string(),
+            paths,
+            acquired_at: now,
+            expires_at: now + lease,
+        };
+        self.claims.push(claim.clone());
+        claim
+    }
+
+    /// The holder's answer to an open negotiation. Yielding hands the
+    /// contested paths to the requester; holding escalates to the
+    /// orchestrator and queues the request until the holder releases.
+    pub fn respond(
+        &mut self,
+        negotiation_id: u64,
+        responder: &str,
+        yield_paths: bool,
+        now: DateTime<Utc>,
+    ) -> Result<(Negotiation, Vec<ClaimEvent>)> {
+        let mut events = self.expire(now);
+        let index = self
+            .negotiations
+            .iter()
+            .position(|n| n.id == negotiation_id)
+            .ok_or_else(|| anyhow!("No negotiation {}", negotiation_id))?;
+        let negotiation = &self.negotiations[index];
+        if negotiation.holder != responder {
+            return Err(anyhow!(
+                "Negotiation {} is addressed to {}, not {}",
+                negotiation_id,
+                negotiation.holder,
+                responder
+            ));
+        }
+        if negotiation.status != NegotiationStatus::Open {
+            return Err(anyhow!("Negotiation {} is no longer open", negotiation_id));
+        }
+
+        if !yield_paths {
+            let negotiation = &mut self.negotiations[index];
+            negotiation.status = NegotiationStatus::Escalated;
+            let negotiation = negotiation.clone();
+            events.push(ClaimEvent::new("escalated", json!(negotiation)).notify(
+                responder,
+                ORCHESTRATOR,
+                MessageType::Alert,
+            ));
+            events.push(ClaimEvent::new("queued", json!(negotiation)).notify(
+                responder,
+                &negotiation.requester,
+                MessageType::Answer,
+            ));
+            return Ok((negotiation, events));
+        }
+
+        let contested = negotiation.contested.clone();
+        for claim in self.claims.iter_mut().filter(|c| c.agent == responder) {
+            claim
+                .paths
+                .retain(|held| !contested.iter().any(|p| overlaps(held, p)));
+        }
+        self.claims.retain(|c| !c.paths.is_empty());
+
+        let negotiation = &mut self.negotiations[index];
+        negotiation.status = NegotiationStatus::Yielded;
+        negotiation.resolved_at = Some(now);
+        let negotiation = negotiation.clone();
+        events.push(ClaimEvent::new("yielded", json!(negotiation)).notify(
+            responder,
+            &negotiation.requester,
+            MessageType::Answer,
+        ));
+        // Another holder may still hold part of what was asked for
+        let (_, claimed) = self.claim(
+            &negotiation.requester,
+            negotiation.paths.clone(),
+            Duration::seconds(DEFAULT_LEASE_SECS),
+            now,
+        );
+        events.extend(claimed);
+        Ok((negotiation, events))
+    }
+
+    /// Extends the lease on one of `agent`'s claims.
+    pub fn renew(
+        &mut self,
+        claim_id: u64,
+        agent: &str,
+        lease: Duration,
+        now: DateTime<Utc>,
+    ) -> Option<FileClaim> {
+        let claim = self
+            .claims
+            .iter_mut()
+            .find(|c| c.id == claim_id && c.agent == agent && c.expires_at > now)?;
+        claim.expires_at = now + lease;
+        Some(claim.clone())
+    }
+
+    /// Releases a claim and grants any escalated requests it was blocking.
+    pub fn release(&mut self, claim_id: u64, now: DateTime<Utc>) -> Option<Vec<ClaimEvent>> {
+        let index = self.claims.iter().position(|c| c.id == claim_id)?;
+        let claim = self.claims.remove(index);
+        let mut events = vec![ClaimEvent::new("released", json!(claim))];
+        events.extend(self.expire(now));
+        events.extend(self.grant_queued(now));
+        Some(events)
+    }
+
+    /// Drops expired leases, then grants queued requests they were blocking.
+    pub fn expire(&mut self, now: DateTime<Utc>) -> Vec<ClaimEvent> {
+        let (expired, live): (Vec<_>, Vec<_>) = std::mem::take(&mut self.claims)
+            .into_iter()
+            .partition(|c| c.expires_at <= now);
+        self.claims = live;
+        if expired.is_empty() {
+            return Vec::new();
+        }
+        let mut events: Vec<_> = expired
+            .into_iter()
+            .map(|claim| ClaimEvent::new("expired", json!(claim)))
+            .collect();
+        events.extend(self.grant_queued(now));
+        events
+    }
+
+    /// Grants escalated requests in the order they were opened, once no other
+    /// agent holds any of their paths.
+    fn grant_queued(&mut self, now: DateTime<Utc>) -> Vec<ClaimEvent> {
+        let mut events = Vec::new();
+        for index in 0..self.negotiations.len() {
+            let negotiation = &self.negotiations[index];
+            if negotiation.status != NegotiationStatus::Escalated {
+                continue;
+            }
+            let blocked = self.claims.iter().any(|c| {
+                c.agent != negotiation.requester
+                    && negotiation
+                        .paths
+                        .iter()
+                        .any(|p| c.paths.iter().any(|held| overlaps(held, p)))
+            });
+            if blocked {
+                continue;
+            }
+            let requester = negotiation.requester.clone();
+            let paths = negotiation.paths.clone();
+            let claim = self.grant(
+                &requester,
+                paths,
+                Duration::seconds(DEFAULT_LEASE_SECS),
+                now,
+            );
+            let negotiation = &mut self.negotiations[index];
+            negotiation.status = NegotiationStatus::Serialized;
+            negotiation.resolved_at = Some(now);
+            events.push(
+                ClaimEvent::new(
+                    "serialized",
+                    json!({ "negotiation": negotiation, "claim": claim }),
+                )
+                .notify(ORCHESTRATOR, &requester, MessageType::StatusUpdate),
+            );
+        }
+        events
+    }
+
+    /// The requester no longer wants the paths it was negotiating for.
+    pub fn withdraw(&mut self, negotiation_id: u64, now: DateTime<Utc>) -> Option<ClaimEvent> {
+        let negotiation = self.negotiations.iter_mut().find(|n| {
+            n.id == negotiation_id
+                && matches!(
+                    n.status,
+                    NegotiationStatus::Open | NegotiationStatus::Escalated
+                )
+        })?;
+        negotiation.status = NegotiationStatus::Withdrawn;
+        negotiation.resolved_at = Some(now);
+        Some(ClaimEvent::new("withdrawn", json!(negotiation)))
+    }
+}
+
+static GLOBAL_CLAIMS: OnceLock<Mutex<ClaimRegistry>> = OnceLock::new();
+
+/// The claim registry shared by every agent in this process.
+pub fn registry() -> &'static Mutex<ClaimRegistry> {
+    GLOBAL_CLAIMS.get_or_init(|| Mutex::new(ClaimRegistry::new()))
+}
+
+/// Audits each event and delivers its notice, if any, over the agent bus.
+pub async fn publish(events: Vec<ClaimEvent>) -> Result<()> {
+    for event in &events {
+        audit_log::record(
+            "file_claim",
+            json!({ "event": event.kind, "detail": event.data }),
+        );
+    }
+    let notices: Vec<_> = events.into_iter().filter_map(|e| e.notice).collect();
+    if notices.is_empty() {
+        return Ok(());
+    }
+    let store = mailbox::global().await?;
+    for notice in &notices {
+        store.send(notice).await?;
+    }
+    Ok(())
+}
+
+/// Claims `paths` for `agent` in the shared registry and publishes the result.
+pub async fn claim(agent: &str, paths: Vec<String>, lease: Duration) -> Result<ClaimOutcome> {
+    let (outcome, events) = registry()
+        .lock()
+        .unwrap()
+        .claim(agent, paths, lease, Utc::now());
+    publish(events).await?;
+    Ok(outcome)
+}
+
+/// Answers a negotiation in the shared registry and publishes the result.
+pub async fn respond(
+    negotiation_id: u64,
+    responder: &str,
+    yield_paths: bool,
+) -> Result<Negotiation> {
+    let (negotiation, events) =
+        registry()
+            .lock()
+            .unwrap()
+            .respond(negotiation_id, responder, yield_paths, Utc::now())?;
+    publish(events).await?;
+    Ok(negotiation)
+}
+
+/// Releases a claim in the shared registry; false if it was not held.
+pub async fn release(claim_id: u64) -> Result<bool> {
+    let events = registry().lock().unwrap().release(claim_id, Utc::now());
+    match events {
+        Some(events) => publish(events).await.map(|_| true),
+        None => Ok(false),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn paths(paths: &[&str]) -> Vec<String> {
+        paths.iter().map(|p| p.to_string()).collect()
+    }
+
+    #[test]
+    fn test_overlapping_claim_negotiates_and_yields() {
+        let mut registry = ClaimRegistry::new();
+        let now = Utc::now();
+        let lease = Duration::seconds(60);
+
+        let (first, _) = registry.claim("coder-1", paths(&["src/lib.rs"]), lease, now);
+        assert!(matches!(first, ClaimOutcome::Granted { .. }));
+        let (other, _) = registry.claim("coder-2", paths(&["src/main.rs"]), lease, now);
+        assert!(matches!(other, ClaimOutcome::Granted { .. }));
+
+        let (outcome, events) = registry.claim("coder-3", paths(&["src"]), lease, now);
+        let ClaimOutcome::Conflict { negotiation } = outcome else {
+            panic!("expected a conflict");
+        };
+        assert_eq!(negotiation.holder, "coder-1");
+        assert_eq!(events[0].notice.as_ref().unwrap().to, "coder-1");
+
+        assert!(registry
+            .respond(negotiation.id, "coder-2", true, now)
+            .is_err());
+        let (yielded, events) = registry
+            .respond(negotiation.id, "coder-1", true, now)
+            .unwrap();
+        assert_eq!(yielded.status, NegotiationStatus::Yielded);
+        assert!(registry.claims().iter().all(|c| c.agent != "coder-1"));
+        // coder-2 still holds src/main.rs, so a second negotiation opens
+        let kinds: Vec<_> = events.iter().map(|e| e.kind).collect();
+        assert_eq!(kinds, vec!["yielded", "conflict"]);
+        assert_eq!(registry.negotiations()[1].holder, "coder-2");
+    }
+
+    #[test]
+    fn test_held_claim_escalates_and_serializes() {
+        let mut registry = ClaimRegistry::new();
+        let now = Utc::now();
+        let lease = Duration::seconds(60);
+
+        let (ClaimOutcome::Granted { claim }, _) =
+            registry.claim("coder-1", paths(&["src/lib.rs"]), lease, now)
+        else {
+            panic!("expected a grant");
+        };
+        let (ClaimOutcome::Conflict { negotiation }, _) =
+            registry.claim("coder-2", paths(&["src/lib.rs"]), lease, now)
+        else {
+            panic!("expected a conflict");
+        };
+
+        let (held, events) = registry
+            .respond(negotiation.id, "coder-1", false, now)
+            .unwrap();
+        assert_eq!(held.status, NegotiationStatus::Escalated);
+        assert_eq!(events[0].notice.as_ref().unwrap().to, ORCHESTRATOR);
+
+        let events = registry.release(claim.id, now).unwrap();
+        let kinds: Vec<_> = events.iter().map(|e| e.kind).collect();
+        assert_eq!(kinds, vec!["released", "serialized"]);
+        assert_eq!(registry.claims()[0].agent, "coder-2");
+        assert_eq!(
+            registry.negotiations()[0].status,
+            NegotiationStatus::Serialized
+        );
+
+        // An expired lease frees its paths too
+        let later = now + Duration::seconds(DEFAULT_LEASE_SECS + 1);
+        let (outcome, _) = registry.claim("coder-3", paths(&["src/lib.rs"]), lease, later);
+        assert!(matches!(outcome, ClaimOutcome::Granted { .. }));
+    }
+}
@@ -1,4 +1,5 @@
 use crate::agents::tool_execution::ToolCallResult;
+use crate::recipe::validation::Validation;
 use crate::recipe::Response;
 use indoc::formatdoc;
 use regex::Regex;
@@ -6,6 +7,7 @@ use rmcp::model::{CallToolRequestParams, Content, ErrorCode, ErrorData, Tool, To
 use serde_json::Value;
 use std::borrow::Cow;
 use std::fmt;
+use std::path::{Path, PathBuf};
 
 pub const FINAL_OUTPUT_TOOL_NAME: &str = "recipe__final_output";
 pub const FINAL_OUTPUT_CONTINUATION_MESSAGE: &str =
@@ -123,6 +125,10 @@ pub struct FinalOutputTool {
     validation_history: Vec<ValidationAttempt>,
     /// Whether the output was accepted despite validation failure (retry budget exhausted).
     accepted_with_warning: bool,
+    /// The recipe's completion gates, run once the output itself is valid.
+    validation: Option<Validation>,
+    /// Where the completion gates run; the current directory when unset.
+    working_dir: Option<PathBuf>,
 }
 
 impl FinalOutputTool {
@@ -151,6 +157,8 @@ impl FinalOutputTool {
             retry_count: 0,
             validation_history: Vec::new(),
             accepted_with_warning: false,
+            validation: None,
+            working_dir: None,
         }
     }
 
@@ -168,6 +176,12 @@ impl FinalOutputTool {
         self
     }
 
+    /// Set the completion gates the output must pass.
+    pub fn with_validation(mut self, validation: Option<Validation>) -> Self {
+        self.validation = validation;
+        self
+    }
+
     /// Set the maximum number of retries (0 = unlimited).
     pub fn with_max_retries(mut self, max: u32) -> Self {
         self.max_retries = max;
@@ -252,7 +266,7 @@ impl FinalOutputTool {
     }
 
     pub fn system_prompt(&self) -> String {
-        formatdoc! {r#"
+        let prompt = formatdoc! {r#"
             # Final Output Instructions
 
             You MUST use the `final_output` tool to collect the final output for the user rather than providing the output directly in your response.
@@ -261,13 +275,48 @@ impl FinalOutputTool {
             {}
 
             ----
-        "#, serde_json::to_string_pretty(self.response.json_schema.as_ref().unwrap()).unwrap()}
+        "#, serde_json::to_string_pretty(self.response.json_schema.as_ref().unwrap()).unwrap()};
+        match &self.validation {
+            Some(validation) => format!("{}{}", prompt, gate_instructions(validation)),
+            None => prompt,
+        }
     }
 
     // -- Validation dispatch ----------------------------------------------------
 
-    /// Validate output according to the current validation mode.
+    /// Validate output according to the current validation mode, then run
+    /// the completion gates.
     async fn validate_output(&self, output: &Value) -> Result<Value, String> {
+        let validated = self.validate_format(output).await?;
+        let Some(validation) = &self.validation else {
+            return Ok(validated);
+        };
+        let working_dir = match &self.working_dir {
+            Some(dir) => dir.clone(),
+            None => std::env::current_dir().unwrap_or_default(),
+        };
+        let failures = validation.run(&working_dir).await;
+        if failures.is_empty() {
+            return Ok(validated);
+        }
+        Err(formatdoc! {r#"
+            The output is well-formed, but {count} completion check(s) failed:
+
+            {failures}
+
+            HOW TO FIX:
+            - Keep working on the task until these checks pass.
+            - Then re-call the `final_output` tool."#,
+            count = failures.len(),
+            failures = failures
+                .iter()
+                .map(|f| format!("- {}", f))
+                .collect::<Vec<_>>()
+                .join("\n"),
+        })
+    }
+
+    async fn validate_format(&self, output: &Value) -> Result<Value, String> {
         match &self.validation_mode {
             ValidationMode::JsonSchema => self.validate_json_schema(output).await,
             ValidationMode::TypedFields(rules) => {
@@ -451,6 +500,16 @@ impl FinalOutputTool {
 
     // -- Main execution entry point ---------------------------------------------
 
+    /// Run a tool call with the completion gates in `working_dir`.
+    pub async fn execute_tool_call_in(
+        &mut self,
+        tool_call: CallToolRequestParams,
+        working_dir: &Path,
+    ) -> ToolCallResult {
+        self.working_dir = Some(working_dir.to_path_buf());
+        self.execute_tool_call(tool_call).await
+    }
+
     pub async fn execute_tool_call(&mut self, tool_call: CallToolRequestParams) -> ToolCallResult {
         match tool_call.name.to_string().as_str() {
             FINAL_OUTPUT_TOOL_NAME => {
@@ -568,6 +627,41 @@ impl FinalOutputTool {
 // Helper functions
 // ---------------------------------------------------------------------------
 
+/// Tell the agent up front which checks its final output will have to pass.
+fn gate_instructions(validation: &Validation) -> String {
+    let mut checks: Vec<String> = Vec::new();
+    for gate in &validation.commands {
+        checks.push(format!(
+            "- `{}` exits with one of {:?}",
+            gate.command, gate.expected_exit_codes
+        ));
+    }
+    for gate in &validation.files {
+        let state = if gate.exists {
+            "exists"
+        } else {
+            "does not exist"
+        };
+        checks.push(format!("- {} {}", gate.path, state));
+    }
+    for gate in &validation.quality {
+        let bounds = match (gate.min, gate.max) {
+            (Some(min), Some(max)) => format!("between {} and {}", min, max),
+            (Some(min), None) => format!("at least {}", min),
+            (None, Some(max)) => format!("at most {}", max),
+            (None, None) => "reported".to_string(),
+        };
+        checks.push(format!("- `{}` reports a value {}", gate.command, bounds));
+    }
+    formatdoc! {r#"
+
+        The final output is only accepted once these checks pass in the working directory:
+        {}
+
+        ----
+    "#, checks.join("\n")}
+}
+
 /// Return a human-readable JSON type name for a serde_json::Value.
 fn json_type_name(val: &Value) -> &'static str {
     match val {
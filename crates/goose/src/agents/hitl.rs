@@ -376,12 +376,12 @@ impl Default for InteractiveSession {
 
 /// Gate that pauses execution until the user approves a plan.
 ///
-/// Uses `ActionRequiredManager::global().request_and_wait()` — no new
+/// Uses `ActionRequiredManager::global().elicit()` — no new
 /// channel infrastructure needed.
 ///
 /// Returns `Ok(true)` if approved, `Ok(false)` if rejected or timed out.
 pub async fn await_plan_approval(plan: &Plan) -> Result<bool> {
-    use crate::action_required_manager::ActionRequiredManager;
+    use crate::action_required_manager::{ActionRequiredManager, Elicitation};
     use std::time::Duration;
 
     let schema = serde_json::json!({
@@ -409,10 +409,9 @@ pub async fn await_plan_approval(plan: &Plan) -> Result<bool> {
         plan.goal, step_list
     );
 
-    match ActionRequiredManager::global()
-        .request_and_wait(message, schema, Duration::from_secs(600))
-        .await
-    {
+    let elicitation =
+        Elicitation::new("plan_approval", message, schema).with_timeout(Duration::from_secs(600));
+    match ActionRequiredManager::global().elicit(elicitation).await {
         Ok(response) => {
             let approved = response
                 .get("approved")
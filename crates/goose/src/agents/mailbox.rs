@@ -0,0 +1,668 @@
+//! Persistent agent mailboxes with at-least-once delivery.
+//!
+//! Messages between swarm agents are kept in SQLite until the recipient
+//! acknowledges them. A received message stays hidden for the visibility
+//! timeout; if it is not acknowledged by then it is delivered again, so a
+//! recipient that crashes mid-handling gets another chance. Messages that
+//! exceed the delivery limit move to a dead-letter queue for inspection.
+//!
+//! When wake simulation is on (see [`super::wake_policy`]), every message
+//! sent is also run through the wake policy and the decision is recorded, so
+//! [`MailboxStore::wake_report`] can show what automatic wake-ups would have
+//! done over a period.
+
+use super::swarm::{AgentId, SwarmMessage};
+use super::wake_policy::{self, WakeMode, WakePolicy};
+use crate::config::paths::Paths;
+use crate::database;
+use anyhow::{Context, Result};
+use chrono::{DateTime, TimeZone, Utc};
+use serde::{Deserialize, Serialize};
+use sqlx::{sqlite::SqlitePoolOptions, Pool, Row, Sqlite};
+use std::path::Path;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::sync::OnceCell;
+use utoipa::ToSchema;
+
+pub const MAILBOX_DB_FILE: &str = "mailboxes.db";
+pub const DEFAULT_VISIBILITY_TIMEOUT: Duration = Duration::from_secs(30);
+pub const DEFAULT_MAX_DELIVERIES: u32 = 5;
+
+/// A message handed to its recipient, to be acknowledged by `receipt`.
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct Delivery {
+    pub id: i64,
+    #[schema(value_type = Object)]
+    pub message: SwarmMessage,
+    /// Number of times this message has been delivered, including this one
+    pub attempts: u32,
+    /// Acknowledges this delivery only: once the message is delivered again
+    /// after the visibility timeout, an earlier receipt no longer matches
+    pub receipt: String,
+}
+
+fn receipt(id: i64, attempts: u32) -> String {
+    format!("{}.{}", id, attempts)
+}
+
+fn parse_receipt(receipt: &str) -> Option<(i64, u32)> {
+    let (id, attempts) = receipt.split_once('.')?;
+    Some((id.parse().ok()?, attempts.parse().ok()?))
+}
+
+/// A message that was delivered too many times without being acknowledged.
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct DeadLetter {
+    pub id: i64,
+    pub recipient: AgentId,
+    #[schema(value_type = Object)]
+    pub message: SwarmMessage,
+    pub attempts: u32,
+    pub reason: String,
+    pub dead_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, ToSchema)]
+pub struct MailboxStats {
+    pub recipient: AgentId,
+    /// Waiting to be delivered
+    pub pending: u64,
+    /// Delivered but not yet acknowledged
+    pub in_flight: u64,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, ToSchema)]
+pub struct WakeRuleCount {
+    /// Policy rule that decided: wakes, message_type, cooldown or hourly_limit
+    pub rule: String,
+    pub count: u64,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, ToSchema)]
+pub struct RecipientWakes {
+    pub recipient: AgentId,
+    pub messages: u64,
+    pub would_wake: u64,
+    pub estimated_cost_usd: f64,
+}
+
+/// What the wake policy would have done with the messages sent in a period.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, ToSchema)]
+pub struct WakeReport {
+    pub since: DateTime<Utc>,
+    pub until: DateTime<Utc>,
+    pub evaluated: u64,
+    pub would_wake: u64,
+    pub estimated_cost_usd: f64,
+    pub rules: Vec<WakeRuleCount>,
+    pub recipients: Vec<RecipientWakes>,
+}
+
+#[derive(Debug)]
+pub struct MailboxStore {
+    pool: Pool<Sqlite>,
+    visibility_timeout: Duration,
+    max_deliveries: u32,
+    /// Policy that sent messages are evaluated against, without waking anyone
+    wake_simulation: Option<WakePolicy>,
+}
+
+static GLOBAL_MAILBOXES: OnceCell<Arc<MailboxStore>> = OnceCell::const_new();
+
+/// The shared mailbox store in the data directory.
+pub async fn global() -> Result<Arc<MailboxStore>> {
+    GLOBAL_MAILBOXES
+        .get_or_try_init(|| async {
+            let store = MailboxStore::open(Paths::in_data_dir(MAILBOX_DB_FILE)).await?;
+            let store = match wake_policy::mode() {
+                WakeMode::Simulate => store.with_wake_simulation(WakePolicy::from_config().await),
+                WakeMode::Off => store,
+            };
+            Ok::<_, anyhow::Error>(Arc::new(store))
+        })
+        .await
+        .cloned()
+}
+
+impl MailboxStore {
+    pub async fn open(path: impl AsRef<Path>) -> Result<Self> {
+        let path = path.as_ref();
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        Self::with_pool(database::open("agent_bus", path))
+            .await
+            .with_context(|| format!("Failed to open mailbox database at {:?}", path))
+    }
+
+    /// Create an in-memory store (for testing)
+    pub async fn in_memory() -> Result<Self> {
+        let pool = SqlitePoolOptions::new()
+            .max_connections(1)
+            .connect("sqlite::memory:")
+            .await?;
+        Self::with_pool(pool).await
+    }
+
+    async fn with_pool(pool: Pool<Sqlite>) -> Result<Self> {
+        let store = Self {
+            pool,
+            visibility_timeout: DEFAULT_VISIBILITY_TIMEOUT,
+            max_deliveries: DEFAULT_MAX_DELIVERIES,
+            wake_simulation: None,
+        };
+        store.init_schema().await?;
+        Ok(store)
+    }
+
+    pub fn with_limits(mut self, visibility_timeout: Duration, max_deliveries: u32) -> Self {
+        self.visibility_timeout = visibility_timeout;
+        self.max_deliveries = max_deliveries.max(1);
+        self
+    }
+
+    pub fn with_wake_simulation(mut self, policy: WakePolicy) -> Self {
+        self.wake_simulation = Some(policy);
+        self
+    }
+
+    async fn init_schema(&self) -> Result<()> {
+        sqlx::query(
+            r#"
+            CREATE TABLE IF NOT EXISTS mailbox_messages (
+                id INTEGER PRIMARY KEY AUTOINCREMENT,
+                recipient TEXT NOT NULL,
+                payload TEXT NOT NULL,
+                attempts INTEGER NOT NULL DEFAULT 0,
+                visible_at INTEGER NOT NULL,
+                created_at INTEGER NOT NULL
+            )
+            "#,
+        )
+        .execute(&self.pool)
+        .await?;
+
+        sqlx::query(
+            r#"
+            CREATE INDEX IF NOT EXISTS idx_mailbox_messages_recipient
+                ON mailbox_messages(recipient, visible_at)
+            "#,
+        )
+        .execute(&self.pool)
+        .await?;
+
+        sqlx::query(
+            r#"
+            CREATE TABLE IF NOT EXISTS mailbox_dead_letters (
+                id INTEGER PRIMARY KEY,
+                recipient TEXT NOT NULL,
+                payload TEXT NOT NULL,
+                attempts INTEGER NOT NULL,
+                reason TEXT NOT NULL,
+                dead_at INTEGER NOT NULL
+            )
+            "#,
+        )
+        .execute(&self.pool)
+        .await?;
+
+        sqlx::query(
+            r#"
+            CREATE TABLE IF NOT EXISTS wake_simulations (
+                id INTEGER PRIMARY KEY AUTOINCREMENT,
+                message_id INTEGER NOT NULL,
+                recipient TEXT NOT NULL,
+                wake INTEGER NOT NULL,
+                rule TEXT NOT NULL,
+                reason TEXT NOT NULL,
+                estimated_cost REAL NOT NULL,
+                decided_at INTEGER NOT NULL
+            )
+            "#,
+        )
+        .execute(&self.pool)
+        .await?;
+
+        sqlx::query(
+            r#"
+            CREATE INDEX IF NOT EXISTS idx_wake_simulations_decided
+                ON wake_simulations(decided_at, recipient)
+            "#,
+        )
+        .execute(&self.pool)
+        .await?;
+
+        Ok(())
+    }
+
+    /// Store a message in the recipient's mailbox.
+    pub async fn send(&self, message: &SwarmMessage) -> Result<i64> {
+        let now = Utc::now().timestamp_millis();
+        let result = sqlx::query(
+            r#"
+            INSERT INTO mailbox_messages (recipient, payload, attempts, visible_at, created_at)
+            VALUES (?1, ?2, 0, ?3, ?3)
+            "#,
+        )
+        .bind(&message.to)
+        .bind(serde_json::to_string(message)?)
+        .bind(now)
+        .execute(&self.pool)
+        .await?;
+        let id = result.last_insert_rowid();
+
+        if let Some(policy) = &self.wake_simulation {
+            if let Err(e) = self.simulate_wake(policy, id, message).await {
+                tracing::warn!("Failed to record simulated wake decision: {}", e);
+            }
+        }
+        Ok(id)
+    }
+
+    async fn simulate_wake(
+        &self,
+        policy: &WakePolicy,
+        message_id: i64,
+        message: &SwarmMessage,
+    ) -> Result<()> {
+        let now = Utc::now();
+        let recent: Vec<i64> = sqlx::query_scalar(
+            r#"
+            SELECT decided_at FROM wake_simulations
+            WHERE recipient = ?1 AND wake = 1 AND decided_at >= ?2
+            ORDER BY decided_at DESC
+            "#,
+        )
+        .bind(&message.to)
+        .bind((now - chrono::Duration::hours(1)).timestamp_millis())
+        .fetch_all(&self.pool)
+        .await?;
+        let recent: Vec<DateTime<Utc>> = recent
+            .into_iter()
+            .filter_map(|ms| Utc.timestamp_millis_opt(ms).single())
+            .collect();
+
+        let decision = policy.evaluate(message, &recent, now);
+        tracing::debug!(
+            recipient = %message.to,
+            wake = decision.wake,
+            reason = %decision.reason,
+            "Simulated wake decision"
+        );
+        sqlx::query(
+            r#"
+            INSERT INTO wake_simulations
+                (message_id, recipient, wake, rule, reason, estimated_cost, decided_at)
+            VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7)
+            "#,
+        )
+        .bind(message_id)
+        .bind(&message.to)
+        .bind(decision.wake)
+        .bind(decision.rule.as_str())
+        .bind(&decision.reason)
+        .bind(decision.estimated_cost_usd)
+        .bind(now.timestamp_millis())
+        .execute(&self.pool)
+        .await?;
+        Ok(())
+    }
+
+    /// Summarizes the simulated wake decisions made between `since` and
+    /// `until`.
+    pub async fn wake_report(
+        &self,
+        since: DateTime<Utc>,
+        until: DateTime<Utc>,
+    ) -> Result<WakeReport> {
+        let (from, to) = (since.timestamp_millis(), until.timestamp_millis());
+        let recipients: Vec<RecipientWakes> = sqlx::query(
+            r#"
+            SELECT recipient, COUNT(*) AS messages, SUM(wake) AS would_wake,
+                   SUM(estimated_cost) AS cost
+            FROM wake_simulations
+            WHERE decided_at >= ?1 AND decided_at < ?2
+            GROUP BY recipient
+            ORDER BY recipient
+            "#,
+        )
+        .bind(from)
+        .bind(to)
+        .fetch_all(&self.pool)
+        .await?
+        .into_iter()
+        .map(|row| RecipientWakes {
+            recipient: row.get("recipient"),
+            messages: row.get::<i64, _>("messages") as u64,
+            would_wake: row.get::<i64, _>("would_wake") as u64,
+            estimated_cost_usd: row.get("cost"),
+        })
+        .collect();
+
+        let rules = sqlx::query(
+            r#"
+            SELECT rule, COUNT(*) AS count FROM wake_simulations
+            WHERE decided_at >= ?1 AND decided_at < ?2
+            GROUP BY rule
+            ORDER BY count DESC, rule
+            "#,
+        )
+        .bind(from)
+        .bind(to)
+        .fetch_all(&self.pool)
+        .await?
+        .into_iter()
+        .map(|row| WakeRuleCount {
+            rule: row.get("rule"),
+            count: row.get::<i64, _>("count") as u64,
+        })
+        .collect();
+
+        Ok(WakeReport {
+            since,
+            until,
+            evaluated: recipients.iter().map(|r| r.messages).sum(),
+            would_wake: recipients.iter().map(|r| r.would_wake).sum(),
+            estimated_cost_usd: recipients.iter().map(|r| r.estimated_cost_usd).sum(),
+            rules,
+            recipients,
+        })
+    }
+
+    /// Deliver up to `limit` visible messages, hiding them until acknowledged
+    /// or the visibility timeout passes. Messages already delivered the
+    /// maximum number of times go to the dead-letter queue instead.
+    pub async fn receive(&self, recipient: &str, limit: usize) -> Result<Vec<Delivery>> {
+        let now = Utc::now().timestamp_millis();
+        let mut tx = self.pool.begin().await?;
+
+        let reason = format!("Not acknowledged after {} deliveries", self.max_deliveries);
+        sqlx::query(
+            r#"
+            INSERT INTO mailbox_dead_letters (id, recipient, payload, attempts, reason, dead_at)
+            SELECT id, recipient, payload, attempts, ?3, ?4 FROM mailbox_messages
+            WHERE recipient = ?1 AND visible_at <= ?4 AND attempts >= ?2
+            "#,
+        )
+        .bind(recipient)
+        .bind(self.max_deliveries)
+        .bind(&reason)
+        .bind(now)
+        .execute(&mut *tx)
+        .await?;
+        let dead = sqlx::query(
+            r#"
+            DELETE FROM mailbox_messages
+            WHERE recipient = ?1 AND visible_at <= ?3 AND attempts >= ?2
+            "#,
+        )
+        .bind(recipient)
+        .bind(self.max_deliveries)
+        .bind(now)
+        .execute(&mut *tx)
+        .await?;
+        if dead.rows_affected() > 0 {
+            tracing::warn!(
+                recipient,
+                count = dead.rows_affected(),
+                "Moved undeliverable messages to the dead-letter queue"
+            );
+        }
+
+        let rows = sqlx::query(
+            r#"
+            SELECT id, payload, attempts FROM mailbox_messages
+            WHERE recipient = ?1 AND visible_at <= ?2
+            ORDER BY id
+            LIMIT ?3
+            "#,
+        )
+        .bind(recipient)
+        .bind(now)
+        .bind(limit as i64)
+        .fetch_all(&mut *tx)
+        .await?;
+
+        let visible_at = now + self.visibility_timeout.as_millis() as i64;
+        let mut deliveries = Vec::with_capacity(rows.len());
+        for row in rows {
+            let id: i64 = row.get("id");
+            let attempts: u32 = row.get::<i64, _>("attempts") as u32 + 1;
+            sqlx::query("UPDATE mailbox_messages SET attempts = ?2, visible_at = ?3 WHERE id = ?1")
+                .bind(id)
+                .bind(attempts)
+                .bind(visible_at)
+                .execute(&mut *tx)
+                .await?;
+            deliveries.push(Delivery {
+                id,
+                message: serde_json::from_str(row.get("payload"))?,
+                attempts,
+                receipt: receipt(id, attempts),
+            });
+        }
+
+        tx.commit().await?;
+        Ok(deliveries)
+    }
+
+    /// Acknowledge a delivery to `recipient` by its receipt so it is not
+    /// redelivered. Returns false if the message was already acknowledged,
+    /// dead-lettered, or delivered again since the receipt was issued.
+    pub async fn ack(&self, recipient: &str, receipt: &str) -> Result<bool> {
+        let Some((id, attempts)) = parse_receipt(receipt) else {
+            return Ok(false);
+        };
+        let result = sqlx::query(
+            "DELETE FROM mailbox_messages WHERE id = ?1 AND recipient = ?2 AND attempts = ?3",
+        )
+        .bind(id)
+        .bind(recipient)
+        .bind(attempts)
+        .execute(&self.pool)
+        .await?;
+        Ok(result.rows_affected() > 0)
+    }
+
+    pub async fn stats(&self) -> Result<Vec<MailboxStats>> {
+        let rows = sqlx::query(
+            r#"
+            SELECT recipient,
+                   SUM(CASE WHEN visible_at <= ?1 THEN 1 ELSE 0 END) AS pending,
+                   SUM(CASE WHEN visible_at > ?1 THEN 1 ELSE 0 END) AS in_flight
+            FROM mailbox_messages
+            GROUP BY recipient
+            ORDER BY recipient
+            "#,
+        )
+        .bind(Utc::now().timestamp_millis())
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(rows
+            .into_iter()
+            .map(|row| MailboxStats {
+                recipient: row.get("recipient"),
+                pending: row.get::<i64, _>("pending") as u64,
+                in_flight: row.get::<i64, _>("in_flight") as u64,
+            })
+            .collect())
+    }
+
+    pub async fn dead_letters(&self) -> Result<Vec<DeadLetter>> {
+        let rows = sqlx::query(
+            r#"
+            SELECT id, recipient, payload, attempts, reason, dead_at
+            FROM mailbox_dead_letters
+            ORDER BY dead_at, id
+            "#,
+        )
+        .fetch_all(&self.pool)
+        .await?;
+
+        rows.into_iter()
+            .map(|row| {
+                Ok(DeadLetter {
+                    id: row.get("id"),
+                    recipient: row.get("recipient"),
+                    message: serde_json::from_str(row.get("payload"))?,
+                    attempts: row.get::<i64, _>("attempts") as u32,
+                    reason: row.get("reason"),
+                    dead_at: Utc
+                        .timestamp_millis_opt(row.get("dead_at"))
+                        .single()
+                        .unwrap_or_else(Utc::now),
+                })
+            })
+            .collect()
+    }
+
+    /// Move a dead letter back into its mailbox with a fresh delivery count.
+    pub async fn requeue_dead_letter(&self, id: i64) -> Result<bool> {
+        let now = Utc::now().timestamp_millis();
+        let mut tx = self.pool.begin().await?;
+        let moved = sqlx::query(
+            r#"
+            INSERT INTO mailbox_messages (recipient, payload, attempts, visible_at, created_at)
+            SELECT recipient, payload, 0, ?2, ?2 FROM mailbox_dead_letters WHERE id = ?1
+            "#,
+        )
+        .bind(id)
+        .bind(now)
+        .execute(&mut *tx)
+        .await?;
+        sqlx::query("DELETE FROM mailbox_dead_letters WHERE id = ?1")
+            .bind(id)
+            .execute(&mut *tx)
+            .await?;
+        tx.commit().await?;
+        Ok(moved.rows_affected() > 0)
+    }
+
+    pub async fn purge_dead_letter(&self, id: i64) -> Result<bool> {
+        let result = sqlx::query("DELETE FROM mailbox_dead_letters WHERE id = ?1")
+            .bind(id)
+            .execute(&self.pool)
+            .await?;
+        Ok(result.rows_affected() > 0)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::agents::swarm::MessageType;
+
+    fn message(to: &str, content: &str) -> SwarmMessage {
+        SwarmMessage {
+            from: "coordinator".into(),
+            to: to.into(),
+            message_type: MessageType::TaskHandoff,
+            content: content.into(),
+            timestamp: Utc::now(),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_acknowledged_messages_are_not_redelivered() {
+        let store = MailboxStore::in_memory().await.unwrap();
+        store.send(&message("coder", "one")).await.unwrap();
+        store.send(&message("coder", "two")).await.unwrap();
+        store.send(&message("tester", "three")).await.unwrap();
+
+        let deliveries = store.receive("coder", 10).await.unwrap();
+        assert_eq!(deliveries.len(), 2);
+        assert_eq!(deliveries[0].message.content, "one");
+        assert_eq!(deliveries[0].attempts, 1);
+
+        // Hidden while in flight
+        assert!(store.receive("coder", 10).await.unwrap().is_empty());
+        assert!(!store.ack("tester", &deliveries[0].receipt).await.unwrap());
+        assert!(store.ack("coder", &deliveries[0].receipt).await.unwrap());
+        assert!(!store.ack("coder", &deliveries[0].receipt).await.unwrap());
+        assert_eq!(
+            store.stats().await.unwrap(),
+            vec![
+                MailboxStats {
+                    recipient: "coder".into(),
+                    pending: 0,
+                    in_flight: 1
+                },
+                MailboxStats {
+                    recipient: "tester".into(),
+                    pending: 1,
+                    in_flight: 0
+                },
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_unacknowledged_messages_are_redelivered_then_dead_lettered() {
+        let store = MailboxStore::in_memory()
+            .await
+            .unwrap()
+            .with_limits(Duration::ZERO, 2);
+        store.send(&message("coder", "poison")).await.unwrap();
+
+        let first = store.receive("coder", 10).await.unwrap().remove(0);
+        assert_eq!(first.attempts, 1);
+        assert_eq!(store.receive("coder", 10).await.unwrap()[0].attempts, 2);
+        // The first receiver's receipt does not acknowledge the redelivery
+        assert!(!store.ack("coder", &first.receipt).await.unwrap());
+        assert!(store.receive("coder", 10).await.unwrap().is_empty());
+
+        let dead = store.dead_letters().await.unwrap();
+        assert_eq!(dead.len(), 1);
+        assert_eq!(dead[0].recipient, "coder");
+        assert_eq!(dead[0].attempts, 2);
+
+        assert!(store.requeue_dead_letter(dead[0].id).await.unwrap());
+        assert!(store.dead_letters().await.unwrap().is_empty());
+        let redelivered = store.receive("coder", 10).await.unwrap();
+        assert_eq!(redelivered[0].message.content, "poison");
+        assert_eq!(redelivered[0].attempts, 1);
+    }
+
+    #[tokio::test]
+    async fn test_wake_simulation_records_decisions_without_delivering() {
+        let store = MailboxStore::in_memory()
+            .await
+            .unwrap()
+            .with_wake_simulation(WakePolicy::default());
+        let start = Utc::now() - chrono::Duration::seconds(1);
+        store.send(&message("coder", "first")).await.unwrap();
+        store.send(&message("coder", "second")).await.unwrap();
+        let mut status = message("tester", "done");
+        status.message_type = MessageType::StatusUpdate;
+        store.send(&status).await.unwrap();
+
+        let report = store
+            .wake_report(start, Utc::now() + chrono::Duration::seconds(1))
+            .await
+            .unwrap();
+        assert_eq!(report.evaluated, 3);
+        assert_eq!(report.would_wake, 1);
+        assert!(report.estimated_cost_usd > 0.0);
+        let rules: Vec<&str> = report.rules.iter().map(|r| r.rule.as_str()).collect();
+        assert_eq!(rules, vec!["cooldown", "message_type", "wakes"]);
+        assert_eq!(report.recipients[0].would_wake, 1);
+        assert_eq!(store.receive("coder", 10).await.unwrap().len(), 2);
+    }
+
+    #[tokio::test]
+    async fn test_purge_dead_letter() {
+        let store = MailboxStore::in_memory()
+            .await
+            .unwrap()
+            .with_limits(Duration::ZERO, 1);
+        store.send(&message("coder", "poison")).await.unwrap();
+        store.receive("coder", 10).await.unwrap();
+        store.receive("coder", 10).await.unwrap();
+
+        let dead = store.dead_letters().await.unwrap();
+        assert!(store.purge_dead_letter(dead[0].id).await.unwrap());
+        assert!(!store.requeue_dead_letter(dead[0].id).await.unwrap());
+    }
+}
@@ -1,6 +1,6 @@
-use crate::action_required_manager::ActionRequiredManager;
+use crate::action_required_manager::{ActionRequiredManager, Elicitation};
 use crate::agents::types::SharedProvider;
-use crate::session_context::{SESSION_ID_HEADER, WORKING_DIR_HEADER};
+use crate::session_context::{ENV_HEADER, SESSION_ID_HEADER, WORKING_DIR_HEADER};
 use rmcp::model::{
     Content, CreateElicitationRequestParams, CreateElicitationResult, ElicitationAction, ErrorCode,
     Extensions, JsonObject, Meta,
@@ -27,6 +27,8 @@ use rmcp::{
     ClientHandler, ErrorData, Peer, RoleClient, ServiceError, ServiceExt,
 };
 use serde_json::Value;
+use std::collections::HashMap;
+use std::sync::{OnceLock, RwLock};
 use std::{sync::Arc, time::Duration};
 use tokio::sync::{
     mpsc::{self, Sender},
@@ -37,6 +39,11 @@ pub type BoxError = Box<dyn std::error::Error + Sync + Send>;
 
 pub type Error = rmcp::ServiceError;
 
+/// A session's resolved environment, shared with the clients it is forwarded to
+pub type SharedEnv = Arc<RwLock<HashMap<String, String>>>;
+
+const NOTIFICATION_SEND_TIMEOUT: Duration = Duration::from_secs(1);
+
 #[async_trait::async_trait]
 pub trait McpClientTrait: Send + Sync {
     async fn list_tools(
@@ -108,6 +115,8 @@ pub struct GooseClient {
     provider: SharedProvider,
     // Single-slot because calls are serialized per MCP client.
     current_session_id: Arc<Mutex<Option<String>>>,
+    /// Namespaces the secrets this extension's elicitations store
+    extension_name: Arc<OnceLock<String>>,
 }
 
 impl GooseClient {
@@ -119,6 +128,7 @@ impl GooseClient {
             notification_handlers: handlers,
             provider,
             current_session_id: Arc::new(Mutex::new(None)),
+            extension_name: Arc::new(OnceLock::new()),
         }
     }
 
@@ -143,6 +153,22 @@ impl GooseClient {
         Self::session_id_from_extensions(extensions).or(current_session_id)
     }
 
+    /// Passes a notification on to every open subscription. A subscriber that
+    /// is behind is waited for, up to `NOTIFICATION_SEND_TIMEOUT`, so that
+    /// streamed tool output slows the extension down instead of being dropped.
+    async fn notify_subscribers(&self, notification: ServerNotification) {
+        let handlers = {
+            let mut handlers = self.notification_handlers.lock().await;
+            handlers.retain(|handler| !handler.is_closed());
+            handlers.clone()
+        };
+        for handler in handlers {
+            let _ = handler
+                .send_timeout(notification.clone(), NOTIFICATION_SEND_TIMEOUT)
+                .await;
+        }
+    }
+
     fn session_id_from_extensions(extensions: &Extensions) -> Option<String> {
         let meta = extensions.get::<Meta>()?;
         meta.0
@@ -159,19 +185,14 @@ impl ClientHandler for GooseClient {
         params: rmcp::model::ProgressNotificationParam,
         context: rmcp::service::NotificationContext<rmcp::RoleClient>,
     ) {
-        self.notification_handlers
-            .lock()
-            .await
-            .iter()
-            .for_each(|handler| {
-                let _ = handler.try_send(ServerNotification::ProgressNotification(
-                    ProgressNotification {
-                        params: params.clone(),
-                        method: ProgressNotificationMethod,
-                        extensions: context.extensions.clone(),
-                    },
-                ));
-            });
+        self.notify_subscribers(ServerNotification::ProgressNotification(
+            ProgressNotification {
+                params,
+                method: ProgressNotificationMethod,
+                extensions: context.extensions,
+            },
+        ))
+        .await;
     }
 
     async fn on_logging_message(
@@ -179,19 +200,14 @@ impl ClientHandler for GooseClient {
         params: rmcp::model::LoggingMessageNotificationParam,
         context: rmcp::service::NotificationContext<rmcp::RoleClient>,
     ) {
-        self.notification_handlers
-            .lock()
-            .await
-            .iter()
-            .for_each(|handler| {
-                let _ = handler.try_send(ServerNotification::LoggingMessageNotification(
-                    LoggingMessageNotification {
-                        params: params.clone(),
-                        method: LoggingMessageNotificationMethod,
-                        extensions: context.extensions.clone(),
-                    },
-                ));
-            });
+        self.notify_subscribers(ServerNotification::LoggingMessageNotification(
+            LoggingMessageNotification {
+                params,
+                method: LoggingMessageNotificationMethod,
+                extensions: context.extensions,
+            },
+        ))
+        .await;
     }
 
     async fn create_message(
@@ -282,7 +298,7 @@ impl ClientHandler for GooseClient {
     async fn create_elicitation(
         &self,
         request: CreateElicitationRequestParams,
-        _context: RequestContext<RoleClient>,
+        context: RequestContext<RoleClient>,
     ) -> Result<CreateElicitationResult, ErrorData> {
         let schema_value = serde_json::to_value(&request.requested_schema).map_err(|e| {
             ErrorData::new(
@@ -292,12 +308,15 @@ impl ClientHandler for GooseClient {
             )
         })?;
 
+        let session_id = self.resolve_session_id(&context.extensions).await;
+        let mut elicitation = Elicitation::new("mcp", request.message.clone(), schema_value)
+            .in_session(session_id)
+            .with_timeout(Duration::from_secs(300));
+        if let Some(name) = self.extension_name.get() {
+            elicitation = elicitation.from_extension(name);
+        }
         ActionRequiredManager::global()
-            .request_and_wait(
-                request.message.clone(),
-                schema_value,
-                Duration::from_secs(300),
-            )
+            .elicit(elicitation)
             .await
             .map(|user_data| CreateElicitationResult {
                 action: ElicitationAction::Accept,
@@ -339,6 +358,7 @@ pub struct McpClient {
     server_info: Option<InitializeResult>,
     timeout: std::time::Duration,
     docker_container: Option<String>,
+    forwarded_env: Option<SharedEnv>,
 }
 
 impl McpClient {
@@ -378,9 +398,29 @@ impl McpClient {
             server_info,
             timeout,
             docker_container,
+            forwarded_env: None,
         })
     }
 
+    /// Sends the session environment with every tool call, for in-process
+    /// servers that cannot be given it when they start.
+    pub fn with_forwarded_env(mut self, env: SharedEnv) -> Self {
+        self.forwarded_env = Some(env);
+        self
+    }
+
+    /// Names the extension this client serves, so secrets its elicitations
+    /// store cannot collide with goose's own or another extension's.
+    pub fn with_extension_name(mut self, name: &str) -> Self {
+        let _ = self
+            .client
+            .get_mut()
+            .service()
+            .extension_name
+            .set(name.to_string());
+        self
+    }
+
     pub fn docker_container(&self) -> Option<&str> {
         self.docker_container.as_deref()
     }
@@ -393,6 +433,10 @@ impl McpClient {
         cancel_token: CancellationToken,
     ) -> Result<ServerResult, Error> {
         let request = inject_session_context_into_request(request, Some(session_id), working_dir);
+        let request = match &self.forwarded_env {
+            Some(env) => inject_env_into_request(request, &env.read().unwrap()),
+            None => request,
+        };
         // ExtensionManager serializes calls per MCP connection, so one current_session_id slot
         // is sufficient for mapping callbacks to the active request session.
         let handle = {
@@ -716,6 +760,27 @@ fn inject_session_context_into_request(
     }
 }
 
+/// Puts the session environment into a tool call's _meta. Values are never
+/// logged; other requests are left alone.
+fn inject_env_into_request(request: ClientRequest, env: &HashMap<String, String>) -> ClientRequest {
+    match request {
+        ClientRequest::CallToolRequest(mut req) => {
+            let mut meta_map = req
+                .extensions
+                .get::<Meta>()
+                .map(|meta| meta.0.clone())
+                .unwrap_or_default();
+            meta_map.retain(|k, _| !k.eq_ignore_ascii_case(ENV_HEADER));
+            if !env.is_empty() {
+                meta_map.insert(ENV_HEADER.to_string(), serde_json::json!(env));
+            }
+            req.extensions.insert(Meta(meta_map));
+            ClientRequest::CallToolRequest(req)
+        }
+        other => other,
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -941,4 +1006,22 @@ mod tests {
 
         assert_eq!(&mcp_meta.0, expected_meta.as_object().unwrap());
     }
+
+    #[test]
+    fn test_env_only_forwarded_on_tool_calls() {
+        let env = HashMap::from([("API_TOKEN".to_string(), "t0ken".to_string())]);
+
+        let request = inject_env_into_request(call_tool_request(Extensions::new()), &env);
+        let meta = request_extensions(&request).unwrap().get::<Meta>().unwrap();
+        assert_eq!(
+            meta.0.get(ENV_HEADER),
+            Some(&json!({ "API_TOKEN": "t0ken" }))
+        );
+
+        let request = inject_env_into_request(list_tools_request(Extensions::new()), &env);
+        assert!(request_extensions(&request)
+            .unwrap()
+            .get::<Meta>()
+            .is_none());
+    }
 }
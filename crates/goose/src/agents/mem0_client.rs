@@ -6,8 +6,11 @@
 //!
 //! Mem0 API: http://localhost:8080 (default, configurable via MEM0_API_URL)
 
+use crate::startup::{self, StartupPhase};
 use reqwest::Client;
 use serde_json::json;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
 use std::time::Duration;
 use tracing::{debug, info, warn};
 
@@ -15,7 +18,7 @@ use tracing::{debug, info, warn};
 pub struct Mem0Client {
     client: Client,
     base_url: String,
-    available: bool,
+    available: Arc<AtomicBool>,
 }
 
 impl Mem0Client {
@@ -32,53 +35,78 @@ impl Mem0Client {
         Self {
             client,
             base_url,
-            available: false,
+            available: Arc::new(AtomicBool::new(false)),
         }
     }
 
     /// Ping the Mem0 service. Sets self.available accordingly.
     pub async fn check_health(&mut self) -> bool {
-        match self
-            .client
-            .get(format!("{}/health", self.base_url))
+        let available = Self::probe(&self.client, &self.base_url).await;
+        self.available.store(available, Ordering::Relaxed);
+        available
+    }
+
+    /// Pings the Mem0 service without waiting for it. Mem0 counts as
+    /// unavailable until it answers.
+    pub fn check_health_in_background(&self) {
+        let client = self.client.clone();
+        let base_url = self.base_url.clone();
+        let available = Arc::clone(&self.available);
+        tokio::spawn(async move {
+            let start = std::time::Instant::now();
+            let healthy = Self::probe(&client, &base_url).await;
+            available.store(healthy, Ordering::Relaxed);
+            startup::record("mem0", StartupPhase::FirstReply, start.elapsed());
+        });
+    }
+
+    async fn probe(client: &Client, base_url: &str) -> bool {
+        match client
+            .get(format!("{}/health", base_url))
             .timeout(Duration::from_secs(2))
             .send()
             .await
         {
             Ok(resp) if resp.status().is_success() => {
-                info!(url = %self.base_url, "Mem0 service is available");
-                self.available = true;
+                info!(url = %base_url, "Mem0 service is available");
                 true
             }
             Ok(resp) => {
                 debug!(
-                    url = %self.base_url,
+                    url = %base_url,
                     status = %resp.status(),
                     "Mem0 service returned non-success status"
                 );
-                self.available = false;
                 false
             }
             Err(e) => {
                 debug!(
-                    url = %self.base_url,
+                    url = %base_url,
                     error = %e,
                     "Mem0 service unavailable (this is OK — local memory still works)"
                 );
-                self.available = false;
                 false
             }
         }
     }
 
-    /// Whether Mem0 is currently available.
+    /// Whether Mem0 is currently available. A remote Mem0 stands down while
+    /// goose is offline; one on this machine keeps working.
     pub fn is_available(&self) -> bool {
-        self.available
+        self.available.load(Ordering::Relaxed)
+            && (self.is_local() || !crate::connectivity::is_offline())
+    }
+
+    fn is_local(&self) -> bool {
+        reqwest::Url::parse(&self.base_url)
+            .ok()
+            .and_then(|url| url.host_str().map(str::to_string))
+            .is_some_and(|host| matches!(host.as_str(), "localhost" | "127.0.0.1" | "[::1]"))
     }
 
     /// Store a memory in Mem0. No-op if Mem0 is unavailable.
     pub async fn add_memory(&self, content: &str, user_id: &str) -> anyhow::Result<()> {
-        if !self.available {
+        if !self.is_available() {
             return Ok(());
         }
         let resp = self
@@ -112,7 +140,7 @@ impl Mem0Client {
 
     /// Search memories in Mem0. Returns empty vec if Mem0 is unavailable.
     pub async fn search_memory(&self, query: &str, user_id: &str) -> Vec<String> {
-        if !self.available {
+        if !self.is_available() {
             return vec![];
         }
         match self
@@ -145,6 +173,25 @@ impl Mem0Client {
             }
         }
     }
+
+    /// Delete every Mem0 memory stored for `user_id`. Returns false when Mem0
+    /// is unavailable and nothing could be deleted.
+    pub async fn delete_memories(&self, user_id: &str) -> anyhow::Result<bool> {
+        if !self.is_available() {
+            return Ok(false);
+        }
+        let resp = self
+            .client
+            .delete(format!("{}/v1/memories", self.base_url))
+            .query(&[("user_id", user_id)])
+            .send()
+            .await?;
+        if !resp.status().is_success() {
+            anyhow::bail!("Mem0 delete returned {}", resp.status());
+        }
+        debug!(user_id, "Memories deleted from Mem0");
+        Ok(true)
+    }
 }
 
 impl Default for Mem0Client {
@@ -1,22 +1,38 @@
 pub mod adversarial;
 mod agent;
 pub(crate) mod apps_extension;
+pub(crate) mod browser_extension;
 mod builtin_skills;
 pub mod capabilities;
+pub mod capability_grant;
 pub(crate) mod chatrecall_extension;
 pub(crate) mod code_execution_extension;
+pub mod command_registry;
+pub mod commit_assistant;
 pub mod container;
+pub mod core_registry;
+pub mod core_selector;
 pub mod critic;
+pub(crate) mod data_extension;
+pub mod dependency_upgrade;
+pub mod dependency_watch;
+pub mod devcontainer;
 pub mod done_gate;
 pub mod dspy_loader;
+pub(crate) mod email_extension;
 pub mod evolution;
 pub mod execute_commands;
 pub mod extension;
+pub mod file_claims;
 #[cfg(feature = "memory")]
 pub mod hitl;
+pub(crate) mod issues_extension;
+pub mod mailbox;
 #[cfg(feature = "memory")]
 pub mod benchmark;
 #[cfg(feature = "memory")]
+pub mod benchmark_matrix;
+#[cfg(feature = "memory")]
 pub mod graph;
 #[cfg(feature = "memory")]
 pub mod skill_registry;
@@ -24,6 +40,8 @@ pub mod skill_registry;
 pub mod extended_thinking;
 #[cfg(feature = "memory")]
 pub mod swarm;
+#[cfg(feature = "memory")]
+pub mod swarm_presets;
 pub mod extension_malware_check;
 pub mod extension_manager;
 pub mod extension_manager_extension;
@@ -37,18 +55,26 @@ pub mod orchestrator;
 pub mod persistence;
 pub mod planner;
 pub mod platform_tools;
+pub mod prefetch;
+pub mod progress_watchdog;
 pub mod project_detector;
 pub mod prompt_manager;
 pub mod reasoning;
 pub mod reflexion;
+pub mod refusal_retry;
 mod reply_parts;
 pub mod retry;
+pub mod running_tools;
 pub mod runbook_compliance; // Phase 7: Markdown-as-Contract execution
 mod schedule_tool;
+pub mod self_report;
 pub mod shell_guard;
+pub mod simulated_extension;
 pub(crate) mod skills_extension;
+pub mod smoke_check;
 pub mod specialists;
 pub mod state_graph;
+pub mod stream_chunking;
 pub mod subagent_execution_tool;
 pub mod subagent_handler;
 mod subagent_task_config;
@@ -57,13 +83,16 @@ pub mod team;
 pub(crate) mod todo_extension;
 pub(crate) mod tom_extension;
 mod tool_execution;
+pub mod tool_output;
 pub mod types;
+pub mod vision;
+pub mod wake_policy;
 pub mod workflow_engine;
 
 pub use adversarial::{
     AdversarialConfig, AdversarialRole, CoachAgent, CoachConfig, CoachReview, IssueCategory,
     IssueSeverity, PlayerAgent, PlayerConfig, PlayerResult, QualityStandards, ReviewCycle,
-    ReviewFeedback, ReviewIssue, ReviewOutcome, ReviewStats,
+    ReviewFeedback, ReviewHistory, ReviewIssue, ReviewOutcome, ReviewStats, ReviewTranscript,
 };
 pub use agent::{
     Agent, AgentConfig, AgentEvent, CritiqueDecision, ExecutionMode, ExtensionLoadResult,
@@ -95,7 +124,7 @@ pub use persistence::{
     CheckpointSummary, Checkpointer, MemoryCheckpointer, SqliteCheckpointer, ThreadId,
 };
 pub use planner::{Plan, PlanContext, PlanManager, PlanStatus, PlanStep, Planner, StepStatus};
-pub use prompt_manager::PromptManager;
+pub use prompt_manager::{PromptManager, PromptSection, SectionTokens};
 pub use reasoning::{
     ActionResult, ReActTrace, ReasonedAction, ReasoningConfig, ReasoningManager, ReasoningMode,
     Thought, ThoughtType,
@@ -104,6 +133,7 @@ pub use reflexion::{
     AttemptAction, AttemptOutcome, Reflection, ReflectionMemory, ReflexionAgent, ReflexionConfig,
     TaskAttempt,
 };
+pub use self_report::SelfReport;
 pub use specialists::{
     CodeAgent, DeployAgent, DocsAgent, SecurityAgent, SpecialistAgent, SpecialistConfig,
     SpecialistContext, SpecialistFactory, TestAgent,
@@ -490,6 +490,11 @@ impl CostTracker {
         *budget = Some(limit);
     }
 
+    /// Remove the budget limit
+    pub async fn clear_budget(&self) {
+        *self.budget_limit.write().await = None;
+    }
+
     /// Check if we're over budget
     pub async fn is_over_budget(&self) -> bool {
         let budget = self.budget_limit.read().await;
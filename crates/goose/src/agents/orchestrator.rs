@@ -532,11 +532,13 @@ impl AgentOrchestrator {
             task.started_at = Some(start_time);
             task.progress_percentage = 10;
 
+            let mut metadata = task.metadata.clone();
+            metadata.insert("task_id".to_string(), task_id.to_string());
             (
                 task.role,
                 task.description.clone(),
                 ".".to_string(), // Default working directory
-                task.metadata.clone(),
+                metadata,
             )
         };
 
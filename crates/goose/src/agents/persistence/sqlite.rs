@@ -1,10 +1,12 @@
 //! SQLite-based checkpoint storage for durable persistence using sqlx
 
 use super::{Checkpoint, CheckpointSummary, Checkpointer};
+use crate::database;
+use crate::security::at_rest;
 use anyhow::{Context, Result};
 use chrono::{DateTime, TimeZone, Utc};
 use sqlx::{sqlite::SqlitePoolOptions, Pool, Row, Sqlite};
-use std::path::Path;
+use std::path::{Path, PathBuf};
 
 /// SQLite-based checkpointer for durable checkpoint storage
 ///
@@ -16,6 +18,15 @@ pub struct SqliteCheckpointer {
 }
 
 impl SqliteCheckpointer {
+    /// Where the agent keeps its checkpoint database
+    pub fn default_path() -> PathBuf {
+        dirs::config_dir()
+            .unwrap_or_else(|| PathBuf::from("."))
+            .join("goose")
+            .join("checkpoints")
+            .join("agent.db")
+    }
+
     /// Create a new SQLite checkpointer with the given database path
     pub async fn new(path: impl AsRef<Path>) -> Result<Self> {
         let path = path.as_ref();
@@ -27,24 +38,16 @@ impl SqliteCheckpointer {
             }
         }
 
-        // Create the database file if it doesn't exist
-        if !path.exists() {
-            std::fs::File::create(path)?;
-        }
-
-        let database_url = format!("sqlite:{}", path.display());
+        let checkpointer = Self {
+            pool: database::open("checkpoints", path),
+        };
 
-        let pool = SqlitePoolOptions::new()
-            .max_connections(5)
-            .connect(&database_url)
+        // Initialize schema
+        checkpointer
+            .init_schema()
             .await
             .with_context(|| format!("Failed to connect to SQLite database at {:?}", path))?;
 
-        let checkpointer = Self { pool };
-
-        // Initialize schema
-        checkpointer.init_schema().await?;
-
         Ok(checkpointer)
     }
 
@@ -98,6 +101,41 @@ impl SqliteCheckpointer {
         Ok(())
     }
 
+    /// Encrypts checkpoint state saved before encryption at rest was turned
+    /// on. Returns how many checkpoints were sealed.
+    pub async fn seal_existing(&self) -> Result<usize> {
+        let rows =
+            sqlx::query("SELECT checkpoint_id, state FROM checkpoints WHERE state NOT LIKE ?1")
+                .bind(at_rest::SEALED_LIKE_PATTERN)
+                .fetch_all(&self.pool)
+                .await?;
+
+        let mut tx = self.pool.begin().await?;
+        for row in &rows {
+            let checkpoint_id: String = row.get("checkpoint_id");
+            let state: String = row.get("state");
+            sqlx::query("UPDATE checkpoints SET state = ?1 WHERE checkpoint_id = ?2")
+                .bind(at_rest::seal_always(&state)?)
+                .bind(&checkpoint_id)
+                .execute(&mut *tx)
+                .await?;
+        }
+        tx.commit().await?;
+
+        self.compact().await?;
+        Ok(rows.len())
+    }
+
+    /// Rewrites the file and empties the WAL, so deleted or overwritten
+    /// checkpoints can no longer be read from free pages.
+    pub async fn compact(&self) -> Result<()> {
+        sqlx::query("VACUUM").execute(&self.pool).await?;
+        sqlx::query("PRAGMA wal_checkpoint(TRUNCATE)")
+            .execute(&self.pool)
+            .await?;
+        Ok(())
+    }
+
     /// Convert a timestamp to DateTime<Utc>
     fn timestamp_to_datetime(ts: i64) -> DateTime<Utc> {
         Utc.timestamp_opt(ts, 0).single().unwrap_or_else(Utc::now)
@@ -107,7 +145,7 @@ impl SqliteCheckpointer {
 #[async_trait::async_trait]
 impl Checkpointer for SqliteCheckpointer {
     async fn save(&self, checkpoint: &Checkpoint) -> Result<()> {
-        let state_json = serde_json::to_string(&checkpoint.state)?;
+        let state_json = at_rest::seal(serde_json::to_string(&checkpoint.state)?)?;
         let metadata_json = serde_json::to_string(&checkpoint.metadata)?;
         let created_at = checkpoint.created_at.timestamp();
 
@@ -157,7 +195,7 @@ impl Checkpointer for SqliteCheckpointer {
                     checkpoint_id,
                     thread_id,
                     parent_id,
-                    state: serde_json::from_str(&state)?,
+                    state: serde_json::from_str(&at_rest::open(state)?)?,
                     metadata: serde_json::from_str(&metadata)?,
                     created_at: Self::timestamp_to_datetime(created_at),
                 }))
@@ -191,7 +229,7 @@ impl Checkpointer for SqliteCheckpointer {
                     checkpoint_id,
                     thread_id,
                     parent_id,
-                    state: serde_json::from_str(&state)?,
+                    state: serde_json::from_str(&at_rest::open(state)?)?,
                     metadata: serde_json::from_str(&metadata)?,
                     created_at: Self::timestamp_to_datetime(created_at),
                 }))
@@ -45,3 +45,170 @@ pub fn manage_schedule_tool() -> Tool {
         open_world_hint: Some(false),
     })
 }
+
+pub const PLATFORM_READ_ATTACHMENT_TOOL_NAME: &str = "platform__read_attachment";
+
+pub fn read_attachment_tool() -> Tool {
+    Tool::new(
+        PLATFORM_READ_ATTACHMENT_TOOL_NAME.to_string(),
+        indoc! {r#"
+            Read a file the user attached to this session.
+
+            PDFs and CSV or text files are returned as text. Images are returned
+            as images when the model can see them, otherwise as a short description.
+            The available attachments and their ids are listed in the system prompt.
+        "#}
+        .to_string(),
+        object!({
+            "type": "object",
+            "required": ["attachment_id"],
+            "properties": {
+                "attachment_id": {"type": "string", "description": "Id of the attachment to read"}
+            }
+        }),
+    )
+    .annotate(ToolAnnotations {
+        title: Some("Read attachment".to_string()),
+        read_only_hint: Some(true),
+        destructive_hint: Some(false),
+        idempotent_hint: Some(true),
+        open_world_hint: Some(false),
+    })
+}
+
+pub const PLATFORM_INSPECT_SCREEN_TOOL_NAME: &str = "platform__inspect_screen";
+
+pub fn inspect_screen_tool() -> Tool {
+    Tool::new(
+        PLATFORM_INSPECT_SCREEN_TOOL_NAME.to_string(),
+        indoc! {r#"
+            Take a screenshot of a display or window and describe it.
+
+            The user is asked to approve every capture. The screenshot is read by a
+            vision-capable model, which extracts the visible text, the UI elements and
+            any error dialogs. Pass a question to have it answered from the screenshot,
+            e.g. "Why did this dialog appear?".
+
+            Capture either a display (0 is the main display) or a window by its exact title.
+        "#}
+        .to_string(),
+        object!({
+            "type": "object",
+            "properties": {
+                "display": {"type": "integer", "description": "Display to capture", "default": 0},
+                "window_title": {"type": "string", "description": "Exact title of a window to capture instead of a display"},
+                "question": {"type": "string", "description": "What to find out from the screenshot"}
+            }
+        }),
+    )
+    .annotate(ToolAnnotations {
+        title: Some("Inspect screen".to_string()),
+        read_only_hint: Some(false), // Not pre-approved, captures need consent
+        destructive_hint: Some(false),
+        idempotent_hint: Some(false),
+        open_world_hint: Some(false),
+    })
+}
+
+pub const PLATFORM_SEARCH_KNOWLEDGE_TOOL_NAME: &str = "platform__search_knowledge";
+
+pub fn search_knowledge_tool() -> Tool {
+    Tool::new(
+        PLATFORM_SEARCH_KNOWLEDGE_TOOL_NAME.to_string(),
+        indoc! {r#"
+            Search this workspace's indexed documents: design docs, READMEs, PDFs and
+            pages listed in .goose/knowledge.
+
+            Use it to answer questions about the project's design or conventions before
+            asking the user. Results are the closest passages with their source file
+            and section.
+        "#}
+        .to_string(),
+        object!({
+            "type": "object",
+            "required": ["query"],
+            "properties": {
+                "query": {"type": "string", "description": "What to look for, in natural language"},
+                "limit": {"type": "integer", "description": "Most passages to return", "default": 5}
+            }
+        }),
+    )
+    .annotate(ToolAnnotations {
+        title: Some("Search knowledge".to_string()),
+        read_only_hint: Some(true),
+        destructive_hint: Some(false),
+        idempotent_hint: Some(true),
+        open_world_hint: Some(false),
+    })
+}
+
+pub const PLATFORM_WRITE_COMMIT_MESSAGE_TOOL_NAME: &str = "platform__write_commit_message";
+
+pub fn write_commit_message_tool() -> Tool {
+    Tool::new(
+        PLATFORM_WRITE_COMMIT_MESSAGE_TOOL_NAME.to_string(),
+        indoc! {r#"
+            Write a conventional commit message or pull request description for the
+            changes staged in the working directory's git repository.
+
+            Stage the changes first, then pass a one-line summary of what they do,
+            in the imperative and without a trailing period. The staged files, their
+            line counts, the commit type and scope, and issue ids from the branch name
+            are filled in from git, using the project's commit or PR template. Use the
+            result as-is with git commit -F or as the PR body.
+        "#}
+        .to_string(),
+        object!({
+            "type": "object",
+            "required": ["summary"],
+            "properties": {
+                "summary": {"type": "string", "description": "What the change does, e.g. \"stage shell updates before restarting\""},
+                "type": {"type": "string", "description": "Conventional commit type, inferred from the branch and files when omitted"},
+                "scope": {"type": "string", "description": "Commit scope, inferred from the files when omitted"},
+                "breaking": {"type": "boolean", "description": "Mark the change as breaking", "default": false},
+                "output": {"type": "string", "enum": ["commit", "pull_request"], "default": "commit"}
+            }
+        }),
+    )
+    .annotate(ToolAnnotations {
+        title: Some("Write commit message".to_string()),
+        read_only_hint: Some(true),
+        destructive_hint: Some(false),
+        idempotent_hint: Some(true),
+        open_world_hint: Some(false),
+    })
+}
+
+pub const PLATFORM_UPGRADE_DEPENDENCIES_TOOL_NAME: &str = "platform__upgrade_dependencies";
+
+pub fn upgrade_dependencies_tool() -> Tool {
+    Tool::new(
+        PLATFORM_UPGRADE_DEPENDENCIES_TOOL_NAME.to_string(),
+        indoc! {r#"
+            Upgrade outdated cargo and npm dependencies in the working directory's
+            repository and open a pull request with the result.
+
+            Outdated packages are upgraded in batches on a new deps/upgrade-<date>
+            branch. Each batch must pass the test suites and the quality validators to
+            be committed; a batch that breaks something is rolled back and its failure
+            is noted in the PR description. The working tree must be clean. This runs
+            the full test suite once per batch, so it can take a long time.
+        "#}
+        .to_string(),
+        object!({
+            "type": "object",
+            "properties": {
+                "batch_size": {"type": "integer", "description": "Packages upgraded per batch", "default": 5},
+                "validate": {"type": "boolean", "description": "Also gate batches on the quality validators", "default": true},
+                "open_pr": {"type": "boolean", "description": "Push the branch and open a pull request with gh", "default": true}
+            }
+        }),
+    )
+    .annotate(ToolAnnotations {
+        title: Some("Upgrade dependencies".to_string()),
+        read_only_hint: Some(false),
+        destructive_hint: Some(false),
+        idempotent_hint: Some(false),
+        open_world_hint: Some(true),
+    })
+}
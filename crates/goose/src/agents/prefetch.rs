@@ -0,0 +1,269 @@
+//! Speculative prefetching of reads hinted at by the current plan step.
+//!
+//! When the active plan step hints at reading files (`tool_hints` such as
+//! `read_file`) and names them in its description, the reads start while the
+//! model is still working out its reply. If the model then asks for exactly
+//! one of those calls, the prefetched result is served instead of running
+//! the tool again; whatever it does not ask for is dropped when the next turn
+//! starts. Only file reads are prefetched, since other hints, such as
+//! `search`, do not say what to look for.
+//!
+//! At most `GOOSE_PREFETCH_BUDGET` calls (default 3, 0 turns prefetching
+//! off) are started per turn, and only in modes where reads run without
+//! asking the user.
+
+use crate::agents::extension_manager::ExtensionManager;
+use crate::agents::planner::PlanStep;
+use crate::config::{Config, GooseMode};
+use rmcp::model::{CallToolRequestParams, CallToolResult, ErrorCode, ErrorData, Tool};
+use serde_json::{json, Map, Value};
+use std::collections::{BTreeMap, HashMap};
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use tokio::task::JoinHandle;
+use tokio_util::sync::CancellationToken;
+
+pub const PREFETCH_BUDGET_KEY: &str = "GOOSE_PREFETCH_BUDGET";
+const DEFAULT_PREFETCH_BUDGET: usize = 3;
+
+/// Plan hints that mean reading a file
+const READ_HINTS: &[&str] = &["read_file", "view_file", "read", "view"];
+/// Characters trimmed from words of a step description before they are
+/// taken as paths
+const PATH_TRIM: &[char] = &['`', '\'', '"', '(', ')', ',', ':', ';', '.'];
+
+type Prefetched = JoinHandle<Result<CallToolResult, ErrorData>>;
+
+pub fn budget() -> usize {
+    Config::global()
+        .get_param::<usize>(PREFETCH_BUDGET_KEY)
+        .unwrap_or(DEFAULT_PREFETCH_BUDGET)
+}
+
+/// Whether tools run in `goose_mode` without the user being asked first,
+/// at least read-only ones.
+pub fn mode_allows_prefetch(goose_mode: GooseMode) -> bool {
+    matches!(goose_mode, GooseMode::Auto | GooseMode::SmartApprove)
+}
+
+fn bare_name(tool_name: &str) -> &str {
+    tool_name
+        .rsplit_once("__")
+        .map_or(tool_name, |(_, name)| name)
+}
+
+/// Files named in `description` that exist under `working_dir`.
+fn mentioned_files(description: &str, working_dir: &Path) -> Vec<PathBuf> {
+    let mut files = Vec::new();
+    for word in description.split_whitespace() {
+        let word = word.trim_matches(PATH_TRIM);
+        if !word.contains('.') && !word.contains('/') {
+            continue;
+        }
+        let path = working_dir.join(word);
+        if path.starts_with(working_dir) && path.is_file() && !files.contains(&path) {
+            files.push(path);
+        }
+    }
+    files
+}
+
+/// The call that reads `path` with the tools on offer: a read-only tool
+/// named like a read hint that takes a `path`, or the developer text
+/// editor's `view` command.
+fn read_call(tools: &[Tool], path: &Path) -> Option<CallToolRequestParams> {
+    let takes_path = |tool: &Tool| {
+        tool.input_schema
+            .get("properties")
+            .is_some_and(|p| p.get("path").is_some())
+    };
+    let read_only = |tool: &Tool| {
+        tool.annotations
+            .as_ref()
+            .and_then(|a| a.read_only_hint)
+            .unwrap_or(false)
+    };
+    let path = path.to_string_lossy().to_string();
+
+    let (tool, arguments) = if let Some(tool) = tools
+        .iter()
+        .find(|t| READ_HINTS.contains(&bare_name(&t.name)) && read_only(t) && takes_path(t))
+    {
+        (tool, json!({ "path": path }))
+    } else {
+        let tool = tools.iter().find(|t| bare_name(&t.name) == "text_editor")?;
+        (tool, json!({ "command": "view", "path": path }))
+    };
+    Some(CallToolRequestParams {
+        meta: None,
+        task: None,
+        name: tool.name.clone(),
+        arguments: arguments.as_object().cloned(),
+    })
+}
+
+/// Read calls worth starting for `step`, at most `budget` of them.
+pub fn speculative_calls(
+    step: &PlanStep,
+    tools: &[Tool],
+    working_dir: &Path,
+    budget: usize,
+) -> Vec<CallToolRequestParams> {
+    let hints_read = step
+        .tool_hints
+        .iter()
+        .any(|hint| READ_HINTS.contains(&hint.to_lowercase().as_str()));
+    if !hints_read {
+        return Vec::new();
+    }
+    mentioned_files(&step.description, working_dir)
+        .iter()
+        .filter_map(|path| read_call(tools, path))
+        .take(budget)
+        .collect()
+}
+
+/// Identifies a call by tool and arguments, with a relative `path` resolved
+/// against `working_dir` so that both spellings of a file match.
+fn call_key(name: &str, arguments: Option<&Map<String, Value>>, working_dir: &Path) -> String {
+    let mut arguments: BTreeMap<&str, Value> = arguments
+        .into_iter()
+        .flatten()
+        .map(|(key, value)| (key.as_str(), value.clone()))
+        .collect();
+    if let Some(Value::String(path)) = arguments.get("path") {
+        let resolved = working_dir.join(path).to_string_lossy().to_string();
+        arguments.insert("path", Value::String(resolved));
+    }
+    format!("{}:{}", name, json!(arguments))
+}
+
+/// Prefetched tool calls of the current turn.
+#[derive(Default)]
+pub struct ToolPrefetcher {
+    pending: Mutex<HashMap<String, Prefetched>>,
+}
+
+impl ToolPrefetcher {
+    /// Drops whatever the previous turn prefetched but was never asked for.
+    pub fn clear(&self) {
+        for (_, handle) in self.pending.lock().unwrap().drain() {
+            handle.abort();
+        }
+    }
+
+    /// Starts `calls` in the background.
+    pub fn start(
+        &self,
+        extension_manager: Arc<ExtensionManager>,
+        session_id: &str,
+        working_dir: &Path,
+        calls: Vec<CallToolRequestParams>,
+    ) {
+        let mut pending = self.pending.lock().unwrap();
+        for call in calls {
+            let key = call_key(&call.name, call.arguments.as_ref(), working_dir);
+            if pending.contains_key(&key) {
+                continue;
+            }
+            tracing::debug!(tool = %call.name, "Prefetching hinted tool call");
+            let extension_manager = extension_manager.clone();
+            let session_id = session_id.to_string();
+            let working_dir = working_dir.to_path_buf();
+            let handle = tokio::spawn(async move {
+                let result = extension_manager
+                    .dispatch_tool_call_with_guard(
+                        &session_id,
+                        call,
+                        Some(working_dir.as_path()),
+                        CancellationToken::new(),
+                        None,
+                    )
+                    .await
+                    .map_err(|e| ErrorData::new(ErrorCode::INTERNAL_ERROR, e.to_string(), None))?;
+                result.result.await
+            });
+            pending.insert(key, handle);
+        }
+    }
+
+    /// The prefetched result of `tool_call`, if it was prefetched.
+    pub async fn take(
+        &self,
+        tool_call: &CallToolRequestParams,
+        working_dir: &Path,
+    ) -> Option<Result<CallToolResult, ErrorData>> {
+        let key = call_key(&tool_call.name, tool_call.arguments.as_ref(), working_dir);
+        let handle = self.pending.lock().unwrap().remove(&key)?;
+        match handle.await {
+            Ok(result) => {
+                tracing::debug!(tool = %tool_call.name, "Served tool call from prefetch");
+                Some(result)
+            }
+            Err(_) => None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rmcp::model::ToolAnnotations;
+    use rmcp::object;
+    use std::sync::Arc;
+
+    fn tool(name: &str, read_only: bool) -> Tool {
+        let tool = Tool::new(
+            name.to_string(),
+            String::new(),
+            Arc::new(object!({"type": "object", "properties": {"path": {"type": "string"}}})),
+        );
+        if read_only {
+            tool.annotate(ToolAnnotations {
+                title: None,
+                read_only_hint: Some(true),
+                destructive_hint: Some(false),
+                idempotent_hint: Some(true),
+                open_world_hint: Some(false),
+            })
+        } else {
+            tool
+        }
+    }
+
+    #[test]
+    fn test_speculative_calls_read_files_named_in_step() {
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::create_dir(dir.path().join("src")).unwrap();
+        std::fs::write(dir.path().join("src/main.rs"), "fn main() {}").unwrap();
+        std::fs::write(dir.path().join("Cargo.toml"), "[package]").unwrap();
+        let tools = vec![tool("developer__text_editor", false)];
+
+        let step = PlanStep::new(0, "Read `src/main.rs` and Cargo.toml, then missing.rs.")
+            .with_tools(vec!["read_file".to_string()]);
+        let calls = speculative_calls(&step, &tools, dir.path(), 3);
+        assert_eq!(calls.len(), 2);
+        assert_eq!(calls[0].name, "developer__text_editor");
+        assert_eq!(calls[0].arguments.as_ref().unwrap()["command"], "view");
+
+        assert_eq!(speculative_calls(&step, &tools, dir.path(), 1).len(), 1);
+
+        let edit = PlanStep::new(1, "Edit src/main.rs").with_tools(vec!["edit_file".to_string()]);
+        assert!(speculative_calls(&edit, &tools, dir.path(), 3).is_empty());
+
+        let read_tool = vec![tools[0].clone(), tool("files__read_file", true)];
+        let calls = speculative_calls(&step, &read_tool, dir.path(), 3);
+        assert_eq!(calls[0].name, "files__read_file");
+    }
+
+    #[test]
+    fn test_relative_and_absolute_paths_match() {
+        let dir = Path::new("/work");
+        let relative = object!({"command": "view", "path": "src/main.rs"});
+        let absolute = object!({"path": "/work/src/main.rs", "command": "view"});
+        assert_eq!(
+            call_key("developer__text_editor", Some(&relative), dir),
+            call_key("developer__text_editor", Some(&absolute), dir)
+        );
+    }
+}
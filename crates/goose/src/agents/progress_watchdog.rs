@@ -0,0 +1,249 @@
+//! Catching replies that loop without making progress.
+//!
+//! Each turn of a reply is fingerprinted by the tool calls it made (tool
+//! name and a hash of the arguments, in order) and the text that came with
+//! them. When the last turns repeat a cycle of one to [`MAX_PERIOD`] turns
+//! [`MIN_REPEATS`] times, with the same calls and near-identical text, the
+//! reply is looping. The watchdog first nudges the model to change
+//! strategy. If the loop comes back it escalates as configured for the goose
+//! mode in `GOOSE_LOOP_WATCHDOG`:
+//!
+//! ```yaml
+//! GOOSE_LOOP_WATCHDOG:
+//!   auto: switch_core
+//!   approve: ask_user
+//! ```
+//!
+//! `nudge` keeps nudging, `ask_user` ends the reply with a question for the
+//! user, `switch_core` moves to the other execution core, and `off` leaves
+//! loops alone. By default auto mode switches cores, the approval modes ask
+//! the user and chat mode nudges.
+
+use crate::agents::ExecutionMode;
+use crate::config::{Config, GooseMode};
+use crate::conversation::message::{Message, MessageContent, MessageMetadata};
+use rmcp::model::Role;
+use serde::Deserialize;
+use std::collections::hash_map::DefaultHasher;
+use std::collections::{HashMap, HashSet, VecDeque};
+use std::hash::{Hash, Hasher};
+
+pub const WATCHDOG_KEY: &str = "GOOSE_LOOP_WATCHDOG";
+/// Longest cycle of turns recognized
+pub const MAX_PERIOD: usize = 3;
+/// Times a cycle must repeat before it counts as a loop
+pub const MIN_REPEATS: usize = 3;
+/// Word overlap above which two turns' text counts as the same
+const SIMILAR_TEXT: f64 = 0.85;
+
+const NUDGE: &str = "You appear to be looping: the last several turns repeated the same tool \
+     calls without making progress. Stop and change strategy. Re-read the latest tool results, \
+     state what is blocking you, and try a different approach. If you cannot make progress, \
+     say so and explain what you need.";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Escalation {
+    Nudge,
+    AskUser,
+    SwitchCore,
+    Off,
+}
+
+fn mode_key(mode: GooseMode) -> &'static str {
+    match mode {
+        GooseMode::Auto => "auto",
+        GooseMode::Approve => "approve",
+        GooseMode::SmartApprove => "smart_approve",
+        GooseMode::Chat => "chat",
+    }
+}
+
+/// How loops are escalated in `mode`, after the first nudge.
+pub fn escalation_for(mode: GooseMode) -> Escalation {
+    let configured = Config::global()
+        .get_param::<HashMap<String, Escalation>>(WATCHDOG_KEY)
+        .ok()
+        .and_then(|mut modes| modes.remove(mode_key(mode)));
+    configured.unwrap_or(match mode {
+        GooseMode::Auto => Escalation::SwitchCore,
+        GooseMode::Approve | GooseMode::SmartApprove => Escalation::AskUser,
+        GooseMode::Chat => Escalation::Nudge,
+    })
+}
+
+pub enum Intervention {
+    /// Send the model this message and carry on
+    Nudge(Message),
+    /// End the reply with this question for the user
+    AskUser(Message),
+    /// Move to this core, then carry on after the nudge
+    SwitchCore(ExecutionMode, Message),
+}
+
+struct TurnPrint {
+    calls: u64,
+    words: HashSet<String>,
+}
+
+impl TurnPrint {
+    fn of(messages: &[Message]) -> Self {
+        let mut hasher = DefaultHasher::new();
+        let mut words = HashSet::new();
+        for message in messages.iter().filter(|m| m.role == Role::Assistant) {
+            for content in &message.content {
+                match content {
+                    MessageContent::ToolRequest(request) => {
+                        if let Ok(call) = &request.tool_call {
+                            call.name.hash(&mut hasher);
+                            serde_json::to_string(&call.arguments)
+                                .unwrap_or_default()
+                                .hash(&mut hasher);
+                        }
+                    }
+                    MessageContent::Text(text) => {
+                        words.extend(text.text.split_whitespace().map(str::to_lowercase));
+                    }
+                    _ => {}
+                }
+            }
+        }
+        Self {
+            calls: hasher.finish(),
+            words,
+        }
+    }
+
+    fn same_as(&self, other: &TurnPrint) -> bool {
+        if self.calls != other.calls {
+            return false;
+        }
+        if self.words.is_empty() && other.words.is_empty() {
+            return true;
+        }
+        let shared = self.words.intersection(&other.words).count() as f64;
+        let total = self.words.union(&other.words).count() as f64;
+        shared / total >= SIMILAR_TEXT
+    }
+}
+
+/// Watches the turns of one reply.
+pub struct ProgressWatchdog {
+    escalation: Escalation,
+    turns: VecDeque<TurnPrint>,
+    nudged: bool,
+}
+
+impl ProgressWatchdog {
+    pub fn new(escalation: Escalation) -> Self {
+        Self {
+            escalation,
+            turns: VecDeque::new(),
+            nudged: false,
+        }
+    }
+
+    pub fn for_mode(mode: GooseMode) -> Self {
+        Self::new(escalation_for(mode))
+    }
+
+    fn is_looping(&self) -> bool {
+        (1..=MAX_PERIOD).any(|period| {
+            let window = period * MIN_REPEATS;
+            window <= self.turns.len() && {
+                let start = self.turns.len() - window;
+                (start + period..self.turns.len())
+                    .all(|i| self.turns[i].same_as(&self.turns[i - period]))
+            }
+        })
+    }
+
+    /// Records a finished turn, and says how to intervene when the reply is
+    /// looping. `core` is the execution core the turn ran on.
+    pub fn observe(&mut self, turn: &[Message], core: ExecutionMode) -> Option<Intervention> {
+        if self.escalation == Escalation::Off {
+            return None;
+        }
+        let print = TurnPrint::of(turn);
+        if self.turns.len() == MAX_PERIOD * MIN_REPEATS {
+            self.turns.pop_front();
+        }
+        self.turns.push_back(print);
+        if !self.is_looping() {
+            return None;
+        }
+
+        // The cycle has to repeat in full again before the next intervention
+        self.turns.clear();
+        let nudge = Message::user()
+            .with_text(NUDGE)
+            .with_metadata(MessageMetadata::agent_only());
+        if !self.nudged {
+            self.nudged = true;
+            return Some(Intervention::Nudge(nudge));
+        }
+        Some(match self.escalation {
+            Escalation::AskUser => Intervention::AskUser(Message::assistant().with_text(
+                "I seem to be stuck repeating the same steps without making progress. \
+                 How would you like me to proceed?",
+            )),
+            Escalation::SwitchCore => {
+                let other = match core {
+                    ExecutionMode::Freeform => ExecutionMode::Structured,
+                    ExecutionMode::Structured => ExecutionMode::Freeform,
+                };
+                Intervention::SwitchCore(other, nudge)
+            }
+            Escalation::Nudge | Escalation::Off => Intervention::Nudge(nudge),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rmcp::model::CallToolRequestParams;
+    use rmcp::object;
+
+    fn call(path: &str) -> Vec<Message> {
+        vec![Message::assistant()
+            .with_text("Let me check the file.")
+            .with_tool_request(
+                "id",
+                Ok(CallToolRequestParams {
+                    meta: None,
+                    task: None,
+                    name: "developer__read".into(),
+                    arguments: Some(object!({ "path": path })),
+                }),
+            )]
+    }
+
+    #[test]
+    fn test_alternating_calls_are_caught_and_escalated() {
+        let mut watchdog = ProgressWatchdog::new(Escalation::SwitchCore);
+        let core = ExecutionMode::Freeform;
+
+        let mut interventions = Vec::new();
+        for turn in 0..12 {
+            let path = if turn % 2 == 0 { "a.rs" } else { "b.rs" };
+            if let Some(intervention) = watchdog.observe(&call(path), core) {
+                interventions.push((turn, intervention));
+            }
+        }
+        assert_eq!(interventions.len(), 2);
+        assert_eq!(interventions[0].0, 5);
+        assert!(matches!(interventions[0].1, Intervention::Nudge(_)));
+        assert!(matches!(
+            interventions[1].1,
+            Intervention::SwitchCore(ExecutionMode::Structured, _)
+        ));
+
+        // Turns that differ are progress
+        let mut watchdog = ProgressWatchdog::new(Escalation::AskUser);
+        for turn in 0..12 {
+            let path = format!("file_{}.rs", turn);
+            assert!(watchdog.observe(&call(&path), core).is_none());
+        }
+    }
+}
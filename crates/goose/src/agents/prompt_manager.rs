@@ -3,25 +3,76 @@ use chrono::DateTime;
 use chrono::Utc;
 use serde::Serialize;
 use serde_json::Value;
+use std::collections::hash_map::DefaultHasher;
 use std::collections::HashMap;
+use std::hash::{Hash, Hasher};
+use std::sync::Mutex;
+use utoipa::ToSchema;
 
 use super::dspy_loader;
 use crate::agents::extension::ExtensionInfo;
+use crate::agents::ExecutionMode;
 use crate::hints::load_hints::{load_hint_files, AGENTS_MD_FILENAME, GOOSE_HINTS_FILENAME};
+use crate::token_counter::TokenCounter;
 use crate::{
     config::{Config, GooseMode},
     prompt_template,
     utils::sanitize_unicode_tags,
 };
-use std::path::Path;
+use std::path::{Path, PathBuf};
 
 const MAX_EXTENSIONS: usize = 5;
 const MAX_TOOLS: usize = 50;
+const DEFAULT_SYSTEM_TEMPLATE: &str = "system.md";
 
+/// Builds the system prompt from sections that are cached between turns.
+/// A section is only rebuilt when the inputs it was built from change, so a
+/// session's prompt stays byte-identical until something it depends on does.
 pub struct PromptManager {
     system_prompt_override: Option<String>,
     system_prompt_extras: Vec<String>,
+    /// Template selected by the active recipe instead of `system.md`
+    system_prompt_template: Option<String>,
     current_date_timestamp: String,
+    current_date: String,
+    sections: Mutex<SectionCache>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum PromptSection {
+    /// The rendered system template, with the DSPy prefix
+    Base,
+    /// Extension instructions, rendered inside the base template
+    Extensions,
+    /// Frontend instructions, rendered inside the base template
+    Frontend,
+    /// Extras, hints and the chat mode note
+    Instructions,
+}
+
+#[derive(Debug, Clone, Serialize, ToSchema)]
+pub struct SectionTokens {
+    pub section: PromptSection,
+    pub tokens: usize,
+}
+
+struct CachedSection {
+    key: u64,
+    text: String,
+}
+
+#[derive(Default)]
+struct SectionCache {
+    /// Set when the manager's own settings change; drops every section
+    dirty: bool,
+    sections: HashMap<PromptSection, CachedSection>,
+}
+
+fn section_key<T: Hash + ?Sized>(inputs: &T) -> u64 {
+    let mut hasher = DefaultHasher::new();
+    inputs.hash(&mut hasher);
+    hasher.finish()
 }
 
 impl Default for PromptManager {
@@ -34,6 +85,7 @@ impl Default for PromptManager {
 struct SystemPromptContext {
     extensions: Vec<ExtensionInfo>,
     current_date_time: String,
+    current_date: String,
     #[serde(skip_serializing_if = "Option::is_none")]
     extension_tool_limits: Option<(usize, usize)>,
     goose_mode: GooseMode,
@@ -42,6 +94,11 @@ struct SystemPromptContext {
     max_extensions: usize,
     max_tools: usize,
     code_execution_mode: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    model: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    working_dir: Option<String>,
+    core: ExecutionMode,
 }
 
 pub struct SystemPromptBuilder<'a, M> {
@@ -53,6 +110,9 @@ pub struct SystemPromptBuilder<'a, M> {
     subagents_enabled: bool,
     hints: Option<String>,
     code_execution_mode: bool,
+    model: Option<String>,
+    working_dir: Option<PathBuf>,
+    core: ExecutionMode,
 }
 
 impl<'a> SystemPromptBuilder<'a, PromptManager> {
@@ -87,6 +147,23 @@ impl<'a> SystemPromptBuilder<'a, PromptManager> {
         self
     }
 
+    pub fn with_model(mut self, model: impl Into<String>) -> Self {
+        self.model = Some(model.into());
+        self
+    }
+
+    /// Working directory exposed to templates and searched for workspace
+    /// template overrides.
+    pub fn with_working_dir(mut self, working_dir: &Path) -> Self {
+        self.working_dir = Some(working_dir.to_path_buf());
+        self
+    }
+
+    pub fn with_core(mut self, core: ExecutionMode) -> Self {
+        self.core = core;
+        self
+    }
+
     pub fn with_hints(mut self, working_dir: &Path) -> Self {
         let config = Config::global();
         let hints_filenames = config
@@ -120,15 +197,12 @@ impl<'a> SystemPromptBuilder<'a, PromptManager> {
     }
 
     pub fn build(self) -> String {
+        let manager = self.manager;
         let mut extensions_info = self.extensions_info;
 
         // Add frontend instructions to extensions_info to simplify json rendering
-        if let Some(frontend_instructions) = self.frontend_instructions {
-            extensions_info.push(ExtensionInfo::new(
-                "frontend",
-                &frontend_instructions,
-                false,
-            ));
+        if let Some(frontend_instructions) = &self.frontend_instructions {
+            extensions_info.push(ExtensionInfo::new("frontend", frontend_instructions, false));
         }
         // Stable tool ordering is important for multi session prompt caching.
         extensions_info.sort_by(|a, b| a.name.cmp(&b.name));
@@ -141,6 +215,24 @@ impl<'a> SystemPromptBuilder<'a, PromptManager> {
             })
             .collect();
 
+        // Kept only to count their tokens; both render inside the base template
+        let extension_instructions: Vec<&str> = sanitized_extensions_info
+            .iter()
+            .filter(|ext| ext.name != "frontend" || self.frontend_instructions.is_none())
+            .map(|ext| ext.instructions.as_str())
+            .collect();
+        manager.section(
+            PromptSection::Extensions,
+            section_key(&extension_instructions),
+            || extension_instructions.join("\n\n"),
+        );
+        let frontend = self
+            .frontend_instructions
+            .as_deref()
+            .map(sanitize_unicode_tags)
+            .unwrap_or_default();
+        manager.section(PromptSection::Frontend, section_key(&frontend), || frontend);
+
         let config = Config::global();
         let goose_mode = config.get_goose_mode().unwrap_or(GooseMode::Auto);
 
@@ -150,7 +242,8 @@ impl<'a> SystemPromptBuilder<'a, PromptManager> {
 
         let context = SystemPromptContext {
             extensions: sanitized_extensions_info,
-            current_date_time: self.manager.current_date_timestamp.clone(),
+            current_date_time: manager.current_date_timestamp.clone(),
+            current_date: manager.current_date.clone(),
             extension_tool_limits,
             goose_mode,
             is_autonomous: goose_mode == GooseMode::Auto,
@@ -158,26 +251,41 @@ impl<'a> SystemPromptBuilder<'a, PromptManager> {
             max_extensions: MAX_EXTENSIONS,
             max_tools: MAX_TOOLS,
             code_execution_mode: self.code_execution_mode,
+            model: self.model,
+            working_dir: self
+                .working_dir
+                .as_ref()
+                .map(|dir| dir.display().to_string()),
+            core: self.core,
         };
 
-        let base_prompt = if let Some(override_prompt) = &self.manager.system_prompt_override {
-            let sanitized_override_prompt = sanitize_unicode_tags(override_prompt);
-            prompt_template::render_string(&sanitized_override_prompt, &context)
-        } else {
-            prompt_template::render_template("system.md", &context)
-        }
-        .unwrap_or_else(|_| {
-            "You are a general-purpose AI agent called goose, created by Block".to_string()
-        });
+        let dspy_prefix = dspy_loader::load_dspy_prompt_prefix();
+        let base_key = section_key(&(
+            serde_json::to_string(&context).unwrap_or_default(),
+            &manager.system_prompt_override,
+            &manager.system_prompt_template,
+            &dspy_prefix,
+        ));
+        let base_prompt = manager.section(PromptSection::Base, base_key, || {
+            let base_prompt = if let Some(override_prompt) = &manager.system_prompt_override {
+                let sanitized_override_prompt = sanitize_unicode_tags(override_prompt);
+                prompt_template::render_string(&sanitized_override_prompt, &context)
+            } else {
+                manager.render_system_template(self.working_dir.as_deref(), &context)
+            }
+            .unwrap_or_else(|_| {
+                "You are a general-purpose AI agent called goose, created by Block".to_string()
+            });
 
-        // Prepend DSPy-compiled prompt prefix if available
-        let base_prompt = if let Some(dspy_prefix) = dspy_loader::load_dspy_prompt_prefix() {
-            format!("{}\n\n{}", dspy_prefix, base_prompt)
-        } else {
-            base_prompt
-        };
+            // Prepend DSPy-compiled prompt prefix if available
+            if let Some(dspy_prefix) = &dspy_prefix {
+                format!("{}\n\n{}", dspy_prefix, base_prompt)
+            } else {
+                base_prompt
+            }
+        });
 
-        let mut system_prompt_extras = self.manager.system_prompt_extras.clone();
+        let mut system_prompt_extras = manager.system_prompt_extras.clone();
 
         // Add hints if provided
         if let Some(hints) = self.hints {
@@ -191,18 +299,24 @@ impl<'a> SystemPromptBuilder<'a, PromptManager> {
             );
         }
 
-        let sanitized_system_prompt_extras: Vec<String> = system_prompt_extras
-            .into_iter()
-            .map(|extra| sanitize_unicode_tags(&extra))
-            .collect();
+        let instructions = manager.section(
+            PromptSection::Instructions,
+            section_key(&system_prompt_extras),
+            || {
+                system_prompt_extras
+                    .iter()
+                    .map(|extra| sanitize_unicode_tags(extra))
+                    .collect::<Vec<_>>()
+                    .join("\n\n")
+            },
+        );
 
-        if sanitized_system_prompt_extras.is_empty() {
+        if system_prompt_extras.is_empty() {
             base_prompt
         } else {
             format!(
                 "{}\n\n# Additional Instructions:\n\n{}",
-                base_prompt,
-                sanitized_system_prompt_extras.join("\n\n")
+                base_prompt, instructions
             )
         }
     }
@@ -210,12 +324,16 @@ impl<'a> SystemPromptBuilder<'a, PromptManager> {
 
 impl PromptManager {
     pub fn new() -> Self {
+        let now = Utc::now();
         PromptManager {
             system_prompt_override: None,
             system_prompt_extras: Vec::new(),
+            system_prompt_template: None,
             // Use the fixed current date time so that prompt cache can be used.
             // Filtering to an hour to balance user time accuracy and multi session prompt cache hits.
-            current_date_timestamp: Utc::now().format("%Y-%m-%d %H:00").to_string(),
+            current_date_timestamp: now.format("%Y-%m-%d %H:00").to_string(),
+            current_date: now.format("%Y-%m-%d").to_string(),
+            sections: Mutex::default(),
         }
     }
 
@@ -224,18 +342,119 @@ impl PromptManager {
         PromptManager {
             system_prompt_override: None,
             system_prompt_extras: Vec::new(),
+            system_prompt_template: None,
             current_date_timestamp: dt.format("%Y-%m-%d %H:%M:%S").to_string(),
+            current_date: dt.format("%Y-%m-%d").to_string(),
+            sections: Mutex::default(),
         }
     }
 
     /// Add an additional instruction to the system prompt
     pub fn add_system_prompt_extra(&mut self, instruction: String) {
         self.system_prompt_extras.push(instruction);
+        self.invalidate();
     }
 
     /// Override the system prompt with custom text
     pub fn set_system_prompt_override(&mut self, template: String) {
         self.system_prompt_override = Some(template);
+        self.invalidate();
+    }
+
+    /// Select a template from the workspace or user prompts dir to use in
+    /// place of `system.md`, e.g. for a recipe. `None` restores the default.
+    pub fn set_system_prompt_template(&mut self, name: Option<String>) {
+        self.system_prompt_template = name;
+        self.invalidate();
+    }
+
+    /// Makes the next build rebuild every section, e.g. after prompt
+    /// templates changed on disk.
+    pub fn invalidate(&self) {
+        self.cache().dirty = true;
+    }
+
+    fn cache(&self) -> std::sync::MutexGuard<'_, SectionCache> {
+        self.sections.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
+    /// The cached text of `section` when it was last built from inputs
+    /// hashing to `key`, otherwise the text `build` makes, which is cached.
+    fn section(&self, section: PromptSection, key: u64, build: impl FnOnce() -> String) -> String {
+        let mut cache = self.cache();
+        if std::mem::take(&mut cache.dirty) {
+            cache.sections.clear();
+        }
+        if let Some(cached) = cache.sections.get(&section).filter(|c| c.key == key) {
+            return cached.text.clone();
+        }
+        let text = build();
+        cache.sections.insert(
+            section,
+            CachedSection {
+                key,
+                text: text.clone(),
+            },
+        );
+        text
+    }
+
+    /// Tokens in each section of the last built prompt. The base section
+    /// excludes the extension and frontend instructions rendered inside it.
+    pub fn section_tokens(&self, counter: &TokenCounter) -> Vec<SectionTokens> {
+        let cache = self.cache();
+        let count = |section| {
+            cache
+                .sections
+                .get(&section)
+                .map(|c| counter.count_tokens(&c.text))
+                .unwrap_or(0)
+        };
+        let extensions = count(PromptSection::Extensions);
+        let frontend = count(PromptSection::Frontend);
+        [
+            (
+                PromptSection::Base,
+                count(PromptSection::Base).saturating_sub(extensions + frontend),
+            ),
+            (PromptSection::Extensions, extensions),
+            (PromptSection::Frontend, frontend),
+            (
+                PromptSection::Instructions,
+                count(PromptSection::Instructions),
+            ),
+        ]
+        .into_iter()
+        .map(|(section, tokens)| SectionTokens { section, tokens })
+        .collect()
+    }
+
+    /// Renders the selected system prompt template, falling back to
+    /// `system.md` when the selected one is missing or invalid.
+    fn render_system_template<T: Serialize>(
+        &self,
+        working_dir: Option<&Path>,
+        context: &T,
+    ) -> Result<String, minijinja::Error> {
+        let resolve = |name: &str| {
+            prompt_template::resolve_template(
+                name,
+                working_dir,
+                Some(prompt_template::SYSTEM_PROMPT_VARIABLES),
+            )
+        };
+        let template = match self.system_prompt_template.as_deref() {
+            Some(name) => resolve(name).or_else(|e| {
+                tracing::warn!(
+                    "System prompt template '{}' unavailable, using default: {}",
+                    name,
+                    e
+                );
+                resolve(DEFAULT_SYSTEM_TEMPLATE)
+            }),
+            None => resolve(DEFAULT_SYSTEM_TEMPLATE),
+        }?;
+        prompt_template::render_string(&template.content, context)
     }
 
     pub fn builder<'a>(&'a self) -> SystemPromptBuilder<'a, Self> {
@@ -248,6 +467,9 @@ impl PromptManager {
             subagents_enabled: false,
             hints: None,
             code_execution_mode: false,
+            model: None,
+            working_dir: None,
+            core: ExecutionMode::default(),
         }
     }
 
@@ -346,6 +568,84 @@ mod tests {
         assert!(result.contains("hidden instructions"));
     }
 
+    #[tokio::test]
+    async fn test_unchanged_sections_are_reused_until_inputs_change() {
+        let mut manager = PromptManager::new();
+        manager.set_system_prompt_override(
+            "Base prompt.\n{% for extension in extensions %}{{ extension.instructions }}{% endfor %}"
+                .to_string(),
+        );
+        let build = |manager: &PromptManager| {
+            manager
+                .builder()
+                .with_extension(ExtensionInfo::new("test", "Use the test tools", false))
+                .build()
+        };
+        let first = build(&manager);
+        assert!(first.contains("Base prompt"));
+
+        // A rebuild with the same inputs takes the base section from the cache
+        manager
+            .cache()
+            .sections
+            .get_mut(&PromptSection::Base)
+            .unwrap()
+            .text = "Cached base".to_string();
+        assert!(build(&manager).starts_with("Cached base"));
+
+        // Changing the manager's settings rebuilds every section
+        manager.add_system_prompt_extra("Be brief".to_string());
+        let rebuilt = build(&manager);
+        assert!(rebuilt.contains("Base prompt"));
+        assert!(rebuilt.ends_with("Be brief"));
+
+        let counter = crate::token_counter::create_token_counter().await.unwrap();
+        let tokens: HashMap<_, _> = manager
+            .section_tokens(&counter)
+            .into_iter()
+            .map(|s| (s.section, s.tokens))
+            .collect();
+        assert!(tokens[&PromptSection::Base] > 0);
+        assert!(tokens[&PromptSection::Extensions] > 0);
+        assert_eq!(tokens[&PromptSection::Frontend], 0);
+        assert!(tokens[&PromptSection::Instructions] > 0);
+    }
+
+    #[test]
+    fn test_recipe_template_renders_session_variables() {
+        let workspace = tempfile::tempdir().unwrap();
+        let prompts_dir = workspace
+            .path()
+            .join(prompt_template::WORKSPACE_PROMPTS_DIR);
+        std::fs::create_dir_all(&prompts_dir).unwrap();
+        std::fs::write(
+            prompts_dir.join("reviewer.md"),
+            "Reviewing with {{ model }} ({{ core }}) on {{ current_date }} in {{ working_dir }}",
+        )
+        .unwrap();
+
+        let mut manager =
+            PromptManager::with_timestamp(DateTime::<Utc>::from_timestamp(0, 0).unwrap());
+        manager.set_system_prompt_template(Some("reviewer.md".to_string()));
+        let result = manager
+            .builder()
+            .with_model("gpt-4o")
+            .with_working_dir(workspace.path())
+            .with_core(ExecutionMode::Structured)
+            .build();
+        assert_eq!(
+            result,
+            format!(
+                "Reviewing with gpt-4o (structured) on 1970-01-01 in {}",
+                workspace.path().display()
+            )
+        );
+
+        manager.set_system_prompt_template(Some("missing.md".to_string()));
+        let fallback = manager.builder().build();
+        assert!(fallback.contains("goose"));
+    }
+
     #[test]
     fn test_basic() {
         let manager = PromptManager::with_timestamp(DateTime::<Utc>::from_timestamp(0, 0).unwrap());
@@ -500,6 +500,13 @@ impl ReflexionAgent {
         Some(reflection)
     }
 
+    /// Store a reflection that came from outside an attempt, such as user feedback
+    pub fn add_reflection(&mut self, reflection: Reflection) {
+        if self.config.persist_reflections {
+            self.memory.store(reflection);
+        }
+    }
+
     /// Get relevant reflections for a task
     pub fn get_relevant_reflections(&self, task: &str) -> Vec<&Reflection> {
         self.memory
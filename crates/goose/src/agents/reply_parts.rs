@@ -3,6 +3,7 @@ use regex::Regex;
 use std::sync::Arc;
 
 use async_stream::try_stream;
+use chrono::Utc;
 use futures::stream::StreamExt;
 use serde_json::{json, Value};
 use tracing::debug;
@@ -13,6 +14,9 @@ use crate::agents::skills_extension::EXTENSION_NAME as SKILLS_EXTENSION;
 use crate::agents::subagent_tool::SUBAGENT_TOOL_NAME;
 use crate::conversation::message::{Message, MessageContent, ToolRequest};
 use crate::conversation::Conversation;
+use crate::execution::priority;
+use crate::observability::cost_tracker::TokenUsage;
+use crate::observability::team_accounting::{record_usage, UNASSIGNED};
 use crate::providers::base::{stream_from_single_message, MessageStream, Provider, ProviderUsage};
 use crate::providers::errors::ProviderError;
 use crate::providers::toolshim::{
@@ -181,6 +185,9 @@ impl Agent {
             .with_frontend_instructions(self.frontend_instructions.lock().await.clone())
             .with_extension_and_tool_counts(extension_count, tool_count)
             .with_code_execution_mode(code_execution_active)
+            .with_model(model_config.model_name.clone())
+            .with_working_dir(working_dir)
+            .with_core(self.execution_mode().await)
             .with_hints(working_dir)
             .with_enable_subagents(self.subagents_enabled(session_id).await)
             .build();
@@ -261,6 +268,10 @@ impl Agent {
         let toolshim_tools = toolshim_tools.to_owned();
         let provider = provider.clone();
 
+        // Held until the response stream is dropped; background work waits
+        // here while interactive sessions need the provider
+        let permit = priority::gate().acquire(session_id).await;
+
         // Capture errors during stream creation and return them as part of the stream
         // so they can be handled by the existing error handling logic in the agent
         let stream_result = if provider.supports_streaming() {
@@ -307,6 +318,7 @@ impl Agent {
         };
 
         Ok(Box::pin(try_stream! {
+            let _permit = permit;
             while let Some(result) = stream.next().await {
                 let (mut message, usage) = result?;
 
@@ -454,9 +466,25 @@ impl Agent {
             )
         };
 
+        let mut extension_data = session.extension_data.clone();
+        let provider = session.provider_name.as_deref().unwrap_or(UNASSIGNED);
+        let tokens = |t: Option<i32>| t.unwrap_or(0).max(0) as u64;
+        record_usage(
+            session_id,
+            &mut extension_data,
+            provider,
+            &usage.model,
+            &TokenUsage::new(
+                tokens(usage.usage.input_tokens),
+                tokens(usage.usage.output_tokens),
+            ),
+            Utc::now(),
+        )?;
+
         manager
             .update(session_id)
             .schedule_id(schedule_id)
+            .extension_data(extension_data)
             .total_tokens(current_total)
             .input_tokens(current_input)
             .output_tokens(current_output)
@@ -247,6 +247,15 @@ pub async fn execute_success_checks_guarded(
 pub async fn execute_shell_command(
     command: &str,
     timeout: std::time::Duration,
+) -> Result<std::process::Output> {
+    execute_shell_command_in(command, timeout, None).await
+}
+
+/// Execute a shell command in `working_dir`, or the current directory
+pub async fn execute_shell_command_in(
+    command: &str,
+    timeout: std::time::Duration,
+    working_dir: Option<&std::path::Path>,
 ) -> Result<std::process::Output> {
     debug!(
         "Executing shell command with timeout {:?}: {}",
@@ -265,6 +274,9 @@ pub async fn execute_shell_command(
             cmd.env("GOOSE_TERMINAL", "1");
             cmd
         };
+        if let Some(dir) = working_dir {
+            cmd.current_dir(dir);
+        }
 
         let output = cmd
             .stdout(Stdio::piped())
@@ -0,0 +1,173 @@
+//! Tool calls in flight.
+//!
+//! Each extension tool call is tracked from dispatch until its result is
+//! read, so a client can list what is running and cancel a single call
+//! without cancelling the whole reply. A cancelled call gets its own
+//! cancellation token cancelled, which reaches the extension, and resolves
+//! right away to a "cancelled by user" result the model can work with.
+
+use crate::mcp_utils::ToolResult;
+use rmcp::model::{CallToolResult, Content};
+use serde::Serialize;
+use serde_json::json;
+use std::collections::HashMap;
+use std::future::Future;
+use std::sync::{Arc, Mutex};
+use std::time::Instant;
+use tokio_util::sync::CancellationToken;
+use utoipa::ToSchema;
+
+pub const CANCELLED_BY_USER_RESPONSE: &str = "The user cancelled this tool call while it was \
+    running, so it did not finish and any partial effects are unknown. Do not run it again \
+    unless the user asks; carry on with the rest of the task or ask the user how to proceed.";
+
+#[derive(Debug, Clone, Serialize, ToSchema)]
+pub struct RunningToolCall {
+    /// Tool request id, as in the assistant message that asked for the call
+    pub id: String,
+    pub name: String,
+    pub elapsed_ms: u64,
+}
+
+struct Entry {
+    name: String,
+    started: Instant,
+    token: CancellationToken,
+    cancelled_by_user: CancellationToken,
+}
+
+#[derive(Clone, Default)]
+pub struct RunningToolCalls {
+    calls: Arc<Mutex<HashMap<String, Entry>>>,
+}
+
+impl RunningToolCalls {
+    /// Tracks a call until the returned guard is dropped. The call's token is
+    /// cancelled along with `parent`, or on its own by `cancel`.
+    pub fn start(
+        &self,
+        id: &str,
+        name: &str,
+        parent: Option<&CancellationToken>,
+    ) -> RunningToolCallGuard {
+        let token = parent.map_or_else(CancellationToken::new, |p| p.child_token());
+        let cancelled_by_user = CancellationToken::new();
+        self.calls.lock().unwrap().insert(
+            id.to_string(),
+            Entry {
+                name: name.to_string(),
+                started: Instant::now(),
+                token: token.clone(),
+                cancelled_by_user: cancelled_by_user.clone(),
+            },
+        );
+        RunningToolCallGuard {
+            calls: self.clone(),
+            id: id.to_string(),
+            token,
+            cancelled_by_user,
+        }
+    }
+
+    /// Running calls, longest running first.
+    pub fn list(&self) -> Vec<RunningToolCall> {
+        let mut calls: Vec<RunningToolCall> = self
+            .calls
+            .lock()
+            .unwrap()
+            .iter()
+            .map(|(id, entry)| RunningToolCall {
+                id: id.clone(),
+                name: entry.name.clone(),
+                elapsed_ms: entry.started.elapsed().as_millis() as u64,
+            })
+            .collect();
+        calls.sort_by(|a, b| b.elapsed_ms.cmp(&a.elapsed_ms));
+        calls
+    }
+
+    /// Cancels one running call. Returns false when no call has this id.
+    pub fn cancel(&self, id: &str) -> bool {
+        let calls = self.calls.lock().unwrap();
+        let Some(entry) = calls.get(id) else {
+            return false;
+        };
+        tracing::info!(tool = %entry.name, id, "Tool call cancelled by user");
+        entry.cancelled_by_user.cancel();
+        entry.token.cancel();
+        true
+    }
+}
+
+pub struct RunningToolCallGuard {
+    calls: RunningToolCalls,
+    id: String,
+    token: CancellationToken,
+    cancelled_by_user: CancellationToken,
+}
+
+impl RunningToolCallGuard {
+    pub fn token(&self) -> CancellationToken {
+        self.token.clone()
+    }
+
+    /// Waits for `result`, or for the user to cancel the call, whichever
+    /// comes first.
+    pub async fn run(
+        self,
+        result: impl Future<Output = ToolResult<CallToolResult>>,
+    ) -> ToolResult<CallToolResult> {
+        tokio::select! {
+            biased;
+            _ = self.cancelled_by_user.cancelled() => Ok(cancelled_result()),
+            result = result => result,
+        }
+    }
+}
+
+impl Drop for RunningToolCallGuard {
+    fn drop(&mut self) {
+        self.calls.calls.lock().unwrap().remove(&self.id);
+    }
+}
+
+fn cancelled_result() -> CallToolResult {
+    CallToolResult {
+        content: vec![Content::text(CANCELLED_BY_USER_RESPONSE)],
+        structured_content: Some(json!({ "status": "cancelled", "cancelled_by": "user" })),
+        is_error: Some(true),
+        meta: None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_cancelling_one_call_leaves_the_others_running() {
+        let calls = RunningToolCalls::default();
+        let turn = CancellationToken::new();
+        let build = calls.start("req_1", "developer__shell", Some(&turn));
+        let read = calls.start("req_2", "developer__text_editor", Some(&turn));
+        assert_eq!(calls.list().len(), 2);
+
+        let build_token = build.token();
+        let pending = build.run(futures::future::pending());
+        assert!(calls.cancel("req_1"));
+        let result = pending.await.unwrap();
+        assert_eq!(result.is_error, Some(true));
+        assert_eq!(result.structured_content.unwrap()["status"], "cancelled");
+        assert!(build_token.is_cancelled());
+        assert!(!read.token().is_cancelled());
+
+        let ids: Vec<String> = calls.list().into_iter().map(|c| c.id).collect();
+        assert_eq!(ids, vec!["req_2".to_string()]);
+        assert!(!calls.cancel("req_1"));
+
+        turn.cancel();
+        assert!(read.token().is_cancelled());
+        drop(read);
+        assert!(calls.list().is_empty());
+    }
+}
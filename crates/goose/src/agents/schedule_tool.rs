@@ -157,8 +157,12 @@ impl Agent {
             last_run: None,
             currently_running: false,
             paused: false,
+            paused_by_upstream: false,
             current_session_id: None,
             process_start_time: None,
+            depends_on: Vec::new(),
+            last_status: None,
+            last_finished: None,
         };
 
         match scheduler.add_scheduled_job(job, true).await {
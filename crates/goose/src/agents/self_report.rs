@@ -0,0 +1,367 @@
+//! `/diagnose`: one report answering "why is the agent behaving like this".
+//!
+//! Collects the state that most often explains surprising behaviour: the
+//! execution core and why it was chosen, spend against the budget, loaded
+//! extensions, what the agent has learned, the guardrails in effect, provider
+//! circuit breakers, scheduled jobs and network connectivity. Anything not
+//! working normally is also listed under [`SelfReport::degraded`].
+
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+use std::collections::BTreeMap;
+use utoipa::ToSchema;
+
+use crate::connectivity::{self, ConnectivityStatus};
+use crate::guardrails::{FailMode, GuardrailsConfig};
+use crate::providers::resilience::breaker_health;
+use crate::providers::routing::EndpointHealth;
+
+use super::skills_extension::SkillsClient;
+use super::Agent;
+
+#[derive(Debug, Clone, Serialize, ToSchema)]
+pub struct SelfReport {
+    pub core: String,
+    pub core_reason: String,
+    pub spent_usd: f64,
+    pub budget_usd: Option<f64>,
+    pub remaining_usd: Option<f64>,
+    pub extensions: Vec<ExtensionStatus>,
+    /// Stored memories; `None` when the memory system is off
+    pub memories: Option<usize>,
+    pub skills: usize,
+    /// Reflections learned from past attempts and feedback
+    pub insights: usize,
+    pub guardrails: GuardrailsStatus,
+    pub circuit_breakers: Vec<BreakerStatus>,
+    /// Scheduled jobs that are not paused
+    pub scheduled_jobs: Vec<JobStatus>,
+    pub connectivity: ConnectivityStatus,
+    /// Subsystems not working normally, each with the reason
+    pub degraded: Vec<String>,
+}
+
+#[derive(Debug, Clone, Serialize, ToSchema)]
+pub struct ExtensionStatus {
+    pub name: String,
+    pub tools: usize,
+}
+
+#[derive(Debug, Clone, Serialize, ToSchema)]
+pub struct GuardrailsStatus {
+    pub enabled: bool,
+    /// Whether scans that error block (`fail_closed`) or allow (`fail_open`)
+    pub fail_mode: String,
+    pub detectors: Vec<String>,
+}
+
+impl From<&GuardrailsConfig> for GuardrailsStatus {
+    fn from(config: &GuardrailsConfig) -> Self {
+        let detectors = [
+            ("prompt_injection", config.prompt_injection.enabled),
+            ("pii", config.pii.enabled),
+            ("jailbreak", config.jailbreak.enabled),
+            ("topics", config.topics.enabled),
+            ("keywords", config.keywords.enabled),
+            ("secrets", config.secrets.enabled),
+        ];
+        Self {
+            enabled: config.enabled,
+            fail_mode: match config.fail_mode {
+                FailMode::FailClosed => "fail_closed",
+                FailMode::FailOpen => "fail_open",
+            }
+            .to_string(),
+            detectors: detectors
+                .into_iter()
+                .filter(|(_, enabled)| *enabled)
+                .map(|(name, _)| name.to_string())
+                .collect(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, ToSchema)]
+pub struct BreakerStatus {
+    pub provider: String,
+    pub health: EndpointHealth,
+}
+
+#[derive(Debug, Clone, Serialize, ToSchema)]
+pub struct JobStatus {
+    pub id: String,
+    pub cron: String,
+    pub running: bool,
+    pub last_run: Option<DateTime<Utc>>,
+}
+
+impl SelfReport {
+    fn find_degraded(&self, memory_enabled: bool) -> Vec<String> {
+        let mut degraded = Vec::new();
+        if !self.connectivity.online {
+            degraded.push(format!(
+                "network: offline since {}{}",
+                self.connectivity.since.format("%H:%M:%S"),
+                self.connectivity
+                    .reason
+                    .as_ref()
+                    .map(|r| format!(" ({})", r))
+                    .unwrap_or_default()
+            ));
+        }
+        for subsystem in &self.connectivity.disabled_subsystems {
+            degraded.push(format!(
+                "{}: standing down until the network returns",
+                subsystem
+            ));
+        }
+        for breaker in &self.circuit_breakers {
+            match breaker.health {
+                EndpointHealth::Unhealthy => degraded.push(format!(
+                    "provider {}: circuit open, calls fail fast",
+                    breaker.provider
+                )),
+                EndpointHealth::Degraded => degraded.push(format!(
+                    "provider {}: recent failures, circuit may open",
+                    breaker.provider
+                )),
+                EndpointHealth::Healthy | EndpointHealth::Unknown => {}
+            }
+        }
+        for extension in self.extensions.iter().filter(|e| e.tools == 0) {
+            degraded.push(format!("extension {}: lists no tools", extension.name));
+        }
+        if self.remaining_usd.is_some_and(|remaining| remaining < 0.0) {
+            degraded.push("budget: exceeded".to_string());
+        }
+        if !self.guardrails.enabled {
+            degraded.push("guardrails: disabled".to_string());
+        }
+        if !memory_enabled {
+            degraded.push("memory: disabled".to_string());
+        }
+        degraded
+    }
+
+    pub fn format_display(&self) -> String {
+        let mut lines = vec![
+            "## Diagnostics".to_string(),
+            String::new(),
+            format!("- **Core**: {} ({})", self.core, self.core_reason),
+        ];
+        lines.push(match (self.budget_usd, self.remaining_usd) {
+            (Some(budget), Some(remaining)) => format!(
+                "- **Budget**: ${:.2} of ${:.2} spent, ${:.2} remaining",
+                self.spent_usd, budget, remaining
+            ),
+            _ => format!("- **Budget**: ${:.2} spent, no budget set", self.spent_usd),
+        });
+        let extensions: Vec<_> = self
+            .extensions
+            .iter()
+            .map(|e| format!("{} ({} tools)", e.name, e.tools))
+            .collect();
+        lines.push(format!(
+            "- **Extensions**: {}",
+            none_if_empty(extensions.join(", "))
+        ));
+        lines.push(format!(
+            "- **Learned**: {} memories, {} skills, {} insights",
+            self.memories
+                .map(|m| m.to_string())
+                .unwrap_or_else(|| "no".to_string()),
+            self.skills,
+            self.insights
+        ));
+        lines.push(format!(
+            "- **Guardrails**: {}, {}, detectors: {}",
+            if self.guardrails.enabled {
+                "enabled"
+            } else {
+                "disabled"
+            },
+            self.guardrails.fail_mode,
+            none_if_empty(self.guardrails.detectors.join(", "))
+        ));
+        let breakers: Vec<_> = self
+            .circuit_breakers
+            .iter()
+            .map(|b| format!("{} {:?}", b.provider, b.health).to_lowercase())
+            .collect();
+        lines.push(format!(
+            "- **Circuit breakers**: {}",
+            none_if_empty(breakers.join(", "))
+        ));
+        let jobs: Vec<_> = self
+            .scheduled_jobs
+            .iter()
+            .map(|j| {
+                let state = if j.running { ", running" } else { "" };
+                format!("{} (`{}`{})", j.id, j.cron, state)
+            })
+            .collect();
+        lines.push(format!(
+            "- **Scheduled jobs**: {}",
+            none_if_empty(jobs.join(", "))
+        ));
+        lines.push(String::new());
+        if self.degraded.is_empty() {
+            lines.push("Nothing is degraded.".to_string());
+        } else {
+            lines.push("**Degraded**:".to_string());
+            lines.extend(self.degraded.iter().map(|d| format!("- {}", d)));
+        }
+        lines.join("\n")
+    }
+}
+
+fn none_if_empty(list: String) -> String {
+    if list.is_empty() {
+        "none".to_string()
+    } else {
+        list
+    }
+}
+
+impl Agent {
+    pub async fn self_report(&self, session_id: &str) -> SelfReport {
+        let tracker = self.cost_tracker();
+        let spent_usd = tracker.get_cost().await;
+        let remaining_usd = tracker.remaining_budget().await;
+
+        let mut tool_counts: BTreeMap<String, usize> = BTreeMap::new();
+        for name in self
+            .extension_manager
+            .list_extensions()
+            .await
+            .unwrap_or_default()
+        {
+            tool_counts.insert(name, 0);
+        }
+        let tools = self
+            .extension_manager
+            .get_prefixed_tools_shared(session_id, None)
+            .await
+            .unwrap_or_default();
+        for tool in tools.iter() {
+            if let Some((prefix, _)) = tool.name.split_once("__") {
+                if let Some(count) = tool_counts.get_mut(prefix) {
+                    *count += 1;
+                }
+            }
+        }
+
+        #[cfg(feature = "memory")]
+        let (memories, memory_enabled) = {
+            let memory = self.memory_manager.lock().await;
+            let enabled = memory.config().enabled;
+            (
+                enabled.then_some(memory.stats().await.total_count()),
+                enabled,
+            )
+        };
+        #[cfg(not(feature = "memory"))]
+        let (memories, memory_enabled) = (None, false);
+
+        let scheduled_jobs = match &self.config.scheduler_service {
+            Some(scheduler) => scheduler
+                .list_scheduled_jobs()
+                .await
+                .into_iter()
+                .filter(|job| !job.paused)
+                .map(|job| JobStatus {
+                    id: job.id,
+                    cron: job.cron,
+                    running: job.currently_running,
+                    last_run: job.last_run,
+                })
+                .collect(),
+            None => Vec::new(),
+        };
+
+        let mut report = SelfReport {
+            core: self.execution_mode().await.to_string(),
+            core_reason: self.core_reason().await,
+            spent_usd,
+            budget_usd: remaining_usd.map(|remaining| spent_usd + remaining),
+            remaining_usd,
+            extensions: tool_counts
+                .into_iter()
+                .map(|(name, tools)| ExtensionStatus { name, tools })
+                .collect(),
+            memories,
+            skills: SkillsClient::available_skill_count(),
+            insights: self.reflection_count().await,
+            guardrails: GuardrailsStatus::from(&self.guardrails_config().await),
+            circuit_breakers: breaker_health()
+                .into_iter()
+                .map(|(provider, health)| BreakerStatus { provider, health })
+                .collect(),
+            scheduled_jobs,
+            connectivity: connectivity::global().status(),
+            degraded: Vec::new(),
+        };
+        report.degraded = report.find_degraded(memory_enabled);
+        report
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_degraded_subsystems() {
+        let mut guardrails = GuardrailsStatus::from(&GuardrailsConfig::default());
+        assert_eq!(guardrails.detectors.len(), 6);
+        guardrails.enabled = false;
+        let mut report = SelfReport {
+            core: "freeform".to_string(),
+            core_reason: "the default core".to_string(),
+            spent_usd: 12.0,
+            budget_usd: Some(10.0),
+            remaining_usd: Some(-2.0),
+            extensions: vec![
+                ExtensionStatus {
+                    name: "developer".to_string(),
+                    tools: 4,
+                },
+                ExtensionStatus {
+                    name: "broken".to_string(),
+                    tools: 0,
+                },
+            ],
+            memories: Some(3),
+            skills: 2,
+            insights: 1,
+            guardrails,
+            circuit_breakers: vec![BreakerStatus {
+                provider: "openai".to_string(),
+                health: EndpointHealth::Unhealthy,
+            }],
+            scheduled_jobs: Vec::new(),
+            connectivity: ConnectivityStatus {
+                online: true,
+                since: Utc::now(),
+                reason: None,
+                offline_provider: None,
+                queued_calls: 0,
+                disabled_subsystems: Vec::new(),
+            },
+            degraded: Vec::new(),
+        };
+        report.degraded = report.find_degraded(true);
+        assert_eq!(
+            report.degraded,
+            vec![
+                "provider openai: circuit open, calls fail fast",
+                "extension broken: lists no tools",
+                "budget: exceeded",
+                "guardrails: disabled",
+            ]
+        );
+        let display = report.format_display();
+        assert!(display.contains("- **Core**: freeform (the default core)"));
+        assert!(display.contains("- extension broken: lists no tools"));
+    }
+}
@@ -0,0 +1,197 @@
+use crate::agents::extension::ExtensionConfig;
+use crate::agents::extension_manager::ExtensionManager;
+use crate::agents::mcp_client::{Error, McpClientTrait};
+use async_trait::async_trait;
+use rmcp::model::{
+    CallToolResult, Content, Implementation, InitializeResult, JsonObject, ListToolsResult,
+    ProtocolVersion, ServerCapabilities, Tool, ToolsCapability,
+};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tokio_util::sync::CancellationToken;
+
+/// A fake tool described by a scenario file. Each call returns the next entry
+/// of `results`; the last one repeats once the list runs out.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SimulatedTool {
+    pub name: String,
+    #[serde(default)]
+    pub description: String,
+    #[serde(default)]
+    pub input_schema: Option<Value>,
+    #[serde(default)]
+    pub results: Vec<SimulatedToolResult>,
+    #[serde(default)]
+    pub latency_ms: u64,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct SimulatedToolResult {
+    #[serde(default)]
+    pub text: String,
+    #[serde(default)]
+    pub is_error: bool,
+}
+
+/// A call received by a simulated tool, kept for assertions.
+#[derive(Debug, Clone)]
+pub struct SimulatedToolCall {
+    pub session_id: String,
+    pub name: String,
+    pub arguments: Option<JsonObject>,
+}
+
+/// In-process MCP client that serves [`SimulatedTool`]s without spawning a
+/// server.
+pub struct SimulatedClient {
+    info: InitializeResult,
+    tools: Vec<SimulatedTool>,
+    calls: Arc<Mutex<Vec<SimulatedToolCall>>>,
+}
+
+impl SimulatedClient {
+    pub fn new(name: &str, tools: Vec<SimulatedTool>) -> Self {
+        let info = InitializeResult {
+            protocol_version: ProtocolVersion::V_2025_03_26,
+            capabilities: ServerCapabilities {
+                tasks: None,
+                tools: Some(ToolsCapability {
+                    list_changed: Some(false),
+                }),
+                resources: None,
+                prompts: None,
+                completions: None,
+                experimental: None,
+                logging: None,
+            },
+            server_info: Implementation {
+                name: name.to_string(),
+                title: Some(format!("Simulated {}", name)),
+                version: "1.0.0".to_string(),
+                icons: None,
+                website_url: None,
+            },
+            instructions: None,
+        };
+
+        Self {
+            info,
+            tools,
+            calls: Arc::new(Mutex::new(Vec::new())),
+        }
+    }
+
+    /// Handle to the calls this client receives, which stays valid after the
+    /// client is moved into an [`ExtensionManager`].
+    pub fn calls(&self) -> Arc<Mutex<Vec<SimulatedToolCall>>> {
+        self.calls.clone()
+    }
+
+    /// Registers the tools as extension `name` and returns the call log.
+    pub async fn register(
+        manager: &ExtensionManager,
+        name: &str,
+        tools: Vec<SimulatedTool>,
+    ) -> Arc<Mutex<Vec<SimulatedToolCall>>> {
+        let client = Self::new(name, tools);
+        let calls = client.calls();
+        let config = ExtensionConfig::Builtin {
+            name: name.to_string(),
+            display_name: Some(name.to_string()),
+            description: "Simulated extension".to_string(),
+            timeout: None,
+            bundled: None,
+            available_tools: Vec::new(),
+        };
+        manager
+            .add_client(
+                name.to_string(),
+                config,
+                Arc::new(tokio::sync::Mutex::new(Box::new(client))),
+                None,
+                None,
+            )
+            .await;
+        calls
+    }
+
+    fn to_tool(tool: &SimulatedTool) -> Tool {
+        let schema = match &tool.input_schema {
+            Some(Value::Object(map)) => map.clone(),
+            _ => serde_json::json!({ "type": "object", "properties": {} })
+                .as_object()
+                .cloned()
+                .unwrap_or_default(),
+        };
+        Tool::new(
+            tool.name.clone(),
+            tool.description.clone(),
+            Arc::new(schema),
+        )
+    }
+}
+
+#[async_trait]
+impl McpClientTrait for SimulatedClient {
+    async fn list_tools(
+        &self,
+        _session_id: &str,
+        _next_cursor: Option<String>,
+        _cancellation_token: CancellationToken,
+    ) -> Result<ListToolsResult, Error> {
+        Ok(ListToolsResult {
+            tools: self.tools.iter().map(Self::to_tool).collect(),
+            next_cursor: None,
+            meta: None,
+        })
+    }
+
+    async fn call_tool(
+        &self,
+        session_id: &str,
+        name: &str,
+        arguments: Option<JsonObject>,
+        _working_dir: Option<&str>,
+        _cancellation_token: CancellationToken,
+    ) -> Result<CallToolResult, Error> {
+        let Some(tool) = self.tools.iter().find(|tool| tool.name == name) else {
+            return Ok(CallToolResult::error(vec![Content::text(format!(
+                "Error: Unknown tool: {}",
+                name
+            ))]));
+        };
+
+        let call_index = {
+            let mut calls = self.calls.lock().unwrap();
+            calls.push(SimulatedToolCall {
+                session_id: session_id.to_string(),
+                name: name.to_string(),
+                arguments,
+            });
+            calls.iter().filter(|call| call.name == name).count() - 1
+        };
+
+        if tool.latency_ms > 0 {
+            tokio::time::sleep(Duration::from_millis(tool.latency_ms)).await;
+        }
+
+        let result = tool
+            .results
+            .get(call_index)
+            .or_else(|| tool.results.last())
+            .cloned()
+            .unwrap_or_default();
+        let content = vec![Content::text(result.text)];
+        if result.is_error {
+            Ok(CallToolResult::error(content))
+        } else {
+            Ok(CallToolResult::success(content))
+        }
+    }
+
+    fn get_info(&self) -> Option<&InitializeResult> {
+        Some(&self.info)
+    }
+}
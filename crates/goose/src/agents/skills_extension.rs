@@ -76,6 +76,19 @@ impl SkillsClient {
             instructions: Some(String::new()),
         };
 
+        let skills = Self::load_all_skills();
+        let mut client = Self { info, skills };
+        client.info.instructions = Some(client.generate_instructions());
+        Ok(client)
+    }
+
+    /// Number of skills the extension offers: the built-in ones plus those
+    /// found in the skill directories.
+    pub fn available_skill_count() -> usize {
+        Self::load_all_skills().len()
+    }
+
+    fn load_all_skills() -> HashMap<String, Skill> {
         let mut skills = Self::load_builtin_skills();
 
         let directories = Self::get_default_skill_directories()
@@ -84,10 +97,7 @@ impl SkillsClient {
             .collect::<Vec<_>>();
         let fs_skills = Self::discover_skills_in_directories(&directories);
         skills.extend(fs_skills);
-
-        let mut client = Self { info, skills };
-        client.info.instructions = Some(client.generate_instructions());
-        Ok(client)
+        skills
     }
 
     fn load_builtin_skills() -> HashMap<String, Skill> {
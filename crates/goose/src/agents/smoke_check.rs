@@ -0,0 +1,435 @@
+//! Smoke checks - does the agent actually work, not just start
+//!
+//! A smoke check is a small end-to-end probe: an HTTP request against a key
+//! endpoint, or a canned session replayed through the mock provider with
+//! assertions on the replies. Each check runs under its own timeout and
+//! carries a weight; the weighted pass rate plus any required checks decide
+//! the overall healthy verdict.
+
+use super::done_gate::CheckResult;
+use crate::conversation::message::Message;
+use crate::providers::base::Provider;
+use crate::providers::mock::{MockProvider, Scenario};
+use anyhow::Result;
+use async_trait::async_trait;
+use serde::{Deserialize, Serialize};
+use std::time::{Duration, Instant};
+use tracing::{info, warn};
+
+#[async_trait]
+pub trait SmokeCheck: Send + Sync {
+    fn name(&self) -> &str;
+    async fn check(&self) -> Result<CheckResult>;
+}
+
+/// Per-check settings
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SmokeCheckConfig {
+    #[serde(default = "default_timeout_secs")]
+    pub timeout_secs: u64,
+    #[serde(default = "default_weight")]
+    pub weight: f32,
+    /// A failing required check makes the verdict unhealthy regardless of score
+    #[serde(default)]
+    pub required: bool,
+}
+
+fn default_timeout_secs() -> u64 {
+    30
+}
+
+fn default_weight() -> f32 {
+    1.0
+}
+
+impl Default for SmokeCheckConfig {
+    fn default() -> Self {
+        Self {
+            timeout_secs: default_timeout_secs(),
+            weight: default_weight(),
+            required: false,
+        }
+    }
+}
+
+impl SmokeCheckConfig {
+    pub fn required() -> Self {
+        Self {
+            required: true,
+            ..Default::default()
+        }
+    }
+
+    pub fn with_timeout(mut self, timeout: Duration) -> Self {
+        self.timeout_secs = timeout.as_secs().max(1);
+        self
+    }
+
+    pub fn with_weight(mut self, weight: f32) -> Self {
+        self.weight = weight;
+        self
+    }
+}
+
+/// GET an endpoint and check the status code (and optionally the body)
+pub struct HttpProbe {
+    name: String,
+    url: String,
+    expected_status: u16,
+    body_contains: Option<String>,
+    client: reqwest::Client,
+}
+
+impl HttpProbe {
+    pub fn new(name: &str, url: &str) -> Self {
+        Self {
+            name: name.to_string(),
+            url: url.to_string(),
+            expected_status: 200,
+            body_contains: None,
+            client: reqwest::Client::new(),
+        }
+    }
+
+    pub fn expect_status(mut self, status: u16) -> Self {
+        self.expected_status = status;
+        self
+    }
+
+    pub fn expect_body(mut self, text: &str) -> Self {
+        self.body_contains = Some(text.to_string());
+        self
+    }
+}
+
+#[async_trait]
+impl SmokeCheck for HttpProbe {
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    async fn check(&self) -> Result<CheckResult> {
+        let response = match self.client.get(&self.url).send().await {
+            Ok(response) => response,
+            Err(e) => {
+                return Ok(CheckResult::fail(
+                    &self.name,
+                    &format!("Request to {} failed: {}", self.url, e),
+                ))
+            }
+        };
+        let status = response.status().as_u16();
+        if status != self.expected_status {
+            return Ok(CheckResult::fail(
+                &self.name,
+                &format!(
+                    "{} returned {}, expected {}",
+                    self.url, status, self.expected_status
+                ),
+            ));
+        }
+        if let Some(expected) = &self.body_contains {
+            let body = response.text().await.unwrap_or_default();
+            if !body.contains(expected.as_str()) {
+                return Ok(CheckResult::fail(
+                    &self.name,
+                    &format!("{} response did not contain '{}'", self.url, expected),
+                )
+                .with_details(&body));
+            }
+        }
+        Ok(CheckResult::pass(
+            &self.name,
+            &format!("{} returned {}", self.url, status),
+        ))
+    }
+}
+
+/// One user message in a scripted scenario and what the reply must contain
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ScenarioStep {
+    pub user: String,
+    #[serde(default)]
+    pub expect_reply: Option<String>,
+}
+
+/// Replays a short canned session against the mock provider
+pub struct ScenarioCheck {
+    name: String,
+    scenario: Scenario,
+    steps: Vec<ScenarioStep>,
+}
+
+impl ScenarioCheck {
+    pub fn new(name: &str, scenario: Scenario, steps: Vec<ScenarioStep>) -> Self {
+        Self {
+            name: name.to_string(),
+            scenario,
+            steps,
+        }
+    }
+}
+
+#[async_trait]
+impl SmokeCheck for ScenarioCheck {
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    async fn check(&self) -> Result<CheckResult> {
+        let provider = MockProvider::new(self.scenario.clone());
+        let session_id = format!("smoke-{}", self.name);
+        let mut messages = Vec::new();
+
+        for (index, step) in self.steps.iter().enumerate() {
+            messages.push(Message::user().with_text(&step.user));
+            let reply = match provider.complete(&session_id, "", &messages, &[]).await {
+                Ok((reply, _)) => reply,
+                Err(e) => {
+                    return Ok(CheckResult::fail(
+                        &self.name,
+                        &format!("Step {} failed: {}", index + 1, e),
+                    ))
+                }
+            };
+            let text = reply.as_concat_text();
+            if let Some(expected) = &step.expect_reply {
+                if !text.contains(expected.as_str()) {
+                    return Ok(CheckResult::fail(
+                        &self.name,
+                        &format!("Step {} reply did not contain '{}'", index + 1, expected),
+                    )
+                    .with_details(&text));
+                }
+            }
+            messages.push(reply);
+        }
+
+        Ok(CheckResult::pass(
+            &self.name,
+            &format!("Replayed {} steps", self.steps.len()),
+        ))
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SmokeCheckOutcome {
+    pub result: CheckResult,
+    pub weight: f32,
+    pub required: bool,
+    pub timed_out: bool,
+    pub duration_ms: u64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SmokeReport {
+    pub outcomes: Vec<SmokeCheckOutcome>,
+    /// Weighted share of checks that passed (0.0 - 1.0)
+    pub score: f32,
+    pub healthy: bool,
+}
+
+impl SmokeReport {
+    pub fn failures(&self) -> Vec<&SmokeCheckOutcome> {
+        self.outcomes.iter().filter(|o| !o.result.passed).collect()
+    }
+}
+
+/// Runs smoke checks and folds them into a healthy verdict
+pub struct SmokeCheckRunner {
+    checks: Vec<(Box<dyn SmokeCheck>, SmokeCheckConfig)>,
+    min_score: f32,
+}
+
+impl Default for SmokeCheckRunner {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl SmokeCheckRunner {
+    pub fn new() -> Self {
+        Self {
+            checks: Vec::new(),
+            min_score: 0.8,
+        }
+    }
+
+    pub fn with_min_score(mut self, min_score: f32) -> Self {
+        self.min_score = min_score;
+        self
+    }
+
+    pub fn add_check(mut self, check: Box<dyn SmokeCheck>, config: SmokeCheckConfig) -> Self {
+        self.checks.push((check, config));
+        self
+    }
+
+    pub async fn run(&self) -> SmokeReport {
+        let mut outcomes = Vec::new();
+        for (check, config) in &self.checks {
+            let started = Instant::now();
+            let timeout = Duration::from_secs(config.timeout_secs);
+            let (result, timed_out) = match tokio::time::timeout(timeout, check.check()).await {
+                Ok(Ok(result)) => (result, false),
+                Ok(Err(e)) => (
+                    CheckResult::fail(check.name(), &format!("Check errored: {}", e)),
+                    false,
+                ),
+                Err(_) => (
+                    CheckResult::fail(
+                        check.name(),
+                        &format!("Timed out after {}s", config.timeout_secs),
+                    ),
+                    true,
+                ),
+            };
+            if result.passed {
+                info!("Smoke check {} passed", result.name);
+            } else {
+                warn!("Smoke check {} failed: {}", result.name, result.message);
+            }
+            outcomes.push(SmokeCheckOutcome {
+                result,
+                weight: config.weight,
+                required: config.required,
+                timed_out,
+                duration_ms: started.elapsed().as_millis() as u64,
+            });
+        }
+
+        let total: f32 = outcomes.iter().map(|o| o.weight).sum();
+        let passed: f32 = outcomes
+            .iter()
+            .filter(|o| o.result.passed)
+            .map(|o| o.weight)
+            .sum();
+        let score = if total > 0.0 { passed / total } else { 1.0 };
+        let required_ok = outcomes.iter().all(|o| o.result.passed || !o.required);
+
+        SmokeReport {
+            outcomes,
+            score,
+            healthy: required_ok && score >= self.min_score,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use wiremock::matchers::{method, path};
+    use wiremock::{Mock, MockServer, ResponseTemplate};
+
+    struct Slow;
+
+    #[async_trait]
+    impl SmokeCheck for Slow {
+        fn name(&self) -> &str {
+            "slow"
+        }
+
+        async fn check(&self) -> Result<CheckResult> {
+            tokio::time::sleep(Duration::from_secs(5)).await;
+            Ok(CheckResult::pass("slow", "done"))
+        }
+    }
+
+    fn scenario() -> Scenario {
+        Scenario::parse(
+            r#"
+name: smoke
+turns:
+  - text: "Hello! How can I help?"
+  - expect: "list files"
+    text: "Here are the files: main.rs"
+"#,
+        )
+        .unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_http_probe_and_scenario() {
+        let server = MockServer::start().await;
+        Mock::given(method("GET"))
+            .and(path("/status"))
+            .respond_with(ResponseTemplate::new(200).set_body_string("ok"))
+            .mount(&server)
+            .await;
+
+        let steps = vec![
+            ScenarioStep {
+                user: "hi".to_string(),
+                expect_reply: Some("help".to_string()),
+            },
+            ScenarioStep {
+                user: "please list files".to_string(),
+                expect_reply: Some("main.rs".to_string()),
+            },
+        ];
+        let report = SmokeCheckRunner::new()
+            .add_check(
+                Box::new(
+                    HttpProbe::new("status", &format!("{}/status", server.uri())).expect_body("ok"),
+                ),
+                SmokeCheckConfig::required(),
+            )
+            .add_check(
+                Box::new(ScenarioCheck::new("chat", scenario(), steps)),
+                SmokeCheckConfig::default(),
+            )
+            .run()
+            .await;
+
+        assert!(report.healthy, "{:?}", report.failures());
+        assert_eq!(report.score, 1.0);
+    }
+
+    #[tokio::test]
+    async fn test_verdict_uses_weights_and_required_checks() {
+        let steps = vec![ScenarioStep {
+            user: "hi".to_string(),
+            expect_reply: Some("goodbye".to_string()),
+        }];
+
+        let report = SmokeCheckRunner::new()
+            .with_min_score(0.7)
+            .add_check(
+                Box::new(ScenarioCheck::new("chat", scenario(), steps.clone())),
+                SmokeCheckConfig::default().with_weight(3.0),
+            )
+            .add_check(
+                Box::new(Slow),
+                SmokeCheckConfig::default()
+                    .with_timeout(Duration::from_secs(1))
+                    .with_weight(1.0),
+            )
+            .run()
+            .await;
+        assert!(!report.healthy);
+        assert_eq!(report.score, 0.0);
+        assert!(report.outcomes[1].timed_out);
+
+        let report = SmokeCheckRunner::new()
+            .with_min_score(0.7)
+            .add_check(
+                Box::new(ScenarioCheck::new(
+                    "chat",
+                    scenario(),
+                    vec![ScenarioStep {
+                        user: "hi".to_string(),
+                        expect_reply: None,
+                    }],
+                )),
+                SmokeCheckConfig::default().with_weight(3.0),
+            )
+            .add_check(
+                Box::new(Slow),
+                SmokeCheckConfig::required().with_timeout(Duration::from_secs(1)),
+            )
+            .run()
+            .await;
+        assert_eq!(report.score, 0.75);
+        assert!(!report.healthy, "a failed required check is unhealthy");
+    }
+}
@@ -6,6 +6,7 @@ use std::collections::HashMap;
 
 use super::{SpecialistAgent, SpecialistConfig, SpecialistContext};
 use crate::agents::orchestrator::{AgentRole, TaskResult};
+use crate::artifacts::{ArtifactKind, ArtifactRegistry, NewArtifact};
 
 /// Specialist agent focused on documentation
 pub struct DocsAgent {
@@ -41,12 +42,25 @@ impl SpecialistAgent for DocsAgent {
         let mut artifacts = Vec::new();
 
         // Generate README.md
-        let _readme_content = format!(
+        let readme_content = format!(
             "# {}\n\nGenerated documentation for the project.\n",
             context.task
         );
         files_modified.push(format!("{}/README.md", context.working_dir));
-        artifacts.push("Generated README.md".to_string());
+
+        // Keep the generated docs in the artifact registry for the task
+        let mut readme = NewArtifact::new("docs_agent", ArtifactKind::Docs, "README.md")
+            .with_mime_type("text/markdown");
+        if let Some(task_id) = context.metadata.get("task_id").and_then(|v| v.as_str()) {
+            readme = readme.for_task(task_id);
+        }
+        match ArtifactRegistry::persistent().store(readme, readme_content.as_bytes()) {
+            Ok(record) => artifacts.push(format!("Generated README.md (artifact {})", record.id)),
+            Err(e) => {
+                tracing::warn!("Failed to store generated docs: {}", e);
+                artifacts.push("Generated README.md".to_string());
+            }
+        }
 
         let mut metrics = HashMap::new();
         metrics.insert(
@@ -0,0 +1,310 @@
+//! Flaky test detection and quarantine
+//!
+//! Failed tests are re-run a few times before a run is judged. A test that
+//! fails and then passes is flaky: it does not fail the run, and it is put on
+//! a quarantine list persisted under the goose data directory. While a test is
+//! quarantined its failures are still reported but no longer gate the run.
+//! Entries expire so a fixed test goes back to gating on its own.
+
+use super::state::{TestResult, TestStatus};
+use crate::config::paths::Paths;
+use anyhow::Result;
+use chrono::{DateTime, Duration, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashSet};
+use std::path::{Path, PathBuf};
+
+const QUARANTINE_FILE: &str = "test_quarantine.json";
+
+/// How failed tests are retried and how long flaky tests stay quarantined
+#[derive(Debug, Clone)]
+pub struct FlakyTestConfig {
+    /// Extra runs after the first one while failures remain
+    pub retries: usize,
+    pub quarantine_ttl: Duration,
+}
+
+impl Default for FlakyTestConfig {
+    fn default() -> Self {
+        Self {
+            retries: 2,
+            quarantine_ttl: Duration::days(7),
+        }
+    }
+}
+
+/// Stable identifier for a test across runs
+pub fn test_id(result: &TestResult) -> String {
+    if result.file.is_empty() || result.file == "unknown" {
+        result.test_name.clone()
+    } else {
+        format!("{}::{}", result.file, result.test_name)
+    }
+}
+
+fn is_failure(result: &TestResult) -> bool {
+    matches!(result.status, TestStatus::Failed | TestStatus::Error)
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct QuarantineEntry {
+    pub test_id: String,
+    pub reason: String,
+    pub quarantined_at: DateTime<Utc>,
+    pub expires_at: DateTime<Utc>,
+    /// Runs in which the test was seen flaking
+    pub flaky_runs: u32,
+}
+
+/// Persisted list of quarantined tests
+#[derive(Debug, Clone)]
+pub struct TestQuarantine {
+    path: PathBuf,
+    entries: BTreeMap<String, QuarantineEntry>,
+}
+
+impl TestQuarantine {
+    /// Loads the quarantine list, dropping expired entries. A missing file is
+    /// an empty list.
+    pub fn load(path: impl Into<PathBuf>) -> Result<Self> {
+        let path = path.into();
+        let entries = if path.exists() {
+            let content = std::fs::read_to_string(&path)?;
+            serde_json::from_str::<Vec<QuarantineEntry>>(&content)?
+                .into_iter()
+                .map(|e| (e.test_id.clone(), e))
+                .collect()
+        } else {
+            BTreeMap::new()
+        };
+        let mut quarantine = Self { path, entries };
+        quarantine.prune_expired(Utc::now());
+        Ok(quarantine)
+    }
+
+    pub fn load_default() -> Result<Self> {
+        Self::load(Paths::in_data_dir(QUARANTINE_FILE))
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    pub fn save(&self) -> Result<()> {
+        if let Some(parent) = self.path.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        let entries: Vec<&QuarantineEntry> = self.entries.values().collect();
+        std::fs::write(&self.path, serde_json::to_string_pretty(&entries)?)?;
+        Ok(())
+    }
+
+    pub fn is_quarantined(&self, test_id: &str) -> bool {
+        self.entries
+            .get(test_id)
+            .is_some_and(|e| e.expires_at > Utc::now())
+    }
+
+    /// Quarantines a test, or extends its quarantine if it is already listed
+    pub fn quarantine(&mut self, test_id: &str, reason: &str, ttl: Duration) {
+        let now = Utc::now();
+        let entry = self
+            .entries
+            .entry(test_id.to_string())
+            .or_insert_with(|| QuarantineEntry {
+                test_id: test_id.to_string(),
+                reason: reason.to_string(),
+                quarantined_at: now,
+                expires_at: now,
+                flaky_runs: 0,
+            });
+        entry.reason = reason.to_string();
+        entry.expires_at = now + ttl;
+        entry.flaky_runs += 1;
+    }
+
+    pub fn release(&mut self, test_id: &str) -> bool {
+        self.entries.remove(test_id).is_some()
+    }
+
+    pub fn prune_expired(&mut self, now: DateTime<Utc>) -> usize {
+        let before = self.entries.len();
+        self.entries.retain(|_, e| e.expires_at > now);
+        before - self.entries.len()
+    }
+
+    pub fn entries(&self) -> impl Iterator<Item = &QuarantineEntry> {
+        self.entries.values()
+    }
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct FlakinessStats {
+    pub total: usize,
+    pub passed: usize,
+    /// Failures that count against the run
+    pub failed: usize,
+    /// Test command runs, including the first one
+    pub attempts: usize,
+    pub flaky: usize,
+    /// Quarantined tests that failed in this run
+    pub quarantined_failures: usize,
+    /// Share of the tests that failed at least once which later passed
+    pub flaky_rate: f32,
+}
+
+/// Outcome of a test run with retries and quarantine applied
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct TestRunResult {
+    /// Final result per test (the last attempt it appeared in)
+    pub results: Vec<TestResult>,
+    /// Tests that failed and then passed on a retry
+    pub flaky: Vec<String>,
+    /// Failures of quarantined tests; reported but not gating
+    pub quarantined: Vec<TestResult>,
+    /// Failures that should fail the run
+    pub gating_failures: Vec<TestResult>,
+    pub stats: FlakinessStats,
+}
+
+impl TestRunResult {
+    /// Folds the attempts of one run into a result. `attempts[0]` is the
+    /// initial run; later entries are retries.
+    pub fn from_attempts(attempts: &[Vec<TestResult>], quarantine: &TestQuarantine) -> Self {
+        let mut order = Vec::new();
+        let mut last: BTreeMap<String, TestResult> = BTreeMap::new();
+        let mut failed_once = HashSet::new();
+        let mut passed_after_failure = HashSet::new();
+
+        for attempt in attempts {
+            for result in attempt {
+                let id = test_id(result);
+                if is_failure(result) {
+                    failed_once.insert(id.clone());
+                } else if result.is_passed() && failed_once.contains(&id) {
+                    passed_after_failure.insert(id.clone());
+                }
+                if !last.contains_key(&id) {
+                    order.push(id.clone());
+                }
+                last.insert(id, result.clone());
+            }
+        }
+
+        let mut run = Self {
+            stats: FlakinessStats {
+                attempts: attempts.len(),
+                ..Default::default()
+            },
+            ..Default::default()
+        };
+        for id in order {
+            let result = last.remove(&id).expect("every ordered id has a result");
+            if passed_after_failure.contains(&id) {
+                run.flaky.push(id.clone());
+            }
+            if is_failure(&result) {
+                if quarantine.is_quarantined(&id) {
+                    run.quarantined.push(result.clone());
+                } else {
+                    run.gating_failures.push(result.clone());
+                }
+            } else if result.is_passed() {
+                run.stats.passed += 1;
+            }
+            run.results.push(result);
+        }
+
+        run.stats.total = run.results.len();
+        run.stats.failed = run.gating_failures.len();
+        run.stats.flaky = run.flaky.len();
+        run.stats.quarantined_failures = run.quarantined.len();
+        if !failed_once.is_empty() {
+            run.stats.flaky_rate = run.flaky.len() as f32 / failed_once.len() as f32;
+        }
+        run
+    }
+
+    /// Whether the run passes once quarantined failures are set aside
+    pub fn passed(&self) -> bool {
+        self.gating_failures.is_empty()
+    }
+
+    /// Final results with quarantined failures set aside, for gating
+    pub fn gating_results(&self) -> Vec<TestResult> {
+        let quarantined: HashSet<String> = self.quarantined.iter().map(test_id).collect();
+        self.results
+            .iter()
+            .filter(|r| !quarantined.contains(&test_id(r)))
+            .cloned()
+            .collect()
+    }
+
+    /// Test ids still failing after the last attempt, quarantined or not
+    pub fn failing_ids(&self) -> Vec<String> {
+        self.results
+            .iter()
+            .filter(|r| is_failure(r))
+            .map(test_id)
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn quarantine(dir: &Path) -> TestQuarantine {
+        TestQuarantine::load(dir.join(QUARANTINE_FILE)).unwrap()
+    }
+
+    #[test]
+    fn test_flaky_and_consistent_failures() {
+        let dir = tempfile::tempdir().unwrap();
+        let attempts = vec![
+            vec![
+                TestResult::passed("unknown", "test_add"),
+                TestResult::failed("unknown", "test_network", "timeout"),
+                TestResult::failed("unknown", "test_sub", "expected 5, got 3"),
+            ],
+            vec![
+                TestResult::passed("unknown", "test_add"),
+                TestResult::passed("unknown", "test_network"),
+                TestResult::failed("unknown", "test_sub", "expected 5, got 3"),
+            ],
+        ];
+
+        let run = TestRunResult::from_attempts(&attempts, &quarantine(dir.path()));
+
+        assert_eq!(run.flaky, vec!["test_network"]);
+        assert_eq!(run.gating_failures.len(), 1);
+        assert_eq!(run.gating_failures[0].test_name, "test_sub");
+        assert!(!run.passed());
+        assert_eq!(run.stats.total, 3);
+        assert_eq!(run.stats.passed, 2);
+        assert_eq!(run.stats.attempts, 2);
+        assert_eq!(run.stats.flaky_rate, 0.5);
+    }
+
+    #[test]
+    fn test_quarantined_failures_do_not_gate() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut q = quarantine(dir.path());
+        q.quarantine("test_sub", "flaked on retry", Duration::days(1));
+        q.quarantine("test_old", "flaked on retry", Duration::days(1));
+        q.entries.get_mut("test_old").unwrap().expires_at = Utc::now() - Duration::hours(1);
+        q.save().unwrap();
+
+        let q = quarantine(dir.path());
+        assert!(q.is_quarantined("test_sub"));
+        assert_eq!(q.entries().count(), 1);
+
+        let attempts = vec![vec![TestResult::failed("unknown", "test_sub", "boom")]];
+        let run = TestRunResult::from_attempts(&attempts, &q);
+        assert!(run.passed());
+        assert_eq!(run.quarantined.len(), 1);
+        assert_eq!(run.stats.quarantined_failures, 1);
+        assert_eq!(run.failing_ids(), vec!["test_sub"]);
+        assert!(run.gating_results().is_empty());
+    }
+}
@@ -4,9 +4,11 @@ use std::path::PathBuf;
 use tokio::sync::mpsc;
 use tracing::{error, info, warn};
 
+pub mod flaky;
 pub mod runner;
 pub mod state;
 
+pub use flaky::{FlakinessStats, FlakyTestConfig, TestQuarantine, TestRunResult};
 pub use runner::{ShellTestRunner, StateGraphRunner};
 pub use state::{CodeTestFixState, TestResult, TestStatus};
 
@@ -1,3 +1,4 @@
+use super::flaky::{test_id, FlakyTestConfig, TestQuarantine, TestRunResult};
 use super::{CodeTestFixState, GraphState, StateGraph, StateGraphConfig, StateGraphEvent};
 use crate::test_parsers::{parse_test_output, TestFramework, TestResult};
 use anyhow::Result;
@@ -72,6 +73,7 @@ pub struct ShellTestRunner {
     test_command: String,
     _working_dir: PathBuf,
     framework: TestFramework,
+    flaky: FlakyTestConfig,
 }
 
 impl ShellTestRunner {
@@ -81,9 +83,15 @@ impl ShellTestRunner {
             test_command: test_command.to_string(),
             _working_dir: working_dir,
             framework,
+            flaky: FlakyTestConfig::default(),
         }
     }
 
+    pub fn with_flaky_config(mut self, flaky: FlakyTestConfig) -> Self {
+        self.flaky = flaky;
+        self
+    }
+
     pub fn with_framework(mut self, framework: TestFramework) -> Self {
         self.framework = framework;
         self
@@ -118,12 +126,52 @@ impl ShellTestRunner {
         Ok(results)
     }
 
+    /// Runs the tests, re-running the command while failures remain (up to the
+    /// configured retries). Tests that pass on a retry are quarantined, and
+    /// quarantined failures are reported without failing the run.
+    pub async fn run_with_retries(&self, quarantine: &mut TestQuarantine) -> Result<TestRunResult> {
+        let mut attempts = vec![self.run_tests().await?];
+        let mut run = TestRunResult::from_attempts(&attempts, quarantine);
+
+        while attempts.len() <= self.flaky.retries && !run.failing_ids().is_empty() {
+            info!(
+                "Retrying {} failing tests (attempt {} of {})",
+                run.failing_ids().len(),
+                attempts.len() + 1,
+                self.flaky.retries + 1
+            );
+            attempts.push(self.run_tests().await?);
+            run = TestRunResult::from_attempts(&attempts, quarantine);
+        }
+
+        if !run.flaky.is_empty() {
+            for id in &run.flaky {
+                warn!("Quarantining flaky test {}", id);
+                quarantine.quarantine(
+                    id,
+                    "failed, then passed on retry",
+                    self.flaky.quarantine_ttl,
+                );
+            }
+            quarantine.save()?;
+        }
+        for result in &run.quarantined {
+            warn!("Quarantined test {} failed; not gating", test_id(result));
+        }
+
+        Ok(run)
+    }
+
     pub fn into_callback(self) -> TestRunFn {
         let runner = std::sync::Arc::new(self);
         Box::new(move |_state| {
             let runner = runner.clone();
             let rt = tokio::runtime::Handle::current();
-            rt.block_on(async { runner.run_tests().await })
+            rt.block_on(async {
+                let mut quarantine = TestQuarantine::load_default()?;
+                let run = runner.run_with_retries(&mut quarantine).await?;
+                Ok(run.gating_results())
+            })
         })
     }
 }
@@ -150,4 +198,25 @@ mod tests {
         let runner = ShellTestRunner::new("npm test", PathBuf::from("."));
         assert_eq!(runner.framework, TestFramework::Jest);
     }
+
+    #[tokio::test]
+    async fn test_run_with_retries_quarantines_flaky_test() {
+        let dir = tempfile::tempdir().unwrap();
+        let marker = dir.path().join("ran_once");
+        let command = format!(
+            "if [ -f {m} ]; then echo 'test flaky ... ok'; else touch {m}; echo 'test flaky ... FAILED'; fi; echo 'test stable ... ok'",
+            m = marker.display()
+        );
+        let runner = ShellTestRunner::new(&command, dir.path().to_path_buf())
+            .with_framework(TestFramework::Cargo);
+        let mut quarantine = TestQuarantine::load(dir.path().join("quarantine.json")).unwrap();
+
+        let run = runner.run_with_retries(&mut quarantine).await.unwrap();
+
+        assert!(run.passed());
+        assert_eq!(run.flaky, vec!["flaky"]);
+        assert_eq!(run.stats.attempts, 2);
+        let reloaded = TestQuarantine::load(quarantine.path()).unwrap();
+        assert!(reloaded.is_quarantined("flaky"));
+    }
 }
@@ -0,0 +1,155 @@
+//! Smooths a reply's event stream for display.
+//!
+//! Providers stream text a few characters at a time. When events arrive
+//! faster than the client takes them, [`chunk_events`] takes what has
+//! already arrived, up to [`MAX_PENDING_EVENTS`], and merges consecutive
+//! text deltas of the same message into one update, breaking at sentence
+//! ends so updates land on whole sentences. Nothing further is read from
+//! the reply until the client has taken those updates, so a slow client
+//! holds back the provider instead of buffering its output.
+//!
+//! Only cosmetic thinking notifications are ever dropped: of several
+//! waiting at once, the latest is shown. Message content is always kept.
+
+use crate::agents::AgentEvent;
+use crate::conversation::message::{Message, MessageContent, SystemNotificationType};
+use anyhow::Result;
+use futures::stream::BoxStream;
+use futures::{FutureExt, StreamExt};
+
+/// Events read ahead of the client at most
+pub const MAX_PENDING_EVENTS: usize = 64;
+/// Merged text deltas are sent once they reach this length
+pub const MAX_CHUNK_CHARS: usize = 400;
+
+fn text_delta(message: &Message) -> Option<&str> {
+    match message.content.as_slice() {
+        [MessageContent::Text(text)] if message.id.is_some() => Some(&text.text),
+        _ => None,
+    }
+}
+
+fn is_thinking_notification(event: &Result<AgentEvent>) -> bool {
+    let Ok(AgentEvent::Message(message)) = event else {
+        return false;
+    };
+    message.content.iter().any(|c| {
+        matches!(c, MessageContent::SystemNotification(n)
+            if n.notification_type == SystemNotificationType::ThinkingMessage)
+    })
+}
+
+fn ends_sentence(text: &str) -> bool {
+    text.trim_end_matches([' ', '"', '\'', ')'])
+        .ends_with(['.', '!', '?', '\n'])
+}
+
+/// Merges the text deltas in `events` and keeps only the last thinking
+/// notification.
+fn coalesce(events: Vec<Result<AgentEvent>>) -> Vec<Result<AgentEvent>> {
+    let last_thinking = events.iter().rposition(is_thinking_notification);
+    let mut chunks: Vec<Result<AgentEvent>> = Vec::with_capacity(events.len());
+    let mut open = false;
+
+    for (index, event) in events.into_iter().enumerate() {
+        if is_thinking_notification(&event) && Some(index) != last_thinking {
+            continue;
+        }
+        if let Ok(AgentEvent::Message(message)) = &event {
+            if let Some(delta) = text_delta(message) {
+                if let Some(Ok(AgentEvent::Message(previous))) = chunks.last_mut() {
+                    let mergeable = open
+                        && previous.id == message.id
+                        && previous.role == message.role
+                        && text_delta(previous).is_some();
+                    if mergeable {
+                        if let Some(MessageContent::Text(text)) = previous.content.first_mut() {
+                            text.text.push_str(delta);
+                            open = !ends_sentence(&text.text) && text.text.len() < MAX_CHUNK_CHARS;
+                            continue;
+                        }
+                    }
+                }
+                open = !ends_sentence(delta) && delta.len() < MAX_CHUNK_CHARS;
+                chunks.push(event);
+                continue;
+            }
+        }
+        open = false;
+        chunks.push(event);
+    }
+    chunks
+}
+
+/// Wraps a reply stream so a client gets merged, sentence-sized updates.
+pub fn chunk_events<'a>(
+    events: BoxStream<'a, Result<AgentEvent>>,
+) -> BoxStream<'a, Result<AgentEvent>> {
+    let mut events = events.fuse();
+    Box::pin(async_stream::stream! {
+        while let Some(first) = events.next().await {
+            let mut pending = vec![first];
+            // Whatever else is ready, without waiting for more
+            while pending.len() < MAX_PENDING_EVENTS {
+                match events.next().now_or_never() {
+                    Some(Some(event)) => pending.push(event),
+                    _ => break,
+                }
+            }
+            for chunk in coalesce(pending) {
+                yield chunk;
+            }
+        }
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn delta(text: &str) -> Result<AgentEvent> {
+        Ok(AgentEvent::Message(
+            Message::assistant().with_id("msg_1").with_text(text),
+        ))
+    }
+
+    fn thinking(text: &str) -> Result<AgentEvent> {
+        Ok(AgentEvent::Message(
+            Message::assistant()
+                .with_system_notification(SystemNotificationType::ThinkingMessage, text),
+        ))
+    }
+
+    fn texts(events: &[Result<AgentEvent>]) -> Vec<String> {
+        events
+            .iter()
+            .map(|e| match e {
+                Ok(AgentEvent::Message(m)) => m.as_concat_text(),
+                _ => String::new(),
+            })
+            .collect()
+    }
+
+    #[tokio::test]
+    async fn test_deltas_merge_up_to_sentence_ends_and_old_thinking_is_dropped() {
+        let events: Vec<Result<AgentEvent>> = vec![
+            thinking("Compacting"),
+            delta("Hel"),
+            delta("lo. "),
+            delta("How"),
+            thinking("Still compacting"),
+            delta(" are"),
+            delta(" you?"),
+        ];
+        let chunked: Vec<_> = chunk_events(futures::stream::iter(events).boxed())
+            .collect()
+            .await;
+
+        let texts = texts(&chunked);
+        assert_eq!(texts.len(), 4);
+        assert_eq!(texts[0], "Hello. ");
+        assert_eq!(texts[1], "How");
+        assert!(is_thinking_notification(&chunked[2]));
+        assert_eq!(texts[3], " are you?");
+    }
+}
@@ -1,4 +1,5 @@
 use crate::{
+    action_required_manager::ActionRequiredManager,
     agents::{subagent_task_config::TaskConfig, Agent, AgentConfig, AgentEvent, SessionConfig},
     conversation::{
         message::{Message, MessageContent},
@@ -162,6 +163,10 @@ fn get_agent_messages_with_notifications(
             .await
             .map_err(|e| anyhow!("Failed to set provider on sub agent: {}", e))?;
 
+        if let Some(grant) = &task_config.capabilities {
+            agent.restrict_capabilities(grant.clone()).await;
+        }
+
         for extension in &task_config.extensions {
             if let Err(e) = agent.add_extension(extension.clone(), &session_id).await {
                 debug!(
@@ -174,7 +179,12 @@ fn get_agent_messages_with_notifications(
 
         let has_response_schema = recipe.response.is_some();
         agent
-            .apply_recipe_components(recipe.sub_recipes.clone(), recipe.response.clone(), true)
+            .apply_recipe_components(
+                recipe.sub_recipes.clone(),
+                recipe.response.clone(),
+                recipe.validation.clone(),
+                true,
+            )
             .await;
 
         let subagent_prompt =
@@ -196,7 +206,12 @@ fn get_agent_messages_with_notifications(
             retry_config: recipe.retry,
         };
 
-        conversation = run_subagent_stream(
+        // Questions the subagent asks are shown in the parent's session
+        let elicitations = ActionRequiredManager::global();
+        elicitations
+            .route_session(&session_id, &task_config.parent_session_id)
+            .await;
+        let result = run_subagent_stream(
             agent.clone(),
             user_message,
             session_config,
@@ -205,7 +220,9 @@ fn get_agent_messages_with_notifications(
             &notification_tx,
             conversation,
         )
-        .await?;
+        .await;
+        elicitations.unroute_session(&session_id).await;
+        conversation = result?;
 
         let final_output = get_final_output(&agent, has_response_schema).await;
 
@@ -1,3 +1,4 @@
+use crate::agents::capability_grant::CapabilityGrant;
 use crate::agents::ExtensionConfig;
 use crate::providers::base::Provider;
 use std::env;
@@ -19,6 +20,8 @@ pub struct TaskConfig {
     pub parent_working_dir: PathBuf,
     pub extensions: Vec<ExtensionConfig>,
     pub max_turns: Option<usize>,
+    /// What the subagent may use, when less than its parent's tools
+    pub capabilities: Option<CapabilityGrant>,
 }
 
 impl fmt::Debug for TaskConfig {
@@ -29,6 +32,7 @@ impl fmt::Debug for TaskConfig {
             .field("parent_working_dir", &self.parent_working_dir)
             .field("max_turns", &self.max_turns)
             .field("extensions", &self.extensions)
+            .field("capabilities", &self.capabilities)
             .finish()
     }
 }
@@ -51,6 +55,7 @@ impl TaskConfig {
                     .and_then(|val| val.parse::<usize>().ok())
                     .unwrap_or(DEFAULT_SUBAGENT_MAX_TURNS),
             ),
+            capabilities: None,
         }
     }
 
@@ -60,4 +65,10 @@ impl TaskConfig {
         }
         self
     }
+
+    /// Starts from the parent's own grant, if it is a subagent itself.
+    pub fn with_capabilities(mut self, capabilities: Option<CapabilityGrant>) -> Self {
+        self.capabilities = capabilities;
+        self
+    }
 }
@@ -11,6 +11,7 @@ use tokio::sync::mpsc;
 use tokio_stream::wrappers::UnboundedReceiverStream;
 use tokio_util::sync::CancellationToken;
 
+use crate::agents::capability_grant::CapabilityGrant;
 use crate::agents::subagent_handler::run_complete_subagent_task_with_notifications;
 use crate::agents::subagent_task_config::TaskConfig;
 use crate::agents::tool_execution::ToolCallResult;
@@ -40,6 +41,7 @@ pub struct SubagentParams {
     pub parameters: Option<HashMap<String, Value>>,
     pub extensions: Option<Vec<String>>,
     pub settings: Option<SubagentSettings>,
+    pub capabilities: Option<CapabilityGrant>,
     #[serde(default = "default_summary")]
     pub summary: bool,
 }
@@ -90,6 +92,22 @@ pub fn create_subagent_tool(sub_recipes: &[SubRecipe]) -> Tool {
                 },
                 "description": "Override model/provider/settings."
             },
+            "capabilities": {
+                "type": "object",
+                "properties": {
+                    "tools": {
+                        "type": "array",
+                        "items": {"type": "string"},
+                        "description": "Extensions or extension__tool names the subagent may call. Everything else is unavailable to it."
+                    },
+                    "read_paths": {
+                        "type": "array",
+                        "items": {"type": "string"},
+                        "description": "Make the subagent read-only, limited to these paths. It cannot write or run shell commands, and only tools marked read-only are available to it."
+                    }
+                },
+                "description": "Grant the subagent only what its task needs."
+            },
             "summary": {
                 "type": "boolean",
                 "default": true,
@@ -113,7 +131,8 @@ fn build_tool_description(sub_recipes: &[SubRecipe]) -> String {
          2. Predefined: Provide `subrecipe` name to run a predefined task\n\
          3. Augmented: Provide both `subrecipe` and `instructions` to add context\n\n\
          The subagent has access to the same tools as you by default. \
-         Use `extensions` to limit which extensions the subagent can use.\n\n\
+         Use `extensions` to limit which extensions the subagent can use, and \
+         `capabilities` to grant it only the tools and paths its task needs.\n\n\
          For parallel execution, make multiple `subagent` tool calls in the same message.",
     );
 
@@ -428,6 +447,19 @@ async fn apply_settings_overrides(
         }
     }
 
+    if let Some(requested) = &params.capabilities {
+        let requested = requested.clone().resolved(&task_config.parent_working_dir);
+        task_config.capabilities = Some(match task_config.capabilities.take() {
+            Some(granted) => granted.narrow(&requested),
+            None => requested,
+        });
+    }
+    if let Some(grant) = &task_config.capabilities {
+        task_config
+            .extensions
+            .retain(|ext| grant.allows_extension(&ext.name()));
+    }
+
     if let Some(extension_names) = &params.extensions {
         if extension_names.is_empty() {
             task_config.extensions = Vec::new();
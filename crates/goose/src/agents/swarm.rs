@@ -10,14 +10,19 @@
 //! - **SwarmConfig** — configuration for swarm behavior
 //! - **SwarmRouter** — routes tasks to the best agent (hybrid routing)
 //! - **SwarmMessage** — inter-agent communication
+//! - **CapabilityRecord** — what an agent advertises it can do, refreshed by heartbeats
 //! - **BatchProcessor** — processes multiple tasks in parallel
 
 use std::collections::HashMap;
 use std::fmt;
+use std::sync::Arc;
 use std::time::Duration;
 
 use serde::{Deserialize, Serialize};
 
+use super::mailbox::{Delivery, MailboxStore};
+use super::ExecutionMode;
+
 // ---------------------------------------------------------------------------
 // Swarm Agent
 // ---------------------------------------------------------------------------
@@ -48,10 +53,18 @@ pub struct SwarmAgent {
     pub tasks_completed: u64,
     /// Number of tasks failed
     pub tasks_failed: u64,
+    /// Capabilities published on registration and heartbeat
+    #[serde(default)]
+    pub advertisement: CapabilityRecord,
+    /// Load reported by the last heartbeat, from 0.0 (idle) to 1.0 (saturated)
+    #[serde(default)]
+    pub reported_load: Option<f64>,
+    #[serde(default)]
+    pub last_heartbeat: Option<chrono::DateTime<chrono::Utc>>,
 }
 
 /// Specialization role for an agent.
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize, utoipa::ToSchema)]
 #[serde(rename_all = "snake_case")]
 pub enum SwarmRole {
     /// General-purpose agent
@@ -117,6 +130,9 @@ impl SwarmAgent {
             performance_score: 1.0,
             tasks_completed: 0,
             tasks_failed: 0,
+            advertisement: CapabilityRecord::default(),
+            reported_load: None,
+            last_heartbeat: None,
         }
     }
 
@@ -125,6 +141,31 @@ impl SwarmAgent {
         self
     }
 
+    pub fn with_advertisement(mut self, record: CapabilityRecord) -> Self {
+        self.advertisement = record;
+        self
+    }
+
+    /// Whether the agent's role, advertised roles, skills or cores match `capability`.
+    pub fn has_capability(&self, capability: &str) -> bool {
+        self.role.to_string() == capability
+            || self.capabilities.iter().any(|c| c == capability)
+            || self.advertisement.roles.iter().any(|r| r.to_string() == capability)
+            || self.advertisement.skills.iter().any(|s| s == capability)
+            || self.advertisement.cores.iter().any(|c| c.to_string() == capability)
+    }
+
+    /// Heartbeat-reported load, falling back to the share of task slots in use.
+    pub fn load(&self) -> f64 {
+        self.reported_load.unwrap_or_else(|| {
+            if self.max_concurrent == 0 {
+                1.0
+            } else {
+                self.current_tasks as f64 / self.max_concurrent as f64
+            }
+        })
+    }
+
     pub fn is_available(&self) -> bool {
         self.state == AgentState::Idle || (self.state == AgentState::Working && self.current_tasks < self.max_concurrent)
     }
@@ -158,6 +199,39 @@ impl SwarmAgent {
     }
 }
 
+// ---------------------------------------------------------------------------
+// Capability Advertisement
+// ---------------------------------------------------------------------------
+
+/// Relative cost of running an agent; cheaper agents win ties during discovery.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Default, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum CostTier {
+    Low,
+    #[default]
+    Medium,
+    High,
+}
+
+/// What an agent can do, published when it registers and refreshed on heartbeat.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct CapabilityRecord {
+    /// Roles the agent can take on besides its primary role
+    pub roles: Vec<SwarmRole>,
+    pub skills: Vec<String>,
+    /// Agent cores the agent can run
+    pub cores: Vec<ExecutionMode>,
+    pub cost_tier: CostTier,
+}
+
+/// Periodic liveness report from an agent.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AgentHeartbeat {
+    pub load: f64,
+    /// Replaces the agent's advertisement when its capabilities changed
+    pub advertisement: Option<CapabilityRecord>,
+}
+
 // ---------------------------------------------------------------------------
 // Swarm Configuration
 // ---------------------------------------------------------------------------
@@ -222,6 +296,9 @@ pub struct Swarm {
     agents: HashMap<AgentId, SwarmAgent>,
     message_log: Vec<SwarmMessage>,
     round_robin_index: usize,
+    /// Delivers messages until the recipient acknowledges them
+    #[serde(skip)]
+    mailbox: Option<Arc<MailboxStore>>,
 }
 
 impl Swarm {
@@ -232,9 +309,17 @@ impl Swarm {
             agents: HashMap::new(),
             message_log: Vec::new(),
             round_robin_index: 0,
+            mailbox: None,
         }
     }
 
+    /// Delivers messages through `store`, so they survive restarts and are
+    /// redelivered until acknowledged.
+    pub fn with_mailbox(mut self, store: Arc<MailboxStore>) -> Self {
+        self.mailbox = Some(store);
+        self
+    }
+
     /// Add an agent to the swarm.
     pub fn add_agent(&mut self, agent: SwarmAgent) -> Result<(), SwarmError> {
         if self.agents.len() >= self.config.max_agents {
@@ -269,6 +354,40 @@ impl Swarm {
         self.agents.values().filter(|a| a.is_available()).collect()
     }
 
+    /// Record a heartbeat, updating the agent's load and optionally its advertisement.
+    pub fn heartbeat(&mut self, agent_id: &str, heartbeat: AgentHeartbeat) -> Result<(), SwarmError> {
+        let agent = self
+            .agents
+            .get_mut(agent_id)
+            .ok_or_else(|| SwarmError::AgentNotFound(agent_id.to_string()))?;
+        agent.reported_load = Some(heartbeat.load.clamp(0.0, 1.0));
+        agent.last_heartbeat = Some(chrono::Utc::now());
+        if let Some(advertisement) = heartbeat.advertisement {
+            agent.advertisement = advertisement;
+        }
+        Ok(())
+    }
+
+    /// Agents able to take work matching `capability`, least loaded first,
+    /// then cheapest, then most reliable.
+    pub fn find_agents(&self, capability: &str, max_load: f64, min_success_rate: f64) -> Vec<&SwarmAgent> {
+        let mut found: Vec<_> = self
+            .agents
+            .values()
+            .filter(|a| matches!(a.state, AgentState::Idle | AgentState::Working))
+            .filter(|a| a.has_capability(capability))
+            .filter(|a| a.load() <= max_load && a.success_rate() >= min_success_rate)
+            .collect();
+        found.sort_by(|a, b| {
+            a.load()
+                .total_cmp(&b.load())
+                .then(a.advertisement.cost_tier.cmp(&b.advertisement.cost_tier))
+                .then(b.success_rate().total_cmp(&a.success_rate()))
+                .then(a.id.cmp(&b.id))
+        });
+        found
+    }
+
     /// Route a task to the best agent based on strategy.
     pub fn route_task(&mut self, task: &SwarmTask) -> Result<AgentId, SwarmError> {
         let agent_id = match self.config.routing {
@@ -310,7 +429,7 @@ impl Swarm {
             .available_agents()
             .iter()
             .filter(|a| {
-                task.required_capabilities.iter().all(|cap| a.capabilities.contains(cap))
+                task.required_capabilities.iter().all(|cap| a.has_capability(cap))
                     || a.role == SwarmRole::Generalist
             })
             .map(|a| a.id.clone())
@@ -343,12 +462,12 @@ impl Swarm {
                     1.0
                 } else {
                     let matched = task.required_capabilities.iter()
-                        .filter(|cap| a.capabilities.contains(cap))
+                        .filter(|cap| a.has_capability(cap))
                         .count();
                     matched as f64 / task.required_capabilities.len() as f64
                 };
                 let perf_score = a.performance_score;
-                let load_score = 1.0 - (a.current_tasks as f64 / a.max_concurrent as f64);
+                let load_score = 1.0 - a.load();
 
                 let total = skill_score * 0.4 + perf_score * 0.3 + load_score * 0.3;
                 (a.id.clone(), total)
@@ -371,14 +490,28 @@ impl Swarm {
         Ok(available[0].id.clone())
     }
 
-    /// Send a message between agents.
-    pub fn send_message(&mut self, message: SwarmMessage) {
+    fn mailbox(&self) -> Result<&MailboxStore, SwarmError> {
+        self.mailbox.as_deref().ok_or(SwarmError::NoMailbox)
+    }
+
+    /// Send a message between agents through the mailbox.
+    pub async fn send_message(&mut self, message: SwarmMessage) -> Result<(), SwarmError> {
+        self.mailbox()?.send(&message).await.map_err(|e| SwarmError::Mailbox(e.to_string()))?;
         self.message_log.push(message);
+        Ok(())
     }
 
-    /// Get messages for a specific agent.
-    pub fn messages_for(&self, agent_id: &str) -> Vec<&SwarmMessage> {
-        self.message_log.iter().filter(|m| m.to == agent_id).collect()
+    /// Take up to `limit` messages for an agent. Each stays hidden until
+    /// [`Swarm::ack_message`] is called with its receipt, and is delivered again
+    /// if it is not acknowledged in time.
+    pub async fn receive_messages(&self, agent_id: &str, limit: usize) -> Result<Vec<Delivery>, SwarmError> {
+        self.mailbox()?.receive(agent_id, limit).await.map_err(|e| SwarmError::Mailbox(e.to_string()))
+    }
+
+    /// Acknowledge a handled delivery. Returns false if it was already
+    /// acknowledged or dead-lettered.
+    pub async fn ack_message(&self, agent_id: &str, receipt: &str) -> Result<bool, SwarmError> {
+        self.mailbox()?.ack(agent_id, receipt).await.map_err(|e| SwarmError::Mailbox(e.to_string()))
     }
 
     /// Get swarm-wide summary.
@@ -573,6 +706,8 @@ pub enum SwarmError {
     NoMatchingAgent { capabilities: Vec<String> },
     AgentNotFound(AgentId),
     TaskFailed { task_id: String, error: String },
+    NoMailbox,
+    Mailbox(String),
 }
 
 impl fmt::Display for SwarmError {
@@ -583,6 +718,8 @@ impl fmt::Display for SwarmError {
             SwarmError::NoMatchingAgent { capabilities } => write!(f, "No agent matching: {:?}", capabilities),
             SwarmError::AgentNotFound(id) => write!(f, "Agent not found: {}", id),
             SwarmError::TaskFailed { task_id, error } => write!(f, "Task {} failed: {}", task_id, error),
+            SwarmError::NoMailbox => write!(f, "Swarm has no mailbox to deliver messages"),
+            SwarmError::Mailbox(e) => write!(f, "Mailbox error: {}", e),
         }
     }
 }
@@ -735,21 +872,28 @@ mod tests {
         assert!((agent.success_rate() - 0.8).abs() < 0.01);
     }
 
-    #[test]
-    fn test_inter_agent_messaging() {
-        let mut swarm = test_swarm();
-
-        swarm.send_message(SwarmMessage {
+    #[tokio::test]
+    async fn test_inter_agent_messaging() {
+        let message = SwarmMessage {
             from: "coder-1".into(),
             to: "reviewer-1".into(),
             message_type: MessageType::TaskHandoff,
             content: "Code ready for review".into(),
             timestamp: chrono::Utc::now(),
-        });
-
-        let messages = swarm.messages_for("reviewer-1");
-        assert_eq!(messages.len(), 1);
-        assert_eq!(messages[0].from, "coder-1");
+        };
+        let mut swarm = test_swarm();
+        assert!(matches!(swarm.send_message(message.clone()).await, Err(SwarmError::NoMailbox)));
+
+        let store = Arc::new(MailboxStore::in_memory().await.unwrap());
+        let mut swarm = test_swarm().with_mailbox(store);
+        swarm.send_message(message).await.unwrap();
+
+        let deliveries = swarm.receive_messages("reviewer-1", 10).await.unwrap();
+        assert_eq!(deliveries.len(), 1);
+        assert_eq!(deliveries[0].message.from, "coder-1");
+        assert!(swarm.receive_messages("reviewer-1", 10).await.unwrap().is_empty());
+        assert!(swarm.ack_message("reviewer-1", &deliveries[0].receipt).await.unwrap());
+        assert_eq!(swarm.summary().messages_sent, 1);
     }
 
     #[test]
@@ -836,6 +980,45 @@ mod tests {
         assert_eq!(deserialized.agent_count(), swarm.agent_count());
     }
 
+    #[test]
+    fn test_find_agents_by_advertised_capability() {
+        let mut swarm = test_swarm();
+        swarm.add_agent(
+            SwarmAgent::new("coder-2", "Dana", SwarmRole::Coder)
+                .with_advertisement(CapabilityRecord {
+                    roles: vec![SwarmRole::Tester],
+                    skills: vec!["rust".into()],
+                    cores: vec![ExecutionMode::Structured],
+                    cost_tier: CostTier::Low,
+                })
+        ).unwrap();
+
+        let ids = |agents: Vec<&SwarmAgent>| agents.iter().map(|a| a.id.clone()).collect::<Vec<_>>();
+        assert_eq!(ids(swarm.find_agents("rust", 1.0, 0.0)), vec!["coder-2", "coder-1", "tester-1"]);
+        assert_eq!(ids(swarm.find_agents("structured", 1.0, 0.0)), vec!["coder-2"]);
+        assert_eq!(ids(swarm.find_agents("tester", 1.0, 0.0)), vec!["coder-2", "tester-1"]);
+
+        swarm.heartbeat("coder-2", AgentHeartbeat { load: 0.9, advertisement: None }).unwrap();
+        swarm.get_agent_mut("tester-1").unwrap().tasks_failed = 3;
+        assert_eq!(ids(swarm.find_agents("rust", 0.5, 0.5)), vec!["coder-1"]);
+    }
+
+    #[test]
+    fn test_heartbeat_updates_advertisement() {
+        let mut swarm = test_swarm();
+        let record = CapabilityRecord { skills: vec!["docs".into()], ..Default::default() };
+        swarm.heartbeat("reviewer-1", AgentHeartbeat { load: 0.2, advertisement: Some(record) }).unwrap();
+
+        let agent = swarm.get_agent("reviewer-1").unwrap();
+        assert!(agent.has_capability("docs"));
+        assert!((agent.load() - 0.2).abs() < f64::EPSILON);
+        assert!(agent.last_heartbeat.is_some());
+        assert!(matches!(
+            swarm.heartbeat("missing", AgentHeartbeat { load: 0.0, advertisement: None }),
+            Err(SwarmError::AgentNotFound(_))
+        ));
+    }
+
     #[test]
     fn test_swarm_roles() {
         let roles = vec![
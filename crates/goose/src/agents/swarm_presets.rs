@@ -0,0 +1,557 @@
+//! Named swarm team presets.
+//!
+//! A preset is a reusable team composition: its roles, each with a system
+//! prompt and optionally a model, how the roles pass work to each other and
+//! when the team stops. Presets are YAML files kept like recipes, in a
+//! `teams` directory next to the recipe library: the global one under the
+//! config directory and `.goose/teams` in the working directory, which wins
+//! when both define a name. Two built-in presets ("backend-review" and
+//! "feature-pipeline") are always available and can be overridden by a file
+//! of the same name.
+//!
+//! `/swarm team <name> <task>` instantiates a preset as a [`Swarm`] and hands
+//! the task to the agent with a kickoff prompt that runs each role as a
+//! subagent. Runs and their outcomes are counted per preset in
+//! [`TeamExperience`].
+//!
+//! ```yaml
+//! name: backend-review
+//! description: Review a backend change for design, security and tests
+//! topology: hub
+//! roles:
+//!   - name: lead
+//!     role: coordinator
+//!     prompt: Split the review and merge the findings.
+//!   - name: security
+//!     role: security_analyst
+//!     prompt: Look for injection, authz and secret handling problems.
+//!     model: gpt-4o
+//! termination:
+//!   max_rounds: 3
+//! ```
+
+use super::swarm::{RoutingStrategy, Swarm, SwarmAgent, SwarmConfig, SwarmRole};
+use crate::config::paths::Paths;
+use anyhow::{anyhow, bail, Result};
+use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
+use std::path::{Path, PathBuf};
+use utoipa::ToSchema;
+
+pub const TEAM_EXPERIENCE_FILE: &str = "team_experience.json";
+const PRESET_EXTENSIONS: &[&str] = &["yaml", "yml"];
+
+/// How work and messages move between the roles of a team.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum TeamTopology {
+    /// Each role hands its output to the next, in order
+    #[default]
+    Pipeline,
+    /// The first role splits the task, the others report back to it
+    Hub,
+    /// Every role sees every other role's output
+    Mesh,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, ToSchema)]
+pub struct PresetRole {
+    /// Unique within the preset
+    pub name: String,
+    pub role: SwarmRole,
+    /// System prompt for the role's agent
+    pub prompt: String,
+    /// Model for the role; the session's model when unset
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub model: Option<String>,
+}
+
+/// When a team stops working on a task.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize, ToSchema)]
+pub struct Termination {
+    /// Turns each role's agent may take
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_rounds: Option<u32>,
+    /// Spend at which the team stops, in USD
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub budget_usd: Option<f64>,
+    /// Condition, in plain words, under which the team is done early
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub stop_when: Option<String>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, ToSchema)]
+pub struct TeamPreset {
+    pub name: String,
+    #[serde(default)]
+    pub description: String,
+    pub roles: Vec<PresetRole>,
+    #[serde(default)]
+    pub topology: TeamTopology,
+    #[serde(default)]
+    pub termination: Termination,
+}
+
+impl TeamPreset {
+    pub fn is_builtin(name: &str) -> bool {
+        builtin_presets().iter().any(|p| p.name == name)
+    }
+
+    pub fn validate(&self) -> Result<()> {
+        if self.name.is_empty()
+            || !self
+                .name
+                .chars()
+                .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_')
+        {
+            bail!("Preset name must be letters, digits, '-' or '_'");
+        }
+        if self.roles.is_empty() {
+            bail!("Preset {} has no roles", self.name);
+        }
+        let mut seen = HashSet::new();
+        for role in &self.roles {
+            if !seen.insert(role.name.as_str()) {
+                bail!("Preset {} has two roles named {}", self.name, role.name);
+            }
+        }
+        if self
+            .termination
+            .budget_usd
+            .is_some_and(|b| !b.is_finite() || b < 0.0)
+        {
+            bail!("Budget must be a positive amount");
+        }
+        Ok(())
+    }
+
+    /// A swarm with one agent per role.
+    pub fn instantiate(&self) -> Swarm {
+        let config = SwarmConfig {
+            max_agents: self.roles.len(),
+            routing: RoutingStrategy::SkillBased,
+            inter_agent_communication: self.topology != TeamTopology::Pipeline,
+            ..SwarmConfig::default()
+        };
+        let mut swarm = Swarm::new(self.name.clone(), config);
+        for role in &self.roles {
+            let agent = SwarmAgent::new(
+                format!("{}-{}", self.name, role.name),
+                role.name.clone(),
+                role.role,
+            )
+            .with_capabilities(vec![role.role.to_string()]);
+            // Role names are unique and the swarm is sized to fit them all
+            let _ = swarm.add_agent(agent);
+        }
+        swarm
+    }
+
+    /// The prompt that has the agent run `task` with this team, one
+    /// subagent per role.
+    pub fn kickoff_prompt(&self, task: &str) -> String {
+        let flow = match self.topology {
+            TeamTopology::Pipeline => {
+                "Run the roles one after another, in the order listed, giving each the task and \
+                 the previous role's summary."
+            }
+            TeamTopology::Hub => {
+                "Run the first role to split the task, then run the other roles on their parts \
+                 and give their summaries back to the first role to merge."
+            }
+            TeamTopology::Mesh => {
+                "Run every role on the task, then run each role again with the other roles' \
+                 summaries until they agree or the rounds run out."
+            }
+        };
+        let mut prompt = format!(
+            "Work on the task below as the \"{}\" team. Delegate each role to a subagent with \
+             the subagent tool, passing the role's prompt as its instructions{}. {}\n\n",
+            self.name,
+            match self.termination.max_rounds {
+                Some(rounds) => format!(" and max_turns {} in its settings", rounds),
+                None => String::new(),
+            },
+            flow
+        );
+        for (index, role) in self.roles.iter().enumerate() {
+            prompt.push_str(&format!("{}. **{}** ({})", index + 1, role.name, role.role));
+            if let Some(model) = &role.model {
+                prompt.push_str(&format!(", model {}", model));
+            }
+            prompt.push_str(&format!(": {}\n", role.prompt.trim()));
+        }
+        if let Some(budget) = self.termination.budget_usd {
+            prompt.push_str(&format!(
+                "\nStop delegating once the team has spent ${:.2}.",
+                budget
+            ));
+        }
+        if let Some(condition) = &self.termination.stop_when {
+            prompt.push_str(&format!("\nStop early when {}.", condition.trim()));
+        }
+        prompt.push_str(&format!(
+            "\n\nFinish with the team's combined result.\n\n## Task\n\n{}",
+            task
+        ));
+        prompt
+    }
+}
+
+pub fn builtin_presets() -> Vec<TeamPreset> {
+    let role = |name: &str, role: SwarmRole, prompt: &str| PresetRole {
+        name: name.to_string(),
+        role,
+        prompt: prompt.to_string(),
+        model: None,
+    };
+    vec![
+        TeamPreset {
+            name: "backend-review".to_string(),
+            description: "Review a backend change for design, security and test coverage"
+                .to_string(),
+            roles: vec![
+                role(
+                    "lead",
+                    SwarmRole::Coordinator,
+                    "Read the change, split the review between the other roles and merge their \
+                     findings into one prioritized list.",
+                ),
+                role(
+                    "architect",
+                    SwarmRole::Architect,
+                    "Review API shape, data model changes, error handling and coupling.",
+                ),
+                role(
+                    "security",
+                    SwarmRole::SecurityAnalyst,
+                    "Look for injection, authorization gaps, unsafe deserialization and secrets \
+                     in code or logs.",
+                ),
+                role(
+                    "tester",
+                    SwarmRole::Tester,
+                    "Check which behaviour the tests cover, run them, and list untested paths.",
+                ),
+            ],
+            topology: TeamTopology::Hub,
+            termination: Termination {
+                max_rounds: Some(20),
+                ..Termination::default()
+            },
+        },
+        TeamPreset {
+            name: "feature-pipeline".to_string(),
+            description: "Design, build, test and document a feature in sequence".to_string(),
+            roles: vec![
+                role(
+                    "design",
+                    SwarmRole::Architect,
+                    "Write a short design: the files to change, the interfaces and the risks.",
+                ),
+                role(
+                    "build",
+                    SwarmRole::Coder,
+                    "Implement the design with the smallest change that works.",
+                ),
+                role(
+                    "test",
+                    SwarmRole::Tester,
+                    "Add tests for the new behaviour and run the test suite; fix what fails.",
+                ),
+                role(
+                    "docs",
+                    SwarmRole::Documenter,
+                    "Update user-facing docs and the changelog for the feature.",
+                ),
+            ],
+            topology: TeamTopology::Pipeline,
+            termination: Termination {
+                max_rounds: Some(30),
+                stop_when: Some("the tests pass and the docs are updated".to_string()),
+                ..Termination::default()
+            },
+        },
+    ]
+}
+
+/// Preset files in a global and a project `teams` directory.
+#[derive(Debug, Clone)]
+pub struct TeamLibrary {
+    global_dir: PathBuf,
+    project_dir: Option<PathBuf>,
+}
+
+impl TeamLibrary {
+    pub fn new(global_dir: impl Into<PathBuf>, project_dir: Option<PathBuf>) -> Self {
+        Self {
+            global_dir: global_dir.into(),
+            project_dir,
+        }
+    }
+
+    pub fn load_default() -> Self {
+        let project_dir = std::env::current_dir()
+            .ok()
+            .map(|dir| dir.join(".goose/teams"));
+        Self::new(Paths::config_dir().join("teams"), project_dir)
+    }
+
+    fn dirs(&self) -> impl Iterator<Item = &PathBuf> {
+        std::iter::once(&self.global_dir).chain(self.project_dir.as_ref())
+    }
+
+    /// Built-in presets with file overrides applied, then the other presets
+    /// by name.
+    pub fn list(&self) -> Vec<TeamPreset> {
+        let mut stored: Vec<TeamPreset> = Vec::new();
+        for dir in self.dirs() {
+            for preset in read_dir_presets(dir) {
+                stored.retain(|p| p.name != preset.name);
+                stored.push(preset);
+            }
+        }
+        let mut presets: Vec<TeamPreset> = builtin_presets()
+            .into_iter()
+            .map(|builtin| {
+                stored
+                    .iter()
+                    .find(|p| p.name == builtin.name)
+                    .cloned()
+                    .unwrap_or(builtin)
+            })
+            .collect();
+        let mut custom: Vec<TeamPreset> = stored
+            .into_iter()
+            .filter(|p| !TeamPreset::is_builtin(&p.name))
+            .collect();
+        custom.sort_by(|a, b| a.name.cmp(&b.name));
+        presets.extend(custom);
+        presets
+    }
+
+    pub fn get(&self, name: &str) -> Option<TeamPreset> {
+        self.list().into_iter().find(|p| p.name == name)
+    }
+
+    /// Writes the preset to the global directory. Saving a built-in name
+    /// overrides it.
+    pub fn save(&self, preset: &TeamPreset) -> Result<PathBuf> {
+        preset.validate()?;
+        std::fs::create_dir_all(&self.global_dir)?;
+        let path = self.global_dir.join(format!("{}.yaml", preset.name));
+        std::fs::write(&path, serde_yaml::to_string(preset)?)?;
+        Ok(path)
+    }
+
+    /// Removes the preset's files, resetting a built-in preset to its
+    /// defaults. Returns false if no file held the preset.
+    pub fn delete(&self, name: &str) -> Result<bool> {
+        let mut removed = false;
+        for dir in self.dirs() {
+            for ext in PRESET_EXTENSIONS {
+                let path = dir.join(format!("{}.{}", name, ext));
+                if path.is_file() {
+                    std::fs::remove_file(&path)?;
+                    removed = true;
+                }
+            }
+        }
+        Ok(removed)
+    }
+}
+
+fn read_dir_presets(dir: &Path) -> Vec<TeamPreset> {
+    let Ok(entries) = std::fs::read_dir(dir) else {
+        return Vec::new();
+    };
+    let mut paths: Vec<PathBuf> = entries
+        .flatten()
+        .map(|entry| entry.path())
+        .filter(|path| {
+            path.extension()
+                .and_then(|ext| ext.to_str())
+                .is_some_and(|ext| PRESET_EXTENSIONS.contains(&ext))
+        })
+        .collect();
+    paths.sort();
+    paths
+        .iter()
+        .filter_map(|path| match read_preset(path) {
+            Ok(preset) => Some(preset),
+            Err(e) => {
+                tracing::warn!("Skipping team preset {}: {}", path.display(), e);
+                None
+            }
+        })
+        .collect()
+}
+
+fn read_preset(path: &Path) -> Result<TeamPreset> {
+    let preset: TeamPreset = serde_yaml::from_str(&std::fs::read_to_string(path)?)?;
+    preset.validate()?;
+    Ok(preset)
+}
+
+/// Runs and outcomes of one preset.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize, ToSchema)]
+pub struct PresetStats {
+    pub runs: u64,
+    pub successes: u64,
+    pub failures: u64,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+struct PresetEntry {
+    preset: String,
+    #[serde(flatten)]
+    stats: PresetStats,
+}
+
+/// Team runs and outcomes per preset, kept in the state directory.
+#[derive(Debug, Clone, Default)]
+pub struct TeamExperience {
+    path: PathBuf,
+    entries: Vec<PresetEntry>,
+}
+
+impl TeamExperience {
+    pub fn load(path: impl Into<PathBuf>) -> Self {
+        let path = path.into();
+        let entries = std::fs::read_to_string(&path)
+            .ok()
+            .and_then(|json| serde_json::from_str(&json).ok())
+            .unwrap_or_default();
+        Self { path, entries }
+    }
+
+    pub fn load_default() -> Self {
+        Self::load(Paths::in_state_dir(TEAM_EXPERIENCE_FILE))
+    }
+
+    fn entry(&mut self, preset: &str) -> &mut PresetStats {
+        let index = match self.entries.iter().position(|e| e.preset == preset) {
+            Some(index) => index,
+            None => {
+                self.entries.push(PresetEntry {
+                    preset: preset.to_string(),
+                    stats: PresetStats::default(),
+                });
+                self.entries.len() - 1
+            }
+        };
+        &mut self.entries[index].stats
+    }
+
+    pub fn record_run(&mut self, preset: &str) {
+        self.entry(preset).runs += 1;
+    }
+
+    pub fn record_outcome(&mut self, preset: &str, passed: bool) {
+        let stats = self.entry(preset);
+        if passed {
+            stats.successes += 1;
+        } else {
+            stats.failures += 1;
+        }
+    }
+
+    pub fn stats(&self, preset: &str) -> PresetStats {
+        self.entries
+            .iter()
+            .find(|e| e.preset == preset)
+            .map(|e| e.stats.clone())
+            .unwrap_or_default()
+    }
+
+    pub fn save(&self) -> Result<()> {
+        if let Some(parent) = self.path.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        std::fs::write(&self.path, serde_json::to_string_pretty(&self.entries)?)?;
+        Ok(())
+    }
+}
+
+/// Records a finished run of `preset`, as reported by the user or a client.
+pub fn record_outcome(preset: &str, passed: bool) -> Result<PresetStats> {
+    if TeamLibrary::load_default().get(preset).is_none() {
+        return Err(anyhow!("No team preset named {}", preset));
+    }
+    let mut experience = TeamExperience::load_default();
+    experience.record_outcome(preset, passed);
+    experience.save()?;
+    Ok(experience.stats(preset))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_library_overrides_and_instantiates() {
+        let global = tempfile::tempdir().unwrap();
+        let project = tempfile::tempdir().unwrap();
+        let library = TeamLibrary::new(global.path(), Some(project.path().to_path_buf()));
+
+        let mut review = library.get("backend-review").unwrap();
+        review.topology = TeamTopology::Mesh;
+        library.save(&review).unwrap();
+        std::fs::write(
+            project.path().join("docs.yml"),
+            "name: docs\nroles:\n  - name: writer\n    role: documenter\n    prompt: Write docs\n    model: small-model\n",
+        )
+        .unwrap();
+        std::fs::write(
+            project.path().join("broken.yaml"),
+            "name: broken\nroles: []\n",
+        )
+        .unwrap();
+
+        let names: Vec<String> = library.list().into_iter().map(|p| p.name).collect();
+        assert_eq!(names, vec!["backend-review", "feature-pipeline", "docs"]);
+        assert_eq!(
+            library.get("backend-review").unwrap().topology,
+            TeamTopology::Mesh
+        );
+
+        let docs = library.get("docs").unwrap();
+        assert_eq!(docs.topology, TeamTopology::Pipeline);
+        let swarm = docs.instantiate();
+        assert_eq!(swarm.agent_count(), 1);
+        assert!(swarm.get_agent("docs-writer").is_some());
+        assert!(docs
+            .kickoff_prompt("Document the API")
+            .contains("1. **writer** (documenter), model small-model: Write docs"));
+
+        assert!(library.delete("backend-review").unwrap());
+        assert_eq!(
+            library.get("backend-review").unwrap().topology,
+            TeamTopology::Hub
+        );
+        assert!(!library.delete("feature-pipeline").unwrap());
+    }
+
+    #[test]
+    fn test_experience_counts_runs_and_outcomes() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join(TEAM_EXPERIENCE_FILE);
+        let mut experience = TeamExperience::load(&path);
+        experience.record_run("backend-review");
+        experience.record_run("backend-review");
+        experience.record_outcome("backend-review", true);
+        experience.record_outcome("backend-review", false);
+        experience.save().unwrap();
+
+        let loaded = TeamExperience::load(&path);
+        assert_eq!(
+            loaded.stats("backend-review"),
+            PresetStats {
+                runs: 2,
+                successes: 1,
+                failures: 1
+            }
+        );
+        assert_eq!(loaded.stats("docs"), PresetStats::default());
+    }
+}
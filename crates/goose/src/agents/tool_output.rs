@@ -0,0 +1,91 @@
+//! Streamed output of long-running tools.
+//!
+//! Extensions stream a running command's stdout and stderr as logging
+//! notifications whose data is `{"type": "shell_output", "stream": ...,
+//! "output": ...}`; the developer shell does, and any other extension can do
+//! the same to have its output shown as it arrives. When output comes in
+//! faster than the client reads it, chunks that are already waiting are
+//! merged, so a slow client gets fewer, larger updates rather than a backlog.
+
+use rmcp::model::ServerNotification;
+use serde_json::Value;
+
+pub const TOOL_OUTPUT_TYPE: &str = "shell_output";
+/// Largest chunk that waiting output is merged into
+const MAX_MERGED_BYTES: usize = 16 * 1024;
+
+/// The stream and text of a tool output notification.
+pub fn output_chunk(notification: &ServerNotification) -> Option<(&str, &str)> {
+    let ServerNotification::LoggingMessageNotification(message) = notification else {
+        return None;
+    };
+    let data = message.params.data.as_object()?;
+    if data.get("type").and_then(Value::as_str) != Some(TOOL_OUTPUT_TYPE) {
+        return None;
+    }
+    let stream = data
+        .get("stream")
+        .and_then(Value::as_str)
+        .unwrap_or("stdout");
+    let output = data.get("output").and_then(Value::as_str)?;
+    Some((stream, output))
+}
+
+/// Appends `next` to `chunk` when both are output of the same stream and the
+/// result stays small enough. Returns whether `next` was merged.
+pub fn merge(chunk: &mut ServerNotification, next: &ServerNotification) -> bool {
+    let Some((next_stream, next_output)) = output_chunk(next) else {
+        return false;
+    };
+    match output_chunk(chunk) {
+        Some((stream, output))
+            if stream == next_stream && output.len() + next_output.len() < MAX_MERGED_BYTES => {}
+        _ => return false,
+    }
+    let ServerNotification::LoggingMessageNotification(message) = chunk else {
+        return false;
+    };
+    if let Some(Value::String(output)) = message.params.data.get_mut("output") {
+        output.push('\n');
+        output.push_str(next_output);
+    }
+    true
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rmcp::model::{
+        LoggingLevel, LoggingMessageNotification, LoggingMessageNotificationMethod,
+        LoggingMessageNotificationParam,
+    };
+    use serde_json::json;
+
+    fn output(stream: &str, text: &str) -> ServerNotification {
+        ServerNotification::LoggingMessageNotification(LoggingMessageNotification {
+            params: LoggingMessageNotificationParam {
+                level: LoggingLevel::Info,
+                logger: Some("shell_tool".to_string()),
+                data: json!({"type": TOOL_OUTPUT_TYPE, "stream": stream, "output": text}),
+            },
+            method: LoggingMessageNotificationMethod,
+            extensions: Default::default(),
+        })
+    }
+
+    #[test]
+    fn test_merges_waiting_output_of_the_same_stream() {
+        let mut chunk = output("stdout", "Compiling a");
+        assert!(merge(&mut chunk, &output("stdout", "Compiling b")));
+        assert_eq!(
+            output_chunk(&chunk),
+            Some(("stdout", "Compiling a\nCompiling b"))
+        );
+
+        assert!(!merge(&mut chunk, &output("stderr", "warning: unused")));
+        assert!(!merge(
+            &mut chunk,
+            &output("stdout", &"x".repeat(MAX_MERGED_BYTES))
+        ));
+    }
+}
@@ -0,0 +1,217 @@
+//! Screen understanding for `platform__inspect_screen`.
+//!
+//! A screenshot of a display or window is sent to a vision-capable model
+//! with an extraction prompt asking for the visible text, the UI elements
+//! and any error dialogs as JSON. The tool returns the image itself next to
+//! that structured description, so the agent can reason about what the
+//! user is looking at.
+
+use crate::config::Config;
+use crate::conversation::message::Message;
+use crate::model::ModelConfig;
+use crate::providers::base::Provider;
+use crate::providers::canonical::{maybe_get_canonical_model, Modality};
+use anyhow::{anyhow, Context, Result};
+use base64::Engine;
+use serde::{Deserialize, Serialize};
+use std::io::Cursor;
+use std::sync::Arc;
+use xcap::image::{imageops, DynamicImage, ImageFormat};
+use xcap::{Monitor, Window};
+
+/// Provider used for screen understanding when the session model cannot see images
+pub const VISION_PROVIDER_KEY: &str = "GOOSE_VISION_PROVIDER";
+pub const VISION_MODEL_KEY: &str = "GOOSE_VISION_MODEL";
+
+const MAX_DIMENSION: u32 = 1568;
+
+const EXTRACTION_PROMPT: &str = r#"Describe this screenshot for an assistant that cannot see it.
+Reply with a single JSON object and nothing else, using these fields:
+{
+  "summary": "one or two sentences on what is on screen",
+  "visible_text": ["each distinct piece of readable text"],
+  "ui_elements": [{"kind": "button|input|menu|tab|dialog|link|other", "label": "text on it"}],
+  "error_dialogs": [{"title": "dialog title", "message": "full error text"}],
+  "answer": "answer to the question below, or null"
+}"#;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum CaptureTarget {
+    Display(usize),
+    Window(String),
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct UiElement {
+    pub kind: String,
+    #[serde(default)]
+    pub label: String,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct ErrorDialog {
+    #[serde(default)]
+    pub title: String,
+    pub message: String,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct ScreenDescription {
+    pub summary: String,
+    #[serde(default)]
+    pub visible_text: Vec<String>,
+    #[serde(default)]
+    pub ui_elements: Vec<UiElement>,
+    #[serde(default)]
+    pub error_dialogs: Vec<ErrorDialog>,
+    #[serde(default)]
+    pub answer: Option<String>,
+}
+
+impl ScreenDescription {
+    /// Reads the model reply, tolerating code fences or prose around the
+    /// JSON. A reply without usable JSON becomes the summary.
+    pub fn parse(reply: &str) -> Self {
+        let json = match (reply.find('{'), reply.rfind('}')) {
+            (Some(start), Some(end)) if start < end => &reply[start..=end],
+            _ => "",
+        };
+        serde_json::from_str(json).unwrap_or_else(|_| Self {
+            summary: reply.trim().to_string(),
+            ..Default::default()
+        })
+    }
+}
+
+/// Base64-encoded PNG of the target, scaled down to fit vision model limits
+pub fn capture_screen(target: &CaptureTarget) -> Result<(String, &'static str)> {
+    let image = match target {
+        CaptureTarget::Window(title) => Window::all()
+            .map_err(|e| anyhow!("Failed to list windows: {}", e))?
+            .into_iter()
+            .find(|w| w.title().is_ok_and(|t| &t == title))
+            .ok_or_else(|| anyhow!("No window found with title '{}'", title))?
+            .capture_image()
+            .map_err(|e| anyhow!("Failed to capture window '{}': {}", title, e))?,
+        CaptureTarget::Display(display) => {
+            let monitors =
+                Monitor::all().map_err(|e| anyhow!("Failed to access monitors: {}", e))?;
+            monitors
+                .get(*display)
+                .ok_or_else(|| {
+                    anyhow!(
+                        "{} was not an available monitor, {} found.",
+                        display,
+                        monitors.len()
+                    )
+                })?
+                .capture_image()
+                .map_err(|e| anyhow!("Failed to capture display {}: {}", display, e))?
+        }
+    };
+
+    let mut image = DynamicImage::ImageRgba8(image);
+    let (width, height) = (image.width(), image.height());
+    if width > MAX_DIMENSION || height > MAX_DIMENSION {
+        let scale = MAX_DIMENSION as f32 / width.max(height) as f32;
+        image = DynamicImage::ImageRgba8(imageops::resize(
+            &image,
+            ((width as f32 * scale) as u32).max(1),
+            ((height as f32 * scale) as u32).max(1),
+            imageops::FilterType::Lanczos3,
+        ));
+    }
+    let mut bytes = Vec::new();
+    image
+        .write_to(&mut Cursor::new(&mut bytes), ImageFormat::Png)
+        .context("Failed to encode screenshot")?;
+    Ok((
+        base64::engine::general_purpose::STANDARD.encode(bytes),
+        "image/png",
+    ))
+}
+
+/// Whether the model is known to accept image input
+pub fn supports_images(provider: &dyn Provider) -> bool {
+    maybe_get_canonical_model(provider.get_name(), &provider.get_model_config().model_name)
+        .is_some_and(|model| model.modalities.input.contains(&Modality::Image))
+}
+
+/// The configured vision model, or `current` when it can see images itself
+pub async fn vision_provider(current: Arc<dyn Provider>) -> Result<Arc<dyn Provider>> {
+    let config = Config::global();
+    if let Ok(model) = config.get_param::<String>(VISION_MODEL_KEY) {
+        let provider_name = config
+            .get_param::<String>(VISION_PROVIDER_KEY)
+            .unwrap_or_else(|_| current.get_name().to_string());
+        let model_config = ModelConfig::new(&model)
+            .map_err(|e| anyhow!("Invalid vision model {}: {}", model, e))?;
+        return crate::providers::create(&provider_name, model_config)
+            .await
+            .map_err(|e| anyhow!("Could not create vision provider: {}", e));
+    }
+    if supports_images(current.as_ref()) {
+        return Ok(current);
+    }
+    Err(anyhow!(
+        "The current model cannot view images. Set {} (and optionally {}) to a vision-capable model.",
+        VISION_MODEL_KEY,
+        VISION_PROVIDER_KEY
+    ))
+}
+
+pub async fn describe_screenshot(
+    provider: &dyn Provider,
+    session_id: &str,
+    data: &str,
+    mime_type: &str,
+    question: Option<&str>,
+) -> Result<ScreenDescription> {
+    let prompt = format!(
+        "{}\n\nQuestion: {}",
+        EXTRACTION_PROMPT,
+        question.unwrap_or("none")
+    );
+    let message = Message::user()
+        .with_text(prompt)
+        .with_image(data, mime_type);
+    let (reply, _) = provider
+        .complete(
+            session_id,
+            "You extract information from screenshots.",
+            &[message],
+            &[],
+        )
+        .await?;
+    Ok(ScreenDescription::parse(&reply.as_concat_text()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_description() {
+        let reply = r#"Here you go:
+```json
+{
+  "summary": "An installer showing an error",
+  "visible_text": ["Setup", "Disk full"],
+  "ui_elements": [{"kind": "button", "label": "Retry"}],
+  "error_dialogs": [{"title": "Setup", "message": "Disk full"}],
+  "answer": "There is not enough disk space"
+}
+```"#;
+        let description = ScreenDescription::parse(reply);
+        assert_eq!(description.error_dialogs[0].message, "Disk full");
+        assert_eq!(description.ui_elements[0].label, "Retry");
+        assert_eq!(
+            description.answer.as_deref(),
+            Some("There is not enough disk space")
+        );
+
+        let description = ScreenDescription::parse("Just a desktop with a browser open.");
+        assert_eq!(description.summary, "Just a desktop with a browser open.");
+        assert!(description.visible_text.is_empty());
+    }
+}
@@ -0,0 +1,225 @@
+//! Whether a mailbox message should wake the agent it is addressed to.
+//!
+//! Waking an agent starts a turn, which costs tokens, so the policy only
+//! wakes for some message types and limits how often one recipient is woken.
+//! Agents are not woken automatically yet: with `GOOSE_WAKE_POLICY_MODE` set
+//! to `simulate`, the mailbox store evaluates every message it receives and
+//! records what the policy would have done, with the reason and an estimate
+//! of the cost, so the policy can be tuned before anything is woken for real.
+//!
+//! Policy settings:
+//! - `GOOSE_WAKE_ON`: message types that wake (default question, task_handoff, alert)
+//! - `GOOSE_WAKE_COOLDOWN`: seconds between wakes of one recipient (default 300)
+//! - `GOOSE_WAKE_MAX_PER_HOUR`: wakes of one recipient per hour (default 6)
+
+use super::swarm::{MessageType, SwarmMessage};
+use crate::config::Config;
+use crate::observability::{CostTracker, ModelPricing};
+use chrono::{DateTime, Duration, Utc};
+use serde::{Deserialize, Serialize};
+
+pub const WAKE_POLICY_MODE_KEY: &str = "GOOSE_WAKE_POLICY_MODE";
+const DEFAULT_COOLDOWN_SECS: i64 = 300;
+const DEFAULT_MAX_WAKES_PER_HOUR: usize = 6;
+/// Prompt tokens a woken turn spends before reading the message: system
+/// prompt, tool definitions and recent history
+const WAKE_BASE_INPUT_TOKENS: u64 = 6_000;
+const WAKE_OUTPUT_TOKENS: u64 = 500;
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum WakeMode {
+    #[default]
+    Off,
+    /// Record would-wake decisions without waking anyone
+    Simulate,
+}
+
+pub fn mode() -> WakeMode {
+    Config::global()
+        .get_param(WAKE_POLICY_MODE_KEY)
+        .unwrap_or_default()
+}
+
+/// Which policy rule decided
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum WakeRule {
+    Wakes,
+    MessageType,
+    Cooldown,
+    HourlyLimit,
+}
+
+impl WakeRule {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Wakes => "wakes",
+            Self::MessageType => "message_type",
+            Self::Cooldown => "cooldown",
+            Self::HourlyLimit => "hourly_limit",
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct WakeDecision {
+    pub wake: bool,
+    pub rule: WakeRule,
+    pub reason: String,
+    /// Estimated cost of the turn the wake would start; zero when the
+    /// policy does not wake
+    pub estimated_cost_usd: f64,
+}
+
+#[derive(Debug, Clone)]
+pub struct WakePolicy {
+    pub wake_on: Vec<MessageType>,
+    pub cooldown: Duration,
+    pub max_wakes_per_hour: usize,
+    pub pricing: ModelPricing,
+}
+
+impl Default for WakePolicy {
+    fn default() -> Self {
+        Self {
+            wake_on: vec![
+                MessageType::Question,
+                MessageType::TaskHandoff,
+                MessageType::Alert,
+            ],
+            cooldown: Duration::seconds(DEFAULT_COOLDOWN_SECS),
+            max_wakes_per_hour: DEFAULT_MAX_WAKES_PER_HOUR,
+            pricing: ModelPricing::default(),
+        }
+    }
+}
+
+impl WakePolicy {
+    /// The configured policy, priced for the configured model.
+    pub async fn from_config() -> Self {
+        let config = Config::global();
+        let defaults = Self::default();
+        let pricing = match config.get_goose_model() {
+            Ok(model) => CostTracker::new().get_pricing(&model).await,
+            Err(_) => defaults.pricing,
+        };
+        Self {
+            wake_on: config
+                .get_param("GOOSE_WAKE_ON")
+                .unwrap_or(defaults.wake_on),
+            cooldown: config
+                .get_param::<i64>("GOOSE_WAKE_COOLDOWN")
+                .map(Duration::seconds)
+                .unwrap_or(defaults.cooldown),
+            max_wakes_per_hour: config
+                .get_param("GOOSE_WAKE_MAX_PER_HOUR")
+                .unwrap_or(defaults.max_wakes_per_hour),
+            pricing,
+        }
+    }
+
+    fn estimated_cost(&self, message: &SwarmMessage) -> f64 {
+        let input_tokens = WAKE_BASE_INPUT_TOKENS + message.content.len() as u64 / 4;
+        input_tokens as f64 / 1000.0 * self.pricing.input_per_1k
+            + WAKE_OUTPUT_TOKENS as f64 / 1000.0 * self.pricing.output_per_1k
+    }
+
+    /// Decides for `message`, given when its recipient was last woken, most
+    /// recent first.
+    pub fn evaluate(
+        &self,
+        message: &SwarmMessage,
+        recent_wakes: &[DateTime<Utc>],
+        now: DateTime<Utc>,
+    ) -> WakeDecision {
+        let skip = |rule, reason| WakeDecision {
+            wake: false,
+            rule,
+            reason,
+            estimated_cost_usd: 0.0,
+        };
+        let message_type = serde_json::to_value(message.message_type)
+            .ok()
+            .and_then(|v| v.as_str().map(String::from))
+            .unwrap_or_default();
+
+        if !self.wake_on.contains(&message.message_type) {
+            return skip(
+                WakeRule::MessageType,
+                format!("{} messages do not wake agents", message_type),
+            );
+        }
+        if let Some(last) = recent_wakes.first().filter(|t| now - **t < self.cooldown) {
+            return skip(
+                WakeRule::Cooldown,
+                format!(
+                    "{} was woken {}s ago; cooldown is {}s",
+                    message.to,
+                    (now - *last).num_seconds(),
+                    self.cooldown.num_seconds()
+                ),
+            );
+        }
+        let last_hour = recent_wakes
+            .iter()
+            .filter(|t| now - **t < Duration::hours(1))
+            .count();
+        if last_hour >= self.max_wakes_per_hour {
+            return skip(
+                WakeRule::HourlyLimit,
+                format!(
+                    "{} was already woken {} times in the last hour",
+                    message.to, last_hour
+                ),
+            );
+        }
+        WakeDecision {
+            wake: true,
+            rule: WakeRule::Wakes,
+            reason: format!("{} message from {}", message_type, message.from),
+            estimated_cost_usd: self.estimated_cost(message),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn message(message_type: MessageType) -> SwarmMessage {
+        SwarmMessage {
+            from: "coordinator".into(),
+            to: "coder".into(),
+            message_type,
+            content: "x".repeat(4000),
+            timestamp: Utc::now(),
+        }
+    }
+
+    #[test]
+    fn test_policy_rules() {
+        let policy = WakePolicy {
+            pricing: ModelPricing::new(0.003, 0.015),
+            ..WakePolicy::default()
+        };
+        let now = Utc::now();
+
+        let decision = policy.evaluate(&message(MessageType::Question), &[], now);
+        assert!(decision.wake);
+        assert!((decision.estimated_cost_usd - (7.0 * 0.003 + 0.5 * 0.015)).abs() < 1e-9);
+
+        let status = policy.evaluate(&message(MessageType::StatusUpdate), &[], now);
+        assert_eq!(status.rule, WakeRule::MessageType);
+        assert_eq!(status.reason, "status_update messages do not wake agents");
+
+        let recent = [now - Duration::seconds(60)];
+        let cooling = policy.evaluate(&message(MessageType::Alert), &recent, now);
+        assert_eq!(cooling.rule, WakeRule::Cooldown);
+
+        let busy: Vec<_> = (0..6).map(|i| now - Duration::minutes(6 + i * 9)).collect();
+        let limited = policy.evaluate(&message(MessageType::Alert), &busy, now);
+        assert_eq!(limited.rule, WakeRule::HourlyLimit);
+        assert_eq!(limited.estimated_cost_usd, 0.0);
+    }
+}
@@ -0,0 +1,553 @@
+//! One registry for the files goose produces and keeps: downloaded update
+//! packages, generated docs, test reports, screenshots, exports and session
+//! attachments.
+//!
+//! Content is stored once per SHA-256 under `blobs/`, whoever produced it,
+//! and an index records each artifact's producer, session or task, type and
+//! retention class. Deleting an artifact removes its content once no other
+//! artifact refers to it. [`ArtifactRegistry::gc`] drops what its retention
+//! class no longer keeps and runs with the data retention job:
+//! - `ephemeral`: a day
+//! - `standard`: `GOOSE_ARTIFACT_RETENTION_DAYS` (30 when unset)
+//! - `session`: until its session is deleted
+//! - `pinned`: until it is deleted or unpinned
+
+use crate::config::paths::Paths;
+use crate::config::Config;
+use anyhow::{bail, Context, Result};
+use chrono::{DateTime, Duration, Utc};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use sha2::{Digest, Sha256};
+use std::collections::HashSet;
+use std::fs;
+use std::io::Read;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use utoipa::ToSchema;
+
+pub const ARTIFACTS_FOLDER: &str = "artifacts";
+pub const RETENTION_DAYS_KEY: &str = "GOOSE_ARTIFACT_RETENTION_DAYS";
+pub const DEFAULT_RETENTION_DAYS: i64 = 30;
+
+const BLOBS_FOLDER: &str = "blobs";
+const STAGING_FOLDER: &str = "staging";
+const INDEX_FILE: &str = "index.json";
+const EPHEMERAL_RETENTION: Duration = Duration::days(1);
+
+/// Serializes index updates; every registry in the process shares it
+static INDEX_LOCK: Mutex<()> = Mutex::new(());
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum ArtifactKind {
+    Binary,
+    /// Installable package, such as a shell update
+    Package,
+    Docs,
+    TestReport,
+    Screenshot,
+    Export,
+    Attachment,
+    Other,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum RetentionClass {
+    Ephemeral,
+    #[default]
+    Standard,
+    /// Kept until its session is deleted
+    Session,
+    Pinned,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, ToSchema)]
+pub struct ArtifactRecord {
+    pub id: String,
+    pub name: String,
+    pub kind: ArtifactKind,
+    /// What stored it, such as `attachments` or `shell_update`
+    pub producer: String,
+    pub session_id: Option<String>,
+    pub task_id: Option<String>,
+    pub retention: RetentionClass,
+    pub mime_type: Option<String>,
+    pub sha256: String,
+    pub size: u64,
+    pub created_at: DateTime<Utc>,
+    /// Details only the producer interprets
+    #[serde(default)]
+    #[schema(value_type = Option<Object>)]
+    pub metadata: Option<Value>,
+    /// Location of the content within the registry
+    blob: String,
+}
+
+/// An artifact about to be stored.
+#[derive(Debug, Clone)]
+pub struct NewArtifact {
+    name: String,
+    kind: ArtifactKind,
+    producer: String,
+    session_id: Option<String>,
+    task_id: Option<String>,
+    retention: RetentionClass,
+    mime_type: Option<String>,
+    metadata: Option<Value>,
+    id: Option<String>,
+    created_at: Option<DateTime<Utc>>,
+}
+
+impl NewArtifact {
+    pub fn new(producer: impl Into<String>, kind: ArtifactKind, name: impl Into<String>) -> Self {
+        Self {
+            name: name.into(),
+            kind,
+            producer: producer.into(),
+            session_id: None,
+            task_id: None,
+            retention: RetentionClass::default(),
+            mime_type: None,
+            metadata: None,
+            id: None,
+            created_at: None,
+        }
+    }
+
+    pub fn for_session(mut self, session_id: impl Into<String>) -> Self {
+        self.session_id = Some(session_id.into());
+        self
+    }
+
+    pub fn for_task(mut self, task_id: impl Into<String>) -> Self {
+        self.task_id = Some(task_id.into());
+        self
+    }
+
+    pub fn with_retention(mut self, retention: RetentionClass) -> Self {
+        self.retention = retention;
+        self
+    }
+
+    pub fn with_mime_type(mut self, mime_type: impl Into<String>) -> Self {
+        self.mime_type = Some(mime_type.into());
+        self
+    }
+
+    pub fn with_metadata(mut self, metadata: Value) -> Self {
+        self.metadata = Some(metadata);
+        self
+    }
+
+    /// Keeps the id and creation time of content moved in from elsewhere.
+    pub fn imported(mut self, id: impl Into<String>, created_at: DateTime<Utc>) -> Self {
+        self.id = Some(id.into());
+        self.created_at = Some(created_at);
+        self
+    }
+}
+
+/// Which artifacts to list or delete; unset fields match anything.
+#[derive(Debug, Clone, Default)]
+pub struct ArtifactFilter {
+    pub producer: Option<String>,
+    pub session_id: Option<String>,
+    pub task_id: Option<String>,
+    pub kind: Option<ArtifactKind>,
+    pub created_before: Option<DateTime<Utc>>,
+}
+
+impl ArtifactFilter {
+    pub fn producer(producer: impl Into<String>) -> Self {
+        Self {
+            producer: Some(producer.into()),
+            ..Default::default()
+        }
+    }
+
+    pub fn matches(&self, record: &ArtifactRecord) -> bool {
+        self.producer.as_ref().is_none_or(|p| *p == record.producer)
+            && self
+                .session_id
+                .as_ref()
+                .is_none_or(|s| record.session_id.as_ref() == Some(s))
+            && self
+                .task_id
+                .as_ref()
+                .is_none_or(|t| record.task_id.as_ref() == Some(t))
+            && self.kind.is_none_or(|k| k == record.kind)
+            && self.created_before.is_none_or(|b| record.created_at < b)
+    }
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize, ToSchema)]
+pub struct GcReport {
+    /// Artifacts whose retention ran out
+    pub removed: usize,
+    /// Bytes of content no artifact refers to any more
+    pub freed_bytes: u64,
+}
+
+/// Only the final component of a name is kept, it is never used as a path
+fn file_name(name: &str) -> String {
+    let name: String = Path::new(name)
+        .file_name()
+        .and_then(|n| n.to_str())
+        .unwrap_or_default()
+        .chars()
+        .map(|c| if c.is_control() { '_' } else { c })
+        .collect();
+    if name.is_empty() {
+        "artifact".to_string()
+    } else {
+        name
+    }
+}
+
+fn hash_file(path: &Path) -> Result<(String, u64)> {
+    let mut reader = fs::File::open(path)?;
+    let mut hasher = Sha256::new();
+    let mut buffer = [0u8; 64 * 1024];
+    let mut size = 0;
+    loop {
+        let read = reader.read(&mut buffer)?;
+        if read == 0 {
+            break;
+        }
+        hasher.update(&buffer[..read]);
+        size += read as u64;
+    }
+    Ok((format!("{:x}", hasher.finalize()), size))
+}
+
+fn dir_size(dir: &Path) -> u64 {
+    fs::read_dir(dir)
+        .into_iter()
+        .flatten()
+        .filter_map(|entry| entry.ok()?.metadata().ok())
+        .map(|metadata| metadata.len())
+        .sum()
+}
+
+#[derive(Debug, Clone)]
+pub struct ArtifactRegistry {
+    root: PathBuf,
+}
+
+impl ArtifactRegistry {
+    pub fn new(root: PathBuf) -> Self {
+        Self { root }
+    }
+
+    pub fn persistent() -> Self {
+        Self::new(Paths::in_data_dir(ARTIFACTS_FOLDER))
+    }
+
+    fn load(&self) -> Result<Vec<ArtifactRecord>> {
+        match fs::read(self.root.join(INDEX_FILE)) {
+            Ok(json) => Ok(serde_json::from_slice(&json).context("Artifact index is corrupt")?),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(Vec::new()),
+            Err(e) => Err(e.into()),
+        }
+    }
+
+    fn save(&self, records: &[ArtifactRecord]) -> Result<()> {
+        fs::create_dir_all(&self.root)?;
+        let staged = self.root.join(format!("{}.tmp", INDEX_FILE));
+        fs::write(&staged, serde_json::to_vec_pretty(records)?)?;
+        fs::rename(staged, self.root.join(INDEX_FILE))?;
+        Ok(())
+    }
+
+    fn update<T>(&self, f: impl FnOnce(&mut Vec<ArtifactRecord>) -> Result<T>) -> Result<T> {
+        let _guard = INDEX_LOCK.lock().unwrap_or_else(|e| e.into_inner());
+        let mut records = self.load()?;
+        let result = f(&mut records)?;
+        self.save(&records)?;
+        Ok(result)
+    }
+
+    fn staging_path(&self) -> Result<PathBuf> {
+        let dir = self.root.join(STAGING_FOLDER);
+        fs::create_dir_all(&dir)?;
+        Ok(dir.join(uuid::Uuid::new_v4().simple().to_string()))
+    }
+
+    pub fn store(&self, artifact: NewArtifact, bytes: &[u8]) -> Result<ArtifactRecord> {
+        let staged = self.staging_path()?;
+        fs::write(&staged, bytes)?;
+        let sha256 = format!("{:x}", Sha256::digest(bytes));
+        self.commit(artifact, staged, sha256, bytes.len() as u64)
+    }
+
+    /// Moves the file at `path` into the registry.
+    pub fn import_file(&self, artifact: NewArtifact, path: &Path) -> Result<ArtifactRecord> {
+        let (sha256, size) = hash_file(path)?;
+        let staged = self.staging_path()?;
+        if fs::rename(path, &staged).is_err() {
+            fs::copy(path, &staged)?;
+            fs::remove_file(path)?;
+        }
+        self.commit(artifact, staged, sha256, size)
+    }
+
+    fn commit(
+        &self,
+        artifact: NewArtifact,
+        staged: PathBuf,
+        sha256: String,
+        size: u64,
+    ) -> Result<ArtifactRecord> {
+        let result = self.update(|records| {
+            let blob = match records.iter().find(|r| r.sha256 == sha256) {
+                Some(existing) => existing.blob.clone(),
+                None => {
+                    let dir = self.root.join(BLOBS_FOLDER).join(&sha256);
+                    // Left over from an interrupted delete
+                    if dir.exists() {
+                        fs::remove_dir_all(&dir)?;
+                    }
+                    fs::create_dir_all(&dir)?;
+                    let name = file_name(&artifact.name);
+                    fs::rename(&staged, dir.join(&name))?;
+                    format!("{}/{}/{}", BLOBS_FOLDER, sha256, name)
+                }
+            };
+            let id = artifact
+                .id
+                .unwrap_or_else(|| uuid::Uuid::new_v4().simple().to_string());
+            if records.iter().any(|r| r.id == id) {
+                bail!("Artifact {} already exists", id);
+            }
+            let record = ArtifactRecord {
+                id,
+                name: artifact.name,
+                kind: artifact.kind,
+                producer: artifact.producer,
+                session_id: artifact.session_id,
+                task_id: artifact.task_id,
+                retention: artifact.retention,
+                mime_type: artifact.mime_type,
+                sha256: sha256.clone(),
+                size,
+                created_at: artifact.created_at.unwrap_or_else(Utc::now),
+                metadata: artifact.metadata,
+                blob,
+            };
+            records.push(record.clone());
+            Ok(record)
+        });
+        if staged.exists() {
+            let _ = fs::remove_file(&staged);
+        }
+        result
+    }
+
+    pub fn get(&self, id: &str) -> Result<ArtifactRecord> {
+        let _guard = INDEX_LOCK.lock().unwrap_or_else(|e| e.into_inner());
+        self.load()?
+            .into_iter()
+            .find(|r| r.id == id)
+            .with_context(|| format!("Artifact {} not found", id))
+    }
+
+    /// Where the content of `record` is on disk.
+    pub fn path(&self, record: &ArtifactRecord) -> PathBuf {
+        self.root.join(&record.blob)
+    }
+
+    pub fn read(&self, id: &str) -> Result<Vec<u8>> {
+        let record = self.get(id)?;
+        fs::read(self.path(&record))
+            .with_context(|| format!("Content of artifact {} is missing", id))
+    }
+
+    /// Matching artifacts, oldest first
+    pub fn list(&self, filter: &ArtifactFilter) -> Result<Vec<ArtifactRecord>> {
+        let _guard = INDEX_LOCK.lock().unwrap_or_else(|e| e.into_inner());
+        let mut records: Vec<ArtifactRecord> = self
+            .load()?
+            .into_iter()
+            .filter(|r| filter.matches(r))
+            .collect();
+        records.sort_by(|a, b| a.created_at.cmp(&b.created_at));
+        Ok(records)
+    }
+
+    pub fn set_retention(&self, id: &str, retention: RetentionClass) -> Result<ArtifactRecord> {
+        self.update(|records| {
+            let record = records
+                .iter_mut()
+                .find(|r| r.id == id)
+                .with_context(|| format!("Artifact {} not found", id))?;
+            record.retention = retention;
+            Ok(record.clone())
+        })
+    }
+
+    pub fn delete(&self, id: &str) -> Result<ArtifactRecord> {
+        let mut removed = self.remove_where(|r| r.id == id)?.0;
+        removed
+            .pop()
+            .with_context(|| format!("Artifact {} not found", id))
+    }
+
+    /// Deletes every matching artifact and returns how many there were.
+    pub fn delete_matching(&self, filter: &ArtifactFilter) -> Result<usize> {
+        Ok(self.remove_where(|r| filter.matches(r))?.0.len())
+    }
+
+    /// Deletes the artifacts kept for the lifetime of a session that was
+    /// deleted.
+    pub fn remove_session(&self, session_id: &str) -> Result<usize> {
+        let removed = self.remove_where(|r| {
+            r.retention == RetentionClass::Session && r.session_id.as_deref() == Some(session_id)
+        })?;
+        Ok(removed.0.len())
+    }
+
+    /// Removes the records `remove` picks, then the content none of the
+    /// rest refers to. Returns the removed records and the bytes freed.
+    fn remove_where(
+        &self,
+        remove: impl Fn(&ArtifactRecord) -> bool,
+    ) -> Result<(Vec<ArtifactRecord>, u64)> {
+        self.update(|records| {
+            let (removed, kept): (Vec<_>, Vec<_>) = records.drain(..).partition(|r| remove(r));
+            *records = kept;
+            let referenced: HashSet<&str> = records.iter().map(|r| r.sha256.as_str()).collect();
+            let mut freed = 0;
+            let mut swept = HashSet::new();
+            for record in &removed {
+                if referenced.contains(record.sha256.as_str()) || !swept.insert(&record.sha256) {
+                    continue;
+                }
+                let dir = self.root.join(BLOBS_FOLDER).join(&record.sha256);
+                if dir.exists() {
+                    freed += dir_size(&dir);
+                    fs::remove_dir_all(&dir)?;
+                }
+            }
+            Ok((removed, freed))
+        })
+    }
+
+    fn expired(record: &ArtifactRecord, now: DateTime<Utc>, standard: Duration) -> bool {
+        let kept_for = match record.retention {
+            RetentionClass::Ephemeral => EPHEMERAL_RETENTION,
+            RetentionClass::Standard => standard,
+            // Nothing deletes a session that was never recorded
+            RetentionClass::Session if record.session_id.is_none() => standard,
+            RetentionClass::Session | RetentionClass::Pinned => return false,
+        };
+        record.created_at < now - kept_for
+    }
+
+    /// Deletes the artifacts whose retention ran out by `now`, along with
+    /// content and staged files left behind by interrupted writes.
+    pub fn gc(&self, now: DateTime<Utc>) -> Result<GcReport> {
+        let days = Config::global()
+            .get_param::<i64>(RETENTION_DAYS_KEY)
+            .ok()
+            .filter(|days| *days > 0)
+            .unwrap_or(DEFAULT_RETENTION_DAYS);
+        let standard = Duration::days(days);
+        let (removed, mut freed_bytes) = self.remove_where(|r| Self::expired(r, now, standard))?;
+
+        let _guard = INDEX_LOCK.lock().unwrap_or_else(|e| e.into_inner());
+        let referenced: HashSet<String> = self.load()?.into_iter().map(|r| r.sha256).collect();
+        for entry in fs::read_dir(self.root.join(BLOBS_FOLDER))
+            .into_iter()
+            .flatten()
+        {
+            let path = entry?.path();
+            let orphaned = path
+                .file_name()
+                .and_then(|n| n.to_str())
+                .is_some_and(|sha256| !referenced.contains(sha256));
+            if orphaned && path.is_dir() {
+                freed_bytes += dir_size(&path);
+                fs::remove_dir_all(&path)?;
+            }
+        }
+        for entry in fs::read_dir(self.root.join(STAGING_FOLDER))
+            .into_iter()
+            .flatten()
+        {
+            let entry = entry?;
+            let modified: DateTime<Utc> = entry.metadata()?.modified()?.into();
+            if modified < now - EPHEMERAL_RETENTION {
+                fs::remove_file(entry.path())?;
+            }
+        }
+
+        Ok(GcReport {
+            removed: removed.len(),
+            freed_bytes,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_content_is_shared_and_collected_by_retention() {
+        let dir = TempDir::new().unwrap();
+        let registry = ArtifactRegistry::new(dir.path().to_path_buf());
+
+        let report = registry
+            .store(
+                NewArtifact::new("test_agent", ArtifactKind::TestReport, "report.xml")
+                    .for_task("task-1")
+                    .with_retention(RetentionClass::Ephemeral),
+                b"<testsuite/>",
+            )
+            .unwrap();
+        let copy = registry
+            .store(
+                NewArtifact::new("exports", ArtifactKind::Export, "copy.xml").for_session("s1"),
+                b"<testsuite/>",
+            )
+            .unwrap();
+        assert_eq!(report.sha256, copy.sha256);
+        assert_eq!(registry.path(&copy), registry.path(&report));
+        assert!(registry.path(&report).ends_with("report.xml"));
+
+        let pinned = registry
+            .store(
+                NewArtifact::new("shell_update", ArtifactKind::Package, "Goose.zip")
+                    .with_retention(RetentionClass::Pinned),
+                b"package",
+            )
+            .unwrap();
+        let filter = ArtifactFilter {
+            task_id: Some("task-1".to_string()),
+            ..Default::default()
+        };
+        assert_eq!(registry.list(&filter).unwrap(), vec![report.clone()]);
+
+        // Shared content outlives the first artifact that stored it
+        let gc = registry.gc(Utc::now() + Duration::days(2)).unwrap();
+        assert_eq!(gc.removed, 1);
+        assert_eq!(gc.freed_bytes, 0);
+        assert_eq!(registry.read(&copy.id).unwrap(), b"<testsuite/>");
+
+        let gc = registry.gc(Utc::now() + Duration::days(365)).unwrap();
+        assert_eq!(gc.removed, 1);
+        assert_eq!(gc.freed_bytes, 12);
+        assert!(registry.get(&copy.id).is_err());
+        assert_eq!(
+            registry.list(&ArtifactFilter::default()).unwrap(),
+            vec![pinned.clone()]
+        );
+
+        registry.delete(&pinned.id).unwrap();
+        assert!(!registry.path(&pinned).exists());
+        assert!(registry.delete(&pinned.id).is_err());
+    }
+}
@@ -0,0 +1,487 @@
+//! The user's calendar availability, for scheduling disruptive work.
+//!
+//! Calendars are configured by name in `GOOSE_CALENDARS`:
+//!
+//! ```yaml
+//! GOOSE_CALENDARS:
+//!   work:
+//!     provider: microsoft
+//!     account: work
+//!   home:
+//!     provider: caldav
+//!     url: https://dav.example.com/calendars/ada/personal/
+//! ```
+//!
+//! Google and Microsoft calendars sign in through the email account named by
+//! `account` (see [`crate::email`]); CalDAV calendars read a username and
+//! password from the secrets manager under `GOOSE_CALENDAR_CREDENTIALS_<NAME>`.
+//!
+//! A background job keeps a snapshot of the busy blocks for the coming week.
+//! Restarts, low-priority jobs and anything else that would get in the way
+//! ask [`is_busy`] or [`next_safe_window`] before they start. When no
+//! calendar is configured, or the snapshot could not be refreshed for a
+//! while, every moment counts as free so work is never held indefinitely.
+
+use crate::config::Config;
+use crate::email;
+use anyhow::{anyhow, bail, Context, Result};
+use chrono::{DateTime, Duration, NaiveDateTime, Utc};
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use std::collections::HashMap;
+use std::sync::{LazyLock, RwLock};
+use utoipa::ToSchema;
+
+pub const CALENDARS_KEY: &str = "GOOSE_CALENDARS";
+/// Minutes kept clear before and after each busy block
+pub const BUFFER_MINUTES_KEY: &str = "GOOSE_CALENDAR_BUFFER_MINUTES";
+pub const DEFAULT_BUFFER_MINUTES: i64 = 5;
+const CREDENTIALS_PREFIX: &str = "GOOSE_CALENDAR_CREDENTIALS_";
+const REFRESH_INTERVAL: std::time::Duration = std::time::Duration::from_secs(5 * 60);
+const REQUEST_TIMEOUT: std::time::Duration = std::time::Duration::from_secs(30);
+/// How far ahead busy blocks are fetched
+const HORIZON_DAYS: i64 = 7;
+/// A snapshot older than this is ignored
+const STALE_AFTER_MINUTES: i64 = 30;
+
+static SNAPSHOT: LazyLock<RwLock<Option<Availability>>> = LazyLock::new(|| RwLock::new(None));
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum CalendarProvider {
+    Google,
+    Microsoft,
+    Caldav,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, ToSchema)]
+pub struct CalendarSource {
+    pub provider: CalendarProvider,
+    /// Email account whose sign-in is used, for Google and Microsoft
+    #[serde(default)]
+    pub account: Option<String>,
+    /// Google calendar id (`primary` when unset) or CalDAV collection URL
+    #[serde(default)]
+    pub url: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CaldavCredentials {
+    pub username: String,
+    pub password: String,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, ToSchema)]
+pub struct BusyBlock {
+    pub start: DateTime<Utc>,
+    pub end: DateTime<Utc>,
+    /// Name of the calendar it came from
+    pub calendar: String,
+}
+
+/// Busy blocks across all calendars up to `until`.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, ToSchema)]
+pub struct Availability {
+    pub busy: Vec<BusyBlock>,
+    pub fetched_at: DateTime<Utc>,
+    pub until: DateTime<Utc>,
+}
+
+impl Availability {
+    pub fn new(mut busy: Vec<BusyBlock>, fetched_at: DateTime<Utc>, until: DateTime<Utc>) -> Self {
+        busy.retain(|block| block.end > block.start);
+        busy.sort_by_key(|block| block.start);
+        Self {
+            busy,
+            fetched_at,
+            until,
+        }
+    }
+
+    /// The busy block covering `at`, widened by `buffer` on both sides.
+    pub fn busy_at(&self, at: DateTime<Utc>, buffer: Duration) -> Option<&BusyBlock> {
+        self.busy
+            .iter()
+            .find(|block| block.start - buffer <= at && at < block.end + buffer)
+    }
+
+    /// The earliest start at or after `after` from which `length` runs
+    /// clear of every busy block and its buffer. Time past `until` is
+    /// unknown and counts as free.
+    pub fn next_free(
+        &self,
+        after: DateTime<Utc>,
+        length: Duration,
+        buffer: Duration,
+    ) -> DateTime<Utc> {
+        let mut candidate = after;
+        for block in &self.busy {
+            if block.end + buffer <= candidate {
+                continue;
+            }
+            if candidate + length <= block.start - buffer {
+                break;
+            }
+            candidate = block.end + buffer;
+        }
+        candidate
+    }
+}
+
+pub fn calendars() -> HashMap<String, CalendarSource> {
+    Config::global()
+        .get_param::<HashMap<String, CalendarSource>>(CALENDARS_KEY)
+        .unwrap_or_default()
+}
+
+fn buffer() -> Duration {
+    let minutes = Config::global()
+        .get_param::<i64>(BUFFER_MINUTES_KEY)
+        .unwrap_or(DEFAULT_BUFFER_MINUTES);
+    Duration::minutes(minutes.max(0))
+}
+
+fn credentials_key(name: &str) -> String {
+    format!("{}{}", CREDENTIALS_PREFIX, name.to_uppercase())
+}
+
+pub fn set_caldav_credentials(name: &str, credentials: &CaldavCredentials) -> Result<()> {
+    Config::global().set_secret(&credentials_key(name), credentials)?;
+    Ok(())
+}
+
+/// The current snapshot, unless it is missing or stale.
+pub fn snapshot() -> Option<Availability> {
+    let snapshot = SNAPSHOT.read().unwrap_or_else(|e| e.into_inner());
+    snapshot
+        .as_ref()
+        .filter(|a| Utc::now() - a.fetched_at < Duration::minutes(STALE_AFTER_MINUTES))
+        .cloned()
+}
+
+fn set_snapshot(availability: Option<Availability>) {
+    *SNAPSHOT.write().unwrap_or_else(|e| e.into_inner()) = availability;
+}
+
+/// Whether the user is in a meeting, or close to one, at `at`.
+pub fn is_busy(at: DateTime<Utc>) -> bool {
+    snapshot().is_some_and(|a| a.busy_at(at, buffer()).is_some())
+}
+
+/// When something taking `length` can start without overlapping a busy
+/// block; `after` itself when it is free.
+pub fn next_safe_window(after: DateTime<Utc>, length: Duration) -> DateTime<Utc> {
+    match snapshot() {
+        Some(availability) => availability.next_free(after, length, buffer()),
+        None => after,
+    }
+}
+
+/// Fetches busy blocks for the coming week from every calendar. A calendar
+/// that fails is logged and left out rather than failing the rest.
+pub async fn refresh() -> Result<Option<Availability>> {
+    let calendars = calendars();
+    if calendars.is_empty() {
+        set_snapshot(None);
+        return Ok(None);
+    }
+    let client = reqwest::Client::builder()
+        .timeout(REQUEST_TIMEOUT)
+        .build()?;
+    let now = Utc::now();
+    let until = now + Duration::days(HORIZON_DAYS);
+    let mut busy = Vec::new();
+    let mut failures = 0;
+    for (name, source) in &calendars {
+        match fetch_busy(&client, name, source, now, until).await {
+            Ok(blocks) => busy.extend(blocks),
+            Err(e) => {
+                failures += 1;
+                tracing::warn!("Failed to read calendar '{}': {}", name, e);
+            }
+        }
+    }
+    if failures == calendars.len() {
+        bail!("No calendar could be read");
+    }
+    let availability = Availability::new(busy, now, until);
+    set_snapshot(Some(availability.clone()));
+    Ok(Some(availability))
+}
+
+/// Refreshes the snapshot periodically for the life of the process.
+pub fn spawn_refresh() -> tokio::task::JoinHandle<()> {
+    tokio::spawn(async move {
+        let mut interval = tokio::time::interval(REFRESH_INTERVAL);
+        loop {
+            interval.tick().await;
+            if let Err(e) = refresh().await {
+                tracing::warn!("Calendar availability not refreshed: {}", e);
+            }
+        }
+    })
+}
+
+async fn fetch_busy(
+    client: &reqwest::Client,
+    name: &str,
+    source: &CalendarSource,
+    from: DateTime<Utc>,
+    until: DateTime<Utc>,
+) -> Result<Vec<BusyBlock>> {
+    let block = |start, end| BusyBlock {
+        start,
+        end,
+        calendar: name.to_string(),
+    };
+    let periods = match source.provider {
+        CalendarProvider::Google => {
+            let token = oauth_token(source).await?;
+            let id = source.url.as_deref().unwrap_or("primary");
+            let response: Value = client
+                .post("https://www.googleapis.com/calendar/v3/freeBusy")
+                .bearer_auth(token)
+                .json(&json!({
+                    "timeMin": from.to_rfc3339(),
+                    "timeMax": until.to_rfc3339(),
+                    "items": [{ "id": id }],
+                }))
+                .send()
+                .await?
+                .error_for_status()?
+                .json()
+                .await?;
+            google_busy(&response, id)
+        }
+        CalendarProvider::Microsoft => {
+            let token = oauth_token(source).await?;
+            let response: Value = client
+                .get("https://graph.microsoft.com/v1.0/me/calendarView")
+                .query(&[
+                    ("startDateTime", from.to_rfc3339()),
+                    ("endDateTime", until.to_rfc3339()),
+                    ("$select", "start,end,showAs".to_string()),
+                    ("$top", "500".to_string()),
+                ])
+                .bearer_auth(token)
+                .header("Prefer", "outlook.timezone=\"UTC\"")
+                .send()
+                .await?
+                .error_for_status()?
+                .json()
+                .await?;
+            graph_busy(&response)
+        }
+        CalendarProvider::Caldav => {
+            let url = source
+                .url
+                .as_deref()
+                .ok_or_else(|| anyhow!("CalDAV calendar '{}' has no url", name))?;
+            let credentials: CaldavCredentials = Config::global()
+                .get_secret(&credentials_key(name))
+                .with_context(|| format!("No credentials stored for calendar '{}'", name))?;
+            let stamp = |at: DateTime<Utc>| at.format("%Y%m%dT%H%M%SZ").to_string();
+            let body = format!(
+                r#"<?xml version="1.0" encoding="utf-8"?>
+<C:free-busy-query xmlns:C="urn:ietf:params:xml:ns:caldav">
+  <C:time-range start="{}" end="{}"/>
+</C:free-busy-query>"#,
+                stamp(from),
+                stamp(until)
+            );
+            let text = client
+                .request(reqwest::Method::from_bytes(b"REPORT")?, url)
+                .basic_auth(&credentials.username, Some(&credentials.password))
+                .header("Depth", "1")
+                .header("Content-Type", "application/xml; charset=utf-8")
+                .body(body)
+                .send()
+                .await?
+                .error_for_status()?
+                .text()
+                .await?;
+            freebusy_periods(&text)
+        }
+    };
+    Ok(periods
+        .into_iter()
+        .map(|(start, end)| block(start, end))
+        .collect())
+}
+
+async fn oauth_token(source: &CalendarSource) -> Result<String> {
+    let (name, account) = email::account(source.account.as_deref())?;
+    email::access_token(&name, &account).await
+}
+
+fn google_busy(response: &Value, id: &str) -> Vec<(DateTime<Utc>, DateTime<Utc>)> {
+    let parse = |v: &Value| {
+        v.as_str()
+            .and_then(|s| DateTime::parse_from_rfc3339(s).ok())
+            .map(|at| at.with_timezone(&Utc))
+    };
+    response["calendars"][id]["busy"]
+        .as_array()
+        .into_iter()
+        .flatten()
+        .filter_map(|period| Some((parse(&period["start"])?, parse(&period["end"])?)))
+        .collect()
+}
+
+fn graph_busy(response: &Value) -> Vec<(DateTime<Utc>, DateTime<Utc>)> {
+    let parse = |v: &Value| {
+        v["dateTime"]
+            .as_str()
+            .and_then(|s| NaiveDateTime::parse_from_str(s, "%Y-%m-%dT%H:%M:%S%.f").ok())
+            .map(|at| at.and_utc())
+    };
+    response["value"]
+        .as_array()
+        .into_iter()
+        .flatten()
+        .filter(|event| !matches!(event["showAs"].as_str(), Some("free" | "workingElsewhere")))
+        .filter_map(|event| Some((parse(&event["start"])?, parse(&event["end"])?)))
+        .collect()
+}
+
+/// `FREEBUSY` periods of a VFREEBUSY reply, other than explicitly free ones.
+/// Periods are `start/end` or `start/duration` in UTC.
+fn freebusy_periods(ical: &str) -> Vec<(DateTime<Utc>, DateTime<Utc>)> {
+    let unfolded = ical.replace("\r\n ", "").replace("\n ", "");
+    let parse_at = |s: &str| {
+        NaiveDateTime::parse_from_str(s, "%Y%m%dT%H%M%SZ")
+            .ok()
+            .map(|at| at.and_utc())
+    };
+    let mut periods = Vec::new();
+    for line in unfolded.lines() {
+        let Some((property, values)) = line.split_once(':') else {
+            continue;
+        };
+        let mut params = property.split(';');
+        if !params
+            .next()
+            .is_some_and(|p| p.eq_ignore_ascii_case("FREEBUSY"))
+            || params.any(|p| p.eq_ignore_ascii_case("FBTYPE=FREE"))
+        {
+            continue;
+        }
+        for period in values.trim().split(',') {
+            let Some((start, end)) = period.split_once('/') else {
+                continue;
+            };
+            let Some(start) = parse_at(start) else {
+                continue;
+            };
+            let end = parse_at(end).or_else(|| ical_duration(end).map(|d| start + d));
+            if let Some(end) = end {
+                periods.push((start, end));
+            }
+        }
+    }
+    periods
+}
+
+/// An iCalendar duration such as `PT1H30M` or `P1D`.
+fn ical_duration(value: &str) -> Option<Duration> {
+    let mut rest = value.strip_prefix('P')?;
+    let mut total = Duration::zero();
+    let mut in_time = false;
+    while !rest.is_empty() {
+        if let Some(after) = rest.strip_prefix('T') {
+            in_time = true;
+            rest = after;
+            continue;
+        }
+        let digits = rest.find(|c: char| !c.is_ascii_digit())?;
+        let amount: i64 = rest[..digits].parse().ok()?;
+        total += match (rest[digits..].chars().next()?, in_time) {
+            ('W', false) => Duration::weeks(amount),
+            ('D', false) => Duration::days(amount),
+            ('H', true) => Duration::hours(amount),
+            ('M', true) => Duration::minutes(amount),
+            ('S', true) => Duration::seconds(amount),
+            _ => return None,
+        };
+        rest = &rest[digits + 1..];
+    }
+    Some(total)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+
+    fn at(hour: u32, minute: u32) -> DateTime<Utc> {
+        Utc.with_ymd_and_hms(2026, 10, 19, hour, minute, 0).unwrap()
+    }
+
+    #[test]
+    fn test_next_free_skips_meetings_and_buffers() {
+        let block = |start, end| BusyBlock {
+            start,
+            end,
+            calendar: "work".to_string(),
+        };
+        let availability = Availability::new(
+            vec![
+                block(at(10, 0), at(11, 0)),
+                block(at(9, 0), at(9, 30)),
+                block(at(11, 20), at(12, 0)),
+            ],
+            at(8, 0),
+            at(18, 0),
+        );
+        let buffer = Duration::minutes(5);
+
+        assert!(availability.busy_at(at(8, 56), buffer).is_some());
+        assert!(availability.busy_at(at(9, 40), buffer).is_none());
+        assert_eq!(
+            availability.next_free(at(8, 0), Duration::minutes(30), buffer),
+            at(8, 0)
+        );
+        assert_eq!(
+            availability.next_free(at(9, 0), Duration::minutes(20), buffer),
+            at(9, 35)
+        );
+        // 11:05-11:15 is too short for half an hour
+        assert_eq!(
+            availability.next_free(at(10, 30), Duration::minutes(30), buffer),
+            at(12, 5)
+        );
+        assert_eq!(
+            availability.next_free(at(17, 50), Duration::minutes(30), buffer),
+            at(17, 50)
+        );
+    }
+
+    #[test]
+    fn test_parse_provider_replies() {
+        let ical = "BEGIN:VCALENDAR\r\nBEGIN:VFREEBUSY\r\n\
+                    FREEBUSY;FBTYPE=BUSY:20261019T090000Z/20261019T093000Z,\r\n \
+                    20261019T140000Z/PT1H30M\r\n\
+                    FREEBUSY;FBTYPE=FREE:20261019T120000Z/20261019T130000Z\r\n\
+                    END:VFREEBUSY\r\nEND:VCALENDAR\r\n";
+        assert_eq!(
+            freebusy_periods(ical),
+            vec![(at(9, 0), at(9, 30)), (at(14, 0), at(15, 30))]
+        );
+        assert_eq!(ical_duration("P1DT2H"), Some(Duration::hours(26)));
+        assert_eq!(ical_duration("PT"), Some(Duration::zero()));
+        assert_eq!(ical_duration("1H"), None);
+
+        let graph = json!({ "value": [
+            { "showAs": "busy", "start": { "dateTime": "2026-10-19T09:00:00.0000000" },
+              "end": { "dateTime": "2026-10-19T10:00:00.0000000" } },
+            { "showAs": "free", "start": { "dateTime": "2026-10-19T11:00:00.0000000" },
+              "end": { "dateTime": "2026-10-19T12:00:00.0000000" } }
+        ]});
+        assert_eq!(graph_busy(&graph), vec![(at(9, 0), at(10, 0))]);
+
+        let google = json!({ "calendars": { "primary": { "busy": [
+            { "start": "2026-10-19T11:00:00+02:00", "end": "2026-10-19T12:00:00+02:00" }
+        ]}}});
+        assert_eq!(google_busy(&google, "primary"), vec![(at(9, 0), at(10, 0))]);
+    }
+}
@@ -131,10 +131,14 @@ pub enum MessageImportance {
     Disposable,
 }
 
+/// Most recent messages kept verbatim however often summaries miss things
+const MAX_PRESERVED_MESSAGES: usize = 40;
+
 /// Compaction manager
 pub struct CompactionManager {
     config: CompactionConfig,
     history: Vec<CompactionResult>,
+    summary_misses: usize,
 }
 
 impl CompactionManager {
@@ -142,9 +146,24 @@ impl CompactionManager {
         Self {
             config,
             history: Vec::new(),
+            summary_misses: 0,
         }
     }
 
+    /// Record details a summary dropped and the agent needed again (see
+    /// [`crate::context_mgmt::summary_misses`]); each one keeps another
+    /// recent message out of later summaries.
+    pub fn record_summary_misses(&mut self, misses: usize) {
+        self.summary_misses += misses;
+        self.config.preserve_recent_messages =
+            (self.config.preserve_recent_messages + misses).min(MAX_PRESERVED_MESSAGES);
+    }
+
+    /// Update the usage ratio at which compaction triggers
+    pub fn set_trigger_threshold(&mut self, threshold: f32) {
+        self.config.trigger_threshold = threshold.clamp(0.0, 1.0);
+    }
+
     /// Check if compaction should be triggered
     pub fn should_compact(&self, current_tokens: usize, max_tokens: usize) -> bool {
         let usage = current_tokens as f32 / max_tokens as f32;
@@ -282,6 +301,7 @@ impl CompactionManager {
             total_compactions,
             total_tokens_saved: total_saved,
             average_reduction_percent: avg_reduction,
+            summary_misses: self.summary_misses,
         }
     }
 }
@@ -291,6 +311,8 @@ pub struct CompactionStats {
     pub total_compactions: usize,
     pub total_tokens_saved: usize,
     pub average_reduction_percent: f32,
+    /// Details summaries dropped that the agent had to re-read or re-ask
+    pub summary_misses: usize,
 }
 
 #[cfg(test)]
@@ -312,6 +334,19 @@ mod tests {
         assert!(manager.should_compact(8600, 10000)); // 86% - above threshold
     }
 
+    #[test]
+    fn test_summary_misses_preserve_more_messages() {
+        let mut manager = CompactionManager::new(CompactionConfig::default());
+        manager.record_summary_misses(3);
+        assert_eq!(manager.stats().summary_misses, 3);
+        assert_eq!(manager.config.preserve_recent_messages, 13);
+        manager.record_summary_misses(100);
+        assert_eq!(
+            manager.config.preserve_recent_messages,
+            MAX_PRESERVED_MESSAGES
+        );
+    }
+
     #[test]
     fn test_compaction_result() {
         let result = CompactionResult::new(
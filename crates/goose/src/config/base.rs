@@ -1,3 +1,4 @@
+use crate::config::layers::{self, ConfigChange, ConfigSource, ConfigWatcher, EffectiveValue};
 use crate::config::paths::Paths;
 use crate::config::GooseMode;
 use fs2::FileExt;
@@ -6,17 +7,19 @@ use once_cell::sync::OnceCell;
 use serde::{Deserialize, Serialize};
 use serde_json::Value;
 use serde_yaml::Mapping;
-use std::collections::HashMap;
+use std::collections::{BTreeMap, HashMap};
 use std::env;
 use std::fs::OpenOptions;
 use std::io::Write;
 use std::path::{Path, PathBuf};
 use std::sync::{Arc, Mutex};
 use thiserror::Error;
+use tokio::sync::broadcast;
 
 const KEYRING_SERVICE: &str = "goose";
 const KEYRING_USERNAME: &str = "secrets";
 pub const CONFIG_YAML_NAME: &str = "config.yaml";
+const CONFIG_CHANGE_CAPACITY: usize = 32;
 
 #[derive(Error, Debug)]
 pub enum ConfigError {
@@ -32,6 +35,8 @@ pub enum ConfigError {
     KeyringError(String),
     #[error("Failed to lock config file: {0}")]
     LockError(String),
+    #[error("Failed to watch config file: {0}")]
+    WatchError(String),
     #[error("Secret stored using file-based fallback")]
     FallbackToFileStorage,
 }
@@ -66,7 +71,9 @@ impl From<keyring::Error> for ConfigError {
 ///
 /// Configuration values are loaded with the following precedence:
 /// 1. Environment variables (exact key match)
-/// 2. Configuration file (~/.config/goose/config.yaml by default)
+/// 2. Workspace configuration file (.goose/config.yaml in or above the workspace)
+/// 3. Configuration file (~/.config/goose/config.yaml by default)
+/// 4. Built-in defaults
 ///
 /// Secrets are loaded with the following precedence:
 /// 1. Environment variables (exact key match)
@@ -105,6 +112,8 @@ pub struct Config {
     secrets: SecretStorage,
     guard: Mutex<()>,
     secrets_cache: Arc<Mutex<Option<HashMap<String, Value>>>>,
+    workspace_dir: Mutex<Option<PathBuf>>,
+    changes: broadcast::Sender<ConfigChange>,
 }
 
 enum SecretStorage {
@@ -134,6 +143,8 @@ impl Default for Config {
             secrets,
             guard: Mutex::new(()),
             secrets_cache: Arc::new(Mutex::new(None)),
+            workspace_dir: Mutex::new(env::current_dir().ok()),
+            changes: broadcast::channel(CONFIG_CHANGE_CAPACITY).0,
         }
     }
 }
@@ -238,6 +249,8 @@ impl Config {
             },
             guard: Mutex::new(()),
             secrets_cache: Arc::new(Mutex::new(None)),
+            workspace_dir: Mutex::new(None),
+            changes: broadcast::channel(CONFIG_CHANGE_CAPACITY).0,
         })
     }
 
@@ -256,6 +269,8 @@ impl Config {
             },
             guard: Mutex::new(()),
             secrets_cache: Arc::new(Mutex::new(None)),
+            workspace_dir: Mutex::new(None),
+            changes: broadcast::channel(CONFIG_CHANGE_CAPACITY).0,
         })
     }
 
@@ -649,7 +664,12 @@ impl Config {
     ///
     /// This will attempt to get the value from:
     /// 1. Environment variable with the exact key name
-    /// 2. Configuration file
+    /// 2. Workspace configuration file
+    /// 3. Configuration file
+    /// 4. Built-in defaults
+    ///
+    /// Mapping values defined in several files are merged key by key, with the
+    /// higher precedence file winning.
     ///
     /// The value will be deserialized into the requested type. This works with
     /// both simple types (String, i32, etc.) and complex types that implement
@@ -662,17 +682,229 @@ impl Config {
     /// - The value cannot be deserialized into the requested type
     /// - There is an error reading the config file
     pub fn get_param<T: for<'de> Deserialize<'de>>(&self, key: &str) -> Result<T, ConfigError> {
+        let workspace_dir = self.workspace_dir.lock().unwrap().clone();
+        self.resolve_param(key, workspace_dir.as_deref())
+    }
+
+    /// Get a configuration value as seen from `workspace_dir`, so a session
+    /// reads the workspace config above its own working directory rather
+    /// than the process's.
+    pub fn get_param_in<T: for<'de> Deserialize<'de>>(
+        &self,
+        key: &str,
+        workspace_dir: &Path,
+    ) -> Result<T, ConfigError> {
+        self.resolve_param(key, Some(workspace_dir))
+    }
+
+    fn resolve_param<T: for<'de> Deserialize<'de>>(
+        &self,
+        key: &str,
+        workspace_dir: Option<&Path>,
+    ) -> Result<T, ConfigError> {
         let env_key = key.to_uppercase();
         if let Ok(val) = env::var(&env_key) {
             let value = Self::parse_env_value(&val)?;
             return Ok(serde_json::from_value(value)?);
         }
 
-        let values = self.load()?;
-        values
+        let mut merged: Option<serde_yaml::Value> = None;
+        for (_, values) in self.layers_in(workspace_dir)? {
+            if let Some(value) = values.get(key) {
+                match merged.as_mut() {
+                    Some(existing) => layers::merge_values(existing, value.clone()),
+                    None => merged = Some(value.clone()),
+                }
+            }
+        }
+
+        merged
+            .ok_or_else(|| ConfigError::NotFound(key.to_string()))
+            .and_then(|v| Ok(serde_yaml::from_value(v)?))
+    }
+
+    /// The file-backed layers in increasing precedence: defaults, user, and
+    /// the workspace config found from `workspace_dir`.
+    fn layers_in(
+        &self,
+        workspace_dir: Option<&Path>,
+    ) -> Result<Vec<(ConfigSource, Mapping)>, ConfigError> {
+        let user = self.load()?;
+        let workspace_config = workspace_dir.and_then(layers::find_workspace_config);
+        let trusted = workspace_config
+            .as_deref()
+            .is_some_and(|path| layers::is_trusted(&user, path));
+        let mut result = vec![
+            (ConfigSource::Default, layers::builtin_defaults()),
+            (ConfigSource::User, user),
+        ];
+        if let Some(path) = workspace_config {
+            if !trusted {
+                tracing::debug!("Ignoring untrusted workspace config {}", path.display());
+                return Ok(result);
+            }
+            match layers::load_yaml_file(&path) {
+                Ok(values) => result.push((ConfigSource::Workspace, values)),
+                Err(e) => tracing::warn!(
+                    "Ignoring invalid workspace config {}: {}",
+                    path.display(),
+                    e
+                ),
+            }
+        }
+        Ok(result)
+    }
+
+    /// Sets the directory used to discover a workspace `.goose/config.yaml`.
+    pub fn set_workspace_dir(&self, dir: Option<PathBuf>) {
+        let before = self.effective_values().ok();
+        *self.workspace_dir.lock().unwrap() = dir;
+        if let (Some(before), Ok(after)) = (before, self.effective_values()) {
+            self.notify_change(layers::changed_keys(&before, &after));
+        }
+    }
+
+    /// The closest existing workspace config file, if any.
+    pub fn workspace_config_path(&self) -> Option<PathBuf> {
+        let dir = self.workspace_dir.lock().unwrap().clone()?;
+        layers::find_workspace_config(&dir)
+    }
+
+    /// Whether the workspace config at `path` has been trusted by the user.
+    pub fn is_workspace_trusted(&self, path: &Path) -> Result<bool, ConfigError> {
+        Ok(layers::is_trusted(&self.load()?, path))
+    }
+
+    /// Trust the workspace config at `path`, so its values are layered over
+    /// the user config from now on.
+    pub fn trust_workspace(&self, path: &Path) -> Result<(), ConfigError> {
+        let before = self.effective_values().ok();
+        {
+            let _guard = self.guard.lock().unwrap();
+            self.add_trusted_workspace(path)?;
+        }
+        if let (Some(before), Ok(after)) = (before, self.effective_values()) {
+            self.notify_change(layers::changed_keys(&before, &after));
+        }
+        Ok(())
+    }
+
+    fn add_trusted_workspace(&self, path: &Path) -> Result<(), ConfigError> {
+        let mut values = self.load()?;
+        if layers::is_trusted(&values, path) {
+            return Ok(());
+        }
+        let key = serde_yaml::Value::from(layers::TRUSTED_WORKSPACES_KEY);
+        let mut trusted = values
+            .get(&key)
+            .and_then(|v| v.as_sequence())
+            .cloned()
+            .unwrap_or_default();
+        trusted.push(serde_yaml::Value::from(layers::trust_entry(path)));
+        values.insert(key, serde_yaml::Value::Sequence(trusted));
+        self.save_values(values)
+    }
+
+    /// Get a value from the user config file alone, ignoring the environment
+    /// and any workspace config. Use this for values that are read, modified
+    /// and written back with [`Config::set_param`].
+    pub fn get_user_param<T: for<'de> Deserialize<'de>>(
+        &self,
+        key: &str,
+    ) -> Result<T, ConfigError> {
+        self.load()?
             .get(key)
+            .cloned()
             .ok_or_else(|| ConfigError::NotFound(key.to_string()))
-            .and_then(|v| Ok(serde_yaml::from_value(v.clone())?))
+            .and_then(|v| Ok(serde_yaml::from_value(v)?))
+    }
+
+    /// Every configured value with the layer it was resolved from.
+    ///
+    /// Environment overrides are reported for keys that appear in any file layer.
+    pub fn effective_values(&self) -> Result<BTreeMap<String, EffectiveValue>, ConfigError> {
+        let workspace_dir = self.workspace_dir.lock().unwrap().clone();
+        self.effective_values_in(workspace_dir.as_deref())
+    }
+
+    /// Like [`Config::effective_values`], with the workspace layer found from
+    /// `workspace_dir`.
+    pub fn effective_values_in(
+        &self,
+        workspace_dir: Option<&Path>,
+    ) -> Result<BTreeMap<String, EffectiveValue>, ConfigError> {
+        let mut resolved = layers::resolve_layers(&self.layers_in(workspace_dir)?);
+        for effective in resolved.values_mut() {
+            if let Ok(val) = env::var(effective.key.to_uppercase()) {
+                effective.value = Self::parse_env_value(&val)?;
+                effective.overrides.push(effective.source);
+                effective.source = ConfigSource::Env;
+            }
+        }
+        Ok(resolved)
+    }
+
+    /// Set a value in the workspace config file, creating `.goose/config.yaml`
+    /// in the workspace directory when none exists yet. Writing the file
+    /// trusts it, since its contents now come from the user.
+    pub fn set_workspace_param<V: Serialize>(
+        &self,
+        key: &str,
+        value: V,
+    ) -> Result<(), ConfigError> {
+        let workspace_dir = self
+            .workspace_dir
+            .lock()
+            .unwrap()
+            .clone()
+            .ok_or_else(|| ConfigError::NotFound("workspace directory".to_string()))?;
+        self.set_workspace_param_in(key, value, &workspace_dir)
+    }
+
+    /// Like [`Config::set_workspace_param`], for the workspace config found
+    /// from `workspace_dir`.
+    pub fn set_workspace_param_in<V: Serialize>(
+        &self,
+        key: &str,
+        value: V,
+        workspace_dir: &Path,
+    ) -> Result<(), ConfigError> {
+        let _guard = self.guard.lock().unwrap();
+        let path = layers::find_workspace_config(workspace_dir).unwrap_or_else(|| {
+            workspace_dir
+                .join(layers::WORKSPACE_CONFIG_DIR)
+                .join(layers::WORKSPACE_CONFIG_FILE)
+        });
+
+        let mut values = layers::load_yaml_file(&path)?;
+        values.insert(serde_yaml::to_value(key)?, serde_yaml::to_value(value)?);
+
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        std::fs::write(&path, serde_yaml::to_string(&values)?)?;
+        self.add_trusted_workspace(&path)?;
+        self.notify_change(vec![key.to_string()]);
+        Ok(())
+    }
+
+    /// Subscribe to configuration changes made through this instance or,
+    /// once [`Config::watch`] is running, by editing the config files.
+    pub fn subscribe(&self) -> broadcast::Receiver<ConfigChange> {
+        self.changes.subscribe()
+    }
+
+    pub(crate) fn notify_change(&self, keys: Vec<String>) {
+        if !keys.is_empty() {
+            // No receivers is fine; nobody needs to react
+            let _ = self.changes.send(ConfigChange { keys });
+        }
+    }
+
+    /// Start watching the user and workspace config files for edits.
+    /// The returned watcher must be kept alive for notifications to continue.
+    pub fn watch(&'static self) -> Result<ConfigWatcher, ConfigError> {
+        ConfigWatcher::start(self)
     }
 
     /// Set a configuration value in the config file (non-secret).
@@ -692,7 +924,9 @@ impl Config {
         let _guard = self.guard.lock().unwrap();
         let mut values = self.load()?;
         values.insert(serde_yaml::to_value(key)?, serde_yaml::to_value(value)?);
-        self.save_values(values)
+        self.save_values(values)?;
+        self.notify_change(vec![key.to_string()]);
+        Ok(())
     }
 
     /// Delete a configuration value in the config file.
@@ -715,7 +949,9 @@ impl Config {
         let mut values = self.load()?;
         values.shift_remove(key);
 
-        self.save_values(values)
+        self.save_values(values)?;
+        self.notify_change(vec![key.to_string()]);
+        Ok(())
     }
 
     /// Get a secret value.
@@ -1713,6 +1949,87 @@ mod tests {
         assert!(matches!(result, Err(ConfigError::NotFound(_))));
     }
 
+    #[test]
+    fn test_workspace_config_overrides_user_config() -> Result<(), ConfigError> {
+        let config = new_test_config();
+        let workspace = tempfile::tempdir().unwrap();
+        config.set_workspace_dir(Some(workspace.path().to_path_buf()));
+
+        config.set_param("layered_key", "user")?;
+        config.set_param("layered_map", serde_json::json!({"a": 1, "b": 2}))?;
+        assert_eq!(config.get_param::<String>("layered_key")?, "user");
+
+        config.set_workspace_param("layered_key", "workspace")?;
+        config.set_workspace_param("layered_map", serde_json::json!({"b": 3}))?;
+        assert_eq!(config.get_param::<String>("layered_key")?, "workspace");
+        assert_eq!(
+            config.get_param::<serde_json::Value>("layered_map")?,
+            serde_json::json!({"a": 1, "b": 3})
+        );
+
+        let effective = config.effective_values()?;
+        assert_eq!(effective["layered_key"].source, ConfigSource::Workspace);
+        assert_eq!(effective["layered_key"].overrides, vec![ConfigSource::User]);
+        assert_eq!(
+            effective["GOOSE_AUTO_COMPACT_THRESHOLD"].source,
+            ConfigSource::Default
+        );
+
+        // Another session's working directory sees its own workspace layer
+        let other = tempfile::tempdir().unwrap();
+        let nested = other.path().join("src");
+        std::fs::create_dir_all(&nested).unwrap();
+        assert_eq!(
+            config.get_param_in::<String>("layered_key", &nested)?,
+            "user"
+        );
+        config.set_workspace_param_in("layered_key", "other", other.path())?;
+        assert_eq!(
+            config.get_param_in::<String>("layered_key", &nested)?,
+            "other"
+        );
+        assert_eq!(config.get_param::<String>("layered_key")?, "workspace");
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_untrusted_workspace_config_is_ignored() -> Result<(), ConfigError> {
+        let config = new_test_config();
+        let workspace = tempfile::tempdir().unwrap();
+        let goose_dir = workspace.path().join(layers::WORKSPACE_CONFIG_DIR);
+        std::fs::create_dir_all(&goose_dir)?;
+        let path = goose_dir.join(layers::WORKSPACE_CONFIG_FILE);
+        std::fs::write(&path, "trust_key: untrusted\n")?;
+        config.set_param("trust_key", "user")?;
+        config.set_workspace_dir(Some(workspace.path().to_path_buf()));
+
+        assert!(!config.is_workspace_trusted(&path)?);
+        assert_eq!(config.get_param::<String>("trust_key")?, "user");
+
+        config.trust_workspace(&path)?;
+        assert!(config.is_workspace_trusted(&path)?);
+        assert_eq!(config.get_param::<String>("trust_key")?, "untrusted");
+        assert_eq!(config.get_user_param::<String>("trust_key")?, "user");
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_subscribers_are_notified_of_changes() -> Result<(), ConfigError> {
+        let config = new_test_config();
+        let mut changes = config.subscribe();
+
+        config.set_param("watched_key", 1)?;
+        config.delete("watched_key")?;
+
+        assert!(changes.try_recv().unwrap().touches("watched_key"));
+        assert!(changes.try_recv().unwrap().touches("watched_key"));
+        assert!(changes.try_recv().is_err());
+
+        Ok(())
+    }
+
     fn new_test_config() -> Config {
         let config_file = NamedTempFile::new().unwrap();
         let secrets_file = NamedTempFile::new().unwrap();
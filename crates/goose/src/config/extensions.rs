@@ -1,4 +1,4 @@
-use super::base::Config;
+use super::base::{Config, ConfigError};
 use crate::agents::ExtensionConfig;
 use indexmap::IndexMap;
 use serde::{Deserialize, Serialize};
@@ -41,7 +41,10 @@ fn get_extensions_map_with_config(config: &Config) -> IndexMap<String, Extension
             );
             Default::default()
         });
+    parse_extensions_map(raw)
+}
 
+fn parse_extensions_map(raw: Mapping) -> IndexMap<String, ExtensionEntry> {
     let mut extensions_map = IndexMap::with_capacity(raw.len());
     for (k, v) in raw {
         match (k, serde_yaml::from_value::<ExtensionEntry>(v)) {
@@ -65,6 +68,23 @@ fn get_extensions_map() -> IndexMap<String, ExtensionEntry> {
     get_extensions_map_with_config(Config::global())
 }
 
+/// The extensions in the user config alone. Changes are made to this map so
+/// that extensions from a workspace config are never copied into the user's.
+fn get_user_extensions_map() -> IndexMap<String, ExtensionEntry> {
+    let raw: Mapping = match Config::global().get_user_param(EXTENSIONS_CONFIG_KEY) {
+        Ok(raw) => raw,
+        Err(ConfigError::NotFound(_)) => Mapping::new(),
+        Err(err) => {
+            warn!(
+                "Failed to load {}: {err}. Falling back to empty object.",
+                EXTENSIONS_CONFIG_KEY
+            );
+            Mapping::new()
+        }
+    };
+    parse_extensions_map(raw)
+}
+
 fn save_extensions_map(extensions: IndexMap<String, ExtensionEntry>) {
     let config = Config::global();
     if let Err(e) = config.set_param(EXTENSIONS_CONFIG_KEY, &extensions) {
@@ -82,20 +102,20 @@ pub fn get_extension_by_name(name: &str) -> Option<ExtensionConfig> {
 }
 
 pub fn set_extension(entry: ExtensionEntry) {
-    let mut extensions = get_extensions_map();
+    let mut extensions = get_user_extensions_map();
     let key = entry.config.key();
     extensions.insert(key, entry);
     save_extensions_map(extensions);
 }
 
 pub fn remove_extension(key: &str) {
-    let mut extensions = get_extensions_map();
+    let mut extensions = get_user_extensions_map();
     extensions.shift_remove(key);
     save_extensions_map(extensions);
 }
 
 pub fn set_extension_enabled(key: &str, enabled: bool) {
-    let mut extensions = get_extensions_map();
+    let mut extensions = get_user_extensions_map();
     if let Some(entry) = extensions.get_mut(key) {
         entry.enabled = enabled;
         save_extensions_map(extensions);
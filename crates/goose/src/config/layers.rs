@@ -0,0 +1,312 @@
+//! Layered configuration: built-in defaults < user config < workspace config < env.
+//!
+//! The user layer is the regular `config.yaml` owned by [`Config`]. The workspace
+//! layer is an optional `.goose/config.yaml` found by walking up from the
+//! workspace root, letting a project pin settings without touching the user's
+//! global file. Mappings are merged key by key; any other value is replaced.
+//!
+//! A workspace config comes with the repository, so it is only read once the
+//! user has trusted it: its path must be listed under
+//! [`TRUSTED_WORKSPACES_KEY`] in the user config. Untrusted files are ignored.
+
+use super::base::{Config, ConfigError};
+use notify::{RecommendedWatcher, RecursiveMode, Watcher};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use serde_yaml::Mapping;
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+use utoipa::ToSchema;
+
+pub const WORKSPACE_CONFIG_DIR: &str = ".goose";
+pub const WORKSPACE_CONFIG_FILE: &str = "config.yaml";
+/// User-config key listing the workspace config files the user has trusted.
+pub const TRUSTED_WORKSPACES_KEY: &str = "GOOSE_TRUSTED_WORKSPACES";
+
+/// Built-in defaults, matching the fallbacks used by the code that reads them.
+const BUILTIN_DEFAULTS_YAML: &str = r#"
+GOOSE_AUTO_COMPACT_THRESHOLD: 0.8
+GOOSE_MAX_TURNS: 1000
+GOOSE_GUARDRAILS_ENABLED: true
+GOOSE_LOCAL_ANALYTICS_ENABLED: true
+"#;
+
+#[derive(
+    Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize, ToSchema,
+)]
+#[serde(rename_all = "lowercase")]
+pub enum ConfigSource {
+    Default,
+    User,
+    Workspace,
+    Env,
+}
+
+/// A resolved value and the layer it came from.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, ToSchema)]
+pub struct EffectiveValue {
+    pub key: String,
+    pub value: Value,
+    pub source: ConfigSource,
+    /// Lower-precedence layers that also define this key
+    pub overrides: Vec<ConfigSource>,
+}
+
+/// Sent to subscribers when effective configuration values change.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ConfigChange {
+    pub keys: Vec<String>,
+}
+
+impl ConfigChange {
+    pub fn touches(&self, key: &str) -> bool {
+        self.keys.iter().any(|k| k == key)
+    }
+}
+
+pub fn builtin_defaults() -> Mapping {
+    serde_yaml::from_str(BUILTIN_DEFAULTS_YAML).unwrap_or_default()
+}
+
+/// Finds the closest `.goose/config.yaml` at or above `start`.
+pub fn find_workspace_config(start: &Path) -> Option<PathBuf> {
+    start
+        .ancestors()
+        .map(|dir| dir.join(WORKSPACE_CONFIG_DIR).join(WORKSPACE_CONFIG_FILE))
+        .find(|path| path.is_file())
+}
+
+/// The form a workspace config path is recorded in when trusted.
+pub fn trust_entry(path: &Path) -> String {
+    std::fs::canonicalize(path)
+        .unwrap_or_else(|_| path.to_path_buf())
+        .to_string_lossy()
+        .to_string()
+}
+
+/// Whether the user config lists `path` as a trusted workspace config.
+pub fn is_trusted(user: &Mapping, path: &Path) -> bool {
+    let entry = trust_entry(path);
+    user.get(TRUSTED_WORKSPACES_KEY)
+        .and_then(|v| v.as_sequence())
+        .is_some_and(|trusted| trusted.iter().any(|t| t.as_str() == Some(entry.as_str())))
+}
+
+pub fn load_yaml_file(path: &Path) -> Result<Mapping, ConfigError> {
+    if !path.exists() {
+        return Ok(Mapping::new());
+    }
+    let content = std::fs::read_to_string(path)?;
+    if content.trim().is_empty() {
+        return Ok(Mapping::new());
+    }
+    Ok(serde_yaml::from_str(&content)?)
+}
+
+/// Deep-merges `overlay` into `base`: nested mappings merge, everything else replaces.
+pub fn merge_values(base: &mut serde_yaml::Value, overlay: serde_yaml::Value) {
+    match (base, overlay) {
+        (serde_yaml::Value::Mapping(base), serde_yaml::Value::Mapping(overlay)) => {
+            for (key, value) in overlay {
+                match base.get_mut(&key) {
+                    Some(existing) => merge_values(existing, value),
+                    None => {
+                        base.insert(key, value);
+                    }
+                }
+            }
+        }
+        (base, overlay) => *base = overlay,
+    }
+}
+
+/// Resolves every key across layers, given in increasing precedence.
+pub fn resolve_layers(layers: &[(ConfigSource, Mapping)]) -> BTreeMap<String, EffectiveValue> {
+    let mut resolved: BTreeMap<String, (serde_yaml::Value, ConfigSource, Vec<ConfigSource>)> =
+        BTreeMap::new();
+
+    for (source, mapping) in layers {
+        for (key, value) in mapping {
+            let Some(key) = key.as_str() else {
+                continue;
+            };
+            match resolved.get_mut(key) {
+                Some((existing, current_source, overrides)) => {
+                    merge_values(existing, value.clone());
+                    overrides.push(*current_source);
+                    *current_source = *source;
+                }
+                None => {
+                    resolved.insert(key.to_string(), (value.clone(), *source, Vec::new()));
+                }
+            }
+        }
+    }
+
+    resolved
+        .into_iter()
+        .filter_map(|(key, (value, source, overrides))| {
+            Some((
+                key.clone(),
+                EffectiveValue {
+                    value: serde_json::to_value(value).ok()?,
+                    key,
+                    source,
+                    overrides,
+                },
+            ))
+        })
+        .collect()
+}
+
+/// Keys whose effective value differs between two snapshots.
+pub fn changed_keys(
+    before: &BTreeMap<String, EffectiveValue>,
+    after: &BTreeMap<String, EffectiveValue>,
+) -> Vec<String> {
+    let mut keys: Vec<String> = before
+        .keys()
+        .chain(after.keys())
+        .filter(|key| before.get(*key).map(|v| &v.value) != after.get(*key).map(|v| &v.value))
+        .cloned()
+        .collect();
+    keys.sort();
+    keys.dedup();
+    keys
+}
+
+/// Watches the user and workspace config files and broadcasts changed keys.
+pub struct ConfigWatcher {
+    _watcher: RecommendedWatcher,
+}
+
+impl ConfigWatcher {
+    pub fn start(config: &'static Config) -> Result<Self, ConfigError> {
+        let watched_files: Vec<PathBuf> = [
+            Some(PathBuf::from(config.path())),
+            config.workspace_config_path(),
+        ]
+        .into_iter()
+        .flatten()
+        .collect();
+
+        let snapshot = std::sync::Mutex::new(config.effective_values().unwrap_or_default());
+        let files = watched_files.clone();
+        let mut watcher =
+            notify::recommended_watcher(move |res: Result<notify::Event, notify::Error>| {
+                let Ok(event) = res else {
+                    return;
+                };
+                if !event
+                    .paths
+                    .iter()
+                    .any(|p| files.iter().any(|f| p.ends_with(f) || f.ends_with(p)))
+                {
+                    return;
+                }
+                let Ok(current) = config.effective_values() else {
+                    return;
+                };
+                let mut previous = snapshot.lock().unwrap_or_else(|e| e.into_inner());
+                let keys = changed_keys(&previous, &current);
+                *previous = current;
+                if !keys.is_empty() {
+                    tracing::info!("Configuration reloaded, changed keys: {:?}", keys);
+                    config.notify_change(keys);
+                }
+            })
+            .map_err(|e| ConfigError::WatchError(e.to_string()))?;
+
+        // Watch parent directories so atomic rename-on-save is picked up
+        for file in &watched_files {
+            if let Some(dir) = file.parent().filter(|dir| dir.exists()) {
+                watcher
+                    .watch(dir, RecursiveMode::NonRecursive)
+                    .map_err(|e| ConfigError::WatchError(e.to_string()))?;
+            }
+        }
+
+        Ok(Self { _watcher: watcher })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+    use tempfile::TempDir;
+
+    fn yaml(s: &str) -> Mapping {
+        serde_yaml::from_str(s).unwrap()
+    }
+
+    #[test]
+    fn test_resolve_layers_precedence_and_sources() {
+        let resolved = resolve_layers(&[
+            (ConfigSource::Default, yaml("GOOSE_MAX_TURNS: 1000\nA: 1")),
+            (
+                ConfigSource::User,
+                yaml("GOOSE_MAX_TURNS: 50\nGOOSE_MODEL: gpt-4o"),
+            ),
+            (ConfigSource::Workspace, yaml("GOOSE_MAX_TURNS: 10")),
+        ]);
+
+        let turns = &resolved["GOOSE_MAX_TURNS"];
+        assert_eq!(turns.value, json!(10));
+        assert_eq!(turns.source, ConfigSource::Workspace);
+        assert_eq!(
+            turns.overrides,
+            vec![ConfigSource::Default, ConfigSource::User]
+        );
+        assert_eq!(resolved["GOOSE_MODEL"].source, ConfigSource::User);
+        assert_eq!(resolved["A"].source, ConfigSource::Default);
+    }
+
+    #[test]
+    fn test_nested_mappings_are_merged() {
+        let resolved = resolve_layers(&[
+            (
+                ConfigSource::User,
+                yaml("guardrails:\n  pii: true\n  secrets: true\n  topics: [a, b]"),
+            ),
+            (
+                ConfigSource::Workspace,
+                yaml("guardrails:\n  pii: false\n  topics: [c]"),
+            ),
+        ]);
+
+        assert_eq!(
+            resolved["guardrails"].value,
+            json!({"pii": false, "secrets": true, "topics": ["c"]})
+        );
+    }
+
+    #[test]
+    fn test_changed_keys() {
+        let before = resolve_layers(&[(ConfigSource::User, yaml("A: 1\nB: 2\nC: 3"))]);
+        let after = resolve_layers(&[(ConfigSource::User, yaml("A: 1\nB: 5\nD: 4"))]);
+        assert_eq!(changed_keys(&before, &after), vec!["B", "C", "D"]);
+    }
+
+    #[test]
+    fn test_find_workspace_config_walks_up() {
+        let root = TempDir::new().unwrap();
+        let nested = root.path().join("src").join("deep");
+        std::fs::create_dir_all(&nested).unwrap();
+        assert_eq!(find_workspace_config(&nested), None);
+
+        let config_dir = root.path().join(WORKSPACE_CONFIG_DIR);
+        std::fs::create_dir_all(&config_dir).unwrap();
+        std::fs::write(config_dir.join(WORKSPACE_CONFIG_FILE), "A: 1\n").unwrap();
+        assert_eq!(
+            find_workspace_config(&nested),
+            Some(config_dir.join(WORKSPACE_CONFIG_FILE))
+        );
+    }
+
+    #[test]
+    fn test_builtin_defaults_parse() {
+        let defaults = builtin_defaults();
+        assert!(defaults.contains_key("GOOSE_AUTO_COMPACT_THRESHOLD"));
+    }
+}
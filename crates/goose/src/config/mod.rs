@@ -3,9 +3,11 @@ pub mod declarative_providers;
 mod experiments;
 pub mod extensions;
 pub mod goose_mode;
+pub mod layers;
 mod migrations;
 pub mod paths;
 pub mod permission;
+pub mod profiles;
 pub mod search_path;
 pub mod signup_openrouter;
 pub mod signup_tetrate;
@@ -20,6 +22,7 @@ pub use extensions::{
     set_extension, set_extension_enabled, ExtensionEntry,
 };
 pub use goose_mode::GooseMode;
+pub use layers::{ConfigChange, ConfigSource, EffectiveValue};
 pub use permission::PermissionManager;
 pub use signup_openrouter::configure_openrouter;
 pub use signup_tetrate::configure_tetrate;
@@ -0,0 +1,280 @@
+//! Named settings profiles that bundle the knobs usually flipped together.
+//!
+//! Three built-in profiles ("safe", "fast" and "autonomous") are always
+//! available. Custom profiles, and overrides of the built-in ones, are stored
+//! in the config file under [`PROFILES_CONFIG_KEY`].
+
+use crate::agents::{ExecutionMode, ReasoningMode};
+use crate::approval::ApprovalPreset;
+use crate::config::{Config, ConfigError, GooseMode};
+use crate::guardrails::GuardrailsStrictness;
+use crate::session::extension_data::ExtensionState;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
+
+pub const PROFILES_CONFIG_KEY: &str = "GOOSE_SETTINGS_PROFILES";
+
+/// Number of profile switches kept in a session's audit history
+const MAX_PROFILE_HISTORY: usize = 50;
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, ToSchema)]
+pub struct SettingsProfile {
+    pub name: String,
+    #[serde(default)]
+    pub description: String,
+    #[schema(value_type = String)]
+    pub goose_mode: GooseMode,
+    #[schema(value_type = String)]
+    pub approval_preset: ApprovalPreset,
+    /// Spending limit in USD for the session; no limit when unset
+    #[serde(default)]
+    pub budget_usd: Option<f64>,
+    #[schema(value_type = String)]
+    pub reasoning_mode: ReasoningMode,
+    /// Agent core: freeform or structured (code, test, fix)
+    #[schema(value_type = String)]
+    pub core: ExecutionMode,
+    #[schema(value_type = String)]
+    pub guardrails: GuardrailsStrictness,
+}
+
+impl SettingsProfile {
+    pub fn is_builtin(name: &str) -> bool {
+        builtin_profiles().iter().any(|p| p.name == name)
+    }
+
+    pub fn summary(&self) -> String {
+        format!(
+            "mode {}, approvals {}, budget {}, reasoning {}, core {}, guardrails {}",
+            serde_label(&self.goose_mode),
+            self.approval_preset,
+            self.budget_usd
+                .map(|b| format!("${:.2}", b))
+                .unwrap_or_else(|| "unlimited".to_string()),
+            self.reasoning_mode,
+            self.core,
+            serde_label(&self.guardrails),
+        )
+    }
+}
+
+fn serde_label<T: Serialize>(value: &T) -> String {
+    serde_json::to_value(value)
+        .ok()
+        .and_then(|v| v.as_str().map(str::to_string))
+        .unwrap_or_default()
+}
+
+pub fn builtin_profiles() -> Vec<SettingsProfile> {
+    vec![
+        SettingsProfile {
+            name: "safe".to_string(),
+            description: "Cautious interactive use: every change is approved and checked"
+                .to_string(),
+            goose_mode: GooseMode::Approve,
+            approval_preset: ApprovalPreset::Paranoid,
+            budget_usd: Some(2.0),
+            reasoning_mode: ReasoningMode::ChainOfThought,
+            core: ExecutionMode::Freeform,
+            guardrails: GuardrailsStrictness::Strict,
+        },
+        SettingsProfile {
+            name: "fast".to_string(),
+            description: "Quick iteration with light-touch approvals".to_string(),
+            goose_mode: GooseMode::SmartApprove,
+            approval_preset: ApprovalPreset::Safe,
+            budget_usd: None,
+            reasoning_mode: ReasoningMode::Standard,
+            core: ExecutionMode::Freeform,
+            guardrails: GuardrailsStrictness::Standard,
+        },
+        SettingsProfile {
+            name: "autonomous".to_string(),
+            description: "Hands-off runs with validation gates and a spending cap".to_string(),
+            goose_mode: GooseMode::Auto,
+            approval_preset: ApprovalPreset::Autopilot,
+            budget_usd: Some(25.0),
+            reasoning_mode: ReasoningMode::ReAct,
+            core: ExecutionMode::Structured,
+            guardrails: GuardrailsStrictness::Standard,
+        },
+    ]
+}
+
+fn stored_profiles(config: &Config) -> Vec<SettingsProfile> {
+    config
+        .get_param::<Vec<SettingsProfile>>(PROFILES_CONFIG_KEY)
+        .unwrap_or_default()
+}
+
+/// Built-in profiles (with any stored overrides applied) followed by custom ones.
+pub fn list_profiles(config: &Config) -> Vec<SettingsProfile> {
+    let stored = stored_profiles(config);
+    let mut profiles: Vec<SettingsProfile> = builtin_profiles()
+        .into_iter()
+        .map(|builtin| {
+            stored
+                .iter()
+                .find(|p| p.name == builtin.name)
+                .cloned()
+                .unwrap_or(builtin)
+        })
+        .collect();
+    profiles.extend(
+        stored
+            .into_iter()
+            .filter(|p| !SettingsProfile::is_builtin(&p.name)),
+    );
+    profiles
+}
+
+pub fn get_profile(config: &Config, name: &str) -> Option<SettingsProfile> {
+    list_profiles(config).into_iter().find(|p| p.name == name)
+}
+
+/// Profiles stored in the user config alone, so that saving never copies a
+/// workspace's profiles into the user's config.
+fn user_profiles(config: &Config) -> Vec<SettingsProfile> {
+    config
+        .get_user_param::<Vec<SettingsProfile>>(PROFILES_CONFIG_KEY)
+        .unwrap_or_default()
+}
+
+/// Creates or replaces a profile. Saving a built-in name overrides it.
+pub fn save_profile(config: &Config, profile: SettingsProfile) -> Result<(), ConfigError> {
+    let mut stored = user_profiles(config);
+    stored.retain(|p| p.name != profile.name);
+    stored.push(profile);
+    config.set_param(PROFILES_CONFIG_KEY, stored)
+}
+
+/// Removes a custom profile, or resets a built-in one to its defaults.
+/// Returns false if there was nothing stored under that name.
+pub fn delete_profile(config: &Config, name: &str) -> Result<bool, ConfigError> {
+    let mut stored = user_profiles(config);
+    let before = stored.len();
+    stored.retain(|p| p.name != name);
+    if stored.len() == before {
+        return Ok(false);
+    }
+    config.set_param(PROFILES_CONFIG_KEY, stored)?;
+    Ok(true)
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, ToSchema)]
+pub struct ProfileSwitch {
+    pub profile: String,
+    /// Where the switch came from, e.g. "slash_command" or "api"
+    pub source: String,
+    pub switched_at: DateTime<Utc>,
+}
+
+/// The profile active in a session and an audit trail of switches,
+/// stored in the session's extension data.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize, ToSchema)]
+pub struct ActiveProfileState {
+    pub active: Option<String>,
+    #[serde(default)]
+    pub history: Vec<ProfileSwitch>,
+}
+
+impl ExtensionState for ActiveProfileState {
+    const EXTENSION_NAME: &'static str = "settings_profile";
+    const VERSION: &'static str = "v0";
+}
+
+impl ActiveProfileState {
+    pub fn record_switch(&mut self, profile: &str, source: &str) {
+        self.active = Some(profile.to_string());
+        self.history.push(ProfileSwitch {
+            profile: profile.to_string(),
+            source: source.to_string(),
+            switched_at: Utc::now(),
+        });
+        if self.history.len() > MAX_PROFILE_HISTORY {
+            let excess = self.history.len() - MAX_PROFILE_HISTORY;
+            self.history.drain(..excess);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::NamedTempFile;
+
+    fn test_config() -> (Config, NamedTempFile, NamedTempFile) {
+        let config_file = NamedTempFile::new().unwrap();
+        let secrets_file = NamedTempFile::new().unwrap();
+        let config =
+            Config::new_with_file_secrets(config_file.path(), secrets_file.path()).unwrap();
+        (config, config_file, secrets_file)
+    }
+
+    #[test]
+    fn test_builtin_profiles_are_listed() {
+        let (config, _c, _s) = test_config();
+        let names: Vec<String> = list_profiles(&config).into_iter().map(|p| p.name).collect();
+        assert_eq!(names, vec!["safe", "fast", "autonomous"]);
+    }
+
+    #[test]
+    fn test_custom_profiles_and_overrides() {
+        let (config, _c, _s) = test_config();
+
+        let mut review = get_profile(&config, "safe").unwrap();
+        review.name = "review".to_string();
+        review.budget_usd = Some(1.0);
+        save_profile(&config, review.clone()).unwrap();
+
+        let mut autonomous = get_profile(&config, "autonomous").unwrap();
+        autonomous.budget_usd = Some(100.0);
+        save_profile(&config, autonomous).unwrap();
+
+        assert_eq!(get_profile(&config, "review"), Some(review));
+        assert_eq!(
+            get_profile(&config, "autonomous").unwrap().budget_usd,
+            Some(100.0)
+        );
+        assert_eq!(list_profiles(&config).len(), 4);
+
+        assert!(delete_profile(&config, "autonomous").unwrap());
+        assert_eq!(
+            get_profile(&config, "autonomous").unwrap().budget_usd,
+            Some(25.0)
+        );
+        assert!(!delete_profile(&config, "fast").unwrap());
+    }
+
+    #[test]
+    fn test_saving_does_not_copy_workspace_profiles() {
+        let (config, _c, _s) = test_config();
+        let workspace = tempfile::tempdir().unwrap();
+        config.set_workspace_dir(Some(workspace.path().to_path_buf()));
+        let mut pinned = get_profile(&config, "safe").unwrap();
+        pinned.name = "pinned".to_string();
+        config
+            .set_workspace_param(PROFILES_CONFIG_KEY, vec![pinned])
+            .unwrap();
+        assert!(get_profile(&config, "pinned").is_some());
+
+        let mut mine = get_profile(&config, "fast").unwrap();
+        mine.name = "mine".to_string();
+        save_profile(&config, mine).unwrap();
+
+        let user: Vec<SettingsProfile> = config.get_user_param(PROFILES_CONFIG_KEY).unwrap();
+        let names: Vec<&str> = user.iter().map(|p| p.name.as_str()).collect();
+        assert_eq!(names, vec!["mine"]);
+    }
+
+    #[test]
+    fn test_profile_history_is_capped() {
+        let mut state = ActiveProfileState::default();
+        for i in 0..(MAX_PROFILE_HISTORY + 5) {
+            state.record_switch(if i % 2 == 0 { "safe" } else { "fast" }, "api");
+        }
+        assert_eq!(state.history.len(), MAX_PROFILE_HISTORY);
+        assert_eq!(state.active.as_deref(), Some("safe"));
+    }
+}
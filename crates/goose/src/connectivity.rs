@@ -0,0 +1,289 @@
+//! Offline detection and the degradation policy that goes with it.
+//!
+//! A provider call failing with a network error triggers a probe of a few
+//! well-known endpoints (`GOOSE_CONNECTIVITY_PROBE`). Only if none answers
+//! does goose count as offline; a provider that is down on its own stays a
+//! provider problem for the circuit breaker. While offline the probe repeats
+//! in the background and flips the state back once the network returns.
+//!
+//! While offline:
+//! - provider calls switch to `GOOSE_OFFLINE_PROVIDER` (with
+//!   `GOOSE_OFFLINE_MODEL`) when one is configured, typically a local model
+//! - otherwise background work waits for the network to return, up to
+//!   `GOOSE_OFFLINE_QUEUE_TIMEOUT` seconds, and interactive turns fail with
+//!   an error that says goose is offline
+//! - cloud-only subsystems, such as telemetry and a remote Mem0, stand down
+
+use crate::config::Config;
+use crate::providers::errors::ProviderError;
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Arc, OnceLock};
+use std::time::Duration;
+use tokio::sync::{watch, Mutex};
+use utoipa::ToSchema;
+
+const DEFAULT_PROBE_TARGETS: &str = "1.1.1.1:443,8.8.8.8:443";
+const PROBE_TIMEOUT: Duration = Duration::from_secs(3);
+/// How often the network is probed while offline
+const PROBE_INTERVAL: Duration = Duration::from_secs(10);
+const DEFAULT_QUEUE_TIMEOUT_SECS: u64 = 30 * 60;
+/// Subsystems that need the internet and stand down while offline
+pub const CLOUD_SUBSYSTEMS: &[&str] = &["telemetry", "mem0"];
+
+/// Fragments of reqwest/hyper errors that mean the request never reached
+/// the provider
+const NETWORK_ERROR_MARKERS: &[&str] = &[
+    "error sending request",
+    "failed to connect",
+    "connection failed",
+    "connection refused",
+    "connection reset",
+    "dns error",
+    "failed to lookup address",
+    "network is unreachable",
+    "no route to host",
+];
+
+#[derive(Debug, Clone, Serialize, ToSchema)]
+pub struct ConnectivityStatus {
+    pub online: bool,
+    /// When the current state began
+    pub since: DateTime<Utc>,
+    /// The failure that put goose offline
+    pub reason: Option<String>,
+    /// Provider used instead of the configured one while offline
+    pub offline_provider: Option<String>,
+    /// Provider calls waiting for the network to return
+    pub queued_calls: usize,
+    /// Subsystems standing down until the network returns
+    pub disabled_subsystems: Vec<String>,
+}
+
+pub struct Connectivity {
+    probe_targets: Vec<String>,
+    status: watch::Sender<ConnectivityStatus>,
+    queued: AtomicUsize,
+    /// Held while a probe runs so concurrent failures share one probe
+    probing: Mutex<()>,
+}
+
+static CONNECTIVITY: OnceLock<Arc<Connectivity>> = OnceLock::new();
+
+pub fn global() -> &'static Arc<Connectivity> {
+    CONNECTIVITY.get_or_init(|| {
+        let targets = Config::global()
+            .get_param::<String>("GOOSE_CONNECTIVITY_PROBE")
+            .unwrap_or_else(|_| DEFAULT_PROBE_TARGETS.to_string());
+        Arc::new(Connectivity::new(
+            targets
+                .split(',')
+                .map(str::trim)
+                .filter(|t| !t.is_empty())
+                .map(str::to_string)
+                .collect(),
+        ))
+    })
+}
+
+/// Whether goose is known to be offline. Cheap; never probes.
+pub fn is_offline() -> bool {
+    CONNECTIVITY
+        .get()
+        .is_some_and(|connectivity| !connectivity.status().online)
+}
+
+/// Whether `error` means the request never reached the provider.
+pub fn is_network_error(error: &ProviderError) -> bool {
+    match error {
+        ProviderError::RequestFailed(message) => {
+            let message = message.to_lowercase();
+            NETWORK_ERROR_MARKERS
+                .iter()
+                .any(|marker| message.contains(marker))
+        }
+        _ => false,
+    }
+}
+
+pub fn offline_error() -> ProviderError {
+    ProviderError::RequestFailed(
+        "goose is offline: no network connection. Set GOOSE_OFFLINE_PROVIDER to a local \
+         provider to keep working offline."
+            .to_string(),
+    )
+}
+
+pub fn queue_timeout() -> Duration {
+    Duration::from_secs(
+        Config::global()
+            .get_param::<u64>("GOOSE_OFFLINE_QUEUE_TIMEOUT")
+            .unwrap_or(DEFAULT_QUEUE_TIMEOUT_SECS),
+    )
+}
+
+impl Connectivity {
+    pub fn new(probe_targets: Vec<String>) -> Self {
+        let (status, _) = watch::channel(ConnectivityStatus {
+            online: true,
+            since: Utc::now(),
+            reason: None,
+            offline_provider: None,
+            queued_calls: 0,
+            disabled_subsystems: Vec::new(),
+        });
+        Self {
+            probe_targets,
+            status,
+            queued: AtomicUsize::new(0),
+            probing: Mutex::new(()),
+        }
+    }
+
+    pub fn status(&self) -> ConnectivityStatus {
+        self.status.borrow().clone()
+    }
+
+    pub fn subscribe(&self) -> watch::Receiver<ConnectivityStatus> {
+        self.status.subscribe()
+    }
+
+    /// Records which provider stands in while offline, for the status.
+    pub fn set_offline_provider(&self, provider: Option<String>) {
+        self.status.send_if_modified(|status| {
+            let changed = status.offline_provider != provider;
+            status.offline_provider = provider;
+            changed
+        });
+    }
+
+    async fn probe(&self) -> bool {
+        for target in &self.probe_targets {
+            let connect = tokio::net::TcpStream::connect(target.as_str());
+            if let Ok(Ok(_)) = tokio::time::timeout(PROBE_TIMEOUT, connect).await {
+                return true;
+            }
+        }
+        self.probe_targets.is_empty()
+    }
+
+    fn set_online(&self, online: bool, reason: Option<String>) {
+        self.status.send_if_modified(|status| {
+            if status.online == online {
+                return false;
+            }
+            status.online = online;
+            status.since = Utc::now();
+            status.reason = reason;
+            status.disabled_subsystems = if online {
+                Vec::new()
+            } else {
+                CLOUD_SUBSYSTEMS.iter().map(|s| s.to_string()).collect()
+            };
+            if online {
+                status.offline_provider = None;
+                tracing::info!("Network connection restored");
+            } else {
+                tracing::warn!(
+                    "No network connection; degrading to offline mode: {}",
+                    status.reason.as_deref().unwrap_or("probe failed")
+                );
+            }
+            true
+        });
+    }
+
+    /// Called with a provider's network error. Probes the network and
+    /// returns whether goose is offline.
+    pub async fn confirm_offline(self: &Arc<Self>, error: &ProviderError) -> bool {
+        let _probing = self.probing.lock().await;
+        if !self.status().online {
+            return true;
+        }
+        if self.probe().await {
+            return false;
+        }
+        self.set_online(false, Some(error.to_string()));
+        let connectivity = self.clone();
+        tokio::spawn(async move { connectivity.watch_for_recovery().await });
+        true
+    }
+
+    async fn watch_for_recovery(&self) {
+        loop {
+            tokio::time::sleep(PROBE_INTERVAL).await;
+            if self.probe().await {
+                self.set_online(true, None);
+                return;
+            }
+        }
+    }
+
+    /// Holds a provider call until the network returns. Returns false if it
+    /// did not return within `timeout`.
+    pub async fn wait_online(&self, timeout: Duration) -> bool {
+        let mut status = self.subscribe();
+        self.queued.fetch_add(1, Ordering::SeqCst);
+        self.publish_queued();
+        let online = tokio::time::timeout(timeout, status.wait_for(|s| s.online))
+            .await
+            .is_ok_and(|result| result.is_ok());
+        self.queued.fetch_sub(1, Ordering::SeqCst);
+        self.publish_queued();
+        online
+    }
+
+    fn publish_queued(&self) {
+        let queued = self.queued.load(Ordering::SeqCst);
+        self.status
+            .send_modify(|status| status.queued_calls = queued);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_only_connection_failures_count_as_network_errors() {
+        assert!(is_network_error(&ProviderError::RequestFailed(
+            "error sending request for url (https://api.openai.com/v1/chat/completions)".into()
+        )));
+        assert!(is_network_error(&ProviderError::RequestFailed(
+            "dns error: failed to lookup address information".into()
+        )));
+        assert!(!is_network_error(&ProviderError::RequestFailed(
+            "Provider did not respond within 600s".into()
+        )));
+        assert!(!is_network_error(&ProviderError::ServerError(
+            "connection refused".into()
+        )));
+    }
+
+    #[tokio::test]
+    async fn test_goes_offline_when_probe_fails_and_recovers() {
+        let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+        let reachable = listener.local_addr().unwrap().to_string();
+        let error = ProviderError::RequestFailed("error sending request".into());
+
+        let connectivity = Arc::new(Connectivity::new(vec![reachable]));
+        assert!(!connectivity.confirm_offline(&error).await);
+        assert!(connectivity.status().online);
+
+        drop(listener);
+        let unreachable = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+        let target = unreachable.local_addr().unwrap().to_string();
+        drop(unreachable);
+        let connectivity = Arc::new(Connectivity::new(vec![target]));
+        assert!(connectivity.confirm_offline(&error).await);
+        let status = connectivity.status();
+        assert!(!status.online);
+        assert_eq!(status.disabled_subsystems, CLOUD_SUBSYSTEMS);
+
+        assert!(!connectivity.wait_online(Duration::from_millis(20)).await);
+        connectivity.set_online(true, None);
+        assert!(connectivity.wait_online(Duration::from_millis(20)).await);
+        assert_eq!(connectivity.status().queued_calls, 0);
+    }
+}
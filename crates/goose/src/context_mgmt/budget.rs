@@ -0,0 +1,322 @@
+//! Token budget for a single provider call.
+//!
+//! The system prompt, tools, MOIM and conversation are counted before the call
+//! and room is reserved for the response. Blocks the agent injects into the
+//! system prompt (reflections, recalled memories, reasoning instructions) are
+//! optional: when the call would not fit, the lowest-priority blocks are left
+//! out instead of waiting for the provider to reject the request.
+
+use crate::config::Config;
+use crate::conversation::message::Message;
+use crate::model::ModelConfig;
+use crate::token_counter::TokenCounter;
+use rmcp::model::Tool;
+use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
+
+/// Share of the context window kept free for the response when the model has
+/// no configured max tokens.
+pub const DEFAULT_RESPONSE_RESERVE_RATIO: f64 = 0.1;
+pub const MIN_RESPONSE_RESERVE: usize = 1024;
+
+/// Injected blocks in the order they are dropped, lowest priority first.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum BlockPriority {
+    Reflections,
+    Memories,
+    Reasoning,
+}
+
+impl BlockPriority {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            BlockPriority::Reflections => "reflections",
+            BlockPriority::Memories => "memories",
+            BlockPriority::Reasoning => "reasoning",
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct InjectedBlock {
+    pub priority: BlockPriority,
+    /// What produced the block, e.g. "knowledge", for context attribution
+    pub source: &'static str,
+    pub text: String,
+}
+
+impl InjectedBlock {
+    pub fn new(priority: BlockPriority, text: impl Into<String>) -> Self {
+        Self {
+            priority,
+            source: priority.as_str(),
+            text: text.into(),
+        }
+    }
+
+    pub fn with_source(mut self, source: &'static str) -> Self {
+        self.source = source;
+        self
+    }
+}
+
+/// Tokens one part of the request took up.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
+pub struct ComponentTokens {
+    /// `system_prompt`, `tools:<extension>`, `moim`, `conversation`, or
+    /// the source of an injected block
+    pub component: String,
+    pub tokens: usize,
+}
+
+/// `base` followed by every block, as sent when nothing needs trimming.
+pub fn with_blocks(base: &str, blocks: &[InjectedBlock]) -> String {
+    let mut prompt = base.to_string();
+    for block in blocks {
+        prompt.push_str("\n\n");
+        prompt.push_str(&block.text);
+    }
+    prompt
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+pub struct ContextUsage {
+    pub context_limit: usize,
+    pub response_reserve: usize,
+    pub system_prompt: usize,
+    /// Injected blocks that were kept
+    pub injected: usize,
+    pub tools: usize,
+    pub moim: usize,
+    pub conversation: usize,
+    pub total: usize,
+    /// Blocks left out to make the call fit
+    pub trimmed: Vec<BlockPriority>,
+    /// Tokens per injected block source and per extension's tools
+    pub breakdown: Vec<ComponentTokens>,
+}
+
+impl ContextUsage {
+    /// Every part of the request with its tokens, for attribution.
+    pub fn components(&self) -> Vec<ComponentTokens> {
+        let fixed = [
+            ("system_prompt", self.system_prompt),
+            ("moim", self.moim),
+            ("conversation", self.conversation),
+        ];
+        let mut components: Vec<ComponentTokens> = fixed
+            .into_iter()
+            .map(|(component, tokens)| ComponentTokens {
+                component: component.to_string(),
+                tokens,
+            })
+            .collect();
+        components.extend(self.breakdown.iter().cloned());
+        components
+    }
+
+    /// Whether the call still overflows after trimming every optional block.
+    pub fn overflows(&self) -> bool {
+        self.total + self.response_reserve > self.context_limit
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ContextBudget {
+    pub context_limit: usize,
+    pub response_reserve: usize,
+}
+
+impl ContextBudget {
+    /// Budget for `model`, reserving its max output tokens, or
+    /// `GOOSE_RESPONSE_RESERVE` tokens, or a share of the window.
+    pub fn for_model(model: &ModelConfig) -> Self {
+        let context_limit = model.context_limit();
+        let response_reserve = Config::global()
+            .get_param::<usize>("GOOSE_RESPONSE_RESERVE")
+            .ok()
+            .or_else(|| model.max_tokens.map(|tokens| tokens.max(0) as usize))
+            .unwrap_or_else(|| {
+                ((context_limit as f64 * DEFAULT_RESPONSE_RESERVE_RATIO) as usize)
+                    .max(MIN_RESPONSE_RESERVE)
+            });
+        Self {
+            context_limit,
+            response_reserve: response_reserve.min(context_limit / 2),
+        }
+    }
+
+    /// Builds the system prompt for the call from `base` and the injected
+    /// blocks that fit, dropping the lowest-priority blocks first.
+    /// `conversation` is what the session holds and `with_moim` what is sent.
+    pub fn fit(
+        &self,
+        counter: &TokenCounter,
+        base: &str,
+        blocks: &[InjectedBlock],
+        conversation: &[Message],
+        with_moim: &[Message],
+        tools: &[Tool],
+    ) -> (String, ContextUsage) {
+        let conversation_tokens = counter.count_chat_tokens("", conversation, &[]);
+        let sent_tokens = counter.count_chat_tokens("", with_moim, &[]);
+        let mut usage = ContextUsage {
+            context_limit: self.context_limit,
+            response_reserve: self.response_reserve,
+            system_prompt: counter.count_tokens(base),
+            tools: counter.count_tokens_for_tools(tools),
+            moim: sent_tokens.saturating_sub(conversation_tokens),
+            conversation: conversation_tokens,
+            ..ContextUsage::default()
+        };
+        usage.breakdown = tools_by_extension(counter, tools);
+        let fixed = usage.system_prompt + usage.tools + sent_tokens;
+        let available = self
+            .context_limit
+            .saturating_sub(self.response_reserve)
+            .saturating_sub(fixed);
+
+        let block_tokens: Vec<usize> = blocks
+            .iter()
+            .map(|block| counter.count_tokens(&block.text))
+            .collect();
+        let mut keep = vec![true; blocks.len()];
+        let mut injected: usize = block_tokens.iter().sum();
+        let mut by_priority: Vec<usize> = (0..blocks.len()).collect();
+        by_priority.sort_by_key(|&i| blocks[i].priority);
+        for i in by_priority {
+            if injected <= available {
+                break;
+            }
+            keep[i] = false;
+            injected -= block_tokens[i];
+            usage.trimmed.push(blocks[i].priority);
+        }
+
+        let kept: Vec<InjectedBlock> = blocks
+            .iter()
+            .zip(&keep)
+            .filter(|(_, keep)| **keep)
+            .map(|(block, _)| block.clone())
+            .collect();
+        let prompt = with_blocks(base, &kept);
+        for ((block, tokens), keep) in blocks.iter().zip(&block_tokens).zip(&keep) {
+            if *keep {
+                add_tokens(&mut usage.breakdown, block.source, *tokens);
+            }
+        }
+        usage.injected = injected;
+        usage.total = fixed + injected;
+        (prompt, usage)
+    }
+}
+
+fn add_tokens(breakdown: &mut Vec<ComponentTokens>, component: &str, tokens: usize) {
+    match breakdown.iter_mut().find(|c| c.component == component) {
+        Some(existing) => existing.tokens += tokens,
+        None => breakdown.push(ComponentTokens {
+            component: component.to_string(),
+            tokens,
+        }),
+    }
+}
+
+/// Tool schema tokens per extension, taken from the `extension__tool`
+/// naming.
+fn tools_by_extension(counter: &TokenCounter, tools: &[Tool]) -> Vec<ComponentTokens> {
+    let mut groups: Vec<(String, Vec<Tool>)> = Vec::new();
+    for tool in tools {
+        let extension = tool
+            .name
+            .split_once("__")
+            .map_or("other", |(extension, _)| extension);
+        match groups.iter_mut().find(|(name, _)| name == extension) {
+            Some((_, group)) => group.push(tool.clone()),
+            None => groups.push((extension.to_string(), vec![tool.clone()])),
+        }
+    }
+    groups
+        .into_iter()
+        .map(|(extension, group)| ComponentTokens {
+            component: format!("tools:{}", extension),
+            tokens: counter.count_tokens_for_tools(&group),
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::token_counter::create_token_counter;
+
+    fn words(n: usize) -> String {
+        vec!["context"; n].join(" ")
+    }
+
+    #[tokio::test]
+    async fn test_lowest_priority_blocks_are_trimmed_first() {
+        let counter = create_token_counter().await.unwrap();
+        let budget = ContextBudget {
+            context_limit: 500,
+            response_reserve: 100,
+        };
+        let blocks = vec![
+            InjectedBlock::new(BlockPriority::Reasoning, words(100)),
+            InjectedBlock::new(BlockPriority::Reflections, words(150)),
+            InjectedBlock::new(BlockPriority::Memories, words(150)),
+        ];
+        let conversation = vec![Message::user().with_text(words(50))];
+
+        let (prompt, usage) =
+            budget.fit(&counter, "base", &blocks, &conversation, &conversation, &[]);
+        assert_eq!(usage.trimmed, vec![BlockPriority::Reflections]);
+        assert!(!usage.overflows());
+        assert!(prompt.starts_with("base\n\n"));
+        assert_eq!(prompt.matches("context").count(), 250);
+        assert_eq!(usage.moim, 0);
+    }
+
+    #[tokio::test]
+    async fn test_everything_fits_without_trimming() {
+        let counter = create_token_counter().await.unwrap();
+        let budget = ContextBudget {
+            context_limit: 10_000,
+            response_reserve: 1_000,
+        };
+        let blocks = vec![
+            InjectedBlock::new(BlockPriority::Memories, "- likes tea"),
+            InjectedBlock::new(BlockPriority::Memories, "README.md").with_source("attachments"),
+        ];
+        let conversation = vec![Message::user().with_text("hi")];
+        let with_moim = vec![
+            Message::user().with_text("hi"),
+            Message::user().with_text(words(20)),
+        ];
+
+        let (prompt, usage) = budget.fit(&counter, "base", &blocks, &conversation, &with_moim, &[]);
+        assert_eq!(prompt, "base\n\n- likes tea\n\nREADME.md");
+        assert!(usage.trimmed.is_empty());
+        assert!(usage.moim >= 20);
+        assert_eq!(
+            usage.total,
+            usage.system_prompt + usage.injected + usage.conversation + usage.moim
+        );
+        let components = usage.components();
+        let sources: Vec<&str> = components.iter().map(|c| c.component.as_str()).collect();
+        assert_eq!(
+            sources,
+            [
+                "system_prompt",
+                "moim",
+                "conversation",
+                "memories",
+                "attachments"
+            ]
+        );
+        assert_eq!(
+            components.iter().skip(3).map(|c| c.tokens).sum::<usize>(),
+            usage.injected
+        );
+    }
+}
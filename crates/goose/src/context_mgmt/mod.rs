@@ -1,9 +1,13 @@
+pub mod budget;
+pub mod summary_misses;
+
 use crate::conversation::message::{ActionRequiredData, MessageMetadata};
 use crate::conversation::message::{Message, MessageContent};
 use crate::conversation::{merge_consecutive_messages, Conversation};
 use crate::prompt_template::render_template;
 use crate::providers::base::{Provider, ProviderUsage};
 use crate::providers::errors::ProviderError;
+use crate::providers::task_routing::{self, TaskCategory};
 use crate::{config::Config, token_counter::create_token_counter};
 use anyhow::Result;
 use indoc::indoc;
@@ -34,6 +38,8 @@ Just continue the conversation naturally based on the summarized context";
 #[derive(Serialize)]
 struct SummarizeContext {
     messages: String,
+    /// Details earlier summaries dropped and the agent then needed
+    missed: Vec<String>,
 }
 
 /// Compact messages by summarizing them
@@ -290,6 +296,7 @@ async fn do_compact(
 
         let context = SummarizeContext {
             messages: messages_text,
+            missed: summary_misses::global().lock().unwrap().guidance(),
         };
 
         let system_prompt = render_template("compaction.md", &context)?;
@@ -298,8 +305,15 @@ async fn do_compact(
             .with_text("Please summarize the conversation history provided in the system prompt.");
         let summarization_request = vec![user_message];
 
-        match provider
-            .complete_fast(session_id, &system_prompt, &summarization_request, &[])
+        match task_routing::global()
+            .complete(
+                TaskCategory::Compaction,
+                provider,
+                session_id,
+                &system_prompt,
+                &summarization_request,
+                &[],
+            )
             .await
         {
             Ok((mut response, mut provider_usage)) => {
@@ -488,8 +502,15 @@ pub async fn summarize_tool_call(
 
             "#};
 
-    let (mut response, _) = provider
-        .complete_fast(session_id, system_prompt, &summarization_request, &[])
+    let (mut response, _) = task_routing::global()
+        .complete(
+            TaskCategory::Compaction,
+            provider,
+            session_id,
+            system_prompt,
+            &summarization_request,
+            &[],
+        )
         .await?;
 
     response.role = Role::User;
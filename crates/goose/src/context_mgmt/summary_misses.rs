@@ -0,0 +1,281 @@
+//! Measuring what compaction summaries leave out.
+//!
+//! Compaction hides the summarized messages from the agent but keeps them in
+//! the conversation. So a summary "miss" can be found in the conversation
+//! itself: after the latest compaction, the agent either
+//! - repeats a tool call that was already made before it (a re-read), or
+//! - asks the user something the user already answered before it (a re-ask).
+//!
+//! Each miss is attributed to the compaction that hid the information. Once
+//! the next compaction happens, the previous one is scored as an attempt of
+//! the summarization prompt in the evolution [`MetricsTracker`]. Recent
+//! misses are also added to the next summarization prompt so that details of
+//! the same kind are kept.
+
+use crate::agents::evolution::MetricsTracker;
+use crate::conversation::message::{Message, MessageContent};
+use rmcp::model::Role;
+use serde::Serialize;
+use std::collections::{HashMap, HashSet, VecDeque};
+use std::sync::{Mutex, OnceLock};
+
+/// Prompt id the summarization prompt is tracked under
+pub const SUMMARY_PROMPT_ID: &str = "compaction.md";
+/// How many recent misses are passed on to the next summary
+const GUIDANCE_LIMIT: usize = 5;
+/// Words a question must share with an earlier user message to count as
+/// asking for it again
+const MIN_SHARED_WORDS: usize = 3;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum MissKind {
+    Reread,
+    Reask,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct SummaryMiss {
+    pub kind: MissKind,
+    pub detail: String,
+}
+
+/// The latest compaction in `messages`, identified by how many messages it
+/// and earlier compactions hid; `None` when nothing has been compacted.
+pub fn latest_compaction(messages: &[Message]) -> Option<usize> {
+    let hidden = messages.iter().filter(|m| !m.is_agent_visible()).count();
+    (hidden > 0).then_some(hidden)
+}
+
+fn tool_call_key(message: &Message) -> Vec<(String, String)> {
+    message
+        .content
+        .iter()
+        .filter_map(|content| match content {
+            MessageContent::ToolRequest(request) => request.tool_call.as_ref().ok(),
+            _ => None,
+        })
+        .map(|call| {
+            let arguments = call
+                .arguments
+                .as_ref()
+                .map(|a| serde_json::Value::Object(a.clone()).to_string())
+                .unwrap_or_default();
+            (call.name.to_string(), arguments)
+        })
+        .collect()
+}
+
+fn words(text: &str) -> HashSet<String> {
+    text.split(|c: char| !c.is_alphanumeric())
+        .filter(|w| w.chars().count() >= 5)
+        .map(str::to_lowercase)
+        .collect()
+}
+
+fn questions(text: &str) -> impl Iterator<Item = &str> {
+    text.split_inclusive(['.', '!', '?', '\n'])
+        .map(str::trim)
+        .filter(|sentence| sentence.ends_with('?'))
+}
+
+/// Misses against the latest compaction in `messages`.
+pub fn find_misses(messages: &[Message]) -> Vec<SummaryMiss> {
+    let Some(last_hidden) = messages.iter().rposition(|m| !m.is_agent_visible()) else {
+        return Vec::new();
+    };
+    let (before, after) = messages.split_at(last_hidden + 1);
+    let hidden: Vec<&Message> = before.iter().filter(|m| !m.is_agent_visible()).collect();
+    let hidden_calls: HashSet<(String, String)> =
+        hidden.iter().flat_map(|m| tool_call_key(m)).collect();
+    let hidden_answers: Vec<HashSet<String>> = hidden
+        .iter()
+        .filter(|m| m.role == Role::User)
+        .map(|m| words(&m.as_concat_text()))
+        .filter(|w| w.len() >= MIN_SHARED_WORDS)
+        .collect();
+
+    let mut misses = Vec::new();
+    for message in after.iter().filter(|m| m.role == Role::Assistant) {
+        for (name, arguments) in tool_call_key(message) {
+            if hidden_calls.contains(&(name.clone(), arguments.clone())) {
+                let detail: String = format!("{} {}", name, arguments)
+                    .chars()
+                    .take(200)
+                    .collect();
+                misses.push(SummaryMiss {
+                    kind: MissKind::Reread,
+                    detail,
+                });
+            }
+        }
+        for question in questions(&message.as_concat_text()) {
+            let asked = words(question);
+            if hidden_answers
+                .iter()
+                .any(|answer| answer.intersection(&asked).count() >= MIN_SHARED_WORDS)
+            {
+                misses.push(SummaryMiss {
+                    kind: MissKind::Reask,
+                    detail: question.chars().take(200).collect(),
+                });
+            }
+        }
+    }
+    misses.dedup();
+    misses
+}
+
+#[derive(Default)]
+struct SessionMisses {
+    compaction: usize,
+    counted: HashSet<(MissKind, String)>,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+pub struct SummaryMissStats {
+    pub compactions: usize,
+    pub misses: usize,
+    pub rereads: usize,
+    pub reasks: usize,
+}
+
+/// Misses per session, and how the summarization prompt has done so far.
+pub struct SummaryMissLedger {
+    sessions: HashMap<String, SessionMisses>,
+    recent: VecDeque<SummaryMiss>,
+    stats: SummaryMissStats,
+    metrics: MetricsTracker,
+}
+
+impl Default for SummaryMissLedger {
+    fn default() -> Self {
+        let mut metrics = MetricsTracker::new();
+        metrics.track_prompt(SUMMARY_PROMPT_ID, SUMMARY_PROMPT_ID);
+        Self {
+            sessions: HashMap::new(),
+            recent: VecDeque::new(),
+            stats: SummaryMissStats::default(),
+            metrics,
+        }
+    }
+}
+
+impl SummaryMissLedger {
+    /// Records the misses in `messages` not seen before for this session and
+    /// returns them.
+    pub fn observe(&mut self, session_id: &str, messages: &[Message]) -> Vec<SummaryMiss> {
+        let Some(compaction) = latest_compaction(messages) else {
+            return Vec::new();
+        };
+        let session = self.sessions.entry(session_id.to_string()).or_default();
+        if session.compaction != compaction {
+            if session.compaction != 0 {
+                let missed = session.counted.len();
+                let _ = self.metrics.record_attempt(
+                    SUMMARY_PROMPT_ID,
+                    missed == 0,
+                    1.0 / (1.0 + missed as f32),
+                    0,
+                );
+            }
+            session.compaction = compaction;
+            session.counted.clear();
+            self.stats.compactions += 1;
+        }
+
+        let new: Vec<SummaryMiss> = find_misses(messages)
+            .into_iter()
+            .filter(|miss| session.counted.insert((miss.kind, miss.detail.clone())))
+            .collect();
+        for miss in &new {
+            self.stats.misses += 1;
+            match miss.kind {
+                MissKind::Reread => self.stats.rereads += 1,
+                MissKind::Reask => self.stats.reasks += 1,
+            }
+            self.recent.push_back(miss.clone());
+            if self.recent.len() > GUIDANCE_LIMIT {
+                self.recent.pop_front();
+            }
+        }
+        new
+    }
+
+    /// Details recent summaries dropped, for the next summarization prompt.
+    pub fn guidance(&self) -> Vec<String> {
+        self.recent
+            .iter()
+            .map(|miss| match miss.kind {
+                MissKind::Reread => format!("tool output that was needed again: {}", miss.detail),
+                MissKind::Reask => {
+                    format!("a user answer that was asked for again: {}", miss.detail)
+                }
+            })
+            .collect()
+    }
+
+    pub fn stats(&self) -> SummaryMissStats {
+        self.stats.clone()
+    }
+
+    pub fn metrics(&self) -> &MetricsTracker {
+        &self.metrics
+    }
+}
+
+static LEDGER: OnceLock<Mutex<SummaryMissLedger>> = OnceLock::new();
+
+pub fn global() -> &'static Mutex<SummaryMissLedger> {
+    LEDGER.get_or_init(Mutex::default)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::conversation::message::MessageMetadata;
+    use rmcp::model::CallToolRequestParams;
+    use rmcp::object;
+
+    fn read(path: &str) -> Message {
+        Message::assistant().with_tool_request(
+            "call",
+            Ok(CallToolRequestParams {
+                meta: None,
+                task: None,
+                name: "developer__text_editor".into(),
+                arguments: Some(object!({"command": "view", "path": path})),
+            }),
+        )
+    }
+
+    #[test]
+    fn test_rereads_and_reasks_after_compaction_are_misses() {
+        let hidden = MessageMetadata::default().with_agent_invisible();
+        let messages = vec![
+            Message::user()
+                .with_text("Deploy to the staging cluster in region frankfurt please")
+                .with_metadata(hidden.clone()),
+            read("src/main.rs").with_metadata(hidden.clone()),
+            read("src/lib.rs").with_metadata(hidden),
+            Message::user().with_text("Summary: working on the deploy"),
+            read("src/main.rs"),
+            read("src/config.rs"),
+            Message::assistant()
+                .with_text("Done. Which staging cluster region should I deploy to?"),
+        ];
+
+        let misses = find_misses(&messages);
+        assert_eq!(misses.len(), 2);
+        assert_eq!(misses[0].kind, MissKind::Reread);
+        assert!(misses[0].detail.contains("src/main.rs"));
+        assert_eq!(misses[1].kind, MissKind::Reask);
+
+        let mut ledger = SummaryMissLedger::default();
+        assert_eq!(ledger.observe("s", &messages).len(), 2);
+        assert!(ledger.observe("s", &messages).is_empty());
+        assert_eq!(ledger.stats().misses, 2);
+        assert_eq!(ledger.guidance().len(), 2);
+        assert!(find_misses(&messages[3..]).is_empty());
+    }
+}
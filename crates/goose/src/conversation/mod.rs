@@ -6,8 +6,11 @@ use thiserror::Error;
 use utoipa::ToSchema;
 
 pub mod message;
+pub mod repair;
 pub mod tool_result_serde;
 
+use repair::ConversationFixer;
+
 #[derive(Debug, Clone, Serialize, Deserialize, ToSchema, PartialEq)]
 pub struct Conversation(Vec<Message>);
 
@@ -160,64 +163,14 @@ impl<'a> IntoIterator for &'a Conversation {
 }
 
 /// Fix a conversation that we're about to send to an LLM. So the last and first
-/// messages should always be from the user.
+/// messages should always be from the user. Repairs follow the
+/// `GOOSE_CONVERSATION_REPAIR` config; see [`repair::ConversationFixer`].
 pub fn fix_conversation(conversation: Conversation) -> (Conversation, Vec<String>) {
-    let all_messages = conversation.messages();
-
-    // Create a shadow map: track each message as either Visible or NonVisible with its index
-    enum MessageSlot {
-        Visible(usize),      // Index into agent_visible_messages
-        NonVisible(Message), // Non-visible messages pass through unchanged
-    }
-
-    let mut agent_visible_messages = Vec::new();
-    let shadow_map: Vec<MessageSlot> = all_messages
-        .iter()
-        .map(|msg| {
-            if msg.metadata.agent_visible {
-                let idx = agent_visible_messages.len();
-                agent_visible_messages.push(msg.clone());
-                MessageSlot::Visible(idx)
-            } else {
-                MessageSlot::NonVisible(msg.clone())
-            }
-        })
-        .collect();
-
-    // Fix only the agent-visible messages
-    let (fixed_visible, issues) = fix_messages(agent_visible_messages);
-
-    // Reconstruct using shadow map: replace Visible slots with fixed messages
-    let final_messages: Vec<Message> = shadow_map
-        .into_iter()
-        .filter_map(|slot| match slot {
-            MessageSlot::Visible(idx) => fixed_visible.get(idx).cloned(),
-            MessageSlot::NonVisible(msg) => Some(msg),
-        })
-        .collect();
-
-    (Conversation::new_unvalidated(final_messages), issues)
+    ConversationFixer::from_global_config().fix(conversation)
 }
 
 fn fix_messages(messages: Vec<Message>) -> (Vec<Message>, Vec<String>) {
-    [
-        merge_text_content_items,
-        trim_assistant_text_whitespace,
-        remove_empty_messages,
-        fix_tool_calling,
-        merge_consecutive_messages,
-        fix_lead_trail,
-        populate_if_empty,
-    ]
-    .into_iter()
-    .fold(
-        (messages, Vec::new()),
-        |(msgs, mut all_issues), processor| {
-            let (new_msgs, issues) = processor(msgs);
-            all_issues.extend(issues);
-            (new_msgs, all_issues)
-        },
-    )
+    ConversationFixer::default().fix_messages(messages)
 }
 
 fn merge_text_content_in_message(mut msg: Message) -> Message {
@@ -0,0 +1,307 @@
+//! Repair strategies applied to a conversation before it is sent to a provider.
+//!
+//! Each strategy is one step of the fix pipeline and can be switched off with
+//! the `GOOSE_CONVERSATION_REPAIR` config key. `ConversationFixer::diagnose`
+//! runs the same pipeline as a dry run and reports what every step changed.
+
+use super::message::{Message, MessageContent};
+use super::Conversation;
+use crate::config::Config;
+use crate::utils::safe_truncate;
+use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
+
+pub const CONVERSATION_REPAIR_KEY: &str = "GOOSE_CONVERSATION_REPAIR";
+
+type RepairFn = fn(Vec<Message>) -> (Vec<Message>, Vec<String>);
+
+pub trait RepairStrategy: Send + Sync {
+    fn name(&self) -> &str;
+    fn repair(&self, messages: Vec<Message>) -> (Vec<Message>, Vec<String>);
+}
+
+struct BuiltinStrategy {
+    name: &'static str,
+    repair: RepairFn,
+}
+
+impl RepairStrategy for BuiltinStrategy {
+    fn name(&self) -> &str {
+        self.name
+    }
+
+    fn repair(&self, messages: Vec<Message>) -> (Vec<Message>, Vec<String>) {
+        (self.repair)(messages)
+    }
+}
+
+/// Truncates text content longer than `max_chars`
+pub struct OversizedContent {
+    pub max_chars: usize,
+}
+
+impl RepairStrategy for OversizedContent {
+    fn name(&self) -> &str {
+        "oversized_content"
+    }
+
+    fn repair(&self, mut messages: Vec<Message>) -> (Vec<Message>, Vec<String>) {
+        let mut issues = Vec::new();
+        for message in &mut messages {
+            let role = super::effective_role(message);
+            for content in &mut message.content {
+                if let MessageContent::Text(text) = content {
+                    if text.text.chars().count() > self.max_chars {
+                        text.text = safe_truncate(&text.text, self.max_chars);
+                        issues.push(format!(
+                            "Truncated {} text content to {} characters",
+                            role, self.max_chars
+                        ));
+                    }
+                }
+            }
+        }
+        (messages, issues)
+    }
+}
+
+/// `GOOSE_CONVERSATION_REPAIR` settings
+#[derive(Debug, Clone, Default, Serialize, Deserialize, ToSchema)]
+pub struct ConversationRepairConfig {
+    /// Strategy names to skip
+    #[serde(default)]
+    pub disabled: Vec<String>,
+    /// Enables `oversized_content` with this limit
+    #[serde(default)]
+    pub max_content_chars: Option<usize>,
+}
+
+impl ConversationRepairConfig {
+    pub fn load() -> Self {
+        Config::global()
+            .get_param::<Self>(CONVERSATION_REPAIR_KEY)
+            .unwrap_or_default()
+    }
+}
+
+/// What one strategy did during a dry run
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct RepairStepReport {
+    pub strategy: String,
+    pub enabled: bool,
+    pub issues: Vec<String>,
+    pub messages_before: usize,
+    pub messages_after: usize,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct RepairDiagnostics {
+    pub steps: Vec<RepairStepReport>,
+    pub issues: Vec<String>,
+    pub changed: bool,
+    /// The conversation as it would be sent after repair
+    pub repaired: Conversation,
+}
+
+pub struct ConversationFixer {
+    strategies: Vec<(Box<dyn RepairStrategy>, bool)>,
+}
+
+impl Default for ConversationFixer {
+    fn default() -> Self {
+        let builtin: [(&'static str, RepairFn); 7] = [
+            ("merge_text", super::merge_text_content_items),
+            ("trim_whitespace", super::trim_assistant_text_whitespace),
+            ("empty_messages", super::remove_empty_messages),
+            ("tool_calling", super::fix_tool_calling),
+            ("role_alternation", super::merge_consecutive_messages),
+            ("lead_trail", super::fix_lead_trail),
+            ("placeholder", super::populate_if_empty),
+        ];
+        Self {
+            strategies: builtin
+                .into_iter()
+                .map(|(name, repair)| {
+                    (
+                        Box::new(BuiltinStrategy { name, repair }) as Box<dyn RepairStrategy>,
+                        true,
+                    )
+                })
+                .collect(),
+        }
+    }
+}
+
+impl ConversationFixer {
+    pub fn from_config(config: &ConversationRepairConfig) -> Self {
+        let mut fixer = Self::default();
+        if let Some(max_chars) = config.max_content_chars {
+            // After consecutive messages are merged, so the limit applies to
+            // the text that is actually sent
+            fixer.insert_before("lead_trail", Box::new(OversizedContent { max_chars }));
+        }
+        for (strategy, enabled) in &mut fixer.strategies {
+            *enabled = !config.disabled.iter().any(|d| d == strategy.name());
+        }
+        fixer
+    }
+
+    pub fn from_global_config() -> Self {
+        Self::from_config(&ConversationRepairConfig::load())
+    }
+
+    /// Inserts a strategy ahead of `before`, or at the end if there is none
+    pub fn insert_before(&mut self, before: &str, strategy: Box<dyn RepairStrategy>) {
+        let index = self
+            .strategies
+            .iter()
+            .position(|(s, _)| s.name() == before)
+            .unwrap_or(self.strategies.len());
+        self.strategies.insert(index, (strategy, true));
+    }
+
+    pub fn set_enabled(&mut self, name: &str, enabled: bool) {
+        for (strategy, flag) in &mut self.strategies {
+            if strategy.name() == name {
+                *flag = enabled;
+            }
+        }
+    }
+
+    pub fn strategy_names(&self) -> Vec<&str> {
+        self.strategies.iter().map(|(s, _)| s.name()).collect()
+    }
+
+    pub(crate) fn fix_messages(&self, messages: Vec<Message>) -> (Vec<Message>, Vec<String>) {
+        let (messages, steps) = self.run(messages);
+        (messages, steps.into_iter().flat_map(|s| s.issues).collect())
+    }
+
+    fn run(&self, mut messages: Vec<Message>) -> (Vec<Message>, Vec<RepairStepReport>) {
+        let mut steps = Vec::new();
+        for (strategy, enabled) in &self.strategies {
+            let messages_before = messages.len();
+            let issues = if *enabled {
+                let (fixed, issues) = strategy.repair(messages);
+                messages = fixed;
+                issues
+            } else {
+                Vec::new()
+            };
+            steps.push(RepairStepReport {
+                strategy: strategy.name().to_string(),
+                enabled: *enabled,
+                issues,
+                messages_before,
+                messages_after: messages.len(),
+            });
+        }
+        (messages, steps)
+    }
+
+    /// Repairs the agent-visible messages; messages hidden from the agent pass
+    /// through in place
+    pub fn fix(&self, conversation: Conversation) -> (Conversation, Vec<String>) {
+        let (conversation, steps) = self.fix_with_steps(conversation);
+        (
+            conversation,
+            steps.into_iter().flat_map(|s| s.issues).collect(),
+        )
+    }
+
+    /// Runs the pipeline without persisting anything and reports each step
+    pub fn diagnose(&self, conversation: &Conversation) -> RepairDiagnostics {
+        let (repaired, steps) = self.fix_with_steps(conversation.clone());
+        let issues: Vec<String> = steps.iter().flat_map(|s| s.issues.clone()).collect();
+        RepairDiagnostics {
+            changed: &repaired != conversation,
+            steps,
+            issues,
+            repaired,
+        }
+    }
+
+    fn fix_with_steps(&self, conversation: Conversation) -> (Conversation, Vec<RepairStepReport>) {
+        // Track each message as either visible (index into the fixed list) or
+        // hidden (passed through unchanged)
+        enum MessageSlot {
+            Visible(usize),
+            NonVisible(Message),
+        }
+
+        let mut agent_visible_messages = Vec::new();
+        let shadow_map: Vec<MessageSlot> = conversation
+            .into_iter()
+            .map(|msg| {
+                if msg.metadata.agent_visible {
+                    let idx = agent_visible_messages.len();
+                    agent_visible_messages.push(msg);
+                    MessageSlot::Visible(idx)
+                } else {
+                    MessageSlot::NonVisible(msg)
+                }
+            })
+            .collect();
+
+        let (fixed_visible, steps) = self.run(agent_visible_messages);
+
+        let final_messages: Vec<Message> = shadow_map
+            .into_iter()
+            .filter_map(|slot| match slot {
+                MessageSlot::Visible(idx) => fixed_visible.get(idx).cloned(),
+                MessageSlot::NonVisible(msg) => Some(msg),
+            })
+            .collect();
+
+        (Conversation::new_unvalidated(final_messages), steps)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_disabled_strategy_is_reported_but_skipped() {
+        let conversation = Conversation::new_unvalidated([
+            Message::user().with_text("hi"),
+            Message::user().with_text("again"),
+            Message::assistant().with_text("hello   "),
+        ]);
+
+        let fixer = ConversationFixer::from_config(&ConversationRepairConfig {
+            disabled: vec!["role_alternation".to_string()],
+            max_content_chars: None,
+        });
+        let diagnostics = fixer.diagnose(&conversation);
+
+        assert!(diagnostics.changed);
+        let step = |name: &str| {
+            diagnostics
+                .steps
+                .iter()
+                .find(|s| s.strategy == name)
+                .unwrap()
+        };
+        assert!(!step("role_alternation").enabled);
+        assert!(step("role_alternation").issues.is_empty());
+        assert_eq!(step("lead_trail").messages_before, 3);
+        assert_eq!(step("lead_trail").messages_after, 2);
+        assert_eq!(diagnostics.repaired.len(), 2);
+        assert!(!fixer.strategy_names().contains(&"oversized_content"));
+    }
+
+    #[test]
+    fn test_oversized_content() {
+        let conversation =
+            Conversation::new_unvalidated([Message::user().with_text("x".repeat(50))]);
+        let fixer = ConversationFixer::from_config(&ConversationRepairConfig {
+            disabled: vec![],
+            max_content_chars: Some(10),
+        });
+
+        let (fixed, issues) = fixer.fix(conversation);
+        assert_eq!(issues, vec!["Truncated user text content to 10 characters"]);
+        assert!(fixed.messages()[0].as_concat_text().chars().count() <= 10);
+    }
+}
@@ -0,0 +1,178 @@
+//! Shared SQLite setup for the crate's stores.
+//!
+//! Sessions, checkpoints, the agent mailbox, tasks and local analytics each
+//! keep their own database file, but all of them open it here so they get the
+//! same settings: WAL journaling, a busy timeout instead of an immediate
+//! "database is locked" when another connection holds the write lock, and a
+//! bounded pool. `GOOSE_SQLITE_BUSY_TIMEOUT_MS` and
+//! `GOOSE_SQLITE_MAX_CONNECTIONS` change the defaults.
+//!
+//! Queries run on sqlx's own connection threads, but filesystem work around a
+//! database (copying backups, reading logs) is synchronous and goes through
+//! [`blocking`] so it never stalls the async runtime.
+
+use crate::config::Config;
+use anyhow::Result;
+use serde::Serialize;
+use sqlx::pool::PoolConnectionMetadata;
+use sqlx::sqlite::{SqliteConnectOptions, SqliteJournalMode, SqlitePoolOptions, SqliteSynchronous};
+use sqlx::{Pool, Sqlite};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::{Arc, LazyLock, Mutex};
+use std::time::Duration;
+use utoipa::ToSchema;
+
+pub const BUSY_TIMEOUT_KEY: &str = "GOOSE_SQLITE_BUSY_TIMEOUT_MS";
+pub const MAX_CONNECTIONS_KEY: &str = "GOOSE_SQLITE_MAX_CONNECTIONS";
+pub const DEFAULT_BUSY_TIMEOUT: Duration = Duration::from_secs(5);
+pub const DEFAULT_MAX_CONNECTIONS: u32 = 5;
+
+#[derive(Debug, Clone, Serialize, ToSchema)]
+pub struct StoreMetrics {
+    pub store: String,
+    #[schema(value_type = String)]
+    pub path: PathBuf,
+    pub max_connections: u32,
+    /// Connections opened since the store was opened
+    pub connections_opened: u64,
+    /// Queries and transactions run since the store was opened
+    pub uses: u64,
+}
+
+struct StoreStats {
+    name: &'static str,
+    path: PathBuf,
+    max_connections: u32,
+    connections_opened: AtomicU64,
+    uses: AtomicU64,
+    open: AtomicBool,
+}
+
+/// Owned by the pool's hooks, so it is dropped with the pool. The registry
+/// holds no pool of its own, which would keep the database open.
+struct PoolAlive(Arc<StoreStats>);
+
+impl Drop for PoolAlive {
+    fn drop(&mut self) {
+        self.0.open.store(false, Ordering::Relaxed);
+    }
+}
+
+static STORES: LazyLock<Mutex<Vec<Arc<StoreStats>>>> = LazyLock::new(Mutex::default);
+
+fn busy_timeout() -> Duration {
+    Config::global()
+        .get_param::<u64>(BUSY_TIMEOUT_KEY)
+        .map(Duration::from_millis)
+        .unwrap_or(DEFAULT_BUSY_TIMEOUT)
+}
+
+fn max_connections() -> u32 {
+    Config::global()
+        .get_param::<u32>(MAX_CONNECTIONS_KEY)
+        .ok()
+        .filter(|n| *n > 0)
+        .unwrap_or(DEFAULT_MAX_CONNECTIONS)
+}
+
+/// The settings every store's connections use.
+pub fn connect_options(path: &Path) -> SqliteConnectOptions {
+    SqliteConnectOptions::new()
+        .filename(path)
+        .create_if_missing(true)
+        .busy_timeout(busy_timeout())
+        .journal_mode(SqliteJournalMode::Wal)
+        .synchronous(SqliteSynchronous::Normal)
+}
+
+/// A pool for the database at `path`, connecting on first use. The parent
+/// directory must exist. `store` names the pool in [`metrics`].
+pub fn open(store: &'static str, path: &Path) -> Pool<Sqlite> {
+    let stats = Arc::new(StoreStats {
+        name: store,
+        path: path.to_path_buf(),
+        max_connections: max_connections(),
+        connections_opened: AtomicU64::new(0),
+        uses: AtomicU64::new(0),
+        open: AtomicBool::new(true),
+    });
+    let connected = Arc::clone(&stats);
+    let released = PoolAlive(Arc::clone(&stats));
+    let pool = SqlitePoolOptions::new()
+        .max_connections(stats.max_connections)
+        .after_connect(move |_conn, _meta: PoolConnectionMetadata| {
+            connected.connections_opened.fetch_add(1, Ordering::Relaxed);
+            Box::pin(async { Ok(()) })
+        })
+        .after_release(move |_conn, _meta: PoolConnectionMetadata| {
+            released.0.uses.fetch_add(1, Ordering::Relaxed);
+            Box::pin(async { Ok(true) })
+        })
+        .connect_lazy_with(connect_options(path));
+
+    let mut stores = STORES.lock().unwrap_or_else(|e| e.into_inner());
+    stores.retain(|s| s.open.load(Ordering::Relaxed));
+    stores.push(stats);
+    pool
+}
+
+/// Runs synchronous work, such as copying database files, off the async runtime.
+pub async fn blocking<T, F>(work: F) -> Result<T>
+where
+    F: FnOnce() -> Result<T> + Send + 'static,
+    T: Send + 'static,
+{
+    tokio::task::spawn_blocking(work).await?
+}
+
+/// Usage of every store whose pool is still alive.
+pub fn metrics() -> Vec<StoreMetrics> {
+    let mut stores = STORES.lock().unwrap_or_else(|e| e.into_inner());
+    stores.retain(|s| s.open.load(Ordering::Relaxed));
+    stores
+        .iter()
+        .map(|s| StoreMetrics {
+            store: s.name.to_string(),
+            path: s.path.clone(),
+            max_connections: s.max_connections,
+            connections_opened: s.connections_opened.load(Ordering::Relaxed),
+            uses: s.uses.load(Ordering::Relaxed),
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_stores_share_settings_and_report_metrics() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("store.db");
+        let pool = open("test_store", &path);
+
+        let mode: String = sqlx::query_scalar("PRAGMA journal_mode")
+            .fetch_one(&pool)
+            .await
+            .unwrap();
+        assert_eq!(mode, "wal");
+        let timeout: i64 = sqlx::query_scalar("PRAGMA busy_timeout")
+            .fetch_one(&pool)
+            .await
+            .unwrap();
+        assert_eq!(timeout, DEFAULT_BUSY_TIMEOUT.as_millis() as i64);
+
+        let store = metrics()
+            .into_iter()
+            .find(|m| m.path == path)
+            .expect("open stores are listed");
+        assert_eq!(store.store, "test_store");
+        assert_eq!(store.max_connections, DEFAULT_MAX_CONNECTIONS);
+        assert!(store.connections_opened >= 1);
+
+        pool.close().await;
+        drop(pool);
+        assert!(metrics().iter().all(|m| m.path != path));
+    }
+}
@@ -0,0 +1,333 @@
+//! Form widgets for elicitations beyond the plain JSON types.
+//!
+//! A property in an elicitation schema names its widget with
+//! `x-goose-widget`; clients that do not know the keyword fall back to the
+//! property's JSON type:
+//!
+//! - `file` and `directory`: a path, resolved against the session's working
+//!   directory and checked to exist inside it before the answer is handed on
+//! - `multi_select`: an array of distinct strings from an `enum`
+//! - `diff`: a yes/no confirmation shown under the markdown in
+//!   `x-goose-preview`, usually a proposed diff
+//! - `password`: a string stored in the secrets manager under the key in
+//!   `x-goose-secret`, prefixed with `ext.<name>.` for the extension or
+//!   kind that asked so it cannot replace goose's own secrets; the waiting
+//!   tool and the session history only see the key
+//!
+//! Answers are checked against the schema by [`check_response`] before the
+//! waiting request sees them, so a tool never receives an answer its own
+//! schema rejects.
+
+use serde_json::{json, Map, Value};
+use std::path::{Path, PathBuf};
+
+pub const WIDGET_KEY: &str = "x-goose-widget";
+pub const PREVIEW_KEY: &str = "x-goose-preview";
+pub const SECRET_KEY: &str = "x-goose-secret";
+
+pub fn file_picker(description: &str) -> Value {
+    json!({ "type": "string", "description": description, WIDGET_KEY: "file" })
+}
+
+pub fn directory_picker(description: &str) -> Value {
+    json!({ "type": "string", "description": description, WIDGET_KEY: "directory" })
+}
+
+pub fn multi_select(description: &str, options: &[String]) -> Value {
+    json!({
+        "type": "array",
+        "description": description,
+        "items": { "type": "string", "enum": options },
+        "uniqueItems": true,
+        WIDGET_KEY: "multi_select",
+    })
+}
+
+/// A confirmation shown under `diff`, rendered as a fenced diff block.
+pub fn diff_preview(description: &str, diff: &str) -> Value {
+    json!({
+        "type": "boolean",
+        "description": description,
+        WIDGET_KEY: "diff",
+        PREVIEW_KEY: format!("```diff\n{}\n```", diff.trim_end()),
+    })
+}
+
+/// A masked field whose value is stored as the secret `secret_key`.
+pub fn password(description: &str, secret_key: &str) -> Value {
+    json!({
+        "type": "string",
+        "description": description,
+        "format": "password",
+        "writeOnly": true,
+        WIDGET_KEY: "password",
+        SECRET_KEY: secret_key,
+    })
+}
+
+/// An answer that passed [`check_response`].
+#[derive(Debug, Clone, PartialEq)]
+pub struct CheckedResponse {
+    /// The answer for the waiting request, with paths resolved and
+    /// passwords replaced by their secret keys; safe to record
+    pub user_data: Value,
+    /// Secret keys and values to store before handing the answer on
+    pub secrets: Vec<(String, String)>,
+}
+
+/// The key a password answer is stored under. `namespace` must not contain
+/// `.`, or two namespaces could share a key.
+pub fn secret_key(namespace: &str, key: &str) -> String {
+    format!("ext.{}.{}", namespace, key)
+}
+
+/// Validates an answer against its schema and applies the widgets. Returns
+/// every problem found rather than the first.
+pub fn check_response(
+    schema: &Value,
+    mut user_data: Value,
+    workspace: Option<&Path>,
+    secret_namespace: &str,
+) -> Result<CheckedResponse, Vec<String>> {
+    // Forms submit optional fields left empty as null or ""; treat them as
+    // omitted rather than as values of the wrong type
+    if let (Some(answers), Some(properties)) = (
+        user_data.as_object_mut(),
+        schema.get("properties").and_then(Value::as_object),
+    ) {
+        answers.retain(|name, answer| {
+            let empty = answer.is_null() || answer.as_str().is_some_and(|s| s.trim().is_empty());
+            !(empty && properties.contains_key(name) && !is_required(schema, name))
+        });
+    }
+
+    match jsonschema::validator_for(schema) {
+        Ok(validator) => {
+            let errors: Vec<String> = validator
+                .iter_errors(&user_data)
+                .map(|e| match e.instance_path.to_string() {
+                    path if path.is_empty() => e.to_string(),
+                    path => format!("{}: {}", path, e),
+                })
+                .collect();
+            if !errors.is_empty() {
+                return Err(errors);
+            }
+        }
+        // Checking against a schema that does not compile would reject
+        // every answer; the client rendered it, so take what it sent
+        Err(e) => tracing::warn!("Elicitation schema does not compile: {}", e),
+    }
+
+    let mut errors = Vec::new();
+    let mut secrets = Vec::new();
+    let properties = schema.get("properties").and_then(Value::as_object);
+    if let (Some(properties), Some(answers)) = (properties, user_data.as_object_mut()) {
+        for (name, property) in properties {
+            let Some(answer) = answers.get_mut(name) else {
+                continue;
+            };
+            if let Err(e) =
+                apply_widget(property, answer, workspace, secret_namespace, &mut secrets)
+            {
+                errors.push(format!("{} {}", name, e));
+            }
+        }
+    }
+
+    if errors.is_empty() {
+        Ok(CheckedResponse { user_data, secrets })
+    } else {
+        Err(errors)
+    }
+}
+
+fn apply_widget(
+    property: &Value,
+    answer: &mut Value,
+    workspace: Option<&Path>,
+    secret_namespace: &str,
+    secrets: &mut Vec<(String, String)>,
+) -> Result<(), String> {
+    let widget = property.get(WIDGET_KEY).and_then(Value::as_str);
+    let (Some(widget), Some(text)) = (widget, answer.as_str()) else {
+        return Ok(());
+    };
+    match widget {
+        "file" | "directory" => {
+            let workspace = workspace.ok_or("cannot be resolved without a session workspace")?;
+            let path = resolve_path(text, workspace)?;
+            let found = if widget == "file" {
+                path.is_file()
+            } else {
+                path.is_dir()
+            };
+            if !found {
+                return Err(format!(
+                    "must be an existing {}: {}",
+                    widget,
+                    path.display()
+                ));
+            }
+            *answer = json!(path.to_string_lossy());
+        }
+        "password" => {
+            let key = property
+                .get(SECRET_KEY)
+                .and_then(Value::as_str)
+                .ok_or_else(|| format!("has no {} to store the password under", SECRET_KEY))?;
+            if secret_namespace.contains('.') {
+                return Err(format!(
+                    "cannot be stored under namespace {}, which contains '.'",
+                    secret_namespace
+                ));
+            }
+            let key = secret_key(secret_namespace, key);
+            *answer = json!(key);
+            secrets.push((key, text.to_string()));
+        }
+        _ => {}
+    }
+    Ok(())
+}
+
+fn is_required(schema: &Value, name: &str) -> bool {
+    schema
+        .get("required")
+        .and_then(Value::as_array)
+        .is_some_and(|required| required.iter().any(|r| r.as_str() == Some(name)))
+}
+
+/// Whether answers to `schema` contain paths, which need a workspace.
+pub fn has_path_widgets(schema: &Value) -> bool {
+    schema
+        .get("properties")
+        .and_then(Value::as_object)
+        .is_some_and(|properties| {
+            properties.values().any(|property| {
+                matches!(
+                    property.get(WIDGET_KEY).and_then(Value::as_str),
+                    Some("file" | "directory")
+                )
+            })
+        })
+}
+
+/// Resolves `text` against the workspace, following symlinks, and refuses
+/// paths that end up outside it.
+fn resolve_path(text: &str, workspace: &Path) -> Result<PathBuf, String> {
+    let path = match text.trim().strip_prefix("~/") {
+        Some(rest) => dirs::home_dir().unwrap_or_default().join(rest),
+        None => PathBuf::from(text.trim()),
+    };
+    let path = if path.is_absolute() {
+        path
+    } else {
+        workspace.join(path)
+    };
+    let root = workspace
+        .canonicalize()
+        .unwrap_or_else(|_| workspace.to_path_buf());
+    match path.canonicalize() {
+        Ok(path) if path.starts_with(&root) => Ok(path),
+        Ok(path) => Err(format!(
+            "must be inside the session workspace {}: {}",
+            root.display(),
+            path.display()
+        )),
+        // Missing paths are reported by the caller
+        Err(_) => Ok(path),
+    }
+}
+
+/// Builds an object schema from `(name, property, required)` fields.
+pub fn form(fields: Vec<(&str, Value, bool)>) -> Value {
+    let mut properties = Map::new();
+    let mut required = Vec::new();
+    for (name, property, is_required) in fields {
+        if is_required {
+            required.push(name.to_string());
+        }
+        properties.insert(name.to_string(), property);
+    }
+    json!({ "type": "object", "properties": properties, "required": required })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_check_response_applies_widgets() {
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::write(dir.path().join("notes.md"), "").unwrap();
+        let tags = multi_select("Tags", &["a".into(), "b".into()]);
+        let schema = form(vec![
+            ("notes", file_picker("Notes to read"), true),
+            ("out", directory_picker("Where to write"), true),
+            ("tags", tags, false),
+            ("token", password("API token", "EXAMPLE_TOKEN"), true),
+        ]);
+
+        let checked = check_response(
+            &schema,
+            json!({ "notes": "notes.md", "out": ".", "tags": "", "token": "hunter2" }),
+            Some(dir.path()),
+            "example",
+        )
+        .unwrap();
+        let workspace = dir.path().canonicalize().unwrap();
+        assert_eq!(
+            checked.user_data["notes"],
+            json!(workspace.join("notes.md").to_string_lossy())
+        );
+        assert_eq!(
+            checked.user_data["token"],
+            json!("ext.example.EXAMPLE_TOKEN")
+        );
+        assert_eq!(
+            checked.secrets,
+            vec![(
+                "ext.example.EXAMPLE_TOKEN".to_string(),
+                "hunter2".to_string()
+            )]
+        );
+
+        let errors = check_response(
+            &schema,
+            json!({ "notes": "missing.md", "out": ".", "tags": ["a", "a"], "token": "x" }),
+            Some(dir.path()),
+            "example",
+        )
+        .unwrap_err();
+        assert_eq!(errors.len(), 1, "{:?}", errors);
+        let errors = check_response(
+            &schema,
+            json!({ "notes": "missing.md", "out": "notes.md", "token": "x" }),
+            Some(dir.path()),
+            "example",
+        )
+        .unwrap_err();
+        assert_eq!(errors.len(), 2, "{:?}", errors);
+
+        let outside = tempfile::tempdir().unwrap();
+        std::fs::write(outside.path().join("secret.md"), "").unwrap();
+        let escaped = outside.path().join("secret.md");
+        for notes in [escaped.to_str().unwrap(), "../"] {
+            let errors = check_response(
+                &schema,
+                json!({ "notes": notes, "out": "..", "token": "x" }),
+                Some(dir.path()),
+                "example",
+            )
+            .unwrap_err();
+            assert_eq!(errors.len(), 2, "{:?}", errors);
+        }
+
+        let answer = json!({ "notes": "notes.md", "out": ".", "token": "x" });
+        let errors = check_response(&schema, answer.clone(), None, "example").unwrap_err();
+        assert_eq!(errors.len(), 2, "{:?}", errors);
+        let errors = check_response(&schema, answer, Some(dir.path()), "ex.ample").unwrap_err();
+        assert_eq!(errors.len(), 1, "{:?}", errors);
+    }
+}
@@ -0,0 +1,305 @@
+//! Gmail through the Gmail API. Drafts and sends are built as RFC 5322
+//! messages; replies carry the original's thread and `Message-ID`.
+
+use super::{
+    checked, encode_query, Draft, EmailAccount, EmailMessage, MailBackend, MessageSummary,
+};
+use anyhow::{anyhow, Result};
+use async_trait::async_trait;
+use base64::Engine;
+use serde_json::{json, Value};
+
+const DEFAULT_HOST: &str = "https://gmail.googleapis.com";
+
+pub struct GmailBackend {
+    client: reqwest::Client,
+    token: String,
+    host: String,
+    from: String,
+}
+
+/// Thread, `Message-ID` and subject of a message being replied to
+struct ReplyContext {
+    thread_id: String,
+    message_id: Option<String>,
+    subject: String,
+}
+
+fn header(payload: &Value, name: &str) -> Option<String> {
+    payload["headers"]
+        .as_array()?
+        .iter()
+        .find(|h| {
+            h["name"]
+                .as_str()
+                .is_some_and(|n| n.eq_ignore_ascii_case(name))
+        })
+        .and_then(|h| h["value"].as_str())
+        .map(str::to_string)
+}
+
+fn addresses(payload: &Value, name: &str) -> Vec<String> {
+    header(payload, name)
+        .map(|value| value.split(',').map(|a| a.trim().to_string()).collect())
+        .unwrap_or_default()
+}
+
+fn decode(data: &str) -> Option<String> {
+    let bytes = base64::engine::general_purpose::URL_SAFE_NO_PAD
+        .decode(data.trim_end_matches('='))
+        .ok()?;
+    Some(String::from_utf8_lossy(&bytes).into_owned())
+}
+
+/// The first part of `mime_type` in a message payload
+fn body_part(payload: &Value, mime_type: &str) -> Option<String> {
+    if payload["mimeType"].as_str() == Some(mime_type) {
+        if let Some(text) = payload["body"]["data"].as_str().and_then(decode) {
+            return Some(text);
+        }
+    }
+    payload["parts"]
+        .as_array()?
+        .iter()
+        .find_map(|part| body_part(part, mime_type))
+}
+
+/// Plain-text body, or the HTML body with its tags dropped
+pub(crate) fn plain_text(payload: &Value) -> String {
+    if let Some(text) = body_part(payload, "text/plain") {
+        return text;
+    }
+    let Some(html) = body_part(payload, "text/html") else {
+        return String::new();
+    };
+    let mut text = String::with_capacity(html.len());
+    let mut in_tag = false;
+    for c in html.chars() {
+        match c {
+            '<' => in_tag = true,
+            '>' => in_tag = false,
+            _ if !in_tag => text.push(c),
+            _ => {}
+        }
+    }
+    text
+}
+
+fn encode_header(value: &str) -> String {
+    if value.is_ascii() {
+        value.to_string()
+    } else {
+        format!(
+            "=?UTF-8?B?{}?=",
+            base64::engine::general_purpose::STANDARD.encode(value)
+        )
+    }
+}
+
+/// The draft as an RFC 5322 message.
+pub(crate) fn compose(from: &str, draft: &Draft, message_id: Option<&str>) -> String {
+    let mut message = format!("From: {}\r\nTo: {}\r\n", from, draft.to.join(", "));
+    if !draft.cc.is_empty() {
+        message.push_str(&format!("Cc: {}\r\n", draft.cc.join(", ")));
+    }
+    message.push_str(&format!("Subject: {}\r\n", encode_header(&draft.subject)));
+    if let Some(message_id) = message_id {
+        message.push_str(&format!(
+            "In-Reply-To: {}\r\nReferences: {}\r\n",
+            message_id, message_id
+        ));
+    }
+    message.push_str(
+        "MIME-Version: 1.0\r\n\
+         Content-Type: text/plain; charset=\"UTF-8\"\r\n\
+         Content-Transfer-Encoding: base64\r\n\r\n",
+    );
+    let body = base64::engine::general_purpose::STANDARD.encode(&draft.body);
+    for line in body.as_bytes().chunks(76) {
+        message.push_str(&String::from_utf8_lossy(line));
+        message.push_str("\r\n");
+    }
+    message
+}
+
+impl GmailBackend {
+    pub fn new(client: reqwest::Client, token: String, account: &EmailAccount) -> Self {
+        Self {
+            client,
+            token,
+            host: account
+                .host
+                .clone()
+                .unwrap_or_else(|| DEFAULT_HOST.to_string()),
+            from: account.address.clone(),
+        }
+    }
+
+    fn url(&self, path: &str) -> String {
+        format!(
+            "{}/gmail/v1/users/me/{}",
+            self.host.trim_end_matches('/'),
+            path
+        )
+    }
+
+    async fn get(&self, path: &str) -> Result<Value> {
+        let response = self
+            .client
+            .get(self.url(path))
+            .bearer_auth(&self.token)
+            .send()
+            .await?;
+        Ok(checked(response).await?.json().await?)
+    }
+
+    async fn post(&self, path: &str, body: &Value) -> Result<Value> {
+        let response = self
+            .client
+            .post(self.url(path))
+            .bearer_auth(&self.token)
+            .json(body)
+            .send()
+            .await?;
+        Ok(checked(response).await?.json().await?)
+    }
+
+    async fn reply_context(&self, id: &str) -> Result<ReplyContext> {
+        let message = self
+            .get(&format!(
+                "messages/{}?format=metadata&metadataHeaders=Message-ID&metadataHeaders=Subject",
+                encode_query(id)
+            ))
+            .await?;
+        Ok(ReplyContext {
+            thread_id: message["threadId"]
+                .as_str()
+                .ok_or_else(|| anyhow!("Message {} has no thread", id))?
+                .to_string(),
+            message_id: header(&message["payload"], "Message-ID"),
+            subject: header(&message["payload"], "Subject").unwrap_or_default(),
+        })
+    }
+
+    /// The raw message and thread for a draft
+    async fn message(&self, draft: &Draft) -> Result<Value> {
+        let Some(reply_to) = &draft.reply_to else {
+            let raw = compose(&self.from, draft, None);
+            return Ok(json!({ "raw": base64::engine::general_purpose::URL_SAFE.encode(raw) }));
+        };
+        let context = self.reply_context(reply_to).await?;
+        let mut draft = draft.clone();
+        if draft.subject.is_empty() {
+            draft.subject = format!("Re: {}", context.subject);
+        }
+        let raw = compose(&self.from, &draft, context.message_id.as_deref());
+        Ok(json!({
+            "raw": base64::engine::general_purpose::URL_SAFE.encode(raw),
+            "threadId": context.thread_id,
+        }))
+    }
+}
+
+#[async_trait]
+impl MailBackend for GmailBackend {
+    async fn search(&self, query: &str, limit: usize) -> Result<Vec<MessageSummary>> {
+        let query = if query.trim().is_empty() {
+            "in:inbox"
+        } else {
+            query
+        };
+        let list = self
+            .get(&format!(
+                "messages?q={}&maxResults={}",
+                encode_query(query),
+                limit
+            ))
+            .await?;
+        let mut summaries = Vec::new();
+        for id in list["messages"]
+            .as_array()
+            .into_iter()
+            .flatten()
+            .filter_map(|m| m["id"].as_str())
+        {
+            let message = self
+                .get(&format!(
+                    "messages/{}?format=metadata&metadataHeaders=From&metadataHeaders=Subject&metadataHeaders=Date",
+                    encode_query(id)
+                ))
+                .await?;
+            let payload = &message["payload"];
+            summaries.push(MessageSummary {
+                id: id.to_string(),
+                from: header(payload, "From").unwrap_or_default(),
+                subject: header(payload, "Subject").unwrap_or_default(),
+                date: header(payload, "Date"),
+                snippet: message["snippet"].as_str().unwrap_or_default().to_string(),
+                unread: message["labelIds"]
+                    .as_array()
+                    .is_some_and(|labels| labels.iter().any(|l| l == "UNREAD")),
+            });
+        }
+        Ok(summaries)
+    }
+
+    async fn read(&self, id: &str) -> Result<EmailMessage> {
+        let message = self
+            .get(&format!("messages/{}?format=full", encode_query(id)))
+            .await?;
+        let payload = &message["payload"];
+        Ok(EmailMessage {
+            id: id.to_string(),
+            from: header(payload, "From").unwrap_or_default(),
+            to: addresses(payload, "To"),
+            cc: addresses(payload, "Cc"),
+            subject: header(payload, "Subject").unwrap_or_default(),
+            date: header(payload, "Date"),
+            body: plain_text(payload),
+        })
+    }
+
+    async fn draft(&self, draft: &Draft) -> Result<String> {
+        let message = self.message(draft).await?;
+        let created = self.post("drafts", &json!({ "message": message })).await?;
+        created["id"]
+            .as_str()
+            .map(str::to_string)
+            .ok_or_else(|| anyhow!("Gmail did not return a draft id"))
+    }
+
+    async fn send(&self, draft: &Draft) -> Result<()> {
+        let message = self.message(draft).await?;
+        self.post("messages/send", &message).await?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_compose_and_read_back() {
+        let draft = Draft {
+            to: vec!["ada@example.com".to_string()],
+            cc: vec!["alan@example.com".to_string()],
+            subject: "Café plans".to_string(),
+            body: "See you at noon.".to_string(),
+            reply_to: None,
+        };
+        let raw = compose("me@example.com", &draft, Some("<abc@mail>"));
+        assert!(raw.starts_with(
+            "From: me@example.com\r\nTo: ada@example.com\r\nCc: alan@example.com\r\n"
+        ));
+        assert!(raw.contains("Subject: =?UTF-8?B?"));
+        assert!(raw.contains("In-Reply-To: <abc@mail>\r\n"));
+
+        let encoded = base64::engine::general_purpose::URL_SAFE_NO_PAD.encode("Hello <b>there</b>");
+        let payload = json!({
+            "mimeType": "multipart/alternative",
+            "parts": [{ "mimeType": "text/html", "body": { "data": encoded } }]
+        });
+        assert_eq!(plain_text(&payload), "Hello there");
+    }
+}
@@ -0,0 +1,180 @@
+//! Microsoft 365 and Outlook.com mailboxes through Microsoft Graph.
+
+use super::{
+    checked, encode_query, Draft, EmailAccount, EmailMessage, MailBackend, MessageSummary,
+};
+use anyhow::{anyhow, Result};
+use async_trait::async_trait;
+use serde_json::{json, Value};
+
+const DEFAULT_HOST: &str = "https://graph.microsoft.com";
+const SUMMARY_FIELDS: &str = "id,from,subject,receivedDateTime,bodyPreview,isRead";
+
+pub struct GraphBackend {
+    client: reqwest::Client,
+    token: String,
+    host: String,
+}
+
+fn recipients(addresses: &[String]) -> Value {
+    addresses
+        .iter()
+        .map(|address| json!({ "emailAddress": { "address": address } }))
+        .collect()
+}
+
+fn address(recipient: &Value) -> String {
+    let email = &recipient["emailAddress"];
+    let address = email["address"].as_str().unwrap_or_default();
+    match email["name"].as_str() {
+        Some(name) if !name.is_empty() && name != address => format!("{} <{}>", name, address),
+        _ => address.to_string(),
+    }
+}
+
+fn addresses(recipients: &Value) -> Vec<String> {
+    recipients
+        .as_array()
+        .map(|list| list.iter().map(address).collect())
+        .unwrap_or_default()
+}
+
+impl GraphBackend {
+    pub fn new(client: reqwest::Client, token: String, account: &EmailAccount) -> Self {
+        Self {
+            client,
+            token,
+            host: account
+                .host
+                .clone()
+                .unwrap_or_else(|| DEFAULT_HOST.to_string()),
+        }
+    }
+
+    fn url(&self, path: &str) -> String {
+        format!("{}/v1.0/me/{}", self.host.trim_end_matches('/'), path)
+    }
+
+    async fn get(&self, path: &str) -> Result<Value> {
+        let response = self
+            .client
+            .get(self.url(path))
+            .bearer_auth(&self.token)
+            .header("Prefer", "outlook.body-content-type=\"text\"")
+            .send()
+            .await?;
+        Ok(checked(response).await?.json().await?)
+    }
+
+    /// Posts `body` and returns the response, which is empty for sends.
+    async fn post(&self, path: &str, body: &Value) -> Result<Value> {
+        let response = self
+            .client
+            .post(self.url(path))
+            .bearer_auth(&self.token)
+            .json(body)
+            .send()
+            .await?;
+        let text = checked(response).await?.text().await?;
+        Ok(serde_json::from_str(&text).unwrap_or(Value::Null))
+    }
+
+    fn message(draft: &Draft) -> Value {
+        let mut message = json!({
+            "toRecipients": recipients(&draft.to),
+            "ccRecipients": recipients(&draft.cc),
+        });
+        if draft.reply_to.is_none() {
+            message["subject"] = json!(draft.subject);
+            message["body"] = json!({ "contentType": "Text", "content": draft.body });
+        }
+        message
+    }
+}
+
+#[async_trait]
+impl MailBackend for GraphBackend {
+    async fn search(&self, query: &str, limit: usize) -> Result<Vec<MessageSummary>> {
+        let path = if query.trim().is_empty() {
+            format!(
+                "mailFolders/inbox/messages?$top={}&$select={}&$orderby=receivedDateTime%20desc",
+                limit, SUMMARY_FIELDS
+            )
+        } else {
+            format!(
+                "messages?$top={}&$select={}&$search={}",
+                limit,
+                SUMMARY_FIELDS,
+                encode_query(&format!("\"{}\"", query.replace('"', "")))
+            )
+        };
+        let list = self.get(&path).await?;
+        Ok(list["value"]
+            .as_array()
+            .into_iter()
+            .flatten()
+            .map(|message| MessageSummary {
+                id: message["id"].as_str().unwrap_or_default().to_string(),
+                from: address(&message["from"]),
+                subject: message["subject"].as_str().unwrap_or_default().to_string(),
+                date: message["receivedDateTime"].as_str().map(str::to_string),
+                snippet: message["bodyPreview"]
+                    .as_str()
+                    .unwrap_or_default()
+                    .to_string(),
+                unread: message["isRead"].as_bool() == Some(false),
+            })
+            .collect())
+    }
+
+    async fn read(&self, id: &str) -> Result<EmailMessage> {
+        let message = self
+            .get(&format!(
+                "messages/{}?$select=id,from,toRecipients,ccRecipients,subject,receivedDateTime,body",
+                encode_query(id)
+            ))
+            .await?;
+        Ok(EmailMessage {
+            id: id.to_string(),
+            from: address(&message["from"]),
+            to: addresses(&message["toRecipients"]),
+            cc: addresses(&message["ccRecipients"]),
+            subject: message["subject"].as_str().unwrap_or_default().to_string(),
+            date: message["receivedDateTime"].as_str().map(str::to_string),
+            body: message["body"]["content"]
+                .as_str()
+                .unwrap_or_default()
+                .to_string(),
+        })
+    }
+
+    async fn draft(&self, draft: &Draft) -> Result<String> {
+        let created = match &draft.reply_to {
+            Some(id) => {
+                let body = json!({ "message": Self::message(draft), "comment": draft.body });
+                self.post(&format!("messages/{}/createReply", encode_query(id)), &body)
+                    .await?
+            }
+            None => self.post("messages", &Self::message(draft)).await?,
+        };
+        created["id"]
+            .as_str()
+            .map(str::to_string)
+            .ok_or_else(|| anyhow!("Microsoft Graph did not return a draft id"))
+    }
+
+    async fn send(&self, draft: &Draft) -> Result<()> {
+        match &draft.reply_to {
+            Some(id) => {
+                let body = json!({ "message": Self::message(draft), "comment": draft.body });
+                self.post(&format!("messages/{}/reply", encode_query(id)), &body)
+                    .await?;
+            }
+            None => {
+                let body = json!({ "message": Self::message(draft), "saveToSentItems": true });
+                self.post("sendMail", &body).await?;
+            }
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,283 @@
+//! Email accounts the agent can search, read and write from.
+//!
+//! Accounts are configured by name in `GOOSE_EMAIL_ACCOUNTS`:
+//!
+//! ```yaml
+//! GOOSE_EMAIL_ACCOUNTS:
+//!   work:
+//!     provider: microsoft
+//!     address: ada@example.com
+//!   personal:
+//!     provider: gmail
+//!     address: ada@gmail.com
+//! ```
+//!
+//! Each account's OAuth client and refresh token are kept in the secrets
+//! manager under `GOOSE_EMAIL_CREDENTIALS_<NAME>`, and exchanged for an
+//! access token when needed. Gmail goes through the Gmail API and Microsoft
+//! accounts through Microsoft Graph.
+
+pub mod gmail;
+pub mod graph;
+
+use crate::config::Config;
+use anyhow::{anyhow, bail, Context, Result};
+use async_trait::async_trait;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::sync::LazyLock;
+use std::time::{Duration, Instant};
+use tokio::sync::Mutex;
+use utoipa::ToSchema;
+
+pub const ACCOUNTS_KEY: &str = "GOOSE_EMAIL_ACCOUNTS";
+const CREDENTIALS_PREFIX: &str = "GOOSE_EMAIL_CREDENTIALS_";
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(30);
+/// Access tokens are refreshed this long before they expire
+const EXPIRY_MARGIN: Duration = Duration::from_secs(60);
+
+/// Access tokens per account, with when they stop being usable
+static ACCESS_TOKENS: LazyLock<Mutex<HashMap<String, (String, Instant)>>> =
+    LazyLock::new(|| Mutex::new(HashMap::new()));
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum EmailProvider {
+    Gmail,
+    Microsoft,
+}
+
+impl EmailProvider {
+    fn token_endpoint(&self) -> &'static str {
+        match self {
+            Self::Gmail => "https://oauth2.googleapis.com/token",
+            Self::Microsoft => "https://login.microsoftonline.com/common/oauth2/v2.0/token",
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, ToSchema)]
+pub struct EmailAccount {
+    pub provider: EmailProvider,
+    pub address: String,
+    /// API base URL, for proxies and tests
+    #[serde(default)]
+    pub host: Option<String>,
+}
+
+/// OAuth client and refresh token of an account.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct EmailCredentials {
+    pub client_id: String,
+    #[serde(default)]
+    pub client_secret: Option<String>,
+    pub refresh_token: String,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct MessageSummary {
+    pub id: String,
+    pub from: String,
+    pub subject: String,
+    pub date: Option<String>,
+    pub snippet: String,
+    pub unread: bool,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct EmailMessage {
+    pub id: String,
+    pub from: String,
+    pub to: Vec<String>,
+    pub cc: Vec<String>,
+    pub subject: String,
+    pub date: Option<String>,
+    /// Plain-text body
+    pub body: String,
+}
+
+/// A message to save as a draft or send.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Draft {
+    pub to: Vec<String>,
+    #[serde(default)]
+    pub cc: Vec<String>,
+    pub subject: String,
+    pub body: String,
+    /// Message this replies to, keeping it in the same thread
+    #[serde(default)]
+    pub reply_to: Option<String>,
+}
+
+impl Draft {
+    pub fn check(&self) -> Result<()> {
+        if self.to.is_empty() {
+            bail!("A message needs at least one recipient");
+        }
+        for address in self.to.iter().chain(&self.cc) {
+            let valid = address.contains('@') && !address.contains(['\r', '\n', ',']);
+            if !valid {
+                bail!("Invalid address: {}", address);
+            }
+        }
+        if self.subject.contains(['\r', '\n']) {
+            bail!("The subject must be a single line");
+        }
+        Ok(())
+    }
+}
+
+#[async_trait]
+pub trait MailBackend: Send + Sync {
+    /// Messages matching `query` in the provider's search syntax, newest
+    /// first; the inbox when the query is empty
+    async fn search(&self, query: &str, limit: usize) -> Result<Vec<MessageSummary>>;
+
+    async fn read(&self, id: &str) -> Result<EmailMessage>;
+
+    /// Saves a draft and returns its id.
+    async fn draft(&self, draft: &Draft) -> Result<String>;
+
+    async fn send(&self, draft: &Draft) -> Result<()>;
+}
+
+pub fn accounts() -> HashMap<String, EmailAccount> {
+    Config::global()
+        .get_param::<HashMap<String, EmailAccount>>(ACCOUNTS_KEY)
+        .unwrap_or_default()
+}
+
+/// The named account, or the only one when no name is given.
+pub fn account(name: Option<&str>) -> Result<(String, EmailAccount)> {
+    let mut accounts = accounts();
+    match name {
+        Some(name) => accounts
+            .remove_entry(name)
+            .ok_or_else(|| anyhow!("No email account named '{}'", name)),
+        None if accounts.len() == 1 => Ok(accounts.into_iter().next().unwrap()),
+        None if accounts.is_empty() => bail!("No email accounts are configured"),
+        None => {
+            let mut names: Vec<String> = accounts.into_keys().collect();
+            names.sort();
+            bail!("Choose an account: {}", names.join(", "))
+        }
+    }
+}
+
+fn credentials_key(name: &str) -> String {
+    format!("{}{}", CREDENTIALS_PREFIX, name.to_uppercase())
+}
+
+pub fn set_credentials(name: &str, credentials: &EmailCredentials) -> Result<()> {
+    Config::global().set_secret(&credentials_key(name), credentials)?;
+    Ok(())
+}
+
+pub fn delete_credentials(name: &str) -> Result<()> {
+    Config::global().delete_secret(&credentials_key(name))?;
+    Ok(())
+}
+
+fn http_client() -> Result<reqwest::Client> {
+    Ok(reqwest::Client::builder()
+        .timeout(REQUEST_TIMEOUT)
+        .build()?)
+}
+
+/// A current access token for the account, refreshed when needed.
+pub async fn access_token(name: &str, account: &EmailAccount) -> Result<String> {
+    let mut tokens = ACCESS_TOKENS.lock().await;
+    if let Some((token, expires)) = tokens.get(name) {
+        if Instant::now() + EXPIRY_MARGIN < *expires {
+            return Ok(token.clone());
+        }
+    }
+
+    let credentials: EmailCredentials = Config::global()
+        .get_secret(&credentials_key(name))
+        .with_context(|| format!("No credentials stored for email account '{}'", name))?;
+    let mut params = vec![
+        ("grant_type", "refresh_token"),
+        ("refresh_token", credentials.refresh_token.as_str()),
+        ("client_id", credentials.client_id.as_str()),
+    ];
+    if let Some(secret) = &credentials.client_secret {
+        params.push(("client_secret", secret));
+    }
+    let response = http_client()?
+        .post(account.provider.token_endpoint())
+        .form(&params)
+        .send()
+        .await?;
+    if !response.status().is_success() {
+        bail!(
+            "Failed to refresh the token of email account '{}': {}",
+            name,
+            response.text().await.unwrap_or_default()
+        );
+    }
+    let token: serde_json::Value = response.json().await?;
+    let access = token["access_token"]
+        .as_str()
+        .ok_or_else(|| anyhow!("Token response has no access_token"))?
+        .to_string();
+    let lifetime = Duration::from_secs(token["expires_in"].as_u64().unwrap_or(3600));
+    tokens.insert(
+        name.to_string(),
+        (access.clone(), Instant::now() + lifetime),
+    );
+    Ok(access)
+}
+
+pub async fn backend(name: &str, account: &EmailAccount) -> Result<Box<dyn MailBackend>> {
+    let token = access_token(name, account).await?;
+    let client = http_client()?;
+    Ok(match account.provider {
+        EmailProvider::Gmail => Box::new(gmail::GmailBackend::new(client, token, account)),
+        EmailProvider::Microsoft => Box::new(graph::GraphBackend::new(client, token, account)),
+    })
+}
+
+/// Fails with the body of an unsuccessful response.
+async fn checked(response: reqwest::Response) -> Result<reqwest::Response> {
+    let status = response.status();
+    if status.is_success() {
+        return Ok(response);
+    }
+    let body = response.text().await.unwrap_or_default();
+    bail!(
+        "Mail API returned {}: {}",
+        status,
+        crate::utils::safe_truncate(&body, 500)
+    )
+}
+
+fn encode_query(value: &str) -> String {
+    url::form_urlencoded::byte_serialize(value.as_bytes()).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_draft_rejects_header_injection() {
+        let mut draft = Draft {
+            to: vec!["ada@example.com".to_string()],
+            cc: Vec::new(),
+            subject: "Quarterly numbers".to_string(),
+            body: "Attached.".to_string(),
+            reply_to: None,
+        };
+        assert!(draft.check().is_ok());
+
+        draft.subject = "Hi\r\nBcc: eve@example.com".to_string();
+        assert!(draft.check().is_err());
+        draft.subject = "Hi".to_string();
+        draft.cc = vec!["eve@example.com\nBcc: mallory@example.com".to_string()];
+        assert!(draft.check().is_err());
+        draft.cc.clear();
+        draft.to.clear();
+        assert!(draft.check().is_err());
+    }
+}
@@ -10,7 +10,7 @@ use lru::LruCache;
 use std::num::NonZeroUsize;
 use std::sync::Arc;
 use tokio::sync::{OnceCell, RwLock};
-use tracing::{debug, info};
+use tracing::{debug, info, warn};
 
 const DEFAULT_MAX_SESSION: usize = 100;
 
@@ -94,6 +94,9 @@ impl AgentManager {
                 .unwrap_or(false),
         );
         let agent = Arc::new(Agent::with_config(config));
+        if let Err(e) = agent.load_feedback_reflections().await {
+            warn!("Failed to load feedback reflections: {}", e);
+        }
         if let Some(provider) = &*self.default_provider.read().await {
             agent
                 .update_provider(Arc::clone(provider), &session_id)
@@ -0,0 +1,382 @@
+//! Interactive work goes ahead of background work.
+//!
+//! Provider calls pass through one admission gate. Interactive turns may use
+//! every slot. Background work (scheduled runs, detached jobs) is kept out of
+//! the slots reserved for interactive use. It also holds back while an
+//! interactive call is waiting for a slot. Background tool calls likewise
+//! pause while interactive calls are waiting. A background wait is capped so
+//! it cannot starve for good.
+//!
+//! Work is background when it runs inside [`with_execution_mode`] with
+//! [`SessionExecutionMode::Background`]; everything else counts as
+//! interactive. Each time background work is held back, a preemption event
+//! is logged and kept in [`ProviderGate::status`], so a slow job can be
+//! explained.
+
+use super::SessionExecutionMode;
+use crate::config::Config;
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+use std::collections::VecDeque;
+use std::sync::{Arc, LazyLock, Mutex};
+use std::time::{Duration, Instant};
+use tokio::sync::Notify;
+use tokio::task_local;
+use utoipa::ToSchema;
+
+pub const PROVIDER_CONCURRENCY_KEY: &str = "GOOSE_PROVIDER_CONCURRENCY";
+pub const DEFAULT_PROVIDER_CONCURRENCY: usize = 8;
+/// Provider slots background work may never take
+pub const INTERACTIVE_RESERVED_KEY: &str = "GOOSE_INTERACTIVE_RESERVED_SLOTS";
+pub const DEFAULT_INTERACTIVE_RESERVED: usize = 1;
+/// Longest background work defers to interactive demand before it takes a
+/// free slot anyway
+const MAX_BACKGROUND_DEFER: Duration = Duration::from_secs(60);
+const RECENT_EVENTS: usize = 50;
+
+task_local! {
+    static EXECUTION_MODE: SessionExecutionMode;
+}
+
+pub async fn with_execution_mode<F>(mode: SessionExecutionMode, f: F) -> F::Output
+where
+    F: std::future::Future,
+{
+    EXECUTION_MODE.scope(mode, f).await
+}
+
+pub fn is_background() -> bool {
+    EXECUTION_MODE
+        .try_with(|mode| *mode == SessionExecutionMode::Background)
+        .unwrap_or(false)
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum PreemptionKind {
+    /// Waited for a provider slot
+    Provider,
+    /// Held a tool call back
+    Tool,
+}
+
+#[derive(Debug, Clone, Serialize, ToSchema)]
+pub struct PreemptionEvent {
+    pub at: DateTime<Utc>,
+    pub kind: PreemptionKind,
+    pub session_id: Option<String>,
+    pub waited_ms: u64,
+    pub reason: String,
+}
+
+#[derive(Debug, Clone, Serialize, ToSchema)]
+pub struct ProviderGateStatus {
+    pub slots: usize,
+    pub reserved_interactive: usize,
+    pub interactive_active: usize,
+    pub background_active: usize,
+    pub interactive_waiting: usize,
+    pub background_waiting: usize,
+    /// Newest last
+    pub recent_preemptions: Vec<PreemptionEvent>,
+}
+
+#[derive(Default)]
+struct GateState {
+    interactive_active: usize,
+    background_active: usize,
+    interactive_waiting: usize,
+    background_waiting: usize,
+    events: VecDeque<PreemptionEvent>,
+}
+
+pub struct ProviderGate {
+    state: Mutex<GateState>,
+    slots: usize,
+    reserved_interactive: usize,
+    released: Notify,
+}
+
+/// Holds a provider slot until dropped.
+pub struct GatePermit {
+    gate: Arc<ProviderGate>,
+    background: bool,
+}
+
+impl Drop for GatePermit {
+    fn drop(&mut self) {
+        self.gate.with_state(|state| {
+            if self.background {
+                state.background_active -= 1;
+            } else {
+                state.interactive_active -= 1;
+            }
+        });
+        self.gate.released.notify_waiters();
+    }
+}
+
+/// Counts a caller as waiting until admitted or dropped, so a cancelled
+/// `acquire` does not leave the waiting count raised.
+struct Waiting<'a> {
+    gate: &'a ProviderGate,
+    background: bool,
+    admitted: bool,
+}
+
+impl Drop for Waiting<'_> {
+    fn drop(&mut self) {
+        if self.admitted {
+            return;
+        }
+        self.gate.with_state(|state| {
+            if self.background {
+                state.background_waiting -= 1;
+            } else {
+                state.interactive_waiting -= 1;
+            }
+        });
+        self.gate.released.notify_waiters();
+    }
+}
+
+static GATE: LazyLock<Arc<ProviderGate>> = LazyLock::new(|| {
+    let config = Config::global();
+    Arc::new(ProviderGate::new(
+        config
+            .get_param(PROVIDER_CONCURRENCY_KEY)
+            .unwrap_or(DEFAULT_PROVIDER_CONCURRENCY),
+        config
+            .get_param(INTERACTIVE_RESERVED_KEY)
+            .unwrap_or(DEFAULT_INTERACTIVE_RESERVED),
+    ))
+});
+
+pub fn gate() -> Arc<ProviderGate> {
+    GATE.clone()
+}
+
+impl ProviderGate {
+    pub fn new(slots: usize, reserved_interactive: usize) -> Self {
+        let slots = slots.max(1);
+        Self {
+            state: Mutex::new(GateState::default()),
+            slots,
+            reserved_interactive: reserved_interactive.min(slots - 1),
+            released: Notify::new(),
+        }
+    }
+
+    fn with_state<T>(&self, f: impl FnOnce(&mut GateState) -> T) -> T {
+        let mut state = self.state.lock().unwrap_or_else(|e| e.into_inner());
+        f(&mut state)
+    }
+
+    /// Why background work may not start now, if it may not
+    fn background_blocked(&self, state: &GateState, deferred_too_long: bool) -> Option<String> {
+        let active = state.interactive_active + state.background_active;
+        if active >= self.slots {
+            return Some("all provider slots are busy".to_string());
+        }
+        if state.background_active >= self.slots - self.reserved_interactive {
+            return Some(format!(
+                "{} provider slot(s) are reserved for interactive sessions",
+                self.reserved_interactive
+            ));
+        }
+        if state.interactive_waiting > 0 && !deferred_too_long {
+            return Some(format!(
+                "{} interactive request(s) are waiting",
+                state.interactive_waiting
+            ));
+        }
+        None
+    }
+
+    fn record(
+        &self,
+        kind: PreemptionKind,
+        session_id: Option<String>,
+        waited: Duration,
+        reason: String,
+    ) {
+        let event = PreemptionEvent {
+            at: Utc::now(),
+            kind,
+            session_id,
+            waited_ms: waited.as_millis() as u64,
+            reason,
+        };
+        tracing::info!(
+            counter.goose.background_preemptions = 1,
+            session_id = ?event.session_id,
+            waited_ms = event.waited_ms,
+            "Background work yielded to interactive sessions: {}",
+            event.reason
+        );
+        self.with_state(|state| {
+            if state.events.len() >= RECENT_EVENTS {
+                state.events.pop_front();
+            }
+            state.events.push_back(event);
+        });
+    }
+
+    /// Waits for a provider slot for the current task's execution mode.
+    pub async fn acquire(self: &Arc<Self>, session_id: &str) -> GatePermit {
+        let background = is_background();
+        let started = Instant::now();
+        let mut held_back: Option<String> = None;
+        self.with_state(|state| {
+            if background {
+                state.background_waiting += 1;
+            } else {
+                state.interactive_waiting += 1;
+            }
+        });
+        let mut waiting = Waiting {
+            gate: self,
+            background,
+            admitted: false,
+        };
+
+        loop {
+            let released = self.released.notified();
+            let admitted = self.with_state(|state| {
+                let blocked = if background {
+                    self.background_blocked(state, started.elapsed() >= MAX_BACKGROUND_DEFER)
+                } else if state.interactive_active + state.background_active >= self.slots {
+                    Some("all provider slots are busy".to_string())
+                } else {
+                    None
+                };
+                match blocked {
+                    Some(reason) => {
+                        held_back.get_or_insert(reason);
+                        false
+                    }
+                    None => {
+                        if background {
+                            state.background_waiting -= 1;
+                            state.background_active += 1;
+                        } else {
+                            state.interactive_waiting -= 1;
+                            state.interactive_active += 1;
+                        }
+                        true
+                    }
+                }
+            });
+            if admitted {
+                waiting.admitted = true;
+                break;
+            }
+            // Re-check periodically since a deferral can expire without a release
+            let _ = tokio::time::timeout(Duration::from_secs(1), released).await;
+        }
+
+        if let Some(reason) = held_back.filter(|_| background) {
+            self.record(
+                PreemptionKind::Provider,
+                Some(session_id.to_string()),
+                started.elapsed(),
+                reason,
+            );
+        }
+        GatePermit {
+            gate: self.clone(),
+            background,
+        }
+    }
+
+    /// Holds a background tool call back while interactive calls wait for
+    /// the provider. Interactive calls return at once.
+    pub async fn yield_to_interactive(&self, session_id: &str) {
+        if !is_background() {
+            return;
+        }
+        let started = Instant::now();
+        let mut waiting = self.with_state(|state| state.interactive_waiting);
+        if waiting == 0 {
+            return;
+        }
+        let reason = format!("{} interactive request(s) are waiting", waiting);
+        while waiting > 0 && started.elapsed() < MAX_BACKGROUND_DEFER {
+            let _ = tokio::time::timeout(Duration::from_secs(1), self.released.notified()).await;
+            waiting = self.with_state(|state| state.interactive_waiting);
+        }
+        self.record(
+            PreemptionKind::Tool,
+            Some(session_id.to_string()),
+            started.elapsed(),
+            reason,
+        );
+    }
+
+    pub fn status(&self) -> ProviderGateStatus {
+        self.with_state(|state| ProviderGateStatus {
+            slots: self.slots,
+            reserved_interactive: self.reserved_interactive,
+            interactive_active: state.interactive_active,
+            background_active: state.background_active,
+            interactive_waiting: state.interactive_waiting,
+            background_waiting: state.background_waiting,
+            recent_preemptions: state.events.iter().cloned().collect(),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_background_keeps_out_of_reserved_slots() {
+        let gate = Arc::new(ProviderGate::new(2, 1));
+        let background = with_execution_mode(SessionExecutionMode::Background, async {
+            gate.acquire("job").await
+        })
+        .await;
+        assert_eq!(gate.status().background_active, 1);
+
+        let second = {
+            let gate = gate.clone();
+            tokio::spawn(with_execution_mode(
+                SessionExecutionMode::Background,
+                async move {
+                    let _permit = gate.acquire("job-2").await;
+                },
+            ))
+        };
+        tokio::time::sleep(Duration::from_millis(50)).await;
+        assert_eq!(gate.status().background_waiting, 1);
+
+        let interactive = gate.acquire("chat").await;
+        assert_eq!(gate.status().interactive_active, 1);
+        drop(interactive);
+
+        drop(background);
+        second.await.unwrap();
+        let status = gate.status();
+        assert_eq!(status.background_active, 0);
+        assert_eq!(status.recent_preemptions.len(), 1);
+        assert_eq!(
+            status.recent_preemptions[0].session_id.as_deref(),
+            Some("job-2")
+        );
+    }
+
+    #[tokio::test]
+    async fn test_cancelled_acquire_stops_waiting() {
+        let gate = Arc::new(ProviderGate::new(1, 0));
+        let held = gate.acquire("chat").await;
+        let cancelled =
+            tokio::time::timeout(Duration::from_millis(50), gate.acquire("chat-2")).await;
+        assert!(cancelled.is_err());
+        assert_eq!(gate.status().interactive_waiting, 0);
+
+        drop(held);
+        let _permit = gate.acquire("chat-3").await;
+        assert_eq!(gate.status().interactive_active, 1);
+    }
+}
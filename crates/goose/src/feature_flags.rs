@@ -0,0 +1,408 @@
+//! Feature flags evaluated per session and user.
+//!
+//! Flags are defined under `GOOSE_FEATURE_FLAGS`. A flag is on for a
+//! subject when it is enabled, the subject falls inside its rollout
+//! percentage, and every flag it requires is on at the required version.
+//! Rollout buckets are taken from the user when there is one, so a user
+//! sees the same value in every session, and from the session otherwise.
+//! Sessions can override single flags; the overrides live in the session's
+//! extension data.
+
+use crate::config::Config;
+use crate::observability::team_accounting::TeamAssignment;
+use crate::session::extension_data::ExtensionState;
+use crate::session::SessionManager;
+use anyhow::{bail, Result};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::sync::LazyLock;
+use tokio::sync::broadcast;
+use utoipa::ToSchema;
+
+pub const FLAGS_KEY: &str = "GOOSE_FEATURE_FLAGS";
+
+const SESSION_CHANGE_CAPACITY: usize = 64;
+
+/// Sessions whose overrides changed; flag definitions changes arrive
+/// through [`Config::subscribe`] under [`FLAGS_KEY`].
+static SESSION_CHANGES: LazyLock<broadcast::Sender<String>> =
+    LazyLock::new(|| broadcast::channel(SESSION_CHANGE_CAPACITY).0);
+
+fn full_rollout() -> u8 {
+    100
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, ToSchema)]
+pub struct FlagDefinition {
+    #[serde(default)]
+    pub description: String,
+    #[serde(default)]
+    pub enabled: bool,
+    /// Share of subjects the flag is on for, 0 to 100
+    #[serde(default = "full_rollout")]
+    pub rollout_percent: u8,
+    /// Users the flag is on for regardless of the rollout
+    #[serde(default)]
+    pub users: Vec<String>,
+    /// Version of the feature behind the flag, for flags that require it
+    #[serde(default)]
+    pub version: Option<String>,
+    /// Flags that must be on, each with the minimum version it must be at
+    #[serde(default)]
+    pub requires: BTreeMap<String, Option<String>>,
+}
+
+/// Who a flag is evaluated for
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize, ToSchema)]
+pub struct FlagSubject {
+    #[serde(default)]
+    pub session_id: Option<String>,
+    #[serde(default)]
+    pub user_id: Option<String>,
+}
+
+/// Flags a session turned on or off regardless of their definition
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct SessionFlagOverrides {
+    pub flags: BTreeMap<String, bool>,
+}
+
+impl ExtensionState for SessionFlagOverrides {
+    const EXTENSION_NAME: &'static str = "feature_flags";
+    const VERSION: &'static str = "v0";
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum FlagReason {
+    Disabled,
+    SessionOverride,
+    User,
+    Rollout,
+    OutsideRollout,
+    /// A required flag is off or below the required version
+    Requires,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, ToSchema)]
+pub struct FlagEvaluation {
+    pub enabled: bool,
+    pub version: Option<String>,
+    pub reason: FlagReason,
+    /// The required flag that kept this one off
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub blocked_by: Option<String>,
+}
+
+pub fn flags() -> HashMap<String, FlagDefinition> {
+    Config::global().get_param(FLAGS_KEY).unwrap_or_default()
+}
+
+/// Checks that every required flag exists and that no flag requires itself
+/// through others.
+pub fn validate(flags: &HashMap<String, FlagDefinition>) -> Result<()> {
+    for (name, flag) in flags {
+        if flag.rollout_percent > 100 {
+            bail!("Flag '{}' rolls out to more than 100%", name);
+        }
+        if let Some(missing) = flag.requires.keys().find(|r| !flags.contains_key(*r)) {
+            bail!("Flag '{}' requires unknown flag '{}'", name, missing);
+        }
+    }
+    fn visit<'a>(
+        name: &'a str,
+        flags: &'a HashMap<String, FlagDefinition>,
+        path: &mut Vec<&'a str>,
+        done: &mut HashSet<&'a str>,
+    ) -> Result<()> {
+        if let Some(start) = path.iter().position(|n| *n == name) {
+            bail!(
+                "Flags require each other: {} -> {}",
+                path[start..].join(" -> "),
+                name
+            );
+        }
+        if !done.insert(name) {
+            return Ok(());
+        }
+        path.push(name);
+        for required in flags[name].requires.keys() {
+            visit(required, flags, path, done)?;
+        }
+        path.pop();
+        Ok(())
+    }
+    let mut done = HashSet::new();
+    for name in flags.keys() {
+        visit(name, flags, &mut Vec::new(), &mut done)?;
+    }
+    Ok(())
+}
+
+pub fn set_flag(name: &str, flag: FlagDefinition) -> Result<()> {
+    let mut all = flags();
+    all.insert(name.to_string(), flag);
+    validate(&all)?;
+    Config::global().set_param(FLAGS_KEY, all)?;
+    Ok(())
+}
+
+/// Removes a flag. Returns whether it existed; flags other flags require
+/// cannot be removed.
+pub fn remove_flag(name: &str) -> Result<bool> {
+    let mut all = flags();
+    if let Some(dependent) = all
+        .iter()
+        .find(|(_, flag)| flag.requires.contains_key(name))
+    {
+        bail!("Flag '{}' is required by '{}'", name, dependent.0);
+    }
+    if all.remove(name).is_none() {
+        return Ok(false);
+    }
+    Config::global().set_param(FLAGS_KEY, all)?;
+    Ok(true)
+}
+
+/// Stable bucket in 0..100 for `flag` and `key`
+fn bucket(flag: &str, key: &str) -> u8 {
+    let digest = Sha256::digest(format!("{}:{}", flag, key).as_bytes());
+    (u16::from_be_bytes([digest[0], digest[1]]) % 100) as u8
+}
+
+/// Compares dotted versions numerically; missing parts count as zero.
+fn version_at_least(version: Option<&str>, minimum: &str) -> bool {
+    let parts = |v: &str| -> Vec<u64> { v.split('.').map(|p| p.parse().unwrap_or(0)).collect() };
+    let Some(version) = version else {
+        return false;
+    };
+    let (have, want) = (parts(version), parts(minimum));
+    let len = have.len().max(want.len());
+    let at = |v: &[u64], i: usize| v.get(i).copied().unwrap_or(0);
+    (0..len)
+        .map(|i| at(&have, i).cmp(&at(&want, i)))
+        .find(|ordering| ordering.is_ne())
+        .is_none_or(|ordering| ordering.is_gt())
+}
+
+struct Evaluator<'a> {
+    flags: &'a HashMap<String, FlagDefinition>,
+    subject: &'a FlagSubject,
+    overrides: &'a BTreeMap<String, bool>,
+    results: BTreeMap<String, FlagEvaluation>,
+    visiting: HashSet<String>,
+}
+
+impl Evaluator<'_> {
+    fn resolve(&mut self, name: &str) -> FlagEvaluation {
+        if let Some(done) = self.results.get(name) {
+            return done.clone();
+        }
+        let flags = self.flags;
+        let Some(flag) = flags.get(name) else {
+            return off(None, FlagReason::Disabled, None);
+        };
+        // A cycle slipped past validation; keep every flag in it off
+        if !self.visiting.insert(name.to_string()) {
+            return off(flag.version.clone(), FlagReason::Requires, Some(name));
+        }
+
+        let mut result = self.own_value(name, flag);
+        if result.enabled {
+            for (required, minimum) in &flag.requires {
+                let dependency = self.resolve(required);
+                let satisfied = dependency.enabled
+                    && minimum.as_deref().is_none_or(|minimum| {
+                        version_at_least(dependency.version.as_deref(), minimum)
+                    });
+                if !satisfied {
+                    result = off(flag.version.clone(), FlagReason::Requires, Some(required));
+                    break;
+                }
+            }
+        }
+
+        self.visiting.remove(name);
+        self.results.insert(name.to_string(), result.clone());
+        result
+    }
+
+    /// The flag's value before its requirements are checked
+    fn own_value(&self, name: &str, flag: &FlagDefinition) -> FlagEvaluation {
+        let on = |reason| FlagEvaluation {
+            enabled: true,
+            version: flag.version.clone(),
+            reason,
+            blocked_by: None,
+        };
+        if let Some(&value) = self.overrides.get(name) {
+            return if value {
+                on(FlagReason::SessionOverride)
+            } else {
+                off(flag.version.clone(), FlagReason::SessionOverride, None)
+            };
+        }
+        if !flag.enabled {
+            return off(flag.version.clone(), FlagReason::Disabled, None);
+        }
+        let user = self.subject.user_id.as_deref();
+        if user.is_some_and(|user| flag.users.iter().any(|u| u == user)) {
+            return on(FlagReason::User);
+        }
+        let in_rollout = match user.or(self.subject.session_id.as_deref()) {
+            Some(key) => bucket(name, key) < flag.rollout_percent,
+            None => flag.rollout_percent >= 100,
+        };
+        if in_rollout {
+            on(FlagReason::Rollout)
+        } else {
+            off(flag.version.clone(), FlagReason::OutsideRollout, None)
+        }
+    }
+}
+
+fn off(version: Option<String>, reason: FlagReason, blocked_by: Option<&str>) -> FlagEvaluation {
+    FlagEvaluation {
+        enabled: false,
+        version,
+        reason,
+        blocked_by: blocked_by.map(str::to_string),
+    }
+}
+
+/// Every flag's value for `subject`
+pub fn evaluate(
+    flags: &HashMap<String, FlagDefinition>,
+    subject: &FlagSubject,
+    overrides: &BTreeMap<String, bool>,
+) -> BTreeMap<String, FlagEvaluation> {
+    let mut evaluator = Evaluator {
+        flags,
+        subject,
+        overrides,
+        results: BTreeMap::new(),
+        visiting: HashSet::new(),
+    };
+    for name in flags.keys() {
+        evaluator.resolve(name);
+    }
+    evaluator.results
+}
+
+/// Every flag's value for `subject`, with its session's overrides applied.
+/// The user defaults to the one the session is assigned to.
+pub async fn evaluate_for(subject: &FlagSubject) -> Result<BTreeMap<String, FlagEvaluation>> {
+    let mut subject = subject.clone();
+    let mut overrides = BTreeMap::new();
+    if let Some(session_id) = &subject.session_id {
+        let session = SessionManager::instance()
+            .get_session(session_id, false)
+            .await?;
+        if let Some(saved) = SessionFlagOverrides::from_extension_data(&session.extension_data) {
+            overrides = saved.flags;
+        }
+        if subject.user_id.is_none() {
+            subject.user_id = TeamAssignment::from_extension_data(&session.extension_data)
+                .and_then(|assignment| assignment.user);
+        }
+    }
+    Ok(evaluate(&flags(), &subject, &overrides))
+}
+
+/// Replaces a session's overrides and tells subscribers
+pub async fn set_session_overrides(session_id: &str, flags: BTreeMap<String, bool>) -> Result<()> {
+    let manager = SessionManager::instance();
+    let mut extension_data = manager.get_session(session_id, false).await?.extension_data;
+    SessionFlagOverrides { flags }.to_extension_data(&mut extension_data)?;
+    manager
+        .update(session_id)
+        .extension_data(extension_data)
+        .apply()
+        .await?;
+    // No receivers is fine; no client is listening
+    let _ = SESSION_CHANGES.send(session_id.to_string());
+    Ok(())
+}
+
+/// Ids of sessions as their overrides change
+pub fn subscribe_session_changes() -> broadcast::Receiver<String> {
+    SESSION_CHANGES.subscribe()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn flag(enabled: bool, version: Option<&str>) -> FlagDefinition {
+        FlagDefinition {
+            description: String::new(),
+            enabled,
+            rollout_percent: 100,
+            users: Vec::new(),
+            version: version.map(str::to_string),
+            requires: BTreeMap::new(),
+        }
+    }
+
+    #[test]
+    fn test_dependency_needs_minimum_version() {
+        let mut canary = flag(true, None);
+        canary
+            .requires
+            .insert("conductor".to_string(), Some("2.1".to_string()));
+        let mut flags = HashMap::from([
+            ("conductor".to_string(), flag(true, Some("2.0.5"))),
+            ("canary_ota".to_string(), canary),
+        ]);
+        let subject = FlagSubject::default();
+
+        let values = evaluate(&flags, &subject, &BTreeMap::new());
+        assert!(values["conductor"].enabled);
+        assert!(!values["canary_ota"].enabled);
+        assert_eq!(
+            values["canary_ota"].blocked_by.as_deref(),
+            Some("conductor")
+        );
+
+        flags.get_mut("conductor").unwrap().version = Some("2.1".to_string());
+        assert!(evaluate(&flags, &subject, &BTreeMap::new())["canary_ota"].enabled);
+
+        let overrides = BTreeMap::from([("conductor".to_string(), false)]);
+        assert!(!evaluate(&flags, &subject, &overrides)["canary_ota"].enabled);
+
+        flags
+            .get_mut("conductor")
+            .unwrap()
+            .requires
+            .insert("canary_ota".to_string(), None);
+        assert!(validate(&flags).is_err());
+    }
+
+    #[test]
+    fn test_rollout_is_stable_per_user() {
+        let mut half = flag(true, None);
+        half.rollout_percent = 50;
+        let flags = HashMap::from([("half".to_string(), half)]);
+
+        let on = (0..200)
+            .filter(|i| {
+                let subject = FlagSubject {
+                    session_id: Some(format!("session-{}", i)),
+                    user_id: Some(format!("user-{}", i)),
+                };
+                let first = evaluate(&flags, &subject, &BTreeMap::new())["half"].enabled;
+                let other_session = FlagSubject {
+                    session_id: Some("another".to_string()),
+                    ..subject
+                };
+                assert_eq!(
+                    evaluate(&flags, &other_session, &BTreeMap::new())["half"].enabled,
+                    first
+                );
+                first
+            })
+            .count();
+        assert!((60..140).contains(&on));
+    }
+}
@@ -253,6 +253,46 @@ pub enum TopicMode {
     Allowlist,
 }
 
+/// Coarse strictness levels that map onto the detailed configuration
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum GuardrailsStrictness {
+    /// Guardrails disabled
+    Off,
+    /// Low sensitivity, errors fail open
+    Relaxed,
+    /// Default detector settings
+    #[default]
+    Standard,
+    /// High sensitivity on every detector, errors fail closed
+    Strict,
+}
+
+impl GuardrailsStrictness {
+    /// Build a configuration for this level, starting from `base`
+    pub fn apply(self, base: &GuardrailsConfig) -> GuardrailsConfig {
+        let mut config = match self {
+            GuardrailsStrictness::Standard => GuardrailsConfig::default(),
+            _ => base.clone(),
+        };
+        config.enabled = self != GuardrailsStrictness::Off;
+
+        let (sensitivity, fail_mode) = match self {
+            GuardrailsStrictness::Off | GuardrailsStrictness::Standard => return config,
+            GuardrailsStrictness::Relaxed => (Sensitivity::Low, FailMode::FailOpen),
+            GuardrailsStrictness::Strict => (Sensitivity::High, FailMode::FailClosed),
+        };
+        config.fail_mode = fail_mode;
+        config.prompt_injection.sensitivity = sensitivity;
+        config.pii.sensitivity = sensitivity;
+        config.jailbreak.sensitivity = sensitivity;
+        config.topics.sensitivity = sensitivity;
+        config.keywords.sensitivity = sensitivity;
+        config.secrets.sensitivity = sensitivity;
+        config
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -272,6 +312,25 @@ mod tests {
         assert_eq!(Sensitivity::High.multiplier(), 1.2);
     }
 
+    #[test]
+    fn test_strictness_levels() {
+        let base = GuardrailsConfig::default();
+        assert!(!GuardrailsStrictness::Off.apply(&base).enabled);
+
+        let strict = GuardrailsStrictness::Strict.apply(&base);
+        assert!(strict.enabled);
+        assert_eq!(strict.jailbreak.sensitivity, Sensitivity::High);
+        assert_eq!(strict.fail_mode, FailMode::FailClosed);
+
+        let relaxed = GuardrailsStrictness::Relaxed.apply(&strict);
+        assert_eq!(relaxed.pii.sensitivity, Sensitivity::Low);
+        assert_eq!(relaxed.fail_mode, FailMode::FailOpen);
+
+        let standard = GuardrailsStrictness::Standard.apply(&relaxed);
+        assert_eq!(standard.pii.sensitivity, Sensitivity::High);
+        assert_eq!(standard.fail_mode, FailMode::FailClosed);
+    }
+
     #[test]
     fn test_config_serialization() {
         let config = GuardrailsConfig::default();
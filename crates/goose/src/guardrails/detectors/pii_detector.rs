@@ -232,6 +232,24 @@ impl PiiDetector {
         }
     }
 
+    /// Every PII match in `input` with its byte range, leaving out allowed
+    /// types and numbers that fail the credit card checksum.
+    pub fn find(&self, input: &str) -> Vec<(PiiType, std::ops::Range<usize>)> {
+        PII_PATTERNS
+            .iter()
+            .filter(|pattern| !self.allowed_types.contains(&pattern.pii_type))
+            .flat_map(|pattern| {
+                pattern
+                    .regex
+                    .find_iter(input)
+                    .filter(|m| {
+                        pattern.pii_type != PiiType::CreditCard || self.validate_luhn(m.as_str())
+                    })
+                    .map(|m| (pattern.pii_type, m.range()))
+            })
+            .collect()
+    }
+
     /// Redact a value for safe logging
     #[allow(clippy::string_slice)]
     fn redact_value(&self, value: &str) -> String {
@@ -165,6 +165,11 @@ impl PromptInjectionDetector {
         Self { config }
     }
 
+    /// Whether any injection pattern matches `text`, regardless of confidence
+    pub(crate) fn matches_any(&self, text: &str) -> bool {
+        self.config.enabled && INJECTION_PATTERNS.is_match(text)
+    }
+
     /// Get confidence score based on number of matches and their severities
     fn calculate_confidence(&self, matches: &[usize]) -> (f64, Severity) {
         if matches.is_empty() {
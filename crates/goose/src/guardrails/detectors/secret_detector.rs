@@ -331,6 +331,19 @@ impl SecretDetector {
         Self { config }
     }
 
+    /// Every secret match in `input` with its byte range
+    pub fn find(&self, input: &str) -> Vec<(SecretType, std::ops::Range<usize>)> {
+        SECRET_PATTERNS
+            .iter()
+            .flat_map(|pattern| {
+                pattern
+                    .regex
+                    .find_iter(input)
+                    .map(|m| (pattern.secret_type, m.range()))
+            })
+            .collect()
+    }
+
     /// Redact a secret value
     #[allow(clippy::string_slice)]
     fn redact_secret(&self, value: &str) -> String {
@@ -12,6 +12,9 @@
 //! - `KeywordDetector` - Detects custom keyword blocklists
 //! - `SecretDetector` - Detects API keys, tokens, and credentials
 //!
+//! Tool results are screened for prompt injection separately, see
+//! [`tool_results`].
+//!
 //! ## Usage
 //!
 //! ```rust,ignore
@@ -28,8 +31,9 @@
 pub mod config;
 pub mod detectors;
 pub mod errors;
+pub mod tool_results;
 
-pub use config::{DetectorConfig, FailMode, GuardrailsConfig, Sensitivity};
+pub use config::{DetectorConfig, FailMode, GuardrailsConfig, GuardrailsStrictness, Sensitivity};
 pub use detectors::{
     DetectionContext, DetectionResult, Detector, JailbreakDetector, KeywordDetector, PiiDetector,
     PromptInjectionDetector, SecretDetector, TopicDetector,
@@ -0,0 +1,186 @@
+//! GitHub Issues through the REST API.
+
+use super::{Issue, IssueQuery, IssueState, IssueTracker, IssueUpdate, NewIssue, TrackerConfig};
+use anyhow::{anyhow, bail, Result};
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use serde_json::{json, Value};
+
+const DEFAULT_API: &str = "https://api.github.com";
+const DEFAULT_LIMIT: usize = 20;
+
+pub struct GithubTracker {
+    client: reqwest::Client,
+    token: String,
+    api: String,
+    repo: String,
+}
+
+fn names(list: &Value, field: &str) -> Vec<String> {
+    list.as_array()
+        .into_iter()
+        .flatten()
+        .filter_map(|item| item[field].as_str().map(str::to_string))
+        .collect()
+}
+
+pub(crate) fn issue_from(issue: &Value) -> Option<Issue> {
+    Some(Issue {
+        key: issue["number"].as_u64()?.to_string(),
+        title: issue["title"].as_str().unwrap_or_default().to_string(),
+        body: issue["body"].as_str().unwrap_or_default().to_string(),
+        state: match issue["state"].as_str() {
+            Some("closed") => IssueState::Closed,
+            _ => IssueState::Open,
+        },
+        url: issue["html_url"].as_str().unwrap_or_default().to_string(),
+        assignees: names(&issue["assignees"], "login"),
+        labels: names(&issue["labels"], "name"),
+        updated_at: issue["updated_at"]
+            .as_str()
+            .and_then(|s| DateTime::parse_from_rfc3339(s).ok())
+            .map(|at| at.with_timezone(&Utc)),
+    })
+}
+
+/// An `issues` event assigning `assignee`. Pull requests are left out.
+pub(crate) fn assignment(assignee: &str, payload: &Value) -> Option<Issue> {
+    let assigned = payload["action"].as_str() == Some("assigned")
+        && payload["assignee"]["login"]
+            .as_str()
+            .is_some_and(|login| login.eq_ignore_ascii_case(assignee));
+    if !assigned || payload["issue"].get("pull_request").is_some() {
+        return None;
+    }
+    issue_from(&payload["issue"])
+}
+
+impl GithubTracker {
+    pub fn new(client: reqwest::Client, token: String, config: &TrackerConfig) -> Self {
+        Self {
+            client,
+            token,
+            api: config
+                .url
+                .clone()
+                .unwrap_or_else(|| DEFAULT_API.to_string())
+                .trim_end_matches('/')
+                .to_string(),
+            repo: config.project.clone(),
+        }
+    }
+
+    async fn send(&self, request: reqwest::RequestBuilder) -> Result<Value> {
+        let response = request
+            .bearer_auth(&self.token)
+            .header("Accept", "application/vnd.github+json")
+            .send()
+            .await?;
+        let status = response.status();
+        let text = response.text().await?;
+        if !status.is_success() {
+            bail!(
+                "GitHub returned {}: {}",
+                status,
+                crate::utils::safe_truncate(&text, 500)
+            );
+        }
+        Ok(serde_json::from_str(&text).unwrap_or(Value::Null))
+    }
+
+    fn issue_url(&self, key: &str) -> Result<String> {
+        Ok(format!(
+            "{}/repos/{}/issues/{}",
+            self.api,
+            self.repo,
+            super::valid_key(key)?
+        ))
+    }
+}
+
+#[async_trait]
+impl IssueTracker for GithubTracker {
+    async fn search(&self, query: &IssueQuery) -> Result<Vec<Issue>> {
+        let mut q = format!("repo:{} is:issue", self.repo);
+        match query.state {
+            Some(IssueState::Closed) => q.push_str(" is:closed"),
+            Some(_) => q.push_str(" is:open"),
+            None => {}
+        }
+        if let Some(assignee) = &query.assignee {
+            q.push_str(&format!(" assignee:{}", assignee));
+        }
+        if let Some(text) = &query.text {
+            q.push_str(&format!(" {}", text));
+        }
+        let limit = query.limit.unwrap_or(DEFAULT_LIMIT).clamp(1, 100);
+        let found = self
+            .send(
+                self.client
+                    .get(format!("{}/search/issues", self.api))
+                    .query(&[("q", q), ("per_page", limit.to_string())]),
+            )
+            .await?;
+        Ok(found["items"]
+            .as_array()
+            .into_iter()
+            .flatten()
+            .filter_map(issue_from)
+            .collect())
+    }
+
+    async fn get(&self, key: &str) -> Result<Issue> {
+        let issue = self.send(self.client.get(self.issue_url(key)?)).await?;
+        issue_from(&issue).ok_or_else(|| anyhow!("GitHub returned no issue {}", key))
+    }
+
+    async fn create(&self, issue: &NewIssue) -> Result<Issue> {
+        let created = self
+            .send(
+                self.client
+                    .post(format!("{}/repos/{}/issues", self.api, self.repo))
+                    .json(&json!({
+                        "title": issue.title,
+                        "body": issue.body,
+                        "labels": issue.labels,
+                    })),
+            )
+            .await?;
+        issue_from(&created).ok_or_else(|| anyhow!("GitHub did not return the new issue"))
+    }
+
+    async fn update(&self, key: &str, update: &IssueUpdate) -> Result<()> {
+        let mut fields = serde_json::Map::new();
+        if let Some(title) = &update.title {
+            fields.insert("title".into(), json!(title));
+        }
+        if let Some(body) = &update.body {
+            fields.insert("body".into(), json!(body));
+        }
+        if let Some(labels) = &update.labels {
+            fields.insert("labels".into(), json!(labels));
+        }
+        // GitHub has no in-progress state; such tickets stay open
+        if let Some(state) = update.state {
+            let state = if state == IssueState::Closed {
+                "closed"
+            } else {
+                "open"
+            };
+            fields.insert("state".into(), json!(state));
+        }
+        if !fields.is_empty() {
+            self.send(self.client.patch(self.issue_url(key)?).json(&fields))
+                .await?;
+        }
+        if let Some(comment) = &update.comment {
+            self.send(
+                self.client
+                    .post(format!("{}/comments", self.issue_url(key)?))
+                    .json(&json!({ "body": comment })),
+            )
+            .await?;
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,259 @@
+//! Jira through the REST API (v2, for plain-text descriptions and comments).
+
+use super::{Issue, IssueQuery, IssueState, IssueTracker, IssueUpdate, NewIssue, TrackerConfig};
+use anyhow::{anyhow, bail, Result};
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use serde_json::{json, Value};
+
+const DEFAULT_LIMIT: usize = 20;
+const ISSUE_FIELDS: &str = "summary,description,status,assignee,labels,updated";
+/// Issue type of tickets goose opens
+const ISSUE_TYPE: &str = "Task";
+
+pub struct JiraTracker {
+    client: reqwest::Client,
+    token: String,
+    user: Option<String>,
+    base: String,
+    project: String,
+}
+
+fn state_of(category: Option<&str>) -> IssueState {
+    match category {
+        Some("done") => IssueState::Closed,
+        Some("indeterminate") => IssueState::InProgress,
+        _ => IssueState::Open,
+    }
+}
+
+fn category_of(state: IssueState) -> &'static str {
+    match state {
+        IssueState::Open => "new",
+        IssueState::InProgress => "indeterminate",
+        IssueState::Closed => "done",
+    }
+}
+
+/// Who an assignee field names: account id on Cloud, user name on Server.
+fn account(user: &Value) -> Option<&str> {
+    user["accountId"].as_str().or_else(|| user["name"].as_str())
+}
+
+pub(crate) fn issue_from(base: &str, issue: &Value) -> Option<Issue> {
+    let key = issue["key"].as_str()?;
+    let fields = &issue["fields"];
+    Some(Issue {
+        key: key.to_string(),
+        title: fields["summary"].as_str().unwrap_or_default().to_string(),
+        body: fields["description"]
+            .as_str()
+            .unwrap_or_default()
+            .to_string(),
+        state: state_of(fields["status"]["statusCategory"]["key"].as_str()),
+        url: format!("{}/browse/{}", base, key),
+        assignees: fields["assignee"]["displayName"]
+            .as_str()
+            .map(|name| vec![name.to_string()])
+            .unwrap_or_default(),
+        labels: fields["labels"]
+            .as_array()
+            .into_iter()
+            .flatten()
+            .filter_map(|l| l.as_str().map(str::to_string))
+            .collect(),
+        updated_at: fields["updated"]
+            .as_str()
+            .and_then(|s| DateTime::parse_from_str(s, "%Y-%m-%dT%H:%M:%S%.f%z").ok())
+            .map(|at| at.with_timezone(&Utc)),
+    })
+}
+
+/// A created or updated ticket now assigned to `assignee`. Updates count
+/// only when the assignee is what changed.
+pub(crate) fn assignment(config: &TrackerConfig, assignee: &str, payload: &Value) -> Option<Issue> {
+    let reassigned = match payload["webhookEvent"].as_str() {
+        Some("jira:issue_created") => true,
+        Some("jira:issue_updated") => payload["changelog"]["items"]
+            .as_array()
+            .into_iter()
+            .flatten()
+            .any(|item| {
+                item["field"]
+                    .as_str()
+                    .is_some_and(|f| f.eq_ignore_ascii_case("assignee"))
+            }),
+        _ => false,
+    };
+    let issue = &payload["issue"];
+    if !reassigned || account(&issue["fields"]["assignee"]) != Some(assignee) {
+        return None;
+    }
+    issue_from(config.url.as_deref()?.trim_end_matches('/'), issue)
+}
+
+/// JQL string literal
+fn quoted(value: &str) -> String {
+    format!("\"{}\"", value.replace('\\', "\\\\").replace('"', "\\\""))
+}
+
+impl JiraTracker {
+    pub fn new(client: reqwest::Client, token: String, config: &TrackerConfig) -> Result<Self> {
+        let base = config
+            .url
+            .as_deref()
+            .ok_or_else(|| anyhow!("A Jira tracker needs a url"))?;
+        Ok(Self {
+            client,
+            token,
+            user: config.user.clone(),
+            base: base.trim_end_matches('/').to_string(),
+            project: config.project.clone(),
+        })
+    }
+
+    async fn send(&self, request: reqwest::RequestBuilder) -> Result<Value> {
+        let request = match &self.user {
+            Some(user) => request.basic_auth(user, Some(&self.token)),
+            None => request.bearer_auth(&self.token),
+        };
+        let response = request.header("Accept", "application/json").send().await?;
+        let status = response.status();
+        let text = response.text().await?;
+        if !status.is_success() {
+            bail!(
+                "Jira returned {}: {}",
+                status,
+                crate::utils::safe_truncate(&text, 500)
+            );
+        }
+        Ok(serde_json::from_str(&text).unwrap_or(Value::Null))
+    }
+
+    fn api(&self, path: &str) -> String {
+        format!("{}/rest/api/2/{}", self.base, path)
+    }
+
+    /// Moves the ticket through the first transition into `state`'s category.
+    async fn transition(&self, key: &str, state: IssueState) -> Result<()> {
+        let key = super::valid_key(key)?;
+        let available = self
+            .send(
+                self.client
+                    .get(self.api(&format!("issue/{}/transitions", key))),
+            )
+            .await?;
+        let id = available["transitions"]
+            .as_array()
+            .into_iter()
+            .flatten()
+            .find(|t| t["to"]["statusCategory"]["key"].as_str() == Some(category_of(state)))
+            .and_then(|t| t["id"].as_str())
+            .ok_or_else(|| anyhow!("{} has no transition to {:?}", key, state))?;
+        self.send(
+            self.client
+                .post(self.api(&format!("issue/{}/transitions", key)))
+                .json(&json!({ "transition": { "id": id } })),
+        )
+        .await?;
+        Ok(())
+    }
+}
+
+#[async_trait]
+impl IssueTracker for JiraTracker {
+    async fn search(&self, query: &IssueQuery) -> Result<Vec<Issue>> {
+        let mut jql = format!("project = {}", quoted(&self.project));
+        if let Some(state) = query.state {
+            jql.push_str(&format!(
+                " AND statusCategory = {}",
+                quoted(category_of(state))
+            ));
+        }
+        if let Some(assignee) = &query.assignee {
+            jql.push_str(&format!(" AND assignee = {}", quoted(assignee)));
+        }
+        if let Some(text) = &query.text {
+            jql.push_str(&format!(" AND text ~ {}", quoted(text)));
+        }
+        jql.push_str(" ORDER BY updated DESC");
+        let limit = query.limit.unwrap_or(DEFAULT_LIMIT).clamp(1, 100);
+        let found = self
+            .send(self.client.get(self.api("search")).query(&[
+                ("jql", jql),
+                ("maxResults", limit.to_string()),
+                ("fields", ISSUE_FIELDS.to_string()),
+            ]))
+            .await?;
+        Ok(found["issues"]
+            .as_array()
+            .into_iter()
+            .flatten()
+            .filter_map(|issue| issue_from(&self.base, issue))
+            .collect())
+    }
+
+    async fn get(&self, key: &str) -> Result<Issue> {
+        let key = super::valid_key(key)?;
+        let issue = self
+            .send(
+                self.client
+                    .get(self.api(&format!("issue/{}", key)))
+                    .query(&[("fields", ISSUE_FIELDS)]),
+            )
+            .await?;
+        issue_from(&self.base, &issue).ok_or_else(|| anyhow!("Jira returned no issue {}", key))
+    }
+
+    async fn create(&self, issue: &NewIssue) -> Result<Issue> {
+        let created = self
+            .send(self.client.post(self.api("issue")).json(&json!({
+                "fields": {
+                    "project": { "key": self.project },
+                    "summary": issue.title,
+                    "description": issue.body,
+                    "issuetype": { "name": ISSUE_TYPE },
+                    "labels": issue.labels,
+                }
+            })))
+            .await?;
+        let key = created["key"]
+            .as_str()
+            .ok_or_else(|| anyhow!("Jira did not return the new issue"))?;
+        self.get(key).await
+    }
+
+    async fn update(&self, key: &str, update: &IssueUpdate) -> Result<()> {
+        let key = super::valid_key(key)?;
+        let mut fields = serde_json::Map::new();
+        if let Some(title) = &update.title {
+            fields.insert("summary".into(), json!(title));
+        }
+        if let Some(body) = &update.body {
+            fields.insert("description".into(), json!(body));
+        }
+        if let Some(labels) = &update.labels {
+            fields.insert("labels".into(), json!(labels));
+        }
+        if !fields.is_empty() {
+            self.send(
+                self.client
+                    .put(self.api(&format!("issue/{}", key)))
+                    .json(&json!({ "fields": fields })),
+            )
+            .await?;
+        }
+        if let Some(comment) = &update.comment {
+            self.send(
+                self.client
+                    .post(self.api(&format!("issue/{}/comment", key)))
+                    .json(&json!({ "body": comment })),
+            )
+            .await?;
+        }
+        if let Some(state) = update.state {
+            self.transition(key, state).await?;
+        }
+        Ok(())
+    }
+}
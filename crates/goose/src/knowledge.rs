@@ -0,0 +1,777 @@
+//! Workspace knowledge base.
+//!
+//! Project documents are split into chunks, embedded with the shared
+//! embedding service and kept in one index per workspace under the data
+//! dir. The sources are:
+//! - `README.md`, `ARCHITECTURE.md`, `docs/`, `doc/` and `design/` when present
+//! - every line of `.goose/knowledge`, each a workspace path or an http(s)
+//!   URL; blank lines and `#` comments are skipped
+//!
+//! Markdown, text, HTML and PDF files are read. The index refreshes
+//! incrementally before a search, at most every [`REFRESH_INTERVAL`]; the
+//! excerpts injected into a turn come from the index as it is, while a
+//! refresh runs in the background. Files are re-embedded only when their
+//! contents change.
+//!
+//! A cloned repository decides what `.goose/knowledge` lists, so its URLs
+//! are only fetched for workspaces the user listed under
+//! `GOOSE_KNOWLEDGE_URL_WORKSPACES`. They are refetched after [`URL_TTL`],
+//! and bodies over [`MAX_FILE_BYTES`] are rejected.
+
+use crate::config::paths::Paths;
+use crate::config::Config;
+use crate::memory::embedding_service::EmbeddingService;
+use crate::memory::embeddings::EmbeddingProvider;
+use crate::memory::semantic_store::cosine_similarity;
+use anyhow::{bail, Context, Result};
+use chrono::{DateTime, Utc};
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::collections::{HashMap, HashSet};
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, LazyLock, Mutex};
+use std::time::{Duration, Instant};
+
+/// Workspace file listing extra sources, one path or URL per line
+pub const SOURCES_FILE: &str = ".goose/knowledge";
+/// Workspaces whose listed URLs may be fetched
+pub const URL_WORKSPACES_KEY: &str = "GOOSE_KNOWLEDGE_URL_WORKSPACES";
+const DEFAULT_SOURCES: &[&str] = &["README.md", "ARCHITECTURE.md", "docs", "doc", "design"];
+const EXTENSIONS: &[&str] = &[
+    "md", "markdown", "mdx", "txt", "rst", "adoc", "html", "htm", "pdf",
+];
+const MAX_FILES: usize = 2000;
+pub const MAX_FILE_BYTES: u64 = 5 * 1024 * 1024;
+const CHUNK_CHARS: usize = 1200;
+pub const REFRESH_INTERVAL: Duration = Duration::from_secs(30);
+pub const URL_TTL: chrono::Duration = chrono::Duration::hours(24);
+/// Least similarity for a chunk to be injected into a turn unasked
+const INJECT_MIN_SCORE: f64 = 0.35;
+const INJECT_LIMIT: usize = 3;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct Chunk {
+    source: String,
+    heading: Option<String>,
+    text: String,
+    embedding: Vec<f32>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct SourceRecord {
+    /// Modification time and size for files; unused for URLs
+    fingerprint: String,
+    content_hash: String,
+    indexed_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+struct Index {
+    workspace: PathBuf,
+    /// Backend name and dimension the chunks were embedded with
+    embedder: String,
+    sources: HashMap<String, SourceRecord>,
+    chunks: Vec<Chunk>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct KnowledgeHit {
+    pub source: String,
+    pub heading: Option<String>,
+    pub text: String,
+    pub score: f64,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize)]
+pub struct RefreshReport {
+    pub indexed: usize,
+    pub unchanged: usize,
+    pub removed: usize,
+    pub failed: usize,
+    /// Listed URLs not fetched because the workspace has not opted in
+    pub skipped_urls: usize,
+}
+
+pub struct KnowledgeBase {
+    index_path: PathBuf,
+    index: Index,
+    last_refresh: Option<Instant>,
+}
+
+static BASES: LazyLock<Mutex<HashMap<PathBuf, Arc<tokio::sync::Mutex<KnowledgeBase>>>>> =
+    LazyLock::new(|| Mutex::new(HashMap::new()));
+
+fn sha256_hex(bytes: &[u8]) -> String {
+    format!("{:x}", Sha256::digest(bytes))
+}
+
+fn index_path(workspace: &Path) -> PathBuf {
+    let name = &sha256_hex(workspace.to_string_lossy().as_bytes())[..16];
+    Paths::in_data_dir("knowledge").join(format!("{}.json", name))
+}
+
+fn is_url(source: &str) -> bool {
+    source.starts_with("http://") || source.starts_with("https://")
+}
+
+impl KnowledgeBase {
+    /// The knowledge base for a workspace, shared across sessions.
+    pub fn for_workspace(workspace: &Path) -> Arc<tokio::sync::Mutex<KnowledgeBase>> {
+        let workspace = workspace
+            .canonicalize()
+            .unwrap_or_else(|_| workspace.to_path_buf());
+        let mut bases = BASES.lock().unwrap_or_else(|e| e.into_inner());
+        bases
+            .entry(workspace.clone())
+            .or_insert_with(|| {
+                let index_path = index_path(&workspace);
+                Arc::new(tokio::sync::Mutex::new(Self::load(workspace, index_path)))
+            })
+            .clone()
+    }
+
+    /// Deletes the index of a workspace. Returns whether there was one.
+    pub fn forget_workspace(workspace: &Path) -> Result<bool> {
+        let workspace = workspace
+            .canonicalize()
+            .unwrap_or_else(|_| workspace.to_path_buf());
+        BASES
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .remove(&workspace);
+        let index_path = index_path(&workspace);
+        if !index_path.exists() {
+            return Ok(false);
+        }
+        std::fs::remove_file(&index_path)?;
+        Ok(true)
+    }
+
+    pub fn load(workspace: PathBuf, index_path: PathBuf) -> Self {
+        let index = std::fs::read_to_string(&index_path)
+            .ok()
+            .and_then(|s| serde_json::from_str::<Index>(&s).ok())
+            .filter(|index| index.workspace == workspace)
+            .unwrap_or_else(|| Index {
+                workspace,
+                ..Index::default()
+            });
+        Self {
+            index_path,
+            index,
+            last_refresh: None,
+        }
+    }
+
+    fn save(&self) -> Result<()> {
+        if let Some(parent) = self.index_path.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        let tmp = self.index_path.with_extension("json.tmp");
+        std::fs::write(&tmp, serde_json::to_vec(&self.index)?)?;
+        std::fs::rename(&tmp, &self.index_path)?;
+        Ok(())
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.index.chunks.is_empty()
+    }
+
+    /// Listed sources and default locations, as workspace paths and URLs
+    fn configured_sources(&self) -> Vec<String> {
+        let workspace = &self.index.workspace;
+        let mut sources: Vec<String> = DEFAULT_SOURCES
+            .iter()
+            .filter(|s| workspace.join(s).exists())
+            .map(|s| s.to_string())
+            .collect();
+        if let Ok(listed) = std::fs::read_to_string(workspace.join(SOURCES_FILE)) {
+            sources.extend(
+                listed
+                    .lines()
+                    .map(str::trim)
+                    .filter(|l| !l.is_empty() && !l.starts_with('#'))
+                    .map(str::to_string),
+            );
+        }
+        sources.sort();
+        sources.dedup();
+        sources
+    }
+
+    /// Whether the user allowed fetching the URLs this workspace lists
+    fn urls_allowed(&self) -> bool {
+        let allowed: Vec<PathBuf> = Config::global()
+            .get_param(URL_WORKSPACES_KEY)
+            .unwrap_or_default();
+        allowed.iter().any(|path| {
+            path.canonicalize().unwrap_or_else(|_| path.clone()) == self.index.workspace
+        })
+    }
+
+    /// Readable files under the configured paths, relative to the workspace.
+    /// Files reached through a symlink that leaves the workspace are skipped.
+    /// Paths that leave the workspace are ignored.
+    fn source_files(&self, sources: &[String]) -> Vec<String> {
+        let workspace = &self.index.workspace;
+        let mut files = Vec::new();
+        for source in sources.iter().filter(|s| !is_url(s)) {
+            let Ok(root) = workspace.join(source).canonicalize() else {
+                continue;
+            };
+            if !root.starts_with(workspace) {
+                continue;
+            }
+            for entry in ignore::WalkBuilder::new(&root).build().flatten() {
+                let path = entry.path();
+                let readable = path
+                    .extension()
+                    .and_then(|e| e.to_str())
+                    .is_some_and(|e| EXTENSIONS.contains(&e.to_ascii_lowercase().as_str()));
+                // A symlink in the tree may point anywhere; only files that
+                // really live in the workspace are indexed
+                let target = path.canonicalize().ok().filter(|p| p.is_file());
+                if let Some(target) = target.filter(|_| readable) {
+                    if let Ok(relative) = target.strip_prefix(workspace) {
+                        files.push(relative.to_string_lossy().to_string());
+                    }
+                }
+                if files.len() >= MAX_FILES {
+                    return files;
+                }
+            }
+        }
+        files.sort();
+        files.dedup();
+        files
+    }
+
+    /// Brings the index up to date with its sources. Throttled unless
+    /// `force` is set.
+    pub async fn refresh(
+        &mut self,
+        embedder: &EmbeddingService,
+        force: bool,
+    ) -> Result<RefreshReport> {
+        if !force
+            && self
+                .last_refresh
+                .is_some_and(|at| at.elapsed() < REFRESH_INTERVAL)
+        {
+            return Ok(RefreshReport::default());
+        }
+        self.last_refresh = Some(Instant::now());
+
+        let embedder_id = format!("{}:{}", embedder.name(), embedder.dimension());
+        if self.index.embedder != embedder_id {
+            self.index.sources.clear();
+            self.index.chunks.clear();
+            self.index.embedder = embedder_id;
+        }
+
+        let sources = self.configured_sources();
+        let files = self.source_files(&sources);
+        let mut report = RefreshReport::default();
+        let mut urls: Vec<&String> = sources.iter().filter(|s| is_url(s)).collect();
+        if !urls.is_empty() && !self.urls_allowed() {
+            report.skipped_urls = urls.len();
+            urls.clear();
+        }
+        let wanted: HashSet<&str> = files
+            .iter()
+            .map(String::as_str)
+            .chain(urls.iter().map(|s| s.as_str()))
+            .collect();
+
+        let stale: Vec<String> = self
+            .index
+            .sources
+            .keys()
+            .filter(|key| !wanted.contains(key.as_str()))
+            .cloned()
+            .collect();
+        for key in stale {
+            self.remove_source(&key);
+            report.removed += 1;
+        }
+
+        for file in &files {
+            let path = self.index.workspace.join(file);
+            let Ok(meta) = std::fs::metadata(&path) else {
+                continue;
+            };
+            if meta.len() > MAX_FILE_BYTES {
+                continue;
+            }
+            let modified = meta
+                .modified()
+                .ok()
+                .and_then(|t| t.duration_since(std::time::UNIX_EPOCH).ok())
+                .map(|d| d.as_secs())
+                .unwrap_or(0);
+            let fingerprint = format!("{}:{}", modified, meta.len());
+            if self
+                .index
+                .sources
+                .get(file)
+                .is_some_and(|r| r.fingerprint == fingerprint)
+            {
+                report.unchanged += 1;
+                continue;
+            }
+            let outcome = match std::fs::read(&path) {
+                Ok(bytes) => self.ingest(embedder, file, fingerprint, &bytes).await,
+                Err(e) => Err(e.into()),
+            };
+            match outcome {
+                Ok(true) => report.indexed += 1,
+                Ok(false) => report.unchanged += 1,
+                Err(e) => {
+                    tracing::warn!(source = %file, "Knowledge ingestion failed: {}", e);
+                    report.failed += 1;
+                }
+            }
+        }
+
+        for url in urls {
+            let fresh = self
+                .index
+                .sources
+                .get(url.as_str())
+                .is_some_and(|r| Utc::now() - r.indexed_at < URL_TTL);
+            if fresh {
+                report.unchanged += 1;
+                continue;
+            }
+            match self.ingest_url(embedder, url).await {
+                Ok(true) => report.indexed += 1,
+                Ok(false) => report.unchanged += 1,
+                Err(e) => {
+                    tracing::warn!(source = %url, "Knowledge ingestion failed: {}", e);
+                    report.failed += 1;
+                }
+            }
+        }
+
+        if report != RefreshReport::default() {
+            self.save()?;
+            tracing::info!(
+                workspace = %self.index.workspace.display(),
+                indexed = report.indexed,
+                removed = report.removed,
+                failed = report.failed,
+                chunks = self.index.chunks.len(),
+                "Knowledge base refreshed"
+            );
+        }
+        Ok(report)
+    }
+
+    async fn ingest_url(&mut self, embedder: &EmbeddingService, url: &str) -> Result<bool> {
+        let mut response = reqwest::Client::new()
+            .get(url)
+            .timeout(Duration::from_secs(30))
+            .send()
+            .await?
+            .error_for_status()?;
+        let is_html = response
+            .headers()
+            .get(reqwest::header::CONTENT_TYPE)
+            .and_then(|v| v.to_str().ok())
+            .is_some_and(|v| v.contains("html"));
+        if response
+            .content_length()
+            .is_some_and(|length| length > MAX_FILE_BYTES)
+        {
+            bail!("Larger than {} bytes", MAX_FILE_BYTES);
+        }
+        let mut bytes = Vec::new();
+        while let Some(chunk) = response.chunk().await? {
+            if (bytes.len() + chunk.len()) as u64 > MAX_FILE_BYTES {
+                bail!("Larger than {} bytes", MAX_FILE_BYTES);
+            }
+            bytes.extend_from_slice(&chunk);
+        }
+        let name = if is_html {
+            "page.html"
+        } else {
+            url.rsplit('/').next().unwrap_or(url)
+        };
+        let text = extract_text(name, &bytes)?;
+        self.replace_source(embedder, url, String::new(), &text)
+            .await
+    }
+
+    async fn ingest(
+        &mut self,
+        embedder: &EmbeddingService,
+        file: &str,
+        fingerprint: String,
+        bytes: &[u8],
+    ) -> Result<bool> {
+        let text = extract_text(file, bytes)?;
+        self.replace_source(embedder, file, fingerprint, &text)
+            .await
+    }
+
+    /// Re-embeds a source if its text changed; returns whether it did
+    async fn replace_source(
+        &mut self,
+        embedder: &EmbeddingService,
+        source: &str,
+        fingerprint: String,
+        text: &str,
+    ) -> Result<bool> {
+        let content_hash = sha256_hex(text.as_bytes());
+        let record = SourceRecord {
+            fingerprint,
+            content_hash: content_hash.clone(),
+            indexed_at: Utc::now(),
+        };
+        if self
+            .index
+            .sources
+            .get(source)
+            .is_some_and(|r| r.content_hash == content_hash)
+        {
+            self.index.sources.insert(source.to_string(), record);
+            return Ok(false);
+        }
+
+        let pieces = chunk_text(text);
+        let inputs: Vec<String> = pieces
+            .iter()
+            .map(|(heading, body)| match heading {
+                Some(heading) => format!("{}\n{}", heading, body),
+                None => body.clone(),
+            })
+            .collect();
+        let refs: Vec<&str> = inputs.iter().map(String::as_str).collect();
+        let embeddings = embedder.embed_batch(&refs).await?;
+
+        self.remove_source(source);
+        self.index
+            .chunks
+            .extend(
+                pieces
+                    .into_iter()
+                    .zip(embeddings)
+                    .map(|((heading, text), embedding)| Chunk {
+                        source: source.to_string(),
+                        heading,
+                        text,
+                        embedding,
+                    }),
+            );
+        self.index.sources.insert(source.to_string(), record);
+        Ok(true)
+    }
+
+    fn remove_source(&mut self, source: &str) {
+        self.index.sources.remove(source);
+        self.index.chunks.retain(|c| c.source != source);
+    }
+
+    pub async fn search(
+        &self,
+        embedder: &EmbeddingService,
+        query: &str,
+        limit: usize,
+    ) -> Result<Vec<KnowledgeHit>> {
+        if self.index.chunks.is_empty() {
+            return Ok(Vec::new());
+        }
+        let query_embedding = embedder.embed(query).await?;
+        let mut hits: Vec<KnowledgeHit> = self
+            .index
+            .chunks
+            .iter()
+            .map(|chunk| KnowledgeHit {
+                source: chunk.source.clone(),
+                heading: chunk.heading.clone(),
+                text: chunk.text.clone(),
+                score: cosine_similarity(&query_embedding, &chunk.embedding),
+            })
+            .collect();
+        hits.sort_by(|a, b| b.score.total_cmp(&a.score));
+        hits.truncate(limit);
+        Ok(hits)
+    }
+}
+
+/// With several roots, sources are prefixed with their root's directory
+/// name.
+fn label_hits(workspaces: &[&Path], workspace: &Path, hits: &mut [KnowledgeHit]) {
+    if workspaces.len() > 1 {
+        let label = workspace
+            .file_name()
+            .map(|n| n.to_string_lossy().to_string())
+            .unwrap_or_default();
+        for hit in hits {
+            hit.source = format!("{}/{}", label, hit.source);
+        }
+    }
+}
+
+/// Refreshes and searches the knowledge bases of every workspace root.
+pub async fn search(workspaces: &[&Path], query: &str, limit: usize) -> Result<Vec<KnowledgeHit>> {
+    let embedder = EmbeddingService::shared().await;
+    let mut hits = Vec::new();
+    for workspace in workspaces {
+        let base = KnowledgeBase::for_workspace(workspace);
+        let mut base = base.lock().await;
+        if let Err(e) = base.refresh(&embedder, false).await {
+            tracing::warn!(workspace = %workspace.display(), "Knowledge refresh failed: {}", e);
+        }
+        let mut found = base.search(&embedder, query, limit).await?;
+        label_hits(workspaces, workspace, &mut found);
+        hits.extend(found);
+    }
+    hits.sort_by(|a, b| b.score.total_cmp(&a.score));
+    hits.truncate(limit);
+    Ok(hits)
+}
+
+/// Starts a throttled refresh of each workspace's index without waiting
+/// for it.
+fn refresh_in_background(workspaces: &[&Path]) {
+    for workspace in workspaces {
+        let base = KnowledgeBase::for_workspace(workspace);
+        let workspace = workspace.to_path_buf();
+        tokio::spawn(async move {
+            let embedder = EmbeddingService::shared().await;
+            if let Err(e) = base.lock().await.refresh(&embedder, false).await {
+                tracing::warn!(workspace = %workspace.display(), "Knowledge refresh failed: {}", e);
+            }
+        });
+    }
+}
+
+/// Chunks close enough to the message to include in the system prompt.
+/// Hash embeddings are too coarse to judge relevance, so nothing is
+/// injected with them. Only what is already indexed is searched, and a
+/// workspace whose index is being refreshed is skipped, so the reply never
+/// waits on ingestion.
+pub async fn context_for(workspaces: &[&Path], message: &str) -> Option<String> {
+    let embedder = EmbeddingService::shared().await;
+    if message.trim().is_empty() || embedder.name() == "hash" {
+        return None;
+    }
+    refresh_in_background(workspaces);
+    let mut hits = Vec::new();
+    for workspace in workspaces {
+        let base = KnowledgeBase::for_workspace(workspace);
+        let Ok(indexed) = base.try_lock() else {
+            continue;
+        };
+        let mut found = indexed
+            .search(&embedder, message, INJECT_LIMIT)
+            .await
+            .ok()?;
+        label_hits(workspaces, workspace, &mut found);
+        hits.extend(found);
+    }
+    hits.sort_by(|a, b| b.score.total_cmp(&a.score));
+    hits.truncate(INJECT_LIMIT);
+    let relevant: Vec<String> = hits
+        .into_iter()
+        .filter(|hit| hit.score >= INJECT_MIN_SCORE)
+        .map(|hit| format_hit(&hit))
+        .collect();
+    if relevant.is_empty() {
+        return None;
+    }
+    Some(format!(
+        "[PROJECT KNOWLEDGE]: Excerpts from this workspace's documents that may help. \
+         Use platform__search_knowledge for more.\n\n{}",
+        relevant.join("\n\n")
+    ))
+}
+
+pub fn format_hit(hit: &KnowledgeHit) -> String {
+    let location = match &hit.heading {
+        Some(heading) => format!("{} > {}", hit.source, heading),
+        None => hit.source.clone(),
+    };
+    format!("--- {} (score {:.2})\n{}", location, hit.score, hit.text)
+}
+
+fn extract_text(name: &str, bytes: &[u8]) -> Result<String> {
+    let extension = Path::new(name)
+        .extension()
+        .and_then(|e| e.to_str())
+        .map(|e| e.to_ascii_lowercase());
+    match extension.as_deref() {
+        Some("pdf") => crate::session::attachments::pdf_text(bytes),
+        Some("html") | Some("htm") => Ok(html_text(&String::from_utf8_lossy(bytes))),
+        _ => String::from_utf8(bytes.to_vec()).context("Not a UTF-8 text document"),
+    }
+}
+
+fn html_text(html: &str) -> String {
+    static HIDDEN: LazyLock<Regex> = LazyLock::new(|| {
+        Regex::new(r"(?is)<(script|style|nav|head)[^>]*>.*?</(script|style|nav|head)>").unwrap()
+    });
+    static HEADING: LazyLock<Regex> = LazyLock::new(|| Regex::new(r"(?i)<h([1-6])[^>]*>").unwrap());
+    static BLOCK: LazyLock<Regex> =
+        LazyLock::new(|| Regex::new(r"(?i)</?(p|div|li|tr|br|h[1-6]|pre|section)[^>]*>").unwrap());
+    static TAG: LazyLock<Regex> = LazyLock::new(|| Regex::new(r"<[^>]+>").unwrap());
+
+    let text = HIDDEN.replace_all(html, "");
+    let text = HEADING.replace_all(&text, |caps: &regex::Captures| {
+        let level: usize = caps[1].parse().unwrap_or(1);
+        format!("\n\n{} ", "#".repeat(level))
+    });
+    let text = BLOCK.replace_all(&text, "\n");
+    let text = TAG.replace_all(&text, "");
+    text.replace("&nbsp;", " ")
+        .replace("&lt;", "<")
+        .replace("&gt;", ">")
+        .replace("&quot;", "\"")
+        .replace("&#39;", "'")
+        .replace("&amp;", "&")
+}
+
+/// Splits text into pieces of about [`CHUNK_CHARS`], keeping paragraphs
+/// whole where possible. Each piece carries the Markdown heading it sits
+/// under.
+fn chunk_text(text: &str) -> Vec<(Option<String>, String)> {
+    let mut chunks = Vec::new();
+    let mut heading: Option<String> = None;
+    let mut current = String::new();
+
+    let mut flush = |heading: &Option<String>, current: &mut String| {
+        let body = current.trim();
+        if !body.is_empty() {
+            chunks.push((heading.clone(), body.to_string()));
+        }
+        current.clear();
+    };
+
+    for paragraph in text.split("\n\n") {
+        let paragraph = paragraph.trim();
+        if paragraph.is_empty() {
+            continue;
+        }
+        if let Some(title) = paragraph
+            .lines()
+            .next()
+            .filter(|l| l.starts_with('#'))
+            .map(|l| l.trim_start_matches('#').trim())
+        {
+            flush(&heading, &mut current);
+            heading = Some(title.to_string());
+        }
+        if !current.is_empty() && current.len() + paragraph.len() > CHUNK_CHARS {
+            flush(&heading, &mut current);
+        }
+        // A single paragraph longer than a chunk is cut on char boundaries
+        let chars: Vec<char> = paragraph.chars().collect();
+        for piece in chars.chunks(CHUNK_CHARS) {
+            if !current.is_empty() {
+                current.push_str("\n\n");
+            }
+            current.extend(piece);
+            if current.len() >= CHUNK_CHARS {
+                flush(&heading, &mut current);
+            }
+        }
+    }
+    flush(&heading, &mut current);
+    chunks
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::memory::embedding_service::EmbeddingServiceConfig;
+    use crate::memory::embeddings::HashEmbeddingProvider;
+
+    #[test]
+    fn test_chunks_follow_headings() {
+        let text = "# Design\n\nIntro paragraph.\n\n## Storage\n\nWe use SQLite.\n\nMigrations run at startup.";
+        let chunks = chunk_text(text);
+        assert_eq!(chunks.len(), 2);
+        assert_eq!(chunks[0].0.as_deref(), Some("Design"));
+        assert_eq!(chunks[1].0.as_deref(), Some("Storage"));
+        assert!(chunks[1].1.contains("Migrations run at startup."));
+    }
+
+    #[tokio::test]
+    async fn test_refresh_is_incremental() {
+        let root = tempfile::tempdir().unwrap();
+        std::fs::create_dir_all(root.path().join("project/docs")).unwrap();
+        let workspace = root.path().join("project").canonicalize().unwrap();
+        std::fs::write(
+            workspace.join("docs/storage.md"),
+            "# Storage\n\nSessions live in SQLite.",
+        )
+        .unwrap();
+        std::fs::write(workspace.join("README.md"), "# Project\n\nA CLI agent.").unwrap();
+        let embedder = EmbeddingService::new(
+            Arc::new(HashEmbeddingProvider::new(64)),
+            EmbeddingServiceConfig::default(),
+        );
+        let mut base = KnowledgeBase::load(workspace.clone(), root.path().join("index.json"));
+
+        let first = base.refresh(&embedder, true).await.unwrap();
+        assert_eq!(first.indexed, 2);
+        let second = base.refresh(&embedder, true).await.unwrap();
+        assert_eq!(second.unchanged, 2);
+
+        std::fs::remove_file(workspace.join("README.md")).unwrap();
+        let third = base.refresh(&embedder, true).await.unwrap();
+        assert_eq!(third.removed, 1);
+
+        let hits = base
+            .search(&embedder, "where do sessions live sqlite", 1)
+            .await
+            .unwrap();
+        assert_eq!(hits[0].source, "docs/storage.md");
+
+        let reloaded = KnowledgeBase::load(workspace, root.path().join("index.json"));
+        assert_eq!(reloaded.index.chunks.len(), 1);
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_symlinks_out_of_the_workspace_are_skipped() {
+        let root = tempfile::tempdir().unwrap();
+        let root_path = root.path().canonicalize().unwrap();
+        let workspace = root_path.join("project");
+        std::fs::create_dir_all(workspace.join("docs")).unwrap();
+        std::fs::write(workspace.join("docs/guide.md"), "# Guide").unwrap();
+        std::fs::write(root_path.join("secret.md"), "# Secret").unwrap();
+        std::os::unix::fs::symlink(root_path.join("secret.md"), workspace.join("docs/leak.md"))
+            .unwrap();
+        let base = KnowledgeBase::load(workspace, root_path.join("index.json"));
+
+        assert_eq!(
+            base.source_files(&["docs".to_string()]),
+            vec!["docs/guide.md".to_string()]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_listed_urls_need_opt_in() {
+        let root = tempfile::tempdir().unwrap();
+        let workspace = root.path().canonicalize().unwrap();
+        std::fs::create_dir_all(workspace.join(".goose")).unwrap();
+        std::fs::write(
+            workspace.join(SOURCES_FILE),
+            "# fetched only when allowed\nhttp://169.254.169.254/latest/meta-data\n",
+        )
+        .unwrap();
+        let embedder = EmbeddingService::new(
+            Arc::new(HashEmbeddingProvider::new(64)),
+            EmbeddingServiceConfig::default(),
+        );
+        let mut base = KnowledgeBase::load(workspace, root.path().join("index.json"));
+
+        let report = base.refresh(&embedder, true).await.unwrap();
+        assert_eq!(report.skipped_urls, 1);
+        assert_eq!(report.failed, 0);
+        assert!(base.is_empty());
+    }
+}
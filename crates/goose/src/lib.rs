@@ -1,24 +1,35 @@
 pub mod action_required_manager;
 pub mod agents;
 pub mod approval;
+pub mod artifacts;
 pub mod builtin_extension;
+pub mod calendar;
 pub mod compaction;
 pub mod config;
+pub mod connectivity;
 pub mod context_mgmt;
 pub mod conversation;
+pub mod database;
 pub mod dictation;
+pub mod elicitation_form;
+pub mod email;
 pub mod execution;
+pub mod feature_flags;
 pub mod goose_apps;
 pub mod guardrails;
 pub mod hints;
 pub mod hooks;
+pub mod issues;
 
+#[cfg(feature = "memory")]
+pub mod knowledge;
 #[cfg(feature = "memory")]
 pub mod memory;
 
 #[cfg(feature = "swarm-experimental")]
 pub mod swarm;
 
+pub mod local_analytics;
 pub mod logging;
 pub mod mcp_utils;
 pub mod model;
@@ -27,19 +38,24 @@ pub mod observability;
 pub mod permission;
 pub mod policies;
 pub mod posthog;
+pub mod privacy;
 pub mod prompt_template;
 pub mod prompts;
 pub mod providers;
 pub mod quality;
 pub mod recipe;
 pub mod recipe_deeplink;
+pub mod release_notes;
+pub mod resources;
 pub mod scheduler;
 pub mod scheduler_trait;
 pub mod security;
+pub mod self_build;
 pub mod session;
 pub mod session_context;
 pub mod skills;
 pub mod slash_commands;
+pub mod startup;
 pub mod status;
 pub mod subagents;
 pub mod subprocess;
@@ -50,5 +66,6 @@ pub mod tool_inspection;
 pub mod tool_monitor;
 pub mod tools;
 pub mod tracing;
+pub mod tts;
 pub mod utils;
 pub mod validators;
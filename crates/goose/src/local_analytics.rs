@@ -0,0 +1,416 @@
+//! Local analytics - anonymized daily usage rollups stored on this machine.
+//!
+//! Mirrors the events sent to PostHog (sessions started, tools used, error
+//! classes, core usage and costs) but never leaves the device, so users who
+//! disable external telemetry still get a usage dashboard. Only counters keyed
+//! by coarse labels are stored; no message content, paths or identifiers.
+
+use crate::config::paths::Paths;
+use crate::config::Config;
+use crate::database;
+use anyhow::Result;
+use chrono::{NaiveDate, Utc};
+use serde::{Deserialize, Serialize};
+use sqlx::{Pool, Sqlite};
+use std::collections::BTreeMap;
+use std::path::Path;
+use std::sync::LazyLock;
+use utoipa::ToSchema;
+
+/// Config key for the local analytics opt-out preference
+pub const LOCAL_ANALYTICS_ENABLED_KEY: &str = "GOOSE_LOCAL_ANALYTICS_ENABLED";
+
+const DB_NAME: &str = "local_analytics.db";
+const MAX_KEY_LEN: usize = 128;
+
+static GLOBAL_STORE: LazyLock<LocalAnalyticsStore> =
+    LazyLock::new(|| LocalAnalyticsStore::new(&Paths::data_dir().join(DB_NAME)));
+
+/// Local analytics are on unless the user explicitly turned them off.
+pub fn is_local_analytics_enabled() -> bool {
+    Config::global()
+        .get_param::<bool>(LOCAL_ANALYTICS_ENABLED_KEY)
+        .unwrap_or(true)
+}
+
+#[derive(
+    Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord, Serialize, Deserialize, ToSchema,
+)]
+#[serde(rename_all = "snake_case")]
+pub enum AnalyticsMetric {
+    SessionStarted,
+    ToolUsed,
+    Error,
+    CoreUsed,
+    CostUsd,
+    Event,
+}
+
+impl AnalyticsMetric {
+    fn as_str(&self) -> &'static str {
+        match self {
+            AnalyticsMetric::SessionStarted => "session_started",
+            AnalyticsMetric::ToolUsed => "tool_used",
+            AnalyticsMetric::Error => "error",
+            AnalyticsMetric::CoreUsed => "core_used",
+            AnalyticsMetric::CostUsd => "cost_usd",
+            AnalyticsMetric::Event => "event",
+        }
+    }
+
+    fn parse(s: &str) -> Option<Self> {
+        match s {
+            "session_started" => Some(AnalyticsMetric::SessionStarted),
+            "tool_used" => Some(AnalyticsMetric::ToolUsed),
+            "error" => Some(AnalyticsMetric::Error),
+            "core_used" => Some(AnalyticsMetric::CoreUsed),
+            "cost_usd" => Some(AnalyticsMetric::CostUsd),
+            "event" => Some(AnalyticsMetric::Event),
+            _ => None,
+        }
+    }
+}
+
+/// One row of the daily rollup: how often `key` was seen for `metric` on `day`.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, ToSchema)]
+pub struct DailyRollup {
+    pub day: NaiveDate,
+    pub metric: AnalyticsMetric,
+    pub key: String,
+    pub count: i64,
+    /// Summed amount; equal to `count` for plain counters, USD for costs.
+    pub total: f64,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize, ToSchema)]
+pub struct RollupQuery {
+    pub metric: Option<AnalyticsMetric>,
+    pub start: Option<NaiveDate>,
+    pub end: Option<NaiveDate>,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize, ToSchema)]
+pub struct KeyTotal {
+    pub key: String,
+    pub count: i64,
+    pub total: f64,
+}
+
+/// Totals for a date range, shaped for the built-in usage dashboard.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize, ToSchema)]
+pub struct UsageSummary {
+    pub sessions_started: i64,
+    pub tool_calls: i64,
+    pub errors: i64,
+    pub total_cost_usd: f64,
+    pub top_tools: Vec<KeyTotal>,
+    pub error_classes: Vec<KeyTotal>,
+    pub core_usage: Vec<KeyTotal>,
+    pub cost_by_model: Vec<KeyTotal>,
+}
+
+impl UsageSummary {
+    pub fn from_rollups(rollups: &[DailyRollup]) -> Self {
+        let mut grouped: BTreeMap<(AnalyticsMetric, &str), KeyTotal> = BTreeMap::new();
+        for rollup in rollups {
+            let entry = grouped
+                .entry((rollup.metric, rollup.key.as_str()))
+                .or_insert_with(|| KeyTotal {
+                    key: rollup.key.clone(),
+                    ..Default::default()
+                });
+            entry.count += rollup.count;
+            entry.total += rollup.total;
+        }
+
+        let collect = |metric: AnalyticsMetric| -> Vec<KeyTotal> {
+            let mut totals: Vec<KeyTotal> = grouped
+                .iter()
+                .filter(|((m, _), _)| *m == metric)
+                .map(|(_, total)| total.clone())
+                .collect();
+            totals.sort_by(|a, b| {
+                b.total
+                    .partial_cmp(&a.total)
+                    .unwrap_or(std::cmp::Ordering::Equal)
+                    .then_with(|| a.key.cmp(&b.key))
+            });
+            totals
+        };
+
+        let top_tools = collect(AnalyticsMetric::ToolUsed);
+        let error_classes = collect(AnalyticsMetric::Error);
+        let cost_by_model = collect(AnalyticsMetric::CostUsd);
+
+        Self {
+            sessions_started: collect(AnalyticsMetric::SessionStarted)
+                .iter()
+                .map(|t| t.count)
+                .sum(),
+            tool_calls: top_tools.iter().map(|t| t.count).sum(),
+            errors: error_classes.iter().map(|t| t.count).sum(),
+            total_cost_usd: cost_by_model.iter().map(|t| t.total).sum(),
+            core_usage: collect(AnalyticsMetric::CoreUsed),
+            top_tools,
+            error_classes,
+            cost_by_model,
+        }
+    }
+}
+
+pub struct LocalAnalyticsStore {
+    pool: Pool<Sqlite>,
+    initialized: tokio::sync::OnceCell<()>,
+}
+
+impl LocalAnalyticsStore {
+    pub fn new(path: &Path) -> Self {
+        if let Some(parent) = path.parent() {
+            let _ = std::fs::create_dir_all(parent);
+        }
+
+        Self {
+            pool: database::open("analytics", path),
+            initialized: tokio::sync::OnceCell::new(),
+        }
+    }
+
+    pub fn global() -> &'static LocalAnalyticsStore {
+        &GLOBAL_STORE
+    }
+
+    async fn pool(&self) -> Result<&Pool<Sqlite>> {
+        self.initialized
+            .get_or_try_init(|| async {
+                sqlx::query(
+                    r#"
+                    CREATE TABLE IF NOT EXISTS daily_rollups (
+                        day TEXT NOT NULL,
+                        metric TEXT NOT NULL,
+                        key TEXT NOT NULL,
+                        count INTEGER NOT NULL DEFAULT 0,
+                        total REAL NOT NULL DEFAULT 0,
+                        PRIMARY KEY (day, metric, key)
+                    )
+                    "#,
+                )
+                .execute(&self.pool)
+                .await?;
+                Ok::<(), anyhow::Error>(())
+            })
+            .await?;
+        Ok(&self.pool)
+    }
+
+    /// Adds one occurrence of `key` with the given amount to the day's rollup.
+    pub async fn record(
+        &self,
+        day: NaiveDate,
+        metric: AnalyticsMetric,
+        key: &str,
+        amount: f64,
+    ) -> Result<()> {
+        let pool = self.pool().await?;
+        let key: String = key.chars().take(MAX_KEY_LEN).collect();
+        sqlx::query(
+            r#"
+            INSERT INTO daily_rollups (day, metric, key, count, total)
+            VALUES (?, ?, ?, 1, ?)
+            ON CONFLICT(day, metric, key) DO UPDATE SET
+                count = count + 1,
+                total = total + excluded.total
+            "#,
+        )
+        .bind(day.to_string())
+        .bind(metric.as_str())
+        .bind(key)
+        .bind(amount)
+        .execute(pool)
+        .await?;
+        Ok(())
+    }
+
+    pub async fn daily_rollups(&self, query: &RollupQuery) -> Result<Vec<DailyRollup>> {
+        let pool = self.pool().await?;
+        let rows = sqlx::query_as::<_, (String, String, String, i64, f64)>(
+            r#"
+            SELECT day, metric, key, count, total FROM daily_rollups
+            WHERE (?1 IS NULL OR metric = ?1)
+              AND (?2 IS NULL OR day >= ?2)
+              AND (?3 IS NULL OR day <= ?3)
+            ORDER BY day ASC, metric ASC, key ASC
+            "#,
+        )
+        .bind(query.metric.map(|m| m.as_str()))
+        .bind(query.start.map(|d| d.to_string()))
+        .bind(query.end.map(|d| d.to_string()))
+        .fetch_all(pool)
+        .await?;
+
+        Ok(rows
+            .into_iter()
+            .filter_map(|(day, metric, key, count, total)| {
+                Some(DailyRollup {
+                    day: day.parse().ok()?,
+                    metric: AnalyticsMetric::parse(&metric)?,
+                    key,
+                    count,
+                    total,
+                })
+            })
+            .collect())
+    }
+
+    pub async fn summary(
+        &self,
+        start: Option<NaiveDate>,
+        end: Option<NaiveDate>,
+    ) -> Result<UsageSummary> {
+        let rollups = self
+            .daily_rollups(&RollupQuery {
+                metric: None,
+                start,
+                end,
+            })
+            .await?;
+        Ok(UsageSummary::from_rollups(&rollups))
+    }
+
+    /// Drops rollups older than `before`; returns the number of rows removed.
+    pub async fn prune(&self, before: NaiveDate) -> Result<u64> {
+        let pool = self.pool().await?;
+        let result = sqlx::query("DELETE FROM daily_rollups WHERE day < ?")
+            .bind(before.to_string())
+            .execute(pool)
+            .await?;
+        Ok(result.rows_affected())
+    }
+}
+
+/// Records an event for today in the background. A no-op when local analytics
+/// are disabled or no tokio runtime is available.
+pub fn record(metric: AnalyticsMetric, key: impl Into<String>, amount: f64) {
+    if !is_local_analytics_enabled() {
+        return;
+    }
+    let Ok(handle) = tokio::runtime::Handle::try_current() else {
+        return;
+    };
+
+    let key = key.into();
+    handle.spawn(async move {
+        let today = Utc::now().date_naive();
+        if let Err(e) = LocalAnalyticsStore::global()
+            .record(today, metric, &key, amount)
+            .await
+        {
+            tracing::debug!("Failed to record local analytics: {}", e);
+        }
+    });
+}
+
+pub fn record_count(metric: AnalyticsMetric, key: impl Into<String>) {
+    record(metric, key, 1.0);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    fn day(s: &str) -> NaiveDate {
+        s.parse().unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_record_accumulates_per_day() {
+        let temp_dir = TempDir::new().unwrap();
+        let store = LocalAnalyticsStore::new(&temp_dir.path().join(DB_NAME));
+
+        store
+            .record(day("2026-03-01"), AnalyticsMetric::ToolUsed, "shell", 1.0)
+            .await
+            .unwrap();
+        store
+            .record(day("2026-03-01"), AnalyticsMetric::ToolUsed, "shell", 1.0)
+            .await
+            .unwrap();
+        store
+            .record(day("2026-03-02"), AnalyticsMetric::ToolUsed, "shell", 1.0)
+            .await
+            .unwrap();
+        store
+            .record(day("2026-03-02"), AnalyticsMetric::CostUsd, "gpt-4o", 0.25)
+            .await
+            .unwrap();
+
+        let tools = store
+            .daily_rollups(&RollupQuery {
+                metric: Some(AnalyticsMetric::ToolUsed),
+                ..Default::default()
+            })
+            .await
+            .unwrap();
+        assert_eq!(tools.len(), 2);
+        assert_eq!(tools[0].day, day("2026-03-01"));
+        assert_eq!(tools[0].count, 2);
+        assert_eq!(tools[1].count, 1);
+
+        let second_day = store
+            .daily_rollups(&RollupQuery {
+                start: Some(day("2026-03-02")),
+                ..Default::default()
+            })
+            .await
+            .unwrap();
+        assert_eq!(second_day.len(), 2);
+    }
+
+    #[tokio::test]
+    async fn test_prune_removes_old_days() {
+        let temp_dir = TempDir::new().unwrap();
+        let store = LocalAnalyticsStore::new(&temp_dir.path().join(DB_NAME));
+
+        store
+            .record(day("2026-01-01"), AnalyticsMetric::Error, "timeout", 1.0)
+            .await
+            .unwrap();
+        store
+            .record(day("2026-02-01"), AnalyticsMetric::Error, "timeout", 1.0)
+            .await
+            .unwrap();
+
+        assert_eq!(store.prune(day("2026-01-15")).await.unwrap(), 1);
+        let remaining = store.daily_rollups(&RollupQuery::default()).await.unwrap();
+        assert_eq!(remaining.len(), 1);
+        assert_eq!(remaining[0].day, day("2026-02-01"));
+    }
+
+    #[test]
+    fn test_summary_from_rollups() {
+        let rollup = |metric, key: &str, count, total| DailyRollup {
+            day: day("2026-03-01"),
+            metric,
+            key: key.to_string(),
+            count,
+            total,
+        };
+        let summary = UsageSummary::from_rollups(&[
+            rollup(AnalyticsMetric::SessionStarted, "cli", 2, 2.0),
+            rollup(AnalyticsMetric::SessionStarted, "desktop", 1, 1.0),
+            rollup(AnalyticsMetric::ToolUsed, "shell", 3, 3.0),
+            rollup(AnalyticsMetric::ToolUsed, "text_editor", 5, 5.0),
+            rollup(AnalyticsMetric::Error, "timeout", 1, 1.0),
+            rollup(AnalyticsMetric::CostUsd, "gpt-4o", 2, 0.5),
+            rollup(AnalyticsMetric::CostUsd, "claude-sonnet", 1, 1.5),
+        ]);
+
+        assert_eq!(summary.sessions_started, 3);
+        assert_eq!(summary.tool_calls, 8);
+        assert_eq!(summary.errors, 1);
+        assert!((summary.total_cost_usd - 2.0).abs() < f64::EPSILON);
+        assert_eq!(summary.top_tools[0].key, "text_editor");
+        assert_eq!(summary.cost_by_model[0].key, "claude-sonnet");
+        assert!(summary.core_usage.is_empty());
+    }
+}
@@ -0,0 +1,487 @@
+//! Shared Embedding Service
+//!
+//! One process-wide service produces embeddings for every caller. Before,
+//! each agent loaded its own model and each caller embedded text one piece
+//! at a time. The service:
+//! - coalesces concurrent requests into batches for the backend
+//! - caches vectors by a hash of the backend and the text
+//! - spaces backend calls to stay under a requests-per-minute limit
+//! - picks its backend from `GOOSE_EMBEDDING_BACKEND`: `local` (Candle
+//!   MiniLM, the default), `provider` (the configured provider's embeddings
+//!   endpoint, e.g. OpenAI) or `hash`
+//!
+//! A backend that fails to start falls back to hash embeddings, as the
+//! memory system always has.
+
+use super::embeddings::{CandleEmbeddingProvider, EmbeddingProvider, HashEmbeddingProvider};
+use super::{MemoryError, MemoryResult};
+use crate::config::Config;
+use crate::providers::base::Provider;
+use crate::startup::{self, StartupPhase};
+use async_trait::async_trait;
+use lru::LruCache;
+use serde::Serialize;
+use sha2::{Digest, Sha256};
+use std::collections::HashMap;
+use std::num::NonZeroUsize;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tokio::sync::{oneshot, OnceCell};
+use tokio::time::Instant;
+use tracing::{info, warn};
+
+pub const EMBEDDING_BACKEND_KEY: &str = "GOOSE_EMBEDDING_BACKEND";
+/// Backend calls per minute; 0 means unlimited
+pub const EMBEDDING_RPM_KEY: &str = "GOOSE_EMBEDDING_RPM";
+pub const EMBEDDING_CACHE_SIZE_KEY: &str = "GOOSE_EMBEDDING_CACHE_SIZE";
+/// Hash fallback dimension, matching MiniLM and the memory default
+pub const DEFAULT_DIMENSION: usize = 384;
+
+type CacheKey = [u8; 32];
+type Reply = oneshot::Sender<Result<Arc<Vec<f32>>, String>>;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum EmbeddingBackend {
+    Local,
+    Provider,
+    Hash,
+}
+
+impl EmbeddingBackend {
+    fn from_config() -> Self {
+        let value: String = Config::global()
+            .get_param(EMBEDDING_BACKEND_KEY)
+            .unwrap_or_else(|_| "local".to_string());
+        match value.to_ascii_lowercase().as_str() {
+            "provider" | "openai" => Self::Provider,
+            "hash" => Self::Hash,
+            "local" | "candle" => Self::Local,
+            other => {
+                warn!("Unknown embedding backend '{}', using local", other);
+                Self::Local
+            }
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct EmbeddingServiceConfig {
+    /// Most texts sent to the backend in one call
+    pub max_batch: usize,
+    /// How long the first request waits for others to join its batch
+    pub batch_window: Duration,
+    pub cache_capacity: usize,
+    pub requests_per_minute: u32,
+}
+
+impl Default for EmbeddingServiceConfig {
+    fn default() -> Self {
+        Self {
+            max_batch: 64,
+            batch_window: Duration::from_millis(10),
+            cache_capacity: 4096,
+            requests_per_minute: 0,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct EmbeddingStats {
+    pub backend: String,
+    pub dimension: usize,
+    pub texts_requested: u64,
+    pub cache_hits: u64,
+    pub backend_calls: u64,
+}
+
+/// Embeddings from the provider's API, e.g. OpenAI `v1/embeddings`.
+pub struct ProviderEmbeddingProvider {
+    provider: Arc<dyn Provider>,
+    dimension: usize,
+}
+
+impl std::fmt::Debug for ProviderEmbeddingProvider {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("ProviderEmbeddingProvider")
+            .field("provider", &self.provider.get_name())
+            .field("dimension", &self.dimension)
+            .finish()
+    }
+}
+
+impl ProviderEmbeddingProvider {
+    /// Probes the provider once to learn the vector dimension.
+    pub async fn try_new(provider: Arc<dyn Provider>) -> anyhow::Result<Self> {
+        if !provider.supports_embeddings() {
+            anyhow::bail!("{} does not support embeddings", provider.get_name());
+        }
+        let probe = provider
+            .create_embeddings("embeddings", vec!["dimension probe".to_string()])
+            .await?;
+        let dimension = probe.first().map(Vec::len).unwrap_or(0);
+        if dimension == 0 {
+            anyhow::bail!("{} returned an empty embedding", provider.get_name());
+        }
+        Ok(Self {
+            provider,
+            dimension,
+        })
+    }
+
+    async fn from_config() -> anyhow::Result<Self> {
+        let config = Config::global();
+        let model_config = crate::model::ModelConfig::new(&config.get_goose_model()?)?;
+        let provider =
+            crate::providers::create(&config.get_goose_provider()?, model_config).await?;
+        Self::try_new(provider).await
+    }
+}
+
+#[async_trait]
+impl EmbeddingProvider for ProviderEmbeddingProvider {
+    async fn embed(&self, text: &str) -> MemoryResult<Vec<f32>> {
+        self.embed_batch(&[text])
+            .await?
+            .pop()
+            .ok_or_else(|| MemoryError::embedding("Provider returned no embedding"))
+    }
+
+    async fn embed_batch(&self, texts: &[&str]) -> MemoryResult<Vec<Vec<f32>>> {
+        let texts = texts.iter().map(|t| t.to_string()).collect();
+        self.provider
+            .create_embeddings("embeddings", texts)
+            .await
+            .map_err(|e| MemoryError::embedding(e.to_string()))
+    }
+
+    fn dimension(&self) -> usize {
+        self.dimension
+    }
+
+    fn name(&self) -> &str {
+        "provider"
+    }
+}
+
+struct Inner {
+    backend: Arc<dyn EmbeddingProvider>,
+    config: EmbeddingServiceConfig,
+    cache: Mutex<LruCache<CacheKey, Arc<Vec<f32>>>>,
+    pending: Mutex<HashMap<CacheKey, (String, Vec<Reply>)>>,
+    next_call: tokio::sync::Mutex<Instant>,
+    texts_requested: AtomicU64,
+    cache_hits: AtomicU64,
+    backend_calls: AtomicU64,
+}
+
+/// Cheap to clone; clones share batches, cache and rate limit.
+#[derive(Clone)]
+pub struct EmbeddingService {
+    inner: Arc<Inner>,
+}
+
+impl std::fmt::Debug for EmbeddingService {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("EmbeddingService")
+            .field("backend", &self.inner.backend.name())
+            .field("config", &self.inner.config)
+            .finish_non_exhaustive()
+    }
+}
+
+static SHARED: OnceCell<EmbeddingService> = OnceCell::const_new();
+
+impl EmbeddingService {
+    pub fn new(backend: Arc<dyn EmbeddingProvider>, config: EmbeddingServiceConfig) -> Self {
+        let capacity = NonZeroUsize::new(config.cache_capacity).unwrap_or(NonZeroUsize::MIN);
+        Self {
+            inner: Arc::new(Inner {
+                backend,
+                config,
+                cache: Mutex::new(LruCache::new(capacity)),
+                pending: Mutex::new(HashMap::new()),
+                next_call: tokio::sync::Mutex::new(Instant::now()),
+                texts_requested: AtomicU64::new(0),
+                cache_hits: AtomicU64::new(0),
+                backend_calls: AtomicU64::new(0),
+            }),
+        }
+    }
+
+    /// The process-wide service, built from config on first use.
+    pub async fn shared() -> Self {
+        SHARED.get_or_init(Self::from_config).await.clone()
+    }
+
+    /// The shared service if it has loaded. Otherwise starts loading it in
+    /// the background and returns `None`, so a caller can go on without it.
+    pub fn ready_or_load_in_background() -> Option<Self> {
+        if let Some(service) = SHARED.get() {
+            return Some(service.clone());
+        }
+        tokio::spawn(async {
+            let start = Instant::now();
+            Self::shared().await;
+            startup::record("embeddings", StartupPhase::FirstReply, start.elapsed());
+        });
+        None
+    }
+
+    async fn from_config() -> Self {
+        let backend_kind = EmbeddingBackend::from_config();
+        let fallback = || -> Arc<dyn EmbeddingProvider> {
+            Arc::new(HashEmbeddingProvider::new(DEFAULT_DIMENSION))
+        };
+        let backend: Arc<dyn EmbeddingProvider> = match backend_kind {
+            EmbeddingBackend::Hash => fallback(),
+            EmbeddingBackend::Local => match CandleEmbeddingProvider::try_new().await {
+                Ok(provider) => Arc::new(provider),
+                Err(e) => {
+                    warn!(
+                        "Candle embedding model unavailable ({}), using hash-based fallback. \
+                         Semantic search will work but with reduced quality.",
+                        e
+                    );
+                    fallback()
+                }
+            },
+            EmbeddingBackend::Provider => match ProviderEmbeddingProvider::from_config().await {
+                Ok(provider) => Arc::new(provider),
+                Err(e) => {
+                    warn!(
+                        "Provider embeddings unavailable ({}), using hash-based fallback",
+                        e
+                    );
+                    fallback()
+                }
+            },
+        };
+
+        let config = Config::global();
+        let defaults = EmbeddingServiceConfig::default();
+        let service_config = EmbeddingServiceConfig {
+            cache_capacity: config
+                .get_param(EMBEDDING_CACHE_SIZE_KEY)
+                .unwrap_or(defaults.cache_capacity),
+            requests_per_minute: config.get_param(EMBEDDING_RPM_KEY).unwrap_or(
+                if backend_kind == EmbeddingBackend::Provider {
+                    120
+                } else {
+                    0
+                },
+            ),
+            ..defaults
+        };
+        info!(
+            backend = backend.name(),
+            dimension = backend.dimension(),
+            "Embedding service ready"
+        );
+        Self::new(backend, service_config)
+    }
+
+    fn cache_key(&self, text: &str) -> CacheKey {
+        let mut hasher = Sha256::new();
+        hasher.update(self.inner.backend.name().as_bytes());
+        hasher.update([0]);
+        hasher.update(text.as_bytes());
+        hasher.finalize().into()
+    }
+
+    pub fn stats(&self) -> EmbeddingStats {
+        EmbeddingStats {
+            backend: self.inner.backend.name().to_string(),
+            dimension: self.inner.backend.dimension(),
+            texts_requested: self.inner.texts_requested.load(Ordering::Relaxed),
+            cache_hits: self.inner.cache_hits.load(Ordering::Relaxed),
+            backend_calls: self.inner.backend_calls.load(Ordering::Relaxed),
+        }
+    }
+
+    async fn embed_shared(&self, texts: &[&str]) -> MemoryResult<Vec<Arc<Vec<f32>>>> {
+        self.inner
+            .texts_requested
+            .fetch_add(texts.len() as u64, Ordering::Relaxed);
+
+        let mut results: Vec<Option<Arc<Vec<f32>>>> = vec![None; texts.len()];
+        let mut waiting = Vec::new();
+        let mut starts_batch = false;
+        {
+            let mut cache = self.inner.cache.lock().unwrap_or_else(|e| e.into_inner());
+            let mut pending = self.inner.pending.lock().unwrap_or_else(|e| e.into_inner());
+            for (i, text) in texts.iter().enumerate() {
+                let key = self.cache_key(text);
+                if let Some(hit) = cache.get(&key) {
+                    results[i] = Some(hit.clone());
+                    self.inner.cache_hits.fetch_add(1, Ordering::Relaxed);
+                    continue;
+                }
+                starts_batch |= pending.is_empty();
+                let (tx, rx) = oneshot::channel();
+                pending
+                    .entry(key)
+                    .or_insert_with(|| (text.to_string(), Vec::new()))
+                    .1
+                    .push(tx);
+                waiting.push((i, rx));
+            }
+        }
+
+        // The request that opens a batch flushes it on its own task, so a
+        // dropped caller never strands the others
+        if starts_batch {
+            let inner = self.inner.clone();
+            tokio::spawn(async move {
+                tokio::time::sleep(inner.config.batch_window).await;
+                inner.flush().await;
+            });
+        }
+
+        for (i, rx) in waiting {
+            let embedding = rx
+                .await
+                .map_err(|_| MemoryError::embedding("Embedding batch was dropped"))?
+                .map_err(MemoryError::embedding)?;
+            results[i] = Some(embedding);
+        }
+        Ok(results.into_iter().flatten().collect())
+    }
+}
+
+impl Inner {
+    async fn flush(&self) {
+        let batch: Vec<(CacheKey, (String, Vec<Reply>))> = {
+            let mut pending = self.pending.lock().unwrap_or_else(|e| e.into_inner());
+            pending.drain().collect()
+        };
+
+        for chunk in batch.chunks(self.config.max_batch.max(1)) {
+            self.wait_for_rate_limit().await;
+            self.backend_calls.fetch_add(1, Ordering::Relaxed);
+            let texts: Vec<&str> = chunk.iter().map(|(_, (text, _))| text.as_str()).collect();
+            let outcome = self.backend.embed_batch(&texts).await;
+            let vectors = match outcome {
+                Ok(vectors) if vectors.len() == chunk.len() => Ok(vectors),
+                Ok(vectors) => Err(format!(
+                    "Backend returned {} embeddings for {} texts",
+                    vectors.len(),
+                    chunk.len()
+                )),
+                Err(e) => Err(e.to_string()),
+            };
+
+            match vectors {
+                Ok(vectors) => {
+                    let mut cache = self.cache.lock().unwrap_or_else(|e| e.into_inner());
+                    for ((key, _), vector) in chunk.iter().zip(vectors) {
+                        cache.put(*key, Arc::new(vector));
+                    }
+                }
+                Err(e) => warn!(
+                    backend = self.backend.name(),
+                    "Embedding batch failed: {}", e
+                ),
+            }
+        }
+
+        // Every text of a successful chunk is now cached; anything missing failed
+        let mut cache = self.cache.lock().unwrap_or_else(|e| e.into_inner());
+        for (key, (_, replies)) in batch {
+            let result = cache
+                .get(&key)
+                .cloned()
+                .ok_or_else(|| "Embedding backend failed".to_string());
+            for reply in replies {
+                let _ = reply.send(result.clone());
+            }
+        }
+    }
+
+    async fn wait_for_rate_limit(&self) {
+        if self.config.requests_per_minute == 0 {
+            return;
+        }
+        let interval = Duration::from_secs(60) / self.config.requests_per_minute;
+        let mut next_call = self.next_call.lock().await;
+        tokio::time::sleep_until(*next_call).await;
+        *next_call = Instant::now() + interval;
+    }
+}
+
+#[async_trait]
+impl EmbeddingProvider for EmbeddingService {
+    async fn embed(&self, text: &str) -> MemoryResult<Vec<f32>> {
+        let mut vectors = self.embed_shared(&[text]).await?;
+        Ok(vectors.pop().map(Arc::unwrap_or_clone).unwrap_or_default())
+    }
+
+    async fn embed_batch(&self, texts: &[&str]) -> MemoryResult<Vec<Vec<f32>>> {
+        let vectors = self.embed_shared(texts).await?;
+        Ok(vectors.into_iter().map(Arc::unwrap_or_clone).collect())
+    }
+
+    fn dimension(&self) -> usize {
+        self.inner.backend.dimension()
+    }
+
+    fn name(&self) -> &str {
+        self.inner.backend.name()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[derive(Debug)]
+    struct CountingBackend {
+        hash: HashEmbeddingProvider,
+        calls: AtomicU64,
+    }
+
+    #[async_trait]
+    impl EmbeddingProvider for CountingBackend {
+        async fn embed(&self, text: &str) -> MemoryResult<Vec<f32>> {
+            self.hash.embed(text).await
+        }
+
+        async fn embed_batch(&self, texts: &[&str]) -> MemoryResult<Vec<Vec<f32>>> {
+            self.calls.fetch_add(1, Ordering::Relaxed);
+            self.hash.embed_batch(texts).await
+        }
+
+        fn dimension(&self) -> usize {
+            self.hash.dimension()
+        }
+
+        fn name(&self) -> &str {
+            "counting"
+        }
+    }
+
+    #[tokio::test]
+    async fn test_concurrent_requests_share_a_batch_and_cache() {
+        let backend = Arc::new(CountingBackend {
+            hash: HashEmbeddingProvider::new(32),
+            calls: AtomicU64::new(0),
+        });
+        let service = EmbeddingService::new(backend.clone(), EmbeddingServiceConfig::default());
+
+        let (a, b, c) = tokio::join!(
+            service.embed("alpha"),
+            service.embed("beta"),
+            service.embed_batch(&["alpha", "gamma"]),
+        );
+        assert_eq!(backend.calls.load(Ordering::Relaxed), 1);
+        let c = c.unwrap();
+        assert_eq!(a.unwrap(), c[0]);
+        assert_ne!(b.unwrap(), c[1]);
+
+        service.embed("gamma").await.unwrap();
+        let stats = service.stats();
+        assert_eq!(backend.calls.load(Ordering::Relaxed), 1);
+        assert_eq!(stats.texts_requested, 5);
+        assert_eq!(stats.cache_hits, 1);
+    }
+}
@@ -62,6 +62,10 @@ pub enum MemoryError {
     /// Lock error
     #[error("Lock error: {0}")]
     LockError(String),
+
+    /// Refused by a memory class policy
+    #[error("Memory rejected: {0}")]
+    Rejected(String),
 }
 
 impl MemoryError {
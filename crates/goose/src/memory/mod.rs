@@ -34,7 +34,9 @@
 //! let memories = manager.recall("user preferences", &context).await?;
 //! ```
 
+pub mod classification;
 pub mod consolidation;
+pub mod embedding_service;
 pub mod embeddings;
 pub mod episodic_memory;
 pub mod errors;
@@ -46,8 +48,8 @@ pub use errors::{MemoryError, MemoryResult};
 
 use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
-use std::collections::HashMap;
-use std::sync::Arc;
+use std::collections::{HashMap, HashSet};
+use std::sync::{Arc, LazyLock};
 use tokio::sync::RwLock;
 use uuid::Uuid;
 
@@ -194,6 +196,9 @@ pub struct MemoryMetadata {
     pub relationships: Vec<MemoryRelation>,
     /// Additional custom data
     pub custom: HashMap<String, serde_json::Value>,
+    /// PII and secret classes detected in the content when it was stored
+    #[serde(default)]
+    pub pii_classes: Vec<String>,
 }
 
 impl Default for MemoryMetadata {
@@ -207,6 +212,7 @@ impl Default for MemoryMetadata {
             confidence: 1.0,
             relationships: Vec::new(),
             custom: HashMap::new(),
+            pii_classes: Vec::new(),
         }
     }
 }
@@ -280,6 +286,10 @@ pub struct MemoryEntry {
     pub content: String,
     /// Vector embedding (optional, generated if not provided)
     pub embedding: Option<Vec<f32>>,
+    /// Embedding backend that produced the stored vector; `None` for the
+    /// semantic store's hash fallback
+    #[serde(default)]
+    pub embedding_backend: Option<String>,
     /// Associated metadata
     pub metadata: MemoryMetadata,
     /// When this memory was created
@@ -303,6 +313,7 @@ impl MemoryEntry {
             memory_type,
             content: content.into(),
             embedding: None,
+            embedding_backend: None,
             metadata: MemoryMetadata::default(),
             created_at: now,
             accessed_at: now,
@@ -605,8 +616,45 @@ impl MemoryManager {
             .unwrap_or("hash-fallback")
     }
 
-    /// Store a new memory entry
-    pub async fn store(&self, entry: MemoryEntry) -> MemoryResult<String> {
+    /// Re-embed semantic memories whose vectors came from another backend,
+    /// such as the hash fallback used before the provider was set or for
+    /// memories loaded from disk, so searches compare vectors from one space.
+    /// Returns how many were re-embedded.
+    pub async fn reembed_stale(&self) -> MemoryResult<usize> {
+        let Some(provider) = self.embedding_provider.clone() else {
+            return Ok(0);
+        };
+        let backend = provider.name().to_string();
+        let stale: Vec<MemoryEntry> = self
+            .semantic
+            .read()
+            .await
+            .all_entries()
+            .into_iter()
+            .filter(|e| e.embedding_backend.as_deref() != Some(backend.as_str()))
+            .collect();
+        if stale.is_empty() {
+            return Ok(0);
+        }
+
+        let texts: Vec<String> = stale.iter().map(classification::embedding_text).collect();
+        let texts: Vec<&str> = texts.iter().map(String::as_str).collect();
+        let embeddings = provider.embed_batch(&texts).await?;
+
+        let mut semantic = self.semantic.write().await;
+        let mut count = 0;
+        for (entry, embedding) in stale.iter().zip(embeddings) {
+            if semantic.reembed(&entry.id, embedding, &backend)? {
+                count += 1;
+            }
+        }
+        Ok(count)
+    }
+
+    /// Store a new memory entry. Entries are classified first and refused
+    /// when a class policy forbids storing them.
+    pub async fn store(&self, mut entry: MemoryEntry) -> MemoryResult<String> {
+        classification::apply(&mut entry)?;
         let id = entry.id.clone();
 
         match entry.memory_type {
@@ -626,12 +674,15 @@ impl MemoryManager {
             }
             MemoryType::Semantic | MemoryType::Procedural => {
                 // Pre-compute embedding via provider if available and entry lacks one
-                let mut entry = entry;
                 if entry.embedding.is_none() {
                     if let Some(ref provider) = self.embedding_provider {
-                        match provider.embed(&entry.content).await {
+                        match provider
+                            .embed(&classification::embedding_text(&entry))
+                            .await
+                        {
                             Ok(emb) => {
                                 entry.embedding = Some(emb);
+                                entry.embedding_backend = Some(provider.name().to_string());
                             }
                             Err(e) => {
                                 tracing::debug!(
@@ -769,26 +820,37 @@ impl MemoryManager {
         let hours = self.config.decay_interval_hours as f64;
         let threshold = self.config.min_importance_threshold;
 
-        let working_removed;
-        let episodic_removed;
-        let semantic_removed;
+        let now = Utc::now();
+
+        let mut working_removed;
+        let mut episodic_removed;
+        let mut semantic_removed;
 
         // Decay working memory
         {
             let mut working = self.working.write().await;
             working_removed = working.apply_decay(hours, threshold)?;
+            for id in classification::expired(working.all(), now) {
+                working_removed += usize::from(working.delete(&id)?);
+            }
         }
 
         // Decay episodic memory
         {
             let mut episodic = self.episodic.write().await;
             episodic_removed = episodic.apply_decay(hours, threshold)?;
+            for id in classification::expired(&episodic.all_entries(), now) {
+                episodic_removed += usize::from(episodic.delete(&id)?);
+            }
         }
 
         // Decay semantic memory (slower)
         {
             let mut semantic = self.semantic.write().await;
             semantic_removed = semantic.apply_decay(hours, threshold)?;
+            for id in classification::expired(&semantic.all_entries(), now) {
+                semantic_removed += usize::from(semantic.delete(&id)?);
+            }
         }
 
         Ok(DecayReport {
@@ -829,28 +891,35 @@ impl MemoryManager {
 
     /// Save all persistent memories (episodic + semantic) to disk for cross-session persistence
     pub async fn save_to_disk(&self) -> MemoryResult<std::path::PathBuf> {
-        let memory_dir = dirs::config_dir()
-            .unwrap_or_else(|| std::path::PathBuf::from("."))
-            .join("goose")
-            .join("memory");
-        std::fs::create_dir_all(&memory_dir).map_err(|e| {
+        let file_path = snapshot_path();
+        let memory_dir = file_path
+            .parent()
+            .unwrap_or_else(|| std::path::Path::new("."));
+        std::fs::create_dir_all(memory_dir).map_err(|e| {
             MemoryError::storage(format!("Failed to create memory directory: {}", e))
         })?;
 
-        let snapshot = MemorySnapshot {
-            version: 1,
-            saved_at: Utc::now(),
-            episodic: self.episodic.read().await.all_entries(),
-            semantic: self.semantic.read().await.all_entries(),
+        let mut episodic = self.episodic.read().await.all_entries();
+        let mut semantic = self.semantic.read().await.all_entries();
+        let snapshot = {
+            let erased = ERASED.lock().unwrap_or_else(|e| e.into_inner());
+            let keep = |entry: &MemoryEntry| !erased.contains(&entry.id);
+            episodic.retain(keep);
+            semantic.retain(keep);
+            MemorySnapshot {
+                version: 1,
+                saved_at: Utc::now(),
+                episodic,
+                semantic,
+            }
         };
 
-        let file_path = memory_dir.join("memories.json");
-        let json = serde_json::to_string_pretty(&snapshot).map_err(|e| {
-            MemoryError::storage(format!("Failed to serialize memories: {}", e))
-        })?;
-        std::fs::write(&file_path, json).map_err(|e| {
-            MemoryError::storage(format!("Failed to write memory file: {}", e))
-        })?;
+        let json = serde_json::to_string_pretty(&snapshot)
+            .map_err(|e| MemoryError::storage(format!("Failed to serialize memories: {}", e)))?;
+        let json = crate::security::at_rest::seal(json)
+            .map_err(|e| MemoryError::storage(format!("Failed to encrypt memories: {}", e)))?;
+        std::fs::write(&file_path, json)
+            .map_err(|e| MemoryError::storage(format!("Failed to write memory file: {}", e)))?;
 
         tracing::info!(
             "Saved {} episodic + {} semantic memories to {:?}",
@@ -864,29 +933,29 @@ impl MemoryManager {
 
     /// Load persistent memories from disk (call on startup for cross-session recall)
     pub async fn load_from_disk(&self) -> MemoryResult<usize> {
-        let file_path = dirs::config_dir()
-            .unwrap_or_else(|| std::path::PathBuf::from("."))
-            .join("goose")
-            .join("memory")
-            .join("memories.json");
+        let file_path = snapshot_path();
 
         if !file_path.exists() {
             return Ok(0);
         }
 
-        let json = std::fs::read_to_string(&file_path).map_err(|e| {
-            MemoryError::storage(format!("Failed to read memory file: {}", e))
-        })?;
-        let snapshot: MemorySnapshot = serde_json::from_str(&json).map_err(|e| {
-            MemoryError::storage(format!("Failed to deserialize memories: {}", e))
-        })?;
+        let json = std::fs::read_to_string(&file_path)
+            .map_err(|e| MemoryError::storage(format!("Failed to read memory file: {}", e)))?;
+        let json = crate::security::at_rest::open(json)
+            .map_err(|e| MemoryError::storage(format!("Failed to decrypt memory file: {}", e)))?;
+        let snapshot: MemorySnapshot = serde_json::from_str(&json)
+            .map_err(|e| MemoryError::storage(format!("Failed to deserialize memories: {}", e)))?;
 
         let mut loaded = 0;
+        let now = Utc::now();
 
         // Load episodic memories
         {
             let mut episodic = self.episodic.write().await;
             for entry in snapshot.episodic {
+                if classification::is_expired(&entry, now) {
+                    continue;
+                }
                 if episodic.store(entry).is_ok() {
                     loaded += 1;
                 }
@@ -897,6 +966,9 @@ impl MemoryManager {
         {
             let mut semantic = self.semantic.write().await;
             for entry in snapshot.semantic {
+                if classification::is_expired(&entry, now) {
+                    continue;
+                }
                 if semantic.store(entry).is_ok() {
                     loaded += 1;
                 }
@@ -908,6 +980,88 @@ impl MemoryManager {
     }
 }
 
+/// Where persistent memories are saved between sessions
+pub fn snapshot_path() -> std::path::PathBuf {
+    dirs::config_dir()
+        .unwrap_or_else(|| std::path::PathBuf::from("."))
+        .join("goose")
+        .join("memory")
+        .join("memories.json")
+}
+
+/// Encrypts a memory snapshot saved before encryption at rest was turned
+/// on. Returns whether there was one to seal.
+pub fn seal_existing_snapshot() -> MemoryResult<bool> {
+    let path = snapshot_path();
+    let Ok(contents) = std::fs::read_to_string(&path) else {
+        return Ok(false);
+    };
+    if crate::security::at_rest::is_sealed(&contents) {
+        return Ok(false);
+    }
+    let sealed = crate::security::at_rest::seal_always(&contents)
+        .map_err(|e| MemoryError::storage(format!("Failed to encrypt memories: {}", e)))?;
+    std::fs::write(&path, sealed)
+        .map_err(|e| MemoryError::storage(format!("Failed to write memory file: {}", e)))?;
+    Ok(true)
+}
+
+/// Memories erased from the snapshot by this process. Agents that loaded
+/// them earlier leave them out when they save.
+static ERASED: LazyLock<std::sync::Mutex<HashSet<String>>> =
+    LazyLock::new(|| std::sync::Mutex::new(HashSet::new()));
+
+/// Removes the memories matching `erase` from the saved snapshot. Returns
+/// how many were removed.
+pub fn erase_from_snapshot(erase: impl Fn(&MemoryEntry) -> bool) -> MemoryResult<usize> {
+    let path = snapshot_path();
+    let Ok(contents) = std::fs::read_to_string(&path) else {
+        return Ok(0);
+    };
+    let json = crate::security::at_rest::open(contents)
+        .map_err(|e| MemoryError::storage(format!("Failed to decrypt memory file: {}", e)))?;
+    let mut snapshot: MemorySnapshot = serde_json::from_str(&json)
+        .map_err(|e| MemoryError::storage(format!("Failed to deserialize memories: {}", e)))?;
+
+    let mut erased = ERASED.lock().unwrap_or_else(|e| e.into_inner());
+    let mut removed = 0;
+    for entries in [&mut snapshot.episodic, &mut snapshot.semantic] {
+        entries.retain(|entry| {
+            let matches = erase(entry);
+            if matches {
+                erased.insert(entry.id.clone());
+                removed += 1;
+            }
+            !matches
+        });
+    }
+    if removed > 0 {
+        let json = serde_json::to_string_pretty(&snapshot)
+            .map_err(|e| MemoryError::storage(format!("Failed to serialize memories: {}", e)))?;
+        let json = crate::security::at_rest::seal(json)
+            .map_err(|e| MemoryError::storage(format!("Failed to encrypt memories: {}", e)))?;
+        std::fs::write(&path, json)
+            .map_err(|e| MemoryError::storage(format!("Failed to write memory file: {}", e)))?;
+    }
+    Ok(removed)
+}
+
+/// Every memory in the saved snapshot
+pub fn saved_entries() -> MemoryResult<Vec<MemoryEntry>> {
+    let Ok(contents) = std::fs::read_to_string(snapshot_path()) else {
+        return Ok(Vec::new());
+    };
+    let json = crate::security::at_rest::open(contents)
+        .map_err(|e| MemoryError::storage(format!("Failed to decrypt memory file: {}", e)))?;
+    let snapshot: MemorySnapshot = serde_json::from_str(&json)
+        .map_err(|e| MemoryError::storage(format!("Failed to deserialize memories: {}", e)))?;
+    Ok(snapshot
+        .episodic
+        .into_iter()
+        .chain(snapshot.semantic)
+        .collect())
+}
+
 /// Snapshot of persistent memories for disk serialization
 #[derive(Debug, Clone, Serialize, Deserialize)]
 struct MemorySnapshot {
@@ -1202,6 +1356,27 @@ mod tests {
         assert_eq!(results[0].id, id);
     }
 
+    #[tokio::test]
+    async fn test_reembed_stale_replaces_hash_fallback_vectors() {
+        let mut manager = MemoryManager::new(MemoryConfig::minimal()).unwrap();
+        manager
+            .store(MemoryEntry::new(MemoryType::Semantic, "Rust uses cargo"))
+            .await
+            .unwrap();
+        assert_eq!(manager.reembed_stale().await.unwrap(), 0);
+
+        let dimension = manager.config().embedding_dimension;
+        manager.set_embedding_provider(Arc::new(embeddings::HashEmbeddingProvider::new(dimension)));
+        assert_eq!(manager.reembed_stale().await.unwrap(), 1);
+        assert_eq!(manager.reembed_stale().await.unwrap(), 0);
+
+        manager
+            .store(MemoryEntry::new(MemoryType::Semantic, "Python uses pip"))
+            .await
+            .unwrap();
+        assert_eq!(manager.reembed_stale().await.unwrap(), 0);
+    }
+
     #[tokio::test]
     async fn test_memory_manager_get_by_id() {
         let config = MemoryConfig::minimal();
@@ -5,7 +5,7 @@
 
 use std::collections::HashMap;
 
-use super::{MemoryEntry, MemoryError, MemoryResult, MemoryType, RecallContext};
+use super::{classification, MemoryEntry, MemoryError, MemoryResult, MemoryType, RecallContext};
 
 /// Semantic memory store with vector search capabilities
 #[derive(Debug)]
@@ -51,10 +51,13 @@ impl SemanticStore {
         }
 
         // Extract or generate embedding
-        let embedding = entry
-            .embedding
-            .take()
-            .unwrap_or_else(|| self.generate_embedding(&entry.content));
+        let embedding = match entry.embedding.take() {
+            Some(embedding) => embedding,
+            None => {
+                entry.embedding_backend = None;
+                self.generate_embedding(&classification::embedding_text(&entry))
+            }
+        };
 
         // Validate embedding dimension
         if embedding.len() != self.embedding_dim {
@@ -289,6 +292,17 @@ impl SemanticStore {
         }
     }
 
+    /// Replace an entry's embedding with one produced by `backend`
+    pub fn reembed(&mut self, id: &str, embedding: Vec<f32>, backend: &str) -> MemoryResult<bool> {
+        if !self.update_embedding(id, embedding)? {
+            return Ok(false);
+        }
+        if let Some(entry) = self.entries.get_mut(id) {
+            entry.embedding_backend = Some(backend.to_string());
+        }
+        Ok(true)
+    }
+
     /// Generate a simple embedding from text
     /// This is a basic implementation - production would use a proper model
     fn generate_embedding(&self, text: &str) -> Vec<f32> {
@@ -370,7 +384,7 @@ impl SemanticStore {
 }
 
 /// Calculate cosine similarity between two vectors
-fn cosine_similarity(a: &[f32], b: &[f32]) -> f64 {
+pub(crate) fn cosine_similarity(a: &[f32], b: &[f32]) -> f64 {
     if a.len() != b.len() || a.is_empty() {
         return 0.0;
     }
@@ -5,6 +5,7 @@
 
 use super::errors::ObservabilityError;
 use super::ReportFormat;
+use crate::providers::canonical::maybe_get_canonical_model;
 use chrono::{DateTime, Utc};
 use once_cell::sync::Lazy;
 use serde::{Deserialize, Serialize};
@@ -366,8 +367,15 @@ impl CostTracker {
 
     /// Calculate cost for a single request
     pub fn calculate_cost(&self, usage: &TokenUsage, model: &str) -> f64 {
-        let pricing = self.get_pricing_sync(model);
+        Self::cost_with_pricing(usage, &self.get_pricing_sync(model))
+    }
+
+    /// Calculate cost for a single request to `model` served by `provider`
+    pub fn calculate_provider_cost(&self, usage: &TokenUsage, provider: &str, model: &str) -> f64 {
+        Self::cost_with_pricing(usage, &self.get_provider_pricing(provider, model))
+    }
 
+    fn cost_with_pricing(usage: &TokenUsage, pricing: &ModelPricing) -> f64 {
         // Calculate base input cost
         let input_cost = (usage.input_tokens as f64 / 1000.0) * pricing.input_per_1k;
 
@@ -390,29 +398,56 @@ impl CostTracker {
 
     /// Get pricing for a model (sync version for internal use)
     fn get_pricing_sync(&self, model: &str) -> ModelPricing {
+        self.lookup_pricing(model).unwrap_or_default()
+    }
+
+    fn lookup_pricing(&self, model: &str) -> Option<ModelPricing> {
         // Try overrides first (blocking read for sync context)
         if let Ok(overrides) = self.pricing_overrides.try_read() {
             if let Some(pricing) = overrides.get(model) {
-                return *pricing;
+                return Some(*pricing);
             }
         }
 
         // Try exact match in static pricing
         if let Some(pricing) = MODEL_PRICING.get(model) {
-            return *pricing;
+            return Some(*pricing);
         }
 
         // Try prefix match for wildcards (e.g., "ollama/*")
         for (pattern, pricing) in MODEL_PRICING.iter() {
             if let Some(prefix) = pattern.strip_suffix("/*") {
                 if model.starts_with(prefix) {
-                    return *pricing;
+                    return Some(*pricing);
                 }
             }
         }
 
-        // Default conservative pricing
-        ModelPricing::default()
+        None
+    }
+
+    /// Get pricing for a model served by `provider`
+    ///
+    /// Looks up the model itself, then the provider-qualified name (so
+    /// provider wildcards like "ollama/*" apply), then the canonical model
+    /// registry, before falling back to the default pricing.
+    pub fn get_provider_pricing(&self, provider: &str, model: &str) -> ModelPricing {
+        self.lookup_pricing(model)
+            .or_else(|| self.lookup_pricing(&format!("{}/{}", provider, model)))
+            .or_else(|| {
+                let cost = maybe_get_canonical_model(provider, model)?.cost;
+                let input_per_1k = cost.input? / 1000.0;
+                let output_per_1k = cost.output? / 1000.0;
+                Some(match cost.cache_read {
+                    Some(cache_read) => ModelPricing::with_cache_cost(
+                        input_per_1k,
+                        output_per_1k,
+                        cache_read / 1000.0,
+                    ),
+                    None => ModelPricing::new(input_per_1k, output_per_1k),
+                })
+            })
+            .unwrap_or_default()
     }
 
     /// Get pricing for a model
@@ -843,6 +878,21 @@ mod tests {
         assert_eq!(cost, 0.0);
     }
 
+    #[test]
+    fn test_provider_pricing() {
+        let tracker = CostTracker::new();
+        let usage = TokenUsage::new(1000, 1000);
+
+        let cost = tracker.calculate_provider_cost(&usage, "openai", "gpt-4o");
+        assert!((cost - 0.0125).abs() < 1e-9);
+
+        // Provider wildcards apply to bare model names
+        assert_eq!(
+            tracker.calculate_provider_cost(&usage, "ollama", "llama3"),
+            0.0
+        );
+    }
+
     #[test]
     fn test_local_free_pricing() {
         let tracker = CostTracker::new();
@@ -0,0 +1,275 @@
+//! Cost Forecasting
+//!
+//! The cost tracker reports what has been spent; this module projects it
+//! forward. A session's burn rate predicts how many more turns its budget
+//! covers, so the user is warned while there is still room to change course
+//! rather than at the hard stop, and the daily burn of persisted sessions is
+//! extrapolated to month-end spend per team or provider.
+
+use super::team_accounting::{
+    month_start, next_month_start, ChargebackGroupBy, SessionUsageRecord, TeamQuota,
+};
+use chrono::{DateTime, Duration, NaiveDate, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap, VecDeque};
+use utoipa::ToSchema;
+
+/// Turns of cost samples the session burn rate is averaged over
+const BURN_WINDOW: usize = 5;
+
+/// Warn once the remaining budget covers this many turns or fewer
+pub const WARN_TURNS: f64 = 5.0;
+
+/// Days of history the monthly projection's daily burn is taken from
+const TRAILING_DAYS: i64 = 7;
+
+/// Cumulative session cost sampled at each turn
+#[derive(Debug, Clone, Default)]
+pub struct BurnRate {
+    samples: VecDeque<(DateTime<Utc>, f64)>,
+}
+
+impl BurnRate {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Record the session's total cost so far
+    pub fn record(&mut self, at: DateTime<Utc>, total_cost: f64) {
+        if self.samples.len() > BURN_WINDOW {
+            self.samples.pop_front();
+        }
+        self.samples.push_back((at, total_cost));
+    }
+
+    /// Average cost of the recent turns
+    pub fn per_turn(&self) -> Option<f64> {
+        let (first, last) = (self.samples.front()?, self.samples.back()?);
+        let turns = self.samples.len() - 1;
+        (turns > 0).then(|| (last.1 - first.1).max(0.0) / turns as f64)
+    }
+
+    /// Average cost per hour over the recent turns
+    pub fn per_hour(&self) -> Option<f64> {
+        let (first, last) = (self.samples.front()?, self.samples.back()?);
+        let seconds = (last.0 - first.0).num_milliseconds() as f64 / 1000.0;
+        (seconds > 0.0).then(|| (last.1 - first.1).max(0.0) / seconds * 3600.0)
+    }
+
+    /// Where the session is headed given `remaining` budget
+    pub fn forecast(&self, remaining: f64) -> TaskForecast {
+        let per_turn = self.per_turn().filter(|rate| *rate > 0.0);
+        let turns_left = per_turn.map(|rate| remaining.max(0.0) / rate);
+        let exhausted_at = self.per_hour().filter(|rate| *rate > 0.0).and_then(|rate| {
+            let seconds = remaining.max(0.0) / rate * 3600.0;
+            let (at, _) = self.samples.back()?;
+            Some(*at + Duration::milliseconds((seconds * 1000.0) as i64))
+        });
+        TaskForecast {
+            remaining_usd: remaining,
+            per_turn_usd: per_turn,
+            turns_left,
+            exhausted_at,
+        }
+    }
+}
+
+/// Projection of a session against its budget
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, ToSchema)]
+pub struct TaskForecast {
+    pub remaining_usd: f64,
+    pub per_turn_usd: Option<f64>,
+    /// Turns the remaining budget covers at the current burn rate
+    pub turns_left: Option<f64>,
+    pub exhausted_at: Option<DateTime<Utc>>,
+}
+
+impl TaskForecast {
+    /// Warning for the user once the budget is on course to run out within
+    /// [`WARN_TURNS`] turns; `None` while there is headroom or no rate yet.
+    pub fn warning(&self) -> Option<String> {
+        let turns_left = self.turns_left.filter(|turns| *turns <= WARN_TURNS)?;
+        let per_turn = self.per_turn_usd?;
+        let when = self
+            .exhausted_at
+            .map(|at| format!(", around {} UTC", at.format("%H:%M")))
+            .unwrap_or_default();
+        Some(format!(
+            "Budget forecast: at ${:.4} per turn the remaining ${:.4} covers about {:.0} more turn(s){}. \
+             Consider narrowing the task or raising the budget before it halts.",
+            per_turn, self.remaining_usd, turns_left, when
+        ))
+    }
+}
+
+/// Spend attributed to one UTC day
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, ToSchema)]
+pub struct DailySpend {
+    pub date: NaiveDate,
+    pub cost_usd: f64,
+}
+
+/// Month-end projection for one team or provider
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct ForecastRow {
+    pub group: String,
+    pub month_to_date_usd: f64,
+    /// Average daily spend over the trailing week of this month
+    pub daily_burn_usd: f64,
+    pub projected_usd: f64,
+    /// The team's monthly cost quota, when grouped by team
+    pub limit_usd: Option<f64>,
+    pub projected_over_limit: bool,
+}
+
+/// Month-end spend projection
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct SpendForecast {
+    pub generated_at: DateTime<Utc>,
+    pub group_by: ChargebackGroupBy,
+    pub period_start: DateTime<Utc>,
+    pub period_end: DateTime<Utc>,
+    pub rows: Vec<ForecastRow>,
+    pub month_to_date_usd: f64,
+    pub projected_usd: f64,
+    pub daily: Vec<DailySpend>,
+}
+
+/// Spend per day since `since`, from the usage each session recorded per day.
+pub fn daily_spend(records: &[SessionUsageRecord], since: DateTime<Utc>) -> Vec<DailySpend> {
+    let mut days: BTreeMap<NaiveDate, f64> = BTreeMap::new();
+    for record in records.iter().filter(|r| r.updated_at >= since) {
+        *days.entry(record.updated_at.date_naive()).or_default() += record.cost_usd;
+    }
+    days.into_iter()
+        .map(|(date, cost_usd)| DailySpend { date, cost_usd })
+        .collect()
+}
+
+/// Project each group's spend to the end of the month containing `now`:
+/// month-to-date spend plus the trailing daily burn for the days left.
+pub fn project_month(
+    records: &[SessionUsageRecord],
+    group_by: ChargebackGroupBy,
+    quotas: &HashMap<String, TeamQuota>,
+    now: DateTime<Utc>,
+) -> SpendForecast {
+    let period_start = month_start(now);
+    let period_end = next_month_start(now);
+    let window_start = (now - Duration::days(TRAILING_DAYS)).max(period_start);
+    let window_days = ((now - window_start).num_seconds() as f64 / 86_400.0).max(1.0);
+    let days_left = (period_end - now).num_seconds().max(0) as f64 / 86_400.0;
+
+    let mut groups: BTreeMap<String, (f64, f64)> = BTreeMap::new();
+    for record in records.iter().filter(|r| r.updated_at >= period_start) {
+        let key = match group_by {
+            ChargebackGroupBy::Team => &record.team,
+            ChargebackGroupBy::Project => &record.project,
+            ChargebackGroupBy::User => &record.user,
+            ChargebackGroupBy::Provider => &record.provider,
+            ChargebackGroupBy::Model => &record.model,
+        };
+        let (month_to_date, trailing) = groups.entry(key.clone()).or_default();
+        *month_to_date += record.cost_usd;
+        if record.updated_at >= window_start {
+            *trailing += record.cost_usd;
+        }
+    }
+
+    let rows: Vec<ForecastRow> = groups
+        .into_iter()
+        .map(|(group, (month_to_date, trailing))| {
+            let daily_burn_usd = trailing / window_days;
+            let projected_usd = month_to_date + daily_burn_usd * days_left;
+            let limit_usd = match group_by {
+                ChargebackGroupBy::Team => quotas
+                    .get(&group)
+                    .and_then(|quota| quota.monthly_cost_limit_usd),
+                _ => None,
+            };
+            ForecastRow {
+                projected_over_limit: limit_usd.is_some_and(|limit| projected_usd > limit),
+                group,
+                month_to_date_usd: month_to_date,
+                daily_burn_usd,
+                projected_usd,
+                limit_usd,
+            }
+        })
+        .collect();
+
+    SpendForecast {
+        generated_at: now,
+        group_by,
+        period_start,
+        period_end,
+        month_to_date_usd: rows.iter().map(|r| r.month_to_date_usd).sum(),
+        projected_usd: rows.iter().map(|r| r.projected_usd).sum(),
+        rows,
+        daily: daily_spend(records, period_start),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::observability::team_accounting::UNASSIGNED;
+    use chrono::TimeZone;
+
+    #[test]
+    fn test_burn_rate_warns_before_budget_runs_out() {
+        let start = Utc::now();
+        let mut burn = BurnRate::new();
+        for turn in 0..4 {
+            burn.record(start + Duration::minutes(turn), turn as f64 * 0.5);
+        }
+        assert_eq!(burn.per_turn(), Some(0.5));
+
+        let roomy = burn.forecast(10.0);
+        assert_eq!(roomy.turns_left, Some(20.0));
+        assert!(roomy.warning().is_none());
+
+        let tight = burn.forecast(2.0);
+        assert_eq!(tight.turns_left, Some(4.0));
+        assert!(tight.warning().is_some());
+        assert!(BurnRate::new().forecast(0.1).warning().is_none());
+    }
+
+    #[test]
+    fn test_month_projection_flags_team_over_quota() {
+        let now = Utc.with_ymd_and_hms(2026, 4, 16, 0, 0, 0).unwrap();
+        let record = |team: &str, days_ago: i64, cost: f64| SessionUsageRecord {
+            session_id: format!("{}-{}", team, days_ago),
+            team: team.to_string(),
+            project: UNASSIGNED.to_string(),
+            user: UNASSIGNED.to_string(),
+            provider: "anthropic".to_string(),
+            model: "gpt-4o".to_string(),
+            updated_at: now - Duration::days(days_ago),
+            input_tokens: 0,
+            output_tokens: 0,
+            cost_usd: cost,
+        };
+        let records = vec![
+            record("platform", 1, 7.0),
+            record("platform", 10, 8.0),
+            record("research", 40, 50.0),
+        ];
+        let quotas = HashMap::from([(
+            "platform".to_string(),
+            TeamQuota {
+                monthly_cost_limit_usd: Some(25.0),
+                ..Default::default()
+            },
+        )]);
+
+        let forecast = project_month(&records, ChargebackGroupBy::Team, &quotas, now);
+        assert_eq!(forecast.rows.len(), 1);
+        let platform = &forecast.rows[0];
+        assert_eq!(platform.month_to_date_usd, 15.0);
+        assert_eq!(platform.daily_burn_usd, 1.0);
+        assert_eq!(platform.projected_usd, 30.0);
+        assert!(platform.projected_over_limit);
+        assert_eq!(forecast.daily.len(), 2);
+    }
+}
@@ -8,17 +8,23 @@
 //! - Cost tracking for LLM API usage
 //! - MCP-specific metrics and instrumentation
 //! - Export capabilities for Prometheus/OTLP
+//! - Team accounting with monthly quotas and chargeback reports
+//! - Burn-rate forecasts of task budgets and month-end spend
 
 pub mod cost_tracker;
 pub mod errors;
 pub mod exporters;
+pub mod forecast;
 pub mod metrics;
 pub mod semantic_conventions;
+pub mod team_accounting;
 
 pub use cost_tracker::{CostTracker, ModelPricing, RequestCost, SessionCost, TokenUsage};
 pub use errors::ObservabilityError;
+pub use forecast::{BurnRate, SpendForecast, TaskForecast};
 pub use metrics::{GenAiMetrics, McpMetrics, ObservabilityMetrics};
 pub use semantic_conventions::{gen_ai, mcp};
+pub use team_accounting::{ChargebackReport, TeamAssignment, TeamQuota};
 
 use chrono::{DateTime, Utc};
 use opentelemetry::{global, KeyValue};
@@ -27,7 +33,7 @@ use std::collections::HashMap;
 use std::sync::Arc;
 
 /// Report format for cost exports
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, utoipa::ToSchema)]
 #[serde(rename_all = "snake_case")]
 pub enum ReportFormat {
     /// JSON format
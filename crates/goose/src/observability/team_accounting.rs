@@ -0,0 +1,743 @@
+//! Team Accounting
+//!
+//! Organizational accounting on top of persisted session usage: sessions are
+//! assigned to teams and projects, teams get monthly token/cost quotas, and
+//! chargeback reports are priced with the cost tracker's model pricing.
+//!
+//! Each reply's usage is recorded in its session by day, so a long-lived
+//! session is billed to the months its tokens were used in. Team totals for
+//! the current month are cached and kept up to date as replies are recorded.
+
+use super::cost_tracker::{CostTracker, TokenUsage};
+use super::errors::ObservabilityError;
+use super::ReportFormat;
+use crate::config::Config;
+use crate::session::extension_data::{ExtensionData, ExtensionState};
+use crate::session::Session;
+use chrono::{DateTime, Datelike, NaiveDate, TimeZone, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::sync::{LazyLock, Mutex};
+use utoipa::ToSchema;
+
+/// Config key holding per-team quotas
+pub const TEAM_QUOTAS_CONFIG_KEY: &str = "team_quotas";
+
+/// Label used when a session has no team, project, user, provider or model
+pub const UNASSIGNED: &str = "unassigned";
+
+/// How long a cached team total is used before it is rebuilt from the
+/// sessions, picking up usage recorded by other processes
+const TEAM_USAGE_CACHE_MINUTES: i64 = 5;
+
+static TEAM_USAGE_CACHE: LazyLock<Mutex<HashMap<String, CachedTeamUsage>>> =
+    LazyLock::new(|| Mutex::new(HashMap::new()));
+
+struct CachedTeamUsage {
+    usage: TeamUsage,
+    session_ids: HashSet<String>,
+    loaded_at: DateTime<Utc>,
+}
+
+/// Team/project ownership of a session, stored in the session's extension data
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
+pub struct TeamAssignment {
+    /// Team the session is billed to
+    pub team: String,
+    /// Optional project within the team
+    #[serde(default)]
+    pub project: Option<String>,
+    /// Optional user who owns the session
+    #[serde(default)]
+    pub user: Option<String>,
+}
+
+impl ExtensionState for TeamAssignment {
+    const EXTENSION_NAME: &'static str = "team_assignment";
+    const VERSION: &'static str = "v0";
+}
+
+/// Tokens a session used with one provider and model on one day (UTC)
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct DayUsage {
+    pub date: NaiveDate,
+    pub provider: String,
+    pub model: String,
+    pub input_tokens: u64,
+    pub output_tokens: u64,
+}
+
+/// A session's usage by day, stored in the session's extension data
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SessionDailyUsage {
+    pub days: Vec<DayUsage>,
+}
+
+impl ExtensionState for SessionDailyUsage {
+    const EXTENSION_NAME: &'static str = "daily_usage";
+    const VERSION: &'static str = "v0";
+}
+
+impl SessionDailyUsage {
+    fn add(&mut self, date: NaiveDate, provider: &str, model: &str, input: u64, output: u64) {
+        let existing = self
+            .days
+            .iter_mut()
+            .find(|d| d.date == date && d.provider == provider && d.model == model);
+        match existing {
+            Some(day) => {
+                day.input_tokens += input;
+                day.output_tokens += output;
+            }
+            None => self.days.push(DayUsage {
+                date,
+                provider: provider.to_string(),
+                model: model.to_string(),
+                input_tokens: input,
+                output_tokens: output,
+            }),
+        }
+    }
+
+    fn input_tokens(&self) -> u64 {
+        self.days.iter().map(|d| d.input_tokens).sum()
+    }
+
+    fn output_tokens(&self) -> u64 {
+        self.days.iter().map(|d| d.output_tokens).sum()
+    }
+}
+
+/// What happens when a team goes past its monthly quota
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum QuotaAction {
+    /// Refuse new requests until the next month
+    #[default]
+    Reject,
+    /// Refuse new requests until an administrator approves the overage
+    RequireApproval,
+}
+
+/// Monthly quota for a team
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize, ToSchema)]
+pub struct TeamQuota {
+    /// Maximum tokens per calendar month (UTC)
+    #[serde(default)]
+    pub monthly_token_limit: Option<u64>,
+    /// Maximum cost in USD per calendar month (UTC)
+    #[serde(default)]
+    pub monthly_cost_limit_usd: Option<f64>,
+    /// Action taken once a limit is reached
+    #[serde(default)]
+    pub on_exceeded: QuotaAction,
+    /// Overage approval, valid until the end of the month it was granted in
+    #[serde(default)]
+    pub approved_until: Option<DateTime<Utc>>,
+}
+
+/// Result of checking a team against its quota
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, ToSchema)]
+#[serde(tag = "decision", rename_all = "snake_case")]
+pub enum QuotaDecision {
+    Allowed,
+    RequiresApproval { reason: String },
+    Rejected { reason: String },
+}
+
+impl QuotaDecision {
+    pub fn is_allowed(&self) -> bool {
+        matches!(self, QuotaDecision::Allowed)
+    }
+}
+
+/// Usage of a single session, priced with the cost tracker
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct SessionUsageRecord {
+    pub session_id: String,
+    pub team: String,
+    pub project: String,
+    pub user: String,
+    pub provider: String,
+    pub model: String,
+    pub updated_at: DateTime<Utc>,
+    pub input_tokens: u64,
+    pub output_tokens: u64,
+    pub cost_usd: f64,
+}
+
+/// Team usage within the current quota period
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct TeamUsage {
+    pub team: String,
+    pub period_start: DateTime<Utc>,
+    pub sessions: usize,
+    pub total_tokens: u64,
+    pub cost_usd: f64,
+}
+
+/// Dimension a chargeback report is grouped by
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum ChargebackGroupBy {
+    #[default]
+    Team,
+    Project,
+    User,
+    Provider,
+    Model,
+}
+
+/// Filters for a chargeback report
+#[derive(Debug, Clone, Default, Serialize, Deserialize, ToSchema)]
+pub struct ChargebackQuery {
+    #[serde(default)]
+    pub group_by: ChargebackGroupBy,
+    /// Only include sessions updated at or after this time
+    #[serde(default)]
+    pub start: Option<DateTime<Utc>>,
+    /// Only include sessions updated before this time
+    #[serde(default)]
+    pub end: Option<DateTime<Utc>>,
+    /// Only include sessions billed to this team
+    #[serde(default)]
+    pub team: Option<String>,
+}
+
+/// One line of a chargeback report
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct ChargebackRow {
+    pub group: String,
+    pub sessions: usize,
+    pub input_tokens: u64,
+    pub output_tokens: u64,
+    pub cost_usd: f64,
+}
+
+/// Chargeback report across sessions
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct ChargebackReport {
+    pub generated_at: DateTime<Utc>,
+    pub group_by: ChargebackGroupBy,
+    pub start: Option<DateTime<Utc>>,
+    pub end: Option<DateTime<Utc>>,
+    pub rows: Vec<ChargebackRow>,
+    pub total_cost_usd: f64,
+}
+
+impl ChargebackReport {
+    /// Render the report in the requested format
+    pub fn export(&self, format: ReportFormat) -> Result<String, ObservabilityError> {
+        match format {
+            ReportFormat::Json => {
+                serde_json::to_string_pretty(self).map_err(ObservabilityError::SerializationError)
+            }
+            ReportFormat::Csv => Ok(self.to_csv()),
+            ReportFormat::Markdown => Ok(self.to_markdown()),
+        }
+    }
+
+    fn to_csv(&self) -> String {
+        let mut csv = String::from("group,sessions,input_tokens,output_tokens,cost_usd\n");
+        for row in &self.rows {
+            csv.push_str(&format!(
+                "{},{},{},{},{:.6}\n",
+                csv_field(&row.group),
+                row.sessions,
+                row.input_tokens,
+                row.output_tokens,
+                row.cost_usd
+            ));
+        }
+        csv
+    }
+
+    fn to_markdown(&self) -> String {
+        let mut md = String::from("# Chargeback Report\n\n");
+        md.push_str(&format!(
+            "Generated: {}\n\n",
+            self.generated_at.to_rfc3339()
+        ));
+        md.push_str("| Group | Sessions | Input | Output | Cost |\n");
+        md.push_str("|-------|----------|-------|--------|------|\n");
+        for row in &self.rows {
+            md.push_str(&format!(
+                "| {} | {} | {} | {} | ${:.4} |\n",
+                row.group, row.sessions, row.input_tokens, row.output_tokens, row.cost_usd
+            ));
+        }
+        md.push_str(&format!("\n**Total:** ${:.4}\n", self.total_cost_usd));
+        md
+    }
+}
+
+fn csv_field(value: &str) -> String {
+    if value.contains([',', '"', '\n']) {
+        format!("\"{}\"", value.replace('"', "\"\""))
+    } else {
+        value.to_string()
+    }
+}
+
+/// Start of the calendar month (UTC) containing `now`
+pub fn month_start(now: DateTime<Utc>) -> DateTime<Utc> {
+    Utc.with_ymd_and_hms(now.year(), now.month(), 1, 0, 0, 0)
+        .single()
+        .unwrap_or(now)
+}
+
+/// Start of the calendar month (UTC) after the one containing `now`
+pub(crate) fn next_month_start(now: DateTime<Utc>) -> DateTime<Utc> {
+    let (year, month) = if now.month() == 12 {
+        (now.year() + 1, 1)
+    } else {
+        (now.year(), now.month() + 1)
+    };
+    Utc.with_ymd_and_hms(year, month, 1, 0, 0, 0)
+        .single()
+        .unwrap_or(now)
+}
+
+/// Load all team quotas from config
+pub fn load_team_quotas() -> HashMap<String, TeamQuota> {
+    Config::global()
+        .get_param(TEAM_QUOTAS_CONFIG_KEY)
+        .unwrap_or_default()
+}
+
+/// Persist a team's quota to config
+pub fn save_team_quota(team: &str, quota: TeamQuota) -> Result<(), ObservabilityError> {
+    let mut quotas = load_team_quotas();
+    quotas.insert(team.to_string(), quota);
+    Config::global()
+        .set_param(TEAM_QUOTAS_CONFIG_KEY, quotas)
+        .map_err(|e| ObservabilityError::config(e.to_string()))
+}
+
+/// Approve a team's overage until the end of the current month
+pub fn approve_team_overage(
+    team: &str,
+    now: DateTime<Utc>,
+) -> Result<TeamQuota, ObservabilityError> {
+    let mut quota = load_team_quotas().remove(team).ok_or_else(|| {
+        ObservabilityError::config(format!("No quota configured for team '{}'", team))
+    })?;
+    quota.approved_until = Some(next_month_start(now));
+    save_team_quota(team, quota.clone())?;
+    Ok(quota)
+}
+
+/// Records one reply's usage in the session's extension data, and adds it
+/// to the cached month total of the session's team.
+pub fn record_usage(
+    session_id: &str,
+    extension_data: &mut ExtensionData,
+    provider: &str,
+    model: &str,
+    usage: &TokenUsage,
+    now: DateTime<Utc>,
+) -> anyhow::Result<()> {
+    let mut daily = SessionDailyUsage::from_extension_data(extension_data).unwrap_or_default();
+    daily.add(
+        now.date_naive(),
+        provider,
+        model,
+        usage.input_tokens,
+        usage.output_tokens,
+    );
+    daily.to_extension_data(extension_data)?;
+
+    let Some(assignment) = TeamAssignment::from_extension_data(extension_data) else {
+        return Ok(());
+    };
+    let mut cache = TEAM_USAGE_CACHE.lock().unwrap_or_else(|e| e.into_inner());
+    if let Some(cached) = cache.get_mut(&assignment.team) {
+        if cached.usage.period_start == month_start(now) {
+            if cached.session_ids.insert(session_id.to_string()) {
+                cached.usage.sessions += 1;
+            }
+            cached.usage.total_tokens += usage.total_tokens();
+            cached.usage.cost_usd +=
+                CostTracker::new().calculate_provider_cost(usage, provider, model);
+        }
+    }
+    Ok(())
+}
+
+/// The current month's usage of `team`, if a fresh total is cached.
+pub fn cached_team_usage(team: &str, now: DateTime<Utc>) -> Option<TeamUsage> {
+    let cache = TEAM_USAGE_CACHE.lock().unwrap_or_else(|e| e.into_inner());
+    cache
+        .get(team)
+        .filter(|cached| {
+            cached.usage.period_start == month_start(now)
+                && now - cached.loaded_at < chrono::Duration::minutes(TEAM_USAGE_CACHE_MINUTES)
+        })
+        .map(|cached| cached.usage.clone())
+}
+
+/// Computes the current month's usage of `team` from `records` and caches it
+/// for [`cached_team_usage`].
+pub fn cache_team_usage(
+    records: &[SessionUsageRecord],
+    team: &str,
+    now: DateTime<Utc>,
+) -> TeamUsage {
+    let usage = team_usage(records, team, now);
+    let session_ids = records
+        .iter()
+        .filter(|r| r.team == team && r.updated_at >= usage.period_start)
+        .map(|r| r.session_id.clone())
+        .collect();
+    TEAM_USAGE_CACHE
+        .lock()
+        .unwrap_or_else(|e| e.into_inner())
+        .insert(
+            team.to_string(),
+            CachedTeamUsage {
+                usage: usage.clone(),
+                session_ids,
+                loaded_at: now,
+            },
+        );
+    usage
+}
+
+/// Drops cached team totals, e.g. after a session moves to another team.
+pub fn invalidate_team_usage() {
+    TEAM_USAGE_CACHE
+        .lock()
+        .unwrap_or_else(|e| e.into_inner())
+        .clear();
+}
+
+/// Price every session's usage with the tracker's provider/model pricing.
+///
+/// A session yields one record per day and model it was used with. Usage
+/// from before daily recording, the part of the accumulated totals not
+/// covered by days, is billed to when the session was created.
+pub fn usage_records(sessions: &[Session], tracker: &CostTracker) -> Vec<SessionUsageRecord> {
+    let mut records = Vec::new();
+    for session in sessions {
+        let assignment = TeamAssignment::from_extension_data(&session.extension_data);
+        let label = |value: Option<String>| value.unwrap_or_else(|| UNASSIGNED.to_string());
+        let team = label(assignment.as_ref().map(|a| a.team.clone()));
+        let project = label(assignment.as_ref().and_then(|a| a.project.clone()));
+        let user = label(assignment.as_ref().and_then(|a| a.user.clone()));
+        let record = |provider: String,
+                      model: String,
+                      updated_at: DateTime<Utc>,
+                      input_tokens: u64,
+                      output_tokens: u64| {
+            let cost_usd = tracker.calculate_provider_cost(
+                &TokenUsage::new(input_tokens, output_tokens),
+                &provider,
+                &model,
+            );
+            SessionUsageRecord {
+                session_id: session.id.clone(),
+                team: team.clone(),
+                project: project.clone(),
+                user: user.clone(),
+                provider,
+                model,
+                updated_at,
+                input_tokens,
+                output_tokens,
+                cost_usd,
+            }
+        };
+
+        let daily =
+            SessionDailyUsage::from_extension_data(&session.extension_data).unwrap_or_default();
+        for day in &daily.days {
+            records.push(record(
+                day.provider.clone(),
+                day.model.clone(),
+                day.date.and_time(chrono::NaiveTime::MIN).and_utc(),
+                day.input_tokens,
+                day.output_tokens,
+            ));
+        }
+
+        let accumulated = |tokens: Option<i32>| tokens.unwrap_or(0).max(0) as u64;
+        let input_tokens =
+            accumulated(session.accumulated_input_tokens).saturating_sub(daily.input_tokens());
+        let output_tokens =
+            accumulated(session.accumulated_output_tokens).saturating_sub(daily.output_tokens());
+        if daily.days.is_empty() || input_tokens + output_tokens > 0 {
+            let updated_at = if daily.days.is_empty() {
+                session.updated_at
+            } else {
+                session.created_at
+            };
+            records.push(record(
+                label(session.provider_name.clone()),
+                label(session.model_config.as_ref().map(|m| m.model_name.clone())),
+                updated_at,
+                input_tokens,
+                output_tokens,
+            ));
+        }
+    }
+    records
+}
+
+/// Usage of sessions billed to `team` in the current month
+pub fn team_usage(records: &[SessionUsageRecord], team: &str, now: DateTime<Utc>) -> TeamUsage {
+    let period_start = month_start(now);
+    let in_period = records
+        .iter()
+        .filter(|r| r.team == team && r.updated_at >= period_start);
+
+    let mut usage = TeamUsage {
+        team: team.to_string(),
+        period_start,
+        sessions: 0,
+        total_tokens: 0,
+        cost_usd: 0.0,
+    };
+    let mut sessions = HashSet::new();
+    for record in in_period {
+        sessions.insert(&record.session_id);
+        usage.total_tokens += record.input_tokens + record.output_tokens;
+        usage.cost_usd += record.cost_usd;
+    }
+    usage.sessions = sessions.len();
+    usage
+}
+
+/// Check a team's usage against its quota
+pub fn check_quota(quota: &TeamQuota, usage: &TeamUsage, now: DateTime<Utc>) -> QuotaDecision {
+    let exceeded = if let Some(limit) = quota
+        .monthly_token_limit
+        .filter(|limit| usage.total_tokens >= *limit)
+    {
+        Some(format!(
+            "Team '{}' used {} of {} monthly tokens",
+            usage.team, usage.total_tokens, limit
+        ))
+    } else {
+        quota
+            .monthly_cost_limit_usd
+            .filter(|limit| usage.cost_usd >= *limit)
+            .map(|limit| {
+                format!(
+                    "Team '{}' spent ${:.2} of ${:.2} monthly budget",
+                    usage.team, usage.cost_usd, limit
+                )
+            })
+    };
+
+    let Some(reason) = exceeded else {
+        return QuotaDecision::Allowed;
+    };
+
+    match quota.on_exceeded {
+        QuotaAction::Reject => QuotaDecision::Rejected { reason },
+        QuotaAction::RequireApproval => match quota.approved_until {
+            Some(until) if until > now => QuotaDecision::Allowed,
+            _ => QuotaDecision::RequiresApproval { reason },
+        },
+    }
+}
+
+/// Aggregate usage records into a chargeback report
+pub fn build_chargeback_report(
+    records: &[SessionUsageRecord],
+    query: &ChargebackQuery,
+) -> ChargebackReport {
+    let mut groups: BTreeMap<String, ChargebackRow> = BTreeMap::new();
+    let mut group_sessions: HashSet<(&str, &str)> = HashSet::new();
+
+    let matching = records.iter().filter(|r| {
+        query.start.is_none_or(|start| r.updated_at >= start)
+            && query.end.is_none_or(|end| r.updated_at < end)
+            && query.team.as_ref().is_none_or(|team| &r.team == team)
+    });
+
+    for record in matching {
+        let key = match query.group_by {
+            ChargebackGroupBy::Team => &record.team,
+            ChargebackGroupBy::Project => &record.project,
+            ChargebackGroupBy::User => &record.user,
+            ChargebackGroupBy::Provider => &record.provider,
+            ChargebackGroupBy::Model => &record.model,
+        };
+        let row = groups.entry(key.clone()).or_insert_with(|| ChargebackRow {
+            group: key.clone(),
+            sessions: 0,
+            input_tokens: 0,
+            output_tokens: 0,
+            cost_usd: 0.0,
+        });
+        if group_sessions.insert((key.as_str(), record.session_id.as_str())) {
+            row.sessions += 1;
+        }
+        row.input_tokens += record.input_tokens;
+        row.output_tokens += record.output_tokens;
+        row.cost_usd += record.cost_usd;
+    }
+
+    let rows: Vec<ChargebackRow> = groups.into_values().collect();
+    let total_cost_usd = rows.iter().map(|r| r.cost_usd).sum();
+
+    ChargebackReport {
+        generated_at: Utc::now(),
+        group_by: query.group_by,
+        start: query.start,
+        end: query.end,
+        rows,
+        total_cost_usd,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn record(team: &str, model: &str, tokens: u64, cost: f64) -> SessionUsageRecord {
+        SessionUsageRecord {
+            session_id: format!("{}-{}", team, model),
+            team: team.to_string(),
+            project: UNASSIGNED.to_string(),
+            user: UNASSIGNED.to_string(),
+            provider: "anthropic".to_string(),
+            model: model.to_string(),
+            updated_at: Utc::now(),
+            input_tokens: tokens,
+            output_tokens: 0,
+            cost_usd: cost,
+        }
+    }
+
+    #[test]
+    fn test_usage_records_use_provider_model_pricing() {
+        let session = |id: &str, provider: &str, model: &str| Session {
+            id: id.to_string(),
+            provider_name: Some(provider.to_string()),
+            model_config: Some(crate::model::ModelConfig::new_or_fail(model)),
+            accumulated_input_tokens: Some(1_000),
+            accumulated_output_tokens: Some(1_000),
+            ..Default::default()
+        };
+        let sessions = vec![
+            session("hosted", "openai", "gpt-4o"),
+            session("local", "ollama", "llama3"),
+        ];
+
+        let records = usage_records(&sessions, &CostTracker::new());
+        assert!((records[0].cost_usd - 0.0125).abs() < 1e-9);
+        assert_eq!(records[1].cost_usd, 0.0);
+        assert_eq!(records[1].provider, "ollama");
+    }
+
+    #[test]
+    fn test_quota_rejects_past_token_limit() {
+        let records = vec![record("platform", "gpt-4o", 1_500, 0.5)];
+        let now = Utc::now();
+        let usage = team_usage(&records, "platform", now);
+        let quota = TeamQuota {
+            monthly_token_limit: Some(1_000),
+            ..Default::default()
+        };
+
+        assert!(matches!(
+            check_quota(&quota, &usage, now),
+            QuotaDecision::Rejected { .. }
+        ));
+        assert!(check_quota(&TeamQuota::default(), &usage, now).is_allowed());
+    }
+
+    #[test]
+    fn test_quota_approval_unblocks_until_month_end() {
+        let records = vec![record("platform", "gpt-4o", 10, 25.0)];
+        let now = Utc::now();
+        let usage = team_usage(&records, "platform", now);
+        let mut quota = TeamQuota {
+            monthly_cost_limit_usd: Some(20.0),
+            on_exceeded: QuotaAction::RequireApproval,
+            ..Default::default()
+        };
+
+        assert!(matches!(
+            check_quota(&quota, &usage, now),
+            QuotaDecision::RequiresApproval { .. }
+        ));
+
+        quota.approved_until = Some(next_month_start(now));
+        assert!(check_quota(&quota, &usage, now).is_allowed());
+    }
+
+    #[test]
+    fn test_team_usage_ignores_previous_months() {
+        let mut old = record("platform", "gpt-4o", 5_000, 10.0);
+        old.updated_at = month_start(Utc::now()) - chrono::Duration::days(1);
+        let records = vec![old, record("platform", "gpt-4o", 100, 1.0)];
+
+        let usage = team_usage(&records, "platform", Utc::now());
+        assert_eq!(usage.sessions, 1);
+        assert_eq!(usage.total_tokens, 100);
+    }
+
+    #[test]
+    fn test_long_sessions_are_billed_to_the_month_of_use() {
+        let now = Utc::now();
+        let last_month = month_start(now) - chrono::Duration::days(1);
+        let mut extension_data = ExtensionData::default();
+        TeamAssignment {
+            team: "platform".to_string(),
+            project: None,
+            user: None,
+        }
+        .to_extension_data(&mut extension_data)
+        .unwrap();
+        for (at, tokens) in [(last_month, 5_000), (now, 100), (now, 50)] {
+            let usage = TokenUsage::new(tokens, 0);
+            record_usage("long", &mut extension_data, "openai", "gpt-4o", &usage, at).unwrap();
+        }
+        let session = Session {
+            id: "long".to_string(),
+            extension_data,
+            accumulated_input_tokens: Some(5_150),
+            accumulated_output_tokens: Some(0),
+            updated_at: now,
+            ..Default::default()
+        };
+
+        let records = usage_records(&[session], &CostTracker::new());
+        assert_eq!(records.len(), 2);
+        let usage = team_usage(&records, "platform", now);
+        assert_eq!(usage.sessions, 1);
+        assert_eq!(usage.total_tokens, 150);
+    }
+
+    #[test]
+    fn test_chargeback_report_groups_and_exports_csv() {
+        let records = vec![
+            record("platform", "gpt-4o", 100, 1.0),
+            record("platform", "claude-sonnet-4-20250514", 200, 2.0),
+            record("research", "gpt-4o", 300, 3.0),
+        ];
+
+        let by_team = build_chargeback_report(&records, &ChargebackQuery::default());
+        assert_eq!(by_team.rows.len(), 2);
+        assert_eq!(by_team.rows[0].group, "platform");
+        assert_eq!(by_team.rows[0].sessions, 2);
+        assert!((by_team.total_cost_usd - 6.0).abs() < f64::EPSILON);
+
+        let by_model = build_chargeback_report(
+            &records,
+            &ChargebackQuery {
+                group_by: ChargebackGroupBy::Model,
+                team: Some("platform".to_string()),
+                ..Default::default()
+            },
+        );
+        assert_eq!(by_model.rows.len(), 2);
+
+        let csv = by_team.export(ReportFormat::Csv).unwrap();
+        assert!(csv.starts_with("group,sessions,input_tokens,output_tokens,cost_usd\n"));
+        assert!(csv.contains("research,1,300,0,3.000000"));
+    }
+}
@@ -1,4 +1,6 @@
+use crate::agents::capability_grant::CapabilityGrant;
 use crate::agents::extension_manager_extension::MANAGE_EXTENSIONS_TOOL_NAME_COMPLETE;
+use crate::agents::platform_tools::PLATFORM_INSPECT_SCREEN_TOOL_NAME;
 use crate::config::permission::PermissionLevel;
 use crate::config::{GooseMode, PermissionManager};
 use crate::conversation::message::{Message, ToolRequest};
@@ -7,13 +9,15 @@ use crate::tool_inspection::{InspectionAction, InspectionResult, ToolInspector};
 use anyhow::Result;
 use async_trait::async_trait;
 use std::collections::HashSet;
-use std::sync::Arc;
+use std::sync::{Arc, RwLock};
 
 /// Permission Inspector that handles tool permission checking
 pub struct PermissionInspector {
     readonly_tools: HashSet<String>,
     regular_tools: HashSet<String>,
     pub permission_manager: Arc<PermissionManager>,
+    /// A subagent's grant; tools outside it are denied outright
+    capability_grant: RwLock<Option<CapabilityGrant>>,
 }
 
 impl PermissionInspector {
@@ -26,9 +30,25 @@ impl PermissionInspector {
             readonly_tools,
             regular_tools,
             permission_manager,
+            capability_grant: RwLock::new(None),
         }
     }
 
+    pub fn set_capability_grant(&self, grant: Option<CapabilityGrant>) {
+        *self
+            .capability_grant
+            .write()
+            .unwrap_or_else(|e| e.into_inner()) = grant;
+    }
+
+    fn is_granted(&self, tool_name: &str) -> bool {
+        self.capability_grant
+            .read()
+            .unwrap_or_else(|e| e.into_inner())
+            .as_ref()
+            .is_none_or(|grant| grant.allows_tool(tool_name))
+    }
+
     /// Process inspection results into permission decisions
     /// This method takes all inspection results and converts them into a PermissionCheckResult
     /// that can be used by the agent to determine which tools to approve, deny, or ask for approval
@@ -116,8 +136,22 @@ impl ToolInspector for PermissionInspector {
             if let Ok(tool_call) = &request.tool_call {
                 let tool_name = &tool_call.name;
 
+                let granted = self.is_granted(tool_name);
                 let action = match goose_mode {
                     GooseMode::Chat => continue,
+                    // Not even the user can approve what a subagent wasn't granted
+                    _ if !granted => InspectionAction::Deny,
+                    // Screenshots can expose anything on screen, so they are
+                    // confirmed even in auto mode unless always allowed
+                    _ if tool_name == PLATFORM_INSPECT_SCREEN_TOOL_NAME => {
+                        match permission_manager.get_user_permission(tool_name) {
+                            Some(PermissionLevel::AlwaysAllow) => InspectionAction::Allow,
+                            Some(PermissionLevel::NeverAllow) => InspectionAction::Deny,
+                            _ => InspectionAction::RequireApproval(Some(
+                                "Screen capture requires approval".to_string(),
+                            )),
+                        }
+                    }
                     GooseMode::Auto => InspectionAction::Allow,
                     GooseMode::Approve | GooseMode::SmartApprove => {
                         // 1. Check user-defined permission first
@@ -161,10 +195,15 @@ impl ToolInspector for PermissionInspector {
                             "User permission allows this tool".to_string()
                         }
                     }
+                    InspectionAction::Deny if !granted => {
+                        "Tool not granted to this subagent".to_string()
+                    }
                     InspectionAction::Deny => "User permission denies this tool".to_string(),
                     InspectionAction::RequireApproval(_) => {
                         if tool_name == MANAGE_EXTENSIONS_TOOL_NAME_COMPLETE {
                             "Extension management requires user approval".to_string()
+                        } else if tool_name == PLATFORM_INSPECT_SCREEN_TOOL_NAME {
+                            "Screen capture requires user approval".to_string()
                         } else {
                             "Tool requires user approval".to_string()
                         }
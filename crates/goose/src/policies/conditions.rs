@@ -6,14 +6,17 @@
 //! - Collection conditions (in_list, not_in_list, has_key)
 //! - Temporal conditions (before, after, within_last)
 //! - Logical conditions (and, or, not)
+//! - Expression conditions (CEL-like expressions, see [`super::expression`])
 
 use super::errors::PolicyError;
+use super::expression::{context_document, Expression};
 use super::rule_engine::Event;
 use chrono::{DateTime, Duration, Utc};
 use regex::Regex;
 use serde::{Deserialize, Serialize};
 use serde_json::Value;
 use std::collections::HashMap;
+use std::sync::Arc;
 
 /// Condition types for rule evaluation
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -118,6 +121,12 @@ pub enum Condition {
     /// Logical NOT of a condition
     Not { condition: Box<Condition> },
 
+    // =========================================================================
+    // Expression Conditions
+    // =========================================================================
+    /// CEL-like expression over the event's context document
+    Expression { expr: String },
+
     // =========================================================================
     // Special Conditions
     // =========================================================================
@@ -173,6 +182,8 @@ impl ConditionResult {
 pub struct ConditionEvaluator {
     /// Compiled regex cache
     regex_cache: std::sync::RwLock<HashMap<String, Regex>>,
+    /// Parsed expression cache
+    expression_cache: std::sync::RwLock<HashMap<String, Arc<Expression>>>,
 }
 
 impl ConditionEvaluator {
@@ -180,6 +191,7 @@ impl ConditionEvaluator {
     pub fn new() -> Self {
         Self {
             regex_cache: std::sync::RwLock::new(HashMap::new()),
+            expression_cache: std::sync::RwLock::new(HashMap::new()),
         }
     }
 
@@ -263,6 +275,9 @@ impl ConditionEvaluator {
 
             Condition::Not { condition } => self.eval_not(condition, context).await,
 
+            // Expression conditions
+            Condition::Expression { expr } => self.eval_expression(expr, context),
+
             // Special conditions
             Condition::Always => Ok(ConditionResult::matched("Always condition")),
 
@@ -760,6 +775,27 @@ impl ConditionEvaluator {
         })
     }
 
+    // =========================================================================
+    // Expression condition implementation
+    // =========================================================================
+
+    fn eval_expression(
+        &self,
+        expr: &str,
+        context: &ConditionContext,
+    ) -> Result<ConditionResult, PolicyError> {
+        let expression = self.get_or_parse_expression(expr)?;
+
+        if expression.evaluate(&context_document(&context.event))? {
+            Ok(ConditionResult::matched(format!(
+                "Expression '{}' matched",
+                expr
+            )))
+        } else {
+            Ok(ConditionResult::not_matched())
+        }
+    }
+
     // =========================================================================
     // Helper methods
     // =========================================================================
@@ -883,6 +919,22 @@ impl ConditionEvaluator {
         }
     }
 
+    fn get_or_parse_expression(&self, expr: &str) -> Result<Arc<Expression>, PolicyError> {
+        {
+            let cache = self.expression_cache.read().unwrap();
+            if let Some(expression) = cache.get(expr) {
+                return Ok(expression.clone());
+            }
+        }
+
+        let expression = Arc::new(Expression::parse(expr)?);
+        {
+            let mut cache = self.expression_cache.write().unwrap();
+            cache.insert(expr.to_string(), expression.clone());
+        }
+        Ok(expression)
+    }
+
     fn get_or_compile_regex(&self, pattern: &str) -> Result<Regex, PolicyError> {
         // Check cache first
         {
@@ -0,0 +1,714 @@
+//! Expression Conditions
+//!
+//! A small CEL-like expression language for rules the built-in condition
+//! types cannot express without code changes:
+//!
+//! ```yaml
+//! conditions:
+//!   - type: expression
+//!     expr: 'now.weekday == "friday" && tool_name == "self_update"'
+//!   - type: expression
+//!     expr: 'changed_files.exists(f, f.startsWith("crates/goose-conductor/"))'
+//! ```
+//!
+//! Expressions are evaluated against a context document built from the event:
+//! every metadata and data field at the top level (data wins on conflicts),
+//! `event_type`, `timestamp`, and `now` with `weekday`, `hour`, `minute` and
+//! `date` in local time at the event's timestamp. Callers put the change set,
+//! risk level, cost or target paths in the event data under whatever names
+//! their rules use.
+//!
+//! Supported syntax:
+//! - literals: numbers, `"strings"` or `'strings'`, `true`, `false`, `null`, `[lists]`
+//! - field access: `a.b.c`, `list[0]`, `map["key"]`; missing fields are `null`
+//! - operators: `!`, `-`, `&&`, `||`, `==`, `!=`, `<`, `<=`, `>`, `>=`, `in`
+//! - methods: `startsWith`, `endsWith`, `contains`, `matches` (regex), `size`
+//! - macros: `list.exists(x, expr)`, `list.all(x, expr)`
+//! - functions: `size(x)`
+
+use super::errors::PolicyError;
+use super::rule_engine::{Event, EventType};
+use chrono::{Datelike, Local, Timelike};
+use regex::Regex;
+use serde_json::{Map, Value};
+use std::cmp::Ordering;
+
+/// A parsed expression, reusable across evaluations
+#[derive(Debug, Clone)]
+pub struct Expression {
+    source: String,
+    ast: Expr,
+}
+
+impl Expression {
+    /// Parse an expression, failing on syntax errors
+    pub fn parse(source: &str) -> Result<Self, PolicyError> {
+        let tokens = tokenize(source)?;
+        let mut parser = Parser { tokens, pos: 0 };
+        let ast = parser.parse_or()?;
+        if let Some(token) = parser.peek() {
+            return Err(PolicyError::invalid_condition(format!(
+                "Unexpected {:?} in expression '{}'",
+                token, source
+            )));
+        }
+        Ok(Self {
+            source: source.to_string(),
+            ast,
+        })
+    }
+
+    /// Source text of the expression
+    pub fn source(&self) -> &str {
+        &self.source
+    }
+
+    /// Evaluate against a context document. `null` counts as false; any other
+    /// non-boolean result is an error.
+    pub fn evaluate(&self, document: &Value) -> Result<bool, PolicyError> {
+        let scope = Scope {
+            root: document,
+            bindings: Vec::new(),
+        };
+        let value = eval(&self.ast, &scope)?;
+        truthy(&value).map_err(|_| {
+            PolicyError::evaluation(format!(
+                "Expression '{}' returned {} instead of a boolean",
+                self.source, value
+            ))
+        })
+    }
+}
+
+/// Build the document expressions are evaluated against
+pub fn context_document(event: &Event) -> Value {
+    let mut doc = Map::new();
+    for (key, value) in event.metadata.iter().chain(event.data.iter()) {
+        doc.insert(key.clone(), value.clone());
+    }
+
+    let event_type = match &event.event_type {
+        EventType::Custom(name) => name.clone(),
+        other => serde_json::to_value(other)
+            .ok()
+            .and_then(|v| v.as_str().map(str::to_string))
+            .unwrap_or_default(),
+    };
+    doc.insert("event_type".to_string(), Value::String(event_type));
+    doc.insert(
+        "timestamp".to_string(),
+        Value::String(event.timestamp.to_rfc3339()),
+    );
+
+    let local = event.timestamp.with_timezone(&Local);
+    doc.insert(
+        "now".to_string(),
+        serde_json::json!({
+            "weekday": local.format("%A").to_string().to_lowercase(),
+            "hour": local.hour(),
+            "minute": local.minute(),
+            "day": local.day(),
+            "date": local.format("%Y-%m-%d").to_string(),
+        }),
+    );
+    Value::Object(doc)
+}
+
+// =============================================================================
+// Tokenizer
+// =============================================================================
+
+#[derive(Debug, Clone, PartialEq)]
+enum Token {
+    Number(f64),
+    Str(String),
+    Ident(String),
+    Op(&'static str),
+}
+
+const OPERATORS: &[&str] = &[
+    "&&", "||", "==", "!=", "<=", ">=", "<", ">", "!", "-", "(", ")", "[", "]", ",", ".",
+];
+
+fn tokenize(source: &str) -> Result<Vec<Token>, PolicyError> {
+    let chars: Vec<char> = source.chars().collect();
+    let mut tokens = Vec::new();
+    let mut i = 0;
+    while i < chars.len() {
+        let c = chars[i];
+        if c.is_whitespace() {
+            i += 1;
+        } else if c.is_ascii_digit() {
+            let start = i;
+            while i < chars.len() && (chars[i].is_ascii_digit() || chars[i] == '.') {
+                i += 1;
+            }
+            let text: String = chars[start..i].iter().collect();
+            let number = text.parse::<f64>().map_err(|_| {
+                PolicyError::invalid_condition(format!("Invalid number '{}'", text))
+            })?;
+            tokens.push(Token::Number(number));
+        } else if c == '"' || c == '\'' {
+            let mut text = String::new();
+            i += 1;
+            loop {
+                match chars.get(i) {
+                    None => {
+                        return Err(PolicyError::invalid_condition(format!(
+                            "Unterminated string in expression '{}'",
+                            source
+                        )))
+                    }
+                    Some(&q) if q == c => break,
+                    Some('\\') => {
+                        i += 1;
+                        match chars.get(i) {
+                            Some('n') => text.push('\n'),
+                            Some('t') => text.push('\t'),
+                            Some(&other) => text.push(other),
+                            None => continue,
+                        }
+                    }
+                    Some(&other) => text.push(other),
+                }
+                i += 1;
+            }
+            i += 1;
+            tokens.push(Token::Str(text));
+        } else if c.is_alphabetic() || c == '_' {
+            let start = i;
+            while i < chars.len() && (chars[i].is_alphanumeric() || chars[i] == '_') {
+                i += 1;
+            }
+            tokens.push(Token::Ident(chars[start..i].iter().collect()));
+        } else {
+            let rest: String = chars[i..chars.len().min(i + 2)].iter().collect();
+            let op = OPERATORS
+                .iter()
+                .find(|op| rest.starts_with(**op))
+                .ok_or_else(|| {
+                    PolicyError::invalid_condition(format!(
+                        "Unexpected character '{}' in expression '{}'",
+                        c, source
+                    ))
+                })?;
+            tokens.push(Token::Op(op));
+            i += op.len();
+        }
+    }
+    Ok(tokens)
+}
+
+// =============================================================================
+// Parser
+// =============================================================================
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum CompareOp {
+    Eq,
+    Ne,
+    Lt,
+    Le,
+    Gt,
+    Ge,
+    In,
+}
+
+#[derive(Debug, Clone)]
+enum Expr {
+    Literal(Value),
+    List(Vec<Expr>),
+    Ident(String),
+    Member(Box<Expr>, String),
+    Index(Box<Expr>, Box<Expr>),
+    Call {
+        target: Option<Box<Expr>>,
+        name: String,
+        args: Vec<Expr>,
+    },
+    Not(Box<Expr>),
+    Neg(Box<Expr>),
+    And(Box<Expr>, Box<Expr>),
+    Or(Box<Expr>, Box<Expr>),
+    Compare(Box<Expr>, CompareOp, Box<Expr>),
+}
+
+struct Parser {
+    tokens: Vec<Token>,
+    pos: usize,
+}
+
+impl Parser {
+    fn peek(&self) -> Option<&Token> {
+        self.tokens.get(self.pos)
+    }
+
+    fn advance(&mut self) -> Option<Token> {
+        let token = self.tokens.get(self.pos).cloned();
+        self.pos += 1;
+        token
+    }
+
+    fn eat(&mut self, op: &str) -> bool {
+        if matches!(self.peek(), Some(Token::Op(o)) if *o == op) {
+            self.pos += 1;
+            true
+        } else {
+            false
+        }
+    }
+
+    fn expect(&mut self, op: &str) -> Result<(), PolicyError> {
+        if self.eat(op) {
+            Ok(())
+        } else {
+            Err(PolicyError::invalid_condition(format!(
+                "Expected '{}' but found {:?}",
+                op,
+                self.peek()
+            )))
+        }
+    }
+
+    fn parse_or(&mut self) -> Result<Expr, PolicyError> {
+        let mut left = self.parse_and()?;
+        while self.eat("||") {
+            left = Expr::Or(Box::new(left), Box::new(self.parse_and()?));
+        }
+        Ok(left)
+    }
+
+    fn parse_and(&mut self) -> Result<Expr, PolicyError> {
+        let mut left = self.parse_comparison()?;
+        while self.eat("&&") {
+            left = Expr::And(Box::new(left), Box::new(self.parse_comparison()?));
+        }
+        Ok(left)
+    }
+
+    fn parse_comparison(&mut self) -> Result<Expr, PolicyError> {
+        let left = self.parse_unary()?;
+        let op = match self.peek() {
+            Some(Token::Op("==")) => CompareOp::Eq,
+            Some(Token::Op("!=")) => CompareOp::Ne,
+            Some(Token::Op("<")) => CompareOp::Lt,
+            Some(Token::Op("<=")) => CompareOp::Le,
+            Some(Token::Op(">")) => CompareOp::Gt,
+            Some(Token::Op(">=")) => CompareOp::Ge,
+            Some(Token::Ident(word)) if word == "in" => CompareOp::In,
+            _ => return Ok(left),
+        };
+        self.pos += 1;
+        let right = self.parse_unary()?;
+        Ok(Expr::Compare(Box::new(left), op, Box::new(right)))
+    }
+
+    fn parse_unary(&mut self) -> Result<Expr, PolicyError> {
+        if self.eat("!") {
+            return Ok(Expr::Not(Box::new(self.parse_unary()?)));
+        }
+        if self.eat("-") {
+            return Ok(Expr::Neg(Box::new(self.parse_unary()?)));
+        }
+        self.parse_postfix()
+    }
+
+    fn parse_postfix(&mut self) -> Result<Expr, PolicyError> {
+        let mut expr = self.parse_primary()?;
+        loop {
+            if self.eat(".") {
+                let name = match self.advance() {
+                    Some(Token::Ident(name)) => name,
+                    other => {
+                        return Err(PolicyError::invalid_condition(format!(
+                            "Expected a field or method name after '.', found {:?}",
+                            other
+                        )))
+                    }
+                };
+                if self.eat("(") {
+                    let args = self.parse_args()?;
+                    expr = Expr::Call {
+                        target: Some(Box::new(expr)),
+                        name,
+                        args,
+                    };
+                } else {
+                    expr = Expr::Member(Box::new(expr), name);
+                }
+            } else if self.eat("[") {
+                let index = self.parse_or()?;
+                self.expect("]")?;
+                expr = Expr::Index(Box::new(expr), Box::new(index));
+            } else {
+                return Ok(expr);
+            }
+        }
+    }
+
+    fn parse_args(&mut self) -> Result<Vec<Expr>, PolicyError> {
+        let mut args = Vec::new();
+        if self.eat(")") {
+            return Ok(args);
+        }
+        loop {
+            args.push(self.parse_or()?);
+            if self.eat(")") {
+                return Ok(args);
+            }
+            self.expect(",")?;
+        }
+    }
+
+    fn parse_primary(&mut self) -> Result<Expr, PolicyError> {
+        match self.advance() {
+            Some(Token::Number(n)) => Ok(Expr::Literal(serde_json::json!(n))),
+            Some(Token::Str(s)) => Ok(Expr::Literal(Value::String(s))),
+            Some(Token::Ident(word)) => match word.as_str() {
+                "true" => Ok(Expr::Literal(Value::Bool(true))),
+                "false" => Ok(Expr::Literal(Value::Bool(false))),
+                "null" => Ok(Expr::Literal(Value::Null)),
+                _ if self.eat("(") => Ok(Expr::Call {
+                    target: None,
+                    name: word,
+                    args: self.parse_args()?,
+                }),
+                _ => Ok(Expr::Ident(word)),
+            },
+            Some(Token::Op("(")) => {
+                let expr = self.parse_or()?;
+                self.expect(")")?;
+                Ok(expr)
+            }
+            Some(Token::Op("[")) => {
+                let mut items = Vec::new();
+                if !self.eat("]") {
+                    loop {
+                        items.push(self.parse_or()?);
+                        if self.eat("]") {
+                            break;
+                        }
+                        self.expect(",")?;
+                    }
+                }
+                Ok(Expr::List(items))
+            }
+            other => Err(PolicyError::invalid_condition(format!(
+                "Unexpected {:?} in expression",
+                other
+            ))),
+        }
+    }
+}
+
+// =============================================================================
+// Evaluation
+// =============================================================================
+
+struct Scope<'a> {
+    root: &'a Value,
+    bindings: Vec<(String, Value)>,
+}
+
+impl Scope<'_> {
+    fn lookup(&self, name: &str) -> Value {
+        self.bindings
+            .iter()
+            .rev()
+            .find(|(bound, _)| bound == name)
+            .map(|(_, value)| value.clone())
+            .or_else(|| self.root.get(name).cloned())
+            .unwrap_or(Value::Null)
+    }
+}
+
+fn truthy(value: &Value) -> Result<bool, PolicyError> {
+    match value {
+        Value::Bool(b) => Ok(*b),
+        Value::Null => Ok(false),
+        other => Err(PolicyError::evaluation(format!(
+            "Expected a boolean, got {}",
+            other
+        ))),
+    }
+}
+
+fn eval(expr: &Expr, scope: &Scope) -> Result<Value, PolicyError> {
+    match expr {
+        Expr::Literal(value) => Ok(value.clone()),
+        Expr::List(items) => Ok(Value::Array(
+            items
+                .iter()
+                .map(|item| eval(item, scope))
+                .collect::<Result<_, _>>()?,
+        )),
+        Expr::Ident(name) => Ok(scope.lookup(name)),
+        Expr::Member(target, name) => Ok(eval(target, scope)?
+            .get(name)
+            .cloned()
+            .unwrap_or(Value::Null)),
+        Expr::Index(target, index) => {
+            let target = eval(target, scope)?;
+            let found = match eval(index, scope)? {
+                Value::Number(n) => n.as_u64().and_then(|i| target.get(i as usize)),
+                Value::String(key) => target.get(&key),
+                _ => None,
+            };
+            Ok(found.cloned().unwrap_or(Value::Null))
+        }
+        Expr::Not(inner) => Ok(Value::Bool(!truthy(&eval(inner, scope)?)?)),
+        Expr::Neg(inner) => match eval(inner, scope)?.as_f64() {
+            Some(n) => Ok(serde_json::json!(-n)),
+            None => Err(PolicyError::evaluation("Unary '-' needs a number")),
+        },
+        Expr::And(left, right) => Ok(Value::Bool(
+            truthy(&eval(left, scope)?)? && truthy(&eval(right, scope)?)?,
+        )),
+        Expr::Or(left, right) => Ok(Value::Bool(
+            truthy(&eval(left, scope)?)? || truthy(&eval(right, scope)?)?,
+        )),
+        Expr::Compare(left, op, right) => {
+            let left = eval(left, scope)?;
+            let right = eval(right, scope)?;
+            compare(&left, *op, &right).map(Value::Bool)
+        }
+        Expr::Call { target, name, args } => call(target.as_deref(), name, args, scope),
+    }
+}
+
+fn values_equal(left: &Value, right: &Value) -> bool {
+    match (left.as_f64(), right.as_f64()) {
+        (Some(a), Some(b)) => a == b,
+        _ => left == right,
+    }
+}
+
+fn compare(left: &Value, op: CompareOp, right: &Value) -> Result<bool, PolicyError> {
+    match op {
+        CompareOp::Eq => return Ok(values_equal(left, right)),
+        CompareOp::Ne => return Ok(!values_equal(left, right)),
+        CompareOp::In => {
+            return match (left, right) {
+                (_, Value::Array(items)) => Ok(items.iter().any(|item| values_equal(left, item))),
+                (Value::String(needle), Value::String(haystack)) => {
+                    Ok(haystack.contains(needle.as_str()))
+                }
+                (Value::String(key), Value::Object(map)) => Ok(map.contains_key(key)),
+                (_, Value::Null) => Ok(false),
+                _ => Err(PolicyError::evaluation(format!(
+                    "Cannot check {} in {}",
+                    left, right
+                ))),
+            };
+        }
+        _ => {}
+    }
+
+    let ordering = match (left, right) {
+        (Value::Null, _) | (_, Value::Null) => return Ok(false),
+        (Value::String(a), Value::String(b)) => a.cmp(b),
+        _ => match (left.as_f64(), right.as_f64()) {
+            (Some(a), Some(b)) => a.partial_cmp(&b).unwrap_or(Ordering::Equal),
+            _ => {
+                return Err(PolicyError::evaluation(format!(
+                    "Cannot compare {} with {}",
+                    left, right
+                )))
+            }
+        },
+    };
+    Ok(match op {
+        CompareOp::Lt => ordering == Ordering::Less,
+        CompareOp::Le => ordering != Ordering::Greater,
+        CompareOp::Gt => ordering == Ordering::Greater,
+        CompareOp::Ge => ordering != Ordering::Less,
+        _ => unreachable!("equality and membership handled above"),
+    })
+}
+
+fn size(value: &Value) -> Result<Value, PolicyError> {
+    let len = match value {
+        Value::String(s) => s.chars().count(),
+        Value::Array(items) => items.len(),
+        Value::Object(map) => map.len(),
+        Value::Null => 0,
+        other => {
+            return Err(PolicyError::evaluation(format!(
+                "size() needs a string, list or map, got {}",
+                other
+            )))
+        }
+    };
+    Ok(serde_json::json!(len))
+}
+
+fn call(
+    target: Option<&Expr>,
+    name: &str,
+    args: &[Expr],
+    scope: &Scope,
+) -> Result<Value, PolicyError> {
+    let Some(target) = target else {
+        return match (name, args) {
+            ("size", [arg]) => size(&eval(arg, scope)?),
+            _ => Err(PolicyError::evaluation(format!(
+                "Unknown function {}() with {} argument(s)",
+                name,
+                args.len()
+            ))),
+        };
+    };
+
+    if name == "exists" || name == "all" {
+        let (var, body) = match args {
+            [Expr::Ident(var), body] => (var, body),
+            _ => {
+                return Err(PolicyError::evaluation(format!(
+                    "{}() takes a variable name and an expression",
+                    name
+                )))
+            }
+        };
+        let items = match eval(target, scope)? {
+            Value::Array(items) => items,
+            Value::Null => Vec::new(),
+            other => {
+                return Err(PolicyError::evaluation(format!(
+                    "{}() needs a list, got {}",
+                    name, other
+                )))
+            }
+        };
+        let mut inner = Scope {
+            root: scope.root,
+            bindings: scope.bindings.clone(),
+        };
+        for item in items {
+            inner.bindings.push((var.clone(), item));
+            let matched = truthy(&eval(body, &inner)?)?;
+            inner.bindings.pop();
+            if name == "exists" && matched {
+                return Ok(Value::Bool(true));
+            }
+            if name == "all" && !matched {
+                return Ok(Value::Bool(false));
+            }
+        }
+        return Ok(Value::Bool(name == "all"));
+    }
+
+    let target = eval(target, scope)?;
+    let args: Vec<Value> = args
+        .iter()
+        .map(|arg| eval(arg, scope))
+        .collect::<Result<_, _>>()?;
+    match (name, &target, args.as_slice()) {
+        ("size", _, []) => size(&target),
+        ("contains", Value::Array(items), [needle]) => Ok(Value::Bool(
+            items.iter().any(|item| values_equal(item, needle)),
+        )),
+        (_, Value::Null, _) => Ok(Value::Bool(false)),
+        ("startsWith", Value::String(s), [Value::String(prefix)]) => {
+            Ok(Value::Bool(s.starts_with(prefix.as_str())))
+        }
+        ("endsWith", Value::String(s), [Value::String(suffix)]) => {
+            Ok(Value::Bool(s.ends_with(suffix.as_str())))
+        }
+        ("contains", Value::String(s), [Value::String(needle)]) => {
+            Ok(Value::Bool(s.contains(needle.as_str())))
+        }
+        ("matches", Value::String(s), [Value::String(pattern)]) => {
+            Ok(Value::Bool(Regex::new(pattern)?.is_match(s)))
+        }
+        _ => Err(PolicyError::evaluation(format!(
+            "Unknown method {}() on {} with {} argument(s)",
+            name,
+            target,
+            args.len()
+        ))),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::{TimeZone, Utc};
+    use serde_json::json;
+
+    fn eval_str(expr: &str, doc: &Value) -> bool {
+        Expression::parse(expr).unwrap().evaluate(doc).unwrap()
+    }
+
+    #[test]
+    fn test_operators_and_literals() {
+        let doc = json!({
+            "risk_level": "high",
+            "cost_usd": 12.5,
+            "tool": {"name": "developer__shell"},
+            "tags": ["release", "infra"],
+        });
+
+        assert!(eval_str(r#"risk_level == "high" && cost_usd > 10"#, &doc));
+        assert!(eval_str("cost_usd >= 12.5 && !(cost_usd < 1)", &doc));
+        assert!(eval_str(r#"risk_level in ["high", "critical"]"#, &doc));
+        assert!(eval_str(r#""infra" in tags && tags[0] == 'release'"#, &doc));
+        assert!(eval_str(r#"tool.name.startsWith("developer__")"#, &doc));
+        assert!(eval_str(
+            r#"tool.name.matches("shell$") && size(tags) == 2"#,
+            &doc
+        ));
+        assert!(!eval_str(r#"missing.field == "x" || missing > 3"#, &doc));
+        assert!(!eval_str("missing", &doc));
+        assert!(eval_str("-1 < 0", &doc));
+    }
+
+    #[test]
+    fn test_exists_and_all_macros() {
+        let doc = json!({
+            "changed_files": ["crates/goose/src/lib.rs", "crates/goose-conductor/src/main.rs"],
+        });
+
+        assert!(eval_str(
+            r#"changed_files.exists(f, f.startsWith("crates/goose-conductor/"))"#,
+            &doc
+        ));
+        assert!(!eval_str(
+            r#"changed_files.all(f, f.endsWith(".toml"))"#,
+            &doc
+        ));
+        assert!(!eval_str(r#"nothing.exists(f, f == "x")"#, &doc));
+    }
+
+    #[test]
+    fn test_errors() {
+        assert!(Expression::parse("a ==").is_err());
+        assert!(Expression::parse("a == 'open").is_err());
+        assert!(Expression::parse("a $ b").is_err());
+
+        let doc = json!({"count": 3});
+        let not_bool = Expression::parse("count").unwrap();
+        assert!(not_bool.evaluate(&doc).is_err());
+        let bad_call = Expression::parse("count.startsWith('x')").unwrap();
+        assert!(bad_call.evaluate(&doc).is_err());
+    }
+
+    #[test]
+    fn test_context_document() {
+        // Noon UTC on a Friday stays Friday in every local timezone within ±11h
+        let timestamp = Utc.with_ymd_and_hms(2026, 1, 2, 12, 0, 0).unwrap();
+        let mut event = Event::new(EventType::ToolExecution)
+            .with_data("tool_name", "self_update")
+            .with_metadata("tool_name", "ignored")
+            .with_metadata("session_id", "abc");
+        event.timestamp = timestamp;
+
+        let doc = context_document(&event);
+        assert_eq!(doc["tool_name"], "self_update");
+        assert_eq!(doc["session_id"], "abc");
+        assert_eq!(doc["event_type"], "tool_execution");
+        assert!(eval_str(
+            r#"now.weekday == "friday" && tool_name == "self_update""#,
+            &doc
+        ));
+    }
+}
@@ -2,7 +2,9 @@
 //!
 //! Loads policy files from YAML and supports hot-reload.
 
+use super::conditions::Condition;
 use super::errors::PolicyError;
+use super::expression::Expression;
 use super::rule_engine::RuleSet;
 use notify::{RecommendedWatcher, RecursiveMode, Watcher};
 use std::path::{Path, PathBuf};
@@ -113,6 +115,16 @@ impl PolicyLoader {
                     rule.id, rule_set.name, source
                 )));
             }
+
+            // Parse expressions up front so syntax errors fail the load
+            for condition in &rule.conditions {
+                validate_expressions(condition).map_err(|e| {
+                    PolicyError::config(format!(
+                        "Rule '{}' in rule set '{}' ({:?}): {}",
+                        rule.id, rule_set.name, source, e
+                    ))
+                })?;
+            }
         }
 
         Ok(())
@@ -136,6 +148,17 @@ impl PolicyLoader {
     }
 }
 
+fn validate_expressions(condition: &Condition) -> Result<(), PolicyError> {
+    match condition {
+        Condition::Expression { expr } => Expression::parse(expr).map(|_| ()),
+        Condition::And { conditions } | Condition::Or { conditions } => {
+            conditions.iter().try_for_each(validate_expressions)
+        }
+        Condition::Not { condition } => validate_expressions(condition),
+        _ => Ok(()),
+    }
+}
+
 /// Watches for policy file changes and triggers reloads
 pub struct PolicyWatcher {
     /// Channel for receiving file change events
@@ -264,6 +287,7 @@ impl Default for PolicySchema {
                 "and".to_string(),
                 "or".to_string(),
                 "not".to_string(),
+                "expression".to_string(),
                 "always".to_string(),
                 "never".to_string(),
                 "custom".to_string(),
@@ -482,4 +506,39 @@ rules:
         let err = result.unwrap_err();
         assert!(err.to_string().contains("Duplicate rule ID"));
     }
+
+    #[test]
+    fn test_expression_conditions() {
+        let yaml = r#"
+version: "1.0"
+name: "release-policies"
+rules:
+  - id: "no-friday-self-update"
+    description: "No self-updates on Fridays"
+    event_types: [tool_execution]
+    conditions:
+      - type: expression
+        expr: 'now.weekday == "friday" && tool_name == "self_update"'
+    actions:
+      - type: block
+        reason: "No self-updates on Fridays"
+  - id: "conductor-approval"
+    description: "Conductor changes need approval"
+    event_types: [tool_execution]
+    conditions:
+      - type: expression
+        expr: 'changed_files.exists(f, f.startsWith("crates/goose-conductor/"))'
+    actions:
+      - type: require_approval
+        approvers: ["release-team"]
+"#;
+
+        let loader = PolicyLoader::new(PathBuf::from("policies"));
+        let rule_set = loader.load_from_string(yaml).unwrap();
+        assert_eq!(rule_set.rules.len(), 2);
+
+        let invalid = yaml.replace("&& tool_name", "&& && tool_name");
+        let err = loader.load_from_string(&invalid).unwrap_err();
+        assert!(err.to_string().contains("no-friday-self-update"));
+    }
 }
@@ -5,19 +5,21 @@
 //!
 //! This module provides:
 //! - YAML-based rule definition and loading
-//! - Flexible condition evaluation (string, numeric, temporal, logical)
+//! - Flexible condition evaluation (string, numeric, temporal, logical, expressions)
 //! - Configurable actions (block, warn, notify, require approval)
 //! - Hot-reload support for runtime policy updates
 
 pub mod actions;
 pub mod conditions;
 pub mod errors;
+pub mod expression;
 pub mod loader;
 pub mod rule_engine;
 
 pub use actions::{Action, ActionContext, ActionExecutor, ActionResult};
 pub use conditions::{Condition, ConditionContext, ConditionEvaluator, ConditionResult};
 pub use errors::PolicyError;
+pub use expression::Expression;
 pub use loader::{PolicyLoader, PolicyWatcher};
 pub use rule_engine::{
     Event, EventType, Rule, RuleEngine, RuleEvaluationResult, RuleMatch, RuleSet, Severity,
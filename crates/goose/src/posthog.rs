@@ -2,6 +2,8 @@
 
 use crate::config::paths::Paths;
 use crate::config::{get_enabled_extensions, Config};
+use crate::connectivity;
+use crate::local_analytics::{self, AnalyticsMetric};
 use crate::session::session_manager::CURRENT_SCHEMA_VERSION;
 use crate::session::SessionManager;
 use chrono::{DateTime, Utc};
@@ -50,6 +52,12 @@ pub fn is_telemetry_enabled() -> bool {
     get_telemetry_choice().unwrap_or(false)
 }
 
+/// Whether events can be sent now. Telemetry is cloud-only, so it stands
+/// down while goose is offline; local analytics are still recorded.
+fn can_send() -> bool {
+    is_telemetry_enabled() && !connectivity::is_offline()
+}
+
 // ============================================================================
 // Installation Tracking
 // ============================================================================
@@ -211,7 +219,9 @@ fn get_session_is_resumed() -> bool {
 // ============================================================================
 
 pub fn emit_session_started() {
-    if !is_telemetry_enabled() {
+    local_analytics::record_count(AnalyticsMetric::SessionStarted, get_session_interface());
+
+    if !can_send() {
         return;
     }
 
@@ -240,7 +250,9 @@ pub fn emit_error(error_type: &str, error_message: &str) {
 }
 
 pub fn emit_error_with_context(error_type: &str, context: ErrorContext) {
-    if !is_telemetry_enabled() {
+    local_analytics::record_count(AnalyticsMetric::Error, classify_error(error_type));
+
+    if !can_send() {
         return;
     }
 
@@ -258,7 +270,7 @@ pub fn emit_error_with_context(error_type: &str, context: ErrorContext) {
 }
 
 pub fn emit_custom_slash_command_used() {
-    if !is_telemetry_enabled() {
+    if !can_send() {
         return;
     }
 
@@ -534,11 +546,32 @@ fn sanitize_value(value: serde_json::Value) -> serde_json::Value {
 // ============================================================================
 // Generic Event API (for frontend)
 // ============================================================================
+
+/// Mirrors frontend events into the local analytics store. Only the event name
+/// and coarse labels are kept, never the raw properties.
+fn record_local_event(
+    event_name: &str,
+    properties: &std::collections::HashMap<String, serde_json::Value>,
+) {
+    local_analytics::record_count(AnalyticsMetric::Event, event_name);
+
+    if let Some(serde_json::Value::String(core)) = properties.get("core") {
+        local_analytics::record_count(AnalyticsMetric::CoreUsed, sanitize_string(core));
+    }
+    if event_name == "error_occurred" || event_name == "app_crashed" {
+        if let Some(serde_json::Value::String(error_type)) = properties.get("error_type") {
+            local_analytics::record_count(AnalyticsMetric::Error, classify_error(error_type));
+        }
+    }
+}
+
 pub async fn emit_event(
     event_name: &str,
     mut properties: std::collections::HashMap<String, serde_json::Value>,
 ) -> Result<(), String> {
-    if !is_telemetry_enabled() {
+    record_local_event(event_name, &properties);
+
+    if !can_send() {
         return Ok(());
     }
 
@@ -0,0 +1,531 @@
+//! Data lifecycle: how long each class of stored data is kept, the job that
+//! enforces it, and erasure of everything tied to a session, workspace or
+//! user on request.
+//!
+//! Retention is off for a class unless configured:
+//! - sessions: archived and purged per [`RetentionPolicy`]
+//! - memories: `GOOSE_RETENTION_MEMORIES_DAYS` after they were created, and
+//!   sooner for memories holding PII (see [`crate::memory::classification`])
+//! - audit logs: `GOOSE_RETENTION_AUDIT_LOG_DAYS`, dropped a signed batch at
+//!   a time so the rest of the chain still verifies
+//! - cost records: `GOOSE_RETENTION_COST_RECORDS_DAYS` for the local usage
+//!   rollups
+//! - artifacts: `GOOSE_RETENTION_ARTIFACTS_DAYS` for attachments and the
+//!   exports retention writes before purging a session
+//!
+//! Erasure cannot rewrite the audit log without breaking its chain, and it
+//! does not open database backups; both age out on their own. The deletion
+//! report lists what was kept and why.
+
+use crate::agents::mem0_client::Mem0Client;
+use crate::agents::persistence::{Checkpointer, SqliteCheckpointer};
+use crate::artifacts::ArtifactRegistry;
+use crate::config::Config;
+use crate::local_analytics::LocalAnalyticsStore;
+use crate::security::audit_log;
+use crate::session::retention::RetentionPolicy;
+use crate::session::{SessionManager, SessionType};
+use anyhow::Result;
+use chrono::{DateTime, Duration, Utc};
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+use tracing::{info, warn};
+use utoipa::ToSchema;
+
+pub const MEMORIES_DAYS_KEY: &str = "GOOSE_RETENTION_MEMORIES_DAYS";
+pub const AUDIT_LOG_DAYS_KEY: &str = "GOOSE_RETENTION_AUDIT_LOG_DAYS";
+pub const COST_RECORDS_DAYS_KEY: &str = "GOOSE_RETENTION_COST_RECORDS_DAYS";
+pub const ARTIFACTS_DAYS_KEY: &str = "GOOSE_RETENTION_ARTIFACTS_DAYS";
+
+const ALL_SESSION_TYPES: &[SessionType] = &[
+    SessionType::User,
+    SessionType::Scheduled,
+    SessionType::SubAgent,
+    SessionType::Hidden,
+    SessionType::Terminal,
+];
+
+#[derive(
+    Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize, ToSchema,
+)]
+#[serde(rename_all = "snake_case")]
+pub enum DataClass {
+    Sessions,
+    Memories,
+    AuditLogs,
+    CostRecords,
+    Artifacts,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
+pub struct DataRetention {
+    pub sessions: RetentionPolicy,
+    pub memories_days: Option<u32>,
+    pub audit_log_days: Option<u32>,
+    pub cost_records_days: Option<u32>,
+    pub artifacts_days: Option<u32>,
+}
+
+impl DataRetention {
+    pub fn from_config() -> Self {
+        let config = Config::global();
+        let days = |key: &str| config.get_param::<u32>(key).ok().filter(|days| *days > 0);
+        Self {
+            sessions: RetentionPolicy::from_config(),
+            memories_days: days(MEMORIES_DAYS_KEY),
+            audit_log_days: days(AUDIT_LOG_DAYS_KEY),
+            cost_records_days: days(COST_RECORDS_DAYS_KEY),
+            artifacts_days: days(ARTIFACTS_DAYS_KEY),
+        }
+    }
+
+    pub fn is_enabled(&self) -> bool {
+        self.sessions.is_enabled()
+            || self.memories_days.is_some()
+            || self.audit_log_days.is_some()
+            || self.cost_records_days.is_some()
+            || self.artifacts_days.is_some()
+    }
+}
+
+/// What to erase
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, ToSchema)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum PurgeScope {
+    Session {
+        id: String,
+    },
+    /// Every session whose working directory is inside `path`
+    Workspace {
+        #[schema(value_type = String)]
+        path: PathBuf,
+    },
+    /// Memories attributed to a user, locally and in Mem0
+    User {
+        id: String,
+    },
+}
+
+impl PurgeScope {
+    fn kind(&self) -> &'static str {
+        match self {
+            PurgeScope::Session { .. } => "session",
+            PurgeScope::Workspace { .. } => "workspace",
+            PurgeScope::User { .. } => "user",
+        }
+    }
+
+    /// Text that identifies the scope in free-form records
+    fn needle(&self) -> String {
+        match self {
+            PurgeScope::Session { id } | PurgeScope::User { id } => id.clone(),
+            PurgeScope::Workspace { path } => path.to_string_lossy().into_owned(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, ToSchema)]
+pub struct RetainedData {
+    pub class: DataClass,
+    pub location: String,
+    pub reason: String,
+}
+
+#[derive(Debug, Clone, Serialize, ToSchema)]
+pub struct DeletionReport {
+    /// What was erased; absent for scheduled retention
+    pub scope: Option<PurgeScope>,
+    pub started_at: DateTime<Utc>,
+    pub completed_at: DateTime<Utc>,
+    /// Items deleted per data class
+    pub deleted: BTreeMap<DataClass, usize>,
+    /// Ids of the deleted sessions
+    pub sessions: Vec<String>,
+    /// Data that was kept, and why
+    pub retained: Vec<RetainedData>,
+    /// Steps that failed; the other steps still ran
+    pub errors: Vec<String>,
+}
+
+impl DeletionReport {
+    fn new(scope: Option<PurgeScope>) -> Self {
+        let now = Utc::now();
+        Self {
+            scope,
+            started_at: now,
+            completed_at: now,
+            deleted: BTreeMap::new(),
+            sessions: Vec::new(),
+            retained: Vec::new(),
+            errors: Vec::new(),
+        }
+    }
+
+    fn count(&mut self, class: DataClass, deleted: usize) {
+        if deleted > 0 {
+            *self.deleted.entry(class).or_default() += deleted;
+        }
+    }
+
+    fn record<T>(&mut self, step: &str, result: Result<T>) -> Option<T> {
+        match result {
+            Ok(value) => Some(value),
+            Err(e) => {
+                warn!("Data lifecycle step '{}' failed: {}", step, e);
+                self.errors.push(format!("{}: {}", step, e));
+                None
+            }
+        }
+    }
+
+    fn retain(&mut self, class: DataClass, location: impl Into<String>, reason: &str) {
+        self.retained.push(RetainedData {
+            class,
+            location: location.into(),
+            reason: reason.to_string(),
+        });
+    }
+
+    pub fn total_deleted(&self) -> usize {
+        self.deleted.values().sum()
+    }
+}
+
+fn cutoff(now: DateTime<Utc>, days: u32) -> DateTime<Utc> {
+    now - Duration::days(days as i64)
+}
+
+async fn checkpointer() -> Result<Option<SqliteCheckpointer>> {
+    let path = SqliteCheckpointer::default_path();
+    if !path.exists() {
+        return Ok(None);
+    }
+    Ok(Some(SqliteCheckpointer::new(path).await?))
+}
+
+async fn sessions_in_workspace(manager: &SessionManager, workspace: &Path) -> Result<Vec<String>> {
+    let canonical = workspace.canonicalize().ok();
+    Ok(manager
+        .list_sessions_by_types(ALL_SESSION_TYPES)
+        .await?
+        .into_iter()
+        .filter(|session| {
+            session.working_dir.starts_with(workspace)
+                || canonical
+                    .as_ref()
+                    .is_some_and(|c| session.working_dir.starts_with(c))
+        })
+        .map(|session| session.id)
+        .collect())
+}
+
+/// Deletes a session with its messages, attachments, checkpoints and any
+/// export retention wrote for it.
+async fn erase_session(
+    manager: &SessionManager,
+    checkpointer: Option<&SqliteCheckpointer>,
+    id: &str,
+    report: &mut DeletionReport,
+) {
+    let attachments = manager.attachments().list(id).map_or(0, |a| a.len());
+    if report
+        .record("delete session", manager.delete_session(id).await)
+        .is_none()
+    {
+        return;
+    }
+    report.sessions.push(id.to_string());
+    report.count(DataClass::Sessions, 1);
+    report.count(DataClass::Artifacts, attachments);
+
+    let export = manager.purged_exports_dir().join(format!("{}.json", id));
+    if export.exists()
+        && report
+            .record(
+                "delete session export",
+                std::fs::remove_file(&export).map_err(Into::into),
+            )
+            .is_some()
+    {
+        report.count(DataClass::Artifacts, 1);
+    }
+    if let Some(checkpointer) = checkpointer {
+        report.record("delete checkpoints", checkpointer.delete_thread(id).await);
+    }
+}
+
+/// Erases everything stored for `scope` and reports what was deleted and
+/// what had to be kept. Steps that fail are reported rather than stopping
+/// the rest.
+pub async fn purge(scope: PurgeScope) -> Result<DeletionReport> {
+    let manager = SessionManager::instance();
+    let mut report = DeletionReport::new(Some(scope.clone()));
+
+    let session_ids = match &scope {
+        PurgeScope::Session { id } => match manager.get_session(id, false).await {
+            Ok(_) => vec![id.clone()],
+            Err(_) => Vec::new(),
+        },
+        PurgeScope::Workspace { path } => sessions_in_workspace(&manager, path).await?,
+        PurgeScope::User { .. } => Vec::new(),
+    };
+    let checkpointer = report
+        .record("open checkpoints", checkpointer().await)
+        .flatten();
+    for id in &session_ids {
+        erase_session(&manager, checkpointer.as_ref(), id, &mut report).await;
+    }
+    // Deleted rows stay readable in free pages and the WAL until rewritten
+    if !report.sessions.is_empty() {
+        report.record("compact session database", manager.compact().await);
+        if let Some(checkpointer) = &checkpointer {
+            report.record("compact checkpoints", checkpointer.compact().await);
+        }
+    }
+
+    // Memories can outlive the session they came from
+    let mut owner_sessions = session_ids.clone();
+    if let PurgeScope::Session { id } = &scope {
+        if !owner_sessions.contains(id) {
+            owner_sessions.push(id.clone());
+        }
+    }
+
+    #[cfg(feature = "memory")]
+    {
+        let erased = crate::memory::erase_from_snapshot(|entry| {
+            let metadata = &entry.metadata;
+            let in_erased_session = metadata
+                .session_id
+                .as_ref()
+                .is_some_and(|id| owner_sessions.contains(id));
+            in_erased_session
+                || match &scope {
+                    PurgeScope::Session { .. } => false,
+                    PurgeScope::Workspace { path } => {
+                        metadata.project_id.as_deref() == Some(&*path.to_string_lossy())
+                    }
+                    PurgeScope::User { id } => metadata.user_id.as_ref() == Some(id),
+                }
+        });
+        if let Some(erased) = report.record("erase memories", erased.map_err(Into::into)) {
+            report.count(DataClass::Memories, erased);
+        }
+
+        if let PurgeScope::Workspace { path } = &scope {
+            let forgotten = crate::knowledge::KnowledgeBase::forget_workspace(path);
+            if report.record("delete knowledge index", forgotten) == Some(true) {
+                report.count(DataClass::Artifacts, 1);
+            }
+        }
+    }
+
+    // Mem0 keys memories by session id, or by user for user scopes
+    let mut mem0_users = owner_sessions;
+    if let PurgeScope::User { id } = &scope {
+        mem0_users.push(id.clone());
+    }
+    if !mem0_users.is_empty() {
+        let mut mem0 = Mem0Client::new();
+        if mem0.check_health().await {
+            for user in &mem0_users {
+                report.record("delete Mem0 memories", mem0.delete_memories(user).await);
+            }
+        }
+    }
+
+    if let Some(log) = audit_log::global() {
+        let needle = scope.needle();
+        if let Some(records) = report.record("read audit log", log.records()) {
+            let mentions = records
+                .iter()
+                .filter(|record| record.data.to_string().contains(&needle))
+                .count();
+            if mentions > 0 {
+                report.retain(
+                    DataClass::AuditLogs,
+                    format!("{} audit log entries", mentions),
+                    "audit entries are tamper-evident; they expire with the audit log retention period",
+                );
+            }
+        }
+    }
+    if !report.sessions.is_empty() {
+        if let Some(backups) = report.record("list backups", manager.list_backups()) {
+            for backup in backups {
+                report.retain(
+                    DataClass::Sessions,
+                    backup.display().to_string(),
+                    "database backups are rotated out by session maintenance",
+                );
+            }
+        }
+    }
+
+    report.completed_at = Utc::now();
+    audit_log::record(
+        "privacy_purge",
+        json!({
+            "scope": scope.kind(),
+            "deleted": report.deleted,
+            "errors": report.errors.len(),
+        }),
+    );
+    info!(
+        "Purged {} items for a {} scope",
+        report.total_deleted(),
+        scope.kind()
+    );
+    Ok(report)
+}
+
+/// Deletes whatever has outlived its retention period.
+pub async fn apply_retention(retention: &DataRetention, now: DateTime<Utc>) -> DeletionReport {
+    let manager = SessionManager::instance();
+    let mut report = DeletionReport::new(None);
+
+    if retention.sessions.is_enabled() {
+        let applied = manager.apply_retention(&retention.sessions).await;
+        if let Some(sessions) = report.record("session retention", applied) {
+            report.count(DataClass::Sessions, sessions.purged.len());
+            if !sessions.purged.is_empty() {
+                if let Some(Some(checkpointer)) =
+                    report.record("open checkpoints", checkpointer().await)
+                {
+                    for id in &sessions.purged {
+                        report.record("delete checkpoints", checkpointer.delete_thread(id).await);
+                    }
+                }
+            }
+            report.sessions = sessions.purged;
+        }
+    }
+
+    #[cfg(feature = "memory")]
+    {
+        if let Some(days) = retention.memories_days {
+            let before = cutoff(now, days);
+            let erased = crate::memory::erase_from_snapshot(|entry| entry.created_at < before);
+            if let Some(erased) = report.record("memory retention", erased.map_err(Into::into)) {
+                report.count(DataClass::Memories, erased);
+            }
+        }
+        // PII classes carry their own, usually shorter, retention
+        let erased = crate::memory::erase_from_snapshot(|entry| {
+            crate::memory::classification::is_expired(entry, now)
+        });
+        if let Some(erased) = report.record("memory class retention", erased.map_err(Into::into)) {
+            report.count(DataClass::Memories, erased);
+        }
+    }
+
+    if let Some(days) = retention.audit_log_days {
+        if let Some(log) = audit_log::global() {
+            let pruned = log.prune_before(cutoff(now, days));
+            if let Some(pruned) = report.record("audit log retention", pruned) {
+                report.count(DataClass::AuditLogs, pruned);
+            }
+        }
+    }
+
+    if let Some(days) = retention.cost_records_days {
+        let pruned = LocalAnalyticsStore::global()
+            .prune(cutoff(now, days).date_naive())
+            .await;
+        if let Some(pruned) = report.record("cost record retention", pruned) {
+            report.count(DataClass::CostRecords, pruned as usize);
+        }
+    }
+
+    if let Some(days) = retention.artifacts_days {
+        let before = cutoff(now, days);
+        let attachments = manager.attachments().remove_older_than(before);
+        if let Some(removed) = report.record("attachment retention", attachments) {
+            report.count(DataClass::Artifacts, removed);
+        }
+        let exports = remove_files_older_than(&manager.purged_exports_dir(), before);
+        if let Some(removed) = report.record("export retention", exports) {
+            report.count(DataClass::Artifacts, removed);
+        }
+    }
+
+    report.completed_at = Utc::now();
+    if report.total_deleted() > 0 {
+        info!(
+            "Data retention deleted {} items: {:?}",
+            report.total_deleted(),
+            report.deleted
+        );
+    }
+    report
+}
+
+fn remove_files_older_than(dir: &Path, before: DateTime<Utc>) -> Result<usize> {
+    if !dir.exists() {
+        return Ok(0);
+    }
+    let mut removed = 0;
+    for entry in std::fs::read_dir(dir)? {
+        let entry = entry?;
+        let modified: DateTime<Utc> = entry.metadata()?.modified()?.into();
+        if entry.file_type()?.is_file() && modified < before {
+            std::fs::remove_file(entry.path())?;
+            removed += 1;
+        }
+    }
+    Ok(removed)
+}
+
+/// Applies the configured retention every [`MAINTENANCE_INTERVAL`], and
+/// collects the artifacts whose retention class ran out.
+///
+/// [`MAINTENANCE_INTERVAL`]: crate::session::maintenance::MAINTENANCE_INTERVAL
+pub fn spawn_retention_job() -> tokio::task::JoinHandle<()> {
+    tokio::spawn(async move {
+        let mut interval = tokio::time::interval(crate::session::maintenance::MAINTENANCE_INTERVAL);
+        interval.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Delay);
+        loop {
+            interval.tick().await;
+            // Memories holding PII expire by class even when no retention
+            // period is configured
+            apply_retention(&DataRetention::from_config(), Utc::now()).await;
+            let registry = ArtifactRegistry::persistent();
+            match crate::database::blocking(move || registry.gc(Utc::now())).await {
+                Ok(gc) if gc.removed > 0 || gc.freed_bytes > 0 => info!(
+                    "Artifact GC removed {} artifacts and freed {} bytes",
+                    gc.removed, gc.freed_bytes
+                ),
+                Ok(_) => {}
+                Err(e) => warn!("Artifact GC failed: {}", e),
+            }
+        }
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_purge_scope_wire_format() {
+        let scope: PurgeScope =
+            serde_json::from_value(json!({"kind": "workspace", "path": "/work/app"})).unwrap();
+        assert_eq!(
+            scope,
+            PurgeScope::Workspace {
+                path: PathBuf::from("/work/app")
+            }
+        );
+        assert_eq!(scope.needle(), "/work/app");
+
+        let mut report = DeletionReport::new(Some(scope));
+        report.count(DataClass::Sessions, 2);
+        report.count(DataClass::Memories, 0);
+        report.count(DataClass::Artifacts, 3);
+        let json = serde_json::to_value(&report).unwrap();
+        assert_eq!(json["deleted"], json!({"sessions": 2, "artifacts": 3}));
+        assert_eq!(report.total_deleted(), 5);
+    }
+}
@@ -1,8 +1,9 @@
 use crate::config::paths::Paths;
 use include_dir::{include_dir, Dir};
 use minijinja::{Environment, Error as MiniJinjaError, Value as MJValue};
-use serde::Serialize;
-use std::path::PathBuf;
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+use utoipa::ToSchema;
 
 static CORE_PROMPTS_DIR: Dir = include_dir!("$CARGO_MANIFEST_DIR/src/prompts");
 
@@ -47,8 +48,57 @@ static TEMPLATE_REGISTRY: &[(&str, &str)] = &[
         "plan.md",
         "Prompt used when goose creates step-by-step plans. CLI only",
     ),
+    (
+        "release_notes.md",
+        "Prompt for writing release notes for one audience from a changelog entry",
+    ),
+    (
+        "project_status.md",
+        "Prompt for the hand-off note carried over to the next session in the same project",
+    ),
 ];
 
+/// Workspace-level template overrides, relative to the session working
+/// directory. They take precedence over the user-level overrides.
+pub const WORKSPACE_PROMPTS_DIR: &str = ".goose/prompts";
+
+/// Variables available to system prompt templates. Templates referencing
+/// anything else are rejected when they are loaded.
+pub const SYSTEM_PROMPT_VARIABLES: &[&str] = &[
+    "extensions",
+    "current_date_time",
+    "current_date",
+    "extension_tool_limits",
+    "goose_mode",
+    "is_autonomous",
+    "enable_subagents",
+    "max_extensions",
+    "max_tools",
+    "code_execution_mode",
+    "model",
+    "working_dir",
+    "core",
+];
+
+/// Functions minijinja provides to every template
+const TEMPLATE_GLOBALS: &[&str] = &["range", "dict", "namespace", "debug"];
+
+/// Where a resolved template was loaded from
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum TemplateSource {
+    Workspace,
+    User,
+    BuiltIn,
+}
+
+#[derive(Debug, Clone)]
+pub struct ResolvedTemplate {
+    pub name: String,
+    pub source: TemplateSource,
+    pub content: String,
+}
+
 /// Information about a template including its content and customization status
 #[derive(Debug, Clone, serde::Serialize, serde::Deserialize, utoipa::ToSchema)]
 pub struct Template {
@@ -64,10 +114,112 @@ fn user_prompts_dir() -> PathBuf {
     Paths::config_dir().join("prompts")
 }
 
+fn workspace_prompts_dir(working_dir: &Path) -> PathBuf {
+    working_dir.join(WORKSPACE_PROMPTS_DIR)
+}
+
 fn is_registered(name: &str) -> bool {
     TEMPLATE_REGISTRY.iter().any(|(n, _)| *n == name)
 }
 
+/// Template names are plain file names inside a prompts directory.
+fn is_valid_name(name: &str) -> bool {
+    !name.is_empty() && !name.contains(['/', '\\']) && name != "." && name != ".."
+}
+
+/// Checks that `content` parses and, when `allowed_variables` is given, that
+/// it only references those variables.
+pub fn validate_template(
+    content: &str,
+    allowed_variables: Option<&[&str]>,
+) -> Result<(), MiniJinjaError> {
+    let mut env = Environment::new();
+    env.add_template("template", content)?;
+    let Some(allowed) = allowed_variables else {
+        return Ok(());
+    };
+
+    let mut unknown: Vec<String> = env
+        .get_template("template")?
+        .undeclared_variables(false)
+        .into_iter()
+        .filter(|var| !allowed.contains(&var.as_str()) && !TEMPLATE_GLOBALS.contains(&var.as_str()))
+        .collect();
+    if unknown.is_empty() {
+        return Ok(());
+    }
+    unknown.sort();
+    Err(MiniJinjaError::new(
+        minijinja::ErrorKind::UndefinedError,
+        format!("Unknown template variables: {}", unknown.join(", ")),
+    ))
+}
+
+/// Resolves `name` from the workspace prompts dir, then the user prompts dir,
+/// then the built-in templates. Overrides that fail validation are skipped
+/// with a warning so a broken file never leaves the agent without a prompt.
+/// Names that are not registered only resolve from the override dirs.
+pub fn resolve_template(
+    name: &str,
+    working_dir: Option<&Path>,
+    allowed_variables: Option<&[&str]>,
+) -> Result<ResolvedTemplate, MiniJinjaError> {
+    if !is_valid_name(name) {
+        return Err(MiniJinjaError::new(
+            minijinja::ErrorKind::TemplateNotFound,
+            format!("Invalid template name '{}'", name),
+        ));
+    }
+
+    let overrides = working_dir
+        .map(|dir| (TemplateSource::Workspace, workspace_prompts_dir(dir)))
+        .into_iter()
+        .chain(std::iter::once((TemplateSource::User, user_prompts_dir())));
+    for (source, dir) in overrides {
+        let path = dir.join(name);
+        if !path.exists() {
+            continue;
+        }
+        let content = match std::fs::read_to_string(&path) {
+            Ok(content) => content,
+            Err(e) => {
+                tracing::warn!("Failed to read prompt template {}: {}", path.display(), e);
+                continue;
+            }
+        };
+        match validate_template(&content, allowed_variables) {
+            Ok(()) => {
+                return Ok(ResolvedTemplate {
+                    name: name.to_string(),
+                    source,
+                    content,
+                })
+            }
+            Err(e) => {
+                tracing::warn!("Ignoring invalid prompt template {}: {}", path.display(), e)
+            }
+        }
+    }
+
+    if !is_registered(name) {
+        return Err(MiniJinjaError::new(
+            minijinja::ErrorKind::TemplateNotFound,
+            format!("Template '{}' not found in the prompts directories", name),
+        ));
+    }
+    let file = CORE_PROMPTS_DIR.get_file(name).ok_or_else(|| {
+        MiniJinjaError::new(
+            minijinja::ErrorKind::TemplateNotFound,
+            format!("Built-in template '{}' not found", name),
+        )
+    })?;
+    Ok(ResolvedTemplate {
+        name: name.to_string(),
+        source: TemplateSource::BuiltIn,
+        content: String::from_utf8_lossy(file.contents()).to_string(),
+    })
+}
+
 pub fn render_string<T: Serialize>(
     template_str: &str,
     context: &T,
@@ -90,25 +242,8 @@ pub fn render_template<T: Serialize>(name: &str, context: &T) -> Result<String,
         ));
     }
 
-    let user_path = user_prompts_dir().join(name);
-    let template_str = if user_path.exists() {
-        std::fs::read_to_string(&user_path).map_err(|e| {
-            MiniJinjaError::new(
-                minijinja::ErrorKind::InvalidOperation,
-                format!("Failed to read user template: {}", e),
-            )
-        })?
-    } else {
-        let file = CORE_PROMPTS_DIR.get_file(name).ok_or_else(|| {
-            MiniJinjaError::new(
-                minijinja::ErrorKind::TemplateNotFound,
-                format!("Built-in template '{}' not found", name),
-            )
-        })?;
-        String::from_utf8_lossy(file.contents()).to_string()
-    };
-
-    render_string(&template_str, context)
+    let template = resolve_template(name, None, None)?;
+    render_string(&template.content, context)
 }
 
 pub fn get_template(name: &str) -> Option<Template> {
@@ -144,6 +279,10 @@ pub fn save_template(name: &str, content: &str) -> std::io::Result<()> {
         ));
     }
 
+    let allowed_variables = (name == "system.md").then_some(SYSTEM_PROMPT_VARIABLES);
+    validate_template(content, allowed_variables)
+        .map_err(|e| std::io::Error::new(std::io::ErrorKind::InvalidData, e.to_string()))?;
+
     let prompts_dir = user_prompts_dir();
     std::fs::create_dir_all(&prompts_dir)?;
     let path = prompts_dir.join(name);
@@ -220,6 +359,46 @@ mod tests {
         assert!(!result.unwrap().is_empty());
     }
 
+    #[test]
+    fn test_validate_template() {
+        assert!(validate_template("Hello {{ model }}", Some(SYSTEM_PROMPT_VARIABLES)).is_ok());
+        assert!(validate_template("{% if %}", None).is_err());
+
+        let err = validate_template("{{ modle }} {{ cor }}", Some(SYSTEM_PROMPT_VARIABLES))
+            .unwrap_err()
+            .to_string();
+        assert!(err.contains("cor, modle"), "{}", err);
+
+        let builtin = get_template("system.md").unwrap().default_content;
+        assert!(validate_template(&builtin, Some(SYSTEM_PROMPT_VARIABLES)).is_ok());
+    }
+
+    #[test]
+    fn test_resolve_workspace_template() {
+        let workspace = tempfile::tempdir().unwrap();
+        let prompts_dir = workspace.path().join(WORKSPACE_PROMPTS_DIR);
+        std::fs::create_dir_all(&prompts_dir).unwrap();
+        std::fs::write(prompts_dir.join("reviewer.md"), "Review with {{ model }}").unwrap();
+
+        let template = resolve_template(
+            "reviewer.md",
+            Some(workspace.path()),
+            Some(SYSTEM_PROMPT_VARIABLES),
+        )
+        .unwrap();
+        assert_eq!(template.source, TemplateSource::Workspace);
+        assert_eq!(template.content, "Review with {{ model }}");
+
+        std::fs::write(prompts_dir.join("reviewer.md"), "Review with {{ modle }}").unwrap();
+        assert!(resolve_template(
+            "reviewer.md",
+            Some(workspace.path()),
+            Some(SYSTEM_PROMPT_VARIABLES)
+        )
+        .is_err());
+        assert!(resolve_template("../reviewer.md", Some(workspace.path()), None).is_err());
+    }
+
     #[test]
     fn test_list_templates() {
         let templates = list_templates();
@@ -16,6 +16,7 @@ use super::errors::ProviderError;
 use super::formats::anthropic::{
     create_request, get_usage, response_to_message, response_to_streaming_message,
 };
+use super::key_pool;
 use super::openai_compatible::handle_status_openai_compat;
 use super::openai_compatible::map_http_error_to_provider_error;
 use super::utils::get_model;
@@ -62,7 +63,12 @@ impl AnthropicProvider {
         let model = model.with_fast(ANTHROPIC_DEFAULT_FAST_MODEL.to_string());
 
         let config = crate::config::Config::global();
-        let api_key: String = config.get_secret("ANTHROPIC_API_KEY")?;
+        let api_key: String = match config.get_secret("ANTHROPIC_API_KEY") {
+            Ok(key) => key,
+            // Every request takes a key from the pool instead
+            Err(_) if key_pool::has_keys(ANTHROPIC_PROVIDER_NAME) => String::new(),
+            Err(e) => return Err(e.into()),
+        };
         let host: String = config
             .get_param("ANTHROPIC_HOST")
             .unwrap_or_else(|_| "https://api.anthropic.com".to_string());
@@ -72,8 +78,9 @@ impl AnthropicProvider {
             key: api_key,
         };
 
-        let api_client =
-            ApiClient::new(host, auth)?.with_header("anthropic-version", ANTHROPIC_API_VERSION)?;
+        let api_client = ApiClient::new(host, auth)?
+            .with_header("anthropic-version", ANTHROPIC_API_VERSION)?
+            .with_key_pool(ANTHROPIC_PROVIDER_NAME);
 
         Ok(Self {
             api_client,
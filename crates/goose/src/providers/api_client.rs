@@ -1,8 +1,9 @@
+use super::key_pool::{self, KeyLease};
 use crate::session_context::SESSION_ID_HEADER;
 use anyhow::Result;
 use async_trait::async_trait;
 use reqwest::{
-    header::{HeaderMap, HeaderName, HeaderValue},
+    header::{HeaderMap, HeaderName, HeaderValue, RETRY_AFTER},
     Certificate, Client, Identity, Response, StatusCode,
 };
 use serde_json::Value;
@@ -19,6 +20,8 @@ pub struct ApiClient {
     default_query: Vec<(String, String)>,
     timeout: Duration,
     tls_config: Option<TlsConfig>,
+    /// Provider whose pooled keys requests use when one is available
+    key_pool: Option<String>,
 }
 
 pub enum AuthMethod {
@@ -225,6 +228,7 @@ impl ApiClient {
             default_query: Vec::new(),
             timeout,
             tls_config,
+            key_pool: None,
         })
     }
 
@@ -268,6 +272,13 @@ impl ApiClient {
         Ok(self)
     }
 
+    /// Takes a key from `provider`'s key pool for each request, when one is
+    /// available, in place of the client's own credentials.
+    pub fn with_key_pool(mut self, provider: &str) -> Self {
+        self.key_pool = Some(provider.to_string());
+        self
+    }
+
     pub fn with_query(mut self, params: Vec<(String, String)>) -> Self {
         self.default_query = params;
         self
@@ -372,14 +383,20 @@ impl<'a> ApiRequestBuilder<'a> {
     }
 
     pub async fn response_post(self, payload: &Value) -> Result<Response> {
-        let request = self.send_request(|url, client| client.post(url)).await?;
-        Ok(request.json(payload).send().await?)
+        let lease = self.lease();
+        let request = self
+            .send_request(|url, client| client.post(url), lease.as_ref())
+            .await?;
+        Ok(settle(lease, request.json(payload).send().await?))
     }
 
     // NOLINT(cleartext-logging): transmits multipart form data to configured API server, not logging it
     pub async fn multipart_post(self, form: reqwest::multipart::Form) -> Result<Response> {
-        let request = self.send_request(|url, client| client.post(url)).await?;
-        Ok(request.multipart(form).send().await?)
+        let lease = self.lease();
+        let request = self
+            .send_request(|url, client| client.post(url), lease.as_ref())
+            .await?;
+        Ok(settle(lease, request.multipart(form).send().await?))
     }
 
     pub async fn api_get(self) -> Result<ApiResponse> {
@@ -389,11 +406,23 @@ impl<'a> ApiRequestBuilder<'a> {
 
     // NOLINT(cleartext-logging): sends GET request to configured API server, not logging sensitive data
     pub async fn response_get(self) -> Result<Response> {
-        let request = self.send_request(|url, client| client.get(url)).await?;
-        Ok(request.send().await?)
+        let lease = self.lease();
+        let request = self
+            .send_request(|url, client| client.get(url), lease.as_ref())
+            .await?;
+        Ok(settle(lease, request.send().await?))
+    }
+
+    fn lease(&self) -> Option<KeyLease> {
+        let provider = self.client.key_pool.as_deref()?;
+        key_pool::checkout(provider, self.session_id)
     }
 
-    async fn send_request<F>(&self, request_builder: F) -> Result<reqwest::RequestBuilder>
+    async fn send_request<F>(
+        &self,
+        request_builder: F,
+        lease: Option<&KeyLease>,
+    ) -> Result<reqwest::RequestBuilder>
     where
         F: FnOnce(url::Url, &Client) -> reqwest::RequestBuilder,
     {
@@ -409,6 +438,16 @@ impl<'a> ApiRequestBuilder<'a> {
         let mut request = request_builder(url, &self.client.client);
         request = request.headers(headers);
 
+        // A pooled key goes where the client's own key would
+        if let Some(lease) = lease {
+            return Ok(match &self.client.auth {
+                AuthMethod::ApiKey { header_name, .. } => {
+                    request.header(header_name.as_str(), &lease.secret)
+                }
+                _ => request.header("Authorization", format!("Bearer {}", lease.secret)),
+            });
+        }
+
         request = match &self.client.auth {
             AuthMethod::NoAuth => request,
             AuthMethod::BearerToken(token) => {
@@ -429,6 +468,20 @@ impl<'a> ApiRequestBuilder<'a> {
     }
 }
 
+/// Tells the key pool how the provider answered a pooled key.
+fn settle(lease: Option<KeyLease>, response: Response) -> Response {
+    if let Some(lease) = lease {
+        let retry_after = response
+            .headers()
+            .get(RETRY_AFTER)
+            .and_then(|v| v.to_str().ok())
+            .and_then(|v| v.trim().parse::<u64>().ok())
+            .map(Duration::from_secs);
+        key_pool::report(&lease, response.status(), retry_after);
+    }
+    response
+}
+
 impl fmt::Debug for ApiClient {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         f.debug_struct("ApiClient")
@@ -467,7 +520,7 @@ mod tests {
                 builder = builder.header(key, value).unwrap();
             }
             let request = builder
-                .send_request(|url, client| client.get(url))
+                .send_request(|url, client| client.get(url), None)
                 .await
                 .unwrap();
 
@@ -0,0 +1,219 @@
+//! Learned error rates for provider circuit breakers.
+//!
+//! Each breaker counts calls and failures in one-minute windows. Completed
+//! windows feed an exponentially weighted mean and variance of the
+//! provider's error rate, kept in the state directory so the baseline
+//! survives restarts. The baseline drives two things: the number of
+//! consecutive failures that opens the circuit (few for a provider that
+//! rarely fails, more for a noisy one), and anomaly tripping, which opens
+//! the circuit when the current window's error rate is `K` standard
+//! deviations above normal. After the cool-down, [`Ramp`] lets a growing
+//! share of calls through before the circuit closes again.
+
+use crate::config::paths::Paths;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant};
+
+pub const BASELINE_FILE: &str = "circuit_baselines.json";
+pub const DEFAULT_ANOMALY_K: f64 = 3.0;
+
+const WINDOW: Duration = Duration::from_secs(60);
+/// Calls a window needs before its error rate means anything
+const MIN_WINDOW_CALLS: usize = 5;
+/// Windows observed before the baseline is trusted
+const MIN_BASELINE_WINDOWS: u32 = 10;
+/// Weight of the newest window in the moving average
+const BASELINE_ALPHA: f64 = 0.1;
+/// Floor on the standard deviation, so a provider that never failed is not
+/// tripped by a single error
+const MIN_STDDEV: f64 = 0.05;
+/// A run of failures this unlikely under the baseline opens the circuit
+const FAILURE_RUN_PROBABILITY: f64 = 0.01;
+const MIN_FAILURE_THRESHOLD: usize = 2;
+const MAX_FAILURE_THRESHOLD: usize = 20;
+
+/// Share of calls let through at each half-open stage, in percent
+const RAMP_STAGES: &[u64] = &[25, 50];
+/// Successes that move a half-open circuit to the next stage
+const RAMP_STAGE_SUCCESSES: usize = 2;
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+pub struct ErrorRateBaseline {
+    pub mean: f64,
+    pub variance: f64,
+    pub windows: u32,
+}
+
+impl ErrorRateBaseline {
+    pub fn observe(&mut self, rate: f64) {
+        if self.windows == 0 {
+            self.mean = rate;
+        } else {
+            let delta = rate - self.mean;
+            self.mean += BASELINE_ALPHA * delta;
+            self.variance =
+                (1.0 - BASELINE_ALPHA) * (self.variance + BASELINE_ALPHA * delta * delta);
+        }
+        self.windows = self.windows.saturating_add(1);
+    }
+
+    pub fn is_established(&self) -> bool {
+        self.windows >= MIN_BASELINE_WINDOWS
+    }
+
+    /// Error rate above which a window is anomalous.
+    pub fn anomaly_threshold(&self, k: f64) -> f64 {
+        self.mean + k * self.variance.sqrt().max(MIN_STDDEV)
+    }
+
+    /// Consecutive failures that open the circuit: `configured` until the
+    /// baseline is established, then the shortest run that would happen by
+    /// chance less than 1% of the time at the usual error rate.
+    pub fn failure_threshold(&self, configured: usize) -> usize {
+        if !self.is_established() {
+            return configured;
+        }
+        let rate = self.mean.clamp(0.001, 0.99);
+        let run = (FAILURE_RUN_PROBABILITY.ln() / rate.ln()).ceil() as usize;
+        run.clamp(MIN_FAILURE_THRESHOLD, MAX_FAILURE_THRESHOLD)
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct RateWindow {
+    started: Instant,
+    pub calls: usize,
+    pub failures: usize,
+}
+
+impl Default for RateWindow {
+    fn default() -> Self {
+        Self {
+            started: Instant::now(),
+            calls: 0,
+            failures: 0,
+        }
+    }
+}
+
+impl RateWindow {
+    pub fn record(&mut self, failed: bool) {
+        self.calls += 1;
+        if failed {
+            self.failures += 1;
+        }
+    }
+
+    /// Error rate, once the window has seen enough calls.
+    pub fn rate(&self) -> Option<f64> {
+        (self.calls >= MIN_WINDOW_CALLS).then(|| self.failures as f64 / self.calls as f64)
+    }
+
+    pub fn is_complete(&self) -> bool {
+        self.started.elapsed() >= WINDOW
+    }
+}
+
+/// Gradual reopening of a half-open circuit.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Ramp {
+    stage: usize,
+    successes: usize,
+    seen: u64,
+}
+
+impl Ramp {
+    pub fn percent(&self) -> u64 {
+        RAMP_STAGES[self.stage]
+    }
+
+    /// Whether to let the next call through.
+    pub fn admit(&mut self) -> bool {
+        let admit = self.seen % (100 / self.percent()) == 0;
+        self.seen += 1;
+        admit
+    }
+
+    /// Counts a successful call. Returns true once the last stage is
+    /// complete and the circuit can close.
+    pub fn record_success(&mut self) -> bool {
+        self.successes += 1;
+        if self.successes < RAMP_STAGE_SUCCESSES {
+            return false;
+        }
+        if self.stage + 1 == RAMP_STAGES.len() {
+            return true;
+        }
+        *self = Self {
+            stage: self.stage + 1,
+            ..Self::default()
+        };
+        false
+    }
+}
+
+pub fn baseline_path() -> PathBuf {
+    Paths::in_state_dir(BASELINE_FILE)
+}
+
+fn load_all(path: &Path) -> HashMap<String, ErrorRateBaseline> {
+    std::fs::read_to_string(path)
+        .ok()
+        .and_then(|json| serde_json::from_str(&json).ok())
+        .unwrap_or_default()
+}
+
+pub fn load(path: &Path, name: &str) -> ErrorRateBaseline {
+    load_all(path).remove(name).unwrap_or_default()
+}
+
+pub fn save(path: &Path, name: &str, baseline: ErrorRateBaseline) {
+    let mut baselines = load_all(path);
+    baselines.insert(name.to_string(), baseline);
+    let saved = path
+        .parent()
+        .map_or(Ok(()), std::fs::create_dir_all)
+        .and_then(|_| {
+            let json = serde_json::to_string_pretty(&baselines).map_err(std::io::Error::other)?;
+            std::fs::write(path, json)
+        });
+    if let Err(e) = saved {
+        tracing::warn!("Failed to save error rate baseline for {}: {}", name, e);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_baseline_sets_thresholds() {
+        let mut reliable = ErrorRateBaseline::default();
+        let mut noisy = ErrorRateBaseline::default();
+        for _ in 0..MIN_BASELINE_WINDOWS {
+            assert_eq!(reliable.failure_threshold(5), 5);
+            reliable.observe(0.02);
+            noisy.observe(0.4);
+        }
+        assert_eq!(reliable.failure_threshold(5), 2);
+        assert_eq!(noisy.failure_threshold(5), 6);
+        assert!((reliable.anomaly_threshold(3.0) - 0.17).abs() < 1e-9);
+        assert!(noisy.anomaly_threshold(3.0) < 0.6);
+    }
+
+    #[test]
+    fn test_ramp_lets_more_calls_through_before_closing() {
+        let mut ramp = Ramp::default();
+        let admitted = (0..8).filter(|_| ramp.admit()).count();
+        assert_eq!(admitted, 2);
+        assert!(!ramp.record_success());
+        assert!(!ramp.record_success());
+        assert_eq!(ramp.percent(), 50);
+        let admitted = (0..8).filter(|_| ramp.admit()).count();
+        assert_eq!(admitted, 4);
+        assert!(!ramp.record_success());
+        assert!(ramp.record_success());
+    }
+}
@@ -32,7 +32,46 @@ pub enum ProviderError {
     NotImplemented(String),
 }
 
+/// Class of a provider failure. The server's error codes and the results of
+/// headless runs both come from it, so clients see the same codes.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ProviderErrorKind {
+    AuthFailed,
+    RateLimited,
+    ContextLengthExceeded,
+    RequestFailed,
+    Unavailable,
+    Unsupported,
+}
+
+impl ProviderErrorKind {
+    /// Stable, machine-readable code, e.g. `provider_rate_limited`
+    pub fn code(self) -> &'static str {
+        match self {
+            Self::AuthFailed => "provider_auth_failed",
+            Self::RateLimited => "provider_rate_limited",
+            Self::ContextLengthExceeded => "provider_context_length_exceeded",
+            Self::RequestFailed => "provider_request_failed",
+            Self::Unavailable => "provider_unavailable",
+            Self::Unsupported => "provider_unsupported",
+        }
+    }
+}
+
 impl ProviderError {
+    pub fn kind(&self) -> ProviderErrorKind {
+        match self {
+            ProviderError::Authentication(_) => ProviderErrorKind::AuthFailed,
+            ProviderError::ContextLengthExceeded(_) => ProviderErrorKind::ContextLengthExceeded,
+            ProviderError::RateLimitExceeded { .. } => ProviderErrorKind::RateLimited,
+            ProviderError::ServerError(_) => ProviderErrorKind::Unavailable,
+            ProviderError::NotImplemented(_) => ProviderErrorKind::Unsupported,
+            ProviderError::RequestFailed(_)
+            | ProviderError::ExecutionError(_)
+            | ProviderError::UsageError(_) => ProviderErrorKind::RequestFailed,
+        }
+    }
+
     pub fn telemetry_type(&self) -> &'static str {
         match self {
             ProviderError::Authentication(_) => "auth",
@@ -21,8 +21,10 @@ use super::{
     openai::OpenAiProvider,
     openrouter::OpenRouterProvider,
     provider_registry::ProviderRegistry,
+    resilience::{ResilienceConfig, ResilientProvider},
     sagemaker_tgi::SageMakerTgiProvider,
     snowflake::SnowflakeProvider,
+    testprovider::TestProvider,
     tetrate::TetrateProvider,
     venice::VeniceProvider,
     xai::XaiProvider,
@@ -114,13 +116,62 @@ async fn get_from_registry(name: &str) -> Result<ProviderEntry> {
 pub async fn create(name: &str, model: ModelConfig) -> Result<Arc<dyn Provider>> {
     let config = crate::config::Config::global();
 
-    if let Ok(lead_model_name) = config.get_param::<String>("GOOSE_LEAD_MODEL") {
+    if let Ok(path) = config.get_param::<String>("GOOSE_PROVIDER_REPLAY") {
+        tracing::info!("Replaying provider responses from {}", path);
+        let replay = TestProvider::new_replaying(path)?.with_model_config(model);
+        return Ok(Arc::new(replay));
+    }
+
+    let provider = if let Ok(lead_model_name) = config.get_param::<String>("GOOSE_LEAD_MODEL") {
         tracing::info!("Creating lead/worker provider from environment variables");
-        return create_lead_worker_from_env(name, &model, &lead_model_name).await;
+        create_lead_worker_from_env(name, &model, &lead_model_name).await?
+    } else {
+        let constructor = get_from_registry(name).await?.constructor.clone();
+        constructor(model.clone()).await?
+    };
+
+    let provider = with_resilience(name, provider).await?;
+
+    if let Ok(path) = config.get_param::<String>("GOOSE_PROVIDER_RECORD") {
+        tracing::info!("Recording provider responses to {}", path);
+        let recording = TestProvider::new_recording(provider, path).with_model_config(model);
+        return Ok(Arc::new(recording));
+    }
+
+    Ok(provider)
+}
+
+/// Adds timeouts and circuit breaking, failing over to `GOOSE_FALLBACK_PROVIDER`
+/// (with `GOOSE_FALLBACK_MODEL`) while the primary provider is unavailable and
+/// switching to `GOOSE_OFFLINE_PROVIDER` (with `GOOSE_OFFLINE_MODEL`) while
+/// there is no network.
+async fn with_resilience(name: &str, provider: Arc<dyn Provider>) -> Result<Arc<dyn Provider>> {
+    let config = crate::config::Config::global();
+    let mut resilient = ResilientProvider::new(provider, ResilienceConfig::from_config());
+
+    if let Ok(fallback_name) = config.get_param::<String>("GOOSE_FALLBACK_PROVIDER") {
+        if fallback_name != name {
+            let entry = get_from_registry(&fallback_name).await?;
+            let model = match config.get_param::<String>("GOOSE_FALLBACK_MODEL") {
+                Ok(model_name) => ModelConfig::new(&model_name)?,
+                Err(_) => ModelConfig::new(entry.default_model())?,
+            };
+            resilient = resilient.with_fallback(entry.constructor.clone(), model);
+        }
+    }
+
+    if let Ok(offline_name) = config.get_param::<String>("GOOSE_OFFLINE_PROVIDER") {
+        if offline_name != name {
+            let entry = get_from_registry(&offline_name).await?;
+            let model = match config.get_param::<String>("GOOSE_OFFLINE_MODEL") {
+                Ok(model_name) => ModelConfig::new(&model_name)?,
+                Err(_) => ModelConfig::new(entry.default_model())?,
+            };
+            resilient = resilient.with_offline_provider(entry.constructor.clone(), model);
+        }
     }
 
-    let constructor = get_from_registry(name).await?.constructor.clone();
-    constructor(model).await
+    Ok(Arc::new(resilient))
 }
 
 pub async fn create_with_default_model(name: impl AsRef<str>) -> Result<Arc<dyn Provider>> {
@@ -0,0 +1,476 @@
+//! Pools of provider API keys shared by the server's users.
+//!
+//! Administrators register several keys per provider in
+//! `GOOSE_PROVIDER_KEYS`, with the secrets themselves kept in the secret
+//! store. A key can be limited to a team or a user; keys without an owner
+//! are shared by everyone. Providers built with
+//! [`ApiClient::with_key_pool`](super::api_client::ApiClient::with_key_pool)
+//! take a key from the pool for every request, picking among the keys the
+//! session's owner may use that are under their rate limit and daily quota
+//! and not cooling down. `GOOSE_PROVIDER_KEY_SELECTION` picks per provider
+//! between taking turns (`round_robin`, the default) and taking the key with
+//! the most headroom left (`quota`):
+//!
+//! ```yaml
+//! GOOSE_PROVIDER_KEY_SELECTION:
+//!   openai: quota
+//! ```
+//!
+//! A key the provider answers with 429 cools down for as long as the
+//! response's `Retry-After` asks, or [`DEFAULT_COOLDOWN`]. When no pooled key
+//! is available the provider's own configured key is used. Usage is counted
+//! in memory and starts over when the server restarts.
+
+use crate::config::Config;
+use anyhow::{anyhow, Result};
+use reqwest::StatusCode;
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, VecDeque};
+use std::sync::{LazyLock, Mutex};
+use std::time::{Duration, Instant};
+use tracing::warn;
+use utoipa::ToSchema;
+
+pub const KEYS_KEY: &str = "GOOSE_PROVIDER_KEYS";
+pub const SELECTION_KEY: &str = "GOOSE_PROVIDER_KEY_SELECTION";
+/// How long a rate limited key rests when the provider doesn't say
+pub const DEFAULT_COOLDOWN: Duration = Duration::from_secs(60);
+
+const SECRET_PREFIX: &str = "GOOSE_PROVIDER_KEY_";
+const MINUTE: Duration = Duration::from_secs(60);
+const DAY: Duration = Duration::from_secs(24 * 60 * 60);
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
+pub struct ProviderKey {
+    pub id: String,
+    pub provider: String,
+    #[serde(default)]
+    pub label: Option<String>,
+    /// Team allowed to use the key; anyone when unset
+    #[serde(default)]
+    pub team: Option<String>,
+    /// User allowed to use the key; anyone when unset
+    #[serde(default)]
+    pub user: Option<String>,
+    /// The provider's request rate limit for the key
+    #[serde(default)]
+    pub requests_per_minute: Option<u32>,
+    /// Requests allowed in any 24 hours
+    #[serde(default)]
+    pub daily_requests: Option<u64>,
+}
+
+#[derive(Debug, Deserialize, ToSchema)]
+pub struct NewProviderKey {
+    pub provider: String,
+    /// The API key itself; never returned
+    pub key: String,
+    #[serde(default)]
+    pub label: Option<String>,
+    #[serde(default)]
+    pub team: Option<String>,
+    #[serde(default)]
+    pub user: Option<String>,
+    #[serde(default)]
+    pub requests_per_minute: Option<u32>,
+    #[serde(default)]
+    pub daily_requests: Option<u64>,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum KeySelection {
+    /// Take turns
+    #[default]
+    RoundRobin,
+    /// Take the key with the most of its limits left
+    Quota,
+}
+
+#[derive(Debug, Clone, Serialize, ToSchema)]
+pub struct KeyStatus {
+    pub key: ProviderKey,
+    pub requests_last_minute: usize,
+    pub requests_today: u64,
+    /// Requests since the server started
+    pub total_requests: u64,
+    /// 429 responses since the server started
+    pub rate_limited: u64,
+    /// Seconds until the key is used again, while it cools down
+    pub cooling_down_secs: Option<u64>,
+}
+
+/// A key taken for one request, to be reported back with [`report`].
+pub struct KeyLease {
+    pub key_id: String,
+    pub secret: String,
+}
+
+#[derive(Debug, Clone, Default)]
+struct Owner {
+    team: Option<String>,
+    user: Option<String>,
+}
+
+struct PooledKey {
+    key: ProviderKey,
+    secret: String,
+    minute: VecDeque<Instant>,
+    day_started: Option<Instant>,
+    day_requests: u64,
+    total_requests: u64,
+    rate_limited: u64,
+    cooling_until: Option<Instant>,
+}
+
+impl PooledKey {
+    fn new(key: ProviderKey, secret: String) -> Self {
+        Self {
+            key,
+            secret,
+            minute: VecDeque::new(),
+            day_started: None,
+            day_requests: 0,
+            total_requests: 0,
+            rate_limited: 0,
+            cooling_until: None,
+        }
+    }
+
+    fn forget_old(&mut self, now: Instant) {
+        while self
+            .minute
+            .front()
+            .is_some_and(|t| now.duration_since(*t) >= MINUTE)
+        {
+            self.minute.pop_front();
+        }
+        if self
+            .day_started
+            .is_some_and(|t| now.duration_since(t) >= DAY)
+        {
+            self.day_started = None;
+            self.day_requests = 0;
+        }
+        if self.cooling_until.is_some_and(|t| t <= now) {
+            self.cooling_until = None;
+        }
+    }
+
+    fn usable_by(&self, owner: &Owner) -> bool {
+        let allowed = |required: &Option<String>, actual: &Option<String>| {
+            required.is_none() || required == actual
+        };
+        allowed(&self.key.team, &owner.team) && allowed(&self.key.user, &owner.user)
+    }
+
+    /// The smallest fraction left of the key's limits, or `None` when one
+    /// of them is used up.
+    fn headroom(&self) -> Option<f64> {
+        if self.cooling_until.is_some() {
+            return None;
+        }
+        let left = |used: f64, limit: Option<f64>| match limit {
+            Some(limit) if used >= limit => None,
+            Some(limit) => Some(1.0 - used / limit),
+            None => Some(1.0),
+        };
+        let minute = left(
+            self.minute.len() as f64,
+            self.key.requests_per_minute.map(f64::from),
+        )?;
+        let day = left(
+            self.day_requests as f64,
+            self.key.daily_requests.map(|d| d as f64),
+        )?;
+        Some(minute.min(day))
+    }
+
+    fn record_request(&mut self, now: Instant) {
+        self.minute.push_back(now);
+        self.day_started.get_or_insert(now);
+        self.day_requests += 1;
+        self.total_requests += 1;
+    }
+
+    fn status(&self, now: Instant) -> KeyStatus {
+        KeyStatus {
+            key: self.key.clone(),
+            requests_last_minute: self.minute.len(),
+            requests_today: self.day_requests,
+            total_requests: self.total_requests,
+            rate_limited: self.rate_limited,
+            cooling_down_secs: self
+                .cooling_until
+                .map(|t| t.saturating_duration_since(now).as_secs()),
+        }
+    }
+}
+
+#[derive(Default)]
+struct KeyPool {
+    keys: Vec<PooledKey>,
+    /// Where round robin selection resumes, per provider
+    turns: HashMap<String, usize>,
+    owners: HashMap<String, Owner>,
+}
+
+impl KeyPool {
+    fn load() -> Self {
+        let config = Config::global();
+        let keys: Vec<ProviderKey> = config.get_param(KEYS_KEY).unwrap_or_default();
+        let mut pool = Self::default();
+        for key in keys {
+            match config.get_secret::<String>(&secret_name(&key.id)) {
+                Ok(secret) => pool.keys.push(PooledKey::new(key, secret)),
+                Err(e) => warn!("Skipping provider key {} without a secret: {}", key.id, e),
+            }
+        }
+        pool
+    }
+
+    fn checkout(
+        &mut self,
+        provider: &str,
+        session_id: Option<&str>,
+        selection: KeySelection,
+        now: Instant,
+    ) -> Option<KeyLease> {
+        let owner = session_id
+            .and_then(|id| self.owners.get(id).cloned())
+            .unwrap_or_default();
+        let mut candidates = Vec::new();
+        for (index, pooled) in self.keys.iter_mut().enumerate() {
+            if pooled.key.provider != provider || !pooled.usable_by(&owner) {
+                continue;
+            }
+            pooled.forget_old(now);
+            if let Some(headroom) = pooled.headroom() {
+                candidates.push((index, headroom));
+            }
+        }
+
+        let index = match selection {
+            KeySelection::RoundRobin => {
+                let turn = self.turns.entry(provider.to_string()).or_default();
+                let next = candidates
+                    .iter()
+                    .find(|(index, _)| *index >= *turn)
+                    .or(candidates.first())?
+                    .0;
+                *turn = next + 1;
+                next
+            }
+            // The first of the keys with the most headroom
+            KeySelection::Quota => candidates.iter().min_by(|a, b| b.1.total_cmp(&a.1))?.0,
+        };
+        let pooled = &mut self.keys[index];
+        pooled.record_request(now);
+        Some(KeyLease {
+            key_id: pooled.key.id.clone(),
+            secret: pooled.secret.clone(),
+        })
+    }
+
+    fn report(&mut self, key_id: &str, status: StatusCode, retry_after: Option<Duration>) {
+        if status != StatusCode::TOO_MANY_REQUESTS {
+            return;
+        }
+        if let Some(pooled) = self.keys.iter_mut().find(|k| k.key.id == key_id) {
+            pooled.rate_limited += 1;
+            pooled.cooling_until = Some(Instant::now() + retry_after.unwrap_or(DEFAULT_COOLDOWN));
+        }
+    }
+}
+
+static POOL: LazyLock<Mutex<KeyPool>> = LazyLock::new(|| Mutex::new(KeyPool::load()));
+
+fn pool() -> std::sync::MutexGuard<'static, KeyPool> {
+    POOL.lock().unwrap_or_else(|e| e.into_inner())
+}
+
+fn secret_name(key_id: &str) -> String {
+    format!("{}{}", SECRET_PREFIX, key_id)
+}
+
+fn save_keys(pool: &KeyPool) -> Result<()> {
+    let keys: Vec<&ProviderKey> = pool.keys.iter().map(|k| &k.key).collect();
+    Config::global().set_param(KEYS_KEY, keys)?;
+    Ok(())
+}
+
+pub fn selection_for(provider: &str) -> KeySelection {
+    Config::global()
+        .get_param::<HashMap<String, KeySelection>>(SELECTION_KEY)
+        .ok()
+        .and_then(|mut providers| providers.remove(provider))
+        .unwrap_or_default()
+}
+
+pub fn set_selection(provider: &str, selection: KeySelection) -> Result<()> {
+    let config = Config::global();
+    let mut providers: HashMap<String, KeySelection> =
+        config.get_param(SELECTION_KEY).unwrap_or_default();
+    providers.insert(provider.to_string(), selection);
+    config.set_param(SELECTION_KEY, providers)?;
+    Ok(())
+}
+
+pub fn register(new: NewProviderKey) -> Result<ProviderKey> {
+    if new.provider.trim().is_empty() || new.key.trim().is_empty() {
+        return Err(anyhow!("Provider and key are required"));
+    }
+    let key = ProviderKey {
+        id: uuid::Uuid::new_v4().simple().to_string(),
+        provider: new.provider,
+        label: new.label,
+        team: new.team,
+        user: new.user,
+        requests_per_minute: new.requests_per_minute,
+        daily_requests: new.daily_requests,
+    };
+    Config::global().set_secret(&secret_name(&key.id), &new.key)?;
+    let mut pool = pool();
+    pool.keys.push(PooledKey::new(key.clone(), new.key));
+    save_keys(&pool)?;
+    Ok(key)
+}
+
+/// Removes a key and its secret, returning whether it was registered.
+pub fn remove(key_id: &str) -> Result<bool> {
+    let mut pool = pool();
+    let before = pool.keys.len();
+    pool.keys.retain(|k| k.key.id != key_id);
+    if pool.keys.len() == before {
+        return Ok(false);
+    }
+    save_keys(&pool)?;
+    Config::global().delete_secret(&secret_name(key_id))?;
+    Ok(true)
+}
+
+pub fn statuses() -> Vec<KeyStatus> {
+    let now = Instant::now();
+    let mut pool = pool();
+    pool.keys
+        .iter_mut()
+        .map(|k| {
+            k.forget_old(now);
+            k.status(now)
+        })
+        .collect()
+}
+
+pub fn is_empty() -> bool {
+    pool().keys.is_empty()
+}
+
+pub fn has_keys(provider: &str) -> bool {
+    pool().keys.iter().any(|k| k.key.provider == provider)
+}
+
+/// Lets the session use the keys of its team and user.
+pub fn assign_session(session_id: &str, team: Option<&str>, user: Option<&str>) {
+    pool().owners.insert(
+        session_id.to_string(),
+        Owner {
+            team: team.map(str::to_string),
+            user: user.map(str::to_string),
+        },
+    );
+}
+
+/// A key for one request to `provider`, if the pool has one available.
+pub fn checkout(provider: &str, session_id: Option<&str>) -> Option<KeyLease> {
+    let selection = selection_for(provider);
+    pool().checkout(provider, session_id, selection, Instant::now())
+}
+
+/// Records how the provider answered a request made with `lease`.
+pub fn report(lease: &KeyLease, status: StatusCode, retry_after: Option<Duration>) {
+    pool().report(&lease.key_id, status, retry_after);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn key(id: &str, team: Option<&str>, requests_per_minute: Option<u32>) -> PooledKey {
+        PooledKey::new(
+            ProviderKey {
+                id: id.to_string(),
+                provider: "openai".to_string(),
+                label: None,
+                team: team.map(str::to_string),
+                user: None,
+                requests_per_minute,
+                daily_requests: None,
+            },
+            format!("secret-{}", id),
+        )
+    }
+
+    fn take(pool: &mut KeyPool, session: &str, selection: KeySelection) -> Option<String> {
+        pool.checkout("openai", Some(session), selection, Instant::now())
+            .map(|lease| lease.key_id)
+    }
+
+    #[test]
+    fn test_keys_rotate_within_owner_limits_and_cool_down() {
+        let mut pool = KeyPool::default();
+        pool.keys.push(key("shared", None, None));
+        pool.keys.push(key("red", Some("red"), Some(2)));
+        pool.owners.insert(
+            "red-session".to_string(),
+            Owner {
+                team: Some("red".to_string()),
+                user: None,
+            },
+        );
+
+        // Other teams only see the shared key
+        for _ in 0..3 {
+            assert_eq!(
+                take(&mut pool, "blue-session", KeySelection::RoundRobin).as_deref(),
+                Some("shared")
+            );
+        }
+
+        // Red takes turns until its own key reaches its rate limit
+        let taken: Vec<_> = (0..6)
+            .filter_map(|_| take(&mut pool, "red-session", KeySelection::RoundRobin))
+            .collect();
+        assert_eq!(
+            taken,
+            ["red", "shared", "red", "shared", "shared", "shared"]
+        );
+
+        // Quota selection prefers the key with the most headroom
+        let mut pool = KeyPool::default();
+        pool.keys.push(key("small", None, Some(10)));
+        pool.keys.push(key("large", None, Some(100)));
+        assert_eq!(
+            take(&mut pool, "s", KeySelection::Quota).as_deref(),
+            Some("small")
+        );
+        assert_eq!(
+            take(&mut pool, "s", KeySelection::Quota).as_deref(),
+            Some("large")
+        );
+
+        // A rate limited key rests until its cool-down ends
+        pool.report(
+            "large",
+            StatusCode::TOO_MANY_REQUESTS,
+            Some(Duration::from_secs(30)),
+        );
+        assert_eq!(
+            take(&mut pool, "s", KeySelection::Quota).as_deref(),
+            Some("small")
+        );
+        let large = pool.keys.iter().find(|k| k.key.id == "large").unwrap();
+        let status = large.status(Instant::now());
+        assert_eq!(status.rate_limited, 1);
+        assert!(status.cooling_down_secs.is_some());
+    }
+}
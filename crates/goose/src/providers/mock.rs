@@ -0,0 +1,409 @@
+use anyhow::{anyhow, Context, Result};
+use async_trait::async_trait;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::fs;
+use std::path::Path;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use super::base::{Provider, ProviderDef, ProviderMetadata, ProviderUsage, Usage};
+use super::errors::ProviderError;
+use crate::agents::simulated_extension::SimulatedTool;
+use crate::conversation::message::Message;
+use crate::conversation::Conversation;
+use crate::model::ModelConfig;
+use futures::future::BoxFuture;
+use rmcp::model::{CallToolRequestParams, Tool};
+
+/// A scripted conversation for [`MockProvider`]. Scenario files are YAML or
+/// JSON; each completion request consumes the next turn.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct Scenario {
+    #[serde(default)]
+    pub name: String,
+    /// Delay applied to every turn that does not set its own.
+    #[serde(default)]
+    pub latency_ms: u64,
+    #[serde(default)]
+    pub turns: Vec<ScenarioTurn>,
+    /// Fake tools served by a simulated extension alongside the provider.
+    #[serde(default)]
+    pub tools: Vec<SimulatedTool>,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct ScenarioTurn {
+    /// Text the latest message must contain; a mismatch fails the request so
+    /// a scenario that drifted from the code under test is caught early.
+    #[serde(default)]
+    pub expect: Option<String>,
+    #[serde(default)]
+    pub text: Option<String>,
+    #[serde(default)]
+    pub tool_calls: Vec<ScenarioToolCall>,
+    #[serde(default)]
+    pub error: Option<ScenarioError>,
+    #[serde(default)]
+    pub latency_ms: Option<u64>,
+    #[serde(default)]
+    pub input_tokens: Option<i32>,
+    #[serde(default)]
+    pub output_tokens: Option<i32>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ScenarioToolCall {
+    pub name: String,
+    #[serde(default)]
+    pub arguments: Value,
+    #[serde(default)]
+    pub id: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum ScenarioError {
+    Authentication {
+        message: String,
+    },
+    ContextLengthExceeded {
+        message: String,
+    },
+    RateLimit {
+        message: String,
+        #[serde(default)]
+        retry_after_ms: Option<u64>,
+    },
+    Server {
+        message: String,
+    },
+    Request {
+        message: String,
+    },
+    Execution {
+        message: String,
+    },
+}
+
+impl From<&ScenarioError> for ProviderError {
+    fn from(error: &ScenarioError) -> Self {
+        match error {
+            ScenarioError::Authentication { message } => {
+                ProviderError::Authentication(message.clone())
+            }
+            ScenarioError::ContextLengthExceeded { message } => {
+                ProviderError::ContextLengthExceeded(message.clone())
+            }
+            ScenarioError::RateLimit {
+                message,
+                retry_after_ms,
+            } => ProviderError::RateLimitExceeded {
+                details: message.clone(),
+                retry_delay: retry_after_ms.map(Duration::from_millis),
+            },
+            ScenarioError::Server { message } => ProviderError::ServerError(message.clone()),
+            ScenarioError::Request { message } => ProviderError::RequestFailed(message.clone()),
+            ScenarioError::Execution { message } => ProviderError::ExecutionError(message.clone()),
+        }
+    }
+}
+
+impl Scenario {
+    pub fn from_file(path: impl AsRef<Path>) -> Result<Self> {
+        let path = path.as_ref();
+        let content = fs::read_to_string(path)
+            .with_context(|| format!("Failed to read scenario {}", path.display()))?;
+        Self::parse(&content).with_context(|| format!("Invalid scenario {}", path.display()))
+    }
+
+    /// Parses a YAML or JSON scenario.
+    pub fn parse(content: &str) -> Result<Self> {
+        let scenario: Scenario = serde_yaml::from_str(content)?;
+        for (index, turn) in scenario.turns.iter().enumerate() {
+            if turn.text.is_none() && turn.tool_calls.is_empty() && turn.error.is_none() {
+                return Err(anyhow!(
+                    "Turn {} needs a text, tool_calls or error",
+                    index + 1
+                ));
+            }
+        }
+        Ok(scenario)
+    }
+}
+
+/// A request received by [`MockProvider`], kept for assertions.
+#[derive(Debug, Clone)]
+pub struct MockRequest {
+    pub system: String,
+    pub messages: Vec<Message>,
+    pub tools: Vec<Tool>,
+}
+
+/// Provider that plays back a [`Scenario`] instead of calling an LLM.
+pub struct MockProvider {
+    scenario: Scenario,
+    cursor: Arc<Mutex<usize>>,
+    requests: Arc<Mutex<Vec<MockRequest>>>,
+    model_config: ModelConfig,
+}
+
+impl MockProvider {
+    const PROVIDER_NAME: &str = "mock";
+
+    pub fn new(scenario: Scenario) -> Self {
+        Self {
+            scenario,
+            cursor: Arc::new(Mutex::new(0)),
+            requests: Arc::new(Mutex::new(Vec::new())),
+            model_config: ModelConfig::new_or_fail("mock-model"),
+        }
+    }
+
+    pub fn from_file(path: impl AsRef<Path>) -> Result<Self> {
+        Ok(Self::new(Scenario::from_file(path)?))
+    }
+
+    pub fn with_model_config(mut self, model_config: ModelConfig) -> Self {
+        self.model_config = model_config;
+        self
+    }
+
+    pub fn scenario(&self) -> &Scenario {
+        &self.scenario
+    }
+
+    pub fn requests(&self) -> Vec<MockRequest> {
+        self.requests.lock().unwrap().clone()
+    }
+
+    /// Number of turns not yet consumed.
+    pub fn remaining_turns(&self) -> usize {
+        self.scenario
+            .turns
+            .len()
+            .saturating_sub(*self.cursor.lock().unwrap())
+    }
+
+    fn next_turn(&self) -> Result<(usize, ScenarioTurn), ProviderError> {
+        let mut cursor = self.cursor.lock().unwrap();
+        let index = *cursor;
+        let turn = self.scenario.turns.get(index).cloned().ok_or_else(|| {
+            ProviderError::ExecutionError(format!(
+                "Scenario '{}' has no turn {} left to play",
+                self.scenario.name,
+                index + 1
+            ))
+        })?;
+        *cursor += 1;
+        Ok((index, turn))
+    }
+
+    fn build_message(index: usize, turn: &ScenarioTurn) -> Result<Message, ProviderError> {
+        let mut message = Message::assistant();
+        if let Some(text) = &turn.text {
+            message = message.with_text(text);
+        }
+        for (call_index, call) in turn.tool_calls.iter().enumerate() {
+            let arguments = match &call.arguments {
+                Value::Null => None,
+                Value::Object(map) => Some(map.clone()),
+                other => {
+                    return Err(ProviderError::ExecutionError(format!(
+                        "Tool call {} arguments must be an object, got {}",
+                        call.name, other
+                    )))
+                }
+            };
+            let id = call
+                .id
+                .clone()
+                .unwrap_or_else(|| format!("mock_call_{}_{}", index + 1, call_index + 1));
+            message = message.with_tool_request(
+                id,
+                Ok(CallToolRequestParams {
+                    meta: None,
+                    task: None,
+                    name: call.name.clone().into(),
+                    arguments,
+                }),
+            );
+        }
+        Ok(message)
+    }
+}
+
+impl ProviderDef for MockProvider {
+    type Provider = Self;
+
+    fn metadata() -> ProviderMetadata {
+        ProviderMetadata::new(
+            Self::PROVIDER_NAME,
+            "Mock Provider",
+            "Scripted provider that plays back scenario files for tests",
+            "mock-model",
+            vec!["mock-model"],
+            "",
+            vec![],
+        )
+    }
+
+    fn from_env(_model: ModelConfig) -> BoxFuture<'static, Result<Self::Provider>> {
+        Box::pin(async { Err(anyhow!("MockProvider must be constructed from a scenario")) })
+    }
+}
+
+#[async_trait]
+impl Provider for MockProvider {
+    fn get_name(&self) -> &str {
+        Self::PROVIDER_NAME
+    }
+
+    async fn complete_with_model(
+        &self,
+        _session_id: Option<&str>,
+        model_config: &ModelConfig,
+        system: &str,
+        messages: &[Message],
+        tools: &[Tool],
+    ) -> Result<(Message, ProviderUsage), ProviderError> {
+        self.requests.lock().unwrap().push(MockRequest {
+            system: system.to_string(),
+            messages: messages.to_vec(),
+            tools: tools.to_vec(),
+        });
+
+        let (index, turn) = self.next_turn()?;
+
+        let latency = turn.latency_ms.unwrap_or(self.scenario.latency_ms);
+        if latency > 0 {
+            tokio::time::sleep(Duration::from_millis(latency)).await;
+        }
+
+        if let Some(expected) = &turn.expect {
+            let latest = messages
+                .last()
+                .map(|msg| serde_json::to_string(&msg.content).unwrap_or_default())
+                .unwrap_or_default();
+            if !latest.contains(expected.as_str()) {
+                return Err(ProviderError::ExecutionError(format!(
+                    "Scenario '{}' turn {} expected the latest message to contain '{}'",
+                    self.scenario.name,
+                    index + 1,
+                    expected
+                )));
+            }
+        }
+
+        if let Some(error) = &turn.error {
+            return Err(error.into());
+        }
+
+        let message = Self::build_message(index, &turn)?;
+        let usage = Usage::new(
+            Some(turn.input_tokens.unwrap_or(10)),
+            Some(turn.output_tokens.unwrap_or(5)),
+            None,
+        );
+        Ok((
+            message,
+            ProviderUsage::new(model_config.model_name.clone(), usage),
+        ))
+    }
+
+    fn get_model_config(&self) -> ModelConfig {
+        self.model_config.clone()
+    }
+
+    /// Session naming runs in the background, so it must not consume
+    /// scripted turns.
+    async fn generate_session_name(
+        &self,
+        _session_id: &str,
+        _messages: &Conversation,
+    ) -> Result<String, ProviderError> {
+        Ok(if self.scenario.name.is_empty() {
+            "Mock session".to_string()
+        } else {
+            self.scenario.name.clone()
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::conversation::message::MessageContent;
+
+    const SCENARIO: &str = r#"
+name: unit
+turns:
+  - text: "Listing files"
+    tool_calls:
+      - name: sim__ls
+        arguments: { path: "." }
+  - error: { kind: rate_limit, message: "slow down", retry_after_ms: 5 }
+  - expect: "done"
+    text: "All finished"
+"#;
+
+    #[tokio::test]
+    async fn test_plays_turns_in_order() {
+        let provider = MockProvider::new(Scenario::parse(SCENARIO).unwrap());
+
+        let (message, usage) = provider
+            .complete("s", "system", &[Message::user().with_text("go")], &[])
+            .await
+            .unwrap();
+        assert_eq!(message.as_concat_text(), "Listing files");
+        assert!(message.content.iter().any(|c| matches!(
+            c,
+            MessageContent::ToolRequest(req) if req.id == "mock_call_1_1"
+        )));
+        assert_eq!(usage.usage.total_tokens, Some(15));
+
+        let err = provider
+            .complete("s", "system", &[], &[])
+            .await
+            .unwrap_err();
+        assert!(matches!(
+            err,
+            ProviderError::RateLimitExceeded {
+                retry_delay: Some(d),
+                ..
+            } if d == Duration::from_millis(5)
+        ));
+
+        let (message, _) = provider
+            .complete("s", "system", &[Message::user().with_text("done")], &[])
+            .await
+            .unwrap();
+        assert_eq!(message.as_concat_text(), "All finished");
+        assert_eq!(provider.remaining_turns(), 0);
+        assert_eq!(provider.requests().len(), 3);
+
+        assert!(provider.complete("s", "system", &[], &[]).await.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_expectation_mismatch_fails() {
+        let provider = MockProvider::new(Scenario::parse(SCENARIO).unwrap());
+        provider.complete("s", "system", &[], &[]).await.unwrap();
+        provider
+            .complete("s", "system", &[], &[])
+            .await
+            .unwrap_err();
+
+        let err = provider
+            .complete("s", "system", &[Message::user().with_text("nope")], &[])
+            .await
+            .unwrap_err();
+        assert!(err.to_string().contains("expected the latest message"));
+    }
+
+    #[test]
+    fn test_rejects_empty_turn() {
+        let err = Scenario::parse("turns:\n  - latency_ms: 5\n").unwrap_err();
+        assert!(err.to_string().contains("Turn 1"));
+    }
+}
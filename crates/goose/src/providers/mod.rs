@@ -7,6 +7,7 @@ pub mod base;
 pub mod bedrock;
 pub mod canonical;
 pub mod chatgpt_codex;
+pub mod circuit_baseline;
 pub mod claude_code;
 pub mod codex;
 pub mod cursor_agent;
@@ -20,9 +21,11 @@ pub mod gemini_cli;
 pub mod githubcopilot;
 pub mod google;
 mod init;
+pub mod key_pool;
 pub mod lead_worker;
 pub mod litellm;
 pub mod lmstudio;
+pub mod mock;
 pub mod oauth;
 pub mod ollama;
 pub mod openai;
@@ -30,10 +33,12 @@ pub mod openai_compatible;
 pub mod openrouter;
 pub mod provider_registry;
 pub mod provider_test;
+pub mod resilience;
 mod retry;
 pub mod routing;
 pub mod sagemaker_tgi;
 pub mod snowflake;
+pub mod task_routing;
 pub mod testprovider;
 pub mod tetrate;
 pub mod toolshim;
@@ -45,4 +50,4 @@ pub mod xai;
 pub use init::{
     create, create_with_default_model, create_with_named_model, providers, refresh_custom_providers,
 };
-pub use retry::{retry_operation, RetryConfig};
+pub use retry::{retry_operation, should_retry, RetryConfig};
@@ -96,7 +96,8 @@ impl OpenAiProvider {
             _ => AuthMethod::NoAuth,
         };
         let mut api_client =
-            ApiClient::with_timeout(host, auth, std::time::Duration::from_secs(timeout_secs))?;
+            ApiClient::with_timeout(host, auth, std::time::Duration::from_secs(timeout_secs))?
+                .with_key_pool(OPEN_AI_PROVIDER_NAME);
 
         if let Some(org) = &organization {
             api_client = api_client.with_header("OpenAI-Organization", org)?;
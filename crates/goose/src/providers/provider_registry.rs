@@ -17,6 +17,10 @@ pub struct ProviderEntry {
 }
 
 impl ProviderEntry {
+    pub fn default_model(&self) -> &str {
+        &self.metadata.default_model
+    }
+
     pub async fn create_with_default_model(&self) -> Result<Arc<dyn Provider>> {
         let default_model = &self.metadata.default_model;
         let model_config = ModelConfig::new(default_model.as_str())?;
@@ -0,0 +1,861 @@
+//! Timeouts, retries and circuit breaking around provider calls.
+//!
+//! Providers already retry failed HTTP requests (see [`super::retry`]), but a
+//! stalled endpoint never fails and so is never retried. [`ResilientProvider`]
+//! bounds every call with a timeout, retries timed-out calls with jittered
+//! backoff, and keeps a circuit breaker per provider. Once a provider keeps
+//! failing, or fails far more often than it usually does (see
+//! [`super::circuit_baseline`]), the breaker opens, calls fail fast, and
+//! requests go to the configured fallback provider until the cool-down has
+//! passed and the circuit has ramped back up.
+//!
+//! A call that fails because goose has no network at all is handled by the
+//! offline policy in [`crate::connectivity`] instead of failing over.
+//!
+//! Calls to a provider that runs on this machine wait for a permit from
+//! [`crate::resources`] first, held until the response or stream is done.
+
+use super::base::{LeadWorkerProviderTrait, MessageStream, Provider, ProviderUsage};
+use super::circuit_baseline::{self, ErrorRateBaseline, Ramp, RateWindow, DEFAULT_ANOMALY_K};
+use super::errors::ProviderError;
+use super::provider_registry::ProviderConstructor;
+use super::retry::RetryConfig;
+use super::routing::EndpointHealth;
+use crate::config::Config;
+use crate::connectivity;
+use crate::conversation::message::Message;
+use crate::conversation::Conversation;
+use crate::execution::priority::is_background;
+use crate::model::ModelConfig;
+use crate::resources::{self, ResourcePermit};
+use async_trait::async_trait;
+use futures::StreamExt;
+use once_cell::sync::Lazy;
+use rmcp::model::Tool;
+use std::collections::HashMap;
+use std::future::Future;
+use std::path::PathBuf;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+use tokio::sync::OnceCell;
+
+pub const DEFAULT_REQUEST_TIMEOUT_SECS: u64 = 600;
+pub const DEFAULT_TIMEOUT_RETRIES: usize = 2;
+pub const DEFAULT_CIRCUIT_FAILURE_THRESHOLD: usize = 5;
+pub const DEFAULT_CIRCUIT_COOLDOWN_SECS: u64 = 60;
+
+#[derive(Debug, Clone)]
+pub struct ResilienceConfig {
+    /// Bound on a completion, or on the gap between streamed chunks. `None` waits forever.
+    pub request_timeout: Option<Duration>,
+    /// Backoff for retrying timed-out calls; `max_retries` is the retry count
+    pub retry: RetryConfig,
+    /// Consecutive failures that open the circuit
+    pub failure_threshold: usize,
+    /// How long an open circuit fails fast before letting trial calls through
+    pub cooldown: Duration,
+    /// Standard deviations above the usual error rate that open the circuit;
+    /// zero turns anomaly tripping off
+    pub anomaly_k: f64,
+}
+
+impl Default for ResilienceConfig {
+    fn default() -> Self {
+        Self {
+            request_timeout: Some(Duration::from_secs(DEFAULT_REQUEST_TIMEOUT_SECS)),
+            retry: RetryConfig {
+                max_retries: DEFAULT_TIMEOUT_RETRIES,
+                ..RetryConfig::default()
+            },
+            failure_threshold: DEFAULT_CIRCUIT_FAILURE_THRESHOLD,
+            cooldown: Duration::from_secs(DEFAULT_CIRCUIT_COOLDOWN_SECS),
+            anomaly_k: DEFAULT_ANOMALY_K,
+        }
+    }
+}
+
+impl ResilienceConfig {
+    pub fn from_config() -> Self {
+        let config = Config::global();
+        let defaults = Self::default();
+        Self {
+            request_timeout: match config.get_param::<u64>("GOOSE_PROVIDER_TIMEOUT") {
+                Ok(0) => None,
+                Ok(secs) => Some(Duration::from_secs(secs)),
+                Err(_) => defaults.request_timeout,
+            },
+            retry: RetryConfig {
+                max_retries: config
+                    .get_param::<usize>("GOOSE_PROVIDER_TIMEOUT_RETRIES")
+                    .unwrap_or(DEFAULT_TIMEOUT_RETRIES),
+                ..defaults.retry
+            },
+            failure_threshold: config
+                .get_param::<usize>("GOOSE_PROVIDER_CIRCUIT_THRESHOLD")
+                .unwrap_or(DEFAULT_CIRCUIT_FAILURE_THRESHOLD)
+                .max(1),
+            cooldown: config
+                .get_param::<u64>("GOOSE_PROVIDER_CIRCUIT_COOLDOWN")
+                .map(Duration::from_secs)
+                .unwrap_or(defaults.cooldown),
+            anomaly_k: config
+                .get_param::<f64>("GOOSE_PROVIDER_CIRCUIT_ANOMALY_K")
+                .unwrap_or(DEFAULT_ANOMALY_K)
+                .max(0.0),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum CircuitState {
+    Closed { failures: usize },
+    Open { until: Instant },
+    HalfOpen(Ramp),
+}
+
+#[derive(Debug)]
+struct BreakerState {
+    circuit: CircuitState,
+    window: RateWindow,
+    baseline: ErrorRateBaseline,
+}
+
+/// Circuit breaker shared by all instances of a provider. It opens after a
+/// run of consecutive failures, or when the error rate is anomalous for the
+/// provider.
+#[derive(Debug)]
+pub struct CircuitBreaker {
+    name: String,
+    failure_threshold: usize,
+    cooldown: Duration,
+    anomaly_k: f64,
+    /// Where the learned baseline is kept; in memory only when `None`
+    baseline_file: Option<PathBuf>,
+    state: Mutex<BreakerState>,
+}
+
+static BREAKERS: Lazy<Mutex<HashMap<String, Arc<CircuitBreaker>>>> =
+    Lazy::new(|| Mutex::new(HashMap::new()));
+
+impl CircuitBreaker {
+    pub fn new(name: impl Into<String>, failure_threshold: usize, cooldown: Duration) -> Self {
+        Self {
+            name: name.into(),
+            failure_threshold,
+            cooldown,
+            anomaly_k: DEFAULT_ANOMALY_K,
+            baseline_file: None,
+            state: Mutex::new(BreakerState {
+                circuit: CircuitState::Closed { failures: 0 },
+                window: RateWindow::default(),
+                baseline: ErrorRateBaseline::default(),
+            }),
+        }
+    }
+
+    pub fn with_anomaly_k(mut self, anomaly_k: f64) -> Self {
+        self.anomaly_k = anomaly_k;
+        self
+    }
+
+    /// Loads the baseline learned in earlier runs from `path` and keeps it
+    /// there as it changes.
+    pub fn with_baseline_file(mut self, path: PathBuf) -> Self {
+        let baseline = circuit_baseline::load(&path, &self.name);
+        self.state.get_mut().unwrap().baseline = baseline;
+        self.baseline_file = Some(path);
+        self
+    }
+
+    /// The breaker for `name`, created on first use.
+    pub fn for_provider(name: &str, config: &ResilienceConfig) -> Arc<Self> {
+        BREAKERS
+            .lock()
+            .unwrap()
+            .entry(name.to_string())
+            .or_insert_with(|| {
+                Arc::new(
+                    Self::new(name, config.failure_threshold, config.cooldown)
+                        .with_anomaly_k(config.anomaly_k)
+                        .with_baseline_file(circuit_baseline::baseline_path()),
+                )
+            })
+            .clone()
+    }
+
+    /// Fails fast while the circuit is open. After the cool-down a growing
+    /// share of calls is let through; enough successes close the circuit and
+    /// any failure re-opens it.
+    pub fn allow(&self) -> Result<(), ProviderError> {
+        let mut state = self.state.lock().unwrap();
+        if let CircuitState::Open { until } = state.circuit {
+            if Instant::now() < until {
+                return Err(ProviderError::ServerError(format!(
+                    "{} is failing repeatedly; requests are paused for {}s",
+                    self.name,
+                    until.saturating_duration_since(Instant::now()).as_secs() + 1
+                )));
+            }
+            state.circuit = CircuitState::HalfOpen(Ramp::default());
+        }
+        if let CircuitState::HalfOpen(ramp) = &mut state.circuit {
+            if !ramp.admit() {
+                return Err(ProviderError::ServerError(format!(
+                    "{} is recovering; {}% of requests are let through",
+                    self.name,
+                    ramp.percent()
+                )));
+            }
+        }
+        Ok(())
+    }
+
+    pub fn record_success(&self) {
+        let mut state = self.state.lock().unwrap();
+        state.window.record(false);
+        let recovering = match &mut state.circuit {
+            CircuitState::HalfOpen(ramp) => !ramp.record_success(),
+            _ => false,
+        };
+        if !recovering {
+            state.circuit = CircuitState::Closed { failures: 0 };
+        }
+        self.roll_window(&mut state);
+    }
+
+    pub fn record_failure(&self) {
+        let mut state = self.state.lock().unwrap();
+        state.window.record(true);
+        let reason = match state.circuit {
+            CircuitState::Closed { failures } => {
+                let failures = failures + 1;
+                let threshold = state.baseline.failure_threshold(self.failure_threshold);
+                if failures >= threshold {
+                    Some(format!("{} consecutive failures", failures))
+                } else if let Some(rate) = self.anomalous_rate(&state) {
+                    Some(format!(
+                        "an error rate of {:.0}% against a usual {:.0}%",
+                        rate * 100.0,
+                        state.baseline.mean * 100.0
+                    ))
+                } else {
+                    state.circuit = CircuitState::Closed { failures };
+                    None
+                }
+            }
+            _ => Some("a failed trial call".to_string()),
+        };
+        if let Some(reason) = reason {
+            tracing::warn!("Opening circuit for {} after {}", self.name, reason);
+            state.circuit = CircuitState::Open {
+                until: Instant::now() + self.cooldown,
+            };
+        }
+        self.roll_window(&mut state);
+    }
+
+    /// The current window's error rate, when it is `anomaly_k` standard
+    /// deviations above the baseline.
+    fn anomalous_rate(&self, state: &BreakerState) -> Option<f64> {
+        if self.anomaly_k <= 0.0 || !state.baseline.is_established() {
+            return None;
+        }
+        state
+            .window
+            .rate()
+            .filter(|rate| *rate > state.baseline.anomaly_threshold(self.anomaly_k))
+    }
+
+    /// Folds a completed window into the baseline and starts a new one.
+    /// Anomalous windows are left out so an outage does not become normal.
+    fn roll_window(&self, state: &mut BreakerState) {
+        if !state.window.is_complete() {
+            return;
+        }
+        if let Some(rate) = state.window.rate() {
+            if self.anomalous_rate(state).is_none() {
+                state.baseline.observe(rate);
+                if let Some(path) = &self.baseline_file {
+                    circuit_baseline::save(path, &self.name, state.baseline);
+                }
+            }
+        }
+        state.window = RateWindow::default();
+    }
+
+    /// Errors that say the provider is unhealthy count against the circuit;
+    /// errors caused by the request itself do not.
+    pub fn record(&self, result: Result<(), &ProviderError>) {
+        match result {
+            Ok(()) => self.record_success(),
+            Err(error) if trips_circuit(error) => self.record_failure(),
+            Err(_) => {}
+        }
+    }
+
+    pub fn health(&self) -> EndpointHealth {
+        match self.state.lock().unwrap().circuit {
+            CircuitState::Closed { failures: 0 } => EndpointHealth::Healthy,
+            CircuitState::Closed { .. } | CircuitState::HalfOpen(_) => EndpointHealth::Degraded,
+            CircuitState::Open { .. } => EndpointHealth::Unhealthy,
+        }
+    }
+}
+
+/// Health of every provider's breaker created so far, by provider name.
+pub fn breaker_health() -> Vec<(String, EndpointHealth)> {
+    let mut health: Vec<_> = BREAKERS
+        .lock()
+        .unwrap()
+        .iter()
+        .map(|(name, breaker)| (name.clone(), breaker.health()))
+        .collect();
+    health.sort_by(|a, b| a.0.cmp(&b.0));
+    health
+}
+
+fn trips_circuit(error: &ProviderError) -> bool {
+    matches!(
+        error,
+        ProviderError::ServerError(_) | ProviderError::RequestFailed(_)
+    )
+}
+
+fn timeout_error(timeout: Duration) -> ProviderError {
+    ProviderError::RequestFailed(format!(
+        "Provider did not respond within {}s",
+        timeout.as_secs()
+    ))
+}
+
+/// Wraps a provider with timeouts, retries of timed-out calls, a circuit
+/// breaker and optional failover.
+pub struct ResilientProvider {
+    inner: Arc<dyn Provider>,
+    config: ResilienceConfig,
+    breaker: Arc<CircuitBreaker>,
+    fallback: Option<(ProviderConstructor, ModelConfig)>,
+    fallback_provider: OnceCell<Arc<dyn Provider>>,
+    offline: Option<(ProviderConstructor, ModelConfig)>,
+    offline_provider: OnceCell<Arc<dyn Provider>>,
+}
+
+/// How a call that failed for lack of a network goes on
+enum OfflinePlan {
+    Local(Arc<dyn Provider>),
+    /// The network came back while the call was held
+    Retry,
+}
+
+impl ResilientProvider {
+    pub fn new(inner: Arc<dyn Provider>, config: ResilienceConfig) -> Self {
+        let breaker = CircuitBreaker::for_provider(inner.get_name(), &config);
+        Self {
+            inner,
+            config,
+            breaker,
+            fallback: None,
+            fallback_provider: OnceCell::new(),
+            offline: None,
+            offline_provider: OnceCell::new(),
+        }
+    }
+
+    /// Provider to fail over to while this one's circuit is open. It is
+    /// constructed on first use.
+    pub fn with_fallback(mut self, constructor: ProviderConstructor, model: ModelConfig) -> Self {
+        self.fallback = Some((constructor, model));
+        self
+    }
+
+    /// Provider, usually a local one, to use while goose is offline. It is
+    /// constructed on first use.
+    pub fn with_offline_provider(
+        mut self,
+        constructor: ProviderConstructor,
+        model: ModelConfig,
+    ) -> Self {
+        self.offline = Some((constructor, model));
+        self
+    }
+
+    pub fn breaker(&self) -> &Arc<CircuitBreaker> {
+        &self.breaker
+    }
+
+    async fn call<T, F, Fut>(&self, operation: F) -> Result<T, ProviderError>
+    where
+        F: Fn() -> Fut,
+        Fut: Future<Output = Result<T, ProviderError>>,
+    {
+        self.breaker.allow()?;
+        let Some(timeout) = self.config.request_timeout else {
+            let result = operation().await;
+            self.breaker.record(result.as_ref().map(|_| ()));
+            return result;
+        };
+
+        let mut attempts = 0;
+        loop {
+            match tokio::time::timeout(timeout, operation()).await {
+                Ok(result) => {
+                    self.breaker.record(result.as_ref().map(|_| ()));
+                    return result;
+                }
+                Err(_) => {
+                    self.breaker.record_failure();
+                    if attempts >= self.config.retry.max_retries() || self.breaker.allow().is_err()
+                    {
+                        return Err(timeout_error(timeout));
+                    }
+                    attempts += 1;
+                    let delay = self.config.retry.delay_for_attempt(attempts);
+                    tracing::warn!(
+                        "{} timed out after {}s, retrying ({}/{}) in {:?}",
+                        self.inner.get_name(),
+                        timeout.as_secs(),
+                        attempts,
+                        self.config.retry.max_retries(),
+                        delay
+                    );
+                    tokio::time::sleep(delay).await;
+                }
+            }
+        }
+    }
+
+    /// The fallback provider when `error` means this provider is unavailable.
+    async fn failover(&self, error: &ProviderError) -> Option<Arc<dyn Provider>> {
+        if !trips_circuit(error) {
+            return None;
+        }
+        let (constructor, model) = self.fallback.as_ref()?;
+        let provider = self
+            .fallback_provider
+            .get_or_try_init(|| constructor(model.clone()))
+            .await
+            .inspect_err(|e| tracing::error!("Failed to create fallback provider: {}", e))
+            .ok()?;
+        tracing::warn!(
+            "{} unavailable ({}), failing over to {}",
+            self.inner.get_name(),
+            error,
+            provider.get_name()
+        );
+        Some(provider.clone())
+    }
+
+    async fn offline_provider(&self) -> Option<Arc<dyn Provider>> {
+        let (constructor, model) = self.offline.as_ref()?;
+        let provider = self
+            .offline_provider
+            .get_or_try_init(|| constructor(model.clone()))
+            .await
+            .inspect_err(|e| tracing::error!("Failed to create offline provider: {}", e))
+            .ok()?;
+        connectivity::global().set_offline_provider(Some(provider.get_name().to_string()));
+        Some(provider.clone())
+    }
+
+    /// The local provider to use right away when goose is already offline.
+    async fn offline_provider_if_offline(&self) -> Option<Arc<dyn Provider>> {
+        if !connectivity::is_offline() {
+            return None;
+        }
+        self.offline_provider().await
+    }
+
+    /// Applies the offline policy when `error` is down to goose having no
+    /// network. `None` when the network is fine and the error is the
+    /// provider's own. Background calls are held until the network returns;
+    /// interactive ones fail straight away rather than hang.
+    async fn offline_plan(
+        &self,
+        error: &ProviderError,
+    ) -> Option<Result<OfflinePlan, ProviderError>> {
+        if !connectivity::is_network_error(error)
+            || !connectivity::global().confirm_offline(error).await
+        {
+            return None;
+        }
+        if let Some(provider) = self.offline_provider().await {
+            tracing::warn!(
+                "Offline; switching from {} to {}",
+                self.inner.get_name(),
+                provider.get_name()
+            );
+            return Some(Ok(OfflinePlan::Local(provider)));
+        }
+        if !is_background() {
+            return Some(Err(connectivity::offline_error()));
+        }
+        tracing::info!(
+            "Offline; holding background call to {} until the network returns",
+            self.inner.get_name()
+        );
+        if connectivity::global()
+            .wait_online(connectivity::queue_timeout())
+            .await
+        {
+            Some(Ok(OfflinePlan::Retry))
+        } else {
+            Some(Err(connectivity::offline_error()))
+        }
+    }
+
+    /// Streams from the local offline provider under a resource permit.
+    async fn local_stream(
+        local: Arc<dyn Provider>,
+        session_id: &str,
+        system: &str,
+        messages: &[Message],
+        tools: &[Tool],
+    ) -> Result<MessageStream, ProviderError> {
+        let permit = resources::acquire_for_provider(local.get_name()).await;
+        let stream = local.stream(session_id, system, messages, tools).await?;
+        Ok(holding_permit(stream, permit))
+    }
+
+    fn with_idle_timeout(&self, stream: MessageStream) -> MessageStream {
+        let Some(timeout) = self.config.request_timeout else {
+            return stream;
+        };
+        let breaker = self.breaker.clone();
+        Box::pin(async_stream::stream! {
+            let mut stream = stream;
+            loop {
+                match tokio::time::timeout(timeout, stream.next()).await {
+                    Ok(Some(item)) => {
+                        if let Err(error) = &item {
+                            breaker.record(Err(error));
+                        }
+                        yield item;
+                    }
+                    Ok(None) => {
+                        breaker.record_success();
+                        break;
+                    }
+                    Err(_) => {
+                        breaker.record_failure();
+                        yield Err(timeout_error(timeout));
+                        break;
+                    }
+                }
+            }
+        })
+    }
+}
+
+/// Keeps `permit` until `stream` has been read to the end or dropped.
+fn holding_permit(stream: MessageStream, permit: Option<ResourcePermit>) -> MessageStream {
+    let Some(permit) = permit else {
+        return stream;
+    };
+    Box::pin(async_stream::stream! {
+        let _permit = permit;
+        let mut stream = stream;
+        while let Some(item) = stream.next().await {
+            yield item;
+        }
+    })
+}
+
+#[async_trait]
+impl Provider for ResilientProvider {
+    fn get_name(&self) -> &str {
+        self.inner.get_name()
+    }
+
+    async fn complete_with_model(
+        &self,
+        session_id: Option<&str>,
+        model_config: &ModelConfig,
+        system: &str,
+        messages: &[Message],
+        tools: &[Tool],
+    ) -> Result<(Message, ProviderUsage), ProviderError> {
+        if let Some(local) = self.offline_provider_if_offline().await {
+            let _permit = resources::acquire_for_provider(local.get_name()).await;
+            return local
+                .complete_with_model(
+                    session_id,
+                    &local.get_model_config(),
+                    system,
+                    messages,
+                    tools,
+                )
+                .await;
+        }
+        let operation = || {
+            self.inner
+                .complete_with_model(session_id, model_config, system, messages, tools)
+        };
+        let permit = resources::acquire_for_provider(self.inner.get_name()).await;
+        let result = self.call(operation).await;
+        drop(permit);
+        match result {
+            Err(error) => match self.offline_plan(&error).await {
+                Some(Ok(OfflinePlan::Local(local))) => {
+                    let _permit = resources::acquire_for_provider(local.get_name()).await;
+                    local
+                        .complete_with_model(
+                            session_id,
+                            &local.get_model_config(),
+                            system,
+                            messages,
+                            tools,
+                        )
+                        .await
+                }
+                Some(Ok(OfflinePlan::Retry)) => self.call(operation).await,
+                Some(Err(offline)) => Err(offline),
+                None => match self.failover(&error).await {
+                    Some(fallback) => {
+                        fallback
+                            .complete_with_model(
+                                session_id,
+                                &fallback.get_model_config(),
+                                system,
+                                messages,
+                                tools,
+                            )
+                            .await
+                    }
+                    None => Err(error),
+                },
+            },
+            ok => ok,
+        }
+    }
+
+    fn get_model_config(&self) -> ModelConfig {
+        self.inner.get_model_config()
+    }
+
+    fn retry_config(&self) -> RetryConfig {
+        self.inner.retry_config()
+    }
+
+    async fn fetch_supported_models(&self) -> Result<Vec<String>, ProviderError> {
+        self.inner.fetch_supported_models().await
+    }
+
+    fn supports_embeddings(&self) -> bool {
+        self.inner.supports_embeddings()
+    }
+
+    async fn supports_cache_control(&self) -> bool {
+        self.inner.supports_cache_control().await
+    }
+
+    async fn create_embeddings(
+        &self,
+        session_id: &str,
+        texts: Vec<String>,
+    ) -> Result<Vec<Vec<f32>>, ProviderError> {
+        self.inner.create_embeddings(session_id, texts).await
+    }
+
+    fn as_lead_worker(&self) -> Option<&dyn LeadWorkerProviderTrait> {
+        self.inner.as_lead_worker()
+    }
+
+    async fn stream(
+        &self,
+        session_id: &str,
+        system: &str,
+        messages: &[Message],
+        tools: &[Tool],
+    ) -> Result<MessageStream, ProviderError> {
+        if let Some(local) = self.offline_provider_if_offline().await {
+            if local.supports_streaming() {
+                return Self::local_stream(local, session_id, system, messages, tools).await;
+            }
+        }
+        let permit = resources::acquire_for_provider(self.inner.get_name()).await;
+        let operation = || self.inner.stream(session_id, system, messages, tools);
+        let result = match self.call(operation).await {
+            Err(error) => match self.offline_plan(&error).await {
+                Some(Ok(OfflinePlan::Local(local))) if local.supports_streaming() => {
+                    drop(permit);
+                    return Self::local_stream(local, session_id, system, messages, tools).await;
+                }
+                Some(Ok(OfflinePlan::Retry)) => self.call(operation).await,
+                Some(Err(offline)) => return Err(offline),
+                _ => Err(error),
+            },
+            ok => ok,
+        };
+        match result {
+            Ok(stream) => Ok(holding_permit(self.with_idle_timeout(stream), permit)),
+            Err(error) => match self.failover(&error).await {
+                Some(fallback) if fallback.supports_streaming() => {
+                    fallback.stream(session_id, system, messages, tools).await
+                }
+                _ => Err(error),
+            },
+        }
+    }
+
+    fn supports_streaming(&self) -> bool {
+        self.inner.supports_streaming()
+    }
+
+    async fn generate_session_name(
+        &self,
+        session_id: &str,
+        messages: &Conversation,
+    ) -> Result<String, ProviderError> {
+        self.inner.generate_session_name(session_id, messages).await
+    }
+
+    async fn configure_oauth(&self) -> Result<(), ProviderError> {
+        self.inner.configure_oauth().await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::providers::base::Usage;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    struct FlakyProvider {
+        name: &'static str,
+        calls: AtomicUsize,
+        stall_first: usize,
+        fail: bool,
+    }
+
+    impl FlakyProvider {
+        fn new(name: &'static str) -> Self {
+            Self {
+                name,
+                calls: AtomicUsize::new(0),
+                stall_first: 0,
+                fail: false,
+            }
+        }
+    }
+
+    #[async_trait]
+    impl Provider for FlakyProvider {
+        fn get_name(&self) -> &str {
+            self.name
+        }
+
+        fn get_model_config(&self) -> ModelConfig {
+            ModelConfig::new_or_fail("flaky-model")
+        }
+
+        async fn complete_with_model(
+            &self,
+            _session_id: Option<&str>,
+            _model_config: &ModelConfig,
+            _system: &str,
+            _messages: &[Message],
+            _tools: &[Tool],
+        ) -> Result<(Message, ProviderUsage), ProviderError> {
+            let call = self.calls.fetch_add(1, Ordering::SeqCst);
+            if call < self.stall_first {
+                futures::future::pending::<()>().await;
+            }
+            if self.fail {
+                return Err(ProviderError::ServerError("503".to_string()));
+            }
+            Ok((
+                Message::assistant().with_text(self.name),
+                ProviderUsage::new(self.name.to_string(), Usage::default()),
+            ))
+        }
+    }
+
+    fn config(threshold: usize) -> ResilienceConfig {
+        ResilienceConfig {
+            request_timeout: Some(Duration::from_millis(50)),
+            retry: RetryConfig::new(2, 1, 1.0, 1),
+            failure_threshold: threshold,
+            cooldown: Duration::from_secs(60),
+            anomaly_k: DEFAULT_ANOMALY_K,
+        }
+    }
+
+    #[tokio::test]
+    async fn test_timed_out_calls_are_retried() {
+        let inner = Arc::new(FlakyProvider {
+            stall_first: 2,
+            ..FlakyProvider::new("resilience-retry")
+        });
+        let provider = ResilientProvider::new(inner.clone(), config(10));
+
+        let (message, _) = provider.complete("s", "system", &[], &[]).await.unwrap();
+        assert_eq!(message.as_concat_text(), "resilience-retry");
+        assert_eq!(inner.calls.load(Ordering::SeqCst), 3);
+        assert_eq!(provider.breaker().health(), EndpointHealth::Healthy);
+    }
+
+    #[tokio::test]
+    async fn test_open_circuit_fails_fast_and_fails_over() {
+        let inner = Arc::new(FlakyProvider {
+            fail: true,
+            ..FlakyProvider::new("resilience-circuit")
+        });
+        let provider = ResilientProvider::new(inner.clone(), config(2));
+
+        for _ in 0..2 {
+            assert!(provider.complete("s", "system", &[], &[]).await.is_err());
+        }
+        assert_eq!(provider.breaker().health(), EndpointHealth::Unhealthy);
+        let error = provider
+            .complete("s", "system", &[], &[])
+            .await
+            .unwrap_err();
+        assert!(error.to_string().contains("requests are paused"));
+        assert_eq!(inner.calls.load(Ordering::SeqCst), 2);
+
+        let constructor: ProviderConstructor = Arc::new(|_| {
+            Box::pin(async {
+                Ok(Arc::new(FlakyProvider::new("resilience-fallback")) as Arc<dyn Provider>)
+            })
+        });
+        let provider = ResilientProvider::new(inner.clone(), config(2))
+            .with_fallback(constructor, ModelConfig::new_or_fail("fallback-model"));
+        let (message, _) = provider.complete("s", "system", &[], &[]).await.unwrap();
+        assert_eq!(message.as_concat_text(), "resilience-fallback");
+        assert_eq!(inner.calls.load(Ordering::SeqCst), 2);
+    }
+
+    #[test]
+    fn test_request_errors_do_not_trip_circuit() {
+        let breaker = CircuitBreaker::new("test", 1, Duration::from_secs(60));
+        breaker.record(Err(&ProviderError::ContextLengthExceeded(
+            "too long".into(),
+        )));
+        assert!(breaker.allow().is_ok());
+        breaker.record(Err(&ProviderError::RequestFailed("reset".into())));
+        assert!(breaker.allow().is_err());
+    }
+
+    #[test]
+    fn test_anomalous_error_rate_opens_circuit_and_ramps_back() {
+        let breaker = CircuitBreaker::new("test", 5, Duration::ZERO);
+        breaker.state.lock().unwrap().baseline = ErrorRateBaseline {
+            mean: 0.02,
+            variance: 0.0,
+            windows: 20,
+        };
+        for _ in 0..4 {
+            breaker.record_success();
+        }
+        breaker.record_failure();
+        assert_eq!(breaker.health(), EndpointHealth::Unhealthy);
+
+        assert!(breaker.allow().is_ok());
+        let error = breaker.allow().unwrap_err();
+        assert!(error.to_string().contains("25% of requests"));
+        for _ in 0..4 {
+            breaker.record_success();
+        }
+        assert_eq!(breaker.health(), EndpointHealth::Healthy);
+    }
+}
@@ -136,7 +136,7 @@ impl EndpointConfig {
 }
 
 /// Health status of an endpoint
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, Default)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, Default, utoipa::ToSchema)]
 pub enum EndpointHealth {
     /// Endpoint is healthy and responding
     Healthy,
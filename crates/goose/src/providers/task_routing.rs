@@ -0,0 +1,412 @@
+//! Model routing by task type.
+//!
+//! Background chores such as compaction or session naming rarely need the
+//! frontier model the session talks to. `GOOSE_MODEL_ROUTES` maps a task
+//! category to the provider/model pair that should handle it, with optional
+//! fallbacks:
+//!
+//! ```yaml
+//! GOOSE_MODEL_ROUTES:
+//!   compaction:
+//!     provider: openai
+//!     model: gpt-4o-mini
+//!     fallbacks:
+//!       - provider: anthropic
+//!         model: claude-3-5-haiku-latest
+//!   session_naming:
+//!     provider: ollama
+//!     model: qwen2.5:3b
+//! ```
+//!
+//! A chore tries its route's targets in order and ends up on the session's
+//! own provider (its fast model) when none is configured or all fail. The
+//! `code_generation` route replaces the session's provider for the agent's
+//! own turns. Calls and their cost are tracked per category and model.
+
+use super::base::{Provider, ProviderUsage};
+use super::errors::ProviderError;
+use crate::config::Config;
+use crate::conversation::message::Message;
+use crate::conversation::Conversation;
+use crate::model::ModelConfig;
+use crate::observability::{CostTracker, TokenUsage};
+use rmcp::model::Tool;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap};
+use std::sync::{Arc, Mutex, OnceLock};
+use utoipa::ToSchema;
+
+pub const MODEL_ROUTES_KEY: &str = "GOOSE_MODEL_ROUTES";
+
+#[derive(
+    Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord, Serialize, Deserialize, ToSchema,
+)]
+#[serde(rename_all = "snake_case")]
+pub enum TaskCategory {
+    Compaction,
+    SessionNaming,
+    CoachReview,
+    /// The agent's own turns
+    CodeGeneration,
+    Planning,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize, ToSchema)]
+pub struct RouteTarget {
+    pub provider: String,
+    pub model: String,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, ToSchema)]
+pub struct TaskRoute {
+    pub provider: String,
+    pub model: String,
+    /// Tried in order when the primary target fails
+    #[serde(default)]
+    pub fallbacks: Vec<RouteTarget>,
+}
+
+impl TaskRoute {
+    pub fn targets(&self) -> Vec<RouteTarget> {
+        let primary = RouteTarget {
+            provider: self.provider.clone(),
+            model: self.model.clone(),
+        };
+        std::iter::once(primary)
+            .chain(self.fallbacks.iter().cloned())
+            .collect()
+    }
+}
+
+/// The configured routing table. An invalid table is ignored with a warning.
+pub fn routes() -> BTreeMap<TaskCategory, TaskRoute> {
+    match Config::global().get_param::<BTreeMap<TaskCategory, TaskRoute>>(MODEL_ROUTES_KEY) {
+        Ok(routes) => routes,
+        Err(crate::config::ConfigError::NotFound(_)) => BTreeMap::new(),
+        Err(e) => {
+            tracing::warn!("Ignoring invalid {}: {}", MODEL_ROUTES_KEY, e);
+            BTreeMap::new()
+        }
+    }
+}
+
+fn route_targets(category: TaskCategory) -> Vec<RouteTarget> {
+    routes()
+        .remove(&category)
+        .map(|route| route.targets())
+        .unwrap_or_default()
+}
+
+/// Calls and spend of one model on one task category. Token counts and cost
+/// only cover calls that report usage; session naming does not.
+#[derive(Debug, Clone, Serialize, ToSchema)]
+pub struct RouteUsage {
+    pub category: TaskCategory,
+    pub provider: String,
+    pub model: String,
+    pub calls: u64,
+    pub failures: u64,
+    pub input_tokens: u64,
+    pub output_tokens: u64,
+    pub cost_usd: f64,
+}
+
+#[derive(Debug, Clone, Serialize, ToSchema)]
+pub struct ModelRoutes {
+    pub routes: BTreeMap<TaskCategory, TaskRoute>,
+    pub usage: Vec<RouteUsage>,
+}
+
+pub struct TaskRouter {
+    providers: tokio::sync::Mutex<HashMap<RouteTarget, Arc<dyn Provider>>>,
+    usage: Mutex<BTreeMap<(TaskCategory, String, String), RouteUsage>>,
+    pricing: CostTracker,
+}
+
+pub fn global() -> &'static TaskRouter {
+    static ROUTER: OnceLock<TaskRouter> = OnceLock::new();
+    ROUTER.get_or_init(TaskRouter::new)
+}
+
+impl TaskRouter {
+    pub fn new() -> Self {
+        Self {
+            providers: tokio::sync::Mutex::new(HashMap::new()),
+            usage: Mutex::new(BTreeMap::new()),
+            pricing: CostTracker::new(),
+        }
+    }
+
+    /// The provider for one route target, created on first use. A target
+    /// that cannot be created counts as a failed call.
+    async fn target_provider(
+        &self,
+        category: TaskCategory,
+        target: &RouteTarget,
+    ) -> Option<Arc<dyn Provider>> {
+        let mut providers = self.providers.lock().await;
+        if let Some(provider) = providers.get(target) {
+            return Some(provider.clone());
+        }
+        let created = match ModelConfig::new(&target.model) {
+            Ok(model) => super::create(&target.provider, model).await,
+            Err(e) => Err(e.into()),
+        };
+        match created {
+            Ok(provider) => {
+                providers.insert(target.clone(), provider.clone());
+                Some(provider)
+            }
+            Err(e) => {
+                tracing::warn!(
+                    "Cannot create {}/{} for {:?}: {}",
+                    target.provider,
+                    target.model,
+                    category,
+                    e
+                );
+                self.record_failure(category, &target.provider, &target.model);
+                None
+            }
+        }
+    }
+
+    /// The provider for the first target of `category`'s route that can be
+    /// created, for callers that stream. `None` when nothing is routed.
+    pub async fn provider_for(&self, category: TaskCategory) -> Option<Arc<dyn Provider>> {
+        for target in route_targets(category) {
+            if let Some(provider) = self.target_provider(category, &target).await {
+                return Some(provider);
+            }
+        }
+        None
+    }
+
+    /// Another provider to retry `category` on when `provider` declined the
+    /// request: the first target of the route on a different provider, else
+    /// `GOOSE_FALLBACK_PROVIDER` (with `GOOSE_FALLBACK_MODEL`).
+    pub async fn fallback_for(
+        &self,
+        category: TaskCategory,
+        provider: &str,
+    ) -> Option<Arc<dyn Provider>> {
+        for target in route_targets(category) {
+            if target.provider == provider {
+                continue;
+            }
+            if let Some(fallback) = self.target_provider(category, &target).await {
+                return Some(fallback);
+            }
+        }
+        let config = Config::global();
+        let fallback = config
+            .get_param::<String>("GOOSE_FALLBACK_PROVIDER")
+            .ok()
+            .filter(|name| name != provider)?;
+        match config.get_param::<String>("GOOSE_FALLBACK_MODEL") {
+            Ok(model) => {
+                let target = RouteTarget {
+                    provider: fallback,
+                    model,
+                };
+                self.target_provider(category, &target).await
+            }
+            Err(_) => super::create_with_default_model(&fallback)
+                .await
+                .inspect_err(|e| tracing::warn!("Cannot create {}: {}", fallback, e))
+                .ok(),
+        }
+    }
+
+    /// Runs a chore completion on its route, falling back to `default`'s
+    /// fast model.
+    pub async fn complete(
+        &self,
+        category: TaskCategory,
+        default: &dyn Provider,
+        session_id: &str,
+        system: &str,
+        messages: &[Message],
+        tools: &[Tool],
+    ) -> Result<(Message, ProviderUsage), ProviderError> {
+        for target in route_targets(category) {
+            let Some(provider) = self.target_provider(category, &target).await else {
+                continue;
+            };
+            match provider.complete(session_id, system, messages, tools).await {
+                Ok(result) => {
+                    self.record(category, &target.provider, &result.1);
+                    return Ok(result);
+                }
+                Err(e) => {
+                    tracing::warn!(
+                        "{:?} on {}/{} failed: {}",
+                        category,
+                        target.provider,
+                        target.model,
+                        e
+                    );
+                    self.record_failure(category, &target.provider, &target.model);
+                }
+            }
+        }
+
+        let result = default
+            .complete_fast(session_id, system, messages, tools)
+            .await;
+        match &result {
+            Ok((_, usage)) => self.record(category, default.get_name(), usage),
+            Err(_) => self.record_failure(
+                category,
+                default.get_name(),
+                &default.get_model_config().model_name,
+            ),
+        }
+        result
+    }
+
+    /// Names a session on the `session_naming` route, falling back to
+    /// `default`.
+    pub async fn generate_session_name(
+        &self,
+        default: &dyn Provider,
+        session_id: &str,
+        conversation: &Conversation,
+    ) -> Result<String, ProviderError> {
+        let category = TaskCategory::SessionNaming;
+        for target in route_targets(category) {
+            let Some(provider) = self.target_provider(category, &target).await else {
+                continue;
+            };
+            match provider
+                .generate_session_name(session_id, conversation)
+                .await
+            {
+                Ok(name) => {
+                    self.record_call(category, &target.provider, &target.model);
+                    return Ok(name);
+                }
+                Err(e) => {
+                    tracing::warn!("Session naming on {} failed: {}", target.model, e);
+                    self.record_failure(category, &target.provider, &target.model);
+                }
+            }
+        }
+
+        let model = default.get_model_config().model_name;
+        let result = default
+            .generate_session_name(session_id, conversation)
+            .await;
+        match &result {
+            Ok(_) => self.record_call(category, default.get_name(), &model),
+            Err(_) => self.record_failure(category, default.get_name(), &model),
+        }
+        result
+    }
+
+    fn update(
+        &self,
+        category: TaskCategory,
+        provider: &str,
+        model: &str,
+        update: impl FnOnce(&mut RouteUsage),
+    ) {
+        let mut usage = self.usage.lock().unwrap();
+        let entry = usage
+            .entry((category, provider.to_string(), model.to_string()))
+            .or_insert_with(|| RouteUsage {
+                category,
+                provider: provider.to_string(),
+                model: model.to_string(),
+                calls: 0,
+                failures: 0,
+                input_tokens: 0,
+                output_tokens: 0,
+                cost_usd: 0.0,
+            });
+        update(entry);
+    }
+
+    /// Records a successful call and its cost.
+    pub fn record(&self, category: TaskCategory, provider: &str, usage: &ProviderUsage) {
+        let input = usage.usage.input_tokens.unwrap_or(0).max(0) as u64;
+        let output = usage.usage.output_tokens.unwrap_or(0).max(0) as u64;
+        let cached = usage.usage.cache_read_input_tokens.unwrap_or(0).max(0) as u64;
+        let cost = self
+            .pricing
+            .calculate_cost(&TokenUsage::with_cache(input, output, cached), &usage.model);
+        self.update(category, provider, &usage.model, |entry| {
+            entry.calls += 1;
+            entry.input_tokens += input;
+            entry.output_tokens += output;
+            entry.cost_usd += cost;
+        });
+    }
+
+    fn record_call(&self, category: TaskCategory, provider: &str, model: &str) {
+        self.update(category, provider, model, |entry| entry.calls += 1);
+    }
+
+    fn record_failure(&self, category: TaskCategory, provider: &str, model: &str) {
+        self.update(category, provider, model, |entry| entry.failures += 1);
+    }
+
+    pub fn usage(&self) -> Vec<RouteUsage> {
+        self.usage.lock().unwrap().values().cloned().collect()
+    }
+
+    pub fn status(&self) -> ModelRoutes {
+        ModelRoutes {
+            routes: routes(),
+            usage: self.usage(),
+        }
+    }
+}
+
+impl Default for TaskRouter {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::providers::base::Usage;
+
+    #[test]
+    fn test_route_table_parses_from_config_yaml() {
+        let table: BTreeMap<TaskCategory, TaskRoute> = serde_yaml::from_str(
+            "compaction:\n  provider: openai\n  model: gpt-4o-mini\n  fallbacks:\n    - provider: ollama\n      model: qwen2.5\nsession_naming:\n  provider: ollama\n  model: qwen2.5\n",
+        )
+        .unwrap();
+        let targets = table[&TaskCategory::Compaction].targets();
+        assert_eq!(targets.len(), 2);
+        assert_eq!(targets[0].model, "gpt-4o-mini");
+        assert_eq!(targets[1].provider, "ollama");
+        assert!(table[&TaskCategory::SessionNaming].fallbacks.is_empty());
+    }
+
+    #[test]
+    fn test_usage_is_tracked_per_category_and_model() {
+        let router = TaskRouter::new();
+        let usage = ProviderUsage::new(
+            "gpt-4o-mini".to_string(),
+            Usage::new(Some(1000), Some(100), Some(1100)),
+        );
+        router.record(TaskCategory::Compaction, "openai", &usage);
+        router.record(TaskCategory::Compaction, "openai", &usage);
+        router.record_failure(TaskCategory::Compaction, "anthropic", "claude-3-5-haiku");
+        router.record_call(TaskCategory::SessionNaming, "openai", "gpt-4o-mini");
+
+        let usage = router.usage();
+        assert_eq!(usage.len(), 3);
+        let compaction = usage
+            .iter()
+            .find(|u| u.category == TaskCategory::Compaction && u.model == "gpt-4o-mini")
+            .unwrap();
+        assert_eq!(compaction.calls, 2);
+        assert_eq!(compaction.input_tokens, 2000);
+        assert!(compaction.cost_usd > 0.0);
+    }
+}
@@ -2,7 +2,7 @@ use anyhow::{anyhow, Result};
 use async_trait::async_trait;
 use serde::{Deserialize, Serialize};
 use sha2::{Digest, Sha256};
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 use std::fs;
 use std::path::Path;
 use std::sync::{Arc, Mutex};
@@ -14,6 +14,10 @@ use crate::model::ModelConfig;
 use futures::future::BoxFuture;
 use rmcp::model::Tool;
 
+/// Minimum token overlap for a recorded request to stand in for one whose
+/// hash was not recorded.
+const FUZZY_MATCH_THRESHOLD: f64 = 0.8;
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 struct TestInput {
     system: String,
@@ -38,6 +42,7 @@ pub struct TestProvider {
     records: Arc<Mutex<HashMap<String, TestRecord>>>,
     file_path: String,
     name: String,
+    model_config: ModelConfig,
 }
 
 impl TestProvider {
@@ -49,6 +54,7 @@ impl TestProvider {
             records: Arc::new(Mutex::new(HashMap::new())),
             file_path: file_path.into(),
             name: Self::PROVIDER_NAME.to_string(),
+            model_config: ModelConfig::new_or_fail("test-model"),
         }
     }
 
@@ -61,9 +67,17 @@ impl TestProvider {
             records: Arc::new(Mutex::new(records)),
             file_path,
             name: Self::PROVIDER_NAME.to_string(),
+            model_config: ModelConfig::new_or_fail("test-model"),
         })
     }
 
+    /// Reports `model_config` as this provider's model, so a recorded or
+    /// replayed session sees the same limits as the real one.
+    pub fn with_model_config(mut self, model_config: ModelConfig) -> Self {
+        self.model_config = model_config;
+        self
+    }
+
     pub fn finish_recording(self) -> Result<()> {
         if self.inner.is_some() {
             self.save_records()?;
@@ -82,6 +96,55 @@ impl TestProvider {
         format!("{:x}", hasher.finalize())
     }
 
+    /// Finds the recorded request closest to `messages` when there is no
+    /// exact match, e.g. because a tool returned a timestamp. Candidates must
+    /// have the same shape of conversation; ties go to the smallest hash so
+    /// replays stay deterministic.
+    fn fuzzy_match<'a>(
+        records: &'a HashMap<String, TestRecord>,
+        messages: &[Message],
+    ) -> Option<(&'a str, &'a TestRecord)> {
+        let tokens = Self::tokens(messages);
+        records
+            .iter()
+            .filter(|(_, record)| {
+                record.input.messages.len() == messages.len()
+                    && record
+                        .input
+                        .messages
+                        .iter()
+                        .zip(messages)
+                        .all(|(recorded, msg)| recorded.role == msg.role)
+            })
+            .map(|(hash, record)| {
+                let recorded = Self::tokens(&record.input.messages);
+                let union = tokens.union(&recorded).count();
+                let score = if union == 0 {
+                    1.0
+                } else {
+                    tokens.intersection(&recorded).count() as f64 / union as f64
+                };
+                (score, hash.as_str(), record)
+            })
+            .filter(|(score, _, _)| *score >= FUZZY_MATCH_THRESHOLD)
+            .max_by(|a, b| a.0.total_cmp(&b.0).then_with(|| b.1.cmp(a.1)))
+            .map(|(_, hash, record)| (hash, record))
+    }
+
+    fn tokens(messages: &[Message]) -> HashSet<String> {
+        messages
+            .iter()
+            .flat_map(|msg| {
+                let content = serde_json::to_string(&msg.content).unwrap_or_default();
+                content
+                    .split(|c: char| !c.is_alphanumeric())
+                    .filter(|token| !token.is_empty())
+                    .map(str::to_string)
+                    .collect::<Vec<_>>()
+            })
+            .collect()
+    }
+
     fn load_records(file_path: &str) -> Result<HashMap<String, TestRecord>> {
         if !Path::new(file_path).exists() {
             return Ok(HashMap::new());
@@ -162,12 +225,23 @@ impl Provider for TestProvider {
                 let mut records = self.records.lock().unwrap();
                 records.insert(hash, record);
             }
+            // Persist as we go so a crashed or interrupted session can still be replayed
+            if let Err(e) = self.save_records() {
+                tracing::warn!("Failed to save provider recording: {}", e);
+            }
 
             Ok((message, usage))
         } else {
             let records = self.records.lock().unwrap();
             if let Some(record) = records.get(&hash) {
                 Ok((record.output.message.clone(), record.output.usage.clone()))
+            } else if let Some((matched, record)) = Self::fuzzy_match(&records, messages) {
+                tracing::warn!(
+                    "No recorded response for input hash {}, replaying closest match {}",
+                    hash,
+                    matched
+                );
+                Ok((record.output.message.clone(), record.output.usage.clone()))
             } else {
                 Err(ProviderError::ExecutionError(format!(
                     "No recorded response found for input hash: {}",
@@ -178,7 +252,7 @@ impl Provider for TestProvider {
     }
 
     fn get_model_config(&self) -> ModelConfig {
-        ModelConfig::new_or_fail("test-model")
+        self.model_config.clone()
     }
 }
 
@@ -281,6 +355,60 @@ mod tests {
         let _ = fs::remove_file(temp_file);
     }
 
+    #[tokio::test]
+    async fn test_replay_falls_back_to_closest_recording() {
+        let temp_file = format!(
+            "{}/test_fuzzy_{}.json",
+            env::temp_dir().display(),
+            std::process::id()
+        );
+        let mock = Arc::new(MockProvider {
+            model_config: ModelConfig::new_or_fail("mock-model"),
+            response: "Recorded answer".to_string(),
+        });
+        let request = |text: &str| vec![Message::user().with_text(text)];
+
+        let recorder = TestProvider::new_recording(mock, &temp_file);
+        recorder
+            .complete(
+                "test-session-id",
+                "You are helpful",
+                &request(
+                    "Summarize the build log written at 2024-05-01 10:01 for the release branch",
+                ),
+                &[],
+            )
+            .await
+            .unwrap();
+        drop(recorder);
+
+        let replay = TestProvider::new_replaying(&temp_file)
+            .unwrap()
+            .with_model_config(ModelConfig::new_or_fail("mock-model"));
+        assert_eq!(replay.get_model_config().model_name, "mock-model");
+        let (message, _) = replay
+            .complete(
+                "test-session-id",
+                "You are helpful",
+                &request(
+                    "Summarize the build log written at 2024-05-01 10:02 for the release branch",
+                ),
+                &[],
+            )
+            .await
+            .unwrap();
+        assert_eq!(message.as_concat_text(), "Recorded answer");
+
+        let mut longer = request("Summarize the build log");
+        longer.push(Message::assistant().with_text("Done"));
+        assert!(replay
+            .complete("test-session-id", "You are helpful", &longer, &[])
+            .await
+            .is_err());
+
+        let _ = fs::remove_file(temp_file);
+    }
+
     #[tokio::test]
     async fn test_replay_missing_record() {
         let temp_file = format!(
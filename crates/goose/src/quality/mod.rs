@@ -13,4 +13,4 @@ pub use comprehensive_validator::{ComprehensiveReport, ComprehensiveValidator};
 pub use logger::{IssueDetail, Severity as LogSeverity, ValidationLogger};
 pub use multipass_validator::{FinalReport, MultiPassValidator, ValidationSnapshot};
 pub use sonarqube::{QualityGateStatus, SonarQubeConfig};
-pub use validator::{CheckResult, PostCodeValidator, ValidationReport};
+pub use validator::{ChangeValidator, CheckResult, PostCodeValidator, ValidationReport};
@@ -1,6 +1,14 @@
+use async_trait::async_trait;
 use std::path::Path;
 use std::process::Command;
 
+/// Checks a set of changed files and reports what it found.
+#[async_trait]
+pub trait ChangeValidator: Send + Sync + std::fmt::Debug {
+    async fn validate(&self, files_changed: &[String]) -> Result<ValidationReport, String>;
+}
+
+#[derive(Debug)]
 pub struct PostCodeValidator {
     strict_mode: bool,
 }
@@ -23,9 +31,10 @@ impl PostCodeValidator {
     ) -> Result<ValidationReport, String> {
         let mut report = ValidationReport::new();
 
-        println!("\n╔══════════════════════════════════════════╗");
-        println!("║   RUNNING POST-CODE VALIDATION CHECKS    ║");
-        println!("╚══════════════════════════════════════════╝\n");
+        tracing::info!(
+            files = files_changed.len(),
+            "Running post-code validation checks"
+        );
 
         // Step 1: Syntax Check
         report.add_check("Syntax Check", self.check_syntax(files_changed).await?);
@@ -57,7 +66,7 @@ impl PostCodeValidator {
     }
 
     async fn check_syntax(&self, files: &[String]) -> Result<CheckResult, String> {
-        println!("🔍 Checking syntax...");
+        tracing::debug!("Checking syntax");
         let mut issues = Vec::new();
 
         for file in files {
@@ -102,7 +111,7 @@ impl PostCodeValidator {
     }
 
     async fn scan_for_incomplete_markers(&self, files: &[String]) -> Result<CheckResult, String> {
-        println!("🔍 Scanning for incomplete markers...");
+        tracing::debug!("Scanning for incomplete markers");
         let markers = vec!["TODO", "FIXME", "HACK", "XXX", "STUB", "PLACEHOLDER"];
         let mut issues = Vec::new();
 
@@ -133,7 +142,7 @@ impl PostCodeValidator {
     }
 
     async fn verify_wiring(&self, files: &[String]) -> Result<CheckResult, String> {
-        println!("🔍 Checking component wiring...");
+        tracing::debug!("Checking component wiring");
         let mut issues = Vec::new();
 
         for file in files {
@@ -171,7 +180,7 @@ impl PostCodeValidator {
     }
 
     async fn run_linters(&self, files: &[String]) -> Result<CheckResult, String> {
-        println!("🔍 Running linters...");
+        tracing::debug!("Running linters");
         let mut issues = Vec::new();
 
         // TypeScript/JavaScript linting
@@ -230,7 +239,7 @@ impl PostCodeValidator {
     }
 
     async fn check_types(&self, files: &[String]) -> Result<CheckResult, String> {
-        println!("🔍 Running type checks...");
+        tracing::debug!("Running type checks");
         let mut issues = Vec::new();
 
         // TypeScript type checking
@@ -281,7 +290,7 @@ impl PostCodeValidator {
     }
 
     async fn attempt_build(&self) -> Result<CheckResult, String> {
-        println!("🔍 Attempting build...");
+        tracing::debug!("Attempting build");
         let mut issues = Vec::new();
 
         // Try TypeScript build
@@ -348,6 +357,13 @@ impl PostCodeValidator {
     }
 }
 
+#[async_trait]
+impl ChangeValidator for PostCodeValidator {
+    async fn validate(&self, files_changed: &[String]) -> Result<ValidationReport, String> {
+        self.validate_changes(files_changed).await
+    }
+}
+
 impl Default for PostCodeValidator {
     fn default() -> Self {
         Self::new()
@@ -381,6 +397,10 @@ impl ValidationReport {
         self.checks.push((name.to_string(), result));
     }
 
+    pub fn checks(&self) -> &[(String, CheckResult)] {
+        &self.checks
+    }
+
     pub fn has_failures(&self) -> bool {
         self.checks
             .iter()
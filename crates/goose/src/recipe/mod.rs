@@ -7,6 +7,7 @@ use std::path::Path;
 use crate::agents::extension::ExtensionConfig;
 use crate::agents::types::RetryConfig;
 use crate::recipe::read_recipe_file_content::read_recipe_file;
+use crate::recipe::validation::Validation;
 use crate::recipe::yaml_format_utils::reformat_fields_with_multiline_values;
 use crate::utils::contains_unicode_tags;
 use serde::de::Deserializer;
@@ -15,10 +16,13 @@ use utoipa::ToSchema;
 
 pub mod build_recipe;
 pub mod local_recipes;
+pub mod parameter_form;
 pub mod read_recipe_file_content;
 mod recipe_extension_adapter;
+pub mod registry;
 pub mod template_recipe;
 pub mod validate_recipe;
+pub mod validation;
 pub mod yaml_format_utils;
 
 pub const BUILT_IN_RECIPE_DIR_PARAM: &str = "recipe_dir";
@@ -63,6 +67,9 @@ pub struct Recipe {
     )]
     pub extensions: Option<Vec<ExtensionConfig>>, // a list of extensions to enable
 
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub dependencies: Option<Vec<ExtensionDependency>>, // extensions the recipe needs, checked on install
+
     #[serde(skip_serializing_if = "Option::is_none")]
     pub settings: Option<Settings>, // settings for the recipe
 
@@ -83,6 +90,17 @@ pub struct Recipe {
 
     #[serde(skip_serializing_if = "Option::is_none")]
     pub retry: Option<RetryConfig>,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub validation: Option<Validation>, // gates the final output must pass
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, ToSchema)]
+pub struct ExtensionDependency {
+    pub name: String,
+    /// Semver requirement such as ">=1.2, <2"
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub version: Option<String>,
 }
 
 #[derive(Serialize, Deserialize, Debug, Clone, ToSchema)]
@@ -107,6 +125,11 @@ pub struct Settings {
 
     #[serde(skip_serializing_if = "Option::is_none")]
     pub max_turns: Option<usize>,
+
+    /// Template from the workspace or user prompts dir to render the system
+    /// prompt from instead of `system.md`
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub system_prompt_template: Option<String>,
 }
 
 #[derive(Serialize, Deserialize, Debug, Clone, ToSchema)]
@@ -216,6 +239,7 @@ pub struct RecipeBuilder {
     // Optional fields
     prompt: Option<String>,
     extensions: Option<Vec<ExtensionConfig>>,
+    dependencies: Option<Vec<ExtensionDependency>>,
     settings: Option<Settings>,
     activities: Option<Vec<String>>,
     author: Option<Author>,
@@ -223,6 +247,7 @@ pub struct RecipeBuilder {
     response: Option<Response>,
     sub_recipes: Option<Vec<SubRecipe>>,
     retry: Option<RetryConfig>,
+    validation: Option<Validation>,
 }
 
 impl Recipe {
@@ -261,6 +286,7 @@ impl Recipe {
             instructions: None,
             prompt: None,
             extensions: None,
+            dependencies: None,
             settings: None,
             activities: None,
             author: None,
@@ -268,6 +294,7 @@ impl Recipe {
             response: None,
             sub_recipes: None,
             retry: None,
+            validation: None,
         }
     }
 
@@ -326,6 +353,11 @@ impl RecipeBuilder {
         self
     }
 
+    pub fn dependencies(mut self, dependencies: Vec<ExtensionDependency>) -> Self {
+        self.dependencies = Some(dependencies);
+        self
+    }
+
     pub fn settings(mut self, settings: Settings) -> Self {
         self.settings = Some(settings);
         self
@@ -361,6 +393,11 @@ impl RecipeBuilder {
         self
     }
 
+    pub fn validation(mut self, validation: Validation) -> Self {
+        self.validation = Some(validation);
+        self
+    }
+
     pub fn build(self) -> Result<Recipe, &'static str> {
         let title = self.title.ok_or("Title is required")?;
         let description = self.description.ok_or("Description is required")?;
@@ -376,6 +413,7 @@ impl RecipeBuilder {
             instructions: self.instructions,
             prompt: self.prompt,
             extensions: self.extensions,
+            dependencies: self.dependencies,
             settings: self.settings,
             activities: self.activities,
             author: self.author,
@@ -383,6 +421,7 @@ impl RecipeBuilder {
             response: self.response,
             sub_recipes: self.sub_recipes,
             retry: self.retry,
+            validation: self.validation,
         })
     }
 }
@@ -714,6 +753,7 @@ isGlobal: true"#;
             instructions: Some("clean instructions".to_string()),
             prompt: Some("clean prompt".to_string()),
             extensions: None,
+            dependencies: None,
             settings: None,
             activities: Some(vec!["clean activity 1".to_string()]),
             author: None,
@@ -721,6 +761,7 @@ isGlobal: true"#;
             response: None,
             sub_recipes: None,
             retry: None,
+            validation: None,
         };
 
         assert!(!recipe.check_for_security_warnings());
@@ -0,0 +1,204 @@
+//! Forms for recipe parameters the user has not supplied yet.
+//!
+//! The schema is the flat JSON Schema subset elicitation clients render
+//! (strings, numbers, booleans, enums and dates, with a file picker for file
+//! parameters), and the submitted values are checked against the parameter
+//! definitions again before the recipe is rendered.
+
+use crate::elicitation_form::file_picker;
+use crate::recipe::{RecipeParameter, RecipeParameterInputType};
+use chrono::NaiveDate;
+use serde_json::{json, Map, Value};
+
+pub fn form_schema(parameters: &[RecipeParameter]) -> Value {
+    let mut properties = Map::new();
+    let mut required = Vec::new();
+    for param in parameters {
+        let mut property = match param.input_type {
+            RecipeParameterInputType::Number => json!({ "type": "number" }),
+            RecipeParameterInputType::Boolean => json!({ "type": "boolean" }),
+            RecipeParameterInputType::Date => json!({ "type": "string", "format": "date" }),
+            RecipeParameterInputType::Select => json!({
+                "type": "string",
+                "enum": param.options.clone().unwrap_or_default(),
+            }),
+            RecipeParameterInputType::File => file_picker(&param.description),
+            RecipeParameterInputType::String => json!({ "type": "string" }),
+        };
+        property["title"] = json!(param.key);
+        property["description"] = json!(param.description);
+        if let Some(default) = &param.default {
+            property["default"] = default_value(param, default);
+        } else {
+            required.push(param.key.clone());
+        }
+        properties.insert(param.key.clone(), property);
+    }
+    json!({
+        "type": "object",
+        "properties": properties,
+        "required": required,
+    })
+}
+
+fn default_value(param: &RecipeParameter, default: &str) -> Value {
+    match param.input_type {
+        RecipeParameterInputType::Number => default
+            .parse::<f64>()
+            .map(|n| json!(n))
+            .unwrap_or_else(|_| json!(default)),
+        RecipeParameterInputType::Boolean => default
+            .parse::<bool>()
+            .map(Value::Bool)
+            .unwrap_or_else(|_| json!(default)),
+        _ => json!(default),
+    }
+}
+
+/// Turns a submitted form into parameter values, filling in defaults for
+/// fields left empty. Returns every problem found rather than the first.
+pub fn values_from_form(
+    parameters: &[RecipeParameter],
+    user_data: &Value,
+) -> Result<Vec<(String, String)>, Vec<String>> {
+    let mut values = Vec::new();
+    let mut errors = Vec::new();
+    for param in parameters {
+        let submitted = user_data.get(&param.key).filter(|v| match v {
+            Value::Null => false,
+            Value::String(s) => !s.trim().is_empty(),
+            _ => true,
+        });
+        let Some(submitted) = submitted else {
+            match &param.default {
+                Some(default) => values.push((param.key.clone(), default.clone())),
+                None => errors.push(format!("{} is required", param.key)),
+            }
+            continue;
+        };
+        match parse_value(param, submitted) {
+            Ok(value) => values.push((param.key.clone(), value)),
+            Err(e) => errors.push(format!("{} {}", param.key, e)),
+        }
+    }
+    if errors.is_empty() {
+        Ok(values)
+    } else {
+        Err(errors)
+    }
+}
+
+fn parse_value(param: &RecipeParameter, value: &Value) -> Result<String, String> {
+    match (&param.input_type, value) {
+        (RecipeParameterInputType::Number, Value::Number(n)) => Ok(n.to_string()),
+        (RecipeParameterInputType::Number, Value::String(s)) => s
+            .trim()
+            .parse::<f64>()
+            .map(|_| s.trim().to_string())
+            .map_err(|_| "must be a number".to_string()),
+        (RecipeParameterInputType::Number, _) => Err("must be a number".to_string()),
+        (RecipeParameterInputType::Boolean, Value::Bool(b)) => Ok(b.to_string()),
+        (RecipeParameterInputType::Boolean, Value::String(s)) => s
+            .trim()
+            .parse::<bool>()
+            .map(|b| b.to_string())
+            .map_err(|_| "must be true or false".to_string()),
+        (RecipeParameterInputType::Boolean, _) => Err("must be true or false".to_string()),
+        (_, Value::String(s)) => {
+            let s = s.trim();
+            match param.input_type {
+                RecipeParameterInputType::Date => NaiveDate::parse_from_str(s, "%Y-%m-%d")
+                    .map(|_| s.to_string())
+                    .map_err(|_| "must be a date (YYYY-MM-DD)".to_string()),
+                RecipeParameterInputType::Select => {
+                    let options = param.options.as_deref().unwrap_or_default();
+                    if options.iter().any(|o| o == s) {
+                        Ok(s.to_string())
+                    } else {
+                        Err(format!("must be one of: {}", options.join(", ")))
+                    }
+                }
+                _ => Ok(s.to_string()),
+            }
+        }
+        _ => Err("must be text".to_string()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::recipe::RecipeParameterRequirement;
+
+    fn param(
+        key: &str,
+        input_type: RecipeParameterInputType,
+        default: Option<&str>,
+    ) -> RecipeParameter {
+        RecipeParameter {
+            key: key.to_string(),
+            input_type,
+            requirement: RecipeParameterRequirement::Required,
+            description: format!("The {}", key),
+            default: default.map(str::to_string),
+            options: None,
+        }
+    }
+
+    #[test]
+    fn test_form_schema() {
+        let mut level = param("level", RecipeParameterInputType::Select, Some("info"));
+        level.options = Some(vec!["info".to_string(), "debug".to_string()]);
+        let schema = form_schema(&[
+            param("repo", RecipeParameterInputType::String, None),
+            param("retries", RecipeParameterInputType::Number, Some("3")),
+            level,
+        ]);
+
+        assert_eq!(schema["required"], json!(["repo"]));
+        assert_eq!(schema["properties"]["retries"]["type"], "number");
+        assert_eq!(schema["properties"]["retries"]["default"], json!(3.0));
+        assert_eq!(
+            schema["properties"]["level"]["enum"],
+            json!(["info", "debug"])
+        );
+        assert_eq!(schema["properties"]["repo"]["description"], "The repo");
+    }
+
+    #[test]
+    fn test_values_from_form() {
+        let params = [
+            param("repo", RecipeParameterInputType::String, None),
+            param("retries", RecipeParameterInputType::Number, Some("3")),
+            param("dry_run", RecipeParameterInputType::Boolean, None),
+            param("since", RecipeParameterInputType::Date, None),
+        ];
+
+        let values = values_from_form(
+            &params,
+            &json!({"repo": " goose ", "retries": "", "dry_run": true, "since": "2026-01-31"}),
+        )
+        .unwrap();
+        assert_eq!(
+            values,
+            vec![
+                ("repo".to_string(), "goose".to_string()),
+                ("retries".to_string(), "3".to_string()),
+                ("dry_run".to_string(), "true".to_string()),
+                ("since".to_string(), "2026-01-31".to_string()),
+            ]
+        );
+
+        let errors = values_from_form(&params, &json!({"retries": "many", "since": "31/01/2026"}))
+            .unwrap_err();
+        assert_eq!(
+            errors,
+            vec![
+                "repo is required",
+                "retries must be a number",
+                "dry_run is required",
+                "since must be a date (YYYY-MM-DD)",
+            ]
+        );
+    }
+}
@@ -0,0 +1,731 @@
+//! A local registry for managing recipes like packages.
+//!
+//! Every installed version is kept under `recipe_registry/<name>` in the data
+//! directory and pinned by the SHA-256 of its content. The active version is
+//! copied into the global recipe library, so it shows up wherever recipes are
+//! listed and runs by name. Installing validates the recipe and checks the
+//! extensions it declares under `dependencies`.
+
+use crate::agents::extension::ExtensionConfig;
+use crate::config::extensions::get_extension_by_name;
+use crate::config::paths::Paths;
+use crate::recipe::local_recipes::get_recipe_library_dir;
+use crate::recipe::validate_recipe::validate_recipe_template_from_content;
+use crate::recipe::Recipe;
+use chrono::{DateTime, Utc};
+use semver::{Version, VersionReq};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use utoipa::ToSchema;
+
+const INDEX_FILE: &str = "registry.json";
+
+/// Git URL forms that can't be read as a `git clone` option or a local path
+const GIT_URL_PREFIXES: [&str; 3] = ["https://", "ssh://", "git@"];
+
+#[derive(Debug, thiserror::Error)]
+pub enum RegistryError {
+    #[error("Recipe {0} is not installed")]
+    NotInstalled(String),
+    #[error("Version {version} of {name} is not installed")]
+    VersionNotInstalled { name: String, version: String },
+    #[error("Checksum mismatch for {name}: expected {expected}, got {actual}")]
+    ChecksumMismatch {
+        name: String,
+        expected: String,
+        actual: String,
+    },
+    #[error(
+        "{name} {version} is already installed with different content; bump the recipe version"
+    )]
+    VersionConflict { name: String, version: String },
+    #[error("{0}")]
+    InvalidVersion(String),
+    #[error("{0} was not installed by the registry; remove or rename it first")]
+    NotOwned(String),
+    #[error("Unmet dependencies: {}", .0.join("; "))]
+    UnmetDependencies(Vec<String>),
+    #[error("Invalid recipe: {0}")]
+    Invalid(anyhow::Error),
+    #[error("Invalid recipe source: {0}")]
+    InvalidSource(String),
+    #[error("Failed to fetch recipe: {0}")]
+    Fetch(anyhow::Error),
+    #[error(transparent)]
+    Io(#[from] std::io::Error),
+    #[error(transparent)]
+    Json(#[from] serde_json::Error),
+}
+
+pub type Result<T> = std::result::Result<T, RegistryError>;
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, ToSchema)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum RecipeSource {
+    Path {
+        path: String,
+    },
+    Url {
+        url: String,
+    },
+    Git {
+        url: String,
+        /// Branch or tag; defaults to the remote's default branch
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        reference: Option<String>,
+        /// Recipe file inside the repository
+        path: String,
+    },
+}
+
+impl RecipeSource {
+    /// Registry name derived from the recipe file name.
+    fn default_name(&self) -> String {
+        let location = match self {
+            RecipeSource::Path { path } | RecipeSource::Git { path, .. } => path.as_str(),
+            RecipeSource::Url { url } => url.split(['?', '#']).next().unwrap_or_default(),
+        };
+        let stem = Path::new(location)
+            .file_stem()
+            .map(|s| s.to_string_lossy().to_lowercase())
+            .unwrap_or_default();
+        stem.chars()
+            .map(|c| {
+                if c.is_ascii_alphanumeric() || c == '_' {
+                    c
+                } else {
+                    '-'
+                }
+            })
+            .collect::<String>()
+            .trim_matches('-')
+            .to_string()
+    }
+
+    async fn fetch(&self) -> Result<String> {
+        match self {
+            RecipeSource::Path { path } => Ok(fs::read_to_string(path)?),
+            RecipeSource::Url { url } => {
+                let response = reqwest::Client::new()
+                    .get(url)
+                    .send()
+                    .await
+                    .and_then(|r| r.error_for_status())
+                    .map_err(|e| RegistryError::Fetch(e.into()))?;
+                response
+                    .text()
+                    .await
+                    .map_err(|e| RegistryError::Fetch(e.into()))
+            }
+            RecipeSource::Git {
+                url,
+                reference,
+                path,
+            } => {
+                if !GIT_URL_PREFIXES
+                    .iter()
+                    .any(|prefix| url.starts_with(prefix))
+                {
+                    return Err(RegistryError::InvalidSource(
+                        "git sources must be https://, ssh:// or git@ URLs".to_string(),
+                    ));
+                }
+                if let Some(reference) = reference.as_deref().filter(|r| r.starts_with('-')) {
+                    return Err(RegistryError::InvalidSource(format!(
+                        "invalid git reference {}",
+                        reference
+                    )));
+                }
+                let checkout = tempfile::tempdir()?;
+                let mut command = tokio::process::Command::new("git");
+                command.args(["clone", "--quiet", "--depth", "1"]);
+                if let Some(reference) = reference {
+                    command.args(["--branch", reference]);
+                }
+                let output = command
+                    .arg("--")
+                    .arg(url)
+                    .arg(checkout.path())
+                    .output()
+                    .await
+                    .map_err(|e| RegistryError::Fetch(e.into()))?;
+                if !output.status.success() {
+                    return Err(RegistryError::Fetch(anyhow::anyhow!(
+                        "git clone {} failed: {}",
+                        url,
+                        String::from_utf8_lossy(&output.stderr).trim()
+                    )));
+                }
+                let root = checkout.path().canonicalize()?;
+                let file = root.join(path).canonicalize()?;
+                if !file.starts_with(&root) {
+                    return Err(RegistryError::InvalidSource(format!(
+                        "{} is outside the repository",
+                        path
+                    )));
+                }
+                Ok(fs::read_to_string(file)?)
+            }
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct InstalledVersion {
+    pub version: String,
+    /// SHA-256 of the recipe file, hex encoded
+    pub checksum: String,
+    pub source: RecipeSource,
+    pub installed_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct RegistryEntry {
+    pub name: String,
+    pub title: String,
+    pub active_version: String,
+    /// Installed versions, oldest first
+    pub versions: Vec<InstalledVersion>,
+}
+
+impl RegistryEntry {
+    fn version(&self, version: &str) -> Option<&InstalledVersion> {
+        self.versions.iter().find(|v| v.version == version)
+    }
+
+    fn active(&self) -> Option<&InstalledVersion> {
+        self.version(&self.active_version)
+    }
+}
+
+#[derive(Debug, Clone, Serialize, ToSchema)]
+pub struct InstallReport {
+    pub entry: RegistryEntry,
+    /// Where the active version was written in the recipe library
+    #[schema(value_type = String)]
+    pub path: PathBuf,
+    pub warnings: Vec<String>,
+}
+
+pub struct RecipeRegistry {
+    dir: PathBuf,
+    library_dir: PathBuf,
+}
+
+impl Default for RecipeRegistry {
+    fn default() -> Self {
+        Self::new(
+            Paths::data_dir().join("recipe_registry"),
+            get_recipe_library_dir(true),
+        )
+    }
+}
+
+impl RecipeRegistry {
+    pub fn new(dir: PathBuf, library_dir: PathBuf) -> Self {
+        Self { dir, library_dir }
+    }
+
+    fn load_index(&self) -> Result<BTreeMap<String, RegistryEntry>> {
+        let path = self.dir.join(INDEX_FILE);
+        if !path.exists() {
+            return Ok(BTreeMap::new());
+        }
+        Ok(serde_json::from_str(&fs::read_to_string(path)?)?)
+    }
+
+    fn save_index(&self, index: &BTreeMap<String, RegistryEntry>) -> Result<()> {
+        fs::create_dir_all(&self.dir)?;
+        fs::write(
+            self.dir.join(INDEX_FILE),
+            serde_json::to_string_pretty(index)?,
+        )?;
+        Ok(())
+    }
+
+    /// Versions are file names, so only well-formed semantic versions (which
+    /// can't contain separators or be `.`/`..`) are accepted.
+    fn version_path(&self, name: &str, version: &str) -> Result<PathBuf> {
+        validate_name(name)?;
+        let version = parse_version(version)?;
+        Ok(self.dir.join(name).join(format!("{}.yaml", version)))
+    }
+
+    fn library_path(&self, name: &str) -> PathBuf {
+        self.library_dir.join(format!("{}.yaml", name))
+    }
+
+    pub fn list(&self) -> Result<Vec<RegistryEntry>> {
+        Ok(self.load_index()?.into_values().collect())
+    }
+
+    pub fn get(&self, name: &str) -> Result<RegistryEntry> {
+        self.load_index()?
+            .remove(name)
+            .ok_or_else(|| RegistryError::NotInstalled(name.to_string()))
+    }
+
+    /// Installs a recipe and makes it the active version. `checksum` pins the
+    /// expected SHA-256 of the fetched file.
+    pub async fn install(
+        &self,
+        source: RecipeSource,
+        name: Option<&str>,
+        checksum: Option<&str>,
+    ) -> Result<InstallReport> {
+        let name = match name {
+            Some(name) => name.to_string(),
+            None => source.default_name(),
+        };
+        validate_name(&name)?;
+        let content = source.fetch().await?;
+        self.add_version(&name, &content, source, checksum, None)
+    }
+
+    /// Fetches the active version's source again, or switches to `version`
+    /// when it is already installed. Only moves to newer versions.
+    pub async fn upgrade(&self, name: &str, version: Option<&str>) -> Result<InstallReport> {
+        let entry = self.get(name)?;
+        let current = parse_version(&entry.active_version)?;
+
+        if let Some(target) = version {
+            if entry.version(target).is_some() {
+                if parse_version(target)? <= current {
+                    return Err(RegistryError::InvalidVersion(format!(
+                        "{} is not newer than the active version {}",
+                        target, entry.active_version
+                    )));
+                }
+                return self.switch(name, target);
+            }
+        }
+
+        let source = entry
+            .active()
+            .map(|v| v.source.clone())
+            .ok_or_else(|| RegistryError::NotInstalled(name.to_string()))?;
+        let content = source.fetch().await?;
+        self.add_version(name, &content, source, None, Some((&current, version)))
+    }
+
+    /// Switches back to an older installed version.
+    pub fn downgrade(&self, name: &str, version: &str) -> Result<InstallReport> {
+        let entry = self.get(name)?;
+        if parse_version(version)? >= parse_version(&entry.active_version)? {
+            return Err(RegistryError::InvalidVersion(format!(
+                "{} is not older than the active version {}",
+                version, entry.active_version
+            )));
+        }
+        self.switch(name, version)
+    }
+
+    /// Removes every version and the recipe library copy.
+    /// A library recipe that was edited or replaced since it was activated
+    /// is left in place.
+    pub fn uninstall(&self, name: &str) -> Result<()> {
+        validate_name(name)?;
+        let mut index = self.load_index()?;
+        let Some(entry) = index.remove(name) else {
+            return Err(RegistryError::NotInstalled(name.to_string()));
+        };
+        let versions_dir = self.dir.join(name);
+        if versions_dir.exists() {
+            fs::remove_dir_all(versions_dir)?;
+        }
+        let library_path = self.library_path(name);
+        if library_path.exists() && self.owns_library_copy(&entry)? {
+            fs::remove_file(library_path)?;
+        }
+        self.save_index(&index)
+    }
+
+    /// Whether the library recipe for `entry` is one of its installed
+    /// versions, i.e. the registry wrote it and nobody changed it since.
+    fn owns_library_copy(&self, entry: &RegistryEntry) -> Result<bool> {
+        let content = fs::read_to_string(self.library_path(&entry.name))?;
+        let checksum = sha256_hex(&content);
+        Ok(entry.versions.iter().any(|v| v.checksum == checksum))
+    }
+
+    /// `upgrade_from` is the active version and the requested target when
+    /// this is an upgrade rather than an install.
+    fn add_version(
+        &self,
+        name: &str,
+        content: &str,
+        source: RecipeSource,
+        expected_checksum: Option<&str>,
+        upgrade_from: Option<(&Version, Option<&str>)>,
+    ) -> Result<InstallReport> {
+        let checksum = sha256_hex(content);
+        if let Some(expected) = expected_checksum {
+            if !expected.trim().eq_ignore_ascii_case(&checksum) {
+                return Err(RegistryError::ChecksumMismatch {
+                    name: name.to_string(),
+                    expected: expected.trim().to_lowercase(),
+                    actual: checksum,
+                });
+            }
+        }
+
+        let recipe =
+            validate_recipe_template_from_content(content, None).map_err(RegistryError::Invalid)?;
+        if recipe.check_for_security_warnings() {
+            return Err(RegistryError::Invalid(anyhow::anyhow!(
+                "Recipe contains hidden characters"
+            )));
+        }
+        let version = parse_version(&recipe.version)?;
+
+        if let Some((current, target)) = upgrade_from {
+            if let Some(target) = target {
+                if parse_version(target)? != version {
+                    return Err(RegistryError::InvalidVersion(format!(
+                        "Source now has version {}, not {}",
+                        version, target
+                    )));
+                }
+            }
+            if version < *current {
+                return Err(RegistryError::InvalidVersion(format!(
+                    "Source has version {}, older than the active version {}",
+                    version, current
+                )));
+            }
+        }
+
+        let (errors, mut warnings) = check_dependencies(&recipe);
+        if !errors.is_empty() {
+            return Err(RegistryError::UnmetDependencies(errors));
+        }
+        if recipe
+            .sub_recipes
+            .iter()
+            .flatten()
+            .any(|sub| Path::new(&sub.path).is_relative())
+        {
+            warnings.push(
+                "Relative sub-recipe paths resolve against the recipe library, not the source"
+                    .to_string(),
+            );
+        }
+
+        let mut index = self.load_index()?;
+        let entry = index
+            .entry(name.to_string())
+            .or_insert_with(|| RegistryEntry {
+                name: name.to_string(),
+                title: recipe.title.clone(),
+                active_version: version.to_string(),
+                versions: Vec::new(),
+            });
+        match entry.version(&version.to_string()) {
+            Some(existing) if existing.checksum != checksum => {
+                return Err(RegistryError::VersionConflict {
+                    name: name.to_string(),
+                    version: version.to_string(),
+                });
+            }
+            Some(_) => warnings.push(format!("{} {} is already installed", name, version)),
+            None => {
+                let path = self.version_path(name, &version.to_string())?;
+                fs::create_dir_all(path.parent().unwrap_or(&self.dir))?;
+                fs::write(&path, content)?;
+                entry.versions.push(InstalledVersion {
+                    version: version.to_string(),
+                    checksum,
+                    source,
+                    installed_at: Utc::now(),
+                });
+                entry
+                    .versions
+                    .sort_by_cached_key(|v| Version::parse(&v.version).ok());
+            }
+        }
+        entry.title = recipe.title;
+        entry.active_version = version.to_string();
+
+        let entry = entry.clone();
+        let path = self.activate(&entry)?;
+        self.save_index(&index)?;
+        Ok(InstallReport {
+            entry,
+            path,
+            warnings,
+        })
+    }
+
+    fn switch(&self, name: &str, version: &str) -> Result<InstallReport> {
+        let mut index = self.load_index()?;
+        let entry = index
+            .get_mut(name)
+            .ok_or_else(|| RegistryError::NotInstalled(name.to_string()))?;
+        if entry.version(version).is_none() {
+            return Err(RegistryError::VersionNotInstalled {
+                name: name.to_string(),
+                version: version.to_string(),
+            });
+        }
+        entry.active_version = version.to_string();
+
+        let entry = entry.clone();
+        let path = self.activate(&entry)?;
+        self.save_index(&index)?;
+        Ok(InstallReport {
+            entry,
+            path,
+            warnings: Vec::new(),
+        })
+    }
+
+    /// Copies the active version into the recipe library after checking it
+    /// still matches its pinned checksum.
+    fn activate(&self, entry: &RegistryEntry) -> Result<PathBuf> {
+        let installed = entry
+            .active()
+            .ok_or_else(|| RegistryError::VersionNotInstalled {
+                name: entry.name.clone(),
+                version: entry.active_version.clone(),
+            })?;
+        let content = fs::read_to_string(self.version_path(&entry.name, &installed.version)?)?;
+        let actual = sha256_hex(&content);
+        if actual != installed.checksum {
+            return Err(RegistryError::ChecksumMismatch {
+                name: entry.name.clone(),
+                expected: installed.checksum.clone(),
+                actual,
+            });
+        }
+
+        fs::create_dir_all(&self.library_dir)?;
+        let path = self.library_path(&entry.name);
+        if path.exists() && !self.owns_library_copy(entry)? {
+            return Err(RegistryError::NotOwned(path.display().to_string()));
+        }
+        fs::write(&path, content)?;
+        Ok(path)
+    }
+}
+
+fn validate_name(name: &str) -> Result<()> {
+    if name.is_empty()
+        || !name
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_')
+    {
+        return Err(RegistryError::Invalid(anyhow::anyhow!(
+            "Recipe names may only contain letters, digits, '-' and '_'"
+        )));
+    }
+    Ok(())
+}
+
+fn sha256_hex(content: &str) -> String {
+    Sha256::digest(content.as_bytes())
+        .iter()
+        .map(|b| format!("{:02x}", b))
+        .collect()
+}
+
+fn parse_version(version: &str) -> Result<Version> {
+    Version::parse(version).map_err(|e| {
+        RegistryError::InvalidVersion(format!("'{}' is not a semantic version: {}", version, e))
+    })
+}
+
+/// Errors for dependencies that are missing or fail their constraint, and
+/// warnings for constraints that can only be checked once the extension runs.
+fn check_dependencies(recipe: &Recipe) -> (Vec<String>, Vec<String>) {
+    let mut errors = Vec::new();
+    let mut warnings = Vec::new();
+    for dependency in recipe.dependencies.iter().flatten() {
+        let requirement = match dependency.version.as_deref().map(VersionReq::parse) {
+            Some(Ok(requirement)) => Some(requirement),
+            Some(Err(e)) => {
+                errors.push(format!(
+                    "{}: invalid version requirement: {}",
+                    dependency.name, e
+                ));
+                continue;
+            }
+            None => None,
+        };
+        let extension = recipe
+            .extensions
+            .iter()
+            .flatten()
+            .find(|e| e.name() == dependency.name)
+            .cloned()
+            .or_else(|| get_extension_by_name(&dependency.name));
+        let Some(extension) = extension else {
+            errors.push(format!(
+                "{} is neither configured nor provided by the recipe",
+                dependency.name
+            ));
+            continue;
+        };
+        let Some(requirement) = requirement else {
+            continue;
+        };
+        match extension_version(&extension) {
+            Some(version) if requirement.matches(&version) => {}
+            Some(version) => errors.push(format!(
+                "{} {} does not satisfy {}",
+                dependency.name, version, requirement
+            )),
+            None => warnings.push(format!(
+                "{} reports its version only once it starts; {} is not checked",
+                dependency.name, requirement
+            )),
+        }
+    }
+    (errors, warnings)
+}
+
+/// Builtin and platform extensions ship with goose and share its version.
+fn extension_version(extension: &ExtensionConfig) -> Option<Version> {
+    match extension {
+        ExtensionConfig::Builtin { .. } | ExtensionConfig::Platform { .. } => {
+            Version::parse(env!("CARGO_PKG_VERSION")).ok()
+        }
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    fn recipe_file(dir: &Path, version: &str) -> RecipeSource {
+        let path = dir.join("code-review.yaml");
+        fs::write(
+            &path,
+            format!(
+                "version: {}\ntitle: Code review\ndescription: Review a diff\nprompt: Review the staged changes\n",
+                version
+            ),
+        )
+        .unwrap();
+        RecipeSource::Path {
+            path: path.display().to_string(),
+        }
+    }
+
+    fn registry(temp_dir: &TempDir) -> RecipeRegistry {
+        RecipeRegistry::new(
+            temp_dir.path().join("registry"),
+            temp_dir.path().join("library"),
+        )
+    }
+
+    #[tokio::test]
+    async fn test_install_upgrade_downgrade() {
+        let temp_dir = TempDir::new().unwrap();
+        let registry = registry(&temp_dir);
+
+        let source = recipe_file(temp_dir.path(), "1.0.0");
+        let report = registry.install(source.clone(), None, None).await.unwrap();
+        assert_eq!(report.entry.name, "code-review");
+        assert_eq!(report.entry.active_version, "1.0.0");
+        assert!(report.path.ends_with("library/code-review.yaml"));
+
+        recipe_file(temp_dir.path(), "1.1.0");
+        let report = registry.upgrade("code-review", None).await.unwrap();
+        assert_eq!(report.entry.active_version, "1.1.0");
+        assert_eq!(report.entry.versions.len(), 2);
+        assert!(fs::read_to_string(&report.path).unwrap().contains("1.1.0"));
+
+        let report = registry.downgrade("code-review", "1.0.0").unwrap();
+        assert_eq!(report.entry.active_version, "1.0.0");
+        assert!(fs::read_to_string(&report.path).unwrap().contains("1.0.0"));
+        assert!(matches!(
+            registry.downgrade("code-review", "1.1.0"),
+            Err(RegistryError::InvalidVersion(_))
+        ));
+
+        registry.uninstall("code-review").unwrap();
+        assert!(registry.list().unwrap().is_empty());
+        assert!(!report.path.exists());
+    }
+
+    #[tokio::test]
+    async fn test_checksum_pinning() {
+        let temp_dir = TempDir::new().unwrap();
+        let registry = registry(&temp_dir);
+        let source = recipe_file(temp_dir.path(), "1.0.0");
+
+        let err = registry
+            .install(source.clone(), None, Some("deadbeef"))
+            .await
+            .unwrap_err();
+        assert!(matches!(err, RegistryError::ChecksumMismatch { .. }));
+
+        let content = source.fetch().await.unwrap();
+        registry
+            .install(source.clone(), None, Some(&sha256_hex(&content)))
+            .await
+            .unwrap();
+
+        // Same version, different content
+        let RecipeSource::Path { path } = &source else {
+            unreachable!()
+        };
+        fs::write(path, content.replace("staged", "unstaged")).unwrap();
+        let err = registry.install(source, None, None).await.unwrap_err();
+        assert!(matches!(err, RegistryError::VersionConflict { .. }));
+    }
+
+    #[tokio::test]
+    async fn test_rejects_unsafe_sources_and_foreign_recipes() {
+        let temp_dir = TempDir::new().unwrap();
+        let registry = registry(&temp_dir);
+
+        for url in ["--upload-pack=touch /tmp/pwned", "/etc", "file:///etc"] {
+            let source = RecipeSource::Git {
+                url: url.to_string(),
+                reference: None,
+                path: "recipe.yaml".to_string(),
+            };
+            assert!(matches!(
+                source.fetch().await,
+                Err(RegistryError::InvalidSource(_))
+            ));
+        }
+        assert!(registry.version_path("code-review", "..").is_err());
+
+        // A recipe the user saved themselves is neither replaced nor removed
+        let library_recipe = temp_dir.path().join("library/code-review.yaml");
+        fs::create_dir_all(library_recipe.parent().unwrap()).unwrap();
+        fs::write(&library_recipe, "title: mine").unwrap();
+        let source = recipe_file(temp_dir.path(), "1.0.0");
+        let err = registry.install(source, None, None).await.unwrap_err();
+        assert!(matches!(err, RegistryError::NotOwned(_)));
+        assert_eq!(fs::read_to_string(&library_recipe).unwrap(), "title: mine");
+    }
+
+    #[test]
+    fn test_dependencies() {
+        let recipe = Recipe::from_content(
+            r#"
+title: Needs extensions
+description: d
+prompt: p
+dependencies:
+  - name: not-configured-anywhere
+  - name: bad
+    version: "not a requirement"
+"#,
+        )
+        .unwrap();
+        let (errors, warnings) = check_dependencies(&recipe);
+        assert_eq!(errors.len(), 2);
+        assert!(errors[0].contains("not-configured-anywhere"));
+        assert!(errors[1].contains("invalid version requirement"));
+        assert!(warnings.is_empty());
+    }
+}
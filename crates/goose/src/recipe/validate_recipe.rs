@@ -45,6 +45,7 @@ pub fn validate_recipe_template_from_content(
 
     validate_prompt_or_instructions(&recipe)?;
     validate_retry_config(&recipe)?;
+    validate_validation_gates(&recipe)?;
     if let Some(response) = &recipe.response {
         if let Some(json_schema) = &response.json_schema {
             validate_json_schema(json_schema)?;
@@ -66,6 +67,20 @@ fn validate_retry_config(recipe: &Recipe) -> Result<()> {
     Ok(())
 }
 
+fn validate_validation_gates(recipe: &Recipe) -> Result<()> {
+    let Some(validation) = &recipe.validation else {
+        return Ok(());
+    };
+    if recipe.response.is_none() {
+        return Err(anyhow::anyhow!(
+            "Invalid validation: gates run when the final output is collected, so the recipe needs a response schema"
+        ));
+    }
+    validation
+        .validate()
+        .map_err(|e| anyhow::anyhow!("Invalid validation: {}", e))
+}
+
 fn validate_prompt_or_instructions(recipe: &Recipe) -> Result<()> {
     let has_instructions = recipe
         .instructions
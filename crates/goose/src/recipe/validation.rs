@@ -0,0 +1,280 @@
+//! Completion gates for recipes.
+//!
+//! A recipe's `validation` section lists what must hold before its final
+//! output is accepted:
+//!
+//! ```yaml
+//! validation:
+//!   commands:
+//!     - command: cargo test
+//!     - command: ./scripts/lint.sh
+//!       expected_exit_codes: [0, 2]
+//!   files:
+//!     - path: dist/report.html
+//!   quality:
+//!     - command: cargo llvm-cov --summary-only
+//!       pattern: 'TOTAL.* (\d+\.\d+)%'
+//!       min: 80
+//! ```
+//!
+//! The gates run in the session's working directory when the agent calls
+//! `final_output`. Failures go back to the agent as the tool's error, so it
+//! keeps working until they pass or its final output retries run out.
+
+use crate::agents::retry::execute_shell_command_in;
+use crate::agents::types::DEFAULT_RETRY_TIMEOUT_SECONDS;
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use std::path::Path;
+use std::time::Duration;
+use utoipa::ToSchema;
+
+/// Output kept from a failing command, from its end
+const OUTPUT_TAIL_CHARS: usize = 1500;
+
+fn default_exit_codes() -> Vec<i32> {
+    vec![0]
+}
+
+fn default_true() -> bool {
+    true
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, ToSchema)]
+pub struct CommandGate {
+    pub command: String,
+    #[serde(default = "default_exit_codes")]
+    pub expected_exit_codes: Vec<i32>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, ToSchema)]
+pub struct FileGate {
+    /// Relative to the working directory unless absolute
+    pub path: String,
+    /// Whether the file must exist, or must not
+    #[serde(default = "default_true")]
+    pub exists: bool,
+}
+
+/// A number read from a command's output and held to a threshold.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, ToSchema)]
+pub struct QualityGate {
+    pub command: String,
+    /// Regex whose first capture group is the number
+    pub pattern: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub min: Option<f64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub max: Option<f64>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Default, PartialEq, ToSchema)]
+pub struct Validation {
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub commands: Vec<CommandGate>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub files: Vec<FileGate>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub quality: Vec<QualityGate>,
+    /// Seconds each command may run (default: 300)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub timeout_seconds: Option<u64>,
+}
+
+fn tail(text: &str) -> &str {
+    let text = text.trim();
+    match text.char_indices().rev().nth(OUTPUT_TAIL_CHARS) {
+        Some((index, _)) => &text[index..],
+        None => text,
+    }
+}
+
+impl Validation {
+    pub fn validate(&self) -> Result<(), String> {
+        if self.timeout_seconds == Some(0) {
+            return Err("timeout_seconds must be greater than 0 if specified".to_string());
+        }
+        for gate in &self.commands {
+            if gate.command.trim().is_empty() {
+                return Err("command gates need a command".to_string());
+            }
+            if gate.expected_exit_codes.is_empty() {
+                return Err(format!("'{}' has no expected exit codes", gate.command));
+            }
+        }
+        for gate in &self.quality {
+            let regex = Regex::new(&gate.pattern)
+                .map_err(|e| format!("Invalid pattern '{}': {}", gate.pattern, e))?;
+            if regex.captures_len() < 2 {
+                return Err(format!(
+                    "Pattern '{}' needs a capture group for the number",
+                    gate.pattern
+                ));
+            }
+            if gate.min.is_none() && gate.max.is_none() {
+                return Err(format!("'{}' needs a min or a max", gate.command));
+            }
+        }
+        Ok(())
+    }
+
+    fn timeout(&self) -> Duration {
+        Duration::from_secs(
+            self.timeout_seconds
+                .unwrap_or(DEFAULT_RETRY_TIMEOUT_SECONDS),
+        )
+    }
+
+    /// Runs every gate in `working_dir` and describes each one that failed.
+    pub async fn run(&self, working_dir: &Path) -> Vec<String> {
+        let mut failures = Vec::new();
+
+        for gate in &self.commands {
+            match execute_shell_command_in(&gate.command, self.timeout(), Some(working_dir)).await {
+                Ok(output) => {
+                    let code = output.status.code().unwrap_or(-1);
+                    if !gate.expected_exit_codes.contains(&code) {
+                        failures.push(format!(
+                            "`{}` exited with {} (expected {:?}):\n{}\n{}",
+                            gate.command,
+                            code,
+                            gate.expected_exit_codes,
+                            tail(&String::from_utf8_lossy(&output.stdout)),
+                            tail(&String::from_utf8_lossy(&output.stderr)),
+                        ));
+                    }
+                }
+                Err(e) => failures.push(format!("`{}` could not run: {}", gate.command, e)),
+            }
+        }
+
+        for gate in &self.files {
+            let path = working_dir.join(&gate.path);
+            match (gate.exists, path.exists()) {
+                (true, false) => failures.push(format!("{} does not exist", gate.path)),
+                (false, true) => failures.push(format!("{} must not exist", gate.path)),
+                _ => {}
+            }
+        }
+
+        for gate in &self.quality {
+            let output =
+                match execute_shell_command_in(&gate.command, self.timeout(), Some(working_dir))
+                    .await
+                {
+                    Ok(output) => output,
+                    Err(e) => {
+                        failures.push(format!("`{}` could not run: {}", gate.command, e));
+                        continue;
+                    }
+                };
+            let text = format!(
+                "{}\n{}",
+                String::from_utf8_lossy(&output.stdout),
+                String::from_utf8_lossy(&output.stderr)
+            );
+            if let Some(failure) = gate.check(&text) {
+                failures.push(failure);
+            }
+        }
+
+        failures
+    }
+}
+
+impl QualityGate {
+    /// Why `output` misses this gate, if it does.
+    pub fn check(&self, output: &str) -> Option<String> {
+        let regex = match Regex::new(&self.pattern) {
+            Ok(regex) => regex,
+            Err(e) => return Some(format!("Invalid pattern '{}': {}", self.pattern, e)),
+        };
+        let Some(value) = regex
+            .captures(output)
+            .and_then(|c| c.get(1))
+            .and_then(|m| m.as_str().parse::<f64>().ok())
+        else {
+            return Some(format!(
+                "`{}` printed no number matching '{}':\n{}",
+                self.command,
+                self.pattern,
+                tail(output)
+            ));
+        };
+        if let Some(min) = self.min.filter(|min| value < *min) {
+            return Some(format!(
+                "`{}` measured {}, below the minimum of {}",
+                self.command, value, min
+            ));
+        }
+        if let Some(max) = self.max.filter(|max| value > *max) {
+            return Some(format!(
+                "`{}` measured {}, above the maximum of {}",
+                self.command, value, max
+            ));
+        }
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_and_quality_threshold() {
+        let validation: Validation = serde_yaml::from_str(
+            r#"
+commands:
+  - command: cargo test
+files:
+  - path: dist/report.html
+quality:
+  - command: coverage
+    pattern: 'TOTAL.* (\d+\.\d+)%'
+    min: 80
+"#,
+        )
+        .unwrap();
+        assert_eq!(validation.commands[0].expected_exit_codes, vec![0]);
+        assert!(validation.files[0].exists);
+        assert_eq!(validation.validate(), Ok(()));
+
+        let gate = &validation.quality[0];
+        assert_eq!(gate.check("TOTAL   120   12  91.50%"), None);
+        assert!(gate
+            .check("TOTAL   120   40  66.67%")
+            .unwrap()
+            .contains("below the minimum of 80"));
+        assert!(gate.check("no summary").is_some());
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn test_run_reports_failing_gates() {
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::write(dir.path().join("present.txt"), "").unwrap();
+        let validation = Validation {
+            commands: vec![
+                CommandGate {
+                    command: "exit 3".to_string(),
+                    expected_exit_codes: vec![0],
+                },
+                CommandGate {
+                    command: "test -f present.txt".to_string(),
+                    expected_exit_codes: vec![0],
+                },
+            ],
+            files: vec![FileGate {
+                path: "missing.txt".to_string(),
+                exists: true,
+            }],
+            ..Default::default()
+        };
+
+        let failures = validation.run(dir.path()).await;
+        assert_eq!(failures.len(), 2);
+        assert!(failures[0].starts_with("`exit 3` exited with 3"));
+        assert_eq!(failures[1], "missing.txt does not exist");
+    }
+}
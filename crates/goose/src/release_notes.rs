@@ -0,0 +1,278 @@
+//! Release notes written for different readers from a Keep a Changelog file.
+//!
+//! Each audience gets its own pass through the `release_notes.md` template,
+//! so users can override the wording like any other prompt.
+
+use crate::conversation::message::Message;
+use crate::prompt_template::render_template;
+use crate::providers::base::Provider;
+use anyhow::{anyhow, Result};
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::str::FromStr;
+
+/// Sections whose items break existing setups.
+const BREAKING_SECTIONS: &[&str] = &["breaking", "breaking changes", "removed"];
+const BREAKING_MARKER: &str = "BREAKING";
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct ChangelogSection {
+    pub heading: String,
+    pub items: Vec<String>,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct ChangelogEntry {
+    pub version: String,
+    pub date: Option<String>,
+    /// Paragraph text between the version heading and its first section
+    pub summary: Vec<String>,
+    pub sections: Vec<ChangelogSection>,
+}
+
+impl ChangelogEntry {
+    pub fn breaking_changes(&self) -> Vec<&str> {
+        self.sections
+            .iter()
+            .flat_map(|section| {
+                let breaking_section =
+                    BREAKING_SECTIONS.contains(&section.heading.to_lowercase().as_str());
+                section
+                    .items
+                    .iter()
+                    .filter(move |item| breaking_section || item.contains(BREAKING_MARKER))
+                    .map(String::as_str)
+            })
+            .collect()
+    }
+}
+
+/// Parses `## [version] - date` entries and their `###` sections. Headings
+/// at or above an entry's level that are not versions end the entry.
+pub fn parse_changelog(markdown: &str) -> Vec<ChangelogEntry> {
+    let mut entries: Vec<ChangelogEntry> = Vec::new();
+    let mut entry_level: Option<usize> = None;
+
+    for line in markdown.lines() {
+        let trimmed = line.trim();
+        let level = trimmed.chars().take_while(|c| *c == '#').count();
+        if level > 0 && trimmed[level..].starts_with(' ') {
+            let heading = trimmed[level..].trim();
+            if let Some(rest) = heading.strip_prefix('[') {
+                let (version, tail) = rest.split_once(']').unwrap_or((rest, ""));
+                let date = tail
+                    .trim()
+                    .trim_start_matches(['-', '–'])
+                    .trim()
+                    .to_string();
+                entries.push(ChangelogEntry {
+                    version: version.trim().to_string(),
+                    date: (!date.is_empty()).then_some(date),
+                    ..Default::default()
+                });
+                entry_level = Some(level);
+            } else if entry_level.is_some_and(|entry_level| level > entry_level) {
+                if let Some(entry) = entries.last_mut() {
+                    entry.sections.push(ChangelogSection {
+                        heading: heading.to_string(),
+                        items: Vec::new(),
+                    });
+                }
+            } else {
+                entry_level = None;
+            }
+            continue;
+        }
+
+        if entry_level.is_none() || trimmed.is_empty() || trimmed == "---" {
+            continue;
+        }
+        let Some(entry) = entries.last_mut() else {
+            continue;
+        };
+        let item = trimmed
+            .strip_prefix("- ")
+            .or_else(|| trimmed.strip_prefix("* "));
+        match (item, entry.sections.last_mut()) {
+            (Some(item), Some(section)) => section.items.push(item.trim().to_string()),
+            (Some(item), None) => entry.summary.push(item.trim().to_string()),
+            (None, Some(section)) if !section.items.is_empty() => {
+                // Continuation of the previous bullet
+                if let Some(last) = section.items.last_mut() {
+                    last.push(' ');
+                    last.push_str(trimmed);
+                }
+            }
+            (None, _) => entry.summary.push(trimmed.to_string()),
+        }
+    }
+    entries
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Audience {
+    /// Highlights in plain language
+    EndUser,
+    /// Full changelog with technical detail
+    Developer,
+    /// Steps to move past breaking changes; skipped when there are none
+    Migration,
+}
+
+impl Audience {
+    pub const ALL: [Audience; 3] = [Audience::EndUser, Audience::Developer, Audience::Migration];
+
+    fn slug(&self) -> &'static str {
+        match self {
+            Audience::EndUser => "end-user",
+            Audience::Developer => "developer",
+            Audience::Migration => "migration",
+        }
+    }
+}
+
+impl fmt::Display for Audience {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.slug())
+    }
+}
+
+impl FromStr for Audience {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        Audience::ALL
+            .into_iter()
+            .find(|audience| audience.slug() == s.trim())
+            .ok_or_else(|| {
+                anyhow!(
+                    "Unknown audience '{}'; use end-user, developer or migration",
+                    s
+                )
+            })
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct ReleaseNotes {
+    pub audience: Audience,
+    pub version: String,
+    pub content: String,
+}
+
+impl ReleaseNotes {
+    /// File name for these notes, e.g. `release-notes-1.2.0-end-user.md`.
+    pub fn file_name(&self) -> String {
+        format!("release-notes-{}-{}.md", self.version, self.audience)
+    }
+}
+
+#[derive(Serialize)]
+struct TemplateContext<'a> {
+    audience: Audience,
+    entry: &'a ChangelogEntry,
+    breaking_changes: Vec<&'a str>,
+}
+
+/// Writes notes for each audience, one model call per audience.
+pub async fn generate_release_notes(
+    provider: &dyn Provider,
+    entry: &ChangelogEntry,
+    audiences: &[Audience],
+) -> Result<Vec<ReleaseNotes>> {
+    let breaking_changes = entry.breaking_changes();
+    let mut notes = Vec::new();
+    for &audience in audiences {
+        if audience == Audience::Migration && breaking_changes.is_empty() {
+            continue;
+        }
+        let system = render_template(
+            "release_notes.md",
+            &TemplateContext {
+                audience,
+                entry,
+                breaking_changes: breaking_changes.clone(),
+            },
+        )?;
+        let request = Message::user().with_text(format!(
+            "Write the {} release notes for version {}.",
+            audience, entry.version
+        ));
+        let (message, _) = provider
+            .complete_with_model(None, &provider.get_model_config(), &system, &[request], &[])
+            .await?;
+        notes.push(ReleaseNotes {
+            audience,
+            version: entry.version.clone(),
+            content: message.as_concat_text().trim().to_string(),
+        });
+    }
+    Ok(notes)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const CHANGELOG: &str = "# Changelog
+
+## [Unreleased]
+
+### Added
+- Maintenance windows for restarts
+- Heartbeats with the supervisor
+
+---
+
+## Release History
+
+### [1.24.0] - 2026-02-07
+
+**Phase 1 Complete**
+
+#### Removed
+- Legacy `goose-server` flags
+#### Changed
+- BREAKING: config moved to `~/.config/goose`
+- Faster startup,
+  especially on Windows
+";
+
+    #[test]
+    fn test_parse_changelog() {
+        let entries = parse_changelog(CHANGELOG);
+        assert_eq!(entries.len(), 2);
+
+        let unreleased = &entries[0];
+        assert_eq!(unreleased.version, "Unreleased");
+        assert_eq!(unreleased.date, None);
+        assert_eq!(unreleased.sections.len(), 1);
+        assert_eq!(unreleased.sections[0].items.len(), 2);
+        assert!(unreleased.breaking_changes().is_empty());
+
+        let release = &entries[1];
+        assert_eq!(release.version, "1.24.0");
+        assert_eq!(release.date.as_deref(), Some("2026-02-07"));
+        assert_eq!(release.summary, vec!["**Phase 1 Complete**"]);
+        assert_eq!(
+            release.sections[1].items[1],
+            "Faster startup, especially on Windows"
+        );
+        assert_eq!(
+            release.breaking_changes(),
+            vec![
+                "Legacy `goose-server` flags",
+                "BREAKING: config moved to `~/.config/goose`"
+            ]
+        );
+    }
+
+    #[test]
+    fn test_audience_round_trip() {
+        for audience in Audience::ALL {
+            assert_eq!(audience.to_string().parse::<Audience>().unwrap(), audience);
+        }
+        assert!("marketing".parse::<Audience>().is_err());
+    }
+}
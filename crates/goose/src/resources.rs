@@ -0,0 +1,531 @@
+//! Resource-aware scheduling for local models and heavy tools.
+//!
+//! A local model and a large build running at once make the machine thrash.
+//! Calls to local-model providers and known-heavy tool calls (builds, test
+//! suites, indexing) take a permit first. A permit is granted when:
+//! - fewer than the allowed number of the same kind are running, and
+//! - the machine has room for it, or nothing goose started is running
+//!   (so load from other programs never blocks goose outright)
+//!
+//! Otherwise the call queues until a permit is released or a fresh sample
+//! shows room. After `GOOSE_RESOURCE_QUEUE_TIMEOUT` seconds it runs anyway.
+//!
+//! Room means a 1-minute load average per core under
+//! `GOOSE_RESOURCE_MAX_CPU_LOAD`, at least `GOOSE_RESOURCE_MIN_FREE_MEMORY_MB`
+//! of available memory and, for local models when a GPU is visible through
+//! `nvidia-smi`, at least `GOOSE_RESOURCE_MIN_FREE_VRAM_MB` of free VRAM.
+//! Measurements that are unavailable on the platform are not checked.
+
+use crate::config::Config;
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex, OnceLock};
+use std::time::{Duration, Instant};
+use tokio::sync::Notify;
+use utoipa::ToSchema;
+
+/// Providers that run the model on this machine
+pub const LOCAL_PROVIDERS: &[&str] = &["ollama", "lmstudio"];
+/// Shell commands that keep every core busy for a while
+const HEAVY_COMMANDS: &[&str] = &[
+    "cargo build",
+    "cargo test",
+    "cargo clippy",
+    "npm run build",
+    "npm test",
+    "pnpm build",
+    "yarn build",
+    "make",
+    "cmake --build",
+    "gradle",
+    "./gradlew",
+    "mvn",
+    "go build",
+    "go test",
+    "docker build",
+    "bazel",
+];
+/// Tool names, after the extension prefix, that index a codebase
+const HEAVY_TOOLS: &[&str] = &["index", "reindex", "build_index", "index_codebase"];
+/// How long a sample is reused before the machine is measured again
+const SAMPLE_TTL: Duration = Duration::from_secs(2);
+const DEFAULT_QUEUE_TIMEOUT_SECS: u64 = 10 * 60;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum Workload {
+    LocalModel,
+    HeavyTool,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, ToSchema)]
+pub struct GpuStatus {
+    pub name: String,
+    pub vram_used_mb: u64,
+    pub vram_total_mb: u64,
+}
+
+impl GpuStatus {
+    pub fn vram_free_mb(&self) -> u64 {
+        self.vram_total_mb.saturating_sub(self.vram_used_mb)
+    }
+}
+
+/// One measurement of the machine; `None` where the platform cannot tell.
+#[derive(Debug, Clone, PartialEq, Serialize, ToSchema)]
+pub struct ResourceSample {
+    /// 1-minute load average divided by the number of cores
+    pub cpu_load: Option<f64>,
+    pub memory_available_mb: Option<u64>,
+    pub memory_total_mb: Option<u64>,
+    pub gpus: Vec<GpuStatus>,
+    pub sampled_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, ToSchema)]
+pub struct ResourceLimits {
+    pub max_cpu_load: f64,
+    pub min_free_memory_mb: u64,
+    pub min_free_vram_mb: u64,
+    pub max_local_model_calls: usize,
+    pub max_heavy_tools: usize,
+}
+
+impl Default for ResourceLimits {
+    fn default() -> Self {
+        Self {
+            max_cpu_load: 0.9,
+            min_free_memory_mb: 2048,
+            min_free_vram_mb: 1024,
+            max_local_model_calls: 1,
+            max_heavy_tools: 1,
+        }
+    }
+}
+
+impl ResourceLimits {
+    pub fn from_config() -> Self {
+        let config = Config::global();
+        let defaults = Self::default();
+        Self {
+            max_cpu_load: config
+                .get_param("GOOSE_RESOURCE_MAX_CPU_LOAD")
+                .unwrap_or(defaults.max_cpu_load),
+            min_free_memory_mb: config
+                .get_param("GOOSE_RESOURCE_MIN_FREE_MEMORY_MB")
+                .unwrap_or(defaults.min_free_memory_mb),
+            min_free_vram_mb: config
+                .get_param("GOOSE_RESOURCE_MIN_FREE_VRAM_MB")
+                .unwrap_or(defaults.min_free_vram_mb),
+            max_local_model_calls: config
+                .get_param("GOOSE_RESOURCE_MAX_LOCAL_MODEL_CALLS")
+                .unwrap_or(defaults.max_local_model_calls),
+            max_heavy_tools: config
+                .get_param("GOOSE_RESOURCE_MAX_HEAVY_TOOLS")
+                .unwrap_or(defaults.max_heavy_tools),
+        }
+    }
+
+    fn max_running(&self, workload: Workload) -> usize {
+        match workload {
+            Workload::LocalModel => self.max_local_model_calls,
+            Workload::HeavyTool => self.max_heavy_tools,
+        }
+        .max(1)
+    }
+}
+
+#[derive(Debug, Clone, Serialize, ToSchema)]
+pub struct ResourceStatus {
+    pub sample: ResourceSample,
+    pub limits: ResourceLimits,
+    pub running_local_model_calls: usize,
+    pub running_heavy_tools: usize,
+    /// Calls waiting for a permit
+    pub queued: usize,
+    /// Why the machine has no room right now; empty when it has
+    pub constrained: Vec<String>,
+}
+
+/// Why `sample` leaves no room for `workload`, if it does not.
+pub fn constraints(
+    workload: Workload,
+    sample: &ResourceSample,
+    limits: &ResourceLimits,
+) -> Vec<String> {
+    let mut reasons = Vec::new();
+    if let Some(load) = sample.cpu_load.filter(|l| *l > limits.max_cpu_load) {
+        reasons.push(format!(
+            "CPU load {:.2} per core is above {:.2}",
+            load, limits.max_cpu_load
+        ));
+    }
+    if let Some(free) = sample
+        .memory_available_mb
+        .filter(|m| *m < limits.min_free_memory_mb)
+    {
+        reasons.push(format!(
+            "{} MB of memory free, {} MB needed",
+            free, limits.min_free_memory_mb
+        ));
+    }
+    if workload == Workload::LocalModel {
+        if let Some(free) = sample
+            .gpus
+            .iter()
+            .map(GpuStatus::vram_free_mb)
+            .max()
+            .filter(|v| *v < limits.min_free_vram_mb)
+        {
+            reasons.push(format!(
+                "{} MB of VRAM free, {} MB needed",
+                free, limits.min_free_vram_mb
+            ));
+        }
+    }
+    reasons
+}
+
+pub fn is_local_provider(name: &str) -> bool {
+    LOCAL_PROVIDERS.contains(&name)
+}
+
+/// A permit for a call to `provider`, when it runs the model on this machine.
+pub async fn acquire_for_provider(provider: &str) -> Option<ResourcePermit> {
+    if !is_local_provider(provider) {
+        return None;
+    }
+    Some(global().acquire(Workload::LocalModel).await)
+}
+
+/// Whether a tool call is known to be heavy: a build or test shell command,
+/// or an indexing tool.
+pub fn is_heavy_tool(tool_name: &str, shell_command: Option<&str>) -> bool {
+    let name = tool_name.rsplit("__").next().unwrap_or(tool_name);
+    if HEAVY_TOOLS.contains(&name) {
+        return true;
+    }
+    shell_command.is_some_and(|command| {
+        command
+            .split(['&', ';', '|', '\n'])
+            .map(str::trim)
+            .any(|part| {
+                HEAVY_COMMANDS.iter().any(|heavy| {
+                    part == *heavy
+                        || part
+                            .strip_prefix(heavy)
+                            .is_some_and(|rest| rest.starts_with(' '))
+                })
+            })
+    })
+}
+
+#[derive(Default)]
+struct Usage {
+    running: HashMap<Workload, usize>,
+    queued: usize,
+}
+
+impl Usage {
+    fn running(&self, workload: Workload) -> usize {
+        self.running.get(&workload).copied().unwrap_or(0)
+    }
+
+    fn total(&self) -> usize {
+        self.running.values().sum()
+    }
+}
+
+pub struct ResourceManager {
+    limits: ResourceLimits,
+    queue_timeout: Duration,
+    usage: Mutex<Usage>,
+    released: Notify,
+    last_sample: tokio::sync::Mutex<Option<(Instant, ResourceSample)>>,
+}
+
+static RESOURCES: OnceLock<Arc<ResourceManager>> = OnceLock::new();
+
+pub fn global() -> &'static Arc<ResourceManager> {
+    RESOURCES.get_or_init(|| {
+        let queue_timeout = Config::global()
+            .get_param::<u64>("GOOSE_RESOURCE_QUEUE_TIMEOUT")
+            .unwrap_or(DEFAULT_QUEUE_TIMEOUT_SECS);
+        Arc::new(ResourceManager::new(
+            ResourceLimits::from_config(),
+            Duration::from_secs(queue_timeout),
+        ))
+    })
+}
+
+/// Held while a gated call runs; releasing it lets the next one in.
+pub struct ResourcePermit {
+    manager: Arc<ResourceManager>,
+    workload: Workload,
+}
+
+impl Drop for ResourcePermit {
+    fn drop(&mut self) {
+        if let Some(count) = self
+            .manager
+            .usage
+            .lock()
+            .unwrap()
+            .running
+            .get_mut(&self.workload)
+        {
+            *count = count.saturating_sub(1);
+        }
+        self.manager.released.notify_waiters();
+    }
+}
+
+impl ResourceManager {
+    pub fn new(limits: ResourceLimits, queue_timeout: Duration) -> Self {
+        Self {
+            limits,
+            queue_timeout,
+            usage: Mutex::new(Usage::default()),
+            released: Notify::new(),
+            last_sample: tokio::sync::Mutex::new(None),
+        }
+    }
+
+    /// The latest measurement, taken again once it is older than a couple
+    /// of seconds.
+    pub async fn sample(&self) -> ResourceSample {
+        let mut last = self.last_sample.lock().await;
+        if let Some((at, sample)) = last.as_ref() {
+            if at.elapsed() < SAMPLE_TTL {
+                return sample.clone();
+            }
+        }
+        let sample = measure().await;
+        *last = Some((Instant::now(), sample.clone()));
+        sample
+    }
+
+    pub async fn status(&self) -> ResourceStatus {
+        let sample = self.sample().await;
+        let constrained = constraints(Workload::LocalModel, &sample, &self.limits);
+        let usage = self.usage.lock().unwrap();
+        ResourceStatus {
+            limits: self.limits.clone(),
+            running_local_model_calls: usage.running(Workload::LocalModel),
+            running_heavy_tools: usage.running(Workload::HeavyTool),
+            queued: usage.queued,
+            constrained,
+            sample,
+        }
+    }
+
+    /// Takes a permit if `workload` may start now.
+    fn try_admit(
+        self: &Arc<Self>,
+        workload: Workload,
+        sample: &ResourceSample,
+    ) -> Option<ResourcePermit> {
+        let mut usage = self.usage.lock().unwrap();
+        if usage.running(workload) >= self.limits.max_running(workload) {
+            return None;
+        }
+        if usage.total() > 0 && !constraints(workload, sample, &self.limits).is_empty() {
+            return None;
+        }
+        *usage.running.entry(workload).or_default() += 1;
+        Some(ResourcePermit {
+            manager: self.clone(),
+            workload,
+        })
+    }
+
+    fn force_admit(self: &Arc<Self>, workload: Workload) -> ResourcePermit {
+        *self
+            .usage
+            .lock()
+            .unwrap()
+            .running
+            .entry(workload)
+            .or_default() += 1;
+        ResourcePermit {
+            manager: self.clone(),
+            workload,
+        }
+    }
+
+    /// Waits for room to run `workload`, up to the queue timeout.
+    pub async fn acquire(self: &Arc<Self>, workload: Workload) -> ResourcePermit {
+        let sample = self.sample().await;
+        if let Some(permit) = self.try_admit(workload, &sample) {
+            return permit;
+        }
+
+        self.usage.lock().unwrap().queued += 1;
+        tracing::info!("Queueing {:?} until resources free up", workload);
+        let deadline = Instant::now() + self.queue_timeout;
+        let permit = loop {
+            let released = self.released.notified();
+            let wait = deadline.saturating_duration_since(Instant::now());
+            if wait.is_zero() {
+                tracing::warn!(
+                    "{:?} waited {}s for resources; running it anyway",
+                    workload,
+                    self.queue_timeout.as_secs()
+                );
+                break self.force_admit(workload);
+            }
+            let _ = tokio::time::timeout(wait.min(SAMPLE_TTL), released).await;
+            let sample = self.sample().await;
+            if let Some(permit) = self.try_admit(workload, &sample) {
+                break permit;
+            }
+        };
+        self.usage.lock().unwrap().queued -= 1;
+        permit
+    }
+}
+
+/// Measures the machine. Linux reads /proc; GPUs are found with nvidia-smi
+/// wherever it is installed.
+async fn measure() -> ResourceSample {
+    #[cfg(target_os = "linux")]
+    let (cpu_load, memory_available_mb, memory_total_mb) = {
+        let cores = std::thread::available_parallelism()
+            .map(|n| n.get())
+            .unwrap_or(1) as f64;
+        let cpu_load = tokio::fs::read_to_string("/proc/loadavg")
+            .await
+            .ok()
+            .and_then(|text| text.split_whitespace().next()?.parse::<f64>().ok())
+            .map(|load| load / cores);
+        let meminfo = tokio::fs::read_to_string("/proc/meminfo")
+            .await
+            .unwrap_or_default();
+        let field = |name: &str| {
+            meminfo
+                .lines()
+                .find(|line| line.starts_with(name))
+                .and_then(|line| line.split_whitespace().nth(1)?.parse::<u64>().ok())
+                .map(|kb| kb / 1024)
+        };
+        (cpu_load, field("MemAvailable:"), field("MemTotal:"))
+    };
+    #[cfg(not(target_os = "linux"))]
+    let (cpu_load, memory_available_mb, memory_total_mb) = (None, None, None);
+
+    ResourceSample {
+        cpu_load,
+        memory_available_mb,
+        memory_total_mb,
+        gpus: measure_gpus().await,
+        sampled_at: Utc::now(),
+    }
+}
+
+async fn measure_gpus() -> Vec<GpuStatus> {
+    let output = tokio::process::Command::new("nvidia-smi")
+        .args([
+            "--query-gpu=name,memory.used,memory.total",
+            "--format=csv,noheader,nounits",
+        ])
+        .output()
+        .await;
+    match output {
+        Ok(output) if output.status.success() => {
+            parse_nvidia_smi(&String::from_utf8_lossy(&output.stdout))
+        }
+        _ => Vec::new(),
+    }
+}
+
+fn parse_nvidia_smi(output: &str) -> Vec<GpuStatus> {
+    output
+        .lines()
+        .filter_map(|line| {
+            let mut fields = line.split(',').map(str::trim);
+            Some(GpuStatus {
+                name: fields.next()?.to_string(),
+                vram_used_mb: fields.next()?.parse().ok()?,
+                vram_total_mb: fields.next()?.parse().ok()?,
+            })
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sample(cpu_load: f64, vram_used_mb: u64) -> ResourceSample {
+        ResourceSample {
+            cpu_load: Some(cpu_load),
+            memory_available_mb: Some(8192),
+            memory_total_mb: Some(16384),
+            gpus: vec![GpuStatus {
+                name: "RTX".to_string(),
+                vram_used_mb,
+                vram_total_mb: 8192,
+            }],
+            sampled_at: Utc::now(),
+        }
+    }
+
+    #[test]
+    fn test_constraints_and_heavy_tools() {
+        let limits = ResourceLimits::default();
+        assert!(constraints(Workload::LocalModel, &sample(0.2, 1000), &limits).is_empty());
+        assert_eq!(
+            constraints(Workload::LocalModel, &sample(0.2, 7800), &limits),
+            vec!["392 MB of VRAM free, 1024 MB needed"]
+        );
+        assert!(constraints(Workload::HeavyTool, &sample(0.2, 7800), &limits).is_empty());
+        assert_eq!(
+            constraints(Workload::HeavyTool, &sample(1.5, 0), &limits).len(),
+            1
+        );
+
+        assert!(is_heavy_tool(
+            "developer__shell",
+            Some("cd api && cargo build --release")
+        ));
+        assert!(is_heavy_tool("developer__shell", Some("make")));
+        assert!(!is_heavy_tool(
+            "developer__shell",
+            Some("makefile-lint src")
+        ));
+        assert!(!is_heavy_tool("developer__shell", Some("cargo fmt")));
+        assert!(is_heavy_tool("code_index__index", None));
+
+        assert_eq!(
+            parse_nvidia_smi("NVIDIA GeForce RTX 4090, 1200, 24564\n"),
+            vec![GpuStatus {
+                name: "NVIDIA GeForce RTX 4090".to_string(),
+                vram_used_mb: 1200,
+                vram_total_mb: 24564,
+            }]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_second_heavy_tool_queues_until_release() {
+        let manager = Arc::new(ResourceManager::new(
+            ResourceLimits::default(),
+            Duration::from_secs(5),
+        ));
+        let first = manager.acquire(Workload::HeavyTool).await;
+
+        let waiting = manager.clone();
+        let second = tokio::spawn(async move { waiting.acquire(Workload::HeavyTool).await });
+        tokio::time::sleep(Duration::from_millis(50)).await;
+        assert!(!second.is_finished());
+        assert_eq!(manager.usage.lock().unwrap().queued, 1);
+
+        drop(first);
+        let _second = tokio::time::timeout(Duration::from_secs(1), second)
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(manager.usage.lock().unwrap().queued, 0);
+    }
+}
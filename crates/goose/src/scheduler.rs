@@ -18,6 +18,9 @@ use crate::config::paths::Paths;
 use crate::config::{resolve_extensions_for_new_session, Config};
 use crate::conversation::message::Message;
 use crate::conversation::Conversation;
+use crate::execution::priority::with_execution_mode;
+use crate::execution::SessionExecutionMode;
+use crate::issues::LinkedItem;
 use crate::posthog;
 use crate::providers::create;
 use crate::recipe::Recipe;
@@ -26,7 +29,10 @@ use crate::session::session_manager::SessionType;
 use crate::session::{Session, SessionManager};
 
 type RunningTasksMap = HashMap<String, CancellationToken>;
-type JobsMap = HashMap<String, (JobId, ScheduledJob)>;
+type JobsMap = HashMap<String, (Option<JobId>, ScheduledJob)>;
+
+/// How often the scheduler re-evaluates which chained jobs are ready to run.
+const CHAIN_TICK_INTERVAL_SECS: u64 = 5;
 
 pub fn get_default_scheduler_storage_path() -> Result<PathBuf, io::Error> {
     let data_dir = Paths::data_dir();
@@ -50,6 +56,7 @@ pub enum SchedulerError {
     AgentSetupError(String),
     PersistError(String),
     CronParseError(String),
+    InvalidDependency(String),
     SchedulerInternalError(String),
     AnyhowError(anyhow::Error),
 }
@@ -64,6 +71,7 @@ impl std::fmt::Display for SchedulerError {
             SchedulerError::AgentSetupError(e) => write!(f, "Agent setup error: {}", e),
             SchedulerError::PersistError(e) => write!(f, "Failed to persist schedules: {}", e),
             SchedulerError::CronParseError(e) => write!(f, "Invalid cron string: {}", e),
+            SchedulerError::InvalidDependency(e) => write!(f, "Invalid job dependency: {}", e),
             SchedulerError::SchedulerInternalError(e) => {
                 write!(f, "Scheduler internal error: {}", e)
             }
@@ -110,10 +118,251 @@ pub struct ScheduledJob {
     pub currently_running: bool,
     #[serde(default)]
     pub paused: bool,
+    /// Paused because an upstream job was, rather than by the user; lifted
+    /// once none of the jobs it depends on is paused
+    #[serde(default)]
+    pub paused_by_upstream: bool,
     #[serde(default)]
     pub current_session_id: Option<String>,
     #[serde(default)]
     pub process_start_time: Option<DateTime<Utc>>,
+    /// Upstream jobs that must finish before this one runs. Jobs with dependencies
+    /// are triggered by the chain rather than by their cron expression.
+    #[serde(default)]
+    pub depends_on: Vec<JobDependency>,
+    #[serde(default)]
+    pub last_status: Option<JobRunStatus>,
+    #[serde(default)]
+    pub last_finished: Option<DateTime<Utc>>,
+}
+
+impl ScheduledJob {
+    pub fn is_chained(&self) -> bool {
+        !self.depends_on.is_empty()
+    }
+
+    fn mark_started(&mut self, now: DateTime<Utc>) {
+        self.last_run = Some(now);
+        self.currently_running = true;
+        self.process_start_time = Some(now);
+    }
+
+    fn mark_finished(&mut self, status: JobRunStatus, now: DateTime<Utc>) {
+        self.currently_running = false;
+        self.current_session_id = None;
+        self.process_start_time = None;
+        self.last_status = Some(status);
+        self.last_finished = Some(now);
+    }
+}
+
+#[derive(Clone, Copy, Serialize, Deserialize, Debug, Default, PartialEq, Eq, utoipa::ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum DependencyCondition {
+    #[default]
+    Success,
+    Failure,
+    Always,
+}
+
+impl DependencyCondition {
+    pub fn is_satisfied_by(self, status: JobRunStatus) -> bool {
+        match self {
+            DependencyCondition::Success => status == JobRunStatus::Success,
+            DependencyCondition::Failure => {
+                matches!(status, JobRunStatus::Failure | JobRunStatus::Cancelled)
+            }
+            DependencyCondition::Always => true,
+        }
+    }
+}
+
+#[derive(Clone, Serialize, Deserialize, Debug, PartialEq, Eq, utoipa::ToSchema)]
+pub struct JobDependency {
+    pub job_id: String,
+    #[serde(default)]
+    pub condition: DependencyCondition,
+}
+
+#[derive(Clone, Copy, Serialize, Deserialize, Debug, PartialEq, Eq, utoipa::ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum JobRunStatus {
+    Success,
+    Failure,
+    Cancelled,
+    /// The job's dependency conditions were not met, so it did not run.
+    Skipped,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ChainDecision {
+    Run,
+    Skip,
+}
+
+/// Computes which chained jobs should be triggered now. A chained job becomes ready once
+/// every upstream job has finished since the chained job last ran; it runs if all
+/// dependency conditions hold and is skipped otherwise.
+pub fn compute_ready_set(jobs: &[ScheduledJob]) -> Vec<(String, ChainDecision)> {
+    let by_id: HashMap<&str, &ScheduledJob> = jobs.iter().map(|j| (j.id.as_str(), j)).collect();
+    let mut ready = Vec::new();
+
+    for job in jobs {
+        if !job.is_chained() || job.paused || job.currently_running {
+            continue;
+        }
+
+        let mut all_finished = true;
+        let mut all_satisfied = true;
+        for dep in &job.depends_on {
+            let Some(upstream) = by_id.get(dep.job_id.as_str()) else {
+                all_finished = false;
+                break;
+            };
+            let finished_since_last_run = !upstream.currently_running
+                && match (upstream.last_finished, job.last_run) {
+                    (Some(finished), Some(last_run)) => finished > last_run,
+                    (Some(_), None) => true,
+                    (None, _) => false,
+                };
+            if !finished_since_last_run {
+                all_finished = false;
+                break;
+            }
+            match upstream.last_status {
+                Some(status) if dep.condition.is_satisfied_by(status) => {}
+                _ => all_satisfied = false,
+            }
+        }
+
+        if all_finished {
+            let decision = if all_satisfied {
+                ChainDecision::Run
+            } else {
+                ChainDecision::Skip
+            };
+            ready.push((job.id.clone(), decision));
+        }
+    }
+
+    ready
+}
+
+/// Jobs that depend on `id`, directly or through other chained jobs.
+fn dependents_of(jobs: &[&ScheduledJob], id: &str) -> Vec<String> {
+    let mut found: Vec<String> = Vec::new();
+    let mut frontier = vec![id.to_string()];
+    while let Some(upstream) = frontier.pop() {
+        for job in jobs {
+            if job.depends_on.iter().any(|dep| dep.job_id == upstream)
+                && job.id != id
+                && !found.contains(&job.id)
+            {
+                found.push(job.id.clone());
+                frontier.push(job.id.clone());
+            }
+        }
+    }
+    found
+}
+
+#[derive(Clone, Copy, Serialize, Debug, PartialEq, Eq, utoipa::ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum ChainStatus {
+    Idle,
+    Running,
+    Succeeded,
+    Failed,
+}
+
+#[derive(Clone, Serialize, Debug, utoipa::ToSchema)]
+pub struct JobChain {
+    /// Job IDs in the chain, upstream jobs first.
+    pub job_ids: Vec<String>,
+    pub status: ChainStatus,
+}
+
+/// Groups jobs connected by dependencies into chains with an aggregate status.
+/// Jobs without any dependency links are not part of a chain.
+pub fn group_job_chains(jobs: &[ScheduledJob]) -> Vec<JobChain> {
+    let index: HashMap<&str, usize> = jobs
+        .iter()
+        .enumerate()
+        .map(|(i, j)| (j.id.as_str(), i))
+        .collect();
+
+    let mut parent: Vec<usize> = (0..jobs.len()).collect();
+    fn find(parent: &mut [usize], i: usize) -> usize {
+        let mut root = i;
+        while parent[root] != root {
+            root = parent[root];
+        }
+        parent[i] = root;
+        root
+    }
+
+    for (i, job) in jobs.iter().enumerate() {
+        for dep in &job.depends_on {
+            if let Some(&j) = index.get(dep.job_id.as_str()) {
+                let (a, b) = (find(&mut parent, i), find(&mut parent, j));
+                parent[a] = b;
+            }
+        }
+    }
+
+    let mut groups: Vec<(usize, Vec<usize>)> = Vec::new();
+    for i in 0..jobs.len() {
+        let root = find(&mut parent, i);
+        match groups.iter_mut().find(|(r, _)| *r == root) {
+            Some((_, members)) => members.push(i),
+            None => groups.push((root, vec![i])),
+        }
+    }
+
+    groups
+        .into_iter()
+        .filter(|(_, members)| members.len() > 1)
+        .map(|(_, members)| {
+            let mut ordered: Vec<&ScheduledJob> = Vec::with_capacity(members.len());
+            let mut remaining: Vec<&ScheduledJob> = members.iter().map(|&i| &jobs[i]).collect();
+            while !remaining.is_empty() {
+                let before = remaining.len();
+                remaining.retain(|job| {
+                    let upstream_placed = job.depends_on.iter().all(|dep| {
+                        !index.contains_key(dep.job_id.as_str())
+                            || ordered.iter().any(|placed| placed.id == dep.job_id)
+                    });
+                    if upstream_placed {
+                        ordered.push(job);
+                    }
+                    !upstream_placed
+                });
+                if remaining.len() == before {
+                    ordered.append(&mut remaining);
+                }
+            }
+
+            let status = if ordered.iter().any(|j| j.currently_running) {
+                ChainStatus::Running
+            } else if ordered.iter().any(|j| {
+                matches!(
+                    j.last_status,
+                    Some(JobRunStatus::Failure | JobRunStatus::Cancelled)
+                )
+            }) {
+                ChainStatus::Failed
+            } else if ordered.iter().all(|j| j.last_status.is_some()) {
+                ChainStatus::Succeeded
+            } else {
+                ChainStatus::Idle
+            };
+
+            JobChain {
+                job_ids: ordered.iter().map(|j| j.id.clone()).collect(),
+                status,
+            }
+        })
+        .collect()
 }
 
 async fn persist_jobs(
@@ -159,6 +408,11 @@ impl Scheduler {
         });
 
         arc_self.load_jobs_from_storage().await;
+        arc_self
+            .tokio_scheduler
+            .add(arc_self.create_chain_tick_task()?)
+            .await
+            .map_err(|e| SchedulerError::SchedulerInternalError(e.to_string()))?;
         arc_self
             .tokio_scheduler
             .start()
@@ -201,78 +455,94 @@ impl Scheduler {
             let task_job_id = job_for_task.id.clone();
             let current_jobs_arc = jobs_arc.clone();
             let local_storage_path = storage_path.clone();
-            let job_to_execute = job_for_task.clone();
             let running_tasks = running_tasks_arc.clone();
 
             Box::pin(async move {
-                let should_execute = {
-                    let jobs_guard = current_jobs_arc.lock().await;
-                    jobs_guard
-                        .get(&task_job_id)
-                        .map(|(_, j)| !j.paused)
-                        .unwrap_or(false)
-                };
-
-                if !should_execute {
-                    return;
-                }
-
-                let current_time = Utc::now();
-                {
+                let job_to_execute = {
                     let mut jobs_guard = current_jobs_arc.lock().await;
-                    if let Some((_, job)) = jobs_guard.get_mut(&task_job_id) {
-                        job.last_run = Some(current_time);
-                        job.currently_running = true;
-                        job.process_start_time = Some(current_time);
+                    match jobs_guard.get_mut(&task_job_id) {
+                        Some((_, job)) if !job.paused => {
+                            job.mark_started(Utc::now());
+                            job.clone()
+                        }
+                        _ => return,
                     }
-                }
-
-                if let Err(e) = persist_jobs(&local_storage_path, &current_jobs_arc).await {
-                    tracing::error!("Failed to persist job status: {}", e);
-                }
-
-                let cancel_token = CancellationToken::new();
-                {
-                    let mut tasks = running_tasks.lock().await;
-                    tasks.insert(task_job_id.clone(), cancel_token.clone());
-                }
+                };
 
-                let result = execute_job(
+                run_tracked_job(
                     job_to_execute,
-                    current_jobs_arc.clone(),
-                    task_job_id.clone(),
-                    cancel_token.clone(),
+                    current_jobs_arc,
+                    local_storage_path,
+                    running_tasks,
                 )
                 .await;
+            })
+        })
+        .map_err(|e| SchedulerError::CronParseError(e.to_string()))
+    }
 
-                {
-                    let mut tasks = running_tasks.lock().await;
-                    tasks.remove(&task_job_id);
-                }
+    fn create_chain_tick_task(&self) -> Result<Job, SchedulerError> {
+        let jobs_arc = self.jobs.clone();
+        let storage_path = self.storage_path.clone();
+        let running_tasks_arc = self.running_tasks.clone();
 
-                {
-                    let mut jobs_guard = current_jobs_arc.lock().await;
-                    if let Some((_, job)) = jobs_guard.get_mut(&task_job_id) {
-                        job.currently_running = false;
-                        job.current_session_id = None;
-                        job.process_start_time = None;
-                    }
-                }
+        Job::new_repeated_async(
+            std::time::Duration::from_secs(CHAIN_TICK_INTERVAL_SECS),
+            move |_uuid, _l| {
+                let jobs = jobs_arc.clone();
+                let storage_path = storage_path.clone();
+                let running_tasks = running_tasks_arc.clone();
+
+                Box::pin(async move {
+                    let (to_run, skipped) = {
+                        let mut jobs_guard = jobs.lock().await;
+                        let snapshot: Vec<ScheduledJob> =
+                            jobs_guard.values().map(|(_, j)| j.clone()).collect();
+                        let now = Utc::now();
+                        let mut to_run = Vec::new();
+                        let mut skipped = false;
+                        for (id, decision) in compute_ready_set(&snapshot) {
+                            let Some((_, job)) = jobs_guard.get_mut(&id) else {
+                                continue;
+                            };
+                            match decision {
+                                ChainDecision::Run => {
+                                    tracing::info!("Dependencies satisfied for job '{}'", id);
+                                    job.mark_started(now);
+                                    to_run.push(job.clone());
+                                }
+                                ChainDecision::Skip => {
+                                    tracing::info!(
+                                        "Skipping job '{}': dependency conditions not met",
+                                        id
+                                    );
+                                    job.last_run = Some(now);
+                                    job.mark_finished(JobRunStatus::Skipped, now);
+                                    skipped = true;
+                                }
+                            }
+                        }
+                        (to_run, skipped)
+                    };
 
-                if let Err(e) = persist_jobs(&local_storage_path, &current_jobs_arc).await {
-                    tracing::error!("Failed to persist job completion: {}", e);
-                }
+                    if skipped && to_run.is_empty() {
+                        if let Err(e) = persist_jobs(&storage_path, &jobs).await {
+                            tracing::error!("Failed to persist skipped jobs: {}", e);
+                        }
+                    }
 
-                match result {
-                    Ok(_) => tracing::info!("Job '{}' completed", task_job_id),
-                    Err(ref e) => {
-                        tracing::error!("Job '{}' failed: {}", task_job_id, e);
-                        crate::posthog::emit_error("scheduler_job_failed", &e.to_string());
+                    for job in to_run {
+                        tokio::spawn(run_tracked_job(
+                            job,
+                            jobs.clone(),
+                            storage_path.clone(),
+                            running_tasks.clone(),
+                        ));
                     }
-                }
-            })
-        })
-        .map_err(|e| SchedulerError::CronParseError(e.to_string()))
+                })
+            },
+        )
+        .map_err(|e| SchedulerError::SchedulerInternalError(e.to_string()))
     }
 
     pub async fn add_scheduled_job(
@@ -285,6 +555,16 @@ impl Scheduler {
             if jobs_guard.contains_key(&original_job_spec.id) {
                 return Err(SchedulerError::JobIdExists(original_job_spec.id.clone()));
             }
+            if let Some(missing) = original_job_spec
+                .depends_on
+                .iter()
+                .find(|dep| !jobs_guard.contains_key(&dep.job_id))
+            {
+                return Err(SchedulerError::InvalidDependency(format!(
+                    "Job '{}' depends on unknown job '{}'",
+                    original_job_spec.id, missing.job_id
+                )));
+            }
         }
 
         let mut stored_job = original_job_spec;
@@ -312,13 +592,17 @@ impl Scheduler {
             stored_job.process_start_time = None;
         }
 
-        let cron_task = self.create_cron_task(stored_job.clone())?;
-
-        let job_uuid = self
-            .tokio_scheduler
-            .add(cron_task)
-            .await
-            .map_err(|e| SchedulerError::SchedulerInternalError(e.to_string()))?;
+        let job_uuid = if stored_job.is_chained() {
+            None
+        } else {
+            let cron_task = self.create_cron_task(stored_job.clone())?;
+            let uuid = self
+                .tokio_scheduler
+                .add(cron_task)
+                .await
+                .map_err(|e| SchedulerError::SchedulerInternalError(e.to_string()))?;
+            Some(uuid)
+        };
 
         {
             let mut jobs_guard = self.jobs.lock().await;
@@ -357,8 +641,12 @@ impl Scheduler {
                         last_run: None,
                         currently_running: false,
                         paused: false,
+                        paused_by_upstream: false,
                         current_session_id: None,
                         process_start_time: None,
+                        depends_on: Vec::new(),
+                        last_status: None,
+                        last_finished: None,
                     };
                     self.add_scheduled_job(job, false).await
                 }
@@ -433,6 +721,12 @@ impl Scheduler {
                 continue;
             }
 
+            if job_to_load.is_chained() {
+                let mut jobs_guard = self.jobs.lock().await;
+                jobs_guard.insert(job_to_load.id.clone(), (None, job_to_load));
+                continue;
+            }
+
             let cron_task = match self.create_cron_task(job_to_load.clone()) {
                 Ok(task) => task,
                 Err(e) => {
@@ -446,7 +740,7 @@ impl Scheduler {
             };
 
             let job_uuid = match self.tokio_scheduler.add(cron_task).await {
-                Ok(uuid) => uuid,
+                Ok(uuid) => Some(uuid),
                 Err(e) => {
                     tracing::error!(
                         "Failed to add job '{}' to scheduler: {}. Skipping.",
@@ -460,6 +754,25 @@ impl Scheduler {
             let mut jobs_guard = self.jobs.lock().await;
             jobs_guard.insert(job_to_load.id.clone(), (job_uuid, job_to_load));
         }
+
+        let mut jobs_guard = self.jobs.lock().await;
+        loop {
+            let orphan = jobs_guard.values().find_map(|(_, job)| {
+                job.depends_on
+                    .iter()
+                    .find(|dep| !jobs_guard.contains_key(&dep.job_id))
+                    .map(|dep| (job.id.clone(), dep.job_id.clone()))
+            });
+            let Some((id, missing)) = orphan else {
+                break;
+            };
+            tracing::warn!(
+                "Job '{}' depends on missing job '{}', skipping it",
+                id,
+                missing
+            );
+            jobs_guard.remove(&id);
+        }
     }
 
     pub async fn list_scheduled_jobs(&self) -> Vec<ScheduledJob> {
@@ -478,16 +791,27 @@ impl Scheduler {
     ) -> Result<(), SchedulerError> {
         let (job_uuid, recipe_path) = {
             let mut jobs_guard = self.jobs.lock().await;
+            let jobs: Vec<&ScheduledJob> = jobs_guard.values().map(|(_, j)| j).collect();
+            let dependents = dependents_of(&jobs, id);
+            if !dependents.is_empty() {
+                return Err(SchedulerError::InvalidDependency(format!(
+                    "Job '{}' is required by {}; remove those jobs first",
+                    id,
+                    dependents.join(", ")
+                )));
+            }
             match jobs_guard.remove(id) {
                 Some((uuid, job)) => (uuid, job.source.clone()),
                 None => return Err(SchedulerError::JobNotFound(id.to_string())),
             }
         };
 
-        self.tokio_scheduler
-            .remove(&job_uuid)
-            .await
-            .map_err(|e| SchedulerError::SchedulerInternalError(e.to_string()))?;
+        if let Some(job_uuid) = job_uuid {
+            self.tokio_scheduler
+                .remove(&job_uuid)
+                .await
+                .map_err(|e| SchedulerError::SchedulerInternalError(e.to_string()))?;
+        }
 
         if remove_recipe {
             let path = Path::new(&recipe_path);
@@ -534,8 +858,7 @@ impl Scheduler {
                             sched_id
                         )));
                     }
-                    job.currently_running = true;
-                    job.process_start_time = Some(Utc::now());
+                    job.mark_started(Utc::now());
                     job.clone()
                 }
                 None => return Err(SchedulerError::JobNotFound(sched_id.to_string())),
@@ -564,12 +887,10 @@ impl Scheduler {
         }
 
         {
+            let status = run_status(&result, &cancel_token);
             let mut jobs_guard = self.jobs.lock().await;
             if let Some((_, job)) = jobs_guard.get_mut(sched_id) {
-                job.currently_running = false;
-                job.current_session_id = None;
-                job.process_start_time = None;
-                job.last_run = Some(Utc::now());
+                job.mark_finished(status, Utc::now());
             }
         }
 
@@ -597,9 +918,11 @@ impl Scheduler {
                         )));
                     }
                     job.paused = true;
+                    job.paused_by_upstream = false;
                 }
                 None => return Err(SchedulerError::JobNotFound(sched_id.to_string())),
             }
+            Self::pause_dependents(&mut jobs_guard, sched_id);
         }
 
         persist_jobs(&self.storage_path, &self.jobs).await
@@ -609,14 +932,61 @@ impl Scheduler {
         {
             let mut jobs_guard = self.jobs.lock().await;
             match jobs_guard.get_mut(sched_id) {
-                Some((_, job)) => job.paused = false,
+                Some((_, job)) => {
+                    job.paused = false;
+                    job.paused_by_upstream = false;
+                }
                 None => return Err(SchedulerError::JobNotFound(sched_id.to_string())),
             }
+            Self::resume_dependents(&mut jobs_guard);
         }
 
         persist_jobs(&self.storage_path, &self.jobs).await
     }
 
+    /// Chained jobs only run after their upstream jobs, so pausing a job
+    /// pauses everything downstream of it. Jobs the user already paused are
+    /// left as they are, so resuming the chain does not lift their pause.
+    fn pause_dependents(jobs: &mut JobsMap, id: &str) {
+        let dependents = dependents_of(&jobs.values().map(|(_, j)| j).collect::<Vec<_>>(), id);
+        for dependent in dependents {
+            if let Some((_, job)) = jobs.get_mut(&dependent) {
+                if !job.paused {
+                    job.paused = true;
+                    job.paused_by_upstream = true;
+                }
+            }
+        }
+    }
+
+    /// Resumes jobs paused by their chain once none of the jobs they depend
+    /// on is paused, working down the chain.
+    fn resume_dependents(jobs: &mut JobsMap) {
+        loop {
+            let resumable: Vec<String> = jobs
+                .values()
+                .map(|(_, job)| job)
+                .filter(|job| job.paused_by_upstream)
+                .filter(|job| {
+                    !job.depends_on.iter().any(|dep| {
+                        jobs.get(&dep.job_id)
+                            .is_some_and(|(_, upstream)| upstream.paused)
+                    })
+                })
+                .map(|job| job.id.clone())
+                .collect();
+            if resumable.is_empty() {
+                return;
+            }
+            for id in resumable {
+                if let Some((_, job)) = jobs.get_mut(&id) {
+                    job.paused = false;
+                    job.paused_by_upstream = false;
+                }
+            }
+        }
+    }
+
     pub async fn update_schedule(
         &self,
         sched_id: &str,
@@ -635,6 +1005,12 @@ impl Scheduler {
                     if new_cron == job.cron {
                         return Ok(());
                     }
+                    if job.is_chained() {
+                        return Err(SchedulerError::InvalidDependency(format!(
+                            "Job '{}' is triggered by its dependencies and has no cron schedule",
+                            sched_id
+                        )));
+                    }
                     job.cron = new_cron.clone();
                     (*uuid, job.clone())
                 }
@@ -642,10 +1018,12 @@ impl Scheduler {
             }
         };
 
-        self.tokio_scheduler
-            .remove(&old_uuid)
-            .await
-            .map_err(|e| SchedulerError::SchedulerInternalError(e.to_string()))?;
+        if let Some(old_uuid) = old_uuid {
+            self.tokio_scheduler
+                .remove(&old_uuid)
+                .await
+                .map_err(|e| SchedulerError::SchedulerInternalError(e.to_string()))?;
+        }
 
         let cron_task = self.create_cron_task(updated_job)?;
         let new_uuid = self
@@ -657,7 +1035,7 @@ impl Scheduler {
         {
             let mut jobs_guard = self.jobs.lock().await;
             if let Some((uuid, _)) = jobs_guard.get_mut(sched_id) {
-                *uuid = new_uuid;
+                *uuid = Some(new_uuid);
             }
         }
 
@@ -707,6 +1085,76 @@ impl Scheduler {
     }
 }
 
+fn run_status(result: &Result<String>, cancel_token: &CancellationToken) -> JobRunStatus {
+    match result {
+        Ok(_) => JobRunStatus::Success,
+        Err(_) if cancel_token.is_cancelled() => JobRunStatus::Cancelled,
+        Err(_) => JobRunStatus::Failure,
+    }
+}
+
+/// Runs a job that has already been marked as started, tracking its cancellation token
+/// and recording the outcome so dependent jobs can react to it.
+async fn run_tracked_job(
+    job: ScheduledJob,
+    jobs: Arc<Mutex<JobsMap>>,
+    storage_path: PathBuf,
+    running_tasks: Arc<Mutex<RunningTasksMap>>,
+) {
+    let job_id = job.id.clone();
+
+    if let Err(e) = persist_jobs(&storage_path, &jobs).await {
+        tracing::error!("Failed to persist job status: {}", e);
+    }
+
+    let cancel_token = CancellationToken::new();
+    {
+        let mut tasks = running_tasks.lock().await;
+        tasks.insert(job_id.clone(), cancel_token.clone());
+    }
+
+    // Cron-fired runs yield to interactive sessions; run_now keeps its caller's priority
+    let result = with_execution_mode(
+        SessionExecutionMode::Background,
+        execute_job(job, jobs.clone(), job_id.clone(), cancel_token.clone()),
+    )
+    .await;
+
+    {
+        let mut tasks = running_tasks.lock().await;
+        tasks.remove(&job_id);
+    }
+
+    let status = run_status(&result, &cancel_token);
+    {
+        let mut jobs_guard = jobs.lock().await;
+        if let Some((_, job)) = jobs_guard.get_mut(&job_id) {
+            job.mark_finished(status, Utc::now());
+        }
+    }
+
+    if let Err(e) = persist_jobs(&storage_path, &jobs).await {
+        tracing::error!("Failed to persist job completion: {}", e);
+    }
+
+    let report = match &result {
+        Ok(session_id) => format!(
+            "Scheduled run finished ({:?}), session {}",
+            status, session_id
+        ),
+        Err(e) => format!("Scheduled run finished ({:?}): {}", status, e),
+    };
+    crate::issues::sync(&LinkedItem::Schedule(job_id.clone()), None, Some(report)).await;
+
+    match result {
+        Ok(_) => tracing::info!("Job '{}' completed", job_id),
+        Err(ref e) => {
+            tracing::error!("Job '{}' failed: {}", job_id, e);
+            crate::posthog::emit_error("scheduler_job_failed", &e.to_string());
+        }
+    }
+}
+
 #[allow(clippy::too_many_lines)]
 async fn execute_job(
     job: ScheduledJob,
@@ -927,6 +1375,155 @@ mod tests {
     use tempfile::tempdir;
     use tokio::time::{sleep, Duration};
 
+    fn chained_job(id: &str, depends_on: &[(&str, DependencyCondition)]) -> ScheduledJob {
+        ScheduledJob {
+            id: id.to_string(),
+            source: String::new(),
+            cron: String::new(),
+            last_run: None,
+            currently_running: false,
+            paused: false,
+            paused_by_upstream: false,
+            current_session_id: None,
+            process_start_time: None,
+            depends_on: depends_on
+                .iter()
+                .map(|(job_id, condition)| JobDependency {
+                    job_id: job_id.to_string(),
+                    condition: *condition,
+                })
+                .collect(),
+            last_status: None,
+            last_finished: None,
+        }
+    }
+
+    fn finished(mut job: ScheduledJob, status: JobRunStatus) -> ScheduledJob {
+        let now = Utc::now();
+        job.last_run = Some(now - chrono::Duration::seconds(10));
+        job.mark_finished(status, now);
+        job
+    }
+
+    #[test]
+    fn test_ready_set_waits_for_upstream() {
+        let jobs = vec![
+            chained_job("deps", &[]),
+            chained_job("tests", &[("deps", DependencyCondition::Success)]),
+        ];
+        assert!(compute_ready_set(&jobs).is_empty());
+
+        let jobs = vec![
+            finished(chained_job("deps", &[]), JobRunStatus::Success),
+            chained_job("tests", &[("deps", DependencyCondition::Success)]),
+        ];
+        assert_eq!(
+            compute_ready_set(&jobs),
+            vec![("tests".to_string(), ChainDecision::Run)]
+        );
+    }
+
+    #[test]
+    fn test_ready_set_applies_conditions() {
+        let jobs = vec![
+            finished(chained_job("deps", &[]), JobRunStatus::Failure),
+            chained_job("tests", &[("deps", DependencyCondition::Success)]),
+            chained_job("notify", &[("deps", DependencyCondition::Failure)]),
+            chained_job("cleanup", &[("deps", DependencyCondition::Always)]),
+        ];
+        let ready: HashMap<String, ChainDecision> = compute_ready_set(&jobs).into_iter().collect();
+        assert_eq!(ready["tests"], ChainDecision::Skip);
+        assert_eq!(ready["notify"], ChainDecision::Run);
+        assert_eq!(ready["cleanup"], ChainDecision::Run);
+    }
+
+    #[test]
+    fn test_ready_set_ignores_stale_upstream_runs() {
+        let upstream = finished(chained_job("deps", &[]), JobRunStatus::Success);
+        let mut downstream = chained_job("tests", &[("deps", DependencyCondition::Success)]);
+        downstream.last_run = Some(Utc::now() + chrono::Duration::seconds(1));
+        assert!(compute_ready_set(&[upstream, downstream]).is_empty());
+    }
+
+    #[test]
+    fn test_group_job_chains_orders_and_aggregates() {
+        let jobs = vec![
+            chained_job("pr", &[("tests", DependencyCondition::Success)]),
+            finished(chained_job("deps", &[]), JobRunStatus::Success),
+            finished(
+                chained_job("tests", &[("deps", DependencyCondition::Success)]),
+                JobRunStatus::Failure,
+            ),
+            chained_job("standalone", &[]),
+        ];
+        let chains = group_job_chains(&jobs);
+        assert_eq!(chains.len(), 1);
+        assert_eq!(chains[0].job_ids, vec!["deps", "tests", "pr"]);
+        assert_eq!(chains[0].status, ChainStatus::Failed);
+    }
+
+    #[tokio::test]
+    async fn test_add_job_with_unknown_dependency_fails() {
+        let temp_dir = tempdir().unwrap();
+        let storage_path = temp_dir.path().join("schedule.json");
+        let session_manager = Arc::new(SessionManager::new(temp_dir.path().to_path_buf()));
+        let scheduler = Scheduler::new(storage_path, session_manager).await.unwrap();
+
+        let job = chained_job("tests", &[("missing", DependencyCondition::Success)]);
+        let result = scheduler.add_scheduled_job(job, false).await;
+        assert!(matches!(result, Err(SchedulerError::InvalidDependency(_))));
+    }
+
+    #[tokio::test]
+    async fn test_jobs_with_dependents_cannot_be_removed_and_pause_together() {
+        let temp_dir = tempdir().unwrap();
+        let storage_path = temp_dir.path().join("schedule.json");
+        let session_manager = Arc::new(SessionManager::new(temp_dir.path().to_path_buf()));
+        let scheduler = Scheduler::new(storage_path, session_manager).await.unwrap();
+
+        let mut deps = chained_job("deps", &[]);
+        deps.cron = "0 0 0 1 1 *".to_string();
+        scheduler.add_scheduled_job(deps, false).await.unwrap();
+        let tests = chained_job("tests", &[("deps", DependencyCondition::Success)]);
+        scheduler.add_scheduled_job(tests, false).await.unwrap();
+        let pr = chained_job("pr", &[("tests", DependencyCondition::Success)]);
+        scheduler.add_scheduled_job(pr, false).await.unwrap();
+
+        let result = scheduler.remove_scheduled_job("deps", false).await;
+        assert!(matches!(result, Err(SchedulerError::InvalidDependency(_))));
+
+        let paused = |jobs: Vec<ScheduledJob>| {
+            let mut ids: Vec<String> = jobs
+                .into_iter()
+                .filter(|j| j.paused)
+                .map(|j| j.id)
+                .collect();
+            ids.sort();
+            ids
+        };
+        scheduler.pause_schedule("deps").await.unwrap();
+        assert_eq!(
+            paused(scheduler.list_scheduled_jobs().await),
+            vec!["deps", "pr", "tests"]
+        );
+        scheduler.unpause_schedule("deps").await.unwrap();
+        assert!(paused(scheduler.list_scheduled_jobs().await).is_empty());
+
+        // A job the user paused stays paused when its upstream resumes
+        scheduler.pause_schedule("pr").await.unwrap();
+        scheduler.pause_schedule("deps").await.unwrap();
+        scheduler.unpause_schedule("deps").await.unwrap();
+        assert_eq!(paused(scheduler.list_scheduled_jobs().await), vec!["pr"]);
+        scheduler.unpause_schedule("pr").await.unwrap();
+
+        scheduler.remove_scheduled_job("pr", false).await.unwrap();
+        scheduler
+            .remove_scheduled_job("tests", false)
+            .await
+            .unwrap();
+        scheduler.remove_scheduled_job("deps", false).await.unwrap();
+    }
+
     fn create_test_recipe(dir: &Path, name: &str) -> PathBuf {
         let recipe_path = dir.join(format!("{}.yaml", name));
         fs::write(&recipe_path, "prompt: test\n").unwrap();
@@ -948,8 +1545,12 @@ mod tests {
             last_run: None,
             currently_running: false,
             paused: false,
+            paused_by_upstream: false,
             current_session_id: None,
             process_start_time: None,
+            depends_on: Vec::new(),
+            last_status: None,
+            last_finished: None,
         };
 
         scheduler.add_scheduled_job(job, true).await.unwrap();
@@ -974,8 +1575,12 @@ mod tests {
             last_run: None,
             currently_running: false,
             paused: false,
+            paused_by_upstream: false,
             current_session_id: None,
             process_start_time: None,
+            depends_on: Vec::new(),
+            last_status: None,
+            last_finished: None,
         };
 
         scheduler.add_scheduled_job(job, true).await.unwrap();
@@ -0,0 +1,205 @@
+//! Optional at-rest encryption for stored conversations, memories and
+//! checkpoints.
+//!
+//! With `GOOSE_ENCRYPT_AT_REST` on, sensitive values are sealed with
+//! AES-256-GCM before they are written: message content in the session
+//! store, checkpoint state, and the memory snapshot. The key is a random
+//! 256-bit secret stored like other goose secrets, in the OS keyring unless
+//! the keyring is disabled, under [`ENCRYPTION_KEY_SECRET`]. It is created
+//! the first time something is sealed. A sealed value is text, the
+//! [`SEALED_PREFIX`] followed by base64 of nonce and ciphertext, so it fits
+//! the existing TEXT columns and files.
+//!
+//! Reads accept sealed and plaintext values alike, so data written before
+//! encryption was turned on stays readable until `goose storage encrypt`
+//! seals it. Turning encryption off only stops sealing new values; sealed
+//! ones stay readable while the key exists. Losing the key loses the sealed
+//! data.
+
+use crate::config::Config;
+use aes_gcm::aead::{Aead, KeyInit};
+use aes_gcm::{Aes256Gcm, Nonce};
+use anyhow::{anyhow, Context, Result};
+use base64::engine::general_purpose::STANDARD;
+use base64::Engine;
+use std::sync::{Arc, Mutex};
+
+/// Config flag that turns sealing on
+pub const ENCRYPT_AT_REST_KEY: &str = "GOOSE_ENCRYPT_AT_REST";
+/// Secret holding the base64 storage key
+pub const ENCRYPTION_KEY_SECRET: &str = "GOOSE_STORAGE_ENCRYPTION_KEY";
+pub const SEALED_PREFIX: &str = "enc:v1:";
+/// SQL LIKE pattern matching sealed values
+pub const SEALED_LIKE_PATTERN: &str = "enc:v1:%";
+
+const NONCE_LEN: usize = 12;
+
+pub struct StorageCipher {
+    cipher: Aes256Gcm,
+}
+
+impl StorageCipher {
+    pub fn new(key: &[u8; 32]) -> Self {
+        Self {
+            cipher: Aes256Gcm::new(key.into()),
+        }
+    }
+
+    pub fn seal(&self, plaintext: &str) -> Result<String> {
+        let nonce_bytes = rand::random::<[u8; NONCE_LEN]>();
+        let ciphertext = self
+            .cipher
+            .encrypt(Nonce::from_slice(&nonce_bytes), plaintext.as_bytes())
+            .map_err(|_| anyhow!("Failed to encrypt stored data"))?;
+        let mut payload = nonce_bytes.to_vec();
+        payload.extend(ciphertext);
+        Ok(format!("{}{}", SEALED_PREFIX, STANDARD.encode(payload)))
+    }
+
+    pub fn open(&self, sealed: &str) -> Result<String> {
+        let encoded = sealed
+            .strip_prefix(SEALED_PREFIX)
+            .ok_or_else(|| anyhow!("Value is not sealed"))?;
+        let payload = STANDARD
+            .decode(encoded)
+            .context("Sealed value is not valid base64")?;
+        if payload.len() < NONCE_LEN {
+            return Err(anyhow!("Sealed value is truncated"));
+        }
+        let (nonce, ciphertext) = payload.split_at(NONCE_LEN);
+        let plaintext = self
+            .cipher
+            .decrypt(Nonce::from_slice(nonce), ciphertext)
+            .map_err(|_| {
+                anyhow!("Failed to decrypt stored data; the storage key does not match")
+            })?;
+        String::from_utf8(plaintext).context("Decrypted data is not UTF-8")
+    }
+}
+
+struct KeyCache {
+    cipher: Option<Arc<StorageCipher>>,
+    looked_up: bool,
+}
+
+static KEY_CACHE: Mutex<KeyCache> = Mutex::new(KeyCache {
+    cipher: None,
+    looked_up: false,
+});
+
+fn decode_key(encoded: &str) -> Result<[u8; 32]> {
+    STANDARD
+        .decode(encoded.trim())
+        .ok()
+        .and_then(|bytes| <[u8; 32]>::try_from(bytes).ok())
+        .ok_or_else(|| anyhow!("{} is not a base64 256-bit key", ENCRYPTION_KEY_SECRET))
+}
+
+/// The storage cipher, creating and storing a key first when `create` is
+/// set and none exists. A missing key is looked up once per process unless
+/// `create` is set.
+fn cipher(create: bool) -> Result<Option<Arc<StorageCipher>>> {
+    let mut cache = KEY_CACHE.lock().unwrap();
+    if let Some(cipher) = &cache.cipher {
+        return Ok(Some(cipher.clone()));
+    }
+    if cache.looked_up && !create {
+        return Ok(None);
+    }
+    cache.looked_up = true;
+
+    let config = Config::global();
+    let key = match config.get_secret::<String>(ENCRYPTION_KEY_SECRET) {
+        Ok(encoded) => decode_key(&encoded)?,
+        Err(_) if create => {
+            let key = rand::random::<[u8; 32]>();
+            config
+                .set_secret(ENCRYPTION_KEY_SECRET, &STANDARD.encode(key))
+                .context("Failed to store the storage encryption key")?;
+            tracing::info!("Created storage encryption key");
+            key
+        }
+        Err(_) => return Ok(None),
+    };
+    let cipher = Arc::new(StorageCipher::new(&key));
+    cache.cipher = Some(cipher.clone());
+    Ok(Some(cipher))
+}
+
+pub fn is_enabled() -> bool {
+    Config::global()
+        .get_param::<bool>(ENCRYPT_AT_REST_KEY)
+        .unwrap_or(false)
+}
+
+pub fn is_sealed(value: &str) -> bool {
+    value.starts_with(SEALED_PREFIX)
+}
+
+/// Whether storage may hold sealed values: encryption is on or a key exists.
+pub fn may_have_sealed_data() -> bool {
+    is_enabled() || cipher(false).ok().flatten().is_some()
+}
+
+/// Makes sure the storage key exists, creating it if needed.
+pub fn ensure_key() -> Result<()> {
+    cipher(true).map(|_| ())
+}
+
+/// Seals `plaintext` for storage when encryption at rest is on; otherwise
+/// returns it unchanged.
+pub fn seal(plaintext: String) -> Result<String> {
+    if !is_enabled() || is_sealed(&plaintext) {
+        return Ok(plaintext);
+    }
+    seal_always(&plaintext)
+}
+
+/// Seals `plaintext` whether or not encryption at rest is on, for migrating
+/// existing data.
+pub fn seal_always(plaintext: &str) -> Result<String> {
+    let cipher = cipher(true)?.ok_or_else(|| anyhow!("No storage encryption key"))?;
+    cipher.seal(plaintext)
+}
+
+/// Opens a value read from storage. Plaintext values pass through.
+pub fn open(value: String) -> Result<String> {
+    if !is_sealed(&value) {
+        return Ok(value);
+    }
+    let cipher = cipher(false)?.ok_or_else(|| {
+        anyhow!(
+            "Stored data is encrypted but {} is missing from the keyring",
+            ENCRYPTION_KEY_SECRET
+        )
+    })?;
+    cipher.open(&value)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_seal_and_open_round_trip() {
+        let cipher = StorageCipher::new(&[7; 32]);
+        let sealed = cipher.seal("fn main() {}").unwrap();
+        assert!(is_sealed(&sealed));
+        assert!(!sealed.contains("main"));
+        assert_ne!(sealed, cipher.seal("fn main() {}").unwrap());
+        assert_eq!(cipher.open(&sealed).unwrap(), "fn main() {}");
+
+        let other = StorageCipher::new(&[8; 32]);
+        assert!(other.open(&sealed).is_err());
+
+        let mut tampered = sealed.clone();
+        tampered.pop();
+        tampered.push(if sealed.ends_with('A') { 'B' } else { 'A' });
+        assert!(cipher.open(&tampered).is_err());
+    }
+
+    #[test]
+    fn test_plaintext_passes_through_open() {
+        assert_eq!(open("[]".to_string()).unwrap(), "[]");
+    }
+}
@@ -0,0 +1,843 @@
+//! Tamper-evident audit log.
+//!
+//! Every record carries the hash of the record before it, so editing or
+//! removing an entry breaks the chain. Every [`CHECKPOINT_INTERVAL`] records
+//! the Merkle root of the batch is signed with HMAC-SHA256 and written to a
+//! sidecar file, which also exposes truncation of the tail of the log.
+//! [`AuditLog::export`] bundles records, checkpoints and a verification
+//! report into a signed document that can be handed to auditors.
+//!
+//! Retention drops old records a checkpointed batch at a time. A signed
+//! [`AuditAnchor`] records where the kept chain starts, so it still verifies.
+
+use crate::config::paths::Paths;
+use crate::config::Config;
+use anyhow::{Context, Result};
+use chrono::{DateTime, SecondsFormat, Utc};
+use hmac::{Hmac, Mac};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use sha2::{Digest, Sha256};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex, OnceLock};
+use utoipa::ToSchema;
+
+pub const AUDIT_LOG_FILE: &str = "audit.jsonl";
+pub const CHECKPOINT_FILE: &str = "audit.checkpoints.jsonl";
+pub const ANCHOR_FILE: &str = "audit.anchor.json";
+
+/// Secret holding the HMAC key used to sign checkpoints and exports
+pub const SIGNING_KEY_SECRET: &str = "GOOSE_AUDIT_SIGNING_KEY";
+
+pub const EXPORT_FORMAT: &str = "goose-audit-v1";
+
+/// Records covered by each signed checkpoint
+pub const CHECKPOINT_INTERVAL: usize = 100;
+
+const GENESIS_HASH: &str = "0000000000000000000000000000000000000000000000000000000000000000";
+
+type HmacSha256 = Hmac<Sha256>;
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, ToSchema)]
+pub struct AuditRecord {
+    pub seq: u64,
+    pub timestamp: DateTime<Utc>,
+    /// What kind of action was audited, e.g. "emergency_stop"
+    pub category: String,
+    pub data: Value,
+    pub prev_hash: String,
+    pub hash: String,
+}
+
+impl AuditRecord {
+    fn compute_hash(&self) -> String {
+        sha256_hex(
+            format!(
+                "{}|{}|{}|{}|{}",
+                self.seq,
+                timestamp_string(&self.timestamp),
+                self.category,
+                canonical_json(&self.data),
+                self.prev_hash
+            )
+            .as_bytes(),
+        )
+    }
+}
+
+/// A signed Merkle root over a contiguous batch of records.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, ToSchema)]
+pub struct AuditCheckpoint {
+    pub first_seq: u64,
+    pub last_seq: u64,
+    pub merkle_root: String,
+    pub created_at: DateTime<Utc>,
+    pub signature: String,
+}
+
+impl AuditCheckpoint {
+    fn signing_input(&self) -> String {
+        format!(
+            "{}|{}|{}|{}",
+            self.first_seq,
+            self.last_seq,
+            self.merkle_root,
+            timestamp_string(&self.created_at)
+        )
+    }
+}
+
+/// Stands in for records pruned from the head of the log.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, ToSchema)]
+pub struct AuditAnchor {
+    /// Last pruned sequence number
+    pub through_seq: u64,
+    /// Hash of the last pruned record, which the first kept record points at
+    pub last_hash: String,
+    pub pruned_at: DateTime<Utc>,
+    pub signature: String,
+}
+
+impl AuditAnchor {
+    fn signing_input(&self) -> String {
+        format!(
+            "{}|{}|{}",
+            self.through_seq,
+            self.last_hash,
+            timestamp_string(&self.pruned_at)
+        )
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, ToSchema)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum AuditIssue {
+    /// A line could not be parsed
+    Unreadable {
+        line: usize,
+    },
+    /// The record's contents no longer match its hash
+    HashMismatch {
+        seq: u64,
+    },
+    /// The record does not point at the hash of the record before it
+    BrokenChain {
+        seq: u64,
+    },
+    SequenceGap {
+        expected: u64,
+        found: u64,
+    },
+    InvalidSignature {
+        last_seq: u64,
+    },
+    MerkleMismatch {
+        last_seq: u64,
+    },
+    /// Records covered by a checkpoint are missing from the end of the log
+    Truncated {
+        expected_last_seq: u64,
+        found_last_seq: Option<u64>,
+    },
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, ToSchema)]
+pub struct AuditVerification {
+    pub valid: bool,
+    pub records: usize,
+    pub checkpoints: usize,
+    /// Last sequence number covered by a valid signed checkpoint
+    pub sealed_through: Option<u64>,
+    pub issues: Vec<AuditIssue>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, ToSchema)]
+pub struct AuditExport {
+    pub format: String,
+    pub exported_at: DateTime<Utc>,
+    pub records: Vec<AuditRecord>,
+    pub checkpoints: Vec<AuditCheckpoint>,
+    pub verification: AuditVerification,
+    pub head_hash: String,
+    /// Present when old records were pruned
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub anchor: Option<AuditAnchor>,
+    /// HMAC over the format, export time, record count and head hash
+    pub signature: String,
+}
+
+impl AuditExport {
+    fn signing_input(&self) -> String {
+        format!(
+            "{}|{}|{}|{}",
+            self.format,
+            timestamp_string(&self.exported_at),
+            self.records.len(),
+            self.head_hash
+        )
+    }
+
+    /// Re-checks an export with the signing key, as an auditor would.
+    pub fn verify(&self, key: &[u8]) -> AuditVerification {
+        let mut verification = verify_entries(
+            &self.records,
+            &self.checkpoints,
+            self.anchor.as_ref(),
+            &[],
+            key,
+        );
+        let head = self
+            .records
+            .last()
+            .map(|r| r.hash.as_str())
+            .or(self.anchor.as_ref().map(|a| a.last_hash.as_str()))
+            .unwrap_or(GENESIS_HASH);
+        if head != self.head_hash || !verify_signature(key, &self.signing_input(), &self.signature)
+        {
+            verification.issues.push(AuditIssue::InvalidSignature {
+                last_seq: self.records.last().map(|r| r.seq).unwrap_or_default(),
+            });
+            verification.valid = false;
+        }
+        verification
+    }
+}
+
+struct ChainState {
+    next_seq: u64,
+    last_hash: String,
+    /// Hashes of records not yet covered by a checkpoint
+    unsealed: Vec<String>,
+}
+
+pub struct AuditLog {
+    path: PathBuf,
+    checkpoint_path: PathBuf,
+    anchor_path: PathBuf,
+    key: Vec<u8>,
+    state: Mutex<ChainState>,
+}
+
+impl AuditLog {
+    /// Opens the log at `path`, continuing the chain from its last record.
+    pub fn open(path: impl Into<PathBuf>, key: Vec<u8>) -> Result<Self> {
+        let path = path.into();
+        let checkpoint_path = path.with_file_name(CHECKPOINT_FILE);
+        let anchor_path = path.with_file_name(ANCHOR_FILE);
+        let (records, _) = read_lines::<AuditRecord>(&path)?;
+        let (checkpoints, _) = read_lines::<AuditCheckpoint>(&checkpoint_path)?;
+        let anchor = read_anchor(&anchor_path)?;
+
+        let sealed_through = checkpoints.iter().map(|c| c.last_seq).max();
+        let unsealed = records
+            .iter()
+            .filter(|r| sealed_through.is_none_or(|sealed| r.seq > sealed))
+            .map(|r| r.hash.clone())
+            .collect();
+        let state = match records.last() {
+            Some(last) => ChainState {
+                next_seq: last.seq + 1,
+                last_hash: last.hash.clone(),
+                unsealed,
+            },
+            None => ChainState {
+                next_seq: anchor.as_ref().map_or(0, |a| a.through_seq + 1),
+                last_hash: anchor
+                    .map(|a| a.last_hash)
+                    .unwrap_or_else(|| GENESIS_HASH.to_string()),
+                unsealed,
+            },
+        };
+
+        Ok(Self {
+            path,
+            checkpoint_path,
+            anchor_path,
+            key,
+            state: Mutex::new(state),
+        })
+    }
+
+    pub fn append(&self, category: &str, data: Value) -> Result<AuditRecord> {
+        let mut state = self.state.lock().unwrap_or_else(|e| e.into_inner());
+        let mut record = AuditRecord {
+            seq: state.next_seq,
+            timestamp: Utc::now(),
+            category: category.to_string(),
+            data,
+            prev_hash: state.last_hash.clone(),
+            hash: String::new(),
+        };
+        record.hash = record.compute_hash();
+        append_line(&self.path, &record)?;
+
+        state.next_seq += 1;
+        state.last_hash = record.hash.clone();
+        state.unsealed.push(record.hash.clone());
+        if state.unsealed.len() >= CHECKPOINT_INTERVAL {
+            self.write_checkpoint(&mut state)?;
+        }
+        Ok(record)
+    }
+
+    /// Writes a checkpoint for any records not yet covered by one.
+    pub fn seal(&self) -> Result<Option<AuditCheckpoint>> {
+        let mut state = self.state.lock().unwrap_or_else(|e| e.into_inner());
+        if state.unsealed.is_empty() {
+            return Ok(None);
+        }
+        self.write_checkpoint(&mut state).map(Some)
+    }
+
+    fn write_checkpoint(&self, state: &mut ChainState) -> Result<AuditCheckpoint> {
+        let mut checkpoint = AuditCheckpoint {
+            first_seq: state.next_seq - state.unsealed.len() as u64,
+            last_seq: state.next_seq - 1,
+            merkle_root: merkle_root(&state.unsealed),
+            created_at: Utc::now(),
+            signature: String::new(),
+        };
+        checkpoint.signature = sign(&self.key, &checkpoint.signing_input());
+        append_line(&self.checkpoint_path, &checkpoint)?;
+        state.unsealed.clear();
+        Ok(checkpoint)
+    }
+
+    pub fn records(&self) -> Result<Vec<AuditRecord>> {
+        Ok(read_lines(&self.path)?.0)
+    }
+
+    pub fn verify(&self) -> Result<AuditVerification> {
+        let _guard = self.state.lock().unwrap_or_else(|e| e.into_inner());
+        self.verify_locked()
+    }
+
+    fn verify_locked(&self) -> Result<AuditVerification> {
+        let (records, mut unreadable) = read_lines::<AuditRecord>(&self.path)?;
+        let (checkpoints, unreadable_checkpoints) =
+            read_lines::<AuditCheckpoint>(&self.checkpoint_path)?;
+        unreadable.extend(unreadable_checkpoints);
+        let anchor = read_anchor(&self.anchor_path)?;
+        Ok(verify_entries(
+            &records,
+            &checkpoints,
+            anchor.as_ref(),
+            &unreadable,
+            &self.key,
+        ))
+    }
+
+    /// Drops records older than `before`, a checkpointed batch at a time so
+    /// every kept record stays covered by its checkpoint. Refuses to touch a
+    /// log that does not verify, since rewriting it would hide the damage.
+    /// Returns how many records were dropped.
+    pub fn prune_before(&self, before: DateTime<Utc>) -> Result<usize> {
+        let _guard = self.state.lock().unwrap_or_else(|e| e.into_inner());
+        let verification = self.verify_locked()?;
+        if !verification.valid {
+            anyhow::bail!(
+                "Audit log does not verify ({} issues); not pruning it",
+                verification.issues.len()
+            );
+        }
+        let (records, _) = read_lines::<AuditRecord>(&self.path)?;
+        let (checkpoints, _) = read_lines::<AuditCheckpoint>(&self.checkpoint_path)?;
+        let last_pruned = checkpoints
+            .iter()
+            .filter_map(|c| records.iter().find(|r| r.seq == c.last_seq))
+            .filter(|r| r.timestamp < before)
+            .max_by_key(|r| r.seq);
+        let Some(last_pruned) = last_pruned else {
+            return Ok(0);
+        };
+
+        let mut anchor = AuditAnchor {
+            through_seq: last_pruned.seq,
+            last_hash: last_pruned.hash.clone(),
+            pruned_at: Utc::now(),
+            signature: String::new(),
+        };
+        anchor.signature = sign(&self.key, &anchor.signing_input());
+        let through = anchor.through_seq;
+        write_file(
+            &self.anchor_path,
+            serde_json::to_string(&anchor)?.as_bytes(),
+        )?;
+        write_lines(
+            &self.checkpoint_path,
+            checkpoints.iter().filter(|c| c.first_seq > through),
+        )?;
+        let kept: Vec<&AuditRecord> = records.iter().filter(|r| r.seq > through).collect();
+        write_lines(&self.path, kept.iter().copied())?;
+        Ok(records.len() - kept.len())
+    }
+
+    /// Seals outstanding records and returns a signed export of the whole log.
+    pub fn export(&self) -> Result<AuditExport> {
+        self.seal()?;
+        let _guard = self.state.lock().unwrap_or_else(|e| e.into_inner());
+        let (records, unreadable) = read_lines::<AuditRecord>(&self.path)?;
+        let (checkpoints, _) = read_lines::<AuditCheckpoint>(&self.checkpoint_path)?;
+        let anchor = read_anchor(&self.anchor_path)?;
+        let verification = verify_entries(
+            &records,
+            &checkpoints,
+            anchor.as_ref(),
+            &unreadable,
+            &self.key,
+        );
+
+        let mut export = AuditExport {
+            format: EXPORT_FORMAT.to_string(),
+            exported_at: Utc::now(),
+            head_hash: records
+                .last()
+                .map(|r| r.hash.clone())
+                .or(anchor.as_ref().map(|a| a.last_hash.clone()))
+                .unwrap_or_else(|| GENESIS_HASH.to_string()),
+            records,
+            checkpoints,
+            verification,
+            anchor,
+            signature: String::new(),
+        };
+        export.signature = sign(&self.key, &export.signing_input());
+        Ok(export)
+    }
+}
+
+static GLOBAL_AUDIT_LOG: OnceLock<Option<Arc<AuditLog>>> = OnceLock::new();
+
+/// The audit log in the state directory, signed with the key from the secret
+/// store. A key is generated on first use.
+pub fn global() -> Option<Arc<AuditLog>> {
+    GLOBAL_AUDIT_LOG
+        .get_or_init(|| {
+            let log = signing_key()
+                .and_then(|key| AuditLog::open(Paths::in_state_dir(AUDIT_LOG_FILE), key));
+            match log {
+                Ok(log) => Some(Arc::new(log)),
+                Err(e) => {
+                    tracing::error!("Audit log unavailable: {}", e);
+                    None
+                }
+            }
+        })
+        .clone()
+}
+
+/// Appends to the global audit log, logging rather than returning failures.
+pub fn record(category: &str, data: Value) {
+    if let Some(log) = global() {
+        if let Err(e) = log.append(category, data) {
+            tracing::error!("Failed to write {} audit entry: {}", category, e);
+        }
+    }
+}
+
+fn signing_key() -> Result<Vec<u8>> {
+    let config = Config::global();
+    if let Ok(key) = config.get_secret::<String>(SIGNING_KEY_SECRET) {
+        return Ok(key.into_bytes());
+    }
+    let key: String = rand::random::<[u8; 32]>()
+        .iter()
+        .map(|b| format!("{:02x}", b))
+        .collect();
+    config
+        .set_secret(SIGNING_KEY_SECRET, &key)
+        .context("Failed to store audit signing key")?;
+    Ok(key.into_bytes())
+}
+
+/// Checks hash links, sequence numbers and every checkpoint's signature and
+/// Merkle root. The chain starts at `anchor` when old records were pruned.
+/// `unreadable` lists line numbers that failed to parse.
+pub fn verify_entries(
+    records: &[AuditRecord],
+    checkpoints: &[AuditCheckpoint],
+    anchor: Option<&AuditAnchor>,
+    unreadable: &[usize],
+    key: &[u8],
+) -> AuditVerification {
+    let mut issues: Vec<AuditIssue> = unreadable
+        .iter()
+        .map(|line| AuditIssue::Unreadable { line: *line })
+        .collect();
+
+    let mut expected_seq = 0;
+    let mut prev_hash = GENESIS_HASH;
+    if let Some(anchor) = anchor {
+        if verify_signature(key, &anchor.signing_input(), &anchor.signature) {
+            expected_seq = anchor.through_seq + 1;
+            prev_hash = &anchor.last_hash;
+        } else {
+            issues.push(AuditIssue::InvalidSignature {
+                last_seq: anchor.through_seq,
+            });
+        }
+    }
+    for record in records {
+        if record.seq != expected_seq {
+            issues.push(AuditIssue::SequenceGap {
+                expected: expected_seq,
+                found: record.seq,
+            });
+        }
+        if record.prev_hash != prev_hash {
+            issues.push(AuditIssue::BrokenChain { seq: record.seq });
+        }
+        if record.compute_hash() != record.hash {
+            issues.push(AuditIssue::HashMismatch { seq: record.seq });
+        }
+        expected_seq = record.seq + 1;
+        prev_hash = &record.hash;
+    }
+
+    let last_seq = records.last().map(|r| r.seq);
+    let mut sealed_through = None;
+    for checkpoint in checkpoints {
+        if !verify_signature(key, &checkpoint.signing_input(), &checkpoint.signature) {
+            issues.push(AuditIssue::InvalidSignature {
+                last_seq: checkpoint.last_seq,
+            });
+            continue;
+        }
+        if last_seq.is_none_or(|last| last < checkpoint.last_seq) {
+            issues.push(AuditIssue::Truncated {
+                expected_last_seq: checkpoint.last_seq,
+                found_last_seq: last_seq,
+            });
+            continue;
+        }
+        let hashes: Vec<String> = records
+            .iter()
+            .filter(|r| (checkpoint.first_seq..=checkpoint.last_seq).contains(&r.seq))
+            .map(|r| r.hash.clone())
+            .collect();
+        if merkle_root(&hashes) != checkpoint.merkle_root {
+            issues.push(AuditIssue::MerkleMismatch {
+                last_seq: checkpoint.last_seq,
+            });
+            continue;
+        }
+        sealed_through = sealed_through.max(Some(checkpoint.last_seq));
+    }
+
+    AuditVerification {
+        valid: issues.is_empty(),
+        records: records.len(),
+        checkpoints: checkpoints.len(),
+        sealed_through,
+        issues,
+    }
+}
+
+/// Merkle root over hex leaf hashes; an odd node is paired with itself.
+pub fn merkle_root(hashes: &[String]) -> String {
+    if hashes.is_empty() {
+        return GENESIS_HASH.to_string();
+    }
+    let mut level = hashes.to_vec();
+    while level.len() > 1 {
+        level = level
+            .chunks(2)
+            .map(|pair| {
+                let right = pair.get(1).unwrap_or(&pair[0]);
+                sha256_hex(format!("{}{}", pair[0], right).as_bytes())
+            })
+            .collect();
+    }
+    level.remove(0)
+}
+
+fn sha256_hex(bytes: &[u8]) -> String {
+    let mut hasher = Sha256::new();
+    hasher.update(bytes);
+    format!("{:x}", hasher.finalize())
+}
+
+fn sign(key: &[u8], message: &str) -> String {
+    let mut mac = HmacSha256::new_from_slice(key).expect("HMAC accepts any key length");
+    mac.update(message.as_bytes());
+    format!("{:x}", mac.finalize().into_bytes())
+}
+
+/// Compares in constant time, so a forger learns nothing from how long a
+/// rejection takes.
+fn verify_signature(key: &[u8], message: &str, signature: &str) -> bool {
+    let Some(signature) = decode_hex(signature) else {
+        return false;
+    };
+    let mut mac = HmacSha256::new_from_slice(key).expect("HMAC accepts any key length");
+    mac.update(message.as_bytes());
+    mac.verify_slice(&signature).is_ok()
+}
+
+fn decode_hex(hex: &str) -> Option<Vec<u8>> {
+    if hex.len() % 2 != 0 {
+        return None;
+    }
+    (0..hex.len())
+        .step_by(2)
+        .map(|i| u8::from_str_radix(hex.get(i..i + 2)?, 16).ok())
+        .collect()
+}
+
+fn timestamp_string(timestamp: &DateTime<Utc>) -> String {
+    timestamp.to_rfc3339_opts(SecondsFormat::Nanos, true)
+}
+
+/// JSON with object keys sorted, so hashes don't depend on map ordering.
+fn canonical_json(value: &Value) -> String {
+    match value {
+        Value::Object(map) => {
+            let mut entries: Vec<_> = map.iter().collect();
+            entries.sort_by(|a, b| a.0.cmp(b.0));
+            let fields: Vec<String> = entries
+                .into_iter()
+                .map(|(key, value)| {
+                    format!("{}:{}", Value::from(key.as_str()), canonical_json(value))
+                })
+                .collect();
+            format!("{{{}}}", fields.join(","))
+        }
+        Value::Array(items) => {
+            let items: Vec<String> = items.iter().map(canonical_json).collect();
+            format!("[{}]", items.join(","))
+        }
+        other => other.to_string(),
+    }
+}
+
+fn append_line<T: Serialize>(path: &Path, entry: &T) -> Result<()> {
+    if let Some(parent) = path.parent() {
+        std::fs::create_dir_all(parent)?;
+    }
+    let mut file = std::fs::OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(path)?;
+    writeln!(file, "{}", serde_json::to_string(entry)?)?;
+    Ok(())
+}
+
+/// Replaces `path` in one step, so a crash leaves the old or the new file.
+fn write_file(path: &Path, contents: &[u8]) -> Result<()> {
+    let tmp = path.with_extension("tmp");
+    std::fs::write(&tmp, contents)?;
+    std::fs::rename(&tmp, path)?;
+    Ok(())
+}
+
+fn write_lines<'a, T: Serialize + 'a>(
+    path: &Path,
+    entries: impl Iterator<Item = &'a T>,
+) -> Result<()> {
+    let mut content = String::new();
+    for entry in entries {
+        content.push_str(&serde_json::to_string(entry)?);
+        content.push('\n');
+    }
+    write_file(path, content.as_bytes())
+}
+
+fn read_anchor(path: &Path) -> Result<Option<AuditAnchor>> {
+    if !path.exists() {
+        return Ok(None);
+    }
+    let content = std::fs::read_to_string(path)
+        .with_context(|| format!("Failed to read {}", path.display()))?;
+    Ok(Some(serde_json::from_str(&content).with_context(|| {
+        format!("{} is not a valid audit anchor", path.display())
+    })?))
+}
+
+/// Parsed entries plus the 1-based numbers of lines that failed to parse.
+fn read_lines<T: for<'de> Deserialize<'de>>(path: &Path) -> Result<(Vec<T>, Vec<usize>)> {
+    if !path.exists() {
+        return Ok((Vec::new(), Vec::new()));
+    }
+    let content = std::fs::read_to_string(path)
+        .with_context(|| format!("Failed to read {}", path.display()))?;
+    let mut entries = Vec::new();
+    let mut unreadable = Vec::new();
+    for (index, line) in content.lines().enumerate() {
+        if line.trim().is_empty() {
+            continue;
+        }
+        match serde_json::from_str(line) {
+            Ok(entry) => entries.push(entry),
+            Err(_) => unreadable.push(index + 1),
+        }
+    }
+    Ok((entries, unreadable))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::Duration;
+    use serde_json::json;
+    use tempfile::TempDir;
+
+    const KEY: &[u8] = b"test-key";
+
+    fn log_with(dir: &TempDir, count: usize) -> AuditLog {
+        let log = AuditLog::open(dir.path().join(AUDIT_LOG_FILE), KEY.to_vec()).unwrap();
+        for i in 0..count {
+            log.append("test", json!({"i": i, "b": true})).unwrap();
+        }
+        log
+    }
+
+    fn rewrite_records(dir: &TempDir, f: impl FnOnce(&mut Vec<AuditRecord>)) {
+        let path = dir.path().join(AUDIT_LOG_FILE);
+        let mut records: Vec<AuditRecord> = read_lines(&path).unwrap().0;
+        f(&mut records);
+        let content: String = records
+            .iter()
+            .map(|r| format!("{}\n", serde_json::to_string(r).unwrap()))
+            .collect();
+        std::fs::write(path, content).unwrap();
+    }
+
+    #[test]
+    fn test_chain_verifies_and_survives_reopen() {
+        let dir = TempDir::new().unwrap();
+        log_with(&dir, 3);
+        let log = log_with(&dir, 2);
+
+        let records = log.records().unwrap();
+        assert_eq!(records.len(), 5);
+        assert_eq!(records[3].prev_hash, records[2].hash);
+        assert!(log.verify().unwrap().valid);
+    }
+
+    #[test]
+    fn test_checkpoints_are_written_every_interval() {
+        let dir = TempDir::new().unwrap();
+        let log = log_with(&dir, CHECKPOINT_INTERVAL + 1);
+        let verification = log.verify().unwrap();
+        assert!(verification.valid);
+        assert_eq!(verification.checkpoints, 1);
+        assert_eq!(
+            verification.sealed_through,
+            Some(CHECKPOINT_INTERVAL as u64 - 1)
+        );
+    }
+
+    #[test]
+    fn test_edited_record_is_detected() {
+        let dir = TempDir::new().unwrap();
+        let log = log_with(&dir, 3);
+        rewrite_records(&dir, |records| records[1].data = json!({"i": 99}));
+        assert_eq!(
+            log.verify().unwrap().issues,
+            vec![AuditIssue::HashMismatch { seq: 1 }]
+        );
+    }
+
+    #[test]
+    fn test_removed_record_is_detected() {
+        let dir = TempDir::new().unwrap();
+        let log = log_with(&dir, 3);
+        rewrite_records(&dir, |records| {
+            records.remove(1);
+        });
+        assert_eq!(
+            log.verify().unwrap().issues,
+            vec![
+                AuditIssue::SequenceGap {
+                    expected: 1,
+                    found: 2
+                },
+                AuditIssue::BrokenChain { seq: 2 }
+            ]
+        );
+    }
+
+    #[test]
+    fn test_truncation_is_detected() {
+        let dir = TempDir::new().unwrap();
+        let log = log_with(&dir, 4);
+        log.seal().unwrap();
+        rewrite_records(&dir, |records| records.truncate(2));
+        assert_eq!(
+            log.verify().unwrap().issues,
+            vec![AuditIssue::Truncated {
+                expected_last_seq: 3,
+                found_last_seq: Some(1)
+            }]
+        );
+    }
+
+    #[test]
+    fn test_rewritten_chain_fails_checkpoint() {
+        let dir = TempDir::new().unwrap();
+        let log = log_with(&dir, 3);
+        log.seal().unwrap();
+        rewrite_records(&dir, |records| {
+            let mut prev = GENESIS_HASH.to_string();
+            for record in records.iter_mut() {
+                record.data = json!("forged");
+                record.prev_hash = prev;
+                record.hash = record.compute_hash();
+                prev = record.hash.clone();
+            }
+        });
+        assert_eq!(
+            log.verify().unwrap().issues,
+            vec![AuditIssue::MerkleMismatch { last_seq: 2 }]
+        );
+    }
+
+    #[test]
+    fn test_export_is_signed() {
+        let dir = TempDir::new().unwrap();
+        let log = log_with(&dir, 3);
+        let mut export = log.export().unwrap();
+        assert!(export.verification.valid);
+        assert_eq!(export.checkpoints.len(), 1);
+        assert!(export.verify(KEY).valid);
+        assert!(!export.verify(b"wrong-key").valid);
+
+        export.records.pop();
+        assert!(!export.verify(KEY).valid);
+    }
+
+    #[test]
+    fn test_pruned_log_still_verifies() {
+        let dir = TempDir::new().unwrap();
+        let log = log_with(&dir, CHECKPOINT_INTERVAL + 5);
+        assert_eq!(
+            log.prune_before(Utc::now() - Duration::hours(1)).unwrap(),
+            0
+        );
+
+        let pruned = log.prune_before(Utc::now() + Duration::hours(1)).unwrap();
+        assert_eq!(pruned, CHECKPOINT_INTERVAL);
+        assert_eq!(log.records().unwrap().len(), 5);
+        assert!(log.verify().unwrap().valid);
+
+        let log = log_with(&dir, 1);
+        assert_eq!(log.records().unwrap()[0].seq, CHECKPOINT_INTERVAL as u64);
+        assert!(log.verify().unwrap().valid);
+        assert!(log.export().unwrap().verify(KEY).valid);
+
+        rewrite_records(&dir, |records| {
+            records.remove(0);
+        });
+        assert!(!log.verify().unwrap().valid);
+        assert!(log.prune_before(Utc::now()).is_err());
+    }
+
+    #[test]
+    fn test_canonical_json_sorts_keys() {
+        assert_eq!(
+            canonical_json(&json!({"b": [1, {"d": 1, "c": "x"}], "a": null})),
+            r#"{"a":null,"b":[1,{"c":"x","d":1}]}"#
+        );
+    }
+}
@@ -1,4 +1,7 @@
+pub mod at_rest;
+pub mod audit_log;
 pub mod classification_client;
+pub mod egress;
 pub mod patterns;
 pub mod scanner;
 pub mod security_inspector;
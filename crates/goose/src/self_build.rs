@@ -0,0 +1,408 @@
+//! Building goose's own binaries for other machines.
+//!
+//! `SelfBuilder` compiles the workspace binaries for each configured target
+//! triple, using plain cargo for the host and `cross` or `cargo zigbuild` for
+//! the rest, then writes a checksum manifest next to the artifacts so an
+//! update channel on another machine can verify what it downloads. The
+//! artifacts and manifest can then be published to a directory, an S3 prefix
+//! (through the `aws` CLI) or a draft GitHub release (through `gh`).
+//!
+//! Targets come from `GOOSE_SELF_BUILD_TARGETS` (the host when unset) and the
+//! build tool from `GOOSE_SELF_BUILD_TOOL` (`cross` when unset).
+
+use crate::config::Config;
+use crate::subprocess::configure_subprocess;
+use anyhow::{anyhow, bail, Context, Result};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::fmt;
+use std::io::Read;
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+use tokio::process::Command;
+
+pub const SELF_BUILD_TARGETS_KEY: &str = "GOOSE_SELF_BUILD_TARGETS";
+pub const SELF_BUILD_TOOL_KEY: &str = "GOOSE_SELF_BUILD_TOOL";
+pub const MANIFEST_FILE: &str = "manifest.json";
+pub const CHECKSUMS_FILE: &str = "SHA256SUMS";
+const DEFAULT_BINARIES: &[&str] = &["goose", "goosed"];
+
+/// How binaries for a target other than the host are built.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum CrossTool {
+    #[default]
+    Cross,
+    Zigbuild,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Artifact {
+    pub target: String,
+    pub binary: String,
+    /// File name within the output directory
+    pub file: String,
+    pub sha256: String,
+    pub size: u64,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ArtifactManifest {
+    pub version: String,
+    pub artifacts: Vec<Artifact>,
+}
+
+impl ArtifactManifest {
+    /// Lines in the format `sha256sum --check` reads.
+    pub fn checksums(&self) -> String {
+        self.artifacts
+            .iter()
+            .map(|a| format!("{}  {}\n", a.sha256, a.file))
+            .collect()
+    }
+
+    pub fn write(&self, dir: &Path) -> Result<()> {
+        std::fs::write(dir.join(MANIFEST_FILE), serde_json::to_string_pretty(self)?)?;
+        std::fs::write(dir.join(CHECKSUMS_FILE), self.checksums())?;
+        Ok(())
+    }
+}
+
+/// Where built artifacts go.
+#[derive(Debug, Clone, PartialEq)]
+pub enum PublishTarget {
+    Directory(PathBuf),
+    S3 { bucket: String, prefix: String },
+    GithubDraft { repo: String, tag: String },
+}
+
+impl FromStr for PublishTarget {
+    type Err = anyhow::Error;
+
+    /// Parses `s3://bucket/prefix`, `github:owner/repo@tag` or a directory.
+    fn from_str(s: &str) -> Result<Self> {
+        if let Some(rest) = s.strip_prefix("s3://") {
+            let (bucket, prefix) = rest.split_once('/').unwrap_or((rest, ""));
+            if bucket.is_empty() {
+                bail!("S3 location '{}' has no bucket", s);
+            }
+            return Ok(Self::S3 {
+                bucket: bucket.to_string(),
+                prefix: prefix.trim_end_matches('/').to_string(),
+            });
+        }
+        if let Some(rest) = s.strip_prefix("github:") {
+            let (repo, tag) = rest
+                .split_once('@')
+                .filter(|(repo, tag)| repo.contains('/') && !tag.is_empty())
+                .ok_or_else(|| anyhow!("Expected github:owner/repo@tag, got '{}'", s))?;
+            return Ok(Self::GithubDraft {
+                repo: repo.to_string(),
+                tag: tag.to_string(),
+            });
+        }
+        Ok(Self::Directory(PathBuf::from(s)))
+    }
+}
+
+impl fmt::Display for PublishTarget {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Directory(dir) => write!(f, "{}", dir.display()),
+            Self::S3 { bucket, prefix } => write!(f, "s3://{}/{}", bucket, prefix),
+            Self::GithubDraft { repo, tag } => write!(f, "github:{}@{}", repo, tag),
+        }
+    }
+}
+
+/// Target triple of the running binary, as rustc names it.
+pub fn host_target() -> String {
+    let os = match std::env::consts::OS {
+        "macos" => "apple-darwin",
+        "windows" => "pc-windows-msvc",
+        _ => "unknown-linux-gnu",
+    };
+    format!("{}-{}", std::env::consts::ARCH, os)
+}
+
+fn binary_file_name(binary: &str, target: &str) -> String {
+    if target.contains("windows") {
+        format!("{}.exe", binary)
+    } else {
+        binary.to_string()
+    }
+}
+
+pub struct SelfBuilder {
+    workspace_dir: PathBuf,
+    targets: Vec<String>,
+    tool: CrossTool,
+    binaries: Vec<String>,
+}
+
+impl SelfBuilder {
+    pub fn new(workspace_dir: impl Into<PathBuf>) -> Self {
+        let config = Config::global();
+        let targets = config
+            .get_param::<Vec<String>>(SELF_BUILD_TARGETS_KEY)
+            .ok()
+            .filter(|targets| !targets.is_empty())
+            .unwrap_or_else(|| vec![host_target()]);
+        Self {
+            workspace_dir: workspace_dir.into(),
+            targets,
+            tool: config.get_param(SELF_BUILD_TOOL_KEY).unwrap_or_default(),
+            binaries: DEFAULT_BINARIES.iter().map(|b| b.to_string()).collect(),
+        }
+    }
+
+    pub fn with_targets(mut self, targets: Vec<String>) -> Self {
+        self.targets = targets;
+        self
+    }
+
+    pub fn with_tool(mut self, tool: CrossTool) -> Self {
+        self.tool = tool;
+        self
+    }
+
+    pub fn targets(&self) -> &[String] {
+        &self.targets
+    }
+
+    /// Program and arguments that build the binaries for `target`.
+    fn build_command(&self, target: &str) -> (String, Vec<String>) {
+        let (program, mut args) = if target == host_target() {
+            ("cargo", vec!["build"])
+        } else {
+            match self.tool {
+                CrossTool::Cross => ("cross", vec!["build"]),
+                CrossTool::Zigbuild => ("cargo", vec!["zigbuild"]),
+            }
+        };
+        args.extend(["--release", "--target", target]);
+        let mut args: Vec<String> = args.into_iter().map(String::from).collect();
+        for binary in &self.binaries {
+            args.push("--bin".to_string());
+            args.push(binary.clone());
+        }
+        (program.to_string(), args)
+    }
+
+    /// Builds every target and copies the binaries into `out_dir` as
+    /// `<binary>-<target>`, with the manifest alongside.
+    pub async fn build_all(&self, version: &str, out_dir: &Path) -> Result<ArtifactManifest> {
+        std::fs::create_dir_all(out_dir)?;
+        let mut artifacts = Vec::new();
+        for target in &self.targets {
+            let (program, args) = self.build_command(target);
+            tracing::info!(target = %target, "Building {} {}", program, args.join(" "));
+            let mut command = Command::new(&program);
+            command.args(&args).current_dir(&self.workspace_dir);
+            run(command)
+                .await
+                .with_context(|| format!("Build for {} failed", target))?;
+
+            for binary in &self.binaries {
+                let built = self
+                    .workspace_dir
+                    .join("target")
+                    .join(target)
+                    .join("release")
+                    .join(binary_file_name(binary, target));
+                let file = binary_file_name(&format!("{}-{}", binary, target), target);
+                std::fs::copy(&built, out_dir.join(&file))
+                    .with_context(|| format!("Missing build output {}", built.display()))?;
+                artifacts.push(artifact(out_dir, target, binary, file)?);
+            }
+        }
+
+        let manifest = ArtifactManifest {
+            version: version.to_string(),
+            artifacts,
+        };
+        manifest.write(out_dir)?;
+        Ok(manifest)
+    }
+}
+
+fn artifact(dir: &Path, target: &str, binary: &str, file: String) -> Result<Artifact> {
+    let mut reader = std::fs::File::open(dir.join(&file))?;
+    let mut hasher = Sha256::new();
+    let mut buffer = [0u8; 64 * 1024];
+    let mut size = 0;
+    loop {
+        let read = reader.read(&mut buffer)?;
+        if read == 0 {
+            break;
+        }
+        hasher.update(&buffer[..read]);
+        size += read as u64;
+    }
+    Ok(Artifact {
+        target: target.to_string(),
+        binary: binary.to_string(),
+        file,
+        sha256: format!("{:x}", hasher.finalize()),
+        size,
+    })
+}
+
+/// Copies or uploads the artifacts of `manifest` in `dir`, manifest files
+/// included.
+pub async fn publish(manifest: &ArtifactManifest, dir: &Path, to: &PublishTarget) -> Result<()> {
+    let files: Vec<PathBuf> = manifest
+        .artifacts
+        .iter()
+        .map(|a| dir.join(&a.file))
+        .chain([dir.join(MANIFEST_FILE), dir.join(CHECKSUMS_FILE)])
+        .collect();
+
+    match to {
+        PublishTarget::Directory(dest) => {
+            std::fs::create_dir_all(dest)?;
+            for file in &files {
+                if let Some(name) = file.file_name() {
+                    std::fs::copy(file, dest.join(name))?;
+                }
+            }
+        }
+        PublishTarget::S3 { bucket, prefix } => {
+            for file in &files {
+                let name = file.file_name().unwrap_or_default().to_string_lossy();
+                let key = if prefix.is_empty() {
+                    name.to_string()
+                } else {
+                    format!("{}/{}", prefix, name)
+                };
+                let mut command = Command::new("aws");
+                command
+                    .args(["s3", "cp"])
+                    .arg(file)
+                    .arg(format!("s3://{}/{}", bucket, key));
+                run(command).await?;
+            }
+        }
+        PublishTarget::GithubDraft { repo, tag } => {
+            let mut command = Command::new("gh");
+            command
+                .args(["release", "create", tag, "--draft", "--repo", repo])
+                .arg("--title")
+                .arg(format!("goose {}", manifest.version))
+                .args(&files);
+            run(command).await?;
+        }
+    }
+    tracing::info!(to = %to, "Published {} artifacts", manifest.artifacts.len());
+    Ok(())
+}
+
+async fn run(mut command: Command) -> Result<()> {
+    let program = command.as_std().get_program().to_string_lossy().to_string();
+    configure_subprocess(&mut command);
+    let output = command
+        .output()
+        .await
+        .with_context(|| format!("Failed to run {}", program))?;
+    if !output.status.success() {
+        bail!(
+            "{} failed: {}",
+            program,
+            String::from_utf8_lossy(&output.stderr)
+        );
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_build_command_per_target() {
+        let builder = SelfBuilder {
+            workspace_dir: PathBuf::from("/src/goose"),
+            targets: vec![],
+            tool: CrossTool::Zigbuild,
+            binaries: vec!["goosed".to_string()],
+        };
+        let (program, args) = builder.build_command(&host_target());
+        assert_eq!(program, "cargo");
+        assert_eq!(args[0], "build");
+
+        let (program, args) = builder.build_command("aarch64-unknown-linux-gnu");
+        assert_eq!(program, "cargo");
+        assert_eq!(
+            args,
+            vec![
+                "zigbuild",
+                "--release",
+                "--target",
+                "aarch64-unknown-linux-gnu",
+                "--bin",
+                "goosed"
+            ]
+        );
+        assert_eq!(
+            binary_file_name("goosed-x86_64-pc-windows-gnu", "x86_64-pc-windows-gnu"),
+            "goosed-x86_64-pc-windows-gnu.exe"
+        );
+    }
+
+    #[tokio::test]
+    async fn test_manifest_checksums_and_directory_publish() {
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::write(dir.path().join("goose-x86_64-unknown-linux-gnu"), b"binary").unwrap();
+        let manifest = ArtifactManifest {
+            version: "1.2.0".to_string(),
+            artifacts: vec![artifact(
+                dir.path(),
+                "x86_64-unknown-linux-gnu",
+                "goose",
+                "goose-x86_64-unknown-linux-gnu".to_string(),
+            )
+            .unwrap()],
+        };
+        assert_eq!(manifest.artifacts[0].size, 6);
+        assert_eq!(
+            manifest.checksums(),
+            format!(
+                "{:x}  goose-x86_64-unknown-linux-gnu\n",
+                Sha256::digest(b"binary")
+            )
+        );
+        manifest.write(dir.path()).unwrap();
+
+        let release = dir.path().join("release");
+        let to: PublishTarget = release.to_string_lossy().parse().unwrap();
+        publish(&manifest, dir.path(), &to).await.unwrap();
+        let published: ArtifactManifest =
+            serde_json::from_str(&std::fs::read_to_string(release.join(MANIFEST_FILE)).unwrap())
+                .unwrap();
+        assert_eq!(published, manifest);
+        assert!(release.join(CHECKSUMS_FILE).exists());
+    }
+
+    #[test]
+    fn test_parse_publish_targets() {
+        assert_eq!(
+            "s3://goose-releases/ota/1.2.0/"
+                .parse::<PublishTarget>()
+                .unwrap(),
+            PublishTarget::S3 {
+                bucket: "goose-releases".to_string(),
+                prefix: "ota/1.2.0".to_string()
+            }
+        );
+        assert_eq!(
+            "github:block/goose@v1.2.0"
+                .parse::<PublishTarget>()
+                .unwrap(),
+            PublishTarget::GithubDraft {
+                repo: "block/goose".to_string(),
+                tag: "v1.2.0".to_string()
+            }
+        );
+        assert!("github:goose".parse::<PublishTarget>().is_err());
+    }
+}
@@ -0,0 +1,479 @@
+//! Files uploaded into a session, such as PDFs, screenshots and CSV exports.
+//!
+//! Uploads are kept in the [artifact registry](crate::artifacts) with
+//! session retention, along with the sniffed type and a short preview that
+//! is shown to the agent. They are removed together with the session, so
+//! retention purges cover attachments too.
+
+use crate::artifacts::{
+    ArtifactFilter, ArtifactKind, ArtifactRecord, ArtifactRegistry, NewArtifact, RetentionClass,
+};
+use crate::config::Config;
+use anyhow::{bail, Context, Result};
+use base64::Engine;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::Path;
+use utoipa::ToSchema;
+
+/// Folder next to the database where attachments were stored before the
+/// artifact registry
+pub const ATTACHMENTS_FOLDER: &str = "attachments";
+pub const MAX_ATTACHMENT_BYTES_KEY: &str = "GOOSE_ATTACHMENT_MAX_BYTES";
+pub const DEFAULT_MAX_ATTACHMENT_BYTES: u64 = 20 * 1024 * 1024;
+
+const PRODUCER: &str = "attachments";
+const PREVIEW_CHARS: usize = 1_000;
+const CSV_PREVIEW_ROWS: usize = 5;
+/// Upper bound on the text handed back by `read`
+const MAX_READ_CHARS: usize = 100_000;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum AttachmentKind {
+    Pdf,
+    Image,
+    Csv,
+    Text,
+    Binary,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, ToSchema)]
+pub struct Attachment {
+    pub id: String,
+    pub filename: String,
+    pub kind: AttachmentKind,
+    pub mime_type: String,
+    pub size: u64,
+    pub created_at: DateTime<Utc>,
+    /// Start of the extracted text, or the column layout for CSV files
+    pub preview: Option<String>,
+}
+
+/// What the agent gets when it reads an attachment
+#[derive(Debug, Clone, PartialEq)]
+pub enum AttachmentContent {
+    Text(String),
+    Image { data: String, mime_type: String },
+}
+
+/// Works out what an upload is from its leading bytes, falling back to the
+/// file extension only to tell CSV apart from other text
+pub fn sniff(bytes: &[u8], filename: &str) -> (AttachmentKind, &'static str) {
+    let image = |mime| (AttachmentKind::Image, mime);
+    if bytes.starts_with(b"%PDF-") {
+        return (AttachmentKind::Pdf, "application/pdf");
+    }
+    if bytes.starts_with(b"\x89PNG\r\n\x1a\n") {
+        return image("image/png");
+    }
+    if bytes.starts_with(&[0xFF, 0xD8, 0xFF]) {
+        return image("image/jpeg");
+    }
+    if bytes.starts_with(b"GIF87a") || bytes.starts_with(b"GIF89a") {
+        return image("image/gif");
+    }
+    if bytes.len() >= 12 && &bytes[..4] == b"RIFF" && &bytes[8..12] == b"WEBP" {
+        return image("image/webp");
+    }
+    if std::str::from_utf8(bytes).is_err() || bytes.contains(&0) {
+        return (AttachmentKind::Binary, "application/octet-stream");
+    }
+    let extension = Path::new(filename)
+        .extension()
+        .and_then(|e| e.to_str())
+        .map(|e| e.to_ascii_lowercase());
+    match extension.as_deref() {
+        Some("csv") => (AttachmentKind::Csv, "text/csv"),
+        Some("tsv") => (AttachmentKind::Csv, "text/tab-separated-values"),
+        Some("svg") => (AttachmentKind::Text, "image/svg+xml"),
+        _ => (AttachmentKind::Text, "text/plain"),
+    }
+}
+
+pub(crate) fn pdf_text(bytes: &[u8]) -> Result<String> {
+    let document = lopdf::Document::load_mem(bytes).context("Failed to parse PDF")?;
+    let pages: Vec<u32> = document.get_pages().keys().copied().collect();
+    Ok(document.extract_text(&pages)?)
+}
+
+/// Column names, a few rows and the row count
+fn csv_preview(text: &str, filename: &str) -> String {
+    let delimiter = if filename.to_ascii_lowercase().ends_with(".tsv") {
+        '\t'
+    } else {
+        ','
+    };
+    let mut lines = text.lines().filter(|l| !l.trim().is_empty());
+    let Some(header) = lines.next() else {
+        return "Empty file".to_string();
+    };
+    let columns: Vec<&str> = header.split(delimiter).map(str::trim).collect();
+    let rows: Vec<&str> = lines.collect();
+
+    let mut preview = format!(
+        "{} columns: {}\n{} rows",
+        columns.len(),
+        columns.join(", "),
+        rows.len()
+    );
+    for row in rows.iter().take(CSV_PREVIEW_ROWS) {
+        preview.push('\n');
+        preview.push_str(row);
+    }
+    preview
+}
+
+fn preview(kind: AttachmentKind, bytes: &[u8], filename: &str) -> Option<String> {
+    let text = match kind {
+        AttachmentKind::Pdf => pdf_text(bytes).ok()?,
+        AttachmentKind::Csv => return Some(csv_preview(&String::from_utf8_lossy(bytes), filename)),
+        AttachmentKind::Text => String::from_utf8_lossy(bytes).into_owned(),
+        AttachmentKind::Image | AttachmentKind::Binary => return None,
+    };
+    let text = text.trim();
+    (!text.is_empty()).then(|| crate::utils::safe_truncate(text, PREVIEW_CHARS))
+}
+
+pub(super) fn check_component(value: &str, what: &str) -> Result<()> {
+    let valid = !value.is_empty()
+        && value
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '-');
+    if !valid {
+        bail!("Invalid {}: {}", what, value);
+    }
+    Ok(())
+}
+
+/// Records what the registry does not already keep for an attachment
+#[derive(Debug, Serialize, Deserialize)]
+struct AttachmentDetails {
+    kind: AttachmentKind,
+    preview: Option<String>,
+}
+
+fn attachment(record: ArtifactRecord) -> Result<Attachment> {
+    let details: AttachmentDetails = serde_json::from_value(record.metadata.unwrap_or_default())
+        .with_context(|| format!("Attachment {} has no details", record.id))?;
+    Ok(Attachment {
+        id: record.id,
+        filename: record.name,
+        kind: details.kind,
+        mime_type: record.mime_type.unwrap_or_default(),
+        size: record.size,
+        created_at: record.created_at,
+        preview: details.preview,
+    })
+}
+
+#[derive(Debug, Clone)]
+pub struct AttachmentStore {
+    registry: ArtifactRegistry,
+    max_bytes: u64,
+}
+
+impl AttachmentStore {
+    pub fn new(registry: ArtifactRegistry) -> Self {
+        Self {
+            registry,
+            max_bytes: Config::global()
+                .get_param::<u64>(MAX_ATTACHMENT_BYTES_KEY)
+                .ok()
+                .filter(|max| *max > 0)
+                .unwrap_or(DEFAULT_MAX_ATTACHMENT_BYTES),
+        }
+    }
+
+    pub fn with_max_bytes(mut self, max_bytes: u64) -> Self {
+        self.max_bytes = max_bytes;
+        self
+    }
+
+    pub fn max_bytes(&self) -> u64 {
+        self.max_bytes
+    }
+
+    fn session_filter(&self, session_id: &str) -> Result<ArtifactFilter> {
+        check_component(session_id, "session id")?;
+        Ok(ArtifactFilter {
+            session_id: Some(session_id.to_string()),
+            ..ArtifactFilter::producer(PRODUCER)
+        })
+    }
+
+    fn new_artifact(
+        session_id: &str,
+        filename: String,
+        mime_type: &str,
+        details: &AttachmentDetails,
+    ) -> Result<NewArtifact> {
+        Ok(
+            NewArtifact::new(PRODUCER, ArtifactKind::Attachment, filename)
+                .for_session(session_id)
+                .with_retention(RetentionClass::Session)
+                .with_mime_type(mime_type)
+                .with_metadata(serde_json::to_value(details)?),
+        )
+    }
+
+    pub fn store(&self, session_id: &str, filename: &str, bytes: &[u8]) -> Result<Attachment> {
+        check_component(session_id, "session id")?;
+        if bytes.is_empty() {
+            bail!("Attachment is empty");
+        }
+        if bytes.len() as u64 > self.max_bytes {
+            bail!(
+                "Attachment is {} bytes, the limit is {}",
+                bytes.len(),
+                self.max_bytes
+            );
+        }
+        // Only the final component of the name is kept, it is never used as a path
+        let filename = Path::new(filename)
+            .file_name()
+            .and_then(|n| n.to_str())
+            .unwrap_or("attachment")
+            .to_string();
+        let (kind, mime_type) = sniff(bytes, &filename);
+        let details = AttachmentDetails {
+            kind,
+            preview: preview(kind, bytes, &filename),
+        };
+        let artifact = Self::new_artifact(session_id, filename, mime_type, &details)?;
+        attachment(self.registry.store(artifact, bytes)?)
+    }
+
+    pub fn get(&self, session_id: &str, id: &str) -> Result<Attachment> {
+        let filter = self.session_filter(session_id)?;
+        let record = self
+            .registry
+            .get(id)
+            .ok()
+            .filter(|record| filter.matches(record))
+            .with_context(|| format!("Attachment {} not found", id))?;
+        attachment(record)
+    }
+
+    /// Attachments of a session, oldest first
+    pub fn list(&self, session_id: &str) -> Result<Vec<Attachment>> {
+        let mut attachments = Vec::new();
+        for record in self.registry.list(&self.session_filter(session_id)?)? {
+            match attachment(record) {
+                Ok(attachment) => attachments.push(attachment),
+                Err(e) => tracing::warn!("Skipping attachment record: {}", e),
+            }
+        }
+        Ok(attachments)
+    }
+
+    /// Full content for the agent: extracted text, or the image itself
+    pub fn read(&self, session_id: &str, id: &str) -> Result<AttachmentContent> {
+        let attachment = self.get(session_id, id)?;
+        let bytes = self.registry.read(id)?;
+        let text = match attachment.kind {
+            AttachmentKind::Image => {
+                return Ok(AttachmentContent::Image {
+                    data: base64::engine::general_purpose::STANDARD.encode(&bytes),
+                    mime_type: attachment.mime_type,
+                })
+            }
+            AttachmentKind::Pdf => pdf_text(&bytes)?,
+            AttachmentKind::Csv | AttachmentKind::Text => String::from_utf8_lossy(&bytes).into(),
+            AttachmentKind::Binary => bail!(
+                "{} is a binary file ({} bytes) and cannot be read as text",
+                attachment.filename,
+                attachment.size
+            ),
+        };
+        Ok(AttachmentContent::Text(crate::utils::safe_truncate(
+            &text,
+            MAX_READ_CHARS,
+        )))
+    }
+
+    pub fn delete(&self, session_id: &str, id: &str) -> Result<()> {
+        self.get(session_id, id)?;
+        self.registry.delete(id)?;
+        Ok(())
+    }
+
+    /// Drops every attachment of a session
+    pub fn remove_session(&self, session_id: &str) -> Result<()> {
+        self.registry
+            .delete_matching(&self.session_filter(session_id)?)?;
+        Ok(())
+    }
+
+    /// Deletes attachments stored before `before`, across all sessions.
+    /// Returns how many were deleted.
+    pub fn remove_older_than(&self, before: DateTime<Utc>) -> Result<usize> {
+        self.registry.delete_matching(&ArtifactFilter {
+            created_before: Some(before),
+            ..ArtifactFilter::producer(PRODUCER)
+        })
+    }
+
+    /// Moves attachments from the per-session folders they were kept in
+    /// before the artifact registry into it, keeping their ids. Returns how
+    /// many were moved.
+    pub fn migrate_legacy(&self, legacy_dir: &Path) -> Result<usize> {
+        if !legacy_dir.exists() {
+            return Ok(0);
+        }
+        let mut moved = 0;
+        for entry in fs::read_dir(legacy_dir)? {
+            let session_dir = entry?.path();
+            let Some(session_id) = session_dir.file_name().and_then(|n| n.to_str()) else {
+                continue;
+            };
+            if !session_dir.is_dir() || check_component(session_id, "session id").is_err() {
+                continue;
+            }
+            for entry in fs::read_dir(&session_dir)? {
+                let meta_path = entry?.path();
+                if meta_path.extension().is_none_or(|e| e != "json") {
+                    continue;
+                }
+                let data_path = meta_path.with_extension("bin");
+                let legacy: Attachment = serde_json::from_slice(&fs::read(&meta_path)?)
+                    .with_context(|| format!("Unreadable attachment record {:?}", meta_path))?;
+                // Already moved when an earlier run was interrupted
+                let moved_before = self.registry.get(&legacy.id).is_ok();
+                if data_path.exists() && !moved_before {
+                    let details = AttachmentDetails {
+                        kind: legacy.kind,
+                        preview: legacy.preview,
+                    };
+                    let artifact = Self::new_artifact(
+                        session_id,
+                        legacy.filename,
+                        &legacy.mime_type,
+                        &details,
+                    )?
+                    .imported(legacy.id, legacy.created_at);
+                    self.registry.import_file(artifact, &data_path)?;
+                    moved += 1;
+                }
+                fs::remove_file(&meta_path)?;
+            }
+            fs::remove_dir_all(&session_dir)?;
+        }
+        fs::remove_dir(legacy_dir)?;
+        Ok(moved)
+    }
+
+    /// Lists the attachments for the system prompt so the agent knows what it
+    /// can read
+    pub fn context_for(&self, session_id: &str) -> Option<String> {
+        let attachments = self.list(session_id).ok()?;
+        if attachments.is_empty() {
+            return None;
+        }
+        let mut context = String::from(
+            "The user attached these files to the session. Read one with the \
+             platform__read_attachment tool when it is relevant.",
+        );
+        for attachment in attachments {
+            context.push_str(&format!(
+                "\n\n- {} ({}, {}, id: {})",
+                attachment.filename, attachment.mime_type, attachment.size, attachment.id
+            ));
+            if let Some(preview) = attachment.preview {
+                context.push_str(&format!("\n{}", preview));
+            }
+        }
+        Some(context)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_sniff() {
+        assert_eq!(sniff(b"%PDF-1.7 ...", "x.bin").0, AttachmentKind::Pdf);
+        assert_eq!(
+            sniff(b"\x89PNG\r\n\x1a\n....", "shot.jpg"),
+            (AttachmentKind::Image, "image/png")
+        );
+        assert_eq!(sniff(b"a,b\n1,2\n", "data.CSV").0, AttachmentKind::Csv);
+        assert_eq!(sniff(b"a,b\n1,2\n", "notes.txt").0, AttachmentKind::Text);
+        assert_eq!(
+            sniff(&[0, 159, 146, 150], "x.csv").0,
+            AttachmentKind::Binary
+        );
+    }
+
+    #[test]
+    fn test_store_read_and_cleanup() {
+        let dir = TempDir::new().unwrap();
+        let store = AttachmentStore::new(ArtifactRegistry::new(dir.path().to_path_buf()))
+            .with_max_bytes(1024);
+
+        let csv = store
+            .store(
+                "20250101_1",
+                "../../etc/data.csv",
+                b"name,age\nada,36\nalan,41\n",
+            )
+            .unwrap();
+        assert_eq!(csv.filename, "data.csv");
+        assert_eq!(
+            csv.preview.as_deref(),
+            Some("2 columns: name, age\n2 rows\nada,36\nalan,41")
+        );
+        let png = store
+            .store("20250101_1", "shot.png", b"\x89PNG\r\n\x1a\nrest")
+            .unwrap();
+        assert!(matches!(
+            store.read("20250101_1", &png.id).unwrap(),
+            AttachmentContent::Image { mime_type, .. } if mime_type == "image/png"
+        ));
+
+        assert!(store.store("20250101_1", "big.txt", &[b'a'; 2048]).is_err());
+        assert!(store.get("../20250101_1", &csv.id).is_err());
+        assert_eq!(store.list("20250101_1").unwrap(), vec![csv.clone(), png]);
+        assert!(store.context_for("20250101_1").unwrap().contains(&csv.id));
+
+        store.delete("20250101_1", &csv.id).unwrap();
+        assert_eq!(store.list("20250101_1").unwrap().len(), 1);
+        store.remove_session("20250101_1").unwrap();
+        assert!(store.list("20250101_1").unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_legacy_attachments_keep_their_ids() {
+        let dir = TempDir::new().unwrap();
+        let legacy_dir = dir.path().join(ATTACHMENTS_FOLDER);
+        let session_dir = legacy_dir.join("20250101_1");
+        fs::create_dir_all(&session_dir).unwrap();
+        let legacy = Attachment {
+            id: "abc123".to_string(),
+            filename: "notes.txt".to_string(),
+            kind: AttachmentKind::Text,
+            mime_type: "text/plain".to_string(),
+            size: 5,
+            created_at: Utc::now(),
+            preview: Some("hello".to_string()),
+        };
+        fs::write(session_dir.join("abc123.bin"), b"hello").unwrap();
+        fs::write(
+            session_dir.join("abc123.json"),
+            serde_json::to_vec(&legacy).unwrap(),
+        )
+        .unwrap();
+
+        let store = AttachmentStore::new(ArtifactRegistry::new(dir.path().join("artifacts")));
+        assert_eq!(store.migrate_legacy(&legacy_dir).unwrap(), 1);
+        assert!(!legacy_dir.exists());
+        assert_eq!(store.get("20250101_1", "abc123").unwrap(), legacy);
+        assert_eq!(
+            store.read("20250101_1", "abc123").unwrap(),
+            AttachmentContent::Text("hello".to_string())
+        );
+    }
+}
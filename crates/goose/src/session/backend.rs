@@ -0,0 +1,129 @@
+//! Where sessions and their conversations are stored.
+//!
+//! By default that is the local SQLite database of [`SessionStorage`].
+//! Setting `GOOSE_SESSION_STORE_URL` to a `postgres://` URL keeps them in a
+//! shared Postgres database instead, so several goosed replicas behind a load
+//! balancer can serve the same sessions. Conversation writes bump the
+//! session's revision, and [`SessionBackend::replace_conversation_if`] only
+//! writes over the revision the caller read, so replicas never overwrite
+//! each other's history. The `X-Goose-Instance` response header tells the
+//! balancer which replica answered, as a session affinity hint.
+//!
+//! The shared backend holds sessions and messages. Feedback, chat history
+//! search, artifacts, database maintenance and retention stay with each
+//! replica's local storage.
+
+use crate::config::Config;
+use crate::conversation::message::{Message, MessageMetadata};
+use crate::conversation::Conversation;
+use crate::session::postgres::PostgresSessionStore;
+use crate::session::session_manager::{
+    MessagePage, Session, SessionFilter, SessionInsights, SessionStorage, SessionType,
+    SessionUpdateBuilder,
+};
+use anyhow::Result;
+use async_trait::async_trait;
+use std::path::PathBuf;
+use std::sync::Arc;
+use tracing::info;
+
+pub const SESSION_STORE_URL_KEY: &str = "GOOSE_SESSION_STORE_URL";
+
+pub type MetadataUpdate<'a> = Box<dyn FnOnce(MessageMetadata) -> MessageMetadata + Send + 'a>;
+
+#[async_trait]
+pub trait SessionBackend: Send + Sync {
+    async fn create_session(
+        &self,
+        working_dir: PathBuf,
+        name: String,
+        session_type: SessionType,
+    ) -> Result<Session>;
+
+    async fn get_session(&self, id: &str, include_messages: bool) -> Result<Session>;
+
+    async fn apply_update(&self, update: SessionUpdateBuilder<'_>) -> Result<()>;
+
+    async fn add_message(&self, id: &str, message: &Message) -> Result<()>;
+
+    /// Like [`Self::add_message`], but backends may hold the message back
+    /// until [`Self::flush_pending`]. Anything reading the session flushes first.
+    async fn queue_message(&self, id: &str, message: &Message) -> Result<()> {
+        self.add_message(id, message).await
+    }
+
+    /// Writes the messages queued for a session, returning how many there were.
+    async fn flush_pending(&self, _id: &str) -> Result<usize> {
+        Ok(0)
+    }
+
+    /// Writes the messages queued for every session.
+    async fn flush_all_pending(&self) -> Result<usize> {
+        Ok(0)
+    }
+
+    /// The whole conversation, including what compaction hid from the agent.
+    async fn get_conversation(&self, id: &str) -> Result<Conversation>;
+
+    async fn replace_conversation(&self, id: &str, conversation: &Conversation) -> Result<()>;
+
+    /// Everything from the first message the agent can see.
+    async fn get_conversation_window(&self, id: &str) -> Result<Conversation>;
+
+    /// Replaces what [`Self::get_conversation_window`] returned, keeping the
+    /// messages before it.
+    async fn replace_conversation_window(
+        &self,
+        id: &str,
+        conversation: &Conversation,
+    ) -> Result<()>;
+
+    async fn get_messages_page(
+        &self,
+        id: &str,
+        before: Option<i64>,
+        limit: usize,
+    ) -> Result<MessagePage>;
+
+    /// Replaces the conversation only if the session is still at
+    /// `expected_revision`, returning the new revision. Fails with
+    /// [`SessionConflict`](super::SessionConflict) otherwise.
+    async fn replace_conversation_if(
+        &self,
+        id: &str,
+        conversation: &Conversation,
+        expected_revision: i64,
+    ) -> Result<i64>;
+
+    /// Sessions of the given types that have messages, newest first.
+    async fn list_sessions_matching(
+        &self,
+        types: &[SessionType],
+        filter: &SessionFilter,
+    ) -> Result<Vec<Session>>;
+
+    async fn delete_session(&self, id: &str) -> Result<()>;
+
+    /// Drops the messages created at or after `timestamp`.
+    async fn truncate_conversation(&self, id: &str, timestamp: i64) -> Result<()>;
+
+    async fn update_message_metadata(
+        &self,
+        id: &str,
+        message_id: &str,
+        update: MetadataUpdate<'_>,
+    ) -> Result<()>;
+
+    async fn get_insights(&self) -> Result<SessionInsights>;
+}
+
+/// The backend `GOOSE_SESSION_STORE_URL` points at, or `local` when it is unset.
+pub fn from_config(local: Arc<SessionStorage>) -> Arc<dyn SessionBackend> {
+    match Config::global().get_secret::<String>(SESSION_STORE_URL_KEY) {
+        Ok(url) if !url.trim().is_empty() => {
+            info!("Storing sessions in the shared session store");
+            Arc::new(PostgresSessionStore::new(url.trim()))
+        }
+        _ => local,
+    }
+}
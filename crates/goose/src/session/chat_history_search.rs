@@ -1,4 +1,5 @@
 use crate::conversation::message::MessageContent;
+use crate::security::at_rest;
 use anyhow::Result;
 use chrono::{DateTime, Utc};
 use serde::Serialize;
@@ -83,6 +84,7 @@ impl<'a> ChatHistorySearch<'a> {
         }
 
         let rows = self.fetch_rows(&keywords).await?;
+        let rows = self.open_sealed_rows(rows, &keywords);
         let session_messages = self.process_rows(rows);
         let session_totals = self.get_session_totals(&session_messages).await?;
         let results = self.convert_to_results(session_messages, session_totals);
@@ -92,7 +94,8 @@ impl<'a> ChatHistorySearch<'a> {
 
     async fn fetch_rows(&self, keywords: &[String]) -> Result<Vec<SqlQueryRow>> {
         let sql = self.build_sql(keywords);
-        let mut query_builder = sqlx::query_as::<_, SqlQueryRow>(&sql);
+        let mut query_builder =
+            sqlx::query_as::<_, SqlQueryRow>(&sql).bind(at_rest::SEALED_LIKE_PATTERN);
 
         for keyword in keywords {
             query_builder = query_builder.bind(keyword);
@@ -109,11 +112,44 @@ impl<'a> ChatHistorySearch<'a> {
             query_builder = query_builder.bind(before);
         }
 
-        query_builder = query_builder.bind(self.limit as i64);
+        // Sealed rows are matched after decrypting, so they cannot be limited in SQL
+        let limit = if at_rest::may_have_sealed_data() {
+            -1
+        } else {
+            self.limit as i64
+        };
+        query_builder = query_builder.bind(limit);
 
         Ok(query_builder.fetch_all(self.pool).await?)
     }
 
+    /// Decrypts sealed rows, which SQL could not match against the keywords,
+    /// keeps those that match, and applies the limit.
+    fn open_sealed_rows(&self, rows: Vec<SqlQueryRow>, keywords: &[String]) -> Vec<SqlQueryRow> {
+        let needles: Vec<&str> = keywords.iter().map(|k| k.trim_matches('%')).collect();
+        rows.into_iter()
+            .filter_map(|mut row| {
+                if !at_rest::is_sealed(&row.5) {
+                    return Some(row);
+                }
+                let content_json = at_rest::open(std::mem::take(&mut row.5))
+                    .inspect_err(|e| tracing::warn!("Skipping unreadable message: {}", e))
+                    .ok()?;
+                let content: Vec<MessageContent> = serde_json::from_str(&content_json).ok()?;
+                let matches = content.iter().any(|c| match c {
+                    MessageContent::Text(t) => {
+                        let text = t.text.to_lowercase();
+                        needles.iter().any(|needle| text.contains(needle))
+                    }
+                    _ => false,
+                });
+                row.5 = content_json;
+                matches.then_some(row)
+            })
+            .take(self.limit)
+            .collect()
+    }
+
     fn parse_keywords(&self) -> Vec<String> {
         self.query
             .split_whitespace()
@@ -134,7 +170,7 @@ impl<'a> ChatHistorySearch<'a> {
                 m.timestamp
             FROM messages m
             INNER JOIN sessions s ON m.session_id = s.id
-            WHERE EXISTS (
+            WHERE CASE WHEN m.content_json LIKE ? THEN 1 ELSE EXISTS (
                 SELECT 1 FROM json_each(m.content_json) 
                 WHERE json_extract(value, '$.type') = 'text' 
                 AND (
@@ -151,7 +187,7 @@ impl<'a> ChatHistorySearch<'a> {
         sql.push_str(
             r#"
                 )
-            )
+            ) END
         "#,
         );
 
@@ -0,0 +1,290 @@
+//! "Previously on this project": a hand-off note kept per workspace.
+//!
+//! When a session ends, the model condenses it into a short status with open
+//! threads, decisions and TODOs, stored under `memory/projects` in the data
+//! directory. The next session started in the same workspace gets that status
+//! injected into its system prompt. `GOOSE_PROJECT_CONTINUITY=false` turns
+//! both halves off.
+
+use crate::config::paths::Paths;
+use crate::config::Config;
+use crate::conversation::message::Message;
+use crate::prompt_template::render_template;
+use crate::providers::base::Provider;
+use crate::session::Session;
+use crate::utils::safe_truncate;
+use anyhow::{Context, Result};
+use chrono::{DateTime, Utc};
+use rmcp::model::Role;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::fs;
+use std::path::{Path, PathBuf};
+use utoipa::ToSchema;
+
+/// Only the tail of long sessions is summarized
+const MAX_TRANSCRIPT_MESSAGES: usize = 40;
+const MAX_MESSAGE_CHARS: usize = 1500;
+const MAX_ITEMS: usize = 5;
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize, ToSchema)]
+pub struct ProjectStatus {
+    #[schema(value_type = String)]
+    pub workspace: PathBuf,
+    /// Session the status was written from
+    pub session_id: String,
+    pub session_name: String,
+    pub updated_at: DateTime<Utc>,
+    pub summary: String,
+    #[serde(default)]
+    pub open_threads: Vec<String>,
+    #[serde(default)]
+    pub decisions: Vec<String>,
+    #[serde(default)]
+    pub todos: Vec<String>,
+}
+
+impl ProjectStatus {
+    /// The block injected into the first session that follows this one.
+    pub fn context_block(&self) -> String {
+        let mut block = format!(
+            "[PREVIOUSLY ON THIS PROJECT] (session \"{}\", {})\n{}\n",
+            self.session_name,
+            self.updated_at.format("%Y-%m-%d %H:%M UTC"),
+            self.summary
+        );
+        for (heading, items) in [
+            ("Open threads", &self.open_threads),
+            ("Decisions", &self.decisions),
+            ("TODOs", &self.todos),
+        ] {
+            if items.is_empty() {
+                continue;
+            }
+            block.push_str(&format!("{}:\n", heading));
+            for item in items {
+                block.push_str(&format!("- {}\n", item));
+            }
+        }
+        block.push_str(
+            "This is context from an earlier session; confirm it is still current before acting on it.\n",
+        );
+        block
+    }
+}
+
+#[derive(Deserialize)]
+struct StatusReply {
+    summary: String,
+    #[serde(default)]
+    open_threads: Vec<String>,
+    #[serde(default)]
+    decisions: Vec<String>,
+    #[serde(default)]
+    todos: Vec<String>,
+}
+
+#[derive(Serialize)]
+struct TranscriptLine {
+    role: &'static str,
+    text: String,
+}
+
+#[derive(Serialize)]
+struct TemplateContext<'a> {
+    workspace: String,
+    previous: Option<&'a str>,
+    messages: Vec<TranscriptLine>,
+}
+
+pub fn is_enabled() -> bool {
+    Config::global()
+        .get_param::<bool>("GOOSE_PROJECT_CONTINUITY")
+        .unwrap_or(true)
+}
+
+/// Project statuses on disk, one JSON file per workspace.
+pub struct ProjectMemory {
+    dir: PathBuf,
+}
+
+impl Default for ProjectMemory {
+    fn default() -> Self {
+        Self::new(Paths::data_dir().join("memory").join("projects"))
+    }
+}
+
+impl ProjectMemory {
+    pub fn new(dir: PathBuf) -> Self {
+        Self { dir }
+    }
+
+    fn path_for(&self, workspace: &Path) -> PathBuf {
+        let workspace = workspace
+            .canonicalize()
+            .unwrap_or_else(|_| workspace.to_path_buf());
+        let digest = Sha256::digest(workspace.to_string_lossy().as_bytes());
+        let key: String = digest[..8].iter().map(|b| format!("{:02x}", b)).collect();
+        self.dir.join(format!("{}.json", key))
+    }
+
+    pub fn load(&self, workspace: &Path) -> Result<Option<ProjectStatus>> {
+        let path = self.path_for(workspace);
+        if !path.exists() {
+            return Ok(None);
+        }
+        let json = fs::read_to_string(&path)?;
+        Ok(Some(serde_json::from_str(&json).with_context(|| {
+            format!("Invalid project status {}", path.display())
+        })?))
+    }
+
+    pub fn save(&self, status: &ProjectStatus) -> Result<()> {
+        fs::create_dir_all(&self.dir)?;
+        fs::write(
+            self.path_for(&status.workspace),
+            serde_json::to_string_pretty(status)?,
+        )?;
+        Ok(())
+    }
+
+    pub fn forget(&self, workspace: &Path) -> Result<bool> {
+        let path = self.path_for(workspace);
+        if !path.exists() {
+            return Ok(false);
+        }
+        fs::remove_file(path)?;
+        Ok(true)
+    }
+
+    /// The status to inject into a new session, unless it was written by
+    /// that same session.
+    pub fn context_for(&self, workspace: &Path, session_id: &str) -> Option<String> {
+        match self.load(workspace) {
+            Ok(Some(status)) if status.session_id != session_id => Some(status.context_block()),
+            Ok(_) => None,
+            Err(e) => {
+                tracing::warn!("Failed to load project status: {}", e);
+                None
+            }
+        }
+    }
+
+    /// Summarizes the session and stores it as the workspace's status.
+    /// Sessions without an assistant reply leave the previous status alone.
+    pub async fn record_session(
+        &self,
+        provider: &dyn Provider,
+        session: &Session,
+    ) -> Result<Option<ProjectStatus>> {
+        let Some(conversation) = &session.conversation else {
+            return Ok(None);
+        };
+        let messages: Vec<&Message> = conversation
+            .messages()
+            .iter()
+            .filter(|m| m.metadata.user_visible && !m.as_concat_text().trim().is_empty())
+            .collect();
+        if !messages.iter().any(|m| m.role == Role::Assistant) {
+            return Ok(None);
+        }
+
+        let previous = self.load(&session.working_dir).ok().flatten();
+        let start = messages.len().saturating_sub(MAX_TRANSCRIPT_MESSAGES);
+        let context = TemplateContext {
+            workspace: session.working_dir.display().to_string(),
+            previous: previous.as_ref().map(|p| p.summary.as_str()),
+            messages: messages[start..]
+                .iter()
+                .map(|m| TranscriptLine {
+                    role: match m.role {
+                        Role::User => "user",
+                        Role::Assistant => "assistant",
+                    },
+                    text: safe_truncate(m.as_concat_text().trim(), MAX_MESSAGE_CHARS),
+                })
+                .collect(),
+        };
+        let system = render_template("project_status.md", &context)?;
+        let request = Message::user().with_text("Write the hand-off note as JSON.");
+        let (reply, _) = provider
+            .complete_fast(&session.id, &system, &[request], &[])
+            .await?;
+
+        let reply = parse_reply(&reply.as_concat_text())?;
+        let status = ProjectStatus {
+            workspace: session.working_dir.clone(),
+            session_id: session.id.clone(),
+            session_name: session.name.clone(),
+            updated_at: Utc::now(),
+            summary: reply.summary.trim().to_string(),
+            open_threads: limit(reply.open_threads),
+            decisions: limit(reply.decisions),
+            todos: limit(reply.todos),
+        };
+        self.save(&status)?;
+        Ok(Some(status))
+    }
+}
+
+fn limit(items: Vec<String>) -> Vec<String> {
+    items
+        .into_iter()
+        .map(|item| item.trim().to_string())
+        .filter(|item| !item.is_empty())
+        .take(MAX_ITEMS)
+        .collect()
+}
+
+/// Models sometimes wrap the JSON in prose or a code fence.
+fn parse_reply(text: &str) -> Result<StatusReply> {
+    let start = text.find('{').context("No JSON object in project status")?;
+    let end = text
+        .rfind('}')
+        .context("No JSON object in project status")?;
+    serde_json::from_str(&text[start..=end]).context("Invalid project status JSON")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_parse_fenced_reply() {
+        let reply = parse_reply(
+            "Here you go:\n```json\n{\"summary\": \"Fixed the parser\", \"todos\": [\"Add tests\"]}\n```",
+        )
+        .unwrap();
+        assert_eq!(reply.summary, "Fixed the parser");
+        assert_eq!(reply.todos, vec!["Add tests"]);
+        assert!(reply.open_threads.is_empty());
+    }
+
+    #[test]
+    fn test_status_round_trip_skips_own_session() {
+        let temp_dir = TempDir::new().unwrap();
+        let memory = ProjectMemory::new(temp_dir.path().join("projects"));
+        let workspace = temp_dir.path().to_path_buf();
+        assert!(memory.load(&workspace).unwrap().is_none());
+
+        let status = ProjectStatus {
+            workspace: workspace.clone(),
+            session_id: "20260101_1".to_string(),
+            session_name: "Parser work".to_string(),
+            summary: "Rewrote the tokenizer".to_string(),
+            decisions: vec!["Keep the hand-written lexer".to_string()],
+            ..Default::default()
+        };
+        memory.save(&status).unwrap();
+
+        assert!(memory.context_for(&workspace, "20260101_1").is_none());
+        let block = memory.context_for(&workspace, "20260102_1").unwrap();
+        assert!(block.contains("Rewrote the tokenizer"));
+        assert!(block.contains("Decisions:\n- Keep the hand-written lexer"));
+        assert!(!block.contains("TODOs"));
+
+        assert!(memory.forget(&workspace).unwrap());
+        assert!(memory.load(&workspace).unwrap().is_none());
+    }
+}
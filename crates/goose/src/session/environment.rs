@@ -0,0 +1,152 @@
+//! Per-session environment for tool processes.
+//!
+//! Tools otherwise inherit goosed's environment. A session can declare its
+//! own variables and secret references, kept in its extension data. Secret
+//! references name a key in the secrets store, so the value itself is never
+//! written to the session. The resolved environment is passed to the stdio
+//! extensions started for the session and to the developer shell, and to
+//! nothing else; values are never logged.
+
+use super::extension_data::ExtensionState;
+use super::Session;
+use crate::config::Config;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap};
+use std::fmt;
+use utoipa::ToSchema;
+
+#[derive(Clone, Default, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
+pub struct SessionEnvironment {
+    /// Variables set as given
+    #[serde(default)]
+    pub vars: BTreeMap<String, String>,
+    /// Variables whose value is read from the secrets store, by secret key
+    #[serde(default)]
+    pub secrets: BTreeMap<String, String>,
+}
+
+impl ExtensionState for SessionEnvironment {
+    const EXTENSION_NAME: &'static str = "session_environment";
+    const VERSION: &'static str = "v0";
+}
+
+impl fmt::Debug for SessionEnvironment {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("SessionEnvironment")
+            .field("vars", &self.vars.keys().collect::<Vec<_>>())
+            .field("secrets", &self.secrets)
+            .finish()
+    }
+}
+
+#[derive(Debug, thiserror::Error, PartialEq, Eq)]
+pub enum EnvironmentError {
+    #[error("Invalid variable name '{0}'; use letters, digits and '_', not starting with a digit")]
+    InvalidName(String),
+    #[error("'{0}' is declared both as a variable and as a secret")]
+    Duplicate(String),
+    #[error("'{0}' is set by goose for every tool and cannot be overridden")]
+    Reserved(String),
+    #[error("Secret '{key}' for {name} is not in the secrets store")]
+    MissingSecret { name: String, key: String },
+}
+
+/// Variables goose itself sets for tool processes
+const RESERVED: &[&str] = &["PATH", "AGENT_SESSION_ID", "GOOSE_TERMINAL"];
+
+fn valid_name(name: &str) -> bool {
+    !name.is_empty()
+        && !name.starts_with(|c: char| c.is_ascii_digit())
+        && name.chars().all(|c| c.is_ascii_alphanumeric() || c == '_')
+}
+
+impl SessionEnvironment {
+    pub fn for_session(session: &Session) -> Self {
+        Self::from_extension_data(&session.extension_data).unwrap_or_default()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.vars.is_empty() && self.secrets.is_empty()
+    }
+
+    pub fn validate(&self) -> Result<(), EnvironmentError> {
+        for name in self.vars.keys().chain(self.secrets.keys()) {
+            if !valid_name(name) {
+                return Err(EnvironmentError::InvalidName(name.clone()));
+            }
+            if RESERVED.contains(&name.as_str()) {
+                return Err(EnvironmentError::Reserved(name.clone()));
+            }
+        }
+        if let Some(name) = self.vars.keys().find(|n| self.secrets.contains_key(*n)) {
+            return Err(EnvironmentError::Duplicate(name.clone()));
+        }
+        Ok(())
+    }
+
+    /// The variables with secret references looked up through `lookup`.
+    pub fn resolve_with(
+        &self,
+        lookup: impl Fn(&str) -> Option<String>,
+    ) -> Result<HashMap<String, String>, EnvironmentError> {
+        let mut env: HashMap<String, String> = self.vars.clone().into_iter().collect();
+        for (name, key) in &self.secrets {
+            let value = lookup(key).ok_or_else(|| EnvironmentError::MissingSecret {
+                name: name.clone(),
+                key: key.clone(),
+            })?;
+            env.insert(name.clone(), value);
+        }
+        Ok(env)
+    }
+
+    /// The variables with secret references read from the secrets store.
+    pub fn resolve(&self) -> Result<HashMap<String, String>, EnvironmentError> {
+        let config = Config::global();
+        self.resolve_with(|key| config.get_secret::<String>(key).ok())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_resolve_and_validate() {
+        let environment = SessionEnvironment {
+            vars: BTreeMap::from([("AWS_REGION".to_string(), "eu-west-1".to_string())]),
+            secrets: BTreeMap::from([(
+                "AWS_SECRET_ACCESS_KEY".to_string(),
+                "project_a_aws".to_string(),
+            )]),
+        };
+        assert_eq!(environment.validate(), Ok(()));
+        assert!(!format!("{:?}", environment).contains("eu-west-1"));
+
+        let env = environment
+            .resolve_with(|key| (key == "project_a_aws").then(|| "s3cr3t".to_string()))
+            .unwrap();
+        assert_eq!(env["AWS_REGION"], "eu-west-1");
+        assert_eq!(env["AWS_SECRET_ACCESS_KEY"], "s3cr3t");
+        assert_eq!(
+            environment.resolve_with(|_| None),
+            Err(EnvironmentError::MissingSecret {
+                name: "AWS_SECRET_ACCESS_KEY".to_string(),
+                key: "project_a_aws".to_string(),
+            })
+        );
+
+        let mut invalid = environment.clone();
+        invalid.vars.insert("1X".to_string(), String::new());
+        assert_eq!(
+            invalid.validate(),
+            Err(EnvironmentError::InvalidName("1X".to_string()))
+        );
+        invalid.vars.clear();
+        invalid.vars.insert("PATH".to_string(), String::new());
+        assert_eq!(
+            invalid.validate(),
+            Err(EnvironmentError::Reserved("PATH".to_string()))
+        );
+    }
+}
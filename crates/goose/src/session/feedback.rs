@@ -0,0 +1,304 @@
+//! Thumbs up/down ratings on sessions and individual messages.
+//!
+//! Ratings live next to the messages they rate so aggregate stats can be
+//! grouped by the model and recipe that produced the answer. Negative ratings
+//! with a comment also become reflections, so the agent sees what the user
+//! disliked the next time it works on a similar request. Those reflections are
+//! stored with the rating so new agents pick them up after a restart.
+
+use crate::agents::reflexion::{AttemptOutcome, Reflection};
+use crate::conversation::message::Message;
+use crate::conversation::Conversation;
+use crate::utils::safe_truncate;
+use anyhow::Result;
+use chrono::{DateTime, Utc};
+use rmcp::model::Role;
+use serde::{Deserialize, Serialize};
+use sqlx::{Pool, Sqlite};
+use utoipa::ToSchema;
+
+pub const MAX_COMMENT_LENGTH: usize = 2000;
+pub const FEEDBACK_TAG: &str = "user_feedback";
+const SUMMARY_CHARS: usize = 500;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum Rating {
+    Up,
+    Down,
+}
+
+impl Rating {
+    fn as_str(&self) -> &'static str {
+        match self {
+            Rating::Up => "up",
+            Rating::Down => "down",
+        }
+    }
+}
+
+impl std::str::FromStr for Rating {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "up" => Ok(Rating::Up),
+            "down" => Ok(Rating::Down),
+            _ => Err(anyhow::anyhow!("Invalid rating: {}", s)),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct Feedback {
+    pub id: i64,
+    pub session_id: String,
+    /// Rated message; `None` rates the session as a whole
+    pub message_id: Option<String>,
+    pub rating: Rating,
+    pub comment: Option<String>,
+    pub created_at: DateTime<Utc>,
+}
+
+impl sqlx::FromRow<'_, sqlx::sqlite::SqliteRow> for Feedback {
+    fn from_row(row: &sqlx::sqlite::SqliteRow) -> Result<Self, sqlx::Error> {
+        use sqlx::Row;
+
+        let rating: String = row.try_get("rating")?;
+        Ok(Feedback {
+            id: row.try_get("id")?,
+            session_id: row.try_get("session_id")?,
+            message_id: row.try_get("message_id")?,
+            rating: rating
+                .parse()
+                .map_err(|e: anyhow::Error| sqlx::Error::Decode(e.into()))?,
+            comment: row.try_get("comment")?,
+            created_at: row.try_get("created_at")?,
+        })
+    }
+}
+
+impl Feedback {
+    /// Turns a negative rating with a comment into a reflection on the
+    /// request that produced the rated answer.
+    pub fn to_reflection(&self, conversation: &Conversation) -> Option<Reflection> {
+        let comment = self.comment.as_deref().map(str::trim).unwrap_or_default();
+        if self.rating != Rating::Down || comment.is_empty() {
+            return None;
+        }
+
+        let messages = conversation.messages();
+        let rated = match &self.message_id {
+            Some(id) => messages.iter().position(|m| m.id.as_deref() == Some(id))?,
+            None => messages.iter().rposition(|m| m.role == Role::Assistant)?,
+        };
+        let request = messages[..rated]
+            .iter()
+            .rev()
+            .find(|m| m.role == Role::User && !m.as_concat_text().trim().is_empty())
+            .map(Message::as_concat_text)
+            .unwrap_or_else(|| "Unknown request".to_string());
+
+        let mut reflection = Reflection::new(
+            safe_truncate(request.trim(), SUMMARY_CHARS),
+            safe_truncate(messages[rated].as_concat_text().trim(), SUMMARY_CHARS),
+            AttemptOutcome::Failure,
+        )
+        .with_diagnosis(format!("The user rated this answer down: {}", comment))
+        .with_lessons(vec![comment.to_string()]);
+        reflection.add_tag(FEEDBACK_TAG);
+        Some(reflection)
+    }
+}
+
+#[derive(Debug, Clone, Default, Serialize, ToSchema)]
+pub struct FeedbackCounts {
+    pub up: i64,
+    pub down: i64,
+    /// Share of positive ratings, 0.0 - 1.0
+    pub score: f64,
+}
+
+impl FeedbackCounts {
+    fn new(up: i64, down: i64) -> Self {
+        let total = up + down;
+        Self {
+            up,
+            down,
+            score: if total == 0 {
+                0.0
+            } else {
+                up as f64 / total as f64
+            },
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, ToSchema)]
+pub struct FeedbackGroup {
+    pub name: String,
+    #[serde(flatten)]
+    pub counts: FeedbackCounts,
+}
+
+#[derive(Debug, Clone, Default, Serialize, ToSchema)]
+pub struct FeedbackStats {
+    pub overall: FeedbackCounts,
+    /// Ratings by the model that answered
+    pub by_model: Vec<FeedbackGroup>,
+    /// Ratings by the recipe the session ran
+    pub by_recipe: Vec<FeedbackGroup>,
+}
+
+pub async fn create_table(conn: &mut sqlx::SqliteConnection) -> Result<()> {
+    sqlx::query(
+        r#"
+        CREATE TABLE IF NOT EXISTS feedback (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            session_id TEXT NOT NULL REFERENCES sessions(id),
+            message_id TEXT,
+            rating TEXT NOT NULL,
+            comment TEXT,
+            created_at TIMESTAMP DEFAULT CURRENT_TIMESTAMP
+        )
+    "#,
+    )
+    .execute(&mut *conn)
+    .await?;
+    sqlx::query("CREATE INDEX IF NOT EXISTS idx_feedback_session ON feedback(session_id)")
+        .execute(&mut *conn)
+        .await?;
+    Ok(())
+}
+
+/// Stores a rating, replacing any earlier rating of the same session or message.
+pub async fn record(
+    pool: &Pool<Sqlite>,
+    session_id: &str,
+    message_id: Option<&str>,
+    rating: Rating,
+    comment: Option<&str>,
+) -> Result<Feedback> {
+    let mut tx = pool.begin().await?;
+    sqlx::query("DELETE FROM feedback WHERE session_id = ? AND message_id IS ?")
+        .bind(session_id)
+        .bind(message_id)
+        .execute(&mut *tx)
+        .await?;
+    let feedback = sqlx::query_as::<_, Feedback>(
+        r#"
+        INSERT INTO feedback (session_id, message_id, rating, comment)
+        VALUES (?, ?, ?, ?)
+        RETURNING *
+    "#,
+    )
+    .bind(session_id)
+    .bind(message_id)
+    .bind(rating.as_str())
+    .bind(comment.map(|c| safe_truncate(c.trim(), MAX_COMMENT_LENGTH)))
+    .fetch_one(&mut *tx)
+    .await?;
+    tx.commit().await?;
+    Ok(feedback)
+}
+
+/// Adds the column holding the reflection learned from a rating
+pub async fn add_reflection_column(conn: &mut sqlx::SqliteConnection) -> Result<()> {
+    sqlx::query("ALTER TABLE feedback ADD COLUMN reflection_json TEXT")
+        .execute(&mut *conn)
+        .await?;
+    Ok(())
+}
+
+/// Stores the reflection learned from a rating next to it.
+pub async fn save_reflection(
+    pool: &Pool<Sqlite>,
+    feedback_id: i64,
+    reflection: &Reflection,
+) -> Result<()> {
+    sqlx::query("UPDATE feedback SET reflection_json = ? WHERE id = ?")
+        .bind(serde_json::to_string(reflection)?)
+        .bind(feedback_id)
+        .execute(pool)
+        .await?;
+    Ok(())
+}
+
+/// Every reflection learned from ratings, oldest first.
+pub async fn reflections(pool: &Pool<Sqlite>) -> Result<Vec<Reflection>> {
+    let rows = sqlx::query_as::<_, (String,)>(
+        "SELECT reflection_json FROM feedback WHERE reflection_json IS NOT NULL ORDER BY created_at, id",
+    )
+    .fetch_all(pool)
+    .await?;
+    rows.into_iter()
+        .map(|(json,)| serde_json::from_str(&json).map_err(Into::into))
+        .collect()
+}
+
+pub async fn list(pool: &Pool<Sqlite>, session_id: &str) -> Result<Vec<Feedback>> {
+    sqlx::query_as::<_, Feedback>(
+        "SELECT * FROM feedback WHERE session_id = ? ORDER BY created_at, id",
+    )
+    .bind(session_id)
+    .fetch_all(pool)
+    .await
+    .map_err(Into::into)
+}
+
+pub async fn delete_for_session(
+    tx: &mut sqlx::Transaction<'_, Sqlite>,
+    session_id: &str,
+) -> Result<()> {
+    sqlx::query("DELETE FROM feedback WHERE session_id = ?")
+        .bind(session_id)
+        .execute(&mut **tx)
+        .await?;
+    Ok(())
+}
+
+pub async fn stats(pool: &Pool<Sqlite>) -> Result<FeedbackStats> {
+    let (up, down) = sqlx::query_as::<_, (i64, i64)>(
+        r#"
+        SELECT COALESCE(SUM(rating = 'up'), 0), COALESCE(SUM(rating = 'down'), 0)
+        FROM feedback
+    "#,
+    )
+    .fetch_one(pool)
+    .await?;
+
+    Ok(FeedbackStats {
+        overall: FeedbackCounts::new(up, down),
+        by_model: grouped(
+            pool,
+            "COALESCE(s.provider_name || '/', '') || json_extract(s.model_config_json, '$.model_name')",
+        )
+        .await?,
+        by_recipe: grouped(pool, "json_extract(s.recipe_json, '$.title')").await?,
+    })
+}
+
+async fn grouped(pool: &Pool<Sqlite>, key: &str) -> Result<Vec<FeedbackGroup>> {
+    let query = format!(
+        r#"
+        SELECT {key} AS group_name,
+               SUM(f.rating = 'up') AS up,
+               SUM(f.rating = 'down') AS down
+        FROM feedback f
+        INNER JOIN sessions s ON s.id = f.session_id
+        WHERE {key} IS NOT NULL
+        GROUP BY group_name
+        ORDER BY up + down DESC, group_name
+    "#
+    );
+    let rows = sqlx::query_as::<_, (String, i64, i64)>(&query)
+        .fetch_all(pool)
+        .await?;
+    Ok(rows
+        .into_iter()
+        .map(|(name, up, down)| FeedbackGroup {
+            name,
+            counts: FeedbackCounts::new(up, down),
+        })
+        .collect())
+}
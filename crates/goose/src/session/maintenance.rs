@@ -0,0 +1,205 @@
+//! Corruption protection for the session database: integrity checks,
+//! timestamped backups with rotation, and restoring the newest backup when
+//! the database can no longer be opened.
+
+use anyhow::{Context, Result};
+use chrono::Utc;
+use serde::Serialize;
+use sqlx::{Pool, Sqlite};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+use utoipa::ToSchema;
+
+pub const BACKUPS_FOLDER: &str = "backups";
+pub const MAX_BACKUPS: usize = 5;
+pub const MAINTENANCE_INTERVAL: Duration = Duration::from_secs(6 * 60 * 60);
+
+const BACKUP_PREFIX: &str = "sessions-";
+const BACKUP_EXTENSION: &str = "db";
+
+#[derive(Debug, Clone, Serialize, ToSchema)]
+pub struct MaintenanceReport {
+    pub integrity_ok: bool,
+    /// Problems reported by SQLite's integrity check
+    pub problems: Vec<String>,
+    #[schema(value_type = Option<String>)]
+    pub backup: Option<PathBuf>,
+    pub vacuumed: bool,
+}
+
+/// Runs SQLite's full integrity check and returns the problems it found.
+pub async fn integrity_check(pool: &Pool<Sqlite>) -> Result<Vec<String>> {
+    run_check(pool, "PRAGMA integrity_check").await
+}
+
+/// Faster check that skips index verification, cheap enough for every open.
+pub async fn quick_check(pool: &Pool<Sqlite>) -> Result<Vec<String>> {
+    run_check(pool, "PRAGMA quick_check").await
+}
+
+async fn run_check(pool: &Pool<Sqlite>, pragma: &str) -> Result<Vec<String>> {
+    let rows: Vec<String> = sqlx::query_scalar(pragma).fetch_all(pool).await?;
+    Ok(rows.into_iter().filter(|row| row != "ok").collect())
+}
+
+/// Writes a consistent copy of the database into `backup_dir` and prunes old backups.
+pub async fn backup(pool: &Pool<Sqlite>, backup_dir: &Path) -> Result<PathBuf> {
+    fs::create_dir_all(backup_dir)?;
+    let path = backup_dir.join(format!(
+        "{}{}.{}",
+        BACKUP_PREFIX,
+        Utc::now().format("%Y%m%dT%H%M%S%.3fZ"),
+        BACKUP_EXTENSION
+    ));
+    sqlx::query("VACUUM INTO ?")
+        .bind(path.to_string_lossy().to_string())
+        .execute(pool)
+        .await
+        .context("Failed to back up session database")?;
+    rotate_backups(backup_dir, MAX_BACKUPS)?;
+    Ok(path)
+}
+
+/// Backups in `backup_dir`, oldest first.
+pub fn list_backups(backup_dir: &Path) -> Result<Vec<PathBuf>> {
+    if !backup_dir.exists() {
+        return Ok(Vec::new());
+    }
+    let mut backups: Vec<PathBuf> = fs::read_dir(backup_dir)?
+        .filter_map(|entry| entry.ok().map(|e| e.path()))
+        .filter(|path| {
+            path.extension().is_some_and(|ext| ext == BACKUP_EXTENSION)
+                && path
+                    .file_name()
+                    .and_then(|name| name.to_str())
+                    .is_some_and(|name| name.starts_with(BACKUP_PREFIX))
+        })
+        .collect();
+    // Timestamped names sort chronologically
+    backups.sort();
+    Ok(backups)
+}
+
+/// Deletes all but the newest `keep` backups, returning the removed paths.
+pub fn rotate_backups(backup_dir: &Path, keep: usize) -> Result<Vec<PathBuf>> {
+    let backups = list_backups(backup_dir)?;
+    let excess = backups.len().saturating_sub(keep);
+    let removed: Vec<PathBuf> = backups.into_iter().take(excess).collect();
+    for path in &removed {
+        fs::remove_file(path)?;
+    }
+    Ok(removed)
+}
+
+pub fn is_corruption_error(error: &anyhow::Error) -> bool {
+    let message = format!("{:#}", error).to_lowercase();
+    ["malformed", "not a database", "corrupt"]
+        .iter()
+        .any(|marker| message.contains(marker))
+}
+
+/// Moves the damaged database aside and replaces it with the newest backup.
+/// Returns the backup that was restored, or `None` if there was none.
+pub fn restore_latest_backup(db_path: &Path, backup_dir: &Path) -> Result<Option<PathBuf>> {
+    let Some(latest) = list_backups(backup_dir)?.pop() else {
+        return Ok(None);
+    };
+
+    if db_path.exists() {
+        let mut corrupt = db_path.as_os_str().to_owned();
+        corrupt.push(format!(".corrupt-{}", Utc::now().format("%Y%m%dT%H%M%SZ")));
+        fs::rename(db_path, PathBuf::from(corrupt))?;
+    }
+    for suffix in ["-wal", "-shm"] {
+        let mut sidecar = db_path.as_os_str().to_owned();
+        sidecar.push(suffix);
+        let sidecar = PathBuf::from(sidecar);
+        if sidecar.exists() {
+            fs::remove_file(sidecar)?;
+        }
+    }
+    fs::copy(&latest, db_path)?;
+    Ok(Some(latest))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use sqlx::sqlite::{SqliteConnectOptions, SqlitePoolOptions};
+    use tempfile::TempDir;
+
+    async fn pool_at(path: &Path) -> Pool<Sqlite> {
+        let options = SqliteConnectOptions::new()
+            .filename(path)
+            .create_if_missing(true);
+        SqlitePoolOptions::new()
+            .connect_with(options)
+            .await
+            .unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_backup_and_restore() {
+        let dir = TempDir::new().unwrap();
+        let db_path = dir.path().join("sessions.db");
+        let backup_dir = dir.path().join(BACKUPS_FOLDER);
+
+        let pool = pool_at(&db_path).await;
+        sqlx::query("CREATE TABLE t (v TEXT)")
+            .execute(&pool)
+            .await
+            .unwrap();
+        sqlx::query("INSERT INTO t VALUES ('kept')")
+            .execute(&pool)
+            .await
+            .unwrap();
+        assert!(integrity_check(&pool).await.unwrap().is_empty());
+        assert!(quick_check(&pool).await.unwrap().is_empty());
+        let backup_path = backup(&pool, &backup_dir).await.unwrap();
+        pool.close().await;
+
+        fs::write(&db_path, b"garbage that is not a database").unwrap();
+        assert_eq!(
+            restore_latest_backup(&db_path, &backup_dir).unwrap(),
+            Some(backup_path)
+        );
+
+        let pool = pool_at(&db_path).await;
+        let value: String = sqlx::query_scalar("SELECT v FROM t")
+            .fetch_one(&pool)
+            .await
+            .unwrap();
+        assert_eq!(value, "kept");
+    }
+
+    #[test]
+    fn test_rotation_keeps_newest() {
+        let dir = TempDir::new().unwrap();
+        for stamp in ["20250101", "20250103", "20250102", "20250104"] {
+            fs::write(dir.path().join(format!("sessions-{}.db", stamp)), b"").unwrap();
+        }
+        fs::write(dir.path().join("unrelated.db"), b"").unwrap();
+
+        let removed = rotate_backups(dir.path(), 2).unwrap();
+        assert_eq!(
+            removed,
+            vec![
+                dir.path().join("sessions-20250101.db"),
+                dir.path().join("sessions-20250102.db")
+            ]
+        );
+        assert_eq!(list_backups(dir.path()).unwrap().len(), 2);
+        assert!(dir.path().join("unrelated.db").exists());
+    }
+
+    #[test]
+    fn test_restore_without_backup() {
+        let dir = TempDir::new().unwrap();
+        let db_path = dir.path().join("sessions.db");
+        assert_eq!(
+            restore_latest_backup(&db_path, &dir.path().join(BACKUPS_FOLDER)).unwrap(),
+            None
+        );
+    }
+}
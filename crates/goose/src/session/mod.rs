@@ -1,11 +1,27 @@
+pub mod attachments;
+pub mod backend;
 mod chat_history_search;
+pub mod continuity;
 mod diagnostics;
+pub mod environment;
 pub mod extension_data;
+pub mod feedback;
 mod legacy;
+pub mod maintenance;
+pub mod postgres;
+pub mod redaction;
+pub mod retention;
 pub mod session_manager;
+pub mod turn_snapshots;
+pub mod workspace;
+pub mod write_buffer;
 
+pub use chat_history_search::ChatRecallResults;
 pub use diagnostics::{generate_diagnostics, get_system_info, SystemInfo};
+pub use environment::SessionEnvironment;
 pub use extension_data::{EnabledExtensionsState, ExtensionData, ExtensionState, TodoState};
 pub use session_manager::{
-    Session, SessionInsights, SessionManager, SessionType, SessionUpdateBuilder,
+    ArchivedFilter, MessagePage, Session, SessionConflict, SessionFilter, SessionInsights,
+    SessionManager, SessionType, SessionUpdateBuilder,
 };
+pub use workspace::{WorkspaceRoot, WorkspaceRoots};
@@ -0,0 +1,394 @@
+//! Removing content from a stored conversation.
+//!
+//! Redaction rewrites the matching content in place; deletion drops whole
+//! messages. Either way the compaction summaries in the conversation may
+//! still quote what was removed, so they are dropped as well and the original
+//! messages they stood in for are made agent-visible again, ready to be
+//! summarized afresh.
+
+use crate::conversation::message::{
+    ActionRequiredData, FrontendToolRequest, Message, MessageContent, ToolRequest,
+};
+use crate::conversation::Conversation;
+use crate::mcp_utils::ToolResult;
+use anyhow::{anyhow, bail, Result};
+use rmcp::model::{CallToolRequestParams, Content, JsonObject};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use utoipa::ToSchema;
+
+pub const REDACTED_PLACEHOLDER: &str = "[redacted]";
+pub const REDACTION_AUDIT_CATEGORY: &str = "session_redaction";
+
+/// Messages on each side of a change that are re-checked by guardrails
+pub const GUARDRAIL_WINDOW: usize = 2;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum RedactionAction {
+    Redact,
+    Delete,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct RedactionRequest {
+    pub action: RedactionAction,
+    /// Messages to change. May be empty when redacting by `text`, which then
+    /// applies to every message containing it.
+    #[serde(default)]
+    pub message_ids: Vec<String>,
+    /// Only replace this text instead of the whole message
+    #[serde(default)]
+    pub text: Option<String>,
+    #[serde(default)]
+    pub reason: Option<String>,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize, ToSchema)]
+pub struct RedactionOutcome {
+    /// Ids of the messages that were rewritten or deleted
+    pub affected: Vec<String>,
+    /// Positions in the rewritten conversation whose neighbourhood changed
+    pub changed_indices: Vec<usize>,
+    /// Compaction summaries dropped because they may quote removed content
+    pub summaries_dropped: usize,
+}
+
+/// A message near the change that guardrails flagged on the re-check
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, ToSchema)]
+pub struct GuardrailFlag {
+    pub index: usize,
+    pub message_id: Option<String>,
+    pub reason: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct RedactionReport {
+    #[serde(flatten)]
+    pub outcome: RedactionOutcome,
+    pub guardrail_flags: Vec<GuardrailFlag>,
+    /// Whether a fresh compaction replaced the dropped summaries
+    pub compacted: bool,
+}
+
+/// Rewrites `conversation` according to `request`
+pub fn redact_conversation(
+    conversation: &Conversation,
+    request: &RedactionRequest,
+) -> Result<(Conversation, RedactionOutcome)> {
+    let text = request.text.as_deref().filter(|t| !t.is_empty());
+    if request.message_ids.is_empty() && text.is_none() {
+        bail!("Specify message_ids, text, or both");
+    }
+    if request.action == RedactionAction::Delete && request.message_ids.is_empty() {
+        bail!("Deleting requires message_ids");
+    }
+    let targeted = |message: &Message| {
+        request.message_ids.is_empty()
+            || message
+                .id
+                .as_ref()
+                .is_some_and(|id| request.message_ids.contains(id))
+    };
+
+    if let Some(missing) = request.message_ids.iter().find(|id| {
+        !conversation
+            .messages()
+            .iter()
+            .any(|m| m.id.as_ref() == Some(*id))
+    }) {
+        bail!("Message {} is not in this session", missing);
+    }
+
+    // Each surviving message is paired with whether it sits where content was
+    // changed; a deletion marks the message that follows it
+    let mut outcome = RedactionOutcome::default();
+    let mut messages: Vec<(Message, bool)> = Vec::new();
+    let mut pending = false;
+    for message in conversation.messages() {
+        if !targeted(message) {
+            messages.push((message.clone(), std::mem::take(&mut pending)));
+            continue;
+        }
+        match request.action {
+            RedactionAction::Delete => {
+                outcome.affected.extend(message.id.clone());
+                pending = true;
+            }
+            RedactionAction::Redact => {
+                let redacted = match text {
+                    Some(text) => redact_text(message, text)?,
+                    None => redact_message(message),
+                };
+                let changed = &redacted != message;
+                if changed {
+                    outcome.affected.extend(message.id.clone());
+                }
+                messages.push((redacted, changed || std::mem::take(&mut pending)));
+            }
+        }
+    }
+
+    // Summaries are agent-only; the messages they replaced stay user-visible
+    // but were hidden from the agent
+    let mut kept: Vec<Message> = Vec::new();
+    for (message, changed) in messages {
+        if message.is_agent_visible() && !message.is_user_visible() {
+            outcome.summaries_dropped += 1;
+            pending |= changed;
+            continue;
+        }
+        if changed || std::mem::take(&mut pending) {
+            outcome.changed_indices.push(kept.len());
+        }
+        let metadata = if message.is_user_visible() {
+            message.metadata.with_agent_visible()
+        } else {
+            message.metadata
+        };
+        kept.push(message.with_metadata(metadata));
+    }
+    if pending && !kept.is_empty() {
+        outcome.changed_indices.push(kept.len() - 1);
+    }
+    outcome.changed_indices.dedup();
+
+    Ok((Conversation::new_unvalidated(kept), outcome))
+}
+
+/// Replaces every string in `value`, keeping its shape
+fn blank_strings(value: &mut Value) {
+    match value {
+        Value::String(text) => *text = REDACTED_PLACEHOLDER.to_string(),
+        Value::Array(items) => items.iter_mut().for_each(blank_strings),
+        Value::Object(fields) => fields.values_mut().for_each(blank_strings),
+        _ => {}
+    }
+}
+
+fn blank_arguments(arguments: &mut JsonObject) {
+    arguments.values_mut().for_each(blank_strings);
+}
+
+fn blank_tool_call(tool_call: &mut ToolResult<CallToolRequestParams>) {
+    match tool_call {
+        Ok(call) => {
+            if let Some(arguments) = &mut call.arguments {
+                blank_arguments(arguments);
+            }
+        }
+        Err(error) => error.message = REDACTED_PLACEHOLDER.into(),
+    }
+}
+
+/// Clears everything the message says, tool arguments and results included,
+/// while keeping tool ids and names so requests still pair with responses.
+fn redact_message(message: &Message) -> Message {
+    let mut message = message.clone();
+    for content in &mut message.content {
+        match content {
+            MessageContent::Text(text) => text.text = REDACTED_PLACEHOLDER.to_string(),
+            MessageContent::Image(_) => *content = MessageContent::text(REDACTED_PLACEHOLDER),
+            MessageContent::Thinking(thinking) => {
+                thinking.thinking = REDACTED_PLACEHOLDER.to_string();
+            }
+            MessageContent::ToolRequest(ToolRequest { tool_call, .. })
+            | MessageContent::FrontendToolRequest(FrontendToolRequest { tool_call, .. }) => {
+                blank_tool_call(tool_call);
+            }
+            MessageContent::ToolResponse(response) => match &mut response.tool_result {
+                Ok(result) => {
+                    result.content = vec![Content::text(REDACTED_PLACEHOLDER)];
+                    result.structured_content = None;
+                }
+                Err(error) => error.message = REDACTED_PLACEHOLDER.into(),
+            },
+            MessageContent::ToolConfirmationRequest(request) => {
+                blank_arguments(&mut request.arguments);
+                request.prompt = None;
+            }
+            MessageContent::ActionRequired(action) => match &mut action.data {
+                ActionRequiredData::ToolConfirmation {
+                    arguments, prompt, ..
+                } => {
+                    blank_arguments(arguments);
+                    *prompt = None;
+                }
+                ActionRequiredData::Elicitation { message, .. } => {
+                    *message = REDACTED_PLACEHOLDER.to_string();
+                }
+                ActionRequiredData::ElicitationResponse { user_data, .. } => {
+                    blank_strings(user_data);
+                }
+            },
+            // Opaque to goose, or written by goose itself
+            MessageContent::RedactedThinking(_) | MessageContent::SystemNotification(_) => {}
+        }
+    }
+    message
+}
+
+fn replace_in_strings(value: &mut Value, text: &str) {
+    match value {
+        Value::String(value) if value.contains(text) => {
+            *value = value.replace(text, REDACTED_PLACEHOLDER);
+        }
+        Value::Array(items) => items.iter_mut().for_each(|v| replace_in_strings(v, text)),
+        Value::Object(fields) => fields
+            .values_mut()
+            .for_each(|v| replace_in_strings(v, text)),
+        _ => {}
+    }
+}
+
+/// Replaces `text` in every content item, including tool arguments and
+/// results, by going through the string values of the serialized form.
+/// Fails when the text is part of the content's structure, such as a type
+/// tag, rather than leaving it in place.
+fn redact_text(message: &Message, text: &str) -> Result<Message> {
+    let mut message = message.clone();
+    for content in &mut message.content {
+        let mut value = serde_json::to_value(&*content)?;
+        let before = value.clone();
+        replace_in_strings(&mut value, text);
+        if value == before {
+            continue;
+        }
+        *content = serde_json::from_value(value).map_err(|e| {
+            anyhow!(
+                "Cannot redact the text from message {} without breaking it ({}); redact the whole message instead",
+                message.id.as_deref().unwrap_or("without id"),
+                e
+            )
+        })?;
+    }
+    Ok(message)
+}
+
+/// Indices around each change, clamped to the conversation
+pub fn guardrail_window(changed: &[usize], len: usize) -> Vec<usize> {
+    let mut window: Vec<usize> = changed
+        .iter()
+        .flat_map(|&i| i.saturating_sub(GUARDRAIL_WINDOW)..(i + GUARDRAIL_WINDOW + 1).min(len))
+        .collect();
+    window.sort_unstable();
+    window.dedup();
+    window
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn conversation() -> Conversation {
+        Conversation::new_unvalidated([
+            Message::user()
+                .with_text("my key is sk-live-1234")
+                .with_id("m1"),
+            Message::assistant()
+                .with_text("Got it, using sk-live-1234")
+                .with_id("m2")
+                .user_only(),
+            Message::user()
+                .with_text("Summary: the user shared sk-live-1234")
+                .with_id("s1")
+                .agent_only(),
+            Message::user().with_text("thanks").with_id("m3"),
+        ])
+    }
+
+    #[test]
+    fn test_redact_text_everywhere_and_drop_summaries() {
+        let request = RedactionRequest {
+            action: RedactionAction::Redact,
+            message_ids: vec![],
+            text: Some("sk-live-1234".to_string()),
+            reason: None,
+        };
+        let (redacted, outcome) = redact_conversation(&conversation(), &request).unwrap();
+
+        assert_eq!(outcome.affected, vec!["m1", "m2", "s1"]);
+        assert_eq!(outcome.summaries_dropped, 1);
+        assert_eq!(outcome.changed_indices, vec![0, 1, 2]);
+        assert_eq!(redacted.len(), 3);
+        assert!(redacted.iter().all(|m| m.is_agent_visible()));
+        assert_eq!(
+            redacted.messages()[0].as_concat_text(),
+            "my key is [redacted]"
+        );
+        assert!(!serde_json::to_string(&redacted)
+            .unwrap()
+            .contains("sk-live"));
+    }
+
+    #[test]
+    fn test_delete_and_unknown_ids() {
+        let request = RedactionRequest {
+            action: RedactionAction::Delete,
+            message_ids: vec!["m1".to_string()],
+            text: None,
+            reason: Some("pasted a secret".to_string()),
+        };
+        let (redacted, outcome) = redact_conversation(&conversation(), &request).unwrap();
+        assert_eq!(outcome.affected, vec!["m1"]);
+        assert_eq!(redacted.messages()[0].id.as_deref(), Some("m2"));
+        assert_eq!(outcome.changed_indices, vec![0]);
+        assert_eq!(redacted.len(), 2);
+        assert_eq!(
+            guardrail_window(&outcome.changed_indices, redacted.len()),
+            vec![0, 1]
+        );
+
+        let request = RedactionRequest {
+            action: RedactionAction::Redact,
+            message_ids: vec!["nope".to_string()],
+            text: None,
+            reason: None,
+        };
+        assert!(redact_conversation(&conversation(), &request).is_err());
+    }
+    #[test]
+    fn test_redact_tool_calls_and_fail_loudly() {
+        let conversation = Conversation::new_unvalidated([
+            Message::assistant()
+                .with_tool_request(
+                    "t1",
+                    Ok(CallToolRequestParams {
+                        meta: None,
+                        task: None,
+                        name: "shell".into(),
+                        arguments: Some(rmcp::object!({"command": "export KEY=sk-live-1234"})),
+                    }),
+                )
+                .with_id("m1"),
+            Message::user()
+                .with_tool_response(
+                    "t1",
+                    Ok(rmcp::model::CallToolResult::success(vec![Content::text(
+                        "KEY=sk-live-1234",
+                    )])),
+                )
+                .with_id("m2"),
+        ]);
+        let request = RedactionRequest {
+            action: RedactionAction::Redact,
+            message_ids: vec!["m1".to_string(), "m2".to_string()],
+            text: None,
+            reason: None,
+        };
+        let (redacted, outcome) = redact_conversation(&conversation, &request).unwrap();
+        assert_eq!(outcome.affected, vec!["m1", "m2"]);
+        let json = serde_json::to_string(&redacted).unwrap();
+        assert!(!json.contains("sk-live"));
+        assert!(json.contains("\"shell\""));
+
+        // The text is also a content type tag, so it can't be replaced in place
+        let request = RedactionRequest {
+            action: RedactionAction::Redact,
+            message_ids: vec![],
+            text: Some("toolResponse".to_string()),
+            reason: None,
+        };
+        assert!(redact_conversation(&conversation, &request).is_err());
+    }
+}
@@ -0,0 +1,96 @@
+//! Retention for sessions that pile up over months of use. Sessions idle for
+//! a while are archived, and archived sessions are exported to JSON and
+//! purged later still. Both steps are off unless configured.
+
+use crate::config::Config;
+use anyhow::Result;
+use chrono::{DateTime, Duration, Utc};
+use serde::{Deserialize, Serialize};
+use sqlx::{Pool, Sqlite};
+use std::path::PathBuf;
+use utoipa::ToSchema;
+
+/// Folder next to the database where purged sessions are exported
+pub const PURGED_FOLDER: &str = "purged";
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
+pub struct RetentionPolicy {
+    /// Archive user and scheduled sessions untouched for this many days
+    pub archive_after_days: Option<u32>,
+    /// Export and delete sessions archived for this many days
+    pub purge_after_days: Option<u32>,
+}
+
+impl RetentionPolicy {
+    /// Reads `GOOSE_SESSION_ARCHIVE_AFTER_DAYS` and `GOOSE_SESSION_PURGE_AFTER_DAYS`.
+    pub fn from_config() -> Self {
+        let config = Config::global();
+        Self {
+            archive_after_days: config
+                .get_param::<u32>("GOOSE_SESSION_ARCHIVE_AFTER_DAYS")
+                .ok()
+                .filter(|days| *days > 0),
+            purge_after_days: config
+                .get_param::<u32>("GOOSE_SESSION_PURGE_AFTER_DAYS")
+                .ok()
+                .filter(|days| *days > 0),
+        }
+    }
+
+    pub fn is_enabled(&self) -> bool {
+        self.archive_after_days.is_some() || self.purge_after_days.is_some()
+    }
+}
+
+#[derive(Debug, Clone, Default, Serialize, ToSchema)]
+pub struct RetentionReport {
+    pub archived: Vec<String>,
+    pub purged: Vec<String>,
+    /// Where the purged sessions were exported
+    #[schema(value_type = Option<String>)]
+    pub export_dir: Option<PathBuf>,
+}
+
+fn cutoff(now: DateTime<Utc>, days: u32) -> DateTime<Utc> {
+    now - Duration::days(days as i64)
+}
+
+// Timestamps are written both by SQLite (`datetime('now')`) and by sqlx
+// (RFC 3339), so comparisons go through `datetime()` to normalize them.
+
+/// Archives idle user and scheduled sessions and returns their ids.
+pub async fn archive_idle(
+    pool: &Pool<Sqlite>,
+    now: DateTime<Utc>,
+    days: u32,
+) -> Result<Vec<String>> {
+    sqlx::query_scalar::<_, String>(
+        r#"
+        UPDATE sessions SET archived_at = ?
+        WHERE archived_at IS NULL
+          AND session_type IN ('user', 'scheduled')
+          AND datetime(updated_at) < datetime(?)
+        RETURNING id
+    "#,
+    )
+    .bind(now)
+    .bind(cutoff(now, days))
+    .fetch_all(pool)
+    .await
+    .map_err(Into::into)
+}
+
+/// Ids of sessions archived long enough ago to purge.
+pub async fn purgeable(pool: &Pool<Sqlite>, now: DateTime<Utc>, days: u32) -> Result<Vec<String>> {
+    sqlx::query_scalar::<_, String>(
+        r#"
+        SELECT id FROM sessions
+        WHERE archived_at IS NOT NULL AND datetime(archived_at) < datetime(?)
+        ORDER BY archived_at
+    "#,
+    )
+    .bind(cutoff(now, days))
+    .fetch_all(pool)
+    .await
+    .map_err(Into::into)
+}
@@ -1,15 +1,27 @@
+use crate::agents::reflexion::Reflection;
+use crate::artifacts::{self, ArtifactRegistry};
 use crate::config::paths::Paths;
 use crate::conversation::message::Message;
 use crate::conversation::Conversation;
+use crate::database;
 use crate::model::ModelConfig;
 use crate::providers::base::{Provider, MSG_COUNT_FOR_SESSION_NAME_GENERATION};
+use crate::providers::task_routing;
 use crate::recipe::Recipe;
+use crate::security::at_rest;
+use crate::session::attachments::{self, AttachmentStore};
+use crate::session::backend::{self, MetadataUpdate, SessionBackend};
 use crate::session::extension_data::ExtensionData;
+use crate::session::feedback::{self, Feedback, FeedbackStats, Rating};
+use crate::session::maintenance::{self, MaintenanceReport, MAINTENANCE_INTERVAL};
+use crate::session::retention::{self, RetentionPolicy, RetentionReport};
+use crate::session::turn_snapshots::{self, TurnSnapshotStore};
+use crate::session::write_buffer::{self, WriteBuffer, MAX_PENDING_MESSAGES};
 use anyhow::Result;
+use async_trait::async_trait;
 use chrono::{DateTime, Utc};
 use rmcp::model::Role;
 use serde::{Deserialize, Serialize};
-use sqlx::sqlite::{SqliteConnectOptions, SqlitePoolOptions};
 use sqlx::{Pool, Sqlite};
 use std::collections::HashMap;
 use std::fs;
@@ -18,10 +30,13 @@ use std::sync::{Arc, LazyLock};
 use tracing::{info, warn};
 use utoipa::ToSchema;
 
-pub const CURRENT_SCHEMA_VERSION: i32 = 7;
+pub const CURRENT_SCHEMA_VERSION: i32 = 11;
 pub const SESSIONS_FOLDER: &str = "sessions";
 pub const DB_NAME: &str = "sessions.db";
 
+/// Row id, role, content, created, metadata and message id
+pub(super) type MessageRow = (i64, String, String, i64, Option<String>, Option<String>);
+
 #[derive(Debug, Clone, Copy, Serialize, Deserialize, ToSchema, PartialEq, Eq, Default)]
 #[serde(rename_all = "snake_case")]
 pub enum SessionType {
@@ -63,6 +78,9 @@ impl std::str::FromStr for SessionType {
 static SESSION_STORAGE: LazyLock<Arc<SessionStorage>> =
     LazyLock::new(|| Arc::new(SessionStorage::new(Paths::data_dir())));
 
+static SESSION_BACKEND: LazyLock<Arc<dyn SessionBackend>> =
+    LazyLock::new(|| backend::from_config(Arc::clone(&SESSION_STORAGE) as _));
+
 #[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
 pub struct Session {
     pub id: String,
@@ -90,27 +108,97 @@ pub struct Session {
     pub message_count: usize,
     pub provider_name: Option<String>,
     pub model_config: Option<ModelConfig>,
+    #[serde(default)]
+    pub tags: Vec<String>,
+    /// Archived sessions are hidden from listings unless asked for
+    #[serde(default)]
+    pub archived_at: Option<DateTime<Utc>>,
+    /// Bumped on every conversation write, for conditional updates
+    #[serde(default)]
+    pub revision: i64,
+}
+
+/// A conditional write found the session at a different revision than the
+/// writer last saw, usually because another replica wrote it first.
+#[derive(Debug, thiserror::Error, PartialEq, Eq)]
+#[error("Session {session_id} is at revision {current}, not {expected}; reload it and retry")]
+pub struct SessionConflict {
+    pub session_id: String,
+    pub expected: i64,
+    pub current: i64,
+}
+
+/// Older messages of a session, loaded a page at a time.
+#[derive(Debug, Clone, Serialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct MessagePage {
+    /// Oldest first
+    pub messages: Vec<Message>,
+    /// Cursor for the page before this one, if there is one
+    pub before: Option<i64>,
+}
+
+/// Which archived sessions a listing includes.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum ArchivedFilter {
+    #[default]
+    Exclude,
+    Include,
+    Only,
+}
+
+#[derive(Debug, Clone, Default, Deserialize, ToSchema)]
+pub struct SessionFilter {
+    /// Only sessions carrying all of these tags
+    #[serde(default)]
+    pub tags: Vec<String>,
+    #[serde(default)]
+    pub archived: ArchivedFilter,
+}
+
+impl SessionFilter {
+    pub fn all() -> Self {
+        Self {
+            tags: Vec::new(),
+            archived: ArchivedFilter::Include,
+        }
+    }
+}
+
+/// Tags are compared case-insensitively, so they are stored trimmed and lowercase.
+pub fn normalize_tags(tags: impl IntoIterator<Item = impl AsRef<str>>) -> Vec<String> {
+    let mut normalized: Vec<String> = tags
+        .into_iter()
+        .map(|tag| tag.as_ref().trim().to_lowercase())
+        .filter(|tag| !tag.is_empty())
+        .collect();
+    normalized.sort();
+    normalized.dedup();
+    normalized
 }
 
 pub struct SessionUpdateBuilder<'a> {
     session_manager: &'a SessionManager,
-    session_id: String,
-    name: Option<String>,
-    user_set_name: Option<bool>,
-    session_type: Option<SessionType>,
-    working_dir: Option<PathBuf>,
-    extension_data: Option<ExtensionData>,
-    total_tokens: Option<Option<i32>>,
-    input_tokens: Option<Option<i32>>,
-    output_tokens: Option<Option<i32>>,
-    accumulated_total_tokens: Option<Option<i32>>,
-    accumulated_input_tokens: Option<Option<i32>>,
-    accumulated_output_tokens: Option<Option<i32>>,
-    schedule_id: Option<Option<String>>,
-    recipe: Option<Option<Recipe>>,
-    user_recipe_values: Option<Option<HashMap<String, String>>>,
-    provider_name: Option<Option<String>>,
-    model_config: Option<Option<ModelConfig>>,
+    pub(super) session_id: String,
+    pub(super) name: Option<String>,
+    pub(super) user_set_name: Option<bool>,
+    pub(super) session_type: Option<SessionType>,
+    pub(super) working_dir: Option<PathBuf>,
+    pub(super) extension_data: Option<ExtensionData>,
+    pub(super) total_tokens: Option<Option<i32>>,
+    pub(super) input_tokens: Option<Option<i32>>,
+    pub(super) output_tokens: Option<Option<i32>>,
+    pub(super) accumulated_total_tokens: Option<Option<i32>>,
+    pub(super) accumulated_input_tokens: Option<Option<i32>>,
+    pub(super) accumulated_output_tokens: Option<Option<i32>>,
+    pub(super) schedule_id: Option<Option<String>>,
+    pub(super) recipe: Option<Option<Recipe>>,
+    pub(super) user_recipe_values: Option<Option<HashMap<String, String>>>,
+    pub(super) provider_name: Option<Option<String>>,
+    pub(super) model_config: Option<Option<ModelConfig>>,
+    pub(super) tags: Option<Vec<String>>,
+    pub(super) archived_at: Option<Option<DateTime<Utc>>>,
 }
 
 #[derive(Serialize, ToSchema, Debug)]
@@ -141,6 +229,8 @@ impl<'a> SessionUpdateBuilder<'a> {
             user_recipe_values: None,
             provider_name: None,
             model_config: None,
+            tags: None,
+            archived_at: None,
         }
     }
 
@@ -238,22 +328,47 @@ impl<'a> SessionUpdateBuilder<'a> {
         self.model_config = Some(Some(model_config));
         self
     }
+
+    pub fn tags(mut self, tags: Vec<String>) -> Self {
+        self.tags = Some(normalize_tags(tags));
+        self
+    }
+
+    pub fn archived(mut self, archived: bool) -> Self {
+        self.archived_at = Some(archived.then(Utc::now));
+        self
+    }
 }
 
+/// Sessions live in a [`SessionBackend`], the local database unless a
+/// shared store is configured (see [`backend`]). Maintenance, retention,
+/// feedback, search and artifacts always use the local [`SessionStorage`].
 pub struct SessionManager {
     storage: Arc<SessionStorage>,
+    backend: Arc<dyn SessionBackend>,
 }
 
 impl SessionManager {
     pub fn new(data_dir: PathBuf) -> Self {
+        let storage = Arc::new(SessionStorage::new(data_dir));
         Self {
-            storage: Arc::new(SessionStorage::new(data_dir)),
+            backend: Arc::clone(&storage) as _,
+            storage,
         }
     }
 
     pub fn instance() -> Self {
         Self {
             storage: Arc::clone(&SESSION_STORAGE),
+            backend: Arc::clone(&SESSION_BACKEND),
+        }
+    }
+
+    /// Local storage under `data_dir`, with sessions kept in `backend`.
+    pub fn with_backend(data_dir: PathBuf, backend: Arc<dyn SessionBackend>) -> Self {
+        Self {
+            storage: Arc::new(SessionStorage::new(data_dir)),
+            backend,
         }
     }
 
@@ -261,19 +376,31 @@ impl SessionManager {
         &self.storage
     }
 
+    pub fn artifacts(&self) -> &ArtifactRegistry {
+        self.storage.artifacts()
+    }
+
+    pub fn attachments(&self) -> AttachmentStore {
+        self.storage.attachments()
+    }
+
+    pub fn turn_snapshots(&self) -> TurnSnapshotStore {
+        self.storage.turn_snapshots()
+    }
+
     pub async fn create_session(
         &self,
         working_dir: PathBuf,
         name: String,
         session_type: SessionType,
     ) -> Result<Session> {
-        self.storage
+        self.backend
             .create_session(working_dir, name, session_type)
             .await
     }
 
     pub async fn get_session(&self, id: &str, include_messages: bool) -> Result<Session> {
-        self.storage.get_session(id, include_messages).await
+        self.backend.get_session(id, include_messages).await
     }
 
     pub fn update(&self, id: &str) -> SessionUpdateBuilder<'_> {
@@ -281,47 +408,272 @@ impl SessionManager {
     }
 
     async fn apply_update_inner(&self, builder: SessionUpdateBuilder<'_>) -> Result<()> {
-        self.storage.apply_update(builder).await
+        self.backend.apply_update(builder).await
     }
 
     pub async fn add_message(&self, id: &str, message: &Message) -> Result<()> {
-        self.storage.add_message(id, message).await
+        self.backend.add_message(id, message).await
+    }
+
+    /// Like [`Self::add_message`], but written together with the session's
+    /// other queued messages, see [`write_buffer`].
+    pub async fn queue_message(&self, id: &str, message: &Message) -> Result<()> {
+        self.backend.queue_message(id, message).await
+    }
+
+    /// Writes the messages queued for a session.
+    pub async fn flush_pending(&self, id: &str) -> Result<usize> {
+        self.backend.flush_pending(id).await
+    }
+
+    /// Writes the messages queued for every session, as on shutdown.
+    pub async fn flush_all_pending(&self) -> Result<usize> {
+        self.backend.flush_all_pending().await
     }
 
     pub async fn replace_conversation(&self, id: &str, conversation: &Conversation) -> Result<()> {
-        self.storage.replace_conversation(id, conversation).await
+        self.backend.replace_conversation(id, conversation).await
+    }
+
+    /// The whole conversation, including what compaction hid from the agent.
+    pub async fn get_conversation(&self, id: &str) -> Result<Conversation> {
+        self.backend.get_conversation(id).await
+    }
+
+    /// The part of the conversation the agent works with: everything from
+    /// the first message it can see, which in a compacted session is the
+    /// latest summary. Messages compaction hid before that are not loaded;
+    /// page through them with [`Self::get_messages_page`].
+    pub async fn get_conversation_window(&self, id: &str) -> Result<Conversation> {
+        self.backend.get_conversation_window(id).await
+    }
+
+    /// Replaces what [`Self::get_conversation_window`] returned, keeping the
+    /// messages before it.
+    pub async fn replace_conversation_window(
+        &self,
+        id: &str,
+        conversation: &Conversation,
+    ) -> Result<()> {
+        self.backend
+            .replace_conversation_window(id, conversation)
+            .await
+    }
+
+    /// Up to `limit` messages before the `before` cursor, or the latest ones
+    /// without it.
+    pub async fn get_messages_page(
+        &self,
+        id: &str,
+        before: Option<i64>,
+        limit: usize,
+    ) -> Result<MessagePage> {
+        self.backend.get_messages_page(id, before, limit).await
+    }
+
+    /// Replaces the conversation only if the session is still at
+    /// `expected_revision`, returning the new revision. Fails with
+    /// [`SessionConflict`] otherwise.
+    pub async fn replace_conversation_if(
+        &self,
+        id: &str,
+        conversation: &Conversation,
+        expected_revision: i64,
+    ) -> Result<i64> {
+        self.backend
+            .replace_conversation_if(id, conversation, expected_revision)
+            .await
     }
 
+    /// User and scheduled sessions, leaving out archived ones.
     pub async fn list_sessions(&self) -> Result<Vec<Session>> {
-        self.storage.list_sessions().await
+        self.list_sessions_filtered(&SessionFilter::default()).await
+    }
+
+    pub async fn list_sessions_filtered(&self, filter: &SessionFilter) -> Result<Vec<Session>> {
+        self.backend
+            .list_sessions_matching(&[SessionType::User, SessionType::Scheduled], filter)
+            .await
+    }
+
+    pub async fn run_maintenance(&self) -> Result<MaintenanceReport> {
+        self.storage.run_maintenance().await
+    }
+
+    /// Encrypts message content stored before encryption at rest was turned
+    /// on. Returns how many messages were sealed.
+    pub async fn seal_existing_messages(&self) -> Result<usize> {
+        self.storage.seal_existing_messages().await
+    }
+
+    /// Scrubs deleted sessions from the database file and its WAL.
+    pub async fn compact(&self) -> Result<()> {
+        self.storage.compact().await
+    }
+
+    /// Database backups, oldest first
+    pub fn list_backups(&self) -> Result<Vec<PathBuf>> {
+        maintenance::list_backups(&self.storage.backup_dir())
+    }
+
+    /// Where retention exports sessions before purging them
+    pub fn purged_exports_dir(&self) -> PathBuf {
+        self.storage.session_dir.join(retention::PURGED_FOLDER)
+    }
+
+    /// Archives idle sessions and purges old archived ones according to `policy`.
+    pub async fn apply_retention(&self, policy: &RetentionPolicy) -> Result<RetentionReport> {
+        self.storage.apply_retention(policy, Utc::now()).await
+    }
+
+    /// Runs database maintenance in the background every
+    /// [`MAINTENANCE_INTERVAL`]. Retention runs with the rest of the data
+    /// lifecycle in [`crate::privacy::spawn_retention_job`].
+    pub fn spawn_maintenance(&self) -> tokio::task::JoinHandle<()> {
+        let storage = Arc::clone(&self.storage);
+        tokio::spawn(async move {
+            let mut interval = tokio::time::interval(MAINTENANCE_INTERVAL);
+            interval.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Delay);
+            loop {
+                interval.tick().await;
+                if let Err(e) = storage.run_maintenance().await {
+                    warn!("Session database maintenance failed: {}", e);
+                }
+            }
+        })
     }
 
+    /// Sessions of the given types, archived ones included.
     pub async fn list_sessions_by_types(&self, types: &[SessionType]) -> Result<Vec<Session>> {
-        self.storage.list_sessions_by_types(types).await
+        self.backend
+            .list_sessions_matching(types, &SessionFilter::all())
+            .await
     }
 
     pub async fn delete_session(&self, id: &str) -> Result<()> {
-        self.storage.delete_session(id).await
+        self.backend.delete_session(id).await
     }
 
     pub async fn get_insights(&self) -> Result<SessionInsights> {
-        self.storage.get_insights().await
+        self.backend.get_insights().await
     }
 
     pub async fn export_session(&self, id: &str) -> Result<String> {
-        self.storage.export_session(id).await
+        let session = self.get_session(id, true).await?;
+        serde_json::to_string_pretty(&session).map_err(Into::into)
+    }
+
+    pub async fn add_feedback(
+        &self,
+        session_id: &str,
+        message_id: Option<&str>,
+        rating: Rating,
+        comment: Option<&str>,
+    ) -> Result<Feedback> {
+        self.storage
+            .add_feedback(session_id, message_id, rating, comment)
+            .await
+    }
+
+    pub async fn list_feedback(&self, session_id: &str) -> Result<Vec<Feedback>> {
+        self.storage.list_feedback(session_id).await
+    }
+
+    pub async fn feedback_stats(&self) -> Result<FeedbackStats> {
+        self.storage.feedback_stats().await
+    }
+
+    pub async fn save_feedback_reflection(
+        &self,
+        feedback_id: i64,
+        reflection: &Reflection,
+    ) -> Result<()> {
+        self.storage
+            .save_feedback_reflection(feedback_id, reflection)
+            .await
+    }
+
+    pub async fn feedback_reflections(&self) -> Result<Vec<Reflection>> {
+        self.storage.feedback_reflections().await
     }
 
     pub async fn import_session(&self, json: &str) -> Result<Session> {
-        self.storage.import_session(self, json).await
+        let import: Session = serde_json::from_str(json)?;
+
+        let session = self
+            .create_session(
+                import.working_dir.clone(),
+                import.name.clone(),
+                import.session_type,
+            )
+            .await?;
+
+        let mut builder = self
+            .update(&session.id)
+            .extension_data(import.extension_data)
+            .total_tokens(import.total_tokens)
+            .input_tokens(import.input_tokens)
+            .output_tokens(import.output_tokens)
+            .accumulated_total_tokens(import.accumulated_total_tokens)
+            .accumulated_input_tokens(import.accumulated_input_tokens)
+            .accumulated_output_tokens(import.accumulated_output_tokens)
+            .schedule_id(import.schedule_id)
+            .recipe(import.recipe)
+            .user_recipe_values(import.user_recipe_values);
+
+        if import.user_set_name {
+            builder = builder.user_provided_name(import.name.clone());
+        }
+
+        builder.apply().await?;
+
+        if let Some(conversation) = import.conversation {
+            self.replace_conversation(&session.id, &conversation)
+                .await?;
+        }
+
+        self.get_session(&session.id, true).await
     }
 
     pub async fn copy_session(&self, session_id: &str, new_name: String) -> Result<Session> {
-        self.storage.copy_session(self, session_id, new_name).await
+        let original_session = self.get_session(session_id, true).await?;
+
+        let new_session = self
+            .create_session(
+                original_session.working_dir.clone(),
+                new_name,
+                original_session.session_type,
+            )
+            .await?;
+
+        let mut builder = self
+            .update(&new_session.id)
+            .extension_data(original_session.extension_data)
+            .schedule_id(original_session.schedule_id)
+            .recipe(original_session.recipe)
+            .user_recipe_values(original_session.user_recipe_values);
+
+        // Preserve provider and model config from original session
+        if let Some(provider_name) = original_session.provider_name {
+            builder = builder.provider_name(provider_name);
+        }
+
+        if let Some(model_config) = original_session.model_config {
+            builder = builder.model_config(model_config);
+        }
+
+        builder.apply().await?;
+
+        if let Some(conversation) = original_session.conversation {
+            self.replace_conversation(&new_session.id, &conversation)
+                .await?;
+        }
+
+        self.get_session(&new_session.id, true).await
     }
 
     pub async fn truncate_conversation(&self, session_id: &str, timestamp: i64) -> Result<()> {
-        self.storage
+        self.backend
             .truncate_conversation(session_id, timestamp)
             .await
     }
@@ -344,7 +696,9 @@ impl SessionManager {
             .count();
 
         if user_message_count <= MSG_COUNT_FOR_SESSION_NAME_GENERATION {
-            let name = provider.generate_session_name(id, &conversation).await?;
+            let name = task_routing::global()
+                .generate_session_name(provider.as_ref(), id, &conversation)
+                .await?;
             self.update(id).system_generated_name(name).apply().await
         } else {
             Ok(())
@@ -367,23 +721,26 @@ impl SessionManager {
     pub async fn update_message_metadata<F>(id: &str, message_id: &str, f: F) -> Result<()>
     where
         F: FnOnce(
-            crate::conversation::message::MessageMetadata,
-        ) -> crate::conversation::message::MessageMetadata,
+                crate::conversation::message::MessageMetadata,
+            ) -> crate::conversation::message::MessageMetadata
+            + Send,
     {
         Self::instance()
-            .storage
-            .update_message_metadata(id, message_id, f)
+            .backend
+            .update_message_metadata(id, message_id, Box::new(f))
             .await
     }
 }
 
 pub struct SessionStorage {
-    pool: Pool<Sqlite>,
-    initialized: tokio::sync::OnceCell<()>,
+    pool: tokio::sync::OnceCell<Pool<Sqlite>>,
+    db_path: PathBuf,
     session_dir: PathBuf,
+    pending: WriteBuffer,
+    artifacts: ArtifactRegistry,
 }
 
-fn role_to_string(role: &Role) -> &'static str {
+pub(super) fn role_to_string(role: &Role) -> &'static str {
     match role {
         Role::User => "user",
         Role::Assistant => "assistant",
@@ -414,6 +771,9 @@ impl Default for Session {
             message_count: 0,
             provider_name: None,
             model_config: None,
+            tags: Vec::new(),
+            archived_at: None,
+            revision: 0,
         }
     }
 }
@@ -450,6 +810,11 @@ impl sqlx::FromRow<'_, sqlx::sqlite::SqliteRow> for Session {
 
         let user_set_name = row.try_get("user_set_name").unwrap_or(false);
 
+        let tags_json: Option<String> = row.try_get("tags_json").ok().flatten();
+        let tags = tags_json
+            .and_then(|json| serde_json::from_str(&json).ok())
+            .unwrap_or_default();
+
         let session_type_str: String = row
             .try_get("session_type")
             .unwrap_or_else(|_| "user".to_string());
@@ -478,6 +843,9 @@ impl sqlx::FromRow<'_, sqlx::sqlite::SqliteRow> for Session {
             message_count: row.try_get("message_count").unwrap_or(0) as usize,
             provider_name: row.try_get("provider_name").ok().flatten(),
             model_config,
+            tags,
+            archived_at: row.try_get("archived_at").ok().flatten(),
+            revision: row.try_get("revision").unwrap_or(0),
         })
     }
 }
@@ -489,52 +857,185 @@ impl SessionStorage {
             fs::create_dir_all(parent).expect("Failed to create session database directory");
         }
 
-        let options = SqliteConnectOptions::new()
-            .filename(path)
-            .create_if_missing(true)
-            .busy_timeout(std::time::Duration::from_secs(5))
-            .journal_mode(sqlx::sqlite::SqliteJournalMode::Wal);
-
-        SqlitePoolOptions::new().connect_lazy_with(options)
+        database::open("sessions", path)
     }
 
     pub fn new(data_dir: PathBuf) -> Self {
         let session_dir = data_dir.join(SESSIONS_FOLDER);
-        let db_path = session_dir.join(DB_NAME);
         Self {
-            pool: Self::create_pool(&db_path),
-            initialized: tokio::sync::OnceCell::new(),
+            pool: tokio::sync::OnceCell::new(),
+            db_path: session_dir.join(DB_NAME),
+            pending: WriteBuffer::new(session_dir.join(write_buffer::PENDING_FOLDER)),
             session_dir,
+            artifacts: ArtifactRegistry::new(data_dir.join(artifacts::ARTIFACTS_FOLDER)),
         }
     }
 
     async fn pool(&self) -> Result<&Pool<Sqlite>> {
-        self.initialized
+        self.pool
             .get_or_try_init(|| async {
+                let pool = self.open_checked().await?;
                 let schema_exists = sqlx::query_scalar::<_, bool>(
                     r#"SELECT EXISTS (SELECT name FROM sqlite_master WHERE type='table' AND name='schema_version')"#,
                 )
-                .fetch_one(&self.pool)
+                .fetch_one(&pool)
                 .await
                 .unwrap_or(false);
 
                 if schema_exists {
-                    Self::run_migrations(&self.pool).await?;
+                    Self::run_migrations(&pool).await?;
                 } else {
-                    Self::create_schema(&self.pool).await?;
-                    if let Err(e) = Self::import_legacy(&self.pool, &self.session_dir).await {
+                    Self::create_schema(&pool).await?;
+                    if let Err(e) = Self::import_legacy(&pool, &self.session_dir).await {
                         warn!("Failed to import some legacy sessions: {}", e);
                     }
                 }
-                Ok::<(), anyhow::Error>(())
+                let store = self.attachments();
+                let legacy_dir = self.session_dir.join(attachments::ATTACHMENTS_FOLDER);
+                let migrated =
+                    database::blocking(move || store.migrate_legacy(&legacy_dir)).await;
+                if let Err(e) = migrated {
+                    warn!("Failed to move attachments into the artifact registry: {}", e);
+                }
+                if let Err(e) = self.replay_pending(&pool).await {
+                    warn!("Failed to recover unsaved session messages: {}", e);
+                }
+                Ok::<_, anyhow::Error>(pool)
             })
+            .await
+    }
+
+    fn backup_dir(&self) -> PathBuf {
+        self.session_dir.join(maintenance::BACKUPS_FOLDER)
+    }
+
+    pub fn artifacts(&self) -> &ArtifactRegistry {
+        &self.artifacts
+    }
+
+    pub fn attachments(&self) -> AttachmentStore {
+        AttachmentStore::new(self.artifacts.clone())
+    }
+
+    pub fn turn_snapshots(&self) -> TurnSnapshotStore {
+        TurnSnapshotStore::new(self.session_dir.join(turn_snapshots::TURN_SNAPSHOTS_FOLDER))
+    }
+
+    /// Opens the database, restoring the newest backup if it turns out to be corrupt.
+    async fn open_checked(&self) -> Result<Pool<Sqlite>> {
+        let pool = Self::create_pool(&self.db_path);
+        let error = match maintenance::quick_check(&pool).await {
+            Ok(problems) if problems.is_empty() => return Ok(pool),
+            Ok(problems) => anyhow::anyhow!("corrupt database: {}", problems.join("; ")),
+            Err(e) if maintenance::is_corruption_error(&e) => e,
+            Err(e) => return Err(e),
+        };
+        pool.close().await;
+
+        let (db_path, backup_dir) = (self.db_path.clone(), self.backup_dir());
+        let restored =
+            database::blocking(move || maintenance::restore_latest_backup(&db_path, &backup_dir))
+                .await?;
+        match restored {
+            Some(backup) => {
+                warn!(
+                    "Session database was corrupt ({}); restored backup {}",
+                    error,
+                    backup.display()
+                );
+                Ok(Self::create_pool(&self.db_path))
+            }
+            None => Err(error.context("Session database is corrupt and no backup is available")),
+        }
+    }
+
+    /// Checks integrity, then backs up and compacts the database. Backups are
+    /// skipped when the check fails so a damaged database never replaces a good copy.
+    pub async fn run_maintenance(&self) -> Result<MaintenanceReport> {
+        self.flush_all_pending().await?;
+        let pool = self.pool().await?;
+        let problems = maintenance::integrity_check(pool).await?;
+        if !problems.is_empty() {
+            warn!("Session database integrity check failed: {:?}", problems);
+            return Ok(MaintenanceReport {
+                integrity_ok: false,
+                problems,
+                backup: None,
+                vacuumed: false,
+            });
+        }
+
+        let backup = maintenance::backup(pool, &self.backup_dir()).await?;
+        sqlx::query("VACUUM").execute(pool).await?;
+        info!(
+            "Session database maintenance complete, backup at {}",
+            backup.display()
+        );
+        Ok(MaintenanceReport {
+            integrity_ok: true,
+            problems,
+            backup: Some(backup),
+            vacuumed: true,
+        })
+    }
+
+    pub async fn seal_existing_messages(&self) -> Result<usize> {
+        const BATCH_SIZE: i64 = 500;
+        self.flush_all_pending().await?;
+        let pool = self.pool().await?;
+        let mut sealed = 0;
+        let mut last_id = 0;
+        loop {
+            let rows = sqlx::query_as::<_, (i64, String)>(
+                "SELECT id, content_json FROM messages WHERE id > ? AND content_json NOT LIKE ? ORDER BY id LIMIT ?",
+            )
+            .bind(last_id)
+            .bind(at_rest::SEALED_LIKE_PATTERN)
+            .bind(BATCH_SIZE)
+            .fetch_all(pool)
+            .await?;
+            let Some((id, _)) = rows.last() else {
+                break;
+            };
+            last_id = *id;
+
+            let mut tx = pool.begin().await?;
+            for (id, content_json) in &rows {
+                sqlx::query("UPDATE messages SET content_json = ? WHERE id = ?")
+                    .bind(at_rest::seal_always(content_json)?)
+                    .bind(id)
+                    .execute(&mut *tx)
+                    .await?;
+            }
+            tx.commit().await?;
+            sealed += rows.len();
+        }
+
+        // No plaintext may be left in free pages
+        self.compact().await?;
+        info!("Sealed {} stored messages", sealed);
+        Ok(sealed)
+    }
+
+    /// Rewrites the file and empties the WAL, so rows that were deleted or
+    /// overwritten can no longer be read from free pages.
+    pub async fn compact(&self) -> Result<()> {
+        let pool = self.pool().await?;
+        sqlx::query("VACUUM").execute(pool).await?;
+        sqlx::query("PRAGMA wal_checkpoint(TRUNCATE)")
+            .execute(pool)
             .await?;
-        Ok(&self.pool)
+        Ok(())
     }
 
     pub async fn create(session_dir: &Path) -> Result<Self> {
         let storage = Self::new(session_dir.to_path_buf());
-        Self::create_schema(&storage.pool).await?;
+        let pool = Self::create_pool(&storage.db_path);
+        Self::create_schema(&pool).await?;
+        storage
+            .pool
+            .set(pool)
+            .map_err(|_| anyhow::anyhow!("Session storage already initialized"))?;
         Ok(storage)
     }
 
@@ -577,7 +1078,10 @@ impl SessionStorage {
                 recipe_json TEXT,
                 user_recipe_values_json TEXT,
                 provider_name TEXT,
-                model_config_json TEXT
+                model_config_json TEXT,
+                tags_json TEXT,
+                archived_at TIMESTAMP,
+                revision INTEGER NOT NULL DEFAULT 0
             )
         "#,
         )
@@ -617,6 +1121,13 @@ impl SessionStorage {
         sqlx::query("CREATE INDEX idx_sessions_type ON sessions(session_type)")
             .execute(pool)
             .await?;
+        sqlx::query("CREATE INDEX idx_sessions_archived ON sessions(archived_at)")
+            .execute(pool)
+            .await?;
+
+        let mut conn = pool.acquire().await?;
+        feedback::create_table(&mut conn).await?;
+        feedback::add_reflection_column(&mut conn).await?;
 
         Ok(())
     }
@@ -885,6 +1396,42 @@ impl SessionStorage {
                     .execute(&mut **tx)
                     .await?;
             }
+            8 => {
+                feedback::create_table(&mut **tx).await?;
+            }
+            9 => {
+                sqlx::query(
+                    r#"
+                    ALTER TABLE sessions ADD COLUMN tags_json TEXT
+                "#,
+                )
+                .execute(&mut **tx)
+                .await?;
+
+                sqlx::query(
+                    r#"
+                    ALTER TABLE sessions ADD COLUMN archived_at TIMESTAMP
+                "#,
+                )
+                .execute(&mut **tx)
+                .await?;
+
+                sqlx::query("CREATE INDEX idx_sessions_archived ON sessions(archived_at)")
+                    .execute(&mut **tx)
+                    .await?;
+            }
+            10 => {
+                sqlx::query(
+                    r#"
+                    ALTER TABLE sessions ADD COLUMN revision INTEGER NOT NULL DEFAULT 0
+                "#,
+                )
+                .execute(&mut **tx)
+                .await?;
+            }
+            11 => {
+                feedback::add_reflection_column(&mut **tx).await?;
+            }
             _ => {
                 anyhow::bail!("Unknown migration version: {}", version);
             }
@@ -935,6 +1482,7 @@ impl SessionStorage {
     }
 
     async fn get_session(&self, id: &str, include_messages: bool) -> Result<Session> {
+        self.flush_pending(id).await?;
         let pool = self.pool().await?;
         let mut session = sqlx::query_as::<_, Session>(
             r#"
@@ -942,7 +1490,7 @@ impl SessionStorage {
                total_tokens, input_tokens, output_tokens,
                accumulated_total_tokens, accumulated_input_tokens, accumulated_output_tokens,
                schedule_id, recipe_json, user_recipe_values_json,
-               provider_name, model_config_json
+               provider_name, model_config_json, tags_json, archived_at, revision
         FROM sessions
         WHERE id = ?
     "#,
@@ -953,7 +1501,7 @@ impl SessionStorage {
             .ok_or_else(|| anyhow::anyhow!("Session not found"))?;
 
         if include_messages {
-            let conv = self.get_conversation(&session.id).await?;
+            let conv = self.get_conversation(&session.id, None).await?;
             session.message_count = conv.messages().len();
             session.conversation = Some(conv);
         } else {
@@ -1005,6 +1553,8 @@ impl SessionStorage {
         add_update!(builder.user_recipe_values, "user_recipe_values_json");
         add_update!(builder.provider_name, "provider_name");
         add_update!(builder.model_config, "model_config_json");
+        add_update!(builder.tags, "tags_json");
+        add_update!(builder.archived_at, "archived_at");
 
         if updates.is_empty() {
             return Ok(());
@@ -1070,6 +1620,12 @@ impl SessionStorage {
                 .transpose()?;
             q = q.bind(model_config_json);
         }
+        if let Some(tags) = builder.tags {
+            q = q.bind(serde_json::to_string(&tags)?);
+        }
+        if let Some(archived_at) = builder.archived_at {
+            q = q.bind(archived_at);
+        }
 
         let pool = self.pool().await?;
         let mut tx = pool.begin().await?;
@@ -1080,17 +1636,23 @@ impl SessionStorage {
         Ok(())
     }
 
-    async fn get_conversation(&self, session_id: &str) -> Result<Conversation> {
+    /// The session's messages, from row `from` on if given.
+    async fn get_conversation(&self, session_id: &str, from: Option<i64>) -> Result<Conversation> {
         let pool = self.pool().await?;
-        let rows = sqlx::query_as::<_, (String, String, i64, Option<String>, Option<String>)>(
-            "SELECT role, content_json, created_timestamp, metadata_json, message_id FROM messages WHERE session_id = ? ORDER BY timestamp",
+        let rows = sqlx::query_as::<_, MessageRow>(
+            "SELECT id, role, content_json, created_timestamp, metadata_json, message_id FROM messages WHERE session_id = ? AND id >= ? ORDER BY timestamp, id",
         )
             .bind(session_id)
+            .bind(from.unwrap_or(0))
             .fetch_all(pool)
             .await?;
 
+        Ok(Conversation::new_unvalidated(Self::read_messages(rows)?))
+    }
+
+    pub(super) fn read_messages(rows: Vec<MessageRow>) -> Result<Vec<Message>> {
         let mut messages = Vec::new();
-        for (role_str, content_json, created_timestamp, metadata_json, message_id) in
+        for (_, role_str, content_json, created_timestamp, metadata_json, message_id) in
             rows.into_iter()
         {
             let role = match role_str.as_str() {
@@ -1099,7 +1661,7 @@ impl SessionStorage {
                 _ => continue,
             };
 
-            let content = serde_json::from_str(&content_json)?;
+            let content = serde_json::from_str(&at_rest::open(content_json)?)?;
             let metadata = metadata_json
                 .and_then(|json| serde_json::from_str(&json).ok())
                 .unwrap_or_default();
@@ -1111,14 +1673,93 @@ impl SessionStorage {
             }
             messages.push(message);
         }
+        Ok(messages)
+    }
 
-        Ok(Conversation::new_unvalidated(messages))
+    /// Row of the first message the agent can see. Visibility is read from
+    /// the metadata column, so hidden messages are never deserialized.
+    async fn window_start<'e, E>(executor: E, session_id: &str) -> Result<Option<i64>>
+    where
+        E: sqlx::Executor<'e, Database = Sqlite>,
+    {
+        Ok(sqlx::query_scalar::<_, Option<i64>>(
+            "SELECT MIN(id) FROM messages WHERE session_id = ? AND COALESCE(json_extract(metadata_json, '$.agentVisible'), 1) = 1",
+        )
+        .bind(session_id)
+        .fetch_one(executor)
+        .await?)
     }
 
-    async fn add_message(&self, session_id: &str, message: &Message) -> Result<()> {
+    async fn get_conversation_window(&self, session_id: &str) -> Result<Conversation> {
+        self.flush_pending(session_id).await?;
+        let pool = self.pool().await?;
+        match Self::window_start(pool, session_id).await? {
+            Some(start) => self.get_conversation(session_id, Some(start)).await,
+            None => Ok(Conversation::new_unvalidated(Vec::new())),
+        }
+    }
+
+    async fn replace_conversation_window(
+        &self,
+        session_id: &str,
+        conversation: &Conversation,
+    ) -> Result<()> {
+        self.flush_pending(session_id).await?;
         let pool = self.pool().await?;
         let mut tx = pool.begin().await?;
+        if let Some(start) = Self::window_start(&mut *tx, session_id).await? {
+            sqlx::query("DELETE FROM messages WHERE session_id = ? AND id >= ?")
+                .bind(session_id)
+                .bind(start)
+                .execute(&mut *tx)
+                .await?;
+        }
+        for message in conversation.messages() {
+            Self::insert_message(&mut tx, session_id, message).await?;
+        }
+        sqlx::query(
+            "UPDATE sessions SET updated_at = datetime('now'), revision = revision + 1 WHERE id = ?",
+        )
+        .bind(session_id)
+        .execute(&mut *tx)
+        .await?;
+        tx.commit().await?;
+        Ok(())
+    }
+
+    async fn get_messages_page(
+        &self,
+        session_id: &str,
+        before: Option<i64>,
+        limit: usize,
+    ) -> Result<MessagePage> {
+        self.flush_pending(session_id).await?;
+        let pool = self.pool().await?;
+        let mut rows = sqlx::query_as::<_, MessageRow>(
+            "SELECT id, role, content_json, created_timestamp, metadata_json, message_id FROM messages WHERE session_id = ? AND id < ? ORDER BY id DESC LIMIT ?",
+        )
+        .bind(session_id)
+        .bind(before.unwrap_or(i64::MAX))
+        .bind(limit as i64 + 1)
+        .fetch_all(pool)
+        .await?;
+
+        // One extra row tells whether an older page exists
+        let more = rows.len() > limit;
+        rows.truncate(limit);
+        rows.reverse();
+        let before = rows.first().map(|row| row.0).filter(|_| more);
+        Ok(MessagePage {
+            messages: Self::read_messages(rows)?,
+            before,
+        })
+    }
 
+    async fn insert_message(
+        tx: &mut sqlx::Transaction<'_, Sqlite>,
+        session_id: &str,
+        message: &Message,
+    ) -> Result<()> {
         let metadata_json = serde_json::to_string(&message.metadata)?;
 
         let message_id = message
@@ -1135,18 +1776,121 @@ impl SessionStorage {
         .bind(message_id)
         .bind(session_id)
         .bind(role_to_string(&message.role))
-        .bind(serde_json::to_string(&message.content)?)
+        .bind(at_rest::seal(serde_json::to_string(&message.content)?)?)
         .bind(message.created)
         .bind(metadata_json)
+        .execute(&mut **tx)
+        .await?;
+        Ok(())
+    }
+
+    async fn add_message(&self, session_id: &str, message: &Message) -> Result<()> {
+        // Queued messages came first
+        self.flush_pending(session_id).await?;
+        let pool = self.pool().await?;
+        let mut tx = pool.begin().await?;
+
+        Self::insert_message(&mut tx, session_id, message).await?;
+
+        sqlx::query(
+            "UPDATE sessions SET updated_at = datetime('now'), revision = revision + 1 WHERE id = ?",
+        )
+        .bind(session_id)
         .execute(&mut *tx)
         .await?;
 
-        sqlx::query("UPDATE sessions SET updated_at = datetime('now') WHERE id = ?")
-            .bind(session_id)
-            .execute(&mut *tx)
-            .await?;
+        tx.commit().await?;
+        Ok(())
+    }
+
+    async fn queue_message(&self, session_id: &str, message: &Message) -> Result<()> {
+        if self.pending.push(session_id, message).await? >= MAX_PENDING_MESSAGES {
+            self.flush_pending(session_id).await?;
+        }
+        Ok(())
+    }
+
+    /// Writes the messages queued for `session_id` in one transaction,
+    /// returning how many there were.
+    async fn flush_pending(&self, session_id: &str) -> Result<usize> {
+        // The pool is opened first: opening it replays logs under the lock
+        let pool = self.pool().await?;
+        let mut pending = self.pending.lock().await;
+        let messages = pending.messages(session_id);
+        if messages.is_empty() {
+            return Ok(0);
+        }
 
+        let mut tx = pool.begin().await?;
+        for message in messages {
+            Self::insert_message(&mut tx, session_id, message).await?;
+        }
+        sqlx::query(
+            "UPDATE sessions SET updated_at = datetime('now'), revision = revision + ? WHERE id = ?",
+        )
+        .bind(messages.len() as i64)
+        .bind(session_id)
+        .execute(&mut *tx)
+        .await?;
         tx.commit().await?;
+
+        let flushed = messages.len();
+        pending.clear(session_id)?;
+        Ok(flushed)
+    }
+
+    async fn flush_all_pending(&self) -> Result<usize> {
+        let session_ids = self.pending.lock().await.session_ids();
+        let mut flushed = 0;
+        for session_id in session_ids {
+            flushed += self.flush_pending(&session_id).await?;
+        }
+        Ok(flushed)
+    }
+
+    /// Writes the messages a stopped process queued but never wrote. Those it
+    /// did write are recognized by id and skipped.
+    async fn replay_pending(&self, pool: &Pool<Sqlite>) -> Result<()> {
+        let mut pending = self.pending.lock().await;
+        for (session_id, messages) in self.pending.recover()? {
+            let mut tx = pool.begin().await?;
+            let exists =
+                sqlx::query_scalar::<_, bool>("SELECT EXISTS(SELECT 1 FROM sessions WHERE id = ?)")
+                    .bind(&session_id)
+                    .fetch_one(&mut *tx)
+                    .await?;
+            let mut replayed = 0;
+            // Messages of a since deleted session are dropped
+            for message in messages.iter().filter(|_| exists) {
+                let written = sqlx::query_scalar::<_, bool>(
+                    "SELECT EXISTS(SELECT 1 FROM messages WHERE message_id = ?)",
+                )
+                .bind(&message.id)
+                .fetch_one(&mut *tx)
+                .await?;
+                if !written {
+                    Self::insert_message(&mut tx, &session_id, message).await?;
+                    replayed += 1;
+                }
+            }
+            if replayed > 0 {
+                sqlx::query(
+                    "UPDATE sessions SET updated_at = datetime('now'), revision = revision + ? WHERE id = ?",
+                )
+                .bind(replayed)
+                .bind(&session_id)
+                .execute(&mut *tx)
+                .await?;
+            }
+            tx.commit().await?;
+            pending.clear(&session_id)?;
+            if replayed > 0 {
+                info!(
+                    "Recovered {} unsaved messages of session {}",
+                    replayed, session_id
+                );
+            }
+        }
         Ok(())
     }
 
@@ -1156,37 +1900,28 @@ impl SessionStorage {
         conversation: &Conversation,
     ) -> Result<()> {
         let mut tx = pool.begin().await?;
-
-        sqlx::query("DELETE FROM messages WHERE session_id = ?")
+        sqlx::query("UPDATE sessions SET revision = revision + 1 WHERE id = ?")
             .bind(session_id)
             .execute(&mut *tx)
             .await?;
+        Self::write_conversation(&mut tx, session_id, conversation).await?;
+        tx.commit().await?;
+        Ok(())
+    }
 
-        for message in conversation.messages() {
-            let metadata_json = serde_json::to_string(&message.metadata)?;
-
-            let message_id = message
-                .id
-                .clone()
-                .unwrap_or_else(|| format!("msg_{}_{}", session_id, uuid::Uuid::new_v4()));
-
-            sqlx::query(
-                r#"
-            INSERT INTO messages (message_id, session_id, role, content_json, created_timestamp, metadata_json)
-            VALUES (?, ?, ?, ?, ?, ?)
-        "#,
-            )
-            .bind(message_id)
+    async fn write_conversation(
+        tx: &mut sqlx::Transaction<'_, Sqlite>,
+        session_id: &str,
+        conversation: &Conversation,
+    ) -> Result<()> {
+        sqlx::query("DELETE FROM messages WHERE session_id = ?")
             .bind(session_id)
-            .bind(role_to_string(&message.role))
-            .bind(serde_json::to_string(&message.content)?)
-            .bind(message.created)
-            .bind(metadata_json)
-            .execute(&mut *tx)
+            .execute(&mut **tx)
             .await?;
-        }
 
-        tx.commit().await?;
+        for message in conversation.messages() {
+            Self::insert_message(tx, session_id, message).await?;
+        }
         Ok(())
     }
 
@@ -1195,49 +1930,138 @@ impl SessionStorage {
         session_id: &str,
         conversation: &Conversation,
     ) -> Result<()> {
+        self.flush_pending(session_id).await?;
         let pool = self.pool().await?;
         Self::replace_conversation_inner(pool, session_id, conversation).await
     }
 
-    async fn list_sessions_by_types(&self, types: &[SessionType]) -> Result<Vec<Session>> {
+    /// The revision check and the write share one transaction, so of two
+    /// writers that saw the same revision exactly one succeeds.
+    pub async fn replace_conversation_if(
+        &self,
+        session_id: &str,
+        conversation: &Conversation,
+        expected_revision: i64,
+    ) -> Result<i64> {
+        self.flush_pending(session_id).await?;
+        let pool = self.pool().await?;
+        let mut tx = pool.begin().await?;
+
+        let updated = sqlx::query(
+            "UPDATE sessions SET revision = revision + 1, updated_at = datetime('now') WHERE id = ? AND revision = ?",
+        )
+        .bind(session_id)
+        .bind(expected_revision)
+        .execute(&mut *tx)
+        .await?
+        .rows_affected();
+        if updated == 0 {
+            let current =
+                sqlx::query_scalar::<_, i64>("SELECT revision FROM sessions WHERE id = ?")
+                    .bind(session_id)
+                    .fetch_optional(&mut *tx)
+                    .await?
+                    .ok_or_else(|| anyhow::anyhow!("Session not found"))?;
+            return Err(SessionConflict {
+                session_id: session_id.to_string(),
+                expected: expected_revision,
+                current,
+            }
+            .into());
+        }
+
+        Self::write_conversation(&mut tx, session_id, conversation).await?;
+        tx.commit().await?;
+        Ok(expected_revision + 1)
+    }
+
+    async fn list_sessions_matching(
+        &self,
+        types: &[SessionType],
+        filter: &SessionFilter,
+    ) -> Result<Vec<Session>> {
         if types.is_empty() {
             return Ok(Vec::new());
         }
 
         let placeholders: String = types.iter().map(|_| "?").collect::<Vec<_>>().join(", ");
+        let mut conditions = match filter.archived {
+            ArchivedFilter::Exclude => " AND s.archived_at IS NULL".to_string(),
+            ArchivedFilter::Include => String::new(),
+            ArchivedFilter::Only => " AND s.archived_at IS NOT NULL".to_string(),
+        };
+        let tags = normalize_tags(&filter.tags);
+        for _ in &tags {
+            conditions.push_str(
+                " AND EXISTS (SELECT 1 FROM json_each(COALESCE(s.tags_json, '[]')) WHERE value = ?)",
+            );
+        }
         let query = format!(
             r#"
             SELECT s.id, s.working_dir, s.name, s.description, s.user_set_name, s.session_type, s.created_at, s.updated_at, s.extension_data,
                    s.total_tokens, s.input_tokens, s.output_tokens,
                    s.accumulated_total_tokens, s.accumulated_input_tokens, s.accumulated_output_tokens,
                    s.schedule_id, s.recipe_json, s.user_recipe_values_json,
-                   s.provider_name, s.model_config_json,
+                   s.provider_name, s.model_config_json, s.tags_json, s.archived_at, s.revision,
                    COUNT(m.id) as message_count
             FROM sessions s
             INNER JOIN messages m ON s.id = m.session_id
-            WHERE s.session_type IN ({})
+            WHERE s.session_type IN ({}){}
             GROUP BY s.id
             ORDER BY s.updated_at DESC
             "#,
-            placeholders
+            placeholders, conditions
         );
 
+        self.flush_all_pending().await?;
         let mut q = sqlx::query_as::<_, Session>(&query);
         for t in types {
             q = q.bind(t.to_string());
         }
+        for tag in tags {
+            q = q.bind(tag);
+        }
 
         let pool = self.pool().await?;
         q.fetch_all(pool).await.map_err(Into::into)
     }
 
-    async fn list_sessions(&self) -> Result<Vec<Session>> {
-        self.list_sessions_by_types(&[SessionType::User, SessionType::Scheduled])
-            .await
+    async fn apply_retention(
+        &self,
+        policy: &RetentionPolicy,
+        now: DateTime<Utc>,
+    ) -> Result<RetentionReport> {
+        let pool = self.pool().await?;
+        let mut report = RetentionReport::default();
+        if let Some(days) = policy.archive_after_days {
+            report.archived = retention::archive_idle(pool, now, days).await?;
+        }
+        if let Some(days) = policy.purge_after_days {
+            let export_dir = self.session_dir.join(retention::PURGED_FOLDER);
+            for id in retention::purgeable(pool, now, days).await? {
+                let json = self.export_session(&id).await?;
+                fs::create_dir_all(&export_dir)?;
+                fs::write(export_dir.join(format!("{}.json", id)), json)?;
+                self.delete_session(&id).await?;
+                report.purged.push(id);
+            }
+            if !report.purged.is_empty() {
+                report.export_dir = Some(export_dir);
+            }
+        }
+        if !report.archived.is_empty() || !report.purged.is_empty() {
+            info!(
+                "Session retention archived {} and purged {} sessions",
+                report.archived.len(),
+                report.purged.len()
+            );
+        }
+        Ok(report)
     }
 
     async fn delete_session(&self, session_id: &str) -> Result<()> {
         let pool = self.pool().await?;
+        self.pending.lock().await.clear(session_id)?;
         let mut tx = pool.begin().await?;
 
         let exists =
@@ -1255,16 +2079,32 @@ impl SessionStorage {
             .execute(&mut *tx)
             .await?;
 
+        feedback::delete_for_session(&mut tx, session_id).await?;
+
         sqlx::query("DELETE FROM sessions WHERE id = ?")
             .bind(session_id)
             .execute(&mut *tx)
             .await?;
 
         tx.commit().await?;
+
+        if let Err(e) = self.artifacts.remove_session(session_id) {
+            warn!(
+                "Failed to remove artifacts of session {}: {}",
+                session_id, e
+            );
+        }
+        if let Err(e) = self.turn_snapshots().remove_session(session_id) {
+            warn!(
+                "Failed to remove turn snapshots of session {}: {}",
+                session_id, e
+            );
+        }
         Ok(())
     }
 
     async fn get_insights(&self) -> Result<SessionInsights> {
+        self.flush_all_pending().await?;
         let pool = self.pool().await?;
         let row = sqlx::query_as::<_, (i64, Option<i64>)>(
             r#"
@@ -1282,102 +2122,60 @@ impl SessionStorage {
         })
     }
 
-    async fn export_session(&self, id: &str) -> Result<String> {
-        let session = self.get_session(id, true).await?;
-        serde_json::to_string_pretty(&session).map_err(Into::into)
-    }
-
-    async fn import_session(
+    async fn add_feedback(
         &self,
-        session_manager: &SessionManager,
-        json: &str,
-    ) -> Result<Session> {
-        let import: Session = serde_json::from_str(json)?;
-
-        let session = self
-            .create_session(
-                import.working_dir.clone(),
-                import.name.clone(),
-                import.session_type,
-            )
-            .await?;
-
-        let mut builder = session_manager
-            .update(&session.id)
-            .extension_data(import.extension_data)
-            .total_tokens(import.total_tokens)
-            .input_tokens(import.input_tokens)
-            .output_tokens(import.output_tokens)
-            .accumulated_total_tokens(import.accumulated_total_tokens)
-            .accumulated_input_tokens(import.accumulated_input_tokens)
-            .accumulated_output_tokens(import.accumulated_output_tokens)
-            .schedule_id(import.schedule_id)
-            .recipe(import.recipe)
-            .user_recipe_values(import.user_recipe_values);
-
-        if import.user_set_name {
-            builder = builder.user_provided_name(import.name.clone());
-        }
-
-        builder.apply().await?;
+        session_id: &str,
+        message_id: Option<&str>,
+        rating: Rating,
+        comment: Option<&str>,
+    ) -> Result<Feedback> {
+        let pool = self.pool().await?;
+        feedback::record(pool, session_id, message_id, rating, comment).await
+    }
 
-        if let Some(conversation) = import.conversation {
-            self.replace_conversation(&session.id, &conversation)
-                .await?;
-        }
+    async fn list_feedback(&self, session_id: &str) -> Result<Vec<Feedback>> {
+        let pool = self.pool().await?;
+        feedback::list(pool, session_id).await
+    }
 
-        self.get_session(&session.id, true).await
+    async fn feedback_stats(&self) -> Result<FeedbackStats> {
+        let pool = self.pool().await?;
+        feedback::stats(pool).await
     }
 
-    async fn copy_session(
+    async fn save_feedback_reflection(
         &self,
-        session_manager: &SessionManager,
-        session_id: &str,
-        new_name: String,
-    ) -> Result<Session> {
-        let original_session = self.get_session(session_id, true).await?;
-
-        let new_session = self
-            .create_session(
-                original_session.working_dir.clone(),
-                new_name,
-                original_session.session_type,
-            )
-            .await?;
-
-        let mut builder = session_manager
-            .update(&new_session.id)
-            .extension_data(original_session.extension_data)
-            .schedule_id(original_session.schedule_id)
-            .recipe(original_session.recipe)
-            .user_recipe_values(original_session.user_recipe_values);
-
-        // Preserve provider and model config from original session
-        if let Some(provider_name) = original_session.provider_name {
-            builder = builder.provider_name(provider_name);
-        }
-
-        if let Some(model_config) = original_session.model_config {
-            builder = builder.model_config(model_config);
-        }
-
-        builder.apply().await?;
+        feedback_id: i64,
+        reflection: &Reflection,
+    ) -> Result<()> {
+        let pool = self.pool().await?;
+        feedback::save_reflection(pool, feedback_id, reflection).await
+    }
 
-        if let Some(conversation) = original_session.conversation {
-            self.replace_conversation(&new_session.id, &conversation)
-                .await?;
-        }
+    async fn feedback_reflections(&self) -> Result<Vec<Reflection>> {
+        let pool = self.pool().await?;
+        feedback::reflections(pool).await
+    }
 
-        self.get_session(&new_session.id, true).await
+    async fn export_session(&self, id: &str) -> Result<String> {
+        let session = self.get_session(id, true).await?;
+        serde_json::to_string_pretty(&session).map_err(Into::into)
     }
 
     async fn truncate_conversation(&self, session_id: &str, timestamp: i64) -> Result<()> {
+        self.flush_pending(session_id).await?;
         let pool = self.pool().await?;
+        let mut tx = pool.begin().await?;
         sqlx::query("DELETE FROM messages WHERE session_id = ? AND created_timestamp >= ?")
             .bind(session_id)
             .bind(timestamp)
-            .execute(pool)
+            .execute(&mut *tx)
             .await?;
+        sqlx::query("UPDATE sessions SET revision = revision + 1 WHERE id = ?")
+            .bind(session_id)
+            .execute(&mut *tx)
+            .await?;
+        tx.commit().await?;
 
         Ok(())
     }
@@ -1392,6 +2190,7 @@ impl SessionStorage {
     ) -> Result<crate::session::chat_history_search::ChatRecallResults> {
         use crate::session::chat_history_search::ChatHistorySearch;
 
+        self.flush_all_pending().await?;
         let pool = self.pool().await?;
         ChatHistorySearch::new(
             pool,
@@ -1416,6 +2215,7 @@ impl SessionStorage {
             crate::conversation::message::MessageMetadata,
         ) -> crate::conversation::message::MessageMetadata,
     {
+        self.flush_pending(session_id).await?;
         let pool = self.pool().await?;
         let mut tx = pool.begin().await?;
 
@@ -1448,9 +2248,114 @@ impl SessionStorage {
     }
 }
 
+#[async_trait]
+impl SessionBackend for SessionStorage {
+    async fn create_session(
+        &self,
+        working_dir: PathBuf,
+        name: String,
+        session_type: SessionType,
+    ) -> Result<Session> {
+        SessionStorage::create_session(self, working_dir, name, session_type).await
+    }
+
+    async fn get_session(&self, id: &str, include_messages: bool) -> Result<Session> {
+        SessionStorage::get_session(self, id, include_messages).await
+    }
+
+    async fn apply_update(&self, update: SessionUpdateBuilder<'_>) -> Result<()> {
+        SessionStorage::apply_update(self, update).await
+    }
+
+    async fn add_message(&self, id: &str, message: &Message) -> Result<()> {
+        SessionStorage::add_message(self, id, message).await
+    }
+
+    async fn queue_message(&self, id: &str, message: &Message) -> Result<()> {
+        SessionStorage::queue_message(self, id, message).await
+    }
+
+    async fn flush_pending(&self, id: &str) -> Result<usize> {
+        SessionStorage::flush_pending(self, id).await
+    }
+
+    async fn flush_all_pending(&self) -> Result<usize> {
+        SessionStorage::flush_all_pending(self).await
+    }
+
+    async fn get_conversation(&self, id: &str) -> Result<Conversation> {
+        SessionStorage::flush_pending(self, id).await?;
+        SessionStorage::get_conversation(self, id, None).await
+    }
+
+    async fn replace_conversation(&self, id: &str, conversation: &Conversation) -> Result<()> {
+        SessionStorage::replace_conversation(self, id, conversation).await
+    }
+
+    async fn get_conversation_window(&self, id: &str) -> Result<Conversation> {
+        SessionStorage::get_conversation_window(self, id).await
+    }
+
+    async fn replace_conversation_window(
+        &self,
+        id: &str,
+        conversation: &Conversation,
+    ) -> Result<()> {
+        SessionStorage::replace_conversation_window(self, id, conversation).await
+    }
+
+    async fn get_messages_page(
+        &self,
+        id: &str,
+        before: Option<i64>,
+        limit: usize,
+    ) -> Result<MessagePage> {
+        SessionStorage::get_messages_page(self, id, before, limit).await
+    }
+
+    async fn replace_conversation_if(
+        &self,
+        id: &str,
+        conversation: &Conversation,
+        expected_revision: i64,
+    ) -> Result<i64> {
+        SessionStorage::replace_conversation_if(self, id, conversation, expected_revision).await
+    }
+
+    async fn list_sessions_matching(
+        &self,
+        types: &[SessionType],
+        filter: &SessionFilter,
+    ) -> Result<Vec<Session>> {
+        SessionStorage::list_sessions_matching(self, types, filter).await
+    }
+
+    async fn delete_session(&self, id: &str) -> Result<()> {
+        SessionStorage::delete_session(self, id).await
+    }
+
+    async fn truncate_conversation(&self, id: &str, timestamp: i64) -> Result<()> {
+        SessionStorage::truncate_conversation(self, id, timestamp).await
+    }
+
+    async fn update_message_metadata(
+        &self,
+        id: &str,
+        message_id: &str,
+        update: MetadataUpdate<'_>,
+    ) -> Result<()> {
+        SessionStorage::update_message_metadata(self, id, message_id, update).await
+    }
+
+    async fn get_insights(&self) -> Result<SessionInsights> {
+        SessionStorage::get_insights(self).await
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::agents::reflexion::AttemptOutcome;
     use crate::conversation::message::{Message, MessageContent};
     use tempfile::TempDir;
 
@@ -1637,4 +2542,284 @@ mod tests {
         assert!(imported.user_set_name);
         assert_eq!(imported.working_dir, PathBuf::from("/tmp/test"));
     }
+
+    #[tokio::test]
+    async fn test_feedback_replaces_earlier_rating_and_aggregates() {
+        let temp_dir = TempDir::new().unwrap();
+        let sm = SessionManager::new(temp_dir.path().to_path_buf());
+        let session = sm
+            .create_session(
+                PathBuf::from("/tmp"),
+                "rated".to_string(),
+                SessionType::User,
+            )
+            .await
+            .unwrap();
+        sm.update(&session.id)
+            .provider_name("openai")
+            .model_config(ModelConfig::new_or_fail("gpt-4o"))
+            .apply()
+            .await
+            .unwrap();
+
+        sm.add_feedback(&session.id, Some("msg_1"), Rating::Up, None)
+            .await
+            .unwrap();
+        sm.add_feedback(&session.id, Some("msg_1"), Rating::Down, Some("wrong file"))
+            .await
+            .unwrap();
+        sm.add_feedback(&session.id, None, Rating::Up, None)
+            .await
+            .unwrap();
+
+        let feedback = sm.list_feedback(&session.id).await.unwrap();
+        assert_eq!(feedback.len(), 2);
+        assert_eq!(feedback[0].rating, Rating::Down);
+        assert_eq!(feedback[0].comment.as_deref(), Some("wrong file"));
+        assert_eq!(feedback[1].message_id, None);
+
+        let stats = sm.feedback_stats().await.unwrap();
+        assert_eq!((stats.overall.up, stats.overall.down), (1, 1));
+        assert_eq!(stats.by_model.len(), 1);
+        assert_eq!(stats.by_model[0].name, "openai/gpt-4o");
+        assert!(stats.by_recipe.is_empty());
+
+        let reflection =
+            Reflection::new("fix the build", "edited main.rs", AttemptOutcome::Failure)
+                .with_lessons(vec!["wrong file".to_string()]);
+        sm.save_feedback_reflection(feedback[0].id, &reflection)
+            .await
+            .unwrap();
+        let reflections = sm.feedback_reflections().await.unwrap();
+        assert_eq!(reflections.len(), 1);
+        assert_eq!(reflections[0].lessons, vec!["wrong file".to_string()]);
+
+        sm.delete_session(&session.id).await.unwrap();
+        assert_eq!(sm.feedback_stats().await.unwrap().overall.up, 0);
+        assert!(sm.feedback_reflections().await.unwrap().is_empty());
+    }
+
+    async fn session_with_message(sm: &SessionManager, name: &str) -> Session {
+        let session = sm
+            .create_session(PathBuf::from("/tmp"), name.to_string(), SessionType::User)
+            .await
+            .unwrap();
+        sm.add_message(&session.id, &Message::user().with_text("hello"))
+            .await
+            .unwrap();
+        session
+    }
+
+    #[tokio::test]
+    async fn test_tags_and_archive_filter_listings() {
+        let temp_dir = TempDir::new().unwrap();
+        let sm = SessionManager::new(temp_dir.path().to_path_buf());
+        let tagged = session_with_message(&sm, "tagged").await;
+        let archived = session_with_message(&sm, "archived").await;
+
+        sm.update(&tagged.id)
+            .tags(vec![" Work ".into(), "rust".into(), "work".into()])
+            .apply()
+            .await
+            .unwrap();
+        sm.update(&archived.id)
+            .archived(true)
+            .apply()
+            .await
+            .unwrap();
+
+        let tagged = sm.get_session(&tagged.id, false).await.unwrap();
+        assert_eq!(tagged.tags, vec!["rust", "work"]);
+
+        let listed = sm.list_sessions().await.unwrap();
+        assert_eq!(listed.len(), 1);
+        assert_eq!(listed[0].id, tagged.id);
+
+        let only_archived = sm
+            .list_sessions_filtered(&SessionFilter {
+                archived: ArchivedFilter::Only,
+                ..Default::default()
+            })
+            .await
+            .unwrap();
+        assert_eq!(only_archived.len(), 1);
+        assert!(only_archived[0].archived_at.is_some());
+
+        let by_tag = |tags: &[&str]| SessionFilter {
+            tags: tags.iter().map(|t| t.to_string()).collect(),
+            archived: ArchivedFilter::Include,
+        };
+        assert_eq!(
+            sm.list_sessions_filtered(&by_tag(&["WORK", "rust"]))
+                .await
+                .unwrap()
+                .len(),
+            1
+        );
+        assert!(sm
+            .list_sessions_filtered(&by_tag(&["work", "python"]))
+            .await
+            .unwrap()
+            .is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_stale_revision_is_rejected() {
+        let temp_dir = TempDir::new().unwrap();
+        let sm = SessionManager::new(temp_dir.path().to_path_buf());
+        let session = session_with_message(&sm, "shared").await;
+        let seen = sm.get_session(&session.id, true).await.unwrap();
+        assert!(seen.revision > 0);
+
+        let mut conversation = seen.conversation.clone().unwrap();
+        conversation.push(Message::assistant().with_text("from replica a"));
+        let revision = sm
+            .replace_conversation_if(&session.id, &conversation, seen.revision)
+            .await
+            .unwrap();
+        assert_eq!(revision, seen.revision + 1);
+
+        let error = sm
+            .replace_conversation_if(&session.id, &conversation, seen.revision)
+            .await
+            .unwrap_err();
+        assert_eq!(
+            error.downcast_ref::<SessionConflict>(),
+            Some(&SessionConflict {
+                session_id: session.id.clone(),
+                expected: seen.revision,
+                current: revision,
+            })
+        );
+        let stored = sm.get_session(&session.id, true).await.unwrap();
+        assert_eq!(stored.revision, revision);
+        assert_eq!(stored.message_count, conversation.len());
+    }
+
+    #[tokio::test]
+    async fn test_queued_messages_are_written_and_replayed() {
+        let temp_dir = TempDir::new().unwrap();
+        let sm = SessionManager::new(temp_dir.path().to_path_buf());
+        let session = session_with_message(&sm, "busy").await;
+
+        sm.queue_message(&session.id, &Message::assistant().with_text("queued"))
+            .await
+            .unwrap();
+        let stored = sm.get_session(&session.id, true).await.unwrap();
+        assert_eq!(stored.message_count, 2);
+        assert_eq!(sm.flush_pending(&session.id).await.unwrap(), 0);
+
+        // A process that stops before writing leaves its queue to the next one
+        sm.queue_message(&session.id, &Message::user().with_text("unsaved"))
+            .await
+            .unwrap();
+        let restarted = SessionManager::new(temp_dir.path().to_path_buf());
+        let recovered = restarted.get_session(&session.id, true).await.unwrap();
+        assert_eq!(recovered.message_count, 3);
+        assert_eq!(
+            recovered.conversation.unwrap().messages()[2].as_concat_text(),
+            "unsaved"
+        );
+    }
+
+    #[tokio::test]
+    async fn test_window_skips_compacted_history_and_pages_it() {
+        let temp_dir = TempDir::new().unwrap();
+        let sm = SessionManager::new(temp_dir.path().to_path_buf());
+        let session = session_with_message(&sm, "long").await;
+
+        let mut messages: Vec<Message> = (0..5)
+            .map(|i| Message::user().with_text(format!("old {}", i)).user_only())
+            .collect();
+        messages.push(Message::assistant().with_text("summary").agent_only());
+        messages.push(Message::user().with_text("recent"));
+        sm.replace_conversation(&session.id, &Conversation::new_unvalidated(messages))
+            .await
+            .unwrap();
+
+        let window = sm.get_conversation_window(&session.id).await.unwrap();
+        let texts: Vec<_> = window
+            .messages()
+            .iter()
+            .map(|m| m.as_concat_text())
+            .collect();
+        assert_eq!(texts, vec!["summary", "recent"]);
+
+        let mut compacted = window.clone();
+        compacted.push(Message::assistant().with_text("answer"));
+        sm.replace_conversation_window(&session.id, &compacted)
+            .await
+            .unwrap();
+        assert_eq!(sm.get_conversation(&session.id).await.unwrap().len(), 8);
+
+        let latest = sm.get_messages_page(&session.id, None, 5).await.unwrap();
+        assert_eq!(latest.messages[0].as_concat_text(), "old 3");
+        assert_eq!(latest.messages[4].as_concat_text(), "answer");
+        let older = sm
+            .get_messages_page(&session.id, latest.before, 5)
+            .await
+            .unwrap();
+        let texts: Vec<_> = older.messages.iter().map(|m| m.as_concat_text()).collect();
+        assert_eq!(texts, vec!["old 0", "old 1", "old 2"]);
+        assert_eq!(older.before, None);
+    }
+
+    #[tokio::test]
+    async fn test_retention_archives_idle_and_purges_with_export() {
+        let temp_dir = TempDir::new().unwrap();
+        let sm = SessionManager::new(temp_dir.path().to_path_buf());
+        let session = session_with_message(&sm, "old").await;
+        let policy = RetentionPolicy {
+            archive_after_days: Some(30),
+            purge_after_days: Some(60),
+        };
+
+        let report = sm
+            .storage()
+            .apply_retention(&policy, Utc::now() + chrono::Duration::days(10))
+            .await
+            .unwrap();
+        assert!(report.archived.is_empty());
+
+        let later = Utc::now() + chrono::Duration::days(31);
+        let report = sm.storage().apply_retention(&policy, later).await.unwrap();
+        assert_eq!(report.archived, vec![session.id.clone()]);
+        assert!(report.purged.is_empty());
+
+        let report = sm
+            .storage()
+            .apply_retention(&policy, later + chrono::Duration::days(61))
+            .await
+            .unwrap();
+        assert_eq!(report.purged, vec![session.id.clone()]);
+        let export = report
+            .export_dir
+            .unwrap()
+            .join(format!("{}.json", session.id));
+        assert!(fs::read_to_string(export).unwrap().contains("\"old\""));
+        assert!(sm.get_session(&session.id, false).await.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_corrupt_database_is_restored_from_backup() {
+        let temp_dir = TempDir::new().unwrap();
+        let session_manager = SessionManager::new(temp_dir.path().to_path_buf());
+        let session = session_manager
+            .create_session(PathBuf::from("/tmp"), "kept".to_string(), SessionType::User)
+            .await
+            .unwrap();
+        let report = session_manager.run_maintenance().await.unwrap();
+        assert!(report.integrity_ok && report.backup.is_some());
+        drop(session_manager);
+
+        let db_path = temp_dir.path().join(SESSIONS_FOLDER).join(DB_NAME);
+        fs::write(&db_path, b"power loss").unwrap();
+
+        let session_manager = SessionManager::new(temp_dir.path().to_path_buf());
+        let restored = session_manager
+            .get_session(&session.id, false)
+            .await
+            .unwrap();
+        assert_eq!(restored.name, "kept");
+    }
 }
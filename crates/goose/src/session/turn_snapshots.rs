@@ -0,0 +1,617 @@
+//! What the agent sent to the provider on each turn of a session, so the
+//! state at any turn can be rebuilt for debugging: the system prompt as sent
+//! (with the injected memories, reflections and reasoning blocks listed
+//! separately), the tool list, the conversation slice and the usage and cost
+//! the turn ran up. Each turn also keeps the token count of every part of
+//! the request, which [`TurnSnapshotStore::context_attribution`] adds up to
+//! show what fills a session's context window.
+//!
+//! Turns are numbered per session from 1, across replies. Each session's
+//! turns are appended to one JSON lines file next to the session database.
+//! A turn records the system prompt and tools only when they changed, and
+//! only the messages past the prefix it shares with the previous turn, so a
+//! long session does not store its conversation once per turn. Lines are
+//! sealed like other stored conversations when encryption at rest is on.
+//! The file is removed together with the session, and dropped when the
+//! session's messages are redacted, since it quotes them. Recording is off
+//! unless `GOOSE_TURN_SNAPSHOTS` is set to true.
+
+use crate::config::Config;
+use crate::context_mgmt::budget::{BlockPriority, ComponentTokens, ContextUsage, InjectedBlock};
+use crate::conversation::message::Message;
+use crate::observability::{CostTracker, TokenUsage};
+use crate::providers::base::ProviderUsage;
+use crate::security::at_rest;
+use anyhow::Result;
+use chrono::{DateTime, Utc};
+use rmcp::model::Tool;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::fs;
+use std::io::Write;
+use std::path::PathBuf;
+use std::sync::{LazyLock, Mutex};
+use tracing::warn;
+use utoipa::ToSchema;
+
+use super::attachments::check_component;
+
+/// Folder next to the database where turn snapshots are stored
+pub const TURN_SNAPSHOTS_FOLDER: &str = "turn_snapshots";
+pub const TURN_SNAPSHOTS_KEY: &str = "GOOSE_TURN_SNAPSHOTS";
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, ToSchema)]
+pub struct InjectedContext {
+    pub kind: BlockPriority,
+    #[serde(default)]
+    pub source: String,
+    pub text: String,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+struct PromptRecord {
+    system_prompt: String,
+    /// Blocks offered for the prompt; those trimmed to fit the context
+    /// window are not in `system_prompt`
+    injected: Vec<InjectedContext>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "record", rename_all = "snake_case")]
+enum TurnRecord {
+    Request {
+        turn: u32,
+        timestamp: DateTime<Utc>,
+        provider: String,
+        model: String,
+        /// Unchanged from the previous turn when absent
+        prompt: Option<PromptRecord>,
+        /// Unchanged from the previous turn when absent
+        tools: Option<Vec<Tool>>,
+        /// Leading messages shared with the previous turn
+        kept: usize,
+        messages: Vec<Message>,
+        #[serde(default)]
+        context: Vec<ComponentTokens>,
+    },
+    Usage {
+        turn: u32,
+        model: String,
+        input_tokens: u64,
+        output_tokens: u64,
+        cached_tokens: u64,
+        cost_usd: f64,
+    },
+}
+
+/// What one turn sends to the provider.
+pub struct TurnRequest<'a> {
+    pub provider: String,
+    pub model: String,
+    pub system_prompt: &'a str,
+    pub injected: &'a [InjectedBlock],
+    pub tools: &'a [Tool],
+    pub messages: &'a [Message],
+    /// Token counts from the context budget, when it ran
+    pub context: Option<&'a ContextUsage>,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct TurnUsage {
+    pub model: String,
+    pub input_tokens: u64,
+    pub output_tokens: u64,
+    pub cached_tokens: u64,
+    pub cost_usd: f64,
+}
+
+#[derive(Debug, Clone, Serialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct TurnSummary {
+    pub turn: u32,
+    pub timestamp: DateTime<Utc>,
+    pub model: String,
+    pub message_count: usize,
+    pub usage: Option<TurnUsage>,
+}
+
+/// The state of a session at one turn, as the provider saw it.
+#[derive(Debug, Clone, Serialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct TurnSnapshot {
+    pub session_id: String,
+    pub turn: u32,
+    pub timestamp: DateTime<Utc>,
+    pub provider: String,
+    pub model: String,
+    pub system_prompt: String,
+    pub injected: Vec<InjectedContext>,
+    #[schema(value_type = Vec<Object>)]
+    pub tools: Vec<Tool>,
+    pub messages: Vec<Message>,
+    /// Tokens per part of the request; empty when no budget was computed
+    pub context: Vec<ComponentTokens>,
+    /// Summed over the provider calls of the turn; absent when the call
+    /// failed before reporting usage
+    pub usage: Option<TurnUsage>,
+}
+
+/// How much of the context window one component took up over a session.
+#[derive(Debug, Clone, PartialEq, Serialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct ComponentAttribution {
+    pub component: String,
+    /// Turns the component was part of
+    pub turns: u32,
+    pub total_tokens: u64,
+    pub peak_tokens: u64,
+    pub mean_tokens: f64,
+    /// Share of all tokens sent over the session
+    pub share: f64,
+}
+
+#[derive(Debug, Clone, Serialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct TurnContext {
+    pub turn: u32,
+    pub components: Vec<ComponentTokens>,
+}
+
+/// Where a session's context window went, per component and per turn.
+#[derive(Debug, Clone, Serialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct ContextAttribution {
+    pub session_id: String,
+    /// Components by total tokens, largest first
+    pub components: Vec<ComponentAttribution>,
+    /// The per-turn breakdown, for a heatmap
+    pub turns: Vec<TurnContext>,
+}
+
+/// The last request written per session file, to write the next as a delta
+#[derive(Clone)]
+struct LastRequest {
+    turn: u32,
+    prompt: PromptRecord,
+    tools: Vec<Tool>,
+    messages: Vec<Message>,
+}
+
+static LAST_REQUESTS: LazyLock<Mutex<HashMap<PathBuf, LastRequest>>> =
+    LazyLock::new(Default::default);
+
+#[derive(Debug, Clone)]
+pub struct TurnSnapshotStore {
+    root: PathBuf,
+}
+
+impl TurnSnapshotStore {
+    pub fn new(root: PathBuf) -> Self {
+        Self { root }
+    }
+
+    fn path(&self, session_id: &str) -> Result<PathBuf> {
+        check_component(session_id, "session id")?;
+        Ok(self.root.join(format!("{}.jsonl", session_id)))
+    }
+
+    fn read(&self, session_id: &str) -> Result<Vec<TurnRecord>> {
+        let path = self.path(session_id)?;
+        if !path.exists() {
+            return Ok(Vec::new());
+        }
+        let mut records = Vec::new();
+        for line in fs::read_to_string(&path)?.lines() {
+            match at_rest::open(line.to_string()).and_then(|json| Ok(serde_json::from_str(&json)?))
+            {
+                Ok(record) => records.push(record),
+                // A line cut short by a crash; later turns are still usable
+                Err(e) => warn!("Skipping turn snapshot record in {:?}: {}", path, e),
+            }
+        }
+        Ok(records)
+    }
+
+    fn append(&self, session_id: &str, record: &TurnRecord) -> Result<()> {
+        let line = at_rest::seal(serde_json::to_string(record)?)?;
+        fs::create_dir_all(&self.root)?;
+        let mut file = fs::OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(self.path(session_id)?)?;
+        writeln!(file, "{}", line)?;
+        Ok(())
+    }
+
+    /// Records the request of a new turn and returns its number.
+    pub fn record_request(&self, session_id: &str, request: TurnRequest<'_>) -> Result<u32> {
+        let path = self.path(session_id)?;
+        let mut last_requests = LAST_REQUESTS.lock().unwrap();
+        let last = match last_requests.get(&path) {
+            Some(last) => Some(last.clone()),
+            None => self.rebuild(session_id)?.pop().map(|s| LastRequest {
+                turn: s.turn,
+                prompt: PromptRecord {
+                    system_prompt: s.system_prompt,
+                    injected: s.injected,
+                },
+                tools: s.tools,
+                messages: s.messages,
+            }),
+        };
+
+        let prompt = PromptRecord {
+            system_prompt: request.system_prompt.to_string(),
+            injected: request
+                .injected
+                .iter()
+                .map(|block| InjectedContext {
+                    kind: block.priority,
+                    source: block.source.to_string(),
+                    text: block.text.clone(),
+                })
+                .collect(),
+        };
+        let turn = last.as_ref().map_or(0, |last| last.turn) + 1;
+        let kept = last.as_ref().map_or(0, |last| {
+            last.messages
+                .iter()
+                .zip(request.messages)
+                .take_while(|(a, b)| a == b)
+                .count()
+        });
+        let record = TurnRecord::Request {
+            turn,
+            timestamp: Utc::now(),
+            provider: request.provider,
+            model: request.model,
+            prompt: (last.as_ref().map(|last| &last.prompt) != Some(&prompt))
+                .then(|| prompt.clone()),
+            tools: (!last
+                .as_ref()
+                .is_some_and(|last| same_tools(&last.tools, request.tools)))
+            .then(|| request.tools.to_vec()),
+            kept,
+            messages: request.messages[kept..].to_vec(),
+            context: request
+                .context
+                .map(ContextUsage::components)
+                .unwrap_or_default(),
+        };
+        self.append(session_id, &record)?;
+
+        last_requests.insert(
+            path,
+            LastRequest {
+                turn,
+                prompt,
+                tools: request.tools.to_vec(),
+                messages: request.messages.to_vec(),
+            },
+        );
+        Ok(turn)
+    }
+
+    pub fn record_usage(&self, session_id: &str, turn: u32, usage: &ProviderUsage) -> Result<()> {
+        let input_tokens = usage.usage.input_tokens.unwrap_or(0).max(0) as u64;
+        let output_tokens = usage.usage.output_tokens.unwrap_or(0).max(0) as u64;
+        let cached_tokens = usage.usage.cache_read_input_tokens.unwrap_or(0).max(0) as u64;
+        let cost_usd = CostTracker::new().calculate_cost(
+            &TokenUsage::with_cache(input_tokens, output_tokens, cached_tokens),
+            &usage.model,
+        );
+        self.append(
+            session_id,
+            &TurnRecord::Usage {
+                turn,
+                model: usage.model.clone(),
+                input_tokens,
+                output_tokens,
+                cached_tokens,
+                cost_usd,
+            },
+        )
+    }
+
+    /// Every recorded turn of a session, in order.
+    fn rebuild(&self, session_id: &str) -> Result<Vec<TurnSnapshot>> {
+        let mut snapshots: Vec<TurnSnapshot> = Vec::new();
+        for record in self.read(session_id)? {
+            match record {
+                TurnRecord::Request {
+                    turn,
+                    timestamp,
+                    provider,
+                    model,
+                    prompt,
+                    tools,
+                    kept,
+                    messages,
+                    context,
+                } => {
+                    let previous = snapshots.last();
+                    let prompt = prompt.or_else(|| {
+                        previous.map(|p| PromptRecord {
+                            system_prompt: p.system_prompt.clone(),
+                            injected: p.injected.clone(),
+                        })
+                    });
+                    let mut slice: Vec<Message> = previous
+                        .map(|p| p.messages.iter().take(kept).cloned().collect())
+                        .unwrap_or_default();
+                    slice.extend(messages);
+                    let prompt = prompt.unwrap_or_else(|| PromptRecord {
+                        system_prompt: String::new(),
+                        injected: Vec::new(),
+                    });
+                    let tools = tools
+                        .or_else(|| previous.map(|p| p.tools.clone()))
+                        .unwrap_or_default();
+                    snapshots.push(TurnSnapshot {
+                        session_id: session_id.to_string(),
+                        turn,
+                        timestamp,
+                        provider,
+                        model,
+                        system_prompt: prompt.system_prompt,
+                        injected: prompt.injected,
+                        tools,
+                        messages: slice,
+                        context,
+                        usage: None,
+                    });
+                }
+                TurnRecord::Usage {
+                    turn,
+                    model,
+                    input_tokens,
+                    output_tokens,
+                    cached_tokens,
+                    cost_usd,
+                } => {
+                    let Some(snapshot) = snapshots.iter_mut().rev().find(|s| s.turn == turn) else {
+                        continue;
+                    };
+                    let usage = snapshot.usage.get_or_insert_with(TurnUsage::default);
+                    usage.model = model;
+                    usage.input_tokens += input_tokens;
+                    usage.output_tokens += output_tokens;
+                    usage.cached_tokens += cached_tokens;
+                    usage.cost_usd += cost_usd;
+                }
+            }
+        }
+        Ok(snapshots)
+    }
+
+    /// Recorded turns of a session, oldest first.
+    pub fn list(&self, session_id: &str) -> Result<Vec<TurnSummary>> {
+        Ok(self
+            .rebuild(session_id)?
+            .into_iter()
+            .map(|s| TurnSummary {
+                turn: s.turn,
+                timestamp: s.timestamp,
+                model: s.model,
+                message_count: s.messages.len(),
+                usage: s.usage,
+            })
+            .collect())
+    }
+
+    pub fn get(&self, session_id: &str, turn: u32) -> Result<Option<TurnSnapshot>> {
+        Ok(self
+            .rebuild(session_id)?
+            .into_iter()
+            .find(|s| s.turn == turn))
+    }
+
+    /// Tokens per component over the recorded turns of a session.
+    pub fn context_attribution(&self, session_id: &str) -> Result<ContextAttribution> {
+        let turns: Vec<TurnContext> = self
+            .rebuild(session_id)?
+            .into_iter()
+            .filter(|s| !s.context.is_empty())
+            .map(|s| TurnContext {
+                turn: s.turn,
+                components: s.context,
+            })
+            .collect();
+
+        let mut components: Vec<ComponentAttribution> = Vec::new();
+        for component in turns.iter().flat_map(|t| &t.components) {
+            let tokens = component.tokens as u64;
+            match components
+                .iter_mut()
+                .find(|c| c.component == component.component)
+            {
+                Some(existing) => {
+                    existing.turns += 1;
+                    existing.total_tokens += tokens;
+                    existing.peak_tokens = existing.peak_tokens.max(tokens);
+                }
+                None => components.push(ComponentAttribution {
+                    component: component.component.clone(),
+                    turns: 1,
+                    total_tokens: tokens,
+                    peak_tokens: tokens,
+                    mean_tokens: 0.0,
+                    share: 0.0,
+                }),
+            }
+        }
+        let total: u64 = components.iter().map(|c| c.total_tokens).sum();
+        for component in &mut components {
+            component.mean_tokens = component.total_tokens as f64 / component.turns as f64;
+            component.share = if total == 0 {
+                0.0
+            } else {
+                component.total_tokens as f64 / total as f64
+            };
+        }
+        components.sort_by(|a, b| b.total_tokens.cmp(&a.total_tokens));
+
+        Ok(ContextAttribution {
+            session_id: session_id.to_string(),
+            components,
+            turns,
+        })
+    }
+
+    /// Drops every turn of a session
+    pub fn remove_session(&self, session_id: &str) -> Result<()> {
+        let path = self.path(session_id)?;
+        LAST_REQUESTS.lock().unwrap().remove(&path);
+        if path.exists() {
+            fs::remove_file(path)?;
+        }
+        Ok(())
+    }
+}
+
+/// Tools compare by their serialized form, which is what the provider sees
+fn same_tools(a: &[Tool], b: &[Tool]) -> bool {
+    a.len() == b.len() && serde_json::to_value(a).ok() == serde_json::to_value(b).ok()
+}
+
+fn enabled() -> bool {
+    Config::global()
+        .get_param::<bool>(TURN_SNAPSHOTS_KEY)
+        .unwrap_or(false)
+}
+
+/// Records a turn's request unless recording is off. A failure is logged
+/// rather than failing the turn.
+pub fn snapshot_request(
+    store: &TurnSnapshotStore,
+    session_id: &str,
+    request: TurnRequest<'_>,
+) -> Option<u32> {
+    if !enabled() {
+        return None;
+    }
+    store
+        .record_request(session_id, request)
+        .map_err(|e| warn!("Failed to record turn snapshot: {}", e))
+        .ok()
+}
+
+/// Records usage reported for a turn recorded by [`snapshot_request`].
+pub fn snapshot_usage(
+    store: &TurnSnapshotStore,
+    session_id: &str,
+    turn: Option<u32>,
+    usage: &ProviderUsage,
+) {
+    if let Some(turn) = turn {
+        if let Err(e) = store.record_usage(session_id, turn, usage) {
+            warn!("Failed to record turn usage: {}", e);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::providers::base::Usage;
+
+    fn request<'a>(
+        messages: &'a [Message],
+        system_prompt: &'a str,
+        injected: &'a [InjectedBlock],
+    ) -> TurnRequest<'a> {
+        TurnRequest {
+            provider: "openai".into(),
+            model: "gpt-4o".into(),
+            system_prompt,
+            injected,
+            tools: &[],
+            messages,
+            context: None,
+        }
+    }
+
+    #[test]
+    fn test_turns_rebuild_from_deltas() {
+        let dir = tempfile::tempdir().unwrap();
+        let store = TurnSnapshotStore::new(dir.path().to_path_buf());
+        let mut messages = vec![Message::user().with_text("fix the build")];
+        let injected = [InjectedBlock::new(BlockPriority::Memories, "uses cargo")];
+
+        assert_eq!(
+            store
+                .record_request("s1", request(&messages, "You are goose", &injected))
+                .unwrap(),
+            1
+        );
+        let usage = ProviderUsage::new("gpt-4o".into(), Usage::new(Some(100), Some(20), Some(120)));
+        store.record_usage("s1", 1, &usage).unwrap();
+        messages.push(Message::assistant().with_text("running cargo build"));
+        store
+            .record_request("s1", request(&messages, "You are goose", &injected))
+            .unwrap();
+        messages.push(Message::user().with_text("now test"));
+        store
+            .record_request("s1", request(&messages, "You are goose, again", &injected))
+            .unwrap();
+
+        // Forget the in-memory state, as after a restart
+        LAST_REQUESTS.lock().unwrap().clear();
+        assert_eq!(
+            store
+                .record_request("s1", request(&messages, "You are goose, again", &injected))
+                .unwrap(),
+            4
+        );
+
+        let turns = store.list("s1").unwrap();
+        assert_eq!(turns.len(), 4);
+        assert_eq!(turns[0].usage.as_ref().unwrap().input_tokens, 100);
+        assert!(turns[1].usage.is_none());
+
+        let second = store.get("s1", 2).unwrap().unwrap();
+        assert_eq!(second.messages, messages[..2]);
+        assert_eq!(second.system_prompt, "You are goose");
+        assert_eq!(second.injected[0].text, "uses cargo");
+        let fourth = store.get("s1", 4).unwrap().unwrap();
+        assert_eq!(fourth.messages, messages);
+        assert_eq!(fourth.system_prompt, "You are goose, again");
+        assert!(store.get("s1", 5).unwrap().is_none());
+
+        store.remove_session("s1").unwrap();
+        assert!(store.list("s1").unwrap().is_empty());
+        assert!(store.list("../escape").is_err());
+    }
+
+    #[test]
+    fn test_context_attribution() {
+        let dir = tempfile::tempdir().unwrap();
+        let store = TurnSnapshotStore::new(dir.path().to_path_buf());
+        let messages = vec![Message::user().with_text("hi")];
+        for conversation in [100, 200] {
+            let usage = ContextUsage {
+                system_prompt: 100,
+                conversation,
+                breakdown: vec![ComponentTokens {
+                    component: "memories".into(),
+                    tokens: 50,
+                }],
+                ..ContextUsage::default()
+            };
+            let mut request = request(&messages, "You are goose", &[]);
+            request.context = Some(&usage);
+            store.record_request("s1", request).unwrap();
+        }
+
+        let attribution = store.context_attribution("s1").unwrap();
+        assert_eq!(attribution.turns.len(), 2);
+        let conversation = &attribution.components[0];
+        assert_eq!(conversation.component, "conversation");
+        assert_eq!(conversation.total_tokens, 300);
+        assert_eq!(conversation.peak_tokens, 200);
+        assert_eq!(conversation.mean_tokens, 150.0);
+        assert_eq!(conversation.share, 0.5);
+        assert_eq!(attribution.components.last().unwrap().component, "moim");
+    }
+}
@@ -0,0 +1,430 @@
+//! Multi-root workspaces.
+//!
+//! A session's working_dir is its primary root. A session may also declare
+//! named roots, e.g. one per repository, which are kept in its extension
+//! data. When a session has more than one root, every extension tool gains a
+//! `root` argument. A call runs with that root as its working directory, so
+//! relative paths and shell commands, git included, resolve there.
+//!
+//! A root can be marked read-only. A tool not annotated read-only is then
+//! refused in that root, whether it is named with `root` or reached through
+//! a path argument. Path arguments are resolved against the call's root
+//! with `..` folded and symlinks followed before they are checked. Shell
+//! commands can write whatever their annotations say, so a shell call in a
+//! read-only root, or naming a path in one, is refused as a write.
+
+use super::extension_data::ExtensionState;
+use super::Session;
+use rmcp::model::{JsonObject, Tool};
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use std::collections::HashSet;
+use std::path::{Component, Path, PathBuf};
+use std::sync::Arc;
+use utoipa::ToSchema;
+
+pub const ROOT_ARGUMENT: &str = "root";
+/// Root name used for a session that declared none
+pub const PRIMARY_ROOT: &str = "main";
+/// Arguments checked for paths into read-only roots
+const PATH_ARGUMENTS: &[&str] = &[
+    "path",
+    "paths",
+    "file_path",
+    "directory",
+    "dir",
+    "cwd",
+    "working_dir",
+    "target",
+];
+
+/// Argument holding a shell command line
+const COMMAND_ARGUMENT: &str = "command";
+
+fn default_writable() -> bool {
+    true
+}
+
+/// `path` resolved against `base`: `~` expanded and symlinks followed as far
+/// as the path exists. Each existing component is canonicalized before the
+/// next is applied, so a `..` after a symlink steps out of the link's target
+/// as the OS would, rather than undoing the link.
+fn resolve_path(base: &Path, path: &str) -> PathBuf {
+    let mut resolved = PathBuf::new();
+    let mut exists = true;
+    for component in base.join(&*shellexpand::tilde(path)).components() {
+        match component {
+            Component::CurDir => {}
+            Component::ParentDir => {
+                resolved.pop();
+            }
+            other => {
+                resolved.push(other);
+                if exists {
+                    match resolved.canonicalize() {
+                        Ok(canonical) => resolved = canonical,
+                        Err(_) => exists = false,
+                    }
+                }
+            }
+        }
+    }
+    resolved
+}
+
+/// Words of a shell command that look like paths
+fn command_paths(command: &str) -> Vec<&str> {
+    command
+        .split(|c: char| c.is_whitespace() || ";|&<>()`'\"=".contains(c))
+        .filter(|word| word.contains('/') || word.starts_with('.') || word.starts_with('~'))
+        .collect()
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
+pub struct WorkspaceRoot {
+    /// Short name tools refer to the root by, e.g. "api"
+    pub name: String,
+    #[schema(value_type = String)]
+    pub path: PathBuf,
+    /// Whether tools that are not read-only may act in this root
+    #[serde(default = "default_writable")]
+    pub writable: bool,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct WorkspaceRoots {
+    pub roots: Vec<WorkspaceRoot>,
+}
+
+impl ExtensionState for WorkspaceRoots {
+    const EXTENSION_NAME: &'static str = "workspace_roots";
+    const VERSION: &'static str = "v0";
+}
+
+#[derive(Debug, thiserror::Error, PartialEq, Eq)]
+pub enum WorkspaceError {
+    #[error("At least one workspace root is required")]
+    Empty,
+    #[error("Invalid root name '{0}'; use letters, digits, '-' or '_'")]
+    InvalidName(String),
+    #[error("Root name '{0}' is used more than once")]
+    DuplicateName(String),
+    #[error("Root '{name}' is not an existing directory: {path}")]
+    NotADirectory { name: String, path: String },
+    #[error("Unknown root '{name}'; this session has: {available}")]
+    UnknownRoot { name: String, available: String },
+    #[error("Root '{root}' is read-only and {tool} may modify files")]
+    ReadOnly { root: String, tool: String },
+}
+
+impl WorkspaceRoots {
+    /// Validates the roots and canonicalizes their paths. The first root is
+    /// the primary one.
+    pub fn new(roots: Vec<WorkspaceRoot>) -> Result<Self, WorkspaceError> {
+        if roots.is_empty() {
+            return Err(WorkspaceError::Empty);
+        }
+        let mut names = HashSet::new();
+        let mut validated = Vec::with_capacity(roots.len());
+        for root in roots {
+            let name = root.name.trim().to_string();
+            if name.is_empty()
+                || !name
+                    .chars()
+                    .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_')
+            {
+                return Err(WorkspaceError::InvalidName(root.name));
+            }
+            if !names.insert(name.clone()) {
+                return Err(WorkspaceError::DuplicateName(name));
+            }
+            let path = root
+                .path
+                .canonicalize()
+                .ok()
+                .filter(|p| p.is_dir())
+                .ok_or_else(|| WorkspaceError::NotADirectory {
+                    name: name.clone(),
+                    path: root.path.display().to_string(),
+                })?;
+            validated.push(WorkspaceRoot {
+                name,
+                path,
+                writable: root.writable,
+            });
+        }
+        Ok(Self { roots: validated })
+    }
+
+    /// The session's declared roots, or its working_dir as the only root.
+    pub fn for_session(session: &Session) -> Self {
+        Self::from_extension_data(&session.extension_data)
+            .filter(|declared| !declared.roots.is_empty())
+            .unwrap_or_else(|| Self {
+                roots: vec![WorkspaceRoot {
+                    name: PRIMARY_ROOT.to_string(),
+                    path: session.working_dir.clone(),
+                    writable: true,
+                }],
+            })
+    }
+
+    pub fn is_multi_root(&self) -> bool {
+        self.roots.len() > 1
+    }
+
+    pub fn primary(&self) -> Option<&WorkspaceRoot> {
+        self.roots.first()
+    }
+
+    pub fn paths(&self) -> Vec<&Path> {
+        self.roots.iter().map(|r| r.path.as_path()).collect()
+    }
+
+    fn names(&self) -> String {
+        self.roots
+            .iter()
+            .map(|r| r.name.as_str())
+            .collect::<Vec<_>>()
+            .join(", ")
+    }
+
+    pub fn get(&self, name: &str) -> Result<&WorkspaceRoot, WorkspaceError> {
+        self.roots
+            .iter()
+            .find(|r| r.name == name)
+            .ok_or_else(|| WorkspaceError::UnknownRoot {
+                name: name.to_string(),
+                available: self.names(),
+            })
+    }
+
+    /// The innermost root holding `path`, for nested roots
+    pub fn containing(&self, path: &Path) -> Option<&WorkspaceRoot> {
+        self.roots
+            .iter()
+            .filter(|r| path.starts_with(&r.path))
+            .max_by_key(|r| r.path.components().count())
+    }
+
+    /// Adds the `root` argument to an extension tool's schema.
+    pub fn add_root_parameter(&self, tool: &mut Tool) {
+        let mut schema = (*tool.input_schema).clone();
+        let properties = schema
+            .entry("properties")
+            .or_insert_with(|| Value::Object(JsonObject::new()));
+        if let Value::Object(properties) = properties {
+            properties.insert(
+                ROOT_ARGUMENT.to_string(),
+                json!({
+                    "type": "string",
+                    "enum": self.roots.iter().map(|r| r.name.as_str()).collect::<Vec<_>>(),
+                    "description": "Workspace root to run in; relative paths resolve against it. Defaults to the primary root."
+                }),
+            );
+        }
+        tool.input_schema = Arc::new(schema);
+    }
+
+    /// Takes the `root` argument out of a tool call and returns the
+    /// directory the call should run in. A call that may write is refused
+    /// when its root, or a root one of its path arguments resolves into, is
+    /// read-only. Shell calls are always treated as writes.
+    pub fn scope_call(
+        &self,
+        tool_name: &str,
+        arguments: &mut Option<JsonObject>,
+        may_write: bool,
+    ) -> Result<PathBuf, WorkspaceError> {
+        let requested = arguments
+            .as_mut()
+            .and_then(|args| args.remove(ROOT_ARGUMENT))
+            .and_then(|v| v.as_str().map(str::to_string));
+        let root = match requested {
+            Some(name) => self.get(&name)?,
+            None => self.primary().ok_or(WorkspaceError::Empty)?,
+        };
+        let command = arguments
+            .as_ref()
+            .and_then(|args| args.get(COMMAND_ARGUMENT))
+            .and_then(Value::as_str);
+        let is_shell = tool_name == "shell" || tool_name.ends_with("__shell");
+        if !may_write && !is_shell {
+            return Ok(root.path.clone());
+        }
+
+        let read_only = |root: &WorkspaceRoot| WorkspaceError::ReadOnly {
+            root: root.name.clone(),
+            tool: tool_name.to_string(),
+        };
+        if !root.writable {
+            return Err(read_only(root));
+        }
+        let mut paths: Vec<&str> = arguments
+            .iter()
+            .flat_map(|args| PATH_ARGUMENTS.iter().filter_map(|key| args.get(*key)))
+            .flat_map(|value| match value {
+                Value::String(s) => vec![s.as_str()],
+                Value::Array(items) => items.iter().filter_map(Value::as_str).collect(),
+                _ => Vec::new(),
+            })
+            .collect();
+        if is_shell {
+            paths.extend(command.map(command_paths).unwrap_or_default());
+        }
+        for path in paths {
+            let resolved = resolve_path(&root.path, path);
+            if let Some(target) = self.containing(&resolved).filter(|r| !r.writable) {
+                return Err(read_only(target));
+            }
+        }
+        Ok(root.path.clone())
+    }
+
+    /// Describes the roots for the system prompt when there are several
+    pub fn prompt_context(&self) -> Option<String> {
+        if !self.is_multi_root() {
+            return None;
+        }
+        let primary = self.primary()?;
+        let listing: Vec<String> = self
+            .roots
+            .iter()
+            .map(|r| {
+                format!(
+                    "- {}: {}{}",
+                    r.name,
+                    r.path.display(),
+                    if r.writable { "" } else { " (read-only)" }
+                )
+            })
+            .collect();
+        Some(format!(
+            "[WORKSPACE ROOTS]: This session spans several roots. Pass `root` to a tool to run it \
+             in another root; without it tools run in '{}'. Run git commands per root.\n{}",
+            primary.name,
+            listing.join("\n")
+        ))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn roots(dir: &Path) -> WorkspaceRoots {
+        std::fs::create_dir_all(dir.join("api")).unwrap();
+        std::fs::create_dir_all(dir.join("vendor")).unwrap();
+        WorkspaceRoots::new(vec![
+            WorkspaceRoot {
+                name: "api".to_string(),
+                path: dir.join("api"),
+                writable: true,
+            },
+            WorkspaceRoot {
+                name: "vendor".to_string(),
+                path: dir.join("vendor"),
+                writable: false,
+            },
+        ])
+        .unwrap()
+    }
+
+    #[test]
+    fn test_scope_call_resolves_root_and_enforces_read_only() {
+        let dir = tempfile::tempdir().unwrap();
+        let roots = roots(dir.path());
+        let vendor = roots.get("vendor").unwrap().path.clone();
+
+        let mut args =
+            Some(serde_json::from_value(json!({"root": "vendor", "path": "src"})).unwrap());
+        assert_eq!(
+            roots.scope_call("developer__list_files", &mut args, false),
+            Ok(vendor.clone())
+        );
+        assert!(!args.unwrap().contains_key(ROOT_ARGUMENT));
+
+        let mut args = Some(serde_json::from_value(json!({"root": "vendor"})).unwrap());
+        assert!(matches!(
+            roots.scope_call("developer__shell", &mut args, true),
+            Err(WorkspaceError::ReadOnly { .. })
+        ));
+
+        let target = vendor.join("lib.rs").display().to_string();
+        let mut args = Some(serde_json::from_value(json!({"path": target})).unwrap());
+        assert!(matches!(
+            roots.scope_call("developer__text_editor", &mut args, true),
+            Err(WorkspaceError::ReadOnly { .. })
+        ));
+
+        // Relative and unnormalized paths resolve before the check
+        let api = roots.get("api").unwrap().path.clone();
+        let escape = format!("{}/../vendor/lib.rs", api.display());
+        for path in ["../vendor/lib.rs", escape.as_str()] {
+            let mut args = Some(serde_json::from_value(json!({"path": path})).unwrap());
+            assert!(matches!(
+                roots.scope_call("developer__text_editor", &mut args, true),
+                Err(WorkspaceError::ReadOnly { .. })
+            ));
+        }
+        let mut args = Some(serde_json::from_value(json!({"path": "src/new.rs"})).unwrap());
+        assert_eq!(
+            roots.scope_call("developer__text_editor", &mut args, true),
+            Ok(api)
+        );
+
+        // Shell calls count as writes even when annotated read-only
+        let mut args =
+            Some(serde_json::from_value(json!({"root": "vendor", "command": "ls"})).unwrap());
+        assert!(roots
+            .scope_call("developer__shell", &mut args, false)
+            .is_err());
+        let mut args =
+            Some(serde_json::from_value(json!({"command": "rm -rf ../vendor/src"})).unwrap());
+        assert!(roots
+            .scope_call("developer__shell", &mut args, false)
+            .is_err());
+
+        let mut args = Some(serde_json::from_value(json!({"root": "web"})).unwrap());
+        assert!(matches!(
+            roots.scope_call("developer__shell", &mut args, false),
+            Err(WorkspaceError::UnknownRoot { .. })
+        ));
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_parent_of_a_symlink_is_taken_in_its_target() {
+        let dir = tempfile::tempdir().unwrap();
+        let roots = roots(dir.path());
+        let api = roots.get("api").unwrap().path.clone();
+        let vendor = roots.get("vendor").unwrap().path.clone();
+        std::fs::create_dir_all(vendor.join("src")).unwrap();
+        std::os::unix::fs::symlink(vendor.join("src"), api.join("link")).unwrap();
+
+        let mut args = Some(serde_json::from_value(json!({"path": "link/../lib.rs"})).unwrap());
+        assert!(matches!(
+            roots.scope_call("developer__text_editor", &mut args, true),
+            Err(WorkspaceError::ReadOnly { .. })
+        ));
+    }
+
+    #[test]
+    fn test_roots_are_validated() {
+        let dir = tempfile::tempdir().unwrap();
+        let root = |name: &str| WorkspaceRoot {
+            name: name.to_string(),
+            path: dir.path().to_path_buf(),
+            writable: true,
+        };
+        assert_eq!(
+            WorkspaceRoots::new(vec![root("a"), root("a")]),
+            Err(WorkspaceError::DuplicateName("a".to_string()))
+        );
+        assert!(matches!(
+            WorkspaceRoots::new(vec![root("has space")]),
+            Err(WorkspaceError::InvalidName(_))
+        ));
+        assert_eq!(WorkspaceRoots::new(vec![]), Err(WorkspaceError::Empty));
+    }
+}
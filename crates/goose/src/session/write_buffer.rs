@@ -0,0 +1,185 @@
+//! Write-behind buffering of session messages.
+//!
+//! The reply loop queues its messages here instead of writing each one to
+//! the database. They are written in one transaction per session when the
+//! reply ends, at checkpoints, on shutdown, once [`MAX_PENDING_MESSAGES`]
+//! pile up, or before anything else reads or changes the session's
+//! messages, so readers never see the buffer.
+//!
+//! Every queued message is first appended to a log file next to the
+//! database. The log is removed once its messages are committed, and logs
+//! left behind by a crash are replayed when the database is next opened.
+//! Messages get their ids when queued, so a log whose messages were already
+//! committed is replayed without duplicates.
+
+use crate::conversation::message::Message;
+use crate::security::at_rest;
+use anyhow::Result;
+use std::collections::HashMap;
+use std::fs;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use tokio::sync::{Mutex, MutexGuard};
+use tracing::warn;
+
+use super::attachments::check_component;
+
+/// Folder next to the database where logs of queued messages are kept
+pub const PENDING_FOLDER: &str = "pending";
+/// Queued messages per session that force a write
+pub const MAX_PENDING_MESSAGES: usize = 64;
+
+const LOG_EXTENSION: &str = "jsonl";
+
+pub struct WriteBuffer {
+    dir: PathBuf,
+    queued: Mutex<HashMap<String, Vec<Message>>>,
+}
+
+/// Held while queued messages are written so none are queued in between.
+pub struct PendingMessages<'a> {
+    dir: &'a Path,
+    queued: MutexGuard<'a, HashMap<String, Vec<Message>>>,
+}
+
+impl WriteBuffer {
+    pub fn new(dir: PathBuf) -> Self {
+        Self {
+            dir,
+            queued: Mutex::new(HashMap::new()),
+        }
+    }
+
+    fn log_path(dir: &Path, session_id: &str) -> Result<PathBuf> {
+        check_component(session_id, "session id")?;
+        Ok(dir.join(format!("{}.{}", session_id, LOG_EXTENSION)))
+    }
+
+    /// Logs and queues `message`, returning how many the session has queued.
+    pub async fn push(&self, session_id: &str, message: &Message) -> Result<usize> {
+        let mut message = message.clone();
+        if message.id.is_none() {
+            message.id = Some(format!("msg_{}_{}", session_id, uuid::Uuid::new_v4()));
+        }
+        let line = at_rest::seal(serde_json::to_string(&message)?)?;
+
+        let mut queued = self.queued.lock().await;
+        fs::create_dir_all(&self.dir)?;
+        let mut log = fs::OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(Self::log_path(&self.dir, session_id)?)?;
+        writeln!(log, "{}", line)?;
+
+        let pending = queued.entry(session_id.to_string()).or_default();
+        pending.push(message);
+        Ok(pending.len())
+    }
+
+    pub async fn lock(&self) -> PendingMessages<'_> {
+        PendingMessages {
+            dir: &self.dir,
+            queued: self.queued.lock().await,
+        }
+    }
+
+    /// Messages logged by a process that stopped before writing them.
+    pub fn recover(&self) -> Result<Vec<(String, Vec<Message>)>> {
+        let entries = match fs::read_dir(&self.dir) {
+            Ok(entries) => entries,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(Vec::new()),
+            Err(e) => return Err(e.into()),
+        };
+
+        let mut recovered = Vec::new();
+        for path in entries.filter_map(|e| e.ok()).map(|e| e.path()) {
+            if path.extension().and_then(|e| e.to_str()) != Some(LOG_EXTENSION) {
+                continue;
+            }
+            let Some(session_id) = path.file_stem().and_then(|s| s.to_str()) else {
+                continue;
+            };
+            let mut messages = Vec::new();
+            for line in fs::read_to_string(&path)?.lines() {
+                // A crash mid-append leaves a partial last line
+                match at_rest::open(line.to_string())
+                    .and_then(|json| Ok(serde_json::from_str::<Message>(&json)?))
+                {
+                    Ok(message) => messages.push(message),
+                    Err(e) => warn!("Skipping unreadable pending message in {:?}: {}", path, e),
+                }
+            }
+            recovered.push((session_id.to_string(), messages));
+        }
+        Ok(recovered)
+    }
+}
+
+impl PendingMessages<'_> {
+    pub fn messages(&self, session_id: &str) -> &[Message] {
+        self.queued
+            .get(session_id)
+            .map(Vec::as_slice)
+            .unwrap_or(&[])
+    }
+
+    pub fn session_ids(&self) -> Vec<String> {
+        self.queued.keys().cloned().collect()
+    }
+
+    /// Drops the session's queued messages and their log, once they are
+    /// written or no longer wanted. Also removes replayed logs.
+    pub fn clear(&mut self, session_id: &str) -> Result<()> {
+        self.queued.remove(session_id);
+        match fs::remove_file(WriteBuffer::log_path(self.dir, session_id)?) {
+            Err(e) if e.kind() != std::io::ErrorKind::NotFound => Err(e.into()),
+            _ => Ok(()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_queued_messages_are_logged_until_cleared() {
+        let dir = tempfile::tempdir().unwrap();
+        let buffer = WriteBuffer::new(dir.path().join(PENDING_FOLDER));
+
+        buffer
+            .push("s1", &Message::user().with_text("first"))
+            .await
+            .unwrap();
+        let count = buffer
+            .push("s1", &Message::assistant().with_text("second"))
+            .await
+            .unwrap();
+        assert_eq!(count, 2);
+        assert!(buffer
+            .push("../escape", &Message::user().with_text("x"))
+            .await
+            .is_err());
+
+        // A restarted process finds the same messages, ids included
+        let queued_ids: Vec<_> = {
+            let pending = buffer.lock().await;
+            pending
+                .messages("s1")
+                .iter()
+                .map(|m| m.id.clone())
+                .collect()
+        };
+        let restarted = WriteBuffer::new(dir.path().join(PENDING_FOLDER));
+        let recovered = restarted.recover().unwrap();
+        assert_eq!(recovered.len(), 1);
+        assert_eq!(recovered[0].0, "s1");
+        let recovered_ids: Vec<_> = recovered[0].1.iter().map(|m| m.id.clone()).collect();
+        assert_eq!(recovered_ids, queued_ids);
+        assert!(queued_ids.iter().all(Option::is_some));
+
+        buffer.lock().await.clear("s1").unwrap();
+        assert!(buffer.lock().await.messages("s1").is_empty());
+        assert!(restarted.recover().unwrap().is_empty());
+    }
+}
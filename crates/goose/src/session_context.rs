@@ -2,6 +2,8 @@ use tokio::task_local;
 
 pub const SESSION_ID_HEADER: &str = "agent-session-id";
 pub const WORKING_DIR_HEADER: &str = "agent-working-dir";
+/// Session environment forwarded to in-process builtin extensions
+pub const ENV_HEADER: &str = "agent-env";
 
 task_local! {
     pub static SESSION_ID: Option<String>;
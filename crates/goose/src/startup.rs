@@ -0,0 +1,194 @@
+//! Where startup time goes, and fast start.
+//!
+//! goosed times each subsystem it brings up before it listens, and the
+//! agent times the subsystems it initializes on its first reply (checkpoints,
+//! memory, embeddings, guardrails). [`profile`] reports both, with how long
+//! the server took to listen and to get its first reply ready for the model.
+//! Mem0's health check never holds up a reply; it is timed in the background.
+//!
+//! Fast start (`goosed agent --fast-start`, or `GOOSE_FAST_START: true`)
+//! defers everything optional: session maintenance and the retention job
+//! wait for the first reply, and replies use hash embeddings until the
+//! embedding model has loaded in the background.
+
+use crate::config::Config;
+use serde::Serialize;
+use std::future::Future;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{LazyLock, Mutex};
+use std::time::{Duration, Instant};
+use tokio::sync::watch;
+use tracing::info;
+use utoipa::ToSchema;
+
+pub const FAST_START_KEY: &str = "GOOSE_FAST_START";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum StartupPhase {
+    /// Before the server listens
+    Startup,
+    /// While the first reply is being prepared
+    FirstReply,
+}
+
+#[derive(Debug, Clone, Serialize, ToSchema)]
+pub struct SubsystemTiming {
+    pub name: String,
+    pub phase: StartupPhase,
+    pub millis: u64,
+}
+
+#[derive(Debug, Clone, Serialize, ToSchema)]
+pub struct StartupProfile {
+    pub fast_start: bool,
+    /// From process start until the server listened
+    pub ready_after_ms: Option<u64>,
+    /// From process start until the first reply was ready for the model
+    pub first_reply_after_ms: Option<u64>,
+    pub subsystems: Vec<SubsystemTiming>,
+    /// Work fast start put off until the first reply
+    pub deferred: Vec<String>,
+}
+
+struct Profiler {
+    started: Instant,
+    ready: Option<Duration>,
+    first_reply: Option<Duration>,
+    timings: Vec<SubsystemTiming>,
+    deferred: Vec<String>,
+}
+
+static PROFILER: LazyLock<Mutex<Profiler>> = LazyLock::new(|| {
+    Mutex::new(Profiler {
+        started: Instant::now(),
+        ready: None,
+        first_reply: None,
+        timings: Vec::new(),
+        deferred: Vec::new(),
+    })
+});
+static FAST_START: AtomicBool = AtomicBool::new(false);
+static FIRST_REPLY: LazyLock<watch::Sender<bool>> = LazyLock::new(|| watch::channel(false).0);
+
+fn profiler() -> std::sync::MutexGuard<'static, Profiler> {
+    PROFILER.lock().unwrap_or_else(|e| e.into_inner())
+}
+
+/// Starts the clock; call first thing in `main`.
+pub fn begin() {
+    LazyLock::force(&PROFILER);
+}
+
+pub fn set_fast_start(enabled: bool) {
+    FAST_START.store(enabled, Ordering::Relaxed);
+}
+
+pub fn is_fast_start() -> bool {
+    FAST_START.load(Ordering::Relaxed)
+        || Config::global()
+            .get_param::<bool>(FAST_START_KEY)
+            .unwrap_or(false)
+}
+
+/// Records how long `name` took to initialize. Only the first time counts,
+/// later calls (such as the agent's next replies) are ignored.
+pub fn record(name: &str, phase: StartupPhase, elapsed: Duration) {
+    let mut profiler = profiler();
+    if profiler.timings.iter().any(|t| t.name == name) {
+        return;
+    }
+    profiler.timings.push(SubsystemTiming {
+        name: name.to_string(),
+        phase,
+        millis: elapsed.as_millis() as u64,
+    });
+}
+
+pub async fn timed<F: Future>(name: &str, phase: StartupPhase, init: F) -> F::Output {
+    let start = Instant::now();
+    let output = init.await;
+    record(name, phase, start.elapsed());
+    output
+}
+
+/// Notes that fast start put `name` off until the first reply.
+pub fn defer(name: &str) {
+    profiler().deferred.push(name.to_string());
+}
+
+/// The server is listening; logs where the time went.
+pub fn mark_ready() {
+    let mut profiler = profiler();
+    let ready = profiler.started.elapsed();
+    profiler.ready = Some(ready);
+    let breakdown: Vec<String> = profiler
+        .timings
+        .iter()
+        .map(|t| format!("{} {}ms", t.name, t.millis))
+        .collect();
+    info!(
+        "Ready after {}ms ({})",
+        ready.as_millis(),
+        breakdown.join(", ")
+    );
+}
+
+pub fn mark_first_reply() {
+    {
+        let mut profiler = profiler();
+        if profiler.first_reply.is_some() {
+            return;
+        }
+        profiler.first_reply = Some(profiler.started.elapsed());
+    }
+    FIRST_REPLY.send_replace(true);
+}
+
+/// Resolves once the first reply is ready for the model.
+pub async fn first_reply() {
+    let mut started = FIRST_REPLY.subscribe();
+    let _ = started.wait_for(|started| *started).await;
+}
+
+pub fn profile() -> StartupProfile {
+    let profiler = profiler();
+    StartupProfile {
+        fast_start: is_fast_start(),
+        ready_after_ms: profiler.ready.map(|d| d.as_millis() as u64),
+        first_reply_after_ms: profiler.first_reply.map(|d| d.as_millis() as u64),
+        subsystems: profiler.timings.clone(),
+        deferred: profiler.deferred.clone(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_first_timing_wins_and_first_reply_releases_deferred_work() {
+        let value = timed("startup_test_subsystem", StartupPhase::FirstReply, async {
+            7
+        })
+        .await;
+        assert_eq!(value, 7);
+        record(
+            "startup_test_subsystem",
+            StartupPhase::FirstReply,
+            Duration::from_secs(60),
+        );
+        let timings: Vec<_> = profile()
+            .subsystems
+            .into_iter()
+            .filter(|t| t.name == "startup_test_subsystem")
+            .collect();
+        assert_eq!(timings.len(), 1);
+        assert!(timings[0].millis < 60_000);
+
+        let deferred = tokio::spawn(first_reply());
+        mark_first_reply();
+        deferred.await.unwrap();
+        assert!(profile().first_reply_after_ms.is_some());
+    }
+}
@@ -84,20 +84,11 @@ impl SqliteTaskPersistence {
     }
 
     pub async fn connect(&mut self) -> Result<()> {
-        use sqlx::sqlite::{SqliteConnectOptions, SqlitePoolOptions};
-        use std::str::FromStr;
-
         if let Some(parent) = self.db_path.parent() {
             tokio::fs::create_dir_all(parent).await?;
         }
 
-        let db_url = format!("sqlite://{}?mode=rwc", self.db_path.display());
-        let options = SqliteConnectOptions::from_str(&db_url)?.create_if_missing(true);
-
-        let pool = SqlitePoolOptions::new()
-            .max_connections(5)
-            .connect_with(options)
-            .await?;
+        let pool = crate::database::open("tasks", &self.db_path);
 
         self.create_tables(&pool).await?;
         self.pool = Some(pool);
@@ -140,6 +140,22 @@ impl ToolInspectionManager {
         tracing::warn!("Permission inspector not found for permission manager update");
     }
 
+    /// Denies tools outside a subagent's grant in the permission inspector
+    pub fn set_capability_grant(
+        &self,
+        grant: Option<crate::agents::capability_grant::CapabilityGrant>,
+    ) {
+        let permission_inspector = self
+            .inspectors
+            .iter()
+            .filter(|inspector| inspector.name() == "permission")
+            .find_map(|inspector| inspector.as_any().downcast_ref::<PermissionInspector>());
+        match permission_inspector {
+            Some(inspector) => inspector.set_capability_grant(grant),
+            None => tracing::warn!("Permission inspector not found for capability grant"),
+        }
+    }
+
     /// Process inspection results using the permission inspector
     /// This delegates to the permission inspector's process_inspection_results method
     pub fn process_inspection_results_with_permission_inspector(
@@ -0,0 +1,201 @@
+//! Offline speech synthesis using OS-native voices or a local Piper model.
+//!
+//! Both backends shell out to a command line tool that writes a WAV file,
+//! which is then returned as a single audio chunk.
+
+use crate::config::Config;
+use crate::subprocess::configure_subprocess;
+use crate::tts::providers::{AudioStream, SpeechSynthesizer, TtsProvider};
+use anyhow::{Context, Result};
+use async_trait::async_trait;
+use std::path::{Path, PathBuf};
+use std::process::Stdio;
+use tokio::io::AsyncWriteExt;
+use tokio::process::Command;
+
+pub const PIPER_VOICE_MODEL_CONFIG_KEY: &str = "PIPER_VOICE_MODEL";
+pub const PIPER_BINARY_CONFIG_KEY: &str = "PIPER_BINARY";
+
+const WAV_MIME_TYPE: &str = "audio/wav";
+
+#[cfg(target_os = "macos")]
+const NATIVE_VOICE_COMMANDS: &[&str] = &["say"];
+#[cfg(target_os = "windows")]
+const NATIVE_VOICE_COMMANDS: &[&str] = &["powershell"];
+#[cfg(not(any(target_os = "macos", target_os = "windows")))]
+const NATIVE_VOICE_COMMANDS: &[&str] = &["espeak-ng", "espeak"];
+
+fn find_native_command() -> Option<&'static str> {
+    NATIVE_VOICE_COMMANDS
+        .iter()
+        .copied()
+        .find(|cmd| which::which(cmd).is_ok())
+}
+
+async fn run_to_wav(
+    mut command: Command,
+    stdin_text: Option<&str>,
+    output: &Path,
+) -> Result<AudioStream> {
+    configure_subprocess(&mut command);
+    command
+        .stdin(if stdin_text.is_some() {
+            Stdio::piped()
+        } else {
+            Stdio::null()
+        })
+        .stdout(Stdio::null())
+        .stderr(Stdio::piped());
+
+    let mut child = command
+        .spawn()
+        .context("Failed to start speech synthesizer")?;
+    if let (Some(text), Some(mut stdin)) = (stdin_text, child.stdin.take()) {
+        stdin.write_all(text.as_bytes()).await?;
+    }
+
+    let result = child.wait_with_output().await?;
+    if !result.status.success() {
+        anyhow::bail!(
+            "Speech synthesis failed: {}",
+            String::from_utf8_lossy(&result.stderr).trim()
+        );
+    }
+
+    let bytes = tokio::fs::read(output)
+        .await
+        .context("Speech synthesizer produced no audio")?;
+    Ok(AudioStream::from_bytes(WAV_MIME_TYPE, bytes))
+}
+
+/// Built-in voices: `say` on macOS, System.Speech on Windows, eSpeak elsewhere.
+pub struct NativeVoice;
+
+#[async_trait]
+impl SpeechSynthesizer for NativeVoice {
+    fn provider(&self) -> TtsProvider {
+        TtsProvider::System
+    }
+
+    fn is_available(&self) -> bool {
+        find_native_command().is_some()
+    }
+
+    async fn synthesize(&self, text: &str, voice: Option<&str>) -> Result<AudioStream> {
+        let program = find_native_command()
+            .ok_or_else(|| anyhow::anyhow!("System speech synthesizer not configured"))?;
+        let dir = tempfile::tempdir()?;
+        let output = dir.path().join("speech.wav");
+
+        let mut command = Command::new(program);
+        let mut stdin_text = None;
+        if cfg!(target_os = "macos") {
+            command
+                .arg("-o")
+                .arg(&output)
+                .arg("--data-format=LEI16@22050");
+            if let Some(voice) = voice {
+                command.arg("-v").arg(voice);
+            }
+            command.arg("--").arg(text);
+        } else if cfg!(target_os = "windows") {
+            // Text is passed through the environment to avoid any quoting issues
+            command
+                .args(["-NoProfile", "-NonInteractive", "-Command"])
+                .arg(
+                    "Add-Type -AssemblyName System.Speech; \
+                     $s = New-Object System.Speech.Synthesis.SpeechSynthesizer; \
+                     if ($env:GOOSE_TTS_VOICE) { $s.SelectVoice($env:GOOSE_TTS_VOICE) }; \
+                     $s.SetOutputToWaveFile($env:GOOSE_TTS_OUTPUT); \
+                     $s.Speak($env:GOOSE_TTS_TEXT); \
+                     $s.Dispose()",
+                )
+                .env("GOOSE_TTS_TEXT", text)
+                .env("GOOSE_TTS_OUTPUT", &output)
+                .env("GOOSE_TTS_VOICE", voice.unwrap_or_default());
+        } else {
+            command.arg("-w").arg(&output);
+            if let Some(voice) = voice {
+                command.arg("-v").arg(voice);
+            }
+            command.arg("--stdin");
+            stdin_text = Some(text);
+        }
+
+        run_to_wav(command, stdin_text, &output).await
+    }
+}
+
+/// Piper neural voices (https://github.com/rhasspy/piper) running locally.
+pub struct PiperVoice;
+
+impl PiperVoice {
+    fn binary() -> String {
+        Config::global()
+            .get_param::<String>(PIPER_BINARY_CONFIG_KEY)
+            .unwrap_or_else(|_| "piper".to_string())
+    }
+
+    fn model_path(voice: Option<&str>) -> Option<PathBuf> {
+        voice
+            .map(PathBuf::from)
+            .filter(|path| path.extension().is_some_and(|ext| ext == "onnx"))
+            .or_else(|| {
+                Config::global()
+                    .get_param::<String>(PIPER_VOICE_MODEL_CONFIG_KEY)
+                    .ok()
+                    .map(PathBuf::from)
+            })
+            .filter(|path| path.exists())
+    }
+}
+
+#[async_trait]
+impl SpeechSynthesizer for PiperVoice {
+    fn provider(&self) -> TtsProvider {
+        TtsProvider::Piper
+    }
+
+    fn is_available(&self) -> bool {
+        which::which(Self::binary()).is_ok() && Self::model_path(None).is_some()
+    }
+
+    async fn synthesize(&self, text: &str, voice: Option<&str>) -> Result<AudioStream> {
+        let model = Self::model_path(voice)
+            .ok_or_else(|| anyhow::anyhow!("{} not configured", PIPER_VOICE_MODEL_CONFIG_KEY))?;
+        let dir = tempfile::tempdir()?;
+        let output = dir.path().join("speech.wav");
+
+        let mut command = Command::new(Self::binary());
+        command
+            .arg("--model")
+            .arg(&model)
+            .arg("--output_file")
+            .arg(&output);
+
+        run_to_wav(command, Some(text), &output).await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_piper_voice_override_requires_onnx_model() {
+        let dir = tempfile::tempdir().unwrap();
+        let model = dir.path().join("en_US-amy-medium.onnx");
+        std::fs::write(&model, b"").unwrap();
+
+        assert_eq!(
+            PiperVoice::model_path(Some(model.to_str().unwrap())),
+            Some(model)
+        );
+        let not_a_model = dir.path().join("voice.txt");
+        std::fs::write(&not_a_model, b"").unwrap();
+        assert_ne!(
+            PiperVoice::model_path(Some(not_a_model.to_str().unwrap())),
+            Some(not_a_model)
+        );
+    }
+}
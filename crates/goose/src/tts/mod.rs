@@ -0,0 +1,3 @@
+pub mod local;
+pub mod providers;
+pub mod settings;
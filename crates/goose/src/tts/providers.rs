@@ -0,0 +1,334 @@
+use crate::config::Config;
+use crate::providers::api_client::{ApiClient, AuthMethod};
+use crate::tts::local::{NativeVoice, PiperVoice};
+use anyhow::{Context, Result};
+use async_trait::async_trait;
+use futures::stream::{self, BoxStream};
+use futures::{StreamExt, TryStreamExt};
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use std::time::Duration;
+use utoipa::ToSchema;
+
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(60);
+
+/// Longest text accepted for a single synthesis request
+pub const MAX_TEXT_CHARS: usize = 4096;
+
+/// Config key for the default speech provider
+pub const TTS_PROVIDER_CONFIG_KEY: &str = "GOOSE_TTS_PROVIDER";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Deserialize, Serialize, ToSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum TtsProvider {
+    System,
+    Piper,
+    OpenAI,
+    ElevenLabs,
+}
+
+pub struct TtsProviderDef {
+    pub provider: TtsProvider,
+    pub config_key: Option<&'static str>,
+    pub default_base_url: &'static str,
+    pub host_key: Option<&'static str>,
+    pub default_voice: &'static str,
+    pub description: &'static str,
+    pub local: bool,
+}
+
+/// Listed in fallback order: local voices first, cloud last.
+pub const PROVIDERS: &[TtsProviderDef] = &[
+    TtsProviderDef {
+        provider: TtsProvider::System,
+        config_key: None,
+        default_base_url: "",
+        host_key: None,
+        default_voice: "",
+        description: "Uses the operating system's built-in voices. No setup needed.",
+        local: true,
+    },
+    TtsProviderDef {
+        provider: TtsProvider::Piper,
+        config_key: Some(crate::tts::local::PIPER_VOICE_MODEL_CONFIG_KEY),
+        default_base_url: "",
+        host_key: None,
+        default_voice: "",
+        description: "Uses a local Piper neural voice model. Runs fully offline.",
+        local: true,
+    },
+    TtsProviderDef {
+        provider: TtsProvider::OpenAI,
+        config_key: Some("OPENAI_API_KEY"),
+        default_base_url: "https://api.openai.com",
+        host_key: Some("OPENAI_HOST"),
+        default_voice: "alloy",
+        description: "Uses the OpenAI speech API for natural sounding voices.",
+        local: false,
+    },
+    TtsProviderDef {
+        provider: TtsProvider::ElevenLabs,
+        config_key: Some("ELEVENLABS_API_KEY"),
+        default_base_url: "https://api.elevenlabs.io",
+        host_key: None,
+        default_voice: "21m00Tcm4TlvDq8ikWAM",
+        description: "Uses the ElevenLabs text-to-speech API for expressive voices.",
+        local: false,
+    },
+];
+
+pub fn get_provider_def(provider: TtsProvider) -> &'static TtsProviderDef {
+    PROVIDERS
+        .iter()
+        .find(|def| def.provider == provider)
+        .unwrap() // Safe because all enum variants are in PROVIDERS
+}
+
+/// Synthesized audio, delivered as a stream of encoded chunks.
+pub struct AudioStream {
+    pub mime_type: &'static str,
+    pub chunks: BoxStream<'static, std::io::Result<Vec<u8>>>,
+}
+
+impl AudioStream {
+    pub fn from_bytes(mime_type: &'static str, bytes: Vec<u8>) -> Self {
+        Self {
+            mime_type,
+            chunks: stream::once(async move { Ok(bytes) }).boxed(),
+        }
+    }
+}
+
+#[async_trait]
+pub trait SpeechSynthesizer: Send + Sync {
+    fn provider(&self) -> TtsProvider;
+
+    /// Whether the provider can be used right now without further setup
+    fn is_available(&self) -> bool;
+
+    async fn synthesize(&self, text: &str, voice: Option<&str>) -> Result<AudioStream>;
+}
+
+pub fn synthesizer(provider: TtsProvider) -> Box<dyn SpeechSynthesizer> {
+    match provider {
+        TtsProvider::System => Box::new(NativeVoice),
+        TtsProvider::Piper => Box::new(PiperVoice),
+        TtsProvider::OpenAI | TtsProvider::ElevenLabs => Box::new(CloudVoice { provider }),
+    }
+}
+
+pub fn is_configured(provider: TtsProvider) -> bool {
+    synthesizer(provider).is_available()
+}
+
+/// Picks the requested provider when usable, otherwise the configured default,
+/// otherwise the first available provider in fallback order.
+pub fn resolve_provider(requested: Option<TtsProvider>) -> Option<TtsProvider> {
+    let configured_default = Config::global()
+        .get_param::<TtsProvider>(TTS_PROVIDER_CONFIG_KEY)
+        .ok();
+
+    requested
+        .into_iter()
+        .chain(configured_default)
+        .chain(PROVIDERS.iter().map(|def| def.provider))
+        .find(|provider| is_configured(*provider))
+}
+
+pub async fn synthesize(
+    provider: TtsProvider,
+    text: &str,
+    voice: Option<&str>,
+) -> Result<AudioStream> {
+    let text = prepare_text(text);
+    if text.is_empty() {
+        anyhow::bail!("Nothing to speak");
+    }
+    synthesizer(provider).synthesize(&text, voice).await
+}
+
+/// Turns markdown into something pleasant to listen to: code blocks are
+/// skipped, formatting markers dropped and the length capped.
+pub fn prepare_text(markdown: &str) -> String {
+    let mut spoken = Vec::new();
+    let mut in_code_block = false;
+
+    for line in markdown.lines() {
+        let trimmed = line.trim();
+        if trimmed.starts_with("```") {
+            if !in_code_block {
+                spoken.push("Code block omitted.".to_string());
+            }
+            in_code_block = !in_code_block;
+            continue;
+        }
+        if in_code_block || trimmed.is_empty() {
+            continue;
+        }
+
+        let line = trimmed
+            .trim_start_matches(['#', '>', '-', '*', '+'])
+            .trim_start();
+        let line: String = line
+            .chars()
+            .filter(|c| !matches!(c, '*' | '_' | '`' | '#'))
+            .collect();
+        if !line.is_empty() {
+            spoken.push(line);
+        }
+    }
+
+    spoken.join("\n").chars().take(MAX_TEXT_CHARS).collect()
+}
+
+/// ElevenLabs voice ids go into the request path, so only plain ids are
+/// accepted; anything else could point the request at another endpoint.
+fn is_voice_id(voice: &str) -> bool {
+    !voice.is_empty() && voice.chars().all(|c| c.is_ascii_alphanumeric())
+}
+
+struct CloudVoice {
+    provider: TtsProvider,
+}
+
+impl CloudVoice {
+    fn api_client(&self) -> Result<ApiClient> {
+        let config = Config::global();
+        let def = get_provider_def(self.provider);
+        let config_key = def
+            .config_key
+            .ok_or_else(|| anyhow::anyhow!("Provider has no API key"))?;
+
+        let api_key: String = config
+            .get_secret(config_key)
+            .context(format!("{} not configured", config_key))?;
+
+        let base_url = def
+            .host_key
+            .and_then(|host_key| config.get_param::<String>(host_key).ok())
+            .unwrap_or_else(|| def.default_base_url.to_string());
+
+        let auth = match self.provider {
+            TtsProvider::ElevenLabs => AuthMethod::ApiKey {
+                header_name: "xi-api-key".to_string(),
+                key: api_key,
+            },
+            _ => AuthMethod::BearerToken(api_key),
+        };
+
+        ApiClient::with_timeout(base_url, auth, REQUEST_TIMEOUT)
+            .context("Failed to create API client")
+    }
+}
+
+#[async_trait]
+impl SpeechSynthesizer for CloudVoice {
+    fn provider(&self) -> TtsProvider {
+        self.provider
+    }
+
+    fn is_available(&self) -> bool {
+        get_provider_def(self.provider)
+            .config_key
+            .is_some_and(|key| Config::global().get_secret::<String>(key).is_ok())
+    }
+
+    async fn synthesize(&self, text: &str, voice: Option<&str>) -> Result<AudioStream> {
+        let client = self.api_client()?;
+        let voice = voice.unwrap_or(get_provider_def(self.provider).default_voice);
+
+        let (path, payload) = match self.provider {
+            TtsProvider::OpenAI => (
+                "v1/audio/speech".to_string(),
+                json!({
+                    "model": "tts-1",
+                    "input": text,
+                    "voice": voice,
+                    "response_format": "mp3",
+                }),
+            ),
+            TtsProvider::ElevenLabs => {
+                if !is_voice_id(voice) {
+                    anyhow::bail!("Invalid ElevenLabs voice id: {}", voice);
+                }
+                (
+                    format!("v1/text-to-speech/{}/stream", voice),
+                    json!({
+                        "text": text,
+                        "model_id": "eleven_turbo_v2_5",
+                    }),
+                )
+            }
+            _ => anyhow::bail!("Local provider should not use API client"),
+        };
+
+        let response = client
+            .request(None, &path)
+            .response_post(&payload)
+            .await
+            .context("Request failed")?;
+
+        if !response.status().is_success() {
+            let status = response.status();
+            let error_text = response.text().await.unwrap_or_default();
+
+            if status == 401 || error_text.contains("Invalid API key") {
+                anyhow::bail!("Invalid API key");
+            } else if status == 429 || error_text.contains("quota") {
+                anyhow::bail!("Rate limit exceeded");
+            } else {
+                anyhow::bail!("API error: {}", error_text);
+            }
+        }
+
+        Ok(AudioStream {
+            mime_type: "audio/mpeg",
+            chunks: response
+                .bytes_stream()
+                .map_ok(|chunk| chunk.to_vec())
+                .map_err(std::io::Error::other)
+                .boxed(),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_prepare_text_skips_code_and_markup() {
+        let markdown = "## Result\n\nThe **build** passed.\n\n```rust\nfn main() {}\n```\n- `cargo test` is green";
+        assert_eq!(
+            prepare_text(markdown),
+            "Result\nThe build passed.\nCode block omitted.\ncargo test is green"
+        );
+    }
+
+    #[test]
+    fn test_prepare_text_caps_length() {
+        let long = "a".repeat(MAX_TEXT_CHARS + 100);
+        assert_eq!(prepare_text(&long).chars().count(), MAX_TEXT_CHARS);
+    }
+
+    #[test]
+    fn test_voice_ids_cannot_change_the_request_path() {
+        assert!(is_voice_id("21m00Tcm4TlvDq8ikWAM"));
+        for voice in ["", "../../v1/user", "abc?x=1", "abc/stream#"] {
+            assert!(!is_voice_id(voice), "{}", voice);
+        }
+    }
+
+    #[test]
+    fn test_every_provider_has_a_def() {
+        for provider in [
+            TtsProvider::System,
+            TtsProvider::Piper,
+            TtsProvider::OpenAI,
+            TtsProvider::ElevenLabs,
+        ] {
+            assert_eq!(get_provider_def(provider).provider, provider);
+            assert_eq!(synthesizer(provider).provider(), provider);
+        }
+    }
+}
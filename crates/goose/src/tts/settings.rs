@@ -0,0 +1,47 @@
+use crate::session::extension_data::ExtensionState;
+use crate::tts::providers::TtsProvider;
+use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
+
+/// Per-session text-to-speech preferences, stored in the session's extension data.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
+pub struct SpeechSettings {
+    /// Read assistant responses aloud as they complete
+    #[serde(default)]
+    pub speak_responses: bool,
+    /// Preferred provider; falls back to the configured default when unavailable
+    #[serde(default)]
+    pub provider: Option<TtsProvider>,
+    /// Provider-specific voice name or id
+    #[serde(default)]
+    pub voice: Option<String>,
+}
+
+impl ExtensionState for SpeechSettings {
+    const EXTENSION_NAME: &'static str = "speech_settings";
+    const VERSION: &'static str = "v0";
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::session::extension_data::ExtensionData;
+
+    #[test]
+    fn test_speech_settings_round_trip() {
+        let mut extension_data = ExtensionData::default();
+        assert_eq!(SpeechSettings::from_extension_data(&extension_data), None);
+
+        let settings = SpeechSettings {
+            speak_responses: true,
+            provider: Some(TtsProvider::Piper),
+            voice: None,
+        };
+        settings.to_extension_data(&mut extension_data).unwrap();
+
+        assert_eq!(
+            SpeechSettings::from_extension_data(&extension_data),
+            Some(settings)
+        );
+    }
+}
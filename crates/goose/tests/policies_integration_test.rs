@@ -427,6 +427,49 @@ async fn test_regex_condition() {
     assert!(!result.matched);
 }
 
+/// Test expression conditions over change sets and risk levels
+#[tokio::test]
+async fn test_expression_condition() {
+    let engine = RuleEngine::new();
+
+    let mut rule_set = RuleSet::new("expression-tests");
+    rule_set.add_rule(
+        Rule::new("conductor-changes", "Conductor changes require approval")
+            .for_event_type(EventType::ToolExecution)
+            .with_condition(Condition::Expression {
+                expr: r#"changed_files.exists(f, f.startsWith("crates/goose-conductor/"))
+                    || (risk_level == "high" && cost_usd > 5)"#
+                    .to_string(),
+            })
+            .with_action(Action::RequireApproval {
+                approvers: vec!["release-team".to_string()],
+            }),
+    );
+
+    engine.add_rule_set(rule_set).await;
+
+    let conductor_event = Event::new(EventType::ToolExecution).with_data(
+        "changed_files",
+        vec!["README.md", "crates/goose-conductor/src/lib.rs"],
+    );
+    let result = engine.evaluate(&conductor_event).await.unwrap();
+    assert!(result.matched);
+
+    let costly_event = Event::new(EventType::ToolExecution)
+        .with_data("changed_files", vec!["README.md"])
+        .with_data("risk_level", "high")
+        .with_data("cost_usd", 7.5);
+    let result = engine.evaluate(&costly_event).await.unwrap();
+    assert!(result.matched);
+
+    let cheap_event = Event::new(EventType::ToolExecution)
+        .with_data("changed_files", vec!["README.md"])
+        .with_data("risk_level", "high")
+        .with_data("cost_usd", 0.5);
+    let result = engine.evaluate(&cheap_event).await.unwrap();
+    assert!(!result.matched);
+}
+
 // =============================================================================
 // YAML Loading Tests
 // =============================================================================
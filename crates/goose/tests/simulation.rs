@@ -0,0 +1,142 @@
+//! Agent loop tests driven by scripted scenarios: a MockProvider plays the
+//! model and a simulated extension serves the tools, so no network or API
+//! keys are needed.
+
+use anyhow::Result;
+use futures::StreamExt;
+use goose::agents::simulated_extension::{SimulatedClient, SimulatedToolCall};
+use goose::agents::{Agent, AgentEvent, SessionConfig};
+use goose::conversation::message::{ActionRequiredData, Message, MessageContent};
+use goose::permission::permission_confirmation::PrincipalType;
+use goose::permission::{Permission, PermissionConfirmation};
+use goose::providers::mock::{MockProvider, Scenario};
+use goose::session::session_manager::SessionType;
+use std::path::PathBuf;
+use std::sync::{Arc, Mutex};
+use tempfile::TempDir;
+
+struct Simulation {
+    agent: Agent,
+    provider: Arc<MockProvider>,
+    calls: Arc<Mutex<Vec<SimulatedToolCall>>>,
+    session_id: String,
+    _temp_dir: TempDir,
+}
+
+impl Simulation {
+    async fn load(file: &str) -> Result<Self> {
+        let path = PathBuf::from(env!("CARGO_MANIFEST_DIR"))
+            .join("tests/scenarios")
+            .join(file);
+        let scenario = Scenario::from_file(path)?;
+
+        let temp_dir = TempDir::new()?;
+        let agent = Agent::new();
+        let session = agent
+            .config
+            .session_manager
+            .create_session(
+                temp_dir.path().to_path_buf(),
+                scenario.name.clone(),
+                SessionType::Hidden,
+            )
+            .await?;
+
+        let calls =
+            SimulatedClient::register(&agent.extension_manager, "sim", scenario.tools.clone())
+                .await;
+        let provider = Arc::new(MockProvider::new(scenario));
+        agent.update_provider(provider.clone(), &session.id).await?;
+
+        Ok(Self {
+            agent,
+            provider,
+            calls,
+            session_id: session.id,
+            _temp_dir: temp_dir,
+        })
+    }
+
+    async fn run(&self, prompt: &str) -> Result<Vec<Message>> {
+        let session_config = SessionConfig {
+            id: self.session_id.clone(),
+            schedule_id: None,
+            max_turns: None,
+            retry_config: None,
+        };
+        let stream = self
+            .agent
+            .reply(Message::user().with_text(prompt), session_config, None)
+            .await?;
+        tokio::pin!(stream);
+
+        let mut messages = Vec::new();
+        while let Some(event) = stream.next().await {
+            if let AgentEvent::Message(message) = event? {
+                if let Some(MessageContent::ActionRequired(action)) = message.content.first() {
+                    if let ActionRequiredData::ToolConfirmation { id, .. } = &action.data {
+                        self.agent
+                            .handle_confirmation(
+                                id.clone(),
+                                PermissionConfirmation {
+                                    principal_type: PrincipalType::Tool,
+                                    permission: Permission::AllowOnce,
+                                },
+                            )
+                            .await;
+                    }
+                }
+                messages.push(message);
+            }
+        }
+        Ok(messages)
+    }
+
+    fn tool_calls(&self) -> Vec<String> {
+        self.calls
+            .lock()
+            .unwrap()
+            .iter()
+            .map(|call| call.name.clone())
+            .collect()
+    }
+}
+
+#[tokio::test]
+async fn test_scenario_runs_tools_to_completion() -> Result<()> {
+    let sim = Simulation::load("read_and_fix.yaml").await?;
+
+    let messages = sim.run("Fix the typo in main.rs").await?;
+
+    assert_eq!(sim.tool_calls(), vec!["read_file", "write_file"]);
+    let calls = sim.calls.lock().unwrap().clone();
+    assert_eq!(
+        calls[0].arguments.as_ref().unwrap()["path"],
+        serde_json::json!("src/main.rs")
+    );
+    assert_eq!(sim.provider.remaining_turns(), 0);
+
+    let last = messages.last().expect("agent produced messages");
+    assert_eq!(last.as_concat_text(), "Fixed the typo in src/main.rs.");
+
+    let first_request = &sim.provider.requests()[0];
+    assert!(first_request
+        .tools
+        .iter()
+        .any(|tool| tool.name == "sim__read_file"));
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_scenario_injects_tool_and_provider_failures() -> Result<()> {
+    let sim = Simulation::load("flaky_tool_and_provider.yaml").await?;
+
+    let messages = sim.run("Make the tests pass").await?;
+
+    assert_eq!(sim.tool_calls(), vec!["run_tests", "run_tests"]);
+    assert_eq!(sim.provider.remaining_turns(), 0);
+
+    let last = messages.last().expect("agent produced messages");
+    assert!(last.as_concat_text().contains("upstream overloaded"));
+    Ok(())
+}